@@ -177,6 +177,8 @@ pub enum DeterminismClaim {
     NoExternalAccess,
     /// All randomness was seeded
     SeededRandomness,
+    /// Only pure host functions were called; no fs or network access
+    PureCompute,
     /// Custom claim with description
     Custom { description: String },
 }
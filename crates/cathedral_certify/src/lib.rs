@@ -15,4 +15,4 @@ pub mod validator;
 pub use certifier::Certifier;
 pub use certificate::{Certificate, CertificateBody, CertificateError};
 pub use signature::{SignatureScheme, Signer, Verifier};
-pub use validator::{DeterminismValidator, ValidationReport};
+pub use validator::{DeterminismValidator, DivergencePoint, ValidationReport};
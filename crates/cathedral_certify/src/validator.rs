@@ -2,11 +2,27 @@
 
 use crate::certificate::{CertificateBody, DeterminismClaim, ValidatorInfo};
 use crate::CertificateError;
-use cathedral_sim::record::{RunComparison, SimRecord};
+use cathedral_sim::record::{RunComparison, RunDelta, SimRecord};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+/// Canonical order of known checks in a [`ValidationReport`]
+///
+/// Checks not listed here sort after these, by name. New checks should be
+/// appended so existing reports keep their order across versions.
+pub const CHECK_ORDER: &[&str] = &[
+    "has_runs",
+    "seed_consistency",
+    "tick_consistency",
+    "event_count_consistency",
+    "event_sequence_consistency",
+    "pure_compute",
+];
 
 /// Result of validating determinism
+///
+/// `checks` is kept in [`CHECK_ORDER`] regardless of insertion order, so
+/// validations with the same outcome produce identical reports.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ValidationReport {
     /// Whether validation passed
@@ -16,7 +32,21 @@ pub struct ValidationReport {
     /// Validation checks performed
     pub checks: Vec<ValidationCheck>,
     /// Additional details
-    pub details: HashMap<String, String>,
+    pub details: BTreeMap<String, String>,
+    /// First point where each diverging pair of runs differs
+    #[serde(default)]
+    pub divergences: Vec<DivergencePoint>,
+}
+
+/// Where two runs first diverged
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DivergencePoint {
+    /// Index of the first run
+    pub left_run: usize,
+    /// Index of the second run
+    pub right_run: usize,
+    /// First difference between the runs
+    pub delta: RunDelta,
 }
 
 impl ValidationReport {
@@ -27,14 +57,17 @@ impl ValidationReport {
             passed,
             run_count,
             checks: Vec::new(),
-            details: HashMap::new(),
+            details: BTreeMap::new(),
+            divergences: Vec::new(),
         }
     }
 
-    /// Add a validation check
+    /// Add a validation check at its canonical position
     #[must_use]
     pub fn with_check(mut self, check: ValidationCheck) -> Self {
-        self.checks.push(check);
+        let key = check.sort_key();
+        let index = self.checks.partition_point(|c| c.sort_key() <= key);
+        self.checks.insert(index, check);
         self
     }
 
@@ -45,6 +78,13 @@ impl ValidationReport {
         self
     }
 
+    /// Record a divergence point
+    #[must_use]
+    pub fn with_divergence(mut self, divergence: DivergencePoint) -> Self {
+        self.divergences.push(divergence);
+        self
+    }
+
     /// Get failed checks
     #[must_use]
     pub fn failed_checks(&self) -> Vec<&ValidationCheck> {
@@ -66,6 +106,35 @@ impl ValidationReport {
             self.run_count
         )
     }
+
+    /// Full human-readable report: summary, every check, and divergences
+    #[must_use]
+    pub fn explain(&self) -> String {
+        let mut out = self.summary();
+        for check in &self.checks {
+            out.push_str(&format!(
+                "\n  [{}] {}: {}",
+                if check.passed { "PASS" } else { "FAIL" },
+                check.name,
+                check.message
+            ));
+        }
+        for divergence in &self.divergences {
+            out.push_str(&format!(
+                "\n  Runs {} and {} diverge at tick {} on node {}: expected '{}', got '{}'",
+                divergence.left_run,
+                divergence.right_run,
+                divergence.delta.tick,
+                divergence.delta.node_id,
+                divergence.delta.expected,
+                divergence.delta.actual
+            ));
+        }
+        for (key, value) in &self.details {
+            out.push_str(&format!("\n  {}: {}", key, value));
+        }
+        out
+    }
 }
 
 /// A single validation check
@@ -86,6 +155,15 @@ impl ValidationCheck {
         Self { name, passed, message }
     }
 
+    /// Position in the canonical check order
+    fn sort_key(&self) -> (usize, &str) {
+        let rank = CHECK_ORDER
+            .iter()
+            .position(|name| *name == self.name)
+            .unwrap_or(CHECK_ORDER.len());
+        (rank, &self.name)
+    }
+
     /// Create a passed check
     #[must_use]
     pub fn passed(name: String) -> Self {
@@ -197,15 +275,26 @@ impl DeterminismValidator {
         // Check 4: Event sequence consistency (pairwise comparison)
         let mut all_match = true;
         let mut mismatches = Vec::new();
+        let mut divergences = Vec::new();
         for (i, run_a) in runs.iter().enumerate() {
-            for run_b in runs.iter().skip(i + 1) {
+            for (j, run_b) in runs.iter().enumerate().skip(i + 1) {
                 let comparison = RunComparison::compare(run_a, run_b);
                 if !comparison.identical {
                     all_match = false;
-                    mismatches.push(format!("Runs {} and {} differ", i, i + 1));
+                    mismatches.push(format!("Runs {} and {} differ", i, j));
+                    if let Some(delta) = comparison.deltas.into_iter().next() {
+                        divergences.push(DivergencePoint {
+                            left_run: i,
+                            right_run: j,
+                            delta,
+                        });
+                    }
                 }
             }
         }
+        for divergence in divergences {
+            report = report.with_divergence(divergence);
+        }
         report = report.with_check(ValidationCheck::new(
             "event_sequence_consistency".to_string(),
             all_match,
@@ -222,6 +311,36 @@ impl DeterminismValidator {
         Ok(report)
     }
 
+    /// Check that a run called only pure host functions
+    ///
+    /// `pure_functions` is the set registered in pure-compute mode; calls to
+    /// anything else mean the run cannot be attested as pure.
+    #[must_use]
+    pub fn check_pure_compute(
+        &self,
+        host_calls: &[String],
+        pure_functions: &[String],
+    ) -> ValidationCheck {
+        let impure: Vec<&str> = host_calls
+            .iter()
+            .filter(|call| !pure_functions.contains(*call))
+            .map(String::as_str)
+            .collect();
+
+        if impure.is_empty() {
+            ValidationCheck::new(
+                "pure_compute".to_string(),
+                true,
+                format!("{} host calls, all pure", host_calls.len()),
+            )
+        } else {
+            ValidationCheck::failed(
+                "pure_compute".to_string(),
+                format!("Impure host calls: {}", impure.join(", ")),
+            )
+        }
+    }
+
     /// Generate a certificate from validation
     ///
     /// # Errors
@@ -267,6 +386,10 @@ impl DeterminismValidator {
             body = body.with_claim(DeterminismClaim::ValidHashChain);
         }
 
+        if report.checks.iter().any(|c| c.name == "pure_compute" && c.passed) {
+            body = body.with_claim(DeterminismClaim::PureCompute);
+        }
+
         // Add validation metadata
         body = body.with_detail("validation_report".to_string(), report.summary());
 
@@ -326,6 +449,44 @@ mod tests {
         assert_eq!(report.checks.len(), 1);
     }
 
+    #[test]
+    fn test_validation_report_check_order() {
+        let names = |report: &ValidationReport| -> Vec<String> {
+            report.checks.iter().map(|c| c.name.clone()).collect()
+        };
+
+        let a = ValidationReport::new(true, 1)
+            .with_check(ValidationCheck::passed("zeta".to_string()))
+            .with_check(ValidationCheck::passed("event_sequence_consistency".to_string()))
+            .with_check(ValidationCheck::passed("alpha".to_string()))
+            .with_check(ValidationCheck::passed("seed_consistency".to_string()));
+        let b = ValidationReport::new(true, 1)
+            .with_check(ValidationCheck::passed("seed_consistency".to_string()))
+            .with_check(ValidationCheck::passed("alpha".to_string()))
+            .with_check(ValidationCheck::passed("zeta".to_string()))
+            .with_check(ValidationCheck::passed("event_sequence_consistency".to_string()));
+
+        assert_eq!(
+            names(&a),
+            vec!["seed_consistency", "event_sequence_consistency", "alpha", "zeta"]
+        );
+        assert_eq!(a, b);
+        assert_eq!(a.summary(), b.summary());
+
+        let validator = DeterminismValidator::default();
+        let record = SimRecord::new().with_seed(SimSeed::from_literal(42));
+        let report = validator.validate_runs(&[record.clone(), record]).unwrap();
+        assert_eq!(
+            names(&report),
+            vec![
+                "seed_consistency",
+                "tick_consistency",
+                "event_count_consistency",
+                "event_sequence_consistency",
+            ]
+        );
+    }
+
     #[test]
     fn test_validation_report_with_detail() {
         let report = ValidationReport::new(true, 1)
@@ -431,6 +592,14 @@ mod tests {
 
         let report = validator.validate_runs(&[record1, record2]).unwrap();
         assert!(!report.passed);
+        assert_eq!(report.divergences.len(), 1);
+        assert_eq!(report.divergences[0].delta.tick, 1);
+        assert_eq!(report.divergences[0].delta.expected, "event1");
+
+        let explanation = report.explain();
+        assert!(explanation.contains("[FAIL] event_sequence_consistency: Sequence mismatches: Runs 0 and 1 differ"));
+        assert!(explanation.contains("diverge at tick 1"));
+        assert!(explanation.contains("expected 'event1', got 'event2'"));
     }
 
     #[test]
@@ -450,6 +619,28 @@ mod tests {
         assert!(!body.claims.is_empty());
     }
 
+    #[test]
+    fn test_certify_pure_compute() {
+        let validator = DeterminismValidator::default();
+        let record = SimRecord::new().with_seed(SimSeed::from_literal(42));
+        let pure = vec!["clock_read".to_string(), "log_write".to_string()];
+
+        let check = validator.check_pure_compute(&["clock_read".to_string()], &pure);
+        let report = ValidationReport::new(true, 1).with_check(check);
+        let body = validator.certify("exec-1".to_string(), 42, &record, &report).unwrap();
+        assert!(body.claims.contains(&DeterminismClaim::PureCompute));
+
+        let check = validator.check_pure_compute(
+            &["clock_read".to_string(), "fs_read".to_string()],
+            &pure,
+        );
+        assert!(!check.passed);
+        assert!(check.message.contains("fs_read"));
+        let report = ValidationReport::new(false, 1).with_check(check);
+        let body = validator.certify("exec-2".to_string(), 42, &record, &report).unwrap();
+        assert!(!body.claims.contains(&DeterminismClaim::PureCompute));
+    }
+
     #[test]
     fn test_compute_log_hash() {
         let validator = DeterminismValidator::default();
@@ -5,8 +5,26 @@
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
-use clap::{Parser, Subcommand};
+use cathedral_certify::Certifier;
+use cathedral_cluster::{ClusterStatus, DrainReport, ExecutionResult, MemberStatus};
+use cathedral_core::{Hash, NodeId, RunId, TenantId};
+use cathedral_log::{Event, EventKind, IndexedLog, LogQuery, SegmentConfig, SegmentedStream};
+use cathedral_plan::{Compiler, Dag, NodeKind, Severity};
+use cathedral_policy::{AuditFormat, AuditQuery, AuditTrail, PolicyBundle};
+use cathedral_replay::debugger::DEFAULT_CHECKPOINT_INTERVAL;
+use cathedral_replay::{
+    build_graph, Breakpoint, BundleBuilder, BundleReader, Debugger, DiffEngine, DivergenceReport,
+    ExecutionGraph, ProvenanceTarget, ReplayConfig, ReplayEngine, Step, TraceEvent, TraceReader,
+};
+use cathedral_runtime::engine::{EngineConfig, ExecutionEngine, ExecutionStatus};
+use cathedral_sim::record::SimRecord;
+use cathedral_sim::{Scenario, SimHarness};
+use cathedral_storage::{HttpRequest, HttpTransport, ReqwestTransport};
+use clap::{Parser, Subcommand, ValueEnum};
 use color_eyre::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "cathedral")]
@@ -18,41 +36,120 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Run a workflow
+    /// Compile a workflow and execute it locally
     Run {
         /// Path to workflow file
         #[arg(short, long)]
         file: String,
-        /// Output directory
+        /// Directory to write the event log and node outputs to; defaults
+        /// to `runs/<run_id>`
         #[arg(short, long)]
         output: Option<String>,
     },
+    /// Check a workflow file without running it
+    ///
+    /// Exits with status 1 if the workflow has errors.
+    Validate {
+        /// Path to workflow file
+        #[arg(short, long)]
+        file: String,
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
     /// Replay a run from logs
+    ///
+    /// Exits with status 2 if the replayed state diverges from `--against`
+    /// or `--expect`.
     Replay {
-        /// Path to replay bundle
+        /// Path to replay bundle (JSON trace, or a bundle from `bundle`)
         #[arg(short, long)]
         bundle: String,
+        /// Trace or bundle of another run the replay must match
+        #[arg(long)]
+        against: Option<String>,
+        /// State hash the replay must end with
+        #[arg(long)]
+        expect: Option<String>,
+        /// Hex Ed25519 public key bundles must be signed with
+        #[arg(long)]
+        signer: Option<String>,
+    },
+    /// Step through a run interactively
+    ///
+    /// Reads commands from stdin; type `help` for the list.
+    Debug {
+        /// Path to replay bundle (JSON trace, or a bundle from `bundle`)
+        #[arg(short, long)]
+        bundle: String,
+        /// Hex Ed25519 public key a bundle must be signed with
+        #[arg(long)]
+        signer: Option<String>,
+        /// Events between the state checkpoints stepping back restores from
+        #[arg(long, default_value_t = DEFAULT_CHECKPOINT_INTERVAL)]
+        checkpoint_interval: usize,
     },
     /// Diff two runs
+    ///
+    /// Exits with status 2 if the runs differ.
     Diff {
-        /// First bundle
+        /// First run (JSON trace, or a bundle from `bundle`)
+        #[arg(long)]
+        left: String,
+        /// Second run (JSON trace, or a bundle from `bundle`)
+        #[arg(long)]
+        right: String,
+        /// Hex Ed25519 public key bundles must be signed with
+        #[arg(long)]
+        signer: Option<String>,
+        /// Replay both runs in lockstep and report the first event where
+        /// their states diverge
+        #[arg(long)]
+        bisect: bool,
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+    },
+    /// Explain the first divergence between two runs
+    ExplainDivergence {
+        /// Left JSON trace
         #[arg(long)]
         left: String,
-        /// Second bundle
+        /// Right JSON trace
         #[arg(long)]
         right: String,
     },
     /// Trace execution
     Trace {
-        /// Run ID or bundle path
+        /// Path to a JSON trace (array of trace events)
         #[arg(short, long)]
         id: String,
+        /// Show the provenance of a node ID, output hash (or a unique prefix
+        /// of either), or of the output blob stored in a file
+        #[arg(long)]
+        from: Option<String>,
+        /// Compiled DAG (dag.json) whose dependencies give the edges between
+        /// nodes, rather than the order they ran in
+        #[arg(long)]
+        dag: Option<String>,
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
     },
     /// Inspect logs
     Inspect {
-        /// Path to log file
+        /// Path to log directory
         #[arg(short, long)]
         log: String,
+        /// Only show events matching a query, e.g. "run=<id> kind=NodeFailed from=10"
+        #[arg(short, long)]
+        query: Option<String>,
+        /// Truncate a damaged log to its longest valid prefix
+        #[arg(long)]
+        repair: bool,
+        /// Repair without asking for confirmation
+        #[arg(long, requires = "repair")]
+        yes: bool,
     },
     /// Show capabilities
     Capabilities {
@@ -62,70 +159,1186 @@ enum Commands {
     },
     /// Certify determinism
     Certify {
-        /// Bundle to certify
+        /// Bundle to certify (JSON array of run records)
         #[arg(short, long)]
         bundle: String,
+        /// Print every check and divergence point, not just the summary
+        #[arg(long)]
+        explain: bool,
     },
     /// Create replay bundle
     Bundle {
-        /// Run ID
+        /// Run directory written by `run`, or a run ID under `runs/`
         #[arg(short, long)]
         run: String,
         /// Output path
         #[arg(short, long)]
         output: String,
+        /// File holding the hex Ed25519 secret key to sign the bundle with
+        #[arg(short, long)]
+        key: String,
+        /// Policy bundle (JSON) to include
+        #[arg(long)]
+        policy: Option<String>,
     },
     /// Verify bundle integrity
     VerifyBundle {
         /// Bundle path
         #[arg(short, long)]
         bundle: String,
+        /// Hex Ed25519 public key the bundle must be signed with
+        #[arg(long)]
+        signer: String,
+        /// Extract this entry, e.g. "blobs/<hash>"
+        #[arg(long)]
+        extract: Option<String>,
+        /// Directory to extract into
+        #[arg(long, default_value = ".", requires = "extract")]
+        to: String,
+    },
+    /// Export the audit trail of a server's API calls and runs
+    Audit {
+        /// Server data directory
+        #[arg(short, long)]
+        data_dir: String,
+        /// Tenant whose calls and runs to export
+        #[arg(short, long, default_value = "default")]
+        tenant: String,
+        /// Only this run's entries
+        #[arg(short, long)]
+        run: Option<String>,
+        /// Only entries by this principal
+        #[arg(long)]
+        actor: Option<String>,
+        /// Only entries with this action, e.g. "PolicyDecision" or "POST /runs"
+        #[arg(long)]
+        action: Option<String>,
+        /// Output format: json, jsonl or csv
+        #[arg(short, long, default_value = "csv")]
+        format: AuditFormat,
+    },
+    /// Inspect and manage a server's cluster
+    Cluster {
+        /// Server URL
+        #[arg(short, long, global = true, default_value = "http://127.0.0.1:8080")]
+        server: String,
+        /// Bearer token to authenticate with
+        #[arg(long, global = true)]
+        token: Option<String>,
+        /// Report format
+        #[arg(long, global = true, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(subcommand)]
+        command: ClusterCommand,
+    },
+    /// Run deterministic simulations
+    Sim {
+        #[command(subcommand)]
+        command: SimCommand,
     },
 }
 
+/// Subcommands of `sim`
+#[derive(Subcommand)]
+enum SimCommand {
+    /// Run a scenario file and write its runs as a bundle for `certify`
+    Run {
+        /// Scenario file (TOML, or JSON with a `.json` extension)
+        #[arg(short, long)]
+        scenario: String,
+        /// Number of times to run the scenario
+        #[arg(long, default_value_t = 1)]
+        runs: usize,
+        /// Output path, `<scenario name>.sim.json` by default
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+/// Subcommands of `cluster`
+#[derive(Subcommand)]
+enum ClusterCommand {
+    /// Show the leader, term and health of the cluster
+    Status,
+    /// List members with their heartbeat ages and in-flight tasks
+    Members,
+    /// List completed task results in the order the log committed them
+    Results,
+    /// Stop assigning to a worker, wait for its tasks and deregister it
+    ///
+    /// Exits with status 1 if tasks were still running at the timeout; the
+    /// worker then stays leaving, and the drain can be retried.
+    Drain {
+        /// Node ID of the worker
+        node: String,
+        /// How long to wait for the worker's running tasks, in milliseconds
+        #[arg(long, default_value_t = 30_000)]
+        timeout_ms: u64,
+    },
+    /// Make a member a voter
+    Promote {
+        /// Node ID of the member
+        node: String,
+    },
+}
+
+/// Exit status of `replay` and `diff` when the runs diverge
+const EXIT_DIVERGENT: i32 = 2;
+
+/// Exit status of `validate` when the workflow has errors
+const EXIT_INVALID: i32 = 1;
+
+/// File in a run directory holding the compiled DAG
+const DAG_FILE: &str = "dag.json";
+
+/// Output format of reports
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    /// Human-readable text
+    Text,
+    /// JSON
+    Json,
+}
+
 fn main() -> Result<()> {
     color_eyre::install()?;
-    let cli = Cli::parse();
+    match run(Cli::parse())? {
+        0 => Ok(()),
+        status => std::process::exit(status),
+    }
+}
 
+/// Execute a command, returning the status to exit with
+fn run(cli: Cli) -> Result<i32> {
     match cli.command {
-        Commands::Run { file, output } => {
-            println!("Running workflow: {}", file);
-            if let Some(out) = output {
-                println!("Output: {}", out);
+        Commands::Run { file, output } => run_workflow(&file, output.as_deref()),
+        Commands::Validate { file, format } => {
+            if !validate_workflow(&file, format)? {
+                return Ok(EXIT_INVALID);
             }
             Ok(())
         }
-        Commands::Replay { bundle } => {
-            println!("Replaying bundle: {}", bundle);
+        Commands::Replay { bundle, against, expect, signer } => {
+            let identical =
+                replay(&bundle, against.as_deref(), expect.as_deref(), signer.as_deref())?;
+            return Ok(divergence_status(identical));
+        }
+        Commands::Debug { bundle, signer, checkpoint_interval } => {
+            debug(&bundle, signer.as_deref(), checkpoint_interval)
+        }
+        Commands::Diff { left, right, signer, bisect: true, format } => {
+            let left = load_trace(&left, signer.as_deref())?;
+            let right = load_trace(&right, signer.as_deref())?;
+            let report = ReplayEngine::new().bisect_divergence(&left, &right)?;
+            match (&report, format) {
+                (_, ReportFormat::Json) => println!("{}", serde_json::to_string_pretty(&report)?),
+                (Some(report), ReportFormat::Text) => print_divergence(report),
+                (None, ReportFormat::Text) => println!("Replayed states are identical"),
+            }
+            return Ok(divergence_status(report.is_none()));
+        }
+        Commands::Diff { left, right, signer, format, .. } => {
+            let left = load_trace(&left, signer.as_deref())?;
+            let right = load_trace(&right, signer.as_deref())?;
+            let diff = DiffEngine::new().diff_traces(&left, &right)?;
+            match format {
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+                ReportFormat::Text if diff.is_identical() => println!("Runs are identical"),
+                ReportFormat::Text => {
+                    if let Some(divergence) = &diff.divergence {
+                        print_divergence(divergence);
+                    }
+                    println!(
+                        "{} differing events, {} differing nodes, {} differing state keys",
+                        diff.events.len(),
+                        diff.state.node_changes.len(),
+                        diff.state.summary.state_change_count
+                    );
+                    print!("{}", diff.to_text());
+                }
+            }
+            return Ok(divergence_status(diff.is_identical()));
+        }
+        Commands::ExplainDivergence { left, right } => {
+            let left: Vec<TraceEvent> = serde_json::from_slice(&std::fs::read(&left)?)?;
+            let right: Vec<TraceEvent> = serde_json::from_slice(&std::fs::read(&right)?)?;
+            match DiffEngine::new().explain_divergence(&left, &right)? {
+                Some(report) => print_divergence(&report),
+                None => println!("Runs are identical"),
+            }
             Ok(())
         }
-        Commands::Diff { left, right } => {
-            println!("Diffing {} vs {}", left, right);
+        Commands::Trace { id, from: Some(target), dag, format } => {
+            let graph = trace_graph(&id, dag.as_deref())?;
+            let target = if Path::new(&target).is_file() {
+                ProvenanceTarget::Output(Hash::compute(&std::fs::read(&target)?))
+            } else {
+                graph.resolve(&target)?
+            };
+            let provenance = graph.provenance(target)?;
+            match format {
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&provenance)?),
+                ReportFormat::Text => {
+                    for line in provenance.lines() {
+                        println!("{}", line);
+                    }
+                }
+            }
             Ok(())
         }
-        Commands::Trace { id } => {
-            println!("Tracing: {}", id);
+        Commands::Trace { id, dag, format, .. } => {
+            let graph = trace_graph(&id, dag.as_deref())?;
+            match format {
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&graph)?),
+                ReportFormat::Text => {
+                    for line in graph.lines() {
+                        println!("{}", line);
+                    }
+                }
+            }
             Ok(())
         }
-        Commands::Inspect { log } => {
-            println!("Inspecting: {}", log);
+        Commands::Inspect { log, repair: true, yes, .. } => repair_log(&log, yes),
+        Commands::Inspect { log, query, .. } => {
+            let query: LogQuery = query.as_deref().unwrap_or_default().parse()?;
+            let stream = IndexedLog::open(&log)?;
+            let positions = stream.index().query(&query);
+            println!("Inspecting: {} ({} of {} events)", log, positions.len(), stream.len());
+            for position in positions {
+                if let Some(event) = stream.read(position)? {
+                    let kind = format!("{:?}", event.kind);
+                    println!(
+                        "{:>8}  t={:<8} {:<20} {} {}",
+                        position,
+                        event.logical_time.as_u64(),
+                        kind,
+                        event.run_id,
+                        event.node_id
+                    );
+                }
+            }
             Ok(())
         }
         Commands::Capabilities { run } => {
             println!("Capabilities for run: {}", run);
             Ok(())
         }
-        Commands::Certify { bundle } => {
-            println!("Certifying bundle: {}", bundle);
+        Commands::Certify { bundle, explain } => {
+            let runs: Vec<SimRecord> = serde_json::from_slice(&std::fs::read(&bundle)?)?;
+            let report = Certifier::default().validate(&runs)?;
+            if explain {
+                println!("{}", report.explain());
+            } else {
+                println!("{}", report.summary());
+            }
+            if !report.passed {
+                return Err(color_eyre::eyre::eyre!("certification failed: {}", bundle));
+            }
             Ok(())
         }
-        Commands::Bundle { run, output } => {
-            println!("Bundling run {} into {}", run, output);
+        Commands::Bundle { run, output, key, policy } => {
+            bundle_run(&run, &output, &key, policy.as_deref())
+        }
+        Commands::VerifyBundle { bundle, signer, extract, to } => {
+            verify_bundle(&bundle, &signer, extract.as_deref(), &to)
+        }
+        Commands::Audit { data_dir, tenant, run, actor, action, format } => {
+            let tenant = TenantId::from_name(&tenant);
+            let run = run.map(|run| {
+                if run.starts_with("run_") { run } else { format!("run_{}", run) }
+            });
+            let query = AuditQuery {
+                run_id: run.clone(),
+                tenant: Some(tenant),
+                actor,
+                action,
+            };
+            let trail = audit_trail(Path::new(&data_dir), tenant, run.as_deref())?;
+            print!("{}", trail.filter(&query).render(format)?);
             Ok(())
         }
-        Commands::VerifyBundle { bundle } => {
-            println!("Verifying bundle: {}", bundle);
+        Commands::Cluster { server, token, format, command } => {
+            let client = ClusterClient::new(&server, token)?;
+            if !cluster(&client, command, format)? {
+                return Ok(EXIT_INVALID);
+            }
             Ok(())
         }
+        Commands::Sim { command: SimCommand::Run { scenario, runs, output } } => {
+            sim_run(&scenario, runs, output.as_deref())
+        }
+    }?;
+    Ok(0)
+}
+
+/// Run a scenario `runs` times and write the records as a `certify` bundle
+fn sim_run(path: &str, runs: usize, output: Option<&str>) -> Result<()> {
+    if runs == 0 {
+        return Err(color_eyre::eyre::eyre!("--runs must be at least 1"));
+    }
+    let scenario = Scenario::load(Path::new(path))?;
+    let output = output.map_or_else(|| format!("{}.sim.json", scenario.name), str::to_string);
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build()?;
+
+    let mut records = Vec::with_capacity(runs);
+    let mut result = None;
+    for _ in 0..runs {
+        let harness = SimHarness::from_scenario(&scenario)?;
+        let started = std::time::Instant::now();
+        let (run, mut record) = runtime.block_on(async {
+            let run = harness.run().await;
+            (run, harness.record().await)
+        });
+        record.started_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        record.duration_ms = started.elapsed().as_millis() as u64;
+        records.push(record);
+        result = Some(run);
+    }
+    std::fs::write(&output, serde_json::to_vec_pretty(&records)?)?;
+
+    let result = result.expect("at least one run");
+    println!(
+        "Simulated {} ({} runs of {} ticks, seed {}): {} events",
+        scenario.name,
+        runs,
+        result.ticks_executed,
+        result.seed().seed,
+        records[0].event_count()
+    );
+    for config in &result.nodes {
+        let name = scenario.node_name(config.node_id).unwrap_or("?");
+        let state = result.final_states.get(&config.node_id).map_or("?", String::as_str);
+        println!("  {:<16} {:<41} {}", name, config.node_id, state);
+    }
+    println!("Wrote {}", output);
+    Ok(())
+}
+
+/// Replay a trace and check it against another run's or an expected state
+/// hash
+///
+/// Returns whether the replay matched.
+fn replay(
+    bundle: &str,
+    against: Option<&str>,
+    expect: Option<&str>,
+    signer: Option<&str>,
+) -> Result<bool> {
+    let trace = load_trace(bundle, signer)?;
+    let config = ReplayConfig {
+        stop_on_error: false,
+        abi_hash: Some(cathedral_runtime::run_header().abi_hash),
+        ..ReplayConfig::default()
+    };
+    let mut engine = ReplayEngine::new().with_config(config);
+    let state = engine.replay(&mut TraceReader::from_events(trace.clone()))?;
+    let hash = state.state_hash()?.to_hex();
+    println!(
+        "Replayed {} events: {} nodes, {} completed, {} errors",
+        trace.len(),
+        state.total_nodes(),
+        state.completed_count(),
+        state.errors.len()
+    );
+    println!("State hash: {}", hash);
+
+    let mut identical = true;
+    if let Some(against) = against {
+        let other = load_trace(against, signer)?;
+        match engine.bisect_divergence(&trace, &other)? {
+            Some(report) => {
+                print_divergence(&report);
+                identical = false;
+            }
+            None => println!("Replayed state matches {}", against),
+        }
+    }
+    if let Some(expect) = expect {
+        if expect.eq_ignore_ascii_case(&hash) {
+            println!("State hash matches the expected hash");
+        } else {
+            println!("State hash differs from the expected {}", expect);
+            identical = false;
+        }
+    }
+    Ok(identical)
+}
+
+/// Load a JSON trace, or the event log of a replay bundle as a trace
+///
+/// A bundle is only trusted if it was signed by `signer`; the key it
+/// carries itself proves nothing about who made it.
+fn load_trace(path: &str, signer: Option<&str>) -> Result<Vec<TraceEvent>> {
+    let data = std::fs::read(path)?;
+    if let Ok(trace) = serde_json::from_slice(&data) {
+        return Ok(trace);
+    }
+    let mut reader = BundleReader::open(path)?;
+    let signer = signer
+        .ok_or_else(|| color_eyre::eyre::eyre!("{} is a bundle; pass --signer to trust it", path))?;
+    reader.verify_signer(&read_key(signer)?)?;
+    let events = reader.events()?;
+    Ok(events.iter().filter_map(TraceEvent::from_log).collect())
+}
+
+/// Debugger commands, printed by `help`
+const DEBUG_HELP: &str = "\
+  step|s [n]           apply the next n events (default 1)
+  back|b [n]           undo the last n events (default 1)
+  continue|c           run to the next breakpoint or the end
+  goto <position>      move to the state after the first <position> events
+  break node <id>      stop when a node starts (ID or unique prefix)
+  break kind <kind>    stop on an event kind, e.g. CapabilityCheck
+  break key <key>      stop when a state key changes
+  delete <n>           remove breakpoint n
+  breaks               list breakpoints
+  watch <key>          report changes to a state key
+  unwatch <key>        stop watching a state key
+  state                show node states and watched keys
+  where                show the current position
+  quit|q               leave the debugger
+
+State keys are global keys or <node id>.output, .error, .completed or
+.side_effects.";
+
+/// Build the execution graph of a JSON trace, taking its edges from a
+/// compiled DAG when one is given
+fn trace_graph(trace: &str, dag: Option<&str>) -> Result<ExecutionGraph> {
+    let events: Vec<TraceEvent> = serde_json::from_slice(&std::fs::read(trace)?)?;
+    let graph = build_graph(events);
+    let Some(dag) = dag else {
+        return Ok(graph);
+    };
+    let dag: Dag = serde_json::from_slice(&std::fs::read(dag)?)?;
+    Ok(graph.with_dependencies(dag.dependency_edges()))
+}
+
+/// Step through a trace interactively, reading commands from stdin
+fn debug(bundle: &str, signer: Option<&str>, checkpoint_interval: usize) -> Result<()> {
+    let trace = load_trace(bundle, signer)?;
+    let graph = build_graph(trace.clone());
+    let mut debugger = ReplayEngine::new()
+        .debug(trace)
+        .with_checkpoint_interval(checkpoint_interval);
+    println!("Debugging {} events from {}; type help for commands", debugger.len(), bundle);
+
+    loop {
+        print!("(debug {}/{}) ", debugger.position(), debugger.len());
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut line = String::new();
+        if std::io::stdin().read_line(&mut line)? == 0 {
+            println!();
+            return Ok(());
+        }
+        match debug_command(&mut debugger, &graph, line.trim()) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
+
+/// Run one debugger command; returns `false` to quit
+fn debug_command(debugger: &mut Debugger, graph: &ExecutionGraph, line: &str) -> Result<bool> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let count = |word: Option<&&str>| -> Result<usize> {
+        Ok(word.map(|n| n.parse()).transpose()?.unwrap_or(1))
+    };
+    match words.as_slice() {
+        [] => {}
+        ["step" | "s", rest @ ..] => {
+            for _ in 0..count(rest.first())? {
+                match debugger.step()? {
+                    Some(step) => print_step(debugger, &step),
+                    None => {
+                        println!("End of trace");
+                        break;
+                    }
+                }
+            }
+        }
+        ["back" | "b", rest @ ..] => {
+            for _ in 0..count(rest.first())? {
+                if !debugger.step_back()? {
+                    println!("Start of trace");
+                    break;
+                }
+            }
+            print_position(debugger);
+        }
+        ["continue" | "c"] => match debugger.resume()? {
+            Some(step) => print_step(debugger, &step),
+            None => println!("End of trace"),
+        },
+        ["goto", position] => {
+            debugger.seek(position.parse()?)?;
+            print_position(debugger);
+        }
+        ["break", kind, value] => {
+            let breakpoint = match *kind {
+                "node" => match graph.resolve(value)? {
+                    ProvenanceTarget::Node(node_id) => Breakpoint::NodeStart(node_id),
+                    ProvenanceTarget::Output(_) => {
+                        return Err(color_eyre::eyre::eyre!("{} is an output, not a node", value));
+                    }
+                },
+                "kind" => Breakpoint::EventKind((*value).to_string()),
+                "key" => Breakpoint::StateKey((*value).to_string()),
+                _ => return Err(color_eyre::eyre::eyre!("break takes node, kind or key")),
+            };
+            let id = debugger.add_breakpoint(breakpoint.clone());
+            println!("Breakpoint {}: {}", id, breakpoint);
+        }
+        ["delete", id] => match debugger.remove_breakpoint(id.parse()?) {
+            Some(breakpoint) => println!("Deleted breakpoint {}: {}", id, breakpoint),
+            None => println!("No breakpoint {}", id),
+        },
+        ["breaks"] => {
+            for (id, breakpoint) in debugger.breakpoints() {
+                println!("{:>4}  {}", id, breakpoint);
+            }
+        }
+        ["watch", key] => {
+            debugger.watch(*key);
+            println!("Watching {} = {}", key, display_value(debugger.state().value(key)));
+        }
+        ["unwatch", key] if debugger.unwatch(key) => println!("Stopped watching {}", key),
+        ["unwatch", key] => println!("Not watching {}", key),
+        ["state"] => {
+            let state = debugger.state();
+            for (node_id, node) in &state.node_outputs {
+                let status = match (&node.error, node.completed) {
+                    (Some(error), _) => format!("failed: {}", error),
+                    (None, true) => "completed".to_string(),
+                    (None, false) => "running".to_string(),
+                };
+                println!("{}  {}", node_id, status);
+            }
+            for (key, value) in debugger.watches() {
+                println!("{} = {}", key, display_value(value));
+            }
+            println!("{} errors, logical time {}", state.errors.len(), state.time());
+        }
+        ["where"] => print_position(debugger),
+        ["help"] => println!("{}", DEBUG_HELP),
+        ["quit" | "q"] => return Ok(false),
+        _ => println!("Unknown command: {}; type help for commands", line),
+    }
+    Ok(true)
+}
+
+/// Print an applied event with the breakpoints it hit and watches it changed
+fn print_step(debugger: &Debugger, step: &Step) {
+    let event = &step.event;
+    println!("#{} {} {} {}", step.index, event.time, event.node_id, event.kind.name());
+    for id in &step.hits {
+        if let Some((_, breakpoint)) = debugger.breakpoints().find(|(b, _)| b == id) {
+            println!("  hit breakpoint {}: {}", id, breakpoint);
+        }
+    }
+    for change in &step.changes {
+        println!(
+            "  {}: {} -> {}",
+            change.key,
+            display_value(change.before.clone()),
+            display_value(change.after.clone())
+        );
+    }
+}
+
+/// Print the position and the event the next step applies
+fn print_position(debugger: &Debugger) {
+    match debugger.upcoming() {
+        Some(event) => println!(
+            "At {}/{}; next #{} {} {}",
+            debugger.position(),
+            debugger.len(),
+            debugger.position(),
+            event.node_id,
+            event.kind.name()
+        ),
+        None => println!("At {}/{}; end of trace", debugger.position(), debugger.len()),
+    }
+}
+
+/// Show a state value as text if it is UTF-8, otherwise as hex
+fn display_value(value: Option<Vec<u8>>) -> String {
+    match value {
+        None => "<unset>".to_string(),
+        Some(bytes) => match String::from_utf8(bytes) {
+            Ok(text) => format!("{:?}", text),
+            Err(e) => hex::encode(e.into_bytes()),
+        },
+    }
+}
+
+/// Exit status of a replay or diff: [`EXIT_DIVERGENT`] unless the runs
+/// were identical
+fn divergence_status(identical: bool) -> i32 {
+    if identical { 0 } else { EXIT_DIVERGENT }
+}
+
+/// Compile a workflow and execute it in a local engine
+///
+/// The run is granted exactly the capabilities its nodes declare. Its event
+/// log is written to `<output>/events` and each node's output to
+/// `<output>/artifacts/<node_id>`, then a summary of every node is printed.
+fn run_workflow(file: &str, output: Option<&str>) -> Result<()> {
+    let source = std::fs::read_to_string(file)?;
+    let compiled = Compiler::new().compile(&cathedral_plan::parse(&source)?)?;
+    for warning in &compiled.warnings {
+        eprintln!("warning: {:?}", warning);
+    }
+
+    let run_id = RunId::new();
+    let output = output.map_or_else(|| Path::new("runs").join(run_id.to_string()), PathBuf::from);
+    let log_dir = output.join("events");
+    if log_dir.exists() {
+        return Err(color_eyre::eyre::eyre!("{} already holds a run", output.display()));
+    }
+    let config = EngineConfig {
+        capabilities: compiled.grants.workflow_set(),
+        grants: Some(compiled.grants.clone()),
+        ..EngineConfig::default()
+    };
+    let mut engine = ExecutionEngine::new(run_id, config);
+    let status = engine.add_dag(&compiled.dag).and_then(|()| engine.run());
+
+    // Whatever the outcome, keep what the run recorded
+    let (mut log, _) = SegmentedStream::open(SegmentConfig::new(&log_dir))?;
+    for event in engine.events() {
+        log.append(event)?;
+    }
+    std::fs::write(output.join(DAG_FILE), serde_json::to_vec(&compiled.dag)?)?;
+    let artifacts = output.join("artifacts");
+    std::fs::create_dir_all(&artifacts)?;
+    for (node_id, node_output) in engine.outputs() {
+        std::fs::write(artifacts.join(node_id.to_string()), &node_output.output)?;
+    }
+
+    let mut states: HashMap<NodeId, &str> = HashMap::new();
+    for event in engine.events() {
+        if let Some(state) = node_state(event.kind) {
+            states.insert(event.node_id, state);
+        }
+    }
+    println!("Run {} of {}", run_id, file);
+    println!("{:<41}  {:<20} {:<12} OUTPUT HASH", "NODE", "KIND", "STATUS");
+    for (node_id, node) in &compiled.dag.nodes {
+        let hash = engine
+            .get_output(*node_id)
+            .map_or_else(|| "-".to_string(), |output| output.output_hash.to_hex());
+        let state = states.get(node_id).copied().unwrap_or("pending");
+        println!("{:<41}  {:<20} {:<12} {}", node_id, node_label(&node.kind), state, hash);
+    }
+    println!("{} events logged to {}", log.len(), log_dir.display());
+
+    match status? {
+        ExecutionStatus::Success => Ok(()),
+        other => Err(color_eyre::eyre::eyre!("run {} ended as {:?}", run_id, other)),
+    }
+}
+
+/// Parse, compile and validate a workflow, printing its diagnostics
+///
+/// Returns whether the workflow is free of errors.
+fn validate_workflow(file: &str, format: ReportFormat) -> Result<bool> {
+    let source = std::fs::read_to_string(file)?;
+    let diagnostics = cathedral_plan::check(&source, &mut Compiler::new());
+    let count = |severity| diagnostics.iter().filter(|d| d.severity == severity).count();
+    let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+
+    match format {
+        ReportFormat::Json => {
+            let report = serde_json::json!({
+                "file": file,
+                "valid": errors == 0,
+                "errors": errors,
+                "warnings": warnings,
+                "diagnostics": diagnostics,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        ReportFormat::Text => {
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic.render(file, &source));
+            }
+            println!("{}: {} errors, {} warnings", file, errors, warnings);
+        }
+    }
+    Ok(errors == 0)
+}
+
+/// Pack a run directory into a signed replay bundle
+///
+/// The bundle holds the run's event log segments, its node outputs as
+/// content-addressed blobs, which the log's completion events reference by
+/// payload hash, the compiled DAG and, if given, a policy bundle.
+fn bundle_run(run: &str, output: &str, key: &str, policy: Option<&str>) -> Result<()> {
+    let dir = if Path::new(run).is_dir() {
+        PathBuf::from(run)
+    } else {
+        Path::new("runs").join(run)
+    };
+    let secret = read_key(&std::fs::read_to_string(key)?)?;
+
+    let log_dir = dir.join("events");
+    if !log_dir.is_dir() {
+        return Err(color_eyre::eyre::eyre!("no run log in {}", dir.display()));
+    }
+    let (log, _) = SegmentedStream::open(SegmentConfig::new(&log_dir))?;
+    let mut builder = BundleBuilder::new();
+    if let Some(first) = log.read(0)? {
+        builder = builder.with_run_id(first.run_id);
+    }
+    drop(log);
+    let segments = builder.add_log_dir(&log_dir)?;
+
+    let artifacts = dir.join("artifacts");
+    if artifacts.is_dir() {
+        let mut paths = std::fs::read_dir(&artifacts)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.sort();
+        for path in paths {
+            builder.add_blob(std::fs::read(&path)?);
+        }
+    }
+    let dag = dir.join(DAG_FILE);
+    if dag.is_file() {
+        let dag: Dag = serde_json::from_slice(&std::fs::read(dag)?)?;
+        builder.add_dag(&dag)?;
+    }
+    if let Some(policy) = policy {
+        builder.add_policy(&PolicyBundle::from_bytes(&std::fs::read(policy)?)?)?;
+    }
+
+    let entries = builder.len();
+    let manifest = builder.write_to_file(output, &secret)?;
+    println!(
+        "Bundled {} into {}: {} entries ({} log segments), {} bytes of data",
+        dir.display(),
+        output,
+        entries,
+        segments,
+        manifest.data_len()
+    );
+    println!("Signed by {}", manifest.signer_hex());
+    Ok(())
+}
+
+/// Verify a bundle was signed by `signer` and optionally extract one of
+/// its entries
+fn verify_bundle(bundle: &str, signer: &str, extract: Option<&str>, to: &str) -> Result<()> {
+    let mut reader = BundleReader::open(bundle)?;
+    reader.verify_signer(&read_key(signer)?)?;
+    let manifest = reader.manifest().clone();
+    println!("Bundle {} is intact, signed by {}", bundle, manifest.signer_hex());
+    if let Some(run_id) = manifest.run_id {
+        println!("Run: {}", run_id);
+    }
+    println!("{:<10} {:>10}  {:<64}  NAME", "KIND", "SIZE", "HASH");
+    for entry in &manifest.entries {
+        let kind = format!("{:?}", entry.kind);
+        println!("{:<10} {:>10}  {}  {}", kind, entry.size, entry.hash.to_hex(), entry.name);
+    }
+    println!("{} events in the bundled log", reader.events()?.len());
+
+    if let Some(name) = extract {
+        let path = reader.extract(name, to)?;
+        println!("Extracted {} to {}", name, path.display());
+    }
+    Ok(())
+}
+
+/// Parse a hex-encoded 32-byte Ed25519 key
+fn read_key(hex_key: &str) -> Result<[u8; 32]> {
+    hex::decode(hex_key.trim())?
+        .try_into()
+        .map_err(|_| color_eyre::eyre::eyre!("key must be 32 bytes of hex"))
+}
+
+/// Client of a server's `/cluster` routes
+struct ClusterClient {
+    /// `http` or `https`
+    scheme: String,
+    /// Server host and port
+    host: String,
+    /// Bearer token, if the server requires one
+    token: Option<String>,
+}
+
+impl ClusterClient {
+    /// Create a client of the server at `url`, e.g. `http://127.0.0.1:8080`
+    fn new(url: &str, token: Option<String>) -> Result<Self> {
+        let (scheme, rest) = url.split_once("://").unwrap_or(("http", url));
+        let host = rest.trim_end_matches('/');
+        if host.is_empty() || host.contains('/') {
+            return Err(color_eyre::eyre::eyre!("invalid server URL {}", url));
+        }
+        Ok(Self {
+            scheme: scheme.to_string(),
+            host: host.to_string(),
+            token,
+        })
+    }
+
+    /// Call a route and parse its JSON response
+    ///
+    /// `timeout` bounds the whole call, so it must cover the time the
+    /// server takes to answer.
+    fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        query: Vec<(String, String)>,
+        timeout: Duration,
+    ) -> Result<T> {
+        let mut headers = vec![("Accept".to_string(), "application/json".to_string())];
+        if let Some(token) = &self.token {
+            headers.push(("Authorization".to_string(), format!("Bearer {}", token)));
+        }
+        let request = HttpRequest {
+            method: method.to_string(),
+            scheme: self.scheme.clone(),
+            host: self.host.clone(),
+            path: path.to_string(),
+            query,
+            headers,
+            body: Vec::new(),
+        };
+        let response = ReqwestTransport::new(timeout)?.send(&request)?;
+        if !(200..300).contains(&response.status) {
+            let body = String::from_utf8_lossy(&response.body).into_owned();
+            let error: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+            let message = error["error"]["message"].as_str().map_or(body.clone(), str::to_string);
+            return Err(color_eyre::eyre::eyre!(
+                "{} {} failed with status {}: {}",
+                method,
+                path,
+                response.status,
+                message
+            ));
+        }
+        Ok(serde_json::from_slice(&response.body)?)
+    }
+}
+
+/// Run a `cluster` subcommand against a server
+///
+/// Returns false if a drain left tasks running on the worker.
+fn cluster(client: &ClusterClient, command: ClusterCommand, format: ReportFormat) -> Result<bool> {
+    let timeout = Duration::from_secs(30);
+    match command {
+        ClusterCommand::Status => {
+            let status: ClusterStatus = client.call("GET", "/cluster", Vec::new(), timeout)?;
+            match format {
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&status)?),
+                ReportFormat::Text => print_cluster_status(&status),
+            }
+        }
+        ClusterCommand::Members => {
+            let status: ClusterStatus = client.call("GET", "/cluster", Vec::new(), timeout)?;
+            match format {
+                ReportFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&status.members)?);
+                }
+                ReportFormat::Text => print_members(&status.members),
+            }
+        }
+        ClusterCommand::Results => {
+            let results: Vec<ExecutionResult> =
+                client.call("GET", "/cluster/results", Vec::new(), timeout)?;
+            match format {
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+                ReportFormat::Text => print_results(&results),
+            }
+        }
+        ClusterCommand::Drain { node, timeout_ms } => {
+            let path = format!("/cluster/members/{}/drain", node);
+            let query = vec![("timeout_ms".to_string(), timeout_ms.to_string())];
+            let timeout = timeout + Duration::from_millis(timeout_ms);
+            let report: DrainReport = client.call("POST", &path, query, timeout)?;
+            match format {
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                ReportFormat::Text => {
+                    println!("Draining {}", report.node_id);
+                    for task in &report.requeued {
+                        println!("  requeued  {}", task);
+                    }
+                    for task in &report.remaining {
+                        println!("  running   {}", task);
+                    }
+                    if report.deregistered {
+                        println!("Deregistered {}", report.node_id);
+                    } else {
+                        println!(
+                            "{} tasks still running after {}ms; {} stays leaving, retry to finish",
+                            report.remaining.len(),
+                            timeout_ms,
+                            report.node_id
+                        );
+                    }
+                }
+            }
+            return Ok(report.deregistered);
+        }
+        ClusterCommand::Promote { node } => {
+            let path = format!("/cluster/members/{}/promote", node);
+            let promoted: serde_json::Value = client.call("POST", &path, Vec::new(), timeout)?;
+            match format {
+                ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&promoted)?),
+                ReportFormat::Text => println!(
+                    "Proposed {} as a voter at log index {}",
+                    promoted["node_id"].as_str().unwrap_or(&node),
+                    promoted["index"]
+                ),
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Print the leader, term and health of a cluster
+fn print_cluster_status(status: &ClusterStatus) {
+    let leader = status.leader.map_or_else(|| "none".to_string(), |leader| leader.to_string());
+    let voters: Vec<String> = status.voters.iter().map(ToString::to_string).collect();
+    let health = match (status.healthy, status.reconfiguring) {
+        (true, false) => "healthy",
+        (true, true) => "healthy, reconfiguring",
+        (false, false) => "degraded",
+        (false, true) => "degraded, reconfiguring",
+    };
+    println!("Coordinator:   {}", status.node_id);
+    println!("Leader:        {}", leader);
+    println!("Term:          {}", status.term);
+    println!("Commit index:  {}", status.commit_index);
+    println!("Logical time:  {}", status.logical_time);
+    println!("Voters:        {}", voters.join(", "));
+    println!("Health:        {}", health);
+    println!("Outstanding:   {} tasks", status.outstanding);
+    println!("Members:       {}", status.members.len());
+}
+
+/// Print a table of cluster members
+fn print_members(members: &[MemberStatus]) {
+    println!(
+        "{:<41} {:<21} {:<10} {:>9} {:>9}  VOTER",
+        "NODE", "ADDRESS", "STATE", "HEARTBEAT", "IN-FLIGHT"
+    );
+    for member in members {
+        let state = format!("{:?}", member.state);
+        println!(
+            "{:<41} {:<21} {:<10} {:>9} {:>9}  {}",
+            member.node_id.to_string(),
+            member.address,
+            state,
+            member.heartbeat_age,
+            member.in_flight,
+            if member.voter { "yes" } else { "no" }
+        );
+    }
+}
+
+/// Print a table of completed task results
+fn print_results(results: &[ExecutionResult]) {
+    println!("{:>9} {:<36} {:<7} {:>9}  ERROR", "INDEX", "TASK", "STATUS", "TIME(MS)");
+    for result in results {
+        println!(
+            "{:>9} {:<36} {:<7} {:>9}  {}",
+            result.log_index,
+            result.task_id,
+            if result.success { "ok" } else { "failed" },
+            result.execution_time_ms,
+            result.error.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+/// Short description of a node for the run summary
+fn node_label(kind: &NodeKind) -> String {
+    match kind {
+        NodeKind::Tool { name, .. } => format!("tool {}", name),
+        other => {
+            let debug = format!("{:?}", other);
+            debug.split([' ', '{', '(']).next().unwrap_or_default().to_lowercase()
+        }
+    }
+}
+
+/// Status a node has after an event of `kind`, if the event changes it
+fn node_state(kind: EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::NodeStarted => Some("running"),
+        EventKind::NodeCompleted => Some("completed"),
+        EventKind::NodeFailed => Some("failed"),
+        EventKind::NodeSkipped => Some("skipped"),
+        EventKind::NodeCancelled => Some("cancelled"),
+        EventKind::Shed => Some("shed"),
+        EventKind::BudgetExceeded => Some("over_budget"),
+        _ => None,
+    }
+}
+
+/// Correlate a server's audit log with a tenant's run logs, or one run's
+fn audit_trail(
+    data_dir: &Path,
+    tenant: TenantId,
+    run: Option<&str>,
+) -> Result<AuditTrail> {
+    let read = |dir: PathBuf| -> Result<Vec<Event>> {
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let (stream, _) = SegmentedStream::open(SegmentConfig::new(dir))?;
+        Ok(stream.query(&LogQuery::default())?)
+    };
+    let runs_dir = data_dir.join("tenants").join(tenant.to_string()).join("runs");
+    let mut run_dirs = match run {
+        Some(run) => vec![runs_dir.join(run)],
+        None if runs_dir.is_dir() => std::fs::read_dir(&runs_dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    run_dirs.sort();
+
+    let mut trail = AuditTrail::new();
+    trail.add_api_calls(&read(data_dir.join("audit"))?);
+    for dir in run_dirs {
+        trail.add_execution(tenant, &read(dir)?)?;
+    }
+    Ok(trail)
+}
+
+/// Print a divergence report
+fn repair_log(log: &str, yes: bool) -> Result<()> {
+    let config = SegmentConfig::new(log);
+    let report = SegmentedStream::verify(&config)?;
+    let Some(reason) = &report.reason else {
+        println!("Log is intact ({} events)", report.valid_events);
+        return Ok(());
+    };
+    println!("Log is damaged: {}", reason);
+    println!("Valid prefix: {} events", report.valid_events);
+    println!(
+        "Repair drops {} readable events ({} bytes)",
+        report.dropped_events, report.dropped_bytes
+    );
+
+    if !yes {
+        print!("Truncate {} to its valid prefix? [y/N] ", log);
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("Aborted, log unchanged");
+            return Ok(());
+        }
+    }
+
+    let (stream, _) = SegmentedStream::repair(config)?;
+    println!("Repaired: {} events, truncation marker at {}", stream.len(), stream.len() - 1);
+    Ok(())
+}
+
+fn print_divergence(report: &DivergenceReport) {
+    println!("Runs diverge at event {}", report.index);
+    if let Some(node_id) = report.node_id {
+        println!("Node: {}", node_id);
+    }
+    if let Some(tool) = &report.tool {
+        println!("Tool: {}", tool);
+    }
+    let hex = |hash: Option<cathedral_core::Hash>| {
+        hash.map_or_else(|| "-".to_string(), |h| h.to_hex())
+    };
+    if report.left_input_hash.is_some() || report.right_input_hash.is_some() {
+        println!(
+            "Input hashes: {} vs {}",
+            hex(report.left_input_hash),
+            hex(report.right_input_hash)
+        );
+    }
+    println!("Cause: {}", report.cause);
+    println!(
+        "State before divergence: {} nodes, {} completed",
+        report.left_state.total_nodes(),
+        report.left_state.completed_count()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_core::{EventId, LogicalTime};
+    use cathedral_replay::trace::TraceEventKind;
+
+    /// Write a trace of one node that completes with `output`
+    fn write_trace(dir: &Path, name: &str, node_id: NodeId, output: &[u8]) -> String {
+        let event = |time, kind, data: &[u8]| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(time),
+            node_id,
+            kind,
+            data: data.to_vec(),
+            parent_id: None,
+        };
+        let trace = vec![
+            event(0, TraceEventKind::NodeStarted, b""),
+            event(1, TraceEventKind::NodeCompleted, output),
+        ];
+        let path = dir.join(name);
+        std::fs::write(&path, serde_json::to_vec(&trace).unwrap()).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn status(args: &[&str]) -> Result<i32> {
+        run(Cli::parse_from(std::iter::once("cathedral").chain(args.iter().copied())))
+    }
+
+    #[test]
+    fn test_replay_and_diff_exit_codes() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_id = NodeId::new();
+        let left = write_trace(dir.path(), "left.json", node_id, b"1");
+        let same = write_trace(dir.path(), "same.json", node_id, b"1");
+        let other = write_trace(dir.path(), "other.json", node_id, b"2");
+
+        assert_eq!(status(&["diff", "--left", &left, "--right", &same]).unwrap(), 0);
+        assert_eq!(status(&["diff", "--left", &left, "--right", &other]).unwrap(), 2);
+        let bisect = ["diff", "--bisect", "--left", &left, "--right", &other];
+        assert_eq!(status(&bisect).unwrap(), 2);
+
+        assert_eq!(status(&["replay", "--bundle", &left, "--against", &same]).unwrap(), 0);
+        assert_eq!(status(&["replay", "--bundle", &left, "--against", &other]).unwrap(), 2);
+        let expect = ["replay", "--bundle", &left, "--expect", "00"];
+        assert_eq!(status(&expect).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_bundles_need_a_trusted_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("events");
+        let (mut log, _) = SegmentedStream::open(SegmentConfig::new(&log_dir)).unwrap();
+        let (run_id, node_id) = (RunId::new(), NodeId::new());
+        for (time, kind) in [(0, EventKind::NodeStarted), (1, EventKind::NodeCompleted)] {
+            let time = LogicalTime::from_raw(time);
+            log.append(&Event::new(EventId::new(), run_id, node_id, time, kind)).unwrap();
+        }
+        drop(log);
+        let mut builder = BundleBuilder::new();
+        builder.add_log_dir(&log_dir).unwrap();
+        let bundle = dir.path().join("run.bundle").to_string_lossy().into_owned();
+        let signer = hex::encode(builder.write_to_file(&bundle, &[7; 32]).unwrap().signer);
+
+        assert!(status(&["diff", "--left", &bundle, "--right", &bundle]).is_err());
+        let untrusted = hex::encode([1u8; 32]);
+        let args = ["diff", "--left", &bundle, "--right", &bundle, "--signer", &untrusted];
+        assert!(status(&args).is_err());
+        let args = ["diff", "--left", &bundle, "--right", &bundle, "--signer", &signer];
+        assert_eq!(status(&args).unwrap(), 0);
     }
 }
@@ -0,0 +1,16 @@
+//! Generates the gRPC transport from `proto/cluster.proto`.
+//!
+//! Uses the `protoc` bundled by `protoc-bin-vendored` unless `PROTOC` points
+//! at another one.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto/cluster.proto");
+    println!("cargo:rerun-if-env-changed=PROTOC");
+    if std::env::var_os("PROTOC").is_none() {
+        let protoc = protoc_bin_vendored::protoc_bin_path()?;
+        // SAFETY: build scripts are single-threaded
+        unsafe { std::env::set_var("PROTOC", protoc) };
+    }
+    tonic_build::configure().compile_protos(&["proto/cluster.proto"], &["proto"])?;
+    Ok(())
+}
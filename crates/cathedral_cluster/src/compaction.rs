@@ -0,0 +1,185 @@
+//! Snapshots and log compaction for the replicated log.
+//!
+//! Once the application has applied a prefix of the log, it can capture its
+//! state as a [`ConsensusSnapshot`] and let [`Consensus::compact`] discard
+//! the entries the snapshot covers. Followers too far behind to catch up
+//! from the retained entries are sent the snapshot instead.
+//!
+//! [`Consensus::compact`]: crate::consensus::Consensus::compact
+
+use crate::configuration::ClusterConfiguration;
+use cathedral_core::{CoreError, CoreResult};
+use cathedral_storage::{ContentAddress, Snapshot, SnapshotBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// When to compact the log and how much of it to keep
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactionPolicy {
+    /// Number of retained entries above which the log should be compacted
+    pub max_log_entries: u64,
+    /// Entries kept behind the snapshot point, so followers that are only
+    /// slightly behind catch up from the log instead of the snapshot
+    pub retained_entries: u64,
+}
+
+impl CompactionPolicy {
+    /// Create a policy
+    #[must_use]
+    pub fn new(max_log_entries: u64, retained_entries: u64) -> Self {
+        Self {
+            max_log_entries,
+            retained_entries,
+        }
+    }
+
+    /// Set the log length that triggers compaction
+    #[must_use]
+    pub fn with_max_log_entries(mut self, max_log_entries: u64) -> Self {
+        self.max_log_entries = max_log_entries;
+        self
+    }
+
+    /// Set the number of entries kept behind the snapshot point
+    #[must_use]
+    pub fn with_retained_entries(mut self, retained_entries: u64) -> Self {
+        self.retained_entries = retained_entries;
+        self
+    }
+
+    /// Check if a log with `retained` entries should be compacted
+    #[must_use]
+    pub fn should_compact(&self, retained: u64) -> bool {
+        retained > self.max_log_entries
+    }
+
+    /// Index of the first entry to keep after a snapshot through `last_index`
+    #[must_use]
+    pub fn log_start(&self, last_index: u64) -> u64 {
+        last_index.saturating_sub(self.retained_entries)
+    }
+}
+
+impl Default for CompactionPolicy {
+    fn default() -> Self {
+        Self::new(10_000, 1_000)
+    }
+}
+
+/// Application state covering a prefix of the replicated log
+///
+/// The state is a [`Snapshot`] whose entries point at content-addressed
+/// blobs. The blob data travels with it, so a follower can install the
+/// snapshot without access to the leader's blob store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsensusSnapshot {
+    /// Number of log entries the snapshot covers
+    pub last_index: u64,
+    /// Term of the last covered entry
+    pub last_term: u64,
+    /// State as of `last_index`
+    pub snapshot: Snapshot,
+    /// Data of the blobs the snapshot entries point at
+    pub blobs: BTreeMap<ContentAddress, Vec<u8>>,
+    /// Cluster configuration as of `last_index`, if there is one
+    #[serde(default)]
+    pub configuration: Option<ClusterConfiguration>,
+}
+
+impl ConsensusSnapshot {
+    /// Build a snapshot of key/value state through `last_index`
+    #[must_use]
+    pub fn from_state(
+        id: String,
+        last_index: u64,
+        last_term: u64,
+        state: BTreeMap<String, Vec<u8>>,
+    ) -> Self {
+        let mut builder = SnapshotBuilder::new(id);
+        let mut blobs = BTreeMap::new();
+        for (key, data) in state {
+            let address = ContentAddress::compute(&data);
+            builder = builder.entry(key, address, data.len() as u64);
+            blobs.insert(address, data);
+        }
+
+        Self {
+            last_index,
+            last_term,
+            snapshot: builder.build(),
+            blobs,
+            configuration: None,
+        }
+    }
+
+    /// Check that every snapshot entry has blob data matching its address
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a blob is missing or does not match its address
+    pub fn verify(&self) -> CoreResult<()> {
+        self.restore().map(|_| ())
+    }
+
+    /// Recover the key/value state
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a blob is missing or does not match its address
+    pub fn restore(&self) -> CoreResult<BTreeMap<String, Vec<u8>>> {
+        self.snapshot
+            .entries
+            .values()
+            .map(|entry| {
+                let data = self
+                    .blobs
+                    .get(&entry.blob_id)
+                    .filter(|data| entry.blob_id.verify(data))
+                    .ok_or_else(|| CoreError::Validation {
+                        field: "snapshot".to_string(),
+                        reason: format!(
+                            "Missing or corrupt blob {} for {}",
+                            entry.blob_id, entry.key
+                        ),
+                    })?;
+                Ok((entry.key.clone(), data.clone()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compaction_policy() {
+        let policy = CompactionPolicy::default()
+            .with_max_log_entries(4)
+            .with_retained_entries(2);
+        assert!(!policy.should_compact(4));
+        assert!(policy.should_compact(5));
+        assert_eq!(policy.log_start(10), 8);
+        assert_eq!(policy.log_start(1), 0);
+    }
+
+    #[test]
+    fn test_consensus_snapshot_restore() {
+        let state = BTreeMap::from([
+            ("a".to_string(), b"1".to_vec()),
+            ("b".to_string(), b"2".to_vec()),
+        ]);
+        let snapshot = ConsensusSnapshot::from_state("s1".to_string(), 5, 2, state.clone());
+        assert_eq!(snapshot.restore().unwrap(), state);
+
+        let json = serde_json::to_vec(&snapshot).unwrap();
+        let decoded: ConsensusSnapshot = serde_json::from_slice(&json).unwrap();
+        assert_eq!(decoded, snapshot);
+
+        let mut tampered = snapshot;
+        for data in tampered.blobs.values_mut() {
+            data.push(0);
+        }
+        assert!(tampered.verify().is_err());
+    }
+}
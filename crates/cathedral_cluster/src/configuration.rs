@@ -0,0 +1,195 @@
+//! Cluster configurations and joint consensus.
+//!
+//! The set of voting nodes is stored in the replicated log itself, as
+//! configuration entries, so every node agrees on which acknowledgments
+//! count. A node always uses the latest configuration in its log, committed
+//! or not.
+//!
+//! Membership changes go through joint consensus: the leader first appends
+//! a [`ClusterConfiguration::Joint`] entry, under which elections and
+//! commits need a majority of both the old and the new voters, and once
+//! that entry commits it appends the [`ClusterConfiguration::Stable`] entry
+//! for the new voters. No two disjoint majorities can form during the
+//! change, so there is never more than one leader per term.
+
+use cathedral_core::NodeId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+/// Prefix marking a log entry as a configuration entry
+///
+/// Entry data without it is an encoded event.
+pub const CONFIGURATION_TAG: &[u8] = b"cathedral.cluster.configuration\0";
+
+/// Voting members of the cluster
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClusterConfiguration {
+    /// A single set of voters
+    Stable {
+        /// Voting nodes
+        voters: BTreeSet<NodeId>,
+    },
+    /// Transition between two sets of voters; decisions need both
+    Joint {
+        /// Voters before the change
+        old: BTreeSet<NodeId>,
+        /// Voters after the change
+        new: BTreeSet<NodeId>,
+    },
+}
+
+impl ClusterConfiguration {
+    /// Create a stable configuration
+    #[must_use]
+    pub fn new(voters: impl IntoIterator<Item = NodeId>) -> Self {
+        Self::Stable {
+            voters: voters.into_iter().collect(),
+        }
+    }
+
+    /// Check if this is a transitional configuration
+    #[must_use]
+    pub fn is_joint(&self) -> bool {
+        matches!(self, Self::Joint { .. })
+    }
+
+    /// Get every node with a vote, old and new
+    #[must_use]
+    pub fn voters(&self) -> BTreeSet<NodeId> {
+        match self {
+            Self::Stable { voters } => voters.clone(),
+            Self::Joint { old, new } => old.union(new).copied().collect(),
+        }
+    }
+
+    /// Check if a node has a vote
+    #[must_use]
+    pub fn contains(&self, node_id: NodeId) -> bool {
+        match self {
+            Self::Stable { voters } => voters.contains(&node_id),
+            Self::Joint { old, new } => old.contains(&node_id) || new.contains(&node_id),
+        }
+    }
+
+    /// Get the joint configuration that moves from this one to `voters`
+    ///
+    /// Returns `None` if this configuration is already joint.
+    #[must_use]
+    pub fn transition_to(&self, voters: BTreeSet<NodeId>) -> Option<Self> {
+        match self {
+            Self::Stable { voters: old } => Some(Self::Joint {
+                old: old.clone(),
+                new: voters,
+            }),
+            Self::Joint { .. } => None,
+        }
+    }
+
+    /// Get the stable configuration that ends this transition
+    ///
+    /// Returns `None` if this configuration is already stable.
+    #[must_use]
+    pub fn finish(&self) -> Option<Self> {
+        match self {
+            Self::Stable { .. } => None,
+            Self::Joint { new, .. } => Some(Self::Stable {
+                voters: new.clone(),
+            }),
+        }
+    }
+
+    /// Check if `granted` holds a majority of every voter set
+    #[must_use]
+    pub fn has_quorum(&self, granted: &HashSet<NodeId>) -> bool {
+        self.sets().iter().all(|set| {
+            set.iter().filter(|node_id| granted.contains(node_id)).count() > set.len() / 2
+        })
+    }
+
+    /// Longest log prefix held by a majority of every voter set
+    ///
+    /// `matched` gives the length of the prefix known to be held by a node.
+    #[must_use]
+    pub fn quorum_index(&self, matched: impl Fn(NodeId) -> u64) -> u64 {
+        self.sets()
+            .iter()
+            .map(|set| {
+                let mut lengths: Vec<u64> = set.iter().map(|&node_id| matched(node_id)).collect();
+                lengths.sort_unstable_by(|a, b| b.cmp(a));
+                lengths.get(set.len() / 2).copied().unwrap_or(0)
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Encode as the data of a configuration entry
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = CONFIGURATION_TAG.to_vec();
+        data.extend(serde_json::to_vec(self).unwrap_or_default());
+        data
+    }
+
+    /// Decode the data of a log entry
+    ///
+    /// Returns `None` if the entry is not a configuration entry.
+    #[must_use]
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        data.strip_prefix(CONFIGURATION_TAG)
+            .and_then(|json| serde_json::from_slice(json).ok())
+    }
+
+    /// Check if entry data holds a configuration
+    #[must_use]
+    pub fn is_configuration(data: &[u8]) -> bool {
+        data.starts_with(CONFIGURATION_TAG)
+    }
+
+    /// Voter sets that must each reach a majority
+    fn sets(&self) -> Vec<&BTreeSet<NodeId>> {
+        match self {
+            Self::Stable { voters } => vec![voters],
+            Self::Joint { old, new } => vec![old, new],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_joint_quorum() {
+        let nodes: Vec<NodeId> = (0..4).map(|_| NodeId::new()).collect();
+        let stable = ClusterConfiguration::new(nodes[..3].iter().copied());
+        let joint = stable
+            .transition_to(BTreeSet::from([nodes[2], nodes[3]]))
+            .unwrap();
+        assert!(joint.is_joint());
+        assert!(joint.transition_to(BTreeSet::new()).is_none());
+        assert_eq!(joint.voters().len(), 4);
+
+        // A majority of the old voters alone is not enough
+        let granted = HashSet::from([nodes[0], nodes[1]]);
+        assert!(stable.has_quorum(&granted));
+        assert!(!joint.has_quorum(&granted));
+        assert!(joint.has_quorum(&HashSet::from([nodes[0], nodes[2], nodes[3]])));
+
+        let matched = |node_id: NodeId| if node_id == nodes[3] { 2 } else { 5 };
+        assert_eq!(stable.quorum_index(matched), 5);
+        assert_eq!(joint.quorum_index(matched), 2);
+
+        let stable_new = joint.finish().unwrap();
+        assert!(!stable_new.contains(nodes[0]));
+        assert!(stable_new.finish().is_none());
+    }
+
+    #[test]
+    fn test_configuration_encoding() {
+        let configuration = ClusterConfiguration::new([NodeId::new(), NodeId::new()]);
+        let data = configuration.encode();
+        assert!(ClusterConfiguration::is_configuration(&data));
+        assert_eq!(ClusterConfiguration::decode(&data), Some(configuration));
+        assert_eq!(ClusterConfiguration::decode(b"event"), None);
+    }
+}
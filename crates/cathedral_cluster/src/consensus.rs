@@ -1,8 +1,12 @@
 //! Distributed consensus for replicated log.
 
+use crate::compaction::{CompactionPolicy, ConsensusSnapshot};
+use crate::configuration::ClusterConfiguration;
+use crate::storage::{ConsensusStorage, HardState, MemoryConsensusStorage, PersistentState};
 use cathedral_core::{CoreResult, CoreError, Hash, NodeId};
+use cathedral_log::{CanonicalDecode, CanonicalEncode, Event};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -17,8 +21,13 @@ pub struct ConsensusConfig {
     pub heartbeat_interval_ms: u64,
     /// Maximum log entries per message
     pub max_entries_per_msg: usize,
-    /// Quorum size
+    /// Quorum size, used until the log holds a cluster configuration
     pub quorum_size: usize,
+    /// Log compaction policy
+    pub compaction: CompactionPolicy,
+    /// Initial voters; empty to size quorums by `quorum_size` alone
+    #[serde(default)]
+    pub voters: BTreeSet<NodeId>,
 }
 
 impl ConsensusConfig {
@@ -31,6 +40,8 @@ impl ConsensusConfig {
             heartbeat_interval_ms: 100,
             max_entries_per_msg: 100,
             quorum_size: 2,
+            compaction: CompactionPolicy::default(),
+            voters: BTreeSet::new(),
         }
     }
 
@@ -54,6 +65,24 @@ impl ConsensusConfig {
         self.quorum_size = size;
         self
     }
+
+    /// Set the log compaction policy
+    #[must_use]
+    pub fn with_compaction(mut self, compaction: CompactionPolicy) -> Self {
+        self.compaction = compaction;
+        self
+    }
+
+    /// Set the initial voters
+    ///
+    /// Quorums are then majorities of the current cluster configuration,
+    /// which starts as these voters and changes through
+    /// [`Consensus::propose_configuration`].
+    #[must_use]
+    pub fn with_voters(mut self, voters: impl IntoIterator<Item = NodeId>) -> Self {
+        self.voters = voters.into_iter().collect();
+        self
+    }
 }
 
 impl Default for ConsensusConfig {
@@ -82,6 +111,167 @@ impl ConsensusEntry {
         let hash = Hash::compute(&data);
         Self { index, term, data, hash }
     }
+
+    /// Check that the entry hash matches its data
+    #[must_use]
+    pub fn verify(&self) -> bool {
+        self.hash.verify(&self.data)
+    }
+}
+
+/// Log replication request (leader -> follower)
+///
+/// Indices count entries: `prev_log_index` is the number of entries that
+/// precede `entries`, so 0 means the entries start the log, and
+/// `leader_commit` is the leader's commit index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppendEntriesRequest {
+    /// Leader's term
+    pub term: u64,
+    /// Leader's node ID
+    pub leader_id: NodeId,
+    /// Number of entries preceding `entries`
+    pub prev_log_index: u64,
+    /// Term of the entry just before `entries`, 0 if there is none
+    pub prev_log_term: u64,
+    /// Entries to replicate, empty for a heartbeat
+    pub entries: Vec<ConsensusEntry>,
+    /// Leader's commit index
+    pub leader_commit: u64,
+}
+
+/// Log replication response (follower -> leader)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppendEntriesResponse {
+    /// Follower's term, for the leader to detect that it is stale
+    pub term: u64,
+    /// Whether the follower's log matched and took the entries
+    pub success: bool,
+    /// On success, the length of the log prefix now matching the leader;
+    /// on failure, an upper bound for where the leader should retry
+    pub match_index: u64,
+}
+
+/// Vote request (candidate -> peer)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestVoteRequest {
+    /// Candidate's term
+    pub term: u64,
+    /// Candidate asking for the vote
+    pub candidate_id: NodeId,
+    /// Length of the candidate's log
+    pub last_log_index: u64,
+    /// Term of the candidate's last entry, 0 if its log is empty
+    pub last_log_term: u64,
+}
+
+/// Vote response (peer -> candidate)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestVoteResponse {
+    /// Peer's term after handling the request
+    pub term: u64,
+    /// Whether the peer voted for the candidate
+    pub vote_granted: bool,
+}
+
+/// Snapshot transfer to a follower too far behind for the retained log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallSnapshotRequest {
+    /// Leader's term
+    pub term: u64,
+    /// Leader's node ID
+    pub leader_id: NodeId,
+    /// Snapshot covering the compacted prefix of the leader's log
+    pub snapshot: ConsensusSnapshot,
+}
+
+/// Next message a leader should send to a peer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum Replication {
+    /// Entries (or a heartbeat) following the peer's next index
+    Entries(AppendEntriesRequest),
+    /// Snapshot, because the entries the peer needs were compacted
+    Snapshot(InstallSnapshotRequest),
+}
+
+/// Log entries retained after compaction
+///
+/// Entry indices are absolute: `entries[i]` has index `start + i`, and
+/// everything before `start` is covered by the snapshot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ReplicatedLog {
+    /// Index of the first retained entry
+    start: u64,
+    /// Term of the entry just before `start`, 0 if there is none
+    start_term: u64,
+    /// Retained entries
+    entries: Vec<ConsensusEntry>,
+}
+
+impl ReplicatedLog {
+    /// Length of the log, including compacted entries
+    fn len(&self) -> u64 {
+        self.start + self.entries.len() as u64
+    }
+
+    /// Get a retained entry by index
+    fn get(&self, index: u64) -> Option<&ConsensusEntry> {
+        index
+            .checked_sub(self.start)
+            .and_then(|position| self.entries.get(position as usize))
+    }
+
+    /// Term of the entry just before the first `count` entries
+    ///
+    /// Returns 0 for an empty prefix and `None` if that entry was
+    /// compacted away or is past the end of the log.
+    fn term_before(&self, count: u64) -> Option<u64> {
+        if count < self.start {
+            None
+        } else if count == self.start {
+            Some(self.start_term)
+        } else {
+            self.get(count - 1).map(|entry| entry.term)
+        }
+    }
+
+    /// Retained entries from `index` onward
+    fn suffix(&self, index: u64) -> &[ConsensusEntry] {
+        let position = (index.saturating_sub(self.start) as usize).min(self.entries.len());
+        &self.entries[position..]
+    }
+
+    /// Drop entries from `index` onward
+    fn truncate(&mut self, index: u64) {
+        self.entries.truncate(index.saturating_sub(self.start) as usize);
+    }
+
+    /// Drop entries before `index`, keeping the term of the last one dropped
+    fn compact(&mut self, index: u64) {
+        if let Some(term) = self.term_before(index).filter(|_| index > self.start) {
+            self.entries.drain(..(index - self.start) as usize);
+            self.start = index;
+            self.start_term = term;
+        }
+    }
+
+    /// Discard every entry and restart the log after a snapshot
+    fn reset(&mut self, start: u64, start_term: u64) {
+        self.entries.clear();
+        self.start = start;
+        self.start_term = start_term;
+    }
+}
+
+impl From<Vec<ConsensusEntry>> for ReplicatedLog {
+    fn from(entries: Vec<ConsensusEntry>) -> Self {
+        Self {
+            start: 0,
+            start_term: 0,
+            entries,
+        }
+    }
 }
 
 /// Consensus state
@@ -121,6 +311,19 @@ pub enum ConsensusError {
     /// Transport error
     #[error("Transport error: {0}")]
     Transport(String),
+
+    /// A membership change has not finished
+    #[error("Reconfiguration in progress")]
+    ReconfigurationInProgress,
+}
+
+impl From<ConsensusError> for CoreError {
+    fn from(err: ConsensusError) -> Self {
+        CoreError::Validation {
+            field: "consensus".to_string(),
+            reason: err.to_string(),
+        }
+    }
 }
 
 /// Distributed consensus implementation
@@ -133,8 +336,10 @@ pub struct Consensus {
     current_term: Arc<RwLock<u64>>,
     /// Voted for in this term
     voted_for: Arc<RwLock<Option<NodeId>>>,
-    /// Log entries
-    log: Arc<RwLock<Vec<ConsensusEntry>>>,
+    /// Log entries since the last compaction
+    log: Arc<RwLock<ReplicatedLog>>,
+    /// Snapshot covering the compacted prefix of the log
+    snapshot: Arc<RwLock<Option<ConsensusSnapshot>>>,
     /// Commit index
     commit_index: Arc<RwLock<u64>>,
     /// Last applied index
@@ -143,22 +348,85 @@ pub struct Consensus {
     leader_id: Arc<RwLock<Option<NodeId>>>,
     /// Votes received in current election
     votes_received: Arc<RwLock<HashSet<NodeId>>>,
+    /// Leader only: index of the next entry to send to each peer
+    next_index: Arc<RwLock<HashMap<NodeId, u64>>>,
+    /// Leader only: length of the log prefix known to match on each peer
+    match_index: Arc<RwLock<HashMap<NodeId, u64>>>,
+    /// Durable term, vote, and log
+    storage: Arc<dyn ConsensusStorage>,
 }
 
 impl Consensus {
     /// Create a new consensus instance
+    ///
+    /// State is kept in a [`MemoryConsensusStorage`] owned by this instance.
     #[must_use]
     pub fn new(config: ConsensusConfig) -> Self {
+        Self::with_state(
+            config,
+            Arc::new(MemoryConsensusStorage::new()),
+            PersistentState::default(),
+        )
+    }
+
+    /// Recover a consensus instance from storage
+    ///
+    /// The node restarts as a follower with its saved term, vote, snapshot,
+    /// and log. Entries covered by the snapshot count as committed and
+    /// applied; later commits are relearned from the leader.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the state cannot be loaded, the log is not
+    /// contiguous from its start with intact entry hashes, or the snapshot
+    /// is corrupt
+    pub fn recover(
+        config: ConsensusConfig,
+        storage: Arc<dyn ConsensusStorage>,
+    ) -> CoreResult<Self> {
+        let state = storage.load()?;
+        for (position, entry) in state.log.iter().enumerate() {
+            let index = state.log_start + position as u64;
+            if entry.index != index || !entry.verify() {
+                return Err(ConsensusError::InvalidEntry(format!(
+                    "corrupt recovered entry at index {}",
+                    index
+                ))
+                .into());
+            }
+        }
+        if let Some(snapshot) = &state.snapshot {
+            snapshot.verify()?;
+        }
+        Ok(Self::with_state(config, storage, state))
+    }
+
+    /// Build an instance from recovered state
+    fn with_state(
+        config: ConsensusConfig,
+        storage: Arc<dyn ConsensusStorage>,
+        state: PersistentState,
+    ) -> Self {
+        let applied = state.snapshot.as_ref().map_or(0, |snapshot| snapshot.last_index);
+        let log = ReplicatedLog {
+            start: state.log_start,
+            start_term: state.log_start_term,
+            entries: state.log,
+        };
         Self {
             config,
             state: Arc::new(RwLock::new(ConsensusState::Follower)),
-            current_term: Arc::new(RwLock::new(0)),
-            voted_for: Arc::new(RwLock::new(None)),
-            log: Arc::new(RwLock::new(Vec::new())),
-            commit_index: Arc::new(RwLock::new(0)),
-            last_applied: Arc::new(RwLock::new(0)),
+            current_term: Arc::new(RwLock::new(state.hard_state.current_term)),
+            voted_for: Arc::new(RwLock::new(state.hard_state.voted_for)),
+            log: Arc::new(RwLock::new(log)),
+            snapshot: Arc::new(RwLock::new(state.snapshot)),
+            commit_index: Arc::new(RwLock::new(applied)),
+            last_applied: Arc::new(RwLock::new(applied)),
             leader_id: Arc::new(RwLock::new(None)),
             votes_received: Arc::new(RwLock::new(HashSet::new())),
+            next_index: Arc::new(RwLock::new(HashMap::new())),
+            match_index: Arc::new(RwLock::new(HashMap::new())),
+            storage,
         }
     }
 
@@ -203,25 +471,85 @@ impl Consensus {
             });
         }
 
-        let mut log = self.log.write().await;
-        let index = log.len() as u64;
         let term = *self.current_term.read().await;
+        let mut log = self.log.write().await;
+        let index = log.len();
         let entry = ConsensusEntry::new(index, term, data);
-        log.push(entry);
+        self.storage.save_log(index, std::slice::from_ref(&entry))?;
+        log.entries.push(entry);
         Ok(index)
     }
 
+    /// Append an event to the replicated log
+    ///
+    /// The returned log index becomes the event's global sequence once
+    /// the entry is committed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not leader
+    pub async fn append_event(&self, event: &Event) -> CoreResult<u64> {
+        self.append(event.encode()).await
+    }
+
+    /// Get committed events in commit order, each stamped with its global sequence
+    ///
+    /// An entry is committed once its index is below the commit index, so
+    /// the sequence is identical on every node that has applied the same log.
+    /// Configuration entries and entries already compacted into the snapshot
+    /// are not included.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a committed entry does not decode as an event
+    pub async fn committed_events(&self) -> CoreResult<Vec<Event>> {
+        self.committed_events_since(0).await
+    }
+
+    /// Get the committed events at log index `from` and later
+    ///
+    /// Lets an application apply the log incrementally; see
+    /// [`Consensus::committed_events`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a committed entry does not decode as an event
+    pub async fn committed_events_since(&self, from: u64) -> CoreResult<Vec<Event>> {
+        let commit_index = *self.commit_index.read().await;
+        let log = self.log.read().await;
+
+        log.suffix(from)
+            .iter()
+            .filter(|entry| {
+                entry.index < commit_index && !ClusterConfiguration::is_configuration(&entry.data)
+            })
+            .map(|entry| {
+                Event::decode(&entry.data)
+                    .map(|event| event.with_global_seq(entry.index))
+                    .map_err(|_| CoreError::InvalidEncoding)
+            })
+            .collect()
+    }
+
     /// Request a vote from this node
     ///
+    /// `last_log_index` is the length of the candidate's log and
+    /// `last_log_term` the term of its last entry, as returned by
+    /// [`Consensus::last_log`]. Following the Raft election restriction, the
+    /// vote is refused unless the candidate's log is at least as up to date
+    /// as this node's: a later last term wins, and equal terms compare
+    /// lengths. The term and vote are saved to storage before the answer is
+    /// returned.
+    ///
     /// # Errors
     ///
-    /// Returns error if vote cannot be granted
+    /// Returns error if storage fails
     pub async fn request_vote(
         &self,
         candidate_id: NodeId,
         term: u64,
-        _last_log_index: u64,
-        _last_log_term: u64,
+        last_log_index: u64,
+        last_log_term: u64,
     ) -> CoreResult<bool> {
         let mut current_term = self.current_term.write().await;
 
@@ -236,47 +564,529 @@ impl Consensus {
             *self.voted_for.write().await = None;
         }
 
+        let (len, last_term) = self.last_log().await;
+        let up_to_date = (last_log_term, last_log_index) >= (last_term, len);
         let mut voted_for = self.voted_for.write().await;
-        if voted_for.is_none() || *voted_for == Some(candidate_id) {
+        let granted = up_to_date && (voted_for.is_none() || *voted_for == Some(candidate_id));
+        if granted {
             *voted_for = Some(candidate_id);
-            Ok(true)
-        } else {
-            Ok(false)
         }
+        self.storage.save_hard_state(&HardState {
+            current_term: *current_term,
+            voted_for: *voted_for,
+        })?;
+        Ok(granted)
+    }
+
+    /// Answer a vote request received from a peer
+    ///
+    /// Decides as [`Consensus::request_vote`] does and reports this node's
+    /// term alongside the decision, so a stale candidate learns of it.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if storage fails
+    pub async fn vote(&self, request: &RequestVoteRequest) -> CoreResult<RequestVoteResponse> {
+        let vote_granted = self
+            .request_vote(
+                request.candidate_id,
+                request.term,
+                request.last_log_index,
+                request.last_log_term,
+            )
+            .await?;
+        Ok(RequestVoteResponse {
+            term: self.current_term().await,
+            vote_granted,
+        })
     }
 
     /// Append entries to the log (leader -> follower)
     ///
+    /// Applies the Raft log matching rules: the request is rejected unless
+    /// the log holds an entry at `prev_log_index` with `prev_log_term`;
+    /// an existing entry that conflicts with a new one is truncated along
+    /// with everything after it; and the commit index follows the leader's,
+    /// capped at the last new entry. A new term and any log changes are
+    /// saved to storage before the response is returned.
+    ///
     /// # Errors
     ///
-    /// Returns error if replication fails
+    /// Returns error if the entries are not contiguous from `prev_log_index`,
+    /// an entry hash does not match its data, a committed entry conflicts,
+    /// or storage fails
     pub async fn append_entries(
         &self,
-        term: u64,
-        _prev_log_index: u64,
-        _prev_log_term: u64,
-        entries: Vec<ConsensusEntry>,
-        _leader_commit: u64,
-    ) -> CoreResult<bool> {
+        request: AppendEntriesRequest,
+    ) -> CoreResult<AppendEntriesResponse> {
         let mut current_term = self.current_term.write().await;
 
-        if term < *current_term {
-            return Ok(false);
+        if request.term < *current_term {
+            let match_index = self.log.read().await.len();
+            return Ok(AppendEntriesResponse {
+                term: *current_term,
+                success: false,
+                match_index,
+            });
+        }
+        let term = self
+            .follow_leader(&mut current_term, request.term, request.leader_id)
+            .await?;
+
+        for (offset, entry) in request.entries.iter().enumerate() {
+            let expected = request.prev_log_index + offset as u64;
+            if entry.index != expected {
+                return Err(ConsensusError::InvalidEntry(format!(
+                    "expected index {}, got {}",
+                    expected, entry.index
+                ))
+                .into());
+            }
+            if !entry.verify() {
+                return Err(ConsensusError::InvalidEntry(format!(
+                    "hash mismatch at index {}",
+                    entry.index
+                ))
+                .into());
+            }
+        }
+
+        let mut log = self.log.write().await;
+        let len = log.len();
+        if request.prev_log_index > len {
+            return Ok(AppendEntriesResponse {
+                term,
+                success: false,
+                match_index: len,
+            });
+        }
+        // A compacted previous entry is committed, so it matches the leader's
+        if log
+            .term_before(request.prev_log_index)
+            .is_some_and(|prev_term| prev_term != request.prev_log_term)
+        {
+            return Ok(AppendEntriesResponse {
+                term,
+                success: false,
+                match_index: request.prev_log_index - 1,
+            });
+        }
+
+        let mut commit_index = self.commit_index.write().await;
+        let last_new = request.prev_log_index + request.entries.len() as u64;
+        let mut first_change = None;
+        for entry in request.entries {
+            if entry.index < log.start {
+                continue;
+            }
+            match log.get(entry.index) {
+                Some(existing) if existing.term == entry.term => continue,
+                Some(_) if entry.index < *commit_index => {
+                    return Err(ConsensusError::LogConflict { index: entry.index }.into());
+                }
+                Some(_) => log.truncate(entry.index),
+                None => {}
+            }
+            first_change.get_or_insert(entry.index);
+            log.entries.push(entry);
+        }
+        if let Some(from) = first_change {
+            self.storage.save_log(from, log.suffix(from))?;
         }
 
+        // A request covering fewer entries than already committed, such as
+        // a delayed one, never moves the commit index back
+        *commit_index = (*commit_index).max(request.leader_commit.min(last_new));
+
+        Ok(AppendEntriesResponse {
+            term,
+            success: true,
+            match_index: last_new,
+        })
+    }
+
+    /// Install a snapshot sent by the leader (leader -> follower)
+    ///
+    /// If the log already holds the snapshot's last entry, the entries
+    /// after it are kept and the prefix is compacted per the compaction
+    /// policy; otherwise the whole log is replaced by the snapshot. The
+    /// commit and applied indices advance to the snapshot, and the
+    /// snapshot is saved to storage before the response is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the snapshot's blobs do not match their addresses,
+    /// or storage fails
+    pub async fn install_snapshot(
+        &self,
+        request: InstallSnapshotRequest,
+    ) -> CoreResult<AppendEntriesResponse> {
+        let mut current_term = self.current_term.write().await;
+        if request.term < *current_term {
+            let match_index = self.log.read().await.len();
+            return Ok(AppendEntriesResponse {
+                term: *current_term,
+                success: false,
+                match_index,
+            });
+        }
+        let term = self
+            .follow_leader(&mut current_term, request.term, request.leader_id)
+            .await?;
+
+        let snapshot = request.snapshot;
+        let last_index = snapshot.last_index;
+        let mut log = self.log.write().await;
+        let mut current = self.snapshot.write().await;
+        if current.as_ref().is_some_and(|current| current.last_index >= last_index) {
+            return Ok(AppendEntriesResponse {
+                term,
+                success: true,
+                match_index: last_index,
+            });
+        }
+        snapshot.verify()?;
+
+        let keeps_suffix = last_index <= log.len()
+            && log.term_before(last_index) == Some(snapshot.last_term);
+        if keeps_suffix {
+            log.compact(self.config.compaction.log_start(last_index));
+        } else {
+            log.reset(last_index, snapshot.last_term);
+        }
+        self.storage.save_snapshot(&snapshot, log.start, log.start_term)?;
+        if !keeps_suffix {
+            self.storage.save_log(last_index, &[])?;
+        }
+        *current = Some(snapshot);
+
+        let mut commit_index = self.commit_index.write().await;
+        *commit_index = (*commit_index).max(last_index);
+        let mut last_applied = self.last_applied.write().await;
+        *last_applied = (*last_applied).max(last_index);
+
+        Ok(AppendEntriesResponse {
+            term,
+            success: true,
+            match_index: last_index,
+        })
+    }
+
+    /// Adopt the term of a current leader and follow it
+    ///
+    /// Returns the term now in effect.
+    async fn follow_leader(
+        &self,
+        current_term: &mut u64,
+        term: u64,
+        leader_id: NodeId,
+    ) -> CoreResult<u64> {
         if term > *current_term {
             *current_term = term;
-            *self.state.write().await = ConsensusState::Follower;
+            *self.voted_for.write().await = None;
+            self.storage.save_hard_state(&HardState {
+                current_term: term,
+                voted_for: None,
+            })?;
         }
 
-        *self.leader_id.write().await = Some(self.config.node_id);
+        // A leader holds this term, so candidates and stale leaders step down
+        *self.state.write().await = ConsensusState::Follower;
+        *self.leader_id.write().await = Some(leader_id);
+        Ok(*current_term)
+    }
 
-        if !entries.is_empty() {
-            let mut log = self.log.write().await;
-            log.extend(entries);
+    /// Build the next replication request for a peer
+    ///
+    /// Sends entries from the peer's next index, at most
+    /// `max_entries_per_msg` at a time, or the snapshot if those entries
+    /// were compacted. A peer not seen since this node became leader starts
+    /// at the end of the log.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not leader
+    pub async fn replication_request(&self, peer: NodeId) -> CoreResult<Replication> {
+        let term = *self.current_term.read().await;
+        if *self.state.read().await != ConsensusState::Leader {
+            return Err(ConsensusError::NotLeader.into());
         }
 
-        Ok(true)
+        let log = self.log.read().await;
+        let len = log.len();
+        let next = (*self.next_index.write().await.entry(peer).or_insert(len)).min(len);
+        let Some(prev_log_term) = log.term_before(next) else {
+            let snapshot = self.snapshot.read().await.clone().ok_or_else(|| {
+                ConsensusError::InvalidEntry("log compacted without a snapshot".to_string())
+            })?;
+            return Ok(Replication::Snapshot(InstallSnapshotRequest {
+                term,
+                leader_id: self.config.node_id,
+                snapshot,
+            }));
+        };
+        let entries = log
+            .suffix(next)
+            .iter()
+            .take(self.config.max_entries_per_msg)
+            .cloned()
+            .collect();
+
+        Ok(Replication::Entries(AppendEntriesRequest {
+            term,
+            leader_id: self.config.node_id,
+            prev_log_index: next,
+            prev_log_term,
+            entries,
+            leader_commit: *self.commit_index.read().await,
+        }))
+    }
+
+    /// Handle a peer's reply to a replication request
+    ///
+    /// A reply from a later term demotes this node to follower. On success
+    /// the peer's match index advances and the commit index is recomputed;
+    /// on failure the peer's next index backs off toward the reply's hint.
+    /// Returns the commit index after the reply is applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if lock acquisition fails
+    pub async fn handle_append_response(
+        &self,
+        peer: NodeId,
+        response: AppendEntriesResponse,
+    ) -> CoreResult<u64> {
+        {
+            let mut current_term = self.current_term.write().await;
+            if response.term > *current_term {
+                *current_term = response.term;
+                *self.voted_for.write().await = None;
+                self.storage.save_hard_state(&HardState {
+                    current_term: response.term,
+                    voted_for: None,
+                })?;
+                drop(current_term);
+                self.become_follower().await;
+                return Ok(self.commit_index().await);
+            }
+            if response.term < *current_term {
+                return Ok(self.commit_index().await);
+            }
+        }
+        if *self.state.read().await != ConsensusState::Leader {
+            return Ok(self.commit_index().await);
+        }
+
+        let len = self.log.read().await.len();
+        let mut next_index = self.next_index.write().await;
+        let mut match_index = self.match_index.write().await;
+        if response.success {
+            let matched = match_index.entry(peer).or_insert(0);
+            *matched = (*matched).max(response.match_index.min(len));
+            next_index.insert(peer, *matched);
+        } else {
+            let next = next_index.entry(peer).or_insert(len);
+            *next = next.saturating_sub(1).min(response.match_index);
+        }
+        drop(match_index);
+        drop(next_index);
+
+        self.advance_commit().await
+    }
+
+    /// Advance the commit index to the longest prefix held by a quorum
+    ///
+    /// Counts this node's own log alongside peer match indices, and only
+    /// commits through an entry from the current term, so the result
+    /// depends only on the acknowledgments received. Under a cluster
+    /// configuration only voters count, and a joint configuration needs a
+    /// majority of both voter sets.
+    ///
+    /// Committing a joint configuration appends the stable configuration
+    /// that ends it; committing a configuration that excludes this node
+    /// makes it step down. Returns the new commit index.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not leader, or the stable configuration cannot be
+    /// appended
+    pub async fn advance_commit(&self) -> CoreResult<u64> {
+        let term = *self.current_term.read().await;
+        if *self.state.read().await != ConsensusState::Leader {
+            return Err(ConsensusError::NotLeader.into());
+        }
+
+        let log = self.log.read().await;
+        let configuration = self.latest_configuration(&log).await;
+        let match_index = self.match_index.read().await;
+        let quorum = match &configuration {
+            Some((_, configuration)) => Some(configuration.quorum_index(|node_id| {
+                if node_id == self.config.node_id {
+                    log.len()
+                } else {
+                    match_index.get(&node_id).copied().unwrap_or(0)
+                }
+            })),
+            None => {
+                let mut matched: Vec<u64> = match_index.values().copied().collect();
+                matched.push(log.len());
+                matched.sort_unstable_by(|a, b| b.cmp(a));
+                matched.get(self.config.quorum_size.saturating_sub(1)).copied()
+            }
+        };
+        drop(match_index);
+
+        let mut commit_index = self.commit_index.write().await;
+        if let Some(candidate) = quorum.filter(|&candidate| {
+            candidate > *commit_index
+                && log
+                    .get(candidate - 1)
+                    .is_some_and(|entry| entry.term == term)
+        }) {
+            *commit_index = candidate;
+        }
+        let committed = *commit_index;
+        drop(commit_index);
+        drop(log);
+
+        match configuration {
+            Some((Some(index), configuration)) if index < committed => {
+                if let Some(stable) = configuration.finish() {
+                    self.append(stable.encode()).await?;
+                } else if !configuration.contains(self.config.node_id) {
+                    tracing::info!(node_id = %self.config.node_id, "removed from cluster");
+                    self.become_follower().await;
+                }
+            }
+            _ => {}
+        }
+        Ok(committed)
+    }
+
+    /// Get the current cluster configuration
+    ///
+    /// This is the latest configuration in the log, committed or not, or
+    /// the initial voters if the log holds none. Returns `None` if neither
+    /// exists, in which case quorums are sized by the configured quorum
+    /// size.
+    pub async fn configuration(&self) -> Option<ClusterConfiguration> {
+        let log = self.log.read().await;
+        self.latest_configuration(&log)
+            .await
+            .map(|(_, configuration)| configuration)
+    }
+
+    /// Check if a membership change is under way
+    ///
+    /// True while the current configuration is joint or not yet committed.
+    /// Writes that assume a settled membership should wait until it ends.
+    pub async fn reconfiguration_in_progress(&self) -> bool {
+        let log = self.log.read().await;
+        let commit_index = *self.commit_index.read().await;
+        self.latest_configuration(&log)
+            .await
+            .is_some_and(|(index, configuration)| {
+                configuration.is_joint() || index.is_some_and(|index| index >= commit_index)
+            })
+    }
+
+    /// Propose a new set of voters
+    ///
+    /// Appends the joint configuration moving from the current voters to
+    /// `voters`; once it commits, [`Self::advance_commit`] appends the
+    /// stable configuration that completes the change. Returns the index
+    /// of the joint entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not leader, `voters` is empty, there is no current
+    /// configuration to change, or another change is in progress
+    pub async fn propose_configuration(&self, voters: BTreeSet<NodeId>) -> CoreResult<u64> {
+        if *self.state.read().await != ConsensusState::Leader {
+            return Err(ConsensusError::NotLeader.into());
+        }
+        if voters.is_empty() {
+            return Err(ConsensusError::InvalidEntry(
+                "a configuration needs at least one voter".to_string(),
+            )
+            .into());
+        }
+        if self.reconfiguration_in_progress().await {
+            return Err(ConsensusError::ReconfigurationInProgress.into());
+        }
+
+        let current = self.configuration().await.ok_or_else(|| {
+            ConsensusError::InvalidEntry("no cluster configuration to change".to_string())
+        })?;
+        let joint = current
+            .transition_to(voters)
+            .ok_or(ConsensusError::ReconfigurationInProgress)?;
+        self.append(joint.encode()).await
+    }
+
+    /// Get the voters other than this node, in node ID order
+    pub async fn peers(&self) -> Vec<NodeId> {
+        self.configuration()
+            .await
+            .map(|configuration| {
+                configuration
+                    .voters()
+                    .into_iter()
+                    .filter(|&node_id| node_id != self.config.node_id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Latest configuration in `log`, with the index of its entry
+    async fn latest_configuration(
+        &self,
+        log: &ReplicatedLog,
+    ) -> Option<(Option<u64>, ClusterConfiguration)> {
+        let snapshot = self.snapshot.read().await;
+        self.configuration_before(log, snapshot.as_ref(), log.len())
+    }
+
+    /// Latest configuration among the first `end` log entries
+    ///
+    /// Falls back to the snapshot's configuration, then to the initial
+    /// voters. The index of the configuration entry is given while the
+    /// entry is still in the log.
+    fn configuration_before(
+        &self,
+        log: &ReplicatedLog,
+        snapshot: Option<&ConsensusSnapshot>,
+        end: u64,
+    ) -> Option<(Option<u64>, ClusterConfiguration)> {
+        log.entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.index < end)
+            .find_map(|entry| {
+                ClusterConfiguration::decode(&entry.data)
+                    .map(|configuration| (Some(entry.index), configuration))
+            })
+            .or_else(|| {
+                snapshot
+                    .and_then(|snapshot| snapshot.configuration.clone())
+                    .map(|configuration| (None, configuration))
+            })
+            .or_else(|| {
+                (!self.config.voters.is_empty()).then(|| {
+                    (None, ClusterConfiguration::new(self.config.voters.iter().copied()))
+                })
+            })
+    }
+
+    /// Get the next index to send to a peer, if tracked
+    pub async fn next_index(&self, peer: NodeId) -> Option<u64> {
+        self.next_index.read().await.get(&peer).copied()
+    }
+
+    /// Get the match index of a peer, if tracked
+    pub async fn match_index(&self, peer: NodeId) -> Option<u64> {
+        self.match_index.read().await.get(&peer).copied()
     }
 
     /// Start an election
@@ -292,6 +1102,10 @@ impl Consensus {
         *state = ConsensusState::Candidate;
         *self.leader_id.write().await = None;
         *self.voted_for.write().await = Some(self.config.node_id);
+        self.storage.save_hard_state(&HardState {
+            current_term: *term,
+            voted_for: Some(self.config.node_id),
+        })?;
 
         self.votes_received.write().await.clear();
         self.votes_received.write().await.insert(self.config.node_id);
@@ -319,9 +1133,15 @@ impl Consensus {
         let mut votes = self.votes_received.write().await;
         votes.insert(voter_id);
 
-        if votes.len() >= self.config.quorum_size {
+        let won = match self.configuration().await {
+            Some(configuration) => configuration.has_quorum(&votes),
+            None => votes.len() >= self.config.quorum_size,
+        };
+        if won {
             *self.state.write().await = ConsensusState::Leader;
             *self.leader_id.write().await = Some(self.config.node_id);
+            self.next_index.write().await.clear();
+            self.match_index.write().await.clear();
             Ok(true)
         } else {
             Ok(false)
@@ -350,13 +1170,114 @@ impl Consensus {
         Ok(())
     }
 
-    /// Get the log length
+    /// Get the log length, including compacted entries
     ///
     /// # Errors
     ///
     /// Returns error if lock acquisition fails
     pub async fn log_len(&self) -> usize {
-        self.log.read().await.len()
+        self.log.read().await.len() as usize
+    }
+
+    /// Get the log length and the term of the last entry, 0 if there is none
+    ///
+    /// A candidate sends these in its vote requests.
+    pub async fn last_log(&self) -> (u64, u64) {
+        let log = self.log.read().await;
+        let len = log.len();
+        (len, log.term_before(len).unwrap_or(0))
+    }
+
+    /// Get the term of the entry before log position `index`, 0 for the
+    /// start of the log
+    ///
+    /// Returns `None` if that entry was compacted away.
+    pub async fn term_before(&self, index: u64) -> Option<u64> {
+        self.log.read().await.term_before(index)
+    }
+
+    /// Get the number of entries retained since the last compaction
+    pub async fn retained_len(&self) -> usize {
+        self.log.read().await.entries.len()
+    }
+
+    /// Get the index up to which the application has applied the log
+    pub async fn last_applied(&self) -> u64 {
+        *self.last_applied.read().await
+    }
+
+    /// Get the latest snapshot, if the log was compacted
+    pub async fn snapshot(&self) -> Option<ConsensusSnapshot> {
+        self.snapshot.read().await.clone()
+    }
+
+    /// Index through which the application should snapshot its state
+    ///
+    /// Returns the commit index once the retained log has outgrown the
+    /// compaction policy and there are committed entries not yet covered
+    /// by a snapshot. Pass the resulting snapshot to [`Self::compact`].
+    pub async fn compaction_point(&self) -> Option<u64> {
+        let log = self.log.read().await;
+        let covered = self.snapshot.read().await.as_ref().map_or(0, |s| s.last_index);
+        let commit_index = *self.commit_index.read().await;
+        Some(commit_index).filter(|&commit_index| {
+            self.config.compaction.should_compact(log.entries.len() as u64)
+                && commit_index > covered
+        })
+    }
+
+    /// Compact the log behind a snapshot of the application state
+    ///
+    /// Entries before the snapshot point, less the policy's retained
+    /// entries, are discarded. The snapshot records the cluster
+    /// configuration as of its last entry, is saved to storage first, and
+    /// becomes what lagging followers are sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the snapshot covers uncommitted entries, is not
+    /// newer than the current one, does not match the log's term at its
+    /// last index, has corrupt blobs, or storage fails
+    pub async fn compact(&self, mut snapshot: ConsensusSnapshot) -> CoreResult<()> {
+        let mut log = self.log.write().await;
+        let mut current = self.snapshot.write().await;
+        let last_index = snapshot.last_index;
+
+        if last_index > *self.commit_index.read().await {
+            return Err(ConsensusError::InvalidEntry(format!(
+                "snapshot through {} covers uncommitted entries",
+                last_index
+            ))
+            .into());
+        }
+        if current.as_ref().is_some_and(|current| current.last_index >= last_index) {
+            return Err(ConsensusError::InvalidEntry(format!(
+                "snapshot through {} is not newer than the current one",
+                last_index
+            ))
+            .into());
+        }
+        if log.term_before(last_index) != Some(snapshot.last_term) {
+            return Err(ConsensusError::InvalidEntry(format!(
+                "snapshot term {} does not match the log at {}",
+                snapshot.last_term, last_index
+            ))
+            .into());
+        }
+        snapshot.verify()?;
+        if snapshot.configuration.is_none() {
+            snapshot.configuration = self
+                .configuration_before(&log, current.as_ref(), last_index)
+                .map(|(_, configuration)| configuration);
+        }
+
+        log.compact(self.config.compaction.log_start(last_index));
+        self.storage.save_snapshot(&snapshot, log.start, log.start_term)?;
+        *current = Some(snapshot);
+
+        let mut last_applied = self.last_applied.write().await;
+        *last_applied = (*last_applied).max(last_index);
+        Ok(())
     }
 
     /// Become a follower
@@ -375,6 +1296,34 @@ impl Default for Consensus {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::BTreeMap;
+
+    fn request(
+        leader_id: NodeId,
+        term: u64,
+        prev_log_index: u64,
+        prev_log_term: u64,
+        entries: Vec<ConsensusEntry>,
+        leader_commit: u64,
+    ) -> AppendEntriesRequest {
+        AppendEntriesRequest {
+            term,
+            leader_id,
+            prev_log_index,
+            prev_log_term,
+            entries,
+            leader_commit,
+        }
+    }
+
+    /// Run one replication round trip from `leader` to `follower`
+    async fn replicate(leader: &Consensus, peer: NodeId, follower: &Consensus) {
+        let response = match leader.replication_request(peer).await.unwrap() {
+            Replication::Entries(request) => follower.append_entries(request).await.unwrap(),
+            Replication::Snapshot(request) => follower.install_snapshot(request).await.unwrap(),
+        };
+        leader.handle_append_response(peer, response).await.unwrap();
+    }
 
     #[tokio::test]
     async fn test_consensus_new() {
@@ -439,6 +1388,36 @@ mod tests {
         assert!(!granted);
     }
 
+    #[tokio::test]
+    async fn test_request_vote_refuses_stale_candidate() {
+        let consensus = Consensus::new(ConsensusConfig::new(NodeId::new()));
+        consensus
+            .append_entries(AppendEntriesRequest {
+                term: 2,
+                leader_id: NodeId::new(),
+                prev_log_index: 0,
+                prev_log_term: 0,
+                entries: vec![
+                    ConsensusEntry::new(0, 1, b"a".to_vec()),
+                    ConsensusEntry::new(1, 2, b"b".to_vec()),
+                ],
+                leader_commit: 0,
+            })
+            .await
+            .unwrap();
+        assert_eq!(consensus.last_log().await, (2, 2));
+
+        // Older last term, even with a longer log
+        assert!(!consensus.request_vote(NodeId::new(), 3, 5, 1).await.unwrap());
+        // Same last term but a shorter log
+        assert!(!consensus.request_vote(NodeId::new(), 3, 1, 2).await.unwrap());
+        // The refusals did not spend the vote for term 3
+        let candidate = NodeId::new();
+        assert!(consensus.request_vote(candidate, 3, 2, 2).await.unwrap());
+        assert!(!consensus.request_vote(NodeId::new(), 3, 4, 3).await.unwrap());
+        assert_eq!(consensus.current_term().await, 3);
+    }
+
     #[tokio::test]
     async fn test_start_election() {
         let config = ConsensusConfig::new(NodeId::new());
@@ -491,10 +1470,245 @@ mod tests {
         let config = ConsensusConfig::new(NodeId::new());
         let consensus = Consensus::new(config);
 
+        let leader_id = NodeId::new();
         let entries = vec![ConsensusEntry::new(0, 1, b"data".to_vec())];
-        let success = consensus.append_entries(1, 0, 0, entries, 0).await.unwrap();
-        assert!(success);
+        let response = consensus
+            .append_entries(request(leader_id, 1, 0, 0, entries, 0))
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.match_index, 1);
         assert_eq!(consensus.log_len().await, 1);
+        assert_eq!(consensus.leader_id().await, Some(leader_id));
+    }
+
+    #[tokio::test]
+    async fn test_append_entries_log_matching() {
+        let consensus = Consensus::new(ConsensusConfig::new(NodeId::new()));
+        let leader_id = NodeId::new();
+
+        // Missing the entry before the new ones
+        let entries = vec![ConsensusEntry::new(2, 1, b"c".to_vec())];
+        let response = consensus
+            .append_entries(request(leader_id, 1, 2, 1, entries, 0))
+            .await
+            .unwrap();
+        assert!(!response.success);
+        assert_eq!(response.match_index, 0);
+
+        // Stale term
+        *consensus.current_term.write().await = 3;
+        let response = consensus
+            .append_entries(request(leader_id, 2, 0, 0, Vec::new(), 0))
+            .await
+            .unwrap();
+        assert!(!response.success);
+        assert_eq!(response.term, 3);
+
+        // Previous entry has the wrong term
+        *consensus.log.write().await = vec![
+            ConsensusEntry::new(0, 1, b"a".to_vec()),
+            ConsensusEntry::new(1, 1, b"b".to_vec()),
+        ].into();
+        let response = consensus
+            .append_entries(request(leader_id, 3, 2, 2, Vec::new(), 0))
+            .await
+            .unwrap();
+        assert!(!response.success);
+        assert_eq!(response.match_index, 1);
+
+        // Non-contiguous and corrupted entries are refused
+        let gap = vec![ConsensusEntry::new(3, 3, b"x".to_vec())];
+        assert!(consensus.append_entries(request(leader_id, 3, 1, 1, gap, 0)).await.is_err());
+        let mut corrupt = ConsensusEntry::new(1, 3, b"x".to_vec());
+        corrupt.data = b"y".to_vec();
+        assert!(consensus
+            .append_entries(request(leader_id, 3, 1, 1, vec![corrupt], 0))
+            .await
+            .is_err());
+        assert_eq!(consensus.log_len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_append_entries_never_lowers_commit_index() {
+        let consensus = Consensus::new(ConsensusConfig::new(NodeId::new()));
+        let leader_id = NodeId::new();
+
+        let entries = (0..3)
+            .map(|index| ConsensusEntry::new(index, 1, vec![index as u8]))
+            .collect();
+        let response = consensus
+            .append_entries(request(leader_id, 1, 0, 0, entries, 2))
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(consensus.commit_index().await, 2);
+
+        // A delayed heartbeat ending before the committed entries
+        let response = consensus
+            .append_entries(request(leader_id, 1, 1, 1, Vec::new(), 3))
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(consensus.commit_index().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_append_entries_truncates_conflicts() {
+        let consensus = Consensus::new(ConsensusConfig::new(NodeId::new()));
+        let leader_id = NodeId::new();
+        *consensus.log.write().await = vec![
+            ConsensusEntry::new(0, 1, b"a".to_vec()),
+            ConsensusEntry::new(1, 1, b"stale".to_vec()),
+            ConsensusEntry::new(2, 1, b"stale".to_vec()),
+        ].into();
+
+        // Re-sending a matching entry leaves the log alone
+        let entries = vec![ConsensusEntry::new(0, 1, b"a".to_vec())];
+        let response = consensus
+            .append_entries(request(leader_id, 2, 0, 0, entries, 1))
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(consensus.log_len().await, 3);
+        assert_eq!(consensus.commit_index().await, 1);
+
+        let entries = vec![ConsensusEntry::new(1, 2, b"b".to_vec())];
+        let response = consensus
+            .append_entries(request(leader_id, 2, 1, 1, entries, 5))
+            .await
+            .unwrap();
+        assert!(response.success);
+        assert_eq!(response.match_index, 2);
+        let log = consensus.log.read().await.clone();
+        assert_eq!(log.entries.iter().map(|e| e.term).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(consensus.commit_index().await, 2);
+
+        // Committed entries are never overwritten
+        let entries = vec![ConsensusEntry::new(1, 3, b"c".to_vec())];
+        let result = consensus.append_entries(request(leader_id, 3, 1, 1, entries, 2)).await;
+        assert!(result.is_err());
+        assert_eq!(consensus.log.read().await.clone(), log);
+    }
+
+    #[tokio::test]
+    async fn test_replication_converges_and_commits() {
+        let leader = Consensus::new(ConsensusConfig::new(NodeId::new()).with_quorum_size(2));
+        *leader.log.write().await = vec![ConsensusEntry::new(0, 1, b"a".to_vec())].into();
+        leader.start_election().await.unwrap();
+        leader.start_election().await.unwrap();
+        assert!(leader.receive_vote(NodeId::new(), 2).await.unwrap());
+        leader.append(b"b".to_vec()).await.unwrap();
+        leader.append(b"c".to_vec()).await.unwrap();
+
+        let followers: Vec<(NodeId, Consensus)> = (0..2)
+            .map(|_| {
+                let id = NodeId::new();
+                (id, Consensus::new(ConsensusConfig::new(id)))
+            })
+            .collect();
+        *followers[1].1.log.write().await = vec![
+            ConsensusEntry::new(0, 1, b"a".to_vec()),
+            ConsensusEntry::new(1, 1, b"stale".to_vec()),
+            ConsensusEntry::new(2, 1, b"stale".to_vec()),
+        ].into();
+
+        for _ in 0..3 {
+            for (id, follower) in &followers {
+                replicate(&leader, *id, follower).await;
+            }
+        }
+
+        let log = leader.log.read().await.clone();
+        assert_eq!(leader.commit_index().await, 3);
+        for (id, follower) in &followers {
+            assert_eq!(*follower.log.read().await, log);
+            assert_eq!(follower.commit_index().await, 3);
+            assert_eq!(leader.match_index(*id).await, Some(3));
+            assert_eq!(leader.next_index(*id).await, Some(3));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_requires_current_term_entry() {
+        let leader = Consensus::new(ConsensusConfig::new(NodeId::new()).with_quorum_size(2));
+        *leader.log.write().await = vec![ConsensusEntry::new(0, 1, b"a".to_vec())].into();
+        leader.start_election().await.unwrap();
+        leader.start_election().await.unwrap();
+        assert!(leader.receive_vote(NodeId::new(), 2).await.unwrap());
+
+        let peer = NodeId::new();
+        let follower = Consensus::new(ConsensusConfig::new(peer));
+
+        // An earlier-term entry on a quorum is not committed by itself
+        for _ in 0..2 {
+            replicate(&leader, peer, &follower).await;
+        }
+        assert_eq!(leader.match_index(peer).await, Some(1));
+        assert_eq!(leader.commit_index().await, 0);
+
+        leader.append(b"b".to_vec()).await.unwrap();
+        replicate(&leader, peer, &follower).await;
+        assert_eq!(leader.commit_index().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_append_response_from_later_term_steps_down() {
+        let leader = Consensus::new(ConsensusConfig::new(NodeId::new()).with_quorum_size(2));
+        leader.start_election().await.unwrap();
+        assert!(leader.receive_vote(NodeId::new(), 1).await.unwrap());
+
+        let response = AppendEntriesResponse {
+            term: 4,
+            success: false,
+            match_index: 0,
+        };
+        leader.handle_append_response(NodeId::new(), response).await.unwrap();
+        assert_eq!(leader.state().await, ConsensusState::Follower);
+        assert_eq!(leader.current_term().await, 4);
+        assert!(leader.replication_request(NodeId::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_committed_events_global_order() {
+        use cathedral_core::{EventId, LogicalTime, RunId};
+        use cathedral_log::{merge_by_global_seq, EventKind};
+
+        let consensus = Consensus::new(ConsensusConfig::new(NodeId::new()));
+        *consensus.state.write().await = ConsensusState::Leader;
+
+        let run_id = RunId::new();
+        let workers = [NodeId::new(), NodeId::new(), NodeId::new()];
+
+        // Workers race to submit; consensus order is whatever the leader appends
+        for round in 0..2u64 {
+            for worker in workers.iter().rev() {
+                let event = Event::new(
+                    EventId::new(),
+                    run_id,
+                    *worker,
+                    LogicalTime::from_raw(round),
+                    EventKind::NodeCompleted,
+                );
+                consensus.append_event(&event).await.unwrap();
+            }
+        }
+
+        // Nothing is sequenced before commit
+        assert!(consensus.committed_events().await.unwrap().is_empty());
+
+        consensus.commit_to(consensus.log_len().await as u64).await.unwrap();
+        let committed = consensus.committed_events().await.unwrap();
+        let seqs: Vec<u64> = committed.iter().filter_map(|e| e.global_seq).collect();
+        assert_eq!(seqs, vec![0, 1, 2, 3, 4, 5]);
+
+        // Split the committed log back out per worker and merge it again
+        let per_worker: Vec<Vec<Event>> = workers
+            .iter()
+            .map(|w| committed.iter().filter(|e| e.node_id == *w).cloned().collect())
+            .collect();
+        let merged = merge_by_global_seq(per_worker).unwrap();
+        assert_eq!(merged, committed);
     }
 
     #[tokio::test]
@@ -527,4 +1741,256 @@ mod tests {
         assert_eq!(ConsensusState::Follower, ConsensusState::Follower);
         assert_ne!(ConsensusState::Follower, ConsensusState::Leader);
     }
+
+    #[tokio::test]
+    async fn test_recover_keeps_vote_and_log() {
+        let storage = Arc::new(MemoryConsensusStorage::new());
+        let node_id = NodeId::new();
+        let (first, second) = (NodeId::new(), NodeId::new());
+
+        {
+            let consensus = Consensus::recover(ConsensusConfig::new(node_id), storage.clone()).unwrap();
+            assert!(consensus.request_vote(first, 3, 0, 0).await.unwrap());
+            let entries = vec![
+                ConsensusEntry::new(0, 3, b"a".to_vec()),
+                ConsensusEntry::new(1, 3, b"b".to_vec()),
+            ];
+            let response = consensus
+                .append_entries(request(first, 3, 0, 0, entries, 2))
+                .await
+                .unwrap();
+            assert!(response.success);
+        }
+
+        let restarted = Consensus::recover(ConsensusConfig::new(node_id), storage.clone()).unwrap();
+        assert_eq!(restarted.current_term().await, 3);
+        assert_eq!(restarted.state().await, ConsensusState::Follower);
+        assert_eq!(restarted.log_len().await, 2);
+        assert!(!restarted.request_vote(second, 3, 0, 0).await.unwrap());
+
+        // Conflicting suffixes are rewritten in storage too
+        let entries = vec![ConsensusEntry::new(1, 4, b"c".to_vec())];
+        restarted
+            .append_entries(request(second, 4, 1, 3, entries, 0))
+            .await
+            .unwrap();
+        let saved = storage.load().unwrap();
+        assert_eq!(saved.log, restarted.log.read().await.entries);
+        assert_eq!(saved.hard_state, HardState {
+            current_term: 4,
+            voted_for: None,
+        });
+    }
+
+    #[tokio::test]
+    async fn test_recover_from_file_storage() {
+        use crate::storage::FileConsensusStorage;
+
+        let dir = tempfile::tempdir().unwrap();
+        let node_id = NodeId::new();
+        {
+            let storage = Arc::new(FileConsensusStorage::open(dir.path()).unwrap());
+            let consensus = Consensus::recover(
+                ConsensusConfig::new(node_id).with_quorum_size(1),
+                storage,
+            )
+            .unwrap();
+            consensus.start_election().await.unwrap();
+            assert!(consensus.receive_vote(node_id, 1).await.unwrap());
+            consensus.append(b"durable".to_vec()).await.unwrap();
+        }
+
+        let storage = Arc::new(FileConsensusStorage::open(dir.path()).unwrap());
+        let restarted = Consensus::recover(ConsensusConfig::new(node_id), storage).unwrap();
+        assert_eq!(restarted.current_term().await, 1);
+        assert_eq!(*restarted.voted_for.read().await, Some(node_id));
+        assert_eq!(
+            restarted.log.read().await.entries,
+            vec![ConsensusEntry::new(0, 1, b"durable".to_vec())]
+        );
+    }
+
+    /// Snapshot of a single `count` key through `last_index`
+    fn counter_snapshot(last_index: u64, last_term: u64) -> ConsensusSnapshot {
+        let state = BTreeMap::from([("count".to_string(), last_index.to_le_bytes().to_vec())]);
+        ConsensusSnapshot::from_state(format!("s{}", last_index), last_index, last_term, state)
+    }
+
+    #[tokio::test]
+    async fn test_compact_log() {
+        let node_id = NodeId::new();
+        let storage = Arc::new(MemoryConsensusStorage::new());
+        let config = ConsensusConfig::new(node_id)
+            .with_quorum_size(1)
+            .with_compaction(CompactionPolicy::new(4, 2));
+        let consensus = Consensus::recover(config.clone(), storage.clone()).unwrap();
+        consensus.start_election().await.unwrap();
+        assert!(consensus.receive_vote(node_id, 1).await.unwrap());
+
+        for i in 0..4u8 {
+            consensus.append(vec![i]).await.unwrap();
+        }
+        consensus.advance_commit().await.unwrap();
+        assert_eq!(consensus.compaction_point().await, None);
+
+        for i in 4..6u8 {
+            consensus.append(vec![i]).await.unwrap();
+        }
+        assert_eq!(consensus.advance_commit().await.unwrap(), 6);
+        assert_eq!(consensus.compaction_point().await, Some(6));
+
+        // Snapshots must cover committed entries of the right term
+        assert!(consensus.compact(counter_snapshot(7, 1)).await.is_err());
+        assert!(consensus.compact(counter_snapshot(6, 2)).await.is_err());
+
+        consensus.compact(counter_snapshot(6, 1)).await.unwrap();
+        assert_eq!(consensus.log_len().await, 6);
+        assert_eq!(consensus.retained_len().await, 2);
+        assert_eq!(consensus.last_applied().await, 6);
+        assert_eq!(consensus.compaction_point().await, None);
+        assert!(consensus.compact(counter_snapshot(6, 1)).await.is_err());
+
+        // Appending continues at the logical end of the log
+        assert_eq!(consensus.append(vec![6]).await.unwrap(), 6);
+
+        let restarted = Consensus::recover(config, storage).unwrap();
+        assert_eq!(restarted.log_len().await, 7);
+        assert_eq!(restarted.retained_len().await, 3);
+        assert_eq!(restarted.commit_index().await, 6);
+        assert_eq!(restarted.snapshot().await, Some(counter_snapshot(6, 1)));
+    }
+
+    #[tokio::test]
+    async fn test_install_snapshot_on_lagging_follower() {
+        let leader_id = NodeId::new();
+        let peer = NodeId::new();
+        let leader = Consensus::new(
+            ConsensusConfig::new(leader_id)
+                .with_quorum_size(2)
+                .with_compaction(CompactionPolicy::new(2, 1)),
+        );
+        let follower = Consensus::new(ConsensusConfig::new(peer));
+        leader.start_election().await.unwrap();
+        assert!(leader.receive_vote(peer, 1).await.unwrap());
+
+        for i in 0..4u8 {
+            leader.append(vec![i]).await.unwrap();
+        }
+        *leader.match_index.write().await = HashMap::from([(peer, 4)]);
+        assert_eq!(leader.advance_commit().await.unwrap(), 4);
+        leader.compact(counter_snapshot(4, 1)).await.unwrap();
+        leader.append(vec![4]).await.unwrap();
+
+        // The follower's next entry was compacted, so it gets the snapshot
+        leader.next_index.write().await.insert(peer, 0);
+        assert!(matches!(
+            leader.replication_request(peer).await.unwrap(),
+            Replication::Snapshot(_)
+        ));
+        replicate(&leader, peer, &follower).await;
+        assert_eq!(follower.snapshot().await, Some(counter_snapshot(4, 1)));
+        assert_eq!(follower.log_len().await, 4);
+        assert_eq!(follower.commit_index().await, 4);
+        assert_eq!(leader.next_index(peer).await, Some(4));
+
+        // Then catches up from the log
+        replicate(&leader, peer, &follower).await;
+        assert_eq!(follower.log_len().await, 5);
+        assert_eq!(leader.commit_index().await, 5);
+
+        // A stale snapshot leaves the log alone
+        let stale = InstallSnapshotRequest {
+            term: 1,
+            leader_id,
+            snapshot: counter_snapshot(4, 1),
+        };
+        assert!(follower.install_snapshot(stale).await.unwrap().success);
+        assert_eq!(follower.log_len().await, 5);
+
+        // A corrupt snapshot is rejected
+        let mut corrupt = counter_snapshot(5, 1);
+        for data in corrupt.blobs.values_mut() {
+            data.push(0);
+        }
+        let request = InstallSnapshotRequest {
+            term: 1,
+            leader_id,
+            snapshot: corrupt,
+        };
+        assert!(follower.install_snapshot(request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_install_snapshot_keeps_matching_suffix() {
+        let leader_id = NodeId::new();
+        let follower = Consensus::new(
+            ConsensusConfig::new(NodeId::new()).with_compaction(CompactionPolicy::new(2, 0)),
+        );
+        let entries = (0..3u8)
+            .map(|i| ConsensusEntry::new(u64::from(i), 1, vec![i]))
+            .collect();
+        follower
+            .append_entries(request(leader_id, 1, 0, 0, entries, 0))
+            .await
+            .unwrap();
+
+        let request = InstallSnapshotRequest {
+            term: 1,
+            leader_id,
+            snapshot: counter_snapshot(2, 1),
+        };
+        let response = follower.install_snapshot(request).await.unwrap();
+        assert!(response.success);
+        assert_eq!(response.match_index, 2);
+        assert_eq!(follower.log_len().await, 3);
+        assert_eq!(follower.retained_len().await, 1);
+        assert_eq!(follower.commit_index().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_joint_consensus_reconfiguration() {
+        let ids: Vec<NodeId> = (0..4).map(|_| NodeId::new()).collect();
+        let node = |i: usize| {
+            Consensus::new(ConsensusConfig::new(ids[i]).with_voters(ids[..3].iter().copied()))
+        };
+        let leader = node(0);
+        let followers: Vec<Consensus> = (1..4).map(node).collect();
+        leader.start_election().await.unwrap();
+        assert!(leader.receive_vote(ids[1], 1).await.unwrap());
+        let mut peers = vec![ids[1], ids[2]];
+        peers.sort();
+        assert_eq!(leader.peers().await, peers);
+
+        // Replace the leader with a new node
+        let voters = BTreeSet::from([ids[1], ids[2], ids[3]]);
+        assert_eq!(leader.propose_configuration(voters.clone()).await.unwrap(), 0);
+        assert!(leader.reconfiguration_in_progress().await);
+        assert!(leader.propose_configuration(voters.clone()).await.is_err());
+
+        // A majority of the old voters alone cannot commit the joint entry
+        for _ in 0..2 {
+            replicate(&leader, ids[1], &followers[0]).await;
+        }
+        assert_eq!(leader.commit_index().await, 0);
+        assert!(followers[0].configuration().await.unwrap().is_joint());
+
+        // With the new majority it commits, and the stable entry follows
+        for _ in 0..2 {
+            replicate(&leader, ids[3], &followers[2]).await;
+        }
+        assert_eq!(leader.commit_index().await, 1);
+        assert_eq!(leader.log_len().await, 2);
+        let stable = ClusterConfiguration::new(voters);
+        assert_eq!(leader.configuration().await, Some(stable.clone()));
+        assert!(leader.reconfiguration_in_progress().await);
+
+        // Committing a configuration without the leader makes it step down
+        replicate(&leader, ids[1], &followers[0]).await;
+        replicate(&leader, ids[3], &followers[2]).await;
+        assert_eq!(leader.commit_index().await, 2);
+        assert_eq!(leader.state().await, ConsensusState::Follower);
+        assert!(!leader.reconfiguration_in_progress().await);
+        assert!(leader.committed_events().await.unwrap().is_empty());
+        assert_eq!(followers[0].configuration().await, Some(stable));
+    }
 }
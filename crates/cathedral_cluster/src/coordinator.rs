@@ -1,25 +1,52 @@
 //! Cluster coordinator for distributed execution.
 
 use crate::{consensus::Consensus, leader::LeaderElection, membership::Membership, remote::RemoteExecutor};
-use cathedral_core::{CoreResult, CoreError, EventId, Hash, NodeId};
+use crate::membership::MemberState;
+use crate::compaction::ConsensusSnapshot;
+use crate::consensus::{
+    ConsensusError, ConsensusState, InstallSnapshotRequest, Replication, RequestVoteRequest,
+};
+use crate::remote::{IdempotencyKey, RemoteClient};
+use cathedral_core::{
+    CapabilitySet, CoreResult, CoreError, EventId, Hash, LogicalTime, NodeId, RunId,
+};
+use cathedral_log::{CanonicalEncode, Event, EventKind};
+use cathedral_runtime::backpressure::BackpressureStatus;
+use cathedral_runtime::{BackpressureController, BackpressureStrategy, Metrics};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{watch, Notify, RwLock, RwLockWriteGuard};
+
+/// Run ID for events the coordinator records on its own behalf
+const COORDINATOR_RUN: RunId = RunId::from_bytes([0; 16]);
 
 /// Coordinator configuration
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CoordinatorConfig {
     /// Node ID for this coordinator
     pub node_id: NodeId,
-    /// Maximum concurrent executions
+    /// Ceiling on concurrent executions, however much capacity the workers
+    /// declare
     pub max_concurrent: usize,
-    /// Execution timeout in milliseconds
+    /// Execution timeout in logical milliseconds, measured on the
+    /// coordinator's logical clock
     pub execution_timeout_ms: u64,
     /// Retry limit for failed executions
     pub retry_limit: usize,
     /// Snapshot interval in milliseconds
     pub snapshot_interval_ms: u64,
+    /// How long `submit` waits for capacity when saturated (0 = reject at once)
+    pub submit_deadline_ms: u64,
+    /// Retry hint returned with backpressure rejections
+    pub retry_after_ms: u64,
+    /// How tasks are placed on capable workers
+    pub scheduling: SchedulingPolicy,
+    /// Queued tasks a worker may hold before idle workers can steal from it
+    pub steal_threshold: usize,
 }
 
 impl CoordinatorConfig {
@@ -32,6 +59,10 @@ impl CoordinatorConfig {
             execution_timeout_ms: 30000,
             retry_limit: 3,
             snapshot_interval_ms: 60000,
+            submit_deadline_ms: 0,
+            retry_after_ms: 100,
+            scheduling: SchedulingPolicy::default(),
+            steal_threshold: 1,
         }
     }
 
@@ -55,6 +86,34 @@ impl CoordinatorConfig {
         self.retry_limit = limit;
         self
     }
+
+    /// Set how long submissions block waiting for capacity
+    #[must_use]
+    pub fn with_submit_deadline(mut self, deadline_ms: u64) -> Self {
+        self.submit_deadline_ms = deadline_ms;
+        self
+    }
+
+    /// Set the retry hint returned with backpressure rejections
+    #[must_use]
+    pub fn with_retry_after(mut self, retry_after_ms: u64) -> Self {
+        self.retry_after_ms = retry_after_ms;
+        self
+    }
+
+    /// Set the worker scheduling policy
+    #[must_use]
+    pub fn with_scheduling(mut self, scheduling: SchedulingPolicy) -> Self {
+        self.scheduling = scheduling;
+        self
+    }
+
+    /// Set how many queued tasks a worker may hold before work is stolen
+    #[must_use]
+    pub fn with_steal_threshold(mut self, threshold: usize) -> Self {
+        self.steal_threshold = threshold;
+        self
+    }
 }
 
 impl Default for CoordinatorConfig {
@@ -63,6 +122,23 @@ impl Default for CoordinatorConfig {
     }
 }
 
+/// How the coordinator picks among workers able to run a task
+///
+/// Every policy first filters out workers that do not declare the task's
+/// required capabilities. Candidates are ordered by node ID, so given the
+/// same membership and task history the choice is the same on replay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SchedulingPolicy {
+    /// Always pick the capable worker with the lowest node ID
+    #[default]
+    CapabilityFiltered,
+    /// Rotate through capable workers in node ID order
+    RoundRobin,
+    /// Pick the capable worker with the fewest assigned or running tasks,
+    /// breaking ties by lowest node ID
+    LeastLoaded,
+}
+
 /// Coordinator errors
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
 pub enum CoordinatorError {
@@ -85,6 +161,44 @@ pub enum CoordinatorError {
     /// Invalid state
     #[error("Invalid state: {0}")]
     InvalidState(String),
+
+    /// Cluster is saturated; retry after the hinted delay
+    #[error("Backpressure: {outstanding}/{capacity} tasks outstanding, retry after {retry_after_ms}ms")]
+    Backpressure {
+        /// Tasks pending, assigned, or running
+        outstanding: usize,
+        /// Cluster capacity
+        capacity: usize,
+        /// Suggested delay before retrying
+        retry_after_ms: u64,
+    },
+
+    /// Another attempt's result was committed for the task first
+    #[error("Attempt {attempt} of task {task_id} superseded by an earlier result")]
+    Superseded {
+        /// Task ID
+        task_id: String,
+        /// Attempt whose result was discarded
+        attempt: u64,
+    },
+
+    /// A membership change has not finished committing
+    #[error("Cluster membership is changing")]
+    Reconfiguring,
+
+    /// A change was appended to the replicated log but a quorum has not
+    /// acknowledged it yet; it takes effect once it commits
+    #[error("Log entry {0} is not committed yet")]
+    Uncommitted(u64),
+}
+
+impl From<CoordinatorError> for CoreError {
+    fn from(err: CoordinatorError) -> Self {
+        CoreError::Validation {
+            field: "coordinator".to_string(),
+            reason: err.to_string(),
+        }
+    }
 }
 
 /// Execution task
@@ -102,6 +216,14 @@ pub struct ExecutionTask {
     pub retry_count: usize,
     /// Creation time
     pub created_at: u64,
+    /// Capabilities a worker must declare to run this task
+    pub required_capabilities: CapabilitySet,
+    /// Logical time by which the running attempt must finish
+    pub deadline: Option<u64>,
+    /// Run the executed node belongs to
+    pub run_id: RunId,
+    /// Node being executed
+    pub node_id: NodeId,
 }
 
 impl ExecutionTask {
@@ -118,9 +240,36 @@ impl ExecutionTask {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_millis() as u64,
+            required_capabilities: CapabilitySet::new(),
+            deadline: None,
+            run_id: COORDINATOR_RUN,
+            node_id: NodeId::from_bytes(*event_id.as_bytes()),
         }
     }
 
+    /// Set the run and node the task executes
+    ///
+    /// Without them the task is keyed by its event ID alone.
+    #[must_use]
+    pub fn with_node(mut self, run_id: RunId, node_id: NodeId) -> Self {
+        self.run_id = run_id;
+        self.node_id = node_id;
+        self
+    }
+
+    /// Idempotency key of the current attempt
+    #[must_use]
+    pub fn idempotency_key(&self) -> IdempotencyKey {
+        IdempotencyKey::new(self.run_id, self.node_id, self.retry_count as u64)
+    }
+
+    /// Set the capabilities required to run this task
+    #[must_use]
+    pub fn with_required_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.required_capabilities = capabilities;
+        self
+    }
+
     /// Assign a worker
     #[must_use]
     pub fn with_worker(mut self, worker: NodeId) -> Self {
@@ -142,6 +291,15 @@ impl ExecutionTask {
         self.retry_count += 1;
         self
     }
+
+    /// Check if the task is pending, assigned, or running
+    #[must_use]
+    pub fn is_outstanding(&self) -> bool {
+        matches!(
+            self.status,
+            TaskStatus::Pending | TaskStatus::Assigned | TaskStatus::Running
+        )
+    }
 }
 
 /// Task status
@@ -157,6 +315,179 @@ pub enum TaskStatus {
     Completed,
     /// Task failed
     Failed,
+    /// Task exhausted its retries and is parked in the dead-letter queue
+    DeadLettered,
+}
+
+/// A task that exhausted its retries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    /// Task as it was when it was dead-lettered
+    pub task: ExecutionTask,
+    /// Error from the final attempt
+    pub error: String,
+}
+
+/// Dead-letter queue transition
+///
+/// Each transition is committed to the replicated log, as a
+/// `TaskDeadLettered` or `TaskRequeued` event, and takes effect once the
+/// event is applied with [`Coordinator::apply_committed`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeadLetterTransition {
+    /// Task moved into the dead-letter queue
+    DeadLettered {
+        /// Task ID
+        task_id: String,
+        /// Attempts made, including the first
+        attempts: usize,
+        /// Error from the final attempt
+        error: String,
+    },
+    /// Task moved back to pending by an operator
+    Requeued {
+        /// Task ID
+        task_id: String,
+    },
+}
+
+/// A queued task moved from an overloaded worker to an idle one
+///
+/// Each steal is recorded as a `TaskStolen` event in the replicated log
+/// and takes effect once the event commits, so replaying the log with
+/// [`Coordinator::apply_steal`] reproduces the final assignment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkSteal {
+    /// Task ID
+    pub task_id: String,
+    /// Worker the task was queued on
+    pub from: NodeId,
+    /// Idle worker that took it
+    pub to: NodeId,
+}
+
+impl WorkSteal {
+    /// Read the steal recorded by a `TaskStolen` event
+    ///
+    /// Returns `None` for events of any other kind or with a malformed payload.
+    #[must_use]
+    pub fn from_event(event: &Event) -> Option<Self> {
+        if event.kind != EventKind::TaskStolen {
+            return None;
+        }
+        serde_json::from_slice(&event.payload).ok()
+    }
+
+    /// ID of the event recording this steal of the task queued by
+    /// `parent`, decided at logical time `time`
+    fn event_id(&self, parent: EventId, time: u64) -> EventId {
+        let mut seed = Vec::with_capacity(56);
+        seed.extend_from_slice(parent.as_bytes());
+        seed.extend_from_slice(self.from.as_bytes());
+        seed.extend_from_slice(self.to.as_bytes());
+        seed.extend_from_slice(&time.to_le_bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&Hash::compute(&seed).as_bytes()[..16]);
+        EventId::from_bytes(bytes)
+    }
+
+    /// Move the task to the thief if it is still queued on the victim
+    fn apply(&self, tasks: &mut HashMap<String, ExecutionTask>) -> bool {
+        match tasks.get_mut(&self.task_id) {
+            Some(task)
+                if task.status == TaskStatus::Assigned
+                    && task.assigned_worker == Some(self.from) =>
+            {
+                task.assigned_worker = Some(self.to);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Step of the coordinator shutdown sequence
+///
+/// [`Coordinator::shutdown`] always performs the steps in declaration order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShutdownStep {
+    /// Pending tasks were run until none remained or the deadline passed
+    Drained {
+        /// Tasks completed while draining
+        completed: usize,
+        /// Outstanding task IDs left for recovery, sorted
+        remaining: Vec<String>,
+    },
+    /// Coordinator state was snapshotted and, from a leader, sent to peers
+    Snapshotted {
+        /// Number of log entries the snapshot covers
+        index: u64,
+    },
+    /// Leadership was relinquished so another node can be elected
+    SteppedDown {
+        /// Whether this node was the leader
+        was_leader: bool,
+    },
+    /// The coordinator removed itself from membership
+    Unregistered {
+        /// Coordinator node ID
+        node_id: NodeId,
+    },
+}
+
+/// Snapshot of the cluster as seen by a coordinator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterStatus {
+    /// Coordinator node ID
+    pub node_id: NodeId,
+    /// Current leader, if one is known
+    pub leader: Option<NodeId>,
+    /// Consensus term
+    pub term: u64,
+    /// Highest committed log index
+    pub commit_index: u64,
+    /// Coordinator logical time heartbeat ages are measured against
+    pub logical_time: u64,
+    /// Voters of the current configuration, sorted
+    pub voters: Vec<NodeId>,
+    /// Whether a membership change is under way
+    pub reconfiguring: bool,
+    /// Whether the cluster has a leader and a quorum of active members
+    pub healthy: bool,
+    /// Tasks pending, assigned, or running
+    pub outstanding: usize,
+    /// Every member, sorted by node ID
+    pub members: Vec<MemberStatus>,
+}
+
+/// State of one cluster member
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberStatus {
+    /// Member node ID
+    pub node_id: NodeId,
+    /// Member address
+    pub address: String,
+    /// Member state
+    pub state: MemberState,
+    /// Logical time since the member's last heartbeat
+    pub heartbeat_age: u64,
+    /// Tasks assigned to or running on the member
+    pub in_flight: usize,
+    /// Whether the member votes in the current configuration
+    pub voter: bool,
+}
+
+/// Outcome of draining a worker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrainReport {
+    /// Drained worker
+    pub node_id: NodeId,
+    /// Tasks queued on the worker that were returned to pending, sorted
+    pub requeued: Vec<String>,
+    /// Tasks still running on the worker when the deadline passed, sorted
+    pub remaining: Vec<String>,
+    /// Whether the worker was removed from membership
+    pub deregistered: bool,
 }
 
 /// Execution result
@@ -174,6 +505,9 @@ pub struct ExecutionResult {
     pub error: Option<String>,
     /// Execution time in milliseconds
     pub execution_time_ms: u64,
+    /// Index of the replicated log entry that committed this result, 0
+    /// until it is committed
+    pub log_index: u64,
 }
 
 impl ExecutionResult {
@@ -187,6 +521,7 @@ impl ExecutionResult {
             success: true,
             error: None,
             execution_time_ms: time_ms,
+            log_index: 0,
         }
     }
 
@@ -200,8 +535,55 @@ impl ExecutionResult {
             success: false,
             error: Some(error),
             execution_time_ms: 0,
+            log_index: 0,
         }
     }
+
+    /// Set the index of the log entry that committed this result
+    #[must_use]
+    pub fn with_log_index(mut self, index: u64) -> Self {
+        self.log_index = index;
+        self
+    }
+}
+
+/// Task results as decided by the replicated log
+///
+/// The first `NodeCompleted` entry for a run's node wins. Every later one
+/// is superseded, whichever leader appended it.
+#[derive(Debug, Default)]
+struct AppliedResults {
+    /// Log index up to which committed entries have been applied
+    applied: u64,
+    /// Attempt whose result won, by run and node
+    winners: HashMap<(RunId, NodeId), IdempotencyKey>,
+    /// Results appended to the log as `NodeCompleted` but not applied yet,
+    /// by attempt
+    proposed: HashMap<IdempotencyKey, ExecutionResult>,
+}
+
+/// Encode one part of the coordinator state for a snapshot
+fn encode_state<T: Serialize>(part: &str, value: &T) -> CoreResult<(String, Vec<u8>)> {
+    let data = serde_json::to_vec(value).map_err(|e| CoreError::ParseError {
+        message: format!("Failed to encode coordinator {}: {}", part, e),
+    })?;
+    Ok((part.to_string(), data))
+}
+
+/// Decode one part of the coordinator state from a snapshot, empty if the
+/// snapshot does not hold it
+fn decode_state<T: DeserializeOwned + Default>(
+    state: &BTreeMap<String, Vec<u8>>,
+    part: &str,
+) -> CoreResult<T> {
+    state.get(part).map_or_else(
+        || Ok(T::default()),
+        |data| {
+            serde_json::from_slice(data).map_err(|e| CoreError::ParseError {
+                message: format!("Invalid coordinator {} in snapshot: {}", part, e),
+            })
+        },
+    )
 }
 
 /// Cluster coordinator
@@ -220,8 +602,24 @@ pub struct Coordinator {
     tasks: Arc<RwLock<HashMap<String, ExecutionTask>>>,
     /// Completed tasks
     completed: Arc<RwLock<HashMap<String, ExecutionResult>>>,
-    /// Current snapshot index
+    /// Results decided by the replicated log
+    results: Arc<RwLock<AppliedResults>>,
+    /// Log entries covered by the latest snapshot taken or restored
     snapshot_index: Arc<RwLock<u64>>,
+    /// Admission control for submissions
+    backpressure: Arc<RwLock<BackpressureController>>,
+    /// Woken when a task stops being outstanding
+    capacity_freed: Arc<Notify>,
+    /// Tasks that exhausted their retries, in the order they failed
+    dead_letters: Arc<RwLock<Vec<DeadLetter>>>,
+    /// Log of dead-letter queue transitions
+    dead_letter_log: Arc<RwLock<Vec<DeadLetterTransition>>>,
+    /// Active scheduling policy
+    scheduling: Arc<RwLock<SchedulingPolicy>>,
+    /// Worker picked by the last round-robin selection
+    last_selected: Arc<RwLock<Option<NodeId>>>,
+    /// Logical clock that task deadlines are measured against
+    clock: watch::Sender<u64>,
 }
 
 impl Coordinator {
@@ -234,7 +632,19 @@ impl Coordinator {
         membership: Arc<Membership>,
         remote: Arc<RemoteExecutor>,
     ) -> Self {
+        let strategy = if config.submit_deadline_ms > 0 {
+            BackpressureStrategy::Block
+        } else {
+            BackpressureStrategy::Signal
+        };
+        let backpressure = BackpressureController::new(config.max_concurrent, 1.0, strategy);
+
         Self {
+            backpressure: Arc::new(RwLock::new(backpressure)),
+            capacity_freed: Arc::new(Notify::new()),
+            scheduling: Arc::new(RwLock::new(config.scheduling)),
+            last_selected: Arc::new(RwLock::new(None)),
+            clock: watch::Sender::new(0),
             config,
             consensus,
             election,
@@ -242,7 +652,10 @@ impl Coordinator {
             remote,
             tasks: Arc::new(RwLock::new(HashMap::new())),
             completed: Arc::new(RwLock::new(HashMap::new())),
+            results: Arc::new(RwLock::new(AppliedResults::default())),
             snapshot_index: Arc::new(RwLock::new(0)),
+            dead_letters: Arc::new(RwLock::new(Vec::new())),
+            dead_letter_log: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
@@ -252,6 +665,51 @@ impl Coordinator {
     ///
     /// Returns error if submission fails
     pub async fn submit(&self, event_id: EventId) -> CoreResult<String> {
+        self.submit_with_capabilities(event_id, CapabilitySet::new()).await
+    }
+
+    /// Submit a task that may only run on workers declaring `required`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if submission fails
+    pub async fn submit_with_capabilities(
+        &self,
+        event_id: EventId,
+        required: CapabilitySet,
+    ) -> CoreResult<String> {
+        self.submit_task(ExecutionTask::new(event_id).with_required_capabilities(required))
+            .await
+    }
+
+    /// Submit the execution of one node of a run
+    ///
+    /// Attempts are keyed by run and node, so only the first result
+    /// committed for the node counts, even if the node is submitted again.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if submission fails
+    pub async fn submit_node(
+        &self,
+        run_id: RunId,
+        node_id: NodeId,
+        event_id: EventId,
+        required: CapabilitySet,
+    ) -> CoreResult<String> {
+        let task = ExecutionTask::new(event_id)
+            .with_node(run_id, node_id)
+            .with_required_capabilities(required);
+        self.submit_task(task).await
+    }
+
+    /// Queue a task once this node may take new work
+    async fn submit_task(&self, task: ExecutionTask) -> CoreResult<String> {
+        // A leader that lost its lease hands off instead of taking new work
+        if self.election.is_stepping_down().await {
+            return Err(CoordinatorError::QuorumLost.into());
+        }
+
         // Only leader can accept submissions
         if !self.election.is_leader().await {
             return Err(CoreError::Validation {
@@ -260,15 +718,184 @@ impl Coordinator {
             });
         }
 
-        let task = ExecutionTask::new(event_id.clone());
+        // Work placed under a half-committed membership could be lost
+        if self.consensus.reconfiguration_in_progress().await {
+            return Err(CoordinatorError::Reconfiguring.into());
+        }
+
         let task_id = task.task_id.clone();
 
-        let mut tasks = self.tasks.write().await;
-        tasks.insert(task_id.clone(), task);
+        // Checked and inserted under one lock, so concurrent submissions
+        // cannot overshoot the capacity
+        self.reserve().await?.insert(task_id.clone(), task);
 
         Ok(task_id)
     }
 
+    /// Change the cluster's voting members
+    ///
+    /// The change is proposed through joint consensus and completes once
+    /// the replicated log commits it; submissions are rejected with
+    /// [`CoordinatorError::Reconfiguring`] until then. Every voter other
+    /// than this node must already be a member. Returns the log index of
+    /// the proposed change.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a voter is not a member, this node is not the
+    /// consensus leader, or another change is in progress
+    pub async fn reconfigure(&self, voters: BTreeSet<NodeId>) -> CoreResult<u64> {
+        for &voter in &voters {
+            if voter != self.config.node_id && self.membership.get_member(voter).await.is_none() {
+                return Err(CoreError::NotFound {
+                    kind: "member".to_string(),
+                    id: voter.to_string(),
+                });
+            }
+        }
+
+        let index = self.consensus.propose_configuration(voters).await?;
+        tracing::info!(index, "cluster reconfiguration proposed");
+        Ok(index)
+    }
+
+    /// Make an active member a voter
+    ///
+    /// Proposes the current voters plus `node_id` through
+    /// [`Coordinator::reconfigure`]. Returns the log index of the proposed
+    /// change.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the node is not an active member or already votes,
+    /// or the reconfiguration is refused
+    pub async fn promote(&self, node_id: NodeId) -> CoreResult<u64> {
+        self.membership
+            .get_member(node_id)
+            .await
+            .filter(|member| member.is_active())
+            .ok_or_else(|| CoreError::NotFound {
+                kind: "active member".to_string(),
+                id: node_id.to_string(),
+            })?;
+
+        let mut voters = self.voters().await;
+        if !voters.insert(node_id) {
+            return Err(CoordinatorError::InvalidState(format!(
+                "{} is already a voter",
+                node_id
+            ))
+            .into());
+        }
+        self.reconfigure(voters).await
+    }
+
+    /// Get the voters of the current configuration
+    ///
+    /// Without a configuration, the coordinator is the only voter.
+    async fn voters(&self) -> BTreeSet<NodeId> {
+        self.consensus
+            .configuration()
+            .await
+            .map_or_else(|| BTreeSet::from([self.config.node_id]), |c| c.voters())
+    }
+
+    /// Get the number of tasks the cluster can hold at once
+    ///
+    /// This is the sum of `max_concurrent` declared by the active workers,
+    /// capped at the coordinator's `max_concurrent`. Until some worker
+    /// declares slots it is the coordinator's `max_concurrent`, so tasks
+    /// queue as pending until a worker joins.
+    pub async fn capacity(&self) -> usize {
+        let declared: usize = self
+            .membership
+            .active_members()
+            .await
+            .iter()
+            .filter(|member| member.node_id != self.config.node_id)
+            .map(|member| member.max_concurrent)
+            .sum();
+        if declared == 0 {
+            return self.config.max_concurrent;
+        }
+        declared.min(self.config.max_concurrent)
+    }
+
+    /// Wait for capacity to accept one more task
+    ///
+    /// With a zero submit deadline a saturated cluster is rejected at once;
+    /// otherwise this blocks until capacity frees up or the deadline passes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoordinatorError::Backpressure`] with a retry hint if the
+    /// cluster stays saturated
+    pub async fn admit(&self) -> Result<(), CoordinatorError> {
+        self.reserve().await.map(drop)
+    }
+
+    /// Wait for capacity, returning the task table locked while it lasts
+    ///
+    /// The backpressure controller decides admission from the outstanding
+    /// tasks against the current capacity. A blocked submission waits for
+    /// a task to finish or the membership to change before asking again.
+    async fn reserve(
+        &self,
+    ) -> Result<RwLockWriteGuard<'_, HashMap<String, ExecutionTask>>, CoordinatorError> {
+        let deadline = tokio::time::Instant::now()
+            + Duration::from_millis(self.config.submit_deadline_ms);
+        let mut members = self.membership.subscribe();
+
+        loop {
+            // Registered before checking, so a task finishing in between
+            // still wakes this submission
+            let freed = self.capacity_freed.notified();
+            tokio::pin!(freed);
+            freed.as_mut().enable();
+            members.borrow_and_update();
+
+            let capacity = self.capacity().await;
+            let tasks = self.tasks.write().await;
+            let outstanding = tasks.values().filter(|task| task.is_outstanding()).count();
+            let status = {
+                let mut controller = self.backpressure.write().await;
+                controller.set_max_buffer_size(capacity);
+                controller.update_buffer_size(outstanding);
+                controller.status()
+            };
+
+            match status {
+                BackpressureStatus::Ok => return Ok(tasks),
+                BackpressureStatus::Block if tokio::time::Instant::now() < deadline => {
+                    drop(tasks);
+                }
+                _ => {
+                    return Err(CoordinatorError::Backpressure {
+                        outstanding,
+                        capacity,
+                        retry_after_ms: self.config.retry_after_ms,
+                    });
+                }
+            }
+
+            tokio::select! {
+                () = &mut freed => {}
+                Ok(()) = members.changed() => {}
+                () = tokio::time::sleep_until(deadline) => {}
+            }
+        }
+    }
+
+    /// Get the number of tasks pending, assigned, or running
+    pub async fn outstanding_task_count(&self) -> usize {
+        self.tasks
+            .read()
+            .await
+            .values()
+            .filter(|task| task.is_outstanding())
+            .count()
+    }
+
     /// Assign a task to a worker
     ///
     /// # Errors
@@ -288,19 +915,24 @@ impl Coordinator {
         }
     }
 
-    /// Get pending tasks
+    /// Get pending tasks, ordered by event ID
+    ///
+    /// The fixed order makes worker placement repeatable on replay.
     ///
     /// # Errors
     ///
     /// Returns error if lock acquisition fails
     pub async fn pending_tasks(&self) -> Vec<ExecutionTask> {
-        self.tasks
+        let mut pending: Vec<ExecutionTask> = self
+            .tasks
             .read()
             .await
             .values()
             .filter(|t| t.status == TaskStatus::Pending)
             .cloned()
-            .collect()
+            .collect();
+        pending.sort_by(|a, b| (a.event_id, &a.task_id).cmp(&(b.event_id, &b.task_id)));
+        pending
     }
 
     /// Select a worker for a task
@@ -309,36 +941,209 @@ impl Coordinator {
     ///
     /// Returns error if no workers available
     pub async fn select_worker(&self) -> CoreResult<NodeId> {
+        self.select_worker_for(&CapabilitySet::new()).await
+    }
+
+    /// Select a worker declaring every capability in `required`
+    ///
+    /// Among capable active workers, the choice is made by the current
+    /// [`SchedulingPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoordinatorError::NoWorkers`] if no active worker is capable
+    pub async fn select_worker_for(&self, required: &CapabilitySet) -> CoreResult<NodeId> {
         let members = self.membership.active_members().await;
         let coordinator_id = self.config.node_id;
 
-        // Filter out the coordinator itself
-        let workers: Vec<NodeId> = members
+        // Filter out the coordinator itself and workers lacking capabilities
+        let mut workers: Vec<NodeId> = members
             .iter()
+            .filter(|m| m.node_id != coordinator_id && m.supports(required))
             .map(|m| m.node_id)
-            .filter(|id| *id != coordinator_id)
             .collect();
+        workers.sort();
 
-        if workers.is_empty() {
-            return Err(CoreError::Validation {
-                field: "workers".to_string(),
-                reason: "No workers available".to_string(),
-            });
+        let Some(&first) = workers.first() else {
+            return Err(CoordinatorError::NoWorkers.into());
+        };
+
+        let selected = match *self.scheduling.read().await {
+            SchedulingPolicy::CapabilityFiltered => first,
+            SchedulingPolicy::RoundRobin => {
+                let mut last_selected = self.last_selected.write().await;
+                let next = last_selected
+                    .and_then(|last| workers.iter().copied().find(|&worker| worker > last))
+                    .unwrap_or(first);
+                *last_selected = Some(next);
+                next
+            }
+            SchedulingPolicy::LeastLoaded => {
+                let load = self.worker_load().await;
+                workers
+                    .iter()
+                    .copied()
+                    .min_by_key(|worker| (load.get(worker).copied().unwrap_or(0), *worker))
+                    .unwrap_or(first)
+            }
+        };
+
+        Ok(selected)
+    }
+
+    /// Get the active scheduling policy
+    pub async fn scheduling_policy(&self) -> SchedulingPolicy {
+        *self.scheduling.read().await
+    }
+
+    /// Change the scheduling policy for subsequent selections
+    pub async fn set_scheduling_policy(&self, policy: SchedulingPolicy) {
+        *self.scheduling.write().await = policy;
+        *self.last_selected.write().await = None;
+    }
+
+    /// Count assigned and running tasks per worker
+    async fn worker_load(&self) -> HashMap<NodeId, usize> {
+        let mut load = HashMap::new();
+        for task in self.tasks.read().await.values() {
+            if let Some(worker) = task
+                .assigned_worker
+                .filter(|_| matches!(task.status, TaskStatus::Assigned | TaskStatus::Running))
+            {
+                *load.entry(worker).or_insert(0) += 1;
+            }
+        }
+        load
+    }
+
+    /// Let an idle worker take a queued task from an overloaded one
+    ///
+    /// A worker is idle when it has no assigned or running tasks, and
+    /// overloaded when more than `steal_threshold` of its tasks are
+    /// assigned but not yet started. The victim is the overloaded worker
+    /// with the most queued tasks, ties going to the lowest node ID, and
+    /// the stolen task is the one it would start last: its highest by
+    /// event ID among those the thief is capable of running. Given the
+    /// same task history the choice is the same on replay.
+    ///
+    /// The steal is appended to the replicated log as a `TaskStolen` event
+    /// and replicated; the task moves only once the event commits. Returns
+    /// the steal if it took effect, or `None` if the thief is not idle,
+    /// there is nothing to steal, or the event is not committed yet, in
+    /// which case the task moves when a later replication commits it.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the thief is not an active member, or this node is
+    /// not leader or cannot record the steal in the replicated log
+    pub async fn steal_work(&self, thief: NodeId) -> CoreResult<Option<WorkSteal>> {
+        let member = self
+            .membership
+            .get_member(thief)
+            .await
+            .filter(|member| member.is_active())
+            .ok_or_else(|| CoreError::NotFound {
+                kind: "active member".to_string(),
+                id: thief.to_string(),
+            })?;
+
+        let tasks = self.tasks.read().await;
+        let busy = tasks.values().any(|t| {
+            t.assigned_worker == Some(thief)
+                && matches!(t.status, TaskStatus::Assigned | TaskStatus::Running)
+        });
+        if busy {
+            return Ok(None);
         }
 
-        // Simple round-robin: use first available
-        Ok(workers[0])
+        let mut queued: HashMap<NodeId, Vec<&ExecutionTask>> = HashMap::new();
+        for task in tasks.values().filter(|t| t.status == TaskStatus::Assigned) {
+            if let Some(worker) = task.assigned_worker {
+                queued.entry(worker).or_default().push(task);
+            }
+        }
+        let mut victims: Vec<(NodeId, Vec<&ExecutionTask>)> = queued
+            .into_iter()
+            .filter(|(_, queue)| queue.len() > self.config.steal_threshold)
+            .collect();
+        victims.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(&b.0)));
+
+        let Some((parent, steal)) = victims.iter().find_map(|(victim, queue)| {
+            queue
+                .iter()
+                .filter(|t| member.supports(&t.required_capabilities))
+                .max_by(|a, b| (a.event_id, &a.task_id).cmp(&(b.event_id, &b.task_id)))
+                .map(|t| {
+                    let steal = WorkSteal {
+                        task_id: t.task_id.clone(),
+                        from: *victim,
+                        to: thief,
+                    };
+                    (t.event_id, steal)
+                })
+        }) else {
+            return Ok(None);
+        };
+        drop(tasks);
+
+        let time = self.logical_time();
+        let event = Event::new(
+            steal.event_id(parent, time),
+            COORDINATOR_RUN,
+            thief,
+            LogicalTime::from_raw(time),
+            EventKind::TaskStolen,
+        )
+        .with_parent(parent)
+        .with_payload(serde_json::to_vec(&steal).unwrap_or_default());
+        self.consensus.append(event.encode()).await?;
+        self.replicate().await?;
+
+        let stolen = self.tasks.read().await.get(&steal.task_id).is_some_and(|task| {
+            task.status == TaskStatus::Assigned && task.assigned_worker == Some(thief)
+        });
+        if !stolen {
+            return Ok(None);
+        }
+        tracing::info!(task_id = %steal.task_id, from = %steal.from, to = %thief, "task stolen");
+        Ok(Some(steal))
+    }
+
+    /// Reapply a steal read back from the replicated log
+    ///
+    /// The task moves to the stealing worker only if it is still queued on
+    /// the worker it was stolen from. Returns whether it moved.
+    pub async fn apply_steal(&self, steal: &WorkSteal) -> bool {
+        steal.apply(&mut *self.tasks.write().await)
     }
 
     /// Execute a task on a worker
     ///
+    /// The attempt must finish within `execution_timeout_ms` of logical
+    /// time. If [`Coordinator::advance_clock`] moves the clock past the
+    /// deadline first, the remote execution is cancelled and the task
+    /// times out.
+    ///
+    /// The request carries the attempt's idempotency key and its result is
+    /// committed with [`Coordinator::commit_result`], so an attempt that
+    /// finishes after a retry has already succeeded changes nothing.
+    ///
     /// # Errors
     ///
-    /// Returns error if execution fails
+    /// Returns error if execution fails, [`CoordinatorError::Timeout`] if
+    /// the deadline passes, or [`CoordinatorError::Superseded`] if another
+    /// attempt's result was committed first
     pub async fn execute_task(&self, task_id: String) -> CoreResult<ExecutionResult> {
-        let (worker_id, event_id) = {
-            let tasks = self.tasks.read().await;
-            let task = tasks.get(&task_id).ok_or_else(|| CoreError::NotFound {
+        if self.election.is_stepping_down().await {
+            return Err(CoordinatorError::QuorumLost.into());
+        }
+
+        let deadline = self
+            .logical_time()
+            .saturating_add(self.config.execution_timeout_ms);
+        let (worker_id, event_id, key) = {
+            let mut tasks = self.tasks.write().await;
+            let task = tasks.get_mut(&task_id).ok_or_else(|| CoreError::NotFound {
                 kind: "task".to_string(),
                 id: task_id.clone(),
             })?;
@@ -348,28 +1153,29 @@ impl Coordinator {
                 reason: "Task not assigned".to_string(),
             })?;
 
-            (worker_id, task.event_id.clone())
+            task.status = TaskStatus::Running;
+            task.deadline = Some(deadline);
+            (worker_id, task.event_id, task.idempotency_key())
         };
 
         let start = std::time::Instant::now();
 
-        // Update task status to running
-        {
-            let mut tasks = self.tasks.write().await;
-            if let Some(task) = tasks.get_mut(&task_id) {
-                task.status = TaskStatus::Running;
-            }
-        }
-
-        // Execute remotely
+        // Execute remotely, racing the logical deadline
         let request = crate::remote::RemoteRequest::new(
             self.config.node_id,
-            event_id.clone(),
+            event_id,
             Vec::new(),
-        );
+        )
+        .with_idempotency_key(key);
+        let mut clock = self.clock.subscribe();
+        let outcome = tokio::select! {
+            biased;
+            _ = clock.wait_for(|now| *now >= deadline) => None,
+            outcome = async { self.client(worker_id).await?.send(request).await } => Some(outcome),
+        };
 
-        match self.remote.execute_remote(worker_id, request).await {
-            Ok(response) => {
+        match outcome {
+            Some(Ok(response)) => {
                 let elapsed = start.elapsed().as_millis() as u64;
                 let result = ExecutionResult::success(
                     task_id.clone(),
@@ -378,76 +1184,721 @@ impl Coordinator {
                     elapsed,
                 );
 
-                // Update task status
-                {
-                    let mut tasks = self.tasks.write().await;
-                    if let Some(task) = tasks.get_mut(&task_id) {
-                        task.status = TaskStatus::Completed;
-                    }
+                if self.commit_result(&task_id, key, result.clone()).await? {
+                    // The committed copy carries the log index it won at
+                    Ok(self.get_result(task_id).await.unwrap_or(result))
+                } else {
+                    Err(CoordinatorError::Superseded { task_id, attempt: key.attempt }.into())
                 }
-
-                // Store result
-                let mut completed = self.completed.write().await;
-                completed.insert(task_id.clone(), result.clone());
-
-                Ok(result)
             }
-            Err(e) => {
-                let mut tasks = self.tasks.write().await;
-                if let Some(task) = tasks.get_mut(&task_id) {
-                    task.status = TaskStatus::Failed;
-
-                    // Retry if under limit
-                    if task.retry_count < self.config.retry_limit {
-                        task.status = TaskStatus::Pending;
-                        task.assigned_worker = None;
-                        task.retry_count += 1;
-                    }
+            Some(Err(e)) => {
+                let dead = {
+                    let mut tasks = self.tasks.write().await;
+                    let running = tasks
+                        .get_mut(&task_id)
+                        .filter(|t| t.status == TaskStatus::Running);
+                    running.and_then(|task| self.retry_or_dead_letter(task, e.to_string()))
+                };
+                if let Some(dead) = dead
+                    && let Err(err) = self.commit_dead_letter(&dead).await
+                {
+                    tracing::warn!(%task_id, %err, "dead letter not committed");
                 }
 
                 Err(e)
             }
+            None => {
+                if let Err(err) = self.time_out(&task_id).await {
+                    tracing::warn!(%task_id, %err, "timeout not committed");
+                }
+                Err(CoordinatorError::Timeout(self.config.execution_timeout_ms).into())
+            }
         }
     }
 
-    /// Get task by ID
+    /// Commit the result of one attempt at a task; the first write wins
+    ///
+    /// Results are decided per run and node by the replicated log. The
+    /// first result committed for a node is recorded as a `NodeCompleted`
+    /// event and completes the task once the event is applied, even if the
+    /// attempt that produced it had timed out and a retry is under way.
+    /// Every later result for the node is discarded and recorded as a
+    /// `Superseded` event carrying the losing attempt's key. Both events
+    /// get IDs derived from the key, and results decided under earlier
+    /// leaders are first applied from the committed log. Returns whether
+    /// this result won.
     ///
     /// # Errors
     ///
-    /// Returns error if lock acquisition fails
-    pub async fn get_task(&self, task_id: String) -> Option<ExecutionTask> {
-        self.tasks.read().await.get(&task_id).cloned()
+    /// Returns error if the task is unknown or dead-lettered, the committed
+    /// log cannot be applied, or the outcome cannot be recorded in the
+    /// replicated log, and [`CoordinatorError::Uncommitted`] if a quorum
+    /// has not acknowledged it after one round of replication, in which
+    /// case a winning result completes the task once it commits
+    pub async fn commit_result(
+        &self,
+        task_id: &str,
+        key: IdempotencyKey,
+        result: ExecutionResult,
+    ) -> CoreResult<bool> {
+        self.apply_committed().await?;
+
+        let (node, index) = {
+            let tasks = self.tasks.read().await;
+            let task = tasks.get(task_id).ok_or_else(|| CoreError::NotFound {
+                kind: "task".to_string(),
+                id: task_id.to_string(),
+            })?;
+            if task.status == TaskStatus::DeadLettered {
+                return Err(CoordinatorError::InvalidState(format!(
+                    "task {} is dead-lettered",
+                    task_id
+                ))
+                .into());
+            }
+
+            // Held until the outcome is appended, so the log orders results
+            // the same way they were decided
+            let mut results = self.results.write().await;
+            let node = (task.run_id, task.node_id);
+            let won = match results.winners.get(&node) {
+                None => true,
+                Some(winner) if *winner == key => return Ok(true),
+                Some(_) => false,
+            };
+            let kind = if won { EventKind::NodeCompleted } else { EventKind::Superseded };
+            let payload = serde_json::to_vec(&key).map_err(|e| CoreError::ParseError {
+                message: format!("Failed to encode idempotency key: {}", e),
+            })?;
+            let event = Event::new(
+                key.event_id(kind),
+                task.run_id,
+                task.node_id,
+                LogicalTime::from_raw(self.logical_time()),
+                kind,
+            )
+            .with_parent(task.event_id)
+            .with_payload(payload);
+            let index = self.consensus.append(event.encode()).await?;
+            if won {
+                results.proposed.insert(key, result);
+            }
+            (node, index)
+        };
+
+        if self.replicate().await? <= index {
+            return Err(CoordinatorError::Uncommitted(index).into());
+        }
+        let won = self.results.read().await.winners.get(&node) == Some(&key);
+        if !won {
+            tracing::info!(task_id, %key, "result superseded");
+        }
+        Ok(won)
     }
 
-    /// Get result by task ID
+    /// Apply newly committed log entries to the coordinator's state
+    ///
+    /// Learns the results decided under other leaders: the first
+    /// `NodeCompleted` entry for a run's node wins and later ones are
+    /// ignored. A winning result this coordinator proposed completes its
+    /// task, stamped with the entry's log index. Committed `TaskStolen` entries move their tasks,
+    /// `TaskDeadLettered` and `TaskRequeued` entries move tasks into and out
+    /// of the dead-letter queue, and `MemberDraining` and `MemberRemoved`
+    /// entries drain and remove their members. Returns the number of
+    /// events applied.
     ///
     /// # Errors
     ///
-    /// Returns error if lock acquisition fails
-    pub async fn get_result(&self, task_id: String) -> Option<ExecutionResult> {
-        self.completed.read().await.get(&task_id).cloned()
+    /// Returns error if a committed entry does not decode as an event
+    pub async fn apply_committed(&self) -> CoreResult<usize> {
+        // Locked in the order commit_result uses
+        let mut tasks = self.tasks.write().await;
+        let mut results = self.results.write().await;
+        if let Some(snapshot) = self.consensus.snapshot().await
+            && snapshot.last_index > *self.snapshot_index.read().await
+        {
+            self.restore_snapshot(&snapshot, &mut tasks, &mut results).await?;
+        }
+        let commit_index = self.consensus.commit_index().await;
+        let events = self.consensus.committed_events_since(results.applied).await?;
+        for event in &events {
+            if event.kind == EventKind::NodeCompleted
+                && let Ok(key) = serde_json::from_slice::<IdempotencyKey>(&event.payload)
+            {
+                let proposed = results.proposed.remove(&key);
+                if let Entry::Vacant(winner) = results.winners.entry((event.run_id, event.node_id)) {
+                    winner.insert(key);
+                    if let Some(result) = proposed {
+                        let index = event.global_seq.unwrap_or_default();
+                        self.apply_result(&mut tasks, result.with_log_index(index)).await;
+                    }
+                }
+            } else if let Some(steal) = WorkSteal::from_event(event) {
+                steal.apply(&mut tasks);
+            } else if event.kind == EventKind::TaskDeadLettered
+                && let Ok(dead) = serde_json::from_slice::<DeadLetter>(&event.payload)
+            {
+                self.apply_dead_letter(&mut tasks, dead).await;
+            } else if event.kind == EventKind::TaskRequeued {
+                self.apply_requeue(&mut tasks, &String::from_utf8_lossy(&event.payload))
+                    .await;
+            } else if event.kind == EventKind::MemberDraining {
+                self.membership.update_state(event.node_id, MemberState::Leaving).await?;
+                for task in tasks.values_mut() {
+                    if task.assigned_worker == Some(event.node_id)
+                        && task.status == TaskStatus::Assigned
+                    {
+                        task.status = TaskStatus::Pending;
+                        task.assigned_worker = None;
+                    }
+                }
+            } else if event.kind == EventKind::MemberRemoved {
+                self.membership.remove_member(event.node_id).await?;
+            }
+        }
+
+        let last = events.iter().filter_map(|event| event.global_seq).max();
+        results.applied = commit_index.max(last.map_or(0, |seq| seq + 1));
+        Ok(events.len())
+    }
+
+    /// Replace the coordinator state with a snapshot taken by
+    /// [`Coordinator::create_snapshot`], here or on another node
+    ///
+    /// Entries committed after the snapshot are applied again on top of it.
+    async fn restore_snapshot(
+        &self,
+        snapshot: &ConsensusSnapshot,
+        tasks: &mut HashMap<String, ExecutionTask>,
+        results: &mut AppliedResults,
+    ) -> CoreResult<()> {
+        let state = snapshot.restore()?;
+        let restored: BTreeMap<String, ExecutionTask> = decode_state(&state, "tasks")?;
+        let completed: BTreeMap<String, ExecutionResult> = decode_state(&state, "completed")?;
+        let winners: Vec<IdempotencyKey> = decode_state(&state, "winners")?;
+        let dead_letters: Vec<DeadLetter> = decode_state(&state, "dead_letters")?;
+        let dead_letter_log: Vec<DeadLetterTransition> =
+            decode_state(&state, "dead_letter_log")?;
+
+        *tasks = restored.into_iter().collect();
+        *self.completed.write().await = completed.into_iter().collect();
+        results.winners = winners
+            .into_iter()
+            .map(|key| ((key.run_id, key.node_id), key))
+            .collect();
+        results.proposed.clear();
+        results.applied = snapshot.last_index;
+        *self.dead_letters.write().await = dead_letters;
+        *self.dead_letter_log.write().await = dead_letter_log;
+        *self.snapshot_index.write().await = snapshot.last_index;
+        self.capacity_freed.notify_waiters();
+        tracing::info!(last_index = snapshot.last_index, "coordinator state restored from snapshot");
+        Ok(())
     }
 
-    /// Create a snapshot
+    /// Complete a task with the result that won its node
+    async fn apply_result(
+        &self,
+        tasks: &mut HashMap<String, ExecutionTask>,
+        result: ExecutionResult,
+    ) {
+        if let Some(task) = tasks.get_mut(&result.task_id) {
+            task.status = TaskStatus::Completed;
+            task.deadline = None;
+        }
+        self.completed.write().await.insert(result.task_id.clone(), result);
+        self.capacity_freed.notify_waiters();
+    }
+
+    /// Check the leader lease and hand off leadership if it was lost
+    ///
+    /// While the lease is lost, submissions are rejected with
+    /// [`CoordinatorError::QuorumLost`] and no new executions start.
+    /// Assigned tasks that have not started go back to pending so the next
+    /// leader can place them; once no task is running, this node steps
+    /// down. Returns true if this node is leader and holds the lease.
     ///
     /// # Errors
     ///
-    /// Returns error if snapshot creation fails
-    pub async fn create_snapshot(&self) -> CoreResult<u64> {
-        let mut index = self.snapshot_index.write().await;
-        *index += 1;
+    /// Returns error if lock acquisition fails
+    pub async fn check_lease(&self, now: u64) -> CoreResult<bool> {
+        if self.election.check_lease(now).await {
+            return Ok(true);
+        }
+        if !self.election.is_stepping_down().await {
+            return Ok(false);
+        }
+
+        let running = {
+            let mut tasks = self.tasks.write().await;
+            let mut running = 0;
+            for task in tasks.values_mut() {
+                match task.status {
+                    TaskStatus::Assigned => {
+                        task.status = TaskStatus::Pending;
+                        task.assigned_worker = None;
+                    }
+                    TaskStatus::Running => running += 1,
+                    _ => {}
+                }
+            }
+            running
+        };
+
+        if running == 0 {
+            tracing::info!("in-flight tasks drained, stepping down");
+            self.election.step_down().await;
+        }
+        Ok(false)
+    }
+
+    /// Get the client for a cluster member, dialing the address it
+    /// registered with over gRPC if it has none yet
+    async fn client(&self, node_id: NodeId) -> CoreResult<RemoteClient> {
+        if let Some(client) = self.remote.get_client(node_id).await {
+            return Ok(client);
+        }
+        let member = self
+            .membership
+            .get_member(node_id)
+            .await
+            .ok_or_else(|| CoreError::NotFound {
+                kind: "member".to_string(),
+                id: node_id.to_string(),
+            })?;
+        Ok(self.remote.dial(node_id, &member.address).await?)
+    }
+
+    /// Replicate the log to every peer over the transport
+    ///
+    /// Each peer is sent the next message built by
+    /// [`Consensus::replication_request`], entries or a snapshot, and its
+    /// reply is fed back into consensus. Peers that cannot be reached are
+    /// skipped until the next round. A reply from a later term makes this
+    /// node step down. Newly committed entries are then applied with
+    /// [`Coordinator::apply_committed`]. Returns the commit index afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if this node is not leader, consensus fails to handle
+    /// a reply, or the committed entries cannot be applied
+    pub async fn replicate(&self) -> CoreResult<u64> {
+        for peer in self.consensus.peers().await {
+            let replication = self.consensus.replication_request(peer).await?;
+            let client = match self.client(peer).await {
+                Ok(client) => client,
+                Err(err) => {
+                    tracing::debug!(%peer, %err, "no route to peer");
+                    continue;
+                }
+            };
+            let response = match &replication {
+                Replication::Entries(request) => client.append_entries(request).await,
+                Replication::Snapshot(request) => client.transfer_snapshot(request).await,
+            };
+            match response {
+                Ok(response) => {
+                    self.consensus.handle_append_response(peer, response).await?;
+                }
+                Err(err) => tracing::debug!(%peer, %err, "replication not delivered"),
+            }
+            if self.consensus.state().await != ConsensusState::Leader {
+                self.election.step_down().await;
+                return Err(ConsensusError::NotLeader.into());
+            }
+        }
+        let committed = self.consensus.advance_commit().await?;
+        self.apply_committed().await?;
+        Ok(committed)
+    }
+
+    /// Stand for election, asking every peer for its vote over the transport
+    ///
+    /// Returns true once a quorum has voted for this node, which is then
+    /// leader; peers that cannot be reached count as refusals.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the election cannot be started or a vote cannot be
+    /// counted
+    pub async fn campaign(&self) -> CoreResult<bool> {
+        self.election.start_election().await?;
+        let term = self.consensus.current_term().await;
+        if self.election.receive_vote(self.config.node_id, term).await? {
+            return Ok(true);
+        }
+
+        let (last_log_index, last_log_term) = self.consensus.last_log().await;
+        let request = RequestVoteRequest {
+            term,
+            candidate_id: self.config.node_id,
+            last_log_index,
+            last_log_term,
+        };
+        for peer in self.consensus.peers().await {
+            let response = match self.client(peer).await {
+                Ok(client) => client.request_vote(&request).await.map_err(CoreError::from),
+                Err(err) => Err(err),
+            };
+            match response {
+                Ok(response) if response.vote_granted => {
+                    if self.election.receive_vote(peer, term).await? {
+                        return Ok(true);
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => tracing::debug!(%peer, %err, "vote not received"),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Return a failed attempt to the queue, or dead-letter the task once
+    /// its retries are exhausted
+    ///
+    /// A task out of retries is marked failed. The returned dead letter
+    /// must be committed with [`Coordinator::commit_dead_letter`], and the
+    /// task enters the queue once the entry is applied.
+    fn retry_or_dead_letter(&self, task: &mut ExecutionTask, error: String) -> Option<DeadLetter> {
+        task.deadline = None;
+        if task.retry_count < self.config.retry_limit {
+            task.status = TaskStatus::Pending;
+            task.assigned_worker = None;
+            task.retry_count += 1;
+            None
+        } else {
+            task.status = TaskStatus::Failed;
+            self.capacity_freed.notify_waiters();
+            Some(DeadLetter {
+                task: task.clone().with_status(TaskStatus::DeadLettered),
+                error,
+            })
+        }
+    }
+
+    /// Get the coordinator's logical time
+    #[must_use]
+    pub fn logical_time(&self) -> u64 {
+        *self.clock.borrow()
+    }
+
+    /// Advance the logical clock and time out overdue tasks
+    ///
+    /// In-flight executions observe the new time and cancel themselves;
+    /// running tasks whose execution is no longer awaited are timed out
+    /// here. Returns the IDs of the tasks this call timed out, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a timeout cannot be recorded in the replicated log
+    pub async fn advance_clock(&self, ticks: u64) -> CoreResult<Vec<String>> {
+        self.clock.send_modify(|now| *now = now.saturating_add(ticks));
+        let now = self.logical_time();
+
+        let mut overdue: Vec<(u64, String)> = self
+            .tasks
+            .read()
+            .await
+            .values()
+            .filter(|t| t.status == TaskStatus::Running)
+            .filter_map(|t| {
+                t.deadline
+                    .filter(|deadline| *deadline <= now)
+                    .map(|deadline| (deadline, t.task_id.clone()))
+            })
+            .collect();
+        overdue.sort();
+
+        let mut timed_out = Vec::new();
+        for (_, task_id) in overdue {
+            if self.time_out(&task_id).await? {
+                timed_out.push(task_id);
+            }
+        }
+        Ok(timed_out)
+    }
+
+    /// Time out a running task whose deadline has passed
+    ///
+    /// Sends the task down the retry path and commits a `TaskTimedOut`
+    /// event to the replicated log, ahead of the dead letter the timeout
+    /// may cause. Returns false if the task was not running or not yet
+    /// overdue, so each attempt times out at most once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoordinatorError::Uncommitted`] if a quorum has not
+    /// acknowledged the timeout after one round of replication, or error
+    /// if it cannot be appended
+    async fn time_out(&self, task_id: &str) -> CoreResult<bool> {
+        let now = self.logical_time();
+        let (event, dead) = {
+            let mut tasks = self.tasks.write().await;
+            let Some(task) = tasks.get_mut(task_id).filter(|t| {
+                t.status == TaskStatus::Running && t.deadline.is_some_and(|d| d <= now)
+            }) else {
+                return Ok(false);
+            };
+            let deadline = task.deadline.unwrap_or(now);
+            let worker = task.assigned_worker.unwrap_or(self.config.node_id);
+
+            let event = Event::new(
+                task.idempotency_key().event_id(EventKind::TaskTimedOut),
+                COORDINATOR_RUN,
+                worker,
+                LogicalTime::from_raw(deadline),
+                EventKind::TaskTimedOut,
+            )
+            .with_parent(task.event_id)
+            .with_payload(task_id.as_bytes().to_vec());
+
+            tracing::warn!(task_id, deadline, "task timed out");
+            let dead = self.retry_or_dead_letter(
+                task,
+                CoordinatorError::Timeout(self.config.execution_timeout_ms).to_string(),
+            );
+            (event, dead)
+        };
+
+        let timed_out = self.commit_event(event).await;
+        match dead {
+            // Appended after the timeout, so its commit covers the timeout too
+            Some(dead) => self.commit_dead_letter(&dead).await?,
+            None => timed_out?,
+        }
+        Ok(true)
+    }
+
+    /// Record a dead-lettered task in the replicated log
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoordinatorError::Uncommitted`] if the entry is not
+    /// committed yet, or error if it cannot be appended
+    async fn commit_dead_letter(&self, dead: &DeadLetter) -> CoreResult<()> {
+        let payload = serde_json::to_vec(dead).unwrap_or_default();
+        self.commit_command(EventKind::TaskDeadLettered, dead.task.node_id, payload)
+            .await
+    }
+
+    /// Move a task into the dead-letter queue and log the transition
+    async fn apply_dead_letter(
+        &self,
+        tasks: &mut HashMap<String, ExecutionTask>,
+        dead: DeadLetter,
+    ) {
+        let task_id = dead.task.task_id.clone();
+        tracing::warn!(%task_id, error = %dead.error, "task dead-lettered");
+        tasks.insert(task_id.clone(), dead.task.clone());
+        self.capacity_freed.notify_waiters();
+        self.dead_letter_log
+            .write()
+            .await
+            .push(DeadLetterTransition::DeadLettered {
+                task_id,
+                attempts: dead.task.retry_count + 1,
+                error: dead.error.clone(),
+            });
+        self.dead_letters.write().await.push(dead);
+    }
+
+    /// Move a task out of the dead-letter queue, back to pending with a
+    /// fresh retry budget, and log the transition
+    async fn apply_requeue(&self, tasks: &mut HashMap<String, ExecutionTask>, task_id: &str) {
+        let mut dead_letters = self.dead_letters.write().await;
+        let Some(index) = dead_letters.iter().position(|d| d.task.task_id == task_id) else {
+            return;
+        };
+        let mut task = dead_letters.remove(index).task;
+        drop(dead_letters);
+
+        task.status = TaskStatus::Pending;
+        task.assigned_worker = None;
+        task.retry_count = 0;
+        task.deadline = None;
+        tasks.insert(task_id.to_string(), task);
+
+        tracing::info!(task_id, "dead-lettered task requeued");
+        self.dead_letter_log
+            .write()
+            .await
+            .push(DeadLetterTransition::Requeued {
+                task_id: task_id.to_string(),
+            });
+    }
+
+    /// Get tasks that exhausted their retries, in the order they failed
+    pub async fn dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.read().await.clone()
+    }
+
+    /// Get the log of dead-letter queue transitions
+    pub async fn dead_letter_log(&self) -> Vec<DeadLetterTransition> {
+        self.dead_letter_log.read().await.clone()
+    }
+
+    /// Move a dead-lettered task back to pending with a fresh retry budget
+    ///
+    /// The task becomes outstanding again, so it waits for capacity like a
+    /// submission. The requeue is recorded as a `TaskRequeued` event in the
+    /// replicated log and takes effect once the event commits.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the task is not in the dead-letter queue,
+    /// [`CoordinatorError::Backpressure`] if the cluster stays saturated,
+    /// [`CoordinatorError::Uncommitted`] if the entry is not committed yet,
+    /// or error if this node cannot append to the replicated log
+    pub async fn requeue(&self, task_id: String) -> CoreResult<()> {
+        let node_id = self
+            .dead_letters
+            .read()
+            .await
+            .iter()
+            .find(|d| d.task.task_id == task_id)
+            .map(|d| d.task.node_id)
+            .ok_or_else(|| CoreError::NotFound {
+                kind: "dead letter".to_string(),
+                id: task_id.clone(),
+            })?;
+
+        drop(self.reserve().await?);
+        self.commit_command(EventKind::TaskRequeued, node_id, task_id.into_bytes())
+            .await
+    }
+
+    /// Stop a cancelled run's jobs on every connected worker
+    ///
+    /// Returns the IDs of the stopped jobs, by worker.
+    pub async fn cancel_run(&self, run_id: RunId) -> Vec<(NodeId, Vec<String>)> {
+        let cancelled = self.remote.cancel_run(run_id).await;
+        tracing::info!(%run_id, workers = cancelled.len(), "run cancelled on workers");
+        cancelled
+    }
 
-        // In a real implementation, this would serialize state
-        let _ = (self.tasks.read().await, self.completed.read().await);
+    /// Drop a finished run's idempotency state on every connected worker
+    pub async fn finish_run(&self, run_id: RunId) {
+        self.remote.finish_run(run_id).await;
+    }
 
-        Ok(*index)
+    /// Get task by ID
+    ///
+    /// # Errors
+    ///
+    /// Returns error if lock acquisition fails
+    pub async fn get_task(&self, task_id: String) -> Option<ExecutionTask> {
+        self.tasks.read().await.get(&task_id).cloned()
     }
 
-    /// Get current snapshot index
+    /// Get result by task ID
     ///
     /// # Errors
     ///
     /// Returns error if lock acquisition fails
+    pub async fn get_result(&self, task_id: String) -> Option<ExecutionResult> {
+        self.completed.read().await.get(&task_id).cloned()
+    }
+
+    /// Get all completed results in a stable order
+    ///
+    /// Results are ordered by the index of the log entry that committed
+    /// them, then by task ID. The log is the same on every replica and on
+    /// replay, so reports list completed work identically across runs.
+    pub async fn completed_results_sorted(&self) -> Vec<ExecutionResult> {
+        let mut results: Vec<ExecutionResult> =
+            self.completed.read().await.values().cloned().collect();
+        results.sort_by(|a, b| (a.log_index, &a.task_id).cmp(&(b.log_index, &b.task_id)));
+        results
+    }
+
+    /// Snapshot the coordinator state and compact the replicated log
+    /// behind it
+    ///
+    /// A leader first commits a `SnapshotCreated` entry so the snapshot
+    /// covers a log position of its own; if a quorum does not acknowledge
+    /// it, the snapshot covers what was committed before. Tasks, completed
+    /// results, the winning attempt of each node and the dead-letter queue,
+    /// as applied from the log, are stored as a [`ConsensusSnapshot`] in
+    /// consensus storage, which sends it to peers whose log is too far
+    /// behind. A peer restores it in [`Coordinator::apply_committed`].
+    /// Returns the number of log entries the snapshot covers, unchanged if
+    /// nothing was applied since the last one.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the state cannot be encoded or the log cannot be
+    /// compacted
+    pub async fn create_snapshot(&self) -> CoreResult<u64> {
+        if self.consensus.state().await == ConsensusState::Leader
+            && let Err(err) = self
+                .commit_command(EventKind::SnapshotCreated, self.config.node_id, Vec::new())
+                .await
+        {
+            tracing::warn!(%err, "snapshot marker not committed");
+        }
+
+        let tasks = self.tasks.read().await;
+        let results = self.results.read().await;
+        let mut snapshot_index = self.snapshot_index.write().await;
+        let last_index = results.applied;
+        if last_index <= *snapshot_index {
+            return Ok(*snapshot_index);
+        }
+        let last_term = self.consensus.term_before(last_index).await.ok_or_else(|| {
+            ConsensusError::InvalidEntry(format!("log entry {} was compacted", last_index))
+        })?;
+
+        let mut winners: Vec<IdempotencyKey> = results.winners.values().copied().collect();
+        winners.sort();
+        let state = BTreeMap::from([
+            encode_state("tasks", &tasks.iter().collect::<BTreeMap<_, _>>())?,
+            encode_state(
+                "completed",
+                &self.completed.read().await.iter().collect::<BTreeMap<_, _>>(),
+            )?,
+            encode_state("winners", &winners)?,
+            encode_state("dead_letters", &*self.dead_letters.read().await)?,
+            encode_state("dead_letter_log", &*self.dead_letter_log.read().await)?,
+        ]);
+        let snapshot = ConsensusSnapshot::from_state(
+            format!("coordinator-{}", last_index),
+            last_index,
+            last_term,
+            state,
+        );
+        self.consensus.compact(snapshot).await?;
+        *snapshot_index = last_index;
+        Ok(last_index)
+    }
+
+    /// Send the latest snapshot to every peer, so whichever is elected
+    /// next starts from this leader's state
+    async fn send_snapshot(&self) {
+        let Some(snapshot) = self.consensus.snapshot().await else {
+            return;
+        };
+        let request = InstallSnapshotRequest {
+            term: self.consensus.current_term().await,
+            leader_id: self.config.node_id,
+            snapshot,
+        };
+        for peer in self.consensus.peers().await {
+            let response = match self.client(peer).await {
+                Ok(client) => client.transfer_snapshot(&request).await.map_err(CoreError::from),
+                Err(err) => Err(err),
+            };
+            let handled = match response {
+                Ok(response) => self.consensus.handle_append_response(peer, response).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = handled {
+                tracing::debug!(%peer, %err, "snapshot not delivered");
+            }
+        }
+    }
+
+    /// Get the number of log entries covered by the latest snapshot taken
+    /// or restored
     pub async fn snapshot_index(&self) -> u64 {
         *self.snapshot_index.read().await
     }
@@ -485,7 +1936,7 @@ impl Coordinator {
         let mut results = Vec::new();
 
         for task in pending {
-            let worker = self.select_worker().await?;
+            let worker = self.select_worker_for(&task.required_capabilities).await?;
             self.assign_task(task.task_id.clone(), worker).await?;
 
             match self.execute_task(task.task_id.clone()).await {
@@ -497,6 +1948,265 @@ impl Coordinator {
         Ok(results)
     }
 
+    /// Shut down in a fixed order: drain, snapshot, step down, unregister
+    ///
+    /// Draining runs pending tasks until none remain, no worker can take
+    /// them, or `deadline` has elapsed; tasks still outstanding are kept
+    /// in the snapshot for recovery, which a leader sends to every peer
+    /// before stepping down. The remaining steps always run so a new
+    /// leader can be elected. Each step is logged as it completes.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if snapshotting or unregistering fails
+    pub async fn shutdown(&self, deadline: Duration) -> CoreResult<Vec<ShutdownStep>> {
+        let started = Instant::now();
+        let mut steps = Vec::new();
+
+        let mut completed = 0;
+        while started.elapsed() < deadline && !self.pending_tasks().await.is_empty() {
+            match self.process_pending().await {
+                Ok(results) => completed += results.len(),
+                Err(_) => break,
+            }
+        }
+        let mut remaining: Vec<String> = self
+            .tasks
+            .read()
+            .await
+            .values()
+            .filter(|t| {
+                matches!(
+                    t.status,
+                    TaskStatus::Pending | TaskStatus::Assigned | TaskStatus::Running
+                )
+            })
+            .map(|t| t.task_id.clone())
+            .collect();
+        remaining.sort();
+        Self::log_shutdown_step(&mut steps, ShutdownStep::Drained { completed, remaining });
+
+        let index = self.create_snapshot().await?;
+        if self.consensus.state().await == ConsensusState::Leader {
+            self.send_snapshot().await;
+        }
+        Self::log_shutdown_step(&mut steps, ShutdownStep::Snapshotted { index });
+
+        let was_leader =
+            self.election.is_leader().await || self.election.is_stepping_down().await;
+        if was_leader {
+            self.election.step_down().await;
+        }
+        Self::log_shutdown_step(&mut steps, ShutdownStep::SteppedDown { was_leader });
+
+        self.membership.remove_member(self.config.node_id).await?;
+        Self::log_shutdown_step(
+            &mut steps,
+            ShutdownStep::Unregistered {
+                node_id: self.config.node_id,
+            },
+        );
+
+        Ok(steps)
+    }
+
+    /// Record a completed shutdown step
+    fn log_shutdown_step(steps: &mut Vec<ShutdownStep>, step: ShutdownStep) {
+        tracing::info!(sequence = steps.len() + 1, ?step, "coordinator shutdown step");
+        steps.push(step);
+    }
+
+    /// Drain a worker: stop assigning to it, wait for its jobs, deregister
+    ///
+    /// The drain is recorded as a `MemberDraining` event in the replicated
+    /// log, and once it commits the worker is marked
+    /// [`MemberState::Leaving`], so it is no longer selected, and the tasks
+    /// queued on it are returned to pending for other workers. Its running
+    /// tasks are awaited until they finish or `deadline` has elapsed. Only
+    /// if none remain is a `MemberRemoved` event committed, removing the
+    /// worker from membership, so a drain that times out can be retried.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the node is this coordinator or not a member, this
+    /// node is not leader, or a change cannot be committed
+    pub async fn drain(&self, node_id: NodeId, deadline: Duration) -> CoreResult<DrainReport> {
+        if node_id == self.config.node_id {
+            return Err(CoordinatorError::InvalidState(
+                "The coordinator cannot drain itself; shut it down instead".to_string(),
+            )
+            .into());
+        }
+        if !self.election.is_leader().await {
+            return Err(ConsensusError::NotLeader.into());
+        }
+        if self.membership.get_member(node_id).await.is_none() {
+            return Err(CoreError::NotFound {
+                kind: "member".to_string(),
+                id: node_id.to_string(),
+            });
+        }
+
+        let mut queued: Vec<String> = self
+            .tasks
+            .read()
+            .await
+            .values()
+            .filter(|t| t.assigned_worker == Some(node_id) && t.status == TaskStatus::Assigned)
+            .map(|t| t.task_id.clone())
+            .collect();
+        queued.sort();
+        self.commit_member_change(EventKind::MemberDraining, node_id).await?;
+        tracing::info!(%node_id, "draining worker");
+
+        let tasks = self.tasks.read().await;
+        let requeued: Vec<String> = queued
+            .into_iter()
+            .filter(|id| tasks.get(id).is_some_and(|t| t.status == TaskStatus::Pending))
+            .collect();
+        drop(tasks);
+
+        let started = Instant::now();
+        let remaining = loop {
+            let mut running: Vec<String> = self
+                .tasks
+                .read()
+                .await
+                .values()
+                .filter(|t| t.assigned_worker == Some(node_id) && t.status == TaskStatus::Running)
+                .map(|t| t.task_id.clone())
+                .collect();
+            if running.is_empty() || started.elapsed() >= deadline {
+                running.sort();
+                break running;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+
+        if remaining.is_empty() {
+            self.commit_member_change(EventKind::MemberRemoved, node_id).await?;
+        }
+        let deregistered = self.membership.get_member(node_id).await.is_none();
+        let report = DrainReport {
+            node_id,
+            requeued,
+            remaining,
+            deregistered,
+        };
+        tracing::info!(?report, "worker drained");
+        Ok(report)
+    }
+
+    /// Record a change to a member in the replicated log and apply it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoordinatorError::Uncommitted`] if a quorum has not
+    /// acknowledged the change after one round of replication, which then
+    /// takes effect once it commits, or error if it cannot be appended
+    async fn commit_member_change(&self, kind: EventKind, node_id: NodeId) -> CoreResult<()> {
+        self.commit_command(kind, node_id, Vec::new()).await
+    }
+
+    /// Record a command in the replicated log and apply it
+    ///
+    /// The event ID is derived from the command and the log position it is
+    /// appended at.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoordinatorError::Uncommitted`] if a quorum has not
+    /// acknowledged the command after one round of replication, which then
+    /// takes effect once it commits, or error if it cannot be appended
+    async fn commit_command(
+        &self,
+        kind: EventKind,
+        node_id: NodeId,
+        payload: Vec<u8>,
+    ) -> CoreResult<()> {
+        let (len, term) = self.consensus.last_log().await;
+        let mut seed = Vec::with_capacity(33 + payload.len());
+        seed.extend_from_slice(node_id.as_bytes());
+        seed.push(kind.code());
+        seed.extend_from_slice(&len.to_le_bytes());
+        seed.extend_from_slice(&term.to_le_bytes());
+        seed.extend_from_slice(&payload);
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&Hash::compute(&seed).as_bytes()[..16]);
+
+        let event = Event::new(
+            EventId::from_bytes(bytes),
+            COORDINATOR_RUN,
+            node_id,
+            LogicalTime::from_raw(self.logical_time()),
+            kind,
+        )
+        .with_payload(payload);
+        self.commit_event(event).await
+    }
+
+    /// Record an event in the replicated log and apply it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CoordinatorError::Uncommitted`] if a quorum has not
+    /// acknowledged the event after one round of replication, which then
+    /// takes effect once it commits, or error if it cannot be appended
+    async fn commit_event(&self, event: Event) -> CoreResult<()> {
+        let index = self.consensus.append(event.encode()).await?;
+        if self.replicate().await? <= index {
+            return Err(CoordinatorError::Uncommitted(index).into());
+        }
+        Ok(())
+    }
+
+    /// Record the consensus term and the age of every member's last
+    /// heartbeat, measured against the coordinator's logical clock
+    pub async fn observe_metrics(&self, metrics: &mut Metrics) {
+        metrics.record_term(self.consensus.current_term().await);
+        let now = self.logical_time();
+        for member in self.membership.members().await {
+            metrics.record_heartbeat_age(member.node_id, now.saturating_sub(member.last_heartbeat));
+        }
+    }
+
+    /// Get the leader, term, voters and the state of every member
+    ///
+    /// Heartbeat ages are measured against the coordinator's logical clock.
+    pub async fn status(&self) -> ClusterStatus {
+        let now = self.logical_time();
+        let voters = self.voters().await;
+        let load = self.worker_load().await;
+        let mut members: Vec<MemberStatus> = self
+            .membership
+            .members()
+            .await
+            .into_iter()
+            .map(|member| MemberStatus {
+                node_id: member.node_id,
+                heartbeat_age: now.saturating_sub(member.last_heartbeat),
+                in_flight: load.get(&member.node_id).copied().unwrap_or(0),
+                voter: voters.contains(&member.node_id),
+                address: member.address,
+                state: member.state,
+            })
+            .collect();
+        members.sort_by_key(|member| member.node_id);
+
+        ClusterStatus {
+            node_id: self.config.node_id,
+            leader: self.election.leader().await,
+            term: self.consensus.current_term().await,
+            commit_index: self.consensus.commit_index().await,
+            logical_time: now,
+            voters: voters.into_iter().collect(),
+            reconfiguring: self.consensus.reconfiguration_in_progress().await,
+            healthy: self.is_healthy().await,
+            outstanding: self.outstanding_task_count().await,
+            members,
+        }
+    }
+
     /// Check if coordinator is healthy
     ///
     /// # Errors
@@ -526,26 +2236,87 @@ mod tests {
     use super::*;
     use crate::{consensus::ConsensusConfig, leader::ElectionConfig};
 
-    #[tokio::test]
-    async fn test_coordinator_config_new() {
-        let node_id = NodeId::new();
-        let config = CoordinatorConfig::new(node_id);
-        assert_eq!(config.node_id, node_id);
-        assert_eq!(config.max_concurrent, 100);
-        assert_eq!(config.execution_timeout_ms, 30000);
+    /// Register an active worker that runs `slots` jobs at once
+    async fn add_worker(membership: &Membership, worker: NodeId, slots: usize) {
+        let member = crate::membership::Member::new(worker, worker.to_string())
+            .with_state(MemberState::Active)
+            .with_max_concurrent(slots);
+        membership.add_member(member).await.unwrap();
     }
 
-    #[tokio::test]
-    async fn test_coordinator_config_with_max_concurrent() {
-        let config = CoordinatorConfig::new(NodeId::new()).with_max_concurrent(50);
-        assert_eq!(config.max_concurrent, 50);
+    /// Build a coordinator that leads its election and a replicated log on
+    /// which it is the only voter
+    async fn leader_coordinator(
+        config: CoordinatorConfig,
+        membership: &Arc<Membership>,
+    ) -> Coordinator {
+        leader_coordinator_with_peers(config, membership, &[]).await
     }
 
-    #[tokio::test]
-    async fn test_execution_task_new() {
-        let event_id = EventId::new();
-        let task = ExecutionTask::new(event_id.clone());
-        assert_eq!(task.event_id, event_id);
+    /// Build a coordinator that leads its election and a replicated log
+    /// shared with `peers`, which cannot be reached, so entries that need
+    /// them commit only once their acknowledgements are fed in with
+    /// [`acknowledge`]
+    async fn leader_coordinator_with_peers(
+        config: CoordinatorConfig,
+        membership: &Arc<Membership>,
+        peers: &[NodeId],
+    ) -> Coordinator {
+        let node_id = config.node_id;
+        let voters: Vec<NodeId> = std::iter::once(node_id).chain(peers.iter().copied()).collect();
+        let consensus = Arc::new(Consensus::new(
+            ConsensusConfig::new(node_id).with_voters(voters.clone()),
+        ));
+        consensus.start_election().await.unwrap();
+        for voter in voters {
+            consensus.receive_vote(voter, 1).await.unwrap();
+        }
+        assert_eq!(consensus.state().await, ConsensusState::Leader);
+        let election = Arc::new(LeaderElection::new(
+            ElectionConfig::new(node_id),
+            consensus.clone(),
+            membership.clone(),
+        ));
+        election.set_state(crate::leader::ElectionState::Leader).await;
+        Coordinator::new(
+            config,
+            consensus,
+            election,
+            membership.clone(),
+            Arc::new(RemoteExecutor::new(node_id)),
+        )
+    }
+
+    /// Feed in `peer`'s acknowledgement of the first `match_index` entries
+    async fn acknowledge(consensus: &Consensus, peer: NodeId, match_index: u64) {
+        let response = crate::consensus::AppendEntriesResponse {
+            term: 1,
+            success: true,
+            match_index,
+        };
+        consensus.handle_append_response(peer, response).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_config_new() {
+        let node_id = NodeId::new();
+        let config = CoordinatorConfig::new(node_id);
+        assert_eq!(config.node_id, node_id);
+        assert_eq!(config.max_concurrent, 100);
+        assert_eq!(config.execution_timeout_ms, 30000);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_config_with_max_concurrent() {
+        let config = CoordinatorConfig::new(NodeId::new()).with_max_concurrent(50);
+        assert_eq!(config.max_concurrent, 50);
+    }
+
+    #[tokio::test]
+    async fn test_execution_task_new() {
+        let event_id = EventId::new();
+        let task = ExecutionTask::new(event_id.clone());
+        assert_eq!(task.event_id, event_id);
         assert_eq!(task.status, TaskStatus::Pending);
         assert!(task.assigned_worker.is_none());
         assert_eq!(task.retry_count, 0);
@@ -615,28 +2386,8 @@ mod tests {
     #[tokio::test]
     async fn test_coordinator_submit() {
         let node_id = NodeId::new();
-        let config = CoordinatorConfig::new(node_id);
-        let consensus_config = crate::consensus::ConsensusConfig::new(node_id);
-        let consensus = Arc::new(Consensus::new(consensus_config));
-        let election_config = ElectionConfig::new(node_id);
-        let election = Arc::new(LeaderElection::new(
-            election_config,
-            consensus.clone(),
-            Arc::new(Membership::new(node_id)),
-        ));
-        // Set as leader
-        election.set_state(crate::leader::ElectionState::Leader).await;
-
         let membership = Arc::new(Membership::new(node_id));
-        let remote = Arc::new(RemoteExecutor::new(node_id));
-
-        let coordinator = Coordinator::new(
-            config,
-            consensus,
-            election,
-            membership,
-            remote,
-        );
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
 
         let event_id = EventId::new();
         let task_id = coordinator.submit(event_id).await;
@@ -644,30 +2395,104 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_coordinator_assign_task() {
+    async fn test_coordinator_submit_backpressure() {
         let node_id = NodeId::new();
-        let config = CoordinatorConfig::new(node_id);
-        let consensus_config = crate::consensus::ConsensusConfig::new(node_id);
-        let consensus = Arc::new(Consensus::new(consensus_config));
-        let election_config = ElectionConfig::new(node_id);
-        let election = Arc::new(LeaderElection::new(
-            election_config,
-            consensus.clone(),
-            Arc::new(Membership::new(node_id)),
-        ));
-        // Set as leader
-        election.set_state(crate::leader::ElectionState::Leader).await;
+        let config = CoordinatorConfig::new(node_id)
+            .with_max_concurrent(4)
+            .with_retry_after(250);
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(config, &membership).await;
+
+        // Without workers, tasks queue up to the coordinator's ceiling
+        assert_eq!(coordinator.capacity().await, 4);
+        coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.submit(EventId::new()).await.unwrap();
+
+        add_worker(&membership, NodeId::new(), 2).await;
+        assert_eq!(coordinator.capacity().await, 2);
+
+        let err = coordinator.admit().await.unwrap_err();
+        assert_eq!(
+            err,
+            CoordinatorError::Backpressure {
+                outstanding: 2,
+                capacity: 2,
+                retry_after_ms: 250,
+            }
+        );
+        assert!(coordinator.submit(EventId::new()).await.is_err());
+        assert_eq!(coordinator.outstanding_task_count().await, 2);
 
+        // Capacity grows with the workers, up to the coordinator's ceiling
+        add_worker(&membership, NodeId::new(), 1_000).await;
+        assert_eq!(coordinator.capacity().await, 4);
+        coordinator.submit(EventId::new()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_concurrent_submissions_respect_capacity() {
+        let node_id = NodeId::new();
         let membership = Arc::new(Membership::new(node_id));
-        let remote = Arc::new(RemoteExecutor::new(node_id));
+        add_worker(&membership, NodeId::new(), 3).await;
+        let coordinator =
+            Arc::new(leader_coordinator(CoordinatorConfig::new(node_id), &membership).await);
+        let submissions: Vec<_> = (0..16)
+            .map(|_| {
+                let coordinator = coordinator.clone();
+                tokio::spawn(async move { coordinator.submit(EventId::new()).await })
+            })
+            .collect();
+        let mut accepted = 0;
+        for submission in submissions {
+            if submission.await.unwrap().is_ok() {
+                accepted += 1;
+            }
+        }
 
-        let coordinator = Coordinator::new(
-            config,
-            consensus,
-            election,
-            membership,
-            remote,
-        );
+        assert_eq!(accepted, 3);
+        assert_eq!(coordinator.outstanding_task_count().await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_submit_blocks_until_deadline() {
+        let node_id = NodeId::new();
+        let config = CoordinatorConfig::new(node_id).with_submit_deadline(30);
+        let membership = Arc::new(Membership::new(node_id));
+        add_worker(&membership, NodeId::new(), 1).await;
+        let coordinator = leader_coordinator(config, &membership).await;
+
+        coordinator.submit(EventId::new()).await.unwrap();
+        let start = Instant::now();
+        assert!(coordinator.submit(EventId::new()).await.is_err());
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_blocked_submission_wakes_when_worker_joins() {
+        let node_id = NodeId::new();
+        let config = CoordinatorConfig::new(node_id).with_submit_deadline(60_000);
+        let membership = Arc::new(Membership::new(node_id));
+        add_worker(&membership, NodeId::new(), 1).await;
+        let coordinator = Arc::new(leader_coordinator(config, &membership).await);
+        coordinator.submit(EventId::new()).await.unwrap();
+        let blocked = {
+            let coordinator = coordinator.clone();
+            tokio::spawn(async move { coordinator.submit(EventId::new()).await })
+        };
+        tokio::task::yield_now().await;
+        assert!(!blocked.is_finished());
+
+        add_worker(&membership, NodeId::new(), 1).await;
+        let admitted = tokio::time::timeout(Duration::from_secs(5), blocked).await;
+        assert!(admitted.unwrap().unwrap().is_ok());
+        assert_eq!(coordinator.outstanding_task_count().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_assign_task() {
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
 
         let event_id = EventId::new();
         let task_id = coordinator.submit(event_id).await.unwrap();
@@ -682,33 +2507,918 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_coordinator_create_snapshot() {
+    async fn test_coordinator_select_worker_by_capability() {
+        use crate::membership::{Member, MemberState};
+        use cathedral_core::Capability;
+
         let node_id = NodeId::new();
-        let config = CoordinatorConfig::new(node_id);
-        let consensus_config = crate::consensus::ConsensusConfig::new(node_id);
-        let consensus = Arc::new(Consensus::new(consensus_config));
-        let election_config = ElectionConfig::new(node_id);
-        let election = Arc::new(LeaderElection::new(
-            election_config,
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+
+        let wasm = Capability::WasmExec {
+            fuel: 1_000_000,
+            memory: 64 * 1024 * 1024,
+        };
+        let mut required = CapabilitySet::new();
+        required.grant(wasm.clone());
+
+        let plain = Member::new(NodeId::new(), "plain".to_string())
+            .with_state(MemberState::Active);
+        membership.add_member(plain.clone()).await.unwrap();
+
+        let err = coordinator.select_worker_for(&required).await.unwrap_err();
+        assert_eq!(err, CoreError::from(CoordinatorError::NoWorkers));
+        assert_eq!(coordinator.select_worker().await.unwrap(), plain.node_id);
+
+        let mut declared = CapabilitySet::new();
+        declared.grant(wasm);
+        let capable = Member::new(NodeId::new(), "capable".to_string())
+            .with_state(MemberState::Active)
+            .with_capabilities(declared);
+        membership.add_member(capable.clone()).await.unwrap();
+
+        for _ in 0..5 {
+            let selected = coordinator.select_worker_for(&required).await.unwrap();
+            assert_eq!(selected, capable.node_id);
+        }
+
+        let task_id = coordinator
+            .submit_with_capabilities(EventId::new(), required.clone())
+            .await
+            .unwrap();
+        let task = coordinator.get_task(task_id).await.unwrap();
+        assert_eq!(task.required_capabilities, required);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_scheduling_policies() {
+        use crate::membership::{Member, MemberState};
+        use cathedral_core::Capability;
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let config = CoordinatorConfig::new(node_id).with_scheduling(SchedulingPolicy::RoundRobin);
+        let coordinator = leader_coordinator(config, &membership).await;
+        assert_eq!(coordinator.scheduling_policy().await, SchedulingPolicy::RoundRobin);
+
+        let mut declared = CapabilitySet::new();
+        declared.grant(Capability::ClockRead);
+        let mut workers: Vec<NodeId> = (0..3).map(|_| NodeId::new()).collect();
+        for (i, worker) in workers.iter().enumerate() {
+            let mut member =
+                Member::new(*worker, format!("w{}", i)).with_state(MemberState::Active);
+            if i > 0 {
+                member = member.with_capabilities(declared.clone());
+            }
+            membership.add_member(member).await.unwrap();
+        }
+        workers.sort();
+
+        // Round-robin visits workers in node ID order and wraps around
+        let mut selected = Vec::new();
+        for _ in 0..4 {
+            selected.push(coordinator.select_worker().await.unwrap());
+        }
+        assert_eq!(selected, vec![workers[0], workers[1], workers[2], workers[0]]);
+
+        // Capability filtering applies under every policy
+        let mut required = CapabilitySet::new();
+        required.grant(Capability::ClockRead);
+        let mut capable: Vec<NodeId> = membership
+            .active_members()
+            .await
+            .iter()
+            .filter(|m| m.supports(&required))
+            .map(|m| m.node_id)
+            .collect();
+        capable.sort();
+        assert_eq!(capable.len(), 2);
+        coordinator.set_scheduling_policy(SchedulingPolicy::CapabilityFiltered).await;
+        for _ in 0..3 {
+            assert_eq!(coordinator.select_worker_for(&required).await.unwrap(), capable[0]);
+        }
+
+        // Least-loaded prefers idle workers, then the lowest node ID
+        coordinator.set_scheduling_policy(SchedulingPolicy::LeastLoaded).await;
+        let task_id = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(task_id, capable[0]).await.unwrap();
+        assert_eq!(coordinator.select_worker_for(&required).await.unwrap(), capable[1]);
+        let task_id = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(task_id, capable[1]).await.unwrap();
+        assert_eq!(coordinator.select_worker_for(&required).await.unwrap(), capable[0]);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_dead_letter_and_requeue() {
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let config = CoordinatorConfig::new(node_id).with_retry_limit(1);
+        let coordinator = leader_coordinator(config, &membership).await;
+
+        // No remote client is registered for the worker, so every attempt fails
+        let task_id = coordinator.submit(EventId::new()).await.unwrap();
+        let worker = NodeId::new();
+        for _ in 0..2 {
+            coordinator.assign_task(task_id.clone(), worker).await.unwrap();
+            assert!(coordinator.execute_task(task_id.clone()).await.is_err());
+        }
+
+        let dead = coordinator.dead_letters().await;
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].task.task_id, task_id);
+        assert_eq!(dead[0].task.status, TaskStatus::DeadLettered);
+        assert_eq!(
+            coordinator.get_task(task_id.clone()).await.unwrap().status,
+            TaskStatus::DeadLettered
+        );
+        assert_eq!(coordinator.outstanding_task_count().await, 0);
+
+        // A coordinator that did not see the failure learns it from the log
+        let consensus = coordinator.consensus.clone();
+        let follower = Coordinator::new(
+            CoordinatorConfig::new(node_id),
             consensus.clone(),
-            Arc::new(Membership::new(node_id)),
+            coordinator.election.clone(),
+            membership,
+            Arc::new(RemoteExecutor::new(node_id)),
+        );
+        assert_eq!(follower.apply_committed().await.unwrap(), 1);
+        assert_eq!(follower.dead_letters().await, dead);
+
+        coordinator.requeue(task_id.clone()).await.unwrap();
+        assert!(coordinator.dead_letters().await.is_empty());
+        let task = coordinator.get_task(task_id.clone()).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(task.retry_count, 0);
+        assert!(coordinator.requeue(task_id.clone()).await.is_err());
+
+        let log = coordinator.dead_letter_log().await;
+        assert_eq!(log.len(), 2);
+        assert!(matches!(
+            &log[0],
+            DeadLetterTransition::DeadLettered { task_id: id, attempts: 2, .. } if *id == task_id
         ));
+        assert_eq!(log[1], DeadLetterTransition::Requeued { task_id: task_id.clone() });
+
+        let kinds: Vec<EventKind> =
+            consensus.committed_events().await.unwrap().iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [EventKind::TaskDeadLettered, EventKind::TaskRequeued]);
+        follower.apply_committed().await.unwrap();
+        assert!(follower.dead_letters().await.is_empty());
+        assert_eq!(follower.dead_letter_log().await, log);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_requeue_waits_for_capacity() {
+        let node_id = NodeId::new();
         let membership = Arc::new(Membership::new(node_id));
-        let remote = Arc::new(RemoteExecutor::new(node_id));
+        let config = CoordinatorConfig::new(node_id).with_retry_limit(0);
+        let coordinator = leader_coordinator(config, &membership).await;
+
+        let task_id = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(task_id.clone(), NodeId::new()).await.unwrap();
+        assert!(coordinator.execute_task(task_id.clone()).await.is_err());
+        assert_eq!(coordinator.dead_letters().await.len(), 1);
+
+        // The only slot is taken, so the requeue is refused like a submission
+        add_worker(&membership, NodeId::new(), 1).await;
+        coordinator.submit(EventId::new()).await.unwrap();
+        let err = coordinator.requeue(task_id.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("Backpressure"));
+        assert_eq!(coordinator.dead_letters().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_dead_letter_waits_for_commit() {
+        let (node_id, peer) = (NodeId::new(), NodeId::new());
+        let membership = Arc::new(Membership::new(node_id));
+        let config = CoordinatorConfig::new(node_id).with_retry_limit(0);
+        let coordinator = leader_coordinator_with_peers(config, &membership, &[peer]).await;
+
+        // The execution error is returned, not the dead letter's commit failure
+        let task_id = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(task_id.clone(), NodeId::new()).await.unwrap();
+        let err = coordinator.execute_task(task_id.clone()).await.unwrap_err();
+        assert!(matches!(err, CoreError::NotFound { .. }));
+
+        // The task is only failed until the dead letter commits
+        let task = coordinator.get_task(task_id.clone()).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Failed);
+        assert_eq!(coordinator.outstanding_task_count().await, 0);
+        assert!(coordinator.dead_letters().await.is_empty());
+        assert!(matches!(
+            coordinator.requeue(task_id.clone()).await.unwrap_err(),
+            CoreError::NotFound { .. }
+        ));
+
+        acknowledge(&coordinator.consensus, peer, 1).await;
+        assert_eq!(coordinator.apply_committed().await.unwrap(), 1);
+        let task = coordinator.get_task(task_id.clone()).await.unwrap();
+        assert_eq!(task.status, TaskStatus::DeadLettered);
+        assert_eq!(coordinator.dead_letters().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_rejects_submissions_while_reconfiguring() {
+        use crate::consensus::AppendEntriesResponse;
+        use crate::membership::Member;
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+        let consensus = coordinator.consensus.clone();
+
+        let joining = NodeId::new();
+        let voters = BTreeSet::from([node_id, joining]);
+        assert!(coordinator.reconfigure(voters.clone()).await.is_err());
+        membership
+            .add_member(Member::new(joining, "joining".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(coordinator.reconfigure(voters.clone()).await.unwrap(), 0);
+        assert!(coordinator.reconfigure(voters).await.is_err());
+        assert!(coordinator.submit(EventId::new()).await.is_err());
+
+        // The joint and then the stable configuration commit
+        for match_index in 1..=2 {
+            let response = AppendEntriesResponse {
+                term: 1,
+                success: true,
+                match_index,
+            };
+            consensus.handle_append_response(joining, response).await.unwrap();
+        }
+        assert!(!consensus.reconfiguration_in_progress().await);
+        assert!(coordinator.submit(EventId::new()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_steal_work() {
+        use crate::membership::{Member, MemberState};
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+        let consensus = coordinator.consensus.clone();
+
+        let mut workers: Vec<NodeId> = (0..3).map(|_| NodeId::new()).collect();
+        workers.sort();
+        for (i, worker) in workers.iter().enumerate() {
+            let member =
+                Member::new(*worker, format!("w{}", i)).with_state(MemberState::Active);
+            membership.add_member(member).await.unwrap();
+        }
+        let mut task_ids = Vec::new();
+        for _ in 0..3 {
+            let task_id = coordinator.submit(EventId::new()).await.unwrap();
+            coordinator.assign_task(task_id.clone(), workers[0]).await.unwrap();
+            task_ids.push(task_id);
+        }
+        let mut by_event: Vec<ExecutionTask> = Vec::new();
+        for task_id in &task_ids {
+            by_event.push(coordinator.get_task(task_id.clone()).await.unwrap());
+        }
+        by_event.sort_by_key(|t| t.event_id);
+
+        // The newest queued task goes first, and a busy thief gets nothing
+        let first = coordinator.steal_work(workers[1]).await.unwrap().unwrap();
+        assert_eq!(first.task_id, by_event[2].task_id);
+        assert_eq!((first.from, first.to), (workers[0], workers[1]));
+        assert_eq!(coordinator.steal_work(workers[1]).await.unwrap(), None);
+
+        let second = coordinator.steal_work(workers[2]).await.unwrap().unwrap();
+        assert_eq!(second.task_id, by_event[1].task_id);
 
+        // One queued task is within the threshold
+        let idle = NodeId::new();
+        membership
+            .add_member(Member::new(idle, "idle".to_string()).with_state(MemberState::Active))
+            .await
+            .unwrap();
+        assert_eq!(coordinator.steal_work(idle).await.unwrap(), None);
+        assert!(coordinator.steal_work(NodeId::new()).await.is_err());
+
+        // The log holds every steal, and reapplying them is a no-op
+        consensus.advance_commit().await.unwrap();
+        let steals: Vec<WorkSteal> = consensus
+            .committed_events()
+            .await
+            .unwrap()
+            .iter()
+            .filter_map(WorkSteal::from_event)
+            .collect();
+        assert_eq!(steals, vec![first.clone(), second]);
+        assert!(!coordinator.apply_steal(&first).await);
+        let task = coordinator.get_task(first.task_id).await.unwrap();
+        assert_eq!(task.assigned_worker, Some(workers[1]));
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_observe_metrics() {
+        use crate::membership::Member;
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let consensus = Arc::new(Consensus::new(ConsensusConfig::new(node_id)));
+        consensus.start_election().await.unwrap();
+        let election = Arc::new(LeaderElection::new(
+            ElectionConfig::new(node_id),
+            consensus.clone(),
+            membership.clone(),
+        ));
         let coordinator = Coordinator::new(
-            config,
+            CoordinatorConfig::new(node_id),
             consensus,
             election,
+            membership.clone(),
+            Arc::new(RemoteExecutor::new(node_id)),
+        );
+        let worker = NodeId::new();
+        membership
+            .add_member(Member::new(worker, "worker".to_string()).with_heartbeat(40))
+            .await
+            .unwrap();
+        coordinator.advance_clock(100).await.unwrap();
+
+        let mut metrics = Metrics::new();
+        coordinator.observe_metrics(&mut metrics).await;
+        assert_eq!(metrics.term_changes, 1);
+        assert_eq!(metrics.heartbeat_age.get(&worker), Some(&60));
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_execution_timeout() {
+        use crate::remote::RemoteClient;
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let config = CoordinatorConfig::new(node_id).with_execution_timeout(5);
+        let coordinator = Arc::new(leader_coordinator(config, &membership).await);
+        let consensus = coordinator.consensus.clone();
+
+        let worker = NodeId::new();
+        coordinator
+            .remote
+            .add_client(RemoteClient::new(worker, "worker".to_string()))
+            .await
+            .unwrap();
+
+        let event_id = EventId::new();
+        let task_id = coordinator.submit(event_id).await.unwrap();
+        coordinator.assign_task(task_id.clone(), worker).await.unwrap();
+        let running = {
+            let coordinator = coordinator.clone();
+            let task_id = task_id.clone();
+            tokio::spawn(async move { coordinator.execute_task(task_id).await })
+        };
+        let task = loop {
+            let task = coordinator.get_task(task_id.clone()).await.unwrap();
+            if task.status == TaskStatus::Running {
+                break task;
+            }
+            tokio::task::yield_now().await;
+        };
+        assert_eq!(task.deadline, Some(5));
+
+        // The deadline is logical, so only advancing the clock expires it
+        assert!(coordinator.advance_clock(4).await.unwrap().is_empty());
+        assert_eq!(coordinator.advance_clock(1).await.unwrap(), vec![task_id.clone()]);
+        let err = running.await.unwrap().unwrap_err();
+        assert_eq!(err, CoreError::from(CoordinatorError::Timeout(5)));
+        assert!(coordinator.advance_clock(10).await.unwrap().is_empty());
+
+        let task = coordinator.get_task(task_id.clone()).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(task.retry_count, 1);
+        assert_eq!(task.deadline, None);
+
+        // The timeout is committed before the retry runs
+        assert_eq!(consensus.log_len().await, 1);
+        assert_eq!(consensus.commit_index().await, 1);
+        let events = consensus.committed_events().await.unwrap();
+        assert_eq!(events[0].kind, EventKind::TaskTimedOut);
+        assert_eq!(events[0].node_id, worker);
+        assert_eq!(events[0].parent_event_id, Some(event_id));
+        assert_eq!(events[0].logical_time, LogicalTime::from_raw(5));
+        assert_eq!(events[0].payload, task_id.as_bytes());
+
+        // The retry finishes before its deadline at logical time 20
+        coordinator.assign_task(task_id.clone(), worker).await.unwrap();
+        let retry = coordinator.execute_task(task_id.clone()).await.unwrap();
+        let task = coordinator.get_task(task_id.clone()).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert_eq!(task.idempotency_key().attempt, 1);
+
+        // The timed-out original completes late and loses to the retry
+        let original = IdempotencyKey::new(task.run_id, task.node_id, 0);
+        let late = ExecutionResult::success(task_id.clone(), event_id, Hash::empty(), 0);
+        assert!(!coordinator.commit_result(&task_id, original, late).await.unwrap());
+        assert_eq!(coordinator.get_result(task_id.clone()).await, Some(retry));
+
+        consensus.advance_commit().await.unwrap();
+        let events = consensus.committed_events().await.unwrap();
+        assert_eq!(events.len(), 3);
+        let key = |event: &Event| serde_json::from_slice::<IdempotencyKey>(&event.payload).unwrap();
+        assert_eq!(events[1].kind, EventKind::NodeCompleted);
+        assert_eq!(key(&events[1]), task.idempotency_key());
+        assert_eq!(events[2].kind, EventKind::Superseded);
+        assert_eq!(events[2].event_id, original.event_id(EventKind::Superseded));
+        assert_eq!(events[2].parent_event_id, Some(event_id));
+        assert_eq!(key(&events[2]), original);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_timeout_committed_before_dead_letter() {
+        use crate::remote::RemoteClient;
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let config = CoordinatorConfig::new(node_id)
+            .with_execution_timeout(5)
+            .with_retry_limit(0);
+        let coordinator = Arc::new(leader_coordinator(config, &membership).await);
+        let worker = NodeId::new();
+        coordinator
+            .remote
+            .add_client(RemoteClient::new(worker, "worker".to_string()))
+            .await
+            .unwrap();
+
+        let task_id = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(task_id.clone(), worker).await.unwrap();
+        let running = {
+            let coordinator = coordinator.clone();
+            let task_id = task_id.clone();
+            tokio::spawn(async move { coordinator.execute_task(task_id).await })
+        };
+        while coordinator.get_task(task_id.clone()).await.unwrap().status != TaskStatus::Running {
+            tokio::task::yield_now().await;
+        }
+        coordinator.advance_clock(5).await.unwrap();
+        let err = running.await.unwrap().unwrap_err();
+        assert_eq!(err, CoreError::from(CoordinatorError::Timeout(5)));
+
+        let kinds: Vec<EventKind> = coordinator
+            .consensus
+            .committed_events()
+            .await
+            .unwrap()
+            .iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(kinds, [EventKind::TaskTimedOut, EventKind::TaskDeadLettered]);
+        let task = coordinator.get_task(task_id).await.unwrap();
+        assert_eq!(task.status, TaskStatus::DeadLettered);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_first_result_wins_across_leaders() {
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let (run_id, node) = (RunId::new(), NodeId::new());
+        let result = |task_id: &str| {
+            ExecutionResult::success(task_id.to_string(), EventId::new(), Hash::empty(), 0)
+        };
+
+        let first = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+        let consensus = first.consensus.clone();
+        let task_id = first
+            .submit_node(run_id, node, EventId::new(), CapabilitySet::new())
+            .await
+            .unwrap();
+        let key = first.get_task(task_id.clone()).await.unwrap().idempotency_key();
+        assert_eq!(key, IdempotencyKey::new(run_id, node, 0));
+        assert!(first.commit_result(&task_id, key, result(&task_id)).await.unwrap());
+        consensus.advance_commit().await.unwrap();
+
+        // A later leader learns the winner from the committed log, not
+        // from memory, and the node's resubmission loses to it
+        let election = Arc::new(LeaderElection::new(
+            ElectionConfig::new(node_id),
+            consensus.clone(),
+            membership.clone(),
+        ));
+        election.set_state(crate::leader::ElectionState::Leader).await;
+        let second = Coordinator::new(
+            CoordinatorConfig::new(node_id),
+            consensus.clone(),
+            election,
+            membership,
+            Arc::new(RemoteExecutor::new(node_id)),
+        );
+        let task_id = second
+            .submit_node(run_id, node, EventId::new(), CapabilitySet::new())
+            .await
+            .unwrap();
+        let retry = IdempotencyKey::new(run_id, node, 1);
+        assert!(!second.commit_result(&task_id, retry, result(&task_id)).await.unwrap());
+        assert!(second.commit_result(&task_id, key, result(&task_id)).await.unwrap());
+        assert_eq!(second.get_result(task_id).await, None);
+
+        consensus.advance_commit().await.unwrap();
+        let events = consensus.committed_events().await.unwrap();
+        let kinds: Vec<EventKind> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(kinds, [EventKind::NodeCompleted, EventKind::Superseded]);
+        assert_eq!(events[0].event_id, key.event_id(EventKind::NodeCompleted));
+        assert_eq!((events[1].run_id, events[1].node_id), (run_id, node));
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_result_waits_for_commit() {
+        let (node_id, peer) = (NodeId::new(), NodeId::new());
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator =
+            leader_coordinator_with_peers(CoordinatorConfig::new(node_id), &membership, &[peer])
+                .await;
+        let consensus = coordinator.consensus.clone();
+
+        let task_id = coordinator.submit(EventId::new()).await.unwrap();
+        let key = coordinator.get_task(task_id.clone()).await.unwrap().idempotency_key();
+        let result = ExecutionResult::success(task_id.clone(), EventId::new(), Hash::empty(), 0);
+
+        // The peer cannot be reached, so the result is appended but not decided
+        let err = coordinator.commit_result(&task_id, key, result.clone()).await.unwrap_err();
+        assert_eq!(err, CoreError::from(CoordinatorError::Uncommitted(0)));
+        let task = coordinator.get_task(task_id.clone()).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(coordinator.get_result(task_id.clone()).await, None);
+
+        // Once the peer acknowledges the entry, applying it completes the task
+        acknowledge(&consensus, peer, 1).await;
+        assert_eq!(coordinator.apply_committed().await.unwrap(), 1);
+        let task = coordinator.get_task(task_id.clone()).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert_eq!(coordinator.get_result(task_id.clone()).await, Some(result.clone()));
+        assert!(coordinator.commit_result(&task_id, key, result).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_steps_down_on_lease_loss() {
+        use crate::leader::ElectionState;
+        use crate::membership::{Member, MemberState};
+        use crate::remote::RemoteClient;
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let consensus = Arc::new(Consensus::new(
+            ConsensusConfig::new(node_id).with_quorum_size(1),
+        ));
+        let election = Arc::new(LeaderElection::new(
+            ElectionConfig::new(node_id).with_lease_duration(100),
+            consensus.clone(),
+            membership.clone(),
+        ));
+        election.start_election().await.unwrap();
+        assert!(election.receive_vote(node_id, 1).await.unwrap());
+
+        let worker = NodeId::new();
+        membership
+            .add_member(Member::new(worker, "worker".to_string()).with_state(MemberState::Active))
+            .await
+            .unwrap();
+        let remote = Arc::new(RemoteExecutor::new(node_id));
+        remote
+            .add_client(RemoteClient::new(worker, "worker".to_string()))
+            .await
+            .unwrap();
+        let coordinator = Arc::new(Coordinator::new(
+            CoordinatorConfig::new(node_id),
+            consensus,
+            election.clone(),
             membership,
             remote,
+        ));
+
+        assert!(coordinator.check_lease(0).await.unwrap());
+        let waiting = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(waiting.clone(), worker).await.unwrap();
+        let in_flight = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(in_flight.clone(), worker).await.unwrap();
+        let running = {
+            let coordinator = coordinator.clone();
+            let task_id = in_flight.clone();
+            tokio::spawn(async move { coordinator.execute_task(task_id).await })
+        };
+        loop {
+            let task = coordinator.get_task(in_flight.clone()).await.unwrap();
+            if task.status == TaskStatus::Running {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+
+        // The worker's acknowledgement keeps the lease alive
+        election.record_ack(worker, 50).await;
+        assert!(coordinator.check_lease(120).await.unwrap());
+
+        // Without further acknowledgements the lease runs out, and the
+        // running task holds the node in the step-down window
+        assert!(!coordinator.check_lease(150).await.unwrap());
+        assert_eq!(election.state().await, ElectionState::SteppingDown);
+        let err = coordinator.submit(EventId::new()).await.unwrap_err();
+        assert_eq!(err, CoreError::from(CoordinatorError::QuorumLost));
+        let task = coordinator.get_task(waiting.clone()).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(task.assigned_worker, None);
+        let err = coordinator.execute_task(waiting).await.unwrap_err();
+        assert_eq!(err, CoreError::from(CoordinatorError::QuorumLost));
+
+        // Once the in-flight task drains, the node steps down
+        running.await.unwrap().unwrap();
+        assert!(!coordinator.check_lease(160).await.unwrap());
+        assert_eq!(election.state().await, ElectionState::Follower(node_id));
+        assert!(coordinator.submit(EventId::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_shutdown_hands_off() {
+        use crate::membership::{Member, MemberState};
+        use crate::remote::RemoteClient;
+
+        let node_id = NodeId::new();
+        let peer_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        for id in [node_id, peer_id] {
+            let member = Member::new(id, "addr".to_string()).with_state(MemberState::Active);
+            membership.add_member(member).await.unwrap();
+        }
+
+        // Results are committed through the replicated log, which this node leads
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+        let election = coordinator.election.clone();
+        coordinator
+            .remote
+            .add_client(RemoteClient::new(peer_id, "peer".to_string()))
+            .await
+            .unwrap();
+        for _ in 0..3 {
+            coordinator.submit(EventId::new()).await.unwrap();
+        }
+
+        let steps = coordinator.shutdown(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                ShutdownStep::Drained {
+                    completed: 3,
+                    remaining: Vec::new(),
+                },
+                // Three results and the snapshot marker
+                ShutdownStep::Snapshotted { index: 4 },
+                ShutdownStep::SteppedDown { was_leader: true },
+                ShutdownStep::Unregistered { node_id },
+            ]
         );
+        assert!(!election.is_leader().await);
+        assert_eq!(coordinator.snapshot_index().await, 4);
+        assert_eq!(coordinator.consensus.snapshot().await.unwrap().last_index, 4);
+        assert!(membership.get_member(node_id).await.is_none());
+    }
 
+    #[tokio::test]
+    async fn test_coordinator_shutdown_deadline() {
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+        let mut task_ids = Vec::new();
+        for _ in 0..2 {
+            task_ids.push(coordinator.submit(EventId::new()).await.unwrap());
+        }
+        task_ids.sort();
+
+        let steps = coordinator.shutdown(Duration::ZERO).await.unwrap();
+        assert_eq!(
+            steps[0],
+            ShutdownStep::Drained {
+                completed: 0,
+                remaining: task_ids.clone(),
+            }
+        );
+        assert_eq!(steps[1], ShutdownStep::Snapshotted { index: 1 });
+        assert_eq!(steps.len(), 4);
+
+        // The undrained tasks are kept in the snapshot for recovery
+        let state = coordinator.consensus.snapshot().await.unwrap().restore().unwrap();
+        let tasks: BTreeMap<String, ExecutionTask> =
+            serde_json::from_slice(&state["tasks"]).unwrap();
+        assert_eq!(tasks.into_keys().collect::<Vec<_>>(), task_ids);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_status_and_promote() {
+        use crate::membership::Member;
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+        let worker = NodeId::new();
+        membership
+            .add_member(Member::new(worker, "worker:7000".to_string()).with_heartbeat(40))
+            .await
+            .unwrap();
+        let task_id = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(task_id, worker).await.unwrap();
+        coordinator.advance_clock(100).await.unwrap();
+
+        let status = coordinator.status().await;
+        assert_eq!(status.node_id, node_id);
+        assert_eq!(status.term, 1);
+        assert_eq!(status.voters, vec![node_id]);
+        assert_eq!(status.outstanding, 1);
+        let member = status.members.iter().find(|m| m.node_id == worker).unwrap();
+        assert_eq!(member.address, "worker:7000");
+        assert_eq!(member.heartbeat_age, 60);
+        assert_eq!(member.in_flight, 1);
+        assert!(!member.voter);
+
+        // Only active members that do not vote yet can be promoted
+        assert!(coordinator.promote(worker).await.is_err());
+        membership.update_state(worker, MemberState::Active).await.unwrap();
+        assert!(coordinator.promote(node_id).await.is_err());
+        assert_eq!(coordinator.promote(worker).await.unwrap(), 0);
+        let status = coordinator.status().await;
+        assert!(status.reconfiguring);
+        assert!(status.members.iter().any(|m| m.node_id == worker && m.voter));
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_drain() {
+        use crate::membership::Member;
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+        let (consensus, election) = (coordinator.consensus.clone(), coordinator.election.clone());
+        let (worker, other) = (NodeId::new(), NodeId::new());
+        for id in [worker, other] {
+            membership
+                .add_member(Member::new(id, id.to_string()).with_state(MemberState::Active))
+                .await
+                .unwrap();
+        }
+        let queued = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(queued.clone(), worker).await.unwrap();
+        let running = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(running.clone(), worker).await.unwrap();
+        coordinator.tasks.write().await.get_mut(&running).unwrap().status = TaskStatus::Running;
+
+        // A follower refuses, so followers never diverge from the log
+        election.set_state(crate::leader::ElectionState::Follower(other)).await;
+        assert!(coordinator.drain(worker, Duration::ZERO).await.is_err());
+        assert!(membership.get_member(worker).await.unwrap().is_active());
+        election.set_state(crate::leader::ElectionState::Leader).await;
+
+        assert!(coordinator.drain(node_id, Duration::ZERO).await.is_err());
+        assert!(coordinator.drain(NodeId::new(), Duration::ZERO).await.is_err());
+
+        // The running task holds the worker in membership past the deadline
+        let report = coordinator.drain(worker, Duration::ZERO).await.unwrap();
+        assert_eq!(report.requeued, vec![queued.clone()]);
+        assert_eq!(report.remaining, vec![running.clone()]);
+        assert!(!report.deregistered);
+        let task = coordinator.get_task(queued).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+        assert_eq!(task.assigned_worker, None);
+        assert_eq!(coordinator.select_worker().await.unwrap(), other);
+
+        coordinator.tasks.write().await.get_mut(&running).unwrap().status = TaskStatus::Completed;
+        let report = coordinator.drain(worker, Duration::from_secs(1)).await.unwrap();
+        assert!(report.requeued.is_empty() && report.remaining.is_empty());
+        assert!(report.deregistered);
+        assert!(membership.get_member(worker).await.is_none());
+
+        // The drain and the removal are both in the committed log
+        let kinds: Vec<EventKind> =
+            consensus.committed_events().await.unwrap().iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [EventKind::MemberDraining, EventKind::MemberDraining, EventKind::MemberRemoved]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_create_snapshot() {
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let coordinator = leader_coordinator(CoordinatorConfig::new(node_id), &membership).await;
+
+        // Each snapshot covers the marker committed for it
         let index1 = coordinator.create_snapshot().await.unwrap();
         let index2 = coordinator.create_snapshot().await.unwrap();
 
         assert_eq!(index1, 1);
         assert_eq!(index2, 2);
+        assert_eq!(coordinator.snapshot_index().await, 2);
+        let snapshot = coordinator.consensus.snapshot().await.unwrap();
+        assert_eq!(snapshot.last_index, 2);
+        let events = coordinator.consensus.committed_events_since(1).await.unwrap();
+        assert_eq!(events[0].kind, EventKind::SnapshotCreated);
+    }
+
+    #[tokio::test]
+    async fn test_coordinator_snapshot_restores_on_peer() {
+        use crate::consensus::InstallSnapshotRequest;
+        use crate::remote::RemoteClient;
+
+        let (node_id, worker, peer_id) = (NodeId::new(), NodeId::new(), NodeId::new());
+        let membership = Arc::new(Membership::new(node_id));
+        add_worker(&membership, worker, 4).await;
+        let config = CoordinatorConfig::new(node_id).with_retry_limit(0);
+        let coordinator = leader_coordinator(config, &membership).await;
+        coordinator
+            .remote
+            .add_client(RemoteClient::new(worker, "worker".to_string()))
+            .await
+            .unwrap();
+
+        let done = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(done.clone(), worker).await.unwrap();
+        coordinator.execute_task(done.clone()).await.unwrap();
+        let dead = coordinator.submit(EventId::new()).await.unwrap();
+        coordinator.assign_task(dead.clone(), NodeId::new()).await.unwrap();
+        coordinator.execute_task(dead.clone()).await.unwrap_err();
+        let pending = coordinator.submit(EventId::new()).await.unwrap();
+
+        // The result, the dead letter and the snapshot marker
+        let index = coordinator.create_snapshot().await.unwrap();
+        assert_eq!(index, 3);
+        let snapshot = coordinator.consensus.snapshot().await.unwrap();
+
+        let peer_membership = Arc::new(Membership::new(peer_id));
+        let peer_consensus = Arc::new(Consensus::new(
+            ConsensusConfig::new(peer_id).with_voters([node_id, peer_id]),
+        ));
+        let response = peer_consensus
+            .install_snapshot(InstallSnapshotRequest {
+                term: coordinator.consensus.current_term().await,
+                leader_id: node_id,
+                snapshot,
+            })
+            .await
+            .unwrap();
+        assert!(response.success);
+        let peer = Coordinator::new(
+            CoordinatorConfig::new(peer_id),
+            peer_consensus.clone(),
+            Arc::new(LeaderElection::new(
+                ElectionConfig::new(peer_id),
+                peer_consensus,
+                peer_membership.clone(),
+            )),
+            peer_membership,
+            Arc::new(RemoteExecutor::new(peer_id)),
+        );
+
+        // The peer picks up the coordinator state as of the snapshot
+        assert_eq!(peer.apply_committed().await.unwrap(), 0);
+        assert_eq!(peer.snapshot_index().await, index);
+        assert_eq!(
+            peer.get_task(pending.clone()).await,
+            coordinator.get_task(pending).await
+        );
+        assert_eq!(peer.get_task(done.clone()).await.unwrap().status, TaskStatus::Completed);
+        assert_eq!(
+            peer.completed_results_sorted().await,
+            coordinator.completed_results_sorted().await
+        );
+        assert_eq!(peer.dead_letters().await, coordinator.dead_letters().await);
+        assert_eq!(peer.dead_letters().await[0].task.task_id, dead);
+        assert_eq!(peer.dead_letter_log().await, coordinator.dead_letter_log().await);
+        assert_eq!(
+            peer.results.read().await.winners,
+            coordinator.results.read().await.winners
+        );
+    }
+
+    #[tokio::test]
+    async fn test_completed_results_sorted() {
+        let coordinator = Coordinator::default();
+
+        let make = |task_id: &str, index: u64| {
+            ExecutionResult::success(task_id.to_string(), EventId::new(), Hash::compute(&[]), 1)
+                .with_log_index(index)
+        };
+        let results = vec![
+            make("task-c", 20),
+            make("task-b", 10),
+            make("task-a", 20),
+            make("task-d", 5),
+        ];
+
+        {
+            let mut completed = coordinator.completed.write().await;
+            for result in results {
+                completed.insert(result.task_id.clone(), result);
+            }
+        }
+
+        let ids: Vec<String> = coordinator
+            .completed_results_sorted()
+            .await
+            .into_iter()
+            .map(|r| r.task_id)
+            .collect();
+        assert_eq!(ids, vec!["task-d", "task-b", "task-a", "task-c"]);
     }
 
     #[test]
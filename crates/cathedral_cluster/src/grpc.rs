@@ -0,0 +1,1214 @@
+//! gRPC transport between cluster nodes.
+//!
+//! The wire protocol is defined in `proto/cluster.proto`. Clients created
+//! with [`RemoteClient::grpc`](crate::remote::RemoteClient::grpc) send their
+//! calls through it, and a [`TransportService`] answers them on the
+//! receiving node by dispatching to its worker, membership, and consensus.
+//! Raft's `AppendEntries` and `RequestVote` travel the same way. A cancelled
+//! run is stopped on every worker through `CancelRun`, and a finished run's
+//! idempotency state is dropped through `FinishRun`. Idle workers take
+//! queued tasks from overloaded ones through the coordinator's `StealWork`.
+//!
+//! A client is only cached once the `Handshake` call has shown that both
+//! builds are compatible; the server refuses an incompatible client with
+//! [`TransportError::IncompatiblePeer`]. Every call carries the client's
+//! timeout as its gRPC deadline, which the server enforces, and failures are
+//! mapped to [`TransportError`]s. Both ends can require mutual TLS.
+
+use crate::consensus::{
+    AppendEntriesRequest, AppendEntriesResponse, Consensus, ConsensusEntry,
+    InstallSnapshotRequest, RequestVoteRequest, RequestVoteResponse,
+};
+use crate::coordinator::Coordinator;
+use crate::membership::Membership;
+use crate::remote::{Handshake, IdempotencyKey, RemoteRequest, RemoteResponse, TransportError};
+use crate::worker::Worker;
+use cathedral_core::{CoreError, EventId, Hash, NodeId, RunId};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::transport::{
+    Certificate, Channel, ClientTlsConfig, Endpoint, Identity, Server, ServerTlsConfig,
+};
+use prost::Message;
+use tonic::{Code, Request, Response, Status, TimeoutExpired};
+
+/// Types generated from `proto/cluster.proto`
+#[allow(missing_docs, clippy::all)]
+pub mod proto {
+    tonic::include_proto!("cathedral.cluster.v1");
+}
+
+use proto::cluster_transport_client::ClusterTransportClient;
+use proto::cluster_transport_server::{ClusterTransport, ClusterTransportServer};
+
+/// TLS material for one end of the transport, in PEM form
+///
+/// Connections are mutually authenticated: each side presents its own
+/// certificate and verifies the other's against the CA.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// CA certificate that peer certificates must chain to
+    pub ca_pem: Vec<u8>,
+    /// This node's certificate
+    pub cert_pem: Vec<u8>,
+    /// This node's private key
+    pub key_pem: Vec<u8>,
+    /// Name the server's certificate is verified against
+    pub domain: String,
+}
+
+impl TlsConfig {
+    /// Create a TLS configuration
+    #[must_use]
+    pub fn new(
+        ca_pem: Vec<u8>,
+        cert_pem: Vec<u8>,
+        key_pem: Vec<u8>,
+        domain: impl Into<String>,
+    ) -> Self {
+        Self {
+            ca_pem,
+            cert_pem,
+            key_pem,
+            domain: domain.into(),
+        }
+    }
+
+    /// Client side of the configuration
+    fn client(&self) -> ClientTlsConfig {
+        ClientTlsConfig::new()
+            .ca_certificate(Certificate::from_pem(&self.ca_pem))
+            .identity(Identity::from_pem(&self.cert_pem, &self.key_pem))
+            .domain_name(self.domain.clone())
+    }
+
+    /// Server side of the configuration
+    fn server(&self) -> ServerTlsConfig {
+        ServerTlsConfig::new()
+            .identity(Identity::from_pem(&self.cert_pem, &self.key_pem))
+            .client_ca_root(Certificate::from_pem(&self.ca_pem))
+    }
+}
+
+impl std::fmt::Debug for TlsConfig {
+    /// Omits the private key
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TlsConfig")
+            .field("domain", &self.domain)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Open a lazily connected client for the node at `address`
+///
+/// An address without a scheme gets `https://` with TLS and `http://`
+/// without.
+///
+/// # Errors
+///
+/// Returns [`TransportError::ConnectionFailed`] if the address or TLS
+/// configuration is invalid
+pub(crate) fn connect_lazy(
+    address: &str,
+    tls: Option<&TlsConfig>,
+) -> Result<ClusterTransportClient<Channel>, TransportError> {
+    let uri = if address.contains("://") {
+        address.to_string()
+    } else if tls.is_some() {
+        format!("https://{}", address)
+    } else {
+        format!("http://{}", address)
+    };
+    let connection_failed = |e: tonic::transport::Error| {
+        TransportError::ConnectionFailed(format!("{}: {}", address, e))
+    };
+
+    let mut endpoint = Endpoint::from_shared(uri).map_err(connection_failed)?;
+    if let Some(tls) = tls {
+        endpoint = endpoint.tls_config(tls.client()).map_err(connection_failed)?;
+    }
+    Ok(ClusterTransportClient::new(endpoint.connect_lazy()))
+}
+
+/// Wrap a message in a request whose deadline is `timeout_ms` from now
+pub(crate) fn with_deadline<T>(message: T, timeout_ms: u64) -> Request<T> {
+    let mut request = Request::new(message);
+    request.set_timeout(Duration::from_millis(timeout_ms));
+    request
+}
+
+/// Map a status returned by `target` to a transport error
+///
+/// Deadline expiry, whether detected by the client or the server, becomes
+/// [`TransportError::Timeout`]. tonic reports an expired deadline as
+/// `CANCELLED` with its own message; any other cancellation becomes
+/// [`TransportError::Cancelled`].
+#[must_use]
+pub fn status_to_error(target: NodeId, timeout_ms: u64, status: &Status) -> TransportError {
+    match status.code() {
+        Code::DeadlineExceeded => TransportError::Timeout(timeout_ms),
+        Code::Cancelled if status.message() == TimeoutExpired(()).to_string() => {
+            TransportError::Timeout(timeout_ms)
+        }
+        Code::Cancelled => TransportError::Cancelled(status.message().to_string()),
+        Code::Unavailable => TransportError::NodeUnavailable(target),
+        Code::InvalidArgument => TransportError::Serialization(status.message().to_string()),
+        code => TransportError::InvalidResponse(format!("{:?}: {}", code, status.message())),
+    }
+}
+
+/// Recover the rejection of a handshake from the status the server
+/// answered it with
+///
+/// The server attaches its own handshake to the status details, so the
+/// client can name the mismatch the same way it would have found it.
+/// Returns `None` for any other failure.
+pub(crate) fn handshake_rejection(local: &Handshake, status: &Status) -> Option<TransportError> {
+    if status.code() != Code::FailedPrecondition {
+        return None;
+    }
+    let response = proto::HandshakeResponse::decode(status.details()).ok()?;
+    let remote = Handshake::try_from(response).ok()?;
+    local.check_compatible(&remote).err()
+}
+
+/// Map an error raised while serving a call to its status
+fn core_error_status(err: &CoreError) -> Status {
+    match err {
+        CoreError::NotFound { .. } => Status::not_found(err.to_string()),
+        CoreError::ParseError { .. } => Status::invalid_argument(err.to_string()),
+        CoreError::Validation { .. } => Status::failed_precondition(err.to_string()),
+        CoreError::Cancelled => Status::aborted(err.to_string()),
+        _ => Status::internal(err.to_string()),
+    }
+}
+
+/// Decode a 16-byte identifier
+fn id_bytes(field: &str, bytes: &[u8]) -> Result<[u8; 16], TransportError> {
+    bytes.try_into().map_err(|_| {
+        TransportError::Serialization(format!("{}: expected 16 bytes, got {}", field, bytes.len()))
+    })
+}
+
+/// Decode a handshake's content hash
+fn abi_hash(bytes: &[u8]) -> Result<Hash, TransportError> {
+    let hash: [u8; 32] = bytes.try_into().map_err(|_| {
+        TransportError::Serialization(format!("abi_hash: expected 32 bytes, got {}", bytes.len()))
+    })?;
+    Ok(Hash::from_bytes(hash))
+}
+
+impl From<&Handshake> for proto::HandshakeRequest {
+    fn from(handshake: &Handshake) -> Self {
+        Self {
+            node_id: handshake.node_id.as_bytes().to_vec(),
+            crate_version: handshake.crate_version.clone(),
+            abi_version: handshake.abi_version.clone(),
+            abi_hash: handshake.abi_hash.as_bytes().to_vec(),
+            features: handshake.features.iter().cloned().collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::HandshakeRequest> for Handshake {
+    type Error = TransportError;
+
+    fn try_from(request: proto::HandshakeRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            node_id: NodeId::from_bytes(id_bytes("node_id", &request.node_id)?),
+            crate_version: request.crate_version,
+            abi_version: request.abi_version,
+            abi_hash: abi_hash(&request.abi_hash)?,
+            features: request.features.into_iter().collect(),
+        })
+    }
+}
+
+impl From<&Handshake> for proto::HandshakeResponse {
+    fn from(handshake: &Handshake) -> Self {
+        Self {
+            node_id: handshake.node_id.as_bytes().to_vec(),
+            crate_version: handshake.crate_version.clone(),
+            abi_version: handshake.abi_version.clone(),
+            abi_hash: handshake.abi_hash.as_bytes().to_vec(),
+            features: handshake.features.iter().cloned().collect(),
+        }
+    }
+}
+
+impl TryFrom<proto::HandshakeResponse> for Handshake {
+    type Error = TransportError;
+
+    fn try_from(response: proto::HandshakeResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            node_id: NodeId::from_bytes(id_bytes("node_id", &response.node_id)?),
+            crate_version: response.crate_version,
+            abi_version: response.abi_version,
+            abi_hash: abi_hash(&response.abi_hash)?,
+            features: response.features.into_iter().collect(),
+        })
+    }
+}
+
+impl From<IdempotencyKey> for proto::IdempotencyKey {
+    fn from(key: IdempotencyKey) -> Self {
+        Self {
+            run_id: key.run_id.as_bytes().to_vec(),
+            node_id: key.node_id.as_bytes().to_vec(),
+            attempt: key.attempt,
+        }
+    }
+}
+
+impl TryFrom<proto::IdempotencyKey> for IdempotencyKey {
+    type Error = TransportError;
+
+    fn try_from(key: proto::IdempotencyKey) -> Result<Self, Self::Error> {
+        Ok(Self::new(
+            RunId::from_bytes(id_bytes("idempotency_key.run_id", &key.run_id)?),
+            NodeId::from_bytes(id_bytes("idempotency_key.node_id", &key.node_id)?),
+            key.attempt,
+        ))
+    }
+}
+
+impl From<&RemoteRequest> for proto::ExecuteRequest {
+    fn from(request: &RemoteRequest) -> Self {
+        Self {
+            request_id: request.request_id.clone(),
+            source: request.source.as_bytes().to_vec(),
+            event_id: request.event_id.as_bytes().to_vec(),
+            payload: request.payload.clone(),
+            idempotency_key: request.idempotency_key.map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<proto::ExecuteRequest> for RemoteRequest {
+    type Error = TransportError;
+
+    fn try_from(request: proto::ExecuteRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            request_id: request.request_id,
+            source: NodeId::from_bytes(id_bytes("source", &request.source)?),
+            event_id: EventId::from_bytes(id_bytes("event_id", &request.event_id)?),
+            payload: request.payload,
+            idempotency_key: request.idempotency_key.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl From<RemoteResponse> for proto::ExecuteResponse {
+    fn from(response: RemoteResponse) -> Self {
+        Self {
+            request_id: response.request_id,
+            payload: response.payload,
+            success: response.success,
+            error: response.error,
+        }
+    }
+}
+
+impl From<proto::ExecuteResponse> for RemoteResponse {
+    fn from(response: proto::ExecuteResponse) -> Self {
+        Self {
+            request_id: response.request_id,
+            payload: response.payload,
+            success: response.success,
+            error: response.error,
+        }
+    }
+}
+
+impl From<&ConsensusEntry> for proto::LogEntry {
+    fn from(entry: &ConsensusEntry) -> Self {
+        Self {
+            index: entry.index,
+            term: entry.term,
+            data: entry.data.clone(),
+            hash: entry.hash.as_bytes().to_vec(),
+        }
+    }
+}
+
+impl TryFrom<proto::LogEntry> for ConsensusEntry {
+    type Error = TransportError;
+
+    fn try_from(entry: proto::LogEntry) -> Result<Self, Self::Error> {
+        let hash: [u8; 32] = entry.hash.as_slice().try_into().map_err(|_| {
+            TransportError::Serialization(format!(
+                "entries.hash: expected 32 bytes, got {}",
+                entry.hash.len()
+            ))
+        })?;
+        Ok(Self {
+            index: entry.index,
+            term: entry.term,
+            data: entry.data,
+            hash: Hash::from_bytes(hash),
+        })
+    }
+}
+
+impl From<&AppendEntriesRequest> for proto::AppendEntriesRequest {
+    fn from(request: &AppendEntriesRequest) -> Self {
+        Self {
+            term: request.term,
+            leader_id: request.leader_id.as_bytes().to_vec(),
+            prev_log_index: request.prev_log_index,
+            prev_log_term: request.prev_log_term,
+            entries: request.entries.iter().map(Into::into).collect(),
+            leader_commit: request.leader_commit,
+        }
+    }
+}
+
+impl TryFrom<proto::AppendEntriesRequest> for AppendEntriesRequest {
+    type Error = TransportError;
+
+    fn try_from(request: proto::AppendEntriesRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            term: request.term,
+            leader_id: NodeId::from_bytes(id_bytes("leader_id", &request.leader_id)?),
+            prev_log_index: request.prev_log_index,
+            prev_log_term: request.prev_log_term,
+            entries: request
+                .entries
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?,
+            leader_commit: request.leader_commit,
+        })
+    }
+}
+
+impl From<AppendEntriesResponse> for proto::AppendEntriesResponse {
+    fn from(response: AppendEntriesResponse) -> Self {
+        Self {
+            term: response.term,
+            success: response.success,
+            match_index: response.match_index,
+        }
+    }
+}
+
+impl From<proto::AppendEntriesResponse> for AppendEntriesResponse {
+    fn from(response: proto::AppendEntriesResponse) -> Self {
+        Self {
+            term: response.term,
+            success: response.success,
+            match_index: response.match_index,
+        }
+    }
+}
+
+impl From<&RequestVoteRequest> for proto::RequestVoteRequest {
+    fn from(request: &RequestVoteRequest) -> Self {
+        Self {
+            term: request.term,
+            candidate_id: request.candidate_id.as_bytes().to_vec(),
+            last_log_index: request.last_log_index,
+            last_log_term: request.last_log_term,
+        }
+    }
+}
+
+impl TryFrom<proto::RequestVoteRequest> for RequestVoteRequest {
+    type Error = TransportError;
+
+    fn try_from(request: proto::RequestVoteRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            term: request.term,
+            candidate_id: NodeId::from_bytes(id_bytes("candidate_id", &request.candidate_id)?),
+            last_log_index: request.last_log_index,
+            last_log_term: request.last_log_term,
+        })
+    }
+}
+
+impl From<RequestVoteResponse> for proto::RequestVoteResponse {
+    fn from(response: RequestVoteResponse) -> Self {
+        Self {
+            term: response.term,
+            vote_granted: response.vote_granted,
+        }
+    }
+}
+
+impl From<proto::RequestVoteResponse> for RequestVoteResponse {
+    fn from(response: proto::RequestVoteResponse) -> Self {
+        Self {
+            term: response.term,
+            vote_granted: response.vote_granted,
+        }
+    }
+}
+
+impl TryFrom<&InstallSnapshotRequest> for proto::SnapshotTransfer {
+    type Error = TransportError;
+
+    fn try_from(request: &InstallSnapshotRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            term: request.term,
+            leader_id: request.leader_id.as_bytes().to_vec(),
+            snapshot: serde_json::to_vec(&request.snapshot)
+                .map_err(|e| TransportError::Serialization(e.to_string()))?,
+        })
+    }
+}
+
+impl TryFrom<proto::SnapshotTransfer> for InstallSnapshotRequest {
+    type Error = TransportError;
+
+    fn try_from(transfer: proto::SnapshotTransfer) -> Result<Self, Self::Error> {
+        Ok(Self {
+            term: transfer.term,
+            leader_id: NodeId::from_bytes(id_bytes("leader_id", &transfer.leader_id)?),
+            snapshot: serde_json::from_slice(&transfer.snapshot)
+                .map_err(|e| TransportError::Serialization(e.to_string()))?,
+        })
+    }
+}
+
+impl From<AppendEntriesResponse> for proto::SnapshotResponse {
+    fn from(response: AppendEntriesResponse) -> Self {
+        Self {
+            term: response.term,
+            success: response.success,
+            match_index: response.match_index,
+        }
+    }
+}
+
+impl From<proto::SnapshotResponse> for AppendEntriesResponse {
+    fn from(response: proto::SnapshotResponse) -> Self {
+        Self {
+            term: response.term,
+            success: response.success,
+            match_index: response.match_index,
+        }
+    }
+}
+
+/// Server side of the transport
+///
+/// Each call is dispatched to the component that handles it; a node
+/// without that component answers `UNIMPLEMENTED`.
+#[derive(Clone)]
+pub struct TransportService {
+    /// Node serving the calls
+    node_id: NodeId,
+    /// Handshake this node presents to clients
+    handshake: Handshake,
+    /// Worker executing jobs
+    worker: Option<Arc<Worker>>,
+    /// Membership tracking peer heartbeats
+    membership: Option<Arc<Membership>>,
+    /// Consensus answering replication, votes and snapshots
+    consensus: Option<Arc<Consensus>>,
+    /// Coordinator handing out stolen work
+    coordinator: Option<Arc<Coordinator>>,
+}
+
+impl TransportService {
+    /// Create a service that handles no calls yet
+    #[must_use]
+    pub fn new(node_id: NodeId) -> Self {
+        Self {
+            node_id,
+            handshake: Handshake::local(node_id),
+            worker: None,
+            membership: None,
+            consensus: None,
+            coordinator: None,
+        }
+    }
+
+    /// Present a handshake other than this build's own
+    #[must_use]
+    pub fn with_handshake(mut self, handshake: Handshake) -> Self {
+        self.handshake = handshake;
+        self
+    }
+
+    /// Execute jobs on a worker
+    #[must_use]
+    pub fn with_worker(mut self, worker: Arc<Worker>) -> Self {
+        self.worker = Some(worker);
+        self
+    }
+
+    /// Record heartbeats in a membership
+    #[must_use]
+    pub fn with_membership(mut self, membership: Arc<Membership>) -> Self {
+        self.membership = Some(membership);
+        self
+    }
+
+    /// Answer replication, vote and snapshot calls from a consensus instance
+    #[must_use]
+    pub fn with_consensus(mut self, consensus: Arc<Consensus>) -> Self {
+        self.consensus = Some(consensus);
+        self
+    }
+
+    /// Answer work-stealing calls from a coordinator
+    #[must_use]
+    pub fn with_coordinator(mut self, coordinator: Arc<Coordinator>) -> Self {
+        self.coordinator = Some(coordinator);
+        self
+    }
+
+    /// Wrap the service for a tonic router
+    #[must_use]
+    pub fn into_server(self) -> ClusterTransportServer<Self> {
+        ClusterTransportServer::new(self)
+    }
+
+    /// Serve the transport on `addr` until the server fails
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the TLS
+    /// configuration is invalid or the server cannot run
+    pub async fn serve(
+        self,
+        addr: SocketAddr,
+        tls: Option<&TlsConfig>,
+    ) -> Result<(), TransportError> {
+        let failed = |e: tonic::transport::Error| TransportError::ConnectionFailed(e.to_string());
+        let mut builder = Server::builder();
+        if let Some(tls) = tls {
+            builder = builder.tls_config(tls.server()).map_err(failed)?;
+        }
+        builder.add_service(self.into_server()).serve(addr).await.map_err(failed)
+    }
+}
+
+/// Get a component a call needs, or `UNIMPLEMENTED` if the node lacks it
+#[allow(clippy::result_large_err)]
+fn required<'a, T>(component: Option<&'a Arc<T>>, call: &str) -> Result<&'a Arc<T>, Status> {
+    component.ok_or_else(|| Status::unimplemented(format!("{} is not served by this node", call)))
+}
+
+#[tonic::async_trait]
+impl ClusterTransport for TransportService {
+    async fn handshake(
+        &self,
+        request: Request<proto::HandshakeRequest>,
+    ) -> Result<Response<proto::HandshakeResponse>, Status> {
+        let remote = Handshake::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        if let Err(err) = self.handshake.check_compatible(&remote) {
+            tracing::warn!(node_id = %self.node_id, %err, "rejected peer handshake");
+            let details = proto::HandshakeResponse::from(&self.handshake).encode_to_vec();
+            return Err(Status::with_details(
+                Code::FailedPrecondition,
+                err.to_string(),
+                details.into(),
+            ));
+        }
+        Ok(Response::new((&self.handshake).into()))
+    }
+
+    async fn execute(
+        &self,
+        request: Request<proto::ExecuteRequest>,
+    ) -> Result<Response<proto::ExecuteResponse>, Status> {
+        let worker = required(self.worker.as_ref(), "Execute")?;
+        let request = RemoteRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let job_id = worker
+            .accept_job(request.event_id, request)
+            .await
+            .map_err(|e| core_error_status(&e))?;
+        let response = worker.execute_job(job_id).await.map_err(|e| core_error_status(&e))?;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn heartbeat(
+        &self,
+        request: Request<proto::HeartbeatRequest>,
+    ) -> Result<Response<proto::HeartbeatResponse>, Status> {
+        let membership = required(self.membership.as_ref(), "Heartbeat")?;
+        let heartbeat = request.into_inner();
+        let node_id = id_bytes("node_id", &heartbeat.node_id)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let node_id = NodeId::from_bytes(node_id);
+
+        let known = membership
+            .update_heartbeat(node_id, heartbeat.timestamp_ms)
+            .await
+            .map_err(|e| core_error_status(&e))?;
+        if !known {
+            return Err(Status::not_found(format!("member {}", node_id)));
+        }
+        Ok(Response::new(proto::HeartbeatResponse {
+            node_id: self.node_id.as_bytes().to_vec(),
+            timestamp_ms: heartbeat.timestamp_ms,
+        }))
+    }
+
+    async fn append_entries(
+        &self,
+        request: Request<proto::AppendEntriesRequest>,
+    ) -> Result<Response<proto::AppendEntriesResponse>, Status> {
+        let consensus = required(self.consensus.as_ref(), "AppendEntries")?;
+        let request = AppendEntriesRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let response = consensus
+            .append_entries(request)
+            .await
+            .map_err(|e| core_error_status(&e))?;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn request_vote(
+        &self,
+        request: Request<proto::RequestVoteRequest>,
+    ) -> Result<Response<proto::RequestVoteResponse>, Status> {
+        let consensus = required(self.consensus.as_ref(), "RequestVote")?;
+        let request = RequestVoteRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let response = consensus.vote(&request).await.map_err(|e| core_error_status(&e))?;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn transfer_snapshot(
+        &self,
+        request: Request<proto::SnapshotTransfer>,
+    ) -> Result<Response<proto::SnapshotResponse>, Status> {
+        let consensus = required(self.consensus.as_ref(), "TransferSnapshot")?;
+        let request = InstallSnapshotRequest::try_from(request.into_inner())
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let response = consensus
+            .install_snapshot(request)
+            .await
+            .map_err(|e| core_error_status(&e))?;
+        Ok(Response::new(response.into()))
+    }
+
+    async fn cancel_run(
+        &self,
+        request: Request<proto::CancelRunRequest>,
+    ) -> Result<Response<proto::CancelRunResponse>, Status> {
+        let worker = required(self.worker.as_ref(), "CancelRun")?;
+        let run_id = id_bytes("run_id", &request.into_inner().run_id)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let job_ids = worker.cancel_run(RunId::from_bytes(run_id)).await;
+        Ok(Response::new(proto::CancelRunResponse { job_ids }))
+    }
+
+    async fn finish_run(
+        &self,
+        request: Request<proto::FinishRunRequest>,
+    ) -> Result<Response<proto::FinishRunResponse>, Status> {
+        let worker = required(self.worker.as_ref(), "FinishRun")?;
+        let run_id = id_bytes("run_id", &request.into_inner().run_id)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let forgotten = worker.finish_run(RunId::from_bytes(run_id)).await;
+        Ok(Response::new(proto::FinishRunResponse { forgotten: forgotten as u64 }))
+    }
+
+    async fn steal_work(
+        &self,
+        request: Request<proto::StealWorkRequest>,
+    ) -> Result<Response<proto::StealWorkResponse>, Status> {
+        let coordinator = required(self.coordinator.as_ref(), "StealWork")?;
+        let thief = id_bytes("thief", &request.into_inner().thief)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let steal = coordinator
+            .steal_work(NodeId::from_bytes(thief))
+            .await
+            .map_err(|e| core_error_status(&e))?;
+        Ok(Response::new(match steal {
+            Some(steal) => proto::StealWorkResponse {
+                task_id: Some(steal.task_id),
+                from: steal.from.as_bytes().to_vec(),
+            },
+            None => proto::StealWorkResponse::default(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_request_round_trip() {
+        let key = IdempotencyKey::new(RunId::new(), NodeId::new(), 2);
+        let request = RemoteRequest::new(NodeId::new(), EventId::new(), b"job".to_vec())
+            .with_idempotency_key(key);
+        let wire = proto::ExecuteRequest::from(&request);
+        assert_eq!(RemoteRequest::try_from(wire.clone()).unwrap(), request);
+
+        let truncated = proto::ExecuteRequest {
+            event_id: vec![0; 4],
+            ..wire
+        };
+        assert_eq!(
+            RemoteRequest::try_from(truncated).unwrap_err(),
+            TransportError::Serialization("event_id: expected 16 bytes, got 4".to_string())
+        );
+
+        let response = RemoteResponse::error("req".to_string(), "failed".to_string());
+        let wire = proto::ExecuteResponse::from(response.clone());
+        assert_eq!(RemoteResponse::from(wire), response);
+    }
+
+    #[test]
+    fn test_status_to_error() {
+        let target = NodeId::new();
+        let error = |status: Status| status_to_error(target, 250, &status);
+        assert_eq!(error(Status::deadline_exceeded("late")), TransportError::Timeout(250));
+        assert_eq!(error(Status::cancelled("Timeout expired")), TransportError::Timeout(250));
+        assert_eq!(
+            error(Status::cancelled("client went away")),
+            TransportError::Cancelled("client went away".to_string())
+        );
+        assert_eq!(error(Status::unavailable("down")), TransportError::NodeUnavailable(target));
+        assert_eq!(
+            error(Status::invalid_argument("bad")),
+            TransportError::Serialization("bad".to_string())
+        );
+        assert_eq!(
+            error(Status::not_found("job")),
+            TransportError::InvalidResponse("NotFound: job".to_string())
+        );
+        assert_eq!(core_error_status(&CoreError::Cancelled).code(), Code::Aborted);
+    }
+
+    /// Dial `target` at `addr`, retrying while its server starts up
+    async fn dial_when_serving(
+        executor: &crate::remote::RemoteExecutor,
+        target: NodeId,
+        addr: SocketAddr,
+    ) -> Result<crate::remote::RemoteClient, TransportError> {
+        let mut dialed = executor.dial(target, &addr.to_string()).await;
+        for _ in 0..50 {
+            if !matches!(dialed, Err(TransportError::NodeUnavailable(_))) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            dialed = executor.dial(target, &addr.to_string()).await;
+        }
+        dialed
+    }
+
+    #[test]
+    fn test_handshake_round_trip() {
+        let handshake = Handshake::local(NodeId::new());
+        let wire = proto::HandshakeRequest::from(&handshake);
+        assert_eq!(Handshake::try_from(wire.clone()).unwrap(), handshake);
+        let reply = proto::HandshakeResponse::from(&handshake);
+        assert_eq!(Handshake::try_from(reply).unwrap(), handshake);
+
+        let truncated = proto::HandshakeRequest {
+            abi_hash: vec![0; 8],
+            ..wire
+        };
+        assert_eq!(
+            Handshake::try_from(truncated).unwrap_err(),
+            TransportError::Serialization("abi_hash: expected 32 bytes, got 8".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dial_handshakes_over_loopback() {
+        use crate::remote::RemoteExecutor;
+
+        let target = NodeId::new();
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        tokio::spawn(TransportService::new(target).serve(addr, None));
+
+        // A compatible peer is cached after the handshake
+        let executor = RemoteExecutor::new(NodeId::new());
+        let client = dial_when_serving(&executor, target, addr).await.unwrap();
+        assert_eq!(client.target(), target);
+        assert_eq!(executor.connection_count().await, 1);
+        let remote = client.handshake(executor.handshake()).await.unwrap();
+        assert_eq!(remote, Handshake::local(target));
+
+        // A server answering for another node is not trusted
+        let impostor = RemoteExecutor::new(NodeId::new());
+        let dialed = impostor.dial(NodeId::new(), &addr.to_string()).await;
+        assert!(matches!(dialed, Err(TransportError::InvalidResponse(_))));
+        assert_eq!(impostor.connection_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_dial_rejects_abi_mismatch_over_loopback() {
+        use crate::remote::RemoteExecutor;
+        use cathedral_wasm::DeterministicAbi;
+
+        let target = NodeId::new();
+        let mut abi = DeterministicAbi::new();
+        abi.version.major += 1;
+        let server = Handshake::for_abi(target, &abi);
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let service = TransportService::new(target).with_handshake(server.clone());
+        tokio::spawn(service.serve(addr, None));
+
+        let executor = RemoteExecutor::new(NodeId::new());
+        match dial_when_serving(&executor, target, addr).await {
+            Err(TransportError::IncompatiblePeer { peer, field, local, remote }) => {
+                assert_eq!(peer, target);
+                assert_eq!(field, "abi_version");
+                assert_eq!(local, executor.handshake().abi_version);
+                assert_eq!(remote, server.abi_version);
+            }
+            Err(other) => panic!("unexpected error: {other}"),
+            Ok(_) => panic!("dialed a peer with a different ABI"),
+        }
+        assert_eq!(executor.connection_count().await, 0);
+
+        // The server itself refuses the client, attaching its own handshake
+        let mut wire = connect_lazy(&addr.to_string(), None).unwrap();
+        let status = wire
+            .handshake(proto::HandshakeRequest::from(executor.handshake()))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::FailedPrecondition);
+        let details = proto::HandshakeResponse::decode(status.details()).unwrap();
+        assert_eq!(Handshake::try_from(details).unwrap(), server);
+    }
+
+    #[tokio::test]
+    async fn test_dial_rejects_abi_signature_drift_over_loopback() {
+        use crate::remote::RemoteExecutor;
+        use cathedral_wasm::{AbiType, DeterministicAbi};
+
+        // Same ABI version, different signatures: only abi_hash tells them apart
+        let target = NodeId::new();
+        let mut abi = DeterministicAbi::new();
+        abi.functions.get_mut("fs_read").unwrap().returns = AbiType::String;
+        let server = Handshake::for_abi(target, &abi);
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let service = TransportService::new(target).with_handshake(server.clone());
+        tokio::spawn(service.serve(addr, None));
+
+        let executor = RemoteExecutor::new(NodeId::new());
+        assert_eq!(executor.handshake().abi_version, server.abi_version);
+        match dial_when_serving(&executor, target, addr).await {
+            Err(TransportError::IncompatiblePeer { peer, field, local, remote }) => {
+                assert_eq!(peer, target);
+                assert_eq!(field, "abi_hash");
+                assert_eq!(local, executor.handshake().abi_hash.to_hex());
+                assert_eq!(remote, server.abi_hash.to_hex());
+            }
+            Err(other) => panic!("unexpected error: {other}"),
+            Ok(_) => panic!("dialed a peer with different ABI signatures"),
+        }
+        assert_eq!(executor.connection_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_run_over_loopback() {
+        use crate::remote::RemoteClient;
+        use crate::worker::JobPayload;
+        use cathedral_core::CapabilitySet;
+        use cathedral_plan::dag::ResourceRequirements;
+        use cathedral_plan::{Node, NodeKind};
+
+        let worker = Arc::new(Worker::default());
+        let run_id = RunId::new();
+        let node = Node {
+            id: NodeId::new(),
+            kind: NodeKind::Map { function: "identity".to_string() },
+            dependencies: Default::default(),
+            capabilities: Vec::new(),
+            resources: ResourceRequirements::new(),
+        };
+        let payload = JobPayload::new(run_id, node, CapabilitySet::new()).encode().unwrap();
+        let event_id = EventId::new();
+        let request = RemoteRequest::new(NodeId::new(), event_id, payload);
+        let job_id = worker.accept_job(event_id, request).await.unwrap();
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let service = TransportService::new(worker.node_id()).with_worker(Arc::clone(&worker));
+        tokio::spawn(service.serve(addr, None));
+        let client = RemoteClient::grpc(worker.node_id(), addr.to_string(), None).unwrap();
+
+        let mut cancelled = client.cancel_run(run_id).await;
+        for _ in 0..50 {
+            if !matches!(cancelled, Err(TransportError::NodeUnavailable(_))) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            cancelled = client.cancel_run(run_id).await;
+        }
+        assert_eq!(cancelled.unwrap(), [job_id]);
+        assert_eq!(worker.active_job_count().await, 0);
+    }
+
+    #[test]
+    fn test_append_entries_round_trip() {
+        let request = AppendEntriesRequest {
+            term: 3,
+            leader_id: NodeId::new(),
+            prev_log_index: 1,
+            prev_log_term: 2,
+            entries: vec![ConsensusEntry::new(1, 3, b"entry".to_vec())],
+            leader_commit: 1,
+        };
+        let wire = proto::AppendEntriesRequest::from(&request);
+        assert_eq!(AppendEntriesRequest::try_from(wire.clone()).unwrap(), request);
+
+        let mut corrupt = wire;
+        corrupt.entries[0].hash.truncate(8);
+        assert_eq!(
+            AppendEntriesRequest::try_from(corrupt).unwrap_err(),
+            TransportError::Serialization("entries.hash: expected 32 bytes, got 8".to_string())
+        );
+
+        let vote = RequestVoteRequest {
+            term: 4,
+            candidate_id: NodeId::new(),
+            last_log_index: 2,
+            last_log_term: 3,
+        };
+        let wire = proto::RequestVoteRequest::from(&vote);
+        assert_eq!(RequestVoteRequest::try_from(wire).unwrap(), vote);
+    }
+
+    #[tokio::test]
+    async fn test_raft_over_loopback() {
+        use crate::consensus::ConsensusConfig;
+        use cathedral_core::LogicalTime;
+        use cathedral_log::{Event, EventKind};
+        use crate::coordinator::{Coordinator, CoordinatorConfig};
+        use crate::leader::{ElectionConfig, LeaderElection};
+        use crate::membership::Member;
+        use crate::remote::RemoteExecutor;
+
+        let (leader, follower) = (NodeId::new(), NodeId::new());
+        let voters = [leader, follower];
+        let remote = Arc::new(Consensus::new(ConsensusConfig::new(follower).with_voters(voters)));
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let service = TransportService::new(follower).with_consensus(Arc::clone(&remote));
+        tokio::spawn(service.serve(addr, None));
+
+        let consensus = Arc::new(Consensus::new(ConsensusConfig::new(leader).with_voters(voters)));
+        let membership = Arc::new(Membership::new(leader));
+        membership.add_member(Member::new(follower, addr.to_string())).await.unwrap();
+        let election = Arc::new(LeaderElection::new(
+            ElectionConfig::new(leader),
+            Arc::clone(&consensus),
+            Arc::clone(&membership),
+        ));
+        let coordinator = Coordinator::new(
+            CoordinatorConfig::new(leader),
+            Arc::clone(&consensus),
+            Arc::clone(&election),
+            membership,
+            Arc::new(RemoteExecutor::new(leader)),
+        );
+
+        // The follower's vote arrives over RequestVote once it is serving
+        let mut elected = false;
+        for _ in 0..50 {
+            elected = coordinator.campaign().await.unwrap();
+            if elected {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(elected);
+        assert!(election.is_leader().await);
+        let term = consensus.current_term().await;
+        assert_eq!(remote.current_term().await, term);
+
+        // AppendEntries finds the follower's log end, then replicates to it
+        let event = Event::new(
+            EventId::new(),
+            RunId::new(),
+            leader,
+            LogicalTime::from_raw(0),
+            EventKind::NodeScheduled,
+        );
+        consensus.append_event(&event).await.unwrap();
+        let mut committed = 0;
+        for _ in 0..3 {
+            committed = coordinator.replicate().await.unwrap();
+        }
+        assert_eq!(committed, 1);
+        assert_eq!(remote.log_len().await, 1);
+        assert_eq!(remote.commit_index().await, 1);
+        assert_eq!(remote.leader_id().await, Some(leader));
+    }
+
+    #[tokio::test]
+    async fn test_steal_work_over_loopback() {
+        use crate::consensus::ConsensusConfig;
+        use crate::coordinator::CoordinatorConfig;
+        use crate::leader::{ElectionConfig, ElectionState, LeaderElection};
+        use crate::membership::{Member, MemberState};
+        use crate::remote::{RemoteClient, RemoteExecutor};
+        use crate::worker::WorkerConfig;
+        use cathedral_runtime::Executor;
+
+        let leader = NodeId::new();
+        let consensus = Arc::new(Consensus::new(ConsensusConfig::new(leader).with_quorum_size(1)));
+        consensus.start_election().await.unwrap();
+        assert!(consensus.receive_vote(leader, 1).await.unwrap());
+        let membership = Arc::new(Membership::new(leader));
+        let election = Arc::new(LeaderElection::new(
+            ElectionConfig::new(leader),
+            Arc::clone(&consensus),
+            Arc::clone(&membership),
+        ));
+        election.set_state(ElectionState::Leader).await;
+        let coordinator = Arc::new(Coordinator::new(
+            CoordinatorConfig::new(leader),
+            consensus,
+            election,
+            Arc::clone(&membership),
+            Arc::new(RemoteExecutor::new(leader)),
+        ));
+
+        let busy = NodeId::new();
+        let member = Member::new(busy, "busy".to_string())
+            .with_state(MemberState::Active)
+            .with_max_concurrent(10);
+        membership.add_member(member).await.unwrap();
+        for _ in 0..2 {
+            let task_id = coordinator.submit(EventId::new()).await.unwrap();
+            coordinator.assign_task(task_id, busy).await.unwrap();
+        }
+        let thief = Worker::new(
+            WorkerConfig::default(),
+            Arc::clone(&membership),
+            Arc::new(Executor::default()),
+        );
+        thief.register().await.unwrap();
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let service = TransportService::new(leader).with_coordinator(Arc::clone(&coordinator));
+        tokio::spawn(service.serve(addr, None));
+        let client = RemoteClient::grpc(leader, addr.to_string(), None).unwrap();
+
+        let mut stolen = thief.steal_work(&client).await;
+        for _ in 0..50 {
+            if !matches!(stolen, Err(TransportError::NodeUnavailable(_))) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            stolen = thief.steal_work(&client).await;
+        }
+        let steal = stolen.unwrap().unwrap();
+        assert_eq!((steal.from, steal.to), (busy, thief.node_id()));
+        let task = coordinator.get_task(steal.task_id).await.unwrap();
+        assert_eq!(task.assigned_worker, Some(thief.node_id()));
+
+        // The busy worker is down to the threshold
+        assert_eq!(thief.steal_work(&client).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_hands_off_over_loopback() {
+        use crate::consensus::ConsensusConfig;
+        use crate::coordinator::{CoordinatorConfig, ShutdownStep, TaskStatus};
+        use crate::leader::{ElectionConfig, LeaderElection};
+        use crate::membership::Member;
+        use crate::remote::RemoteExecutor;
+        use std::time::Instant;
+
+        /// Start a coordinator serving consensus on `addr` that reaches its
+        /// peer at `peer_addr`
+        async fn start(
+            node_id: NodeId,
+            addr: SocketAddr,
+            peer: NodeId,
+            peer_addr: SocketAddr,
+        ) -> (Arc<Coordinator>, Arc<LeaderElection>) {
+            let consensus = Arc::new(Consensus::new(
+                ConsensusConfig::new(node_id).with_voters([node_id, peer]),
+            ));
+            let membership = Arc::new(Membership::new(node_id));
+            membership.add_member(Member::new(peer, peer_addr.to_string())).await.unwrap();
+            let election = Arc::new(LeaderElection::new(
+                ElectionConfig::new(node_id),
+                Arc::clone(&consensus),
+                Arc::clone(&membership),
+            ));
+            let coordinator = Arc::new(Coordinator::new(
+                CoordinatorConfig::new(node_id),
+                Arc::clone(&consensus),
+                Arc::clone(&election),
+                membership,
+                Arc::new(RemoteExecutor::new(node_id)),
+            ));
+            let service = TransportService::new(node_id).with_consensus(consensus);
+            tokio::spawn(service.serve(addr, None));
+            (coordinator, election)
+        }
+
+        /// Campaign until elected, giving the peer time to start serving
+        async fn elect(coordinator: &Coordinator) -> bool {
+            for _ in 0..50 {
+                if coordinator.campaign().await.unwrap() {
+                    return true;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            false
+        }
+
+        let (a, b) = (NodeId::new(), NodeId::new());
+        let addr_a = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let addr_b = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let (first, first_election) = start(a, addr_a, b, addr_b).await;
+        let (second, second_election) = start(b, addr_b, a, addr_a).await;
+        assert!(elect(&first).await);
+        // Heartbeats find where the peer's log ends
+        for _ in 0..3 {
+            first.replicate().await.unwrap();
+        }
+
+        // No member can run the task, so shutdown leaves it for recovery
+        let task_id = first.submit(EventId::new()).await.unwrap();
+        let deadline = Duration::from_secs(5);
+        let started = Instant::now();
+        let steps = first.shutdown(deadline).await.unwrap();
+        assert_eq!(
+            steps[0],
+            ShutdownStep::Drained {
+                completed: 0,
+                remaining: vec![task_id.clone()],
+            }
+        );
+        assert_eq!(steps[1], ShutdownStep::Snapshotted { index: 1 });
+        assert_eq!(steps[2], ShutdownStep::SteppedDown { was_leader: true });
+        assert!(!first_election.is_leader().await);
+
+        // The stepped-down node votes for its peer before the deadline runs out
+        let remaining = deadline.saturating_sub(started.elapsed());
+        let elected = tokio::time::timeout(remaining, elect(&second)).await;
+        assert_eq!(elected, Ok(true));
+        assert!(second_election.is_leader().await);
+
+        // The new leader recovers the outstanding task from the snapshot
+        second.apply_committed().await.unwrap();
+        assert_eq!(second.snapshot_index().await, 1);
+        let task = second.get_task(task_id).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Pending);
+    }
+
+    #[test]
+    fn test_tls_config_debug_omits_key() {
+        let tls = TlsConfig::new(b"ca".to_vec(), b"cert".to_vec(), b"secret".to_vec(), "node");
+        let debug = format!("{:?}", tls);
+        assert!(debug.contains("node"));
+        assert!(!debug.contains("key_pem"));
+    }
+}
@@ -3,6 +3,7 @@
 use crate::{consensus::Consensus, membership::Membership};
 use cathedral_core::{CoreResult, NodeId};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -15,6 +16,12 @@ pub struct ElectionConfig {
     pub election_timeout_ms: u64,
     /// Heartbeat interval in milliseconds
     pub heartbeat_interval_ms: u64,
+    /// How long a quorum of heartbeat acknowledgements keeps the leader's
+    /// lease valid, in milliseconds
+    ///
+    /// Should be shorter than the election timeout, so the lease runs out
+    /// before followers can elect a new leader.
+    pub lease_duration_ms: u64,
 }
 
 impl ElectionConfig {
@@ -25,8 +32,16 @@ impl ElectionConfig {
             node_id,
             election_timeout_ms: 1000,
             heartbeat_interval_ms: 100,
+            lease_duration_ms: 800,
         }
     }
+
+    /// Set the leader lease duration
+    #[must_use]
+    pub fn with_lease_duration(mut self, lease_duration_ms: u64) -> Self {
+        self.lease_duration_ms = lease_duration_ms;
+        self
+    }
 }
 
 impl Default for ElectionConfig {
@@ -60,6 +75,8 @@ pub enum ElectionState {
     InProgress,
     /// This node is leader
     Leader,
+    /// Lease lost; no longer accepting work, handing off before stepping down
+    SteppingDown,
     /// Following a leader
     Follower(NodeId),
 }
@@ -76,6 +93,10 @@ pub struct LeaderElection {
     consensus: Arc<Consensus>,
     /// Membership instance
     membership: Arc<Membership>,
+    /// Time at which the leader lease runs out, once established
+    lease_expires_at: Arc<RwLock<Option<u64>>>,
+    /// Latest heartbeat acknowledged by each peer
+    acks: Arc<RwLock<HashMap<NodeId, u64>>>,
 }
 
 impl LeaderElection {
@@ -88,6 +109,8 @@ impl LeaderElection {
             leader: Arc::new(RwLock::new(None)),
             consensus,
             membership,
+            lease_expires_at: Arc::new(RwLock::new(None)),
+            acks: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -137,11 +160,22 @@ impl LeaderElection {
 
     /// Cast a vote for a candidate
     ///
+    /// The vote is refused if the candidate's log, given by its length and
+    /// last term, is behind this node's.
+    ///
     /// # Errors
     ///
     /// Returns error if vote cannot be cast
-    pub async fn vote(&self, candidate_id: NodeId, term: u64) -> CoreResult<bool> {
-        self.consensus.request_vote(candidate_id, term, 0, 0).await
+    pub async fn vote(
+        &self,
+        candidate_id: NodeId,
+        term: u64,
+        last_log_index: u64,
+        last_log_term: u64,
+    ) -> CoreResult<bool> {
+        self.consensus
+            .request_vote(candidate_id, term, last_log_index, last_log_term)
+            .await
     }
 
     /// Receive a vote
@@ -155,6 +189,8 @@ impl LeaderElection {
         if won {
             *self.state.write().await = ElectionState::Leader;
             *self.leader.write().await = Some(self.config.node_id);
+            *self.lease_expires_at.write().await = None;
+            self.acks.write().await.clear();
         }
 
         Ok(won)
@@ -168,9 +204,84 @@ impl LeaderElection {
     pub async fn step_down(&self) {
         *self.state.write().await = ElectionState::Follower(self.config.node_id);
         *self.leader.write().await = None;
+        *self.lease_expires_at.write().await = None;
         self.consensus.become_follower().await;
     }
 
+    /// Check if this node lost its lease and is handing off leadership
+    pub async fn is_stepping_down(&self) -> bool {
+        matches!(*self.state.read().await, ElectionState::SteppingDown)
+    }
+
+    /// Record a peer's acknowledgement of the heartbeat sent at `sent_at`
+    pub async fn record_ack(&self, peer: NodeId, sent_at: u64) {
+        let mut acks = self.acks.write().await;
+        let ack = acks.entry(peer).or_insert(sent_at);
+        *ack = (*ack).max(sent_at);
+    }
+
+    /// Get the time at which the leader lease runs out, once established
+    pub async fn lease_expires_at(&self) -> Option<u64> {
+        *self.lease_expires_at.read().await
+    }
+
+    /// Renew the leader lease and step down if it has run out
+    ///
+    /// The lease extends `lease_duration_ms` past the latest heartbeat a
+    /// quorum of members (this node included) has acknowledged. A new
+    /// leader's lease starts at its first check. Once `now` reaches the
+    /// expiry the node enters [`ElectionState::SteppingDown`]; callers
+    /// finish handing off and then call [`LeaderElection::step_down`].
+    ///
+    /// Returns true if this node is leader and holds the lease.
+    pub async fn check_lease(&self, now: u64) -> bool {
+        let mut state = self.state.write().await;
+        if *state != ElectionState::Leader {
+            return false;
+        }
+
+        let mut voters: BTreeSet<NodeId> = self
+            .membership
+            .members()
+            .await
+            .iter()
+            .map(|m| m.node_id)
+            .collect();
+        voters.insert(self.config.node_id);
+        let quorum = voters.len() / 2 + 1;
+
+        let acks = self.acks.read().await;
+        let mut acked: Vec<u64> = voters
+            .iter()
+            .filter_map(|voter| {
+                if *voter == self.config.node_id {
+                    Some(now)
+                } else {
+                    acks.get(voter).copied()
+                }
+            })
+            .collect();
+        acked.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut lease_expires_at = self.lease_expires_at.write().await;
+        let renewed = acked
+            .get(quorum - 1)
+            .map(|at| at.saturating_add(self.config.lease_duration_ms));
+        let expires_at = match (*lease_expires_at, renewed) {
+            (None, _) => now.saturating_add(self.config.lease_duration_ms),
+            (Some(current), Some(renewed)) => current.max(renewed),
+            (Some(current), None) => current,
+        };
+        *lease_expires_at = Some(expires_at);
+
+        if now >= expires_at {
+            tracing::warn!(now, expires_at, "leader lease expired, stepping down");
+            *state = ElectionState::SteppingDown;
+            return false;
+        }
+        true
+    }
+
     /// Recognize a new leader
     ///
     /// # Errors
@@ -339,4 +450,46 @@ mod tests {
         assert_eq!(ElectionState::Idle, ElectionState::Idle);
         assert_ne!(ElectionState::Idle, ElectionState::Leader);
     }
+
+    #[tokio::test]
+    async fn test_leader_lease() {
+        use crate::membership::Member;
+
+        let node_id = NodeId::new();
+        let config = ElectionConfig::new(node_id).with_lease_duration(800);
+        let consensus = Arc::new(Consensus::new(
+            ConsensusConfig::new(node_id).with_quorum_size(1),
+        ));
+        let membership = Arc::new(Membership::new(node_id));
+        let peers = [NodeId::new(), NodeId::new()];
+        for peer in peers {
+            membership
+                .add_member(Member::new(peer, "peer".to_string()))
+                .await
+                .unwrap();
+        }
+
+        let election = LeaderElection::new(config, consensus, membership);
+        election.start_election().await.unwrap();
+        assert!(election.receive_vote(node_id, 1).await.unwrap());
+
+        // The first check starts the lease
+        assert!(election.check_lease(0).await);
+        assert_eq!(election.lease_expires_at().await, Some(800));
+
+        // One peer plus this node is a quorum of three
+        election.record_ack(peers[0], 500).await;
+        assert!(election.check_lease(700).await);
+        assert_eq!(election.lease_expires_at().await, Some(1300));
+
+        assert!(!election.check_lease(1300).await);
+        assert_eq!(election.state().await, ElectionState::SteppingDown);
+        assert!(election.is_stepping_down().await);
+        assert!(!election.is_leader().await);
+
+        election.step_down().await;
+        assert_eq!(election.state().await, ElectionState::Follower(node_id));
+        assert_eq!(election.lease_expires_at().await, None);
+        assert!(!election.check_lease(1400).await);
+    }
 }
@@ -7,15 +7,31 @@
 #![warn(clippy::all)]
 
 pub mod consensus;
+pub mod configuration;
 pub mod membership;
 pub mod leader;
 pub mod remote;
 pub mod coordinator;
 pub mod worker;
+pub mod storage;
+pub mod compaction;
+pub mod grpc;
 
-pub use consensus::{Consensus, ConsensusConfig, ConsensusError};
+pub use consensus::{
+    AppendEntriesRequest, AppendEntriesResponse, Consensus, ConsensusConfig, ConsensusEntry,
+    ConsensusError, InstallSnapshotRequest, Replication, RequestVoteRequest, RequestVoteResponse,
+};
+pub use configuration::ClusterConfiguration;
 pub use membership::{Membership, Member, MemberState};
 pub use leader::{LeaderElection, ElectionConfig, ElectionError};
-pub use remote::{RemoteExecutor, RemoteClient, TransportError};
-pub use coordinator::{Coordinator, CoordinatorConfig, CoordinatorError};
-pub use worker::{Worker, WorkerConfig, WorkerError};
+pub use remote::{Handshake, IdempotencyKey, RemoteExecutor, RemoteClient, TransportError};
+pub use coordinator::{
+    ClusterStatus, Coordinator, CoordinatorConfig, CoordinatorError, DeadLetter,
+    DeadLetterTransition, DrainReport, ExecutionResult, MemberStatus, SchedulingPolicy, ShutdownStep, WorkSteal,
+};
+pub use worker::{JobOutput, JobPayload, JobRecord, Worker, WorkerConfig, WorkerError};
+pub use storage::{
+    ConsensusStorage, FileConsensusStorage, HardState, MemoryConsensusStorage, PersistentState,
+};
+pub use compaction::{CompactionPolicy, ConsensusSnapshot};
+pub use grpc::{TlsConfig, TransportService};
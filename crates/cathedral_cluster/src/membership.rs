@@ -1,10 +1,10 @@
 //! Cluster membership management.
 
-use cathedral_core::{CoreResult, NodeId};
+use cathedral_core::{Capability, CapabilitySet, CoreResult, NodeId};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 /// Member state in the cluster
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -33,7 +33,9 @@ pub struct Member {
     /// Last heartbeat timestamp
     pub last_heartbeat: u64,
     /// Member capabilities
-    pub capabilities: Vec<String>,
+    pub capabilities: CapabilitySet,
+    /// Jobs the member runs at once, 0 for a node that runs none
+    pub max_concurrent: usize,
 }
 
 impl Member {
@@ -45,7 +47,8 @@ impl Member {
             state: MemberState::Joining,
             address,
             last_heartbeat: 0,
-            capabilities: Vec::new(),
+            capabilities: CapabilitySet::new(),
+            max_concurrent: 0,
         }
     }
 
@@ -63,6 +66,32 @@ impl Member {
         self
     }
 
+    /// Set member capabilities
+    #[must_use]
+    pub fn with_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Set how many jobs the member runs at once
+    #[must_use]
+    pub fn with_max_concurrent(mut self, max: usize) -> Self {
+        self.max_concurrent = max;
+        self
+    }
+
+    /// Check if member can host work requiring the given capabilities
+    #[must_use]
+    pub fn supports(&self, required: &CapabilitySet) -> bool {
+        self.capabilities.covers_all(required)
+    }
+
+    /// Check if member declares a single capability
+    #[must_use]
+    pub fn has_capability(&self, capability: &Capability) -> bool {
+        self.capabilities.covers(capability)
+    }
+
     /// Check if member is active
     #[must_use]
     pub fn is_active(&self) -> bool {
@@ -84,6 +113,8 @@ pub struct Membership {
     node_id: NodeId,
     /// Heartbeat timeout in milliseconds
     heartbeat_timeout_ms: u64,
+    /// Bumped whenever a member joins, leaves, or changes state
+    changes: watch::Sender<u64>,
 }
 
 impl Membership {
@@ -94,9 +125,21 @@ impl Membership {
             members: Arc::new(RwLock::new(HashMap::new())),
             node_id,
             heartbeat_timeout_ms: 5000,
+            changes: watch::Sender::new(0),
         }
     }
 
+    /// Watch for members joining, leaving, or changing state
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    /// Wake everyone watching for membership changes
+    fn changed(&self) {
+        self.changes.send_modify(|version| *version += 1);
+    }
+
     /// Get all members
     ///
     /// # Errors
@@ -129,6 +172,7 @@ impl Membership {
     pub async fn add_member(&self, member: Member) -> CoreResult<()> {
         let mut members = self.members.write().await;
         members.insert(member.node_id, member);
+        self.changed();
         Ok(())
     }
 
@@ -139,7 +183,11 @@ impl Membership {
     /// Returns error if remove fails
     pub async fn remove_member(&self, node_id: NodeId) -> CoreResult<bool> {
         let mut members = self.members.write().await;
-        Ok(members.remove(&node_id).is_some())
+        let removed = members.remove(&node_id).is_some();
+        if removed {
+            self.changed();
+        }
+        Ok(removed)
     }
 
     /// Get a member by ID
@@ -160,6 +208,7 @@ impl Membership {
         let mut members = self.members.write().await;
         if let Some(member) = members.get_mut(&node_id) {
             member.state = state;
+            self.changed();
             return Ok(true);
         }
         Ok(false)
@@ -176,6 +225,7 @@ impl Membership {
             member.last_heartbeat = timestamp;
             if member.state == MemberState::Suspected {
                 member.state = MemberState::Active;
+                self.changed();
             }
             return Ok(true);
         }
@@ -204,6 +254,9 @@ impl Membership {
                 }
             }
         }
+        if !suspected.is_empty() {
+            self.changed();
+        }
 
         Ok(suspected)
     }
@@ -288,6 +341,28 @@ mod tests {
         assert_eq!(member.last_heartbeat, 12345);
     }
 
+    #[tokio::test]
+    async fn test_member_supports() {
+        let mut caps = CapabilitySet::new();
+        caps.grant(Capability::WasmExec {
+            fuel: 1_000_000,
+            memory: 64 * 1024 * 1024,
+        });
+        let member = Member::new(NodeId::new(), "addr".to_string()).with_capabilities(caps);
+
+        let mut required = CapabilitySet::new();
+        required.grant(Capability::WasmExec {
+            fuel: 1_000,
+            memory: 1024,
+        });
+        assert!(member.supports(&required));
+        assert!(member.supports(&CapabilitySet::new()));
+
+        required.grant(Capability::ClockRead);
+        assert!(!member.supports(&required));
+        assert!(!member.has_capability(&Capability::ClockRead));
+    }
+
     #[tokio::test]
     async fn test_add_member() {
         let node_id = NodeId::new();
@@ -1,10 +1,19 @@
 //! Remote execution over network.
 
-use cathedral_core::{CoreResult, CoreError, EventId, NodeId};
+use crate::consensus::{
+    AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, RequestVoteRequest,
+    RequestVoteResponse,
+};
+use crate::coordinator::WorkSteal;
+use crate::grpc::{self, proto, TlsConfig};
+use cathedral_core::{CoreResult, CoreError, EventId, Hash, NodeId, RunId};
+use cathedral_log::EventKind;
+use cathedral_wasm::DeterministicAbi;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tonic::transport::Channel;
 
 /// Transport errors
 #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
@@ -17,6 +26,10 @@ pub enum TransportError {
     #[error("Request timeout after {0}ms")]
     Timeout(u64),
 
+    /// Call was cancelled before it completed
+    #[error("Request cancelled: {0}")]
+    Cancelled(String),
+
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(String),
@@ -28,6 +41,181 @@ pub enum TransportError {
     /// Node unavailable
     #[error("Node unavailable: {0}")]
     NodeUnavailable(NodeId),
+
+    /// Peer build is incompatible with this node
+    #[error("Incompatible peer {peer}: {field} differs (local {local}, remote {remote})")]
+    IncompatiblePeer {
+        /// Peer that was rejected
+        peer: NodeId,
+        /// Handshake field that did not match
+        field: String,
+        /// Local value
+        local: String,
+        /// Remote value
+        remote: String,
+    },
+}
+
+impl From<TransportError> for CoreError {
+    fn from(err: TransportError) -> Self {
+        CoreError::Validation {
+            field: "transport".to_string(),
+            reason: err.to_string(),
+        }
+    }
+}
+
+/// Protocol features this build speaks
+pub const PROTOCOL_FEATURES: &[&str] = &["capability-dispatch", "global-sequence"];
+
+/// Self-description exchanged when two cluster nodes connect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshake {
+    /// Node sending the handshake
+    pub node_id: NodeId,
+    /// Crate version of the sender
+    pub crate_version: String,
+    /// Host ABI version of the sender
+    pub abi_version: String,
+    /// Hash of the sender's canonical ABI signatures, sent over the wire
+    /// so builds that share an ABI version but not its signatures are
+    /// refused
+    pub abi_hash: Hash,
+    /// Protocol features enabled on the sender
+    pub features: BTreeSet<String>,
+}
+
+impl Handshake {
+    /// Describe this build
+    #[must_use]
+    pub fn local(node_id: NodeId) -> Self {
+        Self::for_abi(node_id, &DeterministicAbi::new())
+    }
+
+    /// Describe this build running the given host ABI
+    #[must_use]
+    pub fn for_abi(node_id: NodeId, abi: &DeterministicAbi) -> Self {
+        Self {
+            node_id,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            abi_version: abi.version.to_string(),
+            abi_hash: abi.content_hash(),
+            features: PROTOCOL_FEATURES.iter().map(|f| (*f).to_string()).collect(),
+        }
+    }
+
+    /// Check that a remote handshake describes a build this node can cluster with
+    ///
+    /// Crate versions must be semver-compatible, ABI versions and signature
+    /// hashes must match exactly, and both sides must enable the same
+    /// protocol features.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::IncompatiblePeer`] naming the first mismatch
+    pub fn check_compatible(&self, remote: &Handshake) -> Result<(), TransportError> {
+        let mismatch = |field: &str, local: String, remote_value: String| {
+            TransportError::IncompatiblePeer {
+                peer: remote.node_id,
+                field: field.to_string(),
+                local,
+                remote: remote_value,
+            }
+        };
+
+        if !versions_compatible(&self.crate_version, &remote.crate_version) {
+            return Err(mismatch(
+                "crate_version",
+                self.crate_version.clone(),
+                remote.crate_version.clone(),
+            ));
+        }
+
+        if self.abi_version != remote.abi_version {
+            return Err(mismatch(
+                "abi_version",
+                self.abi_version.clone(),
+                remote.abi_version.clone(),
+            ));
+        }
+
+        if self.abi_hash != remote.abi_hash {
+            return Err(mismatch(
+                "abi_hash",
+                self.abi_hash.to_hex(),
+                remote.abi_hash.to_hex(),
+            ));
+        }
+
+        if self.features != remote.features {
+            let join = |set: &BTreeSet<String>| set.iter().cloned().collect::<Vec<_>>().join(",");
+            return Err(mismatch("features", join(&self.features), join(&remote.features)));
+        }
+
+        Ok(())
+    }
+}
+
+/// Check semver compatibility: same major, and same minor while major is 0
+fn versions_compatible(local: &str, remote: &str) -> bool {
+    match (
+        cathedral_core::Version::parse(local),
+        cathedral_core::Version::parse(remote),
+    ) {
+        (Ok(l), Ok(r)) => l.major == r.major && (l.major != 0 || l.minor == r.minor),
+        _ => local == remote,
+    }
+}
+
+/// Identity of one attempt at executing a node
+///
+/// A redelivered request carries the same key as the original, so a
+/// worker can recognise it and answer without executing the node again.
+/// A retry is a new attempt and gets a new key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct IdempotencyKey {
+    /// Run the node belongs to
+    pub run_id: RunId,
+    /// Node being executed
+    pub node_id: NodeId,
+    /// Attempt number, starting at 0
+    pub attempt: u64,
+}
+
+impl IdempotencyKey {
+    /// Create the key of an attempt
+    #[must_use]
+    pub const fn new(run_id: RunId, node_id: NodeId, attempt: u64) -> Self {
+        Self { run_id, node_id, attempt }
+    }
+
+    /// Content hash of the key
+    #[must_use]
+    pub fn hash(&self) -> Hash {
+        let mut bytes = Vec::with_capacity(40);
+        bytes.extend_from_slice(self.run_id.as_bytes());
+        bytes.extend_from_slice(self.node_id.as_bytes());
+        bytes.extend_from_slice(&self.attempt.to_be_bytes());
+        Hash::compute(&bytes)
+    }
+
+    /// ID of the event recording `kind` for this attempt
+    ///
+    /// Derived from the key, so every node that records the same outcome
+    /// of the attempt gives its event the same ID.
+    #[must_use]
+    pub fn event_id(&self, kind: EventKind) -> EventId {
+        let hash = self.hash().chain(&Hash::compute(&[kind.code()]));
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash.as_bytes()[..16]);
+        EventId::from_bytes(bytes)
+    }
+}
+
+impl std::fmt::Display for IdempotencyKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}#{}", self.run_id, self.node_id, self.attempt)
+    }
 }
 
 /// Remote execution request
@@ -41,6 +229,9 @@ pub struct RemoteRequest {
     pub event_id: EventId,
     /// Request payload
     pub payload: Vec<u8>,
+    /// Attempt this request executes, for duplicate suppression
+    #[serde(default)]
+    pub idempotency_key: Option<IdempotencyKey>,
 }
 
 impl RemoteRequest {
@@ -52,8 +243,16 @@ impl RemoteRequest {
             source,
             event_id,
             payload,
+            idempotency_key: None,
         }
     }
+
+    /// Tag the request with the attempt it executes
+    #[must_use]
+    pub fn with_idempotency_key(mut self, key: IdempotencyKey) -> Self {
+        self.idempotency_key = Some(key);
+        self
+    }
 }
 
 /// Remote execution response
@@ -102,19 +301,50 @@ pub struct RemoteClient {
     address: String,
     /// Request timeout in milliseconds
     timeout_ms: u64,
+    /// gRPC channel, if this client talks over the wire
+    transport: Option<proto::cluster_transport_client::ClusterTransportClient<Channel>>,
 }
 
 impl RemoteClient {
     /// Create a new remote client
+    ///
+    /// The client is not connected to the network; requests are answered by
+    /// a local stub, which makes it useful for tests only. Use
+    /// [`RemoteClient::grpc`] or [`RemoteExecutor::dial`] to reach a real
+    /// node.
     #[must_use]
     pub fn new(target: NodeId, address: String) -> Self {
         Self {
             target,
             address,
             timeout_ms: 5000,
+            transport: None,
         }
     }
 
+    /// Create a client that talks to `target` over gRPC
+    ///
+    /// The channel connects lazily on the first call, over mutual TLS if
+    /// `tls` is given.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the address or TLS
+    /// configuration is invalid
+    pub fn grpc(
+        target: NodeId,
+        address: String,
+        tls: Option<&TlsConfig>,
+    ) -> Result<Self, TransportError> {
+        let transport = grpc::connect_lazy(&address, tls)?;
+        Ok(Self {
+            target,
+            address,
+            timeout_ms: 5000,
+            transport: Some(transport),
+        })
+    }
+
     /// Get the target node ID
     #[must_use]
     pub fn target(&self) -> NodeId {
@@ -123,14 +353,29 @@ impl RemoteClient {
 
     /// Send a request to the target node
     ///
+    /// Over gRPC the client's timeout is sent as the call deadline. Without a
+    /// wire transport the request is answered by a local stub.
+    ///
     /// # Errors
     ///
-    /// Returns error if request fails
+    /// Returns [`CoreError::Timeout`] if the deadline passes,
+    /// [`CoreError::Cancelled`] if the call is cancelled, or
+    /// [`CoreError::Internal`] carrying the [`TransportError`] if the call
+    /// otherwise fails
     pub async fn send(&self, request: RemoteRequest) -> CoreResult<RemoteResponse> {
+        if let Some(transport) = &self.transport {
+            let call = grpc::with_deadline(proto::ExecuteRequest::from(&request), self.timeout_ms);
+            let response = transport
+                .clone()
+                .execute(call)
+                .await
+                .map_err(|status| self.call_failed(&status))?;
+            return Ok(response.into_inner().into());
+        }
+
         let request_id = request.request_id.clone();
 
-        // In a real implementation, this would use gRPC/HTTP/QUIC
-        // For now, simulate a successful response
+        // No wire transport: simulate a successful response
         let _ = (self.target, self.address.clone(), self.timeout_ms, request);
 
         // Simulate network delay
@@ -142,6 +387,213 @@ impl RemoteClient {
         ))
     }
 
+    /// Tell the target node that `from` is alive as of `timestamp_ms`
+    ///
+    /// Returns the target's own timestamp from its reply.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the client has no wire
+    /// transport, or the mapped error if the call fails
+    pub async fn heartbeat(&self, from: NodeId, timestamp_ms: u64) -> Result<u64, TransportError> {
+        let call = proto::HeartbeatRequest {
+            node_id: from.as_bytes().to_vec(),
+            timestamp_ms,
+        };
+        let response = self
+            .wire()?
+            .heartbeat(grpc::with_deadline(call, self.timeout_ms))
+            .await
+            .map_err(|status| grpc::status_to_error(self.target, self.timeout_ms, &status))?;
+        Ok(response.into_inner().timestamp_ms)
+    }
+
+    /// Exchange handshakes with the target node
+    ///
+    /// Sends `local` and returns the target's handshake once each side has
+    /// checked that the other's build is compatible.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::IncompatiblePeer`] if either side rejects
+    /// the other's build, [`TransportError::InvalidResponse`] if the reply
+    /// comes from another node, [`TransportError::ConnectionFailed`] if the
+    /// client has no wire transport, or the mapped error if the call fails
+    pub async fn handshake(&self, local: &Handshake) -> Result<Handshake, TransportError> {
+        let call = proto::HandshakeRequest::from(local);
+        let response = self
+            .wire()?
+            .handshake(grpc::with_deadline(call, self.timeout_ms))
+            .await
+            .map_err(|status| {
+                grpc::handshake_rejection(local, &status).unwrap_or_else(|| {
+                    grpc::status_to_error(self.target, self.timeout_ms, &status)
+                })
+            })?;
+        let remote = Handshake::try_from(response.into_inner())?;
+        if remote.node_id != self.target {
+            return Err(TransportError::InvalidResponse(format!(
+                "handshake from {} for connection to {}",
+                remote.node_id, self.target
+            )));
+        }
+        local.check_compatible(&remote)?;
+        Ok(remote)
+    }
+
+    /// Replicate entries, or a heartbeat, to the target node
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the client has no wire
+    /// transport, or the mapped error if the call fails
+    pub async fn append_entries(
+        &self,
+        request: &AppendEntriesRequest,
+    ) -> Result<AppendEntriesResponse, TransportError> {
+        let call = proto::AppendEntriesRequest::from(request);
+        let response = self
+            .wire()?
+            .append_entries(grpc::with_deadline(call, self.timeout_ms))
+            .await
+            .map_err(|status| grpc::status_to_error(self.target, self.timeout_ms, &status))?;
+        Ok(response.into_inner().into())
+    }
+
+    /// Ask the target node for its vote
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the client has no wire
+    /// transport, or the mapped error if the call fails
+    pub async fn request_vote(
+        &self,
+        request: &RequestVoteRequest,
+    ) -> Result<RequestVoteResponse, TransportError> {
+        let call = proto::RequestVoteRequest::from(request);
+        let response = self
+            .wire()?
+            .request_vote(grpc::with_deadline(call, self.timeout_ms))
+            .await
+            .map_err(|status| grpc::status_to_error(self.target, self.timeout_ms, &status))?;
+        Ok(response.into_inner().into())
+    }
+
+    /// Send a leader's snapshot to the target node
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the client has no wire
+    /// transport, [`TransportError::Serialization`] if the snapshot cannot be
+    /// encoded, or the mapped error if the call fails
+    pub async fn transfer_snapshot(
+        &self,
+        request: &InstallSnapshotRequest,
+    ) -> Result<AppendEntriesResponse, TransportError> {
+        let call = proto::SnapshotTransfer::try_from(request)?;
+        let response = self
+            .wire()?
+            .transfer_snapshot(grpc::with_deadline(call, self.timeout_ms))
+            .await
+            .map_err(|status| grpc::status_to_error(self.target, self.timeout_ms, &status))?;
+        Ok(response.into_inner().into())
+    }
+
+    /// Stop a cancelled run's jobs on the target node
+    ///
+    /// Returns the IDs of the jobs that were stopped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the client has no wire
+    /// transport, or the mapped error if the call fails
+    pub async fn cancel_run(&self, run_id: RunId) -> Result<Vec<String>, TransportError> {
+        let call = proto::CancelRunRequest {
+            run_id: run_id.as_bytes().to_vec(),
+        };
+        let response = self
+            .wire()?
+            .cancel_run(grpc::with_deadline(call, self.timeout_ms))
+            .await
+            .map_err(|status| grpc::status_to_error(self.target, self.timeout_ms, &status))?;
+        Ok(response.into_inner().job_ids)
+    }
+
+    /// Ask the target coordinator for a task queued on an overloaded worker
+    ///
+    /// Returns the steal if a task was moved to `thief`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the client has no wire
+    /// transport, [`TransportError::InvalidResponse`] if the reply names no
+    /// valid worker, or the mapped error if the call fails
+    pub async fn steal_work(&self, thief: NodeId) -> Result<Option<WorkSteal>, TransportError> {
+        let call = proto::StealWorkRequest {
+            thief: thief.as_bytes().to_vec(),
+        };
+        let response = self
+            .wire()?
+            .steal_work(grpc::with_deadline(call, self.timeout_ms))
+            .await
+            .map_err(|status| grpc::status_to_error(self.target, self.timeout_ms, &status))?
+            .into_inner();
+        let Some(task_id) = response.task_id else {
+            return Ok(None);
+        };
+        let from: [u8; 16] = response.from.as_slice().try_into().map_err(|_| {
+            TransportError::InvalidResponse(format!("from: got {} bytes", response.from.len()))
+        })?;
+        Ok(Some(WorkSteal {
+            task_id,
+            from: NodeId::from_bytes(from),
+            to: thief,
+        }))
+    }
+
+    /// Drop a finished run's idempotency state on the target node
+    ///
+    /// Returns how many idempotency keys were dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the client has no wire
+    /// transport, or the mapped error if the call fails
+    pub async fn finish_run(&self, run_id: RunId) -> Result<u64, TransportError> {
+        let call = proto::FinishRunRequest {
+            run_id: run_id.as_bytes().to_vec(),
+        };
+        let response = self
+            .wire()?
+            .finish_run(grpc::with_deadline(call, self.timeout_ms))
+            .await
+            .map_err(|status| grpc::status_to_error(self.target, self.timeout_ms, &status))?;
+        Ok(response.into_inner().forgotten)
+    }
+
+    /// Get a handle to the gRPC channel
+    fn wire(
+        &self,
+    ) -> Result<proto::cluster_transport_client::ClusterTransportClient<Channel>, TransportError>
+    {
+        self.transport.clone().ok_or_else(|| {
+            TransportError::ConnectionFailed(format!("{}: no wire transport", self.address))
+        })
+    }
+
+    /// Convert a failed call into a core error
+    fn call_failed(&self, status: &tonic::Status) -> CoreError {
+        match grpc::status_to_error(self.target, self.timeout_ms, status) {
+            TransportError::Timeout(_) => CoreError::Timeout {
+                operation: format!("execute on {}", self.target),
+            },
+            TransportError::Cancelled(_) => CoreError::Cancelled,
+            err => CoreError::Internal {
+                message: err.to_string(),
+            },
+        }
+    }
+
     /// Set timeout
     #[must_use]
     pub fn with_timeout(mut self, timeout_ms: u64) -> Self {
@@ -154,6 +606,10 @@ impl RemoteClient {
 pub struct RemoteExecutor {
     /// Node ID
     node_id: NodeId,
+    /// Handshake this node presents to peers
+    handshake: Handshake,
+    /// TLS material for dialed peers, if connections use mutual TLS
+    tls: Option<TlsConfig>,
     /// Connected clients
     clients: Arc<RwLock<HashMap<NodeId, RemoteClient>>>,
 }
@@ -164,10 +620,76 @@ impl RemoteExecutor {
     pub fn new(node_id: NodeId) -> Self {
         Self {
             node_id,
+            handshake: Handshake::local(node_id),
+            tls: None,
             clients: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Dial peers over mutual TLS
+    #[must_use]
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Get the client for `target`, dialing it over gRPC at `address` if
+    /// there is none yet
+    ///
+    /// A new client exchanges handshakes with the peer and is only cached
+    /// once both builds are compatible, so an unreachable or incompatible
+    /// peer is reported here and dialed again next time.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::ConnectionFailed`] if the address or TLS
+    /// configuration is invalid, [`TransportError::IncompatiblePeer`] if
+    /// either side rejects the handshake, or the mapped error if the peer
+    /// cannot be reached
+    pub async fn dial(
+        &self,
+        target: NodeId,
+        address: &str,
+    ) -> Result<RemoteClient, TransportError> {
+        if let Some(client) = self.clients.read().await.get(&target) {
+            return Ok(client.clone());
+        }
+        let client = RemoteClient::grpc(target, address.to_string(), self.tls.as_ref())?;
+        let remote = client.handshake(&self.handshake).await?;
+        self.connect(client.clone(), &remote).await?;
+        tracing::debug!(node_id = %self.node_id, %target, address, "dialed peer");
+        Ok(client)
+    }
+
+    /// Get the handshake this node presents to peers
+    #[must_use]
+    pub fn handshake(&self) -> &Handshake {
+        &self.handshake
+    }
+
+    /// Connect to a peer after verifying its handshake
+    ///
+    /// The client is only added if the peer's build is compatible.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransportError::IncompatiblePeer`] if the handshake is rejected
+    pub async fn connect(&self, client: RemoteClient, remote: &Handshake) -> Result<(), TransportError> {
+        if remote.node_id != client.target() {
+            return Err(TransportError::InvalidResponse(format!(
+                "handshake from {} for connection to {}",
+                remote.node_id,
+                client.target()
+            )));
+        }
+
+        self.handshake.check_compatible(remote)?;
+
+        let mut clients = self.clients.write().await;
+        clients.insert(client.target(), client);
+        Ok(())
+    }
+
     /// Add a client connection
     ///
     /// # Errors
@@ -234,6 +756,46 @@ impl RemoteExecutor {
         Ok(responses)
     }
 
+    /// Stop a cancelled run's jobs on every connected node
+    ///
+    /// Returns the IDs of the stopped jobs, by node in node order. Nodes
+    /// that cannot be reached are skipped.
+    pub async fn cancel_run(&self, run_id: RunId) -> Vec<(NodeId, Vec<String>)> {
+        let clients: BTreeMap<NodeId, RemoteClient> = self
+            .clients
+            .read()
+            .await
+            .iter()
+            .map(|(node_id, client)| (*node_id, client.clone()))
+            .collect();
+        let mut cancelled = Vec::new();
+        for (node_id, client) in clients {
+            match client.cancel_run(run_id).await {
+                Ok(job_ids) => cancelled.push((node_id, job_ids)),
+                Err(err) => tracing::debug!(%node_id, %err, "cancel not delivered"),
+            }
+        }
+        cancelled
+    }
+
+    /// Drop a finished run's idempotency state on every connected node
+    ///
+    /// Nodes that cannot be reached keep theirs.
+    pub async fn finish_run(&self, run_id: RunId) {
+        let clients: Vec<(NodeId, RemoteClient)> = self
+            .clients
+            .read()
+            .await
+            .iter()
+            .map(|(node_id, client)| (*node_id, client.clone()))
+            .collect();
+        for (node_id, client) in clients {
+            if let Err(err) = client.finish_run(run_id).await {
+                tracing::debug!(%node_id, %err, "finish not delivered");
+            }
+        }
+    }
+
     /// Get connected node count
     ///
     /// # Errors
@@ -263,6 +825,27 @@ mod tests {
 
         assert_eq!(request.source, source);
         assert_eq!(request.payload, b"data");
+        assert_eq!(request.idempotency_key, None);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key() {
+        let (run_id, node_id) = (RunId::new(), NodeId::new());
+        let key = IdempotencyKey::new(run_id, node_id, 0);
+        assert_eq!(key.hash(), IdempotencyKey::new(run_id, node_id, 0).hash());
+        assert_ne!(key.hash(), IdempotencyKey::new(run_id, node_id, 1).hash());
+
+        let kind = EventKind::NodeCompleted;
+        let completed = key.event_id(kind);
+        assert_eq!(completed, IdempotencyKey::new(run_id, node_id, 0).event_id(kind));
+        assert_ne!(completed, IdempotencyKey::new(run_id, node_id, 1).event_id(kind));
+        assert_ne!(completed, key.event_id(EventKind::Superseded));
+
+        let request = RemoteRequest::new(NodeId::new(), EventId::new(), Vec::new())
+            .with_idempotency_key(key);
+        let decoded: RemoteRequest =
+            serde_json::from_slice(&serde_json::to_vec(&request).unwrap()).unwrap();
+        assert_eq!(decoded.idempotency_key, Some(key));
     }
 
     #[tokio::test]
@@ -301,6 +884,18 @@ mod tests {
         assert!(response.success);
     }
 
+    #[tokio::test]
+    async fn test_remote_client_grpc() {
+        let target = NodeId::new();
+        let stub = RemoteClient::new(target, "127.0.0.1:8080".to_string());
+        let err = stub.heartbeat(NodeId::new(), 1).await.unwrap_err();
+        assert!(matches!(err, TransportError::ConnectionFailed(_)));
+
+        let client = RemoteClient::grpc(target, "127.0.0.1:8080".to_string(), None).unwrap();
+        assert_eq!(client.target(), target);
+        assert!(RemoteClient::grpc(target, "not a uri".to_string(), None).is_err());
+    }
+
     #[tokio::test]
     async fn test_remote_executor_new() {
         let node_id = NodeId::new();
@@ -364,6 +959,83 @@ mod tests {
         assert!(response.success);
     }
 
+    #[tokio::test]
+    async fn test_handshake_compatible() {
+        let local = Handshake::local(NodeId::new());
+        let remote = Handshake::local(NodeId::new());
+        assert!(local.check_compatible(&remote).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rejects_abi_mismatch() {
+        let executor = RemoteExecutor::new(NodeId::new());
+
+        let target = NodeId::new();
+        let mut remote = Handshake::local(target);
+        remote.abi_version = "9.9.9".to_string();
+
+        let client = RemoteClient::new(target, "addr".to_string());
+        let err = executor.connect(client, &remote).await.unwrap_err();
+        match err {
+            TransportError::IncompatiblePeer { peer, field, remote, .. } => {
+                assert_eq!(peer, target);
+                assert_eq!(field, "abi_version");
+                assert_eq!(remote, "9.9.9");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        assert_eq!(executor.connection_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rejects_abi_signature_drift() {
+        use cathedral_wasm::AbiType;
+
+        let mut abi = DeterministicAbi::new();
+        abi.functions.get_mut("fs_read").unwrap().returns = AbiType::String;
+
+        let local = Handshake::local(NodeId::new());
+        let remote = Handshake::for_abi(NodeId::new(), &abi);
+        assert_eq!(local.abi_version, remote.abi_version);
+
+        let err = local.check_compatible(&remote).unwrap_err();
+        match err {
+            TransportError::IncompatiblePeer { field, local: l, remote: r, .. } => {
+                assert_eq!(field, "abi_hash");
+                assert_ne!(l, r);
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rejects_feature_and_version_mismatch() {
+        let local = Handshake::local(NodeId::new());
+
+        let mut remote = Handshake::local(NodeId::new());
+        remote.features.insert("teleport".to_string());
+        let err = local.check_compatible(&remote).unwrap_err();
+        assert!(err.to_string().contains("features"));
+
+        let mut remote = Handshake::local(NodeId::new());
+        remote.crate_version = "7.0.0".to_string();
+        let err = local.check_compatible(&remote).unwrap_err();
+        assert!(err.to_string().contains("crate_version"));
+    }
+
+    #[tokio::test]
+    async fn test_connect_compatible_peer() {
+        let executor = RemoteExecutor::new(NodeId::new());
+        let target = NodeId::new();
+        let client = RemoteClient::new(target, "addr".to_string());
+
+        executor
+            .connect(client, &Handshake::local(target))
+            .await
+            .unwrap();
+        assert_eq!(executor.connection_count().await, 1);
+    }
+
     #[tokio::test]
     async fn test_transport_error_display() {
         let err = TransportError::ConnectionFailed("test".to_string());
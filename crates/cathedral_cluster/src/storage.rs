@@ -0,0 +1,380 @@
+//! Durable consensus state.
+//!
+//! Raft requires the current term, the vote, and the log to survive a
+//! restart: a node that forgets its vote can vote twice in one term, and a
+//! node that forgets its log can drop committed entries. [`Consensus`]
+//! writes through a [`ConsensusStorage`] before answering any RPC.
+//!
+//! [`Consensus`]: crate::consensus::Consensus
+
+use crate::compaction::ConsensusSnapshot;
+use crate::consensus::ConsensusEntry;
+use cathedral_core::{CoreError, CoreResult, NodeId};
+use cathedral_storage::store::FsContentStore;
+use cathedral_storage::ContentAddress;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Name of the manifest file in a storage directory
+const MANIFEST_FILE: &str = "consensus.json";
+
+/// Term and vote that must be durable before replying to an RPC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HardState {
+    /// Latest term this node has seen
+    pub current_term: u64,
+    /// Candidate voted for in the current term
+    pub voted_for: Option<NodeId>,
+}
+
+/// Everything recovered from storage on startup
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PersistentState {
+    /// Term and vote
+    pub hard_state: HardState,
+    /// Latest snapshot, if the log was compacted
+    pub snapshot: Option<ConsensusSnapshot>,
+    /// Index of the first retained log entry
+    pub log_start: u64,
+    /// Term of the entry just before `log_start`, 0 if there is none
+    pub log_start_term: u64,
+    /// Retained log entries in index order, starting at `log_start`
+    pub log: Vec<ConsensusEntry>,
+}
+
+/// Durable backing for consensus state
+///
+/// Each save must be durable when it returns.
+pub trait ConsensusStorage: Send + Sync {
+    /// Load the persisted state, empty if nothing was saved
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the stored state cannot be read or is corrupt
+    fn load(&self) -> CoreResult<PersistentState>;
+
+    /// Persist the term and vote
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the write fails
+    fn save_hard_state(&self, state: &HardState) -> CoreResult<()>;
+
+    /// Replace the log from index `from` onward with `entries`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the write fails
+    fn save_log(&self, from: u64, entries: &[ConsensusEntry]) -> CoreResult<()>;
+
+    /// Persist a snapshot and drop log entries before `log_start`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the write fails
+    fn save_snapshot(
+        &self,
+        snapshot: &ConsensusSnapshot,
+        log_start: u64,
+        log_start_term: u64,
+    ) -> CoreResult<()>;
+}
+
+/// In-memory storage, for single-process clusters and tests
+///
+/// Survives dropping a [`Consensus`] when shared through an `Arc`, but not
+/// a process restart.
+///
+/// [`Consensus`]: crate::consensus::Consensus
+#[derive(Debug, Default)]
+pub struct MemoryConsensusStorage {
+    /// Saved state
+    state: Mutex<PersistentState>,
+}
+
+impl MemoryConsensusStorage {
+    /// Create empty storage
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ConsensusStorage for MemoryConsensusStorage {
+    fn load(&self) -> CoreResult<PersistentState> {
+        Ok(self.state.lock()?.clone())
+    }
+
+    fn save_hard_state(&self, state: &HardState) -> CoreResult<()> {
+        self.state.lock()?.hard_state = *state;
+        Ok(())
+    }
+
+    fn save_log(&self, from: u64, entries: &[ConsensusEntry]) -> CoreResult<()> {
+        let mut state = self.state.lock()?;
+        state.log.retain(|entry| entry.index < from);
+        state.log.extend_from_slice(entries);
+        Ok(())
+    }
+
+    fn save_snapshot(
+        &self,
+        snapshot: &ConsensusSnapshot,
+        log_start: u64,
+        log_start_term: u64,
+    ) -> CoreResult<()> {
+        let mut state = self.state.lock()?;
+        state.snapshot = Some(snapshot.clone());
+        state.log_start = log_start;
+        state.log_start_term = log_start_term;
+        state.log.retain(|entry| entry.index >= log_start);
+        Ok(())
+    }
+}
+
+/// Log entry as recorded in the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct EntryRecord {
+    /// Entry index
+    index: u64,
+    /// Entry term
+    term: u64,
+    /// Address of the entry data in the blob store
+    address: ContentAddress,
+}
+
+/// Manifest listing the hard state, snapshot, and log
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Manifest {
+    /// Term and vote
+    hard_state: HardState,
+    /// Address of the encoded snapshot, if the log was compacted
+    #[serde(default)]
+    snapshot: Option<ContentAddress>,
+    /// Index of the first retained log entry
+    #[serde(default)]
+    log_start: u64,
+    /// Term of the entry just before `log_start`
+    #[serde(default)]
+    log_start_term: u64,
+    /// Retained log entries in index order
+    entries: Vec<EntryRecord>,
+}
+
+/// File-backed storage
+///
+/// Entry data and snapshots live in a content-addressed [`FsContentStore`],
+/// so they are verified against their hash when read back. A JSON manifest
+/// lists the hard state, the snapshot, and the log by address, and is
+/// replaced atomically by writing a temporary file, syncing it, and
+/// renaming it into place.
+pub struct FileConsensusStorage {
+    /// Storage directory
+    dir: PathBuf,
+    /// Blob store holding entry data
+    blobs: FsContentStore,
+    /// Manifest as last written
+    manifest: Mutex<Manifest>,
+}
+
+impl FileConsensusStorage {
+    /// Open storage in `dir`, creating it if missing
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory cannot be created or an existing
+    /// manifest cannot be read
+    pub fn open(dir: impl AsRef<Path>) -> CoreResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let blobs = FsContentStore::new(dir.join("blobs").to_string_lossy().into_owned())?;
+
+        let path = dir.join(MANIFEST_FILE);
+        let manifest = if path.exists() {
+            let data = std::fs::read(&path)?;
+            serde_json::from_slice(&data).map_err(|e| CoreError::ParseError {
+                message: format!("Invalid consensus manifest: {}", e),
+            })?
+        } else {
+            Manifest::default()
+        };
+
+        Ok(Self {
+            dir,
+            blobs,
+            manifest: Mutex::new(manifest),
+        })
+    }
+
+    /// Write data to the blob store and flush it
+    fn write_blob(&self, data: Vec<u8>) -> CoreResult<ContentAddress> {
+        let address = self.blobs.write(data)?;
+        self.blobs.sync(&address)?;
+        Ok(address)
+    }
+
+    /// Atomically replace the manifest on disk
+    fn write_manifest(&self, manifest: &Manifest) -> CoreResult<()> {
+        let data = serde_json::to_vec(manifest).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to encode consensus manifest: {}", e),
+        })?;
+        let path = self.dir.join(MANIFEST_FILE);
+        let tmp = self.dir.join(format!("{}.tmp", MANIFEST_FILE));
+        std::fs::write(&tmp, data)
+            .and_then(|()| File::open(&tmp)?.sync_all())
+            .and_then(|()| std::fs::rename(&tmp, &path))
+            .and_then(|()| File::open(&self.dir)?.sync_all())
+            .map_err(CoreError::from)
+    }
+}
+
+impl ConsensusStorage for FileConsensusStorage {
+    fn load(&self) -> CoreResult<PersistentState> {
+        let manifest = self.manifest.lock()?.clone();
+        let log = manifest
+            .entries
+            .iter()
+            .map(|record| {
+                let blob = self.blobs.read(&record.address)?;
+                Ok(ConsensusEntry::new(record.index, record.term, blob.as_bytes().to_vec()))
+            })
+            .collect::<CoreResult<_>>()?;
+        let snapshot = manifest
+            .snapshot
+            .map(|address| {
+                let blob = self.blobs.read(&address)?;
+                serde_json::from_slice(blob.as_bytes()).map_err(|e| CoreError::ParseError {
+                    message: format!("Invalid consensus snapshot: {}", e),
+                })
+            })
+            .transpose()?;
+
+        Ok(PersistentState {
+            hard_state: manifest.hard_state,
+            snapshot,
+            log_start: manifest.log_start,
+            log_start_term: manifest.log_start_term,
+            log,
+        })
+    }
+
+    fn save_hard_state(&self, state: &HardState) -> CoreResult<()> {
+        let mut manifest = self.manifest.lock()?;
+        let mut next = manifest.clone();
+        next.hard_state = *state;
+        self.write_manifest(&next)?;
+        *manifest = next;
+        Ok(())
+    }
+
+    fn save_log(&self, from: u64, entries: &[ConsensusEntry]) -> CoreResult<()> {
+        let mut manifest = self.manifest.lock()?;
+        let mut next = manifest.clone();
+        next.entries.retain(|record| record.index < from);
+        for entry in entries {
+            let address = self.write_blob(entry.data.clone())?;
+            next.entries.push(EntryRecord {
+                index: entry.index,
+                term: entry.term,
+                address,
+            });
+        }
+        self.write_manifest(&next)?;
+        *manifest = next;
+        Ok(())
+    }
+
+    fn save_snapshot(
+        &self,
+        snapshot: &ConsensusSnapshot,
+        log_start: u64,
+        log_start_term: u64,
+    ) -> CoreResult<()> {
+        let data = serde_json::to_vec(snapshot).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to encode consensus snapshot: {}", e),
+        })?;
+        let address = self.write_blob(data)?;
+
+        let mut manifest = self.manifest.lock()?;
+        let mut next = manifest.clone();
+        next.snapshot = Some(address);
+        next.log_start = log_start;
+        next.log_start_term = log_start_term;
+        next.entries.retain(|record| record.index >= log_start);
+        self.write_manifest(&next)?;
+        *manifest = next;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_storage_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let voter = NodeId::new();
+        let entries: Vec<ConsensusEntry> = (0..3)
+            .map(|i| ConsensusEntry::new(i, 1, format!("entry {}", i).into_bytes()))
+            .collect();
+
+        {
+            let storage = FileConsensusStorage::open(dir.path()).unwrap();
+            assert_eq!(storage.load().unwrap(), PersistentState::default());
+            storage
+                .save_hard_state(&HardState {
+                    current_term: 2,
+                    voted_for: Some(voter),
+                })
+                .unwrap();
+            storage.save_log(0, &entries).unwrap();
+            storage.save_log(2, &[ConsensusEntry::new(2, 2, b"replaced".to_vec())]).unwrap();
+        }
+
+        let state = FileConsensusStorage::open(dir.path()).unwrap().load().unwrap();
+        assert_eq!(state.hard_state.current_term, 2);
+        assert_eq!(state.hard_state.voted_for, Some(voter));
+        assert_eq!(state.log[..2], entries[..2]);
+        assert_eq!(state.log[2], ConsensusEntry::new(2, 2, b"replaced".to_vec()));
+    }
+
+    #[test]
+    fn test_file_storage_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let entries: Vec<ConsensusEntry> = (0..4)
+            .map(|i| ConsensusEntry::new(i, 1, format!("entry {}", i).into_bytes()))
+            .collect();
+        let snapshot = ConsensusSnapshot::from_state(
+            "s1".to_string(),
+            3,
+            1,
+            [("key".to_string(), b"value".to_vec())].into(),
+        );
+
+        {
+            let storage = FileConsensusStorage::open(dir.path()).unwrap();
+            storage.save_log(0, &entries).unwrap();
+            storage.save_snapshot(&snapshot, 2, 1).unwrap();
+        }
+
+        let state = FileConsensusStorage::open(dir.path()).unwrap().load().unwrap();
+        assert_eq!(state.snapshot, Some(snapshot));
+        assert_eq!((state.log_start, state.log_start_term), (2, 1));
+        assert_eq!(state.log, entries[2..]);
+    }
+
+    #[test]
+    fn test_file_storage_detects_corrupt_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FileConsensusStorage::open(dir.path()).unwrap();
+        storage.save_log(0, &[ConsensusEntry::new(0, 1, b"data".to_vec())]).unwrap();
+
+        let address = ContentAddress::compute(b"data");
+        let blob = dir.path().join("blobs").join(format!("{}.blob", address.hash.to_hex()));
+        std::fs::write(blob, b"tampered").unwrap();
+
+        assert!(FileConsensusStorage::open(dir.path()).unwrap().load().is_err());
+    }
+}
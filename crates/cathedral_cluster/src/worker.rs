@@ -1,13 +1,26 @@
 //! Worker node for cluster execution.
 
-use crate::{membership::Membership, remote::RemoteRequest};
-use cathedral_core::{CoreResult, CoreError, EventId, NodeId};
-use cathedral_runtime::Executor;
+use crate::{
+    coordinator::WorkSteal,
+    membership::Membership,
+    remote::{IdempotencyKey, RemoteClient, RemoteRequest, RemoteResponse, TransportError},
+};
+use cathedral_core::{
+    CancellationToken, Capability, CapabilitySet, CoreResult, CoreError, EventId, Hash,
+    LogicalTime, NodeId, RunId,
+};
+use cathedral_log::Event;
+use cathedral_plan::Node;
+use cathedral_runtime::executor::ExecutionContext;
+use cathedral_runtime::{Executor, ExecutorResult};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// Cancelled runs whose tokens are kept, so their late jobs fail
+const MAX_CANCELLED_RUNS: usize = 1024;
+
 /// Worker configuration
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkerConfig {
@@ -21,7 +34,7 @@ pub struct WorkerConfig {
     pub execution_timeout_ms: u64,
     /// Heartbeat interval in milliseconds
     pub heartbeat_interval_ms: u64,
-    /// Capabilities
+    /// Capabilities, in [`Capability`] display form (e.g. `WasmExec(fuel:1000,mem:65536)`)
     pub capabilities: Vec<String>,
 }
 
@@ -59,6 +72,18 @@ impl WorkerConfig {
         self.capabilities.push(capability);
         self
     }
+
+    /// Parse the declared capabilities
+    ///
+    /// # Errors
+    ///
+    /// Returns error if any declared capability cannot be parsed
+    pub fn capability_set(&self) -> CoreResult<CapabilitySet> {
+        self.capabilities
+            .iter()
+            .map(|s| s.parse::<Capability>())
+            .collect()
+    }
 }
 
 impl Default for WorkerConfig {
@@ -91,6 +116,15 @@ pub enum WorkerError {
     ShuttingDown,
 }
 
+impl From<WorkerError> for CoreError {
+    fn from(err: WorkerError) -> Self {
+        CoreError::Validation {
+            field: "worker".to_string(),
+            reason: err.to_string(),
+        }
+    }
+}
+
 /// Worker state
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WorkerState {
@@ -117,6 +151,8 @@ pub struct Job {
     pub status: JobStatus,
     /// Start time
     pub started_at: u64,
+    /// Worker logical tick at which the job was accepted
+    pub started_tick: u64,
 }
 
 impl Job {
@@ -132,6 +168,7 @@ impl Job {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_millis() as u64,
+            started_tick: 0,
         }
     }
 
@@ -153,6 +190,231 @@ pub enum JobStatus {
     Completed,
     /// Job failed
     Failed,
+    /// Job's run was cancelled
+    Cancelled,
+}
+
+/// Work carried in the payload of a [`RemoteRequest`]
+///
+/// Holds everything a worker needs to execute one DAG node: the node
+/// itself, the outputs of its dependencies, the named bindings its tool
+/// input may refer to, and the capabilities the run grants it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobPayload {
+    /// Run the node belongs to
+    pub run_id: RunId,
+    /// Node to execute
+    pub node: Node,
+    /// Logical time at which the node starts
+    pub logical_time: LogicalTime,
+    /// Outputs of the node's dependencies
+    pub inputs: BTreeMap<NodeId, Vec<u8>>,
+    /// Named values available to tool input bindings
+    pub bindings: BTreeMap<String, Vec<u8>>,
+    /// Capabilities granted to the node
+    pub capabilities: CapabilitySet,
+}
+
+impl JobPayload {
+    /// Create a payload for a node with the given capability grant
+    #[must_use]
+    pub fn new(run_id: RunId, node: Node, capabilities: CapabilitySet) -> Self {
+        Self {
+            run_id,
+            node,
+            logical_time: LogicalTime::zero(),
+            inputs: BTreeMap::new(),
+            bindings: BTreeMap::new(),
+            capabilities,
+        }
+    }
+
+    /// Set the logical start time
+    #[must_use]
+    pub fn with_logical_time(mut self, logical_time: LogicalTime) -> Self {
+        self.logical_time = logical_time;
+        self
+    }
+
+    /// Add the output of a dependency
+    #[must_use]
+    pub fn with_input(mut self, from: NodeId, data: Vec<u8>) -> Self {
+        self.inputs.insert(from, data);
+        self
+    }
+
+    /// Bind a named value for the tool input binding
+    #[must_use]
+    pub fn with_binding(mut self, name: impl Into<String>, data: Vec<u8>) -> Self {
+        self.bindings.insert(name.into(), data);
+        self
+    }
+
+    /// Encode for a request payload
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn encode(&self) -> CoreResult<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to encode job payload: {}", e),
+        })
+    }
+
+    /// Decode from a request payload
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the payload is not a valid job
+    pub fn decode(data: &[u8]) -> CoreResult<Self> {
+        serde_json::from_slice(data).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to decode job payload: {}", e),
+        })
+    }
+
+    /// Build the executor context for this job
+    fn context(&self) -> ExecutionContext {
+        let mut ctx = ExecutionContext::new(
+            self.run_id,
+            self.node.id,
+            self.logical_time,
+            self.capabilities.clone(),
+        );
+        for (from, data) in &self.inputs {
+            ctx.add_input(*from, data.clone());
+        }
+        for (name, data) in &self.bindings {
+            ctx.bind(name.clone(), data.clone());
+        }
+        ctx
+    }
+}
+
+/// Outcome of a job, carried in the payload of a [`RemoteResponse`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobOutput {
+    /// Executor result, including the output and its hash
+    pub result: ExecutorResult,
+    /// Events the execution emitted, in order
+    pub logs: Vec<Event>,
+    /// Logical ticks from the job's start to its last emitted event
+    ///
+    /// This measures the log, not fuel: the executor does not meter the
+    /// nodes it runs.
+    pub logical_span: u64,
+}
+
+impl JobOutput {
+    /// Encode for a response payload
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn encode(&self) -> CoreResult<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to encode job output: {}", e),
+        })
+    }
+
+    /// Decode from a response payload
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the payload is not a valid job output
+    pub fn decode(data: &[u8]) -> CoreResult<Self> {
+        serde_json::from_slice(data).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to decode job output: {}", e),
+        })
+    }
+
+    /// Hash of the output, if execution succeeded
+    #[must_use]
+    pub fn output_hash(&self) -> Option<Hash> {
+        match &self.result {
+            ExecutorResult::Success { output_hash, .. } => Some(*output_hash),
+            _ => None,
+        }
+    }
+
+    /// Describe why execution did not succeed, if it did not
+    #[must_use]
+    pub fn error(&self) -> Option<String> {
+        match &self.result {
+            ExecutorResult::Success { .. } => None,
+            ExecutorResult::Failed { error } => Some(error.clone()),
+            ExecutorResult::Skipped { missing } => {
+                Some(format!("Missing capabilities: {:?}", missing))
+            }
+        }
+    }
+}
+
+/// Audit record of a finished job
+///
+/// Records are numbered in the order the worker finished them and hash
+/// chained, so a history can be checked with [`JobRecord::verify_history`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobRecord {
+    /// Position in the worker's history
+    pub sequence: u64,
+    /// Job ID
+    pub job_id: String,
+    /// Event that was executed
+    pub event_id: EventId,
+    /// Final status
+    pub status: JobStatus,
+    /// Hash of the job output, if it produced one
+    pub output_hash: Option<Hash>,
+    /// Logical tick at which the job was accepted
+    pub started_tick: u64,
+    /// Logical tick at which the job finished
+    pub finished_tick: u64,
+    /// Hash of the previous record (empty for the first)
+    pub prev_hash: Hash,
+}
+
+impl JobRecord {
+    /// Duration in logical ticks
+    #[must_use]
+    pub fn duration_ticks(&self) -> u64 {
+        self.finished_tick.saturating_sub(self.started_tick)
+    }
+
+    /// Canonical encoding for the audit log
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn encode_canonical(&self) -> CoreResult<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to encode job record: {}", e),
+        })
+    }
+
+    /// Hash of the canonical encoding, chained to `prev_hash`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn record_hash(&self) -> CoreResult<Hash> {
+        Ok(self.prev_hash.chain(&Hash::compute(&self.encode_canonical()?)))
+    }
+
+    /// Check that a history is contiguous and its hash chain is intact
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn verify_history(history: &[JobRecord]) -> CoreResult<bool> {
+        let mut prev = Hash::empty();
+        for (index, record) in history.iter().enumerate() {
+            if record.sequence != index as u64 || record.prev_hash != prev {
+                return Ok(false);
+            }
+            prev = record.record_hash()?;
+        }
+        Ok(true)
+    }
 }
 
 /// Worker node
@@ -171,6 +433,19 @@ pub struct Worker {
     executor: Arc<Executor>,
     /// Registered flag
     registered: Arc<RwLock<bool>>,
+    /// Logical clock, advanced on every job transition
+    tick: Arc<RwLock<u64>>,
+    /// Finished jobs in completion order
+    history: Arc<RwLock<Vec<JobRecord>>>,
+    /// Cancellation tokens of the runs with active jobs, and of the most
+    /// recently cancelled runs
+    cancellations: Arc<RwLock<HashMap<RunId, CancellationToken>>>,
+    /// Cancelled runs in the order they were cancelled
+    cancelled_runs: Arc<RwLock<VecDeque<RunId>>>,
+    /// Job accepted for each idempotency key
+    idempotent_jobs: Arc<RwLock<HashMap<IdempotencyKey, String>>>,
+    /// Responses of executed jobs that carried an idempotency key
+    responses: Arc<RwLock<HashMap<String, RemoteResponse>>>,
 }
 
 impl Worker {
@@ -189,6 +464,12 @@ impl Worker {
             completed: Arc::new(RwLock::new(HashMap::new())),
             executor,
             registered: Arc::new(RwLock::new(false)),
+            tick: Arc::new(RwLock::new(0)),
+            history: Arc::new(RwLock::new(Vec::new())),
+            cancellations: Arc::new(RwLock::new(HashMap::new())),
+            cancelled_runs: Arc::new(RwLock::new(VecDeque::new())),
+            idempotent_jobs: Arc::new(RwLock::new(HashMap::new())),
+            responses: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -219,8 +500,11 @@ impl Worker {
     ///
     /// Returns error if registration fails
     pub async fn register(&self) -> CoreResult<()> {
+        let capabilities = self.config.capability_set()?;
         let member = crate::membership::Member::new(self.config.node_id, self.config.address.clone())
             .with_state(crate::membership::MemberState::Active)
+            .with_capabilities(capabilities)
+            .with_max_concurrent(self.config.max_concurrent)
             .with_heartbeat(
                 std::time::SystemTime::now()
                     .duration_since(std::time::UNIX_EPOCH)
@@ -230,10 +514,6 @@ impl Worker {
 
         self.membership.add_member(member).await?;
 
-        // Add capabilities to member
-        // In a real implementation, we'd update the member with capabilities
-        let _ = &self.config.capabilities;
-
         *self.registered.write().await = true;
         Ok(())
     }
@@ -273,12 +553,46 @@ impl Worker {
         Ok(())
     }
 
+    /// Ask the coordinator for work queued on an overloaded worker
+    ///
+    /// Only a worker that is accepting jobs and has none active asks.
+    /// Returns the steal if a task was moved to this worker; the
+    /// coordinator then sends it as usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the coordinator cannot be reached or refuses
+    pub async fn steal_work(
+        &self,
+        coordinator: &RemoteClient,
+    ) -> Result<Option<WorkSteal>, TransportError> {
+        if !self.can_accept_jobs().await || self.active_job_count().await > 0 {
+            return Ok(None);
+        }
+        coordinator.steal_work(self.config.node_id).await
+    }
+
     /// Accept a job for execution
     ///
+    /// A request carrying an idempotency key the worker has already seen is
+    /// a duplicate delivery: no new job is created and the ID of the job
+    /// accepted for the key is returned instead.
+    ///
     /// # Errors
     ///
     /// Returns error if job cannot be accepted
     pub async fn accept_job(&self, event_id: EventId, request: RemoteRequest) -> CoreResult<String> {
+        // Held until the job is recorded, so concurrent duplicates cannot
+        // both be accepted
+        let key = request.idempotency_key;
+        let mut idempotent_jobs = self.idempotent_jobs.write().await;
+        if let Some(key) = key
+            && let Some(job_id) = idempotent_jobs.get(&key)
+        {
+            tracing::debug!(%key, job_id, "duplicate request suppressed");
+            return Ok(job_id.clone());
+        }
+
         let state = *self.state.read().await;
         if state == WorkerState::Shutdown || state == WorkerState::Draining {
             return Err(CoreError::Validation {
@@ -295,8 +609,12 @@ impl Worker {
             });
         }
 
-        let job = Job::new(event_id, request);
+        let mut job = Job::new(event_id, request);
+        job.started_tick = self.advance_tick().await;
         let job_id = job.job_id.clone();
+        if let Some(key) = key {
+            idempotent_jobs.insert(key, job_id.clone());
+        }
 
         let mut jobs = self.jobs.write().await;
         jobs.insert(job_id.clone(), job);
@@ -306,11 +624,26 @@ impl Worker {
 
     /// Execute a job
     ///
+    /// The request payload is decoded as a [`JobPayload`] and the node is
+    /// run by the executor with the granted capabilities. A node whose
+    /// required capabilities are not granted is skipped. The response
+    /// payload is the encoded [`JobOutput`]; the job completes only if
+    /// execution succeeded.
+    ///
+    /// Executing a job that carried an idempotency key again returns the
+    /// response it produced the first time, without running the node.
+    ///
     /// # Errors
     ///
-    /// Returns error if execution fails
-    pub async fn execute_job(&self, job_id: String) -> CoreResult<Vec<u8>> {
-        let (event_id, request) = {
+    /// Returns `CoreError::Cancelled` if the job's run was cancelled, or
+    /// error if the job is not active, its payload cannot be decoded, or
+    /// the executor fails
+    pub async fn execute_job(&self, job_id: String) -> CoreResult<RemoteResponse> {
+        if let Some(response) = self.responses.read().await.get(&job_id) {
+            return Ok(response.clone());
+        }
+
+        let request = {
             let mut jobs = self.jobs.write().await;
             let job = jobs.get_mut(&job_id).ok_or_else(|| CoreError::NotFound {
                 kind: "job".to_string(),
@@ -318,27 +651,228 @@ impl Worker {
             })?;
 
             job.status = JobStatus::Running;
-            (job.event_id.clone(), job.request.clone())
+            job.request.clone()
         };
 
-        // Execute the event
-        // In a real implementation, this would use the executor
-        let _ = (event_id, self.executor.clone());
+        let output = match self.run_payload(&request.payload).await {
+            Ok(output) => output,
+            Err(err) => {
+                let status = if err == CoreError::Cancelled {
+                    JobStatus::Cancelled
+                } else {
+                    JobStatus::Failed
+                };
+                self.finish_job(&job_id, status, None).await;
+                return Err(err);
+            }
+        };
 
-        // Simulate execution
-        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+        let output_hash = output.output_hash();
+        let status = if output_hash.is_some() {
+            JobStatus::Completed
+        } else {
+            JobStatus::Failed
+        };
+        self.finish_job(&job_id, status, output_hash).await;
 
-        // Complete the job
-        let mut jobs = self.jobs.write().await;
-        if let Some(job) = jobs.remove(&job_id) {
-            let mut completed_job = job;
-            completed_job.status = JobStatus::Completed;
+        let response = RemoteResponse {
+            request_id: request.request_id,
+            payload: output.encode()?,
+            success: status == JobStatus::Completed,
+            error: output.error(),
+        };
+        if request.idempotency_key.is_some() {
+            self.responses.write().await.insert(job_id, response.clone());
+        }
+        Ok(response)
+    }
 
-            let mut completed = self.completed.write().await;
-            completed.insert(job_id.clone(), completed_job);
+    /// Decode a job payload and run its node on the executor
+    ///
+    /// The node runs with its run's cancellation token; a job whose run is
+    /// cancelled while it executes fails with `CoreError::Cancelled` and its
+    /// output is discarded.
+    async fn run_payload(&self, payload: &[u8]) -> CoreResult<JobOutput> {
+        let job = JobPayload::decode(payload)?;
+        let executor = Arc::clone(&self.executor);
+        let cancellation = self.cancellation(job.run_id).await;
+
+        tokio::task::spawn_blocking(move || {
+            let ctx = job.context().with_cancellation(cancellation.clone());
+            let (logs, result) = match executor.check_capabilities(&ctx, &job.node.capabilities) {
+                Ok(()) => executor.execute_node(&ctx, &job.node.kind)?,
+                Err(_) => {
+                    let missing = job
+                        .node
+                        .capabilities
+                        .iter()
+                        .filter(|capability| !ctx.has_capability(capability))
+                        .cloned()
+                        .collect();
+                    (Vec::new(), ExecutorResult::Skipped { missing })
+                }
+            };
+            let logical_span = logs
+                .iter()
+                .map(|event| event.logical_time.as_u64())
+                .max()
+                .map_or(0, |end| end.saturating_sub(job.logical_time.as_u64()));
+            if cancellation.is_cancelled() {
+                return Err(CoreError::Cancelled);
+            }
+
+            Ok(JobOutput {
+                result,
+                logs,
+                logical_span,
+            })
+        })
+        .await
+        .map_err(|e| WorkerError::ExecutionFailed(e.to_string()))?
+    }
+
+    /// Get the cancellation token of a run, creating it on first use
+    async fn cancellation(&self, run_id: RunId) -> CancellationToken {
+        self.cancellations.write().await.entry(run_id).or_default().clone()
+    }
+
+    /// Cancel a run's jobs
+    ///
+    /// Trips the run's cancellation token, so nodes executing for it stop
+    /// and jobs accepted for it later fail, then marks its active jobs
+    /// cancelled in the order they were accepted. Returns the IDs of the
+    /// cancelled jobs.
+    ///
+    /// Tokens of the last [`MAX_CANCELLED_RUNS`] cancelled runs are kept;
+    /// older ones are dropped.
+    pub async fn cancel_run(&self, run_id: RunId) -> Vec<String> {
+        let token = self.cancellation(run_id).await;
+        if !token.is_cancelled() {
+            token.cancel();
+            let mut cancelled_runs = self.cancelled_runs.write().await;
+            cancelled_runs.push_back(run_id);
+            if cancelled_runs.len() > MAX_CANCELLED_RUNS
+                && let Some(oldest) = cancelled_runs.pop_front()
+            {
+                self.cancellations.write().await.remove(&oldest);
+            }
         }
 
-        Ok(request.payload)
+        let mut cancelled: Vec<(u64, String)> = self
+            .jobs
+            .read()
+            .await
+            .values()
+            .filter(|job| {
+                JobPayload::decode(&job.request.payload).is_ok_and(|p| p.run_id == run_id)
+            })
+            .map(|job| (job.started_tick, job.job_id.clone()))
+            .collect();
+        cancelled.sort();
+
+        let mut job_ids = Vec::with_capacity(cancelled.len());
+        for (_, job_id) in cancelled {
+            if self.finish_job(&job_id, JobStatus::Cancelled, None).await {
+                job_ids.push(job_id);
+            }
+        }
+        job_ids
+    }
+
+    /// Drop the idempotency state of a finished run
+    ///
+    /// Forgets the run's idempotency keys and the responses stored for
+    /// them, except those of jobs still active. Returns how many keys were
+    /// dropped. A duplicate request arriving later executes again, and the
+    /// coordinator's replicated log discards its result.
+    pub async fn finish_run(&self, run_id: RunId) -> usize {
+        // Locked in the order accept_job uses
+        let mut idempotent_jobs = self.idempotent_jobs.write().await;
+        let jobs = self.jobs.read().await;
+        let mut responses = self.responses.write().await;
+        let before = idempotent_jobs.len();
+        idempotent_jobs.retain(|key, job_id| {
+            let keep = key.run_id != run_id || jobs.contains_key(job_id);
+            if !keep {
+                responses.remove(job_id);
+            }
+            keep
+        });
+        before - idempotent_jobs.len()
+    }
+
+    /// Mark an active job as failed
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the job is not active
+    pub async fn fail_job(&self, job_id: &str) -> CoreResult<()> {
+        if self.finish_job(job_id, JobStatus::Failed, None).await {
+            Ok(())
+        } else {
+            Err(CoreError::NotFound {
+                kind: "job".to_string(),
+                id: job_id.to_string(),
+            })
+        }
+    }
+
+    /// Move an active job to the completed set and append its audit record
+    ///
+    /// The token of a run that was not cancelled is dropped once its last
+    /// active job finishes.
+    async fn finish_job(&self, job_id: &str, status: JobStatus, output_hash: Option<Hash>) -> bool {
+        let (mut job, idle_run) = {
+            let mut jobs = self.jobs.write().await;
+            let Some(job) = jobs.remove(job_id) else {
+                return false;
+            };
+            let run_id = JobPayload::decode(&job.request.payload).ok().map(|p| p.run_id);
+            let active = jobs.values().any(|other| {
+                JobPayload::decode(&other.request.payload).ok().map(|p| p.run_id) == run_id
+            });
+            (job, run_id.filter(|_| !active))
+        };
+        if let Some(run_id) = idle_run {
+            let mut cancellations = self.cancellations.write().await;
+            if cancellations.get(&run_id).is_some_and(|token| !token.is_cancelled()) {
+                cancellations.remove(&run_id);
+            }
+        }
+        job.status = status;
+        let finished_tick = self.advance_tick().await;
+
+        let mut history = self.history.write().await;
+        let prev_hash = match history.last() {
+            Some(last) => last.record_hash().unwrap_or_else(|_| Hash::empty()),
+            None => Hash::empty(),
+        };
+        let sequence = history.len() as u64;
+        history.push(JobRecord {
+            sequence,
+            job_id: job.job_id.clone(),
+            event_id: job.event_id,
+            status,
+            output_hash,
+            started_tick: job.started_tick,
+            finished_tick,
+            prev_hash,
+        });
+
+        self.completed.write().await.insert(job.job_id.clone(), job);
+        true
+    }
+
+    /// Advance the logical clock and return the new tick
+    async fn advance_tick(&self) -> u64 {
+        let mut tick = self.tick.write().await;
+        *tick += 1;
+        *tick
+    }
+
+    /// Get the ordered audit history of every finished job
+    pub async fn job_history(&self) -> Vec<JobRecord> {
+        self.history.read().await.clone()
     }
 
     /// Get job by ID
@@ -453,6 +987,31 @@ impl Default for Worker {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use cathedral_plan::NodeKind;
+    use cathedral_plan::dag::ResourceRequirements;
+
+    fn make_node(kind: NodeKind, capabilities: Vec<Capability>) -> Node {
+        Node {
+            id: NodeId::new(),
+            kind,
+            dependencies: Default::default(),
+            capabilities,
+            resources: ResourceRequirements::new(),
+        }
+    }
+
+    fn map_node(capabilities: Vec<Capability>) -> Node {
+        let kind = NodeKind::Map {
+            function: "identity".to_string(),
+        };
+        make_node(kind, capabilities)
+    }
+
+    fn job_request(payload: &JobPayload) -> (EventId, RemoteRequest) {
+        let event_id = EventId::new();
+        let request = RemoteRequest::new(NodeId::new(), event_id, payload.encode().unwrap());
+        (event_id, request)
+    }
 
     #[tokio::test]
     async fn test_worker_config_new() {
@@ -518,6 +1077,37 @@ mod tests {
         assert!(worker.is_registered().await);
     }
 
+    #[tokio::test]
+    async fn test_worker_register_capabilities() {
+        let node_id = NodeId::new();
+        let config = WorkerConfig::new(node_id, "addr".to_string())
+            .with_capability("WasmExec(fuel:1000000,mem:67108864)".to_string())
+            .with_capability("ClockRead".to_string());
+        let membership = Arc::new(Membership::new(node_id));
+        let executor = Arc::new(Executor::default());
+
+        let worker = Worker::new(config, membership.clone(), executor);
+        worker.register().await.unwrap();
+
+        let member = membership.get_member(node_id).await.unwrap();
+        assert_eq!(member.capabilities.len(), 2);
+        assert!(member.has_capability(&Capability::ClockRead));
+    }
+
+    #[tokio::test]
+    async fn test_worker_register_invalid_capability() {
+        let node_id = NodeId::new();
+        let config = WorkerConfig::new(node_id, "addr".to_string())
+            .with_capability("wasm".to_string());
+        let membership = Arc::new(Membership::new(node_id));
+        let executor = Arc::new(Executor::default());
+
+        let worker = Worker::new(config, membership.clone(), executor);
+        assert!(worker.register().await.is_err());
+        assert!(!worker.is_registered().await);
+        assert_eq!(membership.member_count().await, 0);
+    }
+
     #[tokio::test]
     async fn test_worker_unregister() {
         let node_id = NodeId::new();
@@ -588,15 +1178,198 @@ mod tests {
 
         let worker = Worker::new(config, membership, executor);
 
+        let mut capabilities = CapabilitySet::new();
+        capabilities.allow(Capability::ClockRead);
+        let node = map_node(vec![Capability::ClockRead]);
+        let payload = JobPayload::new(RunId::new(), node.clone(), capabilities)
+            .with_logical_time(LogicalTime::from_raw(5))
+            .with_input(NodeId::new(), b"data".to_vec());
+        let (event_id, request) = job_request(&payload);
+
+        let job_id = worker.accept_job(event_id, request.clone()).await.unwrap();
+        let response = worker.execute_job(job_id.clone()).await.unwrap();
+
+        assert!(response.success);
+        assert_eq!(response.request_id, request.request_id);
+        let output = JobOutput::decode(&response.payload).unwrap();
+        assert!(matches!(output.result, ExecutorResult::Success { .. }));
+        assert_eq!(output.logs.len(), 2);
+        assert!(output.logs.iter().all(|event| event.node_id == node.id));
+        // Started at tick 5, last event at tick 6
+        assert_eq!(output.logical_span, 1);
+        assert_eq!(worker.active_job_count().await, 0);
+        assert_eq!(worker.completed_job_count().await, 1);
+        assert_eq!(worker.job_history().await[0].output_hash, output.output_hash());
+    }
+
+    #[tokio::test]
+    async fn test_worker_execute_job_missing_capability() {
+        let worker = Worker::default();
+
+        let node = map_node(vec![Capability::ClockRead, Capability::IdGen]);
+        let mut capabilities = CapabilitySet::new();
+        capabilities.allow(Capability::ClockRead);
+        let payload = JobPayload::new(RunId::new(), node, capabilities);
+        let (event_id, request) = job_request(&payload);
+
+        let job_id = worker.accept_job(event_id, request).await.unwrap();
+        let response = worker.execute_job(job_id).await.unwrap();
+
+        assert!(!response.success);
+        assert!(response.error.is_some());
+        let output = JobOutput::decode(&response.payload).unwrap();
+        assert_eq!(output.result, ExecutorResult::Skipped {
+            missing: vec![Capability::IdGen],
+        });
+        assert!(output.logs.is_empty());
+        assert_eq!(worker.job_history().await[0].status, JobStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_worker_execute_job_invalid_payload() {
+        let worker = Worker::default();
+
         let event_id = EventId::new();
         let request = RemoteRequest::new(NodeId::new(), event_id, b"data".to_vec());
+        let job_id = worker.accept_job(event_id, request).await.unwrap();
+        assert!(worker.execute_job(job_id.clone()).await.is_err());
 
-        let job_id = worker.accept_job(event_id.clone(), request.clone()).await.unwrap();
-        let result = worker.execute_job(job_id.clone()).await.unwrap();
+        // Tool nodes need a registry on the worker's executor
+        let kind = NodeKind::Tool {
+            name: "echo".to_string(),
+            version_req: "^1.0".to_string(),
+            input_binding: None,
+        };
+        let node = make_node(kind, Vec::new());
+        let payload = JobPayload::new(RunId::new(), node, CapabilitySet::new());
+        let (event_id, request) = job_request(&payload);
+        let tool_job = worker.accept_job(event_id, request).await.unwrap();
+        assert!(worker.execute_job(tool_job).await.is_err());
 
-        assert_eq!(result, b"data");
         assert_eq!(worker.active_job_count().await, 0);
-        assert_eq!(worker.completed_job_count().await, 1);
+        let history = worker.job_history().await;
+        assert_eq!(history[0].job_id, job_id);
+        assert!(history.iter().all(|record| record.status == JobStatus::Failed));
+    }
+
+    #[tokio::test]
+    async fn test_worker_job_history() {
+        let worker = Worker::default();
+
+        let first = EventId::new();
+        let payload = JobPayload::new(RunId::new(), map_node(Vec::new()), CapabilitySet::new());
+        let request = RemoteRequest::new(NodeId::new(), first, payload.encode().unwrap());
+        let job_a = worker.accept_job(first, request).await.unwrap();
+        let second = EventId::new();
+        let request = RemoteRequest::new(NodeId::new(), second, b"two".to_vec());
+        let job_b = worker.accept_job(second, request).await.unwrap();
+
+        worker.fail_job(&job_b).await.unwrap();
+        let response = worker.execute_job(job_a.clone()).await.unwrap();
+        assert!(worker.fail_job(&job_a).await.is_err());
+        let output = JobOutput::decode(&response.payload).unwrap();
+
+        let history = worker.job_history().await;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].event_id, second);
+        assert_eq!(history[0].status, JobStatus::Failed);
+        assert_eq!(history[0].output_hash, None);
+        assert_eq!(history[0].duration_ticks(), 1);
+        assert_eq!(history[1].job_id, job_a);
+        assert_eq!(history[1].status, JobStatus::Completed);
+        assert_eq!(history[1].output_hash, output.output_hash());
+        assert_eq!(history[1].output_hash, Some(Hash::empty()));
+        assert_eq!(history[1].duration_ticks(), 3);
+        assert!(JobRecord::verify_history(&history).unwrap());
+
+        let mut tampered = history.clone();
+        tampered[0].status = JobStatus::Completed;
+        assert!(!JobRecord::verify_history(&tampered).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_worker_suppresses_duplicate_requests() {
+        let worker = Worker::default();
+        let payload = JobPayload::new(RunId::new(), map_node(Vec::new()), CapabilitySet::new());
+        let key = IdempotencyKey::new(payload.run_id, payload.node.id, 0);
+        let (event_id, request) = job_request(&payload);
+        let request = request.with_idempotency_key(key);
+
+        let job_id = worker.accept_job(event_id, request.clone()).await.unwrap();
+        assert_eq!(worker.accept_job(event_id, request.clone()).await.unwrap(), job_id);
+        assert_eq!(worker.active_job_count().await, 1);
+
+        // A redelivered request gets the first response; the node runs once
+        let response = worker.execute_job(job_id.clone()).await.unwrap();
+        assert_eq!(worker.accept_job(event_id, request).await.unwrap(), job_id);
+        assert_eq!(worker.execute_job(job_id).await.unwrap(), response);
+        assert_eq!(worker.job_history().await.len(), 1);
+
+        // A retry is a new attempt
+        let (event_id, retry) = job_request(&payload);
+        let key = IdempotencyKey::new(payload.run_id, payload.node.id, 1);
+        let retry_job = worker.accept_job(event_id, retry.with_idempotency_key(key)).await.unwrap();
+        worker.execute_job(retry_job).await.unwrap();
+        assert_eq!(worker.job_history().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_worker_finish_run_forgets_idempotency_state() {
+        let worker = Worker::default();
+        let (run, other) = (RunId::new(), RunId::new());
+        let mut job_ids = Vec::new();
+        for run_id in [run, other] {
+            let payload = JobPayload::new(run_id, map_node(Vec::new()), CapabilitySet::new());
+            let key = IdempotencyKey::new(run_id, payload.node.id, 0);
+            let (event_id, request) = job_request(&payload);
+            let request = request.with_idempotency_key(key);
+            let job_id = worker.accept_job(event_id, request).await.unwrap();
+            worker.execute_job(job_id.clone()).await.unwrap();
+            job_ids.push(job_id);
+        }
+
+        // Only the finished run's keys and responses are dropped
+        assert_eq!(worker.finish_run(run).await, 1);
+        assert_eq!(worker.finish_run(run).await, 0);
+        assert_eq!(worker.idempotent_jobs.read().await.len(), 1);
+        let responses = worker.responses.read().await;
+        assert!(!responses.contains_key(&job_ids[0]));
+        assert!(responses.contains_key(&job_ids[1]));
+    }
+
+    #[tokio::test]
+    async fn test_worker_cancel_run() {
+        let worker = Worker::default();
+        let (run, other) = (RunId::new(), RunId::new());
+        let mut job_ids = Vec::new();
+        for run_id in [run, other, run] {
+            let payload = JobPayload::new(run_id, map_node(Vec::new()), CapabilitySet::new());
+            let (event_id, request) = job_request(&payload);
+            job_ids.push(worker.accept_job(event_id, request).await.unwrap());
+        }
+
+        // Only the cancelled run's jobs stop, in the order they were accepted
+        assert_eq!(worker.cancel_run(run).await, [job_ids[0].clone(), job_ids[2].clone()]);
+        assert_eq!(worker.active_job_count().await, 1);
+        assert!(worker.execute_job(job_ids[1].clone()).await.unwrap().success);
+
+        // Jobs accepted for the run afterwards are cancelled when executed
+        let payload = JobPayload::new(run, map_node(Vec::new()), CapabilitySet::new());
+        let (event_id, request) = job_request(&payload);
+        let late = worker.accept_job(event_id, request).await.unwrap();
+        assert_eq!(worker.execute_job(late).await.unwrap_err(), CoreError::Cancelled);
+
+        let statuses: Vec<_> = worker.job_history().await.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [JobStatus::Cancelled, JobStatus::Cancelled, JobStatus::Completed, JobStatus::Cancelled]
+        );
+        assert!(worker.cancel_run(run).await.is_empty());
+
+        // Only the cancelled run's token outlives its jobs
+        let tokens = worker.cancellations.read().await;
+        assert!(tokens.contains_key(&run));
+        assert!(!tokens.contains_key(&other));
     }
 
     #[tokio::test]
@@ -1,9 +1,15 @@
 //! Capability types for capability-based security.
 
+use crate::error::CoreError;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
+use std::str::FromStr;
 
 /// A capability grants permission for a specific type of operation
+///
+/// The derived ordering is canonical: capabilities sort by kind in
+/// declaration order, then by their fields. Sets, reports, and proof hashes
+/// all rely on it, so new variants must be appended at the end.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Capability {
     /// Read from network with domain allowlist
@@ -38,6 +44,9 @@ pub enum Capability {
 
     /// Read environment variables with allowlist
     EnvRead { vars: Vec<String> },
+
+    /// Generate run-unique IDs derived from the run seed
+    IdGen,
 }
 
 impl Capability {
@@ -61,6 +70,146 @@ impl Capability {
             Self::WasmExec { .. } => "WasmExec",
             Self::ClockRead => "ClockRead",
             Self::EnvRead { .. } => "EnvRead",
+            Self::IdGen => "IdGen",
+        }
+    }
+
+    /// Check if this capability covers `other`
+    ///
+    /// A capability covers another of the same kind when everything `other`
+    /// permits is also permitted by `self`: allowlists must be a subset
+    /// (wildcards cover everything) and resource limits must not be larger.
+    #[must_use]
+    pub fn covers(&self, other: &Capability) -> bool {
+        match (self, other) {
+            (Self::NetRead { allowlist: a }, Self::NetRead { allowlist: b })
+            | (Self::NetWrite { allowlist: a }, Self::NetWrite { allowlist: b }) => {
+                b.iter().all(|domain| matches_domain(a, domain))
+            }
+            (Self::FsRead { prefixes: a }, Self::FsRead { prefixes: b })
+            | (Self::FsWrite { prefixes: a }, Self::FsWrite { prefixes: b }) => b
+                .iter()
+                .all(|path| a.iter().any(|prefix| prefix == "*" || matches_path(prefix, path))),
+            (Self::DbRead { tables: a }, Self::DbRead { tables: b })
+            | (Self::DbWrite { tables: a }, Self::DbWrite { tables: b })
+            | (Self::EnvRead { vars: a }, Self::EnvRead { vars: b }) => {
+                b.iter().all(|item| a.contains(item))
+            }
+            (
+                Self::Exec {
+                    cpu_limit: cpu_a,
+                    mem_limit: mem_a,
+                },
+                Self::Exec {
+                    cpu_limit: cpu_b,
+                    mem_limit: mem_b,
+                },
+            ) => cpu_a == cpu_b && mem_a == mem_b,
+            (
+                Self::WasmExec {
+                    fuel: fuel_a,
+                    memory: mem_a,
+                },
+                Self::WasmExec {
+                    fuel: fuel_b,
+                    memory: mem_b,
+                },
+            ) => fuel_b <= fuel_a && mem_b <= mem_a,
+            (Self::ClockRead, Self::ClockRead) | (Self::IdGen, Self::IdGen) => true,
+            _ => false,
+        }
+    }
+
+    /// Narrow this capability to `restriction` for delegation
+    ///
+    /// Attenuation is monotonic: the result may only permit what `self`
+    /// already permits, so `restriction` must be covered by `self`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PermissionDenied` if `restriction` would amplify `self`
+    pub fn attenuate(&self, restriction: &Capability) -> Result<Capability, CoreError> {
+        if !self.covers(restriction) {
+            return Err(CoreError::PermissionDenied {
+                operation: format!("amplify capability {self} to {restriction}"),
+            });
+        }
+        Ok(restriction.clone())
+    }
+}
+
+impl FromStr for Capability {
+    type Err = CoreError;
+
+    /// Parse a capability from its display form, e.g. `NetRead(a.com,b.com)`,
+    /// `WasmExec(fuel:1000,mem:65536)` or `ClockRead`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| CoreError::InvalidCapability {
+            reason: format!("{}: {}", reason, s),
+        };
+
+        let s = s.trim();
+        let (kind, args) = match s.find('(') {
+            Some(open) => {
+                let inner = s[open + 1..]
+                    .strip_suffix(')')
+                    .ok_or_else(|| invalid("missing closing parenthesis"))?;
+                (&s[..open], Some(inner))
+            }
+            None => (s, None),
+        };
+
+        let list = || -> Vec<String> {
+            args.unwrap_or("")
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        };
+
+        let limits = |first: &str, second: &str| -> Result<(String, String), CoreError> {
+            let inner = args.ok_or_else(|| invalid("missing limits"))?;
+            let mut parts = inner.split(',').map(str::trim);
+            let a = parts
+                .next()
+                .and_then(|p| p.strip_prefix(first))
+                .ok_or_else(|| invalid("missing first limit"))?;
+            let b = parts
+                .next()
+                .and_then(|p| p.strip_prefix(second))
+                .ok_or_else(|| invalid("missing second limit"))?;
+            if parts.next().is_some() {
+                return Err(invalid("too many limits"));
+            }
+            Ok((a.to_string(), b.to_string()))
+        };
+
+        match kind {
+            "NetRead" => Ok(Self::NetRead { allowlist: list() }),
+            "NetWrite" => Ok(Self::NetWrite { allowlist: list() }),
+            "FsRead" => Ok(Self::FsRead { prefixes: list() }),
+            "FsWrite" => Ok(Self::FsWrite { prefixes: list() }),
+            "DbRead" => Ok(Self::DbRead { tables: list() }),
+            "DbWrite" => Ok(Self::DbWrite { tables: list() }),
+            "EnvRead" => Ok(Self::EnvRead { vars: list() }),
+            "Exec" => {
+                let (cpu_limit, mem_limit) = limits("cpu:", "mem:")?;
+                Ok(Self::Exec {
+                    cpu_limit,
+                    mem_limit,
+                })
+            }
+            "WasmExec" => {
+                let (fuel, memory) = limits("fuel:", "mem:")?;
+                Ok(Self::WasmExec {
+                    fuel: fuel.parse().map_err(|_| invalid("invalid fuel"))?,
+                    memory: memory.parse().map_err(|_| invalid("invalid memory"))?,
+                })
+            }
+            "ClockRead" if args.is_none() => Ok(Self::ClockRead),
+            "IdGen" if args.is_none() => Ok(Self::IdGen),
+            _ => Err(invalid("unknown capability")),
         }
     }
 }
@@ -99,6 +248,7 @@ impl std::fmt::Display for Capability {
             Self::EnvRead { vars } => {
                 write!(f, "EnvRead({})", vars.join(","))
             }
+            Self::IdGen => write!(f, "IdGen"),
         }
     }
 }
@@ -235,6 +385,52 @@ impl CapabilitySet {
         })
     }
 
+    /// Check if any granted capability covers the given one
+    ///
+    /// See [`Capability::covers`] for the per-kind rules.
+    #[must_use]
+    pub fn covers(&self, capability: &Capability) -> bool {
+        self.capabilities.iter().any(|cap| cap.covers(capability))
+    }
+
+    /// Check if every capability in `required` is covered by this set
+    #[must_use]
+    pub fn covers_all(&self, required: &CapabilitySet) -> bool {
+        required.iter().all(|cap| self.covers(cap))
+    }
+
+    /// Narrow this set to `other` for a delegation step
+    ///
+    /// Every capability in `other` must be covered by this set, so a chain
+    /// of delegations can only ever lose authority.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PermissionDenied` naming the first capability in `other`
+    /// that this set does not cover
+    pub fn attenuated_by(&self, other: &CapabilitySet) -> Result<CapabilitySet, CoreError> {
+        for capability in other.iter() {
+            if !self.covers(capability) {
+                return Err(CoreError::PermissionDenied {
+                    operation: format!("amplify capability set with {capability}"),
+                });
+            }
+        }
+        Ok(other.clone())
+    }
+
+    /// Capabilities granted in this set but not in `other`
+    ///
+    /// Comparison is exact; a capability covered by, but not equal to, one
+    /// in `other` still appears in the difference.
+    #[must_use]
+    pub fn difference(&self, other: &CapabilitySet) -> CapabilitySet {
+        self.capabilities
+            .difference(&other.capabilities)
+            .cloned()
+            .collect()
+    }
+
     /// Get the number of capabilities
     #[must_use]
     pub fn len(&self) -> usize {
@@ -247,7 +443,11 @@ impl CapabilitySet {
         self.capabilities.is_empty()
     }
 
-    /// Iterate over capabilities
+    /// Iterate over capabilities in canonical order
+    ///
+    /// Capabilities are yielded by kind (`NetRead`, `NetWrite`, `FsRead`,
+    /// `FsWrite`, `DbRead`, `DbWrite`, `Exec`, `WasmExec`, `ClockRead`,
+    /// `EnvRead`, `IdGen`) and then by their fields, independent of grant order.
     pub fn iter(&self) -> impl Iterator<Item = &Capability> {
         self.capabilities.iter()
     }
@@ -259,6 +459,14 @@ impl Default for CapabilitySet {
     }
 }
 
+impl FromIterator<Capability> for CapabilitySet {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        Self {
+            capabilities: iter.into_iter().collect(),
+        }
+    }
+}
+
 /// Check if a domain matches an allowlist pattern
 fn matches_domain(allowlist: &[String], domain: &str) -> bool {
     allowlist.iter().any(|pattern| {
@@ -276,32 +484,34 @@ fn matches_domain(allowlist: &[String], domain: &str) -> bool {
 }
 
 /// Check if a path matches a prefix
+///
+/// Both sides are normalized by components first, so `./a/../b` is compared
+/// as `b` and can never match the prefix `./a`.
 fn matches_path(prefix: &str, path: &str) -> bool {
-    let normalized_prefix = if let Some(stripped) = prefix.strip_suffix('/') {
-        stripped
-    } else {
-        prefix
-    };
-
-    let normalized_path = if let Some(stripped) = path.strip_suffix('/') {
-        stripped
-    } else {
-        path
-    };
-
-    if normalized_prefix == "." || normalized_prefix == "./" {
-        return true;
-    }
-
-    if normalized_path == normalized_prefix {
-        return true;
+    match (normalize_path(prefix), normalize_path(path)) {
+        (Some((prefix_root, prefix)), Some((path_root, path))) => {
+            prefix_root == path_root && path.starts_with(&prefix)
+        }
+        _ => false,
     }
+}
 
-    if normalized_path.starts_with(&format!("{normalized_prefix}/")) {
-        return true;
+/// Resolve `.` and `..` components lexically
+///
+/// Returns whether the path is absolute and its remaining components, or
+/// `None` if `..` climbs above the start of the path.
+fn normalize_path(path: &str) -> Option<(bool, Vec<&str>)> {
+    let mut components = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            name => components.push(name),
+        }
     }
-
-    false
+    Some((path.starts_with('/'), components))
 }
 
 #[cfg(test)]
@@ -436,4 +646,151 @@ mod tests {
 
         assert_eq!(set.len(), 3);
     }
+
+    #[test]
+    fn test_capability_set_canonical_order() {
+        let caps = vec![
+            Capability::IdGen,
+            Capability::EnvRead { vars: vec!["HOME".to_string()] },
+            Capability::ClockRead,
+            Capability::WasmExec { fuel: 1, memory: 1 },
+            Capability::Exec {
+                cpu_limit: "1".to_string(),
+                mem_limit: "1M".to_string(),
+            },
+            Capability::DbWrite { tables: vec!["t".to_string()] },
+            Capability::DbRead { tables: vec!["t".to_string()] },
+            Capability::FsWrite { prefixes: vec!["./b".to_string()] },
+            Capability::FsWrite { prefixes: vec!["./a".to_string()] },
+            Capability::FsRead { prefixes: vec!["./a".to_string()] },
+            Capability::NetWrite { allowlist: vec!["*".to_string()] },
+            Capability::NetRead { allowlist: vec!["*".to_string()] },
+        ];
+        let set: CapabilitySet = caps.iter().cloned().collect();
+        let reversed: CapabilitySet = caps.into_iter().rev().collect();
+
+        let order: Vec<String> = set.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            order,
+            [
+                "NetRead(*)",
+                "NetWrite(*)",
+                "FsRead(./a)",
+                "FsWrite(./a)",
+                "FsWrite(./b)",
+                "DbRead(t)",
+                "DbWrite(t)",
+                "Exec(cpu:1,mem:1M)",
+                "WasmExec(fuel:1,mem:1)",
+                "ClockRead",
+                "EnvRead(HOME)",
+                "IdGen",
+            ]
+        );
+        assert!(set.iter().eq(reversed.iter()));
+    }
+
+    #[test]
+    fn test_capability_parse_roundtrip() {
+        let caps = vec![
+            Capability::NetRead {
+                allowlist: vec!["*.example.com".to_string(), "api.service.com".to_string()],
+            },
+            Capability::FsWrite {
+                prefixes: vec!["./outputs".to_string()],
+            },
+            Capability::Exec {
+                cpu_limit: "2".to_string(),
+                mem_limit: "512M".to_string(),
+            },
+            Capability::WasmExec {
+                fuel: 1_000_000,
+                memory: 65_536,
+            },
+            Capability::ClockRead,
+            Capability::IdGen,
+        ];
+
+        for cap in caps {
+            let parsed: Capability = cap.to_string().parse().unwrap();
+            assert_eq!(parsed, cap);
+        }
+
+        assert!("Teleport".parse::<Capability>().is_err());
+        assert!("WasmExec(fuel:lots,mem:1)".parse::<Capability>().is_err());
+    }
+
+    #[test]
+    fn test_capability_covers() {
+        let wasm = Capability::WasmExec {
+            fuel: 1_000,
+            memory: 1_024,
+        };
+        assert!(wasm.covers(&Capability::WasmExec { fuel: 500, memory: 1_024 }));
+        assert!(!wasm.covers(&Capability::WasmExec { fuel: 2_000, memory: 1_024 }));
+        assert!(!wasm.covers(&Capability::ClockRead));
+
+        let fs = Capability::FsRead {
+            prefixes: vec!["./a".to_string(), "./b".to_string()],
+        };
+        assert!(fs.covers(&Capability::FsRead {
+            prefixes: vec!["./a/data".to_string()],
+        }));
+        assert!(!fs.covers(&Capability::FsRead {
+            prefixes: vec!["./c".to_string()],
+        }));
+
+        let caps: CapabilitySet = vec![wasm, Capability::ClockRead].into_iter().collect();
+        assert!(caps.covers(&Capability::ClockRead));
+        assert!(!caps.covers(&Capability::NetRead { allowlist: vec![] }));
+    }
+
+    #[test]
+    fn test_capability_attenuate() {
+        let fs = |paths: &[&str]| Capability::FsRead {
+            prefixes: paths.iter().map(|p| (*p).to_string()).collect(),
+        };
+        let parent = fs(&["./a", "./b"]);
+
+        assert_eq!(parent.attenuate(&fs(&["./a"])).unwrap(), fs(&["./a"]));
+        assert!(matches!(
+            parent.attenuate(&fs(&["./a", "./b", "./c"])),
+            Err(CoreError::PermissionDenied { .. })
+        ));
+        assert!(parent.attenuate(&Capability::ClockRead).is_err());
+
+        // A delegation chain can only narrow
+        let run: CapabilitySet = vec![parent, Capability::ClockRead].into_iter().collect();
+        let sub_workflow = run
+            .attenuated_by(&vec![fs(&["./a"]), Capability::ClockRead].into_iter().collect())
+            .unwrap();
+        let tool = sub_workflow
+            .attenuated_by(&vec![fs(&["./a/data"])].into_iter().collect())
+            .unwrap();
+        assert_eq!(tool.len(), 1);
+
+        let widened: CapabilitySet = vec![fs(&["./a", "./b"])].into_iter().collect();
+        assert!(tool.attenuated_by(&widened).is_err());
+        assert!(sub_workflow.attenuated_by(&widened).is_err());
+    }
+
+    #[test]
+    fn test_capability_attenuate_rejects_parent_components() {
+        let fs = |path: &str| Capability::FsRead {
+            prefixes: vec![path.to_string()],
+        };
+        let parent = fs("./a");
+
+        assert!(matches!(
+            parent.attenuate(&fs("./a/../b")),
+            Err(CoreError::PermissionDenied { .. })
+        ));
+        assert!(parent.attenuate(&fs("./a/../../a")).is_err());
+        assert!(parent.attenuate(&fs("./a/x/../y")).is_ok());
+
+        let caps: CapabilitySet = vec![parent].into_iter().collect();
+        assert!(!caps.can_read_fs("./a/../b/secret"));
+        assert!(!caps.can_read_fs("/a"));
+        assert!(caps.can_read_fs("a/./data"));
+    }
 }
@@ -65,6 +65,12 @@ pub enum CoreError {
         operation: String,
     },
 
+    /// I/O failure
+    Io {
+        /// Error reported by the operating system
+        reason: String,
+    },
+
     /// Internal error (for unexpected errors)
     Internal {
         /// Error message
@@ -102,6 +108,7 @@ impl fmt::Display for CoreError {
             Self::PermissionDenied { operation } => {
                 write!(f, "Permission denied: {}", operation)
             }
+            Self::Io { reason } => write!(f, "I/O error: {}", reason),
             Self::Internal { message } => write!(f, "Internal error: {}", message),
         }
     }
@@ -121,6 +128,22 @@ impl From<postcard::Error> for CoreError {
     }
 }
 
+impl From<std::io::Error> for CoreError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io {
+            reason: err.to_string(),
+        }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for CoreError {
+    fn from(_: std::sync::PoisonError<T>) -> Self {
+        Self::Internal {
+            message: "Lock poisoned".to_string(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -155,6 +178,23 @@ mod tests {
         assert!(s.contains("42"));
     }
 
+    #[test]
+    fn test_io_and_poison_conversions() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "segment missing");
+        let err = CoreError::from(io);
+        assert_eq!(format!("{}", err), "I/O error: segment missing");
+
+        let lock = std::sync::Arc::new(std::sync::Mutex::new(0));
+        let clone = lock.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = clone.lock().unwrap();
+            panic!("poison the lock");
+        })
+        .join();
+        let err: CoreError = lock.lock().unwrap_err().into();
+        assert!(matches!(err, CoreError::Internal { .. }));
+    }
+
     #[test]
     fn test_error_equality() {
         let err1 = CoreError::InvalidEncoding;
@@ -0,0 +1,477 @@
+//! Deterministic float policy.
+//!
+//! IEEE 754 leaves room for nondeterminism that must not reach a hash:
+//!
+//! - NaN has many bit patterns, and which one an operation produces
+//!   depends on the platform. Every NaN is replaced by one canonical quiet
+//!   NaN: positive sign, only the top mantissa bit set.
+//! - `-0.0` compares equal to `0.0` but has different bits. Arithmetic on
+//!   signed zeros is itself deterministic, so WASM guests keep them, but
+//!   data that is hashed or compared, such as canonical encodings and tool
+//!   outputs, normalizes `-0.0` to `0.0`.
+//!
+//! Canonical encodings reject floats outside this policy rather than
+//! silently rewriting them; [`check_floats`] finds the first one in any
+//! serializable value.
+
+use crate::error::{CoreError, CoreResult};
+use serde::ser::{self, Serialize, Serializer};
+use std::fmt;
+
+/// Bits of the canonical `f64` NaN
+pub const CANONICAL_NAN_F64: u64 = 0x7ff8_0000_0000_0000;
+
+/// Bits of the canonical `f32` NaN
+pub const CANONICAL_NAN_F32: u32 = 0x7fc0_0000;
+
+/// Bits of `-0.0_f64`
+const NEG_ZERO_F64: u64 = 0x8000_0000_0000_0000;
+
+/// Bits of `-0.0_f32`
+const NEG_ZERO_F32: u32 = 0x8000_0000;
+
+/// Replace any NaN with the canonical NaN, keeping signed zeros
+#[must_use]
+pub fn canonical_nan_f64(bits: u64) -> u64 {
+    if f64::from_bits(bits).is_nan() { CANONICAL_NAN_F64 } else { bits }
+}
+
+/// Replace any NaN with the canonical NaN, keeping signed zeros
+#[must_use]
+pub fn canonical_nan_f32(bits: u32) -> u32 {
+    if f32::from_bits(bits).is_nan() { CANONICAL_NAN_F32 } else { bits }
+}
+
+/// Canonical form of an `f64`: canonical NaN, and `0.0` for `-0.0`
+#[must_use]
+pub fn canonical_f64(value: f64) -> f64 {
+    match canonical_nan_f64(value.to_bits()) {
+        NEG_ZERO_F64 => 0.0,
+        bits => f64::from_bits(bits),
+    }
+}
+
+/// Canonical form of an `f32`: canonical NaN, and `0.0` for `-0.0`
+#[must_use]
+pub fn canonical_f32(value: f32) -> f32 {
+    match canonical_nan_f32(value.to_bits()) {
+        NEG_ZERO_F32 => 0.0,
+        bits => f32::from_bits(bits),
+    }
+}
+
+/// Check that an `f64` is already in canonical form
+#[must_use]
+pub fn is_canonical_f64(value: f64) -> bool {
+    canonical_f64(value).to_bits() == value.to_bits()
+}
+
+/// Check that an `f32` is already in canonical form
+#[must_use]
+pub fn is_canonical_f32(value: f32) -> bool {
+    canonical_f32(value).to_bits() == value.to_bits()
+}
+
+/// Check that every float in `value` is in canonical form
+///
+/// Walks `value` as a non-human-readable serializer, the way canonical
+/// binary encodings see it.
+///
+/// # Errors
+///
+/// Returns error naming the first non-canonical float, or if `value`
+/// fails to serialize
+pub fn check_floats<T: Serialize + ?Sized>(value: &T) -> CoreResult<()> {
+    value.serialize(FloatCheck).map_err(|FloatCheckError(reason)| CoreError::Validation {
+        field: "float".to_string(),
+        reason,
+    })
+}
+
+/// Describe why a float is not canonical
+fn non_canonical(kind: &str, bits: u64, nan: bool) -> FloatCheckError {
+    let why = if nan { "non-canonical NaN" } else { "negative zero" };
+    FloatCheckError(format!("{} {:#x} is a {}", kind, bits, why))
+}
+
+/// Error raised while checking floats
+#[derive(Debug)]
+struct FloatCheckError(String);
+
+impl fmt::Display for FloatCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FloatCheckError {}
+
+impl ser::Error for FloatCheckError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// Serializer that writes nothing and fails on the first non-canonical float
+#[derive(Debug, Clone, Copy)]
+struct FloatCheck;
+
+type CheckResult = Result<(), FloatCheckError>;
+
+impl Serializer for FloatCheck {
+    type Ok = ();
+    type Error = FloatCheckError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_f32(self, v: f32) -> CheckResult {
+        if is_canonical_f32(v) {
+            Ok(())
+        } else {
+            Err(non_canonical("f32", u64::from(v.to_bits()), v.is_nan()))
+        }
+    }
+
+    fn serialize_f64(self, v: f64) -> CheckResult {
+        if is_canonical_f64(v) {
+            Ok(())
+        } else {
+            Err(non_canonical("f64", v.to_bits(), v.is_nan()))
+        }
+    }
+
+    fn serialize_bool(self, _v: bool) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_i8(self, _v: i8) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_i16(self, _v: i16) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_i32(self, _v: i32) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_i64(self, _v: i64) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_i128(self, _v: i128) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_u8(self, _v: u8) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_u16(self, _v: u16) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_u32(self, _v: u32) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_u64(self, _v: u64) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_u128(self, _v: u128) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_char(self, _v: char) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_str(self, _v: &str) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_none(self) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> CheckResult {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+    ) -> CheckResult {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> CheckResult {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> CheckResult {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, FloatCheckError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, FloatCheckError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self, FloatCheckError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, FloatCheckError> {
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self, FloatCheckError> {
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, FloatCheckError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, FloatCheckError> {
+        Ok(self)
+    }
+}
+
+impl ser::SerializeSeq for FloatCheck {
+    type Ok = ();
+    type Error = FloatCheckError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> CheckResult {
+        value.serialize(*self)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for FloatCheck {
+    type Ok = ();
+    type Error = FloatCheckError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> CheckResult {
+        value.serialize(*self)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for FloatCheck {
+    type Ok = ();
+    type Error = FloatCheckError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> CheckResult {
+        value.serialize(*self)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for FloatCheck {
+    type Ok = ();
+    type Error = FloatCheckError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> CheckResult {
+        value.serialize(*self)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for FloatCheck {
+    type Ok = ();
+    type Error = FloatCheckError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> CheckResult {
+        key.serialize(*self)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> CheckResult {
+        value.serialize(*self)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for FloatCheck {
+    type Ok = ();
+    type Error = FloatCheckError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> CheckResult {
+        value.serialize(*self)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for FloatCheck {
+    type Ok = ();
+    type Error = FloatCheckError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> CheckResult {
+        value.serialize(*self)
+    }
+
+    fn end(self) -> CheckResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    enum Reading {
+        Scalar(f64),
+        Pair { low: f32, high: f32 },
+    }
+
+    #[test]
+    fn test_canonical_f64() {
+        let quiet_with_payload = f64::from_bits(0x7ff8_0000_0000_0001);
+        let negative_nan = f64::from_bits(0xfff8_0000_0000_0000);
+        let signalling = f64::from_bits(0x7ff0_0000_0000_0001);
+        for nan in [quiet_with_payload, negative_nan, signalling, f64::NAN] {
+            assert_eq!(canonical_f64(nan).to_bits(), CANONICAL_NAN_F64);
+        }
+        assert_eq!(canonical_f64(-0.0).to_bits(), 0.0f64.to_bits());
+        assert_eq!(canonical_f64(-1.5), -1.5);
+        assert_eq!(canonical_f64(f64::NEG_INFINITY), f64::NEG_INFINITY);
+        assert!(!is_canonical_f64(-0.0));
+        assert!(!is_canonical_f64(negative_nan));
+        assert!(is_canonical_f64(f64::NAN));
+
+        // NaN canonicalization alone keeps signed zeros
+        assert_eq!(canonical_nan_f64((-0.0f64).to_bits()), NEG_ZERO_F64);
+        assert_eq!(canonical_nan_f32(0xffc0_0001), CANONICAL_NAN_F32);
+        assert_eq!(canonical_f32(-0.0).to_bits(), 0);
+    }
+
+    #[test]
+    fn test_check_floats() {
+        assert!(check_floats(&vec![1.0f64, 0.0, f64::NAN, f64::INFINITY]).is_ok());
+        assert!(check_floats(&(1u8, "text", Some(2.5f32))).is_ok());
+
+        let err = check_floats(&vec![1.0f64, -0.0]).unwrap_err();
+        assert!(err.to_string().contains("negative zero"));
+
+        let nested = BTreeMap::from([("reading", Reading::Scalar(f64::from_bits(0xfff8 << 48)))]);
+        let err = check_floats(&nested).unwrap_err();
+        assert!(err.to_string().contains("non-canonical NaN"));
+
+        let pair = Reading::Pair { low: 0.5, high: -0.0 };
+        assert!(check_floats(&pair).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_canonical_f64_is_canonical_and_idempotent(bits: u64) {
+            let value = f64::from_bits(bits);
+            let canonical = canonical_f64(value);
+            prop_assert!(is_canonical_f64(canonical));
+            prop_assert_eq!(canonical_f64(canonical).to_bits(), canonical.to_bits());
+            prop_assert!(check_floats(&canonical).is_ok());
+        }
+
+        #[test]
+        fn prop_canonical_f64_preserves_equality(a: u64, b: u64) {
+            let (a, b) = (f64::from_bits(a), f64::from_bits(b));
+            // Equal values, or two NaNs, have identical canonical bits
+            if a == b || (a.is_nan() && b.is_nan()) {
+                prop_assert_eq!(canonical_f64(a).to_bits(), canonical_f64(b).to_bits());
+            } else {
+                prop_assert_ne!(canonical_f64(a).to_bits(), canonical_f64(b).to_bits());
+            }
+        }
+
+        #[test]
+        fn prop_canonical_f32_is_canonical(bits: u32) {
+            let canonical = canonical_f32(f32::from_bits(bits));
+            prop_assert!(is_canonical_f32(canonical));
+            prop_assert!(check_floats(&canonical).is_ok());
+        }
+    }
+}
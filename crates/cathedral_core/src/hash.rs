@@ -1,11 +1,14 @@
 //! Cryptographic hashes for content addressing and hash chaining.
 //!
-//! Uses BLAKE3 for all hashing operations.
+//! BLAKE3 is the default; content addresses may also use SHA-256 or
+//! SHA-512 via [`AddressAlgorithm`]. A bare [`Hash`] does not record its
+//! algorithm, so digests computed with another algorithm are kept as a
+//! [`ContentAddress`], which prefixes the digest with the algorithm.
 
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-/// A BLAKE3 hash (256 bits / 32 bytes)
+/// A 256-bit (32 byte) hash, BLAKE3 unless carried in a [`ContentAddress`]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct Hash([u8; 32]);
 
@@ -113,6 +116,8 @@ pub enum HashError {
     InvalidHex,
     /// Invalid length (not 32 bytes)
     InvalidLength(usize),
+    /// Unknown hash algorithm
+    UnknownAlgorithm,
 }
 
 impl std::error::Error for HashError {}
@@ -122,6 +127,7 @@ impl fmt::Display for HashError {
         match self {
             Self::InvalidHex => write!(f, "Invalid hex encoding"),
             Self::InvalidLength(len) => write!(f, "Invalid hash length: {} (expected 32)", len),
+            Self::UnknownAlgorithm => write!(f, "Unknown hash algorithm"),
         }
     }
 }
@@ -229,7 +235,12 @@ impl IntoIterator for HashChain {
 }
 
 /// Content address for blob storage
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+///
+/// Human-readable formats get the `algorithm:hex` string, so the algorithm
+/// travels with the hash and an unknown algorithm is rejected on decode;
+/// the `{hash, algorithm}` struct written before is still accepted. Binary
+/// formats keep the struct encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ContentAddress {
     pub hash: Hash,
     pub algorithm: AddressAlgorithm,
@@ -245,6 +256,21 @@ impl ContentAddress {
         }
     }
 
+    /// Create content address from data using `algorithm`
+    #[must_use]
+    pub fn compute_with(data: &[u8], algorithm: AddressAlgorithm) -> Self {
+        Self {
+            hash: algorithm.hash(data),
+            algorithm,
+        }
+    }
+
+    /// Check if the address matches data under its own algorithm
+    #[must_use]
+    pub fn verify(&self, data: &[u8]) -> bool {
+        self.algorithm.hash(data) == self.hash
+    }
+
     /// Create from hash and algorithm
     #[must_use]
     pub const fn new(hash: Hash, algorithm: AddressAlgorithm) -> Self {
@@ -268,10 +294,8 @@ impl ContentAddress {
             return Err(HashError::InvalidHex);
         }
 
-        let algorithm = match parts[0] {
-            "blake3" => AddressAlgorithm::Blake3,
-            _ => return Err(HashError::InvalidHex),
-        };
+        let algorithm =
+            AddressAlgorithm::from_name(parts[0]).ok_or(HashError::UnknownAlgorithm)?;
 
         let hash = Hash::from_hex(parts[1])?;
 
@@ -285,22 +309,131 @@ impl fmt::Display for ContentAddress {
     }
 }
 
+/// Struct encoding of a [`ContentAddress`]
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ContentAddress")]
+struct AddressFields {
+    hash: Hash,
+    algorithm: AddressAlgorithm,
+}
+
+/// Either human-readable encoding of a [`ContentAddress`]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ReadableAddress {
+    Tagged(String),
+    Fields(AddressFields),
+}
+
+impl Serialize for ContentAddress {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.to_string())
+        } else {
+            AddressFields { hash: self.hash, algorithm: self.algorithm }.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ContentAddress {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let fields = if deserializer.is_human_readable() {
+            match ReadableAddress::deserialize(deserializer)? {
+                ReadableAddress::Tagged(s) => {
+                    return Self::from_str(&s).map_err(serde::de::Error::custom);
+                }
+                ReadableAddress::Fields(fields) => fields,
+            }
+        } else {
+            AddressFields::deserialize(deserializer)?
+        };
+        Ok(Self::new(fields.hash, fields.algorithm))
+    }
+}
+
 /// Hash algorithm used for content addressing
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+///
+/// Every algorithm yields a 32-byte [`Hash`]; SHA-512 digests are
+/// truncated to their first 32 bytes.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
 pub enum AddressAlgorithm {
     /// BLAKE3 (default)
+    #[default]
     Blake3,
+    /// SHA-256
+    Sha256,
+    /// SHA-512, truncated to 32 bytes
+    Sha512,
 }
 
 impl AddressAlgorithm {
+    /// Get string representation
     #[must_use]
     pub const fn as_str(&self) -> &str {
         match self {
             Self::Blake3 => "blake3",
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    /// Look up an algorithm by its string representation
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "blake3" => Some(Self::Blake3),
+            "sha256" => Some(Self::Sha256),
+            "sha512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    /// Parse algorithm from string
+    ///
+    /// # Errors
+    ///
+    /// Returns error if algorithm is unknown
+    pub fn parse(s: &str) -> crate::CoreResult<Self> {
+        Self::from_name(s).ok_or_else(|| crate::CoreError::Validation {
+            field: "algorithm".to_string(),
+            reason: format!("Unknown algorithm: {}", s),
+        })
+    }
+
+    /// Compute the bare digest of data using this algorithm
+    ///
+    /// The digest does not record the algorithm; keep it with
+    /// [`ContentAddress::compute_with`] wherever it is stored.
+    #[must_use]
+    pub fn hash(&self, data: &[u8]) -> Hash {
+        use sha2::Digest;
+        match self {
+            Self::Blake3 => Hash::compute(data),
+            Self::Sha256 => Hash(sha2::Sha256::digest(data).into()),
+            Self::Sha512 => {
+                let digest = sha2::Sha512::digest(data);
+                let mut bytes = [0u8; 32];
+                bytes.copy_from_slice(&digest[..32]);
+                Hash(bytes)
+            }
         }
     }
 }
 
+impl fmt::Display for AddressAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -377,6 +510,55 @@ mod tests {
         assert_eq!(addr, restored);
     }
 
+    #[test]
+    fn test_content_address_algorithms() {
+        let data = b"blob content";
+        let addresses = [
+            ContentAddress::compute_with(data, AddressAlgorithm::Blake3),
+            ContentAddress::compute_with(data, AddressAlgorithm::Sha256),
+            ContentAddress::compute_with(data, AddressAlgorithm::Sha512),
+        ];
+        assert_eq!(addresses[0], ContentAddress::from_data(data));
+        assert_eq!(
+            AddressAlgorithm::Sha256.hash(b"abc").to_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_ne!(addresses[0].hash, addresses[1].hash);
+        assert_ne!(addresses[1].hash, addresses[2].hash);
+
+        for addr in addresses {
+            assert!(addr.verify(data));
+            assert!(!addr.verify(b"other content"));
+
+            let json = serde_json::to_string(&addr).unwrap();
+            assert_eq!(json, format!("\"{}\"", addr));
+            assert_eq!(serde_json::from_str::<ContentAddress>(&json).unwrap(), addr);
+            let bytes = postcard::to_allocvec(&addr).unwrap();
+            assert_eq!(postcard::from_bytes::<ContentAddress>(&bytes).unwrap(), addr);
+        }
+
+        let forged = format!("\"md5:{}\"", addresses[0].hash.to_hex());
+        assert!(serde_json::from_str::<ContentAddress>(&forged).is_err());
+        assert_eq!(
+            ContentAddress::from_str(&forged[1..forged.len() - 1]),
+            Err(HashError::UnknownAlgorithm)
+        );
+    }
+
+    #[test]
+    fn test_content_address_reads_struct_form() {
+        let addr = ContentAddress::compute_with(b"old", AddressAlgorithm::Sha512);
+
+        // Logs and manifests written before the string form still decode
+        let old = serde_json::json!({ "hash": addr.hash, "algorithm": "Sha512" });
+        assert_eq!(serde_json::from_value::<ContentAddress>(old).unwrap(), addr);
+
+        // Binary encodings are unchanged from the derived struct
+        let fields = AddressFields { hash: addr.hash, algorithm: addr.algorithm };
+        let bytes = postcard::to_allocvec(&fields).unwrap();
+        assert_eq!(postcard::to_allocvec(&addr).unwrap(), bytes);
+    }
+
     #[test]
     fn test_hash_chain_concatenation() {
         let h1 = Hash::compute(b"first");
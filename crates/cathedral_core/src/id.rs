@@ -348,6 +348,57 @@ impl std::fmt::Display for DecisionId {
     }
 }
 
+/// Tenant identifier - identifies a team sharing a cluster
+///
+/// Runs, logs and stored blobs belong to exactly one tenant, and a tenant
+/// never sees another tenant's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct TenantId(Uuid);
+
+impl TenantId {
+    /// Create a new random TenantId
+    #[must_use]
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    /// Create from UUID bytes
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(Uuid::from_bytes(bytes))
+    }
+
+    /// Create from a tenant name, so the same name always maps to the same ID
+    #[must_use]
+    pub fn from_name(name: &str) -> Self {
+        Self(Uuid::new_v5(&Uuid::NAMESPACE_OID, name.as_bytes()))
+    }
+
+    /// Get as UUID
+    #[must_use]
+    pub const fn as_uuid(&self) -> Uuid {
+        self.0
+    }
+
+    /// Get as bytes
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 16] {
+        self.0.as_bytes()
+    }
+}
+
+impl Default for TenantId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for TenantId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tenant_{}", self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -387,6 +438,14 @@ mod tests {
         assert_ne!(id1, id3);
     }
 
+    #[test]
+    fn test_tenant_id_from_name() {
+        let tenant = TenantId::from_name("team-a");
+        assert_eq!(tenant, TenantId::from_name("team-a"));
+        assert_ne!(tenant, TenantId::from_name("team-b"));
+        assert!(tenant.to_string().starts_with("tenant_"));
+    }
+
     #[test]
     fn test_id_ord() {
         let id1 = EventId::new();
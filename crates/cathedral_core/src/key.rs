@@ -0,0 +1,25 @@
+//! Wrapped data keys.
+//!
+//! A run's data key is only ever persisted encrypted under a master key.
+//! The wrapped form is a plain value, so it can be stored next to the
+//! run's data (in its log manifest) by crates that never see key material;
+//! wrapping and unwrapping live in `cathedral_storage`.
+
+use crate::id::RunId;
+use serde::{Deserialize, Serialize};
+
+/// Length of the nonce a data key is wrapped with
+pub const WRAP_NONCE_LEN: usize = 24;
+
+/// A run's data key, encrypted under a master key for storage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedKey {
+    /// Run the key belongs to
+    pub run_id: RunId,
+    /// Master key that wrapped it
+    pub master_key_id: String,
+    /// Wrapping nonce
+    pub nonce: [u8; WRAP_NONCE_LEN],
+    /// Encrypted key with authentication tag
+    pub sealed_key: Vec<u8>,
+}
@@ -6,17 +6,25 @@
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+pub mod cancel;
 pub mod capability;
 pub mod error;
+pub mod float;
 pub mod hash;
 pub mod id;
+pub mod key;
 pub mod time;
 pub mod version;
 
 // Re-exports
+pub use cancel::CancellationToken;
 pub use capability::{Capability, CapabilitySet};
 pub use error::{CoreError, CoreResult};
+pub use float::{canonical_f32, canonical_f64, check_floats};
 pub use hash::{AddressAlgorithm, ContentAddress, Hash, HashChain, HashError};
-pub use id::{ClusterId, DecisionId, EventId, NodeId, RunId, SnapshotId, TaskId, WorkerId};
+pub use id::{
+    ClusterId, DecisionId, EventId, NodeId, RunId, SnapshotId, TaskId, TenantId, WorkerId,
+};
+pub use key::WrappedKey;
 pub use time::{Duration, LogicalTime, Timestamp};
 pub use version::{Version, VersionError};
@@ -2,6 +2,7 @@
 //!
 //! Each event's prior_state_hash must match the previous event's post_state_hash.
 
+use crate::event::Event;
 use cathedral_core::{Hash, CoreError, CoreResult};
 
 /// A hash chain linking events together
@@ -148,6 +149,7 @@ impl std::fmt::Display for ChainError {
 impl std::error::Error for ChainError {}
 
 /// Validates hash chains
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ChainValidator {
     expected_prior: Option<Hash>,
 }
@@ -191,6 +193,47 @@ impl ChainValidator {
         Ok(())
     }
 
+    /// Validate the state hashes of an event
+    ///
+    /// Events without a post-state hash carry no state transition and are
+    /// accepted without advancing the chain.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the event's prior state hash breaks the chain
+    pub fn validate_event(&mut self, event: &Event) -> Result<(), ChainError> {
+        match event.post_state_hash {
+            Some(post) => self.validate(event.prior_state_hash, post),
+            None => Ok(()),
+        }
+    }
+
+    /// Find the longest prefix of `events` that keeps the chain intact
+    ///
+    /// The validator is left expecting the successor of the last valid
+    /// event, so verification can resume after the prefix is repaired.
+    #[must_use]
+    pub fn verify_prefix(&mut self, events: &[Event]) -> PrefixVerification {
+        for (position, event) in events.iter().enumerate() {
+            if let Err(err) = self.validate_event(event) {
+                let error = match err {
+                    ChainError::BrokenLink { expected, actual, .. } => {
+                        ChainError::BrokenLink { position, expected, actual }
+                    }
+                    other => other,
+                };
+                return PrefixVerification {
+                    valid: position,
+                    error: Some(error),
+                };
+            }
+        }
+        PrefixVerification {
+            valid: events.len(),
+            error: None,
+        }
+    }
+
     /// Validate a sequence of hashes
     ///
     /// # Errors
@@ -230,6 +273,23 @@ impl Default for ChainValidator {
     }
 }
 
+/// Result of [`ChainValidator::verify_prefix`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixVerification {
+    /// Number of leading events that verified
+    pub valid: usize,
+    /// Why the event after the prefix failed, if one did
+    pub error: Option<ChainError>,
+}
+
+impl PrefixVerification {
+    /// Check if every event verified
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -344,6 +404,34 @@ mod tests {
         assert_eq!(validator.expected(), Some(h1));
     }
 
+    #[test]
+    fn test_verify_prefix() {
+        use cathedral_core::{EventId, LogicalTime, NodeId, RunId};
+        use crate::event::EventKind;
+
+        let states: Vec<Hash> = (0..4u8).map(|i| Hash::compute(&[i])).collect();
+        let event = |prior: Hash, post: Hash| {
+            let (run_id, node_id) = (RunId::new(), NodeId::new());
+            Event::new(EventId::new(), run_id, node_id, LogicalTime::zero(), EventKind::NodeCompleted)
+                .with_state_hashes(prior, post)
+        };
+        let mut events = vec![
+            event(states[0], states[1]),
+            event(states[1], states[2]),
+            event(states[0], states[3]),
+        ];
+
+        let mut validator = ChainValidator::new();
+        let prefix = validator.verify_prefix(&events);
+        assert_eq!(prefix.valid, 2);
+        assert!(!prefix.is_complete());
+        assert!(matches!(prefix.error, Some(ChainError::BrokenLink { position: 2, .. })));
+        assert_eq!(validator.expected(), Some(states[2]));
+
+        events.truncate(2);
+        assert!(ChainValidator::new().verify_prefix(&events).is_complete());
+    }
+
     #[test]
     fn test_validator_broken_sequence() {
         let h1 = Hash::compute(b"event1");
@@ -356,4 +444,35 @@ mod tests {
         let result = validator.validate_sequence(&[h2, h3]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_validator_mixed_algorithms() {
+        use cathedral_core::{AddressAlgorithm, EventId, LogicalTime, NodeId, RunId};
+        use crate::event::EventKind;
+
+        let states: Vec<Hash> = [AddressAlgorithm::Blake3, AddressAlgorithm::Sha256, AddressAlgorithm::Sha512]
+            .iter()
+            .enumerate()
+            .map(|(i, algorithm)| algorithm.hash(&[i as u8]))
+            .collect();
+        let events: Vec<Event> = states
+            .windows(2)
+            .map(|pair| {
+                Event::new(EventId::new(), RunId::new(), NodeId::new(), LogicalTime::zero(), EventKind::NodeCompleted)
+                    .with_state_hashes(pair[0], pair[1])
+            })
+            .collect();
+
+        let mut validator = ChainValidator::with_initial(states[0]);
+        for event in &events {
+            let decoded: Event = serde_json::from_slice(&serde_json::to_vec(event).unwrap()).unwrap();
+            validator.validate_event(&decoded).unwrap();
+        }
+        assert_eq!(validator.expected(), Some(states[2]));
+
+        let relabeled = AddressAlgorithm::Blake3.hash(&[1]);
+        let mut validator = ChainValidator::with_initial(states[0]);
+        validator.validate_event(&events[0]).unwrap();
+        assert!(validator.validate(Some(relabeled), states[2]).is_err());
+    }
 }
@@ -16,6 +16,15 @@ pub enum Direction {
     Backward,
 }
 
+/// Physical location of an event in a segmented stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SegmentOffset {
+    /// Segment ID
+    pub segment: u64,
+    /// Byte offset of the record within the segment
+    pub offset: u64,
+}
+
 impl Cursor {
     #[must_use]
     pub fn new() -> Self {
@@ -33,6 +42,13 @@ impl Cursor {
         }
     }
 
+    /// Set the direction the cursor reads in
+    #[must_use]
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
     pub fn move_forward(&mut self, count: u64) {
         self.position = self.position.saturating_add(count);
         self.direction = Direction::Forward;
@@ -108,6 +124,13 @@ mod tests {
         assert_eq!(cursor.pos(), 0);
     }
 
+    #[test]
+    fn test_cursor_with_direction() {
+        let cursor = Cursor::at(3).with_direction(Direction::Backward);
+        assert_eq!(cursor.pos(), 3);
+        assert_eq!(cursor.direction, Direction::Backward);
+    }
+
     #[test]
     fn test_direction() {
         let cursor = Cursor::new();
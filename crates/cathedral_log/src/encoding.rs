@@ -1,19 +1,41 @@
 //! Canonical encoding for cross-platform reproducibility.
 //!
-//! Uses postcard for byte-stable encoding.
+//! Uses postcard for byte-stable encoding. Floats must follow the policy
+//! of [`cathedral_core::float`]: a non-canonical NaN or a negative zero is
+//! rejected, since its bits would make equal values hash differently.
 
+use cathedral_core::check_floats;
 use serde::{Deserialize, Serialize};
 use std::io::{self, Read, Write};
 
 /// Trait for canonical serialization
 pub trait CanonicalEncode: Serialize {
     /// Encode to canonical bytes
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value holds a non-canonical float or fails to encode
     fn encode(&self) -> Vec<u8> {
-        postcard::to_allocvec(self).expect("encoding failed")
+        self.try_encode().expect("encoding failed")
+    }
+
+    /// Encode to canonical bytes, rejecting non-canonical floats
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the value holds a non-canonical float
+    fn try_encode(&self) -> Result<Vec<u8>, EncodeError> {
+        check_floats(self).map_err(|e| EncodeError::NonCanonicalFloat {
+            reason: e.to_string(),
+        })?;
+        postcard::to_allocvec(self).map_err(|_| EncodeError::BufferTooSmall)
     }
 
     /// Encode into a slice
     fn encode_to_slice(&self, slice: &mut [u8]) -> Result<usize, EncodeError> {
+        check_floats(self).map_err(|e| EncodeError::NonCanonicalFloat {
+            reason: e.to_string(),
+        })?;
         postcard::to_slice(self, slice).map_err(|_| EncodeError::BufferTooSmall)?;
         Ok(self.encoded_len())
     }
@@ -36,6 +58,32 @@ pub trait CanonicalDecode<'de>: Deserialize<'de> {
     {
         postcard::from_bytes(data).map_err(|_| DecodeError::InvalidEncoding)
     }
+
+    /// Decode canonical bytes, rejecting any other encoding of the value
+    ///
+    /// The value must hold only canonical floats and re-encode to exactly
+    /// `data`, so two accepted encodings of equal values are identical.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the bytes do not decode, or are not the canonical
+    /// encoding of the decoded value
+    fn decode_canonical(data: &'de [u8]) -> Result<Self, DecodeError>
+    where
+        Self: Serialize + Sized,
+    {
+        let value = Self::decode(data)?;
+        check_floats(&value).map_err(|e| DecodeError::NonCanonical {
+            reason: e.to_string(),
+        })?;
+        let encoded = postcard::to_allocvec(&value).map_err(|_| DecodeError::InvalidEncoding)?;
+        if encoded != data {
+            return Err(DecodeError::NonCanonical {
+                reason: "bytes differ from the value's canonical encoding".to_string(),
+            });
+        }
+        Ok(value)
+    }
 }
 
 impl<'de, T: Deserialize<'de>> CanonicalDecode<'de> for T {}
@@ -45,12 +93,18 @@ impl<'de, T: Deserialize<'de>> CanonicalDecode<'de> for T {}
 pub enum EncodeError {
     /// Buffer too small for encoded data
     BufferTooSmall,
+    /// A float outside the canonical float policy
+    NonCanonicalFloat {
+        /// Which float, and why it is not canonical
+        reason: String,
+    },
 }
 
 impl std::fmt::Display for EncodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::BufferTooSmall => write!(f, "Buffer too small for encoded data"),
+            Self::NonCanonicalFloat { reason } => write!(f, "Non-canonical float: {}", reason),
         }
     }
 }
@@ -62,12 +116,18 @@ impl std::error::Error for EncodeError {}
 pub enum DecodeError {
     /// Invalid encoding
     InvalidEncoding,
+    /// Valid encoding, but not the canonical one
+    NonCanonical {
+        /// How the encoding departs from canonical form
+        reason: String,
+    },
 }
 
 impl std::fmt::Display for DecodeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::InvalidEncoding => write!(f, "Invalid canonical encoding"),
+            Self::NonCanonical { reason } => write!(f, "Non-canonical encoding: {}", reason),
         }
     }
 }
@@ -87,7 +147,7 @@ impl<W: Write> CanonicalEncoder<W> {
 
     /// Encode a value
     pub fn encode<T: CanonicalEncode>(&mut self, value: &T) -> Result<(), EncodeError> {
-        let bytes = value.encode();
+        let bytes = value.try_encode()?;
         self.writer
             .write_all(&(bytes.len() as u32).to_be_bytes())
             .map_err(|_| EncodeError::BufferTooSmall)?;
@@ -162,6 +222,15 @@ mod tests {
 
     impl CanonicalEncode for TestStruct {}
 
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Measurement {
+        label: String,
+        value: f64,
+        samples: Vec<f32>,
+    }
+
+    impl CanonicalEncode for Measurement {}
+
     #[test]
     fn test_encode_decode_roundtrip() {
         let original = TestStruct {
@@ -243,8 +312,93 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_encode_rejects_non_canonical_floats() {
+        let measurement = |value: f64, samples: Vec<f32>| Measurement {
+            label: "latency".to_string(),
+            value,
+            samples,
+        };
+
+        assert!(measurement(1.5, vec![0.0, f32::NAN]).try_encode().is_ok());
+
+        let err = measurement(-0.0, vec![]).try_encode().unwrap_err();
+        assert!(matches!(err, EncodeError::NonCanonicalFloat { .. }));
+        let payload_nan = f32::from_bits(0x7fc0_0001);
+        assert!(measurement(1.0, vec![payload_nan]).try_encode().is_err());
+
+        let mut buffer = Vec::new();
+        let mut encoder = CanonicalEncoder::new(&mut buffer);
+        assert!(encoder.encode(&measurement(f64::from_bits(0xfff8 << 48), vec![])).is_err());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "encoding failed")]
+    fn test_encode_panics_on_negative_zero() {
+        let _ = Measurement {
+            label: String::new(),
+            value: -0.0,
+            samples: Vec::new(),
+        }
+        .encode();
+    }
+
+    #[test]
+    fn test_decode_canonical_rejects_other_encodings() {
+        let value = Measurement {
+            label: "x".to_string(),
+            value: 2.0,
+            samples: vec![],
+        };
+        let encoded = value.encode();
+        assert_eq!(Measurement::decode_canonical(&encoded).unwrap(), value);
+
+        // Bytes carrying a negative zero decode, but are not canonical
+        let zero = postcard::to_allocvec(&Measurement { value: -0.0, ..value.clone() }).unwrap();
+        assert!(matches!(
+            Measurement::decode_canonical(&zero),
+            Err(DecodeError::NonCanonical { .. })
+        ));
+
+        // An overlong varint for the empty sample list
+        let mut overlong = encoded[..encoded.len() - 1].to_vec();
+        overlong.extend_from_slice(&[0x80, 0x00]);
+        assert_eq!(Measurement::decode(&overlong).ok(), Some(value));
+        assert!(Measurement::decode_canonical(&overlong).is_err());
+    }
+
     // Property tests using proptest
     proptest::proptest! {
+        #[test]
+        fn prop_canonical_floats_encode_equal_values_identically(a: u64, b: u64) {
+            let canonical = |bits: u64| Measurement {
+                label: "m".to_string(),
+                value: cathedral_core::canonical_f64(f64::from_bits(bits)),
+                samples: vec![cathedral_core::canonical_f32(f64::from_bits(bits) as f32)],
+            };
+            let (left, right) = (canonical(a), canonical(b));
+            let (x, y) = (f64::from_bits(a), f64::from_bits(b));
+            let encoded = left.try_encode().unwrap();
+            let decoded = Measurement::decode_canonical(&encoded).unwrap();
+            prop_assert_eq!(decoded.encode(), encoded.clone());
+            if x == y || (x.is_nan() && y.is_nan()) {
+                prop_assert_eq!(encoded, right.try_encode().unwrap());
+            }
+        }
+
+        #[test]
+        fn prop_non_canonical_floats_never_encode(bits: u64) {
+            let value = f64::from_bits(bits);
+            let measurement = Measurement {
+                label: String::new(),
+                value,
+                samples: Vec::new(),
+            };
+            let canonical = cathedral_core::float::is_canonical_f64(value);
+            prop_assert_eq!(measurement.try_encode().is_ok(), canonical);
+        }
+
         #[test]
         fn prop_encode_roundtrip(
             a: u64,
@@ -3,7 +3,8 @@
 //! All events are canonically encoded and part of the hash chain.
 
 use crate::encoding::CanonicalEncode;
-use cathedral_core::{EventId, RunId, NodeId, Hash, LogicalTime};
+use crate::redact::Redaction;
+use cathedral_core::{CoreError, CoreResult, EventId, RunId, NodeId, Hash, LogicalTime};
 use serde::{Deserialize, Serialize};
 
 /// Event kind - type of event
@@ -24,28 +25,209 @@ pub enum EventKind {
     ToolTimedOut,
     CapabilityCheck,
     PolicyDecision,
+    PolicyActivated,
     TaskAssigned,
     TaskAccepted,
     TaskRejected,
+    TaskTimedOut,
     SnapshotCreated,
     SnapshotRestored,
     BlobStored,
     Heartbeat,
     Error,
+    /// A node's resource contract exceeded the run's remaining budget.
+    /// Kept last so earlier kinds keep their encoded variant index.
+    BudgetExceeded,
+    /// A ready node was dropped by backpressure load shedding
+    Shed,
+    /// A node in flight when its run was cancelled
+    NodeCancelled,
+    /// The run was cancelled
+    RunCancelled,
+    /// A task result lost to one already committed for the task
+    Superseded,
+    /// A queued task moved from an overloaded worker to an idle one
+    TaskStolen,
+    /// A damaged log was truncated to its longest valid prefix
+    Truncation,
+    /// A request or run was refused by a rate limit or tenant quota
+    QuotaDenied,
+    /// An API call, with the principal that made it
+    ApiCall,
+    /// A host function call made by a WASM guest, with its result
+    HostCall,
+    /// A side effect a tool applied, described in the payload
+    SideEffect,
+    /// A cluster member stopped taking work ahead of its removal
+    MemberDraining,
+    /// A drained cluster member was removed from membership
+    MemberRemoved,
+    /// A task exhausted its retries and moved to the dead-letter queue
+    TaskDeadLettered,
+    /// A dead-lettered task was moved back to pending
+    TaskRequeued,
 }
 
 impl EventKind {
+    /// Every kind, ordered by [`EventKind::code`]
+    pub const ALL: [Self; 40] = [
+        Self::RunCreated,
+        Self::RunStarted,
+        Self::RunCompleted,
+        Self::RunFailed,
+        Self::NodeScheduled,
+        Self::NodeStarted,
+        Self::NodeCompleted,
+        Self::NodeFailed,
+        Self::NodeSkipped,
+        Self::ToolInvoked,
+        Self::ToolCompleted,
+        Self::ToolFailed,
+        Self::ToolTimedOut,
+        Self::CapabilityCheck,
+        Self::PolicyDecision,
+        Self::PolicyActivated,
+        Self::TaskAssigned,
+        Self::TaskAccepted,
+        Self::TaskRejected,
+        Self::TaskTimedOut,
+        Self::SnapshotCreated,
+        Self::SnapshotRestored,
+        Self::BlobStored,
+        Self::Heartbeat,
+        Self::Error,
+        Self::BudgetExceeded,
+        Self::Shed,
+        Self::NodeCancelled,
+        Self::RunCancelled,
+        Self::Superseded,
+        Self::TaskStolen,
+        Self::Truncation,
+        Self::QuotaDenied,
+        Self::ApiCall,
+        Self::HostCall,
+        Self::SideEffect,
+        Self::MemberDraining,
+        Self::MemberRemoved,
+        Self::TaskDeadLettered,
+        Self::TaskRequeued,
+    ];
+
+    /// Stable one-byte code of the kind, used by on-disk formats
+    ///
+    /// Codes follow declaration order and are never reused; new kinds take
+    /// the next free code.
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::RunCreated => 0,
+            Self::RunStarted => 1,
+            Self::RunCompleted => 2,
+            Self::RunFailed => 3,
+            Self::NodeScheduled => 4,
+            Self::NodeStarted => 5,
+            Self::NodeCompleted => 6,
+            Self::NodeFailed => 7,
+            Self::NodeSkipped => 8,
+            Self::ToolInvoked => 9,
+            Self::ToolCompleted => 10,
+            Self::ToolFailed => 11,
+            Self::ToolTimedOut => 12,
+            Self::CapabilityCheck => 13,
+            Self::PolicyDecision => 14,
+            Self::PolicyActivated => 15,
+            Self::TaskAssigned => 16,
+            Self::TaskAccepted => 17,
+            Self::TaskRejected => 18,
+            Self::TaskTimedOut => 19,
+            Self::SnapshotCreated => 20,
+            Self::SnapshotRestored => 21,
+            Self::BlobStored => 22,
+            Self::Heartbeat => 23,
+            Self::Error => 24,
+            Self::BudgetExceeded => 25,
+            Self::Shed => 26,
+            Self::NodeCancelled => 27,
+            Self::RunCancelled => 28,
+            Self::Superseded => 29,
+            Self::TaskStolen => 30,
+            Self::Truncation => 31,
+            Self::QuotaDenied => 32,
+            Self::ApiCall => 33,
+            Self::HostCall => 34,
+            Self::SideEffect => 35,
+            Self::MemberDraining => 36,
+            Self::MemberRemoved => 37,
+            Self::TaskDeadLettered => 38,
+            Self::TaskRequeued => 39,
+        }
+    }
+
+    /// Kind with a given code, if any
+    #[must_use]
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(code)).copied()
+    }
+
     pub const fn is_terminal(self) -> bool {
         matches!(
             self,
             Self::RunCompleted | Self::RunFailed | Self::NodeCompleted |
             Self::NodeFailed | Self::NodeSkipped | Self::ToolCompleted |
-            Self::ToolFailed | Self::ToolTimedOut
+            Self::ToolFailed | Self::ToolTimedOut | Self::Shed |
+            Self::NodeCancelled | Self::RunCancelled
         )
     }
 
     pub const fn is_error(self) -> bool {
-        matches!(self, Self::RunFailed | Self::NodeFailed | Self::ToolFailed | Self::Error)
+        matches!(
+            self,
+            Self::RunFailed | Self::NodeFailed | Self::ToolFailed | Self::BudgetExceeded |
+            Self::Error
+        )
+    }
+}
+
+/// Payload of a run's `RunCreated` event
+///
+/// Records the deterministic host ABI the run executed against, so replay
+/// can refuse a trace recorded under a different one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunHeader {
+    /// ABI version
+    pub abi_version: String,
+    /// Content hash of the ABI's function signatures
+    pub abi_hash: Hash,
+}
+
+impl RunHeader {
+    /// Create a run header
+    #[must_use]
+    pub fn new(abi_version: impl Into<String>, abi_hash: Hash) -> Self {
+        Self {
+            abi_version: abi_version.into(),
+            abi_hash,
+        }
+    }
+
+    /// Encode for an event payload
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn encode(&self) -> CoreResult<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Decode from the payload of a `RunCreated` or `RunStarted` event
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the payload is not a run header
+    pub fn decode(payload: &[u8]) -> CoreResult<Self> {
+        serde_json::from_slice(payload).map_err(|e| CoreError::ParseError {
+            message: format!("Invalid run header: {}", e),
+        })
     }
 }
 
@@ -62,6 +244,11 @@ pub struct Event {
     pub payload_hash: Hash,
     pub prior_state_hash: Option<Hash>,
     pub post_state_hash: Option<Hash>,
+    /// Cluster-wide sequence assigned by consensus commit order, if committed
+    pub global_seq: Option<u64>,
+    /// Redaction applied to the payload before storage, if any
+    #[serde(default)]
+    pub redaction: Option<Redaction>,
 }
 
 impl Event {
@@ -83,6 +270,8 @@ impl Event {
             payload_hash: Hash::empty(),
             prior_state_hash: None,
             post_state_hash: None,
+            global_seq: None,
+            redaction: None,
         }
     }
 
@@ -103,6 +292,30 @@ impl Event {
         self
     }
 
+    /// Replace the payload with its redacted form
+    ///
+    /// The hash of the current payload is kept as a commitment to the
+    /// original, and `payload_hash` covers the redacted bytes.
+    #[must_use]
+    pub fn with_redaction(mut self, redacted: Vec<u8>, rules: Vec<String>) -> Self {
+        self.redaction = Some(Redaction {
+            original_hash: self.payload_hash,
+            rules,
+        });
+        self.with_payload(redacted)
+    }
+
+    /// Check if the payload was redacted before storage
+    #[must_use]
+    pub fn is_redacted(&self) -> bool {
+        self.redaction.is_some()
+    }
+
+    pub fn with_global_seq(mut self, seq: u64) -> Self {
+        self.global_seq = Some(seq);
+        self
+    }
+
     pub fn is_terminal(&self) -> bool {
         self.kind.is_terminal()
     }
@@ -114,6 +327,37 @@ impl Event {
 
 impl CanonicalEncode for Event {}
 
+/// Merge events from several nodes into one log totally ordered by `global_seq`
+///
+/// Every event must carry a consensus-assigned sequence and no sequence may
+/// appear twice, otherwise the merged order would not be deterministic.
+///
+/// # Errors
+///
+/// Returns error if an event is unsequenced or a sequence is duplicated
+pub fn merge_by_global_seq<I>(sources: I) -> CoreResult<Vec<Event>>
+where
+    I: IntoIterator<Item = Vec<Event>>,
+{
+    let mut merged = std::collections::BTreeMap::new();
+
+    for event in sources.into_iter().flatten() {
+        let seq = event.global_seq.ok_or_else(|| CoreError::Validation {
+            field: "global_seq".to_string(),
+            reason: format!("event {} has no global sequence", event.event_id),
+        })?;
+
+        if merged.insert(seq, event).is_some() {
+            return Err(CoreError::AlreadyExists {
+                kind: "global_seq".to_string(),
+                id: seq.to_string(),
+            });
+        }
+    }
+
+    Ok(merged.into_values().collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -144,6 +388,57 @@ mod tests {
         assert!(event.is_terminal());
     }
 
+    #[test]
+    fn test_merge_by_global_seq() {
+        let run_id = RunId::new();
+        let make = |seq: u64| {
+            Event::new(
+                EventId::new(),
+                run_id,
+                NodeId::new(),
+                LogicalTime::from_raw(seq),
+                EventKind::NodeCompleted,
+            )
+            .with_global_seq(seq)
+        };
+
+        let worker_a = vec![make(0), make(4)];
+        let worker_b = vec![make(2), make(3)];
+        let worker_c = vec![make(1), make(5)];
+
+        let merged = merge_by_global_seq(vec![worker_a, worker_b, worker_c]).unwrap();
+        let seqs: Vec<u64> = merged.iter().filter_map(|e| e.global_seq).collect();
+        assert_eq!(seqs, vec![0, 1, 2, 3, 4, 5]);
+
+        assert!(merge_by_global_seq(vec![vec![make(0)], vec![make(0)]]).is_err());
+
+        let unsequenced = Event::new(
+            EventId::new(),
+            run_id,
+            NodeId::new(),
+            LogicalTime::zero(),
+            EventKind::Heartbeat,
+        );
+        assert!(merge_by_global_seq(vec![vec![unsequenced]]).is_err());
+    }
+
+    #[test]
+    fn test_event_kind_codes() {
+        for (code, kind) in EventKind::ALL.iter().enumerate() {
+            assert_eq!(usize::from(kind.code()), code);
+            assert_eq!(EventKind::from_code(kind.code()), Some(*kind));
+        }
+        assert_eq!(EventKind::TaskRequeued.code(), 39);
+        assert_eq!(EventKind::from_code(40), None);
+    }
+
+    #[test]
+    fn test_run_header_roundtrip() {
+        let header = RunHeader::new("1.0.0", Hash::compute(b"abi"));
+        assert_eq!(RunHeader::decode(&header.encode().unwrap()).unwrap(), header);
+        assert!(RunHeader::decode(b"").is_err());
+    }
+
     #[test]
     fn test_event_encode() {
         let event = Event::new(
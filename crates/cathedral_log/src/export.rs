@@ -0,0 +1,515 @@
+//! Export and import of event logs in interoperable formats.
+//!
+//! - JSON Lines: one JSON event object per line, for ad-hoc tooling.
+//! - Canonical CBOR: a CBOR sequence of a [`CborHeader`] followed by every
+//!   event in packed form (integer field keys and variant indices). The
+//!   header commits to the event count and hash chain root, so
+//!   [`import_cbor`] can rebuild the chain and verify it.
+//! - OTLP: OpenTelemetry trace data in the OTLP/JSON encoding, accepted by
+//!   Jaeger and Tempo over OTLP/HTTP. Each run becomes a trace with a root
+//!   span, and each node a child span carrying the node's events.
+
+use crate::chain::{ChainValidator, HashChain};
+use crate::encoding::CanonicalEncode;
+use crate::event::{Event, EventKind};
+use cathedral_core::{CoreError, CoreResult, Hash, NodeId, RunId};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::{BufRead, Read, Write};
+
+/// Format name written to CBOR headers
+pub const CBOR_FORMAT: &str = "cathedral.log";
+
+/// Version of the CBOR export layout
+pub const CBOR_VERSION: u32 = 1;
+
+/// First item of a CBOR export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CborHeader {
+    /// Always [`CBOR_FORMAT`]
+    pub format: String,
+    /// Layout version
+    pub version: u32,
+    /// Number of events that follow
+    pub events: u64,
+    /// Root of the hash chain over the canonical event encodings
+    pub root: Option<Hash>,
+}
+
+/// Events rebuilt from a CBOR export
+#[derive(Debug, Clone)]
+pub struct ImportedLog {
+    /// Events in log order
+    pub events: Vec<Event>,
+    /// Hash chain over the canonical event encodings
+    pub chain: HashChain,
+}
+
+/// Write events as JSON Lines
+///
+/// Returns the number of events written.
+///
+/// # Errors
+///
+/// Returns error if an event cannot be written
+pub fn export_jsonl<'a, W: Write>(
+    events: impl IntoIterator<Item = &'a Event>,
+    mut writer: W,
+) -> CoreResult<u64> {
+    let mut count = 0;
+    for event in events {
+        serde_json::to_writer(&mut writer, event)?;
+        writer.write_all(b"\n")?;
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Read events written by [`export_jsonl`]
+///
+/// Blank lines are skipped.
+///
+/// # Errors
+///
+/// Returns error if a line cannot be read or is not an event
+pub fn import_jsonl<R: BufRead>(reader: R) -> CoreResult<Vec<Event>> {
+    let mut events = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line).map_err(|e| CoreError::ParseError {
+            message: format!("Invalid event on line {}: {}", number + 1, e),
+        })?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Write events as a canonical CBOR sequence
+///
+/// Returns the header written ahead of the events.
+///
+/// # Errors
+///
+/// Returns error if the export cannot be written
+pub fn export_cbor<W: Write>(events: &[Event], mut writer: W) -> CoreResult<CborHeader> {
+    let mut chain = HashChain::new();
+    for event in events {
+        chain.push(Hash::compute(&event.encode()))?;
+    }
+    let header = CborHeader {
+        format: CBOR_FORMAT.to_string(),
+        version: CBOR_VERSION,
+        events: events.len() as u64,
+        root: chain.root(),
+    };
+
+    write_cbor(&mut writer, &header)?;
+    for event in events {
+        write_cbor(&mut writer, event)?;
+    }
+    writer.flush()?;
+    Ok(header)
+}
+
+/// Read a CBOR export and verify it
+///
+/// Every payload must match its hash, events carrying state hashes must
+/// link to their predecessor, and the rebuilt chain must match the count
+/// and root in the header.
+///
+/// # Errors
+///
+/// Returns error if the export is malformed, truncated, or fails
+/// verification
+pub fn import_cbor<R: Read>(reader: R) -> CoreResult<ImportedLog> {
+    let mut deserializer = serde_cbor::Deserializer::from_reader(reader);
+    let header = CborHeader::deserialize(&mut deserializer).map_err(cbor_error)?;
+    if header.format != CBOR_FORMAT || header.version != CBOR_VERSION {
+        return Err(CoreError::InvalidVersion {
+            reason: format!("Unsupported export {} v{}", header.format, header.version),
+        });
+    }
+
+    let mut events = Vec::new();
+    let mut chain = HashChain::new();
+    let mut validator = ChainValidator::new();
+    for position in 0..header.events as usize {
+        let event = Event::deserialize(&mut deserializer).map_err(cbor_error)?;
+        verify_payload(&event)?;
+        validator
+            .validate_event(&event)
+            .map_err(|_| CoreError::BrokenChain { position })?;
+        chain.push(Hash::compute(&event.encode()))?;
+        events.push(event);
+    }
+    deserializer.end().map_err(cbor_error)?;
+
+    if chain.root() != header.root {
+        let hex = |root: Option<Hash>| root.map_or_else(|| "none".to_string(), |h| h.to_hex());
+        return Err(CoreError::HashMismatch {
+            expected: hex(header.root),
+            actual: hex(chain.root()),
+        });
+    }
+    Ok(ImportedLog { events, chain })
+}
+
+/// Exporter of runs as OpenTelemetry traces
+///
+/// Logical time has no wall-clock meaning, so timestamps are placed at
+/// `epoch + logical_time * tick`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtlpExporter {
+    service_name: String,
+    epoch_unix_nanos: u64,
+    tick_nanos: u64,
+}
+
+impl OtlpExporter {
+    /// Create an exporter reporting as `service_name`
+    #[must_use]
+    pub fn new(service_name: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+            epoch_unix_nanos: 0,
+            tick_nanos: 1_000_000,
+        }
+    }
+
+    /// Set the wall-clock time of logical time zero
+    #[must_use]
+    pub fn with_epoch(mut self, epoch_unix_nanos: u64) -> Self {
+        self.epoch_unix_nanos = epoch_unix_nanos;
+        self
+    }
+
+    /// Set the duration of one logical tick
+    #[must_use]
+    pub fn with_tick(mut self, tick: std::time::Duration) -> Self {
+        self.tick_nanos = u64::try_from(tick.as_nanos()).unwrap_or(u64::MAX);
+        self
+    }
+
+    /// Build an OTLP `ExportTraceServiceRequest` in its JSON encoding
+    #[must_use]
+    pub fn to_json<'a>(&self, events: impl IntoIterator<Item = &'a Event>) -> Value {
+        let mut runs: IndexMap<RunId, IndexMap<NodeId, Vec<&Event>>> = IndexMap::new();
+        for event in events {
+            runs.entry(event.run_id)
+                .or_default()
+                .entry(event.node_id)
+                .or_default()
+                .push(event);
+        }
+
+        let spans: Vec<Value> = runs
+            .iter()
+            .flat_map(|(run_id, nodes)| self.run_spans(*run_id, nodes))
+            .collect();
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [attribute("service.name", &self.service_name)],
+                },
+                "scopeSpans": [{
+                    "scope": { "name": "cathedral_log" },
+                    "spans": spans,
+                }],
+            }],
+        })
+    }
+
+    /// Write the trace request for `events` as JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the request cannot be written
+    pub fn export<'a, W: Write>(
+        &self,
+        events: impl IntoIterator<Item = &'a Event>,
+        mut writer: W,
+    ) -> CoreResult<()> {
+        serde_json::to_writer(&mut writer, &self.to_json(events))?;
+        writer.flush().map_err(CoreError::from)
+    }
+
+    /// Root span of a run followed by one span per node
+    fn run_spans(&self, run_id: RunId, nodes: &IndexMap<NodeId, Vec<&Event>>) -> Vec<Value> {
+        let trace_id = hex(run_id.as_bytes());
+        let root_id = span_id(run_id.as_bytes());
+        let all: Vec<&Event> = nodes.values().flatten().copied().collect();
+        let run_events: Vec<&Event> = all
+            .iter()
+            .copied()
+            .filter(|event| is_run_event(event.kind))
+            .collect();
+
+        let mut spans = vec![self.span(
+            &trace_id,
+            &root_id,
+            None,
+            &run_id.to_string(),
+            &all,
+            &run_events,
+            vec![attribute("cathedral.run_id", &run_id.to_string())],
+        )];
+        for (node_id, events) in nodes {
+            let node_events: Vec<&Event> = events
+                .iter()
+                .copied()
+                .filter(|event| !is_run_event(event.kind))
+                .collect();
+            if node_events.is_empty() {
+                continue;
+            }
+            let mut seed = run_id.as_bytes().to_vec();
+            seed.extend_from_slice(node_id.as_bytes());
+            spans.push(self.span(
+                &trace_id,
+                &span_id(&seed),
+                Some(&root_id),
+                &node_id.to_string(),
+                &node_events,
+                &node_events,
+                vec![
+                    attribute("cathedral.run_id", &run_id.to_string()),
+                    attribute("cathedral.node_id", &node_id.to_string()),
+                ],
+            ));
+        }
+        spans
+    }
+
+    /// Span covering `covered`, with `recorded` attached as span events
+    #[allow(clippy::too_many_arguments)]
+    fn span(
+        &self,
+        trace_id: &str,
+        span_id: &str,
+        parent: Option<&str>,
+        name: &str,
+        covered: &[&Event],
+        recorded: &[&Event],
+        attributes: Vec<Value>,
+    ) -> Value {
+        let times = covered.iter().map(|event| event.logical_time.as_u64());
+        let start = times.clone().min().unwrap_or(0);
+        let end = times.max().unwrap_or(0);
+        // 0 = unset, 1 = ok, 2 = error
+        let status = if covered.iter().any(|event| event.is_error()) {
+            2
+        } else if covered.iter().any(|event| event.is_terminal()) {
+            1
+        } else {
+            0
+        };
+        let events: Vec<Value> = recorded
+            .iter()
+            .map(|event| {
+                json!({
+                    "timeUnixNano": self.time(event.logical_time.as_u64()),
+                    "name": format!("{:?}", event.kind),
+                    "attributes": [
+                        attribute("cathedral.event_id", &event.event_id.to_string()),
+                        attribute("cathedral.payload_hash", &event.payload_hash.to_hex()),
+                    ],
+                })
+            })
+            .collect();
+
+        json!({
+            "traceId": trace_id,
+            "spanId": span_id,
+            "parentSpanId": parent.unwrap_or_default(),
+            "name": name,
+            // SPAN_KIND_INTERNAL
+            "kind": 1,
+            "startTimeUnixNano": self.time(start),
+            "endTimeUnixNano": self.time(end),
+            "attributes": attributes,
+            "events": events,
+            "status": { "code": status },
+        })
+    }
+
+    /// Wall-clock nanoseconds of a logical time, as OTLP/JSON's string form
+    fn time(&self, logical_time: u64) -> String {
+        logical_time
+            .saturating_mul(self.tick_nanos)
+            .saturating_add(self.epoch_unix_nanos)
+            .to_string()
+    }
+}
+
+/// Check if an event belongs to the run rather than one of its nodes
+fn is_run_event(kind: EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::RunCreated |
+            EventKind::RunStarted |
+            EventKind::RunCompleted |
+            EventKind::RunFailed |
+            EventKind::RunCancelled
+    )
+}
+
+/// Check that a payload matches its hash
+fn verify_payload(event: &Event) -> CoreResult<()> {
+    let actual = Hash::compute(&event.payload);
+    let unset = event.payload.is_empty() && event.payload_hash == Hash::empty();
+    if actual != event.payload_hash && !unset {
+        return Err(CoreError::HashMismatch {
+            expected: event.payload_hash.to_hex(),
+            actual: actual.to_hex(),
+        });
+    }
+    Ok(())
+}
+
+/// OTLP string attribute
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// 8-byte span ID derived from `seed`
+fn span_id(seed: &[u8]) -> String {
+    hex(&Hash::compute(seed).as_bytes()[..8])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn write_cbor<W: Write, T: Serialize>(writer: &mut W, value: &T) -> CoreResult<()> {
+    let data = serde_cbor::ser::to_vec_packed(value).map_err(cbor_error)?;
+    writer.write_all(&data).map_err(CoreError::from)
+}
+
+fn cbor_error(err: serde_cbor::Error) -> CoreError {
+    CoreError::ParseError {
+        message: format!("Invalid CBOR export: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_core::{EventId, LogicalTime};
+
+    /// Events of one run whose state hashes form a chain
+    fn run_events() -> Vec<Event> {
+        let run_id = RunId::new();
+        let node_id = NodeId::new();
+        let kinds = [
+            (NodeId::new(), EventKind::RunStarted),
+            (node_id, EventKind::NodeStarted),
+            (node_id, EventKind::NodeFailed),
+            (NodeId::new(), EventKind::RunFailed),
+        ];
+        let mut state = Hash::empty();
+        kinds
+            .into_iter()
+            .enumerate()
+            .map(|(time, (node_id, kind))| {
+                let next = Hash::compute(&[time as u8]);
+                let event = Event::new(
+                    EventId::new(),
+                    run_id,
+                    node_id,
+                    LogicalTime::from_raw(time as u64),
+                    kind,
+                )
+                .with_payload(vec![time as u8; 8])
+                .with_state_hashes(state, next);
+                state = next;
+                event
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_jsonl_round_trip() {
+        let events = run_events();
+        let mut data = Vec::new();
+        assert_eq!(export_jsonl(&events, &mut data).unwrap(), 4);
+        assert_eq!(data.iter().filter(|&&byte| byte == b'\n').count(), 4);
+        assert_eq!(import_jsonl(&data[..]).unwrap(), events);
+        assert!(import_jsonl(&b"{}\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_cbor_round_trip_rebuilds_chain() {
+        let events = run_events();
+        let mut data = Vec::new();
+        let header = export_cbor(&events, &mut data).unwrap();
+        assert_eq!(header.events, 4);
+
+        let imported = import_cbor(&data[..]).unwrap();
+        assert_eq!(imported.events, events);
+        assert_eq!(imported.chain.root(), header.root);
+
+        // Re-exporting the imported events is byte-identical
+        let mut again = Vec::new();
+        export_cbor(&imported.events, &mut again).unwrap();
+        assert_eq!(again, data);
+    }
+
+    #[test]
+    fn test_cbor_import_rejects_tampering() {
+        let mut events = run_events();
+        let mut data = Vec::new();
+        export_cbor(&events, &mut data).unwrap();
+        assert!(import_cbor(&data[..data.len() - 1]).is_err());
+
+        // A payload that no longer matches its hash
+        events[1].payload[0] ^= 0xff;
+        let mut tampered = Vec::new();
+        export_cbor(&events, &mut tampered).unwrap();
+        assert!(matches!(
+            import_cbor(&tampered[..]),
+            Err(CoreError::HashMismatch { .. })
+        ));
+
+        // A dropped event breaks the state hash chain
+        events[1].payload[0] ^= 0xff;
+        events.remove(1);
+        let mut broken = Vec::new();
+        export_cbor(&events, &mut broken).unwrap();
+        assert!(matches!(
+            import_cbor(&broken[..]),
+            Err(CoreError::BrokenChain { position: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_otlp_spans() {
+        let events = run_events();
+        let exporter = OtlpExporter::new("cathedral")
+            .with_epoch(1_000)
+            .with_tick(std::time::Duration::from_micros(1));
+        let request = exporter.to_json(&events);
+        let spans = request["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap();
+        assert_eq!(spans.len(), 2);
+
+        let (root, node) = (&spans[0], &spans[1]);
+        assert_eq!(root["traceId"].as_str().unwrap().len(), 32);
+        assert_eq!(root["spanId"].as_str().unwrap().len(), 16);
+        assert_eq!(root["parentSpanId"], "");
+        assert_eq!(root["startTimeUnixNano"], "1000");
+        assert_eq!(root["endTimeUnixNano"], "4000");
+        assert_eq!(root["events"].as_array().unwrap().len(), 2);
+        assert_eq!(root["status"]["code"], 2);
+
+        assert_eq!(node["traceId"], root["traceId"]);
+        assert_eq!(node["parentSpanId"], root["spanId"]);
+        assert_eq!(node["startTimeUnixNano"], "2000");
+        assert_eq!(node["events"][1]["name"], "NodeFailed");
+    }
+}
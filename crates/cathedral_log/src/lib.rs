@@ -11,12 +11,25 @@ pub mod encoding;
 pub mod chain;
 pub mod stream;
 pub mod cursor;
+pub mod redact;
+pub mod query;
+pub mod export;
+pub mod subscription;
 
-pub use event::{Event, EventKind};
+pub use event::{Event, EventKind, RunHeader, merge_by_global_seq};
 pub use encoding::{CanonicalEncode, CanonicalDecode};
-pub use chain::{HashChain, ChainError, ChainValidator};
-pub use stream::{EventStream, StreamWriter, StreamError};
-pub use cursor::{Cursor, Direction};
+pub use chain::{HashChain, ChainError, ChainValidator, PrefixVerification};
+pub use stream::{
+    EventStream, IndexedLog, RecoveryReport, RepairReport, SegmentConfig, SegmentedStream,
+    StreamError, StreamManifest, StreamWriter, INDEX_FILE, MANIFEST_FILE, SEGMENT_EXTENSION,
+};
+pub use cursor::{Cursor, Direction, SegmentOffset};
+pub use redact::{PayloadRedactor, Redaction};
+pub use query::{EventIndex, IndexEntry, LogQuery};
+pub use subscription::{LiveStream, Subscription};
+pub use export::{
+    export_cbor, export_jsonl, import_cbor, import_jsonl, CborHeader, ImportedLog, OtlpExporter,
+};
 
 #[cfg(test)]
 mod tests {
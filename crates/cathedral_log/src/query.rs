@@ -0,0 +1,557 @@
+//! Indexed queries over the event log.
+//!
+//! An [`EventIndex`] maps run, node, kind, logical time, and payload hash to
+//! the positions of the events carrying them, so a [`LogQuery`] is answered
+//! from the smallest matching posting list instead of by decoding the whole
+//! stream. [`StreamWriter`] and [`SegmentedStream`] extend their index as
+//! events are written; the segmented stream also keeps it on disk as
+//! fixed-width records next to its segments, each with the location of its
+//! event, so an [`IndexedLog`] answers queries from the file alone.
+//!
+//! [`StreamWriter`]: crate::stream::StreamWriter
+//! [`SegmentedStream`]: crate::stream::SegmentedStream
+//! [`IndexedLog`]: crate::stream::IndexedLog
+
+use crate::cursor::SegmentOffset;
+use crate::event::{Event, EventKind};
+use cathedral_core::{CoreError, CoreResult, Hash, LogicalTime, NodeId, RunId};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Length of an encoded [`IndexEntry`]
+pub const INDEX_RECORD_LEN: usize = 8 + 16 + 16 + 1 + 8 + 32 + 1 + 16;
+
+/// Indexed fields of one event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// Sequence number of the event in its stream
+    pub position: u64,
+    /// Run the event belongs to
+    pub run_id: RunId,
+    /// Node the event belongs to
+    pub node_id: NodeId,
+    /// Event kind
+    pub kind: EventKind,
+    /// Logical time of the event
+    pub logical_time: LogicalTime,
+    /// Hash of the stored payload
+    pub payload_hash: Hash,
+    /// Where the event is stored, for events in a segmented stream
+    pub location: Option<SegmentOffset>,
+}
+
+impl IndexEntry {
+    /// Index the event at `position`
+    #[must_use]
+    pub fn new(position: u64, event: &Event) -> Self {
+        Self {
+            position,
+            run_id: event.run_id,
+            node_id: event.node_id,
+            kind: event.kind,
+            logical_time: event.logical_time,
+            payload_hash: event.payload_hash,
+            location: None,
+        }
+    }
+
+    /// Record where the event is stored
+    #[must_use]
+    pub fn with_location(mut self, location: SegmentOffset) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Encode as a fixed-width record
+    ///
+    /// The kind is written as its one-byte [`EventKind::code`]; a missing
+    /// location is a zero flag byte followed by zeros.
+    #[must_use]
+    pub fn encode(&self) -> [u8; INDEX_RECORD_LEN] {
+        let mut record = [0u8; INDEX_RECORD_LEN];
+        record[..8].copy_from_slice(&self.position.to_be_bytes());
+        record[8..24].copy_from_slice(self.run_id.as_bytes());
+        record[24..40].copy_from_slice(self.node_id.as_bytes());
+        record[40] = self.kind.code();
+        record[41..49].copy_from_slice(&self.logical_time.as_u64().to_be_bytes());
+        record[49..81].copy_from_slice(self.payload_hash.as_bytes());
+        if let Some(location) = self.location {
+            record[81] = 1;
+            record[82..90].copy_from_slice(&location.segment.to_be_bytes());
+            record[90..].copy_from_slice(&location.offset.to_be_bytes());
+        }
+        record
+    }
+
+    /// Decode a fixed-width record
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the record is short, names an unknown kind, or has
+    /// an invalid location flag
+    pub fn decode(data: &[u8]) -> CoreResult<Self> {
+        let invalid = |reason: String| CoreError::Validation {
+            field: "index".to_string(),
+            reason,
+        };
+        let record: &[u8; INDEX_RECORD_LEN] = data
+            .get(..INDEX_RECORD_LEN)
+            .and_then(|record| record.try_into().ok())
+            .ok_or_else(|| {
+                invalid(format!("Index record shorter than {} bytes", INDEX_RECORD_LEN))
+            })?;
+        let kind = EventKind::from_code(record[40])
+            .ok_or_else(|| invalid(format!("Unknown event kind code {}", record[40])))?;
+        let location = match record[81] {
+            0 => None,
+            1 => Some(SegmentOffset {
+                segment: u64::from_be_bytes(bytes_at(record, 82)),
+                offset: u64::from_be_bytes(bytes_at(record, 90)),
+            }),
+            flag => return Err(invalid(format!("Invalid location flag {}", flag))),
+        };
+        Ok(Self {
+            position: u64::from_be_bytes(bytes_at(record, 0)),
+            run_id: RunId::from_bytes(bytes_at(record, 8)),
+            node_id: NodeId::from_bytes(bytes_at(record, 24)),
+            kind,
+            logical_time: LogicalTime::from_raw(u64::from_be_bytes(bytes_at(record, 41))),
+            payload_hash: Hash::from_bytes(bytes_at(record, 49)),
+            location,
+        })
+    }
+}
+
+/// Copy the `N` bytes at `at` out of an index record
+fn bytes_at<const N: usize>(record: &[u8; INDEX_RECORD_LEN], at: usize) -> [u8; N] {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(&record[at..at + N]);
+    bytes
+}
+
+/// Filters selecting events from a log
+///
+/// Every set filter must match; an empty query matches every event. Kinds
+/// are alternatives: an event matches if it has any of them. Queries can
+/// also be parsed from whitespace-separated `key=value` terms, with keys
+/// `run`, `node`, `kind` (repeatable, or comma-separated), `from`, `until`,
+/// `hash`, and `limit`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogQuery {
+    /// Run the events must belong to
+    pub run_id: Option<RunId>,
+    /// Node the events must belong to
+    pub node_id: Option<NodeId>,
+    /// Kinds the events may have
+    pub kinds: Vec<EventKind>,
+    /// Earliest logical time, inclusive
+    pub from: Option<LogicalTime>,
+    /// Latest logical time, inclusive
+    pub until: Option<LogicalTime>,
+    /// Hash the stored payload must have
+    pub payload_hash: Option<Hash>,
+    /// Maximum number of events to return
+    pub limit: Option<usize>,
+}
+
+impl LogQuery {
+    /// Create a query matching every event
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match events of a run
+    #[must_use]
+    pub fn run(mut self, run_id: RunId) -> Self {
+        self.run_id = Some(run_id);
+        self
+    }
+
+    /// Only match events of a node
+    #[must_use]
+    pub fn node(mut self, node_id: NodeId) -> Self {
+        self.node_id = Some(node_id);
+        self
+    }
+
+    /// Also match events of `kind`
+    #[must_use]
+    pub fn kind(mut self, kind: EventKind) -> Self {
+        if !self.kinds.contains(&kind) {
+            self.kinds.push(kind);
+        }
+        self
+    }
+
+    /// Only match events at or after a logical time
+    #[must_use]
+    pub fn from(mut self, from: LogicalTime) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Only match events at or before a logical time
+    #[must_use]
+    pub fn until(mut self, until: LogicalTime) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    /// Only match events whose stored payload has `hash`
+    #[must_use]
+    pub fn payload_hash(mut self, hash: Hash) -> Self {
+        self.payload_hash = Some(hash);
+        self
+    }
+
+    /// Return at most `limit` events
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Check if an indexed event matches every filter
+    #[must_use]
+    pub fn matches(&self, entry: &IndexEntry) -> bool {
+        self.run_id.is_none_or(|run_id| entry.run_id == run_id)
+            && self.node_id.is_none_or(|node_id| entry.node_id == node_id)
+            && (self.kinds.is_empty() || self.kinds.contains(&entry.kind))
+            && self.from.is_none_or(|from| entry.logical_time >= from)
+            && self.until.is_none_or(|until| entry.logical_time <= until)
+            && self.payload_hash.is_none_or(|hash| entry.payload_hash == hash)
+    }
+
+    /// Check if an event matches every filter
+    #[must_use]
+    pub fn matches_event(&self, event: &Event) -> bool {
+        self.matches(&IndexEntry::new(0, event))
+    }
+}
+
+impl FromStr for LogQuery {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut query = Self::new();
+        for term in s.split_whitespace() {
+            let (key, value) = term.split_once('=').ok_or_else(|| parse_error(term, "key=value"))?;
+            query = match key {
+                "run" => query.run(RunId::from_bytes(parse_uuid(value, "run_")?)),
+                "node" => query.node(NodeId::from_bytes(parse_uuid(value, "node_")?)),
+                "kind" => value.split(',').try_fold(query, |query, kind| {
+                    serde_json::from_value(serde_json::Value::String(kind.to_string()))
+                        .map(|kind| query.kind(kind))
+                        .map_err(|_| parse_error(kind, "an event kind"))
+                })?,
+                "from" => query.from(LogicalTime::from_raw(parse_number(value)?)),
+                "until" => query.until(LogicalTime::from_raw(parse_number(value)?)),
+                "hash" => query.payload_hash(
+                    Hash::from_hex(value).map_err(|_| parse_error(value, "a hex hash"))?,
+                ),
+                "limit" => query.limit(parse_number(value)? as usize),
+                _ => return Err(parse_error(key, "a query key")),
+            };
+        }
+        Ok(query)
+    }
+}
+
+fn parse_uuid(value: &str, prefix: &str) -> CoreResult<[u8; 16]> {
+    Uuid::parse_str(value.strip_prefix(prefix).unwrap_or(value))
+        .map(|uuid| *uuid.as_bytes())
+        .map_err(|_| parse_error(value, "an ID"))
+}
+
+fn parse_number(value: &str) -> CoreResult<u64> {
+    value.parse().map_err(|_| parse_error(value, "a number"))
+}
+
+fn parse_error(value: &str, expected: &str) -> CoreError {
+    CoreError::ParseError {
+        message: format!("Invalid query term {:?}: expected {}", value, expected),
+    }
+}
+
+/// Posting lists from indexed fields to event positions
+///
+/// Positions are assigned in insertion order, so every posting list is
+/// sorted and query results come back in stream order.
+#[derive(Debug, Clone, Default)]
+pub struct EventIndex {
+    /// Indexed fields of every event, by position
+    entries: Vec<IndexEntry>,
+    /// Positions by run
+    by_run: HashMap<RunId, Vec<u64>>,
+    /// Positions by node
+    by_node: HashMap<NodeId, Vec<u64>>,
+    /// Positions by kind
+    by_kind: HashMap<EventKind, Vec<u64>>,
+    /// Positions by payload hash
+    by_payload: HashMap<Hash, Vec<u64>>,
+    /// Positions by logical time
+    by_time: BTreeMap<LogicalTime, Vec<u64>>,
+}
+
+impl EventIndex {
+    /// Create an empty index
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load an index written as [`IndexEntry`] records
+    ///
+    /// A missing file is an empty index. Loading stops at a torn final
+    /// record, a record out of sequence, or one that cannot be decoded.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file exists but cannot be read
+    pub fn load(path: impl AsRef<Path>) -> CoreResult<Self> {
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(CoreError::Validation {
+                    field: "index".to_string(),
+                    reason: format!("Failed to read index: {}", e),
+                });
+            }
+        };
+
+        let mut index = Self::new();
+        for record in data.chunks_exact(INDEX_RECORD_LEN) {
+            match IndexEntry::decode(record) {
+                Ok(entry) if entry.position == index.len() => index.push(entry),
+                _ => break,
+            }
+        }
+        Ok(index)
+    }
+
+    /// Index the next event, returning its entry
+    pub fn insert(&mut self, event: &Event) -> IndexEntry {
+        let entry = IndexEntry::new(self.len(), event);
+        self.push(entry);
+        entry
+    }
+
+    /// Index the next event, stored at `location`, returning its entry
+    pub fn insert_at(&mut self, event: &Event, location: SegmentOffset) -> IndexEntry {
+        let entry = IndexEntry::new(self.len(), event).with_location(location);
+        self.push(entry);
+        entry
+    }
+
+    /// Add an entry for the next position
+    pub(crate) fn push(&mut self, entry: IndexEntry) {
+        let position = entry.position;
+        self.by_run.entry(entry.run_id).or_default().push(position);
+        self.by_node.entry(entry.node_id).or_default().push(position);
+        self.by_kind.entry(entry.kind).or_default().push(position);
+        self.by_payload.entry(entry.payload_hash).or_default().push(position);
+        self.by_time.entry(entry.logical_time).or_default().push(position);
+        self.entries.push(entry);
+    }
+
+    /// Get the entry at a position
+    #[must_use]
+    pub fn get(&self, position: u64) -> Option<&IndexEntry> {
+        usize::try_from(position).ok().and_then(|p| self.entries.get(p))
+    }
+
+    /// Get every entry, by position
+    #[must_use]
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Number of indexed events
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.entries.len() as u64
+    }
+
+    /// Check if no event is indexed
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Find the positions of the events matching `query`, in stream order
+    ///
+    /// Candidates come from the shortest posting list among the query's
+    /// filters and are then checked against the rest.
+    #[must_use]
+    pub fn query(&self, query: &LogQuery) -> Vec<u64> {
+        let list = |positions: Option<&Vec<u64>>| positions.cloned().unwrap_or_default();
+        let mut lists = Vec::new();
+        if let Some(run_id) = query.run_id {
+            lists.push(list(self.by_run.get(&run_id)));
+        }
+        if let Some(node_id) = query.node_id {
+            lists.push(list(self.by_node.get(&node_id)));
+        }
+        if let Some(hash) = query.payload_hash {
+            lists.push(list(self.by_payload.get(&hash)));
+        }
+        if !query.kinds.is_empty() {
+            let mut positions: Vec<u64> = query
+                .kinds
+                .iter()
+                .flat_map(|kind| self.by_kind.get(kind).into_iter().flatten().copied())
+                .collect();
+            positions.sort_unstable();
+            lists.push(positions);
+        }
+        if query.from.is_some() || query.until.is_some() {
+            let from = query.from.unwrap_or(LogicalTime::zero());
+            let until = query.until.unwrap_or(LogicalTime::from_raw(u64::MAX));
+            let mut positions: Vec<u64> = if from <= until {
+                self.by_time.range(from..=until).flat_map(|(_, p)| p.iter().copied()).collect()
+            } else {
+                Vec::new()
+            };
+            positions.sort_unstable();
+            lists.push(positions);
+        }
+
+        let candidates = match lists.into_iter().min_by_key(Vec::len) {
+            Some(positions) => positions,
+            None => (0..self.len()).collect(),
+        };
+        candidates
+            .into_iter()
+            .filter(|&position| self.get(position).is_some_and(|entry| query.matches(entry)))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_core::EventId;
+
+    fn event(run_id: RunId, node_id: NodeId, time: u64, kind: EventKind) -> Event {
+        Event::new(EventId::new(), run_id, node_id, LogicalTime::from_raw(time), kind)
+            .with_payload(time.to_be_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_index_entry_round_trip() {
+        for kind in EventKind::ALL {
+            let entry = IndexEntry::new(3, &event(RunId::new(), NodeId::new(), 7, kind));
+            assert_eq!(IndexEntry::decode(&entry.encode()).unwrap(), entry);
+        }
+
+        let event = event(RunId::new(), NodeId::new(), 7, EventKind::SideEffect);
+        let entry = IndexEntry::new(3, &event).with_location(SegmentOffset {
+            segment: 2,
+            offset: 4096,
+        });
+        let record = entry.encode();
+        assert_eq!(record[40], EventKind::SideEffect.code());
+        assert_eq!(IndexEntry::decode(&record).unwrap(), entry);
+        assert!(IndexEntry::decode(&record[1..]).is_err());
+
+        let mut unknown = record;
+        unknown[40] = u8::MAX;
+        let err = IndexEntry::decode(&unknown).unwrap_err();
+        assert!(err.to_string().contains("Unknown event kind code 255"));
+    }
+
+    #[test]
+    fn test_index_query() {
+        let (run_a, run_b) = (RunId::new(), RunId::new());
+        let node = NodeId::new();
+        let events = vec![
+            event(run_a, node, 0, EventKind::RunStarted),
+            event(run_a, node, 1, EventKind::NodeFailed),
+            event(run_b, NodeId::new(), 1, EventKind::NodeCompleted),
+            event(run_a, node, 2, EventKind::NodeCompleted),
+            event(run_b, node, 3, EventKind::NodeFailed),
+        ];
+        let mut index = EventIndex::new();
+        for event in &events {
+            index.insert(event);
+        }
+
+        assert_eq!(index.query(&LogQuery::new()), vec![0, 1, 2, 3, 4]);
+        assert_eq!(index.query(&LogQuery::new().run(run_a)), vec![0, 1, 3]);
+        let failed_or_completed = LogQuery::new()
+            .kind(EventKind::NodeFailed)
+            .kind(EventKind::NodeCompleted);
+        assert_eq!(index.query(&failed_or_completed), vec![1, 2, 3, 4]);
+        assert_eq!(index.query(&failed_or_completed.clone().node(node)), vec![1, 3, 4]);
+        assert_eq!(index.query(&failed_or_completed.limit(2)), vec![1, 2]);
+
+        let window = LogQuery::new()
+            .from(LogicalTime::from_raw(1))
+            .until(LogicalTime::from_raw(2));
+        assert_eq!(index.query(&window), vec![1, 2, 3]);
+        assert_eq!(index.query(&window.run(run_b)), vec![2]);
+
+        let by_payload = LogQuery::new().payload_hash(events[4].payload_hash);
+        assert_eq!(index.query(&by_payload), vec![4]);
+        assert!(index.query(&LogQuery::new().run(RunId::new())).is_empty());
+
+        for (position, event) in events.iter().enumerate() {
+            let matched = index.query(&LogQuery::new().run(run_a)).contains(&(position as u64));
+            assert_eq!(LogQuery::new().run(run_a).matches_event(event), matched);
+        }
+    }
+
+    #[test]
+    fn test_query_parse() {
+        let run_id = RunId::new();
+        let hash = Hash::compute(b"payload");
+        let query: LogQuery = format!(
+            "run={} kind=NodeFailed,NodeSkipped from=3 until=9 hash={} limit=5",
+            run_id,
+            hash.to_hex()
+        )
+        .parse()
+        .unwrap();
+        assert_eq!(
+            query,
+            LogQuery::new()
+                .run(run_id)
+                .kind(EventKind::NodeFailed)
+                .kind(EventKind::NodeSkipped)
+                .from(LogicalTime::from_raw(3))
+                .until(LogicalTime::from_raw(9))
+                .payload_hash(hash)
+                .limit(5)
+        );
+
+        assert_eq!("".parse::<LogQuery>().unwrap(), LogQuery::new());
+        assert!("kind=Nope".parse::<LogQuery>().is_err());
+        assert!("color=red".parse::<LogQuery>().is_err());
+        assert!("run".parse::<LogQuery>().is_err());
+    }
+
+    #[test]
+    fn test_index_load_stops_at_torn_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.idx");
+        assert!(EventIndex::load(&path).unwrap().is_empty());
+
+        let mut index = EventIndex::new();
+        let mut data = Vec::new();
+        for time in 0..3 {
+            let event = event(RunId::new(), NodeId::new(), time, EventKind::Heartbeat);
+            let entry = index.insert(&event);
+            data.extend_from_slice(&entry.encode());
+        }
+        data.truncate(data.len() - 1);
+        std::fs::write(&path, data).unwrap();
+
+        let loaded = EventIndex::load(&path).unwrap();
+        assert_eq!(loaded.entries(), &index.entries()[..2]);
+    }
+}
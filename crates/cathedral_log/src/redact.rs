@@ -0,0 +1,35 @@
+//! Payload redaction applied before events are stored.
+//!
+//! A [`PayloadRedactor`] rewrites an event payload before it reaches the log.
+//! The stored event carries the redacted payload, and its [`Redaction`]
+//! record keeps the hash of the original as a commitment, so the original
+//! can later be shown to match without ever being stored.
+
+use cathedral_core::Hash;
+use serde::{Deserialize, Serialize};
+
+/// Record of a redaction applied to an event payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Redaction {
+    /// Hash of the original, unredacted payload
+    pub original_hash: Hash,
+    /// Names of the rules that changed the payload
+    pub rules: Vec<String>,
+}
+
+impl Redaction {
+    /// Check that `original` is the payload this redaction commits to
+    #[must_use]
+    pub fn verify(&self, original: &[u8]) -> bool {
+        Hash::compute(original) == self.original_hash
+    }
+}
+
+/// Stage rewriting event payloads before storage
+pub trait PayloadRedactor: Send + Sync {
+    /// Redact a payload
+    ///
+    /// Returns the redacted payload and the names of the rules that changed
+    /// it, or `None` if no rule matched.
+    fn redact_payload(&self, payload: &[u8]) -> Option<(Vec<u8>, Vec<String>)>;
+}
@@ -1,7 +1,18 @@
 //! Event stream for sequential event access.
 
-use cathedral_core::{RunId, NodeId, LogicalTime, CoreResult};
-use crate::event::EventKind;
+use cathedral_core::{
+    RunId, NodeId, EventId, LogicalTime, CoreError, CoreResult, Hash, TenantId, WrappedKey,
+};
+use crate::chain::{ChainValidator, HashChain};
+use crate::cursor::{Cursor, Direction, SegmentOffset};
+use crate::encoding::CanonicalEncode;
+use crate::event::{self, EventKind};
+use crate::query::{EventIndex, IndexEntry, LogQuery, INDEX_RECORD_LEN};
+use crate::redact::PayloadRedactor;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
 /// Simplified Event for stream testing
 pub struct Event {
@@ -58,24 +69,66 @@ impl EventStream {
 }
 
 /// Stream writer for appending events
+///
+/// When a redactor is configured, every payload passes through it before
+/// the event is stored. Stored events are indexed as they are written, so
+/// they can be queried with a [`LogQuery`].
 pub struct StreamWriter {
-    events: Vec<Event>,
+    events: Vec<event::Event>,
+    redactor: Option<Box<dyn PayloadRedactor>>,
+    index: EventIndex,
 }
 
 impl StreamWriter {
     pub fn new() -> Self {
-        Self { events: Vec::new() }
+        Self {
+            events: Vec::new(),
+            redactor: None,
+            index: EventIndex::new(),
+        }
+    }
+
+    /// Redact event payloads before they are stored
+    #[must_use]
+    pub fn with_redactor(mut self, redactor: impl PayloadRedactor + 'static) -> Self {
+        self.redactor = Some(Box::new(redactor));
+        self
     }
 
-    pub fn write(&mut self, event: Event) {
+    pub fn write(&mut self, event: event::Event) {
+        let redacted = self
+            .redactor
+            .as_ref()
+            .and_then(|redactor| redactor.redact_payload(&event.payload));
+        let event = match redacted {
+            Some((payload, rules)) => event.with_redaction(payload, rules),
+            None => event,
+        };
+        self.index.insert(&event);
         self.events.push(event);
     }
 
-    pub fn events(&self) -> &[Event] {
+    pub fn events(&self) -> &[event::Event] {
         &self.events
     }
 
-    pub fn finalize(self) -> Vec<Event> {
+    /// Get the index of the events written so far
+    #[must_use]
+    pub fn index(&self) -> &EventIndex {
+        &self.index
+    }
+
+    /// Find the written events matching `query`, in write order
+    #[must_use]
+    pub fn query(&self, query: &LogQuery) -> Vec<&event::Event> {
+        self.index
+            .query(query)
+            .into_iter()
+            .filter_map(|position| self.events.get(position as usize))
+            .collect()
+    }
+
+    pub fn finalize(self) -> Vec<event::Event> {
         self.events
     }
 }
@@ -86,6 +139,789 @@ impl Default for StreamWriter {
     }
 }
 
+/// Record header: payload length (u32, big-endian) and payload hash
+const RECORD_HEADER_LEN: usize = 4 + 32;
+
+/// File extension of segment files
+pub const SEGMENT_EXTENSION: &str = "seg";
+
+/// Name of the event index file in the stream directory
+pub const INDEX_FILE: &str = "events.idx";
+
+/// Name of the manifest file in the stream directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Configuration for a [`SegmentedStream`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentConfig {
+    /// Directory holding the segment files
+    pub dir: PathBuf,
+    /// Size in bytes after which appends rotate to a new segment
+    pub max_segment_bytes: u64,
+    /// Whether every append is fsynced before it returns
+    pub fsync: bool,
+    /// Data key of the run, wrapped under a master key
+    ///
+    /// Recorded in the stream's [`StreamManifest`] when the stream is
+    /// opened, and filled in from it when reopening without one.
+    pub wrapped_key: Option<WrappedKey>,
+}
+
+impl SegmentConfig {
+    /// Create a config with 64 MiB segments and fsync on append
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_segment_bytes: 64 * 1024 * 1024,
+            fsync: true,
+            wrapped_key: None,
+        }
+    }
+
+    /// Create a config for a run's log under `root`
+    ///
+    /// Logs are kept in `root/<tenant>/runs/<run>`, so each tenant's runs
+    /// live in a directory of their own.
+    #[must_use]
+    pub fn for_run(root: impl AsRef<Path>, tenant: TenantId, run_id: RunId) -> Self {
+        Self::new(root.as_ref().join(tenant.to_string()).join("runs").join(run_id.to_string()))
+    }
+
+    /// Set the segment size that triggers rotation
+    #[must_use]
+    pub fn with_max_segment_bytes(mut self, bytes: u64) -> Self {
+        self.max_segment_bytes = bytes;
+        self
+    }
+
+    /// Enable or disable fsync on append
+    #[must_use]
+    pub fn with_fsync(mut self, fsync: bool) -> Self {
+        self.fsync = fsync;
+        self
+    }
+
+    /// Record the run's wrapped data key in the stream's manifest
+    #[must_use]
+    pub fn with_wrapped_key(mut self, key: WrappedKey) -> Self {
+        self.wrapped_key = Some(key);
+        self
+    }
+}
+
+/// Metadata kept in [`MANIFEST_FILE`] next to a stream's segments
+///
+/// The data key a run's blobs are sealed with is only persisted here,
+/// wrapped, so the blobs can be opened again after a restart by whoever
+/// holds the master key.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct StreamManifest {
+    /// Data key of the run, if its data is encrypted
+    pub wrapped_key: Option<WrappedKey>,
+}
+
+impl StreamManifest {
+    /// Load the manifest of the stream in `dir`, if it has one
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the manifest exists but cannot be read or parsed
+    pub fn load(dir: impl AsRef<Path>) -> CoreResult<Option<Self>> {
+        match std::fs::read(dir.as_ref().join(MANIFEST_FILE)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the manifest of the stream in `dir`, replacing it atomically
+    fn store(&self, dir: &Path) -> CoreResult<()> {
+        let temp = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        let mut file = File::create(&temp)?;
+        file.write_all(&serde_json::to_vec_pretty(self)?)?;
+        file.sync_all()?;
+        std::fs::rename(&temp, dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+}
+
+/// What [`SegmentedStream::open`] found on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RecoveryReport {
+    /// Number of segments
+    pub segments: usize,
+    /// Number of intact events recovered
+    pub events: u64,
+    /// Bytes of torn tail removed from the last segment
+    pub truncated_bytes: u64,
+}
+
+/// What [`SegmentedStream::verify`] or [`SegmentedStream::repair`] found
+///
+/// Also the JSON payload of the [`EventKind::Truncation`] marker appended
+/// after a repair.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RepairReport {
+    /// Number of events in the longest valid prefix
+    pub valid_events: u64,
+    /// Number of readable events after the prefix
+    pub dropped_events: u64,
+    /// Bytes of segment data after the prefix
+    pub dropped_bytes: u64,
+    /// What ended the prefix, if the log is damaged
+    pub reason: Option<String>,
+}
+
+impl RepairReport {
+    /// Check if the whole log is valid
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.reason.is_none()
+    }
+}
+
+/// Where the valid prefix of a stream ends
+struct Damage {
+    /// Segment and offset of the first invalid record
+    at: SegmentOffset,
+    /// Segment IDs on disk
+    ids: Vec<u64>,
+}
+
+/// Append-only, file-backed event stream split into segments
+///
+/// Each record is framed as `[len: u32][blake3(payload)][payload]`, where
+/// the payload is the canonical encoding of the event. Appends go to the
+/// newest segment and rotate once it would exceed the configured size.
+/// On open, a torn or corrupt tail in the newest segment (a crash mid-write)
+/// is truncated; damage in an older, sealed segment is an error. Record
+/// hashes are folded into a [`HashChain`], and events carrying state hashes
+/// must link to their predecessor.
+///
+/// Every append also adds a record to the [`EventIndex`] kept in
+/// [`INDEX_FILE`]. On open the file is checked against the recovered
+/// events: records past a crash or disagreeing with the segments are
+/// dropped and the missing ones rewritten.
+pub struct SegmentedStream {
+    /// Stream configuration
+    config: SegmentConfig,
+    /// Location of every event, by sequence number
+    index: Vec<SegmentOffset>,
+    /// Segment being appended to
+    active: File,
+    /// ID of the active segment
+    active_id: u64,
+    /// Length of the active segment in bytes
+    active_len: u64,
+    /// Number of segments
+    segments: usize,
+    /// Chain of record hashes
+    chain: HashChain,
+    /// State hash continuity across events
+    validator: ChainValidator,
+    /// Queryable index of the events
+    events: EventIndex,
+    /// Index file being appended to
+    index_file: File,
+}
+
+impl SegmentedStream {
+    /// Open or create a stream, recovering from any torn tail
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory cannot be read, a sealed segment is
+    /// corrupt, the recovered events break the state hash chain, the index
+    /// file cannot be repaired, or the manifest holds a different data key
+    /// than the config
+    pub fn open(mut config: SegmentConfig) -> CoreResult<(Self, RecoveryReport)> {
+        std::fs::create_dir_all(&config.dir)?;
+        config.wrapped_key = manifest_key(&config)?;
+
+        let ids = segment_ids(&config.dir)?;
+        let mut index = Vec::new();
+        let mut chain = HashChain::new();
+        let mut validator = ChainValidator::new();
+        let mut recovered = Vec::new();
+        let mut report = RecoveryReport {
+            segments: ids.len().max(1),
+            ..RecoveryReport::default()
+        };
+        let mut active_len = 0;
+
+        for (position, &id) in ids.iter().enumerate() {
+            let path = segment_path(&config.dir, id);
+            let data = std::fs::read(&path)?;
+
+            let mut offset = 0;
+            while let Some((event, hash, len)) = decode_record(&data[offset..]) {
+                validator
+                    .validate_event(&event)
+                    .map_err(|_| CoreError::BrokenChain { position: index.len() })?;
+                chain.push(hash)?;
+                let location = SegmentOffset {
+                    segment: id,
+                    offset: offset as u64,
+                };
+                recovered.push(IndexEntry::new(index.len() as u64, &event).with_location(location));
+                index.push(location);
+                offset += len;
+            }
+
+            if offset < data.len() {
+                if position + 1 < ids.len() {
+                    return Err(CoreError::Validation {
+                        field: "segment".to_string(),
+                        reason: format!("Corrupt record in sealed segment {} at offset {}", id, offset),
+                    });
+                }
+                let file = OpenOptions::new()
+                    .write(true)
+                    .open(&path)?;
+                file.set_len(offset as u64)
+                    .and_then(|()| file.sync_all())?;
+                report.truncated_bytes = (data.len() - offset) as u64;
+            }
+            active_len = offset as u64;
+        }
+
+        let active_id = ids.last().copied().unwrap_or(0);
+        let active = open_segment(&config, active_id)?;
+        let (events, index_file) = repair_index(&config, recovered)?;
+        report.events = index.len() as u64;
+
+        Ok((
+            Self {
+                config,
+                index,
+                active,
+                active_id,
+                active_len,
+                segments: report.segments,
+                chain,
+                validator,
+                events,
+                index_file,
+            },
+            report,
+        ))
+    }
+
+    /// Find the longest valid prefix of a stream without changing it
+    ///
+    /// Unlike [`SegmentedStream::open`], damage anywhere is reported rather
+    /// than rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the segments cannot be read
+    pub fn verify(config: &SegmentConfig) -> CoreResult<RepairReport> {
+        Self::scan(config).map(|(report, _)| report)
+    }
+
+    /// Truncate a damaged stream to its longest valid prefix and open it
+    ///
+    /// Everything after the prefix is removed, later segments included, and
+    /// an [`EventKind::Truncation`] event recording the [`RepairReport`] is
+    /// appended. An intact stream is opened unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the segments cannot be read or rewritten
+    pub fn repair(config: SegmentConfig) -> CoreResult<(Self, RepairReport)> {
+        let (report, damage) = Self::scan(&config)?;
+        if let Some(damage) = damage {
+            let path = segment_path(&config.dir, damage.at.segment);
+            let file = OpenOptions::new()
+                .write(true)
+                .open(&path)?;
+            file.set_len(damage.at.offset)
+                .and_then(|()| file.sync_all())?;
+            for &id in damage.ids.iter().filter(|&&id| id > damage.at.segment) {
+                std::fs::remove_file(segment_path(&config.dir, id))?;
+            }
+        }
+
+        let (mut stream, _) = Self::open(config)?;
+        if !report.is_clean() {
+            let last = match stream.len() {
+                0 => None,
+                len => stream.read(len - 1)?,
+            };
+            let (run_id, node_id, time) = last.map_or(
+                (RunId::from_bytes([0; 16]), NodeId::from_bytes([0; 16]), 0),
+                |event| (event.run_id, event.node_id, event.logical_time.as_u64() + 1),
+            );
+            let payload = serde_json::to_vec(&report)?;
+            let marker = event::Event::new(
+                EventId::new(),
+                run_id,
+                node_id,
+                LogicalTime::from_raw(time),
+                EventKind::Truncation,
+            )
+            .with_payload(payload);
+            stream.append(&marker)?;
+        }
+        Ok((stream, report))
+    }
+
+    /// Read every segment up to the first corrupt record or broken link
+    fn scan(config: &SegmentConfig) -> CoreResult<(RepairReport, Option<Damage>)> {
+        let ids = if config.dir.exists() {
+            segment_ids(&config.dir)?
+        } else {
+            Vec::new()
+        };
+        let mut records = Vec::new();
+        let mut events = Vec::new();
+        let mut total_bytes = 0;
+        let mut corrupt = None;
+
+        for &id in &ids {
+            let path = segment_path(&config.dir, id);
+            let data = std::fs::read(&path)?;
+            total_bytes += data.len() as u64;
+            if corrupt.is_some() {
+                continue;
+            }
+
+            let mut offset = 0;
+            while let Some((event, _, len)) = decode_record(&data[offset..]) {
+                records.push((SegmentOffset { segment: id, offset: offset as u64 }, len as u64));
+                events.push(event);
+                offset += len;
+            }
+            if offset < data.len() {
+                corrupt = Some((
+                    SegmentOffset { segment: id, offset: offset as u64 },
+                    format!("Corrupt record in segment {} at offset {}", id, offset),
+                ));
+            }
+        }
+
+        let prefix = ChainValidator::new().verify_prefix(&events);
+        let (at, reason) = match (prefix.error, corrupt) {
+            (Some(err), _) => (Some(records[prefix.valid].0), Some(err.to_string())),
+            (None, Some((at, reason))) => (Some(at), Some(reason)),
+            (None, None) => (None, None),
+        };
+        let valid_bytes: u64 = records[..prefix.valid].iter().map(|(_, len)| len).sum();
+        let report = RepairReport {
+            valid_events: prefix.valid as u64,
+            dropped_events: (events.len() - prefix.valid) as u64,
+            dropped_bytes: total_bytes - valid_bytes,
+            reason,
+        };
+        Ok((report, at.map(|at| Damage { at, ids })))
+    }
+
+    /// Append an event, rotating to a new segment when the active one is full
+    ///
+    /// If writing the record fails, the segment is truncated back to its
+    /// previous length, so a partial record never precedes later ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the event breaks the state hash chain or the write
+    /// or fsync fails
+    pub fn append(&mut self, event: &event::Event) -> CoreResult<SegmentOffset> {
+        let mut validator = self.validator.clone();
+        validator
+            .validate_event(event)
+            .map_err(|_| CoreError::BrokenChain { position: self.index.len() })?;
+
+        let payload = event.encode();
+        let len = u32::try_from(payload.len()).map_err(|_| CoreError::EncodingOverflow)?;
+        let hash = Hash::compute(&payload);
+        let record_len = (RECORD_HEADER_LEN + payload.len()) as u64;
+
+        if self.active_len > 0 && self.active_len + record_len > self.config.max_segment_bytes {
+            self.rotate()?;
+        }
+
+        let mut record = Vec::with_capacity(record_len as usize);
+        record.extend_from_slice(&len.to_be_bytes());
+        record.extend_from_slice(hash.as_bytes());
+        record.extend_from_slice(&payload);
+        let written = self.active.write_all(&record).and_then(|()| {
+            if self.config.fsync {
+                self.active.sync_data()
+            } else {
+                Ok(())
+            }
+        });
+        if let Err(e) = written {
+            self.active.set_len(self.active_len)?;
+            return Err(e.into());
+        }
+
+        let location = SegmentOffset {
+            segment: self.active_id,
+            offset: self.active_len,
+        };
+        self.active_len += record_len;
+        self.index.push(location);
+        self.chain.push(hash)?;
+        self.validator = validator;
+
+        // A crash before the index record lands is repaired on open
+        let entry = self.events.insert_at(event, location);
+        self.index_file
+            .write_all(&entry.encode())?;
+        if self.config.fsync {
+            self.index_file
+                .sync_data()?;
+        }
+        Ok(location)
+    }
+
+    /// Read the event at a sequence number
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the segment cannot be read or the record is corrupt
+    pub fn read(&self, position: u64) -> CoreResult<Option<event::Event>> {
+        match usize::try_from(position).ok().and_then(|p| self.index.get(p)) {
+            Some(location) => read_record(&self.config.dir, *location).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Read the event under `cursor` and move it one step in its direction
+    ///
+    /// A forward cursor reads the event at its position; a backward cursor
+    /// reads the event before it. Cursors cross segment boundaries
+    /// transparently.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the event cannot be read
+    pub fn read_next(&self, cursor: &mut Cursor) -> CoreResult<Option<event::Event>> {
+        match cursor.direction {
+            Direction::Forward => {
+                let event = self.read(cursor.pos())?;
+                if event.is_some() {
+                    cursor.move_forward(1);
+                }
+                Ok(event)
+            }
+            Direction::Backward if cursor.pos() == 0 => Ok(None),
+            Direction::Backward => {
+                let event = self.read(cursor.pos() - 1)?;
+                cursor.move_backward(1);
+                Ok(event)
+            }
+        }
+    }
+
+    /// Read the events matching `query`, in stream order
+    ///
+    /// Only the matching records are read from the segments.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a matching event cannot be read
+    pub fn query(&self, query: &LogQuery) -> CoreResult<Vec<event::Event>> {
+        self.events
+            .query(query)
+            .into_iter()
+            .filter_map(|position| self.read(position).transpose())
+            .collect()
+    }
+
+    /// Get the index of the events
+    #[must_use]
+    pub fn event_index(&self) -> &EventIndex {
+        &self.events
+    }
+
+    /// Get the location of the event at a sequence number
+    #[must_use]
+    pub fn location(&self, position: u64) -> Option<SegmentOffset> {
+        usize::try_from(position).ok().and_then(|p| self.index.get(p)).copied()
+    }
+
+    /// Get the chain of record hashes
+    #[must_use]
+    pub fn chain(&self) -> &HashChain {
+        &self.chain
+    }
+
+    /// Data key of the run, wrapped, as recorded in its manifest
+    #[must_use]
+    pub fn wrapped_key(&self) -> Option<&WrappedKey> {
+        self.config.wrapped_key.as_ref()
+    }
+
+    /// Number of segments
+    #[must_use]
+    pub fn segment_count(&self) -> usize {
+        self.segments
+    }
+
+    /// Number of events
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.index.len() as u64
+    }
+
+    /// Check if the stream has no events
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Seal the active segment and start a new one
+    fn rotate(&mut self) -> CoreResult<()> {
+        self.active
+            .sync_all()?;
+        self.active_id += 1;
+        self.active = open_segment(&self.config, self.active_id)?;
+        self.active_len = 0;
+        self.segments += 1;
+        Ok(())
+    }
+}
+
+/// Read-only view of a segmented stream, served from its index file
+///
+/// Opening loads only [`INDEX_FILE`]; queries are answered from the index
+/// and just the matching records are read from the segments, so nothing is
+/// decoded up front and nothing is written. Events appended after the last
+/// index record (a crash before it landed) are not visible until
+/// [`SegmentedStream::open`] repairs the index.
+pub struct IndexedLog {
+    /// Directory holding the segment and index files
+    dir: PathBuf,
+    /// Index loaded from the index file
+    index: EventIndex,
+    /// Data key recorded in the manifest
+    wrapped_key: Option<WrappedKey>,
+}
+
+impl IndexedLog {
+    /// Load the index of the stream in `dir`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `dir` is not a directory or the index or manifest
+    /// file cannot be read
+    pub fn open(dir: impl AsRef<Path>) -> CoreResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        if !dir.is_dir() {
+            return Err(CoreError::NotFound {
+                kind: "log".to_string(),
+                id: dir.display().to_string(),
+            });
+        }
+        let index = EventIndex::load(dir.join(INDEX_FILE))?;
+        let wrapped_key = StreamManifest::load(&dir)?.and_then(|manifest| manifest.wrapped_key);
+        Ok(Self { dir, index, wrapped_key })
+    }
+
+    /// Get the loaded index
+    #[must_use]
+    pub fn index(&self) -> &EventIndex {
+        &self.index
+    }
+
+    /// Data key of the run, wrapped, as recorded in its manifest
+    #[must_use]
+    pub fn wrapped_key(&self) -> Option<&WrappedKey> {
+        self.wrapped_key.as_ref()
+    }
+
+    /// Number of indexed events
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        self.index.len()
+    }
+
+    /// Check if no event is indexed
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Read the event at a sequence number
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the record cannot be read or does not match its
+    /// index record
+    pub fn read(&self, position: u64) -> CoreResult<Option<event::Event>> {
+        let Some(entry) = self.index.get(position) else {
+            return Ok(None);
+        };
+        let stale = || CoreError::Validation {
+            field: "index".to_string(),
+            reason: format!("Index record {} does not match the log", position),
+        };
+        let location = entry.location.ok_or_else(stale)?;
+        let event = read_record(&self.dir, location)?;
+        if IndexEntry::new(position, &event).with_location(location) != *entry {
+            return Err(stale());
+        }
+        Ok(Some(event))
+    }
+
+    /// Read the events matching `query`, in stream order
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a matching event cannot be read
+    pub fn query(&self, query: &LogQuery) -> CoreResult<Vec<event::Event>> {
+        self.index
+            .query(query)
+            .into_iter()
+            .filter_map(|position| self.read(position).transpose())
+            .collect()
+    }
+}
+
+/// Read and decode the record at `location` in the stream in `dir`
+fn read_record(dir: &Path, location: SegmentOffset) -> CoreResult<event::Event> {
+    let mut file = File::open(segment_path(dir, location.segment))?;
+    file.seek(SeekFrom::Start(location.offset))?;
+    let mut header = [0u8; RECORD_HEADER_LEN];
+    file.read_exact(&mut header)?;
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let mut record = header.to_vec();
+    record.resize(RECORD_HEADER_LEN + len, 0);
+    file.read_exact(&mut record[RECORD_HEADER_LEN..])?;
+
+    decode_record(&record)
+        .map(|(event, _, _)| event)
+        .ok_or(CoreError::InvalidEncoding)
+}
+
+/// Decode one record, returning the event, its hash, and the record length
+///
+/// Returns `None` for a short, mismatched, or undecodable record.
+fn decode_record(data: &[u8]) -> Option<(event::Event, Hash, usize)> {
+    let header = data.get(..RECORD_HEADER_LEN)?;
+    let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let payload = data.get(RECORD_HEADER_LEN..RECORD_HEADER_LEN + len)?;
+    let hash = Hash::compute(payload);
+    if hash.as_bytes()[..] != header[4..] {
+        return None;
+    }
+    let event = postcard::from_bytes(payload).ok()?;
+    Some((event, hash, RECORD_HEADER_LEN + len))
+}
+
+/// Reconcile the config's data key with the one in the stream's manifest
+///
+/// A key given for a stream without one is recorded; a stream that has one
+/// keeps it, and a different key is refused rather than overwriting it.
+fn manifest_key(config: &SegmentConfig) -> CoreResult<Option<WrappedKey>> {
+    let stored = StreamManifest::load(&config.dir)?.and_then(|manifest| manifest.wrapped_key);
+    match (stored, &config.wrapped_key) {
+        (Some(stored), Some(given)) if stored != *given => Err(CoreError::Validation {
+            field: "wrapped_key".to_string(),
+            reason: format!(
+                "Stream in {} already has a data key for run {}",
+                config.dir.display(),
+                stored.run_id
+            ),
+        }),
+        (Some(stored), _) => Ok(Some(stored)),
+        (None, Some(given)) => {
+            let manifest = StreamManifest {
+                wrapped_key: Some(given.clone()),
+            };
+            manifest.store(&config.dir)?;
+            Ok(Some(given.clone()))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// List segment IDs in a directory in ascending order
+fn segment_ids(dir: &Path) -> CoreResult<Vec<u64>> {
+    let entries = std::fs::read_dir(dir)?;
+    let mut ids = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let id = path
+            .extension()
+            .filter(|ext| *ext == SEGMENT_EXTENSION)
+            .and_then(|_| path.file_stem()?.to_str()?.parse::<u64>().ok());
+        ids.extend(id);
+    }
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Bring the index file in line with the recovered events and open it
+///
+/// The longest prefix of stored records that agrees with `recovered` is
+/// kept; everything after it is rewritten. When the whole file agrees, the
+/// stored index is served as is rather than rebuilt.
+fn repair_index(
+    config: &SegmentConfig,
+    recovered: Vec<IndexEntry>,
+) -> CoreResult<(EventIndex, File)> {
+    let path = config.dir.join(INDEX_FILE);
+    let stored = EventIndex::load(&path)?;
+    let valid = stored
+        .entries()
+        .iter()
+        .zip(&recovered)
+        .take_while(|(stored, recovered)| stored == recovered)
+        .count();
+    let events = if valid == recovered.len() && stored.len() == valid as u64 {
+        stored
+    } else {
+        let mut events = EventIndex::new();
+        for entry in recovered {
+            events.push(entry);
+        }
+        events
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    let valid_len = (valid * INDEX_RECORD_LEN) as u64;
+    let len = file
+        .metadata()?
+        .len();
+    if len != valid_len || valid < events.entries().len() {
+        file.set_len(valid_len)?;
+        let missing: Vec<u8> = events.entries()[valid..]
+            .iter()
+            .flat_map(IndexEntry::encode)
+            .collect();
+        file.write_all(&missing)
+            .and_then(|()| file.sync_all())?;
+    }
+    Ok((events, file))
+}
+
+/// Path of a segment file
+fn segment_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", id, SEGMENT_EXTENSION))
+}
+
+/// Open a segment for appending, creating it and syncing the directory if new
+fn open_segment(config: &SegmentConfig, id: u64) -> CoreResult<File> {
+    let path = segment_path(&config.dir, id);
+    let created = !path.exists();
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    if created && config.fsync {
+        File::open(&config.dir)
+            .and_then(|dir| dir.sync_all())?;
+    }
+    Ok(file)
+}
+
 /// Stream errors
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StreamError {
@@ -143,7 +979,330 @@ mod tests {
     #[test]
     fn test_writer() {
         let mut writer = StreamWriter::new();
-        writer.write(make_test_event(0));
+        writer.write(chained_events(1).remove(0));
         assert_eq!(writer.events.len(), 1);
+        assert!(!writer.events[0].is_redacted());
+    }
+
+    /// Masks every byte equal to `0x01`
+    struct MaskOnes;
+
+    impl PayloadRedactor for MaskOnes {
+        fn redact_payload(&self, payload: &[u8]) -> Option<(Vec<u8>, Vec<String>)> {
+            payload.contains(&1).then(|| {
+                let masked = payload.iter().map(|&b| if b == 1 { 0 } else { b }).collect();
+                (masked, vec!["ones".to_string()])
+            })
+        }
+    }
+
+    #[test]
+    fn test_writer_redacts_payloads() {
+        let events = chained_events(3);
+        let mut writer = StreamWriter::new().with_redactor(MaskOnes);
+        for event in events.clone() {
+            writer.write(event);
+        }
+
+        let stored = writer.finalize();
+        assert_eq!(stored[0], events[0]);
+        assert_eq!(stored[2], events[2]);
+
+        let redacted = &stored[1];
+        assert_eq!(redacted.payload, vec![0; 32]);
+        assert_eq!(redacted.payload_hash, Hash::compute(&redacted.payload));
+        let redaction = redacted.redaction.as_ref().unwrap();
+        assert_eq!(redaction.rules, vec!["ones".to_string()]);
+        assert!(redaction.verify(&events[1].payload));
+        assert!(!redaction.verify(&redacted.payload));
+    }
+
+    /// Events whose state hashes form a chain starting from `Hash::empty()`
+    fn chained_events(count: u64) -> Vec<event::Event> {
+        let run_id = RunId::new();
+        let node_id = NodeId::new();
+        let mut state = Hash::empty();
+        (0..count)
+            .map(|i| {
+                let next = Hash::compute(&i.to_be_bytes());
+                let event = event::Event::new(
+                    cathedral_core::EventId::new(),
+                    run_id,
+                    node_id,
+                    LogicalTime::from_raw(i),
+                    EventKind::NodeCompleted,
+                )
+                .with_payload(vec![i as u8; 32])
+                .with_state_hashes(state, next);
+                state = next;
+                event
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_segmented_stream_rotates_and_reopens() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SegmentConfig::new(dir.path()).with_max_segment_bytes(256);
+        let events = chained_events(10);
+
+        let (mut stream, report) = SegmentedStream::open(config.clone()).unwrap();
+        assert_eq!(report, RecoveryReport { segments: 1, ..RecoveryReport::default() });
+        for event in &events {
+            stream.append(event).unwrap();
+        }
+        assert!(stream.segment_count() > 1);
+        let root = stream.chain().root();
+        drop(stream);
+
+        let (stream, report) = SegmentedStream::open(config).unwrap();
+        assert_eq!(report.events, 10);
+        assert_eq!(report.truncated_bytes, 0);
+        assert!(report.segments > 1);
+        assert_eq!(stream.chain().root(), root);
+        assert_ne!(
+            stream.location(0).unwrap().segment,
+            stream.location(9).unwrap().segment
+        );
+
+        let mut cursor = Cursor::new();
+        let mut forward = Vec::new();
+        while let Some(event) = stream.read_next(&mut cursor).unwrap() {
+            forward.push(event);
+        }
+        assert_eq!(forward, events);
+
+        let mut cursor = Cursor::at(stream.len()).with_direction(Direction::Backward);
+        let mut backward = Vec::new();
+        while let Some(event) = stream.read_next(&mut cursor).unwrap() {
+            backward.push(event);
+        }
+        backward.reverse();
+        assert_eq!(backward, events);
+    }
+
+    #[test]
+    fn test_segmented_stream_truncates_torn_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SegmentConfig::new(dir.path());
+        let events = chained_events(4);
+
+        let (mut stream, _) = SegmentedStream::open(config.clone()).unwrap();
+        for event in &events[..3] {
+            stream.append(event).unwrap();
+        }
+        let root = stream.chain().root();
+        let end = stream.location(2).unwrap();
+        drop(stream);
+
+        // Simulate a crash midway through writing the fourth record
+        let path = segment_path(dir.path(), end.segment);
+        let full = std::fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        let payload = events[3].encode();
+        file.write_all(&(payload.len() as u32).to_be_bytes()).unwrap();
+        file.write_all(&Hash::compute(&payload).as_bytes()[..]).unwrap();
+        file.write_all(&payload[..payload.len() / 2]).unwrap();
+        drop(file);
+
+        let (mut stream, report) = SegmentedStream::open(config.clone()).unwrap();
+        assert_eq!(report.events, 3);
+        assert!(report.truncated_bytes > 0);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), full);
+        assert_eq!(stream.chain().root(), root);
+
+        stream.append(&events[3]).unwrap();
+        drop(stream);
+        let (stream, report) = SegmentedStream::open(config).unwrap();
+        assert_eq!(report.truncated_bytes, 0);
+        assert_eq!(stream.read(3).unwrap().unwrap(), events[3]);
+    }
+
+    #[test]
+    fn test_segment_config_for_run_separates_tenants() {
+        let run_id = RunId::new();
+        let a = SegmentConfig::for_run("/data", TenantId::from_name("a"), run_id);
+        let b = SegmentConfig::for_run("/data", TenantId::from_name("b"), run_id);
+        assert_ne!(a.dir, b.dir);
+        assert!(a.dir.ends_with(Path::new("runs").join(run_id.to_string())));
+    }
+
+    #[test]
+    fn test_segmented_stream_query_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SegmentConfig::new(dir.path()).with_max_segment_bytes(256);
+        let events = chained_events(6);
+        let query = LogQuery::new()
+            .run(events[0].run_id)
+            .from(LogicalTime::from_raw(2))
+            .until(LogicalTime::from_raw(4));
+
+        let (mut stream, _) = SegmentedStream::open(config.clone()).unwrap();
+        for event in &events[..4] {
+            stream.append(event).unwrap();
+        }
+        assert_eq!(stream.query(&query).unwrap(), events[2..4]);
+        drop(stream);
+
+        // Lose the last index record and tear the one before it
+        let path = dir.path().join(INDEX_FILE);
+        let len = std::fs::metadata(&path).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len - INDEX_RECORD_LEN as u64 - 5).unwrap();
+        drop(file);
+
+        let (mut stream, _) = SegmentedStream::open(config.clone()).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len);
+        assert_eq!(stream.event_index().len(), 4);
+        for event in &events[4..] {
+            stream.append(event).unwrap();
+        }
+        drop(stream);
+
+        let (stream, _) = SegmentedStream::open(config).unwrap();
+        assert_eq!(stream.query(&query).unwrap(), events[2..5]);
+        assert!(stream.query(&query.kind(EventKind::NodeFailed)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_indexed_log_serves_queries_from_index_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SegmentConfig::new(dir.path()).with_max_segment_bytes(256);
+        let events = chained_events(6);
+        let (mut stream, _) = SegmentedStream::open(config).unwrap();
+        for event in &events {
+            stream.append(event).unwrap();
+        }
+
+        let log = IndexedLog::open(dir.path()).unwrap();
+        assert_eq!(log.len(), 6);
+        assert_eq!(log.index().entries(), stream.event_index().entries());
+        let query = LogQuery::new()
+            .from(LogicalTime::from_raw(1))
+            .until(LogicalTime::from_raw(3));
+        assert_eq!(log.query(&query).unwrap(), events[1..4]);
+        assert_eq!(log.read(5).unwrap().unwrap(), events[5]);
+        assert!(log.read(6).unwrap().is_none());
+
+        // An index record pointing at another event is refused
+        let path = dir.path().join(INDEX_FILE);
+        let mut data = std::fs::read(&path).unwrap();
+        let (first, second) = data.split_at_mut(INDEX_RECORD_LEN);
+        first[81..].copy_from_slice(&second[81..INDEX_RECORD_LEN]);
+        std::fs::write(&path, data).unwrap();
+        let log = IndexedLog::open(dir.path()).unwrap();
+        assert!(log.read(0).is_err());
+        assert!(IndexedLog::open(dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_segmented_stream_rejects_corrupt_sealed_segment() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SegmentConfig::new(dir.path()).with_max_segment_bytes(128);
+
+        let (mut stream, _) = SegmentedStream::open(config.clone()).unwrap();
+        for event in &chained_events(4) {
+            stream.append(event).unwrap();
+        }
+        assert!(stream.segment_count() > 1);
+        drop(stream);
+
+        let path = segment_path(dir.path(), 0);
+        let mut data = std::fs::read(&path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        std::fs::write(&path, data).unwrap();
+
+        assert!(SegmentedStream::open(config).is_err());
+    }
+
+    #[test]
+    fn test_segmented_stream_repairs_to_valid_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SegmentConfig::new(dir.path()).with_max_segment_bytes(128);
+        let events = chained_events(6);
+
+        let (mut stream, _) = SegmentedStream::open(config.clone()).unwrap();
+        for event in &events {
+            stream.append(event).unwrap();
+        }
+        let damaged = stream.location(2).unwrap();
+        let segments = stream.segment_count();
+        drop(stream);
+        assert!(SegmentedStream::verify(&config).unwrap().is_clean());
+
+        // Flip a byte inside the third record, in a sealed segment
+        let path = segment_path(dir.path(), damaged.segment);
+        let mut data = std::fs::read(&path).unwrap();
+        data[damaged.offset as usize + RECORD_HEADER_LEN] ^= 0xff;
+        std::fs::write(&path, data).unwrap();
+        assert!(SegmentedStream::open(config.clone()).is_err());
+
+        let report = SegmentedStream::verify(&config).unwrap();
+        assert_eq!(report.valid_events, 2);
+        assert!(report.dropped_bytes > 0);
+        assert!(report.reason.as_deref().unwrap().contains("Corrupt record"));
+
+        let (stream, repaired) = SegmentedStream::repair(config.clone()).unwrap();
+        assert_eq!(repaired, report);
+        assert_eq!(stream.len(), 3);
+        assert!(stream.segment_count() < segments);
+        let marker = stream.read(2).unwrap().unwrap();
+        assert_eq!(marker.kind, EventKind::Truncation);
+        assert_eq!(serde_json::from_slice::<RepairReport>(&marker.payload).unwrap(), report);
+        drop(stream);
+
+        let (stream, recovery) = SegmentedStream::open(config.clone()).unwrap();
+        assert_eq!(recovery.events, 3);
+        assert_eq!(stream.read(1).unwrap().unwrap(), events[1]);
+        assert_eq!(stream.event_index().len(), 3);
+        assert!(SegmentedStream::verify(&config).unwrap().is_clean());
+    }
+
+    #[test]
+    fn test_segmented_stream_rejects_broken_state_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut stream, _) = SegmentedStream::open(SegmentConfig::new(dir.path())).unwrap();
+        let events = chained_events(3);
+
+        stream.append(&events[0]).unwrap();
+        let err = stream.append(&events[2]).unwrap_err();
+        assert_eq!(err, CoreError::BrokenChain { position: 1 });
+        assert_eq!(stream.len(), 1);
+        stream.append(&events[1]).unwrap();
+        assert_eq!(stream.len(), 2);
+    }
+
+    #[test]
+    fn test_segmented_stream_persists_wrapped_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = WrappedKey {
+            run_id: RunId::new(),
+            master_key_id: "primary".to_string(),
+            nonce: [7; 24],
+            sealed_key: vec![1, 2, 3],
+        };
+        let config = SegmentConfig::new(dir.path()).with_wrapped_key(key.clone());
+        let (mut stream, _) = SegmentedStream::open(config).unwrap();
+        stream.append(&chained_events(1)[0]).unwrap();
+        assert_eq!(stream.wrapped_key(), Some(&key));
+        drop(stream);
+
+        // Reopening without the key recovers it from the manifest
+        let (stream, _) = SegmentedStream::open(SegmentConfig::new(dir.path())).unwrap();
+        assert_eq!(stream.wrapped_key(), Some(&key));
+        assert_eq!(stream.len(), 1);
+        drop(stream);
+        assert_eq!(IndexedLog::open(dir.path()).unwrap().wrapped_key(), Some(&key));
+
+        // A different key never replaces the recorded one
+        let other = WrappedKey {
+            sealed_key: vec![4, 5, 6],
+            ..key.clone()
+        };
+        let config = SegmentConfig::new(dir.path()).with_wrapped_key(other);
+        assert!(SegmentedStream::open(config).is_err());
+        let manifest = StreamManifest::load(dir.path()).unwrap().unwrap();
+        assert_eq!(manifest.wrapped_key, Some(key));
     }
 }
@@ -0,0 +1,256 @@
+//! Live subscriptions to a growing event stream.
+//!
+//! A [`LiveStream`] owns a [`SegmentedStream`] and announces every append
+//! to its subscribers. A [`Subscription`] is a forward [`Cursor`] that,
+//! instead of ending at the tail, waits for the next event to be appended,
+//! like `tail -f`.
+//!
+//! Delivery is pull-based: a subscriber reads events from the segments at
+//! its own pace and nothing is queued for it, so a slow subscriber only
+//! falls behind (see [`Subscription::lag`]) and never holds up the writer.
+
+use crate::cursor::{Cursor, Direction, SegmentOffset};
+use crate::event::Event;
+use crate::stream::SegmentedStream;
+use cathedral_core::{CoreError, CoreResult};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use tokio::sync::watch;
+
+/// Segmented stream shared between one writer and any number of subscribers
+pub struct LiveStream {
+    /// Underlying stream
+    stream: Arc<RwLock<SegmentedStream>>,
+    /// Number of events, published after every append
+    appended: watch::Sender<u64>,
+}
+
+impl LiveStream {
+    /// Share a stream with subscribers
+    #[must_use]
+    pub fn new(stream: SegmentedStream) -> Self {
+        let (appended, _) = watch::channel(stream.len());
+        Self {
+            stream: Arc::new(RwLock::new(stream)),
+            appended,
+        }
+    }
+
+    /// Append an event and wake subscribers waiting for it
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the underlying append fails
+    pub fn append(&self, event: &Event) -> CoreResult<SegmentOffset> {
+        let mut stream = write(&self.stream)?;
+        let location = stream.append(event)?;
+        self.appended.send_replace(stream.len());
+        Ok(location)
+    }
+
+    /// Read the event at a sequence number
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the record cannot be read
+    pub fn read(&self, position: u64) -> CoreResult<Option<Event>> {
+        read(&self.stream)?.read(position)
+    }
+
+    /// Number of events
+    #[must_use]
+    pub fn len(&self) -> u64 {
+        *self.appended.borrow()
+    }
+
+    /// Check if the stream has no events
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of open subscriptions
+    #[must_use]
+    pub fn subscriber_count(&self) -> usize {
+        self.appended.receiver_count()
+    }
+
+    /// Follow the stream from `cursor`
+    ///
+    /// Subscriptions always read forward; the cursor's direction is ignored.
+    #[must_use]
+    pub fn subscribe(&self, cursor: Cursor) -> Subscription {
+        Subscription {
+            stream: Arc::clone(&self.stream),
+            appended: self.appended.subscribe(),
+            cursor: cursor.with_direction(Direction::Forward),
+        }
+    }
+}
+
+/// Cursor following a [`LiveStream`] as it grows
+///
+/// Once the [`LiveStream`] is dropped, the remaining events can still be
+/// read and [`Subscription::next`] returns `None` at the tail.
+pub struct Subscription {
+    /// Stream being followed
+    stream: Arc<RwLock<SegmentedStream>>,
+    /// Number of events, as last published
+    appended: watch::Receiver<u64>,
+    /// Position of the next event to deliver
+    cursor: Cursor,
+}
+
+impl Subscription {
+    /// Sequence number of the next event to deliver
+    #[must_use]
+    pub fn position(&self) -> u64 {
+        self.cursor.pos()
+    }
+
+    /// Number of appended events not yet delivered
+    #[must_use]
+    pub fn lag(&self) -> u64 {
+        self.appended.borrow().saturating_sub(self.cursor.pos())
+    }
+
+    /// Read the next event if it has already been appended
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the record cannot be read
+    pub fn try_next(&mut self) -> CoreResult<Option<Event>> {
+        read(&self.stream)?.read_next(&mut self.cursor)
+    }
+
+    /// Wait for the next event
+    ///
+    /// Returns `None` once the writer is gone and every event was delivered.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the record cannot be read
+    pub async fn next(&mut self) -> CoreResult<Option<Event>> {
+        loop {
+            if let Some(event) = self.try_next()? {
+                return Ok(Some(event));
+            }
+            let position = self.cursor.pos();
+            if self.appended.wait_for(|&len| len > position).await.is_err() {
+                return self.try_next();
+            }
+        }
+    }
+
+    /// Wait for the next event, then take up to `max` events without waiting
+    ///
+    /// Returns an empty batch once the writer is gone and every event was
+    /// delivered.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a record cannot be read
+    pub async fn next_batch(&mut self, max: usize) -> CoreResult<Vec<Event>> {
+        let mut batch = Vec::new();
+        if max == 0 {
+            return Ok(batch);
+        }
+        if let Some(event) = self.next().await? {
+            batch.push(event);
+        }
+        while batch.len() < max && !batch.is_empty() {
+            match self.try_next()? {
+                Some(event) => batch.push(event),
+                None => break,
+            }
+        }
+        Ok(batch)
+    }
+}
+
+fn read(stream: &RwLock<SegmentedStream>) -> CoreResult<RwLockReadGuard<'_, SegmentedStream>> {
+    stream.read().map_err(CoreError::from)
+}
+
+fn write(stream: &RwLock<SegmentedStream>) -> CoreResult<RwLockWriteGuard<'_, SegmentedStream>> {
+    stream.write().map_err(CoreError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventKind;
+    use crate::stream::SegmentConfig;
+    use cathedral_core::{EventId, LogicalTime, NodeId, RunId};
+    use std::time::Duration;
+
+    fn events(count: u64) -> Vec<Event> {
+        let (run_id, node_id) = (RunId::new(), NodeId::new());
+        (0..count)
+            .map(|time| {
+                Event::new(
+                    EventId::new(),
+                    run_id,
+                    node_id,
+                    LogicalTime::from_raw(time),
+                    EventKind::Heartbeat,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_subscription_follows_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let (stream, _) = SegmentedStream::open(SegmentConfig::new(dir.path())).unwrap();
+        let live = Arc::new(LiveStream::new(stream));
+        let events = events(5);
+        live.append(&events[0]).unwrap();
+
+        let mut subscription = live.subscribe(Cursor::new());
+        assert_eq!(live.subscriber_count(), 1);
+        assert_eq!(subscription.next().await.unwrap().as_ref(), Some(&events[0]));
+        assert!(subscription.try_next().unwrap().is_none());
+
+        let writer = {
+            let live = Arc::clone(&live);
+            let events = events.clone();
+            tokio::spawn(async move {
+                for event in &events[1..] {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    live.append(event).unwrap();
+                }
+            })
+        };
+        let mut received = Vec::new();
+        while received.len() < 4 {
+            received.push(subscription.next().await.unwrap().unwrap());
+        }
+        writer.await.unwrap();
+        assert_eq!(received, events[1..]);
+        assert_eq!(subscription.lag(), 0);
+
+        // The tail is still delivered after the writer goes away
+        let mut late = live.subscribe(Cursor::at(3));
+        drop(live);
+        assert_eq!(late.next_batch(10).await.unwrap(), events[3..]);
+        assert!(late.next().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slow_subscriber_lags_without_blocking_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let (stream, _) = SegmentedStream::open(SegmentConfig::new(dir.path())).unwrap();
+        let live = LiveStream::new(stream);
+        let mut subscription = live.subscribe(Cursor::new());
+        let events = events(6);
+        for event in &events {
+            live.append(event).unwrap();
+        }
+        assert_eq!(subscription.lag(), 6);
+
+        assert_eq!(subscription.next_batch(4).await.unwrap(), events[..4]);
+        assert_eq!(subscription.position(), 4);
+        assert_eq!(subscription.lag(), 2);
+        assert_eq!(subscription.next_batch(4).await.unwrap(), events[4..]);
+    }
+}
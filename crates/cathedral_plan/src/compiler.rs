@@ -1,8 +1,14 @@
 //! Compiler from DSL AST to executable DAG.
 
-use cathedral_core::{NodeId, Capability, CoreResult};
-use indexmap::IndexSet;
-use super::dag::{Dag, Node, Edge, NodeKind, ResourceRequirements};
+use cathedral_core::{NodeId, Capability, CoreError, CoreResult, Hash};
+use cathedral_tool::ToolRegistry;
+use indexmap::{IndexMap, IndexSet};
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::control::{list_element, Aggregation, Literal, Predicate, ANY_SCHEMA};
+use super::dag::{Dag, Node, Edge, NodeKind, ResourceRequirements, ELSE_PORT, THEN_PORT};
+use super::library::WorkflowLibrary;
+use super::grant::{least_privilege, CapabilityGrantTable};
 
 /// Output from compiling a workflow
 #[derive(Debug, Clone)]
@@ -11,6 +17,8 @@ pub struct CompilerOutput {
     pub dag: Dag,
     /// Compilation warnings
     pub warnings: Vec<CompilerWarning>,
+    /// Least-privilege capabilities for each node
+    pub grants: CapabilityGrantTable,
 }
 
 /// Compilation warning
@@ -22,39 +30,115 @@ pub enum CompilerWarning {
     Deprecated { feature: String },
     /// Resource limit might be exceeded
     ResourceLimit { resource: String },
+    /// Workflow declares no statements and compiles to an empty DAG
+    EmptyWorkflow,
+    /// Node requests a capability its tool's schema never uses
+    UnusedCapability {
+        node_id: NodeId,
+        tool: String,
+        capability: Capability,
+    },
+    /// Tool's schema uses a capability the node did not request
+    UngrantedCapability {
+        node_id: NodeId,
+        tool: String,
+        capability: Capability,
+    },
 }
 
 /// Compiler for transforming AST to DAG
 pub struct Compiler {
-    /// Next node ID counter
+    /// Counter of nodes created directly by the current statement
     next_id: u64,
+    /// Seed of the current statement's node IDs
+    ///
+    /// Starts as the workflow fingerprint and is narrowed by each nested
+    /// statement's content, so IDs do not depend on source order.
+    scope: Hash,
+    /// Registry used to narrow node capabilities to tool schemas
+    registry: Option<Arc<ToolRegistry>>,
+    /// Compiled workflows that can be invoked as sub-workflows
+    workflows: Option<Arc<WorkflowLibrary>>,
+    /// Schemas of the bindings declared so far in the current compilation
+    bindings: IndexMap<String, String>,
 }
 
 impl Compiler {
     /// Create a new compiler
     #[must_use]
     pub fn new() -> Self {
-        Self { next_id: 0 }
+        Self {
+            next_id: 0,
+            scope: Hash::empty(),
+            registry: None,
+            workflows: None,
+            bindings: IndexMap::new(),
+        }
+    }
+
+    /// Narrow each tool node's capabilities to its schema in `registry`
+    #[must_use]
+    pub fn with_registry(mut self, registry: Arc<ToolRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Resolve sub-workflow invocations against `workflows`
+    #[must_use]
+    pub fn with_workflows(mut self, workflows: Arc<WorkflowLibrary>) -> Self {
+        self.workflows = Some(workflows);
+        self
     }
 
     /// Compile an AST to a DAG
     ///
+    /// Node IDs are derived from the workflow's fingerprint and the content
+    /// of the statement creating them, so compiling the same workflow again
+    /// gives the same DAG and content hash, and reordering independent
+    /// statements keeps every statement's node IDs.
+    ///
     /// # Errors
     ///
     /// Returns error if compilation fails
     pub fn compile(&mut self, ast: &Ast) -> CoreResult<CompilerOutput> {
         let mut dag = Dag::new();
         let mut warnings = Vec::new();
+        self.bindings.clear();
+        self.scope = ast.fingerprint();
+        self.next_id = 0;
+
+        if ast.statements.is_empty() {
+            warnings.push(CompilerWarning::EmptyWorkflow);
+        }
 
         // Compile each statement in the AST
-        for stmt in &ast.statements {
-            self.compile_statement(stmt, &mut dag, &mut warnings)?;
+        for (stmt, key) in ast.statements.iter().zip(sibling_keys(&ast.statements)) {
+            self.compile_scoped(stmt, key, &mut dag, &mut warnings)?;
         }
 
         // Validate the resulting DAG
         dag.validate()?;
 
-        Ok(CompilerOutput { dag, warnings })
+        let (grants, grant_warnings) = least_privilege(&dag, self.registry.as_deref());
+        warnings.extend(grant_warnings);
+
+        Ok(CompilerOutput { dag, warnings, grants })
+    }
+
+    /// Compile a statement with node IDs seeded by its sibling key
+    fn compile_scoped(
+        &mut self,
+        stmt: &Statement,
+        key: Hash,
+        dag: &mut Dag,
+        warnings: &mut Vec<CompilerWarning>,
+    ) -> CoreResult<NodeId> {
+        let outer = (self.scope, self.next_id);
+        self.scope = self.scope.chain(&key);
+        self.next_id = 0;
+        let result = self.compile_statement(stmt, dag, warnings);
+        (self.scope, self.next_id) = outer;
+        result
     }
 
     /// Compile a single statement
@@ -65,12 +149,20 @@ impl Compiler {
         warnings: &mut Vec<CompilerWarning>,
     ) -> CoreResult<NodeId> {
         match stmt {
-            Statement::ToolCall { name, args, .. } => {
+            Statement::ToolCall { name, version_req, args, output } => {
+                if let Some(output) = output {
+                    self.bindings.insert(output.clone(), ANY_SCHEMA.to_string());
+                }
+                let input_binding = match args.first() {
+                    Some(Expr::Variable(var)) => Some(var.clone()),
+                    _ => None,
+                };
                 let node = Node {
                     id: self.next_node_id(),
                     kind: NodeKind::Tool {
                         name: name.clone(),
-                        version: "1.0.0".to_string(),
+                        version_req: version_req.clone().unwrap_or_else(|| "*".to_string()),
+                        input_binding,
                     },
                     dependencies: IndexSet::new(),
                     capabilities: self.infer_capabilities(name, args),
@@ -80,7 +172,8 @@ impl Compiler {
                 dag.add_node(node)?;
                 Ok(id)
             }
-            Statement::Input { name, .. } => {
+            Statement::Input { name, schema } => {
+                self.bindings.insert(name.clone(), schema.clone());
                 let node = Node {
                     id: self.next_node_id(),
                     kind: NodeKind::Input {
@@ -110,8 +203,8 @@ impl Compiler {
             }
             Statement::Sequence { statements } => {
                 let mut prev_id = None;
-                for stmt in statements {
-                    let id = self.compile_statement(stmt, dag, warnings)?;
+                for (stmt, key) in statements.iter().zip(sibling_keys(statements)) {
+                    let id = self.compile_scoped(stmt, key, dag, warnings)?;
                     if let Some(prev) = prev_id {
                         dag.add_edge(Edge::new(prev, id))?;
                     }
@@ -122,7 +215,8 @@ impl Compiler {
             Statement::Parallel { branches } => {
                 let branch_ids: Vec<NodeId> = branches
                     .iter()
-                    .map(|stmt| self.compile_statement(stmt, dag, warnings))
+                    .zip(sibling_keys(branches))
+                    .map(|(stmt, key)| self.compile_scoped(stmt, key, dag, warnings))
                     .collect::<CoreResult<Vec<_>>>()?;
 
                 // Create a parallel aggregation node
@@ -146,7 +240,209 @@ impl Compiler {
 
                 Ok(agg_id)
             }
+            Statement::Conditional { binding, predicate, then_branch, else_branch } => {
+                predicate.check_schema(self.binding_schema(binding)?)?;
+                let cond_id = self.next_node_id();
+                dag.add_node(Node {
+                    id: cond_id,
+                    kind: NodeKind::Conditional {
+                        input_binding: binding.clone(),
+                        predicate: predicate.clone(),
+                    },
+                    dependencies: IndexSet::new(),
+                    capabilities: Vec::new(),
+                    resources: ResourceRequirements::new(),
+                })?;
+
+                // Each branch sees the bindings declared before the condition
+                let before = self.bindings.clone();
+                let then_tail =
+                    self.compile_branch(cond_id, THEN_PORT, then_branch, dag, warnings)?;
+                let then_bindings = std::mem::replace(&mut self.bindings, before.clone());
+                let else_tail =
+                    self.compile_branch(cond_id, ELSE_PORT, else_branch, dag, warnings)?;
+                let else_bindings = std::mem::replace(&mut self.bindings, before);
+                self.join_branch_bindings(then_bindings, &else_bindings)?;
+
+                // Join the branches; only the taken one runs
+                let join_id = self.next_node_id();
+                let tails: IndexSet<NodeId> = [then_tail, else_tail].into_iter().collect();
+                dag.add_node(Node {
+                    id: join_id,
+                    kind: NodeKind::Reduce {
+                        function: "merge".to_string(),
+                        initial: Vec::new(),
+                    },
+                    dependencies: tails.clone(),
+                    capabilities: Vec::new(),
+                    resources: ResourceRequirements::new(),
+                })?;
+                for tail in tails {
+                    dag.add_edge(Edge::new(tail, join_id))?;
+                }
+
+                Ok(join_id)
+            }
+            Statement::MapReduce { name, version_req, over, max_parallel, aggregate, output } => {
+                let schema = self.binding_schema(over)?;
+                if schema != ANY_SCHEMA && list_element(schema).is_none() {
+                    return Err(CoreError::Validation {
+                        field: "map".to_string(),
+                        reason: format!(
+                            "cannot map over '{}' of type {}, expected a list",
+                            over, schema
+                        ),
+                    });
+                }
+                if *max_parallel == 0 {
+                    return Err(CoreError::Validation {
+                        field: "parallel".to_string(),
+                        reason: "map parallelism must be at least 1".to_string(),
+                    });
+                }
+                if let Some(output) = output {
+                    self.bindings.insert(output.clone(), aggregate.output_schema());
+                }
+
+                let node = Node {
+                    id: self.next_node_id(),
+                    kind: NodeKind::MapReduce {
+                        name: name.clone(),
+                        version_req: version_req.clone().unwrap_or_else(|| "*".to_string()),
+                        input_binding: over.clone(),
+                        max_parallel: *max_parallel,
+                        aggregate: *aggregate,
+                    },
+                    dependencies: IndexSet::new(),
+                    capabilities: self.infer_capabilities(name, &[]),
+                    resources: ResourceRequirements::new(),
+                };
+                let id = node.id;
+                dag.add_node(node)?;
+                Ok(id)
+            }
+            Statement::SubWorkflow { hash, input, output } => {
+                let workflows = self.workflows.clone();
+                let child = workflows
+                    .as_deref()
+                    .and_then(|workflows| workflows.get(hash))
+                    .ok_or_else(|| CoreError::NotFound {
+                        kind: "Workflow".to_string(),
+                        id: hash.to_hex(),
+                    })?;
+                if let Some(input) = input {
+                    self.binding_schema(input)?;
+                }
+
+                // The invocation needs whatever the invoked nodes need
+                let mut capabilities = Vec::new();
+                for cap in child.nodes.values().flat_map(|n| &n.capabilities) {
+                    if !capabilities.contains(cap) {
+                        capabilities.push(cap.clone());
+                    }
+                }
+                let node = Node {
+                    id: self.next_node_id(),
+                    kind: NodeKind::SubWorkflow {
+                        hash: *hash,
+                        contract: child.resource_contract(),
+                        input_binding: input.clone(),
+                    },
+                    dependencies: IndexSet::new(),
+                    capabilities,
+                    resources: ResourceRequirements::new(),
+                };
+                if let Some(output) = output {
+                    self.bindings.insert(output.clone(), ANY_SCHEMA.to_string());
+                }
+                let id = node.id;
+                dag.add_node(node)?;
+                Ok(id)
+            }
+        }
+    }
+
+    /// Compile one branch of a conditional as a sequence
+    ///
+    /// Returns the branch's last node, or the conditional itself if the
+    /// branch is empty.
+    fn compile_branch(
+        &mut self,
+        cond_id: NodeId,
+        port: &str,
+        statements: &[Statement],
+        dag: &mut Dag,
+        warnings: &mut Vec<CompilerWarning>,
+    ) -> CoreResult<NodeId> {
+        // Keep the two branches' node IDs apart when they hold the same steps
+        let branch = Hash::compute(port.as_bytes());
+        let mut prev_id = None;
+        for (stmt, key) in statements.iter().zip(sibling_keys(statements)) {
+            let id = self.compile_scoped(stmt, branch.chain(&key), dag, warnings)?;
+            match prev_id {
+                Some(prev) => dag.add_edge(Edge::new(prev, id))?,
+                None => dag.add_edge(Edge::branch(cond_id, id, port))?,
+            }
+            prev_id = Some(id);
+        }
+        Ok(prev_id.unwrap_or(cond_id))
+    }
+
+    /// Bind what both branches of a conditional bound
+    ///
+    /// A binding introduced by only one branch, or with a different known
+    /// type in each, would have no single type after the join.
+    fn join_branch_bindings(
+        &mut self,
+        then_bindings: IndexMap<String, String>,
+        else_bindings: &IndexMap<String, String>,
+    ) -> CoreResult<()> {
+        let branch_error = |name: &str, reason: String| CoreError::Validation {
+            field: format!("binding '{}'", name),
+            reason,
+        };
+        for (name, schema) in &then_bindings {
+            if self.bindings.contains_key(name) {
+                continue;
+            }
+            let joined = match else_bindings.get(name) {
+                None => {
+                    return Err(branch_error(
+                        name,
+                        "is only bound when the condition holds".to_string(),
+                    ));
+                }
+                Some(other) if other == schema => schema.clone(),
+                Some(other) if other == ANY_SCHEMA || schema == ANY_SCHEMA => {
+                    ANY_SCHEMA.to_string()
+                }
+                Some(other) => {
+                    return Err(branch_error(
+                        name,
+                        format!("is {} when the condition holds but {} otherwise", schema, other),
+                    ));
+                }
+            };
+            self.bindings.insert(name.clone(), joined);
         }
+        if let Some(name) = else_bindings.keys().find(|name| !self.bindings.contains_key(*name)) {
+            return Err(branch_error(
+                name,
+                "is only bound when the condition does not hold".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Look up the schema of a declared binding
+    fn binding_schema(&self, name: &str) -> CoreResult<&str> {
+        self.bindings
+            .get(name)
+            .map(String::as_str)
+            .ok_or_else(|| CoreError::NotFound {
+                kind: "binding".to_string(),
+                id: name.to_string(),
+            })
     }
 
     /// Infer capabilities required for a tool call
@@ -170,11 +466,13 @@ impl Compiler {
         }
     }
 
-    /// Generate the next node ID
+    /// Derive the next node ID from the statement's scope and counter
     fn next_node_id(&mut self) -> NodeId {
-        let id = NodeId::new(); // In real implementation, use deterministic IDs
+        let hash = self.scope.chain(&Hash::compute(&self.next_id.to_le_bytes()));
         self.next_id += 1;
-        id
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&hash.as_bytes()[..16]);
+        NodeId::from_bytes(bytes)
     }
 }
 
@@ -190,6 +488,7 @@ pub enum Statement {
     /// Tool invocation
     ToolCall {
         name: String,
+        version_req: Option<String>,
         args: Vec<Expr>,
         output: Option<String>,
     },
@@ -211,6 +510,28 @@ pub enum Statement {
     Parallel {
         branches: Vec<Statement>,
     },
+    /// Branch on a prior binding
+    Conditional {
+        binding: String,
+        predicate: Predicate,
+        then_branch: Vec<Statement>,
+        else_branch: Vec<Statement>,
+    },
+    /// Fan a tool out over a list binding and aggregate the results
+    MapReduce {
+        name: String,
+        version_req: Option<String>,
+        over: String,
+        max_parallel: usize,
+        aggregate: Aggregation,
+        output: Option<String>,
+    },
+    /// Invoke a compiled workflow by content hash
+    SubWorkflow {
+        hash: Hash,
+        input: Option<String>,
+        output: Option<String>,
+    },
 }
 
 /// Expression
@@ -247,6 +568,322 @@ impl Ast {
     pub fn add_statement(&mut self, stmt: Statement) {
         self.statements.push(stmt);
     }
+
+    /// Return the canonical form of this AST
+    ///
+    /// Top-level statements and parallel branches are sorted by their
+    /// canonical encoding, except that a statement never moves past one it
+    /// shares a binding with, since bindings flow in source order.
+    /// Sequences keep their order.
+    #[must_use]
+    pub fn canonicalize(&self) -> Self {
+        Self {
+            statements: canonical_order(&self.statements),
+        }
+    }
+
+    /// Compute the workflow fingerprint over the canonical form
+    #[must_use]
+    pub fn fingerprint(&self) -> Hash {
+        let mut buf = Vec::new();
+        for stmt in &self.canonicalize().statements {
+            stmt.encode_canonical(&mut buf);
+        }
+        Hash::compute(&buf)
+    }
+}
+
+/// Canonicalize and sort statements whose relative order is not semantic
+///
+/// Two statements depend on each other when one binds a name the other
+/// reads or binds. Dependent statements keep their source order; among the
+/// statements whose earlier dependencies are all placed, the one with the
+/// smallest encoding goes next.
+fn canonical_order(statements: &[Statement]) -> Vec<Statement> {
+    let keyed: Vec<(Vec<u8>, Statement)> = statements
+        .iter()
+        .map(|stmt| {
+            let stmt = stmt.canonicalize();
+            let mut key = Vec::new();
+            stmt.encode_canonical(&mut key);
+            (key, stmt)
+        })
+        .collect();
+    let usage: Vec<BindingUsage> = statements.iter().map(BindingUsage::of).collect();
+    let mut waiting: Vec<usize> = (0..keyed.len())
+        .map(|j| (0..j).filter(|&i| usage[i].conflicts(&usage[j])).count())
+        .collect();
+    let mut placed = vec![false; keyed.len()];
+    let mut order = Vec::with_capacity(keyed.len());
+    while let Some(next) = (0..keyed.len())
+        .filter(|&i| !placed[i] && waiting[i] == 0)
+        .min_by(|&a, &b| keyed[a].0.cmp(&keyed[b].0))
+    {
+        placed[next] = true;
+        for later in next + 1..keyed.len() {
+            if usage[next].conflicts(&usage[later]) {
+                waiting[later] -= 1;
+            }
+        }
+        order.push(next);
+    }
+    order.into_iter().map(|i| keyed[i].1.clone()).collect()
+}
+
+/// Bindings a statement reads and binds
+#[derive(Default)]
+struct BindingUsage {
+    reads: IndexSet<String>,
+    binds: IndexSet<String>,
+}
+
+impl BindingUsage {
+    /// Collect the bindings `stmt` and its nested statements use
+    fn of(stmt: &Statement) -> Self {
+        let mut usage = Self::default();
+        usage.add(stmt);
+        usage
+    }
+
+    fn add(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::ToolCall { args, output, .. } => {
+                args.iter().for_each(|arg| self.add_expr(arg));
+                self.binds.extend(output.clone());
+            }
+            Statement::Input { name, .. } => {
+                self.binds.insert(name.clone());
+            }
+            Statement::Output { value, .. } => self.add_expr(value),
+            Statement::Sequence { statements: nested } | Statement::Parallel { branches: nested } => {
+                nested.iter().for_each(|stmt| self.add(stmt));
+            }
+            Statement::Conditional { binding, then_branch, else_branch, .. } => {
+                self.reads.insert(binding.clone());
+                then_branch.iter().chain(else_branch).for_each(|stmt| self.add(stmt));
+            }
+            Statement::MapReduce { over, output, .. } => {
+                self.reads.insert(over.clone());
+                self.binds.extend(output.clone());
+            }
+            Statement::SubWorkflow { input, output, .. } => {
+                self.reads.extend(input.clone());
+                self.binds.extend(output.clone());
+            }
+        }
+    }
+
+    fn add_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable(name) => {
+                self.reads.insert(name.clone());
+            }
+            Expr::Call { args, .. } => args.iter().for_each(|arg| self.add_expr(arg)),
+            Expr::String(_) | Expr::Integer(_) => {}
+        }
+    }
+
+    /// Whether swapping the two statements could change what they mean
+    fn conflicts(&self, other: &Self) -> bool {
+        self.binds
+            .iter()
+            .any(|name| other.reads.contains(name) || other.binds.contains(name))
+            || other.binds.iter().any(|name| self.reads.contains(name))
+    }
+}
+
+/// Key each statement by its canonical encoding and occurrence
+///
+/// Identical siblings are told apart by how many came before them; they
+/// are interchangeable, so this never depends on the order of others.
+fn sibling_keys(statements: &[Statement]) -> Vec<Hash> {
+    let mut seen: HashMap<Vec<u8>, u64> = HashMap::new();
+    statements
+        .iter()
+        .map(|stmt| {
+            let mut key = Vec::new();
+            stmt.canonicalize().encode_canonical(&mut key);
+            let occurrence = seen.entry(key.clone()).or_insert(0);
+            key.extend_from_slice(&occurrence.to_le_bytes());
+            *occurrence += 1;
+            Hash::compute(&key)
+        })
+        .collect()
+}
+
+/// Append a length-prefixed string
+fn encode_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u64).to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+impl Statement {
+    /// Return the canonical form of this statement
+    #[must_use]
+    pub fn canonicalize(&self) -> Self {
+        match self {
+            Statement::Sequence { statements } => Statement::Sequence {
+                statements: statements.iter().map(Statement::canonicalize).collect(),
+            },
+            Statement::Parallel { branches } => Statement::Parallel {
+                branches: canonical_order(branches),
+            },
+            Statement::Conditional { binding, predicate, then_branch, else_branch } => {
+                Statement::Conditional {
+                    binding: binding.clone(),
+                    predicate: predicate.clone(),
+                    then_branch: then_branch.iter().map(Statement::canonicalize).collect(),
+                    else_branch: else_branch.iter().map(Statement::canonicalize).collect(),
+                }
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// Append the canonical byte encoding of this statement
+    pub fn encode_canonical(&self, buf: &mut Vec<u8>) {
+        match self {
+            Statement::ToolCall { name, version_req, args, output } => {
+                buf.push(0);
+                encode_str(buf, name);
+                match version_req {
+                    Some(req) => {
+                        buf.push(1);
+                        encode_str(buf, req);
+                    }
+                    None => buf.push(0),
+                }
+                buf.extend_from_slice(&(args.len() as u64).to_le_bytes());
+                for arg in args {
+                    arg.encode_canonical(buf);
+                }
+                match output {
+                    Some(output) => {
+                        buf.push(1);
+                        encode_str(buf, output);
+                    }
+                    None => buf.push(0),
+                }
+            }
+            Statement::Input { name, schema } => {
+                buf.push(1);
+                encode_str(buf, name);
+                encode_str(buf, schema);
+            }
+            Statement::Output { name, value } => {
+                buf.push(2);
+                encode_str(buf, name);
+                value.encode_canonical(buf);
+            }
+            Statement::Sequence { statements } => {
+                buf.push(3);
+                buf.extend_from_slice(&(statements.len() as u64).to_le_bytes());
+                for stmt in statements {
+                    stmt.encode_canonical(buf);
+                }
+            }
+            Statement::Parallel { branches } => {
+                buf.push(4);
+                buf.extend_from_slice(&(branches.len() as u64).to_le_bytes());
+                for stmt in branches {
+                    stmt.encode_canonical(buf);
+                }
+            }
+            Statement::Conditional { binding, predicate, then_branch, else_branch } => {
+                buf.push(5);
+                encode_str(buf, binding);
+                encode_predicate(buf, predicate);
+                for branch in [then_branch, else_branch] {
+                    buf.extend_from_slice(&(branch.len() as u64).to_le_bytes());
+                    for stmt in branch {
+                        stmt.encode_canonical(buf);
+                    }
+                }
+            }
+            Statement::MapReduce { name, version_req, over, max_parallel, aggregate, output } => {
+                buf.push(6);
+                encode_str(buf, name);
+                for field in [version_req, output] {
+                    match field {
+                        Some(value) => {
+                            buf.push(1);
+                            encode_str(buf, value);
+                        }
+                        None => buf.push(0),
+                    }
+                }
+                encode_str(buf, over);
+                buf.extend_from_slice(&(*max_parallel as u64).to_le_bytes());
+                encode_str(buf, aggregate.keyword());
+            }
+            Statement::SubWorkflow { hash, input, output } => {
+                buf.push(7);
+                buf.extend_from_slice(hash.as_bytes());
+                for field in [input, output] {
+                    match field {
+                        Some(value) => {
+                            buf.push(1);
+                            encode_str(buf, value);
+                        }
+                        None => buf.push(0),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Append the canonical byte encoding of a branch predicate
+fn encode_predicate(buf: &mut Vec<u8>, predicate: &Predicate) {
+    match predicate {
+        Predicate::Truthy => buf.push(0),
+        Predicate::Compare { op, value } => {
+            buf.push(1);
+            encode_str(buf, op.token());
+            match value {
+                Literal::Bool(b) => {
+                    buf.push(0);
+                    buf.push(u8::from(*b));
+                }
+                Literal::Integer(i) => {
+                    buf.push(1);
+                    buf.extend_from_slice(&i.to_le_bytes());
+                }
+                Literal::String(s) => {
+                    buf.push(2);
+                    encode_str(buf, s);
+                }
+            }
+        }
+    }
+}
+
+impl Expr {
+    /// Append the canonical byte encoding of this expression
+    pub fn encode_canonical(&self, buf: &mut Vec<u8>) {
+        match self {
+            Expr::String(s) => {
+                buf.push(0);
+                encode_str(buf, s);
+            }
+            Expr::Integer(i) => {
+                buf.push(1);
+                buf.extend_from_slice(&i.to_le_bytes());
+            }
+            Expr::Variable(name) => {
+                buf.push(2);
+                encode_str(buf, name);
+            }
+            Expr::Call { function, args } => {
+                buf.push(3);
+                encode_str(buf, function);
+                buf.extend_from_slice(&(args.len() as u64).to_le_bytes());
+                for arg in args {
+                    arg.encode_canonical(buf);
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -281,7 +918,13 @@ mod tests {
         let ast = Ast::new();
         let result = compiler.compile(&ast);
         assert!(result.is_ok());
-        assert!(result.unwrap().dag.is_empty());
+        let output = result.unwrap();
+        assert!(output.dag.is_empty());
+        assert_eq!(output.warnings, vec![CompilerWarning::EmptyWorkflow]);
+
+        let ast = crate::parse("# nothing to do\n").unwrap();
+        let output = Compiler::new().compile(&ast).unwrap();
+        assert_eq!(output.warnings, vec![CompilerWarning::EmptyWorkflow]);
     }
 
     #[test]
@@ -298,6 +941,29 @@ mod tests {
         assert_eq!(result.unwrap().dag.node_count(), 1);
     }
 
+    #[test]
+    fn test_compile_tool_call() {
+        let mut compiler = Compiler::new();
+        let mut ast = Ast::new();
+        ast.add_statement(Statement::ToolCall {
+            name: "echo".to_string(),
+            version_req: Some("^1.0".to_string()),
+            args: vec![Expr::Variable("data".to_string())],
+            output: None,
+        });
+
+        let dag = compiler.compile(&ast).unwrap().dag;
+        let node = dag.nodes.values().next().unwrap();
+        assert_eq!(
+            node.kind,
+            NodeKind::Tool {
+                name: "echo".to_string(),
+                version_req: "^1.0".to_string(),
+                input_binding: Some("data".to_string()),
+            }
+        );
+    }
+
     #[test]
     fn test_infer_capabilities() {
         let compiler = Compiler::new();
@@ -306,6 +972,270 @@ mod tests {
         assert!(matches!(&caps[0], Capability::FsRead { .. }));
     }
 
+    #[test]
+    fn test_compile_conditional() {
+        let ast = crate::parse(
+            "input n: integer\n\
+             if n > 10\n\
+             tool \"big\" <- n -> label\n\
+             tool \"echo\" <- label\n\
+             else\n\
+             tool \"small\" <- n -> label\n\
+             end\n\
+             output result = label\n",
+        )
+        .unwrap();
+        let dag = Compiler::new().compile(&ast).unwrap().dag;
+
+        let (cond_id, _) = dag
+            .nodes
+            .iter()
+            .find(|(_, n)| matches!(n.kind, NodeKind::Conditional { .. }))
+            .unwrap();
+        let ports: Vec<_> = dag
+            .edges
+            .iter()
+            .filter(|e| e.from == *cond_id)
+            .map(|e| e.from_port.as_deref())
+            .collect();
+        assert_eq!(ports, [Some(THEN_PORT), Some(ELSE_PORT)]);
+
+        // The join waits on the tail of each branch
+        let join = dag
+            .nodes
+            .values()
+            .find(|n| matches!(&n.kind, NodeKind::Reduce { function, .. } if function == "merge"))
+            .unwrap();
+        assert_eq!(join.dependencies.len(), 2);
+        assert!(!join.dependencies.contains(cond_id));
+    }
+
+    #[test]
+    fn test_compile_conditional_branch_typing() {
+        let compile = |src: &str| Compiler::new().compile(&crate::parse(src).unwrap());
+
+        // Predicate must fit the binding's type
+        assert!(compile("input s: string\nif s > 3\nend\n").is_err());
+        assert!(compile("if missing\nend\n").is_err());
+
+        // Both branches must bind the same names with compatible types
+        let one_sided = "input n: integer\nif n\ntool \"a\" -> x\nend\noutput r = x\n";
+        assert!(compile(one_sided).is_err());
+        let mismatched = "input n: integer\nif n\ninput x: integer\nelse\ninput x: string\nend\n";
+        assert!(compile(mismatched).is_err());
+        let joined = "input n: integer\nif n\ninput x: integer\nelse\ntool \"a\" -> x\nend\n";
+        assert!(compile(joined).is_ok());
+    }
+
+    #[test]
+    fn test_compile_map_reduce() {
+        let compile = |src: &str| Compiler::new().compile(&crate::parse(src).unwrap());
+
+        let output = compile(
+            "input files: list<string>\n\
+             map \"read_file\" over files parallel 3 reduce count -> n\n\
+             if n > 0\n\
+             end\n",
+        )
+        .unwrap();
+        let node = output
+            .dag
+            .nodes
+            .values()
+            .find(|n| matches!(n.kind, NodeKind::MapReduce { .. }))
+            .unwrap();
+        assert_eq!(
+            node.kind,
+            NodeKind::MapReduce {
+                name: "read_file".to_string(),
+                version_req: "*".to_string(),
+                input_binding: "files".to_string(),
+                max_parallel: 3,
+                aggregate: Aggregation::Count,
+            }
+        );
+        assert!(matches!(&node.capabilities[..], [Capability::FsRead { .. }]));
+
+        assert!(compile("input s: string\nmap \"a\" over s\n").is_err());
+        assert!(compile("map \"a\" over nowhere\n").is_err());
+    }
+
+    #[test]
+    fn test_compile_sub_workflow() {
+        let mut child_ast = Ast::new();
+        child_ast.add_statement(tool("read_file", "in.txt"));
+        let mut child = Compiler::new().compile(&child_ast).unwrap().dag;
+        let reader = child.nodes.values_mut().next().unwrap();
+        reader.resources = ResourceRequirements::new().with_max_memory(4096);
+        let child_contract = child.resource_contract();
+
+        let mut library = WorkflowLibrary::new();
+        let hash = library.insert(child);
+        let src = format!(
+            "input data: string\nworkflow {} <- data -> out\noutput r = out\n",
+            hash.to_hex()
+        );
+        let mut compiler = Compiler::new().with_workflows(Arc::new(library));
+        let dag = compiler.compile(&crate::parse(&src).unwrap()).unwrap().dag;
+
+        let node = dag
+            .nodes
+            .values()
+            .find(|n| matches!(n.kind, NodeKind::SubWorkflow { .. }))
+            .unwrap();
+        assert_eq!(
+            node.kind,
+            NodeKind::SubWorkflow {
+                hash,
+                contract: child_contract,
+                input_binding: Some("data".to_string()),
+            }
+        );
+        assert!(matches!(&node.capabilities[..], [Capability::FsRead { .. }]));
+        assert_eq!(dag.resource_contract().memory.max, Some(4096));
+
+        // Unknown hashes and bindings are rejected
+        let unknown = format!("workflow {}\n", Hash::compute(b"missing").to_hex());
+        assert!(compiler.compile(&crate::parse(&unknown).unwrap()).is_err());
+        let unbound = format!("workflow {} <- nowhere\n", hash.to_hex());
+        assert!(compiler.compile(&crate::parse(&unbound).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_compile_is_deterministic() {
+        let src = "input data: string\n\
+                   tool \"echo\" <- data -> a\n\
+                   tool \"upper\" <- a -> b\n";
+        let ast = crate::parse(src).unwrap();
+        let mut compiler = Compiler::new();
+        let first = compiler.compile(&ast).unwrap().dag;
+        let again = compiler.compile(&ast).unwrap().dag;
+        let fresh = Compiler::new().compile(&ast).unwrap().dag;
+        let ids = |dag: &Dag| dag.nodes.keys().copied().collect::<Vec<_>>();
+        assert_eq!(ids(&first), ids(&again));
+        assert_eq!(first.content_hash(), again.content_hash());
+        assert_eq!(first.content_hash(), fresh.content_hash());
+
+        // A different workflow gets different node IDs
+        let other = crate::parse("input data: string\ntool \"echo\" <- data -> a\n").unwrap();
+        let other = Compiler::new().compile(&other).unwrap().dag;
+        assert!(other.nodes.keys().all(|id| !first.nodes.contains_key(id)));
+    }
+
+    #[test]
+    fn test_compile_ids_follow_statements_not_source_order() {
+        let compile = |src: &str| Compiler::new().compile(&crate::parse(src).unwrap()).unwrap().dag;
+        let kinds = |dag: &Dag| {
+            let mut kinds: Vec<_> = dag
+                .nodes
+                .iter()
+                .map(|(id, node)| (*id, format!("{:?}", node.kind)))
+                .collect();
+            kinds.sort();
+            kinds
+        };
+        let a = compile("tool \"read_file\" <- \"a\"\ntool \"http_get\" <- \"b\"\n");
+        let b = compile("tool \"http_get\" <- \"b\"\ntool \"read_file\" <- \"a\"\n");
+        assert_eq!(kinds(&a), kinds(&b));
+    }
+
+    #[test]
+    fn test_compile_grants_requested_capabilities_without_registry() {
+        let mut ast = Ast::new();
+        ast.add_statement(tool("read_file", "in.txt"));
+        ast.add_statement(tool("echo", "hi"));
+
+        let output = Compiler::new().compile(&ast).unwrap();
+        assert_eq!(output.grants.len(), 2);
+        for (id, node) in &output.dag.nodes {
+            let granted: Vec<_> = output.grants.for_node(*id).iter().cloned().collect();
+            assert_eq!(granted, node.capabilities);
+        }
+        assert!(output.warnings.is_empty());
+    }
+
+    fn tool(name: &str, arg: &str) -> Statement {
+        Statement::ToolCall {
+            name: name.to_string(),
+            version_req: None,
+            args: vec![Expr::String(arg.to_string())],
+            output: None,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_independent_order() {
+        let mut a = Ast::new();
+        a.add_statement(tool("read_file", "a.txt"));
+        a.add_statement(tool("http_get", "https://example.com"));
+
+        let mut b = Ast::new();
+        b.add_statement(tool("http_get", "https://example.com"));
+        b.add_statement(tool("read_file", "a.txt"));
+
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_eq!(a.canonicalize(), b.canonicalize());
+
+        let par = |x, y| Statement::Parallel { branches: vec![x, y] };
+        let mut c = Ast::new();
+        c.add_statement(par(tool("read_file", "a"), tool("exec", "b")));
+        let mut d = Ast::new();
+        d.add_statement(par(tool("exec", "b"), tool("read_file", "a")));
+        assert_eq!(c.fingerprint(), d.fingerprint());
+    }
+
+    #[test]
+    fn test_fingerprint_keeps_binding_order() {
+        let bind = |name: &str, arg: Expr, output: &str| Statement::ToolCall {
+            name: name.to_string(),
+            version_req: None,
+            args: vec![arg],
+            output: Some(output.to_string()),
+        };
+        let var = |name: &str| Expr::Variable(name.to_string());
+        let producer = bind("fetch", Expr::String("x".to_string()), "a");
+        let consumer = bind("echo", var("a"), "b");
+
+        let mut chain = Ast::new();
+        chain.add_statement(producer.clone());
+        chain.add_statement(consumer.clone());
+        let mut reversed = Ast::new();
+        reversed.add_statement(consumer.clone());
+        reversed.add_statement(producer.clone());
+        assert_ne!(chain.fingerprint(), reversed.fingerprint());
+        assert_eq!(chain.canonicalize(), chain);
+
+        // An unrelated statement still moves freely around the chain
+        let mut around = Ast::new();
+        around.add_statement(producer.clone());
+        around.add_statement(tool("read_file", "z"));
+        around.add_statement(consumer.clone());
+        let mut before = Ast::new();
+        before.add_statement(tool("read_file", "z"));
+        before.add_statement(producer);
+        before.add_statement(consumer);
+        assert_eq!(around.fingerprint(), before.fingerprint());
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_semantic_changes() {
+        let mut a = Ast::new();
+        a.add_statement(tool("read_file", "a.txt"));
+
+        // Same argument, but a tool requiring a different capability
+        let mut b = Ast::new();
+        b.add_statement(tool("write_file", "a.txt"));
+        assert_ne!(a.fingerprint(), b.fingerprint());
+
+        // Sequences are ordered, so swapping steps is a semantic change
+        let seq = |x, y| Statement::Sequence { statements: vec![x, y] };
+        let mut c = Ast::new();
+        c.add_statement(seq(tool("read_file", "a"), tool("exec", "b")));
+        let mut d = Ast::new();
+        d.add_statement(seq(tool("exec", "b"), tool("read_file", "a")));
+        assert_ne!(c.fingerprint(), d.fingerprint());
+    }
+
     #[test]
     fn test_expr_string() {
         let expr = Expr::String("test".to_string());
@@ -0,0 +1,370 @@
+//! Deterministic control flow: branch predicates and fan-in aggregation.
+//!
+//! Predicates and aggregations are closed enums rather than expressions,
+//! so evaluating them on the same inputs always gives the same result and
+//! the compiler can type-check them against binding schemas.
+
+use cathedral_core::{CoreError, CoreResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Schema of a binding whose type is not known at compile time
+pub const ANY_SCHEMA: &str = "any";
+
+/// Items a fan-out keeps in flight when the workflow does not say
+pub const DEFAULT_MAX_PARALLEL: usize = 4;
+
+/// Literal operand of a comparison predicate
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Literal {
+    /// Boolean literal
+    Bool(bool),
+    /// Integer literal
+    Integer(i64),
+    /// String literal
+    String(String),
+}
+
+impl Literal {
+    /// Compare a JSON value against this literal, `None` if the types differ
+    fn compare(&self, value: &Value) -> Option<Ordering> {
+        match (self, value) {
+            (Self::Bool(b), Value::Bool(v)) => Some(v.cmp(b)),
+            (Self::Integer(i), Value::Number(n)) => match n.as_i64() {
+                Some(v) => Some(v.cmp(i)),
+                None => n.as_f64()?.partial_cmp(&(*i as f64)),
+            },
+            (Self::String(s), Value::String(v)) => Some(v.as_str().cmp(s.as_str())),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Literal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::Integer(i) => write!(f, "{}", i),
+            Self::String(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+/// Comparison operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+impl Comparison {
+    /// Parse an operator token
+    #[must_use]
+    pub fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "==" => Some(Self::Eq),
+            "!=" => Some(Self::Ne),
+            "<" => Some(Self::Lt),
+            "<=" => Some(Self::Le),
+            ">" => Some(Self::Gt),
+            ">=" => Some(Self::Ge),
+            _ => None,
+        }
+    }
+
+    /// The operator token
+    #[must_use]
+    pub const fn token(self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+
+    /// Check whether `ordering` satisfies this operator
+    fn holds(self, ordering: Option<Ordering>) -> bool {
+        match self {
+            Self::Ne => ordering != Some(Ordering::Equal),
+            Self::Eq => ordering == Some(Ordering::Equal),
+            Self::Lt => ordering == Some(Ordering::Less),
+            Self::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Self::Gt => ordering == Some(Ordering::Greater),
+            Self::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+/// Deterministic predicate over a prior node's output
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "predicate", rename_all = "snake_case")]
+pub enum Predicate {
+    /// True unless the value is null, false, zero, or empty
+    Truthy,
+    /// Compare the value against a literal
+    Compare {
+        /// Operator
+        op: Comparison,
+        /// Right-hand operand
+        value: Literal,
+    },
+}
+
+impl Predicate {
+    /// Evaluate the predicate on a value
+    ///
+    /// A comparison against a value of a different type is unequal and
+    /// unordered: only `!=` holds.
+    #[must_use]
+    pub fn evaluate(&self, value: &Value) -> bool {
+        match self {
+            Self::Truthy => match value {
+                Value::Null => false,
+                Value::Bool(b) => *b,
+                Value::Number(n) => n.as_f64() != Some(0.0),
+                Value::String(s) => !s.is_empty(),
+                Value::Array(items) => !items.is_empty(),
+                Value::Object(fields) => !fields.is_empty(),
+            },
+            Self::Compare { op, value: literal } => op.holds(literal.compare(value)),
+        }
+    }
+
+    /// Check the predicate can apply to a binding of type `schema`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the comparison is not defined for the schema
+    pub fn check_schema(&self, schema: &str) -> CoreResult<()> {
+        let Self::Compare { op, value } = self else {
+            return Ok(());
+        };
+        let kind = ScalarKind::of(schema);
+        let ok = match (kind, value) {
+            (None, _) => true,
+            (Some(ScalarKind::Bool), Literal::Bool(_)) => {
+                matches!(op, Comparison::Eq | Comparison::Ne)
+            }
+            (Some(ScalarKind::Number), Literal::Integer(_)) => true,
+            (Some(ScalarKind::String), Literal::String(_)) => true,
+            _ => false,
+        };
+        if ok {
+            return Ok(());
+        }
+        Err(CoreError::Validation {
+            field: "predicate".to_string(),
+            reason: format!("cannot compare {} with {} {}", schema, op.token(), value),
+        })
+    }
+}
+
+impl std::fmt::Display for Predicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Truthy => write!(f, "is truthy"),
+            Self::Compare { op, value } => write!(f, "{} {}", op.token(), value),
+        }
+    }
+}
+
+/// Scalar types a comparison can be checked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarKind {
+    Bool,
+    Number,
+    String,
+}
+
+impl ScalarKind {
+    /// Scalar kind of a schema; `None` for unknown or non-scalar schemas,
+    /// which are checked at run time instead
+    fn of(schema: &str) -> Option<Self> {
+        match schema {
+            "bool" | "boolean" => Some(Self::Bool),
+            "int" | "integer" | "number" | "float" => Some(Self::Number),
+            "string" => Some(Self::String),
+            _ => None,
+        }
+    }
+}
+
+/// Element schema of a list schema (`list<T>` or `[T]`)
+#[must_use]
+pub fn list_element(schema: &str) -> Option<&str> {
+    schema
+        .strip_prefix("list<")
+        .and_then(|rest| rest.strip_suffix('>'))
+        .or_else(|| schema.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')))
+        .map(str::trim)
+}
+
+/// How fan-out results are combined, in item order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregation {
+    /// Collect results into a list
+    #[default]
+    Collect,
+    /// Concatenate string results, or flatten list results
+    Concat,
+    /// Sum numeric results
+    Sum,
+    /// Count results
+    Count,
+}
+
+impl Aggregation {
+    /// Parse an aggregation keyword
+    #[must_use]
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "collect" => Some(Self::Collect),
+            "concat" => Some(Self::Concat),
+            "sum" => Some(Self::Sum),
+            "count" => Some(Self::Count),
+            _ => None,
+        }
+    }
+
+    /// The aggregation keyword
+    #[must_use]
+    pub const fn keyword(self) -> &'static str {
+        match self {
+            Self::Collect => "collect",
+            Self::Concat => "concat",
+            Self::Sum => "sum",
+            Self::Count => "count",
+        }
+    }
+
+    /// Schema of the aggregated result
+    #[must_use]
+    pub fn output_schema(self) -> String {
+        match self {
+            Self::Collect => format!("list<{}>", ANY_SCHEMA),
+            Self::Concat | Self::Sum => ANY_SCHEMA.to_string(),
+            Self::Count => "integer".to_string(),
+        }
+    }
+
+    /// Combine item results, given in item order
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the results cannot be combined this way
+    pub fn apply(self, results: Vec<Value>) -> CoreResult<Value> {
+        let invalid = |reason: &str| CoreError::Validation {
+            field: "aggregate".to_string(),
+            reason: format!("cannot {} results: {}", self.keyword(), reason),
+        };
+        match self {
+            Self::Collect => Ok(Value::Array(results)),
+            Self::Count => Ok(Value::from(results.len())),
+            Self::Concat => {
+                if results.iter().all(Value::is_string) {
+                    let joined: String = results.iter().filter_map(Value::as_str).collect();
+                    return Ok(Value::String(joined));
+                }
+                let mut flat = Vec::new();
+                for result in results {
+                    match result {
+                        Value::Array(items) => flat.extend(items),
+                        _ => return Err(invalid("expected all strings or all lists")),
+                    }
+                }
+                Ok(Value::Array(flat))
+            }
+            Self::Sum => {
+                if let Some(ints) = results.iter().map(Value::as_i64).collect::<Option<Vec<_>>>() {
+                    return ints
+                        .into_iter()
+                        .try_fold(0i64, i64::checked_add)
+                        .map(Value::from)
+                        .ok_or_else(|| invalid("integer overflow"));
+                }
+                let floats = results
+                    .iter()
+                    .map(Value::as_f64)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| invalid("expected numbers"))?;
+                Ok(Value::from(floats.into_iter().sum::<f64>()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn compare(op: Comparison, value: Literal) -> Predicate {
+        Predicate::Compare { op, value }
+    }
+
+    #[test]
+    fn test_predicate_evaluate() {
+        assert!(Predicate::Truthy.evaluate(&json!([1])));
+        assert!(!Predicate::Truthy.evaluate(&json!("")));
+        assert!(!Predicate::Truthy.evaluate(&json!(0)));
+
+        assert!(compare(Comparison::Gt, Literal::Integer(3)).evaluate(&json!(4)));
+        assert!(compare(Comparison::Le, Literal::Integer(3)).evaluate(&json!(2.5)));
+        assert!(compare(Comparison::Eq, Literal::String("ok".into())).evaluate(&json!("ok")));
+        // Mismatched types are unequal and unordered
+        assert!(compare(Comparison::Ne, Literal::Integer(1)).evaluate(&json!("1")));
+        assert!(!compare(Comparison::Lt, Literal::Integer(1)).evaluate(&json!("0")));
+    }
+
+    #[test]
+    fn test_predicate_check_schema() {
+        let gt = compare(Comparison::Gt, Literal::Integer(0));
+        assert!(gt.check_schema("integer").is_ok());
+        assert!(gt.check_schema(ANY_SCHEMA).is_ok());
+        assert!(gt.check_schema("string").is_err());
+        assert!(compare(Comparison::Lt, Literal::Bool(true)).check_schema("bool").is_err());
+        assert!(Predicate::Truthy.check_schema("list<string>").is_ok());
+    }
+
+    #[test]
+    fn test_list_element() {
+        assert_eq!(list_element("list<string>"), Some("string"));
+        assert_eq!(list_element("[integer]"), Some("integer"));
+        assert_eq!(list_element("string"), None);
+    }
+
+    #[test]
+    fn test_aggregation_apply() {
+        let results = vec![json!(1), json!(2), json!(3)];
+        assert_eq!(Aggregation::Sum.apply(results.clone()).unwrap(), json!(6));
+        assert_eq!(Aggregation::Count.apply(results.clone()).unwrap(), json!(3));
+        assert_eq!(Aggregation::Collect.apply(results).unwrap(), json!([1, 2, 3]));
+        assert_eq!(
+            Aggregation::Concat.apply(vec![json!("a"), json!("b")]).unwrap(),
+            json!("ab")
+        );
+        assert_eq!(
+            Aggregation::Concat.apply(vec![json!([1]), json!([2, 3])]).unwrap(),
+            json!([1, 2, 3])
+        );
+        assert!(Aggregation::Concat.apply(vec![json!("a"), json!(1)]).is_err());
+        assert!(Aggregation::Sum.apply(vec![json!(i64::MAX), json!(1)]).is_err());
+    }
+}
@@ -3,9 +3,16 @@
 //! The DAG is the result of compiling the planner DSL and represents
 //! the executable workflow with explicit type information.
 
-use cathedral_core::{NodeId, Capability, CoreResult, CoreError};
+use cathedral_core::{NodeId, Capability, CoreResult, CoreError, Hash};
 use indexmap::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
+use super::control::{Aggregation, Predicate};
+use super::resource::ResourceContract;
+
+/// Output port of a conditional node leading to its `then` branch
+pub const THEN_PORT: &str = "then";
+/// Output port of a conditional node leading to its `else` branch
+pub const ELSE_PORT: &str = "else";
 
 /// A directed acyclic graph representing a workflow
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -156,6 +163,23 @@ impl Dag {
             .collect()
     }
 
+    /// Every dependency as `(from, to)`, from edges and declared node
+    /// dependencies, without duplicates
+    #[must_use]
+    pub fn dependency_edges(&self) -> Vec<(NodeId, NodeId)> {
+        let declared = self
+            .nodes
+            .values()
+            .flat_map(|node| node.dependencies.iter().map(move |from| (*from, node.id)));
+        let mut pairs = Vec::new();
+        for pair in self.edges.iter().map(|edge| (edge.from, edge.to)).chain(declared) {
+            if !pairs.contains(&pair) {
+                pairs.push(pair);
+            }
+        }
+        pairs
+    }
+
     /// Get total node count
     #[must_use]
     pub fn node_count(&self) -> usize {
@@ -173,6 +197,22 @@ impl Dag {
     pub fn is_empty(&self) -> bool {
         self.nodes.is_empty()
     }
+
+    /// Content hash identifying this compiled DAG
+    #[must_use]
+    pub fn content_hash(&self) -> Hash {
+        // Serializing plain data with string keys cannot fail
+        let bytes = serde_json::to_vec(self).unwrap_or_default();
+        Hash::compute(&bytes)
+    }
+
+    /// Resource contract covering every node, including sub-workflows
+    #[must_use]
+    pub fn resource_contract(&self) -> ResourceContract {
+        self.nodes.values().fold(ResourceContract::new(), |contract, node| {
+            contract.rollup(&node.resource_contract())
+        })
+    }
 }
 
 impl Default for Dag {
@@ -196,6 +236,18 @@ pub struct Node {
     pub resources: ResourceRequirements,
 }
 
+impl Node {
+    /// Resource contract of this node, including an invoked sub-workflow's
+    #[must_use]
+    pub fn resource_contract(&self) -> ResourceContract {
+        let own = ResourceContract::from_requirements(&self.resources);
+        match &self.kind {
+            NodeKind::SubWorkflow { contract, .. } => own.rollup(contract),
+            _ => own,
+        }
+    }
+}
+
 /// Node kind - type of operation
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum NodeKind {
@@ -209,12 +261,14 @@ pub enum NodeKind {
         /// Output schema
         schema: String,
     },
-    /// Tool execution node
+    /// Tool execution node, resolved against the tool registry at run time
     Tool {
         /// Tool name
         name: String,
-        /// Tool version
-        version: String,
+        /// Semver requirement the registered tool must satisfy (e.g. `^1.0`)
+        version_req: String,
+        /// Name of the binding supplying the tool's input, if any
+        input_binding: Option<String>,
     },
     /// Map/transform operation
     Map {
@@ -255,6 +309,39 @@ pub enum NodeKind {
         /// Max iterations
         max_iterations: Option<u64>,
     },
+    /// Branch on a prior node's output
+    ///
+    /// Edges leaving on [`THEN_PORT`] run when the predicate holds, edges
+    /// leaving on [`ELSE_PORT`] when it does not; the other branch is
+    /// skipped.
+    Conditional {
+        /// Binding the predicate is evaluated on
+        input_binding: String,
+        /// Branch predicate
+        predicate: Predicate,
+    },
+    /// Run a tool once per element of a list input, then aggregate
+    MapReduce {
+        /// Tool name
+        name: String,
+        /// Semver requirement the registered tool must satisfy
+        version_req: String,
+        /// Binding supplying the list to fan out over
+        input_binding: String,
+        /// Maximum number of items in flight at once
+        max_parallel: usize,
+        /// How item results are combined
+        aggregate: Aggregation,
+    },
+    /// Invoke another compiled workflow
+    SubWorkflow {
+        /// Content hash of the invoked DAG
+        hash: Hash,
+        /// Resource contract of the invoked DAG
+        contract: ResourceContract,
+        /// Name of the binding supplying the workflow's input, if any
+        input_binding: Option<String>,
+    },
 }
 
 /// An edge between nodes
@@ -282,6 +369,15 @@ impl Edge {
         }
     }
 
+    /// Create an edge leaving a conditional node on a branch port
+    #[must_use]
+    pub fn branch(from: NodeId, to: NodeId, port: &str) -> Self {
+        Self {
+            from_port: Some(port.to_string()),
+            ..Self::new(from, to)
+        }
+    }
+
     /// Create a new edge with ports
     #[must_use]
     pub fn with_ports(from: NodeId, to: NodeId, from_port: String, to_port: String) -> Self {
@@ -359,7 +455,8 @@ mod tests {
             id,
             kind: NodeKind::Tool {
                 name: "test_tool".to_string(),
-                version: "1.0.0".to_string(),
+                version_req: "^1.0".to_string(),
+                input_binding: None,
             },
             dependencies: IndexSet::new(),
             capabilities: Vec::new(),
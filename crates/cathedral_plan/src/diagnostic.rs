@@ -0,0 +1,467 @@
+//! Diagnostics for workflow sources.
+//!
+//! [`check`] runs the parser, compiler and validator over a workflow source
+//! without executing it and reports every problem as a [`Diagnostic`] with a
+//! line/column span and, where one is known, a suggested fix.
+//!
+//! The parser only records the line of an error and the AST carries no
+//! positions, so spans are recovered by locating the token a message quotes
+//! in the source.
+
+use super::compiler::{Ast, Compiler, CompilerWarning, Statement};
+use super::dsl::parse;
+use super::validate::{ValidationError, Validator};
+use cathedral_core::CoreError;
+use serde::Serialize;
+
+/// Keywords that start a declaration
+const KEYWORDS: &[&str] = &["input", "tool", "output", "map", "workflow", "if", "else", "end"];
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The workflow cannot run
+    Error,
+    /// The workflow runs but is probably not what was meant
+    Warning,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Location of a diagnostic in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+    /// Line, starting at 1
+    pub line: usize,
+    /// Column of the first character, starting at 1
+    pub column: usize,
+    /// Length in characters
+    pub len: usize,
+}
+
+/// A problem found in a workflow source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Severity
+    pub severity: Severity,
+    /// Stable identifier of the kind of problem, e.g. `unknown-binding`
+    pub code: &'static str,
+    /// What is wrong
+    pub message: String,
+    /// Where it is, if it can be pinned to the source
+    pub span: Option<Span>,
+    /// How to fix it, if known
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, code: &'static str, message: String) -> Self {
+        Self {
+            severity,
+            code,
+            message,
+            span: None,
+            suggestion: None,
+        }
+    }
+
+    fn at(mut self, span: Option<Span>) -> Self {
+        self.span = span;
+        self
+    }
+
+    fn suggest(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+
+    /// Render the diagnostic with the source line it points at
+    #[must_use]
+    pub fn render(&self, file: &str, source: &str) -> String {
+        let mut out = format!("{}[{}]: {}\n", self.severity, self.code, self.message);
+        match self.span {
+            Some(span) => {
+                let gutter = " ".repeat(span.line.to_string().len());
+                let text = source.lines().nth(span.line - 1).unwrap_or_default();
+                out.push_str(&format!("{}--> {}:{}:{}\n", gutter, file, span.line, span.column));
+                out.push_str(&format!("{} |\n", gutter));
+                out.push_str(&format!("{} | {}\n", span.line, text));
+                out.push_str(&format!(
+                    "{} | {}{}\n",
+                    gutter,
+                    " ".repeat(span.column - 1),
+                    "^".repeat(span.len.max(1))
+                ));
+            }
+            None => out.push_str(&format!(" --> {}\n", file)),
+        }
+        if let Some(suggestion) = &self.suggestion {
+            out.push_str(&format!("  = help: {}\n", suggestion));
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(span) = self.span {
+            write!(f, "{}:{}: ", span.line, span.column)?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+/// Parse, compile and validate a workflow source, collecting diagnostics
+///
+/// Parsing stops at the first error, so a source that does not parse
+/// yields exactly one error. Otherwise compiler warnings and validator
+/// errors are all reported.
+pub fn check(source: &str, compiler: &mut Compiler) -> Vec<Diagnostic> {
+    let ast = match parse(source) {
+        Ok(ast) => ast,
+        Err(err) => return vec![parse_diagnostic(source, &err)],
+    };
+    let output = match compiler.compile(&ast) {
+        Ok(output) => output,
+        Err(err) => return vec![compile_diagnostic(source, &ast, &err)],
+    };
+
+    let mut diagnostics: Vec<Diagnostic> = output
+        .warnings
+        .iter()
+        .map(|warning| warning_diagnostic(source, warning))
+        .collect();
+    if let Err(errors) = Validator::new().validate(&output.dag) {
+        diagnostics.extend(errors.iter().map(validation_diagnostic));
+    }
+    diagnostics
+}
+
+/// Whether any diagnostic is an error
+#[must_use]
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+fn parse_diagnostic(source: &str, err: &CoreError) -> Diagnostic {
+    let CoreError::ParseError { message } = err else {
+        return Diagnostic::new(Severity::Error, "parse", err.to_string());
+    };
+    let (line, message) = message
+        .strip_prefix("line ")
+        .and_then(|rest| rest.split_once(": "))
+        .and_then(|(line, message)| Some((line.parse::<usize>().ok()?, message)))
+        .unwrap_or((0, message.as_str()));
+
+    let text = source.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+    let span = quoted(message)
+        .and_then(|token| find_token(text, token))
+        .map(|(column, len)| Span { line, column, len })
+        .or_else(|| (line > 0).then(|| line_span(line, text)));
+
+    let suggestion = if let Some(keyword) = message
+        .strip_prefix("unknown declaration '")
+        .and_then(|rest| rest.strip_suffix('\''))
+    {
+        Some(match closest(keyword, KEYWORDS.iter().copied()) {
+            Some(known) => format!("did you mean '{}'?", known),
+            None => format!("declarations start with one of: {}", KEYWORDS.join(", ")),
+        })
+    } else if message.starts_with("unknown comparison") {
+        Some("use one of == != < <= > >=".to_string())
+    } else if message.starts_with("unknown reduction") {
+        Some("use one of collect, concat, sum, count".to_string())
+    } else if message.starts_with("invalid parallelism") {
+        Some("use a positive integer".to_string())
+    } else if message == "'if' without matching 'end'" {
+        Some("close the conditional with 'end'".to_string())
+    } else if message.ends_with("without 'if'") {
+        Some("remove it or open a conditional with 'if <binding>' before it".to_string())
+    } else if message == "duplicate 'else'" {
+        Some("a conditional takes at most one 'else'".to_string())
+    } else if message == "unterminated tool name" {
+        Some("close the tool name with '\"'".to_string())
+    } else {
+        None
+    };
+
+    Diagnostic::new(Severity::Error, "parse", message.to_string())
+        .at(span)
+        .suggest(suggestion)
+}
+
+fn compile_diagnostic(source: &str, ast: &Ast, err: &CoreError) -> Diagnostic {
+    match err {
+        CoreError::NotFound { kind, id } if kind == "binding" => {
+            let mut declared = Vec::new();
+            collect_bindings(&ast.statements, &mut declared);
+            let suggestion = match closest(id, declared.iter().map(String::as_str)) {
+                Some(known) => format!("did you mean '{}'?", known),
+                None => format!(
+                    "declare it with 'input {}: <schema>' or bind it with '-> {}' before use",
+                    id, id
+                ),
+            };
+            Diagnostic::new(Severity::Error, "unknown-binding", format!("unknown binding '{}'", id))
+                .at(locate(source, id))
+                .suggest(Some(suggestion))
+        }
+        CoreError::NotFound { kind, id } if kind == "Workflow" => Diagnostic::new(
+            Severity::Error,
+            "unknown-workflow",
+            format!("no compiled workflow with hash {}", id),
+        )
+        .at(locate(source, id))
+        .suggest(Some(
+            "add the workflow to the library sub-workflows are resolved against".to_string(),
+        )),
+        CoreError::Validation { field, reason } => {
+            let token = quoted(field).or_else(|| quoted(reason));
+            let suggestion = field.starts_with("binding").then(|| {
+                "bind it in both branches of the conditional, with the same type".to_string()
+            });
+            let message = match token {
+                Some(name) if field.starts_with("binding") => format!("binding '{}' {}", name, reason),
+                _ => reason.clone(),
+            };
+            Diagnostic::new(Severity::Error, "invalid-workflow", message)
+                .at(token.and_then(|token| locate(source, token)))
+                .suggest(suggestion)
+        }
+        other => Diagnostic::new(Severity::Error, "invalid-workflow", other.to_string()),
+    }
+}
+
+fn warning_diagnostic(source: &str, warning: &CompilerWarning) -> Diagnostic {
+    let (code, message, token, suggestion) = match warning {
+        CompilerWarning::UnusedVariable { name } => (
+            "unused-binding",
+            format!("binding '{}' is never used", name),
+            Some(name.as_str()),
+            Some("remove the binding or use it".to_string()),
+        ),
+        CompilerWarning::Deprecated { feature } => {
+            ("deprecated", format!("{} is deprecated", feature), None, None)
+        }
+        CompilerWarning::ResourceLimit { resource } => (
+            "resource-limit",
+            format!("resource limit might be exceeded: {}", resource),
+            None,
+            None,
+        ),
+        CompilerWarning::EmptyWorkflow => (
+            "empty-workflow",
+            "workflow declares nothing".to_string(),
+            None,
+            Some("declare at least an 'input', a 'tool' and an 'output'".to_string()),
+        ),
+        CompilerWarning::UnusedCapability { tool, capability, .. } => (
+            "unused-capability",
+            format!("tool '{}' is granted {:?} but its schema never uses it", tool, capability),
+            Some(tool.as_str()),
+            Some("drop the capability from the node's grant".to_string()),
+        ),
+        CompilerWarning::UngrantedCapability { tool, capability, .. } => (
+            "ungranted-capability",
+            format!("tool '{}' uses {:?} but is not granted it", tool, capability),
+            Some(tool.as_str()),
+            Some("grant the capability or the call will be denied at run time".to_string()),
+        ),
+    };
+    Diagnostic::new(Severity::Warning, code, message)
+        .at(token.and_then(|token| locate(source, token)))
+        .suggest(suggestion)
+}
+
+fn validation_diagnostic(error: &ValidationError) -> Diagnostic {
+    let (code, suggestion) = match error {
+        ValidationError::Cycle { .. } => ("cycle", None),
+        ValidationError::Disconnected { .. } => ("disconnected", None),
+        ValidationError::MissingInput { .. } => (
+            "missing-input",
+            Some("declare an input, e.g. 'input data: string'".to_string()),
+        ),
+        ValidationError::MissingOutput => (
+            "missing-output",
+            Some("declare an output, e.g. 'output result = <binding>'".to_string()),
+        ),
+        ValidationError::InvalidNodeKind { .. } => ("invalid-node", None),
+        ValidationError::ResourceViolation { .. } => ("resource-violation", None),
+        ValidationError::CapabilityViolation { .. } => ("capability-violation", None),
+    };
+    Diagnostic::new(Severity::Error, code, error.to_string()).suggest(suggestion)
+}
+
+/// Names bound by `statements`, in declaration order
+fn collect_bindings(statements: &[Statement], out: &mut Vec<String>) {
+    for stmt in statements {
+        match stmt {
+            Statement::Input { name, .. } => out.push(name.clone()),
+            Statement::ToolCall { output, .. }
+            | Statement::MapReduce { output, .. }
+            | Statement::SubWorkflow { output, .. } => out.extend(output.clone()),
+            Statement::Sequence { statements } | Statement::Parallel { branches: statements } => {
+                collect_bindings(statements, out);
+            }
+            Statement::Conditional { then_branch, else_branch, .. } => {
+                collect_bindings(then_branch, out);
+                collect_bindings(else_branch, out);
+            }
+            Statement::Output { .. } => {}
+        }
+    }
+}
+
+/// The first `'quoted'` word in a message
+fn quoted(message: &str) -> Option<&str> {
+    let (_, rest) = message.split_once('\'')?;
+    let (token, _) = rest.split_once('\'')?;
+    (!token.is_empty()).then_some(token)
+}
+
+/// First whole-token occurrence of `token` in a non-comment line
+fn locate(source: &str, token: &str) -> Option<Span> {
+    source.lines().enumerate().find_map(|(index, text)| {
+        if text.trim_start().starts_with('#') {
+            return None;
+        }
+        find_token(text, token).map(|(column, len)| Span { line: index + 1, column, len })
+    })
+}
+
+/// Column and length of the first whole-token occurrence of `token`
+fn find_token(text: &str, token: &str) -> Option<(usize, usize)> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(token).find_map(|(at, _)| {
+        let before = text[..at].chars().next_back();
+        let after = text[at + token.len()..].chars().next();
+        let bounded = !before.is_some_and(is_word) && !after.is_some_and(is_word);
+        bounded.then(|| (text[..at].chars().count() + 1, token.chars().count()))
+    })
+}
+
+/// Span covering a whole line, without surrounding whitespace
+fn line_span(line: usize, text: &str) -> Span {
+    let indent = text.len() - text.trim_start().len();
+    Span {
+        line,
+        column: text[..indent].chars().count() + 1,
+        len: text.trim().chars().count(),
+    }
+}
+
+/// The candidate within edit distance 2 of `name`, if any
+fn closest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnose(source: &str) -> Vec<Diagnostic> {
+        check(source, &mut Compiler::new())
+    }
+
+    #[test]
+    fn test_check_valid_workflow() {
+        let diagnostics = diagnose(
+            "input data: string\n\
+             tool \"echo\" <- data -> echoed\n\
+             output result = echoed\n",
+        );
+        assert!(!has_errors(&diagnostics), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_check_unknown_declaration() {
+        let diagnostics = diagnose("input data: string\n  tol \"echo\" <- data\n");
+        assert_eq!(diagnostics.len(), 1);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.code, "parse");
+        assert_eq!(diagnostic.span, Some(Span { line: 2, column: 3, len: 3 }));
+        assert_eq!(diagnostic.suggestion.as_deref(), Some("did you mean 'tool'?"));
+    }
+
+    #[test]
+    fn test_check_unclosed_conditional() {
+        let diagnostics = diagnose("input x: string\nif x\n");
+        assert_eq!(diagnostics[0].span, Some(Span { line: 2, column: 1, len: 2 }));
+        assert_eq!(
+            diagnostics[0].suggestion.as_deref(),
+            Some("close the conditional with 'end'")
+        );
+    }
+
+    #[test]
+    fn test_check_unknown_binding() {
+        let diagnostics = diagnose(
+            "input items: list<integer>\n\
+             map \"double\" over itmes -> total\n\
+             output result = total\n",
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unknown-binding");
+        assert_eq!(diagnostics[0].span, Some(Span { line: 2, column: 19, len: 5 }));
+        assert_eq!(diagnostics[0].suggestion.as_deref(), Some("did you mean 'items'?"));
+    }
+
+    #[test]
+    fn test_check_reports_validator_errors() {
+        let diagnostics = diagnose("input data: string\n");
+        assert!(has_errors(&diagnostics));
+        assert!(diagnostics.iter().any(|d| d.code == "missing-output"));
+    }
+
+    #[test]
+    fn test_render_points_at_span() {
+        let source = "input data: string\nbogus x\n";
+        let rendered = diagnose(source)[0].render("wf.cf", source);
+        assert_eq!(
+            rendered,
+            "error[parse]: unknown declaration 'bogus'\n\
+             \x20--> wf.cf:2:1\n\
+             \x20 |\n\
+             2 | bogus x\n\
+             \x20 | ^^^^^\n\
+             \x20 = help: declarations start with one of: \
+             input, tool, output, map, workflow, if, else, end\n"
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("tool", "tool"), 0);
+        assert_eq!(edit_distance("tol", "tool"), 1);
+        assert_eq!(edit_distance("ouptut", "output"), 2);
+    }
+}
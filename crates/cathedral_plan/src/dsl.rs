@@ -1,17 +1,109 @@
 //! DSL parser for workflow definitions.
 
-use cathedral_core::{CoreResult, CoreError};
+use cathedral_core::{CoreResult, CoreError, Hash};
 use super::compiler::Ast;
+use super::control::{Aggregation, Comparison, Literal, Predicate, DEFAULT_MAX_PARALLEL};
 
 /// Parse a workflow definition into an AST
 ///
+/// Each non-empty line is one declaration. Lines starting with `#` are
+/// comments. Supported declarations:
+///
+/// - `input <name>: <schema>`
+/// - `tool "<name>" [<version_req>] [<- <binding>] [-> <output>]`
+/// - `output <name> = <binding>`
+/// - `workflow <hash> [<- <binding>] [-> <output>]`, invoking the
+///   compiled workflow with that content hash
+/// - `map "<name>" [<version_req>] over <binding> [parallel <n>]
+///   [reduce collect|concat|sum|count] [-> <output>]`
+/// - `if <binding> [<op> <literal>]`, then declarations, optionally
+///   `else` and more declarations, closed by `end`. `<op>` is one of
+///   `== != < <= > >=`; without one the branch is taken when the binding
+///   is truthy.
+///
 /// # Errors
 ///
 /// Returns error if parsing fails
-pub fn parse(_input: &str) -> CoreResult<Ast> {
-    // TODO: Implement actual parsing
-    // For now, return an empty AST
-    Ok(Ast::new())
+pub fn parse(input: &str) -> CoreResult<Ast> {
+    let mut ast = Ast::new();
+    let mut open: Vec<OpenConditional> = Vec::new();
+
+    for (index, raw) in input.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let lineno = index + 1;
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let stmt = match keyword {
+            "input" => parse_input(rest.trim(), lineno)?,
+            "tool" => parse_tool(rest.trim(), lineno)?,
+            "output" => parse_output(rest.trim(), lineno)?,
+            "map" => parse_map(rest.trim(), lineno)?,
+            "workflow" => parse_workflow(rest.trim(), lineno)?,
+            "if" => {
+                open.push(parse_if(rest.trim(), lineno)?);
+                continue;
+            }
+            "else" | "end" if !rest.trim().is_empty() => {
+                return Err(error(lineno, &format!("unexpected tokens after '{}'", keyword)));
+            }
+            "else" => {
+                match open.last_mut() {
+                    Some(cond) if cond.else_branch.is_none() => cond.else_branch = Some(Vec::new()),
+                    Some(_) => return Err(error(lineno, "duplicate 'else'")),
+                    None => return Err(error(lineno, "'else' without 'if'")),
+                }
+                continue;
+            }
+            "end" => open
+                .pop()
+                .ok_or_else(|| error(lineno, "'end' without 'if'"))?
+                .close(),
+            other => return Err(error(lineno, &format!("unknown declaration '{}'", other))),
+        };
+        match open.last_mut() {
+            Some(cond) => cond.push(stmt),
+            None => ast.add_statement(stmt),
+        }
+    }
+
+    if let Some(cond) = open.last() {
+        return Err(error(cond.lineno, "'if' without matching 'end'"));
+    }
+
+    Ok(ast)
+}
+
+/// A conditional whose `end` has not been reached yet
+struct OpenConditional {
+    /// Line of the `if`
+    lineno: usize,
+    binding: String,
+    predicate: Predicate,
+    then_branch: Vec<Statement>,
+    /// `Some` once `else` has been seen
+    else_branch: Option<Vec<Statement>>,
+}
+
+impl OpenConditional {
+    /// Add a statement to the branch being parsed
+    fn push(&mut self, stmt: Statement) {
+        match &mut self.else_branch {
+            Some(branch) => branch.push(stmt),
+            None => self.then_branch.push(stmt),
+        }
+    }
+
+    fn close(self) -> Statement {
+        Statement::Conditional {
+            binding: self.binding,
+            predicate: self.predicate,
+            then_branch: self.then_branch,
+            else_branch: self.else_branch.unwrap_or_default(),
+        }
+    }
 }
 
 /// Parse error type
@@ -20,6 +112,219 @@ pub type ParseError = CoreError;
 /// Re-export AST types for convenience
 pub use super::compiler::{Statement, Expr};
 
+/// Build a parse error for a line
+fn error(lineno: usize, message: &str) -> CoreError {
+    CoreError::ParseError {
+        message: format!("line {}: {}", lineno, message),
+    }
+}
+
+/// Parse `<name>: <schema>`
+fn parse_input(rest: &str, lineno: usize) -> CoreResult<Statement> {
+    let (name, schema) = rest
+        .split_once(':')
+        .ok_or_else(|| error(lineno, "expected 'input <name>: <schema>'"))?;
+    let (name, schema) = (name.trim(), schema.trim());
+    if name.is_empty() || schema.is_empty() {
+        return Err(error(lineno, "expected 'input <name>: <schema>'"));
+    }
+
+    Ok(Statement::Input {
+        name: name.to_string(),
+        schema: schema.to_string(),
+    })
+}
+
+/// Parse `"<name>" [<version_req>] [<- <binding>] [-> <output>]`
+fn parse_tool(rest: &str, lineno: usize) -> CoreResult<Statement> {
+    let rest = rest
+        .strip_prefix('"')
+        .ok_or_else(|| error(lineno, "expected quoted tool name"))?;
+    let (name, rest) = rest
+        .split_once('"')
+        .ok_or_else(|| error(lineno, "unterminated tool name"))?;
+    if name.is_empty() {
+        return Err(error(lineno, "empty tool name"));
+    }
+
+    let mut version_req = None;
+    let mut args = Vec::new();
+    let mut output = None;
+
+    let mut tokens = rest.split_whitespace();
+    while let Some(token) = tokens.next() {
+        match token {
+            "<-" => {
+                let binding = tokens
+                    .next()
+                    .ok_or_else(|| error(lineno, "expected binding after '<-'"))?;
+                args.push(Expr::Variable(binding.to_string()));
+            }
+            "->" => {
+                let name = tokens
+                    .next()
+                    .ok_or_else(|| error(lineno, "expected output name after '->'"))?;
+                output = Some(name.to_string());
+            }
+            req if version_req.is_none() && args.is_empty() && output.is_none() => {
+                version_req = Some(req.to_string());
+            }
+            other => return Err(error(lineno, &format!("unexpected token '{}'", other))),
+        }
+    }
+
+    Ok(Statement::ToolCall {
+        name: name.to_string(),
+        version_req,
+        args,
+        output,
+    })
+}
+
+/// Parse `<binding> [<op> <literal>]`
+fn parse_if(rest: &str, lineno: usize) -> CoreResult<OpenConditional> {
+    let (binding, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    if binding.is_empty() {
+        return Err(error(lineno, "expected 'if <binding> [<op> <literal>]'"));
+    }
+
+    let rest = rest.trim();
+    let predicate = if rest.is_empty() {
+        Predicate::Truthy
+    } else {
+        let (op, literal) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        let op = Comparison::from_token(op)
+            .ok_or_else(|| error(lineno, &format!("unknown comparison '{}'", op)))?;
+        Predicate::Compare {
+            op,
+            value: parse_literal(literal.trim(), lineno)?,
+        }
+    };
+
+    Ok(OpenConditional {
+        lineno,
+        binding: binding.to_string(),
+        predicate,
+        then_branch: Vec::new(),
+        else_branch: None,
+    })
+}
+
+/// Parse an integer, `true`/`false`, or a quoted string
+fn parse_literal(literal: &str, lineno: usize) -> CoreResult<Literal> {
+    if let Some(inner) = literal.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        return Ok(Literal::String(inner.to_string()));
+    }
+    match literal {
+        "true" => Ok(Literal::Bool(true)),
+        "false" => Ok(Literal::Bool(false)),
+        _ => literal
+            .parse()
+            .map(Literal::Integer)
+            .map_err(|_| error(lineno, &format!("expected literal, found '{}'", literal))),
+    }
+}
+
+/// Parse `"<name>" [<version_req>] over <binding> [parallel <n>] [reduce <agg>] [-> <output>]`
+fn parse_map(rest: &str, lineno: usize) -> CoreResult<Statement> {
+    let rest = rest
+        .strip_prefix('"')
+        .ok_or_else(|| error(lineno, "expected quoted tool name"))?;
+    let (name, rest) = rest
+        .split_once('"')
+        .ok_or_else(|| error(lineno, "unterminated tool name"))?;
+    if name.is_empty() {
+        return Err(error(lineno, "empty tool name"));
+    }
+
+    let mut version_req = None;
+    let mut over = None;
+    let mut max_parallel = DEFAULT_MAX_PARALLEL;
+    let mut aggregate = Aggregation::default();
+    let mut output = None;
+
+    let mut tokens = rest.split_whitespace();
+    while let Some(token) = tokens.next() {
+        let mut operand = || {
+            tokens
+                .next()
+                .ok_or_else(|| error(lineno, &format!("expected value after '{}'", token)))
+        };
+        match token {
+            "over" => over = Some(operand()?.to_string()),
+            "parallel" => {
+                let n = operand()?;
+                max_parallel = n
+                    .parse()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| error(lineno, &format!("invalid parallelism '{}'", n)))?;
+            }
+            "reduce" => {
+                let keyword = operand()?;
+                aggregate = Aggregation::from_keyword(keyword)
+                    .ok_or_else(|| error(lineno, &format!("unknown reduction '{}'", keyword)))?;
+            }
+            "->" => output = Some(operand()?.to_string()),
+            req if version_req.is_none() && over.is_none() => {
+                version_req = Some(req.to_string());
+            }
+            other => return Err(error(lineno, &format!("unexpected token '{}'", other))),
+        }
+    }
+
+    Ok(Statement::MapReduce {
+        name: name.to_string(),
+        version_req,
+        over: over.ok_or_else(|| error(lineno, "expected 'over <binding>'"))?,
+        max_parallel,
+        aggregate,
+        output,
+    })
+}
+
+/// Parse `<hash> [<- <binding>] [-> <output>]`
+fn parse_workflow(rest: &str, lineno: usize) -> CoreResult<Statement> {
+    let mut tokens = rest.split_whitespace();
+    let hash = tokens
+        .next()
+        .ok_or_else(|| error(lineno, "expected workflow hash"))?;
+    let hash = Hash::from_hex(hash)
+        .map_err(|_| error(lineno, &format!("invalid workflow hash '{}'", hash)))?;
+
+    let mut input = None;
+    let mut output = None;
+    while let Some(token) = tokens.next() {
+        let slot = match token {
+            "<-" if input.is_none() => &mut input,
+            "->" if output.is_none() => &mut output,
+            other => return Err(error(lineno, &format!("unexpected token '{}'", other))),
+        };
+        let name = tokens
+            .next()
+            .ok_or_else(|| error(lineno, &format!("expected binding after '{}'", token)))?;
+        *slot = Some(name.to_string());
+    }
+
+    Ok(Statement::SubWorkflow { hash, input, output })
+}
+
+/// Parse `<name> = <binding>`
+fn parse_output(rest: &str, lineno: usize) -> CoreResult<Statement> {
+    let (name, value) = rest
+        .split_once('=')
+        .ok_or_else(|| error(lineno, "expected 'output <name> = <binding>'"))?;
+    let (name, value) = (name.trim(), value.trim());
+    if name.is_empty() || value.is_empty() {
+        return Err(error(lineno, "expected 'output <name> = <binding>'"));
+    }
+
+    Ok(Statement::Output {
+        name: name.to_string(),
+        value: Expr::Variable(value.to_string()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -36,4 +341,110 @@ mod tests {
         let result = parse("input x: string");
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_parse_tool() {
+        let ast = parse(
+            "# echo the input back\n\
+             input data: string\n\
+             tool \"echo\" ^1.0 <- data -> echoed\n\
+             output result = echoed\n",
+        )
+        .unwrap();
+
+        assert_eq!(ast.statements.len(), 3);
+        assert_eq!(
+            ast.statements[1],
+            Statement::ToolCall {
+                name: "echo".to_string(),
+                version_req: Some("^1.0".to_string()),
+                args: vec![Expr::Variable("data".to_string())],
+                output: Some("echoed".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_control_flow() {
+        let ast = parse(
+            "input items: list<integer>\n\
+             input mode: string\n\
+             map \"double\" ^1 over items parallel 2 reduce sum -> total\n\
+             if mode == \"fast\"\n\
+             \x20 tool \"echo\" <- total -> result\n\
+             else\n\
+             \x20 if total\n\
+             \x20 end\n\
+             end\n",
+        )
+        .unwrap();
+
+        assert_eq!(ast.statements.len(), 4);
+        assert_eq!(
+            ast.statements[2],
+            Statement::MapReduce {
+                name: "double".to_string(),
+                version_req: Some("^1".to_string()),
+                over: "items".to_string(),
+                max_parallel: 2,
+                aggregate: Aggregation::Sum,
+                output: Some("total".to_string()),
+            }
+        );
+        let Statement::Conditional { binding, predicate, then_branch, else_branch } =
+            &ast.statements[3]
+        else {
+            panic!("expected conditional, got {:?}", ast.statements[3]);
+        };
+        assert_eq!(binding, "mode");
+        assert_eq!(
+            *predicate,
+            Predicate::Compare {
+                op: Comparison::Eq,
+                value: Literal::String("fast".to_string()),
+            }
+        );
+        assert_eq!(then_branch.len(), 1);
+        assert!(matches!(
+            &else_branch[..],
+            [Statement::Conditional { predicate: Predicate::Truthy, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_parse_control_flow_errors() {
+        assert!(parse("if x\n").is_err());
+        assert!(parse("end\n").is_err());
+        assert!(parse("if x\nelse\nelse\nend\n").is_err());
+        assert!(parse("if x ~ 1\nend\n").is_err());
+        assert!(parse("if x == one\nend\n").is_err());
+        assert!(parse("map \"double\" parallel 2").is_err());
+        assert!(parse("map \"double\" over xs parallel 0").is_err());
+        assert!(parse("map \"double\" over xs reduce avg").is_err());
+    }
+
+    #[test]
+    fn test_parse_workflow() {
+        let hash = Hash::compute(b"child");
+        let ast = parse(&format!("workflow {} <- data -> summary", hash.to_hex())).unwrap();
+        assert_eq!(
+            ast.statements,
+            [Statement::SubWorkflow {
+                hash,
+                input: Some("data".to_string()),
+                output: Some("summary".to_string()),
+            }]
+        );
+
+        assert!(parse("workflow").is_err());
+        assert!(parse("workflow abc").is_err());
+        assert!(parse(&format!("workflow {} -> a -> b", hash.to_hex())).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("tool echo").is_err());
+        assert!(parse("tool \"echo\" ^1.0 ^2.0").is_err());
+        assert!(parse("bogus x").is_err());
+    }
 }
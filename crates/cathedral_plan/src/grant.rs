@@ -0,0 +1,228 @@
+//! Least-privilege capability grants.
+//!
+//! The planner attaches the capabilities it expects each node to need.
+//! This pass narrows them to what the node's tool schema actually uses and
+//! records the result per node, so the runtime can run every node with its
+//! own grant instead of the run-wide capability set.
+
+use crate::compiler::CompilerWarning;
+use crate::dag::{Dag, NodeKind};
+use cathedral_core::{Capability, CapabilitySet, NodeId};
+use cathedral_tool::ToolRegistry;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+/// Capabilities each node of a workflow runs with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityGrantTable {
+    /// Grants by node, in DAG order
+    grants: IndexMap<NodeId, CapabilitySet>,
+}
+
+impl CapabilityGrantTable {
+    /// Create an empty grant table
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the capabilities a node runs with
+    pub fn grant(&mut self, node_id: NodeId, capabilities: CapabilitySet) {
+        self.grants.insert(node_id, capabilities);
+    }
+
+    /// Get a node's grant, if the table has one
+    #[must_use]
+    pub fn get(&self, node_id: NodeId) -> Option<&CapabilitySet> {
+        self.grants.get(&node_id)
+    }
+
+    /// Get a node's grant; a node missing from the table is granted nothing
+    #[must_use]
+    pub fn for_node(&self, node_id: NodeId) -> CapabilitySet {
+        self.grants.get(&node_id).cloned().unwrap_or_default()
+    }
+
+    /// The minimal capability set covering every node's grant
+    ///
+    /// Capabilities covered by another capability in the union are dropped.
+    #[must_use]
+    pub fn workflow_set(&self) -> CapabilitySet {
+        let union: CapabilitySet = self.grants.values().flat_map(|g| g.iter().cloned()).collect();
+        union
+            .iter()
+            .filter(|cap| {
+                !union.iter().any(|other| {
+                    // Of two capabilities covering each other, keep the first
+                    other != *cap && other.covers(cap) && (!cap.covers(other) || other < *cap)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Iterate over node grants in DAG order
+    pub fn iter(&self) -> impl Iterator<Item = (&NodeId, &CapabilitySet)> {
+        self.grants.iter()
+    }
+
+    /// Get the number of nodes with a grant
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.grants.len()
+    }
+
+    /// Check if the table is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.grants.is_empty()
+    }
+}
+
+/// Compute the least-privilege grant for every node in `dag`
+///
+/// A tool node whose tool resolves in `registry` is granted exactly the
+/// schema capabilities its requested capabilities cover. Requested
+/// capabilities the schema never uses are dropped with an
+/// [`CompilerWarning::UnusedCapability`]; schema capabilities the node did
+/// not request are withheld with an [`CompilerWarning::UngrantedCapability`],
+/// since the node will be denied them at run time. Every other node keeps
+/// what it requested.
+#[must_use]
+pub fn least_privilege(
+    dag: &Dag,
+    registry: Option<&ToolRegistry>,
+) -> (CapabilityGrantTable, Vec<CompilerWarning>) {
+    let mut table = CapabilityGrantTable::new();
+    let mut warnings = Vec::new();
+
+    for (id, node) in &dag.nodes {
+        let requested: CapabilitySet = node.capabilities.iter().cloned().collect();
+        let schema = match &node.kind {
+            NodeKind::Tool { name, version_req, .. }
+            | NodeKind::MapReduce { name, version_req, .. } => registry
+                .and_then(|r| r.resolve(name, version_req).ok())
+                .map(|entry| (name, entry.schema)),
+            _ => None,
+        };
+        let Some((tool, schema)) = schema else {
+            table.grant(*id, requested);
+            continue;
+        };
+
+        let warn = |capability: &Capability, used: bool| {
+            let (node_id, tool, capability) = (*id, tool.clone(), capability.clone());
+            if used {
+                CompilerWarning::UngrantedCapability { node_id, tool, capability }
+            } else {
+                CompilerWarning::UnusedCapability { node_id, tool, capability }
+            }
+        };
+        for cap in requested.iter() {
+            if !schema.capabilities.iter().any(|used| cap.covers(used)) {
+                warnings.push(warn(cap, false));
+            }
+        }
+        let mut granted = CapabilitySet::new();
+        for used in &schema.capabilities {
+            if requested.covers(used) {
+                granted.grant(used.clone());
+            } else {
+                warnings.push(warn(used, true));
+            }
+        }
+        table.grant(*id, granted);
+    }
+
+    (table, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::{Node, ResourceRequirements};
+    use cathedral_tool::{Tool, ToolOutput, ToolSchema};
+    use indexmap::IndexSet;
+    use std::sync::Arc;
+
+    struct Reader;
+
+    impl Tool for Reader {
+        fn name(&self) -> &str {
+            "read_file"
+        }
+
+        fn execute(&self, input: &[u8]) -> cathedral_core::CoreResult<ToolOutput> {
+            Ok(ToolOutput::success(input.to_vec()))
+        }
+    }
+
+    fn fs_read(prefix: &str) -> Capability {
+        Capability::FsRead {
+            prefixes: vec![prefix.to_string()],
+        }
+    }
+
+    fn tool_node(name: &str, capabilities: Vec<Capability>) -> Node {
+        Node {
+            id: NodeId::new(),
+            kind: NodeKind::Tool {
+                name: name.to_string(),
+                version_req: "*".to_string(),
+                input_binding: None,
+            },
+            dependencies: IndexSet::new(),
+            capabilities,
+            resources: ResourceRequirements::new(),
+        }
+    }
+
+    #[test]
+    fn test_least_privilege_narrows_to_schema() {
+        let mut registry = ToolRegistry::new();
+        let schema = ToolSchema::new("read_file".to_string(), "1.0.0".to_string())
+            .with_capability(fs_read("./data"))
+            .with_capability(Capability::ClockRead);
+        registry.register(Arc::new(Reader), schema).unwrap();
+
+        let mut dag = Dag::new();
+        let reader = tool_node("read_file", vec![fs_read("."), Capability::IdGen]);
+        let unknown = tool_node("http_get", vec![fs_read("./cache")]);
+        let (reader_id, unknown_id) = (reader.id, unknown.id);
+        dag.add_node(reader).unwrap();
+        dag.add_node(unknown).unwrap();
+
+        let (table, warnings) = least_privilege(&dag, Some(&registry));
+        let granted: Vec<_> = table.for_node(reader_id).iter().cloned().collect();
+        assert_eq!(granted, [fs_read("./data")]);
+        assert_eq!(
+            warnings,
+            [
+                CompilerWarning::UnusedCapability {
+                    node_id: reader_id,
+                    tool: "read_file".to_string(),
+                    capability: Capability::IdGen,
+                },
+                CompilerWarning::UngrantedCapability {
+                    node_id: reader_id,
+                    tool: "read_file".to_string(),
+                    capability: Capability::ClockRead,
+                },
+            ]
+        );
+        assert!(table.for_node(unknown_id).has(&fs_read("./cache")));
+        assert!(table.for_node(NodeId::new()).is_empty());
+
+        let workflow: Vec<_> = table.workflow_set().iter().cloned().collect();
+        assert_eq!(workflow, [fs_read("./cache"), fs_read("./data")]);
+    }
+
+    #[test]
+    fn test_workflow_set_drops_covered_capabilities() {
+        let mut table = CapabilityGrantTable::new();
+        table.grant(NodeId::new(), [fs_read("./a/b"), fs_read(".")].into_iter().collect());
+        table.grant(NodeId::new(), [fs_read("*"), Capability::IdGen].into_iter().collect());
+        let workflow: Vec<_> = table.workflow_set().iter().cloned().collect();
+        assert_eq!(workflow, [fs_read("*"), Capability::IdGen]);
+    }
+}
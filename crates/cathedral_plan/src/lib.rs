@@ -11,10 +11,18 @@ pub mod dag;
 pub mod compiler;
 pub mod resource;
 pub mod validate;
+pub mod grant;
+pub mod control;
+pub mod library;
+pub mod diagnostic;
 
 pub use dsl::{parse, ParseError};
 pub use compiler::Ast;
-pub use dag::{Dag, Node, Edge, NodeKind};
+pub use dag::{Dag, Node, Edge, NodeKind, THEN_PORT, ELSE_PORT};
 pub use compiler::{Compiler, CompilerOutput, CompilerWarning};
 pub use resource::{ResourceContract, ResourceBounds};
 pub use validate::{Validator, ValidationError};
+pub use grant::{least_privilege, CapabilityGrantTable};
+pub use control::{Aggregation, Comparison, Literal, Predicate};
+pub use library::WorkflowLibrary;
+pub use diagnostic::{check, Diagnostic, Severity, Span};
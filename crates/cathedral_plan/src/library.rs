@@ -0,0 +1,51 @@
+//! Compiled workflows addressed by content hash.
+
+use cathedral_core::Hash;
+use indexmap::IndexMap;
+use super::dag::Dag;
+
+/// Compiled workflows that other workflows can invoke as sub-workflows
+#[derive(Debug, Clone, Default)]
+pub struct WorkflowLibrary {
+    /// DAGs by content hash
+    workflows: IndexMap<Hash, Dag>,
+}
+
+impl WorkflowLibrary {
+    /// Create an empty library
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a compiled DAG, returning its content hash
+    pub fn insert(&mut self, dag: Dag) -> Hash {
+        let hash = dag.content_hash();
+        self.workflows.insert(hash, dag);
+        hash
+    }
+
+    /// Get a DAG by content hash
+    #[must_use]
+    pub fn get(&self, hash: &Hash) -> Option<&Dag> {
+        self.workflows.get(hash)
+    }
+
+    /// Check if a DAG is in the library
+    #[must_use]
+    pub fn contains(&self, hash: &Hash) -> bool {
+        self.workflows.contains_key(hash)
+    }
+
+    /// Get the number of workflows
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.workflows.len()
+    }
+
+    /// Check if the library is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.workflows.is_empty()
+    }
+}
@@ -1,9 +1,10 @@
 //! Resource contracts for workflow nodes.
 
 use serde::{Deserialize, Serialize};
+use super::dag::ResourceRequirements;
 
 /// Resource contract for a node or workflow
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ResourceContract {
     /// Memory requirements
     pub memory: ResourceBounds,
@@ -13,6 +14,9 @@ pub struct ResourceContract {
     pub storage: ResourceBounds,
     /// Network requirements
     pub network: ResourceBounds,
+    /// Execution fuel, in logical ticks
+    #[serde(default)]
+    pub fuel: ResourceBounds,
 }
 
 impl ResourceContract {
@@ -24,6 +28,7 @@ impl ResourceContract {
             cpu: ResourceBounds::default(),
             storage: ResourceBounds::default(),
             network: ResourceBounds::default(),
+            fuel: ResourceBounds::default(),
         }
     }
 
@@ -40,6 +45,38 @@ impl ResourceContract {
         self.cpu = bounds;
         self
     }
+
+    /// Contract bounding a single node's requirements
+    #[must_use]
+    pub fn from_requirements(requirements: &ResourceRequirements) -> Self {
+        let max = |value: Option<u64>| ResourceBounds {
+            max: value,
+            ..ResourceBounds::new()
+        };
+        Self {
+            memory: max(requirements.max_memory),
+            cpu: max(requirements.cpu_shares.map(u64::from)),
+            storage: max(requirements.disk_space),
+            network: max(requirements.network_bandwidth),
+            fuel: max(requirements.max_ticks),
+        }
+    }
+
+    /// Roll another contract, such as a sub-workflow's, into this one
+    ///
+    /// Nodes run one at a time, so memory, CPU, and network take the peak
+    /// of the two; stored data outlives the node that wrote it and fuel is
+    /// spent for good, so storage and fuel add up.
+    #[must_use]
+    pub fn rollup(&self, other: &Self) -> Self {
+        Self {
+            memory: self.memory.peak(&other.memory),
+            cpu: self.cpu.peak(&other.cpu),
+            storage: self.storage.total(&other.storage),
+            network: self.network.peak(&other.network),
+            fuel: self.fuel.total(&other.fuel),
+        }
+    }
 }
 
 impl Default for ResourceContract {
@@ -49,7 +86,7 @@ impl Default for ResourceContract {
 }
 
 /// Resource bounds (min/max)
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ResourceBounds {
     /// Minimum required
     pub min: Option<u64>,
@@ -91,6 +128,31 @@ impl ResourceBounds {
         self
     }
 
+    /// Bounds covering either of two consumers, one at a time
+    #[must_use]
+    pub fn peak(&self, other: &Self) -> Self {
+        self.combine(other, u64::max)
+    }
+
+    /// Bounds covering two consumers held at once
+    #[must_use]
+    pub fn total(&self, other: &Self) -> Self {
+        self.combine(other, u64::saturating_add)
+    }
+
+    /// Combine each bound; an unset bound defers to the other side
+    fn combine(&self, other: &Self, op: fn(u64, u64) -> u64) -> Self {
+        let pick = |a: Option<u64>, b: Option<u64>| match (a, b) {
+            (Some(a), Some(b)) => Some(op(a, b)),
+            (a, b) => a.or(b),
+        };
+        Self {
+            min: pick(self.min, other.min),
+            max: pick(self.max, other.max),
+            default_value: pick(self.default_value, other.default_value),
+        }
+    }
+
     /// Check if a value is within bounds
     #[must_use]
     pub fn check(&self, value: u64) -> bool {
@@ -146,6 +208,24 @@ mod tests {
         assert!(contract.cpu.min.is_none());
     }
 
+    #[test]
+    fn test_resource_contract_rollup() {
+        let parent = ResourceContract::new()
+            .with_memory(ResourceBounds::new().with_max(512))
+            .with_cpu(ResourceBounds::new().with_min(2));
+        let mut child = ResourceContract::from_requirements(
+            &ResourceRequirements::new().with_max_memory(1024),
+        );
+        child.storage = ResourceBounds::new().with_max(100);
+
+        let rolled = parent.rollup(&child).rollup(&child);
+        assert_eq!(rolled.memory.max, Some(1024));
+        assert_eq!(rolled.cpu.min, Some(2));
+        assert_eq!(rolled.storage.max, Some(200));
+        assert_eq!(rolled.fuel, ResourceBounds::new());
+        assert_eq!(rolled.network, ResourceBounds::new());
+    }
+
     #[test]
     fn test_resource_contract_with_memory() {
         let bounds = ResourceBounds::new().with_max(1024);
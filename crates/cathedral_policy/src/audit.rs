@@ -0,0 +1,503 @@
+//! Audit trails: who did what, when, under which policy.
+//!
+//! An [`AuditTrail`] correlates the API calls made against runs, recorded
+//! as [`EventKind::ApiCall`] events, with the runs' execution events. Each
+//! `PolicyDecision` event contributes the policy, rule and proof behind
+//! the decision. Execution entries are attributed to the principal that
+//! submitted the run.
+//!
+//! API calls and execution events share no clock, so a trail lists each
+//! source in its own log order: API calls by wall-clock time, execution
+//! events by logical time.
+
+use crate::proof::DecisionProof;
+use cathedral_core::{CoreError, CoreResult, EventId, LogicalTime, NodeId, RunId, TenantId};
+use cathedral_log::{Event, EventKind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+/// Actor of execution events of runs with no recorded submission
+pub const ENGINE_ACTOR: &str = "engine";
+
+/// Principal of calls made without authentication
+pub const ANONYMOUS: &str = "anonymous";
+
+/// An API call, as recorded in the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiCall {
+    /// Token holder that made the call, or [`ANONYMOUS`]
+    pub principal: String,
+    /// Tenant the call acted for
+    pub tenant: TenantId,
+    /// HTTP method
+    pub method: String,
+    /// Route, e.g. `/runs/{id}`
+    pub route: String,
+    /// HTTP status of the response
+    pub status: u16,
+    /// Run the call concerned, if any
+    pub run_id: Option<RunId>,
+    /// Wall-clock time of the call, in milliseconds since the Unix epoch
+    pub at_ms: u64,
+}
+
+impl ApiCall {
+    /// Encode the call as the `position`-th event of an audit log
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the call cannot be encoded
+    pub fn to_event(&self, position: u64) -> CoreResult<Event> {
+        Ok(Event::new(
+            EventId::new(),
+            self.run_id.unwrap_or(RunId::from_bytes([0; 16])),
+            NodeId::from_bytes([0; 16]),
+            LogicalTime::from_raw(position),
+            EventKind::ApiCall,
+        )
+        .with_payload(serde_json::to_vec(self)?))
+    }
+
+    /// Decode the call recorded by an `ApiCall` event
+    #[must_use]
+    pub fn from_event(event: &Event) -> Option<Self> {
+        if event.kind != EventKind::ApiCall {
+            return None;
+        }
+        serde_json::from_slice(&event.payload).ok()
+    }
+}
+
+/// Where an audit entry came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditSource {
+    /// An API call
+    Api,
+    /// An event of a run's log
+    Execution,
+}
+
+/// One row of an audit trail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the trail
+    pub sequence: u64,
+    /// Where the entry came from
+    pub source: AuditSource,
+    /// Run concerned, if any, in its display form
+    pub run_id: Option<String>,
+    /// Node concerned, if any, in its display form
+    pub node_id: Option<String>,
+    /// Tenant the entry belongs to, in its display form
+    pub tenant: String,
+    /// Who acted
+    pub actor: String,
+    /// What was done: `<METHOD> <route>` for calls, the event kind otherwise
+    pub action: String,
+    /// HTTP status of a call, `allowed` or `denied` for a policy decision
+    pub outcome: Option<String>,
+    /// Wall-clock time of a call, in milliseconds since the Unix epoch
+    pub at_ms: Option<u64>,
+    /// Logical time of an execution event
+    pub logical_time: Option<u64>,
+    /// Policy behind a decision
+    pub policy_id: Option<String>,
+    /// Rule that decided
+    pub rule_id: Option<String>,
+    /// Proof of the decision
+    pub proof_id: Option<String>,
+    /// Capability a decision was about
+    pub capability: Option<String>,
+}
+
+/// Filter over audit entries; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditQuery {
+    /// Only entries of this run, in its display form
+    pub run_id: Option<String>,
+    /// Only entries of this tenant
+    pub tenant: Option<TenantId>,
+    /// Only entries by this actor
+    pub actor: Option<String>,
+    /// Only entries with this action
+    pub action: Option<String>,
+}
+
+impl AuditQuery {
+    /// Match every entry
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only match a run's entries
+    #[must_use]
+    pub fn with_run(mut self, run_id: RunId) -> Self {
+        self.run_id = Some(run_id.to_string());
+        self
+    }
+
+    /// Only match a tenant's entries
+    #[must_use]
+    pub fn with_tenant(mut self, tenant: TenantId) -> Self {
+        self.tenant = Some(tenant);
+        self
+    }
+
+    /// Only match an actor's entries
+    #[must_use]
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    /// Only match entries with an action
+    #[must_use]
+    pub fn with_action(mut self, action: impl Into<String>) -> Self {
+        self.action = Some(action.into());
+        self
+    }
+
+    /// Check if an entry matches
+    #[must_use]
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        let same =
+            |wanted: &Option<String>, value: &str| wanted.as_deref().is_none_or(|w| w == value);
+        same(&self.run_id, entry.run_id.as_deref().unwrap_or_default())
+            && self.tenant.is_none_or(|tenant| entry.tenant == tenant.to_string())
+            && same(&self.actor, &entry.actor)
+            && same(&self.action, &entry.action)
+    }
+}
+
+/// Correlated audit record of API calls and run execution
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditTrail {
+    /// Entries, API calls first
+    pub entries: Vec<AuditEntry>,
+    /// Principal that submitted each run
+    #[serde(skip)]
+    submitters: HashMap<RunId, String>,
+}
+
+impl AuditTrail {
+    /// Create an empty trail
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the API calls recorded in an audit log
+    ///
+    /// Events other than `ApiCall` are skipped. Call this before adding
+    /// runs, so their events are attributed to their submitters.
+    pub fn add_api_calls(&mut self, events: &[Event]) {
+        for call in events.iter().filter_map(ApiCall::from_event) {
+            if call.method == "POST"
+                && let Some(run_id) = call.run_id
+            {
+                self.submitters
+                    .entry(run_id)
+                    .or_insert_with(|| call.principal.clone());
+            }
+            self.push(AuditEntry {
+                sequence: 0,
+                source: AuditSource::Api,
+                run_id: call.run_id.map(|id| id.to_string()),
+                node_id: None,
+                tenant: call.tenant.to_string(),
+                actor: call.principal,
+                action: format!("{} {}", call.method, call.route),
+                outcome: Some(call.status.to_string()),
+                at_ms: Some(call.at_ms),
+                logical_time: None,
+                policy_id: None,
+                rule_id: None,
+                proof_id: None,
+                capability: None,
+            });
+        }
+    }
+
+    /// Add the events of a tenant's run log
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a `PolicyDecision` event does not hold a proof
+    pub fn add_execution(&mut self, tenant: TenantId, events: &[Event]) -> CoreResult<()> {
+        for event in events {
+            let actor = self
+                .submitters
+                .get(&event.run_id)
+                .map_or(ENGINE_ACTOR, String::as_str)
+                .to_string();
+            let mut entry = AuditEntry {
+                sequence: 0,
+                source: AuditSource::Execution,
+                run_id: Some(event.run_id.to_string()),
+                node_id: Some(event.node_id.to_string()),
+                tenant: tenant.to_string(),
+                actor,
+                action: format!("{:?}", event.kind),
+                outcome: None,
+                at_ms: None,
+                logical_time: Some(event.logical_time.as_u64()),
+                policy_id: None,
+                rule_id: None,
+                proof_id: None,
+                capability: None,
+            };
+            if event.kind == EventKind::PolicyDecision {
+                let proof: DecisionProof = serde_json::from_slice(&event.payload)?;
+                let outcome = if proof.decision { "allowed" } else { "denied" };
+                entry.outcome = Some(outcome.to_string());
+                entry.policy_id = proof.policy_id;
+                entry.rule_id = proof.rule_id;
+                entry.proof_id = Some(proof.id);
+                entry.capability = proof.capability.map(|capability| capability.to_string());
+            }
+            self.push(entry);
+        }
+        Ok(())
+    }
+
+    /// Keep only the entries a query matches, renumbered
+    #[must_use]
+    pub fn filter(mut self, query: &AuditQuery) -> Self {
+        self.entries.retain(|entry| query.matches(entry));
+        for (sequence, entry) in self.entries.iter_mut().enumerate() {
+            entry.sequence = sequence as u64;
+        }
+        self
+    }
+
+    /// Append an entry, numbering it
+    fn push(&mut self, mut entry: AuditEntry) {
+        entry.sequence = self.entries.len() as u64;
+        self.entries.push(entry);
+    }
+
+    /// Render the trail as one JSON object per line
+    ///
+    /// # Errors
+    ///
+    /// Returns error if an entry cannot be encoded
+    pub fn to_jsonl(&self) -> CoreResult<String> {
+        let mut out = String::new();
+        for entry in &self.entries {
+            out.push_str(&serde_json::to_string(entry)?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    /// Render the trail as CSV with a header row
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut out = CSV_COLUMNS.join(",");
+        out.push('\n');
+        let text = |value: Option<String>| value.unwrap_or_default();
+        for entry in &self.entries {
+            let source = match entry.source {
+                AuditSource::Api => "api",
+                AuditSource::Execution => "execution",
+            };
+            let row = [
+                entry.sequence.to_string(),
+                source.to_string(),
+                text(entry.run_id.clone()),
+                text(entry.node_id.clone()),
+                entry.tenant.clone(),
+                entry.actor.clone(),
+                entry.action.clone(),
+                text(entry.outcome.clone()),
+                text(entry.at_ms.map(|ms| ms.to_string())),
+                text(entry.logical_time.map(|time| time.to_string())),
+                text(entry.policy_id.clone()),
+                text(entry.rule_id.clone()),
+                text(entry.proof_id.clone()),
+                text(entry.capability.clone()),
+            ];
+            let fields: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+            let _ = writeln!(out, "{}", fields.join(","));
+        }
+        out
+    }
+
+    /// Render the trail in a format
+    ///
+    /// # Errors
+    ///
+    /// Returns error if an entry cannot be encoded
+    pub fn render(&self, format: AuditFormat) -> CoreResult<String> {
+        match format {
+            AuditFormat::Json => Ok(serde_json::to_string_pretty(self)? + "\n"),
+            AuditFormat::Jsonl => self.to_jsonl(),
+            AuditFormat::Csv => Ok(self.to_csv()),
+        }
+    }
+}
+
+/// Columns of the CSV export, in order
+pub const CSV_COLUMNS: [&str; 14] = [
+    "sequence",
+    "source",
+    "run_id",
+    "node_id",
+    "tenant",
+    "actor",
+    "action",
+    "outcome",
+    "at_ms",
+    "logical_time",
+    "policy_id",
+    "rule_id",
+    "proof_id",
+    "capability",
+];
+
+/// Quote a CSV field if it holds a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Export format of an audit trail
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditFormat {
+    /// One JSON document
+    #[default]
+    Json,
+    /// One JSON object per entry per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+impl AuditFormat {
+    /// MIME type of the format
+    #[must_use]
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Jsonl => "application/x-ndjson",
+            Self::Csv => "text/csv",
+        }
+    }
+}
+
+impl FromStr for AuditFormat {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> CoreResult<Self> {
+        match s {
+            "json" => Ok(Self::Json),
+            "jsonl" => Ok(Self::Jsonl),
+            "csv" => Ok(Self::Csv),
+            other => Err(CoreError::ParseError {
+                message: format!(
+                    "unknown audit format {:?}, expected json, jsonl or csv",
+                    other
+                ),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::ProofKind;
+    use cathedral_core::Capability;
+
+    fn call(method: &str, route: &str, run_id: RunId, principal: &str) -> Event {
+        ApiCall {
+            principal: principal.to_string(),
+            tenant: TenantId::from_name("acme"),
+            method: method.to_string(),
+            route: route.to_string(),
+            status: 202,
+            run_id: Some(run_id),
+            at_ms: 1_700_000_000_000,
+        }
+        .to_event(0)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_trail_correlates_calls_and_decisions() {
+        let run_id = RunId::new();
+        let node_id = NodeId::new();
+        let proof = DecisionProof::new(ProofKind::CapabilityCheck, false)
+            .with_node(node_id)
+            .with_capability(Capability::ClockRead)
+            .finalize()
+            .unwrap();
+        let decision = Event::new(
+            EventId::new(),
+            run_id,
+            node_id,
+            LogicalTime::from_raw(3),
+            EventKind::PolicyDecision,
+        )
+        .with_payload(serde_json::to_vec(&proof).unwrap());
+        let started = Event::new(
+            EventId::new(),
+            run_id,
+            node_id,
+            LogicalTime::from_raw(2),
+            EventKind::NodeStarted,
+        );
+
+        let mut trail = AuditTrail::new();
+        trail.add_api_calls(&[
+            call("POST", "/runs", run_id, "ci"),
+            call("DELETE", "/runs/{id}", run_id, "ops"),
+        ]);
+        trail
+            .add_execution(TenantId::from_name("acme"), &[started, decision])
+            .unwrap();
+
+        assert_eq!(trail.entries.len(), 4);
+        let denied = &trail.entries[3];
+        assert_eq!(denied.actor, "ci");
+        assert_eq!(denied.action, "PolicyDecision");
+        assert_eq!(denied.outcome.as_deref(), Some("denied"));
+        assert_eq!(denied.proof_id, Some(proof.id));
+        assert_eq!(denied.capability.as_deref(), Some("ClockRead"));
+        assert_eq!(denied.tenant, TenantId::from_name("acme").to_string());
+
+        let by_ops = trail.clone().filter(&AuditQuery::new().with_actor("ops"));
+        assert_eq!(by_ops.entries.len(), 1);
+        assert_eq!(by_ops.entries[0].action, "DELETE /runs/{id}");
+        assert_eq!(by_ops.entries[0].sequence, 0);
+        let other_run = AuditQuery::new().with_run(RunId::new());
+        assert!(trail.filter(&other_run).entries.is_empty());
+    }
+
+    #[test]
+    fn test_trail_exports() {
+        let run_id = RunId::new();
+        let mut trail = AuditTrail::new();
+        trail.add_api_calls(&[call("POST", "/runs", run_id, "ci, \"bot\"")]);
+
+        let csv = trail.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap(), CSV_COLUMNS.join(","));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with(&format!("0,api,{},,{},", run_id, TenantId::from_name("acme"))));
+        assert!(row.contains(",\"ci, \"\"bot\"\"\",POST /runs,202,"));
+
+        let jsonl = trail.render("jsonl".parse().unwrap()).unwrap();
+        let entry: AuditEntry = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(entry, trail.entries[0]);
+        assert!("xml".parse::<AuditFormat>().is_err());
+    }
+}
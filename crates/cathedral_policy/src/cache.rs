@@ -0,0 +1,198 @@
+//! Run-scoped coalescing of repeated capability requests.
+
+use crate::compiler::{CompiledPolicy, EvalContext, PolicyDecision};
+use crate::proof::{DecisionProof, ProofField, ProofKind};
+use cathedral_core::{Capability, CoreError, CoreResult, Hash};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Cache key: capability content hash and context hash
+pub type DecisionKey = (Hash, Hash);
+
+/// A decision and the proof produced when it was first evaluated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedDecision {
+    /// Policy decision
+    pub decision: PolicyDecision,
+    /// Proof of the decision
+    pub proof: DecisionProof,
+}
+
+/// Per-run cache of capability decisions
+///
+/// The cache holds its own copy of the policy, so a policy reload during
+/// the run cannot mix decisions from two policy versions. Start a new
+/// cache for each run.
+pub struct DecisionCache {
+    /// Policy snapshot for this run
+    policy: CompiledPolicy,
+    /// Cached decisions
+    entries: HashMap<DecisionKey, CachedDecision>,
+    /// Number of full policy evaluations
+    evaluations: usize,
+    /// Number of requests served from the cache
+    hits: usize,
+}
+
+impl DecisionCache {
+    /// Create a cache for one run of `policy`
+    #[must_use]
+    pub fn new(policy: CompiledPolicy) -> Self {
+        Self {
+            policy,
+            entries: HashMap::new(),
+            evaluations: 0,
+            hits: 0,
+        }
+    }
+
+    /// Compute the cache key for a request
+    ///
+    /// The context hash covers the node and variables, the inputs that
+    /// evaluation reads. The event ID is excluded so repeated requests
+    /// from different events coalesce.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn key(ctx: &EvalContext, capability: &Capability) -> CoreResult<DecisionKey> {
+        let capability_hash = Hash::compute(&encode(capability)?);
+        let context_hash = Hash::compute(&encode(&(ctx.node_id, &ctx.vars))?);
+        Ok((capability_hash, context_hash))
+    }
+
+    /// Check a capability, reusing a prior identical decision
+    ///
+    /// # Errors
+    ///
+    /// Returns error if policy evaluation fails
+    pub fn check_capability(
+        &mut self,
+        ctx: &EvalContext,
+        capability: &Capability,
+    ) -> CoreResult<CachedDecision> {
+        let key = Self::key(ctx, capability)?;
+        if let Some(cached) = self.entries.get(&key) {
+            self.hits += 1;
+            return Ok(cached.clone());
+        }
+
+        let decision = self.policy.check_capability(ctx, capability)?;
+        self.evaluations += 1;
+
+        let mut proof = DecisionProof::new(ProofKind::CapabilityCheck, decision.allowed)
+            .with_policy(self.policy.id.clone())
+            .with_field(ProofField::string("capability".to_string(), &capability.to_string()))
+            .with_field(ProofField::string("reason".to_string(), &decision.reason));
+        if let Some(node_id) = ctx.node_id {
+            proof = proof.with_node(node_id);
+        }
+
+        let cached = CachedDecision {
+            decision,
+            proof: proof.finalize()?,
+        };
+        self.entries.insert(key, cached.clone());
+        Ok(cached)
+    }
+
+    /// Get the policy this cache evaluates
+    #[must_use]
+    pub fn policy(&self) -> &CompiledPolicy {
+        &self.policy
+    }
+
+    /// Number of full policy evaluations performed
+    #[must_use]
+    pub fn evaluations(&self) -> usize {
+        self.evaluations
+    }
+
+    /// Number of requests served from the cache
+    #[must_use]
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of distinct cached decisions
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no decisions are cached
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Serialize a key component
+fn encode<T: Serialize>(value: &T) -> CoreResult<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|e| CoreError::ParseError {
+        message: format!("Failed to encode decision key: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::{PolicyCompiler, PolicyValue};
+    use cathedral_core::{EventId, NodeId};
+
+    #[test]
+    fn test_decision_cache_coalesces() {
+        let policy = PolicyCompiler::new()
+            .compile_from_source("allow true")
+            .unwrap();
+        let mut cache = DecisionCache::new(policy);
+        let node_id = NodeId::new();
+
+        let first = cache
+            .check_capability(
+                &EvalContext::new().with_node(node_id).with_event(EventId::new()),
+                &Capability::ClockRead,
+            )
+            .unwrap();
+        for _ in 0..100 {
+            let ctx = EvalContext::new().with_node(node_id).with_event(EventId::new());
+            let again = cache.check_capability(&ctx, &Capability::ClockRead).unwrap();
+            assert_eq!(again, first);
+        }
+
+        assert_eq!(cache.evaluations(), 1);
+        assert_eq!(cache.hits(), 100);
+        assert!(first.proof.verify().unwrap());
+    }
+
+    #[test]
+    fn test_decision_cache_key_distinguishes_requests() {
+        let ctx = EvalContext::new()
+            .with_var("a".to_string(), PolicyValue::Bool(true))
+            .with_var("b".to_string(), PolicyValue::Int(2));
+        let reordered = EvalContext::new()
+            .with_var("b".to_string(), PolicyValue::Int(2))
+            .with_var("a".to_string(), PolicyValue::Bool(true));
+        let fs = Capability::FsRead {
+            prefixes: vec!["./data".to_string()],
+        };
+
+        let key = DecisionCache::key(&ctx, &Capability::ClockRead).unwrap();
+        assert_eq!(key, DecisionCache::key(&reordered, &Capability::ClockRead).unwrap());
+        assert_ne!(key, DecisionCache::key(&ctx, &fs).unwrap());
+        assert_ne!(
+            key,
+            DecisionCache::key(&ctx.clone().with_node(NodeId::new()), &Capability::ClockRead)
+                .unwrap()
+        );
+
+        let policy = PolicyCompiler::new()
+            .compile_from_source("allow true")
+            .unwrap();
+        let mut cache = DecisionCache::new(policy);
+        cache.check_capability(&ctx, &Capability::ClockRead).unwrap();
+        cache.check_capability(&ctx, &fs).unwrap();
+        assert_eq!(cache.evaluations(), 2);
+        assert_eq!(cache.len(), 2);
+    }
+}
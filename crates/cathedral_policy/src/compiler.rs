@@ -1,9 +1,10 @@
 //! Policy compiler for evaluating policies.
 
-use crate::lang::{PolicyAst, PolicyExpr, PolicyStmt};
-use cathedral_core::{CoreResult, CoreError, Capability, EventId, NodeId};
+use crate::lang::{PolicyAst, PolicyExpr, PolicyStmt, Quantifier};
+use crate::proof::{DecisionProof, ProofKind};
+use cathedral_core::{CoreResult, CoreError, Capability, EventId, Hash, NodeId};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 /// Policy compilation error
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -47,7 +48,7 @@ pub struct CompiledPolicy {
     /// Compiled rules
     pub rules: Vec<CompiledRule>,
     /// Variables
-    pub vars: HashMap<String, PolicyValue>,
+    pub vars: BTreeMap<String, PolicyValue>,
 }
 
 /// Compiled rule
@@ -63,6 +64,14 @@ pub struct CompiledRule {
     pub capabilities: Vec<Capability>,
 }
 
+impl CompiledRule {
+    /// Identifier recorded in proofs: the rule name, or its position
+    #[must_use]
+    pub fn id(&self, index: usize) -> String {
+        self.name.clone().unwrap_or_else(|| format!("rule-{}", index))
+    }
+}
+
 /// Policy value
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PolicyValue {
@@ -72,6 +81,34 @@ pub enum PolicyValue {
     String(String),
     /// Integer value
     Int(i64),
+    /// List of values
+    List(Vec<PolicyValue>),
+}
+
+impl PolicyValue {
+    /// Value of a literal expression, if it is one
+    #[must_use]
+    pub fn from_literal(expr: &PolicyExpr) -> Option<Self> {
+        match expr {
+            PolicyExpr::Bool(b) => Some(Self::Bool(*b)),
+            PolicyExpr::String(s) => Some(Self::String(s.clone())),
+            PolicyExpr::List(items) => items
+                .iter()
+                .map(Self::from_literal)
+                .collect::<Option<_>>()
+                .map(Self::List),
+            _ => None,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "bool",
+            Self::String(_) => "string",
+            Self::Int(_) => "int",
+            Self::List(_) => "list",
+        }
+    }
 }
 
 /// Evaluation context
@@ -84,7 +121,7 @@ pub struct EvalContext {
     /// Requested capability
     pub requested_capability: Option<Capability>,
     /// User-defined variables
-    pub vars: HashMap<String, PolicyValue>,
+    pub vars: BTreeMap<String, PolicyValue>,
 }
 
 impl EvalContext {
@@ -95,7 +132,7 @@ impl EvalContext {
             node_id: None,
             event_id: None,
             requested_capability: None,
-            vars: HashMap::new(),
+            vars: BTreeMap::new(),
         }
     }
 
@@ -126,6 +163,15 @@ impl EvalContext {
         self.vars.insert(name, value);
         self
     }
+
+    /// Hash of the canonical encoding of this context
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn input_hash(&self) -> CoreResult<Hash> {
+        Ok(Hash::compute(&serde_json::to_vec(self)?))
+    }
 }
 
 impl Default for EvalContext {
@@ -151,7 +197,7 @@ impl PolicyCompiler {
     /// Returns error if compilation fails
     pub fn compile(&self, ast: PolicyAst) -> CoreResult<CompiledPolicy> {
         let mut rules = Vec::new();
-        let mut vars = HashMap::new();
+        let mut vars = BTreeMap::new();
 
         for stmt in ast.statements {
             match stmt {
@@ -172,15 +218,9 @@ impl PolicyCompiler {
                     });
                 }
                 PolicyStmt::Let(name, expr) => {
-                    // Evaluate static expressions
-                    if let PolicyExpr::Bool(b) = expr {
-                        vars.insert(name, PolicyValue::Bool(b));
-                    } else if let PolicyExpr::String(s) = expr {
-                        vars.insert(name, PolicyValue::String(s));
-                    } else {
-                        // Keep as variable reference
-                        vars.insert(name, PolicyValue::Bool(false));
-                    }
+                    // Evaluate static expressions, otherwise keep as variable reference
+                    let value = PolicyValue::from_literal(&expr);
+                    vars.insert(name, value.unwrap_or(PolicyValue::Bool(false)));
                 }
             }
         }
@@ -213,32 +253,46 @@ impl Default for PolicyCompiler {
 impl CompiledPolicy {
     /// Evaluate policy with context
     ///
+    /// The decision carries a finalized [`DecisionProof`] naming the deciding
+    /// rule, the expressions that matched, the input hash, and the requested
+    /// capability.
+    ///
     /// # Errors
     ///
     /// Returns error if evaluation fails
     pub fn evaluate(&self, ctx: &EvalContext) -> CoreResult<PolicyDecision> {
         let mut allowed = false;
         let mut matched_rules = Vec::new();
+        let mut proof = self.new_proof(ctx)?;
 
-        for rule in &self.rules {
+        for (index, rule) in self.rules.iter().enumerate() {
             let result = self.eval_expr(&rule.expr, ctx)?;
 
             if result {
                 matched_rules.push(rule.name.clone().unwrap_or_else(|| "unnamed".to_string()));
+                proof = proof.with_matched_expr(rule.expr.clone());
 
                 // Deny rules take precedence
                 if !rule.is_allow {
+                    proof.kind = ProofKind::Deny;
+                    proof.decision = false;
                     return Ok(PolicyDecision {
                         allowed: false,
                         matched_rules,
                         reason: "Deny rule matched".to_string(),
+                        proof: proof.with_rule(rule.id(index)).finalize()?,
                     });
                 }
 
+                if !allowed {
+                    proof = proof.with_rule(rule.id(index));
+                }
                 allowed = true;
             }
         }
 
+        proof.kind = if allowed { ProofKind::Allow } else { ProofKind::Deny };
+        proof.decision = allowed;
         Ok(PolicyDecision {
             allowed,
             matched_rules,
@@ -247,11 +301,15 @@ impl CompiledPolicy {
             } else {
                 "No matching allow rule".to_string()
             },
+            proof: proof.finalize()?,
         })
     }
 
     /// Check if a specific capability is allowed
     ///
+    /// The proof is a capability check whose rule is the first matching
+    /// allow rule that grants `capability`.
+    ///
     /// # Errors
     ///
     /// Returns error if evaluation fails
@@ -263,7 +321,7 @@ impl CompiledPolicy {
         let decision = self.evaluate(ctx)?;
 
         // Also check if the specific capability was granted
-        let has_capability = self.rules.iter().any(|rule| {
+        let granting = self.rules.iter().enumerate().find(|(_, rule)| {
             rule.is_allow
                 && rule.capabilities.iter().any(|c| c == capability)
                 && self
@@ -271,13 +329,33 @@ impl CompiledPolicy {
                     .unwrap_or(false)
         });
 
+        let allowed = decision.allowed && granting.is_some();
+        let mut proof = decision.proof;
+        proof.kind = ProofKind::CapabilityCheck;
+        proof.decision = allowed;
+        proof.capability = Some(capability.clone());
+        if allowed {
+            proof.rule_id = granting.map(|(index, rule)| rule.id(index));
+        }
+
         Ok(PolicyDecision {
-            allowed: decision.allowed && has_capability,
+            allowed,
             matched_rules: decision.matched_rules,
             reason: decision.reason,
+            proof: proof.finalize()?,
         })
     }
 
+    /// Start a proof for evaluating `ctx` against this policy
+    fn new_proof(&self, ctx: &EvalContext) -> CoreResult<DecisionProof> {
+        let mut proof =
+            DecisionProof::for_input(ProofKind::PolicyEval, false, &self.id, ctx.input_hash()?);
+        proof.event_id = ctx.event_id;
+        proof.node_id = ctx.node_id;
+        proof.capability = ctx.requested_capability.clone();
+        Ok(proof)
+    }
+
     /// Evaluate an expression
     fn eval_expr(&self, expr: &PolicyExpr, ctx: &EvalContext) -> CoreResult<bool> {
         match expr {
@@ -312,6 +390,44 @@ impl CompiledPolicy {
                 self.eval_compare(*op, left, right, ctx)
             }
             PolicyExpr::Call { func, args } => self.eval_call(func, args, ctx),
+            PolicyExpr::List(_) => Ok(false),
+            PolicyExpr::In { item, list } => {
+                let item = self.eval_to_value(item, ctx)?;
+                Ok(self.eval_list(list, ctx)?.contains(&item))
+            }
+            PolicyExpr::StartsWith { value, prefix } => {
+                match (self.eval_to_value(value, ctx)?, self.eval_to_value(prefix, ctx)?) {
+                    (PolicyValue::String(value), PolicyValue::String(prefix)) => {
+                        Ok(value.starts_with(&prefix))
+                    }
+                    _ => Ok(false),
+                }
+            }
+            PolicyExpr::Quantifier { kind, var, collection, body } => {
+                let mut scope = ctx.clone();
+                for element in self.eval_list(collection, ctx)? {
+                    scope.vars.insert(var.clone(), element);
+                    let holds = self.eval_expr(body, &scope)?;
+                    match kind {
+                        Quantifier::Any if holds => return Ok(true),
+                        Quantifier::All if !holds => return Ok(false),
+                        _ => {}
+                    }
+                }
+                Ok(*kind == Quantifier::All)
+            }
+        }
+    }
+
+    /// Evaluate an expression that must produce a list
+    fn eval_list(&self, expr: &PolicyExpr, ctx: &EvalContext) -> CoreResult<Vec<PolicyValue>> {
+        match self.eval_to_value(expr, ctx)? {
+            PolicyValue::List(items) => Ok(items),
+            other => Err(PolicyError::TypeMismatch {
+                expected: "list".to_string(),
+                actual: other.type_name().to_string(),
+            }
+            .into()),
         }
     }
 
@@ -345,6 +461,11 @@ impl CompiledPolicy {
                 crate::lang::CompareOp::Ne => Ok(l != r),
                 _ => Ok(false),
             },
+            (PolicyValue::List(l), PolicyValue::List(r)) => match op {
+                crate::lang::CompareOp::Eq => Ok(l == r),
+                crate::lang::CompareOp::Ne => Ok(l != r),
+                _ => Ok(false),
+            },
             _ => Ok(false),
         }
     }
@@ -363,6 +484,15 @@ impl CompiledPolicy {
                     Ok(PolicyValue::Bool(false))
                 }
             }
+            PolicyExpr::List(items) => Ok(PolicyValue::List(
+                items
+                    .iter()
+                    .map(|item| self.eval_to_value(item, ctx))
+                    .collect::<CoreResult<_>>()?,
+            )),
+            PolicyExpr::In { .. }
+            | PolicyExpr::StartsWith { .. }
+            | PolicyExpr::Quantifier { .. } => Ok(PolicyValue::Bool(self.eval_expr(expr, ctx)?)),
             _ => Ok(PolicyValue::Bool(false)),
         }
     }
@@ -390,6 +520,8 @@ pub struct PolicyDecision {
     pub matched_rules: Vec<String>,
     /// Human-readable reason
     pub reason: String,
+    /// Proof of how the decision was reached
+    pub proof: DecisionProof,
 }
 
 #[cfg(test)]
@@ -455,6 +587,86 @@ mod tests {
         assert!(decision.allowed);
     }
 
+    #[test]
+    fn test_compiled_policy_serializes_canonically() {
+        let compiler = PolicyCompiler::new();
+        let policy = compiler
+            .compile_from_source("let zeta = true\nlet alpha = false\nlet mid = true\nallow true")
+            .unwrap();
+
+        let first = serde_json::to_vec(&policy).unwrap();
+        let second = serde_json::to_vec(&policy.clone()).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(
+            policy.vars.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["alpha", "mid", "zeta"]
+        );
+
+        let a = EvalContext::new()
+            .with_var("b".to_string(), PolicyValue::Int(2))
+            .with_var("a".to_string(), PolicyValue::Int(1));
+        let b = EvalContext::new()
+            .with_var("a".to_string(), PolicyValue::Int(1))
+            .with_var("b".to_string(), PolicyValue::Int(2));
+        assert_eq!(serde_json::to_vec(&a).unwrap(), serde_json::to_vec(&b).unwrap());
+    }
+
+    #[test]
+    fn test_eval_membership() {
+        let compiler = PolicyCompiler::new();
+        let policy = compiler
+            .compile_from_source(
+                "let allowed = [\"a.com\", \"b.com\"]\n\
+                 allow listed: domain in allowed\n\
+                 allow inline: domain in [\"c.com\"]\n\
+                 deny etc: path starts_with \"/etc/\"",
+            )
+            .unwrap();
+        let eval = |domain: &str, path: &str| {
+            let ctx = EvalContext::new()
+                .with_var("domain".to_string(), PolicyValue::String(domain.to_string()))
+                .with_var("path".to_string(), PolicyValue::String(path.to_string()));
+            policy.evaluate(&ctx).unwrap()
+        };
+
+        assert_eq!(eval("a.com", "/tmp/x").matched_rules, vec!["listed".to_string()]);
+        assert_eq!(eval("c.com", "/tmp/x").matched_rules, vec!["inline".to_string()]);
+        assert!(!eval("d.com", "/tmp/x").allowed);
+        assert!(!eval("a.com", "/etc/passwd").allowed);
+    }
+
+    #[test]
+    fn test_eval_quantifiers() {
+        let compiler = PolicyCompiler::new();
+        let any = compiler
+            .compile_from_source("allow any d in domains: d in [\"a.com\"]")
+            .unwrap();
+        let all = compiler
+            .compile_from_source("allow all p in paths: p starts_with \"/tmp/\"")
+            .unwrap();
+        let strings = |items: &[&str]| {
+            PolicyValue::List(items.iter().map(|s| PolicyValue::String(s.to_string())).collect())
+        };
+        let ctx = |domains: &[&str], paths: &[&str]| {
+            EvalContext::new()
+                .with_var("domains".to_string(), strings(domains))
+                .with_var("paths".to_string(), strings(paths))
+        };
+
+        assert!(any.evaluate(&ctx(&["b.com", "a.com"], &[])).unwrap().allowed);
+        assert!(!any.evaluate(&ctx(&["b.com"], &[])).unwrap().allowed);
+        assert!(!any.evaluate(&ctx(&[], &[])).unwrap().allowed);
+
+        assert!(all.evaluate(&ctx(&[], &["/tmp/a", "/tmp/b"])).unwrap().allowed);
+        assert!(!all.evaluate(&ctx(&[], &["/tmp/a", "/etc/b"])).unwrap().allowed);
+        assert!(all.evaluate(&ctx(&[], &[])).unwrap().allowed);
+
+        // Quantifying over a non-list is a type error
+        let scalar = EvalContext::new()
+            .with_var("domains".to_string(), PolicyValue::String("a.com".to_string()));
+        assert!(any.evaluate(&scalar).is_err());
+    }
+
     #[test]
     fn test_eval_error_display() {
         let err = PolicyError::UnknownVar {
@@ -469,7 +681,41 @@ mod tests {
             allowed: true,
             matched_rules: vec!["rule1".to_string()],
             reason: "Allowed".to_string(),
+            proof: DecisionProof::new(ProofKind::Allow, true),
         };
         assert!(decision.allowed);
     }
+
+    #[test]
+    fn test_evaluate_proof() {
+        let compiler = PolicyCompiler::new();
+        let policy = compiler
+            .compile_from_source("allow reads: true => [fs_read]\ndeny writes: false")
+            .unwrap();
+        let ctx = EvalContext::new()
+            .with_node(NodeId::new())
+            .with_capability(Capability::FsRead { prefixes: vec![] });
+
+        let decision = policy.evaluate(&ctx).unwrap();
+        let proof = &decision.proof;
+        assert!(proof.verify().unwrap());
+        assert_eq!(proof.kind, ProofKind::Allow);
+        assert_eq!(proof.rule_id.as_deref(), Some("reads"));
+        assert_eq!(proof.matched_exprs, vec![PolicyExpr::Bool(true)]);
+        assert_eq!(proof.input_hash, ctx.input_hash().unwrap());
+        assert_eq!(proof.capability, ctx.requested_capability);
+        assert_eq!(proof.node_id, ctx.node_id);
+
+        // The same evaluation yields the same proof
+        assert_eq!(policy.evaluate(&ctx).unwrap().proof, decision.proof);
+
+        let other = ctx.clone().with_var("x".to_string(), PolicyValue::Int(1));
+        assert_ne!(policy.evaluate(&other).unwrap().proof.input_hash, proof.input_hash);
+
+        let denied = compiler.compile_from_source("allow true\ndeny true").unwrap();
+        let proof = denied.evaluate(&ctx).unwrap().proof;
+        assert!(!proof.decision);
+        assert_eq!(proof.kind, ProofKind::Deny);
+        assert_eq!(proof.rule_id.as_deref(), Some("rule-1"));
+    }
 }
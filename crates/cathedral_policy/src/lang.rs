@@ -63,6 +63,45 @@ pub enum PolicyExpr {
         func: String,
         args: Vec<PolicyExpr>,
     },
+    /// List literal, e.g. `["a.com", "b.com"]`
+    List(Vec<PolicyExpr>),
+    /// List membership, e.g. `domain in ["a.com", "b.com"]`
+    In {
+        /// Value looked up
+        item: Box<PolicyExpr>,
+        /// List searched
+        list: Box<PolicyExpr>,
+    },
+    /// String prefix match, e.g. `path starts_with "/tmp/"`
+    StartsWith {
+        /// String tested
+        value: Box<PolicyExpr>,
+        /// Required prefix
+        prefix: Box<PolicyExpr>,
+    },
+    /// Quantifier over a collection, e.g. `all p in paths: p starts_with "/tmp/"`
+    ///
+    /// The body extends to the end of the expression and sees `var` bound to
+    /// each element in turn.
+    Quantifier {
+        /// `any` or `all`
+        kind: Quantifier,
+        /// Name bound to each element
+        var: String,
+        /// List quantified over
+        collection: Box<PolicyExpr>,
+        /// Condition tested per element
+        body: Box<PolicyExpr>,
+    },
+}
+
+/// Quantifier kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Quantifier {
+    /// True if the body holds for some element
+    Any,
+    /// True if the body holds for every element (and for an empty collection)
+    All,
 }
 
 /// Comparison operator
@@ -160,11 +199,12 @@ impl PolicyParser {
         let rest = input[if is_allow { 6 } else { 5 }..].trim();
 
         // Parse rule as: "name: expr => [caps]" or just "expr"
-        let (name, rest) = if let Some(colon_idx) = rest.find(':') {
-            let name = Some(rest[..colon_idx].trim().to_string());
-            (name, rest[colon_idx + 1..].trim())
-        } else {
-            (None, rest)
+        let (name, rest) = match rest.find(':') {
+            Some(colon_idx) if is_ident(rest[..colon_idx].trim()) => {
+                let name = Some(rest[..colon_idx].trim().to_string());
+                (name, rest[colon_idx + 1..].trim())
+            }
+            _ => (None, rest),
         };
 
         // Split by =>
@@ -209,10 +249,31 @@ impl PolicyParser {
         }
 
         // String literals
-        if input.starts_with('"') && input.ends_with('"') {
+        if input.len() >= 2
+            && input.starts_with('"')
+            && input.ends_with('"')
+            && !input[1..input.len() - 1].contains('"')
+        {
             return Ok(PolicyExpr::String(input[1..input.len() - 1].to_string()));
         }
 
+        // List literals
+        if let Some(inner) = bracketed(input) {
+            let items = split_top_level(inner)
+                .into_iter()
+                .filter(|item| !item.trim().is_empty())
+                .map(|item| self.parse_expr(item))
+                .collect::<Result<_, _>>()?;
+            return Ok(PolicyExpr::List(items));
+        }
+
+        // Quantifiers
+        for (keyword, kind) in [("any ", Quantifier::Any), ("all ", Quantifier::All)] {
+            if let Some(rest) = input.strip_prefix(keyword) {
+                return self.parse_quantifier(kind, rest);
+            }
+        }
+
         // NOT
         if input.starts_with('!') {
             let inner = self.parse_expr(&input[1..])?;
@@ -253,6 +314,24 @@ impl PolicyParser {
             });
         }
 
+        // Membership and prefix matching
+        if let Some(idx) = input.find(" in ") {
+            let item = self.parse_expr(&input[..idx])?;
+            let list = self.parse_expr(&input[idx + 4..])?;
+            return Ok(PolicyExpr::In {
+                item: Box::new(item),
+                list: Box::new(list),
+            });
+        }
+        if let Some(idx) = input.find(" starts_with ") {
+            let value = self.parse_expr(&input[..idx])?;
+            let prefix = self.parse_expr(&input[idx + 13..])?;
+            return Ok(PolicyExpr::StartsWith {
+                value: Box::new(value),
+                prefix: Box::new(prefix),
+            });
+        }
+
         // Function call - simplified without nested if
         if let Some(open_idx) = input.find('(') {
             if input.len() > open_idx + 1 {
@@ -274,6 +353,27 @@ impl PolicyParser {
         Ok(PolicyExpr::Var(input.to_string()))
     }
 
+    /// Parse `var in collection: body` following `any`/`all`
+    fn parse_quantifier(&self, kind: Quantifier, input: &str) -> Result<PolicyExpr, String> {
+        let colon_idx = input
+            .find(':')
+            .ok_or_else(|| "Expected ':' after quantifier binding".to_string())?;
+        let (var, collection) = input[..colon_idx]
+            .split_once(" in ")
+            .ok_or_else(|| "Expected 'in' in quantifier binding".to_string())?;
+        let var = var.trim();
+        if !is_ident(var) {
+            return Err(format!("Invalid quantifier variable: {}", var));
+        }
+
+        Ok(PolicyExpr::Quantifier {
+            kind,
+            var: var.to_string(),
+            collection: Box::new(self.parse_expr(collection)?),
+            body: Box::new(self.parse_expr(&input[colon_idx + 1..])?),
+        })
+    }
+
     /// Parse capabilities list
     fn parse_capabilities(&self, input: &str) -> Result<Vec<Capability>, String> {
         // Remove brackets
@@ -289,6 +389,49 @@ impl PolicyParser {
     }
 }
 
+/// Whether `input` is a non-empty identifier
+fn is_ident(input: &str) -> bool {
+    !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Contents of `input` if a single `[...]` pair encloses all of it
+fn bracketed(input: &str) -> Option<&str> {
+    let inner = input.strip_prefix('[')?.strip_suffix(']')?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    for c in inner.chars() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+    }
+    (depth == 0 && !in_string).then_some(inner)
+}
+
+/// Split on commas outside string literals and nested lists
+fn split_top_level(input: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut start = 0;
+    for (idx, c) in input.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => depth = depth.saturating_sub(1),
+            ',' if !in_string && depth == 0 => {
+                parts.push(&input[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
 impl Default for PolicyParser {
     fn default() -> Self {
         Self::new()
@@ -368,6 +511,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_policy_parse_membership() {
+        let parser = PolicyParser::new();
+        let ast = parser
+            .parse(r#"allow net: domain in ["a.com", "b,c.com"]"#)
+            .unwrap();
+        let PolicyStmt::Allow(rule) = &ast.statements[0] else {
+            panic!("Expected Allow rule");
+        };
+        assert_eq!(rule.name.as_deref(), Some("net"));
+        assert_eq!(
+            rule.expr,
+            PolicyExpr::In {
+                item: Box::new(PolicyExpr::Var("domain".to_string())),
+                list: Box::new(PolicyExpr::List(vec![
+                    PolicyExpr::String("a.com".to_string()),
+                    PolicyExpr::String("b,c.com".to_string()),
+                ])),
+            }
+        );
+
+        let ast = parser.parse(r#"allow path starts_with "/tmp/""#).unwrap();
+        let PolicyStmt::Allow(rule) = &ast.statements[0] else {
+            panic!("Expected Allow rule");
+        };
+        assert!(matches!(rule.expr, PolicyExpr::StartsWith { .. }));
+    }
+
+    #[test]
+    fn test_policy_parse_quantifier() {
+        let parser = PolicyParser::new();
+        let ast = parser
+            .parse(r#"allow all p in paths: p starts_with "/tmp/" && p != "/tmp/x""#)
+            .unwrap();
+        let PolicyStmt::Allow(rule) = &ast.statements[0] else {
+            panic!("Expected Allow rule");
+        };
+        assert_eq!(rule.name, None);
+        let PolicyExpr::Quantifier { kind, var, collection, body } = &rule.expr else {
+            panic!("Expected quantifier");
+        };
+        assert_eq!(*kind, Quantifier::All);
+        assert_eq!(var, "p");
+        assert_eq!(**collection, PolicyExpr::Var("paths".to_string()));
+        assert!(matches!(**body, PolicyExpr::And(..)));
+
+        assert!(parser.parse("allow any in xs: true").is_err());
+        assert!(parser.parse("allow any x xs: true").is_err());
+    }
+
     #[test]
     fn test_compare_op() {
         assert_eq!(CompareOp::Eq, CompareOp::Eq);
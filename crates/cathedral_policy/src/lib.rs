@@ -11,9 +11,15 @@ pub mod compiler;
 pub mod proof;
 pub mod matcher;
 pub mod redact;
+pub mod cache;
+pub mod store;
+pub mod audit;
 
-pub use lang::{PolicyParser, PolicyAst, PolicyExpr};
-pub use compiler::{PolicyCompiler, CompiledPolicy, PolicyError};
+pub use lang::{PolicyParser, PolicyAst, PolicyExpr, Quantifier};
+pub use compiler::{PolicyCompiler, CompiledPolicy, PolicyError, EvalContext, PolicyDecision};
 pub use proof::{DecisionProof, ProofKind, ProofField};
 pub use matcher::{Matcher, MatchContext, MatchResult};
 pub use redact::{Redactor, RedactionRule, RedactedView};
+pub use cache::{DecisionCache, CachedDecision};
+pub use store::{PolicyBundle, PolicyActivation, PolicyStore};
+pub use audit::{ApiCall, AuditEntry, AuditFormat, AuditQuery, AuditSource, AuditTrail};
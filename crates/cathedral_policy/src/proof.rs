@@ -1,6 +1,7 @@
 //! Decision proofs for policy verification.
 
-use cathedral_core::{CoreResult, EventId, Hash, NodeId};
+use crate::lang::PolicyExpr;
+use cathedral_core::{Capability, CoreResult, EventId, Hash, NodeId};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -69,6 +70,14 @@ pub struct DecisionProof {
     pub decision: bool,
     /// Policy ID used
     pub policy_id: Option<String>,
+    /// Rule that decided the outcome, if any
+    pub rule_id: Option<String>,
+    /// Expressions of the rules that matched, in evaluation order
+    pub matched_exprs: Vec<PolicyExpr>,
+    /// Hash of the evaluated input
+    pub input_hash: Hash,
+    /// Capability requested, if any
+    pub capability: Option<Capability>,
     /// Proof fields
     pub fields: Vec<ProofField>,
     /// Proof signature (hash of all fields)
@@ -92,6 +101,36 @@ impl DecisionProof {
             timestamp,
             decision,
             policy_id: None,
+            rule_id: None,
+            matched_exprs: Vec::new(),
+            input_hash: Hash::empty(),
+            capability: None,
+            fields: Vec::new(),
+            signature: Hash::empty(),
+        }
+    }
+
+    /// Create a proof that is a pure function of the evaluated input
+    ///
+    /// The ID derives from `policy_id` and `input_hash` and the timestamp is
+    /// zero, so replaying the same evaluation yields an identical proof.
+    #[must_use]
+    pub fn for_input(kind: ProofKind, decision: bool, policy_id: &str, input_hash: Hash) -> Self {
+        let mut id = policy_id.as_bytes().to_vec();
+        id.extend_from_slice(input_hash.as_ref());
+
+        Self {
+            id: Hash::compute(&id).to_hex(),
+            kind,
+            event_id: None,
+            node_id: None,
+            timestamp: 0,
+            decision,
+            policy_id: Some(policy_id.to_string()),
+            rule_id: None,
+            matched_exprs: Vec::new(),
+            input_hash,
+            capability: None,
             fields: Vec::new(),
             signature: Hash::empty(),
         }
@@ -118,6 +157,27 @@ impl DecisionProof {
         self
     }
 
+    /// Set the deciding rule
+    #[must_use]
+    pub fn with_rule(mut self, rule_id: String) -> Self {
+        self.rule_id = Some(rule_id);
+        self
+    }
+
+    /// Record a matched expression
+    #[must_use]
+    pub fn with_matched_expr(mut self, expr: PolicyExpr) -> Self {
+        self.matched_exprs.push(expr);
+        self
+    }
+
+    /// Set the requested capability
+    #[must_use]
+    pub fn with_capability(mut self, capability: Capability) -> Self {
+        self.capability = Some(capability);
+        self
+    }
+
     /// Add a field
     #[must_use]
     pub fn with_field(mut self, field: ProofField) -> Self {
@@ -131,19 +191,7 @@ impl DecisionProof {
     ///
     /// Returns error if serialization fails
     pub fn finalize(mut self) -> CoreResult<Self> {
-        // Compute signature from all fields
-        let mut data = Vec::new();
-        data.extend_from_slice(self.id.as_bytes());
-        data.extend_from_slice(format!("{:?}", self.kind).as_bytes());
-        data.extend_from_slice(&self.timestamp.to_be_bytes());
-        data.extend_from_slice(&(if self.decision { 1u8 } else { 0u8 }).to_be_bytes());
-
-        for field in &self.fields {
-            data.extend_from_slice(field.name.as_bytes());
-            data.extend_from_slice(&field.value);
-        }
-
-        self.signature = Hash::compute(&data);
+        self.signature = Hash::compute(&self.signing_bytes()?);
         Ok(self)
     }
 
@@ -153,7 +201,11 @@ impl DecisionProof {
     ///
     /// Returns error if verification fails
     pub fn verify(&self) -> CoreResult<bool> {
-        // Recompute signature
+        Ok(Hash::compute(&self.signing_bytes()?) == self.signature)
+    }
+
+    /// Bytes covered by the signature
+    fn signing_bytes(&self) -> CoreResult<Vec<u8>> {
         let mut data = Vec::new();
         data.extend_from_slice(self.id.as_bytes());
         data.extend_from_slice(format!("{:?}", self.kind).as_bytes());
@@ -165,8 +217,14 @@ impl DecisionProof {
             data.extend_from_slice(&field.value);
         }
 
-        let computed = Hash::compute(&data);
-        Ok(computed == self.signature)
+        if let Some(rule_id) = &self.rule_id {
+            data.extend_from_slice(rule_id.as_bytes());
+        }
+        data.extend_from_slice(self.input_hash.as_ref());
+        let structured = serde_json::to_vec(&(&self.matched_exprs, &self.capability))?;
+        data.extend_from_slice(&structured);
+
+        Ok(data)
     }
 
     /// Get a field by name
@@ -388,6 +446,32 @@ mod tests {
         assert!(proof.verify().unwrap());
     }
 
+    #[test]
+    fn test_proof_for_input() {
+        let input = Hash::compute(b"input");
+        let make = || {
+            DecisionProof::for_input(ProofKind::Allow, true, "policy", input)
+                .with_rule("reads".to_string())
+                .with_matched_expr(PolicyExpr::Bool(true))
+                .with_capability(Capability::ClockRead)
+                .finalize()
+                .unwrap()
+        };
+
+        let proof = make();
+        assert_eq!(proof, make());
+        assert_eq!(proof.timestamp, 0);
+        assert!(proof.verify().unwrap());
+
+        let mut tampered = proof.clone();
+        tampered.rule_id = Some("writes".to_string());
+        assert!(!tampered.verify().unwrap());
+
+        let mut tampered = proof;
+        tampered.capability = None;
+        assert!(!tampered.verify().unwrap());
+    }
+
     #[test]
     fn test_proof_log_new() {
         let log = ProofLog::new();
@@ -0,0 +1,287 @@
+//! Hot-reloadable policy bundles.
+//!
+//! A [`PolicyStore`] holds the active [`PolicyBundle`] behind an atomic swap:
+//! readers take an `Arc` to the bundle that was active when they asked, so a
+//! reload never changes the policy under an in-flight evaluation. Every
+//! activation is recorded with the logical tick it took effect at and is
+//! emitted as a `PolicyActivated` event carrying the bundle hash, so replay
+//! can tell which version was active at any point in the log.
+
+use crate::compiler::CompiledPolicy;
+use cathedral_core::{CoreError, CoreResult, EventId, Hash, LogicalTime, NodeId, RunId};
+use cathedral_log::{Event, EventKind};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+/// Node ID recorded on activation events, which belong to no node
+const STORE_NODE: NodeId = NodeId::from_bytes([0; 16]);
+
+/// A versioned set of compiled policies, activated as a unit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyBundle {
+    /// Bundle version; each activation must increase it
+    pub version: u64,
+    /// Policies by name
+    pub policies: BTreeMap<String, CompiledPolicy>,
+}
+
+impl PolicyBundle {
+    /// Create an empty bundle
+    #[must_use]
+    pub fn new(version: u64) -> Self {
+        Self {
+            version,
+            policies: BTreeMap::new(),
+        }
+    }
+
+    /// Add a named policy
+    #[must_use]
+    pub fn with_policy(mut self, name: &str, policy: CompiledPolicy) -> Self {
+        self.policies.insert(name.to_string(), policy);
+        self
+    }
+
+    /// Get a policy by name
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&CompiledPolicy> {
+        self.policies.get(name)
+    }
+
+    /// Encode the bundle for distribution
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn to_bytes(&self) -> CoreResult<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Decode a bundle produced by [`PolicyBundle::to_bytes`]
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the bytes are not a bundle
+    pub fn from_bytes(bytes: &[u8]) -> CoreResult<Self> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Content hash of the encoded bundle
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn hash(&self) -> CoreResult<Hash> {
+        Ok(Hash::compute(&self.to_bytes()?))
+    }
+}
+
+/// Record of a bundle becoming active
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyActivation {
+    /// Bundle version
+    pub version: u64,
+    /// Bundle content hash
+    pub bundle_hash: Hash,
+    /// Logical tick from which the bundle is active
+    pub activated_at: LogicalTime,
+}
+
+impl PolicyActivation {
+    /// Build the `PolicyActivated` event recording this activation
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn to_event(&self, run_id: RunId) -> CoreResult<Event> {
+        Ok(Event::new(
+            EventId::new(),
+            run_id,
+            STORE_NODE,
+            self.activated_at,
+            EventKind::PolicyActivated,
+        )
+        .with_payload(serde_json::to_vec(self)?))
+    }
+}
+
+/// Active bundle and activation history, swapped together
+#[derive(Debug, Default)]
+struct StoreState {
+    active: Option<Arc<PolicyBundle>>,
+    history: Vec<PolicyActivation>,
+}
+
+/// Store holding the active policy bundle
+#[derive(Debug, Default)]
+pub struct PolicyStore {
+    state: RwLock<StoreState>,
+}
+
+impl PolicyStore {
+    /// Create an empty store
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Atomically make `bundle` the active bundle from tick `at`
+    ///
+    /// Returns the `PolicyActivated` event to append to the log of `run_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the version or tick does not advance past the
+    /// current activation
+    pub fn activate(
+        &self,
+        bundle: PolicyBundle,
+        run_id: RunId,
+        at: LogicalTime,
+    ) -> CoreResult<Event> {
+        let activation = PolicyActivation {
+            version: bundle.version,
+            bundle_hash: bundle.hash()?,
+            activated_at: at,
+        };
+
+        let mut state = self.state.write()?;
+        if let Some(last) = state.history.last() {
+            if activation.version <= last.version {
+                return Err(CoreError::Validation {
+                    field: "version".to_string(),
+                    reason: format!(
+                        "bundle version {} does not supersede active version {}",
+                        activation.version, last.version
+                    ),
+                });
+            }
+            if activation.activated_at < last.activated_at {
+                return Err(CoreError::Validation {
+                    field: "activated_at".to_string(),
+                    reason: format!(
+                        "activation at {} precedes the previous activation at {}",
+                        activation.activated_at, last.activated_at
+                    ),
+                });
+            }
+        }
+
+        let event = activation.to_event(run_id)?;
+        state.active = Some(Arc::new(bundle));
+        state.history.push(activation);
+        Ok(event)
+    }
+
+    /// Decode and activate an encoded bundle
+    ///
+    /// # Errors
+    ///
+    /// Returns error if decoding or activation fails
+    pub fn load(&self, bytes: &[u8], run_id: RunId, at: LogicalTime) -> CoreResult<Event> {
+        self.activate(PolicyBundle::from_bytes(bytes)?, run_id, at)
+    }
+
+    /// The active bundle, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the store lock is poisoned
+    pub fn current(&self) -> CoreResult<Option<Arc<PolicyBundle>>> {
+        Ok(self.state.read()?.active.clone())
+    }
+
+    /// Version of the active bundle, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the store lock is poisoned
+    pub fn version(&self) -> CoreResult<Option<u64>> {
+        Ok(self.current()?.map(|bundle| bundle.version))
+    }
+
+    /// The activation in effect at tick `at`, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the store lock is poisoned
+    pub fn active_at(&self, at: LogicalTime) -> CoreResult<Option<PolicyActivation>> {
+        let state = self.state.read()?;
+        Ok(state.history.iter().rev().find(|a| a.activated_at <= at).cloned())
+    }
+
+    /// All activations, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the store lock is poisoned
+    pub fn history(&self) -> CoreResult<Vec<PolicyActivation>> {
+        Ok(self.state.read()?.history.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::PolicyCompiler;
+
+    fn bundle(version: u64, source: &str) -> PolicyBundle {
+        let policy = PolicyCompiler::new().compile_from_source(source).unwrap();
+        PolicyBundle::new(version).with_policy("default", policy)
+    }
+
+    #[test]
+    fn test_policy_bundle_roundtrip() {
+        let original = bundle(1, "allow true");
+        let decoded = PolicyBundle::from_bytes(&original.to_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, original);
+        assert_eq!(decoded.hash().unwrap(), original.hash().unwrap());
+        assert_ne!(bundle(2, "allow true").hash().unwrap(), original.hash().unwrap());
+    }
+
+    #[test]
+    fn test_policy_store_swap() {
+        let store = PolicyStore::new();
+        let run_id = RunId::new();
+        assert!(store.current().unwrap().is_none());
+
+        let first = bundle(1, "allow true");
+        let event = store
+            .activate(first.clone(), run_id, LogicalTime::from_raw(5))
+            .unwrap();
+        assert_eq!(event.kind, EventKind::PolicyActivated);
+        assert_eq!(event.logical_time, LogicalTime::from_raw(5));
+        let recorded: PolicyActivation = serde_json::from_slice(&event.payload).unwrap();
+        assert_eq!(recorded.bundle_hash, first.hash().unwrap());
+
+        // A reader keeps the bundle it took across a swap
+        let held = store.current().unwrap().unwrap();
+        store
+            .load(&bundle(2, "deny true").to_bytes().unwrap(), run_id, LogicalTime::from_raw(9))
+            .unwrap();
+        assert_eq!(held.version, 1);
+        assert_eq!(store.version().unwrap(), Some(2));
+
+        assert_eq!(store.active_at(LogicalTime::from_raw(4)).unwrap(), None);
+        assert_eq!(store.active_at(LogicalTime::from_raw(8)).unwrap().unwrap().version, 1);
+        assert_eq!(store.active_at(LogicalTime::from_raw(9)).unwrap().unwrap().version, 2);
+        assert_eq!(store.history().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_policy_store_rejects_stale() {
+        let store = PolicyStore::new();
+        let run_id = RunId::new();
+        store
+            .activate(bundle(2, "allow true"), run_id, LogicalTime::from_raw(5))
+            .unwrap();
+
+        let stale = store.activate(bundle(2, "deny true"), run_id, LogicalTime::from_raw(6));
+        assert!(stale.is_err());
+        let backwards = store.activate(bundle(3, "deny true"), run_id, LogicalTime::from_raw(4));
+        assert!(backwards.is_err());
+        assert_eq!(store.version().unwrap(), Some(2));
+        assert_eq!(store.history().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,772 @@
+//! Replay bundles.
+//!
+//! A bundle is a single file holding everything needed to replay a run
+//! offline: the event log segments, the blobs its events reference, node
+//! artifacts, the compiled DAG and the policy bundle. Its layout is
+//!
+//! ```text
+//! "CATHBNDL" | format version (u32 BE) | manifest length (u32 BE)
+//!     | manifest (JSON) | Ed25519 signature over the manifest (64 bytes)
+//!     | entry data, concatenated in manifest order
+//! ```
+//!
+//! The manifest lists every entry with its offset, size and BLAKE3 hash, so
+//! the signature covers the whole bundle. [`BundleReader`] checks the
+//! signature and every entry hash when it opens a bundle, and checks an
+//! entry's hash again whenever the entry is extracted.
+//!
+//! Entries are stored sorted by name and the manifest carries no timestamp,
+//! so the same inputs and key always produce the same bytes.
+
+use cathedral_core::{CoreError, CoreResult, Hash, RunId};
+use cathedral_log::{Event, LogQuery, SegmentConfig, SegmentedStream, SEGMENT_EXTENSION};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// Magic bytes opening every bundle
+pub const BUNDLE_MAGIC: &[u8; 8] = b"CATHBNDL";
+
+/// Bundle format version written by [`BundleBuilder`]
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Entry name of the compiled DAG
+pub const DAG_ENTRY: &str = "dag.json";
+
+/// Entry name of the policy bundle
+pub const POLICY_ENTRY: &str = "policy.json";
+
+/// Largest manifest [`BundleReader`] accepts
+const MAX_MANIFEST_BYTES: u32 = 64 * 1024 * 1024;
+
+/// Length of an Ed25519 signature
+const SIGNATURE_LEN: usize = 64;
+
+/// Bundle error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    /// The data does not start with [`BUNDLE_MAGIC`]
+    BadMagic,
+    /// Bundle written in an unsupported format version
+    UnsupportedVersion { actual: u32 },
+    /// The manifest signature does not verify
+    BadSignature,
+    /// The bundle is signed by a key other than the trusted one
+    UntrustedSigner { signer: String },
+    /// Entry data does not match the hash in the manifest
+    EntryCorrupted { name: String },
+    /// Entry name is empty or escapes the bundle root
+    InvalidName { name: String },
+    /// Two entries share a name
+    DuplicateEntry { name: String },
+    /// No entry with this name
+    EntryNotFound { name: String },
+    /// Manifest and data do not line up
+    Malformed { reason: String },
+}
+
+impl std::fmt::Display for BundleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "Not a replay bundle"),
+            Self::UnsupportedVersion { actual } => {
+                write!(f, "Unsupported bundle version: {}", actual)
+            }
+            Self::BadSignature => write!(f, "Bundle manifest signature is invalid"),
+            Self::UntrustedSigner { signer } => {
+                write!(f, "Bundle signed by untrusted key {}", signer)
+            }
+            Self::EntryCorrupted { name } => write!(f, "Bundle entry corrupted: {}", name),
+            Self::InvalidName { name } => write!(f, "Invalid bundle entry name: {:?}", name),
+            Self::DuplicateEntry { name } => write!(f, "Duplicate bundle entry: {}", name),
+            Self::EntryNotFound { name } => write!(f, "Bundle entry not found: {}", name),
+            Self::Malformed { reason } => write!(f, "Malformed bundle: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<BundleError> for CoreError {
+    fn from(err: BundleError) -> Self {
+        match err {
+            BundleError::EntryNotFound { name } => CoreError::NotFound {
+                kind: "bundle entry".to_string(),
+                id: name,
+            },
+            BundleError::DuplicateEntry { name } => CoreError::AlreadyExists {
+                kind: "bundle entry".to_string(),
+                id: name,
+            },
+            other => CoreError::Validation {
+                field: "bundle".to_string(),
+                reason: other.to_string(),
+            },
+        }
+    }
+}
+
+/// What an entry holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryKind {
+    /// Event log segment file
+    LogSegment,
+    /// Content-addressed blob referenced by events
+    Blob,
+    /// Named output of the run, e.g. a node's output
+    Artifact,
+    /// Compiled DAG
+    Dag,
+    /// Policy bundle
+    Policy,
+}
+
+/// Manifest record of one entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleEntry {
+    /// Entry name, a relative path such as `log/00000000000000000000.seg`
+    pub name: String,
+    /// What the entry holds
+    pub kind: EntryKind,
+    /// Offset of the entry's data from the start of the data section
+    pub offset: u64,
+    /// Size of the entry's data in bytes
+    pub size: u64,
+    /// BLAKE3 hash of the entry's data
+    pub hash: Hash,
+}
+
+/// Signed table of contents of a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Bundle format version
+    pub version: u32,
+    /// Run the bundle was made from
+    pub run_id: Option<RunId>,
+    /// Ed25519 public key the manifest is signed with
+    pub signer: [u8; 32],
+    /// Entries in data order
+    pub entries: Vec<BundleEntry>,
+}
+
+impl BundleManifest {
+    /// Get an entry by name
+    #[must_use]
+    pub fn entry(&self, name: &str) -> Option<&BundleEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Entries of one kind, in data order
+    pub fn entries_of(&self, kind: EntryKind) -> impl Iterator<Item = &BundleEntry> {
+        self.entries.iter().filter(move |e| e.kind == kind)
+    }
+
+    /// Total size of the data section
+    #[must_use]
+    pub fn data_len(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+
+    /// Hex encoding of the signer's public key
+    #[must_use]
+    pub fn signer_hex(&self) -> String {
+        key_hex(&self.signer)
+    }
+}
+
+/// Builder assembling a bundle in memory
+#[derive(Debug, Default)]
+pub struct BundleBuilder {
+    run_id: Option<RunId>,
+    entries: BTreeMap<String, (EntryKind, Vec<u8>)>,
+}
+
+impl BundleBuilder {
+    /// Create an empty builder
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the run the bundle is made from
+    #[must_use]
+    pub fn with_run_id(mut self, run_id: RunId) -> Self {
+        self.run_id = Some(run_id);
+        self
+    }
+
+    /// Add an entry
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the name is not a relative path inside the bundle
+    /// or is already taken
+    pub fn add_entry(&mut self, name: &str, kind: EntryKind, data: Vec<u8>) -> CoreResult<()> {
+        if !is_valid_name(name) {
+            return Err(BundleError::InvalidName { name: name.to_string() }.into());
+        }
+        if self.entries.contains_key(name) {
+            return Err(BundleError::DuplicateEntry { name: name.to_string() }.into());
+        }
+        self.entries.insert(name.to_string(), (kind, data));
+        Ok(())
+    }
+
+    /// Add every segment file of a segmented event log
+    ///
+    /// Returns the number of segments added. The log's index is left out;
+    /// [`SegmentedStream::open`] rebuilds it from the segments.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory or a segment cannot be read
+    pub fn add_log_dir(&mut self, dir: impl AsRef<Path>) -> CoreResult<usize> {
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir.as_ref())? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        for path in &paths {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            let data = std::fs::read(path)?;
+            self.add_entry(&format!("log/{}", file_name), EntryKind::LogSegment, data)?;
+        }
+        Ok(paths.len())
+    }
+
+    /// Add a content-addressed blob, returning its hash
+    ///
+    /// Adding the same content twice stores it once.
+    pub fn add_blob(&mut self, data: Vec<u8>) -> Hash {
+        let hash = Hash::compute(&data);
+        self.entries
+            .entry(blob_name(&hash))
+            .or_insert((EntryKind::Blob, data));
+        hash
+    }
+
+    /// Add a named artifact, stored as `artifacts/<name>`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the name is invalid or already taken
+    pub fn add_artifact(&mut self, name: &str, data: Vec<u8>) -> CoreResult<()> {
+        self.add_entry(&format!("artifacts/{}", name), EntryKind::Artifact, data)
+    }
+
+    /// Add the compiled DAG
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the DAG cannot be encoded or was already added
+    pub fn add_dag<T: Serialize>(&mut self, dag: &T) -> CoreResult<()> {
+        self.add_entry(DAG_ENTRY, EntryKind::Dag, serde_json::to_vec(dag)?)
+    }
+
+    /// Add the policy bundle
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the policy cannot be encoded or was already added
+    pub fn add_policy<T: Serialize>(&mut self, policy: &T) -> CoreResult<()> {
+        self.add_entry(POLICY_ENTRY, EntryKind::Policy, serde_json::to_vec(policy)?)
+    }
+
+    /// Number of entries added so far
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no entries have been added
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sign the manifest with `secret_key` and write the bundle
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the manifest cannot be encoded or the write fails
+    pub fn write<W: Write>(self, writer: &mut W, secret_key: &[u8; 32]) -> CoreResult<BundleManifest> {
+        let signing_key = SigningKey::from_bytes(secret_key);
+        let mut offset = 0;
+        let entries = self
+            .entries
+            .iter()
+            .map(|(name, (kind, data))| {
+                let entry = BundleEntry {
+                    name: name.clone(),
+                    kind: *kind,
+                    offset,
+                    size: data.len() as u64,
+                    hash: Hash::compute(data),
+                };
+                offset += entry.size;
+                entry
+            })
+            .collect();
+        let manifest = BundleManifest {
+            version: BUNDLE_VERSION,
+            run_id: self.run_id,
+            signer: signing_key.verifying_key().to_bytes(),
+            entries,
+        };
+        let manifest_bytes = serde_json::to_vec(&manifest)?;
+        let manifest_len = u32::try_from(manifest_bytes.len())
+            .ok()
+            .filter(|len| *len <= MAX_MANIFEST_BYTES)
+            .ok_or(CoreError::EncodingOverflow)?;
+        let signature = signing_key.sign(&manifest_bytes);
+
+        writer.write_all(BUNDLE_MAGIC)?;
+        writer.write_all(&BUNDLE_VERSION.to_be_bytes())?;
+        writer.write_all(&manifest_len.to_be_bytes())?;
+        writer.write_all(&manifest_bytes)?;
+        writer.write_all(&signature.to_bytes())?;
+        for (_, data) in self.entries.values() {
+            writer.write_all(data)?;
+        }
+        writer.flush()?;
+        Ok(manifest)
+    }
+
+    /// Sign and write the bundle to a file, replacing it if it exists
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be written
+    pub fn write_to_file(
+        self,
+        path: impl AsRef<Path>,
+        secret_key: &[u8; 32],
+    ) -> CoreResult<BundleManifest> {
+        let file = File::create(path.as_ref())?;
+        let mut writer = BufWriter::new(file);
+        let manifest = self.write(&mut writer, secret_key)?;
+        writer
+            .into_inner()
+            .map_err(|e| CoreError::from(e.into_error()))?
+            .sync_all()?;
+        Ok(manifest)
+    }
+}
+
+/// Reader over a verified bundle
+///
+/// Opening a bundle checks its signature and the hash of every entry, so
+/// a reader only exists for bundles that were intact when opened.
+#[derive(Debug)]
+pub struct BundleReader<R> {
+    reader: R,
+    manifest: BundleManifest,
+    /// Position of the data section in `reader`
+    data_start: u64,
+}
+
+impl BundleReader<BufReader<File>> {
+    /// Open and verify a bundle file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read or fails verification
+    pub fn open(path: impl AsRef<Path>) -> CoreResult<Self> {
+        let file = File::open(path.as_ref())?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: Read + Seek> BundleReader<R> {
+    /// Verify a bundle read from `reader`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the header, signature, layout or any entry hash is
+    /// invalid
+    pub fn new(mut reader: R) -> CoreResult<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic).map_err(|_| BundleError::BadMagic)?;
+        if &magic != BUNDLE_MAGIC {
+            return Err(BundleError::BadMagic.into());
+        }
+        let version = read_u32(&mut reader)?;
+        if version != BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion { actual: version }.into());
+        }
+        let manifest_len = read_u32(&mut reader)?;
+        if manifest_len > MAX_MANIFEST_BYTES {
+            return Err(malformed(format!("manifest of {} bytes", manifest_len)));
+        }
+        let mut manifest_bytes = vec![0u8; manifest_len as usize];
+        reader.read_exact(&mut manifest_bytes)?;
+        let mut signature = [0u8; SIGNATURE_LEN];
+        reader.read_exact(&mut signature)?;
+
+        let manifest: BundleManifest = serde_json::from_slice(&manifest_bytes)
+            .map_err(|e| malformed(format!("undecodable manifest: {}", e)))?;
+        VerifyingKey::from_bytes(&manifest.signer)
+            .and_then(|key| key.verify_strict(&manifest_bytes, &Signature::from_bytes(&signature)))
+            .map_err(|_| BundleError::BadSignature)?;
+        if manifest.version != version {
+            return Err(malformed(format!(
+                "manifest version {} in a version {} bundle",
+                manifest.version, version
+            )));
+        }
+
+        let data_start = reader.stream_position()?;
+        let mut bundle = Self { reader, manifest, data_start };
+        bundle.verify_layout()?;
+        bundle.verify_entries()?;
+        Ok(bundle)
+    }
+
+    /// The verified manifest
+    #[must_use]
+    pub fn manifest(&self) -> &BundleManifest {
+        &self.manifest
+    }
+
+    /// Check that the bundle was signed by `public_key`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if another key signed the bundle
+    pub fn verify_signer(&self, public_key: &[u8; 32]) -> CoreResult<()> {
+        if &self.manifest.signer != public_key {
+            return Err(BundleError::UntrustedSigner { signer: self.manifest.signer_hex() }.into());
+        }
+        Ok(())
+    }
+
+    /// Read one entry, checking its hash
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the entry is missing or its data no longer matches
+    /// the manifest
+    pub fn read(&mut self, name: &str) -> CoreResult<Vec<u8>> {
+        let entry = self
+            .manifest
+            .entry(name)
+            .cloned()
+            .ok_or_else(|| BundleError::EntryNotFound { name: name.to_string() })?;
+        self.reader
+            .seek(SeekFrom::Start(self.data_start + entry.offset))?;
+        let mut data = Vec::new();
+        (&mut self.reader).take(entry.size).read_to_end(&mut data)?;
+        if data.len() as u64 != entry.size || Hash::compute(&data) != entry.hash {
+            return Err(BundleError::EntryCorrupted { name: entry.name }.into());
+        }
+        Ok(data)
+    }
+
+    /// Check if the bundle holds a blob
+    #[must_use]
+    pub fn has_blob(&self, hash: &Hash) -> bool {
+        self.manifest.entry(&blob_name(hash)).is_some()
+    }
+
+    /// Read a blob by content hash
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the blob is missing or corrupted
+    pub fn blob(&mut self, hash: &Hash) -> CoreResult<Vec<u8>> {
+        self.read(&blob_name(hash))
+    }
+
+    /// Decode the compiled DAG, if the bundle has one
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the entry is corrupted or does not decode as `T`
+    pub fn dag<T: DeserializeOwned>(&mut self) -> CoreResult<Option<T>> {
+        self.decode(DAG_ENTRY)
+    }
+
+    /// Decode the policy bundle, if the bundle has one
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the entry is corrupted or does not decode as `T`
+    pub fn policy<T: DeserializeOwned>(&mut self) -> CoreResult<Option<T>> {
+        self.decode(POLICY_ENTRY)
+    }
+
+    /// Extract one entry to `dir/<name>`, returning the written path
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the entry is missing or corrupted or the file
+    /// cannot be written
+    pub fn extract(&mut self, name: &str, dir: impl AsRef<Path>) -> CoreResult<PathBuf> {
+        let data = self.read(name)?;
+        let path = dir.as_ref().join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, data)?;
+        Ok(path)
+    }
+
+    /// Extract the event log segments into `dir`, ready for
+    /// [`SegmentedStream::open`]
+    ///
+    /// Returns the number of segments written.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a segment is corrupted or cannot be written
+    pub fn extract_log(&mut self, dir: impl AsRef<Path>) -> CoreResult<usize> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let names: Vec<String> = self
+            .manifest
+            .entries_of(EntryKind::LogSegment)
+            .map(|e| e.name.clone())
+            .collect();
+        for name in &names {
+            let data = self.read(name)?;
+            let file_name = Path::new(name).file_name().unwrap_or_default();
+            std::fs::write(dir.join(file_name), data)?;
+        }
+        Ok(names.len())
+    }
+
+    /// Every event in the bundled log, in log order
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a segment is corrupted or the log does not open
+    pub fn events(&mut self) -> CoreResult<Vec<Event>> {
+        let dir = tempfile::Builder::new()
+            .prefix("cathedral-bundle-")
+            .tempdir()?;
+        self.extract_log(dir.path())?;
+        let (stream, _) = SegmentedStream::open(SegmentConfig::new(dir.path()).with_fsync(false))?;
+        stream.query(&LogQuery::default())
+    }
+
+    fn decode<T: DeserializeOwned>(&mut self, name: &str) -> CoreResult<Option<T>> {
+        if self.manifest.entry(name).is_none() {
+            return Ok(None);
+        }
+        let data = self.read(name)?;
+        Ok(Some(serde_json::from_slice(&data)?))
+    }
+
+    /// Check names are valid and unique and entries tile the data section
+    fn verify_layout(&self) -> CoreResult<()> {
+        let mut names = std::collections::BTreeSet::new();
+        let mut offset = 0u64;
+        for entry in &self.manifest.entries {
+            if !is_valid_name(&entry.name) {
+                return Err(BundleError::InvalidName { name: entry.name.clone() }.into());
+            }
+            if !names.insert(entry.name.as_str()) {
+                return Err(BundleError::DuplicateEntry { name: entry.name.clone() }.into());
+            }
+            if entry.offset != offset {
+                return Err(malformed(format!("entry {} is not contiguous", entry.name)));
+            }
+            offset = offset
+                .checked_add(entry.size)
+                .ok_or_else(|| malformed(format!("entry {} overflows", entry.name)))?;
+        }
+        Ok(())
+    }
+
+    /// Hash every entry and check nothing follows the last one
+    fn verify_entries(&mut self) -> CoreResult<()> {
+        self.reader.seek(SeekFrom::Start(self.data_start))?;
+        for entry in &self.manifest.entries {
+            let mut hasher = blake3::Hasher::new();
+            let copied = std::io::copy(&mut (&mut self.reader).take(entry.size), &mut hasher)?;
+            if copied != entry.size {
+                return Err(malformed(format!("data truncated in entry {}", entry.name)));
+            }
+            if Hash::from_bytes(*hasher.finalize().as_bytes()) != entry.hash {
+                return Err(BundleError::EntryCorrupted { name: entry.name.clone() }.into());
+            }
+        }
+        let mut trailing = [0u8; 1];
+        if self.reader.read(&mut trailing)? != 0 {
+            return Err(malformed("data after the last entry".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Entry name of a blob
+fn blob_name(hash: &Hash) -> String {
+    format!("blobs/{}", hash.to_hex())
+}
+
+/// Whether `name` is a non-empty relative path that stays inside the bundle
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains('\\')
+        && Path::new(name)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> CoreResult<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+fn key_hex(key: &[u8; 32]) -> String {
+    key.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn malformed(reason: String) -> CoreError {
+    BundleError::Malformed { reason }.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_core::{EventId, LogicalTime, NodeId};
+    use cathedral_log::EventKind;
+    use std::io::Cursor;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn builder() -> BundleBuilder {
+        let mut builder = BundleBuilder::new();
+        builder.add_artifact("node-a", b"output a".to_vec()).unwrap();
+        builder.add_dag(&vec!["a", "b"]).unwrap();
+        builder.add_blob(b"payload".to_vec());
+        builder
+    }
+
+    fn write(builder: BundleBuilder) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        builder.write(&mut bytes, &KEY).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_bundle_roundtrip() {
+        let bytes = write(builder());
+        let mut reader = BundleReader::new(Cursor::new(bytes)).unwrap();
+
+        assert_eq!(reader.manifest().entries.len(), 3);
+        assert_eq!(reader.read("artifacts/node-a").unwrap(), b"output a");
+        assert_eq!(reader.dag::<Vec<String>>().unwrap().unwrap(), vec!["a", "b"]);
+        assert_eq!(reader.policy::<Vec<String>>().unwrap(), None);
+        let hash = Hash::compute(b"payload");
+        assert!(reader.has_blob(&hash));
+        assert_eq!(reader.blob(&hash).unwrap(), b"payload");
+        assert!(!reader.has_blob(&Hash::compute(b"other")));
+    }
+
+    #[test]
+    fn test_bundle_bytes_are_deterministic() {
+        assert_eq!(write(builder()), write(builder()));
+    }
+
+    #[test]
+    fn test_bundle_detects_corrupted_entry() {
+        let mut bytes = write(builder());
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+
+        let err = BundleReader::new(Cursor::new(bytes)).unwrap_err();
+        assert!(err.to_string().contains("corrupted"));
+    }
+
+    #[test]
+    fn test_bundle_detects_tampered_manifest() {
+        let bytes = write(builder());
+        let text = String::from_utf8_lossy(&bytes).into_owned();
+        let at = text.find("node-a").unwrap();
+        let mut tampered = bytes.clone();
+        tampered[at] = b'N';
+
+        let err = BundleReader::new(Cursor::new(tampered)).unwrap_err();
+        assert_eq!(err, BundleError::BadSignature.into());
+    }
+
+    #[test]
+    fn test_bundle_rejects_truncation_and_bad_magic() {
+        let bytes = write(builder());
+        assert!(BundleReader::new(Cursor::new(bytes[..bytes.len() - 1].to_vec())).is_err());
+        assert_eq!(
+            BundleReader::new(Cursor::new(b"not a bundle".to_vec())).unwrap_err(),
+            BundleError::BadMagic.into()
+        );
+    }
+
+    #[test]
+    fn test_bundle_verify_signer() {
+        let reader = BundleReader::new(Cursor::new(write(builder()))).unwrap();
+        let public_key = SigningKey::from_bytes(&KEY).verifying_key().to_bytes();
+        assert!(reader.verify_signer(&public_key).is_ok());
+        assert!(reader.verify_signer(&[1; 32]).is_err());
+    }
+
+    #[test]
+    fn test_bundle_rejects_escaping_names() {
+        let mut builder = BundleBuilder::new();
+        assert!(builder.add_artifact("../etc/passwd", Vec::new()).is_err());
+        assert!(builder.add_entry("/abs", EntryKind::Artifact, Vec::new()).is_err());
+        builder.add_artifact("a", Vec::new()).unwrap();
+        assert!(builder.add_artifact("a", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_bundle_extracts_single_artifact() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut reader = BundleReader::new(Cursor::new(write(builder()))).unwrap();
+
+        let path = reader.extract("artifacts/node-a", dir.path()).unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), b"output a");
+        assert!(!dir.path().join(DAG_ENTRY).exists());
+    }
+
+    #[test]
+    fn test_bundle_log_roundtrip() {
+        let log_dir = tempfile::tempdir().unwrap();
+        let config = SegmentConfig::new(log_dir.path()).with_max_segment_bytes(256);
+        let (mut stream, _) = SegmentedStream::open(config).unwrap();
+        let (run_id, node_id) = (RunId::new(), NodeId::new());
+        let mut state = Hash::empty();
+        for i in 0..6u64 {
+            let next = Hash::compute(&i.to_be_bytes());
+            let event = Event::new(
+                EventId::new(),
+                run_id,
+                node_id,
+                LogicalTime::from_raw(i),
+                EventKind::NodeCompleted,
+            )
+            .with_payload(vec![i as u8; 64])
+            .with_state_hashes(state, next);
+            stream.append(&event).unwrap();
+            state = next;
+        }
+        let segments = stream.segment_count();
+        drop(stream);
+
+        let mut builder = BundleBuilder::new().with_run_id(run_id);
+        assert_eq!(builder.add_log_dir(log_dir.path()).unwrap(), segments);
+        let mut reader = BundleReader::new(Cursor::new(write(builder))).unwrap();
+
+        assert_eq!(reader.manifest().run_id, Some(run_id));
+        let events = reader.events().unwrap();
+        assert_eq!(events.len(), 6);
+        assert_eq!(events[5].logical_time, LogicalTime::from_raw(5));
+    }
+}
@@ -0,0 +1,425 @@
+//! Time-travel debugger over a replayed trace.
+//!
+//! Steps a trace forward one event at a time and back again. Stepping back
+//! restores the nearest earlier state checkpoint and replays forward from
+//! it, so every position reproduces exactly the state replay would reach.
+
+use crate::engine::ReplayEngine;
+use crate::state::ReconstructedState;
+use crate::trace::{TraceEvent, TraceEventKind};
+use cathedral_core::{CoreError, CoreResult, NodeId};
+use serde::{Deserialize, Serialize};
+
+/// Events between state checkpoints unless configured otherwise
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 64;
+
+/// Condition that stops [`Debugger::resume`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Breakpoint {
+    /// A node starts
+    NodeStart(NodeId),
+    /// An event of this kind, by variant name such as `CapabilityCheck`
+    EventKind(String),
+    /// A state key changes value, see [`ReconstructedState::value`]
+    StateKey(String),
+}
+
+impl std::fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NodeStart(node_id) => write!(f, "node {} starts", node_id),
+            Self::EventKind(kind) => write!(f, "{} event", kind),
+            Self::StateKey(key) => write!(f, "{} changes", key),
+        }
+    }
+}
+
+/// Change of a watched or breakpoint state key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyChange {
+    /// State key
+    pub key: String,
+    /// Value before the step
+    pub before: Option<Vec<u8>>,
+    /// Value after the step
+    pub after: Option<Vec<u8>>,
+}
+
+/// Event applied by one step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    /// Position of the event in the trace
+    pub index: usize,
+    /// Event applied
+    pub event: TraceEvent,
+    /// Breakpoints the event hit, by breakpoint ID
+    pub hits: Vec<usize>,
+    /// Watched keys the event changed
+    pub changes: Vec<KeyChange>,
+}
+
+/// Interactive replay of a trace that can move in both directions
+pub struct Debugger {
+    engine: ReplayEngine,
+    trace: Vec<TraceEvent>,
+    state: ReconstructedState,
+    /// Events applied to `state`
+    position: usize,
+    /// States after the first `n` events, ascending by `n`
+    checkpoints: Vec<(usize, ReconstructedState)>,
+    checkpoint_interval: usize,
+    /// Breakpoints by ID; removed ones leave a gap so IDs stay stable
+    breakpoints: Vec<Option<Breakpoint>>,
+    watches: Vec<String>,
+}
+
+impl ReplayEngine {
+    /// Start a debugging session over `trace`, positioned before its first
+    /// event
+    #[must_use]
+    pub fn debug(self, trace: Vec<TraceEvent>) -> Debugger {
+        Debugger {
+            engine: self,
+            trace,
+            state: ReconstructedState::new(),
+            position: 0,
+            checkpoints: vec![(0, ReconstructedState::new())],
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            breakpoints: Vec::new(),
+            watches: Vec::new(),
+        }
+    }
+}
+
+impl Debugger {
+    /// Set how many events apart state checkpoints are taken
+    ///
+    /// Shorter intervals make stepping back cheaper at the cost of memory.
+    #[must_use]
+    pub fn with_checkpoint_interval(mut self, interval: usize) -> Self {
+        self.checkpoint_interval = interval.max(1);
+        self
+    }
+
+    /// State after the events applied so far
+    #[must_use]
+    pub fn state(&self) -> &ReconstructedState {
+        &self.state
+    }
+
+    /// Number of events applied so far
+    #[must_use]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of events in the trace
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.trace.len()
+    }
+
+    /// Check if the trace has no events
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.trace.is_empty()
+    }
+
+    /// Check if every event has been applied
+    #[must_use]
+    pub fn at_end(&self) -> bool {
+        self.position == self.trace.len()
+    }
+
+    /// Event applied last, if any
+    #[must_use]
+    pub fn current(&self) -> Option<&TraceEvent> {
+        self.position.checked_sub(1).and_then(|i| self.trace.get(i))
+    }
+
+    /// Event the next step applies, if any
+    #[must_use]
+    pub fn upcoming(&self) -> Option<&TraceEvent> {
+        self.trace.get(self.position)
+    }
+
+    /// Add a breakpoint and return its ID
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(Some(breakpoint));
+        self.breakpoints.len() - 1
+    }
+
+    /// Remove a breakpoint, returning it if it existed
+    pub fn remove_breakpoint(&mut self, id: usize) -> Option<Breakpoint> {
+        self.breakpoints.get_mut(id).and_then(Option::take)
+    }
+
+    /// Breakpoints with their IDs
+    pub fn breakpoints(&self) -> impl Iterator<Item = (usize, &Breakpoint)> {
+        self.breakpoints
+            .iter()
+            .enumerate()
+            .filter_map(|(id, breakpoint)| breakpoint.as_ref().map(|b| (id, b)))
+    }
+
+    /// Watch a state key; steps report when it changes
+    pub fn watch(&mut self, key: impl Into<String>) {
+        let key = key.into();
+        if !self.watches.contains(&key) {
+            self.watches.push(key);
+        }
+    }
+
+    /// Stop watching a state key, returning whether it was watched
+    pub fn unwatch(&mut self, key: &str) -> bool {
+        let watched = self.watches.len();
+        self.watches.retain(|k| k != key);
+        self.watches.len() != watched
+    }
+
+    /// Watched keys with their current values
+    #[must_use]
+    pub fn watches(&self) -> Vec<(&str, Option<Vec<u8>>)> {
+        self.watches
+            .iter()
+            .map(|key| (key.as_str(), self.state.value(key)))
+            .collect()
+    }
+
+    /// Apply the next event
+    ///
+    /// Returns `None` at the end of the trace.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the event cannot be replayed
+    pub fn step(&mut self) -> CoreResult<Option<Step>> {
+        let Some(event) = self.trace.get(self.position).cloned() else {
+            return Ok(None);
+        };
+        let keys = self.tracked_keys();
+        let before: Vec<Option<Vec<u8>>> = keys.iter().map(|key| self.state.value(key)).collect();
+
+        self.engine.process_event(&mut self.state, &event)?;
+        self.position += 1;
+        self.checkpoint();
+
+        let changes: Vec<KeyChange> = keys
+            .into_iter()
+            .zip(before)
+            .filter_map(|(key, before)| {
+                let after = self.state.value(&key);
+                (after != before).then_some(KeyChange { key, before, after })
+            })
+            .collect();
+        let hits = self
+            .breakpoints()
+            .filter(|(_, breakpoint)| match breakpoint {
+                Breakpoint::NodeStart(node_id) => {
+                    event.kind == TraceEventKind::NodeStarted && event.node_id == *node_id
+                }
+                Breakpoint::EventKind(kind) => event.kind.name() == kind,
+                Breakpoint::StateKey(key) => changes.iter().any(|change| change.key == *key),
+            })
+            .map(|(id, _)| id)
+            .collect();
+        let changes = changes
+            .into_iter()
+            .filter(|change| self.watches.contains(&change.key))
+            .collect();
+
+        Ok(Some(Step {
+            index: self.position - 1,
+            event,
+            hits,
+            changes,
+        }))
+    }
+
+    /// Undo the last event
+    ///
+    /// Returns `false` if no event has been applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if replaying from the checkpoint fails
+    pub fn step_back(&mut self) -> CoreResult<bool> {
+        match self.position.checked_sub(1) {
+            Some(target) => self.seek(target).map(|()| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Step forward until a breakpoint hits or the trace ends
+    ///
+    /// Returns the step that hit a breakpoint, or `None` at the end.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if an event cannot be replayed
+    pub fn resume(&mut self) -> CoreResult<Option<Step>> {
+        while let Some(step) = self.step()? {
+            if !step.hits.is_empty() {
+                return Ok(Some(step));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Move to the state after the first `position` events
+    ///
+    /// Moving back restores the nearest checkpoint at or before `position`
+    /// and replays forward from it.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `position` is past the end or replay fails
+    pub fn seek(&mut self, position: usize) -> CoreResult<()> {
+        if position > self.trace.len() {
+            return Err(CoreError::Validation {
+                field: "position".to_string(),
+                reason: format!("{} is past the end of a {} event trace", position, self.len()),
+            });
+        }
+        if position < self.position {
+            let at = self.checkpoints.partition_point(|(n, _)| *n <= position) - 1;
+            let (start, state) = &self.checkpoints[at];
+            self.position = *start;
+            self.state = state.clone();
+        }
+        while self.position < position {
+            let event = &self.trace[self.position];
+            self.engine.process_event(&mut self.state, event)?;
+            self.position += 1;
+            self.checkpoint();
+        }
+        Ok(())
+    }
+
+    /// Keys whose changes a step reports or breaks on
+    fn tracked_keys(&self) -> Vec<String> {
+        let mut keys = self.watches.clone();
+        for (_, breakpoint) in self.breakpoints() {
+            if let Breakpoint::StateKey(key) = breakpoint
+                && !keys.contains(key)
+            {
+                keys.push(key.clone());
+            }
+        }
+        keys
+    }
+
+    /// Checkpoint the state if a new interval boundary was reached
+    fn checkpoint(&mut self) {
+        let last = self.checkpoints.last().map_or(0, |(n, _)| *n);
+        if self.position > last && self.position.is_multiple_of(self.checkpoint_interval) {
+            self.checkpoints.push((self.position, self.state.clone()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_core::{EventId, LogicalTime};
+
+    fn trace(a: NodeId, b: NodeId) -> Vec<TraceEvent> {
+        let event = |time, node_id, kind, data: &[u8]| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(time),
+            node_id,
+            kind,
+            data: data.to_vec(),
+            parent_id: None,
+        };
+        vec![
+            event(0, a, TraceEventKind::NodeStarted, b""),
+            event(1, a, TraceEventKind::NodeCompleted, b"one"),
+            event(2, b, TraceEventKind::NodeStarted, b""),
+            event(3, b, TraceEventKind::SideEffect { effect: "write".to_string() }, b""),
+            event(4, b, TraceEventKind::OutputProduced, b"two"),
+            event(5, b, TraceEventKind::NodeCompleted, b"three"),
+        ]
+    }
+
+    #[test]
+    fn test_step_and_step_back_match_replay() {
+        let (a, b) = (NodeId::new(), NodeId::new());
+        let events = trace(a, b);
+        let mut debugger = ReplayEngine::new().debug(events.clone()).with_checkpoint_interval(2);
+
+        let mut states = vec![debugger.state().clone()];
+        while let Some(step) = debugger.step().unwrap() {
+            assert_eq!(step.event, events[step.index]);
+            states.push(debugger.state().clone());
+        }
+        assert!(debugger.at_end());
+        let replayed = ReplayEngine::new()
+            .replay(&mut crate::trace::TraceReader::from_events(events.clone()))
+            .unwrap();
+        assert_eq!(debugger.state(), &replayed);
+
+        // Every earlier state is reproduced exactly on the way back
+        for expected in states.iter().rev().skip(1) {
+            assert!(debugger.step_back().unwrap());
+            assert_eq!(debugger.state(), expected);
+        }
+        assert_eq!(debugger.position(), 0);
+        assert!(!debugger.step_back().unwrap());
+
+        debugger.seek(5).unwrap();
+        assert_eq!(debugger.state(), &states[5]);
+        assert_eq!(debugger.current(), Some(&events[4]));
+        assert_eq!(debugger.upcoming(), Some(&events[5]));
+        assert!(debugger.seek(7).is_err());
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let (a, b) = (NodeId::new(), NodeId::new());
+        let mut debugger = ReplayEngine::new().debug(trace(a, b));
+
+        let start = debugger.add_breakpoint(Breakpoint::NodeStart(b));
+        let effect = debugger.add_breakpoint(Breakpoint::EventKind("SideEffect".to_string()));
+        let output = debugger.add_breakpoint(Breakpoint::StateKey(format!("{}.output", b)));
+
+        let step = debugger.resume().unwrap().unwrap();
+        assert_eq!((step.index, step.hits), (2, vec![start]));
+        let step = debugger.resume().unwrap().unwrap();
+        assert_eq!((step.index, step.hits), (3, vec![effect]));
+        let step = debugger.resume().unwrap().unwrap();
+        assert_eq!((step.index, step.hits.clone()), (4, vec![output]));
+        // Breakpoint keys are not reported as watch changes
+        assert!(step.changes.is_empty());
+
+        let removed = debugger.remove_breakpoint(output);
+        assert_eq!(removed, Some(Breakpoint::StateKey(format!("{}.output", b))));
+        assert_eq!(debugger.remove_breakpoint(output), None);
+        assert_eq!(debugger.breakpoints().count(), 2);
+        assert!(debugger.resume().unwrap().is_none());
+        assert!(debugger.at_end());
+    }
+
+    #[test]
+    fn test_watches() {
+        let (a, b) = (NodeId::new(), NodeId::new());
+        let mut debugger = ReplayEngine::new().debug(trace(a, b));
+        let key = format!("{}.output", a);
+        debugger.watch(key.clone());
+        debugger.watch(key.clone());
+        assert_eq!(debugger.watches(), vec![(key.as_str(), None)]);
+
+        assert!(debugger.step().unwrap().unwrap().changes.is_empty());
+        let step = debugger.step().unwrap().unwrap();
+        assert_eq!(
+            step.changes,
+            vec![KeyChange { key: key.clone(), before: None, after: Some(b"one".to_vec()) }]
+        );
+        assert_eq!(debugger.watches(), vec![(key.as_str(), Some(b"one".to_vec()))]);
+
+        debugger.step_back().unwrap();
+        assert_eq!(debugger.watches(), vec![(key.as_str(), None)]);
+        assert!(debugger.unwatch(&key));
+        assert!(!debugger.unwatch(&key));
+    }
+}
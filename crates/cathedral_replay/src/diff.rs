@@ -1,9 +1,12 @@
 //! Diff engine for comparing executions.
 
-use cathedral_core::{NodeId, CoreResult, CoreError};
+use cathedral_core::{CapabilitySet, NodeId, CoreResult, Hash};
+use crate::engine::{ReplayConfig, ReplayEngine};
 use crate::state::{ReconstructedState, StateDiff};
+use crate::trace::{TraceEvent, TraceEventKind, TraceReader};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
+use std::fmt::Write as _;
 
 /// Result of a diff operation
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -25,6 +28,34 @@ pub struct DiffReport {
     pub summary: DiffSummary,
     /// Detailed changes by node
     pub node_changes: Vec<NodeChange>,
+    /// Differences between the capability grants of the two runs
+    pub capability_diff: CapabilityDiff,
+}
+
+/// Difference between the capability sets granted to two runs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityDiff {
+    /// Granted to the first run but not the second
+    pub only_before: CapabilitySet,
+    /// Granted to the second run but not the first
+    pub only_after: CapabilitySet,
+}
+
+impl CapabilityDiff {
+    /// Compare two capability sets
+    #[must_use]
+    pub fn compute(before: &CapabilitySet, after: &CapabilitySet) -> Self {
+        Self {
+            only_before: before.difference(after),
+            only_after: after.difference(before),
+        }
+    }
+
+    /// Check if the grants differ
+    #[must_use]
+    pub fn has_changes(&self) -> bool {
+        !self.only_before.is_empty() || !self.only_after.is_empty()
+    }
 }
 
 /// Summary of diff
@@ -90,6 +121,216 @@ pub enum LineChange {
     Unchanged(String),
 }
 
+/// Explanation of the first point where two runs diverge
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DivergenceReport {
+    /// Index of the first divergent event in both traces
+    pub index: usize,
+    /// Node at which the runs diverged
+    pub node_id: Option<NodeId>,
+    /// What differed
+    pub cause: DivergenceCause,
+    /// Left run state reconstructed up to the divergence
+    pub left_state: ReconstructedState,
+    /// Right run state reconstructed up to the divergence
+    pub right_state: ReconstructedState,
+    /// Last tool invoked by the divergent node, if any
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Hash of that tool's input in the left run
+    #[serde(default)]
+    pub left_input_hash: Option<Hash>,
+    /// Hash of that tool's input in the right run
+    #[serde(default)]
+    pub right_input_hash: Option<Hash>,
+}
+
+/// Cause of a divergence between two runs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DivergenceCause {
+    /// The same tool was invoked with different input
+    ToolInput {
+        /// Tool name
+        tool: String,
+        /// Input in the left run
+        left: Vec<u8>,
+        /// Input in the right run
+        right: Vec<u8>,
+    },
+    /// The same host function returned different results
+    HostCallResult {
+        /// Host function name
+        function: String,
+        /// Result in the left run
+        left: Vec<u8>,
+        /// Result in the right run
+        right: Vec<u8>,
+    },
+    /// A capability check was decided differently
+    CapabilityDecision {
+        /// Capability checked
+        capability: String,
+        /// Decision in the left run
+        left: bool,
+        /// Decision in the right run
+        right: bool,
+    },
+    /// The same event carried different data
+    Data {
+        /// Event kind
+        kind: TraceEventKind,
+        /// Data in the left run
+        left: Vec<u8>,
+        /// Data in the right run
+        right: Vec<u8>,
+    },
+    /// The runs emitted different events
+    Event {
+        /// Event in the left run
+        left: TraceEventKind,
+        /// Event in the right run
+        right: TraceEventKind,
+    },
+    /// One run ended before the other
+    Truncated {
+        /// Event count of the left run
+        left_len: usize,
+        /// Event count of the right run
+        right_len: usize,
+    },
+}
+
+impl std::fmt::Display for DivergenceCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).to_string();
+        match self {
+            Self::ToolInput { tool, left, right } => write!(
+                f,
+                "tool {} input differs: {:?} vs {:?}",
+                tool,
+                lossy(left),
+                lossy(right)
+            ),
+            Self::HostCallResult { function, left, right } => write!(
+                f,
+                "host call {} returned different results: {:?} vs {:?}",
+                function,
+                lossy(left),
+                lossy(right)
+            ),
+            Self::CapabilityDecision { capability, left, right } => write!(
+                f,
+                "capability {} decided differently: allowed={} vs allowed={}",
+                capability, left, right
+            ),
+            Self::Data { kind, left, right } => write!(
+                f,
+                "{:?} data differs: {:?} vs {:?}",
+                kind,
+                lossy(left),
+                lossy(right)
+            ),
+            Self::Event { left, right } => {
+                write!(f, "different events: {:?} vs {:?}", left, right)
+            }
+            Self::Truncated { left_len, right_len } => write!(
+                f,
+                "one run ended early: {} vs {} events",
+                left_len, right_len
+            ),
+        }
+    }
+}
+
+/// Events of two aligned traces that differ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventDiff {
+    /// Position in the left trace, or where a right-only event falls in it
+    pub index: usize,
+    /// Position in the right trace, or where a left-only event falls in it
+    pub right_index: usize,
+    /// Left event, unless only the right trace has one here
+    pub left: Option<TraceEvent>,
+    /// Right event, unless only the left trace has one here
+    pub right: Option<TraceEvent>,
+}
+
+/// Unified comparison of two traces: the events that differ and the
+/// differences between the states they replay to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceDiff {
+    /// Events at every position where the traces differ
+    pub events: Vec<EventDiff>,
+    /// Differences between the replayed states
+    pub state: DiffReport,
+    /// Explanation of the first differing event
+    pub divergence: Option<DivergenceReport>,
+}
+
+impl TraceDiff {
+    /// Check if the traces are identical
+    #[must_use]
+    pub fn is_identical(&self) -> bool {
+        self.events.is_empty() && self.state.result.equivalent
+    }
+
+    /// Render the diff in a unified format
+    ///
+    /// Each differing event is shown as a hunk with its left event as a `-`
+    /// line and its right event as a `+` line, followed by the differing
+    /// nodes and global state keys.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).to_string();
+        let mut out = String::from("--- left\n+++ right\n");
+        for diff in &self.events {
+            let _ = writeln!(out, "@@ -{} +{} @@", diff.index, diff.right_index);
+            for (sign, event) in [('-', &diff.left), ('+', &diff.right)] {
+                if let Some(event) = event {
+                    let _ = writeln!(
+                        out,
+                        "{} t={} {} {:?} {:?}",
+                        sign,
+                        event.time.as_u64(),
+                        event.node_id,
+                        event.kind,
+                        lossy(&event.data)
+                    );
+                }
+            }
+        }
+
+        let diff = &self.state.result.diff;
+        if diff.has_changes() {
+            out.push_str("@@ state @@\n");
+        }
+        for change in &self.state.node_changes {
+            let _ = match change.change_type {
+                NodeChangeType::Added => writeln!(out, "+ node {}", change.node_id),
+                NodeChangeType::Removed => writeln!(out, "- node {}", change.node_id),
+                ref other => writeln!(out, "~ node {} {:?}", change.node_id, other),
+            };
+            for line in change.output_diff.iter().flat_map(|output| &output.line_diff) {
+                let _ = match line {
+                    LineChange::Added(new) => writeln!(out, "+   {}", new),
+                    LineChange::Removed(old) => writeln!(out, "-   {}", old),
+                    LineChange::Modified { old, new } => writeln!(out, "-   {}\n+   {}", old, new),
+                    LineChange::Unchanged(_) => Ok(()),
+                };
+            }
+        }
+        for change in &diff.global_changes {
+            if let Some(old) = &change.old_value {
+                let _ = writeln!(out, "- {} = {:?}", change.key, lossy(old));
+            }
+            if let Some(new) = &change.new_value {
+                let _ = writeln!(out, "+ {} = {:?}", change.key, lossy(new));
+            }
+        }
+        out
+    }
+}
+
 /// Engine for diffing two executions
 pub struct DiffEngine;
 
@@ -203,9 +444,27 @@ impl DiffEngine {
             result,
             summary,
             node_changes,
+            capability_diff: CapabilityDiff::default(),
         })
     }
 
+    /// Generate a diff report that also compares capability grants
+    ///
+    /// # Errors
+    ///
+    /// Returns error if report generation fails
+    pub fn generate_report_with_capabilities(
+        &self,
+        before: &ReconstructedState,
+        after: &ReconstructedState,
+        before_capabilities: &CapabilitySet,
+        after_capabilities: &CapabilitySet,
+    ) -> CoreResult<DiffReport> {
+        let mut report = self.generate_report(before, after)?;
+        report.capability_diff = CapabilityDiff::compute(before_capabilities, after_capabilities);
+        Ok(report)
+    }
+
     /// Diff two byte arrays
     fn diff_bytes(old: &[u8], new: &[u8]) -> StringDiff {
         let old_str = String::from_utf8_lossy(old).to_string();
@@ -247,6 +506,28 @@ impl DiffEngine {
         }
     }
 
+    /// Compare two traces event by event and by the states they replay to
+    ///
+    /// Events are compared as in [`explain_divergence`](Self::explain_divergence),
+    /// and the traces are aligned on their longest common subsequence, so
+    /// an event only one trace has does not make every later event differ.
+    /// The first differing event is explained. Node errors do not stop
+    /// either replay.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if replaying either trace fails
+    pub fn diff_traces(&self, left: &[TraceEvent], right: &[TraceEvent]) -> CoreResult<TraceDiff> {
+        let events = Self::align(left, right);
+        let (left_state, right_state) = (Self::replay_prefix(left)?, Self::replay_prefix(right)?);
+        let state = self.generate_report(&left_state, &right_state)?;
+        let divergence = match events.first() {
+            Some(first) => Some(Self::report_at(left, right, first.index)?),
+            None => None,
+        };
+        Ok(TraceDiff { events, state, divergence })
+    }
+
     /// Find divergence point between two traces
     ///
     /// # Errors
@@ -278,6 +559,219 @@ impl DiffEngine {
         Ok(divergence_time)
     }
 
+    /// Explain the first divergence between two traces
+    ///
+    /// Events are compared by node, kind, logical time, and data; event
+    /// and parent IDs are ignored since they are not stable across runs.
+    /// Both runs are replayed up to the divergent event, and the differing
+    /// tool input, host-call result, or capability decision is reported.
+    /// Returns `None` if the traces are equivalent.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if replaying either prefix fails
+    pub fn explain_divergence(
+        &self,
+        left: &[TraceEvent],
+        right: &[TraceEvent],
+    ) -> CoreResult<Option<DivergenceReport>> {
+        let Some(index) = (0..left.len().max(right.len())).find(|&i| {
+            match (left.get(i), right.get(i)) {
+                (Some(a), Some(b)) => !Self::same_event(a, b),
+                _ => true,
+            }
+        }) else {
+            return Ok(None);
+        };
+
+        Self::report_at(left, right, index).map(Some)
+    }
+
+    /// Align two traces on their longest common subsequence of matching
+    /// events, returning the events between matches
+    ///
+    /// Between two matches, removed and added events are paired in order.
+    /// The common prefix and suffix are matched directly, so only the
+    /// region between them is aligned by dynamic programming.
+    fn align(left: &[TraceEvent], right: &[TraceEvent]) -> Vec<EventDiff> {
+        let same = |(a, b): (&TraceEvent, &TraceEvent)| Self::same_event(a, b);
+        let prefix = left.iter().zip(right).take_while(|&pair| same(pair)).count();
+        let (left_rest, right_rest) = (&left[prefix..], &right[prefix..]);
+        let suffix = left_rest
+            .iter()
+            .rev()
+            .zip(right_rest.iter().rev())
+            .take_while(|&pair| same(pair))
+            .count();
+        let a = &left_rest[..left_rest.len() - suffix];
+        let b = &right_rest[..right_rest.len() - suffix];
+
+        // lengths[i * width + j] is the LCS length of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lengths = vec![0usize; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lengths[i * width + j] = if Self::same_event(&a[i], &b[j]) {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+
+        let mut diffs = Vec::new();
+        let mut hunk = |(start_a, end_a): (usize, usize), (start_b, end_b): (usize, usize)| {
+            for k in 0..(end_a - start_a).max(end_b - start_b) {
+                diffs.push(EventDiff {
+                    index: prefix + (start_a + k).min(end_a),
+                    right_index: prefix + (start_b + k).min(end_b),
+                    left: a[start_a..end_a].get(k).cloned(),
+                    right: b[start_b..end_b].get(k).cloned(),
+                });
+            }
+        };
+        let (mut i, mut j) = (0, 0);
+        let (mut start_a, mut start_b) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && Self::same_event(&a[i], &b[j]) {
+                hunk((start_a, i), (start_b, j));
+                i += 1;
+                j += 1;
+                (start_a, start_b) = (i, j);
+            } else if j == b.len()
+                || i < a.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1]
+            {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        hunk((start_a, i), (start_b, j));
+        diffs
+    }
+
+    /// Check if two events match, ignoring their event and parent IDs
+    fn same_event(a: &TraceEvent, b: &TraceEvent) -> bool {
+        a.node_id == b.node_id && a.kind == b.kind && a.time == b.time && a.data == b.data
+    }
+
+    /// Build the report for two traces that diverge at `index`
+    pub(crate) fn report_at(
+        left: &[TraceEvent],
+        right: &[TraceEvent],
+        index: usize,
+    ) -> CoreResult<DivergenceReport> {
+        let (node_id, cause) = match (left.get(index), right.get(index)) {
+            (Some(a), Some(b)) => (Some(a.node_id), Self::divergence_cause(a, b)),
+            (a, b) => (
+                a.or(b).map(|e| e.node_id),
+                DivergenceCause::Truncated {
+                    left_len: left.len(),
+                    right_len: right.len(),
+                },
+            ),
+        };
+
+        let left_input = node_id.and_then(|node| Self::tool_input(left, index, node));
+        let right_input = node_id.and_then(|node| Self::tool_input(right, index, node));
+        let tool = left_input
+            .as_ref()
+            .or(right_input.as_ref())
+            .map(|(tool, _)| tool.clone());
+
+        Ok(DivergenceReport {
+            index,
+            node_id,
+            cause,
+            left_state: Self::replay_prefix(&left[..index.min(left.len())])?,
+            right_state: Self::replay_prefix(&right[..index.min(right.len())])?,
+            tool,
+            left_input_hash: left_input.map(|(_, hash)| hash),
+            right_input_hash: right_input.map(|(_, hash)| hash),
+        })
+    }
+
+    /// Last tool `node_id` invoked at or before `index`, with its input hash
+    fn tool_input(
+        events: &[TraceEvent],
+        index: usize,
+        node_id: NodeId,
+    ) -> Option<(String, Hash)> {
+        events
+            .iter()
+            .take(index + 1)
+            .rev()
+            .filter(|event| event.node_id == node_id)
+            .find_map(|event| match &event.kind {
+                TraceEventKind::ToolInvoked { tool, .. } => {
+                    Some((tool.clone(), Hash::compute(&event.data)))
+                }
+                _ => None,
+            })
+    }
+
+    /// Classify why two events at the same position differ
+    fn divergence_cause(left: &TraceEvent, right: &TraceEvent) -> DivergenceCause {
+        match (&left.kind, &right.kind) {
+            (
+                TraceEventKind::ToolInvoked { tool: a, .. },
+                TraceEventKind::ToolInvoked { tool: b, .. },
+            ) if a == b =>
+            {
+                DivergenceCause::ToolInput {
+                    tool: a.clone(),
+                    left: left.data.clone(),
+                    right: right.data.clone(),
+                }
+            }
+            (TraceEventKind::HostCall { function: a }, TraceEventKind::HostCall { function: b })
+                if a == b =>
+            {
+                DivergenceCause::HostCallResult {
+                    function: a.clone(),
+                    left: left.data.clone(),
+                    right: right.data.clone(),
+                }
+            }
+            (
+                TraceEventKind::CapabilityCheck {
+                    capability: a,
+                    allowed: left_allowed,
+                },
+                TraceEventKind::CapabilityCheck {
+                    capability: b,
+                    allowed: right_allowed,
+                },
+            ) if a == b && left_allowed != right_allowed => DivergenceCause::CapabilityDecision {
+                capability: a.clone(),
+                left: *left_allowed,
+                right: *right_allowed,
+            },
+            (a, b) if a == b => DivergenceCause::Data {
+                kind: a.clone(),
+                left: left.data.clone(),
+                right: right.data.clone(),
+            },
+            (a, b) => DivergenceCause::Event {
+                left: a.clone(),
+                right: b.clone(),
+            },
+        }
+    }
+
+    /// Replay a trace prefix, continuing past node errors
+    fn replay_prefix(events: &[TraceEvent]) -> CoreResult<ReconstructedState> {
+        if events.is_empty() {
+            return Ok(ReconstructedState::new());
+        }
+        let config = ReplayConfig {
+            stop_on_error: false,
+            ..Default::default()
+        };
+        let mut reader = TraceReader::from_events(events.to_vec());
+        ReplayEngine::new().with_config(config).replay(&mut reader)
+    }
+
     /// Check if two states are semantically equivalent
     ///
     /// # Errors
@@ -383,6 +877,39 @@ mod tests {
         assert_eq!(report.summary.added_count, 1);
     }
 
+    #[test]
+    fn test_generate_report_with_capabilities() {
+        use cathedral_core::Capability;
+
+        let engine = DiffEngine::new();
+        let mut state1 = ReconstructedState::new();
+        let mut state2 = ReconstructedState::new();
+        let node_id = NodeId::new();
+        let mut failed = NodeState::new(node_id);
+        failed.error = Some("permission denied".to_string());
+        state1.add_node_state(node_id, NodeState::new(node_id));
+        state2.add_node_state(node_id, failed);
+
+        let fs = Capability::FsRead {
+            prefixes: vec!["./data".to_string()],
+        };
+        let before: CapabilitySet = vec![Capability::ClockRead, fs.clone()].into_iter().collect();
+        let after: CapabilitySet = vec![Capability::ClockRead].into_iter().collect();
+
+        let report = engine
+            .generate_report_with_capabilities(&state1, &state2, &before, &after)
+            .unwrap();
+        assert_eq!(report.summary.modified_count, 1);
+        assert!(report.capability_diff.has_changes());
+        assert!(report.capability_diff.only_before.has(&fs));
+        assert!(report.capability_diff.only_after.is_empty());
+
+        let same = engine
+            .generate_report_with_capabilities(&state1, &state1, &before, &before)
+            .unwrap();
+        assert!(!same.capability_diff.has_changes());
+    }
+
     #[test]
     fn test_diff_bytes() {
         let old = b"line1\nline2";
@@ -392,6 +919,123 @@ mod tests {
         assert_eq!(diff.line_diff.len(), 2);
     }
 
+    fn trace(node_id: NodeId, input: &[u8], clock: &[u8]) -> Vec<TraceEvent> {
+        use cathedral_core::{EventId, LogicalTime};
+
+        let event = |time, kind, data: &[u8]| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(time),
+            node_id,
+            kind,
+            data: data.to_vec(),
+            parent_id: None,
+        };
+        vec![
+            event(0, TraceEventKind::NodeStarted, b""),
+            event(1, TraceEventKind::HostCall { function: "clock_now".to_string() }, clock),
+            event(2, TraceEventKind::ToolInvoked {
+                tool: "echo".to_string(),
+                schema_hash: None,
+            }, input),
+            event(3, TraceEventKind::NodeCompleted, input),
+        ]
+    }
+
+    #[test]
+    fn test_explain_divergence_tool_input() {
+        let engine = DiffEngine::new();
+        let node_id = NodeId::new();
+        let left = trace(node_id, b"hello", b"1");
+        let right = trace(node_id, b"world", b"1");
+
+        let report = engine.explain_divergence(&left, &right).unwrap().unwrap();
+        assert_eq!(report.index, 2);
+        assert_eq!(report.node_id, Some(node_id));
+        assert_eq!(
+            report.cause,
+            DivergenceCause::ToolInput {
+                tool: "echo".to_string(),
+                left: b"hello".to_vec(),
+                right: b"world".to_vec(),
+            }
+        );
+        assert!(report.cause.to_string().contains("tool echo input differs"));
+        assert_eq!(report.tool.as_deref(), Some("echo"));
+        assert_eq!(report.left_input_hash, Some(Hash::compute(b"hello")));
+        assert_eq!(report.right_input_hash, Some(Hash::compute(b"world")));
+        assert_eq!(report.left_state, report.right_state);
+        assert_eq!(report.left_state.total_nodes(), 1);
+    }
+
+    #[test]
+    fn test_explain_divergence_host_call_and_truncation() {
+        let engine = DiffEngine::new();
+        let node_id = NodeId::new();
+
+        let left = trace(node_id, b"x", b"1");
+        let right = trace(node_id, b"x", b"2");
+        let report = engine.explain_divergence(&left, &right).unwrap().unwrap();
+        assert!(matches!(report.cause, DivergenceCause::HostCallResult { .. }));
+
+        let report = engine.explain_divergence(&left, &left[..3]).unwrap().unwrap();
+        assert_eq!(
+            report.cause,
+            DivergenceCause::Truncated {
+                left_len: 4,
+                right_len: 3,
+            }
+        );
+
+        assert!(engine.explain_divergence(&left, &left.clone()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_diff_traces() {
+        let engine = DiffEngine::new();
+        let node_id = NodeId::new();
+        let left = trace(node_id, b"hello", b"1");
+        let right = trace(node_id, b"world", b"1");
+
+        let diff = engine.diff_traces(&left, &right).unwrap();
+        assert!(!diff.is_identical());
+        assert_eq!(diff.events.iter().map(|e| e.index).collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(diff.divergence.as_ref().unwrap().index, 2);
+        assert_eq!(diff.state.node_changes[0].change_type, NodeChangeType::OutputChanged);
+
+        let text = diff.to_text();
+        assert!(text.starts_with("--- left\n+++ right\n@@ -2 +2 @@\n- t=2 "));
+        assert!(text.contains("\n@@ state @@\n~ node "));
+        assert!(text.contains("\n-   hello\n+   world\n"));
+
+        assert!(engine.diff_traces(&left, &left.clone()).unwrap().is_identical());
+    }
+
+    #[test]
+    fn test_diff_traces_aligns_inserted_events() {
+        let engine = DiffEngine::new();
+        let node_id = NodeId::new();
+        let left = trace(node_id, b"hello", b"1");
+        let mut right = left.clone();
+        let mut extra = right[1].clone();
+        extra.data = b"2".to_vec();
+        right.insert(1, extra);
+
+        let diff = engine.diff_traces(&left, &right).unwrap();
+        assert_eq!(diff.events.len(), 1);
+        let inserted = &diff.events[0];
+        assert_eq!((inserted.index, inserted.right_index), (1, 1));
+        assert!(inserted.left.is_none());
+        assert_eq!(inserted.right.as_ref().unwrap().data, b"2");
+        assert_eq!(diff.divergence.as_ref().unwrap().index, 1);
+
+        // Only the events between matches are reported
+        right.remove(3);
+        let diff = engine.diff_traces(&left, &right).unwrap();
+        let positions: Vec<_> = diff.events.iter().map(|e| (e.index, e.right_index)).collect();
+        assert_eq!(positions, [(1, 1), (2, 3)]);
+        assert!(diff.events[1].right.is_none());
+    }
+
     #[test]
     fn test_is_semantically_equivalent() {
         let engine = DiffEngine::new();
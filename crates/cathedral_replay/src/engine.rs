@@ -1,10 +1,17 @@
 //! Replay engine for deterministic reconstruction.
 
-use cathedral_core::{CoreResult, CoreError, NodeId};
+use cathedral_core::{
+    Capability, CapabilitySet, CoreResult, CoreError, EventId, Hash, LogicalTime, NodeId,
+};
+use cathedral_log::HashChain;
+use cathedral_wasm::{HostCallRecord, ReplayHostRegistry};
+use crate::diff::{DiffEngine, DivergenceReport};
 use crate::trace::{TraceReader, TraceEvent};
 use crate::state::{ReconstructedState, NodeState};
 use crate::snapshot::SnapshotLoader;
+use crate::spill::SpillStore;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Replay engine configuration
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -17,6 +24,16 @@ pub struct ReplayConfig {
     pub max_events: usize,
     /// Enable snapshot loading
     pub enable_snapshots: bool,
+    /// Resume from the latest checkpoint at or before this logical time
+    #[serde(default)]
+    pub start_from_snapshot: Option<LogicalTime>,
+    /// Bytes of node output kept in memory before spilling to disk (0 = unlimited)
+    #[serde(default)]
+    pub memory_budget: usize,
+    /// Content hash of the host ABI the trace must have been recorded
+    /// against (None = not checked)
+    #[serde(default)]
+    pub abi_hash: Option<Hash>,
 }
 
 impl Default for ReplayConfig {
@@ -26,6 +43,9 @@ impl Default for ReplayConfig {
             validate_hash_chain: true,
             max_events: 0,
             enable_snapshots: true,
+            start_from_snapshot: None,
+            memory_budget: 0,
+            abi_hash: None,
         }
     }
 }
@@ -43,6 +63,8 @@ pub enum ReplayEngineError {
     CorruptedTrace { reason: String },
     /// Validation failed
     ValidationFailed { reason: String },
+    /// Trace was recorded against a different host ABI
+    AbiMismatch { expected: Hash, recorded: Hash },
 }
 
 impl std::fmt::Display for ReplayEngineError {
@@ -55,6 +77,12 @@ impl std::fmt::Display for ReplayEngineError {
             Self::MissingSnapshot { id } => write!(f, "Missing snapshot: {}", id),
             Self::CorruptedTrace { reason } => write!(f, "Corrupted trace: {}", reason),
             Self::ValidationFailed { reason } => write!(f, "Validation failed: {}", reason),
+            Self::AbiMismatch { expected, recorded } => write!(
+                f,
+                "Trace recorded against ABI {}, expected {}",
+                recorded.to_hex(),
+                expected.to_hex()
+            ),
         }
     }
 }
@@ -70,10 +98,30 @@ impl From<ReplayEngineError> for CoreError {
     }
 }
 
+/// A recorded operation that a reduced capability set would deny
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeniedOperation {
+    /// Position of the capability check in the trace
+    pub index: usize,
+    /// Event that performed the check
+    pub event_id: EventId,
+    /// Node that requested the capability
+    pub node_id: NodeId,
+    /// Logical time of the check
+    pub time: LogicalTime,
+    /// Capability the run needed
+    pub capability: Capability,
+}
+
 /// Replay engine for reconstructing execution from traces
 pub struct ReplayEngine {
     config: ReplayConfig,
     snapshot_loader: Option<SnapshotLoader>,
+    spill: Option<SpillStore>,
+    /// Side effects each node must replay, in the order applied
+    expected_effects: BTreeMap<NodeId, Vec<String>>,
+    /// Host calls each node made in the last replayed trace
+    host_calls: BTreeMap<NodeId, Vec<HostCallRecord>>,
 }
 
 impl ReplayEngine {
@@ -83,6 +131,9 @@ impl ReplayEngine {
         Self {
             config: ReplayConfig::default(),
             snapshot_loader: None,
+            spill: None,
+            expected_effects: BTreeMap::new(),
+            host_calls: BTreeMap::new(),
         }
     }
 
@@ -100,49 +151,143 @@ impl ReplayEngine {
         self
     }
 
+    /// Set the store that outputs are spilled to under a memory budget
+    ///
+    /// Without one, a temporary store is created on first spill.
+    #[must_use]
+    pub fn with_spill_store(mut self, spill: SpillStore) -> Self {
+        self.spill = Some(spill);
+        self
+    }
+
+    /// Check that replay reconstructs these side effects for each node
+    ///
+    /// Effects are described as in [`TraceEventKind::SideEffect`], e.g. the
+    /// effects an execution reported in its results.
+    ///
+    /// [`TraceEventKind::SideEffect`]: crate::trace::TraceEventKind::SideEffect
+    #[must_use]
+    pub fn with_expected_effects(mut self, effects: BTreeMap<NodeId, Vec<String>>) -> Self {
+        self.expected_effects = effects;
+        self
+    }
+
+    /// Store holding spilled outputs, needed to load them back
+    #[must_use]
+    pub fn spill_store(&self) -> Option<&SpillStore> {
+        self.spill.as_ref()
+    }
+
+    /// Serve the host calls `node_id` made in the last replayed trace
+    ///
+    /// Re-executing the node's module against a registry instrumented by
+    /// the returned [`ReplayHostRegistry`] answers each call with its
+    /// recorded result and flags any call that departs from the recording.
+    #[must_use]
+    pub fn host_registry(&self, node_id: NodeId) -> ReplayHostRegistry {
+        ReplayHostRegistry::new(self.host_calls.get(&node_id).cloned().unwrap_or_default())
+    }
+
     /// Replay a trace reader to reconstruct state
     ///
+    /// Events are pulled from the reader one at a time, so a streaming
+    /// reader combined with `memory_budget` replays in bounded memory.
+    /// Once the whole trace is replayed, each node's side effects are
+    /// checked against the expected ones.
+    ///
     /// # Errors
     ///
-    /// Returns error if replay fails
+    /// Returns error if replay fails or a node's replayed side effects
+    /// differ from those expected
     pub fn replay(&mut self, reader: &mut TraceReader) -> CoreResult<ReconstructedState> {
-        let mut state = ReconstructedState::new();
-
         if !reader.has_more() {
             return Err(ReplayEngineError::EmptyTrace.into());
         }
+        self.host_calls.clear();
 
-        let event_count = reader.total();
-        let max_events = if self.config.max_events > 0 {
-            self.config.max_events
-        } else {
-            event_count
+        let mut state = match self.config.start_from_snapshot {
+            Some(target) => self.resume(reader, target)?,
+            None => ReconstructedState::new(),
         };
 
-        for i in 0..max_events {
-            if !reader.has_more() {
-                break;
-            }
-
+        let mut processed = 0;
+        while reader.has_more()
+            && (self.config.max_events == 0 || processed < self.config.max_events)
+        {
             let event = reader.next_event()?;
             self.process_event(&mut state, &event)?;
+            self.enforce_budget(&mut state, &event)?;
+            processed += 1;
 
             if self.config.stop_on_error && state.has_errors() {
                 break;
             }
+        }
 
-            // Check progress
-            if i % 1000 == 0 && i > 0 {
-                // Progress reporting could go here
-                let _ = i;
+        if !reader.has_more() {
+            for (node_id, expected) in &self.expected_effects {
+                state.verify_side_effects(*node_id, expected)?;
             }
         }
-
         Ok(state)
     }
 
+    /// Restore the latest checkpoint at or before `target`
+    ///
+    /// The events the checkpoint covers are consumed from `reader` and their
+    /// hash chain must match the one the checkpoint recorded. Without a
+    /// snapshot loader or a suitable checkpoint, replay starts from scratch.
+    fn resume(
+        &self,
+        reader: &mut TraceReader,
+        target: LogicalTime,
+    ) -> CoreResult<ReconstructedState> {
+        let snapshot = match &self.snapshot_loader {
+            Some(loader) if self.config.enable_snapshots => loader.nearest_before(target),
+            _ => None,
+        };
+        let Some(snapshot) = snapshot else {
+            return Ok(ReconstructedState::new());
+        };
+
+        let mismatch = || ReplayEngineError::ValidationFailed {
+            reason: format!("snapshot {} does not match the trace", snapshot.metadata.id),
+        };
+        let mut chain = HashChain::new();
+        for _ in 0..snapshot.metadata.event_count {
+            if !reader.has_more() {
+                return Err(mismatch().into());
+            }
+            chain.push(reader.next_event()?.hash()?)?;
+        }
+        if chain.root() != snapshot.metadata.chain_root {
+            return Err(mismatch().into());
+        }
+
+        Ok(snapshot.state.clone())
+    }
+
+    /// Spill outputs once the state exceeds the memory budget
+    fn enforce_budget(
+        &mut self,
+        state: &mut ReconstructedState,
+        event: &TraceEvent,
+    ) -> CoreResult<()> {
+        let budget = self.config.memory_budget;
+        if budget == 0 || event.data.is_empty() || state.inline_bytes() <= budget {
+            return Ok(());
+        }
+        let spill = match self.spill.take() {
+            Some(spill) => spill,
+            None => SpillStore::temp()?,
+        };
+        let result = state.spill(&spill, budget);
+        self.spill = Some(spill);
+        result.map(|_| ())
+    }
+
     /// Process a single trace event
-    fn process_event(
+    pub(crate) fn process_event(
         &mut self,
         state: &mut ReconstructedState,
         event: &TraceEvent,
@@ -150,6 +295,15 @@ impl ReplayEngine {
         state.tick();
 
         match &event.kind {
+            crate::trace::TraceEventKind::RunHeader { abi_hash } => {
+                if let Some(expected) = self.config.abi_hash.filter(|hash| hash != abi_hash) {
+                    return Err(ReplayEngineError::AbiMismatch {
+                        expected,
+                        recorded: *abi_hash,
+                    }
+                    .into());
+                }
+            }
             crate::trace::TraceEventKind::NodeStarted => {
                 // Initialize node state
                 let node_state = NodeState::new(event.node_id);
@@ -159,7 +313,7 @@ impl ReplayEngine {
                 // Mark node as completed
                 if let Some(node_state) = state.node_outputs.get_mut(&event.node_id) {
                     node_state.completed = true;
-                    node_state.output = Some(event.data.clone());
+                    node_state.set_output(event.data.clone());
                 }
             }
             crate::trace::TraceEventKind::NodeFailed { exit_code } => {
@@ -179,7 +333,7 @@ impl ReplayEngine {
             crate::trace::TraceEventKind::OutputProduced => {
                 // Update node output
                 if let Some(node_state) = state.node_outputs.get_mut(&event.node_id) {
-                    node_state.output = Some(event.data.clone());
+                    node_state.set_output(event.data.clone());
                 }
             }
             crate::trace::TraceEventKind::SideEffect { effect } => {
@@ -199,6 +353,27 @@ impl ReplayEngine {
                     state.add_error(error);
                 }
             }
+            crate::trace::TraceEventKind::PolicyActivated { version, bundle_hash } => {
+                state.record_policy(crate::state::PolicyVersion {
+                    activated_at: event.time,
+                    version: *version,
+                    bundle_hash: *bundle_hash,
+                });
+            }
+            crate::trace::TraceEventKind::ToolInvoked { .. } => {
+                // A tool node records no start event of its own
+                if state.get_node_state(event.node_id).is_none() {
+                    state.add_node_state(event.node_id, NodeState::new(event.node_id));
+                }
+            }
+            crate::trace::TraceEventKind::HostCall { .. } => {
+                // Host results feed the node and its outcome is recorded by
+                // the completion events; the calls are kept so the node can
+                // be re-executed against them
+                if let Ok(record) = serde_json::from_slice::<HostCallRecord>(&event.data) {
+                    self.host_calls.entry(event.node_id).or_default().push(record);
+                }
+            }
             crate::trace::TraceEventKind::Snapshot => {
                 // Handle snapshot event
                 if let Some(loader) = &self.snapshot_loader {
@@ -235,6 +410,7 @@ impl ReplayEngine {
         while reader.has_more() {
             let event = reader.next_event()?;
             self.process_event(&mut state, &event)?;
+            self.enforce_budget(&mut state, &event)?;
             callback(&event, &state);
 
             if self.config.stop_on_error && state.has_errors() {
@@ -245,6 +421,86 @@ impl ReplayEngine {
         Ok(state)
     }
 
+    /// Replay a trace as if it had only been granted `reduced`
+    ///
+    /// Every capability check the original run passed is re-checked against
+    /// `reduced`. Returns the first operation that would now be denied, or
+    /// `None` if the run never needed anything outside `reduced`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if replay fails or a recorded capability cannot be parsed
+    pub fn replay_with_capabilities(
+        &mut self,
+        reader: &mut TraceReader,
+        reduced: &CapabilitySet,
+    ) -> CoreResult<Option<DeniedOperation>> {
+        let mut state = ReconstructedState::new();
+        let mut index = 0;
+
+        while reader.has_more() {
+            let event = reader.next_event()?;
+            if let crate::trace::TraceEventKind::CapabilityCheck { capability, allowed: true } =
+                &event.kind
+            {
+                let capability: Capability = capability.parse().map_err(|_| {
+                    ReplayEngineError::CorruptedTrace {
+                        reason: format!("unparseable capability: {}", capability),
+                    }
+                })?;
+                if !reduced.covers(&capability) {
+                    return Ok(Some(DeniedOperation {
+                        index,
+                        event_id: event.id,
+                        node_id: event.node_id,
+                        time: event.time,
+                        capability,
+                    }));
+                }
+            }
+            self.process_event(&mut state, &event)?;
+            self.enforce_budget(&mut state, &event)?;
+            index += 1;
+        }
+
+        Ok(None)
+    }
+
+    /// Replay two traces in lockstep and localize their first state divergence
+    ///
+    /// Both states are hashed after every event, and the first index at
+    /// which the hashes differ is reported with its node, the last tool that
+    /// node invoked, and that tool's input hash in each run. An event that
+    /// differs without changing state, such as a tool input, surfaces at the
+    /// later event whose effect it changes. Node errors do not stop the
+    /// replay. Returns `None` if the states never differ.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if replaying either trace fails
+    pub fn bisect_divergence(
+        &mut self,
+        left: &[TraceEvent],
+        right: &[TraceEvent],
+    ) -> CoreResult<Option<DivergenceReport>> {
+        let mut left_state = ReconstructedState::new();
+        let mut right_state = ReconstructedState::new();
+
+        for index in 0..left.len().max(right.len()) {
+            if let Some(event) = left.get(index) {
+                self.process_event(&mut left_state, event)?;
+            }
+            if let Some(event) = right.get(index) {
+                self.process_event(&mut right_state, event)?;
+            }
+            if left_state.state_hash()? != right_state.state_hash()? {
+                return DiffEngine::report_at(left, right, index).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Verify that two traces produce the same state
     ///
     /// # Errors
@@ -348,6 +604,68 @@ mod tests {
         assert_eq!(node_state.output, Some(b"output".to_vec()));
     }
 
+    #[test]
+    fn test_replay_checks_logged_side_effects() {
+        use cathedral_log::{Event, EventKind};
+
+        let (run_id, node_id) = (cathedral_core::RunId::new(), NodeId::new());
+        let log = [
+            (EventKind::ToolInvoked, &b""[..]),
+            (EventKind::SideEffect, b"Write file: ./out.txt"),
+            (EventKind::ToolCompleted, b"done"),
+        ]
+        .map(|(kind, payload)| {
+            Event::new(EventId::new(), run_id, node_id, LogicalTime::zero(), kind)
+                .with_payload(payload.to_vec())
+        });
+        let trace: Vec<TraceEvent> = log.iter().filter_map(TraceEvent::from_log).collect();
+        assert_eq!(
+            trace[1].kind,
+            TraceEventKind::SideEffect { effect: "Write file: ./out.txt".to_string() }
+        );
+
+        let replay = |expected: &[&str]| {
+            let effects = [(node_id, expected.iter().map(ToString::to_string).collect())];
+            ReplayEngine::new()
+                .with_expected_effects(effects.into_iter().collect())
+                .replay(&mut TraceReader::from_events(trace.clone()))
+        };
+        assert!(replay(&["Write file: ./out.txt"]).is_ok());
+        assert!(replay(&[]).is_err());
+        assert!(replay(&["Write file: ./other.txt"]).is_err());
+    }
+
+    #[test]
+    fn test_replay_verifies_side_effects() {
+        let mut engine = ReplayEngine::new();
+        let node_id = NodeId::new();
+
+        let event = |kind| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::zero(),
+            node_id,
+            kind,
+            data: Vec::new(),
+            parent_id: None,
+        };
+        let events = vec![
+            event(TraceEventKind::NodeStarted),
+            event(TraceEventKind::SideEffect {
+                effect: "Write file: ./out.txt".to_string(),
+            }),
+            event(TraceEventKind::NodeCompleted),
+        ];
+
+        let mut reader = TraceReader::from_events(events);
+        let state = engine.replay(&mut reader).unwrap();
+
+        assert!(state
+            .verify_side_effects(node_id, &["Write file: ./out.txt".to_string()])
+            .is_ok());
+        assert!(state.verify_side_effects(node_id, &[]).is_err());
+        assert!(state.verify_side_effects(NodeId::new(), &[]).is_err());
+    }
+
     #[test]
     fn test_replay_node_failed() {
         let config = ReplayConfig {
@@ -373,6 +691,288 @@ mod tests {
         assert!(state.has_errors());
     }
 
+    #[test]
+    fn test_replay_policy_versions() {
+        let node_id = NodeId::new();
+        let event = |time, kind| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(time),
+            node_id,
+            kind,
+            data: Vec::new(),
+            parent_id: None,
+        };
+        let activated = |time, version: u64| {
+            event(time, TraceEventKind::PolicyActivated {
+                version,
+                bundle_hash: cathedral_core::Hash::compute(&version.to_be_bytes()),
+            })
+        };
+        let events = vec![
+            activated(0, 1),
+            event(1, TraceEventKind::NodeStarted),
+            activated(3, 2),
+            event(4, TraceEventKind::NodeCompleted),
+        ];
+
+        let mut engine = ReplayEngine::new();
+        let state = engine.replay(&mut TraceReader::from_events(events)).unwrap();
+        let version_at = |time| {
+            state
+                .active_policy_at(LogicalTime::from_raw(time))
+                .map(|v| v.version)
+        };
+        assert_eq!(version_at(0), Some(1));
+        assert_eq!(version_at(2), Some(1));
+        assert_eq!(version_at(3), Some(2));
+        assert_eq!(version_at(100), Some(2));
+        assert_eq!(
+            state.active_policy_at(LogicalTime::from_raw(3)).unwrap().bundle_hash,
+            cathedral_core::Hash::compute(&2u64.to_be_bytes())
+        );
+    }
+
+    #[test]
+    fn test_replay_with_reduced_capabilities() {
+        let node_id = NodeId::new();
+        let event = |time, kind| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(time),
+            node_id,
+            kind,
+            data: Vec::new(),
+            parent_id: None,
+        };
+        let check = |time, capability: &str| {
+            event(time, TraceEventKind::CapabilityCheck {
+                capability: capability.to_string(),
+                allowed: true,
+            })
+        };
+        let events = vec![
+            event(0, TraceEventKind::NodeStarted),
+            check(1, "NetRead(api.example.com)"),
+            check(2, "NetWrite(api.example.com)"),
+            check(3, "NetWrite(api.example.com)"),
+            event(4, TraceEventKind::NodeCompleted),
+        ];
+        let net_write = events[2].id;
+
+        let mut full = CapabilitySet::new();
+        full.grant(Capability::NetRead {
+            allowlist: vec!["api.example.com".to_string()],
+        });
+        let mut reduced = full.clone();
+        full.grant(Capability::NetWrite {
+            allowlist: vec!["*.example.com".to_string()],
+        });
+
+        let mut engine = ReplayEngine::new();
+        let mut reader = TraceReader::from_events(events.clone());
+        assert_eq!(engine.replay_with_capabilities(&mut reader, &full).unwrap(), None);
+
+        let mut reader = TraceReader::from_events(events);
+        let denied = engine
+            .replay_with_capabilities(&mut reader, &reduced)
+            .unwrap()
+            .unwrap();
+        assert_eq!(denied.index, 2);
+        assert_eq!(denied.event_id, net_write);
+        assert_eq!(denied.time, LogicalTime::from_raw(2));
+        assert_eq!(
+            denied.capability,
+            Capability::NetWrite {
+                allowlist: vec!["api.example.com".to_string()],
+            }
+        );
+
+        // NetWrite is the only extra grant the run needed
+        reduced.grant(Capability::NetWrite {
+            allowlist: vec!["api.example.com".to_string()],
+        });
+        let mut reader = TraceReader::from_events(vec![check(0, "NetWrite(api.example.com)")]);
+        assert_eq!(engine.replay_with_capabilities(&mut reader, &reduced).unwrap(), None);
+    }
+
+    #[test]
+    fn test_bisect_divergence() {
+        use crate::diff::DivergenceCause;
+
+        let node_id = NodeId::new();
+        let trace = |input: &[u8], output: &[u8]| {
+            let event = |time, kind, data: &[u8]| TraceEvent {
+                id: EventId::new(),
+                time: LogicalTime::from_raw(time),
+                node_id,
+                kind,
+                data: data.to_vec(),
+                parent_id: None,
+            };
+            vec![
+                event(0, TraceEventKind::NodeStarted, b""),
+                event(1, TraceEventKind::ToolInvoked {
+                    tool: "echo".to_string(),
+                    schema_hash: None,
+                }, input),
+                event(2, TraceEventKind::HostCall { function: "clock_now".to_string() }, b"1"),
+                event(3, TraceEventKind::NodeCompleted, output),
+            ]
+        };
+        let left = trace(b"hello", b"hello");
+        let right = trace(b"world", b"world");
+
+        let mut engine = ReplayEngine::new();
+        assert!(engine.bisect_divergence(&left, &left).unwrap().is_none());
+
+        // The differing input leaves state untouched until the node completes
+        let report = engine.bisect_divergence(&left, &right).unwrap().unwrap();
+        assert_eq!(report.index, 3);
+        assert_eq!(report.node_id, Some(node_id));
+        assert!(matches!(report.cause, DivergenceCause::Data { .. }));
+        assert_eq!(report.tool.as_deref(), Some("echo"));
+        assert_eq!(report.left_input_hash, Some(cathedral_core::Hash::compute(b"hello")));
+        assert_eq!(report.right_input_hash, Some(cathedral_core::Hash::compute(b"world")));
+        assert_eq!(report.left_state, report.right_state);
+
+        let report = engine.bisect_divergence(&left, &left[..2]).unwrap().unwrap();
+        assert_eq!(report.index, 2);
+        assert!(matches!(report.cause, DivergenceCause::Truncated { .. }));
+    }
+
+    #[test]
+    fn test_replay_resumes_from_checkpoint() {
+        use crate::snapshot::{Snapshot, SnapshotWriter};
+        use cathedral_storage::snapshot::SnapshotStore;
+        use cathedral_storage::ContentStore;
+        use std::sync::Arc;
+
+        let node_id = NodeId::new();
+        let events: Vec<TraceEvent> = (0..6)
+            .map(|time| TraceEvent {
+                id: EventId::new(),
+                time: LogicalTime::from_raw(time),
+                node_id,
+                kind: match time {
+                    0 => TraceEventKind::NodeStarted,
+                    5 => TraceEventKind::NodeCompleted,
+                    _ => TraceEventKind::SideEffect {
+                        effect: format!("step {}", time),
+                    },
+                },
+                data: Vec::new(),
+                parent_id: None,
+            })
+            .collect();
+        let full = ReplayEngine::new()
+            .replay(&mut TraceReader::from_events(events.clone()))
+            .unwrap();
+
+        // Checkpoints after two and four events, kept in storage
+        let checkpoint = |id: &str, count: usize| {
+            let state = ReplayEngine::new()
+                .replay(&mut TraceReader::from_events(events[..count].to_vec()))
+                .unwrap();
+            Snapshot::checkpoint(id.to_string(), state, &events[..count]).unwrap()
+        };
+        let mut store = SnapshotStore::new(Arc::new(ContentStore::new()));
+        let mut loader = SnapshotLoader::new();
+        for snapshot in [checkpoint("early", 2), checkpoint("late", 4)] {
+            let id = SnapshotWriter::new().write_to_store(&mut store, &snapshot).unwrap();
+            loader.load_from_store(&store, &id).unwrap();
+        }
+        assert_eq!(loader.nearest_before(LogicalTime::from_raw(2)).unwrap().metadata.id, "early");
+        assert_eq!(loader.nearest_before(LogicalTime::from_raw(9)).unwrap().metadata.id, "late");
+        assert!(loader.nearest_before(LogicalTime::zero()).is_none());
+
+        let config = ReplayConfig {
+            start_from_snapshot: Some(LogicalTime::from_raw(3)),
+            ..Default::default()
+        };
+        let mut engine = ReplayEngine::new()
+            .with_config(config)
+            .with_snapshot_loader(loader);
+        let mut reader = TraceReader::from_events(events.clone());
+        assert_eq!(engine.replay(&mut reader).unwrap(), full);
+
+        // A trace whose prefix differs from the checkpoint is rejected
+        let mut tampered = events;
+        tampered[0].data = b"x".to_vec();
+        assert!(engine.replay(&mut TraceReader::from_events(tampered)).is_err());
+    }
+
+    #[test]
+    fn test_streaming_replay_spills_outputs() {
+        let nodes: Vec<NodeId> = (0..4).map(|_| NodeId::new()).collect();
+        let events: Vec<TraceEvent> = nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &node_id)| {
+                let event = |kind, data: Vec<u8>| TraceEvent {
+                    id: EventId::new(),
+                    time: LogicalTime::from_raw(i as u64),
+                    node_id,
+                    kind,
+                    data,
+                    parent_id: None,
+                };
+                [
+                    event(TraceEventKind::NodeStarted, Vec::new()),
+                    event(TraceEventKind::NodeCompleted, vec![i as u8; 100 * (i + 1)]),
+                ]
+            })
+            .collect();
+        let full = ReplayEngine::new()
+            .replay(&mut TraceReader::from_events(events.clone()))
+            .unwrap();
+
+        let config = ReplayConfig {
+            memory_budget: 250,
+            ..Default::default()
+        };
+        let mut engine = ReplayEngine::new().with_config(config);
+        let mut reader = TraceReader::from_source(events.into_iter().map(Ok));
+        let state = engine.replay(&mut reader).unwrap();
+
+        assert!(state.inline_bytes() <= 250);
+        assert_eq!(state.completed_count(), 4);
+        let spill = engine.spill_store().unwrap();
+        for node_id in &nodes {
+            let node = state.get_node_state(*node_id).unwrap();
+            let expected = full.get_node_state(*node_id).unwrap().output.clone();
+            assert_eq!(node.load_output(spill).unwrap(), expected);
+        }
+        // The largest outputs went to disk first
+        assert!(state.get_node_state(nodes[3]).unwrap().spilled_output.is_some());
+        assert!(state.get_node_state(nodes[0]).unwrap().output.is_some());
+    }
+
+    #[test]
+    fn test_replay_checks_abi_hash() {
+        let recorded = Hash::compute(b"abi v1");
+        let trace = vec![TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::zero(),
+            node_id: NodeId::new(),
+            kind: TraceEventKind::RunHeader { abi_hash: recorded },
+            data: Vec::new(),
+            parent_id: None,
+        }];
+        let replay = |abi_hash| {
+            let config = ReplayConfig {
+                abi_hash,
+                ..Default::default()
+            };
+            ReplayEngine::new()
+                .with_config(config)
+                .replay(&mut TraceReader::from_events(trace.clone()))
+        };
+
+        assert!(replay(None).is_ok());
+        assert!(replay(Some(recorded)).is_ok());
+        let err = replay(Some(Hash::compute(b"abi v2"))).unwrap_err();
+        assert!(err.to_string().contains(&recorded.to_hex()));
+    }
+
     #[test]
     fn test_replay_error_display() {
         let err = ReplayEngineError::EmptyTrace;
@@ -410,4 +1010,37 @@ mod tests {
         let state = engine.replay(&mut reader).unwrap();
         assert_eq!(state.total_nodes(), 1); // Only first event processed
     }
+
+    #[tokio::test]
+    async fn test_replay_serves_recorded_host_calls() {
+        use cathedral_core::RunId;
+        use cathedral_wasm::abi::AbiValue;
+        use cathedral_wasm::{HostContext, HostRegistry};
+
+        let run_id = RunId::new();
+        let node_id = NodeId::new();
+        let record = HostCallRecord {
+            sequence: 0,
+            function: "clock_read".to_string(),
+            args: vec![AbiValue::I64(0)],
+            result: Ok(AbiValue::I64(42)),
+            timestamp: 42,
+        };
+        let event = record.to_event(run_id, node_id).unwrap();
+        let trace = vec![TraceEvent::from_log(&event).unwrap()];
+
+        let mut engine = ReplayEngine::new();
+        engine.replay(&mut TraceReader::from_events(trace)).unwrap();
+        assert_eq!(engine.host_registry(NodeId::new()).remaining(), 0);
+
+        // The live clock reads 0, the recording answers 42
+        let replay = engine.host_registry(node_id);
+        let registry = replay
+            .instrument(&HostRegistry::with_standard_functions().await)
+            .await;
+        let clock = registry.get("clock_read").await.unwrap();
+        let mut ctx = HostContext::new().with_capabilities(vec![Capability::ClockRead]);
+        assert_eq!(clock.call(&[AbiValue::I64(0)], &mut ctx).unwrap(), AbiValue::I64(42));
+        replay.verify().unwrap();
+    }
 }
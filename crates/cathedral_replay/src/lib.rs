@@ -11,9 +11,25 @@ pub mod diff;
 pub mod state;
 pub mod trace;
 pub mod snapshot;
+pub mod spill;
+pub mod bundle;
+pub mod provenance;
+pub mod debugger;
 
-pub use engine::{ReplayEngine, ReplayConfig, ReplayEngineError};
-pub use diff::{DiffEngine, DiffResult, DiffReport};
-pub use state::{ReconstructedState, StateDiff, ReplayError as StateReplayError};
-pub use trace::{TraceReader, TraceEvent};
-pub use snapshot::{SnapshotLoader, SnapshotError};
+pub use engine::{DeniedOperation, ReplayEngine, ReplayConfig, ReplayEngineError};
+pub use diff::{
+    CapabilityDiff, DiffEngine, DiffReport, DiffResult, DivergenceCause, DivergenceReport,
+    EventDiff, TraceDiff,
+};
+pub use state::{PolicyVersion, ReconstructedState, StateDiff, ReplayError as StateReplayError};
+pub use trace::{
+    build_graph, CapabilityDecision, ExecutionGraph, GraphEdge, GraphNode, TraceEvent,
+    TraceReader,
+};
+pub use snapshot::{Snapshot, SnapshotLoader, SnapshotError, SnapshotWriter};
+pub use spill::SpillStore;
+pub use bundle::{
+    BundleBuilder, BundleEntry, BundleError, BundleManifest, BundleReader, EntryKind,
+};
+pub use debugger::{Breakpoint, Debugger, KeyChange, Step};
+pub use provenance::{HostCallUse, Provenance, ProvenanceNode, ProvenanceTarget, ToolUse};
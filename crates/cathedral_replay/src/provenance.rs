@@ -0,0 +1,568 @@
+//! Provenance reconstruction from an execution graph.
+//!
+//! Traces a node or an output blob back through the graph's event edges to
+//! every node that contributed to it, with the tools, host calls, policy and
+//! capabilities each of them used.
+
+use crate::state::PolicyVersion;
+use crate::trace::{CapabilityDecision, ExecutionGraph, TraceEventKind};
+use cathedral_core::{CoreError, CoreResult, Hash, LogicalTime, NodeId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Node or output blob to trace back from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProvenanceTarget {
+    /// A node
+    Node(NodeId),
+    /// An output blob, by content hash
+    Output(Hash),
+}
+
+impl std::fmt::Display for ProvenanceTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Node(node_id) => write!(f, "{}", node_id),
+            Self::Output(hash) => write!(f, "{}", hash.to_hex()),
+        }
+    }
+}
+
+/// Everything a node or output was derived from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// What was traced
+    pub target: ProvenanceTarget,
+    /// Node that produced the target, with its upstream nodes
+    pub root: ProvenanceNode,
+}
+
+/// Node in a provenance tree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceNode {
+    /// Node ID
+    pub node_id: NodeId,
+    /// Hash of the node's output, if it produced one
+    pub output_hash: Option<Hash>,
+    /// Tools the node invoked, in trace order
+    pub tools: Vec<ToolUse>,
+    /// Host function results the node received, in trace order
+    pub host_calls: Vec<HostCallUse>,
+    /// Policy bundle active when the node started, if any
+    pub policy: Option<PolicyVersion>,
+    /// Capability decisions made for the node, in trace order
+    pub capabilities: Vec<CapabilityDecision>,
+    /// Nodes this node consumed events from, sorted
+    pub upstream: Vec<ProvenanceNode>,
+    /// Whether the node is expanded elsewhere in the tree, in which case its
+    /// details and upstream are left out here
+    pub repeated: bool,
+}
+
+/// Tool invocation recorded for a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolUse {
+    /// Tool name, empty if the trace did not record it
+    pub tool: String,
+    /// Hash of the tool version's schema, if recorded
+    pub schema_hash: Option<Hash>,
+    /// Hash of the tool input
+    pub input_hash: Hash,
+    /// Logical time of the invocation
+    pub time: LogicalTime,
+}
+
+/// Host function result recorded for a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostCallUse {
+    /// Host function name
+    pub function: String,
+    /// Hash of the result returned
+    pub result_hash: Hash,
+    /// Logical time of the call
+    pub time: LogicalTime,
+}
+
+impl ExecutionGraph {
+    /// Resolve a node ID or output hash, or a unique prefix of one
+    ///
+    /// Node IDs may be given with or without the `node_` prefix; output
+    /// hashes are matched against every output any node produced.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if nothing matches or the prefix is ambiguous
+    pub fn resolve(&self, target: &str) -> CoreResult<ProvenanceTarget> {
+        let target = target.trim().to_lowercase();
+        let node_prefix = if target.starts_with("node_") {
+            target.clone()
+        } else {
+            format!("node_{}", target)
+        };
+
+        let mut matches: Vec<ProvenanceTarget> = self
+            .nodes
+            .keys()
+            .filter(|node_id| node_id.to_string().starts_with(&node_prefix))
+            .map(|node_id| ProvenanceTarget::Node(*node_id))
+            .collect();
+        for hash in self.outputs() {
+            let output = ProvenanceTarget::Output(hash);
+            if hash.to_hex().starts_with(&target) && !matches.contains(&output) {
+                matches.push(output);
+            }
+        }
+
+        match matches.as_slice() {
+            [found] if !target.is_empty() => Ok(*found),
+            [] => Err(CoreError::NotFound {
+                kind: "provenance target".to_string(),
+                id: target,
+            }),
+            _ => Err(CoreError::Validation {
+                field: "target".to_string(),
+                reason: format!("{} matches {} nodes or outputs", target, matches.len()),
+            }),
+        }
+    }
+
+    /// Node that produced an output, the first one if several did
+    #[must_use]
+    pub fn producer(&self, output: Hash) -> Option<NodeId> {
+        self.nodes
+            .values()
+            .find(|node| {
+                node.events
+                    .iter()
+                    .any(|event| is_output(&event.kind) && Hash::compute(&event.data) == output)
+            })
+            .map(|node| node.node_id)
+    }
+
+    /// Policy bundle active at a logical time
+    #[must_use]
+    pub fn policy_at(&self, time: LogicalTime) -> Option<&PolicyVersion> {
+        self.policy_activations
+            .iter()
+            .filter(|activation| activation.activated_at <= time)
+            .max_by_key(|activation| (activation.activated_at, activation.version))
+    }
+
+    /// Trace a node or output back to everything it was derived from
+    ///
+    /// Upstream nodes are followed through the graph's edges, which follow
+    /// the workflow's dependencies once given with
+    /// [`ExecutionGraph::with_dependencies`]. A node reached along more than
+    /// one path is expanded the first time and marked `repeated` afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the node or output is not in the graph
+    pub fn provenance(&self, target: ProvenanceTarget) -> CoreResult<Provenance> {
+        let (node_id, output_hash) = match target {
+            ProvenanceTarget::Node(node_id) if self.nodes.contains_key(&node_id) => {
+                (node_id, self.nodes[&node_id].output_hash)
+            }
+            ProvenanceTarget::Output(hash) => match self.producer(hash) {
+                Some(node_id) => (node_id, Some(hash)),
+                None => {
+                    return Err(CoreError::NotFound {
+                        kind: "output".to_string(),
+                        id: hash.to_hex(),
+                    });
+                }
+            },
+            ProvenanceTarget::Node(node_id) => {
+                return Err(CoreError::NotFound {
+                    kind: "node".to_string(),
+                    id: node_id.to_string(),
+                });
+            }
+        };
+
+        let mut root = self.provenance_tree(node_id).ok_or_else(|| CoreError::NotFound {
+            kind: "node".to_string(),
+            id: node_id.to_string(),
+        })?;
+        root.output_hash = output_hash;
+        Ok(Provenance { target, root })
+    }
+
+    /// Build the provenance tree rooted at a node
+    ///
+    /// Nodes are expanded depth-first in upstream order without recursion,
+    /// so a long chain of nodes cannot overflow the stack; each node's
+    /// upstream is attached once every node below it is built.
+    fn provenance_tree(&self, root: NodeId) -> Option<ProvenanceNode> {
+        let mut seen = HashSet::new();
+        let mut nodes: Vec<Option<ProvenanceNode>> = Vec::new();
+        let mut children: Vec<Vec<usize>> = Vec::new();
+        let mut pending = vec![(root, None)];
+        while let Some((node_id, parent)) = pending.pop() {
+            let index = nodes.len();
+            let entry = self.provenance_node(node_id, &mut seen);
+            if !entry.repeated {
+                let upstream = self.parents(node_id);
+                pending.extend(upstream.into_iter().rev().map(|up| (up, Some(index))));
+            }
+            nodes.push(Some(entry));
+            children.push(Vec::new());
+            if let Some(parent) = parent {
+                children[parent].push(index);
+            }
+        }
+
+        // Upstream nodes are always expanded after the node they feed
+        for index in (0..nodes.len()).rev() {
+            let upstream: Vec<ProvenanceNode> =
+                children[index].iter().filter_map(|&child| nodes[child].take()).collect();
+            if let Some(node) = &mut nodes[index] {
+                node.upstream = upstream;
+            }
+        }
+        nodes.into_iter().next().flatten()
+    }
+
+    /// Details of one node, without its upstream
+    fn provenance_node(&self, node_id: NodeId, seen: &mut HashSet<NodeId>) -> ProvenanceNode {
+        let node = &self.nodes[&node_id];
+        let mut entry = ProvenanceNode {
+            node_id,
+            output_hash: node.output_hash,
+            tools: Vec::new(),
+            host_calls: Vec::new(),
+            policy: None,
+            capabilities: Vec::new(),
+            upstream: Vec::new(),
+            repeated: !seen.insert(node_id),
+        };
+        if entry.repeated {
+            return entry;
+        }
+
+        for event in &node.events {
+            match &event.kind {
+                TraceEventKind::ToolInvoked { tool, schema_hash } => entry.tools.push(ToolUse {
+                    tool: tool.clone(),
+                    schema_hash: *schema_hash,
+                    input_hash: Hash::compute(&event.data),
+                    time: event.time,
+                }),
+                TraceEventKind::HostCall { function } => entry.host_calls.push(HostCallUse {
+                    function: function.clone(),
+                    result_hash: Hash::compute(&event.data),
+                    time: event.time,
+                }),
+                _ => {}
+            }
+        }
+        entry.policy = node
+            .events
+            .first()
+            .and_then(|event| self.policy_at(event.time))
+            .cloned();
+        entry.capabilities = self
+            .capability_decisions
+            .iter()
+            .filter(|decision| decision.node_id == node_id)
+            .cloned()
+            .collect();
+        entry
+    }
+
+    /// Hashes of every output produced, in trace order without duplicates
+    fn outputs(&self) -> Vec<Hash> {
+        let mut outputs = Vec::new();
+        for event in self.nodes.values().flat_map(|node| &node.events) {
+            let hash = Hash::compute(&event.data);
+            if is_output(&event.kind) && !outputs.contains(&hash) {
+                outputs.push(hash);
+            }
+        }
+        outputs
+    }
+}
+
+impl Provenance {
+    /// Nodes with no upstream, the inputs the target ultimately derives from
+    #[must_use]
+    pub fn inputs(&self) -> Vec<NodeId> {
+        let mut inputs = Vec::new();
+        let mut pending = vec![&self.root];
+        while let Some(node) = pending.pop() {
+            if node.upstream.is_empty() && !node.repeated && !inputs.contains(&node.node_id) {
+                inputs.push(node.node_id);
+            }
+            pending.extend(node.upstream.iter().rev());
+        }
+        inputs
+    }
+
+    /// Render the tree as indented text, one detail per line
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("provenance of {}", self.target)];
+        let mut pending = vec![(&self.root, 1)];
+        while let Some((node, depth)) = pending.pop() {
+            let indent = "  ".repeat(depth);
+            lines.push(format!("{}{}", indent, node.label()));
+            for detail in node.details() {
+                lines.push(format!("{}  {}", indent, detail));
+            }
+            pending.extend(node.upstream.iter().rev().map(|up| (up, depth + 1)));
+        }
+        lines
+    }
+}
+
+impl ProvenanceNode {
+    /// Label the node as `node (output hash)`, marking repeated nodes
+    #[must_use]
+    pub fn label(&self) -> String {
+        let hash = self.output_hash.map_or_else(|| "-".to_string(), |h| h.to_hex());
+        if self.repeated {
+            format!("{} ({}) (see above)", self.node_id, hash)
+        } else {
+            format!("{} ({})", self.node_id, hash)
+        }
+    }
+
+    /// Describe the node's tools, host calls, policy and capabilities
+    #[must_use]
+    pub fn details(&self) -> Vec<String> {
+        let mut details = Vec::new();
+        for tool in &self.tools {
+            let name = if tool.tool.is_empty() { "tool" } else { tool.tool.as_str() };
+            let schema = tool.schema_hash.map_or_else(|| "-".to_string(), |h| h.to_hex());
+            details.push(format!(
+                "tool {} schema {} input {}",
+                name,
+                schema,
+                tool.input_hash.to_hex()
+            ));
+        }
+        for call in &self.host_calls {
+            details.push(format!("host {} result {}", call.function, call.result_hash.to_hex()));
+        }
+        if let Some(policy) = &self.policy {
+            details.push(format!(
+                "policy v{} bundle {}",
+                policy.version,
+                policy.bundle_hash.to_hex()
+            ));
+        }
+        for decision in &self.capabilities {
+            let outcome = if decision.allowed { "allowed" } else { "denied" };
+            details.push(format!("capability {} {}", decision.capability, outcome));
+        }
+        details
+    }
+}
+
+/// Whether an event of this kind carries a node output
+fn is_output(kind: &TraceEventKind) -> bool {
+    matches!(kind, TraceEventKind::NodeCompleted | TraceEventKind::OutputProduced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::{build_graph, TraceEvent};
+    use cathedral_core::EventId;
+
+    fn event(
+        node_id: NodeId,
+        time: u64,
+        kind: TraceEventKind,
+        data: &[u8],
+        parent_id: Option<EventId>,
+    ) -> TraceEvent {
+        TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(time),
+            node_id,
+            kind,
+            data: data.to_vec(),
+            parent_id,
+        }
+    }
+
+    /// Diamond `a -> (b, c) -> d` under one policy
+    fn diamond() -> (ExecutionGraph, [NodeId; 4]) {
+        let nodes = [NodeId::new(), NodeId::new(), NodeId::new(), NodeId::new()];
+        let [a, b, c, d] = nodes;
+        let policy = event(
+            a,
+            0,
+            TraceEventKind::PolicyActivated { version: 3, bundle_hash: Hash::compute(b"bundle") },
+            b"",
+            None,
+        );
+        let a_done = event(a, 1, TraceEventKind::NodeCompleted, b"a", None);
+        let b_tool = event(
+            b,
+            2,
+            TraceEventKind::ToolInvoked {
+                tool: "echo".to_string(),
+                schema_hash: Some(Hash::compute(b"schema")),
+            },
+            b"a",
+            Some(a_done.id),
+        );
+        let b_done = event(b, 3, TraceEventKind::NodeCompleted, b"b", Some(b_tool.id));
+        let c_check = event(
+            c,
+            2,
+            TraceEventKind::CapabilityCheck {
+                capability: "ClockRead".to_string(),
+                allowed: true,
+            },
+            b"",
+            Some(a_done.id),
+        );
+        let c_clock = event(
+            c,
+            3,
+            TraceEventKind::HostCall { function: "clock_now".to_string() },
+            b"42",
+            Some(c_check.id),
+        );
+        let c_done = event(c, 4, TraceEventKind::NodeCompleted, b"c", Some(c_clock.id));
+        let d_start = event(d, 5, TraceEventKind::NodeStarted, b"", Some(b_done.id));
+        let d_join = event(d, 6, TraceEventKind::OutputProduced, b"d", Some(c_done.id));
+
+        let events = vec![
+            policy, a_done, b_tool, b_done, c_check, c_clock, c_done, d_start, d_join,
+        ];
+        (build_graph(events), nodes)
+    }
+
+    #[test]
+    fn test_provenance_walks_back_to_inputs() {
+        let (graph, [a, b, c, d]) = diamond();
+        let provenance = graph.provenance(ProvenanceTarget::Node(d)).unwrap();
+
+        let root = &provenance.root;
+        assert_eq!(root.node_id, d);
+        assert_eq!(root.output_hash, Some(Hash::compute(b"d")));
+        let upstream: Vec<NodeId> = root.upstream.iter().map(|n| n.node_id).collect();
+        let mut expected = vec![b, c];
+        expected.sort();
+        assert_eq!(upstream, expected);
+        assert_eq!(provenance.inputs(), vec![a]);
+
+        let node = |id| root.upstream.iter().find(|n| n.node_id == id).unwrap();
+        let tool = &node(b).tools[0];
+        assert_eq!(tool.tool, "echo");
+        assert_eq!(tool.schema_hash, Some(Hash::compute(b"schema")));
+        assert_eq!(tool.input_hash, Hash::compute(b"a"));
+        assert_eq!(node(c).capabilities[0].capability, "ClockRead");
+        assert_eq!(node(c).host_calls[0].result_hash, Hash::compute(b"42"));
+        assert_eq!(node(c).policy.as_ref().map(|p| p.version), Some(3));
+
+        // `a` is reached through both `b` and `c` but expanded only once
+        let reached: Vec<&ProvenanceNode> =
+            root.upstream.iter().flat_map(|n| &n.upstream).collect();
+        assert_eq!(reached.len(), 2);
+        assert_eq!(reached.iter().filter(|n| n.repeated).count(), 1);
+        assert!(reached.iter().all(|n| n.node_id == a));
+    }
+
+    #[test]
+    fn test_provenance_follows_dependencies() {
+        // `a` and `b` are independent inputs of `c`, but `b` ran after `a`
+        // and its parent link points at `a`
+        let [a, b, c] = [NodeId::new(), NodeId::new(), NodeId::new()];
+        let a_done = event(a, 1, TraceEventKind::NodeCompleted, b"a", None);
+        let b_start = event(b, 2, TraceEventKind::NodeStarted, b"", Some(a_done.id));
+        let b_done = event(b, 3, TraceEventKind::NodeCompleted, b"b", Some(b_start.id));
+        let c_done = event(c, 4, TraceEventKind::NodeCompleted, b"c", Some(b_done.id));
+        let graph = build_graph(vec![a_done, b_start, b_done, c_done]);
+        assert_eq!(graph.parents(b), vec![a]);
+
+        let graph = graph.with_dependencies([(a, c), (b, c), (NodeId::new(), c)]);
+        assert!(graph.parents(b).is_empty());
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(graph.parents(c), expected);
+
+        let provenance = graph.provenance(ProvenanceTarget::Node(c)).unwrap();
+        let upstream: Vec<NodeId> = provenance.root.upstream.iter().map(|n| n.node_id).collect();
+        assert_eq!(upstream, expected);
+        assert!(provenance.root.upstream.iter().all(|n| n.upstream.is_empty()));
+    }
+
+    #[test]
+    fn test_provenance_of_long_chain() {
+        let nodes: Vec<NodeId> = (0..2_000).map(|_| NodeId::new()).collect();
+        let mut parent = None;
+        let mut events = Vec::new();
+        for (time, node) in nodes.iter().enumerate() {
+            let done = event(*node, time as u64, TraceEventKind::NodeCompleted, b"x", parent);
+            parent = Some(done.id);
+            events.push(done);
+        }
+        let graph = build_graph(events);
+        let last = *nodes.last().unwrap();
+        let provenance = graph.provenance(ProvenanceTarget::Node(last)).unwrap();
+
+        let mut depth = 0;
+        let mut node = &provenance.root;
+        while let Some(up) = node.upstream.first() {
+            assert_eq!(node.upstream.len(), 1);
+            node = up;
+            depth += 1;
+        }
+        assert_eq!(depth, nodes.len() - 1);
+        assert_eq!(provenance.inputs(), vec![nodes[0]]);
+    }
+
+    #[test]
+    fn test_provenance_of_output() {
+        let (graph, [_, b, _, _]) = diamond();
+        let output = Hash::compute(b"b");
+        let provenance = graph.provenance(ProvenanceTarget::Output(output)).unwrap();
+        assert_eq!(provenance.root.node_id, b);
+        assert_eq!(provenance.root.output_hash, Some(output));
+
+        let missing = graph.provenance(ProvenanceTarget::Output(Hash::compute(b"none")));
+        assert!(matches!(missing, Err(CoreError::NotFound { .. })));
+        let missing = graph.provenance(ProvenanceTarget::Node(NodeId::new()));
+        assert!(matches!(missing, Err(CoreError::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let (graph, [a, ..]) = diamond();
+        let id = a.to_string();
+        assert_eq!(graph.resolve(&id).unwrap(), ProvenanceTarget::Node(a));
+        assert_eq!(
+            graph.resolve(id.trim_start_matches("node_")).unwrap(),
+            ProvenanceTarget::Node(a)
+        );
+
+        let output = Hash::compute(b"c");
+        assert_eq!(
+            graph.resolve(&output.to_hex()[..16]).unwrap(),
+            ProvenanceTarget::Output(output)
+        );
+        assert!(matches!(graph.resolve("zz"), Err(CoreError::NotFound { .. })));
+        assert!(matches!(graph.resolve(""), Err(CoreError::Validation { .. })));
+    }
+
+    #[test]
+    fn test_provenance_lines_and_json() {
+        let (graph, [_, _, _, d]) = diamond();
+        let provenance = graph.provenance(ProvenanceTarget::Node(d)).unwrap();
+        let lines = provenance.lines();
+        assert_eq!(lines[0], format!("provenance of {}", d));
+        assert!(lines[1].starts_with(&format!("  {} (", d)));
+        assert!(lines.iter().any(|l| l.contains("tool echo schema")));
+        assert!(lines.iter().any(|l| l.contains("capability ClockRead allowed")));
+        assert!(lines.iter().any(|l| l.ends_with("(see above)")));
+
+        let json = serde_json::to_string(&provenance).unwrap();
+        let decoded: Provenance = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, provenance);
+    }
+}
@@ -1,9 +1,15 @@
 //! Snapshot loader for replay.
 
-use cathedral_core::{CoreResult, CoreError};
+use cathedral_core::{CoreResult, CoreError, Hash, LogicalTime};
+use cathedral_log::HashChain;
+use cathedral_storage::snapshot::SnapshotStore;
 use crate::state::ReconstructedState;
+use crate::trace::TraceEvent;
 use serde::{Deserialize, Serialize};
 
+/// Storage snapshot entry holding an encoded replay snapshot
+pub const SNAPSHOT_ENTRY_KEY: &str = "replay_snapshot";
+
 /// Snapshot error
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SnapshotError {
@@ -58,6 +64,15 @@ pub struct SnapshotMetadata {
     pub node_count: usize,
     /// Size in bytes
     pub size_bytes: usize,
+    /// Logical time of the last event the snapshot covers
+    #[serde(default = "LogicalTime::zero")]
+    pub logical_time: LogicalTime,
+    /// Number of trace events the snapshot covers
+    #[serde(default)]
+    pub event_count: usize,
+    /// Root of the hash chain over the covered events, if it is a checkpoint
+    #[serde(default)]
+    pub chain_root: Option<Hash>,
 }
 
 /// Snapshot containing state at a point in time
@@ -88,11 +103,34 @@ impl Snapshot {
                     .unwrap_or(0),
                 node_count,
                 size_bytes,
+                logical_time: LogicalTime::zero(),
+                event_count: 0,
+                chain_root: None,
             },
             state,
         }
     }
 
+    /// Create a checkpoint of the state reached by replaying `events`
+    ///
+    /// The checkpoint records the hash chain root of `events`, so a resumed
+    /// replay can verify that it is skipping exactly those events.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if an event cannot be hashed
+    pub fn checkpoint(
+        id: String,
+        state: ReconstructedState,
+        events: &[TraceEvent],
+    ) -> CoreResult<Self> {
+        let mut snapshot = Self::new(id, state);
+        snapshot.metadata.logical_time = events.last().map_or(LogicalTime::zero(), |e| e.time);
+        snapshot.metadata.event_count = events.len();
+        snapshot.metadata.chain_root = chain_root(events)?;
+        Ok(snapshot)
+    }
+
     /// Encode snapshot to bytes
     ///
     /// # Errors
@@ -154,6 +192,38 @@ impl SnapshotLoader {
             .ok_or_else(|| SnapshotError::NotFound { id: id.to_string() }.into())
     }
 
+    /// Load a snapshot from a storage snapshot and register it
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the storage snapshot is missing, has no replay
+    /// snapshot entry, or cannot be decoded
+    pub fn load_from_store(&mut self, store: &SnapshotStore, id: &str) -> CoreResult<()> {
+        let entries = store.restore(id)?;
+        let data = entries
+            .get(SNAPSHOT_ENTRY_KEY)
+            .ok_or_else(|| SnapshotError::NotFound { id: id.to_string() })?;
+        let snapshot = Snapshot::decode(data)?;
+        self.validate_version(&snapshot)?;
+        self.register(snapshot);
+        Ok(())
+    }
+
+    /// The latest cached checkpoint at or before logical time `at`
+    ///
+    /// Ties are broken by event count and then ID, so the choice does not
+    /// depend on cache order.
+    #[must_use]
+    pub fn nearest_before(&self, at: LogicalTime) -> Option<&Snapshot> {
+        self.cache
+            .values()
+            .filter(|s| s.metadata.chain_root.is_some() && s.metadata.logical_time <= at)
+            .max_by(|a, b| {
+                let key = |s: &Snapshot| (s.metadata.logical_time, s.metadata.event_count);
+                key(a).cmp(&key(b)).then_with(|| a.metadata.id.cmp(&b.metadata.id))
+            })
+    }
+
     /// Register a snapshot in the cache
     pub fn register(&mut self, snapshot: Snapshot) {
         self.cache.insert(snapshot.metadata.id.clone(), snapshot);
@@ -237,6 +307,33 @@ impl SnapshotWriter {
         })?;
         Ok(())
     }
+
+    /// Store a snapshot as a storage snapshot with the same ID
+    ///
+    /// # Errors
+    ///
+    /// Returns error if encoding or storing fails
+    pub fn write_to_store(
+        &self,
+        store: &mut SnapshotStore,
+        snapshot: &Snapshot,
+    ) -> CoreResult<String> {
+        let entries = [(SNAPSHOT_ENTRY_KEY.to_string(), snapshot.encode()?)];
+        store.snapshot_from(snapshot.metadata.id.clone(), entries.into_iter().collect())
+    }
+}
+
+/// Root of the hash chain over a sequence of trace events
+///
+/// # Errors
+///
+/// Returns error if an event cannot be hashed
+pub fn chain_root(events: &[TraceEvent]) -> CoreResult<Option<Hash>> {
+    let mut chain = HashChain::new();
+    for event in events {
+        chain.push(event.hash()?)?;
+    }
+    Ok(chain.root())
 }
 
 impl Default for SnapshotWriter {
@@ -0,0 +1,70 @@
+//! Spilling of large replay state to disk.
+//!
+//! When a replay runs under a memory budget, node outputs that push the
+//! reconstructed state over budget are moved into a [`SpillStore`], a
+//! content store on disk with no in-memory cache. The state keeps only
+//! their content addresses.
+
+use cathedral_core::{CoreError, CoreResult};
+use cathedral_storage::store::FsContentStore;
+use cathedral_storage::BlobId;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Disk-backed store for spilled node outputs
+pub struct SpillStore {
+    store: FsContentStore,
+    /// Temporary directory removed when the store is dropped
+    _dir: Option<TempDir>,
+}
+
+impl SpillStore {
+    /// Create a store in a fresh temporary directory, removed on drop
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory cannot be created
+    pub fn temp() -> CoreResult<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("cathedral-replay-")
+            .tempdir()
+            .map_err(|e| CoreError::Validation {
+                field: "spill_dir".to_string(),
+                reason: format!("Failed to create spill directory: {}", e),
+            })?;
+        let mut store = Self::in_dir(dir.path())?;
+        store._dir = Some(dir);
+        Ok(store)
+    }
+
+    /// Create a store in `dir`, which is left in place on drop
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory cannot be created
+    pub fn in_dir(dir: impl AsRef<Path>) -> CoreResult<Self> {
+        let dir = dir.as_ref().to_string_lossy().into_owned();
+        Ok(Self {
+            store: FsContentStore::new(dir)?.without_cache(),
+            _dir: None,
+        })
+    }
+
+    /// Write data to disk, returning its content address
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the write fails
+    pub fn write(&self, data: Vec<u8>) -> CoreResult<BlobId> {
+        self.store.write(data)
+    }
+
+    /// Read spilled data back
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the blob is missing or does not match its address
+    pub fn read(&self, id: &BlobId) -> CoreResult<Vec<u8>> {
+        Ok(self.store.read(id)?.as_bytes().to_vec())
+    }
+}
@@ -1,6 +1,8 @@
 //! Reconstructed state during replay.
 
-use cathedral_core::{NodeId, CoreResult, CoreError};
+use cathedral_core::{NodeId, CoreResult, CoreError, Hash, LogicalTime};
+use cathedral_storage::BlobId;
+use crate::spill::SpillStore;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
@@ -16,6 +18,20 @@ pub struct ReconstructedState {
     pub errors: Vec<ReplayError>,
     /// Current logical time
     pub time: u64,
+    /// Policy bundles activated during the run, in logical time order
+    #[serde(default)]
+    pub policy_versions: Vec<PolicyVersion>,
+}
+
+/// A policy bundle version and the logical tick it became active at
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PolicyVersion {
+    /// Logical tick of activation
+    pub activated_at: LogicalTime,
+    /// Bundle version
+    pub version: u64,
+    /// Bundle content hash
+    pub bundle_hash: Hash,
 }
 
 /// State of a single node
@@ -31,6 +47,9 @@ pub struct NodeState {
     pub error: Option<String>,
     /// Side effects performed
     pub side_effects: Vec<String>,
+    /// Address of the output if it was spilled to disk
+    #[serde(default)]
+    pub spilled_output: Option<BlobId>,
 }
 
 /// Error during replay
@@ -53,6 +72,7 @@ impl ReconstructedState {
             global_state: IndexMap::new(),
             errors: Vec::new(),
             time: 0,
+            policy_versions: Vec::new(),
         }
     }
 
@@ -78,11 +98,44 @@ impl ReconstructedState {
         self.global_state.get(key).map(|v| v.as_slice())
     }
 
+    /// Look up a state key
+    ///
+    /// A key is either a global state key or `<node id>.<field>`, where the
+    /// field is `output`, `error`, `completed` or `side_effects`. Spilled
+    /// outputs are not loaded back and read as absent.
+    #[must_use]
+    pub fn value(&self, key: &str) -> Option<Vec<u8>> {
+        if let Some(value) = self.global_state.get(key) {
+            return Some(value.clone());
+        }
+        let (node, field) = key.rsplit_once('.')?;
+        let state = self.node_outputs.values().find(|s| s.node_id.to_string() == node)?;
+        match field {
+            "output" => state.output.clone(),
+            "error" => state.error.clone().map(String::into_bytes),
+            "completed" => Some(state.completed.to_string().into_bytes()),
+            "side_effects" => Some(state.side_effects.join("\n").into_bytes()),
+            _ => None,
+        }
+    }
+
     /// Add an error
     pub fn add_error(&mut self, error: ReplayError) {
         self.errors.push(error);
     }
 
+    /// Record a policy activation
+    pub fn record_policy(&mut self, activation: PolicyVersion) {
+        let idx = self.policy_versions.partition_point(|v| *v <= activation);
+        self.policy_versions.insert(idx, activation);
+    }
+
+    /// The policy version active at logical tick `at`, if any
+    #[must_use]
+    pub fn active_policy_at(&self, at: LogicalTime) -> Option<&PolicyVersion> {
+        self.policy_versions.iter().rev().find(|v| v.activated_at <= at)
+    }
+
     /// Increment logical time
     pub fn tick(&mut self) {
         self.time += 1;
@@ -112,6 +165,86 @@ impl ReconstructedState {
         self.node_outputs.len()
     }
 
+    /// Bytes of node output held in memory
+    #[must_use]
+    pub fn inline_bytes(&self) -> usize {
+        self.node_outputs
+            .values()
+            .filter_map(|node| node.output.as_ref())
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// Move node outputs to `spill` until at most `budget` bytes stay inline
+    ///
+    /// The largest outputs are spilled first, ties in node order. Returns the
+    /// number of outputs spilled.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if writing to the spill store fails
+    pub fn spill(&mut self, spill: &SpillStore, budget: usize) -> CoreResult<usize> {
+        let mut inline = self.inline_bytes();
+        let mut candidates: Vec<(usize, usize)> = self
+            .node_outputs
+            .values()
+            .enumerate()
+            .filter_map(|(index, node)| node.output.as_ref().map(|o| (o.len(), index)))
+            .collect();
+        candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let mut spilled = 0;
+        for (len, index) in candidates {
+            if inline <= budget {
+                break;
+            }
+            let Some((_, node)) = self.node_outputs.get_index_mut(index) else {
+                continue;
+            };
+            if let Some(output) = node.output.take() {
+                node.spilled_output = Some(spill.write(output)?);
+                inline -= len;
+                spilled += 1;
+            }
+        }
+        Ok(spilled)
+    }
+
+    /// Content hash of the state, used to compare runs step by step
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn state_hash(&self) -> CoreResult<Hash> {
+        Ok(Hash::compute(&serde_json::to_vec(self)?))
+    }
+
+    /// Verify that a node performed exactly the expected side effects, in order
+    ///
+    /// Effects are compared by their recorded descriptions.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the node is unknown or its effects differ
+    pub fn verify_side_effects(&self, node_id: NodeId, expected: &[String]) -> CoreResult<()> {
+        let node = self.get_node_state(node_id).ok_or_else(|| CoreError::NotFound {
+            kind: "node".to_string(),
+            id: format!("{:?}", node_id),
+        })?;
+
+        if node.side_effects != expected {
+            return Err(CoreError::Validation {
+                field: "side_effects".to_string(),
+                reason: format!(
+                    "node {:?} replayed effects {:?}, expected {:?}",
+                    node_id, node.side_effects, expected
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
     /// Merge another state into this one
     pub fn merge(&mut self, other: ReconstructedState) {
         for (node_id, state) in other.node_outputs {
@@ -121,6 +254,11 @@ impl ReconstructedState {
             self.global_state.insert(key, value);
         }
         self.errors.extend(other.errors);
+        for activation in other.policy_versions {
+            if !self.policy_versions.contains(&activation) {
+                self.record_policy(activation);
+            }
+        }
         self.time = self.time.max(other.time);
     }
 }
@@ -141,6 +279,26 @@ impl NodeState {
             output: None,
             error: None,
             side_effects: Vec::new(),
+            spilled_output: None,
+        }
+    }
+
+    /// Replace the output, dropping any spilled copy
+    pub fn set_output(&mut self, output: Vec<u8>) {
+        self.output = Some(output);
+        self.spilled_output = None;
+    }
+
+    /// Get the output, reading it back from `spill` if it was spilled
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the spilled output cannot be read
+    pub fn load_output(&self, spill: &SpillStore) -> CoreResult<Option<Vec<u8>>> {
+        match (&self.output, &self.spilled_output) {
+            (Some(output), _) => Ok(Some(output.clone())),
+            (None, Some(id)) => spill.read(id).map(Some),
+            (None, None) => Ok(None),
         }
     }
 
@@ -305,6 +463,21 @@ mod tests {
         assert_eq!(state.get_global("missing"), None);
     }
 
+    #[test]
+    fn test_reconstructed_state_value() {
+        let mut state = ReconstructedState::new();
+        let node_id = NodeId::new();
+        state.add_node_state(node_id, NodeState::new(node_id).with_output(b"out".to_vec()));
+        state.set_global("key".to_string(), b"value".to_vec());
+
+        assert_eq!(state.value("key"), Some(b"value".to_vec()));
+        assert_eq!(state.value(&format!("{}.output", node_id)), Some(b"out".to_vec()));
+        assert_eq!(state.value(&format!("{}.completed", node_id)), Some(b"true".to_vec()));
+        assert_eq!(state.value(&format!("{}.error", node_id)), None);
+        assert_eq!(state.value(&format!("{}.bogus", node_id)), None);
+        assert_eq!(state.value("missing"), None);
+    }
+
     #[test]
     fn test_reconstructed_state_tick() {
         let mut state = ReconstructedState::new();
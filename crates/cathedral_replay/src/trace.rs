@@ -1,8 +1,12 @@
 //! Trace reader for replaying execution logs.
 
-use cathedral_core::{CoreResult, CoreError, EventId, NodeId, LogicalTime};
+use crate::state::PolicyVersion;
+use cathedral_core::{Capability, CoreResult, CoreError, EventId, Hash, NodeId, LogicalTime};
+use cathedral_log::{Event, EventKind, RunHeader};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::io::BufRead;
 
 /// Event from a trace during replay
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -21,9 +25,94 @@ pub struct TraceEvent {
     pub parent_id: Option<EventId>,
 }
 
+impl TraceEvent {
+    /// Content hash of the event, used to chain trace prefixes
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn hash(&self) -> CoreResult<Hash> {
+        Ok(Hash::compute(&serde_json::to_vec(self)?))
+    }
+
+    /// Convert an event log entry into a trace event
+    ///
+    /// Run events carrying a [`RunHeader`] become `RunHeader`, tool
+    /// completions become `OutputProduced`, policy decisions on a
+    /// capability become `CapabilityCheck`. The log does not record tool
+    /// names, so tool invocations carry an empty name. Returns `None` for
+    /// kinds replay does not track and for payloads that cannot be decoded.
+    #[must_use]
+    pub fn from_log(event: &Event) -> Option<Self> {
+        /// Fields of a policy decision proof that replay needs
+        #[derive(Deserialize)]
+        struct Decision {
+            decision: bool,
+            capability: Option<Capability>,
+        }
+
+        /// Fields of a recorded host call that replay needs
+        #[derive(Deserialize)]
+        struct Call {
+            function: String,
+        }
+
+        let kind = match event.kind {
+            EventKind::RunCreated | EventKind::RunStarted => TraceEventKind::RunHeader {
+                abi_hash: RunHeader::decode(&event.payload).ok()?.abi_hash,
+            },
+            EventKind::NodeStarted => TraceEventKind::NodeStarted,
+            EventKind::NodeCompleted => TraceEventKind::NodeCompleted,
+            EventKind::NodeFailed => TraceEventKind::NodeFailed { exit_code: 1 },
+            EventKind::ToolInvoked => TraceEventKind::ToolInvoked {
+                tool: String::new(),
+                schema_hash: None,
+            },
+            EventKind::ToolCompleted => TraceEventKind::OutputProduced,
+            EventKind::SideEffect => TraceEventKind::SideEffect {
+                effect: String::from_utf8(event.payload.clone()).ok()?,
+            },
+            EventKind::SnapshotCreated => TraceEventKind::Snapshot,
+            EventKind::HostCall => {
+                let call: Call = serde_json::from_slice(&event.payload).ok()?;
+                TraceEventKind::HostCall { function: call.function }
+            }
+            EventKind::PolicyDecision => {
+                let proof: Decision = serde_json::from_slice(&event.payload).ok()?;
+                TraceEventKind::CapabilityCheck {
+                    capability: proof.capability?.to_string(),
+                    allowed: proof.decision,
+                }
+            }
+            EventKind::PolicyActivated => {
+                let activation: PolicyVersion = serde_json::from_slice(&event.payload).ok()?;
+                TraceEventKind::PolicyActivated {
+                    version: activation.version,
+                    bundle_hash: activation.bundle_hash,
+                }
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            id: event.event_id,
+            time: event.logical_time,
+            node_id: event.node_id,
+            kind,
+            data: event.payload.clone(),
+            parent_id: event.parent_event_id,
+        })
+    }
+}
+
 /// Kind of trace event
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TraceEventKind {
+    /// Run began executing against a host ABI
+    RunHeader {
+        /// Content hash of the ABI
+        abi_hash: Hash,
+    },
     /// Node started execution
     NodeStarted,
     /// Node completed successfully
@@ -38,6 +127,46 @@ pub enum TraceEventKind {
     CapabilityCheck { capability: String, allowed: bool },
     /// Snapshot taken
     Snapshot,
+    /// Tool invoked; event data carries the tool input
+    ToolInvoked {
+        /// Tool name
+        tool: String,
+        /// Hash of the schema of the tool version invoked, if recorded
+        #[serde(default)]
+        schema_hash: Option<Hash>,
+    },
+    /// Host function returned; event data carries the result
+    HostCall {
+        /// Host function name
+        function: String,
+    },
+    /// Policy bundle became active
+    PolicyActivated {
+        /// Bundle version
+        version: u64,
+        /// Bundle content hash
+        bundle_hash: Hash,
+    },
+}
+
+impl TraceEventKind {
+    /// Variant name, e.g. `NodeStarted`
+    #[must_use]
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::RunHeader { .. } => "RunHeader",
+            Self::NodeStarted => "NodeStarted",
+            Self::NodeCompleted => "NodeCompleted",
+            Self::NodeFailed { .. } => "NodeFailed",
+            Self::OutputProduced => "OutputProduced",
+            Self::SideEffect { .. } => "SideEffect",
+            Self::CapabilityCheck { .. } => "CapabilityCheck",
+            Self::Snapshot => "Snapshot",
+            Self::ToolInvoked { .. } => "ToolInvoked",
+            Self::HostCall { .. } => "HostCall",
+            Self::PolicyActivated { .. } => "PolicyActivated",
+        }
+    }
 }
 
 /// Trace reader for reading execution logs
@@ -50,6 +179,10 @@ pub struct TraceReader {
     total: usize,
     /// Current logical time
     time: LogicalTime,
+    /// Events not yet pulled into the buffer, for streaming readers
+    source: Option<Box<dyn Iterator<Item = CoreResult<TraceEvent>>>>,
+    /// Source error, returned once the buffered events are consumed
+    pending_error: Option<CoreError>,
 }
 
 impl TraceReader {
@@ -61,6 +194,8 @@ impl TraceReader {
             position: 0,
             total: 0,
             time: LogicalTime::zero(),
+            source: None,
+            pending_error: None,
         }
     }
 
@@ -73,6 +208,61 @@ impl TraceReader {
             position: 0,
             total,
             time: LogicalTime::zero(),
+            source: None,
+            pending_error: None,
+        }
+    }
+
+    /// Create a trace reader that pulls events from `source` as they are read
+    ///
+    /// Only one event is buffered ahead, so memory use does not grow with
+    /// the length of the trace. For such a reader, `total` counts the events
+    /// pulled so far and `remaining` the events buffered.
+    #[must_use]
+    pub fn from_source<I>(source: I) -> Self
+    where
+        I: IntoIterator<Item = CoreResult<TraceEvent>>,
+        I::IntoIter: 'static,
+    {
+        let mut reader = Self {
+            source: Some(Box::new(source.into_iter())),
+            ..Self::new()
+        };
+        reader.fill();
+        reader
+    }
+
+    /// Create a streaming trace reader over JSON lines, one event per line
+    #[must_use]
+    pub fn from_json_lines<R: BufRead + 'static>(input: R) -> Self {
+        Self::from_source(input.lines().filter_map(|line| match line {
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => Some(serde_json::from_str(&line).map_err(CoreError::from)),
+            Err(e) => Some(Err(CoreError::Validation {
+                field: "trace".to_string(),
+                reason: format!("Failed to read trace: {}", e),
+            })),
+        }))
+    }
+
+    /// Pull the next event from the source once the buffer runs dry
+    fn fill(&mut self) {
+        if !self.buffer.is_empty() {
+            return;
+        }
+        let Some(source) = &mut self.source else {
+            return;
+        };
+        match source.next() {
+            Some(Ok(event)) => {
+                self.buffer.push_back(event);
+                self.total += 1;
+            }
+            Some(Err(err)) => {
+                self.pending_error = Some(err);
+                self.source = None;
+            }
+            None => self.source = None,
         }
     }
 
@@ -82,12 +272,14 @@ impl TraceReader {
     ///
     /// Returns error if no more events
     pub fn next_event(&mut self) -> CoreResult<TraceEvent> {
-        self.buffer
-            .pop_front()
-            .ok_or_else(|| CoreError::Validation {
+        let Some(event) = self.buffer.pop_front() else {
+            return Err(self.pending_error.take().unwrap_or_else(|| CoreError::Validation {
                 field: "trace".to_string(),
                 reason: "No more events in trace".to_string(),
-            })
+            }));
+        };
+        self.fill();
+        Ok(event)
     }
 
     /// Peek at the next event without consuming it
@@ -105,7 +297,7 @@ impl TraceReader {
     /// Check if there are more events
     #[must_use]
     pub fn has_more(&self) -> bool {
-        !self.buffer.is_empty()
+        !self.buffer.is_empty() || self.pending_error.is_some()
     }
 
     /// Get remaining event count
@@ -169,6 +361,193 @@ impl Iterator for TraceReader {
     }
 }
 
+/// Execution graph reconstructed from a trace
+///
+/// Shared by every consumer that needs the graph (CLI trace, TUI
+/// provenance) so they render identical results for the same log.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ExecutionGraph {
+    /// Nodes in order of first appearance in the trace
+    pub nodes: IndexMap<NodeId, GraphNode>,
+    /// Edges derived from cross-node parent links, sorted
+    pub edges: BTreeSet<GraphEdge>,
+    /// Capability decisions in trace order
+    pub capability_decisions: Vec<CapabilityDecision>,
+    /// Policy bundle activations in trace order
+    #[serde(default)]
+    pub policy_activations: Vec<PolicyVersion>,
+}
+
+/// Node in a reconstructed execution graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// Node ID
+    pub node_id: NodeId,
+    /// Events emitted by this node, in trace order
+    pub events: Vec<TraceEvent>,
+    /// Hash of the last output the node produced, if any
+    pub output_hash: Option<Hash>,
+}
+
+/// Edge in a reconstructed execution graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct GraphEdge {
+    /// Upstream node
+    pub from: NodeId,
+    /// Downstream node
+    pub to: NodeId,
+}
+
+/// Capability check recorded in a trace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CapabilityDecision {
+    /// Node that requested the capability
+    pub node_id: NodeId,
+    /// Capability checked
+    pub capability: String,
+    /// Whether it was allowed
+    pub allowed: bool,
+    /// Logical time of the check
+    pub time: LogicalTime,
+}
+
+impl ExecutionGraph {
+    /// Take the edges between nodes from the workflow's dependencies
+    ///
+    /// Parent links only order events, so the node that ran just before
+    /// another looks like its upstream whether or not it fed it. Each
+    /// `(from, to)` dependency between two nodes in the trace replaces
+    /// those edges; nodes the dependencies never name, such as fan-out
+    /// items, keep the edges their parent links gave them.
+    #[must_use]
+    pub fn with_dependencies<I>(mut self, dependencies: I) -> Self
+    where
+        I: IntoIterator<Item = (NodeId, NodeId)>,
+    {
+        let edges: BTreeSet<GraphEdge> = dependencies
+            .into_iter()
+            .filter(|(from, to)| {
+                from != to && self.nodes.contains_key(from) && self.nodes.contains_key(to)
+            })
+            .map(|(from, to)| GraphEdge { from, to })
+            .collect();
+        let named: HashSet<NodeId> = edges.iter().flat_map(|edge| [edge.from, edge.to]).collect();
+        self.edges.retain(|edge| !named.contains(&edge.to));
+        self.edges.extend(edges);
+        self
+    }
+
+    /// Get the upstream nodes of a node, sorted
+    #[must_use]
+    pub fn parents(&self, node_id: NodeId) -> Vec<NodeId> {
+        self.edges
+            .iter()
+            .filter(|edge| edge.to == node_id)
+            .map(|edge| edge.from)
+            .collect()
+    }
+
+    /// Label the upstream nodes of a node, or `input` if it has none
+    #[must_use]
+    pub fn source_label(&self, node_id: NodeId) -> String {
+        let parents = self.parents(node_id);
+        if parents.is_empty() {
+            return "input".to_string();
+        }
+        parents
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Render one line per node as `node <- parents (output hash)`
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        self.nodes
+            .values()
+            .map(|node| {
+                format!(
+                    "{} <- {} ({})",
+                    node.node_id,
+                    self.source_label(node.node_id),
+                    node.hash_label()
+                )
+            })
+            .collect()
+    }
+}
+
+impl GraphNode {
+    /// Label the node's output hash, or `-` if it produced no output
+    #[must_use]
+    pub fn hash_label(&self) -> String {
+        self.output_hash
+            .map_or_else(|| "-".to_string(), |h| h.to_hex())
+    }
+}
+
+/// Rebuild the execution graph from a stream of trace events
+///
+/// Nodes appear in first-seen order, each event is attached to its node,
+/// and an edge is added whenever an event's parent belongs to another node.
+/// Parents that are not in the stream are ignored. Capability checks and
+/// policy activations are also collected graph-wide.
+pub fn build_graph<I>(stream: I) -> ExecutionGraph
+where
+    I: IntoIterator<Item = TraceEvent>,
+{
+    let mut graph = ExecutionGraph::default();
+    let mut owners: HashMap<EventId, NodeId> = HashMap::new();
+
+    for event in stream {
+        owners.insert(event.id, event.node_id);
+
+        if let Some(parent) = event.parent_id.and_then(|id| owners.get(&id))
+            && *parent != event.node_id
+        {
+            graph.edges.insert(GraphEdge {
+                from: *parent,
+                to: event.node_id,
+            });
+        }
+
+        if let TraceEventKind::CapabilityCheck { capability, allowed } = &event.kind {
+            graph.capability_decisions.push(CapabilityDecision {
+                node_id: event.node_id,
+                capability: capability.clone(),
+                allowed: *allowed,
+                time: event.time,
+            });
+        }
+        if let TraceEventKind::PolicyActivated { version, bundle_hash } = &event.kind {
+            graph.policy_activations.push(PolicyVersion {
+                activated_at: event.time,
+                version: *version,
+                bundle_hash: *bundle_hash,
+            });
+        }
+
+        let node = graph
+            .nodes
+            .entry(event.node_id)
+            .or_insert_with(|| GraphNode {
+                node_id: event.node_id,
+                events: Vec::new(),
+                output_hash: None,
+            });
+        if matches!(
+            event.kind,
+            TraceEventKind::NodeCompleted | TraceEventKind::OutputProduced
+        ) {
+            node.output_hash = Some(Hash::compute(&event.data));
+        }
+        node.events.push(event);
+    }
+
+    graph
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -226,6 +605,34 @@ mod tests {
         assert!(!reader.has_more());
     }
 
+    #[test]
+    fn test_trace_reader_from_json_lines() {
+        let event = |time| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(time),
+            node_id: NodeId::new(),
+            kind: TraceEventKind::NodeStarted,
+            data: Vec::new(),
+            parent_id: None,
+        };
+        let events = vec![event(0), event(1)];
+        let mut input = String::new();
+        for e in &events {
+            input.push_str(&serde_json::to_string(e).unwrap());
+            input.push_str("\n\n");
+        }
+        input.push_str("not json\n");
+
+        let mut reader = TraceReader::from_json_lines(std::io::Cursor::new(input));
+        assert_eq!(reader.total(), 1);
+        assert_eq!(reader.next_event().unwrap(), events[0]);
+        assert_eq!(reader.next_event().unwrap(), events[1]);
+        assert_eq!(reader.total(), 2);
+        assert!(reader.has_more());
+        assert!(reader.next_event().is_err());
+        assert!(!reader.has_more());
+    }
+
     #[test]
     fn test_trace_reader_next_event_empty() {
         let mut reader = TraceReader::new();
@@ -256,4 +663,109 @@ mod tests {
         let deserialized: TraceEventKind = serde_json::from_slice(&serialized).unwrap();
         assert_eq!(kind, deserialized);
     }
+
+    #[test]
+    fn test_build_graph() {
+        let a = NodeId::new();
+        let b = NodeId::new();
+        let event = |node_id, kind, parent_id| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::zero(),
+            node_id,
+            kind,
+            data: b"out".to_vec(),
+            parent_id,
+        };
+
+        let a_start = event(a, TraceEventKind::NodeStarted, None);
+        let a_done = event(a, TraceEventKind::NodeCompleted, Some(a_start.id));
+        let b_start = event(b, TraceEventKind::NodeStarted, Some(a_done.id));
+        let b_check = event(
+            b,
+            TraceEventKind::CapabilityCheck {
+                capability: "ClockRead".to_string(),
+                allowed: false,
+            },
+            Some(b_start.id),
+        );
+        let events = vec![a_start, a_done, b_start, b_check];
+
+        let graph = build_graph(events.clone());
+        assert_eq!(graph.nodes.keys().copied().collect::<Vec<_>>(), vec![a, b]);
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.parents(b), vec![a]);
+        assert_eq!(graph.nodes[&a].events.len(), 2);
+        assert_eq!(graph.nodes[&a].output_hash, Some(Hash::compute(b"out")));
+        assert_eq!(graph.capability_decisions.len(), 1);
+        assert!(!graph.capability_decisions[0].allowed);
+
+        let lines = graph.lines();
+        assert!(lines[0].ends_with(&format!("<- input ({})", Hash::compute(b"out").to_hex())));
+        assert!(lines[1].contains(&format!("<- {} (-)", a)));
+
+        // Rebuilding from the same stream is deterministic
+        assert_eq!(build_graph(events), graph);
+    }
+
+    #[test]
+    fn test_trace_event_from_log() {
+        use cathedral_core::RunId;
+
+        let run_id = RunId::new();
+        let node_id = NodeId::new();
+        let log = |kind, payload: Vec<u8>| {
+            Event::new(EventId::new(), run_id, node_id, LogicalTime::from_raw(4), kind)
+                .with_payload(payload)
+        };
+
+        let invoked = log(EventKind::ToolInvoked, b"input".to_vec());
+        let trace = TraceEvent::from_log(&invoked).unwrap();
+        assert_eq!(trace.id, invoked.event_id);
+        assert_eq!(trace.data, b"input");
+        assert!(matches!(trace.kind, TraceEventKind::ToolInvoked { schema_hash: None, .. }));
+
+        let completed = log(EventKind::ToolCompleted, b"out".to_vec());
+        let trace = TraceEvent::from_log(&completed.with_parent(invoked.event_id)).unwrap();
+        assert_eq!(trace.kind, TraceEventKind::OutputProduced);
+        assert_eq!(trace.parent_id, Some(invoked.event_id));
+
+        let proof = serde_json::json!({
+            "id": "proof",
+            "decision": false,
+            "capability": Capability::ClockRead,
+        });
+        let decision = log(EventKind::PolicyDecision, serde_json::to_vec(&proof).unwrap());
+        assert_eq!(
+            TraceEvent::from_log(&decision).unwrap().kind,
+            TraceEventKind::CapabilityCheck {
+                capability: "ClockRead".to_string(),
+                allowed: false,
+            }
+        );
+
+        let activation = PolicyVersion {
+            activated_at: LogicalTime::from_raw(2),
+            version: 7,
+            bundle_hash: Hash::compute(b"bundle"),
+        };
+        let activated = log(EventKind::PolicyActivated, serde_json::to_vec(&activation).unwrap());
+        let graph = build_graph(TraceEvent::from_log(&activated));
+        assert_eq!(graph.policy_activations[0].version, 7);
+
+        let call = br#"{"sequence":0,"function":"clock_read","args":[],"result":{"Ok":{"I64":7}}}"#;
+        let host_call = TraceEvent::from_log(&log(EventKind::HostCall, call.to_vec())).unwrap();
+        assert_eq!(host_call.kind, TraceEventKind::HostCall { function: "clock_read".to_string() });
+        assert_eq!(host_call.data, call);
+
+        let header = RunHeader::new("1.0.0", Hash::compute(b"abi"));
+        let created = log(EventKind::RunCreated, header.encode().unwrap());
+        assert_eq!(
+            TraceEvent::from_log(&created).unwrap().kind,
+            TraceEventKind::RunHeader { abi_hash: header.abi_hash }
+        );
+
+        assert!(TraceEvent::from_log(&log(EventKind::RunCreated, Vec::new())).is_none());
+        assert!(TraceEvent::from_log(&log(EventKind::Heartbeat, Vec::new())).is_none());
+        assert!(TraceEvent::from_log(&log(EventKind::PolicyDecision, b"{}".to_vec())).is_none());
+    }
 }
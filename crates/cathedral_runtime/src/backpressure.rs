@@ -1,6 +1,13 @@
 //! Backpressure control for execution.
 //!
 //! Monitors resource usage and applies backpressure when needed.
+//!
+//! The queue strategies bound the scheduler's ready queue. Every input
+//! they act on, the queue length and node latency in logical ticks, is
+//! itself deterministic, so a run replays with the same backpressure
+//! decisions.
+
+use super::monitor::Metrics;
 
 /// Backpressure strategy
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -13,6 +20,77 @@ pub enum BackpressureStrategy {
     Block,
     /// Signal producer to slow down
     Signal,
+    /// Hold nodes that become ready while `capacity` nodes are pending
+    /// until the ready queue has room
+    BoundedQueue {
+        /// Maximum number of ready nodes
+        capacity: usize,
+    },
+    /// Shed the oldest ready node to make room once `capacity` nodes are
+    /// pending
+    DropOldest {
+        /// Maximum number of ready nodes
+        capacity: usize,
+    },
+    /// Bound the ready queue by a window that grows additively while node
+    /// latency stays on target and halves when it does not
+    Adaptive(AimdConfig),
+}
+
+/// Additive-increase/multiplicative-decrease parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AimdConfig {
+    /// Window the run starts with
+    pub initial_window: usize,
+    /// Smallest window
+    pub min_window: usize,
+    /// Largest window
+    pub max_window: usize,
+    /// Node latency in logical ticks above which the window halves
+    pub target_latency: u64,
+}
+
+impl AimdConfig {
+    /// Create a config starting at the largest window
+    #[must_use]
+    pub fn new(max_window: usize, target_latency: u64) -> Self {
+        Self {
+            initial_window: max_window.max(1),
+            min_window: 1,
+            max_window: max_window.max(1),
+            target_latency,
+        }
+    }
+
+    /// Set the initial window
+    #[must_use]
+    pub fn with_initial_window(mut self, window: usize) -> Self {
+        self.initial_window = window;
+        self
+    }
+
+    /// Set the smallest window
+    #[must_use]
+    pub fn with_min_window(mut self, window: usize) -> Self {
+        self.min_window = window.max(1);
+        self
+    }
+
+    /// Clamp a window to the configured range
+    fn clamp(&self, window: usize) -> usize {
+        window.clamp(self.min_window, self.max_window.max(self.min_window))
+    }
+}
+
+/// What the scheduler does with a node that becomes ready while the ready
+/// queue is at its limit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueueOverflow {
+    /// Hold the node until the queue has room
+    #[default]
+    Hold,
+    /// Shed the oldest ready node and queue the new one
+    ShedOldest,
 }
 
 /// Backpressure controller
@@ -27,6 +105,8 @@ pub struct BackpressureController {
     threshold: f64,
     /// Strategy
     strategy: BackpressureStrategy,
+    /// Current AIMD window
+    window: usize,
 }
 
 impl BackpressureController {
@@ -38,6 +118,25 @@ impl BackpressureController {
             current_buffer_size: 0,
             threshold: threshold.clamp(0.0, 1.0),
             strategy,
+            window: Self::initial_window(strategy),
+        }
+    }
+
+    /// Create a controller with default buffer settings for `strategy`
+    #[must_use]
+    pub fn with_strategy(strategy: BackpressureStrategy) -> Self {
+        Self {
+            strategy,
+            window: Self::initial_window(strategy),
+            ..Self::default()
+        }
+    }
+
+    /// Starting AIMD window for `strategy`
+    fn initial_window(strategy: BackpressureStrategy) -> usize {
+        match strategy {
+            BackpressureStrategy::Adaptive(aimd) => aimd.clamp(aimd.initial_window),
+            _ => 0,
         }
     }
 
@@ -46,6 +145,11 @@ impl BackpressureController {
         self.current_buffer_size = size;
     }
 
+    /// Resize the buffer the threshold is measured against
+    pub fn set_max_buffer_size(&mut self, size: usize) {
+        self.max_buffer_size = size;
+    }
+
     /// Check if backpressure should be applied
     #[must_use]
     pub fn should_apply(&self) -> bool {
@@ -64,9 +168,15 @@ impl BackpressureController {
         } else {
             match self.strategy {
                 BackpressureStrategy::None => BackpressureStatus::Ok,
-                BackpressureStrategy::Drop => BackpressureStatus::Drop,
-                BackpressureStrategy::Block => BackpressureStatus::Block,
-                BackpressureStrategy::Signal => BackpressureStatus::Signal,
+                BackpressureStrategy::Drop | BackpressureStrategy::DropOldest { .. } => {
+                    BackpressureStatus::Drop
+                }
+                BackpressureStrategy::Block | BackpressureStrategy::BoundedQueue { .. } => {
+                    BackpressureStatus::Block
+                }
+                BackpressureStrategy::Signal | BackpressureStrategy::Adaptive(_) => {
+                    BackpressureStatus::Signal
+                }
             }
         }
     }
@@ -85,6 +195,48 @@ impl BackpressureController {
     pub const fn strategy(&self) -> BackpressureStrategy {
         self.strategy
     }
+
+    /// Maximum number of ready nodes the strategy allows, if it bounds the
+    /// ready queue
+    #[must_use]
+    pub fn queue_limit(&self) -> Option<usize> {
+        match self.strategy {
+            BackpressureStrategy::BoundedQueue { capacity }
+            | BackpressureStrategy::DropOldest { capacity } => Some(capacity.max(1)),
+            BackpressureStrategy::Adaptive(_) => Some(self.window),
+            _ => None,
+        }
+    }
+
+    /// How a full ready queue treats a newly ready node
+    #[must_use]
+    pub const fn overflow(&self) -> QueueOverflow {
+        match self.strategy {
+            BackpressureStrategy::DropOldest { .. } => QueueOverflow::ShedOldest,
+            _ => QueueOverflow::Hold,
+        }
+    }
+
+    /// Adjust the AIMD window to the latest node latency in `metrics`
+    ///
+    /// Returns the new queue limit when the window changed. Other
+    /// strategies never adapt.
+    pub fn adapt(&mut self, metrics: &Metrics) -> Option<usize> {
+        let BackpressureStrategy::Adaptive(aimd) = self.strategy else {
+            return None;
+        };
+        let latency = metrics.last_latency?;
+        let window = if latency > aimd.target_latency {
+            aimd.clamp(self.window / 2)
+        } else {
+            aimd.clamp(self.window.saturating_add(1))
+        };
+        if window == self.window {
+            return None;
+        }
+        self.window = window;
+        Some(window)
+    }
 }
 
 impl Default for BackpressureController {
@@ -94,6 +246,7 @@ impl Default for BackpressureController {
             current_buffer_size: 0,
             threshold: 0.8,
             strategy: BackpressureStrategy::Signal,
+            window: 0,
         }
     }
 }
@@ -179,4 +332,42 @@ mod tests {
         controller.strategy = BackpressureStrategy::Signal;
         assert_eq!(controller.status(), BackpressureStatus::Signal);
     }
+
+    #[test]
+    fn test_backpressure_queue_strategies() {
+        let bounded = BackpressureController::with_strategy(BackpressureStrategy::BoundedQueue {
+            capacity: 4,
+        });
+        assert_eq!(bounded.queue_limit(), Some(4));
+        assert_eq!(bounded.overflow(), QueueOverflow::Hold);
+
+        let shedding =
+            BackpressureController::with_strategy(BackpressureStrategy::DropOldest { capacity: 0 });
+        assert_eq!(shedding.queue_limit(), Some(1));
+        assert_eq!(shedding.overflow(), QueueOverflow::ShedOldest);
+
+        assert_eq!(BackpressureController::default().queue_limit(), None);
+    }
+
+    #[test]
+    fn test_backpressure_aimd() {
+        let aimd = AimdConfig::new(8, 2).with_initial_window(4).with_min_window(2);
+        let mut controller =
+            BackpressureController::with_strategy(BackpressureStrategy::Adaptive(aimd));
+        let mut metrics = Metrics::new();
+        assert_eq!(controller.queue_limit(), Some(4));
+        assert_eq!(controller.adapt(&metrics), None);
+
+        metrics.record_latency(1);
+        assert_eq!(controller.adapt(&metrics), Some(5));
+        metrics.record_latency(3);
+        assert_eq!(controller.adapt(&metrics), Some(2));
+        assert_eq!(controller.adapt(&metrics), None);
+
+        metrics.record_latency(2);
+        for _ in 0..10 {
+            controller.adapt(&metrics);
+        }
+        assert_eq!(controller.queue_limit(), Some(8));
+    }
 }
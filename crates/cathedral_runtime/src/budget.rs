@@ -0,0 +1,171 @@
+//! Per-run resource budgets for scheduler admission control.
+//!
+//! Each node is charged its worst-case usage, the maximum of its resource
+//! contract, when the scheduler admits it to the ready queue. Charges are
+//! cumulative over the run and never refunded, so admission depends only
+//! on which nodes were admitted before, keeping it deterministic.
+
+use cathedral_core::NodeId;
+use cathedral_plan::ResourceContract;
+use serde::{Deserialize, Serialize};
+
+/// Resources charged against a run's budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// Memory in bytes
+    pub memory: u64,
+    /// CPU shares
+    pub cpu: u64,
+    /// Execution fuel in logical ticks
+    pub fuel: u64,
+}
+
+impl ResourceUsage {
+    /// Worst-case usage under a contract
+    ///
+    /// Resources the contract does not bound are not charged.
+    #[must_use]
+    pub fn from_contract(contract: &ResourceContract) -> Self {
+        Self {
+            memory: contract.memory.max.unwrap_or(0),
+            cpu: contract.cpu.max.unwrap_or(0),
+            fuel: contract.fuel.max.unwrap_or(0),
+        }
+    }
+
+    /// Add another usage to this one
+    #[must_use]
+    pub fn plus(self, other: Self) -> Self {
+        Self {
+            memory: self.memory.saturating_add(other.memory),
+            cpu: self.cpu.saturating_add(other.cpu),
+            fuel: self.fuel.saturating_add(other.fuel),
+        }
+    }
+}
+
+/// Resource budget for a run; `None` leaves a resource unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceBudget {
+    /// Memory in bytes
+    pub memory: Option<u64>,
+    /// CPU shares
+    pub cpu: Option<u64>,
+    /// Execution fuel in logical ticks
+    pub fuel: Option<u64>,
+}
+
+impl ResourceBudget {
+    /// Create an unlimited budget
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit memory
+    #[must_use]
+    pub fn with_memory(mut self, bytes: u64) -> Self {
+        self.memory = Some(bytes);
+        self
+    }
+
+    /// Limit CPU shares
+    #[must_use]
+    pub fn with_cpu(mut self, shares: u64) -> Self {
+        self.cpu = Some(shares);
+        self
+    }
+
+    /// Limit execution fuel
+    #[must_use]
+    pub fn with_fuel(mut self, ticks: u64) -> Self {
+        self.fuel = Some(ticks);
+        self
+    }
+
+    /// Check that `demand` fits in what remains after `charged`
+    ///
+    /// # Errors
+    ///
+    /// Returns the first exceeded bound, checking memory, CPU, then fuel
+    pub fn admit(
+        &self,
+        node_id: NodeId,
+        charged: ResourceUsage,
+        demand: ResourceUsage,
+    ) -> Result<(), BudgetExceeded> {
+        let bounds = [
+            ("memory", self.memory, charged.memory, demand.memory),
+            ("cpu", self.cpu, charged.cpu, demand.cpu),
+            ("fuel", self.fuel, charged.fuel, demand.fuel),
+        ];
+        for (resource, limit, charged, requested) in bounds {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let remaining = limit.saturating_sub(charged);
+            if requested > remaining {
+                return Err(BudgetExceeded {
+                    node_id,
+                    resource: resource.to_string(),
+                    requested,
+                    remaining,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A node refused admission because it would exceed the run's budget
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetExceeded {
+    /// The refused node
+    pub node_id: NodeId,
+    /// Exceeded resource: `memory`, `cpu`, or `fuel`
+    pub resource: String,
+    /// Amount the node's contract requires
+    pub requested: u64,
+    /// Amount left in the budget
+    pub remaining: u64,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "node {} needs {} {} but only {} remains",
+            self.node_id, self.requested, self.resource, self.remaining
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_plan::ResourceBounds;
+
+    #[test]
+    fn test_budget_admit() {
+        let contract = ResourceContract::new()
+            .with_memory(ResourceBounds::new().with_min(1).with_max(300))
+            .with_cpu(ResourceBounds::new().with_max(2));
+        let demand = ResourceUsage::from_contract(&contract);
+        assert_eq!(demand, ResourceUsage { memory: 300, cpu: 2, fuel: 0 });
+
+        let node = NodeId::new();
+        let budget = ResourceBudget::new().with_memory(500).with_fuel(10);
+        assert!(budget.admit(node, ResourceUsage::default(), demand).is_ok());
+        let exceeded = budget.admit(node, demand, demand).unwrap_err();
+        assert_eq!(
+            exceeded,
+            BudgetExceeded {
+                node_id: node,
+                resource: "memory".to_string(),
+                requested: 300,
+                remaining: 200,
+            }
+        );
+        assert!(ResourceBudget::new().admit(node, demand.plus(demand), demand).is_ok());
+    }
+}
@@ -1,13 +1,43 @@
 //! Execution engine for DAG workflows.
 //!
 //! Combines scheduler and executor to run complete DAGs deterministically.
+//!
+//! A run is cancelled through its [`CancellationToken`], shared with the
+//! contexts of the nodes it executes. Once the token is tripped the engine
+//! stops scheduling, records a `NodeCancelled` event for each node that was
+//! in flight and a `NodeSkipped` event for each node downstream of them, in
+//! scheduler order, and ends the log with `RunCancelled`.
 
 use cathedral_core::{RunId, NodeId, EventId, LogicalTime, CoreResult, CoreError, CapabilitySet};
-use cathedral_log::{Event, EventKind, EventStream};
+use cathedral_core::CancellationToken;
+use cathedral_log::{Event, EventKind, EventStream, RunHeader};
+use cathedral_plan::{CapabilityGrantTable, Dag, Node, NodeKind, WorkflowLibrary};
+use cathedral_plan::{ELSE_PORT, THEN_PORT};
+use cathedral_tool::registry::SharedRegistry;
 use indexmap::{IndexMap, IndexSet};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
 
+use super::backpressure::{BackpressureController, BackpressureStrategy};
+use super::budget::ResourceBudget;
+use super::monitor::{ExecutionMonitor, Metrics};
 use super::scheduler::{Scheduler, ScheduleDecision};
-use super::executor::{Executor, ExecutionContext, ExecutorResult};
+use super::executor::{Executor, ExecutionContext, ExecutorResult, SeededEventIds};
+
+/// Callback receiving every event as the engine records it
+pub type EventSink = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// Node ID recorded on run-level events, which belong to no node
+const RUN_NODE: NodeId = NodeId::from_bytes([0; 16]);
+
+/// Header recorded at the start of every run
+///
+/// Names the deterministic host ABI this build executes nodes against.
+#[must_use]
+pub fn run_header() -> RunHeader {
+    let abi = cathedral_wasm::DeterministicAbi::new();
+    RunHeader::new(abi.version.to_string(), abi.content_hash())
+}
 
 /// Execution engine configuration
 #[derive(Debug, Clone)]
@@ -16,8 +46,25 @@ pub struct EngineConfig {
     pub max_ticks: u64,
     /// Capability set for execution
     pub capabilities: CapabilitySet,
+    /// Per-node least-privilege grants; without them every node runs
+    /// with the full capability set
+    pub grants: Option<CapabilityGrantTable>,
+    /// Compiled workflows that sub-workflow nodes invoke
+    pub workflows: Option<Arc<WorkflowLibrary>>,
+    /// Resource budget nodes are admitted against
+    pub budget: ResourceBudget,
     /// Whether to enable backpressure
     pub enable_backpressure: bool,
+    /// Backpressure strategy applied to the ready queue when enabled
+    pub backpressure: BackpressureStrategy,
+    /// Registry resolving tool nodes; without it DAG nodes complete
+    /// without running their kind
+    pub tools: Option<Arc<SharedRegistry>>,
+    /// Named run inputs bound for tool input bindings
+    pub inputs: BTreeMap<String, Vec<u8>>,
+    /// Seeded source of event IDs, shared with sub-workflows; random IDs
+    /// when unset
+    pub event_ids: Option<SeededEventIds>,
 }
 
 impl Default for EngineConfig {
@@ -25,7 +72,14 @@ impl Default for EngineConfig {
         Self {
             max_ticks: 1_000_000,
             capabilities: CapabilitySet::new(),
+            grants: None,
+            workflows: None,
+            budget: ResourceBudget::default(),
             enable_backpressure: true,
+            backpressure: BackpressureStrategy::None,
+            tools: None,
+            inputs: BTreeMap::new(),
+            event_ids: None,
         }
     }
 }
@@ -69,6 +123,8 @@ pub enum ExecutionStatus {
     Timeout,
     /// Cycle detected
     CycleDetected,
+    /// The run was cancelled
+    Cancelled,
 }
 
 /// Node output storage
@@ -80,6 +136,8 @@ pub struct NodeOutput {
     pub output: Vec<u8>,
     /// Output hash
     pub output_hash: cathedral_core::Hash,
+    /// Side effects the node performed
+    pub effects: Vec<cathedral_tool::SideEffect>,
 }
 
 /// Execution engine for running DAG workflows
@@ -103,18 +161,54 @@ pub struct ExecutionEngine {
     time: LogicalTime,
     /// Last event ID (for chaining)
     last_event_id: Option<EventId>,
+    /// Sub-workflow nodes and the DAG each invokes
+    subworkflows: IndexMap<NodeId, (cathedral_core::Hash, Dag)>,
+    /// Nodes added from a DAG, run by kind when a tool registry is set
+    dag_nodes: IndexMap<NodeId, Node>,
+    /// Fan-out items of map-reduce nodes, with their node and element
+    map_items: HashMap<NodeId, (NodeId, Vec<u8>)>,
+    /// Backpressure applied to the ready queue
+    backpressure: BackpressureController,
+    /// Execution metrics, including node latency
+    monitor: ExecutionMonitor,
+    /// Token that cancels this run
+    cancellation: CancellationToken,
+    /// Whether this engine runs a sub-workflow; the parent records the
+    /// run-level events
+    nested: bool,
+    /// Receiver of events as they are recorded
+    sink: Option<EventSink>,
+    /// Engine time at which each queued node was first seen ready
+    ready_since: HashMap<NodeId, u64>,
+    /// Metrics published for observers while the run executes
+    shared_metrics: Option<Arc<Mutex<Metrics>>>,
 }
 
 impl ExecutionEngine {
     /// Create a new execution engine
     #[must_use]
     pub fn new(run_id: RunId, config: EngineConfig) -> Self {
-        let executor = Executor::new()
+        let mut executor = Executor::new()
             .with_max_ticks(config.max_ticks)
             .with_strict_capabilities(true);
+        if let Some(tools) = &config.tools {
+            executor = executor.with_tools(Arc::clone(tools));
+        }
+        if let Some(ids) = &config.event_ids {
+            executor = executor.with_event_ids(ids.clone());
+        }
+
+        let strategy = if config.enable_backpressure {
+            config.backpressure
+        } else {
+            BackpressureStrategy::None
+        };
+        let backpressure = BackpressureController::with_strategy(strategy);
+        let mut scheduler = Scheduler::new().with_budget(config.budget);
+        scheduler.set_queue_limit(backpressure.queue_limit(), backpressure.overflow());
 
         Self {
-            scheduler: Scheduler::new(),
+            scheduler,
             executor,
             config,
             outputs: IndexMap::new(),
@@ -122,9 +216,77 @@ impl ExecutionEngine {
             run_id,
             time: LogicalTime::zero(),
             last_event_id: None,
+            subworkflows: IndexMap::new(),
+            dag_nodes: IndexMap::new(),
+            map_items: HashMap::new(),
+            backpressure,
+            monitor: ExecutionMonitor::default(),
+            cancellation: CancellationToken::new(),
+            nested: false,
+            sink: None,
+            ready_since: HashMap::new(),
+            shared_metrics: None,
         }
     }
 
+    /// Cancel the run through a token shared with its caller
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = token;
+        self
+    }
+
+    /// Hand every event to `sink` as soon as it is recorded
+    ///
+    /// Lets a caller follow a run while it executes instead of reading
+    /// [`ExecutionEngine::events`] once it returns. A sub-workflow's events
+    /// reach the sink when its node finishes.
+    #[must_use]
+    pub fn with_event_sink(mut self, sink: EventSink) -> Self {
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Publish the run's metrics to `metrics` before each scheduling step
+    ///
+    /// Lets another thread, such as a metrics exporter, read the metrics of
+    /// a run while it executes.
+    #[must_use]
+    pub fn with_shared_metrics(mut self, metrics: Arc<Mutex<Metrics>>) -> Self {
+        self.shared_metrics = Some(metrics);
+        self
+    }
+
+    /// Get the token that cancels this run
+    ///
+    /// Cancelling it from another thread stops the run before the next
+    /// node is scheduled.
+    #[must_use]
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
+    /// Cancel a run
+    ///
+    /// Trips the run's token, so workers and sandboxes sharing it stop, and
+    /// records the cancellation in the log. Cancelling a finished or
+    /// already-cancelled run records nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::NotFound` if `run_id` is not this engine's run
+    pub fn cancel(&mut self, run_id: RunId) -> CoreResult<()> {
+        if run_id != self.run_id {
+            return Err(CoreError::NotFound {
+                kind: "Run".to_string(),
+                id: run_id.to_string(),
+            });
+        }
+        self.cancellation.cancel();
+        self.record_cancellation();
+        Ok(())
+    }
+
     /// Add a node to the execution plan
     ///
     /// # Errors
@@ -134,21 +296,94 @@ impl ExecutionEngine {
         self.scheduler.add_node(node_id, deps)
     }
 
+    /// Add every node of a compiled DAG to the execution plan
+    ///
+    /// Sub-workflow nodes are resolved against the configured workflow
+    /// library; each runs its DAG when scheduled. With a tool registry
+    /// configured, other nodes run their kind on the executor, gated on the
+    /// capabilities they declare. Each node is admitted against the
+    /// configured budget with its resource contract. Nodes on a branch of a
+    /// conditional run only when the conditional takes that branch.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a sub-workflow is not in the library or a cycle is
+    /// detected
+    pub fn add_dag(&mut self, dag: &Dag) -> CoreResult<()> {
+        for (id, node) in &dag.nodes {
+            if let NodeKind::SubWorkflow { hash, .. } = &node.kind {
+                let child = self
+                    .config
+                    .workflows
+                    .as_ref()
+                    .and_then(|workflows| workflows.get(hash))
+                    .ok_or_else(|| CoreError::NotFound {
+                        kind: "Workflow".to_string(),
+                        id: hash.to_hex(),
+                    })?;
+                self.subworkflows.insert(*id, (*hash, child.clone()));
+            } else {
+                self.dag_nodes.insert(*id, node.clone());
+            }
+
+            let mut deps = node.dependencies.clone();
+            deps.extend(dag.dependencies(*id));
+            self.scheduler.add_node_with_contract(*id, deps, &node.resource_contract())?;
+        }
+        for edge in &dag.edges {
+            match edge.from_port.as_deref() {
+                Some(THEN_PORT) => self.scheduler.add_guard(edge.to, edge.from, true)?,
+                Some(ELSE_PORT) => self.scheduler.add_guard(edge.to, edge.from, false)?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Run the execution to completion
     ///
+    /// The log opens with a `RunCreated` event carrying the [`run_header`].
+    /// A plan with no nodes completes immediately, logging only the header
+    /// and a `RunCompleted` terminal event. A run that reaches `max_ticks`
+    /// skips its unfinished nodes and ends with a `RunFailed` event.
+    ///
     /// # Errors
     ///
     /// Returns error if execution fails
     pub fn run(&mut self) -> CoreResult<ExecutionStatus> {
+        if !self.nested && self.events.is_empty() {
+            self.record_header()?;
+        }
+        if self.scheduler.nodes().is_empty() {
+            self.record_empty_run();
+            return Ok(ExecutionStatus::Success);
+        }
+
         loop {
+            self.observe_queue();
+            self.record_budget_violations();
+            self.record_shed_nodes();
+
+            if self.cancellation.is_cancelled() {
+                self.record_cancellation();
+                return Ok(ExecutionStatus::Cancelled);
+            }
+
             // Check for timeout
             if self.time.as_u64() >= self.config.max_ticks {
+                self.record_timeout();
                 return Ok(ExecutionStatus::Timeout);
             }
 
             match self.scheduler.decide() {
                 ScheduleDecision::Run(node_id) => {
+                    let started = self.time;
+                    let waited = self.ready_since.remove(&node_id).unwrap_or(started.as_u64());
+                    self.monitor
+                        .metrics_mut()
+                        .record_scheduling_latency(started.as_u64().saturating_sub(waited));
                     self.execute_node(node_id)?;
+                    self.adapt_backpressure(self.time.as_u64().saturating_sub(started.as_u64()));
                 }
                 ScheduleDecision::Wait => {
                     // Waiting for dependencies that can't be satisfied
@@ -169,48 +404,284 @@ impl ExecutionEngine {
         }
     }
 
-    /// Execute a single node
-    fn execute_node(&mut self, node_id: NodeId) -> CoreResult<()> {
+    /// Record the `RunCreated` event that opens the log
+    fn record_header(&mut self) -> CoreResult<()> {
+        let created = Event::new(
+            self.executor.next_event_id(),
+            self.run_id,
+            RUN_NODE,
+            self.time,
+            EventKind::RunCreated,
+        )
+        .with_payload(run_header().encode()?)
+        .with_state_hashes(cathedral_core::Hash::empty(), cathedral_core::Hash::empty());
+
+        self.last_event_id = Some(created.event_id);
+        self.record(created);
+        Ok(())
+    }
+
+    /// Record the terminal event of a run with no nodes
+    fn record_empty_run(&mut self) {
+        let mut completed = Event::new(
+            self.executor.next_event_id(),
+            self.run_id,
+            RUN_NODE,
+            self.time,
+            EventKind::RunCompleted,
+        )
+        .with_state_hashes(cathedral_core::Hash::empty(), cathedral_core::Hash::empty());
+        if let Some(parent_id) = self.last_event_id {
+            completed = completed.with_parent(parent_id);
+        }
+
+        self.last_event_id = Some(completed.event_id);
+        self.record(completed);
+    }
+
+    /// Record a `BudgetExceeded` event for each node refused admission
+    fn record_budget_violations(&mut self) {
+        for exceeded in self.scheduler.take_budget_violations() {
+            let payload = serde_json::to_vec(&exceeded).unwrap_or_default();
+            let mut event = Event::new(
+                self.executor.next_event_id(),
+                self.run_id,
+                exceeded.node_id,
+                self.scheduler.time(),
+                EventKind::BudgetExceeded,
+            )
+            .with_payload(payload);
+            if let Some(parent_id) = self.last_event_id {
+                event = event.with_parent(parent_id);
+            }
+            self.last_event_id = Some(event.event_id);
+            self.record(event);
+        }
+    }
+
+    /// Record a `Shed` event for each node dropped by load shedding
+    fn record_shed_nodes(&mut self) {
+        for node_id in self.scheduler.take_shed() {
+            let mut event = Event::new(
+                self.executor.next_event_id(),
+                self.run_id,
+                node_id,
+                self.scheduler.time(),
+                EventKind::Shed,
+            );
+            if let Some(parent_id) = self.last_event_id {
+                event = event.with_parent(parent_id);
+            }
+            self.last_event_id = Some(event.event_id);
+            self.record(event);
+        }
+    }
+
+    /// Record the events of a cancelled run
+    ///
+    /// Does nothing once every node has finished, including after an
+    /// earlier cancellation.
+    fn record_cancellation(&mut self) {
+        if self.scheduler.is_complete() {
+            return;
+        }
+        let Some(cancellation) = self.scheduler.cancel() else {
+            return;
+        };
         let time = self.scheduler.time();
+        let nodes = cancellation
+            .cancelled
+            .into_iter()
+            .map(|node_id| (node_id, EventKind::NodeCancelled))
+            .chain(cancellation.skipped.into_iter().map(|id| (id, EventKind::NodeSkipped)));
+        let run = (!self.nested).then_some((RUN_NODE, EventKind::RunCancelled));
 
-        // Build execution context with inputs from dependencies
+        for (node_id, kind) in nodes.chain(run) {
+            let event_id = self.executor.next_event_id();
+            let mut event = Event::new(event_id, self.run_id, node_id, time, kind);
+            if let Some(parent_id) = self.last_event_id {
+                event = event.with_parent(parent_id);
+            }
+            self.last_event_id = Some(event.event_id);
+            self.record(event);
+        }
+    }
+
+    /// Record the terminal events of a run that ran out of ticks
+    ///
+    /// Nodes that never finished are skipped, and a run that is not nested
+    /// ends with a `RunFailed` event naming the timeout. Later calls record
+    /// nothing more.
+    fn record_timeout(&mut self) {
+        let Some(stopped) = self.scheduler.cancel() else {
+            return;
+        };
+        let time = self.scheduler.time();
+        let nodes = stopped
+            .cancelled
+            .into_iter()
+            .chain(stopped.skipped)
+            .map(|node_id| (node_id, EventKind::NodeSkipped, Vec::new()));
+        let run = (!self.nested).then(|| {
+            let reason = ExecutionError::Timeout.to_string().into_bytes();
+            (RUN_NODE, EventKind::RunFailed, reason)
+        });
+
+        for (node_id, kind, payload) in nodes.chain(run) {
+            let event_id = self.executor.next_event_id();
+            let mut event =
+                Event::new(event_id, self.run_id, node_id, time, kind).with_payload(payload);
+            if let Some(parent_id) = self.last_event_id {
+                event = event.with_parent(parent_id);
+            }
+            self.last_event_id = Some(event.event_id);
+            self.record(event);
+        }
+    }
+
+    /// Sample the ready queue, note when nodes became ready, and publish
+    /// the metrics
+    fn observe_queue(&mut self) {
+        let time = self.time.as_u64();
+        for node_id in self.scheduler.ready_nodes() {
+            self.ready_since.entry(node_id).or_insert(time);
+        }
+        let metrics = self.monitor.metrics_mut();
+        metrics.record_queue_depth(self.scheduler.ready_count() as u64);
+        let charged = self.scheduler.charged().fuel;
+        metrics.record_fuel(charged.saturating_sub(metrics.fuel_consumed));
+        if let Some(shared) = &self.shared_metrics {
+            *shared.lock().unwrap_or_else(|e| e.into_inner()) = self.monitor.metrics().clone();
+        }
+    }
+
+    /// Report a node's latency and let the backpressure strategy resize
+    /// the ready queue
+    fn adapt_backpressure(&mut self, latency: u64) {
+        let metrics = self.monitor.metrics_mut();
+        metrics.record_execution();
+        metrics.record_latency(latency);
+        if let Some(limit) = self.backpressure.adapt(self.monitor.metrics()) {
+            self.scheduler.set_queue_limit(Some(limit), self.backpressure.overflow());
+        }
+    }
+
+    /// Get execution metrics
+    #[must_use]
+    pub fn metrics(&self) -> &crate::monitor::Metrics {
+        self.monitor.metrics()
+    }
+
+    /// Capabilities a node runs with
+    ///
+    /// A node's grant never exceeds the run-wide capability set.
+    fn node_capabilities(&self, node_id: NodeId) -> CapabilitySet {
+        match &self.config.grants {
+            Some(grants) => grants
+                .for_node(node_id)
+                .iter()
+                .filter(|cap| self.config.capabilities.covers(cap))
+                .cloned()
+                .collect(),
+            None => self.config.capabilities.clone(),
+        }
+    }
+
+    /// Build the execution context a node runs in
+    fn node_context(&self, node_id: NodeId) -> ExecutionContext {
         let mut ctx = ExecutionContext::new(
             self.run_id,
             node_id,
-            time,
-            self.config.capabilities.clone(),
+            self.scheduler.time(),
+            self.node_capabilities(node_id),
         );
-
-        // Add inputs from completed dependencies
-        if let Some(deps) = self.scheduler.nodes().iter().find(|&&id| id == node_id) {
-            // This is a simplified version - in real implementation we'd track dependencies
+        for (name, data) in &self.config.inputs {
+            ctx.bind(name.clone(), data.clone());
         }
-
-        // Set parent event
         if let Some(parent_id) = self.last_event_id {
             ctx = ctx.with_parent(parent_id);
         }
+        ctx.with_cancellation(self.cancellation.clone())
+    }
 
-        // Execute with events
-        let (start_event, end_event, result) = self.executor.execute_with_events(&ctx)?;
+    /// Kind of a node added from a DAG
+    fn node_kind(&self, node_id: NodeId) -> Option<&NodeKind> {
+        self.dag_nodes.get(&node_id).map(|node| &node.kind)
+    }
 
-        // Get event ID before moving
-        let end_event_id = end_event.event_id;
+    /// Execute a single node
+    ///
+    /// Conditionals always evaluate their predicate and map-reduce nodes
+    /// always fan out; other kinds run on the executor only when a tool
+    /// registry is configured.
+    fn execute_node(&mut self, node_id: NodeId) -> CoreResult<()> {
+        if self.subworkflows.contains_key(&node_id) {
+            return self.execute_subworkflow(node_id);
+        }
+        if let Some((parent, item)) = self.map_items.get(&node_id).cloned() {
+            return self.execute_map_item(node_id, parent, item);
+        }
+        if matches!(self.node_kind(node_id), Some(NodeKind::MapReduce { .. })) {
+            return self.execute_map_reduce(node_id);
+        }
+
+        let ctx = self.node_context(node_id);
+        let node = self.dag_nodes.get(&node_id).filter(|node| {
+            self.config.tools.is_some() || matches!(node.kind, NodeKind::Conditional { .. })
+        });
+        let (events, result) = match node {
+            Some(node) => {
+                let ctx = ctx.with_required_capabilities(node.capabilities.clone());
+                self.executor.execute_node(&ctx, &node.kind)?
+            }
+            None => {
+                let (start_event, end_event, result) = self.executor.execute_with_events(&ctx)?;
+                (vec![start_event, end_event], result)
+            }
+        };
+        self.settle(node_id, events, result)
+    }
 
-        // Record events
-        self.events.push(start_event);
-        self.events.push(end_event);
-        self.last_event_id = Some(end_event_id);
+    /// Record a node's events and report its result to the scheduler
+    ///
+    /// A conditional completes with the outcome it evaluated to, and a
+    /// `NodeSkipped` event is recorded for each node of the branch not taken.
+    fn settle(
+        &mut self,
+        node_id: NodeId,
+        events: Vec<Event>,
+        result: ExecutorResult,
+    ) -> CoreResult<()> {
+        for event in events {
+            self.last_event_id = Some(event.event_id);
+            self.record(event);
+        }
 
-        // Handle result
         match result {
-            ExecutorResult::Success { output, output_hash } => {
+            ExecutorResult::Success { output, output_hash, effects } => {
+                let conditional =
+                    matches!(self.node_kind(node_id), Some(NodeKind::Conditional { .. }));
+                let outcome = conditional.then(|| output == b"true");
                 self.outputs.insert(node_id, NodeOutput {
                     node_id,
                     output,
                     output_hash,
+                    effects,
                 });
-                self.scheduler.mark_complete(node_id)?;
+                match outcome {
+                    Some(outcome) => {
+                        let before = self.scheduler.skipped_nodes().clone();
+                        self.scheduler.complete_condition(node_id, outcome)?;
+                        let skipped: Vec<NodeId> = self
+                            .scheduler
+                            .skipped_nodes()
+                            .difference(&before)
+                            .copied()
+                            .collect();
+                        self.record_skipped(skipped);
+                    }
+                    None => self.scheduler.mark_complete(node_id)?,
+                }
             }
             ExecutorResult::Failed { error } => {
                 self.scheduler.mark_failed(node_id)?;
@@ -228,6 +699,234 @@ impl ExecutionEngine {
         Ok(())
     }
 
+    /// Record a `NodeSkipped` event for each node in `skipped`
+    fn record_skipped(&mut self, skipped: Vec<NodeId>) {
+        for node_id in skipped {
+            let mut event = Event::new(
+                self.executor.next_event_id(),
+                self.run_id,
+                node_id,
+                self.scheduler.time(),
+                EventKind::NodeSkipped,
+            );
+            if let Some(parent_id) = self.last_event_id {
+                event = event.with_parent(parent_id);
+            }
+            self.last_event_id = Some(event.event_id);
+            self.record(event);
+        }
+    }
+
+    /// Run a map-reduce node
+    ///
+    /// The first time the node is scheduled it fans out into one item per
+    /// element of its input list, each running the node's tool on its
+    /// element. Once every item has completed the node is scheduled again
+    /// and aggregates their outputs, in item order, into its own.
+    fn execute_map_reduce(&mut self, node_id: NodeId) -> CoreResult<()> {
+        let node = self.dag_nodes[&node_id].clone();
+        let NodeKind::MapReduce { input_binding, max_parallel, aggregate, .. } = &node.kind else {
+            return Err(CoreError::Validation {
+                field: format!("node {:?}", node_id),
+                reason: "not a map-reduce node".to_string(),
+            });
+        };
+        let ctx = self
+            .node_context(node_id)
+            .with_required_capabilities(node.capabilities.clone());
+
+        if let Some(items) = self.scheduler.fan_out_items(node_id) {
+            let result = items
+                .iter()
+                .map(|item| {
+                    let output = self.outputs.get(item).map_or(&[][..], |o| &o.output);
+                    serde_json::from_slice(output)
+                })
+                .collect::<Result<Vec<serde_json::Value>, _>>()
+                .map_err(|e| format!("Map item output is not JSON: {}", e))
+                .and_then(|results| aggregate.apply(results).map_err(|e| e.to_string()))
+                .and_then(|value| serde_json::to_vec(&value).map_err(|e| e.to_string()));
+            let result = match result {
+                Ok(output) => ExecutorResult::Success {
+                    output_hash: cathedral_core::Hash::compute(&output),
+                    output,
+                    effects: Vec::new(),
+                },
+                Err(error) => ExecutorResult::Failed { error },
+            };
+
+            let mut end = self.executor.create_complete_event(&ctx, &result);
+            if let ExecutorResult::Success { output, .. } = &result {
+                end = end.with_payload(output.clone());
+            }
+            if let Some(parent_id) = self.last_event_id {
+                end = end.with_parent(parent_id);
+            }
+            return self.settle(node_id, vec![end], result);
+        }
+
+        if ctx.is_cancelled() {
+            return Err(CoreError::Cancelled);
+        }
+        let (mut events, missing) = self.executor.gate_capabilities(&ctx)?;
+        if !missing.is_empty() {
+            let result = ExecutorResult::Skipped { missing };
+            events.push(self.executor.create_complete_event(&ctx, &result));
+            return self.settle(node_id, events, result);
+        }
+
+        let elements = match ctx.bindings.get(input_binding) {
+            Some(data) => serde_json::from_slice::<Vec<serde_json::Value>>(data)
+                .map_err(|e| format!("Input {} is not a JSON list: {}", input_binding, e)),
+            None => Err(format!("Unbound input {} for map", input_binding)),
+        };
+        let elements = match elements {
+            Ok(elements) => elements,
+            Err(error) => {
+                let result = ExecutorResult::Failed { error };
+                events.push(self.executor.create_complete_event(&ctx, &result));
+                return self.settle(node_id, events, result);
+            }
+        };
+
+        events.push(self.executor.create_start_event(&ctx));
+        for event in events {
+            self.last_event_id = Some(event.event_id);
+            self.record(event);
+        }
+        let items = self.scheduler.fan_out(node_id, elements.len(), *max_parallel)?;
+        for (item, element) in items.into_iter().zip(elements) {
+            let element = serde_json::to_vec(&element).map_err(|e| CoreError::Validation {
+                field: input_binding.clone(),
+                reason: e.to_string(),
+            })?;
+            self.map_items.insert(item, (node_id, element));
+        }
+        self.time = self.time.saturating_add(1);
+        Ok(())
+    }
+
+    /// Run one item of a map-reduce node's fan-out
+    ///
+    /// The item runs the node's tool with its element bound in place of
+    /// the input list. The node's capabilities were gated when it fanned out.
+    fn execute_map_item(
+        &mut self,
+        item: NodeId,
+        parent: NodeId,
+        element: Vec<u8>,
+    ) -> CoreResult<()> {
+        let NodeKind::MapReduce { name, version_req, input_binding, .. } =
+            self.dag_nodes[&parent].kind.clone()
+        else {
+            return Err(CoreError::Validation {
+                field: format!("node {:?}", parent),
+                reason: "not a map-reduce node".to_string(),
+            });
+        };
+        let mut ctx = self.node_context(item);
+        ctx.capabilities = self.node_capabilities(parent);
+        ctx.bind(input_binding.clone(), element);
+        if ctx.is_cancelled() {
+            return Err(CoreError::Cancelled);
+        }
+
+        let (events, result) =
+            self.executor.execute_tool(&ctx, &name, &version_req, Some(&input_binding))?;
+        self.settle(item, events, result)
+    }
+
+    /// Run a sub-workflow node's DAG in a child engine
+    ///
+    /// The child shares this run's ID and its events are chained between
+    /// the node's start and end events, so they nest under the parent run
+    /// in the log. It runs with the node's capabilities and whatever tick
+    /// budget the parent has left. The node's output is the output of the
+    /// last node the sub-workflow completed.
+    ///
+    /// The child shares this run's cancellation token. If it is cancelled
+    /// the node is left in flight, to be cancelled with the rest of the run.
+    fn execute_subworkflow(&mut self, node_id: NodeId) -> CoreResult<()> {
+        let (hash, dag) = self.subworkflows[&node_id].clone();
+        let time = self.scheduler.time();
+
+        let start_id = self.executor.next_event_id();
+        let mut start =
+            Event::new(start_id, self.run_id, node_id, time, EventKind::NodeStarted)
+                .with_payload(hash.to_hex().into_bytes());
+        if let Some(parent_id) = self.last_event_id {
+            start = start.with_parent(parent_id);
+        }
+
+        let config = EngineConfig {
+            max_ticks: self.config.max_ticks.saturating_sub(self.time.as_u64()),
+            capabilities: self.node_capabilities(node_id),
+            grants: None,
+            // The parent was charged for the whole sub-workflow on admission
+            budget: ResourceBudget::default(),
+            ..self.config.clone()
+        };
+        let mut child = ExecutionEngine::new(self.run_id, config)
+            .with_cancellation(self.cancellation.clone());
+        child.nested = true;
+        child.last_event_id = Some(start.event_id);
+        child.add_dag(&dag)?;
+        let status = if dag.is_empty() {
+            ExecutionStatus::Success
+        } else {
+            child.run()?
+        };
+
+        if status == ExecutionStatus::Cancelled {
+            self.record(start);
+            self.last_event_id = child.last_event_id;
+            std::mem::take(&mut child.events).into_iter().for_each(|event| self.record(event));
+            self.time = self.time.saturating_add(child.time.as_u64());
+            return Ok(());
+        }
+
+        let kind = if status == ExecutionStatus::Success {
+            EventKind::NodeCompleted
+        } else {
+            EventKind::NodeFailed
+        };
+        let end_id = self.executor.next_event_id();
+        let end = Event::new(end_id, self.run_id, node_id, time.saturating_add(1), kind)
+            .with_parent(child.last_event_id.unwrap_or(start.event_id));
+
+        self.record(start);
+        std::mem::take(&mut child.events).into_iter().for_each(|event| self.record(event));
+        self.last_event_id = Some(end.event_id);
+        self.record(end);
+        self.time = self.time.saturating_add(child.time.as_u64()).saturating_add(1);
+
+        if status != ExecutionStatus::Success {
+            self.scheduler.mark_failed(node_id)?;
+            return Err(CoreError::Validation {
+                field: format!("node {:?}", node_id),
+                reason: format!("sub-workflow {} ended with {:?}", hash, status),
+            });
+        }
+
+        let output = child.outputs.values().last().map_or_else(Vec::new, |o| o.output.clone());
+        self.outputs.insert(node_id, NodeOutput {
+            node_id,
+            output_hash: cathedral_core::Hash::compute(&output),
+            output,
+            effects: child.outputs.values().flat_map(|o| o.effects.clone()).collect(),
+        });
+        self.scheduler.mark_complete(node_id)?;
+        Ok(())
+    }
+
+    /// Append an event to the log and hand it to the sink
+    fn record(&mut self, event: Event) {
+        if let Some(sink) = &self.sink {
+            sink(&event);
+        }
+        self.events.push(event);
+    }
+
     /// Get all events from execution
     #[must_use]
     pub fn events(&self) -> &[Event] {
@@ -269,8 +968,18 @@ impl ExecutionEngine {
     }
 
     /// Reset the engine for re-execution
+    ///
+    /// A cancelled run gets a fresh cancellation token.
     pub fn reset(&mut self) {
+        if self.cancellation.is_cancelled() {
+            self.cancellation = CancellationToken::new();
+        }
+        self.backpressure = BackpressureController::with_strategy(self.backpressure.strategy());
+        self.scheduler
+            .set_queue_limit(self.backpressure.queue_limit(), self.backpressure.overflow());
         self.scheduler.reset();
+        self.monitor.reset();
+        self.ready_since.clear();
         self.outputs.clear();
         self.events.clear();
         self.time = LogicalTime::zero();
@@ -342,7 +1051,40 @@ mod tests {
 
         let result = engine.run().unwrap();
         assert_eq!(result, ExecutionStatus::Success);
-        assert_eq!(engine.events().len(), 4); // 2 start + 2 complete
+        assert_eq!(engine.events().len(), 5); // header + 2 start + 2 complete
+    }
+
+    #[test]
+    fn test_engine_event_sink_sees_every_event() {
+        let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink: EventSink = {
+            let seen = Arc::clone(&seen);
+            Arc::new(move |event: &Event| seen.lock().unwrap().push(event.clone()))
+        };
+        let mut engine =
+            ExecutionEngine::new(make_test_run(), EngineConfig::default()).with_event_sink(sink);
+        engine.add_node(make_test_node(), IndexSet::new()).unwrap();
+        engine.add_node(make_test_node(), IndexSet::new()).unwrap();
+
+        engine.run().unwrap();
+        assert_eq!(*seen.lock().unwrap(), engine.events());
+    }
+
+    #[test]
+    fn test_engine_publishes_scheduling_metrics() {
+        let shared = Arc::new(Mutex::new(Metrics::new()));
+        let mut engine = ExecutionEngine::new(make_test_run(), EngineConfig::default())
+            .with_shared_metrics(Arc::clone(&shared));
+        engine.add_node(make_test_node(), IndexSet::new()).unwrap();
+        engine.add_node(make_test_node(), IndexSet::new()).unwrap();
+
+        engine.run().unwrap();
+        let metrics = shared.lock().unwrap().clone();
+        assert_eq!(metrics.nodes_executed, 2);
+        assert_eq!(metrics.queue_depth, 0);
+        // Both nodes are ready at once; the second waits for the first
+        assert_eq!(metrics.scheduling_latency.count(), 2);
+        assert_eq!(metrics.scheduling_latency.sum(), 1);
     }
 
     #[test]
@@ -361,6 +1103,66 @@ mod tests {
         assert_eq!(result, ExecutionStatus::Success);
     }
 
+    #[test]
+    fn test_engine_cancel() {
+        let mut engine = ExecutionEngine::new(make_test_run(), EngineConfig::default());
+        let (a, b, c) = (make_test_node(), make_test_node(), make_test_node());
+        engine.add_node(a, IndexSet::new()).unwrap();
+        engine.add_node(b, [a].into_iter().collect()).unwrap();
+        engine.add_node(c, [b].into_iter().collect()).unwrap();
+
+        assert!(engine.cancel(make_test_run()).is_err());
+        let token = engine.cancellation_token();
+        engine.cancel(engine.run_id()).unwrap();
+        assert!(token.is_cancelled());
+
+        // The ready node is cancelled, what it feeds is skipped
+        let events = engine.events();
+        let nodes: Vec<_> = events.iter().map(|e| (e.node_id, e.kind)).collect();
+        assert_eq!(
+            nodes,
+            [
+                (a, EventKind::NodeCancelled),
+                (b, EventKind::NodeSkipped),
+                (c, EventKind::NodeSkipped),
+                (RUN_NODE, EventKind::RunCancelled),
+            ]
+        );
+        assert!(events.windows(2).all(|w| w[1].parent_event_id == Some(w[0].event_id)));
+
+        // Later calls record nothing more
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::Cancelled);
+        engine.cancel(engine.run_id()).unwrap();
+        assert_eq!(engine.events().len(), 4);
+
+        engine.reset();
+        assert!(!engine.cancellation_token().is_cancelled());
+    }
+
+    #[test]
+    fn test_engine_cancelled_by_shared_token() {
+        let token = CancellationToken::new();
+        let mut engine = ExecutionEngine::new(make_test_run(), EngineConfig::default())
+            .with_cancellation(token.clone());
+        let (a, b) = (make_test_node(), make_test_node());
+        engine.add_node(a, IndexSet::new()).unwrap();
+        engine.add_node(b, IndexSet::new()).unwrap();
+
+        token.cancel();
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::Cancelled);
+        assert!(engine.outputs().is_empty());
+        let kinds: Vec<_> = engine.events().iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::RunCreated,
+                EventKind::NodeCancelled,
+                EventKind::NodeCancelled,
+                EventKind::RunCancelled,
+            ]
+        );
+    }
+
     #[test]
     fn test_engine_reset() {
         let mut engine = ExecutionEngine::new(make_test_run(), EngineConfig::default());
@@ -378,6 +1180,28 @@ mod tests {
         assert!(engine.events().is_empty());
     }
 
+    #[test]
+    fn test_engine_run_empty() {
+        let mut engine = ExecutionEngine::new(make_test_run(), EngineConfig::default());
+
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::Success);
+
+        let events = engine.events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, EventKind::RunCreated);
+        assert_eq!(events[1].kind, EventKind::RunCompleted);
+        assert!(events[1].is_terminal());
+        assert_eq!(events[1].parent_event_id, Some(events[0].event_id));
+
+        let mut validator = cathedral_log::ChainValidator::new();
+        for event in events {
+            assert_eq!(event.run_id, engine.run_id());
+            validator
+                .validate(event.prior_state_hash, event.post_state_hash.unwrap())
+                .unwrap();
+        }
+    }
+
     #[test]
     fn test_engine_timeout() {
         let config = EngineConfig {
@@ -396,10 +1220,350 @@ mod tests {
         engine.add_node(node1, IndexSet::new()).unwrap();
         engine.add_node(node2, deps).unwrap();
 
-        // Run the engine - may timeout or complete
-        let _result = engine.run();
-
-        // The engine should have run at least one node
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::Timeout);
         assert!(engine.time().as_u64() >= 1);
+
+        // The node that never ran is skipped and the run ends failed
+        let events = engine.events();
+        let nodes: Vec<_> = events[3..].iter().map(|e| (e.node_id, e.kind)).collect();
+        assert_eq!(nodes, [(node2, EventKind::NodeSkipped), (RUN_NODE, EventKind::RunFailed)]);
+        let failed = events.last().unwrap();
+        assert!(failed.is_terminal());
+        assert_eq!(failed.payload, b"Execution timeout");
+        assert!(events[2..].windows(2).all(|w| w[1].parent_event_id == Some(w[0].event_id)));
+
+        // Later calls record nothing more
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::Timeout);
+        engine.cancel(engine.run_id()).unwrap();
+        assert_eq!(engine.events().len(), 5);
+    }
+
+    fn dag_node(kind: NodeKind) -> cathedral_plan::Node {
+        cathedral_plan::Node {
+            id: make_test_node(),
+            kind,
+            dependencies: IndexSet::new(),
+            capabilities: Vec::new(),
+            resources: cathedral_plan::dag::ResourceRequirements::new(),
+        }
+    }
+
+    fn tool_kind(name: &str) -> NodeKind {
+        NodeKind::Tool {
+            name: name.to_string(),
+            version_req: "*".to_string(),
+            input_binding: None,
+        }
+    }
+
+    #[test]
+    fn test_engine_runs_tool_nodes_with_seeded_ids() {
+        let tools = echo_registry();
+        let mut dag = Dag::new();
+        dag.add_node(dag_node(NodeKind::Tool {
+            name: "echo".to_string(),
+            version_req: "*".to_string(),
+            input_binding: Some("data".to_string()),
+        }))
+        .unwrap();
+
+        let run_id = make_test_run();
+        let run = || {
+            let config = EngineConfig {
+                tools: Some(Arc::clone(&tools)),
+                inputs: [("data".to_string(), br#"{"n":1}"#.to_vec())].into_iter().collect(),
+                event_ids: Some(SeededEventIds::new(3)),
+                ..Default::default()
+            };
+            let mut engine = ExecutionEngine::new(run_id, config);
+            engine.add_dag(&dag).unwrap();
+            assert_eq!(engine.run().unwrap(), ExecutionStatus::Success);
+            engine.events().to_vec()
+        };
+
+        let events = run();
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [EventKind::RunCreated, EventKind::ToolInvoked, EventKind::ToolCompleted]
+        );
+        assert_eq!(events[2].payload, br#"{"n":1}"#);
+        assert_eq!(events[0].event_id, SeededEventIds::new(3).next_id());
+        assert_eq!(run(), events);
+    }
+
+    /// Registry holding an `echo` tool that accepts any JSON
+    fn echo_registry() -> Arc<SharedRegistry> {
+        use cathedral_tool::adapter::builtin::EchoTool;
+        use cathedral_tool::{InputSchema, OutputSchema, ToolSchema};
+
+        let tools = Arc::new(SharedRegistry::new());
+        let schema = ToolSchema::new("echo".to_string(), "1.0.0".to_string())
+            .with_input(InputSchema::new().with_json_schema("{}".to_string()))
+            .with_output(OutputSchema::new().with_json_schema("{}".to_string()));
+        tools.register(Arc::new(EchoTool), schema).unwrap();
+        tools
+    }
+
+    /// Compile `source` and run it with the echo registry and `inputs`
+    fn run_source(source: &str, inputs: &[(&str, &[u8])]) -> (Dag, ExecutionEngine) {
+        let ast = cathedral_plan::parse(source).unwrap();
+        let dag = cathedral_plan::Compiler::new().compile(&ast).unwrap().dag;
+        let config = EngineConfig {
+            tools: Some(echo_registry()),
+            inputs: inputs.iter().map(|(k, v)| ((*k).to_string(), v.to_vec())).collect(),
+            ..Default::default()
+        };
+        let mut engine = ExecutionEngine::new(make_test_run(), config);
+        engine.add_dag(&dag).unwrap();
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::Success);
+        (dag, engine)
+    }
+
+    #[test]
+    fn test_engine_runs_only_the_taken_branch() {
+        let source = "input mode: string\n\
+                      if mode == \"fast\"\n\
+                      tool \"echo\" <- mode\n\
+                      else\n\
+                      tool \"echo\" <- mode\n\
+                      end\n";
+        for (mode, taken, not_taken) in [
+            (&br#""fast""#[..], cathedral_plan::THEN_PORT, cathedral_plan::ELSE_PORT),
+            (&br#""slow""#[..], cathedral_plan::ELSE_PORT, cathedral_plan::THEN_PORT),
+        ] {
+            let (dag, engine) = run_source(source, &[("mode", mode)]);
+            let branch = |port: &str| {
+                dag.edges.iter().find(|e| e.from_port.as_deref() == Some(port)).unwrap().to
+            };
+            let kinds_of = |node_id: NodeId| -> Vec<EventKind> {
+                engine
+                    .events()
+                    .iter()
+                    .filter(|e| e.node_id == node_id)
+                    .map(|e| e.kind)
+                    .collect()
+            };
+
+            assert_eq!(kinds_of(branch(taken)), [EventKind::ToolInvoked, EventKind::ToolCompleted]);
+            assert_eq!(kinds_of(branch(not_taken)), [EventKind::NodeSkipped]);
+            let cond = dag.edges.iter().find(|e| e.from_port.is_some()).unwrap().from;
+            let outcome = (taken == cathedral_plan::THEN_PORT).to_string();
+            assert_eq!(engine.outputs()[&cond].output, outcome.into_bytes());
+        }
+    }
+
+    #[test]
+    fn test_engine_maps_and_reduces() {
+        let (dag, engine) = run_source(
+            "input items: list<integer>\n\
+             map \"echo\" over items parallel 2 reduce sum\n",
+            &[("items", b"[1, 2, 3]")],
+        );
+        let (&map_id, _) = dag
+            .nodes
+            .iter()
+            .find(|(_, n)| matches!(n.kind, NodeKind::MapReduce { .. }))
+            .unwrap();
+
+        assert_eq!(engine.outputs()[&map_id].output, b"6");
+        let mut invoked: Vec<_> = engine
+            .events()
+            .iter()
+            .filter(|e| e.kind == EventKind::ToolInvoked)
+            .map(|e| e.payload.clone())
+            .collect();
+        // Items run in scheduler order; the sum aggregates them in item order
+        invoked.sort();
+        assert_eq!(invoked, [b"1".to_vec(), b"2".to_vec(), b"3".to_vec()]);
+        let completed = engine
+            .events()
+            .iter()
+            .find(|e| e.node_id == map_id && e.kind == EventKind::NodeCompleted)
+            .unwrap();
+        assert_eq!(completed.payload, b"6");
+    }
+
+    #[test]
+    fn test_engine_runs_sub_workflow() {
+        let mut child = Dag::new();
+        let (first, second) = (dag_node(tool_kind("a")), dag_node(tool_kind("b")));
+        let (first_id, second_id) = (first.id, second.id);
+        child.add_node(first).unwrap();
+        child.add_node(second).unwrap();
+        child.add_edge(cathedral_plan::Edge::new(first_id, second_id)).unwrap();
+        let mut library = WorkflowLibrary::new();
+        let hash = library.insert(child.clone());
+
+        let mut parent = Dag::new();
+        let sub = dag_node(NodeKind::SubWorkflow {
+            hash,
+            contract: child.resource_contract(),
+            input_binding: None,
+        });
+        let sub_id = sub.id;
+        parent.add_node(sub).unwrap();
+
+        // Without the library the invoked workflow cannot be resolved
+        let mut engine = ExecutionEngine::new(make_test_run(), EngineConfig::default());
+        assert!(engine.add_dag(&parent).is_err());
+
+        let config = EngineConfig {
+            workflows: Some(Arc::new(library)),
+            ..Default::default()
+        };
+        let mut engine = ExecutionEngine::new(make_test_run(), config);
+        engine.add_dag(&parent).unwrap();
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::Success);
+
+        // Child events nest between the sub-workflow node's start and end
+        let events = &engine.events()[1..];
+        let nodes: Vec<_> = events.iter().map(|e| (e.node_id, e.kind)).collect();
+        assert_eq!(
+            nodes,
+            [
+                (sub_id, EventKind::NodeStarted),
+                (first_id, EventKind::NodeStarted),
+                (first_id, EventKind::NodeCompleted),
+                (second_id, EventKind::NodeStarted),
+                (second_id, EventKind::NodeCompleted),
+                (sub_id, EventKind::NodeCompleted),
+            ]
+        );
+        assert!(events.iter().all(|e| e.run_id == engine.run_id()));
+        assert_eq!(events[1].parent_event_id, Some(events[0].event_id));
+        assert_eq!(events[5].parent_event_id, Some(events[4].event_id));
+        assert_eq!(events[0].payload, hash.to_hex().into_bytes());
+        assert!(engine.get_output(sub_id).is_some());
+    }
+
+    #[test]
+    fn test_engine_refuses_nodes_over_budget() {
+        use cathedral_plan::dag::ResourceRequirements;
+
+        let mut dag = Dag::new();
+        let mut small = dag_node(tool_kind("a"));
+        small.resources = ResourceRequirements::new().with_max_memory(300);
+        let mut large = dag_node(tool_kind("b"));
+        large.resources = ResourceRequirements::new().with_max_memory(300);
+        let (small_id, large_id) = (small.id, large.id);
+        dag.add_node(small).unwrap();
+        dag.add_node(large).unwrap();
+        dag.add_edge(cathedral_plan::Edge::new(small_id, large_id)).unwrap();
+
+        let config = EngineConfig {
+            budget: ResourceBudget::new().with_memory(500),
+            ..Default::default()
+        };
+        let mut engine = ExecutionEngine::new(make_test_run(), config);
+        engine.add_dag(&dag).unwrap();
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::PartialFailure);
+
+        let events = &engine.events()[1..];
+        let kinds: Vec<_> = events.iter().map(|e| (e.node_id, e.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (small_id, EventKind::NodeStarted),
+                (small_id, EventKind::NodeCompleted),
+                (large_id, EventKind::BudgetExceeded),
+            ]
+        );
+        assert_eq!(events[2].parent_event_id, Some(events[1].event_id));
+        let exceeded: crate::budget::BudgetExceeded =
+            serde_json::from_slice(&events[2].payload).unwrap();
+        assert_eq!((exceeded.resource.as_str(), exceeded.requested), ("memory", 300));
+        assert_eq!(exceeded.remaining, 200);
+    }
+
+    #[test]
+    fn test_engine_sheds_with_drop_oldest() {
+        let config = EngineConfig {
+            backpressure: BackpressureStrategy::DropOldest { capacity: 1 },
+            ..Default::default()
+        };
+        let mut engine = ExecutionEngine::new(make_test_run(), config);
+        let (first, second) = (make_test_node(), make_test_node());
+        engine.add_node(first, IndexSet::new()).unwrap();
+        engine.add_node(second, IndexSet::new()).unwrap();
+        assert_eq!(engine.run().unwrap(), ExecutionStatus::Success);
+
+        let kinds: Vec<_> = engine.events().iter().map(|e| (e.node_id, e.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (RUN_NODE, EventKind::RunCreated),
+                (first, EventKind::Shed),
+                (second, EventKind::NodeStarted),
+                (second, EventKind::NodeCompleted),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_engine_adaptive_backpressure_replays() {
+        use crate::backpressure::AimdConfig;
+
+        let mut child = Dag::new();
+        child.add_node(dag_node(tool_kind("a"))).unwrap();
+        let mut library = WorkflowLibrary::new();
+        let hash = library.insert(child.clone());
+
+        // Sub-workflows take two ticks, over the target, so the window shrinks.
+        // Fixed IDs pin the scheduler's tie-break order between the nodes.
+        let mut parent = Dag::new();
+        for index in 0..6u8 {
+            let mut node = if index % 2 == 0 {
+                dag_node(NodeKind::SubWorkflow {
+                    hash,
+                    contract: child.resource_contract(),
+                    input_binding: None,
+                })
+            } else {
+                dag_node(tool_kind("b"))
+            };
+            node.id = NodeId::from_bytes([index + 1; 16]);
+            parent.add_node(node).unwrap();
+        }
+        let config = EngineConfig {
+            workflows: Some(Arc::new(library)),
+            backpressure: BackpressureStrategy::Adaptive(AimdConfig::new(4, 1)),
+            ..Default::default()
+        };
+
+        let run = || {
+            let mut engine = ExecutionEngine::new(make_test_run(), config.clone());
+            engine.add_dag(&parent).unwrap();
+            assert_eq!(engine.run().unwrap(), ExecutionStatus::Success);
+            assert_eq!(engine.metrics().nodes_executed, 6);
+            let order: Vec<_> = engine.events().iter().map(|e| (e.node_id, e.kind)).collect();
+            (order, engine.scheduler.queue_limit())
+        };
+        let (order, limit) = run();
+        assert!(limit < Some(4));
+        assert_eq!(run(), (order, limit));
+    }
+
+    #[test]
+    fn test_engine_node_capabilities_use_grants() {
+        use cathedral_core::Capability;
+
+        let fs_read = |prefix: &str| Capability::FsRead {
+            prefixes: vec![prefix.to_string()],
+        };
+        let node = make_test_node();
+        let mut grants = CapabilityGrantTable::new();
+        grants.grant(node, [fs_read("./data"), Capability::IdGen].into_iter().collect());
+        let config = EngineConfig {
+            capabilities: [fs_read("."), Capability::ClockRead].into_iter().collect(),
+            grants: Some(grants),
+            ..Default::default()
+        };
+        let engine = ExecutionEngine::new(make_test_run(), config);
+
+        // The grant is capped by the run-wide set
+        let granted: Vec<_> = engine.node_capabilities(node).iter().cloned().collect();
+        assert_eq!(granted, [fs_read("./data")]);
+        assert!(engine.node_capabilities(make_test_node()).is_empty());
     }
 }
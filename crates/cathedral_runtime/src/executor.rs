@@ -3,11 +3,24 @@
 //! Executes individual nodes with full capability enforcement.
 
 use cathedral_core::{NodeId, RunId, EventId, LogicalTime, Hash, Capability, CapabilitySet, CoreResult, CoreError};
+use cathedral_core::CancellationToken;
 use cathedral_log::{Event, EventKind};
-use std::collections::HashMap;
+use cathedral_plan::{NodeKind, Predicate};
+use cathedral_policy::{CompiledPolicy, DecisionProof, EvalContext, ProofKind};
+use cathedral_tool::adapter::HostAdapter;
+use cathedral_tool::registry::SharedRegistry;
+use cathedral_tool::SideEffect;
+use cathedral_wasm::HostCallRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Policy ID recorded on proofs decided by the capability set alone
+const CAPABILITY_SET_POLICY: &str = "capability-set";
 
 /// Result of node execution
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ExecutorResult {
     /// Execution succeeded
     Success {
@@ -15,6 +28,8 @@ pub enum ExecutorResult {
         output: Vec<u8>,
         /// Output hash
         output_hash: Hash,
+        /// Side effects the node performed, in the order they were applied
+        effects: Vec<SideEffect>,
     },
     /// Execution failed
     Failed {
@@ -28,6 +43,25 @@ pub enum ExecutorResult {
     },
 }
 
+impl ExecutorResult {
+    /// Get the side effects applied by a successful execution
+    #[must_use]
+    pub fn effects(&self) -> &[SideEffect] {
+        match self {
+            Self::Success { effects, .. } => effects,
+            _ => &[],
+        }
+    }
+
+    /// Describe the applied side effects in the form recorded in traces
+    ///
+    /// Replay compares these against the effects it reconstructs.
+    #[must_use]
+    pub fn effect_descriptions(&self) -> Vec<String> {
+        self.effects().iter().map(SideEffect::describe).collect()
+    }
+}
+
 /// Executor error
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ExecutorError {
@@ -68,6 +102,12 @@ pub struct ExecutionContext {
     pub capabilities: CapabilitySet,
     /// Input data from dependencies
     pub inputs: HashMap<NodeId, Vec<u8>>,
+    /// Named values available to tool input bindings
+    pub bindings: BTreeMap<String, Vec<u8>>,
+    /// Capabilities the node must be granted before it runs
+    pub required_capabilities: Vec<Capability>,
+    /// Token cancelling the run this node belongs to
+    pub cancellation: Option<CancellationToken>,
 }
 
 impl ExecutionContext {
@@ -86,6 +126,9 @@ impl ExecutionContext {
             parent_event_id: None,
             capabilities,
             inputs: HashMap::new(),
+            bindings: BTreeMap::new(),
+            required_capabilities: Vec::new(),
+            cancellation: None,
         }
     }
 
@@ -95,11 +138,34 @@ impl ExecutionContext {
         self
     }
 
+    /// Set the capabilities gated before execution
+    pub fn with_required_capabilities(mut self, required: Vec<Capability>) -> Self {
+        self.required_capabilities = required;
+        self
+    }
+
+    /// Set the token that cancels this node's run
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Check whether the node's run was cancelled
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+
     /// Add input from a dependency
     pub fn add_input(&mut self, from: NodeId, data: Vec<u8>) {
         self.inputs.insert(from, data);
     }
 
+    /// Bind a named value for tool input bindings
+    pub fn bind(&mut self, name: impl Into<String>, data: Vec<u8>) {
+        self.bindings.insert(name.into(), data);
+    }
+
     /// Check if a capability is granted
     #[must_use]
     pub fn has_capability(&self, capability: &Capability) -> bool {
@@ -107,6 +173,39 @@ impl ExecutionContext {
     }
 }
 
+/// Source of event IDs derived from a seed
+///
+/// Each ID pairs the seed with a counter shared by every clone, so
+/// executors drawing from one source never repeat an ID and two sources
+/// seeded alike yield the same sequence.
+#[derive(Debug, Clone)]
+pub struct SeededEventIds {
+    /// Seed forming the first half of every ID
+    seed: u64,
+    /// Number of IDs drawn so far
+    counter: Arc<AtomicU64>,
+}
+
+impl SeededEventIds {
+    /// Create a source for `seed`
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            counter: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Draw the next ID
+    #[must_use]
+    pub fn next_id(&self) -> EventId {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.seed.to_be_bytes());
+        bytes[8..].copy_from_slice(&self.counter.fetch_add(1, Ordering::Relaxed).to_be_bytes());
+        EventId::from_bytes(bytes)
+    }
+}
+
 /// Executor for running individual nodes
 ///
 /// Each node is executed with full capability checking.
@@ -115,6 +214,12 @@ pub struct Executor {
     max_ticks: u64,
     /// Strict capability checking
     strict_capabilities: bool,
+    /// Registry used to resolve tool nodes
+    tools: Option<Arc<SharedRegistry>>,
+    /// Policy consulted by capability gates
+    policy: Option<CompiledPolicy>,
+    /// Source of event IDs; random IDs when unset
+    event_ids: Option<SeededEventIds>,
 }
 
 impl Executor {
@@ -124,9 +229,36 @@ impl Executor {
         Self {
             max_ticks: 1_000_000,
             strict_capabilities: true,
+            tools: None,
+            policy: None,
+            event_ids: None,
         }
     }
 
+    /// Set the registry used to resolve tool nodes
+    pub fn with_tools(mut self, tools: Arc<SharedRegistry>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Set the policy consulted by capability gates
+    pub fn with_policy(mut self, policy: CompiledPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Draw event IDs from `ids` instead of at random
+    pub fn with_event_ids(mut self, ids: SeededEventIds) -> Self {
+        self.event_ids = Some(ids);
+        self
+    }
+
+    /// Next ID for an event this executor emits
+    #[must_use]
+    pub fn next_event_id(&self) -> EventId {
+        self.event_ids.as_ref().map_or_else(EventId::new, SeededEventIds::next_id)
+    }
+
     /// Set maximum execution ticks
     pub fn with_max_ticks(mut self, max: u64) -> Self {
         self.max_ticks = max;
@@ -150,9 +282,288 @@ impl Executor {
         Ok(ExecutorResult::Success {
             output: Vec::new(),
             output_hash: Hash::empty(),
+            effects: Vec::new(),
         })
     }
 
+    /// Execute a node of the given kind
+    ///
+    /// The node's required capabilities are gated first, each gate emitting a
+    /// `PolicyDecision` event; a denied node is skipped. Tool nodes are
+    /// resolved against the registry and emit tool lifecycle events; other
+    /// kinds run through [`Executor::execute`] and emit node start/complete
+    /// events.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::Cancelled` if the run was cancelled before the
+    /// node started, or error if execution fails
+    pub fn execute_node(
+        &self,
+        ctx: &ExecutionContext,
+        kind: &NodeKind,
+    ) -> CoreResult<(Vec<Event>, ExecutorResult)> {
+        if ctx.is_cancelled() {
+            return Err(CoreError::Cancelled);
+        }
+        let (mut events, missing) = self.gate_capabilities(ctx)?;
+        if !missing.is_empty() {
+            let result = ExecutorResult::Skipped { missing };
+            events.push(self.create_complete_event(ctx, &result));
+            return Ok((events, result));
+        }
+
+        let (executed, result) = match kind {
+            NodeKind::Tool {
+                name,
+                version_req,
+                input_binding,
+            } => self.execute_tool(ctx, name, version_req, input_binding.as_deref())?,
+            NodeKind::Conditional {
+                input_binding,
+                predicate,
+            } => self.execute_condition(ctx, input_binding, predicate),
+            _ => {
+                let (start, end, result) = self.execute_with_events(ctx)?;
+                (vec![start, end], result)
+            }
+        };
+        events.extend(executed);
+        Ok((events, result))
+    }
+
+    /// Resolve and run a tool node with validated input and output
+    ///
+    /// Emits a `ToolInvoked` event carrying the input, a `HostCall` event
+    /// for each host call a sandboxed tool made, a `SideEffect` event
+    /// describing each effect the tool applied, and `ToolCompleted` carrying
+    /// the output or `ToolFailed` carrying the error.
+    /// Resolution, capability, and validation failures produce a failed
+    /// result rather than an error so they are recorded in the log. A tool
+    /// that can be interrupted is stopped when the context's run is
+    /// cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no tool registry is configured
+    pub fn execute_tool(
+        &self,
+        ctx: &ExecutionContext,
+        name: &str,
+        version_req: &str,
+        input_binding: Option<&str>,
+    ) -> CoreResult<(Vec<Event>, ExecutorResult)> {
+        let tools = self.tools.as_ref().ok_or_else(|| CoreError::Validation {
+            field: "tools".to_string(),
+            reason: format!("No tool registry configured to resolve {}", name),
+        })?;
+
+        let input = match input_binding {
+            Some(binding) => ctx.bindings.get(binding).cloned(),
+            None => Some(Vec::new()),
+        };
+
+        let invoked = Event::new(
+            self.next_event_id(),
+            ctx.run_id,
+            ctx.node_id,
+            ctx.logical_time,
+            EventKind::ToolInvoked,
+        )
+        .with_parent(ctx.parent_event_id.unwrap_or_else(|| self.next_event_id()))
+        .with_payload(input.clone().unwrap_or_default());
+
+        let mut host =
+            HostAdapter::new(Arc::clone(tools)).with_capabilities(ctx.capabilities.clone());
+        if let Some(token) = &ctx.cancellation {
+            host = host.with_cancellation(token.clone());
+        }
+        let outcome = match input {
+            Some(input) => host.invoke(name, version_req, &input),
+            None => Err(CoreError::Validation {
+                field: "input_binding".to_string(),
+                reason: format!("Unbound input {} for tool {}", input_binding.unwrap_or_default(), name),
+            }),
+        };
+
+        let host_calls = outcome
+            .as_ref()
+            .map(|output| output.host_calls.clone())
+            .unwrap_or_default();
+        let (kind, payload, result) = match outcome {
+            Ok(output) if output.is_success() => {
+                let output_hash = Hash::compute(&output.data);
+                (
+                    EventKind::ToolCompleted,
+                    output.data.clone(),
+                    ExecutorResult::Success {
+                        output: output.data,
+                        output_hash,
+                        effects: output.side_effects,
+                    },
+                )
+            }
+            Ok(output) => {
+                let error = format!("Tool {} exited with code {}", name, output.exit_code);
+                (EventKind::ToolFailed, error.clone().into_bytes(), ExecutorResult::Failed { error })
+            }
+            Err(err) => {
+                let error = err.to_string();
+                (EventKind::ToolFailed, error.clone().into_bytes(), ExecutorResult::Failed { error })
+            }
+        };
+
+        // Host calls, then each applied effect, are logged between
+        // invocation and completion
+        let mut events = vec![invoked];
+        for encoded in &host_calls {
+            let record: HostCallRecord = serde_json::from_slice(encoded)?;
+            let mut call = record
+                .to_event(ctx.run_id, ctx.node_id)?
+                .with_parent(events[events.len() - 1].event_id);
+            call.logical_time = ctx.logical_time.saturating_add(1);
+            events.push(call);
+        }
+        for effect in result.effect_descriptions() {
+            let applied = Event::new(
+                self.next_event_id(),
+                ctx.run_id,
+                ctx.node_id,
+                ctx.logical_time.saturating_add(1),
+                EventKind::SideEffect,
+            )
+            .with_parent(events[events.len() - 1].event_id)
+            .with_payload(effect.into_bytes());
+            events.push(applied);
+        }
+
+        let finished = Event::new(
+            self.next_event_id(),
+            ctx.run_id,
+            ctx.node_id,
+            ctx.logical_time.saturating_add(1),
+            kind,
+        )
+        .with_parent(events[events.len() - 1].event_id)
+        .with_payload(payload);
+        events.push(finished);
+
+        Ok((events, result))
+    }
+
+    /// Evaluate a conditional node's predicate on its input binding
+    ///
+    /// Emits `NodeStarted` and a completion event; on success the output,
+    /// also carried by the completion event, is the JSON outcome `true` or
+    /// `false`. An unbound or non-JSON input fails the node.
+    pub fn execute_condition(
+        &self,
+        ctx: &ExecutionContext,
+        input_binding: &str,
+        predicate: &Predicate,
+    ) -> (Vec<Event>, ExecutorResult) {
+        let start = self.create_start_event(ctx);
+        let result = match ctx.bindings.get(input_binding) {
+            Some(data) => match serde_json::from_slice::<serde_json::Value>(data) {
+                Ok(value) => {
+                    let output = predicate.evaluate(&value).to_string().into_bytes();
+                    ExecutorResult::Success {
+                        output_hash: Hash::compute(&output),
+                        output,
+                        effects: Vec::new(),
+                    }
+                }
+                Err(err) => ExecutorResult::Failed {
+                    error: format!("Input {} is not JSON: {}", input_binding, err),
+                },
+            },
+            None => ExecutorResult::Failed {
+                error: format!("Unbound input {} for condition", input_binding),
+            },
+        };
+
+        let mut end = self.create_complete_event(ctx, &result).with_parent(start.event_id);
+        if let ExecutorResult::Success { output, .. } = &result {
+            end = end.with_payload(output.clone());
+        }
+        (vec![start, end], result)
+    }
+
+    /// Gate one capability for the node in `ctx`
+    ///
+    /// The capability set is checked first (unless strict checking is off);
+    /// if it allows the capability and a policy is configured, the policy
+    /// decides. Either way the decision's proof is returned as the payload of
+    /// a `PolicyDecision` event.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if policy evaluation or proof encoding fails
+    pub fn gate_capability(
+        &self,
+        ctx: &ExecutionContext,
+        capability: &Capability,
+    ) -> CoreResult<(Event, bool)> {
+        let mut eval = EvalContext::new()
+            .with_node(ctx.node_id)
+            .with_capability(capability.clone());
+        if let Some(parent) = ctx.parent_event_id {
+            eval = eval.with_event(parent);
+        }
+
+        let granted = !self.strict_capabilities || ctx.has_capability(capability);
+        let proof = match &self.policy {
+            Some(policy) if granted => policy.check_capability(&eval, capability)?.proof,
+            _ => DecisionProof::for_input(
+                ProofKind::CapabilityCheck,
+                granted,
+                CAPABILITY_SET_POLICY,
+                eval.input_hash()?,
+            )
+            .with_node(ctx.node_id)
+            .with_capability(capability.clone())
+            .finalize()?,
+        };
+
+        let mut event = Event::new(
+            self.next_event_id(),
+            ctx.run_id,
+            ctx.node_id,
+            ctx.logical_time,
+            EventKind::PolicyDecision,
+        )
+        .with_payload(serde_json::to_vec(&proof)?);
+        if let Some(parent) = ctx.parent_event_id {
+            event = event.with_parent(parent);
+        }
+
+        Ok((event, proof.decision))
+    }
+
+    /// Gate every capability the node in `ctx` requires
+    ///
+    /// Returns one `PolicyDecision` event per gate, in order, and the
+    /// capabilities that were denied.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a gate fails to evaluate
+    pub fn gate_capabilities(
+        &self,
+        ctx: &ExecutionContext,
+    ) -> CoreResult<(Vec<Event>, Vec<Capability>)> {
+        let mut events = Vec::new();
+        let mut missing = Vec::new();
+        for capability in &ctx.required_capabilities {
+            let (event, allowed) = self.gate_capability(ctx, capability)?;
+            events.push(event);
+            if !allowed {
+                missing.push(capability.clone());
+            }
+        }
+        Ok((events, missing))
+    }
+
     /// Check if execution should proceed based on capabilities
     ///
     /// # Errors
@@ -182,13 +593,13 @@ impl Executor {
     #[must_use]
     pub fn create_start_event(&self, ctx: &ExecutionContext) -> Event {
         Event::new(
-            EventId::new(),
+            self.next_event_id(),
             ctx.run_id,
             ctx.node_id,
             ctx.logical_time,
             EventKind::NodeStarted,
         )
-        .with_parent(ctx.parent_event_id.unwrap_or_else(EventId::new))
+        .with_parent(ctx.parent_event_id.unwrap_or_else(|| self.next_event_id()))
     }
 
     /// Create a completion event for node execution
@@ -205,7 +616,7 @@ impl Executor {
         };
 
         Event::new(
-            EventId::new(),
+            self.next_event_id(),
             ctx.run_id,
             ctx.node_id,
             ctx.logical_time.saturating_add(1),
@@ -349,6 +760,7 @@ mod tests {
         let result = ExecutorResult::Success {
             output: vec![1, 2, 3],
             output_hash: Hash::empty(),
+            effects: Vec::new(),
         };
         let event = executor.create_complete_event(&ctx, &result);
 
@@ -451,6 +863,160 @@ mod tests {
         assert!(!ctx.has_capability(&Capability::FsWrite { prefixes: vec!["/tmp".to_string()] }));
     }
 
+    fn make_tool_registry() -> Arc<SharedRegistry> {
+        use cathedral_tool::adapter::builtin::EchoTool;
+        use cathedral_tool::{InputSchema, OutputSchema, ToolSchema};
+
+        let registry = Arc::new(SharedRegistry::new());
+        let schema = ToolSchema::new("echo".to_string(), "1.0.0".to_string())
+            .with_input(InputSchema::new().with_json_schema("{}".to_string()))
+            .with_output(OutputSchema::new().with_json_schema("{}".to_string()));
+        registry.register(Arc::new(EchoTool), schema).unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_execute_tool_node() {
+        let ast = cathedral_plan::parse("tool \"echo\" ^1.0 <- data").unwrap();
+        let dag = cathedral_plan::Compiler::new().compile(&ast).unwrap().dag;
+        let node = dag.nodes.values().next().unwrap();
+
+        let executor = Executor::new().with_tools(make_tool_registry());
+        let mut ctx = ExecutionContext::new(
+            make_test_run(),
+            node.id,
+            LogicalTime::zero(),
+            CapabilitySet::new(),
+        );
+        ctx.bind("data", br#"{"msg":"hi"}"#.to_vec());
+
+        let (events, result) = executor.execute_node(&ctx, &node.kind).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, EventKind::ToolInvoked);
+        assert_eq!(events[1].kind, EventKind::ToolCompleted);
+        assert_eq!(events[1].payload, br#"{"msg":"hi"}"#);
+        assert!(matches!(result, ExecutorResult::Success { .. }));
+    }
+
+    #[test]
+    fn test_execute_tool_records_effects() {
+        use cathedral_tool::adapter::builtin::WriteTool;
+        use cathedral_tool::ToolSchema;
+
+        let write = SideEffect::FsWrite {
+            path: "./outputs/a.txt".to_string(),
+        };
+        let registry = Arc::new(SharedRegistry::new());
+        let schema = ToolSchema::new("write".to_string(), "1.0.0".to_string())
+            .with_side_effect(write.clone());
+        registry
+            .register(
+                Arc::new(WriteTool {
+                    path: "./outputs/a.txt".to_string(),
+                }),
+                schema,
+            )
+            .unwrap();
+
+        let executor = Executor::new().with_tools(registry);
+        let ctx = ExecutionContext::new(
+            make_test_run(),
+            make_test_node(),
+            LogicalTime::zero(),
+            CapabilitySet::new(),
+        );
+
+        let (events, result) = executor.execute_tool(&ctx, "write", "*", None).unwrap();
+        assert_eq!(result.effects(), &[write]);
+        assert_eq!(result.effect_descriptions(), vec!["Write file: ./outputs/a.txt"]);
+
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [EventKind::ToolInvoked, EventKind::SideEffect, EventKind::ToolCompleted]
+        );
+        assert_eq!(events[1].payload, b"Write file: ./outputs/a.txt");
+        assert_eq!(events[1].parent_event_id, Some(events[0].event_id));
+        assert_eq!(events[2].parent_event_id, Some(events[1].event_id));
+    }
+
+    #[test]
+    fn test_execute_tool_logs_host_calls() {
+        use cathedral_tool::{Tool, ToolOutput, ToolSchema};
+        use cathedral_wasm::abi::AbiValue;
+
+        /// Tool reporting one recorded `clock_read` call
+        struct ClockTool;
+
+        impl Tool for ClockTool {
+            fn name(&self) -> &str {
+                "clock"
+            }
+
+            fn version(&self) -> &str {
+                "1.0.0"
+            }
+
+            fn execute(&self, _input: &[u8]) -> CoreResult<ToolOutput> {
+                let record = HostCallRecord {
+                    sequence: 0,
+                    function: "clock_read".to_string(),
+                    args: vec![AbiValue::I64(0)],
+                    result: Ok(AbiValue::I64(42)),
+                    timestamp: 0,
+                };
+                Ok(ToolOutput::success(b"42".to_vec())
+                    .with_host_calls(vec![serde_json::to_vec(&record)?]))
+            }
+        }
+
+        let registry = Arc::new(SharedRegistry::new());
+        let schema = ToolSchema::new("clock".to_string(), "1.0.0".to_string());
+        registry.register(Arc::new(ClockTool), schema).unwrap();
+        let executor = Executor::new().with_tools(registry);
+        let ctx = ExecutionContext::new(
+            make_test_run(),
+            make_test_node(),
+            LogicalTime::zero(),
+            CapabilitySet::new(),
+        );
+
+        let (events, _) = executor.execute_tool(&ctx, "clock", "*", None).unwrap();
+        let kinds: Vec<_> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [EventKind::ToolInvoked, EventKind::HostCall, EventKind::ToolCompleted]
+        );
+        let record = HostCallRecord::from_event(&events[1]).unwrap();
+        assert_eq!(record.result, Ok(AbiValue::I64(42)));
+        assert_eq!(events[1].event_id, record.event_id(ctx.run_id, ctx.node_id));
+        assert_eq!(events[1].parent_event_id, Some(events[0].event_id));
+        assert_eq!(events[2].parent_event_id, Some(events[1].event_id));
+    }
+
+    #[test]
+    fn test_execute_tool_node_invalid_input() {
+        let executor = Executor::new().with_tools(make_tool_registry());
+        let mut ctx = ExecutionContext::new(
+            make_test_run(),
+            make_test_node(),
+            LogicalTime::zero(),
+            CapabilitySet::new(),
+        );
+        ctx.bind("data", b"not json".to_vec());
+
+        let (events, result) = executor
+            .execute_tool(&ctx, "echo", "^1.0", Some("data"))
+            .unwrap();
+        assert_eq!(events[1].kind, EventKind::ToolFailed);
+        assert!(matches!(result, ExecutorResult::Failed { .. }));
+
+        let (_, result) = executor
+            .execute_tool(&ctx, "echo", "^2.0", Some("data"))
+            .unwrap();
+        assert!(matches!(result, ExecutorResult::Failed { .. }));
+    }
+
     #[test]
     fn test_execute_with_events() {
         let executor = Executor::new();
@@ -469,4 +1035,80 @@ mod tests {
         assert_eq!(end.kind, EventKind::NodeCompleted);
         assert!(matches!(exec_result, ExecutorResult::Success { .. }));
     }
+
+    #[test]
+    fn test_execute_node_cancelled() {
+        let token = CancellationToken::new();
+        let ctx = ExecutionContext::new(
+            make_test_run(),
+            make_test_node(),
+            LogicalTime::from_raw(10),
+            CapabilitySet::new(),
+        )
+        .with_cancellation(token.clone());
+        let kind = NodeKind::Map { function: "id".to_string() };
+
+        assert!(Executor::new().execute_node(&ctx, &kind).is_ok());
+        token.cancel();
+        assert!(ctx.is_cancelled());
+        assert_eq!(Executor::new().execute_node(&ctx, &kind).unwrap_err(), CoreError::Cancelled);
+    }
+
+    #[test]
+    fn test_execute_node_gates_capabilities() {
+        let read = Capability::FsRead { prefixes: vec!["/tmp".to_string()] };
+        let write = Capability::FsWrite { prefixes: vec!["/tmp".to_string()] };
+        let mut capabilities = CapabilitySet::new();
+        capabilities.allow(read.clone());
+        let kind = NodeKind::Map { function: "id".to_string() };
+        let make_ctx = |required: Vec<Capability>| {
+            ExecutionContext::new(
+                make_test_run(),
+                make_test_node(),
+                LogicalTime::from_raw(10),
+                capabilities.clone(),
+            )
+            .with_required_capabilities(required)
+        };
+        let proof = |event: &Event| {
+            serde_json::from_slice::<DecisionProof>(&event.payload).unwrap()
+        };
+
+        // One PolicyDecision event per gate, then the node's own events
+        let (events, result) = Executor::new()
+            .execute_node(&make_ctx(vec![read.clone()]), &kind)
+            .unwrap();
+        assert!(matches!(result, ExecutorResult::Success { .. }));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].kind, EventKind::PolicyDecision);
+        let granted = proof(&events[0]);
+        assert!(granted.decision && granted.verify().unwrap());
+        assert_eq!(granted.capability, Some(read.clone()));
+
+        // A denied gate skips the node
+        let (events, result) = Executor::new()
+            .execute_node(&make_ctx(vec![read.clone(), write.clone()]), &kind)
+            .unwrap();
+        assert_eq!(result, ExecutorResult::Skipped { missing: vec![write] });
+        assert_eq!(events.len(), 3);
+        assert!(!proof(&events[1]).decision);
+        assert_eq!(events[2].kind, EventKind::NodeSkipped);
+
+        // With a policy, its proof is logged and it must grant the capability
+        let mut policy = cathedral_policy::PolicyCompiler::new()
+            .compile_from_source("allow reads: true")
+            .unwrap();
+        let executor = Executor::new().with_policy(policy.clone());
+        let (events, result) = executor.execute_node(&make_ctx(vec![read.clone()]), &kind).unwrap();
+        assert!(matches!(result, ExecutorResult::Skipped { .. }));
+        assert_eq!(proof(&events[0]).policy_id, Some(policy.id.clone()));
+
+        policy.rules[0].capabilities.push(read.clone());
+        let executor = Executor::new().with_policy(policy);
+        let (events, result) = executor.execute_node(&make_ctx(vec![read]), &kind).unwrap();
+        assert!(matches!(result, ExecutorResult::Success { .. }));
+        let decided = proof(&events[0]);
+        assert_eq!(decided.kind, ProofKind::CapabilityCheck);
+        assert_eq!(decided.rule_id.as_deref(), Some("reads"));
+    }
 }
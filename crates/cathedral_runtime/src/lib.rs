@@ -8,12 +8,20 @@
 
 pub mod engine;
 pub mod scheduler;
+pub mod budget;
 pub mod executor;
 pub mod backpressure;
 pub mod monitor;
+#[cfg(any(test, feature = "test-support"))]
+pub mod testing;
 
-pub use engine::{ExecutionEngine, EngineConfig, ExecutionError};
-pub use scheduler::{Scheduler, ScheduleDecision, ScheduleError};
-pub use executor::{Executor, ExecutorResult, ExecutorError};
-pub use backpressure::{BackpressureController, BackpressureStrategy};
-pub use monitor::{ExecutionMonitor, Metrics, Telemetry};
+pub use engine::{run_header, EngineConfig, EventSink, ExecutionEngine, ExecutionError};
+pub use scheduler::{Cancellation, Scheduler, ScheduleDecision, ScheduleError};
+pub use budget::{BudgetExceeded, ResourceBudget, ResourceUsage};
+pub use executor::{Executor, ExecutorResult, ExecutorError, SeededEventIds};
+pub use backpressure::{
+    AimdConfig, BackpressureController, BackpressureStrategy, QueueOverflow,
+};
+pub use monitor::{
+    ExecutionMonitor, Histogram, Metrics, MetricsSource, PrometheusExporter, Telemetry,
+};
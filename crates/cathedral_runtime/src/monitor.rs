@@ -1,10 +1,100 @@
 //! Execution monitor for metrics and telemetry.
 //!
 //! Tracks execution metrics and provides telemetry for observability.
+//! [`render_prometheus`] formats metrics in the Prometheus text format under
+//! stable names, and [`PrometheusExporter`] serves them on `GET /metrics`
+//! for processes without an HTTP server of their own.
 
 use cathedral_core::{NodeId, LogicalTime};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write as _};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Upper bounds, in logical ticks, of the scheduling latency buckets
+pub const LATENCY_BUCKETS: [u64; 8] = [0, 1, 2, 5, 10, 25, 50, 100];
+
+/// Counts of observed values in fixed buckets
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    /// Inclusive upper bound of each bucket, ascending
+    bounds: Vec<u64>,
+    /// Observations in each bucket, not cumulative
+    counts: Vec<u64>,
+    /// Observations above every bound
+    overflow: u64,
+    /// Sum of observed values
+    sum: u64,
+}
+
+impl Histogram {
+    /// Create an empty histogram with the given bucket bounds
+    #[must_use]
+    pub fn new(bounds: &[u64]) -> Self {
+        let mut bounds = bounds.to_vec();
+        bounds.sort_unstable();
+        bounds.dedup();
+        Self {
+            counts: vec![0; bounds.len()],
+            bounds,
+            overflow: 0,
+            sum: 0,
+        }
+    }
+
+    /// Record a value
+    pub fn observe(&mut self, value: u64) {
+        match self.bounds.iter().position(|bound| value <= *bound) {
+            Some(bucket) => self.counts[bucket] += 1,
+            None => self.overflow += 1,
+        }
+        self.sum = self.sum.saturating_add(value);
+    }
+
+    /// Add another histogram's observations to this one
+    ///
+    /// Buckets are matched by bound; observations in buckets this
+    /// histogram lacks land in the next larger bucket.
+    pub fn merge(&mut self, other: &Self) {
+        for (bound, count) in other.bounds.iter().zip(&other.counts) {
+            match self.bounds.iter().position(|own| bound <= own) {
+                Some(bucket) => self.counts[bucket] += count,
+                None => self.overflow += count,
+            }
+        }
+        self.overflow += other.overflow;
+        self.sum = self.sum.saturating_add(other.sum);
+    }
+
+    /// Number of observations
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum::<u64>() + self.overflow
+    }
+
+    /// Sum of observed values
+    #[must_use]
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// Each bucket bound with the observations at or below it
+    pub fn cumulative(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.bounds.iter().zip(&self.counts).scan(0, |total, (bound, count)| {
+            *total += count;
+            Some((*bound, *total))
+        })
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new(&LATENCY_BUCKETS)
+    }
+}
+
 /// Execution metrics
 #[derive(Debug, Clone, Default)]
 pub struct Metrics {
@@ -20,6 +110,20 @@ pub struct Metrics {
     pub total_ticks: u64,
     /// Events generated
     pub events_generated: u64,
+    /// Sum of node latencies (logical ticks)
+    pub total_latency: u64,
+    /// Latency of the most recent node (logical ticks)
+    pub last_latency: Option<u64>,
+    /// Fuel charged for admitted nodes (logical ticks)
+    pub fuel_consumed: u64,
+    /// Nodes in the ready queue when last sampled
+    pub queue_depth: u64,
+    /// Ticks nodes waited in the ready queue before running
+    pub scheduling_latency: Histogram,
+    /// Consensus term changes, the current term as terms only grow
+    pub term_changes: u64,
+    /// Age of each worker's last heartbeat (milliseconds)
+    pub heartbeat_age: BTreeMap<NodeId, u64>,
 }
 
 impl Metrics {
@@ -59,6 +163,57 @@ impl Metrics {
         self.events_generated += 1;
     }
 
+    /// Record how many logical ticks a node took
+    pub fn record_latency(&mut self, ticks: u64) {
+        self.total_latency = self.total_latency.saturating_add(ticks);
+        self.last_latency = Some(ticks);
+    }
+
+    /// Record fuel charged for a node
+    pub fn record_fuel(&mut self, fuel: u64) {
+        self.fuel_consumed = self.fuel_consumed.saturating_add(fuel);
+    }
+
+    /// Record the current depth of the ready queue
+    pub fn record_queue_depth(&mut self, depth: u64) {
+        self.queue_depth = depth;
+    }
+
+    /// Record how many logical ticks a node waited before it ran
+    pub fn record_scheduling_latency(&mut self, ticks: u64) {
+        self.scheduling_latency.observe(ticks);
+    }
+
+    /// Record the current consensus term
+    pub fn record_term(&mut self, term: u64) {
+        self.term_changes = self.term_changes.max(term);
+    }
+
+    /// Record how long ago a worker last sent a heartbeat
+    pub fn record_heartbeat_age(&mut self, worker: NodeId, age_ms: u64) {
+        self.heartbeat_age.insert(worker, age_ms);
+    }
+
+    /// Add another engine's metrics to these
+    ///
+    /// Counters, histograms and queue depths add up; the consensus term is
+    /// the larger of the two, and heartbeat ages are taken from `other`.
+    pub fn merge(&mut self, other: &Self) {
+        self.nodes_executed += other.nodes_executed;
+        self.nodes_completed += other.nodes_completed;
+        self.nodes_failed += other.nodes_failed;
+        self.nodes_skipped += other.nodes_skipped;
+        self.total_ticks += other.total_ticks;
+        self.events_generated += other.events_generated;
+        self.total_latency = self.total_latency.saturating_add(other.total_latency);
+        self.last_latency = other.last_latency.or(self.last_latency);
+        self.fuel_consumed = self.fuel_consumed.saturating_add(other.fuel_consumed);
+        self.queue_depth += other.queue_depth;
+        self.scheduling_latency.merge(&other.scheduling_latency);
+        self.record_term(other.term_changes);
+        self.heartbeat_age.extend(&other.heartbeat_age);
+    }
+
     /// Get success rate (0.0 - 1.0)
     #[must_use]
     pub fn success_rate(&self) -> f64 {
@@ -68,6 +223,15 @@ impl Metrics {
         self.nodes_completed as f64 / self.nodes_executed as f64
     }
 
+    /// Get mean node latency in logical ticks
+    #[must_use]
+    pub fn mean_latency(&self) -> f64 {
+        if self.nodes_executed == 0 {
+            return 0.0;
+        }
+        self.total_latency as f64 / self.nodes_executed as f64
+    }
+
     /// Get failure rate (0.0 - 1.0)
     #[must_use]
     pub fn failure_rate(&self) -> f64 {
@@ -83,6 +247,139 @@ impl Metrics {
     }
 }
 
+/// Source of the metrics an exporter serves
+pub type MetricsSource = Arc<dyn Fn() -> Metrics + Send + Sync>;
+
+/// Stable names of exported metrics
+pub mod names {
+    /// Nodes executed (counter)
+    pub const NODES_EXECUTED: &str = "cathedral_nodes_executed_total";
+    /// Nodes completed successfully (counter)
+    pub const NODES_COMPLETED: &str = "cathedral_nodes_completed_total";
+    /// Nodes failed (counter)
+    pub const NODES_FAILED: &str = "cathedral_nodes_failed_total";
+    /// Nodes skipped (counter)
+    pub const NODES_SKIPPED: &str = "cathedral_nodes_skipped_total";
+    /// Events generated (counter)
+    pub const EVENTS_GENERATED: &str = "cathedral_events_generated_total";
+    /// Fuel charged for admitted nodes (counter)
+    pub const FUEL_CONSUMED: &str = "cathedral_fuel_consumed_total";
+    /// Nodes in ready queues (gauge)
+    pub const QUEUE_DEPTH: &str = "cathedral_queue_depth";
+    /// Ticks nodes waited before running (histogram)
+    pub const SCHEDULING_LATENCY: &str = "cathedral_scheduling_latency_ticks";
+    /// Consensus term changes (counter)
+    pub const TERM_CHANGES: &str = "cathedral_consensus_term_changes_total";
+    /// Age of each worker's last heartbeat, labelled by `worker` (gauge)
+    pub const HEARTBEAT_AGE: &str = "cathedral_worker_heartbeat_age_ms";
+}
+
+/// Format metrics in the Prometheus text exposition format
+#[must_use]
+pub fn render_prometheus(metrics: &Metrics) -> String {
+    fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    }
+
+    let mut out = String::new();
+    let scalars = [
+        (names::NODES_EXECUTED, "counter", "Nodes executed", metrics.nodes_executed),
+        (names::NODES_COMPLETED, "counter", "Nodes completed", metrics.nodes_completed),
+        (names::NODES_FAILED, "counter", "Nodes failed", metrics.nodes_failed),
+        (names::NODES_SKIPPED, "counter", "Nodes skipped", metrics.nodes_skipped),
+        (names::EVENTS_GENERATED, "counter", "Events generated", metrics.events_generated),
+        (names::FUEL_CONSUMED, "counter", "Fuel charged for admitted nodes", metrics.fuel_consumed),
+        (names::QUEUE_DEPTH, "gauge", "Nodes in ready queues", metrics.queue_depth),
+        (names::TERM_CHANGES, "counter", "Consensus term changes", metrics.term_changes),
+    ];
+    for (name, kind, help, value) in scalars {
+        header(&mut out, name, kind, help);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+
+    let name = names::SCHEDULING_LATENCY;
+    let latency = &metrics.scheduling_latency;
+    header(&mut out, name, "histogram", "Logical ticks nodes waited before running");
+    for (bound, count) in latency.cumulative() {
+        let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, count);
+    }
+    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, latency.count());
+    let _ = writeln!(out, "{}_sum {}", name, latency.sum());
+    let _ = writeln!(out, "{}_count {}", name, latency.count());
+
+    let name = names::HEARTBEAT_AGE;
+    header(&mut out, name, "gauge", "Milliseconds since each worker's last heartbeat");
+    for (worker, age) in &metrics.heartbeat_age {
+        let _ = writeln!(out, "{}{{worker=\"{}\"}} {}", name, worker, age);
+    }
+    out
+}
+
+/// Serves metrics to Prometheus scrapers over plain HTTP
+///
+/// Answers `GET /metrics` with the current metrics and every other request
+/// with `404 Not Found`, one connection at a time.
+pub struct PrometheusExporter {
+    /// Where the served metrics come from
+    source: MetricsSource,
+}
+
+impl PrometheusExporter {
+    /// Create an exporter serving the metrics `source` returns
+    #[must_use]
+    pub fn new(source: MetricsSource) -> Self {
+        Self { source }
+    }
+
+    /// Render the current metrics
+    #[must_use]
+    pub fn render(&self) -> String {
+        render_prometheus(&(self.source)())
+    }
+
+    /// Serve scrapes until accepting a connection fails
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the listener fails
+    pub fn serve(&self, listener: &TcpListener) -> std::io::Result<()> {
+        for stream in listener.incoming() {
+            if let Err(e) = self.respond(stream?) {
+                tracing::debug!(error = %e, "Metrics scrape failed");
+            }
+        }
+        Ok(())
+    }
+
+    /// Answer one request
+    fn respond(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let mut request_line = String::new();
+        let mut reader = BufReader::new(&stream);
+        reader.read_line(&mut request_line)?;
+        // Drain the headers so the client sees a complete exchange
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 2 {
+            line.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let (status, body) = match (parts.next(), parts.next()) {
+            (Some("GET"), Some("/metrics")) => ("200 OK", self.render()),
+            _ => ("404 Not Found", String::new()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+}
+
 /// Telemetry data point
 #[derive(Debug, Clone)]
 pub struct Telemetry {
@@ -233,6 +530,19 @@ mod tests {
         assert_eq!(metrics.failure_rate(), 0.5);
     }
 
+    #[test]
+    fn test_metrics_latency() {
+        let mut metrics = Metrics::new();
+        assert_eq!(metrics.mean_latency(), 0.0);
+        for ticks in [1, 3] {
+            metrics.record_execution();
+            metrics.record_latency(ticks);
+        }
+
+        assert_eq!(metrics.last_latency, Some(3));
+        assert_eq!(metrics.mean_latency(), 2.0);
+    }
+
     #[test]
     fn test_metrics_reset() {
         let mut metrics = Metrics::new();
@@ -299,4 +609,67 @@ mod tests {
         let monitor = ExecutionMonitor::default();
         assert_eq!(monitor.max_history, 1000);
     }
+
+    #[test]
+    fn test_histogram_buckets_and_merge() {
+        let mut histogram = Histogram::new(&[1, 5]);
+        for ticks in [0, 1, 3, 9] {
+            histogram.observe(ticks);
+        }
+        assert_eq!(histogram.cumulative().collect::<Vec<_>>(), [(1, 2), (5, 3)]);
+        assert_eq!((histogram.count(), histogram.sum()), (4, 13));
+
+        let mut coarse = Histogram::new(&[5]);
+        coarse.merge(&histogram);
+        assert_eq!(coarse.cumulative().collect::<Vec<_>>(), [(5, 3)]);
+        assert_eq!(coarse.count(), 4);
+    }
+
+    #[test]
+    fn test_render_prometheus() {
+        let mut metrics = Metrics::new();
+        metrics.record_execution();
+        metrics.record_fuel(7);
+        metrics.record_queue_depth(2);
+        metrics.record_scheduling_latency(3);
+        metrics.record_term(4);
+        let worker = NodeId::from_bytes([1; 16]);
+        metrics.record_heartbeat_age(worker, 250);
+
+        let text = render_prometheus(&metrics);
+        assert!(text.contains("# TYPE cathedral_nodes_executed_total counter\n"));
+        assert!(text.contains("\ncathedral_nodes_executed_total 1\n"));
+        assert!(text.contains("\ncathedral_fuel_consumed_total 7\n"));
+        assert!(text.contains("\ncathedral_queue_depth 2\n"));
+        assert!(text.contains("\ncathedral_consensus_term_changes_total 4\n"));
+        assert!(text.contains("cathedral_scheduling_latency_ticks_bucket{le=\"2\"} 0\n"));
+        assert!(text.contains("cathedral_scheduling_latency_ticks_bucket{le=\"5\"} 1\n"));
+        assert!(text.contains("cathedral_scheduling_latency_ticks_count 1\n"));
+        let age = format!("cathedral_worker_heartbeat_age_ms{{worker=\"{}\"}} 250\n", worker);
+        assert!(text.contains(&age));
+    }
+
+    #[test]
+    fn test_exporter_serves_metrics() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let exporter = PrometheusExporter::new(Arc::new(|| {
+            let mut metrics = Metrics::new();
+            metrics.record_execution();
+            metrics
+        }));
+        std::thread::spawn(move || exporter.serve(&listener));
+
+        let scrape = |path: &str| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: test\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            std::io::Read::read_to_string(&mut stream, &mut response).unwrap();
+            response
+        };
+        let response = scrape("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\ncathedral_nodes_executed_total 1\n"));
+        assert!(scrape("/other").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
 }
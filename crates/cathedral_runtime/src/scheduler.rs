@@ -5,10 +5,23 @@
 //! - Priority-based selection (BTreeMap for deterministic ordering)
 //! - Logical time increments on each operation
 //! - No runtime load balancing
-
-use cathedral_core::{NodeId, LogicalTime, CoreResult, CoreError};
+//!
+//! Ready nodes are ordered by (priority, topological index, node hash),
+//! a total order computed from the DAG alone. Every collection that can
+//! reach a [`ScheduleDecision`] is a BTree or an insertion-ordered
+//! IndexMap; hash-ordered `HashMap`/`HashSet` must never be used here, so
+//! the same DAG yields the same schedule on every host.
+
+use cathedral_core::{Hash, NodeId, LogicalTime, CoreResult, CoreError};
+use cathedral_plan::ResourceContract;
 use indexmap::{IndexMap, IndexSet};
-use std::collections::{BTreeSet, BTreeMap};
+use crate::backpressure::QueueOverflow;
+use crate::budget::{BudgetExceeded, ResourceBudget, ResourceUsage};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BTreeMap, VecDeque};
+
+/// Ready-queue ordering: priority descending, topological index, node hash
+type ReadyKey = (Reverse<u64>, u64, Hash);
 
 /// Scheduling decision - which node to run next
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -21,6 +34,15 @@ pub enum ScheduleDecision {
     Complete,
 }
 
+/// Nodes finished by cancelling a run, each in the order they would have run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cancellation {
+    /// Ready or held nodes, which may have been in flight
+    pub cancelled: Vec<NodeId>,
+    /// Unfinished nodes that had not become ready
+    pub skipped: Vec<NodeId>,
+}
+
 /// Scheduler error
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ScheduleError {
@@ -44,23 +66,96 @@ impl std::fmt::Display for ScheduleError {
 
 impl std::error::Error for ScheduleError {}
 
+/// Items of a fanned-out node
+#[derive(Debug, Clone)]
+struct FanOut {
+    /// Item nodes, in item order
+    items: Vec<NodeId>,
+    /// Items not yet released to the ready queue
+    pending: VecDeque<NodeId>,
+    /// Items not yet completed
+    outstanding: usize,
+}
+
 /// Deterministic scheduler for DAG execution
 ///
 /// Uses BTreeMap and BTreeSet for deterministic ordering.
-/// Ready nodes are sorted by priority, then by NodeId.
+/// Ready nodes are sorted by priority (highest first), then by topological
+/// index (lowest first), then by the hash of their NodeId. The tie-breaks
+/// mean equal-priority nodes run in the same order no matter which of them
+/// became ready first, or in which order they were added.
+///
+/// Two kinds of dynamic control flow are supported. A node can be guarded
+/// on a conditional's outcome ([`Scheduler::add_guard`]); once the
+/// conditional completes with [`Scheduler::complete_condition`], nodes on
+/// the branch not taken are skipped, and so is every node whose
+/// dependencies were all skipped. A ready node can be fanned out into item
+/// nodes ([`Scheduler::fan_out`]), at most `max_parallel` of which are
+/// ready at once; when every item has completed the node becomes ready
+/// again for its fan-in.
+///
+/// With a [`ResourceBudget`], a node is admitted to the ready queue only if
+/// its contract fits in what remains of the budget; otherwise it fails and
+/// a [`BudgetExceeded`] is recorded.
+///
+/// The ready queue can be bounded ([`Scheduler::set_queue_limit`]). A node
+/// that becomes ready while the queue is full is either held, in the order
+/// nodes became ready, until a slot frees up, or queued after the oldest
+/// ready node is shed. Shed nodes count as skipped.
+///
+/// Cancelling the run ([`Scheduler::cancel`]) finishes every node at once:
+/// ready and held nodes, which may be in flight, are cancelled and all
+/// other unfinished nodes are skipped.
 pub struct Scheduler {
     /// All nodes in the DAG
     all_nodes: IndexSet<NodeId>,
     /// Nodes ready to run (sorted for determinism)
-    ready: BTreeMap<(u64, NodeId), NodeId>,
+    ready: BTreeMap<ReadyKey, NodeId>,
+    /// Ready nodes in the order they became ready
+    ready_order: IndexSet<NodeId>,
+    /// Maximum number of ready nodes
+    queue_limit: Option<usize>,
+    /// What a full ready queue does with a newly ready node
+    overflow: QueueOverflow,
+    /// Nodes waiting for room in the ready queue
+    held: VecDeque<NodeId>,
+    /// Nodes shed from the ready queue, not yet taken
+    shed: Vec<NodeId>,
     /// Completed nodes
     completed: BTreeSet<NodeId>,
     /// Failed nodes
     failed: BTreeSet<NodeId>,
+    /// Nodes on a branch that was not taken
+    skipped: BTreeSet<NodeId>,
+    /// Nodes in flight when the run was cancelled
+    cancelled: BTreeSet<NodeId>,
+    /// Whether the run was cancelled
+    run_cancelled: bool,
+    /// Guarded nodes: node -> (conditional, outcome the node runs on)
+    guards: IndexMap<NodeId, (NodeId, bool)>,
+    /// Outcomes of completed conditionals
+    outcomes: IndexMap<NodeId, bool>,
+    /// Fanned-out nodes and their items
+    fan_outs: IndexMap<NodeId, FanOut>,
+    /// Item node -> the node it was fanned out from
+    item_parents: IndexMap<NodeId, NodeId>,
+    /// Run-wide resource budget
+    budget: ResourceBudget,
+    /// Worst-case usage of nodes added with a contract
+    demands: IndexMap<NodeId, ResourceUsage>,
+    /// Usage charged for admitted nodes
+    charged: ResourceUsage,
+    /// Nodes refused admission, not yet taken
+    violations: Vec<BudgetExceeded>,
     /// Dependencies: node -> set of nodes it depends on
     dependencies: IndexMap<NodeId, IndexSet<NodeId>>,
     /// Dependents (reverse edges): node -> set of nodes that depend on it
     dependents: IndexMap<NodeId, IndexSet<NodeId>>,
+    /// Node priorities (higher runs first)
+    priorities: IndexMap<NodeId, u64>,
+    /// Topological indices: length of the longest dependency chain ending
+    /// at the node
+    topological_indices: IndexMap<NodeId, u64>,
     /// Current logical time
     time: LogicalTime,
 }
@@ -72,20 +167,78 @@ impl Scheduler {
         Self {
             all_nodes: IndexSet::new(),
             ready: BTreeMap::new(),
+            ready_order: IndexSet::new(),
+            queue_limit: None,
+            overflow: QueueOverflow::default(),
+            held: VecDeque::new(),
+            shed: Vec::new(),
             completed: BTreeSet::new(),
             failed: BTreeSet::new(),
+            skipped: BTreeSet::new(),
+            cancelled: BTreeSet::new(),
+            run_cancelled: false,
+            guards: IndexMap::new(),
+            outcomes: IndexMap::new(),
+            fan_outs: IndexMap::new(),
+            item_parents: IndexMap::new(),
+            budget: ResourceBudget::default(),
+            demands: IndexMap::new(),
+            charged: ResourceUsage::default(),
+            violations: Vec::new(),
             dependencies: IndexMap::new(),
             dependents: IndexMap::new(),
+            priorities: IndexMap::new(),
+            topological_indices: IndexMap::new(),
             time: LogicalTime::zero(),
         }
     }
 
+    /// Set the run's resource budget
+    #[must_use]
+    pub fn with_budget(mut self, budget: ResourceBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Add a node charged its contract's worst case when admitted
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a cycle is detected
+    pub fn add_node_with_contract(
+        &mut self,
+        node_id: NodeId,
+        deps: IndexSet<NodeId>,
+        contract: &ResourceContract,
+    ) -> CoreResult<()> {
+        self.demands.insert(node_id, ResourceUsage::from_contract(contract));
+        self.add_node(node_id, deps)
+    }
+
     /// Add a node to the scheduler
     ///
     /// # Errors
     ///
     /// Returns error if a cycle is detected
     pub fn add_node(&mut self, node_id: NodeId, deps: IndexSet<NodeId>) -> CoreResult<()> {
+        self.add_node_with_priority(node_id, deps, 0)
+    }
+
+    /// Add a node with a scheduling priority
+    ///
+    /// Higher priorities run first. Nodes with equal priority run in
+    /// topological order, then in node hash order. Dependencies may be
+    /// added after the nodes that depend on them.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a cycle is detected
+    pub fn add_node_with_priority(
+        &mut self,
+        node_id: NodeId,
+        deps: IndexSet<NodeId>,
+        priority: u64,
+    ) -> CoreResult<()> {
         // Check for direct self-cycle
         if deps.contains(&node_id) {
             return Err(CoreError::Validation {
@@ -106,6 +259,7 @@ impl Scheduler {
 
         self.all_nodes.insert(node_id);
         self.dependencies.insert(node_id, deps.clone());
+        self.priorities.insert(node_id, priority);
 
         // Update dependents map
         for dep in &deps {
@@ -114,10 +268,11 @@ impl Scheduler {
                 .or_insert_with(IndexSet::new)
                 .insert(node_id);
         }
+        self.update_topological_indices(node_id);
 
         // If no dependencies, node is ready
         if deps.is_empty() && !self.completed.contains(&node_id) {
-            self.ready.insert((0, node_id), node_id);
+            self.admit(node_id);
         }
 
         Ok(())
@@ -137,9 +292,9 @@ impl Scheduler {
     /// This is deterministic: always returns the highest priority ready node
     #[must_use]
     pub fn decide(&self) -> ScheduleDecision {
-        if let Some((_, node_id)) = self.ready.keys().next() {
+        if let Some(node_id) = self.ready.values().next() {
             ScheduleDecision::Run(*node_id)
-        } else if self.completed.len() + self.failed.len() < self.all_nodes.len() {
+        } else if self.finished_count() < self.all_nodes.len() {
             ScheduleDecision::Wait
         } else {
             ScheduleDecision::Complete
@@ -152,28 +307,30 @@ impl Scheduler {
     ///
     /// Returns error if node wasn't ready
     pub fn mark_complete(&mut self, node_id: NodeId) -> CoreResult<()> {
-        // Remove from ready queue
-        let key = self.ready
-            .iter()
-            .find(|(_, id)| **id == node_id)
-            .map(|(k, _)| *k);
-
-        if let Some(key) = key {
-            self.ready.remove(&key);
+        if !self.outcomes.contains_key(&node_id)
+            && self.guards.values().any(|(condition, _)| *condition == node_id)
+        {
+            return Err(CoreError::Validation {
+                field: "outcome".to_string(),
+                reason: format!(
+                    "conditional {:?} must be completed with complete_condition",
+                    node_id
+                ),
+            });
         }
 
+        self.unready(node_id);
         self.completed.insert(node_id);
         self.tick();
 
-        // Check if any dependents are now ready
-        if let Some(dependents) = self.dependents.get(&node_id) {
-            for dep in dependents {
-                if self.is_ready(*dep) && !self.completed.contains(dep) {
-                    self.ready.insert((0, *dep), *dep);
-                }
-            }
+        // A completed item makes room for the next one of its fan-out
+        if let Some(parent) = self.item_parents.get(&node_id).copied() {
+            self.release_item(parent, true);
         }
 
+        // Check if any dependents are now ready
+        self.release_dependents(node_id);
+
         Ok(())
     }
 
@@ -183,7 +340,235 @@ impl Scheduler {
     ///
     /// Returns error if node wasn't ready
     pub fn mark_failed(&mut self, node_id: NodeId) -> CoreResult<()> {
-        // Remove from ready queue
+        self.unready(node_id);
+        self.failed.insert(node_id);
+        self.tick();
+
+        // A failed item fails its fan-out; unreleased items never run
+        if let Some(parent) = self.item_parents.get(&node_id).copied() {
+            self.release_item(parent, false);
+        }
+
+        Ok(())
+    }
+
+    /// Run `node_id` only when `condition` completes with `outcome`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if either node is unknown or `node_id` does not depend
+    /// on `condition`
+    pub fn add_guard(
+        &mut self,
+        node_id: NodeId,
+        condition: NodeId,
+        outcome: bool,
+    ) -> CoreResult<()> {
+        for id in [node_id, condition] {
+            if !self.all_nodes.contains(&id) {
+                return Err(CoreError::NotFound {
+                    kind: "Node".to_string(),
+                    id: format!("{:?}", id),
+                });
+            }
+        }
+        if !self.dependencies.get(&node_id).is_some_and(|deps| deps.contains(&condition)) {
+            return Err(CoreError::Validation {
+                field: "guard".to_string(),
+                reason: format!("node {:?} does not depend on {:?}", node_id, condition),
+            });
+        }
+
+        self.guards.insert(node_id, (condition, outcome));
+        Ok(())
+    }
+
+    /// Complete a conditional node with its predicate's outcome
+    ///
+    /// Nodes guarded on the other outcome are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the node cannot be completed
+    pub fn complete_condition(&mut self, node_id: NodeId, outcome: bool) -> CoreResult<()> {
+        self.outcomes.insert(node_id, outcome);
+        self.mark_complete(node_id)
+    }
+
+    /// Fan a ready node out into `count` item nodes
+    ///
+    /// The node leaves the ready queue and at most `max_parallel` items are
+    /// ready at a time, released in item order. Item IDs are derived from
+    /// the node's ID and item index. Once every item has completed the node
+    /// is ready again so its results can be aggregated.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the node is not ready, was already fanned out, or
+    /// `max_parallel` is zero
+    pub fn fan_out(
+        &mut self,
+        node_id: NodeId,
+        count: usize,
+        max_parallel: usize,
+    ) -> CoreResult<Vec<NodeId>> {
+        if max_parallel == 0 {
+            return Err(CoreError::Validation {
+                field: "max_parallel".to_string(),
+                reason: "fan-out parallelism must be at least 1".to_string(),
+            });
+        }
+        if self.fan_outs.contains_key(&node_id) {
+            return Err(CoreError::AlreadyExists {
+                kind: "FanOut".to_string(),
+                id: format!("{:?}", node_id),
+            });
+        }
+        if !self.ready.contains_key(&self.ready_key(node_id)) {
+            return Err(CoreError::Validation {
+                field: "fan_out".to_string(),
+                reason: format!("node {:?} is not ready", node_id),
+            });
+        }
+
+        let priority = self.priority(node_id).unwrap_or(0);
+        let items: Vec<NodeId> = (0..count)
+            .map(|index| NodeId::from_name(&format!("{}/{}", node_id, index)))
+            .collect();
+        for item in &items {
+            self.all_nodes.insert(*item);
+            self.dependencies.insert(*item, IndexSet::new());
+            self.priorities.insert(*item, priority);
+            self.topological_indices.insert(*item, self.topological_index(node_id));
+            self.item_parents.insert(*item, node_id);
+        }
+
+        let mut pending: VecDeque<NodeId> = items.iter().copied().collect();
+        if count > 0 {
+            self.unready(node_id);
+            for item in pending.drain(..max_parallel.min(count)) {
+                self.admit(item);
+            }
+        }
+        self.fan_outs.insert(node_id, FanOut {
+            items: items.clone(),
+            pending,
+            outstanding: count,
+        });
+
+        Ok(items)
+    }
+
+    /// Get the items of a fanned-out node, in item order
+    #[must_use]
+    pub fn fan_out_items(&self, node_id: NodeId) -> Option<&[NodeId]> {
+        self.fan_outs.get(&node_id).map(|fan_out| fan_out.items.as_slice())
+    }
+
+    /// Account for a finished item of `parent`'s fan-out
+    fn release_item(&mut self, parent: NodeId, succeeded: bool) {
+        let Some(fan_out) = self.fan_outs.get_mut(&parent) else {
+            return;
+        };
+        fan_out.outstanding = fan_out.outstanding.saturating_sub(1);
+
+        if !succeeded {
+            let pending: Vec<NodeId> = fan_out.pending.drain(..).collect();
+            self.skipped.extend(pending);
+            self.failed.insert(parent);
+            return;
+        }
+        if self.failed.contains(&parent) {
+            return;
+        }
+        if let Some(next) = fan_out.pending.pop_front() {
+            self.admit(next);
+        } else if fan_out.outstanding == 0 {
+            // Admitted when it first became ready; not charged again
+            self.enqueue(parent);
+        }
+    }
+
+    /// Queue, or skip, dependents whose dependencies have all finished
+    fn release_dependents(&mut self, node_id: NodeId) {
+        let Some(dependents) = self.dependents.get(&node_id).cloned() else {
+            return;
+        };
+        for dep in dependents {
+            if self.is_finished(dep) || !self.is_ready(dep) {
+                continue;
+            }
+            let guard = self.guards.get(&dep).map(|(condition, outcome)| {
+                self.outcomes.get(condition).map(|taken| taken == outcome)
+            });
+            let all_skipped = self
+                .dependencies
+                .get(&dep)
+                .is_some_and(|deps| deps.iter().all(|d| self.skipped.contains(d)));
+
+            match guard {
+                Some(Some(false)) => self.skip(dep),
+                _ if all_skipped => self.skip(dep),
+                // Conditional completed without an outcome; cannot happen
+                // through `complete_condition`
+                Some(None) => {}
+                _ => self.admit(dep),
+            }
+        }
+    }
+
+    /// Queue a node if its demand fits the budget, otherwise fail it
+    fn admit(&mut self, node_id: NodeId) {
+        let demand = self.demands.get(&node_id).copied().unwrap_or_default();
+        match self.budget.admit(node_id, self.charged, demand) {
+            Ok(()) => {
+                self.charged = self.charged.plus(demand);
+                self.enqueue(node_id);
+            }
+            Err(exceeded) => {
+                self.failed.insert(node_id);
+                self.violations.push(exceeded);
+            }
+        }
+    }
+
+    /// Queue a node, holding it or shedding the oldest ready node if the
+    /// ready queue is full
+    fn enqueue(&mut self, node_id: NodeId) {
+        let full = self.queue_limit.is_some_and(|limit| self.ready.len() >= limit);
+        if !full {
+            self.insert_ready(node_id);
+            return;
+        }
+        match self.overflow {
+            QueueOverflow::Hold => self.held.push_back(node_id),
+            QueueOverflow::ShedOldest => {
+                let Some(oldest) = self.ready_order.first().copied() else {
+                    self.insert_ready(node_id);
+                    return;
+                };
+                self.remove_ready(oldest);
+                self.skipped.insert(oldest);
+                self.shed.push(oldest);
+                self.insert_ready(node_id);
+                // A shed item fails its fan-out, like a failed one
+                if let Some(parent) = self.item_parents.get(&oldest).copied() {
+                    self.release_item(parent, false);
+                }
+                self.release_dependents(oldest);
+            }
+        }
+    }
+
+    /// Put a node in the ready queue
+    fn insert_ready(&mut self, node_id: NodeId) {
+        self.ready.insert(self.ready_key(node_id), node_id);
+        self.ready_order.insert(node_id);
+    }
+
+    /// Remove a node from the ready queue without releasing held nodes
+    fn remove_ready(&mut self, node_id: NodeId) {
+        self.ready_order.shift_remove(&node_id);
         let key = self.ready
             .iter()
             .find(|(_, id)| **id == node_id)
@@ -192,17 +577,180 @@ impl Scheduler {
         if let Some(key) = key {
             self.ready.remove(&key);
         }
+    }
 
-        self.failed.insert(node_id);
+    /// Move held nodes into the ready queue while it has room
+    fn release_held(&mut self) {
+        while self.queue_limit.is_none_or(|limit| self.ready.len() < limit) {
+            let Some(node_id) = self.held.pop_front() else {
+                break;
+            };
+            self.insert_ready(node_id);
+        }
+    }
+
+    /// Bound the ready queue to `limit` nodes, or remove the bound
+    ///
+    /// A limit of zero is treated as one. Raising the limit releases held
+    /// nodes; lowering it leaves already-ready nodes in the queue.
+    pub fn set_queue_limit(&mut self, limit: Option<usize>, overflow: QueueOverflow) {
+        self.queue_limit = limit.map(|limit| limit.max(1));
+        self.overflow = overflow;
+        self.release_held();
+    }
+
+    /// Get the ready-queue bound, if any
+    #[must_use]
+    pub fn queue_limit(&self) -> Option<usize> {
+        self.queue_limit
+    }
+
+    /// Get the number of nodes held for room in the ready queue
+    #[must_use]
+    pub fn held_count(&self) -> usize {
+        self.held.len()
+    }
+
+    /// Take the nodes shed from the ready queue since the last call
+    pub fn take_shed(&mut self) -> Vec<NodeId> {
+        std::mem::take(&mut self.shed)
+    }
+
+    /// Usage charged against the budget so far
+    #[must_use]
+    pub fn charged(&self) -> ResourceUsage {
+        self.charged
+    }
+
+    /// Take the nodes refused admission since the last call
+    pub fn take_budget_violations(&mut self) -> Vec<BudgetExceeded> {
+        std::mem::take(&mut self.violations)
+    }
+
+    /// Cancel the run
+    ///
+    /// Ready and held nodes are cancelled and every other unfinished node,
+    /// including the rest of a fan-out, is skipped. Both lists are in the
+    /// order the nodes would have run: by ready-queue key, so neither the
+    /// order nodes were added nor the order they became ready matters.
+    ///
+    /// Returns `None` if the run was already cancelled.
+    pub fn cancel(&mut self) -> Option<Cancellation> {
+        if self.run_cancelled {
+            return None;
+        }
+        self.run_cancelled = true;
+
+        let mut cancelled: Vec<NodeId> = self
+            .ready
+            .values()
+            .copied()
+            .chain(self.held.drain(..))
+            .collect();
+        cancelled.sort_by_key(|id| self.ready_key(*id));
+        self.ready.clear();
+        self.ready_order.clear();
+        self.cancelled.extend(cancelled.iter().copied());
+
+        let mut skipped: Vec<NodeId> = self
+            .all_nodes
+            .iter()
+            .copied()
+            .filter(|id| !self.is_finished(*id))
+            .collect();
+        skipped.sort_by_key(|id| self.ready_key(*id));
+        self.skipped.extend(skipped.iter().copied());
         self.tick();
 
-        Ok(())
+        Some(Cancellation { cancelled, skipped })
+    }
+
+    /// Check if the run was cancelled
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.run_cancelled
+    }
+
+    /// Skip a node on a branch that was not taken, and what only it feeds
+    fn skip(&mut self, node_id: NodeId) {
+        self.unready(node_id);
+        self.skipped.insert(node_id);
+        self.release_dependents(node_id);
+    }
+
+    /// Remove a node from the ready queue, making room for held nodes
+    fn unready(&mut self, node_id: NodeId) {
+        self.remove_ready(node_id);
+        self.release_held();
+    }
+
+    /// Check if a node completed, failed, was skipped, or was cancelled
+    fn is_finished(&self, node_id: NodeId) -> bool {
+        self.completed.contains(&node_id)
+            || self.failed.contains(&node_id)
+            || self.skipped.contains(&node_id)
+            || self.cancelled.contains(&node_id)
+    }
+
+    /// Number of nodes that completed, failed, were skipped, or were
+    /// cancelled
+    fn finished_count(&self) -> usize {
+        self.completed.len() + self.failed.len() + self.skipped.len() + self.cancelled.len()
+    }
+
+    /// Ready-queue key: priority descending, then topological index, then
+    /// node hash
+    fn ready_key(&self, node_id: NodeId) -> ReadyKey {
+        let priority = self.priorities.get(&node_id).copied().unwrap_or(0);
+        (
+            Reverse(priority),
+            self.topological_index(node_id),
+            Hash::compute(node_id.as_bytes()),
+        )
     }
 
-    /// Check if a node is ready (all dependencies completed)
+    /// Get a node's topological index
+    ///
+    /// Nodes without dependencies have index 0; any other node's index is
+    /// one more than the highest index among its dependencies. Fan-out
+    /// items share their node's index.
+    #[must_use]
+    pub fn topological_index(&self, node_id: NodeId) -> u64 {
+        self.topological_indices.get(&node_id).copied().unwrap_or(0)
+    }
+
+    /// Recompute the topological index of `node_id` and of every dependent
+    /// whose index it raises
+    fn update_topological_indices(&mut self, node_id: NodeId) {
+        let mut stack = vec![node_id];
+        while let Some(id) = stack.pop() {
+            let index = self.dependencies.get(&id).map_or(0, |deps| {
+                deps.iter()
+                    .map(|dep| self.topological_index(*dep) + 1)
+                    .max()
+                    .unwrap_or(0)
+            });
+            if id != node_id && self.topological_indices.get(&id) == Some(&index) {
+                continue;
+            }
+            self.topological_indices.insert(id, index);
+            if let Some(dependents) = self.dependents.get(&id) {
+                stack.extend(dependents.iter().copied());
+            }
+        }
+    }
+
+    /// Get a node's priority
+    #[must_use]
+    pub fn priority(&self, node_id: NodeId) -> Option<u64> {
+        self.priorities.get(&node_id).copied()
+    }
+
+    /// Check if a node is ready (all dependencies completed or skipped)
     fn is_ready(&self, node_id: NodeId) -> bool {
         if let Some(deps) = self.dependencies.get(&node_id) {
-            deps.iter().all(|dep| self.completed.contains(dep))
+            deps.iter()
+                .all(|dep| self.completed.contains(dep) || self.skipped.contains(dep))
         } else {
             true
         }
@@ -237,11 +785,28 @@ impl Scheduler {
         self.failed.len()
     }
 
+    /// Get number of skipped nodes
+    #[must_use]
+    pub fn skipped_count(&self) -> usize {
+        self.skipped.len()
+    }
+
+    /// Get number of cancelled nodes
+    #[must_use]
+    pub fn cancelled_count(&self) -> usize {
+        self.cancelled.len()
+    }
+
+    /// Get ready nodes in the order they would be run
+    #[must_use]
+    pub fn ready_nodes(&self) -> Vec<NodeId> {
+        self.ready.values().copied().collect()
+    }
+
     /// Check if execution is complete
     #[must_use]
     pub fn is_complete(&self) -> bool {
-        self.completed.len() + self.failed.len() == self.all_nodes.len()
-            && self.ready.is_empty()
+        self.finished_count() == self.all_nodes.len() && self.ready.is_empty()
     }
 
     /// Check if execution failed
@@ -253,17 +818,37 @@ impl Scheduler {
     /// Reset the scheduler state
     pub fn reset(&mut self) {
         self.ready.clear();
+        self.ready_order.clear();
+        self.held.clear();
+        self.shed.clear();
         self.completed.clear();
         self.failed.clear();
+        self.skipped.clear();
+        self.cancelled.clear();
+        self.run_cancelled = false;
+        self.outcomes.clear();
+        self.charged = ResourceUsage::default();
+        self.violations.clear();
         self.time = LogicalTime::zero();
 
+        // Forget fan-out items; they are recreated when the node runs again
+        for (item, _) in std::mem::take(&mut self.item_parents) {
+            self.all_nodes.shift_remove(&item);
+            self.dependencies.shift_remove(&item);
+            self.priorities.shift_remove(&item);
+            self.topological_indices.shift_remove(&item);
+        }
+        self.fan_outs.clear();
+
         // Re-populate ready queue with nodes that have no dependencies
-        for &node_id in &self.all_nodes {
-            if let Some(deps) = self.dependencies.get(&node_id) {
-                if deps.is_empty() {
-                    self.ready.insert((0, node_id), node_id);
-                }
-            }
+        let roots: Vec<NodeId> = self
+            .all_nodes
+            .iter()
+            .copied()
+            .filter(|id| self.dependencies.get(id).is_some_and(IndexSet::is_empty))
+            .collect();
+        for node_id in roots {
+            self.admit(node_id);
         }
     }
 
@@ -284,6 +869,12 @@ impl Scheduler {
     pub fn failed_nodes(&self) -> &BTreeSet<NodeId> {
         &self.failed
     }
+
+    /// Get skipped nodes
+    #[must_use]
+    pub fn skipped_nodes(&self) -> &BTreeSet<NodeId> {
+        &self.skipped
+    }
 }
 
 impl Default for Scheduler {
@@ -418,4 +1009,299 @@ mod tests {
         // Should run some node
         assert!(matches!(scheduler.decide(), ScheduleDecision::Run(_)));
     }
+
+    /// Drain the scheduler, returning nodes in the order they ran
+    fn run_order(scheduler: &mut Scheduler) -> Vec<NodeId> {
+        let mut order = Vec::new();
+        while let ScheduleDecision::Run(node) = scheduler.decide() {
+            scheduler.mark_complete(node).unwrap();
+            order.push(node);
+        }
+        order
+    }
+
+    #[test]
+    fn test_scheduler_equal_priority_tie_break() {
+        let mut ids = [make_test_id(), make_test_id(), make_test_id()];
+        ids.sort_by_key(|id| Hash::compute(id.as_bytes()));
+        let permutations = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+
+        for permutation in permutations {
+            let mut scheduler = Scheduler::new();
+            for index in permutation {
+                scheduler
+                    .add_node_with_priority(ids[index], IndexSet::new(), 5)
+                    .unwrap();
+            }
+            assert_eq!(run_order(&mut scheduler), ids.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_scheduler_tie_break_independent_of_readiness() {
+        let mut ids = [make_test_id(), make_test_id()];
+        ids.sort_by_key(|id| Hash::compute(id.as_bytes()));
+        let (low, high) = (ids[0], ids[1]);
+
+        // Whichever equal-priority node is unblocked first, the lower
+        // node hash still runs first once both are ready.
+        for (first, second) in [(low, high), (high, low)] {
+            let mut scheduler = Scheduler::new();
+            let (gate_a, gate_b) = (make_test_id(), make_test_id());
+            scheduler.add_node_with_priority(gate_a, IndexSet::new(), 10).unwrap();
+            scheduler.add_node_with_priority(gate_b, IndexSet::new(), 9).unwrap();
+            scheduler
+                .add_node_with_priority(first, [gate_a].into_iter().collect(), 1)
+                .unwrap();
+            scheduler
+                .add_node_with_priority(second, [gate_b].into_iter().collect(), 1)
+                .unwrap();
+
+            assert_eq!(run_order(&mut scheduler), vec![gate_a, gate_b, low, high]);
+        }
+    }
+
+    #[test]
+    fn test_scheduler_topological_index() {
+        let (a, b, c, d) = (make_test_id(), make_test_id(), make_test_id(), make_test_id());
+
+        // Dependencies added after their dependents still raise their index
+        let mut scheduler = Scheduler::new();
+        scheduler.add_node(d, [b, c].into_iter().collect()).unwrap();
+        assert_eq!(scheduler.topological_index(d), 1);
+        scheduler.add_node(c, [b].into_iter().collect()).unwrap();
+        scheduler.add_node(b, [a].into_iter().collect()).unwrap();
+        scheduler.add_node(a, IndexSet::new()).unwrap();
+        let indices: Vec<_> = [a, b, c, d].map(|id| scheduler.topological_index(id)).to_vec();
+        assert_eq!(indices, [0, 1, 2, 3]);
+
+        // Among equal priorities, a shallower node runs first even when
+        // the deeper one has the lower hash
+        let (shallow, root) = (make_test_id(), make_test_id());
+        let mut scheduler = Scheduler::new();
+        scheduler.add_node_with_priority(root, IndexSet::new(), 2).unwrap();
+        scheduler.add_node(a, [root].into_iter().collect()).unwrap();
+        scheduler.add_node(shallow, IndexSet::new()).unwrap();
+        assert_eq!(run_order(&mut scheduler), vec![root, shallow, a]);
+    }
+
+    #[test]
+    fn test_scheduler_priority_order() {
+        let low = make_test_id();
+        let high = make_test_id();
+        let mut scheduler = Scheduler::new();
+        scheduler.add_node_with_priority(low, IndexSet::new(), 1).unwrap();
+        scheduler.add_node_with_priority(high, IndexSet::new(), 2).unwrap();
+
+        assert_eq!(scheduler.priority(high), Some(2));
+        assert_eq!(run_order(&mut scheduler), vec![high, low]);
+    }
+
+    fn deps(ids: &[NodeId]) -> IndexSet<NodeId> {
+        ids.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_scheduler_conditional_skips_untaken_branch() {
+        let (cond, then_a, then_b, else_a, join) = (
+            make_test_id(),
+            make_test_id(),
+            make_test_id(),
+            make_test_id(),
+            make_test_id(),
+        );
+        let mut scheduler = Scheduler::new();
+        scheduler.add_node(cond, IndexSet::new()).unwrap();
+        scheduler.add_node(then_a, deps(&[cond])).unwrap();
+        scheduler.add_node(then_b, deps(&[then_a])).unwrap();
+        scheduler.add_node(else_a, deps(&[cond])).unwrap();
+        scheduler.add_node(join, deps(&[then_b, else_a])).unwrap();
+        scheduler.add_guard(then_a, cond, true).unwrap();
+        scheduler.add_guard(else_a, cond, false).unwrap();
+        assert!(scheduler.add_guard(join, cond, true).is_err());
+
+        // A conditional must report its outcome
+        assert!(scheduler.mark_complete(cond).is_err());
+        scheduler.complete_condition(cond, false).unwrap();
+
+        // The whole then-branch is skipped; the join waits on the else-branch
+        assert_eq!(scheduler.skipped_count(), 2);
+        assert_eq!(scheduler.ready_nodes(), vec![else_a]);
+        scheduler.mark_complete(else_a).unwrap();
+        assert_eq!(scheduler.ready_nodes(), vec![join]);
+        scheduler.mark_complete(join).unwrap();
+        assert!(scheduler.is_complete());
+        assert!(scheduler.skipped_nodes().contains(&then_b));
+    }
+
+    #[test]
+    fn test_scheduler_fan_out_bounded() {
+        let (map, after) = (make_test_id(), make_test_id());
+        let mut scheduler = Scheduler::new();
+        scheduler.add_node(map, IndexSet::new()).unwrap();
+        scheduler.add_node(after, deps(&[map])).unwrap();
+
+        let items = scheduler.fan_out(map, 5, 2).unwrap();
+        assert_eq!(items.len(), 5);
+        assert_eq!(items[0], NodeId::from_name(&format!("{}/0", map)));
+        assert_eq!(scheduler.fan_out_items(map), Some(items.as_slice()));
+        assert!(scheduler.fan_out(map, 5, 2).is_err());
+
+        // Items are released in order, never more than two at once
+        for done in 0..5 {
+            let ready = scheduler.ready_nodes();
+            assert_eq!(ready.len(), 2.min(5 - done));
+            assert!(ready.iter().all(|id| items[..(done + 2).min(5)].contains(id)));
+            let ScheduleDecision::Run(id) = scheduler.decide() else {
+                panic!("expected an item to run");
+            };
+            scheduler.mark_complete(id).unwrap();
+        }
+
+        // All items done: the node is back for its fan-in
+        assert_eq!(scheduler.ready_nodes(), vec![map]);
+        scheduler.mark_complete(map).unwrap();
+        scheduler.mark_complete(after).unwrap();
+        assert!(scheduler.is_complete());
+
+        scheduler.reset();
+        assert_eq!(scheduler.nodes().len(), 2);
+        assert_eq!(scheduler.fan_out_items(map), None);
+    }
+
+    #[test]
+    fn test_scheduler_fan_out_failure() {
+        let map = make_test_id();
+        let mut scheduler = Scheduler::new();
+        scheduler.add_node(map, IndexSet::new()).unwrap();
+        assert!(scheduler.fan_out(map, 3, 0).is_err());
+        let items = scheduler.fan_out(map, 3, 1).unwrap();
+
+        scheduler.mark_failed(items[0]).unwrap();
+        assert!(scheduler.failed_nodes().contains(&map));
+        assert_eq!(scheduler.skipped_count(), 2);
+        assert_eq!(scheduler.decide(), ScheduleDecision::Complete);
+
+        // An empty fan-out goes straight to its fan-in
+        let empty = make_test_id();
+        scheduler.add_node(empty, IndexSet::new()).unwrap();
+        assert!(scheduler.fan_out(empty, 0, 4).unwrap().is_empty());
+        assert_eq!(scheduler.decide(), ScheduleDecision::Run(empty));
+    }
+
+    #[test]
+    fn test_scheduler_budget_admission() {
+        use cathedral_plan::ResourceBounds;
+
+        let (a, b, c) = (make_test_id(), make_test_id(), make_test_id());
+        let contract = |memory: u64, fuel: u64| {
+            let mut contract =
+                ResourceContract::new().with_memory(ResourceBounds::new().with_max(memory));
+            contract.fuel = ResourceBounds::new().with_max(fuel);
+            contract
+        };
+        let mut scheduler =
+            Scheduler::new().with_budget(ResourceBudget::new().with_memory(100).with_fuel(10));
+        scheduler.add_node_with_contract(a, IndexSet::new(), &contract(60, 4)).unwrap();
+        scheduler.add_node_with_contract(b, [a].into_iter().collect(), &contract(40, 4)).unwrap();
+        scheduler.add_node_with_contract(c, [b].into_iter().collect(), &contract(0, 4)).unwrap();
+        assert_eq!(scheduler.charged(), ResourceUsage { memory: 60, cpu: 0, fuel: 4 });
+
+        scheduler.mark_complete(a).unwrap();
+        assert!(scheduler.take_budget_violations().is_empty());
+        scheduler.mark_complete(b).unwrap();
+
+        // Fuel is charged cumulatively, so the third node no longer fits
+        assert_eq!(scheduler.charged().fuel, 8);
+        assert_eq!(
+            scheduler.take_budget_violations(),
+            [BudgetExceeded {
+                node_id: c,
+                resource: "fuel".to_string(),
+                requested: 4,
+                remaining: 2,
+            }]
+        );
+        assert!(scheduler.take_budget_violations().is_empty());
+        assert_eq!(scheduler.decide(), ScheduleDecision::Complete);
+        assert!(scheduler.has_failures());
+
+        scheduler.reset();
+        assert_eq!(scheduler.charged().memory, 60);
+        assert!(scheduler.take_budget_violations().is_empty());
+    }
+
+    #[test]
+    fn test_scheduler_bounded_queue_holds() {
+        let ids: Vec<NodeId> = (0..4).map(|_| make_test_id()).collect();
+        let mut scheduler = Scheduler::new();
+        scheduler.set_queue_limit(Some(2), QueueOverflow::Hold);
+        for (priority, id) in ids.iter().enumerate() {
+            scheduler.add_node_with_priority(*id, IndexSet::new(), priority as u64).unwrap();
+        }
+        assert_eq!(scheduler.ready_nodes(), [ids[1], ids[0]]);
+        assert_eq!(scheduler.held_count(), 2);
+
+        // Held nodes enter the queue in the order they became ready
+        scheduler.mark_complete(ids[1]).unwrap();
+        assert_eq!(scheduler.ready_nodes(), [ids[2], ids[0]]);
+        assert_eq!(run_order(&mut scheduler), [ids[2], ids[3], ids[0]]);
+        assert!(scheduler.take_shed().is_empty());
+    }
+
+    #[test]
+    fn test_scheduler_shed_oldest() {
+        let (a, b, c, after) = (make_test_id(), make_test_id(), make_test_id(), make_test_id());
+        let mut scheduler = Scheduler::new();
+        scheduler.set_queue_limit(Some(2), QueueOverflow::ShedOldest);
+        scheduler.add_node(a, IndexSet::new()).unwrap();
+        scheduler.add_node(after, [a].into_iter().collect()).unwrap();
+        scheduler.add_node(b, IndexSet::new()).unwrap();
+        scheduler.add_node(c, IndexSet::new()).unwrap();
+
+        // The oldest ready node is shed, and so is what only it feeds
+        assert_eq!(scheduler.take_shed(), [a]);
+        assert!(scheduler.take_shed().is_empty());
+        let skipped: BTreeSet<NodeId> = [a, after].into_iter().collect();
+        assert_eq!(scheduler.skipped_nodes(), &skipped);
+        assert_eq!(scheduler.ready_count(), 2);
+        run_order(&mut scheduler);
+        assert_eq!(scheduler.decide(), ScheduleDecision::Complete);
+    }
+
+    #[test]
+    fn test_scheduler_cancel() {
+        let (a, b, c, d) = (make_test_id(), make_test_id(), make_test_id(), make_test_id());
+        let mut scheduler = Scheduler::new();
+        scheduler.set_queue_limit(Some(1), QueueOverflow::Hold);
+        scheduler.add_node_with_priority(a, IndexSet::new(), 1).unwrap();
+        scheduler.add_node(b, IndexSet::new()).unwrap();
+        scheduler.add_node(c, [a].into_iter().collect()).unwrap();
+        scheduler.add_node(d, [c].into_iter().collect()).unwrap();
+        scheduler.mark_complete(a).unwrap();
+        assert_eq!(scheduler.ready_nodes(), [b]);
+        assert_eq!(scheduler.held_count(), 1);
+
+        // Ready and held nodes are cancelled, the rest skipped
+        let cancellation = scheduler.cancel().unwrap();
+        assert_eq!(cancellation.cancelled, [b, c]);
+        assert_eq!(cancellation.skipped, [d]);
+        assert!(scheduler.is_cancelled());
+        assert_eq!(scheduler.cancelled_count(), 2);
+        assert_eq!(scheduler.held_count(), 0);
+        assert_eq!(scheduler.decide(), ScheduleDecision::Complete);
+        assert!(scheduler.cancel().is_none());
+
+        scheduler.reset();
+        assert!(!scheduler.is_cancelled());
+        assert_eq!(scheduler.cancelled_count(), 0);
+        assert_eq!(scheduler.ready_nodes(), [a]);
+    }
 }
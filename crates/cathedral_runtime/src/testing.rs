@@ -0,0 +1,291 @@
+//! Test support for golden-log determinism checks.
+//!
+//! [`assert_workflow_deterministic`] compiles a workflow, runs it twice on an
+//! [`ExecutionEngine`] with the same seed, and fails on the first event where
+//! the two logs differ. Downstream crates can add a one-line determinism test
+//! per workflow.
+//!
+//! Available with the `test-support` feature.
+
+use crate::engine::{EngineConfig, ExecutionEngine};
+use crate::executor::SeededEventIds;
+use cathedral_core::{CapabilitySet, CoreResult, RunId};
+use cathedral_log::{CanonicalEncode, Event};
+use cathedral_plan::Compiler;
+use cathedral_tool::adapter::builtin::{EchoTool, LengthTool};
+use cathedral_tool::registry::SharedRegistry;
+use cathedral_tool::{InputSchema, OutputSchema, ToolSchema};
+use std::sync::Arc;
+
+/// First event at which two workflow logs differ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogDivergence {
+    /// Index of the first differing event
+    pub index: usize,
+    /// Event from the first run, if the log is long enough
+    pub left: Option<Event>,
+    /// Event from the second run, if the log is long enough
+    pub right: Option<Event>,
+}
+
+impl std::fmt::Display for LogDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Workflow logs diverge at event {}:\n  first run:  {:?}\n  second run: {:?}",
+            self.index, self.left, self.right
+        )
+    }
+}
+
+/// Registry holding the builtin `echo` and `length` tools
+///
+/// Both accept any JSON input.
+#[must_use]
+pub fn builtin_tools() -> Arc<SharedRegistry> {
+    let registry = Arc::new(SharedRegistry::new());
+    for (tool, name) in [
+        (Arc::new(EchoTool) as Arc<dyn cathedral_tool::Tool>, "echo"),
+        (Arc::new(LengthTool), "length"),
+    ] {
+        let schema = ToolSchema::new(name.to_string(), "1.0.0".to_string())
+            .with_input(InputSchema::new().with_json_schema("{}".to_string()))
+            .with_output(OutputSchema::new().with_json_schema("{}".to_string()));
+        registry
+            .register(tool, schema)
+            .expect("builtin tools have distinct names");
+    }
+    registry
+}
+
+/// Compile and run a workflow on an execution engine, returning its log
+///
+/// The run is granted `capabilities`, resolves tool nodes against `tools`
+/// and binds `inputs` by name for tool input bindings. The compiler derives
+/// node IDs from the workflow's content, and the run ID and every event ID
+/// are derived from `seed`, so fresh random IDs do not make two runs differ.
+///
+/// # Errors
+///
+/// Returns error if the workflow fails to parse or compile, or if the run
+/// fails
+pub fn run_workflow(
+    source: &str,
+    inputs: &[(&str, &[u8])],
+    seed: u64,
+    capabilities: &CapabilitySet,
+    tools: Arc<SharedRegistry>,
+) -> CoreResult<Vec<Event>> {
+    let ast = cathedral_plan::parse(source)?;
+    let dag = Compiler::new().compile(&ast)?.dag;
+    let config = EngineConfig {
+        capabilities: capabilities.clone(),
+        tools: Some(tools),
+        inputs: inputs
+            .iter()
+            .map(|(name, data)| ((*name).to_string(), data.to_vec()))
+            .collect(),
+        event_ids: Some(SeededEventIds::new(seed)),
+        ..EngineConfig::default()
+    };
+
+    let mut engine = ExecutionEngine::new(RunId::from_bytes(seeded_bytes(seed, 0)), config);
+    engine.add_dag(&dag)?;
+    engine.run()?;
+    Ok(engine.events().to_vec())
+}
+
+/// Find the first event where two logs differ in canonical encoding
+#[must_use]
+pub fn first_divergence(left: &[Event], right: &[Event]) -> Option<LogDivergence> {
+    (0..left.len().max(right.len()))
+        .find(|&index| {
+            left.get(index).map(CanonicalEncode::encode)
+                != right.get(index).map(CanonicalEncode::encode)
+        })
+        .map(|index| LogDivergence {
+            index,
+            left: left.get(index).cloned(),
+            right: right.get(index).cloned(),
+        })
+}
+
+/// Run a workflow twice and report the first divergent event, if any
+///
+/// # Errors
+///
+/// Returns error if either run fails
+pub fn check_workflow_deterministic(
+    source: &str,
+    inputs: &[(&str, &[u8])],
+    seed: u64,
+    capabilities: &CapabilitySet,
+    tools: Arc<SharedRegistry>,
+) -> CoreResult<Option<LogDivergence>> {
+    let first = run_workflow(source, inputs, seed, capabilities, Arc::clone(&tools))?;
+    let second = run_workflow(source, inputs, seed, capabilities, tools)?;
+    Ok(first_divergence(&first, &second))
+}
+
+/// Assert that a workflow produces byte-identical logs across two runs
+///
+/// Runs against [`builtin_tools`].
+///
+/// # Panics
+///
+/// Panics if either run fails or the logs diverge, naming the first
+/// divergent event
+pub fn assert_workflow_deterministic(
+    source: &str,
+    inputs: &[(&str, &[u8])],
+    seed: u64,
+    capabilities: &CapabilitySet,
+) {
+    assert_workflow_deterministic_with_tools(source, inputs, seed, capabilities, builtin_tools());
+}
+
+/// Assert that a workflow produces byte-identical logs using `tools`
+///
+/// # Panics
+///
+/// Panics if either run fails or the logs diverge, naming the first
+/// divergent event
+pub fn assert_workflow_deterministic_with_tools(
+    source: &str,
+    inputs: &[(&str, &[u8])],
+    seed: u64,
+    capabilities: &CapabilitySet,
+    tools: Arc<SharedRegistry>,
+) {
+    match check_workflow_deterministic(source, inputs, seed, capabilities, tools) {
+        Ok(None) => {}
+        Ok(Some(divergence)) => panic!("{}", divergence),
+        Err(err) => panic!("Workflow failed to run: {}", err),
+    }
+}
+
+/// Build ID bytes from the seed and a counter
+fn seeded_bytes(seed: u64, counter: u64) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&seed.to_be_bytes());
+    bytes[8..].copy_from_slice(&counter.to_be_bytes());
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_log::EventKind;
+
+    const WORKFLOW: &str = "input data: json\n\
+                            tool \"echo\" ^1.0 <- data -> echoed\n\
+                            tool \"length\" <- data\n\
+                            output result = echoed\n";
+
+    fn run(seed: u64) -> Vec<Event> {
+        let inputs: &[(&str, &[u8])] = &[("data", br#"{"n":1}"#)];
+        run_workflow(WORKFLOW, inputs, seed, &CapabilitySet::new(), builtin_tools()).unwrap()
+    }
+
+    #[test]
+    fn test_workflow_deterministic() {
+        let inputs: &[(&str, &[u8])] = &[("data", br#"{"n":1}"#)];
+        assert_workflow_deterministic(WORKFLOW, inputs, 7, &CapabilitySet::new());
+    }
+
+    #[test]
+    fn test_run_workflow_log() {
+        let events = run(7);
+        let kinds: Vec<EventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::RunCreated,
+                EventKind::NodeStarted,
+                EventKind::NodeCompleted,
+                EventKind::ToolInvoked,
+                EventKind::ToolCompleted,
+                EventKind::ToolInvoked,
+                EventKind::ToolCompleted,
+                EventKind::NodeStarted,
+                EventKind::NodeCompleted,
+            ]
+        );
+        assert_eq!(events[4].payload, b"7");
+
+        let other = run(8);
+        assert!(first_divergence(&events, &other).is_some());
+    }
+
+    #[test]
+    fn test_run_workflow_keeps_compiled_node_ids() {
+        let ast = cathedral_plan::parse(WORKFLOW).unwrap();
+        let dag = Compiler::new().compile(&ast).unwrap().dag;
+        for event in run(7).iter().filter(|e| e.kind == EventKind::NodeStarted) {
+            assert!(dag.nodes.contains_key(&event.node_id));
+        }
+    }
+
+    #[test]
+    fn test_run_workflow_gates_capabilities() {
+        use cathedral_core::Capability;
+        use cathedral_tool::ToolOutput;
+
+        struct ReadFile;
+        impl cathedral_tool::Tool for ReadFile {
+            fn name(&self) -> &str {
+                "read_file"
+            }
+
+            fn execute(&self, input: &[u8]) -> CoreResult<ToolOutput> {
+                Ok(ToolOutput::success(input.to_vec()))
+            }
+        }
+
+        let source = "input path: json\ntool \"read_file\" <- path\n";
+        let tools = Arc::new(SharedRegistry::new());
+        let schema = ToolSchema::new("read_file".to_string(), "1.0.0".to_string())
+            .with_input(InputSchema::new().with_json_schema("{}".to_string()))
+            .with_output(OutputSchema::new().with_json_schema("{}".to_string()));
+        tools.register(Arc::new(ReadFile), schema).unwrap();
+        let inputs: &[(&str, &[u8])] = &[("path", br#""notes.txt""#)];
+        let kinds = |capabilities: &CapabilitySet| -> Vec<EventKind> {
+            run_workflow(source, inputs, 7, capabilities, Arc::clone(&tools))
+                .unwrap()
+                .iter()
+                .map(|e| e.kind)
+                .collect()
+        };
+
+        assert!(kinds(&CapabilitySet::new()).contains(&EventKind::NodeSkipped));
+        let granted: CapabilitySet = [Capability::FsRead {
+            prefixes: vec![".".to_string()],
+        }]
+        .into_iter()
+        .collect();
+        assert!(kinds(&granted).contains(&EventKind::ToolCompleted));
+        assert_workflow_deterministic_with_tools(source, inputs, 7, &granted, tools);
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let events = run(7);
+        assert_eq!(first_divergence(&events, &events), None);
+
+        let mut tampered = events.clone();
+        tampered[3] = tampered[3].clone().with_payload(br#"{"n":2}"#.to_vec());
+        let divergence = first_divergence(&events, &tampered).unwrap();
+        assert_eq!(divergence.index, 3);
+        assert!(divergence.to_string().contains("diverge at event 3"));
+
+        let divergence = first_divergence(&events, &events[..4]).unwrap();
+        assert_eq!(divergence.index, 4);
+        assert_eq!(divergence.right, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Workflow failed to run")]
+    fn test_assert_workflow_deterministic_reports_errors() {
+        assert_workflow_deterministic("bogus", &[], 7, &CapabilitySet::new());
+    }
+}
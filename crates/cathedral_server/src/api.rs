@@ -1,16 +1,220 @@
 //! API server
+//!
+//! Routes:
+//!
+//! - `POST /runs`: submit a compiled DAG
+//! - `GET /runs`: the caller's runs
+//! - `GET /runs/{id}`: run status and per-node progress
+//! - `GET /runs/{id}/dag`: the DAG the run was submitted with
+//! - `GET /runs/{id}/events?from=&limit=`: a page of the run's event log
+//! - `GET /runs/{id}/stream`: server-sent events of the run's log as it
+//!   grows, resuming after the position in `Last-Event-ID`
+//! - `GET /runs/{id}/artifacts/{node}`: output of a completed node
+//! - `DELETE /runs/{id}`: cancel a run
+//! - `GET /metrics`: execution and cluster metrics for Prometheus
+//! - `GET /audit?run=&actor=&action=&format=`: the audit trail of the
+//!   caller's API calls and runs, as JSON, JSON lines or CSV
+//! - `GET /cluster`: leader, term, voters and every member's heartbeat age
+//!   and in-flight tasks
+//! - `GET /cluster/results`: completed task results, in the order the log
+//!   committed them
+//! - `POST /cluster/members/{node}/drain?timeout_ms=`: stop assigning to a
+//!   worker, wait for its running tasks and deregister it
+//! - `POST /cluster/members/{node}/promote`: make a member a voter
+//!
+//! Every call is recorded in the audit log under `<data_dir>/audit`. With
+//! quotas, every route counts against the caller's rate limit, and denials
+//! are recorded in the audit log too.
 
-use cathedral_core::error::CoreResult;
+use crate::auth::{AuthConfig, Authenticator};
+use crate::handler::{self, Handler};
+use crate::middleware;
+use crate::quota::{QuotaConfig, Quotas};
+use axum::middleware::from_fn_with_state;
+use axum::routing::{get, post};
+use axum::Router;
+use cathedral_cluster::Coordinator;
+use cathedral_core::error::{CoreError, CoreResult};
+use cathedral_log::{LiveStream, SegmentConfig, SegmentedStream};
+use cathedral_runtime::EngineConfig;
+use cathedral_storage::MasterKey;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tower_http::trace::TraceLayer;
 
-pub struct ApiServer;
-pub struct ServerConfig;
+/// Server configuration
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Address to listen on
+    pub bind: String,
+    /// Directory holding the event logs of runs, one directory per tenant
+    pub data_dir: PathBuf,
+    /// Engine configuration every run executes with
+    pub engine: EngineConfig,
+    /// Events per page when a request sets no limit
+    pub page_size: usize,
+    /// Largest page a request may ask for
+    pub max_page_size: usize,
+    /// Key wrapping each run's data key; without it run data is stored
+    /// unencrypted
+    pub master_key: Option<MasterKey>,
+}
+
+impl ServerConfig {
+    /// Create a config with the default engine and 100-event pages
+    #[must_use]
+    pub fn new(bind: impl Into<String>, data_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            bind: bind.into(),
+            data_dir: data_dir.into(),
+            engine: EngineConfig::default(),
+            page_size: 100,
+            max_page_size: 1000,
+            master_key: None,
+        }
+    }
+
+    /// Set the engine configuration
+    #[must_use]
+    pub fn with_engine(mut self, engine: EngineConfig) -> Self {
+        self.engine = engine;
+        self
+    }
+
+    /// Set the default and largest event page sizes
+    #[must_use]
+    pub fn with_page_size(mut self, page_size: usize, max_page_size: usize) -> Self {
+        self.page_size = page_size;
+        self.max_page_size = max_page_size.max(page_size);
+        self
+    }
+
+    /// Seal every run's blobs under a fresh data key wrapped by `key`
+    ///
+    /// The wrapped key is kept in the manifest of the run's log.
+    #[must_use]
+    pub fn with_master_key(mut self, key: MasterKey) -> Self {
+        self.master_key = Some(key);
+        self
+    }
+}
+
+/// HTTP API server
+pub struct ApiServer {
+    /// Address to listen on
+    bind: String,
+    /// Log of API calls and quota denials
+    audit: Arc<LiveStream>,
+    /// Shared request state
+    handler: Handler,
+}
 
 impl ApiServer {
-    pub fn new(_bind: &str) -> CoreResult<Self> {
-        Ok(ApiServer)
+    /// Create a server
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the data directory or its audit log cannot be
+    /// created
+    pub fn new(config: ServerConfig) -> CoreResult<Self> {
+        std::fs::create_dir_all(&config.data_dir).map_err(|e| CoreError::Internal {
+            message: format!("Failed to create data directory: {}", e),
+        })?;
+        let (audit, _) = SegmentedStream::open(SegmentConfig::new(config.data_dir.join("audit")))?;
+        let audit = Arc::new(LiveStream::new(audit));
+        Ok(Self {
+            bind: config.bind.clone(),
+            audit: Arc::clone(&audit),
+            handler: Handler::new(config).with_audit_log(audit),
+        })
+    }
+
+    /// Admit runs through a cluster coordinator
+    #[must_use]
+    pub fn with_coordinator(mut self, coordinator: Arc<Coordinator>) -> Self {
+        self.handler = self.handler.with_coordinator(coordinator);
+        self
     }
 
+    /// Require a bearer token from `auth` on every request
+    #[must_use]
+    pub fn with_auth(mut self, auth: AuthConfig) -> Self {
+        self.handler = self.handler.with_authenticator(Authenticator::new(auth));
+        self
+    }
+
+    /// Enforce rate limits and tenant quotas, recording denials in the
+    /// audit log
+    #[must_use]
+    pub fn with_quotas(mut self, quotas: QuotaConfig) -> Self {
+        let quotas = Quotas::new(quotas, Arc::clone(&self.audit));
+        self.handler = self.handler.with_quotas(quotas);
+        self
+    }
+
+    /// Get the router serving the API
+    pub fn router(&self) -> Router {
+        router(self.handler.clone()).layer(TraceLayer::new_for_http())
+    }
+
+    /// Listen and serve until the process exits
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the address cannot be bound or serving fails
     pub async fn serve(self) -> CoreResult<()> {
-        Ok(())
+        let listener = tokio::net::TcpListener::bind(&self.bind)
+            .await
+            .map_err(|e| CoreError::Internal {
+                message: format!("Failed to bind {}: {}", self.bind, e),
+            })?;
+        tracing::info!(bind = %self.bind, "Serving API");
+        axum::serve(listener, self.router())
+            .await
+            .map_err(|e| CoreError::Internal {
+                message: format!("Server failed: {}", e),
+            })
     }
 }
+
+/// Build the API routes over shared handler state
+///
+/// If the handler has an authenticator, every route requires a token. If
+/// it has an audit log, every call is recorded. If it has quotas, every
+/// route is rate limited.
+pub fn router(handler: Handler) -> Router {
+    let routes = Router::new()
+        .route("/runs", get(handler::list_runs).post(handler::submit_run))
+        .route("/runs/{id}", get(handler::run_status).delete(handler::cancel_run))
+        .route("/runs/{id}/dag", get(handler::run_dag))
+        .route("/runs/{id}/events", get(handler::run_events))
+        .route("/runs/{id}/stream", get(handler::run_stream))
+        .route("/runs/{id}/artifacts/{node}", get(handler::run_artifact))
+        .route("/metrics", get(handler::metrics))
+        .route("/audit", get(handler::audit))
+        .route("/cluster", get(handler::cluster_status))
+        .route("/cluster/results", get(handler::cluster_results))
+        .route("/cluster/members/{node}/drain", post(handler::drain_member))
+        .route("/cluster/members/{node}/promote", post(handler::promote_member));
+    // Layers added later run first, so requests are authenticated before
+    // they are audited, and audited before they are counted against their
+    // token's rate limit
+    let routes = match handler.quotas() {
+        Some(quotas) => {
+            routes.route_layer(from_fn_with_state(Arc::clone(quotas), middleware::rate_limit))
+        }
+        None => routes,
+    };
+    let routes = match handler.audit_log() {
+        Some(audit) => routes.route_layer(from_fn_with_state(Arc::clone(audit), middleware::audit)),
+        None => routes,
+    };
+    let routes = match handler.authenticator() {
+        Some(authenticator) => routes.route_layer(from_fn_with_state(
+            Arc::clone(authenticator),
+            middleware::authenticate,
+        )),
+        None => routes,
+    };
+    routes.with_state(handler)
+}
@@ -1,5 +1,321 @@
 //! Authentication
+//!
+//! Clients authenticate with `Authorization: Bearer <token>`. Every token is
+//! bound to a [`TokenGrant`]: the tenant it acts for, the capabilities runs
+//! it submits may use and the API operations it may call. Only a hash of each token is kept, so a
+//! token file does not hold usable credentials.
 
-pub struct Authenticator;
-pub struct AuthConfig;
-pub struct AuthError;
+use cathedral_core::{Capability, CapabilitySet, Hash, TenantId};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+/// Name of the tenant requests act for when the server has no authentication
+pub const DEFAULT_TENANT: &str = "default";
+
+/// API operation a token may be allowed to call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// Submit runs
+    SubmitRun,
+    /// Read run status and events, including streams
+    ReadRun,
+    /// Read node outputs
+    ReadArtifacts,
+    /// Cancel runs
+    CancelRun,
+    /// Scrape server metrics
+    ReadMetrics,
+    /// Read the audit trail of the tenant's runs
+    ReadAudit,
+    /// Read cluster leadership and membership
+    ReadCluster,
+    /// Drain workers and promote members to voters
+    ManageCluster,
+}
+
+impl Operation {
+    /// Every operation
+    pub const ALL: [Operation; 8] = [
+        Self::SubmitRun,
+        Self::ReadRun,
+        Self::ReadArtifacts,
+        Self::CancelRun,
+        Self::ReadMetrics,
+        Self::ReadAudit,
+        Self::ReadCluster,
+        Self::ManageCluster,
+    ];
+}
+
+/// What a token is allowed to do
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenGrant {
+    /// Tenant whose runs the token submits and reads
+    pub tenant: TenantId,
+    /// Who holds the token, for logs and errors
+    pub subject: String,
+    /// Hex hash of the token
+    pub token_hash: String,
+    /// Capabilities runs submitted with the token may use
+    pub capabilities: CapabilitySet,
+    /// Operations the token may call
+    pub operations: BTreeSet<Operation>,
+}
+
+impl TokenGrant {
+    /// Grant nothing to a token of `tenant`
+    #[must_use]
+    pub fn new(tenant: TenantId, subject: impl Into<String>, token: &str) -> Self {
+        Self {
+            tenant,
+            subject: subject.into(),
+            token_hash: hash_token(token),
+            capabilities: CapabilitySet::new(),
+            operations: BTreeSet::new(),
+        }
+    }
+
+    /// Allow runs to use a capability
+    #[must_use]
+    pub fn with_capability(mut self, capability: Capability) -> Self {
+        self.capabilities.grant(capability);
+        self
+    }
+
+    /// Allow an operation
+    #[must_use]
+    pub fn with_operation(mut self, operation: Operation) -> Self {
+        self.operations.insert(operation);
+        self
+    }
+}
+
+/// Authentication configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Accepted tokens
+    pub tokens: Vec<TokenGrant>,
+}
+
+impl AuthConfig {
+    /// Accept no tokens
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept a token
+    #[must_use]
+    pub fn with_token(mut self, grant: TokenGrant) -> Self {
+        self.tokens.push(grant);
+        self
+    }
+
+    /// Load a JSON token file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read or parsed
+    pub fn load(path: &Path) -> Result<Self, AuthError> {
+        let json = std::fs::read_to_string(path).map_err(|e| AuthError::Config {
+            reason: format!("Failed to read {}: {}", path.display(), e),
+        })?;
+        serde_json::from_str(&json).map_err(|e| AuthError::Config {
+            reason: format!("Failed to parse {}: {}", path.display(), e),
+        })
+    }
+}
+
+/// Identity and authority resolved from a request's token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthContext {
+    /// Tenant the holder acts for
+    pub tenant: TenantId,
+    /// Hash of the token, identifying it without revealing it
+    pub token_hash: String,
+    /// Who holds the token
+    pub subject: String,
+    /// Capabilities runs submitted by the holder may use
+    pub capabilities: CapabilitySet,
+    /// Operations the holder may call
+    pub operations: BTreeSet<Operation>,
+}
+
+impl AuthContext {
+    /// Check that the holder may call an operation
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::Forbidden` if the token does not allow it
+    pub fn authorize(&self, operation: Operation) -> Result<(), AuthError> {
+        if self.operations.contains(&operation) {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden {
+                subject: self.subject.clone(),
+                operation,
+            })
+        }
+    }
+
+    /// Check that the holder's capabilities cover `capability`
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::CapabilityNotGranted` if they do not
+    pub fn check_capability(&self, capability: &Capability) -> Result<(), AuthError> {
+        if self.capabilities.covers(capability) {
+            Ok(())
+        } else {
+            Err(AuthError::CapabilityNotGranted {
+                subject: self.subject.clone(),
+                capability: capability.to_string(),
+            })
+        }
+    }
+}
+
+/// Resolves bearer tokens to their grants
+#[derive(Debug, Clone)]
+pub struct Authenticator {
+    /// Grants by token hash
+    grants: HashMap<String, TokenGrant>,
+}
+
+impl Authenticator {
+    /// Create an authenticator accepting the configured tokens
+    #[must_use]
+    pub fn new(config: AuthConfig) -> Self {
+        Self {
+            grants: config
+                .tokens
+                .into_iter()
+                .map(|grant| (grant.token_hash.clone(), grant))
+                .collect(),
+        }
+    }
+
+    /// Resolve the value of an `Authorization` header
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the header is missing, is not a bearer token, or
+    /// names an unknown token
+    pub fn authenticate(&self, authorization: Option<&str>) -> Result<AuthContext, AuthError> {
+        let value = authorization.ok_or(AuthError::MissingToken)?;
+        let token = value
+            .split_once(' ')
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, token)| token.trim())
+            .ok_or(AuthError::InvalidScheme)?;
+        let grant = self.grants.get(&hash_token(token)).ok_or(AuthError::UnknownToken)?;
+        Ok(AuthContext {
+            tenant: grant.tenant,
+            token_hash: grant.token_hash.clone(),
+            subject: grant.subject.clone(),
+            capabilities: grant.capabilities.clone(),
+            operations: grant.operations.clone(),
+        })
+    }
+}
+
+/// Hash a token the way grants store it
+#[must_use]
+pub fn hash_token(token: &str) -> String {
+    Hash::compute(token.as_bytes()).to_hex()
+}
+
+/// Authentication error
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AuthError {
+    /// No `Authorization` header
+    #[error("Missing bearer token")]
+    MissingToken,
+    /// The `Authorization` header is not a bearer token
+    #[error("Authorization must use the Bearer scheme")]
+    InvalidScheme,
+    /// The token is not configured
+    #[error("Unknown token")]
+    UnknownToken,
+    /// The token does not allow the operation
+    #[error("{subject} may not {operation:?}")]
+    Forbidden {
+        /// Token holder
+        subject: String,
+        /// Refused operation
+        operation: Operation,
+    },
+    /// A run asked for a capability the token does not grant
+    #[error("{subject} is not granted {capability}")]
+    CapabilityNotGranted {
+        /// Token holder
+        subject: String,
+        /// Capability asked for
+        capability: String,
+    },
+    /// The token configuration is invalid
+    #[error("Invalid auth config: {reason}")]
+    Config {
+        /// What is wrong
+        reason: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticator() -> Authenticator {
+        let grant = TokenGrant::new(TenantId::from_name("acme"), "ci", "s3cret")
+            .with_capability(Capability::NetRead {
+                allowlist: vec!["*.example.com".to_string()],
+            })
+            .with_operation(Operation::SubmitRun);
+        Authenticator::new(AuthConfig::new().with_token(grant))
+    }
+
+    #[test]
+    fn test_authenticate_bearer_token() {
+        let auth = authenticator();
+        let context = auth.authenticate(Some("Bearer s3cret")).unwrap();
+        assert_eq!(context.subject, "ci");
+        assert_eq!(context.tenant, TenantId::from_name("acme"));
+        assert!(context.authorize(Operation::SubmitRun).is_ok());
+        assert!(matches!(
+            context.authorize(Operation::CancelRun),
+            Err(AuthError::Forbidden { operation: Operation::CancelRun, .. })
+        ));
+
+        assert_eq!(auth.authenticate(None), Err(AuthError::MissingToken));
+        assert_eq!(auth.authenticate(Some("Basic s3cret")), Err(AuthError::InvalidScheme));
+        assert_eq!(auth.authenticate(Some("Bearer wrong")), Err(AuthError::UnknownToken));
+    }
+
+    #[test]
+    fn test_capabilities_bounded_by_grant() {
+        let context = authenticator().authenticate(Some("bearer s3cret")).unwrap();
+        let api = Capability::NetRead {
+            allowlist: vec!["api.example.com".to_string()],
+        };
+        assert!(context.check_capability(&api).is_ok());
+        let other = Capability::NetRead {
+            allowlist: vec!["evil.test".to_string()],
+        };
+        assert!(matches!(
+            context.check_capability(&other),
+            Err(AuthError::CapabilityNotGranted { .. })
+        ));
+        assert!(context.check_capability(&Capability::ClockRead).is_err());
+    }
+
+    #[test]
+    fn test_config_keeps_only_token_hashes() {
+        let grant = TokenGrant::new(TenantId::default(), "ci", "s3cret");
+        let config = AuthConfig::new().with_token(grant);
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("s3cret"));
+        assert_eq!(serde_json::from_str::<AuthConfig>(&json).unwrap(), config);
+    }
+}
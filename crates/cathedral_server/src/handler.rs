@@ -1,4 +1,1608 @@
 //! Request handlers
+//!
+//! Submitted runs execute on a blocking thread in an [`ExecutionEngine`]
+//! and log to their own [`LiveStream`] under the server's data directory.
+//! Each event is logged, and the run's node progress updated, as the engine
+//! records it, so status and streams follow the run while it executes.
+//! When a cluster [`Coordinator`] is attached, it decides whether a run is
+//! admitted.
+//!
+//! With an [`Authenticator`], every request carries the [`AuthContext`] of
+//! its token: handlers check the token allows the operation, and a run may
+//! only use capabilities the token grants. Without one, the API is open and
+//! runs use the server's engine capabilities.
+//!
+//! Every run belongs to the tenant of the token that submitted it, and its
+//! log and the outputs of its nodes live in that tenant's directory.
+//! Requests only ever see their own tenant's runs; another tenant's run is
+//! reported as not found. Without authentication every request acts for
+//! [`DEFAULT_TENANT`].
+//!
+//! With [`Quotas`], a run is only admitted while its tenant is within its
+//! quota of concurrent runs, stored log bytes and daily fuel.
+//!
+//! With an audit log, every API call is recorded in it, and `GET /audit`
+//! correlates a tenant's calls with its runs' events into an
+//! [`AuditTrail`].
+//!
+//! The `/cluster` routes report and manage the attached coordinator's
+//! cluster. Without a coordinator they answer that no cluster is attached.
 
-pub struct Handler;
-pub struct HandlerError;
+use crate::api::ServerConfig;
+use crate::auth::{AuthContext, AuthError, Authenticator, Operation, DEFAULT_TENANT};
+use crate::quota::{QuotaDenial, QuotaResource, Quotas, RunDemand};
+use crate::runs::{RunRecord, RunStatus};
+use crate::schema::{
+    ArtifactView, AuditParams, DrainParams, ErrorBody, ErrorDetail, EventPage, EventPageParams,
+    EventView, PromoteView, RunList, RunView, SubmitRunRequest,
+};
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{Extension, Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::sse::{self, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use cathedral_cluster::{
+    ClusterStatus, Coordinator, CoordinatorError, DrainReport, ExecutionResult,
+};
+use cathedral_core::{CoreError, CoreResult, NodeId, RunId, TenantId};
+use cathedral_log::{Cursor, Event, LiveStream, SegmentConfig, SegmentedStream};
+use cathedral_plan::Dag;
+use cathedral_policy::{AuditFormat, AuditQuery, AuditTrail};
+use cathedral_runtime::engine::{EngineConfig, ExecutionEngine, ExecutionStatus};
+use cathedral_runtime::monitor::render_prometheus;
+use cathedral_runtime::{EventSink, Metrics};
+use cathedral_storage::store::FsContentStore;
+use cathedral_storage::BlobId;
+use futures::Stream;
+use indexmap::IndexMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Shared state behind every request
+#[derive(Clone)]
+pub struct Handler {
+    /// Server configuration
+    config: Arc<ServerConfig>,
+    /// Runs by ID
+    runs: Runs,
+    /// Cluster coordinator admitting runs, if any
+    coordinator: Option<Arc<Coordinator>>,
+    /// Resolver of bearer tokens, if the API requires them
+    authenticator: Option<Arc<Authenticator>>,
+    /// Rate limits and tenant quotas, if enforced
+    quotas: Option<Arc<Quotas>>,
+    /// Log of API calls and quota denials, if kept
+    audit: Option<Arc<LiveStream>>,
+}
+
+/// Runs by ID, in submission order
+type Runs = Arc<RwLock<IndexMap<RunId, RunRecord>>>;
+
+/// What the engine left behind for a finished run
+struct Outcome {
+    status: CoreResult<ExecutionStatus>,
+    artifacts: CoreResult<IndexMap<NodeId, BlobId>>,
+}
+
+impl Handler {
+    /// Create handler state
+    #[must_use]
+    pub fn new(config: ServerConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            runs: Arc::new(RwLock::new(IndexMap::new())),
+            coordinator: None,
+            authenticator: None,
+            quotas: None,
+            audit: None,
+        }
+    }
+
+    /// Admit runs through a cluster coordinator
+    #[must_use]
+    pub fn with_coordinator(mut self, coordinator: Arc<Coordinator>) -> Self {
+        self.coordinator = Some(coordinator);
+        self
+    }
+
+    /// Require a bearer token on every request
+    #[must_use]
+    pub fn with_authenticator(mut self, authenticator: Authenticator) -> Self {
+        self.authenticator = Some(Arc::new(authenticator));
+        self
+    }
+
+    /// Get the authenticator, if the API requires tokens
+    #[must_use]
+    pub fn authenticator(&self) -> Option<&Arc<Authenticator>> {
+        self.authenticator.as_ref()
+    }
+
+    /// Enforce rate limits and tenant quotas
+    #[must_use]
+    pub fn with_quotas(mut self, quotas: Quotas) -> Self {
+        self.quotas = Some(Arc::new(quotas));
+        self
+    }
+
+    /// Get the rate limits and quotas, if enforced
+    #[must_use]
+    pub fn quotas(&self) -> Option<&Arc<Quotas>> {
+        self.quotas.as_ref()
+    }
+
+    /// Record API calls in an audit log
+    #[must_use]
+    pub fn with_audit_log(mut self, audit: Arc<LiveStream>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Get the audit log, if API calls are recorded
+    #[must_use]
+    pub fn audit_log(&self) -> Option<&Arc<LiveStream>> {
+        self.audit.as_ref()
+    }
+
+    /// Start executing a DAG on behalf of `auth`
+    ///
+    /// The run belongs to the token's tenant and executes with the token's
+    /// capabilities.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the DAG is invalid, a node asks for a capability
+    /// the token does not grant, the coordinator refuses the run, the
+    /// tenant is over quota, or its log cannot be created
+    pub async fn submit(
+        &self,
+        dag: Dag,
+        auth: Option<&AuthContext>,
+    ) -> Result<RunView, HandlerError> {
+        dag.validate().map_err(|e| HandlerError::InvalidDag(e.to_string()))?;
+        let mut config = self.config.engine.clone();
+        if let Some(auth) = auth {
+            for capability in dag.nodes.values().flat_map(|node| &node.capabilities) {
+                auth.check_capability(capability)?;
+            }
+            config.capabilities = auth.capabilities.clone();
+        }
+        if let Some(coordinator) = &self.coordinator {
+            coordinator.admit().await?;
+        }
+
+        let run_id = RunId::new();
+        let tenant = tenant_of(auth);
+        let root = self.config.data_dir.join("tenants");
+        // Measured before the runs are locked, so walking the tenant's
+        // directory never stalls other requests
+        let stored_bytes = match &self.quotas {
+            Some(_) => {
+                let dir = root.join(tenant.to_string());
+                tokio::task::spawn_blocking(move || crate::quota::stored_bytes(&dir))
+                    .await
+                    .map_err(|e| CoreError::Internal {
+                        message: format!("Failed to measure stored bytes: {}", e),
+                    })??
+            }
+            None => 0,
+        };
+        // Held until the run is recorded, so concurrent submissions of a
+        // tenant cannot both pass its quota of concurrent runs
+        let mut runs = self.runs.write().await;
+        if let Some(quotas) = &self.quotas {
+            let demand = RunDemand {
+                running: runs
+                    .values()
+                    .filter(|record| record.tenant == tenant && !record.status().is_finished())
+                    .count() as u64,
+                stored_bytes,
+                fuel: fuel_demand(&dag, &config),
+            };
+            quotas.admit_run(tenant, auth, demand)?;
+        }
+        let mut log_config = SegmentConfig::for_run(root, tenant, run_id);
+        let data_dir = self.config.data_dir.to_string_lossy();
+        let mut store = FsContentStore::for_tenant(&data_dir, tenant)?.without_cache();
+        if let Some(master_key) = &self.config.master_key {
+            let run_key = master_key.generate_run_key(run_id)?;
+            log_config = log_config.with_wrapped_key(run_key.wrapped().clone());
+            store = store.with_run_key(run_key);
+        }
+        let (stream, _) = SegmentedStream::open(log_config)?;
+        let log = Arc::new(LiveStream::new(stream));
+        let store = Arc::new(store);
+        let record = RunRecord::new(run_id, tenant, &dag, Arc::clone(&log), Arc::clone(&store));
+        let view = RunView::from(&record);
+
+        let failure = Arc::new(OnceLock::new());
+        let engine = ExecutionEngine::new(run_id, config)
+            .with_cancellation(record.cancellation.clone())
+            .with_shared_metrics(Arc::clone(&record.metrics))
+            .with_event_sink(sink(run_id, log, Arc::clone(&self.runs), Arc::clone(&failure)));
+        runs.insert(run_id, record);
+        drop(runs);
+
+        let runs = Arc::clone(&self.runs);
+        let coordinator = self.coordinator.clone();
+        tokio::spawn(async move {
+            let outcome =
+                tokio::task::spawn_blocking(move || execute(engine, &dag, &store)).await;
+            {
+                let mut runs = runs.write().await;
+                let Some(record) = runs.get_mut(&run_id) else {
+                    return;
+                };
+                match outcome {
+                    Ok(outcome) => finish(record, outcome, failure.get().cloned()),
+                    Err(e) => {
+                        let message = format!("Engine task failed: {}", e);
+                        record.finish(RunStatus::Failed, Some(message));
+                    }
+                }
+            }
+            if let Some(coordinator) = coordinator {
+                coordinator.finish_run(run_id).await;
+            }
+        });
+        Ok(view)
+    }
+
+    /// List a tenant's runs, in submission order
+    pub async fn list(&self, tenant: TenantId) -> RunList {
+        let runs = self.runs.read().await;
+        RunList {
+            runs: runs
+                .values()
+                .filter(|record| record.tenant == tenant)
+                .map(RunView::from)
+                .collect(),
+        }
+    }
+
+    /// Get the metrics of every run, with the cluster's if a coordinator
+    /// is attached
+    pub async fn metrics(&self) -> Metrics {
+        let mut metrics = Metrics::new();
+        for record in self.runs.read().await.values() {
+            metrics.merge(&record.metrics.lock().unwrap_or_else(|e| e.into_inner()));
+        }
+        if let Some(coordinator) = &self.coordinator {
+            coordinator.observe_metrics(&mut metrics).await;
+        }
+        metrics
+    }
+
+    /// Get the audit trail of a tenant's API calls and runs, or of one run
+    ///
+    /// Execution events are attributed to the principal that submitted
+    /// their run.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant has no such run, a log cannot be read,
+    /// or a policy decision holds no proof
+    pub async fn audit(
+        &self,
+        tenant: TenantId,
+        run_id: Option<&str>,
+        query: AuditQuery,
+    ) -> Result<AuditTrail, HandlerError> {
+        let mut query = query.with_tenant(tenant);
+        let logs: Vec<Arc<LiveStream>> = {
+            let runs = self.runs.read().await;
+            match run_id {
+                Some(run_id) => {
+                    let record = lookup(&runs, tenant, run_id)?;
+                    query = query.with_run(record.run_id);
+                    vec![Arc::clone(&record.log)]
+                }
+                None => runs
+                    .values()
+                    .filter(|record| record.tenant == tenant)
+                    .map(|record| Arc::clone(&record.log))
+                    .collect(),
+            }
+        };
+
+        let mut trail = AuditTrail::new();
+        if let Some(audit) = &self.audit {
+            trail.add_api_calls(&read_log(audit)?);
+        }
+        for log in logs {
+            trail.add_execution(tenant, &read_log(&log)?)?;
+        }
+        Ok(trail.filter(&query))
+    }
+
+    /// Get the cluster's leadership and membership
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no coordinator is attached
+    pub async fn cluster(&self) -> Result<ClusterStatus, HandlerError> {
+        Ok(self.coordinator()?.status().await)
+    }
+
+    /// Get the cluster's completed task results, in commit order
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no coordinator is attached
+    pub async fn cluster_results(&self) -> Result<Vec<ExecutionResult>, HandlerError> {
+        Ok(self.coordinator()?.completed_results_sorted().await)
+    }
+
+    /// Drain a worker, waiting up to `timeout` for its running tasks
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no coordinator is attached, the node is not a
+    /// member, or it is the coordinator itself
+    pub async fn drain(&self, node: &str, timeout: Duration) -> Result<DrainReport, HandlerError> {
+        let node_id = parse_member(node)?;
+        self.coordinator()?
+            .drain(node_id, timeout)
+            .await
+            .map_err(|e| cluster_error(node, e))
+    }
+
+    /// Make a member a voter
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no coordinator is attached, the node is not an
+    /// active member, or the reconfiguration is refused
+    pub async fn promote(&self, node: &str) -> Result<PromoteView, HandlerError> {
+        let node_id = parse_member(node)?;
+        let index = self
+            .coordinator()?
+            .promote(node_id)
+            .await
+            .map_err(|e| cluster_error(node, e))?;
+        Ok(PromoteView {
+            node_id: node_id.to_string(),
+            index,
+        })
+    }
+
+    /// Get the attached coordinator
+    fn coordinator(&self) -> Result<&Coordinator, HandlerError> {
+        self.coordinator.as_deref().ok_or(HandlerError::NoCluster)
+    }
+
+    /// Get a run's status and node progress
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant has no such run
+    pub async fn status(&self, tenant: TenantId, run_id: &str) -> Result<RunView, HandlerError> {
+        let runs = self.runs.read().await;
+        Ok(RunView::from(lookup(&runs, tenant, run_id)?))
+    }
+
+    /// Get the DAG a run was submitted with
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant has no such run
+    pub async fn dag(&self, tenant: TenantId, run_id: &str) -> Result<Dag, HandlerError> {
+        let runs = self.runs.read().await;
+        Ok(lookup(&runs, tenant, run_id)?.dag.clone())
+    }
+
+    /// Get a page of a run's events
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant has no such run or its log cannot be read
+    pub async fn events(
+        &self,
+        tenant: TenantId,
+        run_id: &str,
+        params: EventPageParams,
+    ) -> Result<EventPage, HandlerError> {
+        let log = Arc::clone(&lookup(&*self.runs.read().await, tenant, run_id)?.log);
+        let from = params.from.unwrap_or(0);
+        let limit = params
+            .limit
+            .unwrap_or(self.config.page_size)
+            .min(self.config.max_page_size) as u64;
+        let total = log.len();
+
+        let mut events = Vec::new();
+        for position in from..total.min(from.saturating_add(limit)) {
+            if let Some(event) = log.read(position)? {
+                events.push(EventView::new(position, &event));
+            }
+        }
+        let end = from + events.len() as u64;
+        Ok(EventPage {
+            run_id: run_id.to_string(),
+            events,
+            next: (end < total).then_some(end),
+            total,
+        })
+    }
+
+    /// Get the output of a completed node
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant has no such run or the node has no output
+    pub async fn artifact(
+        &self,
+        tenant: TenantId,
+        run_id: &str,
+        node: &str,
+    ) -> Result<ArtifactView, HandlerError> {
+        let (node_id, blob_id, store) = {
+            let runs = self.runs.read().await;
+            let record = lookup(&runs, tenant, run_id)?;
+            let (node_id, blob_id) = parse_id(node, "node_")
+                .map(NodeId::from_bytes)
+                .and_then(|node_id| Some((node_id, *record.artifacts.get(&node_id)?)))
+                .ok_or_else(|| HandlerError::ArtifactNotFound(node.to_string()))?;
+            (node_id, blob_id, Arc::clone(&record.store))
+        };
+        let output = store.read(&blob_id)?;
+        Ok(ArtifactView::new(run_id, node_id, &output))
+    }
+
+    /// Cancel a running run
+    ///
+    /// The engine stops before scheduling its next node, and tools already
+    /// running for it are interrupted. With a coordinator attached, the
+    /// run's jobs are stopped on every worker as well.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant has no such run or it already finished
+    pub async fn cancel(&self, tenant: TenantId, run_id: &str) -> Result<RunView, HandlerError> {
+        let (id, view) = {
+            let runs = self.runs.read().await;
+            let record = lookup(&runs, tenant, run_id)?;
+            if record.status().is_finished() {
+                return Err(HandlerError::Conflict(format!(
+                    "Run {} already finished as {:?}",
+                    run_id,
+                    record.status()
+                )));
+            }
+            record.cancellation.cancel();
+            (record.run_id, RunView::from(record))
+        };
+        if let Some(coordinator) = &self.coordinator {
+            coordinator.cancel_run(id).await;
+        }
+        Ok(view)
+    }
+
+    /// Follow a run's events from `from` as they are logged
+    ///
+    /// The stream ends once the run has finished and every event was
+    /// delivered.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tenant has no such run
+    pub async fn follow(
+        &self,
+        tenant: TenantId,
+        run_id: &str,
+        from: u64,
+    ) -> Result<impl Stream<Item = CoreResult<EventView>> + use<>, HandlerError> {
+        let runs = self.runs.read().await;
+        let record = lookup(&runs, tenant, run_id)?;
+        let subscription = record.log.subscribe(Cursor::at(from));
+        let finished = record.watch_status();
+
+        Ok(futures::stream::unfold(
+            (subscription, finished),
+            |(mut subscription, mut finished)| async move {
+                let event = tokio::select! {
+                    biased;
+                    event = subscription.next() => event,
+                    _ = finished.wait_for(|status| status.is_finished()) => {
+                        subscription.try_next()
+                    }
+                };
+                let position = subscription.position().saturating_sub(1);
+                let view = event.transpose()?.map(|event| EventView::new(position, &event));
+                Some((view, (subscription, finished)))
+            },
+        ))
+    }
+}
+
+/// `POST /runs`
+///
+/// # Errors
+///
+/// Returns error if the body is not a valid submission or the run is refused
+///
+/// The response carries the new run's ID as an extension, so the call is
+/// audited against the run.
+pub async fn submit_run(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    request: Result<Json<SubmitRunRequest>, JsonRejection>,
+) -> Result<(StatusCode, Extension<RunId>, Json<RunView>), HandlerError> {
+    authorize(auth.as_deref(), Operation::SubmitRun)?;
+    let Json(request) = request.map_err(|e| HandlerError::BadRequest(e.body_text()))?;
+    let view = handler.submit(request.dag, auth.as_deref()).await?;
+    let run_id = parse_id(&view.run_id, "run_").map(RunId::from_bytes).ok_or_else(|| {
+        HandlerError::Internal(format!("Invalid run ID {}", view.run_id))
+    })?;
+    Ok((StatusCode::ACCEPTED, Extension(run_id), Json(view)))
+}
+
+/// `GET /runs`
+///
+/// # Errors
+///
+/// Returns error if the token may not read runs
+pub async fn list_runs(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+) -> Result<Json<RunList>, HandlerError> {
+    let tenant = authorize(auth.as_deref(), Operation::ReadRun)?;
+    Ok(Json(handler.list(tenant).await))
+}
+
+/// `GET /metrics`
+///
+/// Metrics of all runs, across tenants, in the Prometheus text format.
+///
+/// # Errors
+///
+/// Returns error if the token may not read metrics
+pub async fn metrics(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+) -> Result<Response, HandlerError> {
+    authorize(auth.as_deref(), Operation::ReadMetrics)?;
+    let body = render_prometheus(&handler.metrics().await);
+    let content_type = [(header::CONTENT_TYPE, "text/plain; version=0.0.4")];
+    Ok((content_type, body).into_response())
+}
+
+/// `GET /audit?run=&actor=&action=&format=`
+///
+/// The audit trail of the caller's tenant as JSON, JSON lines or CSV.
+///
+/// # Errors
+///
+/// Returns error if the token may not read the audit trail, the query is
+/// malformed, or the run is unknown
+pub async fn audit(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    params: Result<Query<AuditParams>, QueryRejection>,
+) -> Result<Response, HandlerError> {
+    let tenant = authorize(auth.as_deref(), Operation::ReadAudit)?;
+    let Query(params) = params.map_err(|e| HandlerError::BadRequest(e.body_text()))?;
+    let format = match params.format.as_deref() {
+        Some(format) => format
+            .parse::<AuditFormat>()
+            .map_err(|e| HandlerError::BadRequest(e.to_string()))?,
+        None => AuditFormat::default(),
+    };
+    let mut query = AuditQuery::new();
+    query.actor = params.actor;
+    query.action = params.action;
+    let trail = handler.audit(tenant, params.run.as_deref(), query).await?;
+    let body = trail.render(format)?;
+    Ok(([(header::CONTENT_TYPE, format.content_type())], body).into_response())
+}
+
+/// `GET /cluster`
+///
+/// # Errors
+///
+/// Returns error if the token may not read the cluster or no coordinator is
+/// attached
+pub async fn cluster_status(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+) -> Result<Json<ClusterStatus>, HandlerError> {
+    authorize(auth.as_deref(), Operation::ReadCluster)?;
+    handler.cluster().await.map(Json)
+}
+
+/// `GET /cluster/results`
+///
+/// # Errors
+///
+/// Returns error if the token may not read the cluster or no coordinator is
+/// attached
+pub async fn cluster_results(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+) -> Result<Json<Vec<ExecutionResult>>, HandlerError> {
+    authorize(auth.as_deref(), Operation::ReadCluster)?;
+    handler.cluster_results().await.map(Json)
+}
+
+/// `POST /cluster/members/{node}/drain?timeout_ms=`
+///
+/// Stops assigning to the worker, requeues its queued tasks, waits for
+/// its running ones and deregisters it. A worker with tasks still running
+/// at the timeout stays a leaving member, and the drain can be retried.
+///
+/// # Errors
+///
+/// Returns error if the token may not manage the cluster, no coordinator
+/// is attached, or the node cannot be drained
+pub async fn drain_member(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    Path(node): Path<String>,
+    params: Result<Query<DrainParams>, QueryRejection>,
+) -> Result<Json<DrainReport>, HandlerError> {
+    authorize(auth.as_deref(), Operation::ManageCluster)?;
+    let Query(params) = params.map_err(|e| HandlerError::BadRequest(e.body_text()))?;
+    let timeout = Duration::from_millis(params.timeout_ms.unwrap_or(DEFAULT_DRAIN_TIMEOUT_MS));
+    handler.drain(&node, timeout).await.map(Json)
+}
+
+/// `POST /cluster/members/{node}/promote`
+///
+/// # Errors
+///
+/// Returns error if the token may not manage the cluster, no coordinator
+/// is attached, or the node cannot become a voter
+pub async fn promote_member(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    Path(node): Path<String>,
+) -> Result<(StatusCode, Json<PromoteView>), HandlerError> {
+    authorize(auth.as_deref(), Operation::ManageCluster)?;
+    let view = handler.promote(&node).await?;
+    Ok((StatusCode::ACCEPTED, Json(view)))
+}
+
+/// `GET /runs/{id}`
+///
+/// # Errors
+///
+/// Returns error if the run is unknown
+pub async fn run_status(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    Path(run_id): Path<String>,
+) -> Result<Json<RunView>, HandlerError> {
+    let tenant = authorize(auth.as_deref(), Operation::ReadRun)?;
+    handler.status(tenant, &run_id).await.map(Json)
+}
+
+/// `GET /runs/{id}/events?from=&limit=`
+///
+/// # Errors
+///
+/// Returns error if the run is unknown or the query is malformed
+pub async fn run_events(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    Path(run_id): Path<String>,
+    params: Result<Query<EventPageParams>, QueryRejection>,
+) -> Result<Json<EventPage>, HandlerError> {
+    let tenant = authorize(auth.as_deref(), Operation::ReadRun)?;
+    let Query(params) = params.map_err(|e| HandlerError::BadRequest(e.body_text()))?;
+    handler.events(tenant, &run_id, params).await.map(Json)
+}
+
+/// `GET /runs/{id}/dag`
+///
+/// # Errors
+///
+/// Returns error if the run is unknown
+pub async fn run_dag(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    Path(run_id): Path<String>,
+) -> Result<Json<Dag>, HandlerError> {
+    let tenant = authorize(auth.as_deref(), Operation::ReadRun)?;
+    handler.dag(tenant, &run_id).await.map(Json)
+}
+
+/// `GET /runs/{id}/stream`
+///
+/// Sends each event as a server-sent event whose `id` is its log position
+/// and whose `event` is its kind. A client reconnecting with
+/// `Last-Event-ID` resumes after that position.
+///
+/// # Errors
+///
+/// Returns error if the run is unknown or `Last-Event-ID` is not a position
+pub async fn run_stream(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    Path(run_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = CoreResult<sse::Event>>>, HandlerError> {
+    let tenant = authorize(auth.as_deref(), Operation::ReadRun)?;
+    let from = match headers.get("last-event-id") {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(|last| last + 1)
+            .ok_or_else(|| HandlerError::BadRequest("Invalid Last-Event-ID".to_string()))?,
+        None => 0,
+    };
+    let events = handler.follow(tenant, &run_id, from).await?;
+    let frames = futures::StreamExt::map(events, |view| {
+        let view = view?;
+        sse::Event::default()
+            .id(view.position.to_string())
+            .event(format!("{:?}", view.kind))
+            .json_data(view)
+            .map_err(|e| CoreError::Internal {
+                message: format!("Failed to encode event: {}", e),
+            })
+    });
+    Ok(Sse::new(frames).keep_alive(KeepAlive::default()))
+}
+
+/// `GET /runs/{id}/artifacts/{node}`
+///
+/// # Errors
+///
+/// Returns error if the run or the node's output is unknown
+pub async fn run_artifact(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    Path((run_id, node)): Path<(String, String)>,
+) -> Result<Json<ArtifactView>, HandlerError> {
+    let tenant = authorize(auth.as_deref(), Operation::ReadArtifacts)?;
+    handler.artifact(tenant, &run_id, &node).await.map(Json)
+}
+
+/// `DELETE /runs/{id}`
+///
+/// # Errors
+///
+/// Returns error if the run is unknown or already finished
+pub async fn cancel_run(
+    State(handler): State<Handler>,
+    auth: Option<Extension<AuthContext>>,
+    Path(run_id): Path<String>,
+) -> Result<(StatusCode, Json<RunView>), HandlerError> {
+    let tenant = authorize(auth.as_deref(), Operation::CancelRun)?;
+    let view = handler.cancel(tenant, &run_id).await?;
+    Ok((StatusCode::ACCEPTED, Json(view)))
+}
+
+/// Check the request's token allows `operation` and get the tenant the
+/// request acts for
+///
+/// Without authentication every operation is allowed.
+fn authorize(auth: Option<&AuthContext>, operation: Operation) -> Result<TenantId, HandlerError> {
+    if let Some(auth) = auth {
+        auth.authorize(operation)?;
+    }
+    Ok(tenant_of(auth))
+}
+
+/// Tenant a request acts for
+pub(crate) fn tenant_of(auth: Option<&AuthContext>) -> TenantId {
+    auth.map_or_else(|| TenantId::from_name(DEFAULT_TENANT), |auth| auth.tenant)
+}
+
+/// Log each event of a run and update its node progress
+///
+/// Runs on the engine's blocking thread. The first failed append is kept in
+/// `failure` and fails the run once it ends.
+fn sink(
+    run_id: RunId,
+    log: Arc<LiveStream>,
+    runs: Runs,
+    failure: Arc<OnceLock<String>>,
+) -> EventSink {
+    Arc::new(move |event: &Event| {
+        if let Err(e) = log.append(event) {
+            let _ = failure.set(format!("Failed to log event: {}", e));
+        }
+        if let Some(record) = runs.blocking_write().get_mut(&run_id) {
+            record.apply(event);
+        }
+    })
+}
+
+/// Read every event of a log
+fn read_log(log: &LiveStream) -> CoreResult<Vec<Event>> {
+    let mut events = Vec::new();
+    for position in 0..log.len() {
+        events.extend(log.read(position)?);
+    }
+    Ok(events)
+}
+
+/// Fuel a run of `dag` is charged against its tenant's daily quota
+///
+/// A DAG with a node that declares no fuel bound is charged the engine's
+/// tick limit, the most the run can use.
+fn fuel_demand(dag: &Dag, config: &EngineConfig) -> u64 {
+    dag.nodes
+        .values()
+        .map(|node| node.resource_contract().fuel.max)
+        .try_fold(0u64, |total, fuel| Some(total.saturating_add(fuel?)))
+        .unwrap_or(config.max_ticks)
+}
+
+/// Run a DAG to the end, keeping its nodes' outputs in `store`
+fn execute(mut engine: ExecutionEngine, dag: &Dag, store: &FsContentStore) -> Outcome {
+    let status = engine.add_dag(dag).and_then(|()| engine.run());
+    let artifacts = engine
+        .outputs()
+        .iter()
+        .map(|(&node_id, output)| Ok((node_id, store.write(output.output.clone())?)))
+        .collect();
+    Outcome { status, artifacts }
+}
+
+/// Record how a run ended
+fn finish(record: &mut RunRecord, outcome: Outcome, failure: Option<String>) {
+    let failure = match outcome.artifacts {
+        Ok(artifacts) => {
+            record.artifacts = artifacts;
+            failure
+        }
+        Err(e) => Some(format!("Failed to store outputs: {}", e)),
+    };
+    match (outcome.status, failure) {
+        (Ok(status), None) => record.finish(RunStatus::from_execution(&status), None),
+        (Err(e), _) => record.finish(RunStatus::Failed, Some(e.to_string())),
+        (Ok(_), Some(failure)) => record.finish(RunStatus::Failed, Some(failure)),
+    }
+}
+
+/// Find a tenant's run
+///
+/// Another tenant's run is not found, so its existence is not revealed.
+fn lookup<'a>(
+    runs: &'a IndexMap<RunId, RunRecord>,
+    tenant: TenantId,
+    run_id: &str,
+) -> Result<&'a RunRecord, HandlerError> {
+    parse_id(run_id, "run_")
+        .and_then(|bytes| runs.get(&RunId::from_bytes(bytes)))
+        .filter(|record| record.tenant == tenant)
+        .ok_or_else(|| HandlerError::RunNotFound(run_id.to_string()))
+}
+
+/// Parse an ID in its display form, with or without its prefix
+pub(crate) fn parse_id(value: &str, prefix: &str) -> Option<[u8; 16]> {
+    uuid::Uuid::parse_str(value.strip_prefix(prefix).unwrap_or(value))
+        .ok()
+        .map(|uuid| *uuid.as_bytes())
+}
+
+/// Parse a member's node ID
+fn parse_member(node: &str) -> Result<NodeId, HandlerError> {
+    parse_id(node, "node_")
+        .map(NodeId::from_bytes)
+        .ok_or_else(|| HandlerError::BadRequest(format!("Invalid node ID {}", node)))
+}
+
+/// Map a coordinator's refusal to manage a member to a handler error
+fn cluster_error(node: &str, err: CoreError) -> HandlerError {
+    match err {
+        CoreError::NotFound { .. } => HandlerError::MemberNotFound(node.to_string()),
+        CoreError::Validation { reason, .. } => HandlerError::Conflict(reason),
+        other => other.into(),
+    }
+}
+
+/// How long a drain waits for running tasks when the request sets no timeout
+pub const DEFAULT_DRAIN_TIMEOUT_MS: u64 = 30_000;
+
+/// Resource a quota denial was for
+pub const QUOTA_RESOURCE: HeaderName = HeaderName::from_static("x-quota-resource");
+/// Limit of the exhausted resource
+pub const QUOTA_LIMIT: HeaderName = HeaderName::from_static("x-quota-limit");
+/// Usage of the exhausted resource when the request arrived
+pub const QUOTA_USED: HeaderName = HeaderName::from_static("x-quota-used");
+
+/// Handler errors, rendered as an [`ErrorBody`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HandlerError {
+    /// Malformed request
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    /// Missing or unknown credentials
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    /// The credentials do not allow the request
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    /// The submitted DAG failed validation
+    #[error("Invalid DAG: {0}")]
+    InvalidDag(String),
+    /// No run with this ID
+    #[error("Run not found: {0}")]
+    RunNotFound(String),
+    /// The node has no output
+    #[error("Artifact not found for node {0}")]
+    ArtifactNotFound(String),
+    /// No cluster member with this ID
+    #[error("Member not found: {0}")]
+    MemberNotFound(String),
+    /// The server has no cluster coordinator attached
+    #[error("No cluster is attached to this server")]
+    NoCluster,
+    /// The request conflicts with the run's state
+    #[error("{0}")]
+    Conflict(String),
+    /// A rate limit or tenant quota refused the request
+    #[error("{0}")]
+    QuotaExceeded(QuotaDenial),
+    /// The cluster is saturated
+    #[error("Cluster saturated, retry after {retry_after_ms}ms")]
+    Overloaded {
+        /// Suggested delay before retrying
+        retry_after_ms: u64,
+    },
+    /// Unexpected failure
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl HandlerError {
+    /// Stable error code
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::BadRequest(_) => "bad_request",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::Forbidden(_) => "forbidden",
+            Self::InvalidDag(_) => "invalid_dag",
+            Self::RunNotFound(_) => "run_not_found",
+            Self::ArtifactNotFound(_) => "artifact_not_found",
+            Self::MemberNotFound(_) => "member_not_found",
+            Self::NoCluster => "no_cluster",
+            Self::Conflict(_) => "conflict",
+            Self::QuotaExceeded(denial) if denial.resource == QuotaResource::Requests => {
+                "rate_limited"
+            }
+            Self::QuotaExceeded(_) => "quota_exceeded",
+            Self::Overloaded { .. } => "overloaded",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    /// HTTP status of the error
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            Self::Forbidden(_) => StatusCode::FORBIDDEN,
+            Self::InvalidDag(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::RunNotFound(_) | Self::ArtifactNotFound(_) | Self::MemberNotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            Self::NoCluster => StatusCode::SERVICE_UNAVAILABLE,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::QuotaExceeded(denial) if denial.resource == QuotaResource::Requests => {
+                StatusCode::TOO_MANY_REQUESTS
+            }
+            Self::QuotaExceeded(_) => StatusCode::FORBIDDEN,
+            Self::Overloaded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for HandlerError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: ErrorDetail {
+                code: self.code().to_string(),
+                message: self.to_string(),
+            },
+        };
+        let mut response = (self.status(), Json(body)).into_response();
+        match self {
+            Self::Overloaded { retry_after_ms } => {
+                let retry_after = HeaderValue::from(retry_after_ms.div_ceil(1000));
+                response.headers_mut().insert(header::RETRY_AFTER, retry_after);
+            }
+            Self::Unauthorized(_) => {
+                let challenge = HeaderValue::from_static("Bearer");
+                response.headers_mut().insert(header::WWW_AUTHENTICATE, challenge);
+            }
+            Self::QuotaExceeded(denial) => {
+                let headers = response.headers_mut();
+                let resource = HeaderValue::from_static(denial.resource.as_str());
+                headers.insert(QUOTA_RESOURCE, resource);
+                headers.insert(QUOTA_LIMIT, HeaderValue::from(denial.limit));
+                headers.insert(QUOTA_USED, HeaderValue::from(denial.used));
+                if let Some(retry_after_ms) = denial.retry_after_ms {
+                    let retry_after = HeaderValue::from(retry_after_ms.div_ceil(1000));
+                    headers.insert(header::RETRY_AFTER, retry_after);
+                }
+            }
+            _ => {}
+        }
+        response
+    }
+}
+
+impl From<CoreError> for HandlerError {
+    fn from(err: CoreError) -> Self {
+        Self::Internal(err.to_string())
+    }
+}
+
+impl From<AuthError> for HandlerError {
+    fn from(err: AuthError) -> Self {
+        match err {
+            AuthError::MissingToken | AuthError::InvalidScheme | AuthError::UnknownToken => {
+                Self::Unauthorized(err.to_string())
+            }
+            AuthError::Forbidden { .. } | AuthError::CapabilityNotGranted { .. } => {
+                Self::Forbidden(err.to_string())
+            }
+            AuthError::Config { .. } => Self::Internal(err.to_string()),
+        }
+    }
+}
+
+impl From<QuotaDenial> for HandlerError {
+    fn from(denial: QuotaDenial) -> Self {
+        Self::QuotaExceeded(denial)
+    }
+}
+
+impl From<CoordinatorError> for HandlerError {
+    fn from(err: CoordinatorError) -> Self {
+        match err {
+            CoordinatorError::Backpressure { retry_after_ms, .. } => {
+                Self::Overloaded { retry_after_ms }
+            }
+            other => Self::Internal(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::router;
+    use crate::auth::{AuthConfig, TokenGrant};
+    use crate::middleware::RATE_LIMIT_REMAINING;
+    use crate::quota::{QuotaConfig, RateLimit, TenantQuota};
+    use cathedral_core::Capability;
+    use axum::body::Body;
+    use axum::http::Request;
+    use cathedral_log::IndexedLog;
+    use cathedral_plan::{Edge, Node, NodeKind};
+    use cathedral_storage::{MasterKey, SealedBlob};
+    use tower::ServiceExt;
+
+    fn tool_node() -> Node {
+        Node {
+            id: NodeId::new(),
+            kind: NodeKind::Tool {
+                name: "echo".to_string(),
+                version_req: "*".to_string(),
+                input_binding: None,
+            },
+            dependencies: indexmap::IndexSet::new(),
+            capabilities: Vec::new(),
+            resources: cathedral_plan::dag::ResourceRequirements::new(),
+        }
+    }
+
+    async fn call(
+        app: &axum::Router,
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        call_as(app, None, method, uri, body).await
+    }
+
+    async fn call_as(
+        app: &axum::Router,
+        token: Option<&str>,
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json");
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let request = request
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    /// Submit a run of two chained nodes and return its URI and nodes
+    async fn submit_chain(app: &axum::Router) -> (String, NodeId, NodeId) {
+        let mut dag = Dag::new();
+        let (first, second) = (tool_node(), tool_node());
+        let (first_id, second_id) = (first.id, second.id);
+        dag.add_node(first).unwrap();
+        dag.add_node(second).unwrap();
+        dag.add_edge(Edge::new(first_id, second_id)).unwrap();
+
+        let request = serde_json::json!({ "dag": dag });
+        let (status, run) = call(app, "POST", "/runs", Some(request)).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(run["total_nodes"], 2);
+        (format!("/runs/{}", run["run_id"].as_str().unwrap()), first_id, second_id)
+    }
+
+    /// Read a whole event stream as `(id, event)` pairs
+    async fn stream(
+        app: &axum::Router,
+        uri: &str,
+        last_event_id: Option<u64>,
+    ) -> Vec<(u64, String)> {
+        let mut request = Request::builder().uri(format!("{}/stream", uri));
+        if let Some(id) = last_event_id {
+            request = request.header("last-event-id", id.to_string());
+        }
+        let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec())
+            .unwrap()
+            .split("\n\n")
+            .filter_map(|frame| {
+                let field = |name: &str| {
+                    frame.lines().find_map(|line| line.strip_prefix(name)).map(str::to_string)
+                };
+                Some((field("id: ")?.parse().unwrap(), field("event: ")?))
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_run_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = router(Handler::new(ServerConfig::new("127.0.0.1:0", dir.path())));
+        let (uri, first_id, second_id) = submit_chain(&app).await;
+
+        let mut run = call(&app, "GET", &uri, None).await.1;
+        while run["status"] == "running" {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            run = call(&app, "GET", &uri, None).await.1;
+        }
+        assert_eq!(run["status"], "succeeded");
+        assert_eq!(run["completed_nodes"], 2);
+        let nodes = run["nodes"].as_array().unwrap();
+        assert!(nodes.iter().any(|node| node["node_id"] == first_id.to_string()));
+        assert!(nodes.iter().all(|node| node["state"] == "completed"));
+
+        let (status, dag) = call(&app, "GET", &format!("{}/dag", uri), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let dag: Dag = serde_json::from_value(dag).unwrap();
+        assert_eq!(dag.node_count(), 2);
+        assert!(dag.get_node(first_id).is_some());
+
+        let (status, page) = call(&app, "GET", &format!("{}/events?limit=3", uri), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page["events"].as_array().unwrap().len(), 3);
+        assert_eq!(page["events"][0]["kind"], "RunCreated");
+        assert_eq!(page["events"][1]["kind"], "NodeStarted");
+        let next = page["next"].as_u64().unwrap();
+        let page = call(&app, "GET", &format!("{}/events?from={}", uri, next), None).await.1;
+        assert_eq!(page["events"][0]["position"], 3);
+        assert!(page["next"].is_null());
+
+        let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.contains("\ncathedral_nodes_executed_total 2\n"));
+        assert!(text.contains("\ncathedral_scheduling_latency_ticks_count 2\n"));
+
+        let artifact = format!("{}/artifacts/{}", uri, second_id);
+        let (status, output) = call(&app, "GET", &artifact, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(output["node_id"], second_id.to_string());
+        let blob = format!("{}.blob", output["output_hash"].as_str().unwrap());
+        let tenant = TenantId::from_name(DEFAULT_TENANT).to_string();
+        let tenant_dir = dir.path().join("tenants").join(tenant);
+        assert!(tenant_dir.join(blob).is_file());
+
+        let (status, error) = call(&app, "DELETE", &uri, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"]["code"], "conflict");
+    }
+
+    #[tokio::test]
+    async fn test_run_data_sealed_under_master_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let master = MasterKey::new("primary", [3; 32]);
+        let config = ServerConfig::new("127.0.0.1:0", dir.path()).with_master_key(master.clone());
+        let app = router(Handler::new(config));
+        let (uri, _, second_id) = submit_chain(&app).await;
+        let (other_uri, _, _) = submit_chain(&app).await;
+        for uri in [&uri, &other_uri] {
+            while call(&app, "GET", uri, None).await.1["status"] == "running" {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        }
+
+        // The later run sealing its own copies leaves the first run's readable
+        let artifact = format!("{}/artifacts/{}", uri, second_id);
+        let (status, output) = call(&app, "GET", &artifact, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let hash = output["output_hash"].as_str().unwrap();
+        let tenant = TenantId::from_name(DEFAULT_TENANT);
+        let tenant_dir = dir.path().join("tenants").join(tenant.to_string());
+        let run_id = &uri["/runs/".len()..];
+        let sealed_dir = tenant_dir.join("sealed").join(run_id);
+        let on_disk = std::fs::read(sealed_dir.join(format!("{}.blob", hash))).unwrap();
+        assert!(SealedBlob::decode(&on_disk).is_some());
+
+        // After a restart, the run's key is recovered from its log manifest
+        let run_dir = tenant_dir.join("runs").join(run_id);
+        let log = IndexedLog::open(run_dir).unwrap();
+        let run_key = master.unwrap_key(log.wrapped_key().unwrap()).unwrap();
+        let data_dir = dir.path().to_string_lossy();
+        let store = FsContentStore::for_tenant(&data_dir, tenant).unwrap().without_cache();
+        let blob_id = BlobId::parse(&format!("blake3:{}", hash)).unwrap();
+        assert!(store.read(&blob_id).is_err());
+        store.add_run_key(run_key);
+        let view = ArtifactView::new("", second_id, &store.read(&blob_id).unwrap());
+        assert_eq!(view.output, output["output"].as_str().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_stream_follows_run_and_resumes() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = router(Handler::new(ServerConfig::new("127.0.0.1:0", dir.path())));
+        let (uri, _, _) = submit_chain(&app).await;
+
+        // The stream ends on its own once the run has finished
+        let frames = stream(&app, &uri, None).await;
+        assert_eq!(frames.len(), 5);
+        assert!(frames.iter().enumerate().all(|(i, (id, _))| *id == i as u64));
+        assert_eq!(frames[0].1, "RunCreated");
+        assert_eq!(frames[4].1, "NodeCompleted");
+
+        let resumed = stream(&app, &uri, Some(1)).await;
+        assert_eq!(resumed, frames[2..]);
+
+        let request = Request::builder()
+            .uri(format!("{}/stream", uri))
+            .header("last-event-id", "abc")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_tokens_scope_operations_and_capabilities() {
+        let dir = tempfile::tempdir().unwrap();
+        let example = |domain: &str| Capability::NetRead {
+            allowlist: vec![domain.to_string()],
+        };
+        let acme = TenantId::from_name("acme");
+        let auth = AuthConfig::new()
+            .with_token(
+                TokenGrant::new(acme, "reader", "r-token").with_operation(Operation::ReadRun),
+            )
+            .with_token(
+                TokenGrant::new(acme, "ci", "ci-token")
+                    .with_capability(example("*.example.com"))
+                    .with_operation(Operation::SubmitRun)
+                    .with_operation(Operation::ReadRun),
+            );
+        let handler = Handler::new(ServerConfig::new("127.0.0.1:0", dir.path()))
+            .with_authenticator(Authenticator::new(auth));
+        let app = router(handler);
+        let submit = |capability: Capability| {
+            let mut node = tool_node();
+            node.capabilities.push(capability);
+            let mut dag = Dag::new();
+            dag.add_node(node).unwrap();
+            Some(serde_json::json!({ "dag": dag }))
+        };
+
+        let request = Request::builder().uri("/runs/run_x").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+        let (status, _) = call_as(&app, Some("nope"), "GET", "/runs/run_x", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let body = submit(example("api.example.com"));
+        let (status, error) = call_as(&app, Some("r-token"), "POST", "/runs", body).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(error["error"]["code"], "forbidden");
+
+        let body = submit(example("evil.test"));
+        let (status, error) = call_as(&app, Some("ci-token"), "POST", "/runs", body).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(error["error"]["message"].as_str().unwrap().contains("evil.test"));
+
+        let body = submit(example("api.example.com"));
+        let (status, run) = call_as(&app, Some("ci-token"), "POST", "/runs", body).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let uri = format!("/runs/{}", run["run_id"].as_str().unwrap());
+        let (status, _) = call_as(&app, Some("r-token"), "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call_as(&app, Some("ci-token"), "DELETE", &uri, None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_tenants_only_see_their_own_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let grant = |tenant: &str, token: &str| {
+            Operation::ALL.into_iter().fold(
+                TokenGrant::new(TenantId::from_name(tenant), tenant, token),
+                TokenGrant::with_operation,
+            )
+        };
+        let auth = AuthConfig::new()
+            .with_token(grant("acme", "a-token"))
+            .with_token(grant("umbrella", "u-token"));
+        let handler = Handler::new(ServerConfig::new("127.0.0.1:0", dir.path()))
+            .with_authenticator(Authenticator::new(auth));
+        let app = router(handler);
+        let mut dag = Dag::new();
+        dag.add_node(tool_node()).unwrap();
+        let body = Some(serde_json::json!({ "dag": dag }));
+
+        let (status, run) = call_as(&app, Some("a-token"), "POST", "/runs", body).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(run["tenant_id"], TenantId::from_name("acme").to_string());
+        let run_id = run["run_id"].as_str().unwrap();
+        let uri = format!("/runs/{}", run_id);
+
+        let (_, list) = call_as(&app, Some("a-token"), "GET", "/runs", None).await;
+        assert_eq!(list["runs"][0]["run_id"], run_id);
+        let (status, list) = call_as(&app, Some("u-token"), "GET", "/runs", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(list["runs"].as_array().unwrap().is_empty());
+
+        for (method, path) in [
+            ("GET", uri.clone()),
+            ("GET", format!("{}/events", uri)),
+            ("GET", format!("{}/stream", uri)),
+            ("GET", format!("{}/artifacts/{}", uri, NodeId::new())),
+            ("DELETE", uri.clone()),
+        ] {
+            let (status, error) = call_as(&app, Some("u-token"), method, &path, None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND, "{} {}", method, path);
+            assert_eq!(error["error"]["code"], "run_not_found");
+        }
+
+        let tenant_dir = dir.path().join("tenants").join(TenantId::from_name("acme").to_string());
+        assert!(tenant_dir.join("runs").join(run_id).is_dir());
+    }
+
+    #[test]
+    fn test_unbounded_dags_are_charged_the_tick_limit() {
+        let config = EngineConfig::default();
+        let mut bounded = tool_node();
+        bounded.resources.max_ticks = Some(40);
+        let mut dag = Dag::new();
+        dag.add_node(bounded.clone()).unwrap();
+        bounded.id = NodeId::new();
+        dag.add_node(bounded).unwrap();
+        assert_eq!(fuel_demand(&dag, &config), 80);
+
+        dag.add_node(tool_node()).unwrap();
+        assert_eq!(fuel_demand(&dag, &config), config.max_ticks);
+    }
+
+    #[tokio::test]
+    async fn test_quotas_refuse_runs_and_rate_limit_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = QuotaConfig::new()
+            .with_rate_limit(RateLimit::new(3, 60_000))
+            .with_default_quota(TenantQuota::new().with_concurrent_runs(0));
+        let (audit, _) =
+            SegmentedStream::open(SegmentConfig::new(dir.path().join("audit"))).unwrap();
+        let handler = Handler::new(ServerConfig::new("127.0.0.1:0", dir.path()))
+            .with_quotas(Quotas::new(config, Arc::new(LiveStream::new(audit))));
+        let quotas = Arc::clone(handler.quotas().unwrap());
+        let app = router(handler);
+        let mut dag = Dag::new();
+        dag.add_node(tool_node()).unwrap();
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/runs")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::json!({ "dag": dag }).to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()[QUOTA_RESOURCE], "concurrent_runs");
+        assert_eq!(response.headers()[QUOTA_LIMIT], "0");
+        assert_eq!(response.headers()[RATE_LIMIT_REMAINING], "2");
+
+        for _ in 0..2 {
+            assert_eq!(call(&app, "GET", "/runs", None).await.0, StatusCode::OK);
+        }
+        let request = Request::builder().uri("/runs").body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[QUOTA_RESOURCE], "requests");
+        assert_eq!(response.headers()[header::RETRY_AFTER], "60");
+
+        let denied: Vec<_> = quotas.denials().unwrap().iter().map(|d| d.resource).collect();
+        assert_eq!(denied, [QuotaResource::ConcurrentRuns, QuotaResource::Requests]);
+    }
+
+    #[tokio::test]
+    async fn test_audit_trail_correlates_calls_and_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let grant = |tenant: &str, subject: &str, token: &str| {
+            Operation::ALL.into_iter().fold(
+                TokenGrant::new(TenantId::from_name(tenant), subject, token),
+                TokenGrant::with_operation,
+            )
+        };
+        let auth = AuthConfig::new()
+            .with_token(grant("acme", "ci", "ci-token"))
+            .with_token(grant("acme", "ops", "ops-token"))
+            .with_token(grant("umbrella", "intruder", "u-token"));
+        let (audit, _) =
+            SegmentedStream::open(SegmentConfig::new(dir.path().join("audit"))).unwrap();
+        let handler = Handler::new(ServerConfig::new("127.0.0.1:0", dir.path()))
+            .with_authenticator(Authenticator::new(auth))
+            .with_audit_log(Arc::new(LiveStream::new(audit)));
+        let app = router(handler);
+        let mut dag = Dag::new();
+        dag.add_node(tool_node()).unwrap();
+        let body = Some(serde_json::json!({ "dag": dag }));
+
+        let (_, run) = call_as(&app, Some("ci-token"), "POST", "/runs", body).await;
+        let run_id = run["run_id"].as_str().unwrap().to_string();
+        let uri = format!("/runs/{}", run_id);
+        let mut run = call_as(&app, Some("ops-token"), "GET", &uri, None).await.1;
+        while run["status"] == "running" {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            run = call_as(&app, Some("ops-token"), "GET", &uri, None).await.1;
+        }
+
+        let query = format!("/audit?run={}", run_id);
+        let (status, trail) = call_as(&app, Some("ops-token"), "GET", &query, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let entries = trail["entries"].as_array().unwrap();
+        assert_eq!(entries[0]["actor"], "ci");
+        assert_eq!(entries[0]["action"], "POST /runs");
+        assert_eq!(entries[0]["outcome"], "202");
+        assert_eq!(entries[1]["actor"], "ops");
+        assert_eq!(entries[1]["action"], "GET /runs/{id}");
+        let executed: Vec<_> =
+            entries.iter().filter(|entry| entry["source"] == "execution").collect();
+        assert_eq!(executed[0]["action"], "RunCreated");
+        assert_eq!(executed[1]["action"], "NodeStarted");
+        assert!(executed.iter().all(|entry| entry["actor"] == "ci" && entry["run_id"] == run_id));
+
+        let request = Request::builder()
+            .uri("/audit?actor=ci&format=csv")
+            .header(header::AUTHORIZATION, "Bearer ops-token")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv");
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let csv = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(csv.starts_with("sequence,source,run_id,"));
+        assert_eq!(csv.lines().count(), 1 + executed.len() + 1);
+
+        let (status, _) = call_as(&app, Some("u-token"), "GET", &query, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, trail) = call_as(&app, Some("u-token"), "GET", "/audit", None).await;
+        let entries = trail["entries"].as_array().unwrap();
+        assert!(entries.iter().all(|entry| entry["actor"] == "intruder"));
+        let (status, _) = call_as(&app, Some("ops-token"), "GET", "/audit?format=xml", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_cluster_status_and_drain() {
+        use cathedral_cluster::{
+            Consensus, ConsensusConfig, CoordinatorConfig, ElectionConfig, LeaderElection, Member,
+            MemberState, Membership, RemoteExecutor,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = ServerConfig::new("127.0.0.1:0", dir.path());
+        let app = router(Handler::new(config.clone()));
+        let (status, error) = call(&app, "GET", "/cluster", None).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error["error"]["code"], "no_cluster");
+
+        let node_id = NodeId::new();
+        let membership = Arc::new(Membership::new(node_id));
+        let consensus = Arc::new(Consensus::new(ConsensusConfig::new(node_id).with_quorum_size(1)));
+        let election = Arc::new(LeaderElection::new(
+            ElectionConfig::new(node_id),
+            Arc::clone(&consensus),
+            Arc::clone(&membership),
+        ));
+        let coordinator = Arc::new(Coordinator::new(
+            CoordinatorConfig::new(node_id),
+            consensus,
+            election,
+            Arc::clone(&membership),
+            Arc::new(RemoteExecutor::new(node_id)),
+        ));
+        let worker = NodeId::new();
+        let member = Member::new(worker, "worker:7000".to_string()).with_state(MemberState::Active);
+        membership.add_member(member).await.unwrap();
+        let app = router(Handler::new(config).with_coordinator(Arc::clone(&coordinator)));
+
+        let (status, cluster) = call(&app, "GET", "/cluster", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cluster["members"][0]["address"], "worker:7000");
+        assert_eq!(cluster["members"][0]["in_flight"], 0);
+        let (status, results) = call(&app, "GET", "/cluster/results", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(results.as_array().unwrap().is_empty());
+
+        // Without leadership the configuration change is refused
+        let promote = format!("/cluster/members/{}/promote", worker);
+        let (status, error) = call(&app, "POST", &promote, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"]["code"], "conflict");
+        let drain = format!("/cluster/members/{}/drain?timeout_ms=100", worker);
+        let (status, error) = call(&app, "POST", &drain, None).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(error["error"]["code"], "conflict");
+
+        assert!(coordinator.campaign().await.unwrap());
+        let (status, report) = call(&app, "POST", &drain, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["deregistered"], true);
+        let (status, error) = call(&app, "POST", &drain, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error["error"]["code"], "member_not_found");
+        let (status, _) = call(&app, "POST", "/cluster/members/nope/drain", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(call(&app, "GET", "/cluster", None).await.1["members"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = router(Handler::new(ServerConfig::new("127.0.0.1:0", dir.path())));
+
+        let (status, error) = call(&app, "GET", &format!("/runs/{}", RunId::new()), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error["error"]["code"], "run_not_found");
+
+        let (status, error) = call(&app, "POST", "/runs", Some(serde_json::json!({}))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["error"]["code"], "bad_request");
+
+        let response = HandlerError::from(CoordinatorError::Backpressure {
+            outstanding: 4,
+            capacity: 4,
+            retry_after_ms: 1500,
+        })
+        .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+    }
+}
@@ -9,8 +9,13 @@ pub mod api;
 pub mod auth;
 pub mod handler;
 pub mod middleware;
+pub mod quota;
+pub mod runs;
+pub mod schema;
 
 pub use api::{ApiServer, ServerConfig};
-pub use auth::{Authenticator, AuthConfig, AuthError};
+pub use auth::{Authenticator, AuthConfig, AuthContext, AuthError, Operation, TokenGrant};
 pub use handler::{Handler, HandlerError};
 pub use middleware::{Middleware, MiddlewareStack};
+pub use quota::{QuotaConfig, QuotaDenial, QuotaResource, Quotas, RateLimit, TenantQuota};
+pub use runs::{NodeState, RunRecord, RunStatus};
@@ -6,8 +6,12 @@
 #![warn(clippy::all)]
 
 use anyhow::Result;
-use cathedral_server::api::ApiServer;
+use cathedral_server::api::{ApiServer, ServerConfig};
+use cathedral_server::auth::AuthConfig;
+use cathedral_server::quota::QuotaConfig;
+use cathedral_storage::MasterKey;
 use clap::Parser;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "cathedral-server")]
@@ -16,6 +20,27 @@ struct Args {
     /// Bind address
     #[arg(short, long, default_value = "127.0.0.1:8080")]
     bind: String,
+
+    /// Directory for run event logs
+    #[arg(short, long, default_value = "./cathedral-data")]
+    data_dir: PathBuf,
+
+    /// JSON file of accepted bearer tokens; without it the API is open
+    #[arg(long)]
+    auth: Option<PathBuf>,
+
+    /// JSON file of rate limits and tenant quotas; without it nothing is limited
+    #[arg(long)]
+    quotas: Option<PathBuf>,
+
+    /// File holding the hex master key that wraps each run's data key;
+    /// without it run data is stored unencrypted
+    #[arg(long)]
+    master_key: Option<PathBuf>,
+
+    /// ID recorded with the run keys the master key wraps
+    #[arg(long, default_value = "default", requires = "master_key")]
+    master_key_id: String,
 }
 
 #[tokio::main]
@@ -26,7 +51,18 @@ async fn main() -> Result<()> {
         .with_env_filter("cathedral=debug,tower_http=debug")
         .init();
 
-    let server = ApiServer::new(&args.bind)?;
+    let mut config = ServerConfig::new(args.bind, args.data_dir);
+    if let Some(path) = &args.master_key {
+        let hex = std::fs::read_to_string(path)?;
+        config = config.with_master_key(MasterKey::from_hex(&args.master_key_id, hex.trim())?);
+    }
+    let mut server = ApiServer::new(config)?;
+    if let Some(path) = &args.auth {
+        server = server.with_auth(AuthConfig::load(path)?);
+    }
+    if let Some(path) = &args.quotas {
+        server = server.with_quotas(QuotaConfig::load(path)?);
+    }
     server.serve().await?;
 
     Ok(())
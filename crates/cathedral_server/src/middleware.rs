@@ -1,4 +1,113 @@
 //! Middleware
 
+use crate::auth::{AuthContext, Authenticator};
+use crate::handler::{parse_id, tenant_of, HandlerError};
+use crate::quota::Quotas;
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{header, HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use cathedral_core::RunId;
+use cathedral_log::LiveStream;
+use cathedral_policy::audit::{ApiCall, ANONYMOUS};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
 pub struct Middleware;
 pub struct MiddlewareStack;
+
+/// Requests a token may make per rate-limit window
+pub const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+/// Requests left in the token's current window
+pub const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+
+/// Resolve the request's bearer token and attach its
+/// [`AuthContext`](crate::auth::AuthContext) for handlers
+///
+/// Requests without a valid token are refused with `401 Unauthorized`.
+pub async fn authenticate(
+    State(authenticator): State<Arc<Authenticator>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    match authenticator.authenticate(authorization) {
+        Ok(context) => {
+            request.extensions_mut().insert(context);
+            next.run(request).await
+        }
+        Err(e) => HandlerError::from(e).into_response(),
+    }
+}
+
+/// Count each request against its token's rate limit
+///
+/// Runs after [`authenticate`]. Admitted responses carry the token's limit
+/// and remaining requests; refused requests get `429 Too Many Requests`.
+pub async fn rate_limit(
+    State(quotas): State<Arc<Quotas>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let auth = request.extensions().get::<AuthContext>();
+    match quotas.acquire(tenant_of(auth), auth) {
+        Ok(Some(state)) => {
+            let mut response = next.run(request).await;
+            let headers = response.headers_mut();
+            headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(state.limit));
+            headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(state.remaining));
+            response
+        }
+        Ok(None) => next.run(request).await,
+        Err(denial) => HandlerError::from(denial).into_response(),
+    }
+}
+
+/// Record each request in the audit log as an [`ApiCall`]
+///
+/// Runs after [`authenticate`] and before [`rate_limit`], so calls are
+/// attributed to their token's subject and rate-limited calls are recorded
+/// too. The run a call concerns comes from its `/runs/{id}` path, or from
+/// the [`RunId`] extension a handler puts on its response. A call that
+/// cannot be recorded is logged and still answered.
+pub async fn audit(
+    State(log): State<Arc<LiveStream>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let auth = request.extensions().get::<AuthContext>();
+    let principal = auth.map_or_else(|| ANONYMOUS.to_string(), |auth| auth.subject.clone());
+    let tenant = tenant_of(auth);
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| request.uri().path(), MatchedPath::as_str)
+        .to_string();
+    let path_run = route
+        .starts_with("/runs/{id}")
+        .then(|| request.uri().path().split('/').nth(2))
+        .flatten()
+        .and_then(|id| parse_id(id, "run_"))
+        .map(RunId::from_bytes);
+
+    let response = next.run(request).await;
+    let call = ApiCall {
+        principal,
+        tenant,
+        method,
+        route,
+        status: response.status().as_u16(),
+        run_id: path_run.or_else(|| response.extensions().get::<RunId>().copied()),
+        at_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64),
+    };
+    if let Err(e) = call.to_event(log.len()).and_then(|event| log.append(&event).map(drop)) {
+        tracing::warn!(error = %e, "Failed to audit API call");
+    }
+    response
+}
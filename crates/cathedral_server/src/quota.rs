@@ -0,0 +1,504 @@
+//! Rate limits and tenant quotas
+//!
+//! Each token may make a bounded number of requests per window. Each tenant
+//! is bounded in how many runs it has executing at once, how many bytes its
+//! run logs hold, and how much fuel its runs may declare per UTC day. A run's
+//! fuel is the total of its nodes' fuel contracts, charged when the run is
+//! admitted; a run with a node that declares no fuel bound is charged the
+//! engine's tick limit, the most it can use.
+//!
+//! Every denial is appended to the server's audit log as a
+//! [`EventKind::QuotaDenied`] event whose payload is the JSON
+//! [`QuotaDenial`].
+
+use crate::auth::AuthContext;
+use cathedral_core::{CoreError, CoreResult, EventId, LogicalTime, NodeId, RunId, TenantId};
+use cathedral_log::{Event, EventKind, LiveStream};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Requests a token may make per window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Requests allowed in each window
+    pub requests: u64,
+    /// Window length in milliseconds
+    pub window_ms: u64,
+}
+
+impl RateLimit {
+    /// Allow `requests` per `window_ms`
+    #[must_use]
+    pub fn new(requests: u64, window_ms: u64) -> Self {
+        Self { requests, window_ms }
+    }
+}
+
+/// Bounds on what one tenant may use; `None` leaves a resource unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantQuota {
+    /// Runs executing at once
+    pub max_concurrent_runs: Option<u64>,
+    /// Bytes held by the tenant's run logs
+    pub max_storage_bytes: Option<u64>,
+    /// Fuel the tenant's runs may declare per UTC day
+    pub max_fuel_per_day: Option<u64>,
+}
+
+impl TenantQuota {
+    /// Create an unlimited quota
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit runs executing at once
+    #[must_use]
+    pub fn with_concurrent_runs(mut self, runs: u64) -> Self {
+        self.max_concurrent_runs = Some(runs);
+        self
+    }
+
+    /// Limit bytes held by run logs
+    #[must_use]
+    pub fn with_storage_bytes(mut self, bytes: u64) -> Self {
+        self.max_storage_bytes = Some(bytes);
+        self
+    }
+
+    /// Limit fuel per day
+    #[must_use]
+    pub fn with_fuel_per_day(mut self, fuel: u64) -> Self {
+        self.max_fuel_per_day = Some(fuel);
+        self
+    }
+}
+
+/// Rate limit and quota configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    /// Per-token request rate, if limited
+    pub rate_limit: Option<RateLimit>,
+    /// Quota of tenants without their own
+    #[serde(default)]
+    pub default_quota: TenantQuota,
+    /// Quotas of specific tenants
+    #[serde(default)]
+    pub tenants: BTreeMap<TenantId, TenantQuota>,
+}
+
+impl QuotaConfig {
+    /// Limit nothing
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit each token's request rate
+    #[must_use]
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Set the quota of tenants without their own
+    #[must_use]
+    pub fn with_default_quota(mut self, quota: TenantQuota) -> Self {
+        self.default_quota = quota;
+        self
+    }
+
+    /// Set a tenant's quota
+    #[must_use]
+    pub fn with_tenant_quota(mut self, tenant: TenantId, quota: TenantQuota) -> Self {
+        self.tenants.insert(tenant, quota);
+        self
+    }
+
+    /// Get a tenant's quota
+    #[must_use]
+    pub fn quota(&self, tenant: TenantId) -> TenantQuota {
+        self.tenants.get(&tenant).copied().unwrap_or(self.default_quota)
+    }
+
+    /// Load a JSON quota file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read or parsed
+    pub fn load(path: &Path) -> CoreResult<Self> {
+        let json = std::fs::read_to_string(path).map_err(|e| CoreError::Internal {
+            message: format!("Failed to read {}: {}", path.display(), e),
+        })?;
+        serde_json::from_str(&json).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to parse {}: {}", path.display(), e),
+        })
+    }
+}
+
+/// Resource a denial was for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaResource {
+    /// Requests per rate-limit window
+    Requests,
+    /// Runs executing at once
+    ConcurrentRuns,
+    /// Bytes held by run logs
+    StorageBytes,
+    /// Fuel per day
+    FuelPerDay,
+}
+
+impl QuotaResource {
+    /// Stable name, as sent in the `X-Quota-Resource` header
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Requests => "requests",
+            Self::ConcurrentRuns => "concurrent_runs",
+            Self::StorageBytes => "storage_bytes",
+            Self::FuelPerDay => "fuel_per_day",
+        }
+    }
+}
+
+impl fmt::Display for QuotaResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A request refused by a rate limit or quota
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{resource} quota exceeded for {tenant}: {used} of {limit} used, {requested} requested")]
+pub struct QuotaDenial {
+    /// Tenant the request acted for
+    pub tenant: TenantId,
+    /// Token holder, if the request was authenticated
+    pub subject: Option<String>,
+    /// Exhausted resource
+    pub resource: QuotaResource,
+    /// The limit
+    pub limit: u64,
+    /// Usage when the request arrived
+    pub used: u64,
+    /// Usage the request asked for
+    pub requested: u64,
+    /// Delay after which the limit resets, if it does
+    pub retry_after_ms: Option<u64>,
+}
+
+/// Rate-limit state after an admitted request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateState {
+    /// Requests allowed per window
+    pub limit: u64,
+    /// Requests left in the current window
+    pub remaining: u64,
+}
+
+/// What a tenant uses when it submits a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunDemand {
+    /// Runs of the tenant still executing
+    pub running: u64,
+    /// Bytes held by the tenant's run logs
+    pub stored_bytes: u64,
+    /// Fuel the run declares
+    pub fuel: u64,
+}
+
+/// Requests of one token in the current window
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    start: Instant,
+    count: u64,
+}
+
+/// Fuel a tenant charged on one day
+#[derive(Debug, Clone, Copy)]
+struct DailyFuel {
+    day: u64,
+    used: u64,
+}
+
+/// Enforces rate limits and tenant quotas
+pub struct Quotas {
+    /// Limits
+    config: QuotaConfig,
+    /// Current window of each token, by token hash
+    windows: Mutex<HashMap<String, Window>>,
+    /// Fuel charged today, by tenant
+    fuel: Mutex<HashMap<TenantId, DailyFuel>>,
+    /// Log of denials
+    audit: Arc<LiveStream>,
+}
+
+impl Quotas {
+    /// Create an enforcer appending denials to `audit`
+    #[must_use]
+    pub fn new(config: QuotaConfig, audit: Arc<LiveStream>) -> Self {
+        Self {
+            config,
+            windows: Mutex::new(HashMap::new()),
+            fuel: Mutex::new(HashMap::new()),
+            audit,
+        }
+    }
+
+    /// Get the limits
+    #[must_use]
+    pub fn config(&self) -> &QuotaConfig {
+        &self.config
+    }
+
+    /// Count a request against its token's rate limit
+    ///
+    /// Unauthenticated requests share one limit. Returns `None` if requests
+    /// are not rate limited.
+    ///
+    /// # Errors
+    ///
+    /// Returns the denial if the token used up its window
+    pub fn acquire(
+        &self,
+        tenant: TenantId,
+        auth: Option<&AuthContext>,
+    ) -> Result<Option<RateState>, QuotaDenial> {
+        self.acquire_at(Instant::now(), tenant, auth)
+    }
+
+    fn acquire_at(
+        &self,
+        now: Instant,
+        tenant: TenantId,
+        auth: Option<&AuthContext>,
+    ) -> Result<Option<RateState>, QuotaDenial> {
+        let Some(limit) = self.config.rate_limit else {
+            return Ok(None);
+        };
+        let window_len = Duration::from_millis(limit.window_ms);
+        let key = auth.map_or_else(String::new, |auth| auth.token_hash.clone());
+        // The denial is recorded after the lock is released, so a slow
+        // audit log never stalls other tokens' requests
+        let counted = {
+            let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+            let window = windows.entry(key).or_insert(Window { start: now, count: 0 });
+            let elapsed = now.saturating_duration_since(window.start);
+            if elapsed >= window_len {
+                *window = Window { start: now, count: 0 };
+            }
+            if window.count >= limit.requests {
+                let reset = window_len.saturating_sub(now.saturating_duration_since(window.start));
+                Err(QuotaDenial {
+                    tenant,
+                    subject: auth.map(|auth| auth.subject.clone()),
+                    resource: QuotaResource::Requests,
+                    limit: limit.requests,
+                    used: window.count,
+                    requested: 1,
+                    retry_after_ms: Some(reset.as_millis() as u64),
+                })
+            } else {
+                window.count += 1;
+                Ok(window.count)
+            }
+        };
+        let count = counted.map_err(|denial| self.deny(denial))?;
+        Ok(Some(RateState {
+            limit: limit.requests,
+            remaining: limit.requests - count,
+        }))
+    }
+
+    /// Admit a run against its tenant's quota, charging its fuel
+    ///
+    /// # Errors
+    ///
+    /// Returns the denial for the first exhausted resource, checking
+    /// concurrent runs, storage, then fuel
+    pub fn admit_run(
+        &self,
+        tenant: TenantId,
+        auth: Option<&AuthContext>,
+        demand: RunDemand,
+    ) -> Result<(), QuotaDenial> {
+        self.admit_run_on(utc_day(), tenant, auth, demand)
+    }
+
+    fn admit_run_on(
+        &self,
+        day: u64,
+        tenant: TenantId,
+        auth: Option<&AuthContext>,
+        demand: RunDemand,
+    ) -> Result<(), QuotaDenial> {
+        let quota = self.config.quota(tenant);
+        let mut fuel = self.fuel.lock().unwrap_or_else(|e| e.into_inner());
+        let charged = fuel
+            .get(&tenant)
+            .filter(|daily| daily.day == day)
+            .map_or(0, |daily| daily.used);
+        let checks = [
+            (QuotaResource::ConcurrentRuns, quota.max_concurrent_runs, demand.running, 1),
+            (QuotaResource::StorageBytes, quota.max_storage_bytes, demand.stored_bytes, 0),
+            (QuotaResource::FuelPerDay, quota.max_fuel_per_day, charged, demand.fuel),
+        ];
+        for (resource, limit, used, requested) in checks {
+            let Some(limit) = limit else {
+                continue;
+            };
+            // Storage asks for nothing up front, so it is refused once full
+            if used.saturating_add(requested) > limit || used >= limit && requested == 0 {
+                drop(fuel);
+                return Err(self.deny(QuotaDenial {
+                    tenant,
+                    subject: auth.map(|auth| auth.subject.clone()),
+                    resource,
+                    limit,
+                    used,
+                    requested,
+                    retry_after_ms: None,
+                }));
+            }
+        }
+        fuel.insert(tenant, DailyFuel {
+            day,
+            used: charged.saturating_add(demand.fuel),
+        });
+        Ok(())
+    }
+
+    /// Read every recorded denial, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the audit log cannot be read or decoded
+    pub fn denials(&self) -> CoreResult<Vec<QuotaDenial>> {
+        let mut denials = Vec::new();
+        for position in 0..self.audit.len() {
+            if let Some(event) = self.audit.read(position)?
+                && event.kind == EventKind::QuotaDenied
+            {
+                denials.push(serde_json::from_slice(&event.payload)?);
+            }
+        }
+        Ok(denials)
+    }
+
+    /// Record a denial in the audit log
+    ///
+    /// A denial that cannot be recorded is still returned, so a failing
+    /// audit log never lets a request through.
+    fn deny(&self, denial: QuotaDenial) -> QuotaDenial {
+        let recorded = serde_json::to_vec(&denial).map_err(Into::into).and_then(|payload| {
+            let event = Event::new(
+                EventId::new(),
+                RunId::from_bytes([0; 16]),
+                NodeId::from_bytes([0; 16]),
+                LogicalTime::from_raw(self.audit.len()),
+                EventKind::QuotaDenied,
+            )
+            .with_payload(payload);
+            self.audit.append(&event)
+        });
+        if let Err(e) = recorded {
+            tracing::warn!(error = %e, "Failed to record quota denial");
+        }
+        denial
+    }
+}
+
+/// Bytes held by the files under a directory
+///
+/// A missing directory holds nothing.
+///
+/// # Errors
+///
+/// Returns error if the directory cannot be walked
+pub fn stored_bytes(dir: &Path) -> CoreResult<u64> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let io = |e: std::io::Error| CoreError::Internal {
+        message: format!("Failed to measure {}: {}", dir.display(), e),
+    };
+    let mut total = 0;
+    for entry in std::fs::read_dir(dir).map_err(io)? {
+        let entry = entry.map_err(io)?;
+        let metadata = entry.metadata().map_err(io)?;
+        total += if metadata.is_dir() {
+            stored_bytes(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(total)
+}
+
+/// Days since the Unix epoch, in UTC
+fn utc_day() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_log::{SegmentConfig, SegmentedStream};
+
+    fn quotas(dir: &Path, config: QuotaConfig) -> Quotas {
+        let (audit, _) = SegmentedStream::open(SegmentConfig::new(dir.join("audit"))).unwrap();
+        Quotas::new(config, Arc::new(LiveStream::new(audit)))
+    }
+
+    #[test]
+    fn test_rate_limit_resets_each_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = QuotaConfig::new().with_rate_limit(RateLimit::new(2, 1000));
+        let quotas = quotas(dir.path(), config);
+        let tenant = TenantId::from_name("acme");
+        let start = Instant::now();
+
+        let state = quotas.acquire_at(start, tenant, None).unwrap().unwrap();
+        assert_eq!(state, RateState { limit: 2, remaining: 1 });
+        quotas.acquire_at(start, tenant, None).unwrap();
+        let soon = start + Duration::from_millis(400);
+        let denial = quotas.acquire_at(soon, tenant, None).unwrap_err();
+        assert_eq!(denial.resource, QuotaResource::Requests);
+        assert_eq!(denial.retry_after_ms, Some(600));
+
+        let later = start + Duration::from_millis(1000);
+        assert_eq!(quotas.acquire_at(later, tenant, None).unwrap().unwrap().remaining, 1);
+        assert_eq!(quotas.denials().unwrap(), vec![denial]);
+    }
+
+    #[test]
+    fn test_run_quotas() {
+        let dir = tempfile::tempdir().unwrap();
+        let quota = TenantQuota::new()
+            .with_concurrent_runs(1)
+            .with_storage_bytes(100)
+            .with_fuel_per_day(10);
+        let quotas = quotas(dir.path(), QuotaConfig::new().with_default_quota(quota));
+        let tenant = TenantId::from_name("acme");
+        let demand = |running, stored_bytes, fuel| RunDemand { running, stored_bytes, fuel };
+
+        assert!(quotas.admit_run_on(1, tenant, None, demand(0, 0, 6)).is_ok());
+        let denied = |demand| quotas.admit_run_on(1, tenant, None, demand).unwrap_err().resource;
+        assert_eq!(denied(demand(1, 0, 0)), QuotaResource::ConcurrentRuns);
+        assert_eq!(denied(demand(0, 100, 0)), QuotaResource::StorageBytes);
+        assert_eq!(denied(demand(0, 0, 5)), QuotaResource::FuelPerDay);
+        assert!(quotas.admit_run_on(1, tenant, None, demand(0, 99, 4)).is_ok());
+
+        // Fuel is charged per day
+        assert!(quotas.admit_run_on(2, tenant, None, demand(0, 0, 10)).is_ok());
+        assert_eq!(quotas.denials().unwrap().len(), 3);
+    }
+}
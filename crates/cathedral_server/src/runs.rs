@@ -0,0 +1,197 @@
+//! Runs submitted to the server and their progress.
+
+use cathedral_core::{CancellationToken, NodeId, RunId, TenantId};
+use cathedral_log::{Event, EventKind, LiveStream};
+use cathedral_plan::Dag;
+use cathedral_storage::store::FsContentStore;
+use cathedral_storage::BlobId;
+use cathedral_runtime::engine::ExecutionStatus;
+use cathedral_runtime::Metrics;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+/// Lifecycle of a submitted run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    /// The engine is executing the run
+    Running,
+    /// Every node completed
+    Succeeded,
+    /// A node failed or the run could not be executed
+    Failed,
+    /// The run exceeded its tick budget
+    TimedOut,
+    /// The run was cancelled
+    Cancelled,
+}
+
+impl RunStatus {
+    /// Check if the run has ended
+    #[must_use]
+    pub fn is_finished(self) -> bool {
+        self != Self::Running
+    }
+
+    /// Status of a run the engine finished with `status`
+    #[must_use]
+    pub fn from_execution(status: &ExecutionStatus) -> Self {
+        match status {
+            ExecutionStatus::Success => Self::Succeeded,
+            ExecutionStatus::PartialFailure | ExecutionStatus::CycleDetected => Self::Failed,
+            ExecutionStatus::Timeout => Self::TimedOut,
+            ExecutionStatus::Cancelled => Self::Cancelled,
+        }
+    }
+}
+
+/// Progress of one node of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeState {
+    /// Not started
+    Pending,
+    /// Started and not yet finished
+    Running,
+    /// Finished successfully
+    Completed,
+    /// Finished with an error
+    Failed,
+    /// Not run because a dependency did not complete
+    Skipped,
+    /// In flight when the run was cancelled
+    Cancelled,
+    /// Dropped by load shedding
+    Shed,
+    /// Refused admission by the resource budget
+    OverBudget,
+}
+
+impl NodeState {
+    /// State a node enters on an event of `kind`, if the event changes it
+    #[must_use]
+    pub fn after(kind: EventKind) -> Option<Self> {
+        match kind {
+            EventKind::NodeStarted => Some(Self::Running),
+            EventKind::NodeCompleted => Some(Self::Completed),
+            EventKind::NodeFailed => Some(Self::Failed),
+            EventKind::NodeSkipped => Some(Self::Skipped),
+            EventKind::NodeCancelled => Some(Self::Cancelled),
+            EventKind::Shed => Some(Self::Shed),
+            EventKind::BudgetExceeded => Some(Self::OverBudget),
+            _ => None,
+        }
+    }
+}
+
+/// A run known to the server
+pub struct RunRecord {
+    /// Run ID
+    pub run_id: RunId,
+    /// Tenant the run belongs to
+    pub tenant: TenantId,
+    /// Current status, published to watchers
+    status: watch::Sender<RunStatus>,
+    /// Submitted DAG
+    pub dag: Dag,
+    /// Progress of every node of the submitted DAG, in DAG order
+    pub nodes: IndexMap<NodeId, NodeState>,
+    /// Event log of the run
+    pub log: Arc<LiveStream>,
+    /// Store of the run's tenant, holding its artifacts
+    pub store: Arc<FsContentStore>,
+    /// Outputs of completed nodes, in the tenant's store
+    pub artifacts: IndexMap<NodeId, BlobId>,
+    /// Why the run failed, if it did
+    pub error: Option<String>,
+    /// Token that cancels the run
+    pub cancellation: CancellationToken,
+    /// Execution metrics, published by the engine as the run executes
+    pub metrics: Arc<Mutex<Metrics>>,
+}
+
+impl RunRecord {
+    /// Record a run of `dag` that has just started
+    #[must_use]
+    pub fn new(
+        run_id: RunId,
+        tenant: TenantId,
+        dag: &Dag,
+        log: Arc<LiveStream>,
+        store: Arc<FsContentStore>,
+    ) -> Self {
+        Self {
+            run_id,
+            tenant,
+            status: watch::channel(RunStatus::Running).0,
+            dag: dag.clone(),
+            nodes: dag.nodes.keys().map(|&id| (id, NodeState::Pending)).collect(),
+            log,
+            store,
+            artifacts: IndexMap::new(),
+            error: None,
+            cancellation: CancellationToken::new(),
+            metrics: Arc::new(Mutex::new(Metrics::new())),
+        }
+    }
+
+    /// Current status
+    #[must_use]
+    pub fn status(&self) -> RunStatus {
+        *self.status.borrow()
+    }
+
+    /// Watch the run's status change
+    #[must_use]
+    pub fn watch_status(&self) -> watch::Receiver<RunStatus> {
+        self.status.subscribe()
+    }
+
+    /// Record how the run ended and notify watchers
+    pub fn finish(&mut self, status: RunStatus, error: Option<String>) {
+        self.error = error;
+        self.status.send_replace(status);
+    }
+
+    /// Update node progress from a logged event
+    ///
+    /// Events of nodes outside the submitted DAG, such as the nodes of a
+    /// sub-workflow, are ignored.
+    pub fn apply(&mut self, event: &Event) {
+        if let Some(state) = NodeState::after(event.kind)
+            && let Some(node) = self.nodes.get_mut(&event.node_id)
+        {
+            *node = state;
+        }
+    }
+
+    /// Number of nodes that finished successfully
+    #[must_use]
+    pub fn completed(&self) -> usize {
+        self.nodes.values().filter(|&&state| state == NodeState::Completed).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_status_from_execution() {
+        assert_eq!(RunStatus::from_execution(&ExecutionStatus::Success), RunStatus::Succeeded);
+        assert_eq!(RunStatus::from_execution(&ExecutionStatus::Cancelled), RunStatus::Cancelled);
+        assert!(!RunStatus::Running.is_finished());
+        assert!(RunStatus::TimedOut.is_finished());
+        assert_eq!(serde_json::to_string(&RunStatus::TimedOut).unwrap(), "\"timed_out\"");
+    }
+
+    #[test]
+    fn test_node_state_after_event() {
+        assert_eq!(NodeState::after(EventKind::NodeStarted), Some(NodeState::Running));
+        assert_eq!(NodeState::after(EventKind::BudgetExceeded), Some(NodeState::OverBudget));
+        assert_eq!(NodeState::after(EventKind::Heartbeat), None);
+        assert_eq!(serde_json::to_string(&NodeState::OverBudget).unwrap(), "\"over_budget\"");
+    }
+}
@@ -0,0 +1,214 @@
+//! JSON bodies of the HTTP API.
+//!
+//! These types are the wire contract: IDs travel in their display form
+//! (`run_…`, `node_…`, `evt_…`), hashes and bytes as lowercase hex, and
+//! enums as `snake_case` strings. Fields are only ever added.
+
+use crate::runs::{NodeState, RunRecord, RunStatus};
+use cathedral_core::NodeId;
+use cathedral_log::{Event, EventKind};
+use cathedral_plan::Dag;
+use cathedral_storage::Blob;
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /runs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitRunRequest {
+    /// Compiled DAG to execute
+    pub dag: Dag,
+}
+
+/// Body of `GET /runs/{id}` and `DELETE /runs/{id}`, and of the
+/// `POST /runs` response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunView {
+    /// Run ID
+    pub run_id: String,
+    /// Tenant the run belongs to
+    pub tenant_id: String,
+    /// Current status
+    pub status: RunStatus,
+    /// Number of nodes in the DAG
+    pub total_nodes: usize,
+    /// Number of nodes that completed
+    pub completed_nodes: usize,
+    /// Number of events logged so far
+    pub events: u64,
+    /// Why the run failed, if it did
+    pub error: Option<String>,
+    /// Progress of every node, in DAG order
+    pub nodes: Vec<NodeView>,
+}
+
+impl From<&RunRecord> for RunView {
+    fn from(record: &RunRecord) -> Self {
+        Self {
+            run_id: record.run_id.to_string(),
+            tenant_id: record.tenant.to_string(),
+            status: record.status(),
+            total_nodes: record.nodes.len(),
+            completed_nodes: record.completed(),
+            events: record.log.len(),
+            error: record.error.clone(),
+            nodes: record
+                .nodes
+                .iter()
+                .map(|(node_id, &state)| NodeView {
+                    node_id: node_id.to_string(),
+                    state,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Body of `GET /runs`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunList {
+    /// The caller's runs, in submission order
+    pub runs: Vec<RunView>,
+}
+
+/// Progress of one node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeView {
+    /// Node ID
+    pub node_id: String,
+    /// Current state
+    pub state: NodeState,
+}
+
+/// Query string of `GET /runs/{id}/events`
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct EventPageParams {
+    /// Position of the first event to return
+    pub from: Option<u64>,
+    /// Maximum number of events to return
+    pub limit: Option<usize>,
+}
+
+/// Query string of `GET /audit`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditParams {
+    /// Only this run's entries
+    pub run: Option<String>,
+    /// Only entries by this principal
+    pub actor: Option<String>,
+    /// Only entries with this action, e.g. `PolicyDecision` or `POST /runs`
+    pub action: Option<String>,
+    /// `json` (the default), `jsonl` or `csv`
+    pub format: Option<String>,
+}
+
+/// Query string of `POST /cluster/members/{node}/drain`
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct DrainParams {
+    /// How long to wait for the worker's running tasks, in milliseconds
+    pub timeout_ms: Option<u64>,
+}
+
+/// Body of `POST /cluster/members/{node}/promote`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromoteView {
+    /// Promoted member
+    pub node_id: String,
+    /// Log index of the proposed configuration change
+    pub index: u64,
+}
+
+/// Body of `GET /runs/{id}/events`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventPage {
+    /// Run ID
+    pub run_id: String,
+    /// Events of the page, in log order
+    pub events: Vec<EventView>,
+    /// Position to request the next page from, if more events are logged
+    pub next: Option<u64>,
+    /// Number of events logged so far
+    pub total: u64,
+}
+
+/// One logged event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventView {
+    /// Position in the run's log
+    pub position: u64,
+    /// Event ID
+    pub event_id: String,
+    /// Node the event belongs to
+    pub node_id: String,
+    /// Event kind
+    pub kind: EventKind,
+    /// Logical time of the event
+    pub logical_time: u64,
+    /// Hash of the event payload
+    pub payload_hash: String,
+    /// Event payload as hex
+    pub payload: String,
+}
+
+impl EventView {
+    /// View of the event at `position`
+    #[must_use]
+    pub fn new(position: u64, event: &Event) -> Self {
+        Self {
+            position,
+            event_id: event.event_id.to_string(),
+            node_id: event.node_id.to_string(),
+            kind: event.kind,
+            logical_time: event.logical_time.as_u64(),
+            payload_hash: event.payload_hash.to_hex(),
+            payload: hex(&event.payload),
+        }
+    }
+}
+
+/// Body of `GET /runs/{id}/artifacts/{node}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactView {
+    /// Run ID
+    pub run_id: String,
+    /// Node that produced the output
+    pub node_id: String,
+    /// Hash of the output
+    pub output_hash: String,
+    /// Output size in bytes
+    pub size: usize,
+    /// Output bytes as hex
+    pub output: String,
+}
+
+impl ArtifactView {
+    /// View of a node's output, read back from its blob
+    #[must_use]
+    pub fn new(run_id: &str, node_id: NodeId, output: &Blob) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            node_id: node_id.to_string(),
+            output_hash: output.id().hash.to_hex(),
+            size: output.size(),
+            output: hex(output.as_bytes()),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Body of every error response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    /// The error
+    pub error: ErrorDetail,
+}
+
+/// Machine- and human-readable error
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    /// Stable error code, e.g. `run_not_found`
+    pub code: String,
+    /// Description of the error
+    pub message: String,
+}
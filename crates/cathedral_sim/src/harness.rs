@@ -1,9 +1,15 @@
 //! Simulation harness for running deterministic simulations.
 
 use crate::{seed::SimSeed, network::NetworkSim, failure::{CrashInjector, FailureScenario}, node::{SimNode, SimNodeConfig}, record::SimRecord};
-use cathedral_core::NodeId;
+use crate::failure::{FailureKind, ScheduledFailure};
+use crate::network::{NetworkCondition, ScheduledCondition, ScheduledLinkChange, SendResult};
+use crate::node::{NodeMessage, ReceiveResult};
+use crate::scenario::{Scenario, Workload};
+use cathedral_core::{CoreResult, NodeId};
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -60,8 +66,30 @@ impl Default for SimConfig {
     }
 }
 
+/// Subsystems that receive a seed derived from the base seed
+const SUBSYSTEMS: [&str; 3] = ["network", "crash", "workload"];
+
+/// Fate of a workload message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageOutcome {
+    /// Message reached a running receiver
+    Delivered {
+        /// Simulated latency in milliseconds
+        latency: u64,
+    },
+    /// Message was lost by the network
+    Dropped,
+    /// Sender and receiver cannot reach each other
+    Partitioned,
+    /// Receiver is down
+    NodeDown,
+}
+
 /// Simulation result
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Carries everything needed to re-run the same scenario via
+/// [`SimHarness::reproduce`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SimResult {
     /// Whether simulation completed successfully
     pub success: bool,
@@ -71,6 +99,23 @@ pub struct SimResult {
     pub error: Option<String>,
     /// Final state of all nodes
     pub final_states: HashMap<NodeId, String>,
+    /// Configuration the simulation ran with, including the base seed
+    pub config: SimConfig,
+    /// Seeds derived for each subsystem, by subsystem name
+    pub subsystem_seeds: BTreeMap<String, u64>,
+    /// Node configurations, ordered by node ID
+    pub nodes: Vec<SimNodeConfig>,
+    /// Failure scenario, if one was set
+    pub scenario: Option<FailureScenario>,
+    /// Network conditions applied by tick
+    #[serde(default)]
+    pub network_schedule: Vec<ScheduledCondition>,
+    /// Link cuts, heals and faults applied by tick
+    #[serde(default)]
+    pub link_schedule: Vec<ScheduledLinkChange>,
+    /// Message workload, if one was set
+    #[serde(default)]
+    pub workload: Option<Workload>,
 }
 
 impl SimResult {
@@ -82,6 +127,13 @@ impl SimResult {
             ticks_executed: ticks,
             error: None,
             final_states: HashMap::new(),
+            config: SimConfig::default(),
+            subsystem_seeds: BTreeMap::new(),
+            nodes: Vec::new(),
+            scenario: None,
+            network_schedule: Vec::new(),
+            link_schedule: Vec::new(),
+            workload: None,
         }
     }
 
@@ -90,11 +142,22 @@ impl SimResult {
     pub fn failure(ticks: u64, error: String) -> Self {
         Self {
             success: false,
-            ticks_executed: ticks,
             error: Some(error),
-            final_states: HashMap::new(),
+            ..Self::success(ticks)
         }
     }
+
+    /// Get the base seed the simulation ran with
+    #[must_use]
+    pub fn seed(&self) -> &SimSeed {
+        &self.config.seed
+    }
+
+    /// Get the seed derived for a subsystem
+    #[must_use]
+    pub fn subsystem_seed(&self, subsystem: &str) -> Option<u64> {
+        self.subsystem_seeds.get(subsystem).copied()
+    }
 }
 
 impl Default for SimResult {
@@ -119,15 +182,25 @@ pub struct SimHarness {
     record: Arc<RwLock<SimRecord>>,
     /// Failure scenario
     scenario: Option<FailureScenario>,
+    /// Network conditions applied by tick
+    network_schedule: Vec<ScheduledCondition>,
+    /// Link changes applied by tick
+    link_schedule: Vec<ScheduledLinkChange>,
+    /// Failures to lift, by tick
+    recoveries: Arc<RwLock<BTreeMap<u64, Vec<ScheduledFailure>>>>,
+    /// Message workload
+    workload: Option<Workload>,
+    /// RNG picking workload message receivers
+    workload_rng: Arc<RwLock<ChaCha8Rng>>,
 }
 
 impl SimHarness {
     /// Create a new simulation harness
     #[must_use]
     pub fn new(config: SimConfig) -> Self {
-        let seed = config.seed.clone();
-        let network = Arc::new(RwLock::new(NetworkSim::new(seed)));
-        let crash_injector = Arc::new(CrashInjector::new(config.seed.clone()));
+        let network = Arc::new(RwLock::new(NetworkSim::new(config.seed.derive("network"))));
+        let crash_injector = Arc::new(CrashInjector::new(config.seed.derive("crash")));
+        let workload_rng = Arc::new(RwLock::new(config.seed.derive("workload").rng()));
 
         Self {
             config,
@@ -137,9 +210,66 @@ impl SimHarness {
             tick: Arc::new(RwLock::new(0)),
             record: Arc::new(RwLock::new(SimRecord::new())),
             scenario: None,
+            network_schedule: Vec::new(),
+            link_schedule: Vec::new(),
+            recoveries: Arc::new(RwLock::new(BTreeMap::new())),
+            workload: None,
+            workload_rng,
         }
     }
 
+    /// Build a harness running `scenario`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the scenario fails validation
+    pub fn from_scenario(scenario: &Scenario) -> CoreResult<Self> {
+        scenario.validate()?;
+        let mut harness = Self::new(scenario.config());
+        harness.nodes = Arc::new(RwLock::new(
+            scenario
+                .node_configs()
+                .into_iter()
+                .map(|config| (config.node_id, SimNode::new(config)))
+                .collect(),
+        ));
+        harness.set_scenario(scenario.failure_scenario());
+        harness.set_network_schedule(scenario.network_schedule());
+        harness.set_link_schedule(scenario.link_schedule());
+        harness.workload = scenario.workload.clone();
+        Ok(harness)
+    }
+
+    /// Rebuild the harness that produced `result`
+    ///
+    /// The returned harness has the same seed, configuration, nodes, and
+    /// failure scenario, so running it replays the simulation exactly.
+    #[must_use]
+    pub fn reproduce(result: &SimResult) -> Self {
+        let mut harness = Self::new(result.config.clone());
+        harness.nodes = Arc::new(RwLock::new(
+            result
+                .nodes
+                .iter()
+                .map(|config| (config.node_id, SimNode::new(config.clone())))
+                .collect(),
+        ));
+        harness.scenario = result.scenario.clone();
+        harness.network_schedule = result.network_schedule.clone();
+        harness.link_schedule = result.link_schedule.clone();
+        harness.workload = result.workload.clone();
+        harness
+    }
+
+    /// Get the seeds derived for each subsystem
+    #[must_use]
+    pub fn subsystem_seeds(&self) -> BTreeMap<String, u64> {
+        SUBSYSTEMS
+            .iter()
+            .map(|name| (name.to_string(), self.config.seed.derive(name).seed))
+            .collect()
+    }
+
     /// Add a node to the simulation
     pub async fn add_node(&self, config: SimNodeConfig) {
         let node = SimNode::new(config);
@@ -152,6 +282,21 @@ impl SimHarness {
         self.scenario = Some(scenario);
     }
 
+    /// Set the network conditions to apply by tick
+    pub fn set_network_schedule(&mut self, schedule: Vec<ScheduledCondition>) {
+        self.network_schedule = schedule;
+    }
+
+    /// Set the link cuts, heals and faults to apply by tick
+    pub fn set_link_schedule(&mut self, schedule: Vec<ScheduledLinkChange>) {
+        self.link_schedule = schedule;
+    }
+
+    /// Set a message workload
+    pub fn set_workload(&mut self, workload: Workload) {
+        self.workload = Some(workload);
+    }
+
     /// Run the simulation
     pub async fn run(&self) -> SimResult {
         let max_ticks = self.config.max_ticks;
@@ -190,12 +335,26 @@ impl SimHarness {
             let state = node.state().await;
             final_states.insert(*node_id, format!("{:?}", state));
         }
+        if self.config.record_events {
+            self.record.write().await.final_snapshot = final_states.clone();
+        }
+
+        let mut node_configs: Vec<SimNodeConfig> =
+            nodes.values().map(|node| node.config().clone()).collect();
+        node_configs.sort_by_key(|config| config.node_id);
 
         SimResult {
             success: true,
             ticks_executed: final_tick,
             error: None,
             final_states,
+            config: self.config.clone(),
+            subsystem_seeds: self.subsystem_seeds(),
+            nodes: node_configs,
+            scenario: self.scenario.clone(),
+            network_schedule: self.network_schedule.clone(),
+            link_schedule: self.link_schedule.clone(),
+            workload: self.workload.clone(),
         }
     }
 
@@ -205,20 +364,44 @@ impl SimHarness {
         *tick += 1;
         let current_tick = *tick;
 
+        let node_ids = self.sorted_node_ids().await;
+
+        // Lift failures whose duration has elapsed
+        let expired = self.recoveries.write().await.remove(&current_tick);
+        for failure in expired.unwrap_or_default() {
+            self.lift_failure(&failure, &node_ids).await;
+        }
+
+        // Apply network conditions
+        for scheduled in self.network_schedule.iter().filter(|s| s.tick == current_tick) {
+            let mut network = self.network.write().await;
+            if scheduled.is_default() {
+                network.set_default(scheduled.condition.clone());
+            } else {
+                for (from, to) in scheduled.links(&node_ids) {
+                    network.set_condition(from, to, scheduled.condition.clone()).await;
+                }
+            }
+        }
+
+        // Apply link changes
+        for change in self.link_schedule.iter().filter(|c| c.tick == current_tick) {
+            let mut network = self.network.write().await;
+            network.apply_link_change(change, &node_ids).await;
+        }
+
         // Process scenario failures
         if let Some(ref scenario) = self.scenario {
             let failures = scenario.schedule.get_failures(current_tick);
             for failure in failures {
-                let nodes = self.nodes.read().await;
-                if let Some(node) = nodes.get(&failure.node_id) {
-                    node.apply_failure(failure.kind.clone()).await;
-                }
+                self.apply_scheduled_failure(current_tick, failure, &node_ids).await;
             }
         }
 
-        // Advance all nodes
+        // Advance all nodes, in ID order so the record is reproducible
         let nodes = self.nodes.read().await;
-        for node in nodes.values() {
+        for node_id in &node_ids {
+            let node = &nodes[node_id];
             let events = node.advance().await;
 
             // Record events
@@ -229,6 +412,11 @@ impl SimHarness {
                 }
             }
         }
+        drop(nodes);
+
+        if let Some(ref workload) = self.workload {
+            self.run_workload(current_tick, workload, &node_ids).await;
+        }
 
         // Tick delay for debugging
         if self.config.tick_delay_ms > 0 {
@@ -236,6 +424,148 @@ impl SimHarness {
         }
     }
 
+    /// Node IDs in ascending order
+    async fn sorted_node_ids(&self) -> Vec<NodeId> {
+        let mut ids = self.node_ids().await;
+        ids.sort();
+        ids
+    }
+
+    /// Apply a scheduled failure, remembering when to lift it
+    async fn apply_scheduled_failure(
+        &self,
+        tick: u64,
+        failure: ScheduledFailure,
+        node_ids: &[NodeId],
+    ) {
+        let nodes = self.nodes.read().await;
+        let Some(node) = nodes.get(&failure.node_id) else {
+            return;
+        };
+        node.apply_failure(failure.kind.clone()).await;
+        if let FailureKind::HighLatency { ms } = failure.kind {
+            let latency = NetworkCondition::Latency(ms);
+            self.set_node_links(failure.node_id, node_ids, Some(latency)).await;
+        }
+
+        if failure.duration_ticks > 0 {
+            let mut recoveries = self.recoveries.write().await;
+            recoveries
+                .entry(tick + failure.duration_ticks)
+                .or_default()
+                .push(failure);
+        }
+    }
+
+    /// Undo a failure whose duration has elapsed
+    async fn lift_failure(&self, failure: &ScheduledFailure, node_ids: &[NodeId]) {
+        match failure.kind {
+            FailureKind::Crash | FailureKind::Partition => {
+                if let Some(node) = self.nodes.read().await.get(&failure.node_id) {
+                    node.recover().await;
+                }
+            }
+            FailureKind::HighLatency { .. } => {
+                self.set_node_links(failure.node_id, node_ids, None).await;
+            }
+            FailureKind::Corrupted | FailureKind::Omission { .. } => {}
+        }
+    }
+
+    /// Set or clear the condition of every link to and from a node
+    async fn set_node_links(
+        &self,
+        node_id: NodeId,
+        node_ids: &[NodeId],
+        condition: Option<NetworkCondition>,
+    ) {
+        let network = self.network.read().await;
+        for peer in node_ids.iter().filter(|peer| **peer != node_id) {
+            for (from, to) in [(node_id, *peer), (*peer, node_id)] {
+                match &condition {
+                    Some(condition) => network.set_condition(from, to, condition.clone()).await,
+                    None => network.clear_condition(from, to).await,
+                }
+            }
+        }
+    }
+
+    /// Send the workload's messages for a tick through the network
+    async fn run_workload(&self, tick: u64, workload: &Workload, node_ids: &[NodeId]) {
+        let nodes = self.nodes.read().await;
+        let mut network = self.network.write().await;
+        let mut rng = self.workload_rng.write().await;
+        let payload = vec![0u8; workload.payload_bytes];
+
+        for from in node_ids {
+            let sender = &nodes[from];
+            let peers: Vec<NodeId> = node_ids.iter().copied().filter(|id| id != from).collect();
+            if peers.is_empty() || !sender.is_alive().await {
+                continue;
+            }
+
+            for _ in 0..workload.messages_per_tick {
+                let to = peers[rng.gen_range(0..peers.len())];
+                let outcome = match network.transmit(tick, *from, to, payload.clone()).await {
+                    SendResult::Delivered { .. } => continue,
+                    SendResult::Dropped => MessageOutcome::Dropped,
+                    SendResult::Partitioned => MessageOutcome::Partitioned,
+                };
+                self.record_message(tick, *from, to, tick, false, outcome).await;
+            }
+        }
+
+        // Hand over the messages due this tick, which may have been sent
+        // earlier and may arrive twice
+        for delivery in network.deliver(tick).await {
+            let Some(receiver) = nodes.get(&delivery.to) else {
+                continue;
+            };
+            let message = NodeMessage {
+                from: delivery.from,
+                to: delivery.to,
+                data: delivery.data,
+                tick: delivery.sent_at,
+            };
+            let outcome = match receiver.receive(message).await {
+                ReceiveResult::Received => MessageOutcome::Delivered {
+                    latency: delivery.latency,
+                },
+                ReceiveResult::NodeDown => MessageOutcome::NodeDown,
+                ReceiveResult::Partitioned => MessageOutcome::Partitioned,
+                ReceiveResult::Omitted => MessageOutcome::Dropped,
+            };
+            self.record_message(
+                tick,
+                delivery.from,
+                delivery.to,
+                delivery.sent_at,
+                delivery.duplicate,
+                outcome,
+            )
+            .await;
+        }
+    }
+
+    /// Record the fate of a workload message against its sender
+    async fn record_message(
+        &self,
+        tick: u64,
+        from: NodeId,
+        to: NodeId,
+        sent_at: u64,
+        duplicate: bool,
+        outcome: MessageOutcome,
+    ) {
+        if self.config.record_events {
+            let event = format!(
+                "Message {{ to: {}, sent_at: {}, duplicate: {}, outcome: {:?} }}",
+                to, sent_at, duplicate, outcome
+            );
+            self.record.write().await.events.push((tick, from, event));
+        }
+    }
+
     /// Get the event record
     pub async fn record(&self) -> SimRecord {
         self.record.read().await.clone()
@@ -285,6 +615,8 @@ impl SimHarness {
         *self.tick.write().await = 0;
         *self.record.write().await = SimRecord::new();
         self.crash_injector.reset().await;
+        self.recoveries.write().await.clear();
+        *self.workload_rng.write().await = self.config.seed.derive("workload").rng();
 
         let nodes = self.nodes.read().await;
         for node in nodes.values() {
@@ -400,6 +732,198 @@ mod tests {
         assert_eq!(result.ticks_executed, 10);
     }
 
+    #[tokio::test]
+    async fn test_sim_harness_reproduce() {
+        let node_id = NodeId::new();
+        let config = SimConfig::new(SimSeed::from_literal(7))
+            .with_max_ticks(20)
+            .without_recording();
+        let mut harness = SimHarness::new(config);
+        harness.add_node(SimNodeConfig::new(node_id)).await;
+        harness.add_node(SimNodeConfig::new(NodeId::new())).await;
+        harness.set_scenario(
+            FailureScenario::new("crash".to_string(), "crash one node".to_string())
+                .crash_at(5, node_id),
+        );
+
+        let result = harness.run().await;
+        assert_eq!(result.seed().seed, 7);
+        assert_eq!(
+            result.subsystem_seed("network"),
+            Some(SimSeed::from_literal(7).derive("network").seed)
+        );
+        assert!(result.subsystem_seed("crash").is_some());
+        assert_eq!(result.nodes.len(), 2);
+
+        let replayed = SimHarness::reproduce(&result).run().await;
+        assert_eq!(replayed, result);
+    }
+
+    const SCENARIO: &str = r#"
+        name = "lossy-crash"
+        seed = 11
+        ticks = 20
+
+        [[nodes]]
+        name = "a"
+
+        [[nodes]]
+        name = "b"
+
+        [[nodes]]
+        name = "c"
+
+        [[network]]
+        tick = 3
+        condition = "packet_loss"
+        probability = 0.5
+
+        [[events]]
+        tick = 5
+        node = "b"
+        kind = "crash"
+        duration = 3
+
+        [[events]]
+        tick = 6
+        node = "c"
+        kind = "latency"
+        ms = 250
+        duration = 4
+
+        [workload]
+        messages_per_tick = 2
+    "#;
+
+    #[tokio::test]
+    async fn test_sim_harness_from_scenario() {
+        let scenario = Scenario::from_toml(SCENARIO).unwrap();
+        let harness = SimHarness::from_scenario(&scenario).unwrap();
+        let b = scenario.node_id("b");
+        let c = scenario.node_id("c");
+
+        let result = harness.run().await;
+        assert_eq!(result.ticks_executed, 20);
+        assert_eq!(result.seed().seed, 11);
+        assert_eq!(result.nodes.len(), 3);
+        assert_eq!(result.final_states[&b], "Running");
+
+        let record = harness.record().await;
+        assert_eq!(record.final_snapshot, result.final_states);
+        let b_events = record.events_for_node(b);
+        assert!(b_events.contains(&(5, "Crashed { tick: 5 }".to_string())));
+        assert!(b_events.contains(&(8, "Recovering { tick: 8 }".to_string())));
+        assert!(record.events.iter().any(|(_, _, event)| event.contains("Dropped")));
+
+        let network = harness.network().await;
+        let network = network.read().await;
+        assert_eq!(
+            network.get_condition(c, b).await,
+            NetworkCondition::PacketLoss { probability: 0.5 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sim_harness_scenario_is_reproducible() {
+        let scenario = Scenario::from_toml(SCENARIO).unwrap();
+        let first = SimHarness::from_scenario(&scenario).unwrap();
+        let result = first.run().await;
+        assert_eq!(result.subsystem_seed("workload").map(|_| ()), Some(()));
+
+        let second = SimHarness::from_scenario(&scenario).unwrap();
+        assert_eq!(second.run().await, result);
+        assert_eq!(second.record().await.events, first.record().await.events);
+
+        let replayed = SimHarness::reproduce(&result);
+        assert_eq!(replayed.run().await, result);
+        assert_eq!(replayed.record().await.events, first.record().await.events);
+    }
+
+    #[tokio::test]
+    async fn test_sim_harness_latency_failure_is_lifted() {
+        let scenario = Scenario::from_toml(SCENARIO).unwrap();
+        let harness = SimHarness::from_scenario(&scenario).unwrap();
+        let a = scenario.node_id("a");
+        let c = scenario.node_id("c");
+
+        for _ in 0..6 {
+            harness.advance_tick().await;
+        }
+        let network = harness.network().await;
+        assert_eq!(
+            network.read().await.get_condition(a, c).await,
+            NetworkCondition::Latency(250)
+        );
+
+        for _ in 0..4 {
+            harness.advance_tick().await;
+        }
+        assert_eq!(
+            network.read().await.get_condition(c, a).await,
+            NetworkCondition::PacketLoss { probability: 0.5 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sim_harness_asymmetric_link_cut() {
+        let scenario = Scenario::from_toml(
+            r#"
+            name = "one-way"
+            seed = 3
+            ticks = 12
+
+            [[nodes]]
+            name = "a"
+
+            [[nodes]]
+            name = "b"
+
+            [[links]]
+            tick = 3
+            from = "a"
+            action = "cut"
+            duration = 5
+
+            [[links]]
+            tick = 1
+            action = "faults"
+            reorder_probability = 0.5
+            max_reorder_ticks = 2
+            duplicate_probability = 0.2
+
+            [workload]
+            messages_per_tick = 3
+            "#,
+        )
+        .unwrap();
+        let harness = SimHarness::from_scenario(&scenario).unwrap();
+        let a = scenario.node_id("a");
+        let b = scenario.node_id("b");
+        let result = harness.run().await;
+        let record = harness.record().await;
+
+        let messages = |node_id, from_tick, to_tick| -> Vec<String> {
+            record
+                .events_for_node(node_id)
+                .into_iter()
+                .filter(|(tick, event)| {
+                    (from_tick..to_tick).contains(tick) && event.starts_with("Message")
+                })
+                .map(|(_, event)| event)
+                .collect()
+        };
+        let cut = messages(a, 3, 8);
+        assert!(!cut.is_empty());
+        assert!(cut.iter().all(|event| event.contains("outcome: Partitioned")));
+        assert!(messages(b, 3, 8).iter().all(|event| event.contains("Delivered")));
+        assert!(messages(a, 8, 13).iter().any(|event| event.contains("Delivered")));
+        assert!(record.events.iter().any(|(_, _, event)| event.contains("duplicate: true")));
+
+        let replayed = SimHarness::reproduce(&result);
+        assert_eq!(replayed.run().await, result);
+        assert_eq!(replayed.record().await.events, record.events);
+    }
+
     #[tokio::test]
     async fn test_sim_harness_reset() {
         let harness = SimHarness::new(SimConfig::default());
@@ -12,10 +12,17 @@ pub mod node;
 pub mod seed;
 pub mod harness;
 pub mod record;
+pub mod schedule;
+pub mod scenario;
 
-pub use network::{NetworkSim, NetworkCondition, PacketLoss};
+pub use network::{
+    Delivery, LinkChange, LinkFaults, NetworkCondition, NetworkSim, PacketLoss, ScheduledCondition,
+    ScheduledLinkChange,
+};
 pub use failure::{FailureModel, FailureKind, CrashInjector};
 pub use node::{SimNode, SimNodeConfig};
 pub use seed::{SimSeed, SeedSource};
-pub use harness::{SimHarness, SimConfig, SimResult};
+pub use harness::{SimHarness, SimConfig, SimResult, MessageOutcome};
 pub use record::{SimRecord, RecordedRun};
+pub use schedule::{check_schedule_determinism, ScheduleDeterminism, ScheduleWorkload};
+pub use scenario::{Scenario, ScenarioNode, NetworkStep, ScenarioEvent, LinkStep, Workload};
@@ -5,7 +5,7 @@ use cathedral_core::NodeId;
 use rand_chacha::ChaCha8Rng;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -55,6 +55,229 @@ impl NetworkCondition {
     }
 }
 
+/// A network condition that takes effect at a tick
+///
+/// Applies to every link from `from` to `to`, where a missing end matches
+/// any node. With both ends missing the condition becomes the default for
+/// links without a condition of their own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledCondition {
+    /// Tick the condition takes effect at
+    pub tick: u64,
+    /// Sending end of the affected links, if restricted
+    pub from: Option<NodeId>,
+    /// Receiving end of the affected links, if restricted
+    pub to: Option<NodeId>,
+    /// Condition to apply
+    pub condition: NetworkCondition,
+}
+
+impl ScheduledCondition {
+    /// Apply `condition` to every link at `tick`
+    #[must_use]
+    pub fn new(tick: u64, condition: NetworkCondition) -> Self {
+        Self {
+            tick,
+            from: None,
+            to: None,
+            condition,
+        }
+    }
+
+    /// Restrict to links sent from `node_id`
+    #[must_use]
+    pub fn with_from(mut self, node_id: NodeId) -> Self {
+        self.from = Some(node_id);
+        self
+    }
+
+    /// Restrict to links received by `node_id`
+    #[must_use]
+    pub fn with_to(mut self, node_id: NodeId) -> Self {
+        self.to = Some(node_id);
+        self
+    }
+
+    /// Check that the condition replaces the default rather than single links
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    /// Links among `nodes` the condition applies to, in `nodes` order
+    #[must_use]
+    pub fn links(&self, nodes: &[NodeId]) -> Vec<(NodeId, NodeId)> {
+        select_links(nodes, self.from, self.to)
+    }
+}
+
+/// Links among `nodes` from `from` to `to`, where a missing end matches any
+/// node
+fn select_links(
+    nodes: &[NodeId],
+    from: Option<NodeId>,
+    to: Option<NodeId>,
+) -> Vec<(NodeId, NodeId)> {
+    nodes
+        .iter()
+        .filter(|sender| from.is_none_or(|id| id == **sender))
+        .flat_map(|sender| {
+            nodes
+                .iter()
+                .filter(move |receiver| {
+                    receiver != &sender && to.is_none_or(|id| id == **receiver)
+                })
+                .map(move |receiver| (*sender, *receiver))
+        })
+        .collect()
+}
+
+/// Reordering and duplication of messages on a link
+///
+/// Both are drawn from the network RNG, so they replay exactly from the
+/// seed. A link without faults draws nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct LinkFaults {
+    /// Probability of holding a message back so later ones overtake it (0-1)
+    pub reorder_probability: f64,
+    /// Most ticks a held back message is delayed by
+    pub max_reorder_ticks: u64,
+    /// Probability of delivering a message twice (0-1)
+    pub duplicate_probability: f64,
+}
+
+impl LinkFaults {
+    /// Create faults that leave messages alone
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold messages back by up to `max_ticks` with `probability`
+    #[must_use]
+    pub fn with_reorder(mut self, probability: f64, max_ticks: u64) -> Self {
+        self.reorder_probability = probability;
+        self.max_reorder_ticks = max_ticks;
+        self
+    }
+
+    /// Deliver messages twice with `probability`
+    #[must_use]
+    pub fn with_duplication(mut self, probability: f64) -> Self {
+        self.duplicate_probability = probability;
+        self
+    }
+
+    /// Check if the faults leave messages alone
+    #[must_use]
+    pub fn is_none(&self) -> bool {
+        self.reorder_probability <= 0.0 && self.duplicate_probability <= 0.0
+    }
+}
+
+/// Change to the links between nodes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum LinkChange {
+    /// Stop messages from crossing the link
+    Cut,
+    /// Let messages cross the link again
+    Heal,
+    /// Set the reordering and duplication of the link
+    Faults(LinkFaults),
+}
+
+/// A link change that takes effect at a tick
+///
+/// Applies to every link from `from` to `to`, where a missing end matches
+/// any node, and to the reverse links as well if `bidirectional` is set.
+/// Faults with both ends missing become the default for links without
+/// faults of their own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledLinkChange {
+    /// Tick the change takes effect at
+    pub tick: u64,
+    /// Sending end of the affected links, if restricted
+    pub from: Option<NodeId>,
+    /// Receiving end of the affected links, if restricted
+    pub to: Option<NodeId>,
+    /// Whether the reverse links change too
+    pub bidirectional: bool,
+    /// Change to make
+    pub change: LinkChange,
+}
+
+impl ScheduledLinkChange {
+    /// Apply `change` to every link at `tick`
+    #[must_use]
+    pub fn new(tick: u64, change: LinkChange) -> Self {
+        Self {
+            tick,
+            from: None,
+            to: None,
+            bidirectional: false,
+            change,
+        }
+    }
+
+    /// Restrict to links sent from `node_id`
+    #[must_use]
+    pub fn with_from(mut self, node_id: NodeId) -> Self {
+        self.from = Some(node_id);
+        self
+    }
+
+    /// Restrict to links received by `node_id`
+    #[must_use]
+    pub fn with_to(mut self, node_id: NodeId) -> Self {
+        self.to = Some(node_id);
+        self
+    }
+
+    /// Change the reverse links too
+    #[must_use]
+    pub fn bidirectional(mut self) -> Self {
+        self.bidirectional = true;
+        self
+    }
+
+    /// Check that the change applies to every link
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    /// Links among `nodes` the change applies to
+    #[must_use]
+    pub fn links(&self, nodes: &[NodeId]) -> Vec<(NodeId, NodeId)> {
+        let mut links = select_links(nodes, self.from, self.to);
+        if self.bidirectional {
+            for link in select_links(nodes, self.to, self.from) {
+                if !links.contains(&link) {
+                    links.push(link);
+                }
+            }
+        }
+        links
+    }
+}
+
+/// A message handed to its receiver by [`NetworkSim::deliver`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Sender
+    pub from: NodeId,
+    /// Receiver
+    pub to: NodeId,
+    /// Message data
+    pub data: Vec<u8>,
+    /// Tick the message was sent at
+    pub sent_at: u64,
+    /// Simulated latency in milliseconds
+    pub latency: u64,
+    /// Whether this is a second copy of the message
+    pub duplicate: bool,
+}
+
 /// Packet loss model
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PacketLoss {
@@ -98,6 +321,16 @@ pub struct NetworkSim {
     default: NetworkCondition,
     /// Partition state
     partitions: Arc<RwLock<HashSet<Vec<NodeId>>>>,
+    /// Links cut in one direction, as (from, to)
+    cut_links: Arc<RwLock<HashSet<(NodeId, NodeId)>>>,
+    /// Reordering and duplication by link
+    faults: Arc<RwLock<HashMap<(NodeId, NodeId), LinkFaults>>>,
+    /// Default reordering and duplication
+    default_faults: LinkFaults,
+    /// Messages in flight, by delivery tick and send order
+    in_flight: BTreeMap<(u64, u64), Delivery>,
+    /// Send order of the next message
+    next_seq: u64,
 }
 
 impl NetworkSim {
@@ -109,6 +342,11 @@ impl NetworkSim {
             conditions: Arc::new(RwLock::new(HashMap::new())),
             default: NetworkCondition::Normal,
             partitions: Arc::new(RwLock::new(HashSet::new())),
+            cut_links: Arc::new(RwLock::new(HashSet::new())),
+            faults: Arc::new(RwLock::new(HashMap::new())),
+            default_faults: LinkFaults::default(),
+            in_flight: BTreeMap::new(),
+            next_seq: 0,
         }
     }
 
@@ -127,6 +365,12 @@ impl NetworkSim {
             .unwrap_or_else(|| self.default.clone())
     }
 
+    /// Remove the condition of a pair of nodes, restoring the default
+    pub async fn clear_condition(&self, from: NodeId, to: NodeId) {
+        let mut conditions = self.conditions.write().await;
+        conditions.remove(&(from, to));
+    }
+
     /// Create a partition (isolated groups)
     pub async fn partition(&self, groups: Vec<Vec<NodeId>>) {
         let mut partitions = self.partitions.write().await;
@@ -142,8 +386,76 @@ impl NetworkSim {
         partitions.clear();
     }
 
+    /// Cut the link from one node to another, leaving the reverse link up
+    pub async fn cut_link(&self, from: NodeId, to: NodeId) {
+        let mut cut_links = self.cut_links.write().await;
+        cut_links.insert((from, to));
+    }
+
+    /// Cut the links between two nodes in both directions
+    pub async fn cut_between(&self, a: NodeId, b: NodeId) {
+        self.cut_link(a, b).await;
+        self.cut_link(b, a).await;
+    }
+
+    /// Heal the link from one node to another
+    pub async fn heal_link(&self, from: NodeId, to: NodeId) {
+        let mut cut_links = self.cut_links.write().await;
+        cut_links.remove(&(from, to));
+    }
+
+    /// Heal all cut links
+    pub async fn heal_links(&self) {
+        let mut cut_links = self.cut_links.write().await;
+        cut_links.clear();
+    }
+
+    /// Check if the link from one node to another is cut
+    pub async fn is_cut(&self, from: NodeId, to: NodeId) -> bool {
+        self.cut_links.read().await.contains(&(from, to))
+    }
+
+    /// Set reordering and duplication for a pair of nodes
+    pub async fn set_faults(&self, from: NodeId, to: NodeId, faults: LinkFaults) {
+        let mut all_faults = self.faults.write().await;
+        all_faults.insert((from, to), faults);
+    }
+
+    /// Get reordering and duplication for a pair of nodes
+    pub async fn get_faults(&self, from: NodeId, to: NodeId) -> LinkFaults {
+        let faults = self.faults.read().await;
+        faults.get(&(from, to)).copied().unwrap_or(self.default_faults)
+    }
+
+    /// Set default reordering and duplication
+    pub fn set_default_faults(&mut self, faults: LinkFaults) {
+        self.default_faults = faults;
+    }
+
+    /// Apply a scheduled link change to the links among `nodes`
+    pub async fn apply_link_change(&mut self, change: &ScheduledLinkChange, nodes: &[NodeId]) {
+        if let LinkChange::Faults(faults) = change.change
+            && change.is_default()
+        {
+            self.default_faults = faults;
+            self.faults.write().await.clear();
+            return;
+        }
+        for (from, to) in change.links(nodes) {
+            match change.change {
+                LinkChange::Cut => self.cut_link(from, to).await,
+                LinkChange::Heal => self.heal_link(from, to).await,
+                LinkChange::Faults(faults) => self.set_faults(from, to, faults).await,
+            }
+        }
+    }
+
     /// Check if two nodes can communicate
     pub async fn can_communicate(&self, from: NodeId, to: NodeId) -> bool {
+        if self.is_cut(from, to).await {
+            return false;
+        }
+
         let partitions = self.partitions.read().await;
 
         // Check if nodes are in different partitions
@@ -179,6 +491,79 @@ impl NetworkSim {
         }
     }
 
+    /// Send a message at `tick`, queueing it for [`NetworkSim::deliver`]
+    ///
+    /// Messages are due at the tick they are sent at unless the link's
+    /// faults hold them back; a duplicate is queued with its own delay.
+    pub async fn transmit(
+        &mut self,
+        tick: u64,
+        from: NodeId,
+        to: NodeId,
+        data: Vec<u8>,
+    ) -> SendResult {
+        let result = self.send(from, to, &data).await;
+        let SendResult::Delivered { latency } = result else {
+            return result;
+        };
+
+        let faults = self.get_faults(from, to).await;
+        let delivery = Delivery {
+            from,
+            to,
+            data,
+            sent_at: tick,
+            latency,
+            duplicate: false,
+        };
+        if faults.duplicate_probability > 0.0
+            && self.rng.r#gen::<f64>() < faults.duplicate_probability
+        {
+            let delay = self.rng.gen_range(0..=faults.max_reorder_ticks);
+            let duplicate = Delivery {
+                duplicate: true,
+                ..delivery.clone()
+            };
+            self.enqueue(tick + delay, duplicate);
+        }
+        let delay = if faults.reorder_probability > 0.0
+            && faults.max_reorder_ticks > 0
+            && self.rng.r#gen::<f64>() < faults.reorder_probability
+        {
+            self.rng.gen_range(1..=faults.max_reorder_ticks)
+        } else {
+            0
+        };
+        self.enqueue(tick + delay, delivery);
+        result
+    }
+
+    fn enqueue(&mut self, due: u64, delivery: Delivery) {
+        self.in_flight.insert((due, self.next_seq), delivery);
+        self.next_seq += 1;
+    }
+
+    /// Take the messages due by `tick`, in delivery order
+    ///
+    /// Messages whose link was cut while they were in flight are lost.
+    pub async fn deliver(&mut self, tick: u64) -> Vec<Delivery> {
+        let pending = self.in_flight.split_off(&(tick + 1, 0));
+        let due = std::mem::replace(&mut self.in_flight, pending);
+        let mut deliveries = Vec::with_capacity(due.len());
+        for delivery in due.into_values() {
+            if self.can_communicate(delivery.from, delivery.to).await {
+                deliveries.push(delivery);
+            }
+        }
+        deliveries
+    }
+
+    /// Get the number of messages in flight
+    #[must_use]
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
     /// Set default condition
     pub fn set_default(&mut self, condition: NetworkCondition) {
         self.default = condition;
@@ -313,6 +698,99 @@ mod tests {
         assert_eq!(result, SendResult::Partitioned);
     }
 
+    #[tokio::test]
+    async fn test_network_sim_cut_link_is_asymmetric() {
+        let mut sim = NetworkSim::new(SimSeed::from_literal(42));
+        let node1 = NodeId::new();
+        let node2 = NodeId::new();
+
+        sim.cut_link(node1, node2).await;
+        assert_eq!(sim.send(node1, node2, b"test").await, SendResult::Partitioned);
+        assert!(matches!(sim.send(node2, node1, b"test").await, SendResult::Delivered { .. }));
+
+        sim.heal_link(node1, node2).await;
+        assert!(sim.can_communicate(node1, node2).await);
+
+        sim.cut_between(node1, node2).await;
+        assert!(!sim.can_communicate(node2, node1).await);
+        sim.heal_links().await;
+        assert!(sim.can_communicate(node2, node1).await);
+    }
+
+    #[tokio::test]
+    async fn test_network_sim_apply_link_change() {
+        let mut sim = NetworkSim::new(SimSeed::from_literal(42));
+        let nodes = [NodeId::new(), NodeId::new(), NodeId::new()];
+
+        let cut = ScheduledLinkChange::new(1, LinkChange::Cut)
+            .with_from(nodes[0])
+            .bidirectional();
+        assert_eq!(cut.links(&nodes).len(), 4);
+        sim.apply_link_change(&cut, &nodes).await;
+        assert!(sim.is_cut(nodes[2], nodes[0]).await);
+        assert!(!sim.is_cut(nodes[1], nodes[2]).await);
+
+        let faults = LinkFaults::new().with_duplication(0.5);
+        sim.apply_link_change(&ScheduledLinkChange::new(2, LinkChange::Faults(faults)), &nodes)
+            .await;
+        assert_eq!(sim.get_faults(nodes[1], nodes[2]).await, faults);
+    }
+
+    #[tokio::test]
+    async fn test_network_sim_transmit_in_order_without_faults() {
+        let mut sim = NetworkSim::new(SimSeed::from_literal(42));
+        let node1 = NodeId::new();
+        let node2 = NodeId::new();
+
+        for i in 0..5u8 {
+            sim.transmit(1, node1, node2, vec![i]).await;
+        }
+        assert_eq!(sim.deliver(0).await, Vec::new());
+        let data: Vec<u8> = sim.deliver(1).await.iter().map(|d| d.data[0]).collect();
+        assert_eq!(data, vec![0, 1, 2, 3, 4]);
+        assert_eq!(sim.in_flight(), 0);
+    }
+
+    async fn faulty_run(seed: u64) -> Vec<Delivery> {
+        let mut sim = NetworkSim::new(SimSeed::from_literal(seed));
+        sim.set_default_faults(LinkFaults::new().with_reorder(0.5, 3).with_duplication(0.3));
+        let node1 = NodeId::from_bytes([1; 16]);
+        let node2 = NodeId::from_bytes([2; 16]);
+
+        let mut deliveries = Vec::new();
+        for tick in 1..=20u64 {
+            sim.transmit(tick, node1, node2, tick.to_le_bytes().to_vec()).await;
+            deliveries.extend(sim.deliver(tick).await);
+        }
+        deliveries.extend(sim.deliver(u64::MAX - 1).await);
+        deliveries
+    }
+
+    #[tokio::test]
+    async fn test_network_sim_reorder_and_duplicate_are_seeded() {
+        let deliveries = faulty_run(7).await;
+        assert!(deliveries.iter().any(|d| d.duplicate));
+        assert_eq!(deliveries.iter().filter(|d| !d.duplicate).count(), 20);
+        let sent: Vec<u64> =
+            deliveries.iter().filter(|d| !d.duplicate).map(|d| d.sent_at).collect();
+        assert!(sent.windows(2).any(|pair| pair[0] > pair[1]));
+
+        assert_eq!(faulty_run(7).await, deliveries);
+    }
+
+    #[tokio::test]
+    async fn test_network_sim_cut_drops_in_flight() {
+        let mut sim = NetworkSim::new(SimSeed::from_literal(42));
+        sim.set_default_faults(LinkFaults::new().with_reorder(1.0, 2));
+        let node1 = NodeId::new();
+        let node2 = NodeId::new();
+
+        sim.transmit(1, node1, node2, b"test".to_vec()).await;
+        assert_eq!(sim.in_flight(), 1);
+        sim.cut_link(node1, node2).await;
+        assert!(sim.deliver(10).await.is_empty());
+    }
+
     #[test]
     fn test_send_result_equality() {
         assert_eq!(
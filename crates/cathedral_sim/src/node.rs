@@ -96,6 +96,12 @@ impl SimNode {
         self.config.node_id
     }
 
+    /// Get node configuration
+    #[must_use]
+    pub fn config(&self) -> &SimNodeConfig {
+        &self.config
+    }
+
     /// Get current state
     pub async fn state(&self) -> SimNodeState {
         *self.state.read().await
@@ -0,0 +1,705 @@
+//! Simulation scenarios loaded from files.
+//!
+//! A scenario describes a whole simulation declaratively: the nodes, the
+//! network conditions that change at given ticks, links cut and healed
+//! between nodes, scheduled crashes, partitions and latency spikes, and a
+//! message workload. Scenarios are
+//! written in TOML (or JSON) and compiled into a [`SimHarness`]:
+//!
+//! ```toml
+//! name = "leader-crash"
+//! description = "Crash one node while the network is lossy"
+//! seed = 7
+//! ticks = 50
+//!
+//! [[nodes]]
+//! name = "a"
+//!
+//! [[nodes]]
+//! name = "b"
+//! tick_rate = 50
+//!
+//! [[network]]
+//! tick = 10
+//! condition = "packet_loss"
+//! probability = 0.2
+//!
+//! [[network]]
+//! tick = 12
+//! from = "a"
+//! to = "b"
+//! condition = "latency"
+//! ms = 150
+//!
+//! [[links]]
+//! tick = 15
+//! from = "a"
+//! to = "b"
+//! action = "cut"
+//! duration = 10
+//!
+//! [[links]]
+//! tick = 15
+//! action = "faults"
+//! reorder_probability = 0.1
+//! max_reorder_ticks = 3
+//! duplicate_probability = 0.05
+//!
+//! [[events]]
+//! tick = 20
+//! node = "b"
+//! kind = "crash"
+//! duration = 5
+//!
+//! [workload]
+//! messages_per_tick = 2
+//! payload_bytes = 128
+//! ```
+//!
+//! Link cuts are one-way unless `bidirectional = true`, so `a` above can
+//! still hear from `b` while its own messages are lost.
+//!
+//! Nodes are referred to by name; their IDs are derived from the scenario
+//! seed, so the same file always produces the same simulation.
+//!
+//! [`SimHarness`]: crate::harness::SimHarness
+
+use crate::failure::{FailureKind, FailureScenario, ScheduledFailure};
+use crate::harness::SimConfig;
+use crate::network::{
+    LinkChange, LinkFaults, NetworkCondition, ScheduledCondition, ScheduledLinkChange,
+};
+use crate::node::SimNodeConfig;
+use crate::seed::SimSeed;
+use cathedral_core::{CoreError, CoreResult, NodeId};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// A simulation scenario
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Scenario name
+    pub name: String,
+    /// Description
+    #[serde(default)]
+    pub description: String,
+    /// Base seed of the simulation
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+    /// Number of ticks to run
+    pub ticks: u64,
+    /// Simulated nodes
+    pub nodes: Vec<ScenarioNode>,
+    /// Network conditions by tick
+    #[serde(default)]
+    pub network: Vec<NetworkStep>,
+    /// Link cuts, heals and faults by tick
+    #[serde(default)]
+    pub links: Vec<LinkStep>,
+    /// Scheduled failures
+    #[serde(default)]
+    pub events: Vec<ScenarioEvent>,
+    /// Messages exchanged between nodes each tick
+    #[serde(default)]
+    pub workload: Option<Workload>,
+}
+
+fn default_seed() -> u64 {
+    SimSeed::default().seed
+}
+
+/// A node in a scenario
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioNode {
+    /// Name the rest of the scenario refers to the node by
+    pub name: String,
+    /// Ticks per second
+    #[serde(default = "default_tick_rate")]
+    pub tick_rate: u64,
+}
+
+fn default_tick_rate() -> u64 {
+    SimNodeConfig::new(NodeId::from_bytes([0; 16])).tick_rate
+}
+
+/// A network condition change in a scenario
+///
+/// Without `from` and `to` the condition becomes the default for all links.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkStep {
+    /// Tick the condition takes effect at
+    pub tick: u64,
+    /// Sending node, if restricted
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Receiving node, if restricted
+    #[serde(default)]
+    pub to: Option<String>,
+    /// Condition to apply
+    #[serde(flatten)]
+    pub condition: ConditionSpec,
+}
+
+/// Network condition as written in a scenario
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "condition", rename_all = "snake_case")]
+pub enum ConditionSpec {
+    /// Normal network
+    Normal,
+    /// Fixed latency in milliseconds
+    Latency {
+        /// Latency in milliseconds
+        ms: u64,
+    },
+    /// Random packet loss
+    PacketLoss {
+        /// Probability of dropping a message (0-1)
+        probability: f64,
+    },
+    /// Bandwidth limit
+    BandwidthLimit {
+        /// Bytes per second
+        bytes_per_sec: usize,
+    },
+}
+
+impl ConditionSpec {
+    /// Convert to the simulator's network condition
+    #[must_use]
+    pub fn to_condition(&self) -> NetworkCondition {
+        match self {
+            Self::Normal => NetworkCondition::Normal,
+            Self::Latency { ms } => NetworkCondition::Latency(*ms),
+            Self::PacketLoss { probability } => NetworkCondition::PacketLoss {
+                probability: *probability,
+            },
+            Self::BandwidthLimit { bytes_per_sec } => NetworkCondition::BandwidthLimit {
+                bytes_per_sec: *bytes_per_sec,
+            },
+        }
+    }
+}
+
+/// A link change in a scenario
+///
+/// Without `from` and `to` the change applies to every link.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkStep {
+    /// Tick the change takes effect at
+    pub tick: u64,
+    /// Sending node, if restricted
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Receiving node, if restricted
+    #[serde(default)]
+    pub to: Option<String>,
+    /// Whether the reverse links change too
+    #[serde(default)]
+    pub bidirectional: bool,
+    /// Ticks until the change is undone (0 = permanent)
+    #[serde(default)]
+    pub duration: u64,
+    /// Change to make
+    #[serde(flatten)]
+    pub action: LinkAction,
+}
+
+/// Link changes available to scenarios
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum LinkAction {
+    /// Stop messages from crossing the links
+    Cut,
+    /// Let messages cross the links again
+    Heal,
+    /// Reorder and duplicate messages on the links
+    Faults {
+        /// Probability of holding a message back (0-1)
+        #[serde(default)]
+        reorder_probability: f64,
+        /// Most ticks a held back message is delayed by
+        #[serde(default)]
+        max_reorder_ticks: u64,
+        /// Probability of delivering a message twice (0-1)
+        #[serde(default)]
+        duplicate_probability: f64,
+    },
+}
+
+impl LinkAction {
+    /// Convert to the simulator's link change
+    #[must_use]
+    pub fn to_link_change(&self) -> LinkChange {
+        match self {
+            Self::Cut => LinkChange::Cut,
+            Self::Heal => LinkChange::Heal,
+            Self::Faults {
+                reorder_probability,
+                max_reorder_ticks,
+                duplicate_probability,
+            } => LinkChange::Faults(
+                LinkFaults::new()
+                    .with_reorder(*reorder_probability, *max_reorder_ticks)
+                    .with_duplication(*duplicate_probability),
+            ),
+        }
+    }
+
+    /// Get the change undoing this one once its duration elapses
+    #[must_use]
+    pub fn undo(&self) -> LinkChange {
+        match self {
+            Self::Cut => LinkChange::Heal,
+            Self::Heal => LinkChange::Cut,
+            Self::Faults { .. } => LinkChange::Faults(LinkFaults::new()),
+        }
+    }
+}
+
+/// A scheduled failure in a scenario
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScenarioEvent {
+    /// Tick the failure starts at
+    pub tick: u64,
+    /// Node to fail
+    pub node: String,
+    /// Ticks until the failure is lifted (0 = permanent)
+    #[serde(default)]
+    pub duration: u64,
+    /// Kind of failure
+    #[serde(flatten)]
+    pub action: EventAction,
+}
+
+/// Failure kinds available to scenarios
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventAction {
+    /// Crash the node
+    Crash,
+    /// Partition the node from the cluster
+    Partition,
+    /// Add latency to every link of the node
+    Latency {
+        /// Latency in milliseconds
+        ms: u64,
+    },
+}
+
+impl EventAction {
+    /// Convert to the simulator's failure kind
+    #[must_use]
+    pub fn to_failure_kind(self) -> FailureKind {
+        match self {
+            Self::Crash => FailureKind::Crash,
+            Self::Partition => FailureKind::Partition,
+            Self::Latency { ms } => FailureKind::HighLatency { ms },
+        }
+    }
+}
+
+/// Messages exchanged between nodes each tick
+///
+/// Every running node sends `messages_per_tick` messages to peers picked
+/// from a seeded RNG; each message goes through the network simulator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workload {
+    /// Messages each running node sends per tick
+    #[serde(default = "default_messages_per_tick")]
+    pub messages_per_tick: u32,
+    /// Size of each message payload
+    #[serde(default)]
+    pub payload_bytes: usize,
+}
+
+fn default_messages_per_tick() -> u32 {
+    1
+}
+
+impl Scenario {
+    /// Parse and validate a TOML scenario
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the document is malformed or fails validation
+    pub fn from_toml(source: &str) -> CoreResult<Self> {
+        let scenario: Self = toml::from_str(source).map_err(|e| CoreError::ParseError {
+            message: format!("scenario: {}", e),
+        })?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Parse and validate a JSON scenario
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the document is malformed or fails validation
+    pub fn from_json(source: &str) -> CoreResult<Self> {
+        let scenario: Self = serde_json::from_str(source)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Load a scenario file, as JSON for `.json` files and TOML otherwise
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read, parsed or validated
+    pub fn load(path: &Path) -> CoreResult<Self> {
+        let source = std::fs::read_to_string(path).map_err(|e| CoreError::Validation {
+            field: "scenario".to_string(),
+            reason: format!("{}: {}", path.display(), e),
+        })?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&source)
+        } else {
+            Self::from_toml(&source)
+        }
+    }
+
+    /// Check that the scenario is runnable
+    ///
+    /// # Errors
+    ///
+    /// Returns error naming the first invalid field
+    pub fn validate(&self) -> CoreResult<()> {
+        if self.ticks == 0 {
+            return Err(invalid("ticks", "must be at least 1".to_string()));
+        }
+        if self.nodes.is_empty() {
+            return Err(invalid("nodes", "scenario has no nodes".to_string()));
+        }
+
+        let mut names = HashSet::new();
+        for node in &self.nodes {
+            if node.name.is_empty() {
+                return Err(invalid("nodes", "node name is empty".to_string()));
+            }
+            if !names.insert(node.name.as_str()) {
+                return Err(invalid("nodes", format!("duplicate node '{}'", node.name)));
+            }
+        }
+        let known = |field: &str, name: &str| {
+            if names.contains(name) {
+                Ok(())
+            } else {
+                Err(invalid(field, format!("unknown node '{}'", name)))
+            }
+        };
+
+        for step in &self.network {
+            self.check_tick("network", step.tick)?;
+            for name in step.from.iter().chain(step.to.iter()) {
+                known("network", name)?;
+            }
+            if let ConditionSpec::PacketLoss { probability } = step.condition
+                && !(0.0..=1.0).contains(&probability)
+            {
+                return Err(invalid(
+                    "network",
+                    format!("packet loss probability {} is outside 0-1", probability),
+                ));
+            }
+        }
+
+        for step in &self.links {
+            self.check_tick("links", step.tick)?;
+            for name in step.from.iter().chain(step.to.iter()) {
+                known("links", name)?;
+            }
+            if let LinkAction::Faults {
+                reorder_probability,
+                max_reorder_ticks,
+                duplicate_probability,
+            } = step.action
+            {
+                for probability in [reorder_probability, duplicate_probability] {
+                    if !(0.0..=1.0).contains(&probability) {
+                        return Err(invalid(
+                            "links",
+                            format!("fault probability {} is outside 0-1", probability),
+                        ));
+                    }
+                }
+                if reorder_probability > 0.0 && max_reorder_ticks == 0 {
+                    return Err(invalid(
+                        "links",
+                        "reordering needs max_reorder_ticks of at least 1".to_string(),
+                    ));
+                }
+            }
+        }
+
+        for event in &self.events {
+            self.check_tick("events", event.tick)?;
+            known("events", &event.node)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_tick(&self, field: &str, tick: u64) -> CoreResult<()> {
+        if tick == 0 || tick > self.ticks {
+            return Err(invalid(
+                field,
+                format!("tick {} is outside 1-{}", tick, self.ticks),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Get the ID of a node, derived from the seed and the node name
+    #[must_use]
+    pub fn node_id(&self, name: &str) -> NodeId {
+        let seed = SimSeed::from_literal(self.seed).derive(&format!("node:{}", name));
+        NodeId::from_bytes(seed.rng().r#gen())
+    }
+
+    /// Get the name of a node by ID
+    #[must_use]
+    pub fn node_name(&self, node_id: NodeId) -> Option<&str> {
+        self.nodes
+            .iter()
+            .find(|node| self.node_id(&node.name) == node_id)
+            .map(|node| node.name.as_str())
+    }
+
+    /// Get the simulation config
+    #[must_use]
+    pub fn config(&self) -> SimConfig {
+        SimConfig::new(SimSeed::from_literal(self.seed)).with_max_ticks(self.ticks)
+    }
+
+    /// Get the node configurations, in scenario order
+    #[must_use]
+    pub fn node_configs(&self) -> Vec<SimNodeConfig> {
+        self.nodes
+            .iter()
+            .map(|node| SimNodeConfig::new(self.node_id(&node.name)).with_tick_rate(node.tick_rate))
+            .collect()
+    }
+
+    /// Compile the scheduled failures
+    #[must_use]
+    pub fn failure_scenario(&self) -> FailureScenario {
+        let mut scenario = FailureScenario::new(self.name.clone(), self.description.clone());
+        for event in &self.events {
+            let failure =
+                ScheduledFailure::new(self.node_id(&event.node), event.action.to_failure_kind())
+                    .with_duration(event.duration);
+            scenario.schedule = scenario.schedule.add_failure(event.tick, failure);
+        }
+        scenario
+    }
+
+    /// Compile the network conditions, in scenario order
+    #[must_use]
+    pub fn network_schedule(&self) -> Vec<ScheduledCondition> {
+        self.network
+            .iter()
+            .map(|step| {
+                let condition = step.condition.to_condition();
+                let mut scheduled = ScheduledCondition::new(step.tick, condition);
+                if let Some(from) = &step.from {
+                    scheduled = scheduled.with_from(self.node_id(from));
+                }
+                if let Some(to) = &step.to {
+                    scheduled = scheduled.with_to(self.node_id(to));
+                }
+                scheduled
+            })
+            .collect()
+    }
+
+    /// Compile the link changes, each followed by its undoing if it has a
+    /// duration
+    #[must_use]
+    pub fn link_schedule(&self) -> Vec<ScheduledLinkChange> {
+        let mut schedule = Vec::new();
+        for step in &self.links {
+            let scoped = |tick, change| {
+                let mut scheduled = ScheduledLinkChange::new(tick, change);
+                if let Some(from) = &step.from {
+                    scheduled = scheduled.with_from(self.node_id(from));
+                }
+                if let Some(to) = &step.to {
+                    scheduled = scheduled.with_to(self.node_id(to));
+                }
+                if step.bidirectional {
+                    scheduled = scheduled.bidirectional();
+                }
+                scheduled
+            };
+            schedule.push(scoped(step.tick, step.action.to_link_change()));
+            if step.duration > 0 {
+                schedule.push(scoped(step.tick + step.duration, step.action.undo()));
+            }
+        }
+        schedule
+    }
+}
+
+fn invalid(field: &str, reason: String) -> CoreError {
+    CoreError::Validation {
+        field: format!("scenario.{}", field),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"
+        name = "crash"
+        seed = 7
+        ticks = 30
+
+        [[nodes]]
+        name = "a"
+
+        [[nodes]]
+        name = "b"
+        tick_rate = 50
+
+        [[network]]
+        tick = 5
+        condition = "packet_loss"
+        probability = 0.5
+
+        [[network]]
+        tick = 8
+        from = "a"
+        condition = "latency"
+        ms = 150
+
+        [[events]]
+        tick = 10
+        node = "b"
+        kind = "crash"
+        duration = 4
+
+        [[events]]
+        tick = 12
+        node = "a"
+        kind = "latency"
+        ms = 300
+
+        [workload]
+        messages_per_tick = 2
+    "#;
+
+    #[test]
+    fn test_scenario_from_toml() {
+        let scenario = Scenario::from_toml(SCENARIO).unwrap();
+        assert_eq!(scenario.name, "crash");
+        assert_eq!(scenario.seed, 7);
+        assert_eq!(scenario.nodes[0].tick_rate, 100);
+        assert_eq!(scenario.nodes[1].tick_rate, 50);
+        assert_eq!(
+            scenario.network[0].condition,
+            ConditionSpec::PacketLoss { probability: 0.5 }
+        );
+        assert_eq!(scenario.events[1].action, EventAction::Latency { ms: 300 });
+        assert_eq!(
+            scenario.workload,
+            Some(Workload { messages_per_tick: 2, payload_bytes: 0 })
+        );
+    }
+
+    #[test]
+    fn test_scenario_json_matches_toml() {
+        let scenario = Scenario::from_toml(SCENARIO).unwrap();
+        let json = serde_json::to_string(&scenario).unwrap();
+        assert_eq!(Scenario::from_json(&json).unwrap(), scenario);
+    }
+
+    #[test]
+    fn test_scenario_compiles() {
+        let scenario = Scenario::from_toml(SCENARIO).unwrap();
+        let a = scenario.node_id("a");
+        let b = scenario.node_id("b");
+        assert_ne!(a, b);
+        assert_eq!(Scenario::from_toml(SCENARIO).unwrap().node_id("a"), a);
+        assert_eq!(scenario.node_name(b), Some("b"));
+
+        let config = scenario.config();
+        assert_eq!(config.seed.seed, 7);
+        assert_eq!(config.max_ticks, 30);
+
+        let failures = scenario.failure_scenario();
+        let crash = &failures.schedule.get_failures(10)[0];
+        assert_eq!(crash.node_id, b);
+        assert_eq!(crash.duration_ticks, 4);
+
+        let network = scenario.network_schedule();
+        assert!(network[0].is_default());
+        assert_eq!(network[1].from, Some(a));
+        assert_eq!(network[1].links(&[a, b]), vec![(a, b)]);
+    }
+
+    #[test]
+    fn test_scenario_link_schedule() {
+        let source = format!(
+            "{}{}",
+            SCENARIO,
+            r#"
+            [[links]]
+            tick = 4
+            from = "a"
+            to = "b"
+            bidirectional = true
+            action = "cut"
+            duration = 6
+
+            [[links]]
+            tick = 5
+            action = "faults"
+            reorder_probability = 0.25
+            max_reorder_ticks = 2
+            "#
+        );
+        let scenario = Scenario::from_toml(&source).unwrap();
+        let a = scenario.node_id("a");
+        let b = scenario.node_id("b");
+
+        let schedule = scenario.link_schedule();
+        assert_eq!(schedule.len(), 3);
+        assert_eq!(schedule[0].change, LinkChange::Cut);
+        assert_eq!(schedule[0].links(&[a, b]), vec![(a, b), (b, a)]);
+        assert_eq!((schedule[1].tick, &schedule[1].change), (10, &LinkChange::Heal));
+        assert!(schedule[2].is_default());
+        assert_eq!(
+            schedule[2].change,
+            LinkChange::Faults(LinkFaults::new().with_reorder(0.25, 2))
+        );
+
+        let bad = source.replace("max_reorder_ticks = 2", "max_reorder_ticks = 0");
+        assert!(Scenario::from_toml(&bad).is_err());
+    }
+
+    #[test]
+    fn test_scenario_validation() {
+        let unknown = SCENARIO.replace("node = \"b\"", "node = \"c\"");
+        let err = Scenario::from_toml(&unknown).unwrap_err();
+        assert!(err.to_string().contains("unknown node 'c'"));
+
+        let late = SCENARIO.replace("tick = 10", "tick = 31");
+        assert!(Scenario::from_toml(&late).is_err());
+
+        let lossy = SCENARIO.replace("probability = 0.5", "probability = 1.5");
+        assert!(Scenario::from_toml(&lossy).is_err());
+
+        let duplicate = SCENARIO.replace("name = \"b\"", "name = \"a\"");
+        assert!(Scenario::from_toml(&duplicate).is_err());
+
+        assert!(Scenario::from_toml("name = \"x\"\nticks = 1\nnodes = []").is_err());
+        assert!(Scenario::from_toml("name = \"x\"\nticks = 1\nbogus = 1").is_err());
+    }
+}
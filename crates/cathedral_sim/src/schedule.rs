@@ -0,0 +1,171 @@
+//! Scheduler determinism harness.
+//!
+//! Generates a DAG from a seed and runs the runtime scheduler over it many
+//! times. Each run adds the nodes in a different order, drawn from a
+//! seed derived per run and routed through a fresh `HashMap`, so neither
+//! insertion order nor hash iteration order may change the schedule.
+
+use crate::seed::SimSeed;
+use cathedral_core::{CoreResult, NodeId};
+use cathedral_runtime::{ScheduleDecision, Scheduler};
+use indexmap::IndexSet;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Highest priority a generated node is given; kept low so ties are common
+const MAX_PRIORITY: u64 = 2;
+
+/// A node of a generated workload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkloadNode {
+    /// Node identifier
+    pub id: NodeId,
+    /// Nodes this one depends on
+    pub dependencies: Vec<NodeId>,
+    /// Scheduling priority
+    pub priority: u64,
+}
+
+/// A seeded random DAG for exercising the scheduler
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleWorkload {
+    /// Nodes in generation order; dependencies always point backwards
+    pub nodes: Vec<WorkloadNode>,
+}
+
+impl ScheduleWorkload {
+    /// Generate a workload of `node_count` nodes from a seed
+    #[must_use]
+    pub fn generate(seed: &SimSeed, node_count: usize) -> Self {
+        let mut rng = seed.derive("workload").rng();
+        let mut nodes: Vec<WorkloadNode> = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let dependencies = nodes
+                .iter()
+                .filter(|_| rng.gen_bool(0.2))
+                .map(|node| node.id)
+                .collect();
+            nodes.push(WorkloadNode {
+                id: NodeId::from_bytes(rng.r#gen()),
+                dependencies,
+                priority: rng.gen_range(0..=MAX_PRIORITY),
+            });
+        }
+        Self { nodes }
+    }
+
+    /// Schedule the workload, adding nodes in an order drawn from `order`
+    ///
+    /// Returns the nodes in the order the scheduler ran them.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the scheduler rejects a node
+    pub fn schedule(&self, order: &SimSeed) -> CoreResult<Vec<NodeId>> {
+        let mut rng = order.rng();
+        let mut nodes: Vec<&WorkloadNode> = self.nodes.iter().collect();
+        nodes.shuffle(&mut rng);
+        let by_id: HashMap<NodeId, &WorkloadNode> =
+            nodes.into_iter().map(|node| (node.id, node)).collect();
+
+        let mut scheduler = Scheduler::new();
+        for (id, node) in by_id {
+            let mut deps = node.dependencies.clone();
+            deps.shuffle(&mut rng);
+            let deps: IndexSet<NodeId> = deps.into_iter().collect();
+            scheduler.add_node_with_priority(id, deps, node.priority)?;
+        }
+
+        let mut schedule = Vec::with_capacity(self.nodes.len());
+        while let ScheduleDecision::Run(node_id) = scheduler.decide() {
+            scheduler.mark_complete(node_id)?;
+            schedule.push(node_id);
+        }
+        Ok(schedule)
+    }
+}
+
+/// Outcome of running the scheduler repeatedly over one workload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleDeterminism {
+    /// Number of runs
+    pub runs: usize,
+    /// Schedule of the first run
+    pub schedule: Vec<NodeId>,
+    /// First run whose schedule differed from the first run's
+    pub divergent_run: Option<usize>,
+}
+
+impl ScheduleDeterminism {
+    /// Check whether every run produced the same schedule
+    #[must_use]
+    pub fn is_deterministic(&self) -> bool {
+        self.divergent_run.is_none()
+    }
+}
+
+/// Schedule a seeded workload `runs` times and compare the schedules
+///
+/// # Errors
+///
+/// Returns error if the scheduler rejects a node
+pub fn check_schedule_determinism(
+    seed: &SimSeed,
+    node_count: usize,
+    runs: usize,
+) -> CoreResult<ScheduleDeterminism> {
+    let workload = ScheduleWorkload::generate(seed, node_count);
+    let mut schedule = None;
+    let mut divergent_run = None;
+    for run in 0..runs {
+        let current = workload.schedule(&seed.derive(&format!("order/{run}")))?;
+        match &schedule {
+            None => schedule = Some(current),
+            Some(first) if divergent_run.is_none() && *first != current => {
+                divergent_run = Some(run);
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(ScheduleDeterminism {
+        runs,
+        schedule: schedule.unwrap_or_default(),
+        divergent_run,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_identical_across_runs() {
+        for seed in [1, 42, 2024] {
+            let seed = SimSeed::from_literal(seed);
+            let report = check_schedule_determinism(&seed, 40, 100).unwrap();
+            assert!(report.is_deterministic(), "diverged at run {:?}", report.divergent_run);
+            assert_eq!(report.schedule.len(), 40);
+
+            // Every node runs after its dependencies
+            let workload = ScheduleWorkload::generate(&seed, 40);
+            for node in &workload.nodes {
+                let position = |id| report.schedule.iter().position(|n| *n == id).unwrap();
+                for dep in &node.dependencies {
+                    assert!(position(*dep) < position(node.id));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_workload_generation_is_seeded() {
+        let seed = SimSeed::from_literal(7);
+        assert_eq!(ScheduleWorkload::generate(&seed, 10), ScheduleWorkload::generate(&seed, 10));
+        assert_ne!(
+            ScheduleWorkload::generate(&seed, 10),
+            ScheduleWorkload::generate(&SimSeed::from_literal(8), 10)
+        );
+    }
+}
@@ -3,8 +3,14 @@
 use cathedral_core::{Hash, CoreResult, CoreError};
 use serde::{Deserialize, Serialize};
 
+pub use cathedral_core::AddressAlgorithm;
+
 /// Content address combining hash and algorithm
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+///
+/// Encoded like [`cathedral_core::ContentAddress`]: the `algorithm:hex`
+/// string in human-readable formats, which also accept the older
+/// `{hash, algorithm}` struct, and the struct in binary formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ContentAddress {
     /// Hash of the content
     pub hash: Hash,
@@ -22,12 +28,24 @@ impl ContentAddress {
     /// Compute content address for data using default algorithm
     #[must_use]
     pub fn compute(data: &[u8]) -> Self {
+        Self::compute_with(data, AddressAlgorithm::Blake3)
+    }
+
+    /// Compute content address for data using `algorithm`
+    #[must_use]
+    pub fn compute_with(data: &[u8], algorithm: AddressAlgorithm) -> Self {
         Self {
-            hash: Hash::compute(data),
-            algorithm: AddressAlgorithm::Blake3,
+            hash: algorithm.hash(data),
+            algorithm,
         }
     }
 
+    /// Check if the address matches data under its own algorithm
+    #[must_use]
+    pub fn verify(&self, data: &[u8]) -> bool {
+        self.algorithm.hash(data) == self.hash
+    }
+
     /// Parse from string representation
     ///
     /// # Errors
@@ -70,83 +88,28 @@ impl ContentAddress {
     }
 }
 
-/// Address algorithm for content hashing
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
-pub enum AddressAlgorithm {
-    /// BLAKE3 (default)
-    Blake3,
-    /// SHA-256
-    Sha256,
-    /// SHA-512
-    Sha512,
-}
-
-impl AddressAlgorithm {
-    /// Parse algorithm from string
-    ///
-    /// # Errors
-    ///
-    /// Returns error if algorithm is unknown
-    pub fn parse(s: &str) -> CoreResult<Self> {
-        match s {
-            "blake3" => Ok(Self::Blake3),
-            "sha256" => Ok(Self::Sha256),
-            "sha512" => Ok(Self::Sha512),
-            _ => Err(CoreError::Validation {
-                field: "algorithm".to_string(),
-                reason: format!("Unknown algorithm: {}", s),
-            }),
-        }
-    }
-
-    /// Get string representation
-    #[must_use]
-    pub const fn as_str(&self) -> &str {
-        match self {
-            Self::Blake3 => "blake3",
-            Self::Sha256 => "sha256",
-            Self::Sha512 => "sha512",
-        }
-    }
-
-    /// Get default algorithm
-    #[must_use]
-    pub const fn default() -> Self {
-        Self::Blake3
-    }
-
-    /// Compute hash of data using this algorithm
-    #[must_use]
-    pub fn hash(&self, data: &[u8]) -> Hash {
-        match self {
-            Self::Blake3 => Hash::compute(data),
-            Self::Sha256 => {
-                use sha2::Digest;
-                let hasher = sha2::Sha256::new();
-                let result = hasher.chain_update(data).finalize();
-                Hash::from_bytes(result.into())
-            }
-            Self::Sha512 => {
-                use sha2::Digest;
-                let hasher = sha2::Sha512::new();
-                let result = hasher.chain_update(data).finalize();
-                let mut bytes = [0u8; 32];
-                bytes.copy_from_slice(&result[..32]);
-                Hash::from_bytes(bytes)
-            }
-        }
+impl std::fmt::Display for ContentAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm.as_str(), self.hash.to_hex())
     }
 }
 
-impl Default for AddressAlgorithm {
-    fn default() -> Self {
-        Self::default()
+impl Serialize for ContentAddress {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        cathedral_core::ContentAddress::new(self.hash, self.algorithm).serialize(serializer)
     }
 }
 
-impl std::fmt::Display for ContentAddress {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}", self.algorithm.as_str(), self.hash.to_hex())
+impl<'de> Deserialize<'de> for ContentAddress {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let address = cathedral_core::ContentAddress::deserialize(deserializer)?;
+        Ok(Self::new(address.hash, address.algorithm))
     }
 }
 
@@ -200,6 +163,33 @@ mod tests {
         assert_eq!(AddressAlgorithm::Sha512.as_str(), "sha512");
     }
 
+    #[test]
+    fn test_content_address_mixed_algorithms() {
+        let data = b"mixed content";
+        for algorithm in [AddressAlgorithm::Blake3, AddressAlgorithm::Sha256, AddressAlgorithm::Sha512] {
+            let addr = ContentAddress::compute_with(data, algorithm);
+            assert_eq!(addr.algorithm(), algorithm);
+            assert!(addr.verify(data));
+            assert_eq!(ContentAddress::parse(&addr.as_str()).unwrap(), addr);
+
+            let json = serde_json::to_string(&addr).unwrap();
+            assert_eq!(serde_json::from_str::<ContentAddress>(&json).unwrap(), addr);
+        }
+
+        let sha256 = ContentAddress::compute_with(data, AddressAlgorithm::Sha256);
+        let relabeled = ContentAddress::new(sha256.hash, AddressAlgorithm::Blake3);
+        assert!(!relabeled.verify(data));
+        assert!(serde_json::from_str::<ContentAddress>(&format!("\"md5:{}\"", sha256.hash)).is_err());
+    }
+
+    #[test]
+    fn test_content_address_reads_struct_form() {
+        let addr = ContentAddress::compute_with(b"old", AddressAlgorithm::Sha256);
+        let old = serde_json::json!({ "hash": addr.hash, "algorithm": "Sha256" });
+        assert_eq!(serde_json::from_value::<ContentAddress>(old).unwrap(), addr);
+        assert_eq!(serde_json::to_value(addr).unwrap(), serde_json::json!(addr.as_str()));
+    }
+
     #[test]
     fn test_address_algorithm_hash() {
         let data = b"test data";
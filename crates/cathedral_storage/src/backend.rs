@@ -0,0 +1,646 @@
+//! Pluggable storage backends for sharing blobs and snapshots.
+//!
+//! A [`StorageBackend`] is a flat key/value object store: the local
+//! filesystem ([`LocalBackend`]) or an S3-compatible bucket
+//! ([`S3Backend`](crate::s3::S3Backend)). A [`RemoteStore`] moves blobs,
+//! snapshots and replay bundles between a [`ContentStore`] and a backend,
+//! retrying failed transfers, rehashing everything it fetches against the
+//! expected address, and refusing uploads to network backends unless the
+//! caller holds a matching `NetWrite` capability. A [`TenantBackend`] gives
+//! each tenant its own namespace of a shared backend.
+
+use crate::address::ContentAddress;
+use crate::blob::{BlobData, BlobId};
+use crate::snapshot::{Snapshot, SnapshotStore};
+use crate::store::ContentStore;
+use cathedral_core::{CapabilitySet, CoreError, CoreResult, TenantId};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Key prefix for blobs
+const BLOB_PREFIX: &str = "blobs";
+/// Key prefix for snapshots
+const SNAPSHOT_PREFIX: &str = "snapshots";
+/// Key prefix for replay bundles
+const BUNDLE_PREFIX: &str = "bundles";
+/// Key prefix for tenant namespaces
+const TENANT_PREFIX: &str = "tenants";
+/// Suffix of files a [`LocalBackend`] is still writing
+const PARTIAL_SUFFIX: &str = ".partial";
+
+/// A key/value object store
+pub trait StorageBackend: Send + Sync {
+    /// Human-readable location, e.g. `file:///data` or `s3://bucket`
+    fn location(&self) -> String;
+
+    /// Network host uploads go to, or `None` for local backends
+    fn host(&self) -> Option<String>;
+
+    /// Store `data` under `key`, replacing any existing object
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the object cannot be written
+    fn put(&self, key: &str, data: &[u8]) -> CoreResult<()>;
+
+    /// Fetch the object under `key`, or `None` if there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the object cannot be read
+    fn get(&self, key: &str) -> CoreResult<Option<Vec<u8>>>;
+
+    /// Delete the object under `key`, returning whether it existed
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the object cannot be deleted
+    fn delete(&self, key: &str) -> CoreResult<bool>;
+
+    /// Keys starting with `prefix`, sorted
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the listing fails
+    fn list(&self, prefix: &str) -> CoreResult<Vec<String>>;
+}
+
+/// Check that a key is a relative, `/`-separated path without `..`
+///
+/// # Errors
+///
+/// Returns error if the key is empty, absolute, or escapes its root
+pub fn validate_key(key: &str) -> CoreResult<()> {
+    let valid = !key.is_empty()
+        && !key.starts_with('/')
+        && key
+            .split('/')
+            .all(|part| !part.is_empty() && part != "." && part != "..")
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(CoreError::Validation {
+            field: "key".to_string(),
+            reason: format!("invalid object key: {key:?}"),
+        })
+    }
+}
+
+/// Backend storing objects as files under a root directory
+#[derive(Debug, Clone)]
+pub struct LocalBackend {
+    root: PathBuf,
+}
+
+impl LocalBackend {
+    /// Create a backend rooted at `root`, creating the directory
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory cannot be created
+    pub fn new(root: impl Into<PathBuf>) -> CoreResult<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Root directory
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, key: &str) -> CoreResult<PathBuf> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    fn collect(&self, dir: &Path, keys: &mut Vec<String>) -> CoreResult<()> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.collect(&path, keys)?;
+            } else if let Ok(relative) = path.strip_prefix(&self.root) {
+                let key = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                // Skip partially written files
+                if validate_key(&key).is_ok() && !key.ends_with(PARTIAL_SUFFIX) {
+                    keys.push(key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StorageBackend for LocalBackend {
+    fn location(&self) -> String {
+        format!("file://{}", self.root.display())
+    }
+
+    fn host(&self) -> Option<String> {
+        None
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> CoreResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename, so readers never see a partial object
+        let mut partial = path.clone().into_os_string();
+        partial.push(PARTIAL_SUFFIX);
+        std::fs::write(&partial, data)?;
+        std::fs::rename(&partial, &path).map_err(CoreError::from)
+    }
+
+    fn get(&self, key: &str) -> CoreResult<Option<Vec<u8>>> {
+        match std::fs::read(self.path(key)?) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn delete(&self, key: &str) -> CoreResult<bool> {
+        match std::fs::remove_file(self.path(key)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> CoreResult<Vec<String>> {
+        let mut keys = Vec::new();
+        self.collect(&self.root, &mut keys)?;
+        keys.retain(|key| key.starts_with(prefix));
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Backend confined to one tenant's namespace of a shared backend
+///
+/// Keys are stored under `tenants/<tenant>/`, and listings only see that
+/// namespace, so tenants sharing a bucket never reach each other's objects.
+pub struct TenantBackend {
+    inner: Arc<dyn StorageBackend>,
+    tenant: TenantId,
+    prefix: String,
+}
+
+impl TenantBackend {
+    /// Confine `inner` to `tenant`'s namespace
+    #[must_use]
+    pub fn new(inner: Arc<dyn StorageBackend>, tenant: TenantId) -> Self {
+        Self {
+            inner,
+            tenant,
+            prefix: format!("{TENANT_PREFIX}/{tenant}/"),
+        }
+    }
+
+    /// Tenant whose namespace this is
+    #[must_use]
+    pub fn tenant(&self) -> TenantId {
+        self.tenant
+    }
+
+    fn key(&self, key: &str) -> CoreResult<String> {
+        validate_key(key)?;
+        Ok(format!("{}{key}", self.prefix))
+    }
+}
+
+impl StorageBackend for TenantBackend {
+    fn location(&self) -> String {
+        format!("{}/{}", self.inner.location(), self.prefix.trim_end_matches('/'))
+    }
+
+    fn host(&self) -> Option<String> {
+        self.inner.host()
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> CoreResult<()> {
+        self.inner.put(&self.key(key)?, data)
+    }
+
+    fn get(&self, key: &str) -> CoreResult<Option<Vec<u8>>> {
+        self.inner.get(&self.key(key)?)
+    }
+
+    fn delete(&self, key: &str) -> CoreResult<bool> {
+        self.inner.delete(&self.key(key)?)
+    }
+
+    fn list(&self, prefix: &str) -> CoreResult<Vec<String>> {
+        let keys = self.inner.list(&format!("{}{prefix}", self.prefix))?;
+        Ok(keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect())
+    }
+}
+
+/// How often and how patiently failed transfers are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each further retry
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(200),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    #[must_use]
+    pub const fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    /// Run `op` until it succeeds or the attempts run out
+    ///
+    /// Missing objects and denied permissions are not retried.
+    ///
+    /// # Errors
+    ///
+    /// Returns the last error if every attempt fails
+    pub fn run<T>(&self, mut op: impl FnMut() -> CoreResult<T>) -> CoreResult<T> {
+        let mut delay = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match op() {
+                Ok(value) => return Ok(value),
+                Err(err @ (CoreError::NotFound { .. } | CoreError::PermissionDenied { .. })) => {
+                    return Err(err);
+                }
+                Err(err) if attempt >= self.max_attempts => return Err(err),
+                Err(err) => {
+                    tracing::debug!(attempt, error = %err, "retrying storage transfer");
+                    std::thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Moves blobs, snapshots and bundles between local stores and a backend
+pub struct RemoteStore {
+    backend: Box<dyn StorageBackend>,
+    capabilities: CapabilitySet,
+    retry: RetryPolicy,
+}
+
+impl RemoteStore {
+    /// Create a remote store with no capabilities and the default retry policy
+    #[must_use]
+    pub fn new(backend: impl StorageBackend + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+            capabilities: CapabilitySet::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Grant the capabilities uploads are checked against
+    #[must_use]
+    pub fn with_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Set the retry policy
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The underlying backend
+    #[must_use]
+    pub fn backend(&self) -> &dyn StorageBackend {
+        self.backend.as_ref()
+    }
+
+    /// Upload a blob from `store`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the blob is missing, the upload is not permitted,
+    /// or every attempt fails
+    pub fn push_blob(&self, store: &ContentStore, id: &BlobId) -> CoreResult<()> {
+        let blob = store.read(id)?;
+        self.put(&blob_key(id), blob.as_bytes())
+    }
+
+    /// Download a blob into `store`, verifying it against `id`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the blob is not on the backend or no attempt
+    /// yields data matching `id`
+    pub fn fetch_blob(&self, store: &ContentStore, id: &BlobId) -> CoreResult<()> {
+        if store.contains(id) {
+            return Ok(());
+        }
+        let data = self.fetch_verified(&blob_key(id), id)?;
+        store.write_with_algorithm(data, id.algorithm())?;
+        Ok(())
+    }
+
+    /// Upload a snapshot and every blob it references
+    ///
+    /// Blobs go first, so a snapshot on the backend is always complete.
+    /// Delta snapshots are uploaded resolved, so fetching one does not
+    /// depend on its parents.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the snapshot or a blob is missing, or an upload fails
+    pub fn push_snapshot(&self, snapshots: &SnapshotStore, id: &str) -> CoreResult<()> {
+        let snapshot = snapshots.resolve(id)?;
+        for entry in snapshot.entries.values() {
+            self.push_blob(snapshots.content_store(), &entry.blob_id)?;
+        }
+        self.put(&snapshot_key(id)?, &snapshot.encode()?)
+    }
+
+    /// Download a snapshot and its blobs and register it in `snapshots`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the snapshot is not on the backend, does not
+    /// decode, or one of its blobs cannot be fetched and verified
+    pub fn fetch_snapshot(&self, snapshots: &mut SnapshotStore, id: &str) -> CoreResult<String> {
+        let key = snapshot_key(id)?;
+        let snapshot = self.retry.run(|| {
+            let data = self.get(&key)?;
+            Snapshot::decode(&data)
+        })?;
+        if snapshot.metadata.id != id {
+            return Err(CoreError::Validation {
+                field: "snapshot".to_string(),
+                reason: format!("object {key} holds snapshot {}", snapshot.metadata.id),
+            });
+        }
+        for entry in snapshot.entries.values() {
+            self.fetch_blob(snapshots.content_store(), &entry.blob_id)?;
+        }
+        snapshots.create(snapshot)
+    }
+
+    /// Upload an encoded replay bundle, returning its content address
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the name is invalid, the upload is not permitted,
+    /// or every attempt fails
+    pub fn push_bundle(&self, name: &str, data: &[u8]) -> CoreResult<ContentAddress> {
+        self.put(&bundle_key(name)?, data)?;
+        Ok(ContentAddress::compute(data))
+    }
+
+    /// Download a replay bundle, verifying it against `address`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the bundle is not on the backend or no attempt
+    /// yields data matching `address`
+    pub fn fetch_bundle(&self, name: &str, address: &ContentAddress) -> CoreResult<Vec<u8>> {
+        self.fetch_verified(&bundle_key(name)?, address)
+    }
+
+    /// Check that uploads to the backend are permitted
+    ///
+    /// # Errors
+    ///
+    /// Returns `PermissionDenied` if the backend is remote and no
+    /// `NetWrite` capability covers its host
+    pub fn check_upload(&self) -> CoreResult<()> {
+        match self.backend.host() {
+            Some(host) if !self.capabilities.can_write_net(&host) => {
+                Err(CoreError::PermissionDenied {
+                    operation: format!("upload to {} (requires NetWrite)", self.backend.location()),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> CoreResult<()> {
+        self.check_upload()?;
+        self.retry.run(|| self.backend.put(key, data))
+    }
+
+    /// Fetch an object, treating a missing one as an error
+    fn get(&self, key: &str) -> CoreResult<Vec<u8>> {
+        self.backend.get(key)?.ok_or_else(|| CoreError::NotFound {
+            kind: "object".to_string(),
+            id: format!("{}/{key}", self.backend.location()),
+        })
+    }
+
+    /// Fetch an object and rehash it, retrying on transfer or hash failure
+    fn fetch_verified(&self, key: &str, address: &ContentAddress) -> CoreResult<Vec<u8>> {
+        self.retry.run(|| {
+            let data = self.get(key)?;
+            let fetched = BlobData::new(data, None).with_algorithm(address.algorithm());
+            if fetched.address != *address {
+                return Err(CoreError::Validation {
+                    field: "address".to_string(),
+                    reason: format!("object {key} does not match {address}"),
+                });
+            }
+            Ok(fetched.data)
+        })
+    }
+}
+
+fn blob_key(id: &BlobId) -> String {
+    format!("{BLOB_PREFIX}/{}/{}", id.algorithm().as_str(), id.as_hash().to_hex())
+}
+
+fn snapshot_key(id: &str) -> CoreResult<String> {
+    let key = format!("{SNAPSHOT_PREFIX}/{id}");
+    validate_key(&key)?;
+    Ok(key)
+}
+
+fn bundle_key(name: &str) -> CoreResult<String> {
+    let key = format!("{BUNDLE_PREFIX}/{name}");
+    validate_key(&key)?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cathedral_core::Capability;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Local backend posing as a remote host that corrupts early reads
+    struct FlakyRemote {
+        inner: LocalBackend,
+        corrupt_reads: AtomicUsize,
+    }
+
+    impl StorageBackend for FlakyRemote {
+        fn location(&self) -> String {
+            "s3://shared".to_string()
+        }
+
+        fn host(&self) -> Option<String> {
+            Some("storage.example.com".to_string())
+        }
+
+        fn put(&self, key: &str, data: &[u8]) -> CoreResult<()> {
+            self.inner.put(key, data)
+        }
+
+        fn get(&self, key: &str) -> CoreResult<Option<Vec<u8>>> {
+            let data = self.inner.get(key)?;
+            if self.corrupt_reads.load(Ordering::SeqCst) > 0 {
+                self.corrupt_reads.fetch_sub(1, Ordering::SeqCst);
+                return Ok(data.map(|mut d| {
+                    d.push(0);
+                    d
+                }));
+            }
+            Ok(data)
+        }
+
+        fn delete(&self, key: &str) -> CoreResult<bool> {
+            self.inner.delete(key)
+        }
+
+        fn list(&self, prefix: &str) -> CoreResult<Vec<String>> {
+            self.inner.list(prefix)
+        }
+    }
+
+    fn no_wait() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_local_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = LocalBackend::new(dir.path()).unwrap();
+        backend.put("blobs/a/1", b"one").unwrap();
+        backend.put("bundles/run", b"two").unwrap();
+
+        assert_eq!(backend.get("blobs/a/1").unwrap().unwrap(), b"one");
+        assert_eq!(backend.get("blobs/a/2").unwrap(), None);
+        assert_eq!(backend.list("blobs/").unwrap(), vec!["blobs/a/1".to_string()]);
+        assert_eq!(backend.list("").unwrap().len(), 2);
+        assert!(backend.delete("bundles/run").unwrap());
+        assert!(!backend.delete("bundles/run").unwrap());
+
+        assert!(backend.put("../escape", b"x").is_err());
+        assert!(backend.put("/abs", b"x").is_err());
+        assert!(backend.get("a//b").is_err());
+    }
+
+    #[test]
+    fn test_tenant_backends_are_isolated() {
+        let dir = tempfile::tempdir().unwrap();
+        let shared: Arc<dyn StorageBackend> = Arc::new(LocalBackend::new(dir.path()).unwrap());
+        let a = TenantBackend::new(Arc::clone(&shared), TenantId::from_name("a"));
+        let b = TenantBackend::new(Arc::clone(&shared), TenantId::from_name("b"));
+        a.put("blobs/x", b"a's").unwrap();
+        b.put("blobs/x", b"b's").unwrap();
+
+        assert_eq!(a.get("blobs/x").unwrap().unwrap(), b"a's");
+        assert_eq!(b.get("blobs/x").unwrap().unwrap(), b"b's");
+        assert_eq!(a.list("").unwrap(), vec!["blobs/x".to_string()]);
+        assert!(a.get("../b/blobs/x").is_err());
+        assert_eq!(shared.list("tenants/").unwrap().len(), 2);
+
+        let store = ContentStore::new();
+        let id = store.write(b"output".to_vec()).unwrap();
+        RemoteStore::new(a).push_blob(&store, &id).unwrap();
+        let other = ContentStore::new();
+        assert!(RemoteStore::new(b).with_retry(no_wait()).fetch_blob(&other, &id).is_err());
+    }
+
+    #[test]
+    fn test_remote_store_snapshot_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = RemoteStore::new(LocalBackend::new(dir.path()).unwrap()).with_retry(no_wait());
+
+        let mut source = SnapshotStore::new(Arc::new(ContentStore::new()));
+        let entries = [("a".to_string(), b"alpha".to_vec()), ("b".to_string(), b"beta".to_vec())];
+        source.snapshot_from("snap-1".to_string(), entries.into_iter().collect()).unwrap();
+        remote.push_snapshot(&source, "snap-1").unwrap();
+
+        let mut target = SnapshotStore::new(Arc::new(ContentStore::new()));
+        remote.fetch_snapshot(&mut target, "snap-1").unwrap();
+        assert_eq!(target.restore("snap-1").unwrap(), source.restore("snap-1").unwrap());
+        assert!(remote.fetch_snapshot(&mut target, "missing").is_err());
+
+        let address = remote.push_bundle("run-1.bundle", b"bundle bytes").unwrap();
+        assert_eq!(remote.fetch_bundle("run-1.bundle", &address).unwrap(), b"bundle bytes");
+        let other = ContentAddress::compute(b"other");
+        assert!(remote.fetch_bundle("run-1.bundle", &other).is_err());
+    }
+
+    #[test]
+    fn test_remote_store_gates_uploads_and_retries_corrupt_fetches() {
+        let dir = tempfile::tempdir().unwrap();
+        let flaky = || FlakyRemote {
+            inner: LocalBackend::new(dir.path()).unwrap(),
+            corrupt_reads: AtomicUsize::new(2),
+        };
+        let store = ContentStore::new();
+        let id = store.write(b"shared output".to_vec()).unwrap();
+
+        let ungranted = RemoteStore::new(flaky()).with_retry(no_wait());
+        let denied = ungranted.push_blob(&store, &id).unwrap_err();
+        assert!(matches!(denied, CoreError::PermissionDenied { .. }));
+
+        let mut granted = CapabilitySet::new();
+        granted.grant(Capability::NetWrite {
+            allowlist: vec!["*.example.com".to_string()],
+        });
+        let remote = RemoteStore::new(flaky()).with_capabilities(granted).with_retry(no_wait());
+        remote.push_blob(&store, &id).unwrap();
+
+        // Two corrupted reads are rejected, the third attempt verifies
+        let fetched = ContentStore::new();
+        remote.fetch_blob(&fetched, &id).unwrap();
+        assert_eq!(fetched.read(&id).unwrap().as_bytes(), b"shared output");
+
+        let impatient = RemoteStore::new(flaky()).with_retry(RetryPolicy::none());
+        assert!(impatient.fetch_blob(&ContentStore::new(), &id).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 //! Blob storage primitives.
 
-use crate::address::ContentAddress;
+use crate::address::{AddressAlgorithm, ContentAddress};
 use cathedral_core::{Hash, CoreResult, CoreError};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -42,14 +42,22 @@ impl BlobData {
         self
     }
 
+    /// Re-address the data using `algorithm`
+    #[must_use]
+    pub fn with_algorithm(mut self, algorithm: AddressAlgorithm) -> Self {
+        self.address = ContentAddress::compute_with(&self.data, algorithm);
+        self
+    }
+
     /// Verify the content address matches the data
     ///
+    /// The data is rehashed with the address's own algorithm.
+    ///
     /// # Errors
     ///
     /// Returns error if address doesn't match data
     pub fn verify(&self) -> CoreResult<()> {
-        let computed = ContentAddress::compute(&self.data);
-        if computed != self.address {
+        if !self.address.verify(&self.data) {
             return Err(CoreError::Validation {
                 field: "address".to_string(),
                 reason: "Content address mismatch".to_string(),
@@ -99,6 +107,7 @@ impl<'de> Deserialize<'de> for Blob {
         D: serde::Deserializer<'de>,
     {
         let data = BlobData::deserialize(deserializer)?;
+        data.verify().map_err(serde::de::Error::custom)?;
         Ok(Self {
             inner: Arc::new(data),
         })
@@ -122,6 +131,14 @@ impl Blob {
         }
     }
 
+    /// Create a new blob addressed with `algorithm`
+    #[must_use]
+    pub fn with_algorithm(data: Vec<u8>, algorithm: AddressAlgorithm) -> Self {
+        Self {
+            inner: Arc::new(BlobData::new(data, None).with_algorithm(algorithm)),
+        }
+    }
+
     /// Create from existing blob data
     #[must_use]
     pub fn from_data(data: BlobData) -> Self {
@@ -214,6 +231,173 @@ impl AsRef<[u8]> for Blob {
     }
 }
 
+/// Content-defined chunking parameters
+///
+/// Boundaries are picked by a gear rolling hash (FastCDC), so an insertion
+/// in a large payload only changes the chunks around it and the rest are
+/// shared with earlier versions. Payloads no larger than `max_size` are
+/// stored whole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkingConfig {
+    /// Smallest chunk the hash may cut
+    pub min_size: usize,
+    /// Target chunk size; rounded down to a power of two
+    pub avg_size: usize,
+    /// Largest chunk before a cut is forced
+    pub max_size: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 16 * 1024,
+            avg_size: 64 * 1024,
+            max_size: 256 * 1024,
+        }
+    }
+}
+
+impl ChunkingConfig {
+    /// Whether a payload of `len` bytes is split into chunks
+    #[must_use]
+    pub const fn applies_to(&self, len: usize) -> bool {
+        len > self.max_size
+    }
+
+    /// Split `data` into content-defined chunks
+    ///
+    /// The chunks are contiguous and cover `data` exactly.
+    #[must_use]
+    pub fn split<'a>(&self, data: &'a [u8]) -> Vec<&'a [u8]> {
+        let bits = self.avg_size.max(4).ilog2();
+        // A stricter mask below the target size and a looser one above it
+        // pulls chunk sizes towards `avg_size`
+        let mask_small = !0u64 << (63 - bits);
+        let mask_large = !0u64 << (65 - bits);
+
+        let mut chunks = Vec::new();
+        let mut rest = data;
+        while !rest.is_empty() {
+            let cut = self.cut_point(rest, mask_small, mask_large);
+            let (chunk, tail) = rest.split_at(cut);
+            chunks.push(chunk);
+            rest = tail;
+        }
+        chunks
+    }
+
+    /// Length of the next chunk at the start of `data`
+    fn cut_point(&self, data: &[u8], mask_small: u64, mask_large: u64) -> usize {
+        let min = self.min_size.max(1);
+        if data.len() <= min {
+            return data.len();
+        }
+        let max = data.len().min(self.max_size.max(min));
+        let normal = max.min(self.avg_size.max(min));
+
+        let mut hash = 0u64;
+        for (i, byte) in data.iter().enumerate().take(max).skip(min) {
+            hash = (hash << 1).wrapping_add(GEAR[usize::from(*byte)]);
+            let mask = if i < normal { mask_small } else { mask_large };
+            if hash & mask == 0 {
+                return i + 1;
+            }
+        }
+        max
+    }
+}
+
+/// Gear table for the rolling hash, fixed so chunk boundaries are stable
+const GEAR: [u64; 256] = gear_table();
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state = 0x6361_7468_6564_7261u64;
+    let mut i = 0;
+    while i < 256 {
+        // splitmix64
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// One chunk of a chunked blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkRef {
+    /// Content address of the chunk
+    pub id: BlobId,
+    /// Chunk size in bytes
+    pub size: usize,
+}
+
+/// Reassembly instructions for a blob stored as chunks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    /// Content address of the whole payload
+    pub address: ContentAddress,
+    /// Total payload size in bytes
+    pub size: usize,
+    /// Content type of the whole payload
+    pub content_type: Option<String>,
+    /// Chunks in payload order
+    pub chunks: Vec<ChunkRef>,
+}
+
+impl ChunkManifest {
+    /// Encode the manifest as a blob
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn to_blob(&self) -> CoreResult<Blob> {
+        Ok(Blob::new(serde_json::to_vec(self)?))
+    }
+
+    /// Decode a manifest produced by [`ChunkManifest::to_blob`]
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the blob is not a manifest
+    pub fn from_blob(blob: &Blob) -> CoreResult<Self> {
+        Ok(serde_json::from_slice(blob.as_bytes())?)
+    }
+
+    /// Reassemble the payload from its chunks
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a chunk is missing or the result does not match
+    /// the manifest's address
+    pub fn reassemble<F>(&self, mut chunk: F) -> CoreResult<BlobData>
+    where
+        F: FnMut(&BlobId) -> Option<Arc<Blob>>,
+    {
+        let mut data = Vec::with_capacity(self.size);
+        for part in &self.chunks {
+            let blob = chunk(&part.id).ok_or_else(|| CoreError::NotFound {
+                kind: "chunk".to_string(),
+                id: part.id.to_string(),
+            })?;
+            data.extend_from_slice(blob.as_bytes());
+        }
+
+        let blob = BlobData::new(data, self.content_type.clone())
+            .with_algorithm(self.address.algorithm());
+        if blob.address != self.address {
+            return Err(CoreError::Validation {
+                field: "address".to_string(),
+                reason: "Reassembled chunks do not match the manifest address".to_string(),
+            });
+        }
+        Ok(blob)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,6 +456,25 @@ mod tests {
         assert!(blob_data.verify().is_ok());
     }
 
+    #[test]
+    fn test_blob_serde_verifies_address() {
+        for algorithm in [AddressAlgorithm::Blake3, AddressAlgorithm::Sha256, AddressAlgorithm::Sha512] {
+            let blob = Blob::with_algorithm(b"round trip".to_vec(), algorithm);
+            assert_eq!(blob.address().algorithm(), algorithm);
+            let json = serde_json::to_string(&blob).unwrap();
+            let restored: Blob = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored, blob);
+            assert!(restored.verify().is_ok());
+        }
+
+        let mut tampered = BlobData::new(b"round trip".to_vec(), None)
+            .with_algorithm(AddressAlgorithm::Sha256);
+        tampered.address.algorithm = AddressAlgorithm::Sha512;
+        assert!(tampered.verify().is_err());
+        let json = serde_json::to_string(&tampered).unwrap();
+        assert!(serde_json::from_str::<Blob>(&json).is_err());
+    }
+
     #[test]
     fn test_blob_data_is_empty() {
         let empty = BlobData::new(vec![], None);
@@ -280,4 +483,64 @@ mod tests {
         let non_empty = BlobData::new(b"x".to_vec(), None);
         assert!(!non_empty.is_empty());
     }
+
+    fn noise(len: usize, seed: u64) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunking_resyncs_after_insert() {
+        let config = ChunkingConfig {
+            min_size: 256,
+            avg_size: 1024,
+            max_size: 4096,
+        };
+        let data = noise(64 * 1024, 7);
+        let chunks = config.split(&data);
+        assert_eq!(chunks.concat(), data);
+        assert!(chunks.len() > 8);
+        assert!(chunks.iter().all(|c| c.len() <= config.max_size));
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c.len() >= config.min_size));
+
+        // Bytes inserted at the front only disturb the first chunks
+        let mut edited = b"prefix".to_vec();
+        edited.extend_from_slice(&data);
+        let shifted = config.split(&edited);
+        let shared = shifted.iter().filter(|c| chunks.contains(c)).count();
+        assert!(shared >= chunks.len() - 2);
+        assert!(!config.applies_to(config.max_size));
+    }
+
+    #[test]
+    fn test_chunk_manifest_reassemble() {
+        let config = ChunkingConfig::default();
+        let data = noise(1024 * 1024, 11);
+        let parts: Vec<Arc<Blob>> = config
+            .split(&data)
+            .into_iter()
+            .map(|part| Arc::new(Blob::new(part.to_vec())))
+            .collect();
+        let manifest = ChunkManifest {
+            address: ContentAddress::compute(&data),
+            size: data.len(),
+            content_type: None,
+            chunks: parts.iter().map(|p| ChunkRef { id: p.id(), size: p.size() }).collect(),
+        };
+        let decoded = ChunkManifest::from_blob(&manifest.to_blob().unwrap()).unwrap();
+        assert_eq!(decoded, manifest);
+
+        let find = |id: &BlobId| parts.iter().find(|p| p.id() == *id).cloned();
+        assert_eq!(manifest.reassemble(find).unwrap().data, data);
+        assert!(manifest.reassemble(|_| None).is_err());
+        let swapped = |id: &BlobId| find(id).map(|_| Arc::clone(&parts[0]));
+        assert!(manifest.reassemble(swapped).is_err());
+    }
 }
@@ -42,6 +42,14 @@ impl CompactPlan {
         self.keep.len()
     }
 
+    /// Blobs to delete in deterministic (address) order
+    #[must_use]
+    pub fn delete_order(&self) -> Vec<BlobId> {
+        let mut order: Vec<BlobId> = self.delete.iter().copied().collect();
+        order.sort();
+        order
+    }
+
     /// Update plan statistics
     pub fn update_stats(&mut self, blob_sizes: &HashMap<BlobId, usize>) {
         self.delete_count = self.delete.len();
@@ -71,6 +79,8 @@ pub struct CompactResult {
     pub kept_count: usize,
     /// Number of errors during compaction
     pub error_count: usize,
+    /// Planned deletions skipped because the blob was live at apply time
+    pub skipped_count: usize,
 }
 
 impl CompactResult {
@@ -82,6 +92,7 @@ impl CompactResult {
             reclaimed_bytes: 0,
             kept_count: 0,
             error_count: 0,
+            skipped_count: 0,
         }
     }
 
@@ -97,6 +108,7 @@ impl CompactResult {
         self.reclaimed_bytes += other.reclaimed_bytes;
         self.kept_count += other.kept_count;
         self.error_count += other.error_count;
+        self.skipped_count += other.skipped_count;
     }
 }
 
@@ -106,6 +118,38 @@ impl Default for CompactResult {
     }
 }
 
+/// Progress of an incremental compaction
+///
+/// Deletions are applied in [`CompactPlan::delete_order`], so the last
+/// processed blob is enough to resume without rescanning the store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompactCheckpoint {
+    /// Last blob processed, if any
+    pub cursor: Option<BlobId>,
+    /// Accumulated result so far
+    pub result: CompactResult,
+    /// Whether every planned deletion has been processed
+    pub complete: bool,
+}
+
+impl CompactCheckpoint {
+    /// Create a checkpoint at the start of a plan
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            cursor: None,
+            result: CompactResult::new(),
+            complete: false,
+        }
+    }
+}
+
+impl Default for CompactCheckpoint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Compactor for storage cleanup
 pub struct Compactor {
     /// Content store to compact
@@ -175,6 +219,74 @@ impl Compactor {
         Ok(result)
     }
 
+    /// Apply up to `chunk_size` planned deletions after `checkpoint`
+    ///
+    /// Liveness is re-validated against `live` at apply time: a planned
+    /// blob that has since become referenced is skipped, not deleted.
+    /// Feed the returned checkpoint back in until it is complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the checkpoint is already complete
+    pub fn compact_chunk(
+        &self,
+        plan: &CompactPlan,
+        checkpoint: &CompactCheckpoint,
+        chunk_size: usize,
+        live: &HashSet<BlobId>,
+    ) -> CoreResult<CompactCheckpoint> {
+        if checkpoint.complete {
+            return Err(CoreError::Validation {
+                field: "checkpoint".to_string(),
+                reason: "Compaction already complete".to_string(),
+            });
+        }
+
+        let order = plan.delete_order();
+        let start = match checkpoint.cursor {
+            Some(cursor) => order.partition_point(|id| *id <= cursor),
+            None => 0,
+        };
+        let end = order.len().min(start.saturating_add(chunk_size));
+
+        let mut next = checkpoint.clone();
+        for blob_id in &order[start..end] {
+            next.cursor = Some(*blob_id);
+
+            if live.contains(blob_id) || plan.keep.contains(blob_id) {
+                next.result.skipped_count += 1;
+                continue;
+            }
+
+            let size = self.store.read(blob_id).map(|blob| blob.size() as u64).unwrap_or(0);
+            match self.store.delete(blob_id) {
+                Ok(true) => {
+                    next.result.deleted_count += 1;
+                    next.result.reclaimed_bytes += size;
+                }
+                Ok(false) => {
+                    // Already deleted
+                }
+                Err(_) => {
+                    next.result.error_count += 1;
+                }
+            }
+        }
+
+        if end == order.len() {
+            next.complete = true;
+            next.result.kept_count = plan.keep_count() + next.result.skipped_count;
+        }
+
+        Ok(next)
+    }
+
+    /// Get the store being compacted
+    #[must_use]
+    pub fn store(&self) -> &ContentStore {
+        &self.store
+    }
+
     /// Analyze and compact in one step
     ///
     /// # Errors
@@ -379,6 +491,67 @@ mod tests {
         assert!(result.is_success());
     }
 
+    #[test]
+    fn test_compactor_compact_chunk() {
+        let store = ContentStore::new();
+        let keep = store.write(b"keep".to_vec()).unwrap();
+        for i in 0..5u8 {
+            store.write(vec![b'x', i]).unwrap();
+        }
+
+        let compactor = Compactor::new(store);
+        let referenced: HashSet<BlobId> = [keep].into_iter().collect();
+        let plan = compactor.analyze(&referenced).unwrap();
+        assert_eq!(plan.delete_count, 5);
+
+        // A planned blob gains a reference after the plan was computed
+        let revived = plan.delete_order()[3];
+        let mut live = referenced.clone();
+        live.insert(revived);
+
+        let mut checkpoint = CompactCheckpoint::new();
+        let mut chunks = 0;
+        while !checkpoint.complete {
+            checkpoint = compactor.compact_chunk(&plan, &checkpoint, 2, &live).unwrap();
+            chunks += 1;
+        }
+
+        assert_eq!(chunks, 3);
+        assert_eq!(checkpoint.result.deleted_count, 4);
+        assert_eq!(checkpoint.result.skipped_count, 1);
+        assert_eq!(checkpoint.result.reclaimed_bytes, 8);
+        assert_eq!(checkpoint.result.kept_count, 2);
+        assert!(compactor.store().contains(&revived));
+        assert!(compactor.store().contains(&keep));
+        assert_eq!(compactor.stats().blob_count, 2);
+        assert!(compactor.compact_chunk(&plan, &checkpoint, 2, &live).is_err());
+    }
+
+    #[test]
+    fn test_compact_checkpoint_resume() {
+        let store = ContentStore::new();
+        for i in 0..4u8 {
+            store.write(vec![i]).unwrap();
+        }
+
+        let compactor = Compactor::new(store);
+        let plan = compactor.analyze(&HashSet::new()).unwrap();
+
+        let first = compactor
+            .compact_chunk(&plan, &CompactCheckpoint::new(), 3, &HashSet::new())
+            .unwrap();
+        assert_eq!(first.cursor, Some(plan.delete_order()[2]));
+
+        // Resume from a serialized checkpoint
+        let json = serde_json::to_string(&first).unwrap();
+        let resumed: CompactCheckpoint = serde_json::from_str(&json).unwrap();
+        let done = compactor
+            .compact_chunk(&plan, &resumed, 3, &HashSet::new())
+            .unwrap();
+        assert!(done.complete);
+        assert_eq!(done.result.deleted_count, 4);
+    }
+
     #[test]
     fn test_compactor_stats() {
         let store = ContentStore::new();
@@ -0,0 +1,201 @@
+//! Garbage collection for the content store.
+//!
+//! A mark-and-sweep pass rooted at every registered snapshot and every
+//! pinned run. Marking collects the reachable blob IDs; sweeping removes the
+//! rest in a single atomic batch, or only reports them in dry-run mode.
+
+use crate::compact::{CompactPlan, CompactResult};
+use crate::snapshot::SnapshotStore;
+use crate::{BlobId, ContentStore};
+use cathedral_core::RunId;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// Outcome of a garbage collection pass
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcReport {
+    /// Plan computed by the mark phase
+    pub plan: CompactPlan,
+    /// What the sweep deleted, or would delete in a dry run
+    pub result: CompactResult,
+    /// Whether the sweep left the store untouched
+    pub dry_run: bool,
+}
+
+/// Mark-and-sweep collector over a content store
+pub struct GarbageCollector {
+    /// Store to collect
+    store: Arc<ContentStore>,
+    /// Blobs referenced by live runs, by run
+    pinned: BTreeMap<RunId, HashSet<BlobId>>,
+}
+
+impl GarbageCollector {
+    /// Create a collector with no pinned runs
+    #[must_use]
+    pub fn new(store: Arc<ContentStore>) -> Self {
+        Self {
+            store,
+            pinned: BTreeMap::new(),
+        }
+    }
+
+    /// Keep `blobs` alive for as long as `run_id` is pinned
+    pub fn pin_run(&mut self, run_id: RunId, blobs: impl IntoIterator<Item = BlobId>) {
+        self.pinned.entry(run_id).or_default().extend(blobs);
+    }
+
+    /// Release a run's blobs; returns false if the run was not pinned
+    pub fn unpin_run(&mut self, run_id: RunId) -> bool {
+        self.pinned.remove(&run_id).is_some()
+    }
+
+    /// Pinned runs, in ID order
+    #[must_use]
+    pub fn pinned_runs(&self) -> Vec<RunId> {
+        self.pinned.keys().copied().collect()
+    }
+
+    /// Mark phase: every blob reachable from a snapshot or pinned run
+    #[must_use]
+    pub fn mark(&self, snapshots: &SnapshotStore) -> HashSet<BlobId> {
+        let mut reachable = snapshots.referenced_blobs();
+        for blobs in self.pinned.values() {
+            reachable.extend(blobs.iter().copied());
+        }
+        reachable
+    }
+
+    /// Plan a collection, sizing the unreachable blobs
+    #[must_use]
+    pub fn plan(&self, snapshots: &SnapshotStore) -> CompactPlan {
+        let reachable = self.mark(snapshots);
+        let (keep, delete): (HashSet<BlobId>, HashSet<BlobId>) = self
+            .store
+            .list()
+            .into_iter()
+            .partition(|id| reachable.contains(id));
+
+        let sizes: HashMap<BlobId, usize> = delete
+            .iter()
+            .filter_map(|id| self.store.read(id).ok().map(|blob| (*id, blob.size())))
+            .collect();
+        let mut plan = CompactPlan {
+            keep,
+            delete,
+            ..CompactPlan::default()
+        };
+        plan.update_stats(&sizes);
+        plan
+    }
+
+    /// Sweep phase: delete the planned blobs that are still unreachable
+    ///
+    /// Roots are marked again first, so a blob referenced since the plan was
+    /// made is skipped. The remaining deletions are applied atomically. In a
+    /// dry run nothing is deleted and the result reports what would be.
+    pub fn sweep(
+        &self,
+        plan: &CompactPlan,
+        snapshots: &SnapshotStore,
+        dry_run: bool,
+    ) -> CompactResult {
+        let reachable = self.mark(snapshots);
+        let (doomed, revived): (Vec<BlobId>, Vec<BlobId>) = plan
+            .delete_order()
+            .into_iter()
+            .partition(|id| !reachable.contains(id));
+
+        let mut result = CompactResult::new();
+        result.skipped_count = revived.len();
+        result.kept_count = plan.keep_count() + revived.len();
+        if dry_run {
+            for id in &doomed {
+                if let Ok(blob) = self.store.read(id) {
+                    result.deleted_count += 1;
+                    result.reclaimed_bytes += blob.size() as u64;
+                }
+            }
+        } else {
+            let (deleted, bytes) = self.store.delete_batch(&doomed);
+            result.deleted_count = deleted;
+            result.reclaimed_bytes = bytes;
+        }
+        result
+    }
+
+    /// Mark and sweep in one pass
+    pub fn collect(&self, snapshots: &SnapshotStore, dry_run: bool) -> GcReport {
+        let plan = self.plan(snapshots);
+        let result = self.sweep(&plan, snapshots, dry_run);
+        GcReport {
+            plan,
+            result,
+            dry_run,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_gc_collects_unreachable_blobs() {
+        let content = Arc::new(ContentStore::new());
+        let mut snapshots = SnapshotStore::new(Arc::clone(&content));
+        let entries: HashMap<String, Vec<u8>> =
+            [("state".to_string(), b"snap".to_vec())].into_iter().collect();
+        snapshots.snapshot_from("s1".to_string(), entries).unwrap();
+        let pinned = content.write(b"pinned".to_vec()).unwrap();
+        let garbage = content.write(b"garbage".to_vec()).unwrap();
+
+        let run_id = RunId::new();
+        let mut gc = GarbageCollector::new(Arc::clone(&content));
+        gc.pin_run(run_id, [pinned]);
+
+        let report = gc.collect(&snapshots, true);
+        assert!(report.dry_run);
+        assert_eq!(report.plan.delete, [garbage].into_iter().collect());
+        assert_eq!(report.plan.reclaim_bytes, 7);
+        assert_eq!(report.result.deleted_count, 1);
+        assert_eq!(report.result.reclaimed_bytes, 7);
+        assert_eq!(content.count(), 3);
+
+        let report = gc.collect(&snapshots, false);
+        assert_eq!(report.result.deleted_count, 1);
+        assert_eq!(report.result.kept_count, 2);
+        assert!(!content.contains(&garbage));
+        assert_eq!(content.size(), 10);
+
+        // Unpinning makes the run's blobs collectable
+        assert!(gc.unpin_run(run_id));
+        assert!(!gc.unpin_run(run_id));
+        gc.collect(&snapshots, false);
+        assert!(!content.contains(&pinned));
+        assert_eq!(content.count(), 1);
+    }
+
+    #[test]
+    fn test_gc_sweep_skips_revived_blobs() {
+        let content = Arc::new(ContentStore::new());
+        let mut snapshots = SnapshotStore::new(Arc::clone(&content));
+        let blob = content.write(b"late".to_vec()).unwrap();
+
+        let gc = GarbageCollector::new(Arc::clone(&content));
+        let plan = gc.plan(&snapshots);
+        assert_eq!(plan.delete_count, 1);
+
+        // A snapshot references the blob between plan and sweep
+        let mut snapshot = crate::Snapshot::new("s1".to_string());
+        snapshot.add_entry("late".to_string(), blob, 4);
+        snapshots.create(snapshot).unwrap();
+
+        let result = gc.sweep(&plan, &snapshots, false);
+        assert_eq!(result.deleted_count, 0);
+        assert_eq!(result.skipped_count, 1);
+        assert!(content.contains(&blob));
+    }
+}
@@ -10,10 +10,18 @@ pub mod blob;
 pub mod store;
 pub mod snapshot;
 pub mod compact;
+pub mod gc;
 pub mod address;
+pub mod backend;
+pub mod s3;
+pub mod encrypt;
 
-pub use blob::{Blob, BlobData, BlobId};
+pub use blob::{Blob, BlobData, BlobId, ChunkManifest, ChunkRef, ChunkingConfig};
 pub use store::{ContentStore, StoreError, StoreConfig};
 pub use snapshot::{Snapshot, SnapshotBuilder, SnapshotError};
-pub use compact::{Compactor, CompactPlan, CompactResult};
+pub use compact::{Compactor, CompactCheckpoint, CompactPlan, CompactResult};
+pub use gc::{GarbageCollector, GcReport};
 pub use address::{ContentAddress, AddressAlgorithm};
+pub use backend::{LocalBackend, RemoteStore, RetryPolicy, StorageBackend, TenantBackend};
+pub use encrypt::{MasterKey, RunKey, SealedBlob, WrappedKey};
+pub use s3::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport, S3Backend, S3Config};
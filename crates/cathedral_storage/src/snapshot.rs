@@ -1,9 +1,14 @@
 //! Snapshot storage for point-in-time state.
+//!
+//! A snapshot is either full, holding every entry, or a delta that records
+//! only the keys added, changed or removed since its parent. The store
+//! resolves a delta by walking the parent chain down to a full snapshot,
+//! and flattens any snapshot whose chain grows past the configured depth.
 
 use crate::{BlobId, ContentStore};
 use cathedral_core::{CoreResult, CoreError, EventId};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
 /// Snapshot error
@@ -60,6 +65,12 @@ pub struct SnapshotMetadata {
     pub entry_count: usize,
     /// Total size in bytes
     pub total_bytes: u64,
+    /// Whether the entries are changes relative to the parent
+    #[serde(default)]
+    pub delta: bool,
+    /// Delta links between this snapshot and the full snapshot below it
+    #[serde(default)]
+    pub chain_depth: u32,
 }
 
 impl SnapshotMetadata {
@@ -77,6 +88,8 @@ impl SnapshotMetadata {
             event_id: None,
             entry_count: 0,
             total_bytes: 0,
+            delta: false,
+            chain_depth: 0,
         }
     }
 
@@ -113,6 +126,9 @@ pub struct Snapshot {
     pub metadata: SnapshotMetadata,
     /// Snapshot entries
     pub entries: HashMap<String, SnapshotEntry>,
+    /// Keys removed since the parent (delta snapshots only)
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub removed: BTreeSet<String>,
 }
 
 impl Snapshot {
@@ -122,6 +138,7 @@ impl Snapshot {
         Self {
             metadata: SnapshotMetadata::new(id),
             entries: HashMap::new(),
+            removed: BTreeSet::new(),
         }
     }
 
@@ -131,9 +148,50 @@ impl Snapshot {
         Self {
             metadata: SnapshotMetadata::new(id).with_parent(parent_id),
             entries: HashMap::new(),
+            removed: BTreeSet::new(),
         }
     }
 
+    /// Create a delta recording how `full` differs from `parent`
+    ///
+    /// `parent` must be the resolved view of the parent snapshot.
+    #[must_use]
+    pub fn delta(id: String, parent: &Snapshot, full: &Snapshot) -> Self {
+        let mut delta = Self::with_parent(id, parent.metadata.id.clone());
+        delta.metadata.delta = true;
+        delta.metadata.event_id = full.metadata.event_id;
+        for (key, entry) in &full.entries {
+            if parent.entries.get(key) != Some(entry) {
+                delta.entries.insert(key.clone(), entry.clone());
+            }
+        }
+        delta.removed = parent
+            .entries
+            .keys()
+            .filter(|key| !full.entries.contains_key(*key))
+            .cloned()
+            .collect();
+        delta.update_metadata();
+        delta
+    }
+
+    /// Whether this snapshot only records changes relative to its parent
+    #[must_use]
+    pub fn is_delta(&self) -> bool {
+        self.metadata.delta
+    }
+
+    /// Apply a delta on top of this view
+    pub fn apply(&mut self, delta: &Snapshot) {
+        for key in &delta.removed {
+            self.entries.remove(key);
+        }
+        for (key, entry) in &delta.entries {
+            self.entries.insert(key.clone(), entry.clone());
+        }
+        self.update_metadata();
+    }
+
     /// Add an entry to the snapshot
     pub fn add_entry(&mut self, key: String, blob_id: BlobId, size: u64) {
         let entry = SnapshotEntry { key: key.clone(), blob_id, size };
@@ -276,12 +334,17 @@ impl Default for SnapshotBuilder {
     }
 }
 
+/// Default longest delta chain before a snapshot is stored in full
+pub const DEFAULT_MAX_CHAIN_DEPTH: u32 = 16;
+
 /// Snapshot store for managing snapshots
 pub struct SnapshotStore {
     /// Content store for blob data
     content_store: Arc<ContentStore>,
     /// Snapshots indexed by ID
     snapshots: HashMap<String, Arc<Snapshot>>,
+    /// Longest delta chain kept before flattening
+    max_chain_depth: u32,
 }
 
 impl SnapshotStore {
@@ -291,17 +354,50 @@ impl SnapshotStore {
         Self {
             content_store,
             snapshots: HashMap::new(),
+            max_chain_depth: DEFAULT_MAX_CHAIN_DEPTH,
         }
     }
 
+    /// Flatten deltas whose chain would grow past `depth`
+    #[must_use]
+    pub fn with_max_chain_depth(mut self, depth: u32) -> Self {
+        self.max_chain_depth = depth;
+        self
+    }
+
     /// Create a snapshot
     ///
+    /// A delta snapshot must name a parent in the store. If it would sit
+    /// deeper than the maximum chain depth it is resolved and stored in
+    /// full instead.
+    ///
     /// # Errors
     ///
     /// Returns error if snapshot creation fails
-    pub fn create(&mut self, snapshot: Snapshot) -> CoreResult<String> {
+    pub fn create(&mut self, mut snapshot: Snapshot) -> CoreResult<String> {
         let id = snapshot.metadata.id.clone();
 
+        if snapshot.is_delta() {
+            let parent_id = snapshot.metadata.parent_id.clone().ok_or_else(|| {
+                SnapshotError::Invalid {
+                    reason: format!("delta snapshot {id} has no parent"),
+                }
+            })?;
+            let parent = self.get(&parent_id)?;
+            let depth = parent.metadata.chain_depth + 1;
+            if depth > self.max_chain_depth {
+                let mut full = self.resolve(&parent_id)?;
+                full.apply(&snapshot);
+                full.metadata = snapshot.metadata;
+                snapshot = full;
+                snapshot.metadata.delta = false;
+                snapshot.metadata.chain_depth = 0;
+                snapshot.update_metadata();
+            } else {
+                snapshot.metadata.chain_depth = depth;
+            }
+        }
+
         // Verify all blobs exist
         for entry in snapshot.entries.values() {
             if !self.content_store.contains(&entry.blob_id) {
@@ -328,8 +424,115 @@ impl SnapshotStore {
             .ok_or_else(|| SnapshotError::NotFound { id: id.to_string() }.into())
     }
 
+    /// Create a snapshot from its full view, storing only the changes
+    /// since its parent
+    ///
+    /// Snapshots without a parent are stored in full.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the parent cannot be resolved or creation fails
+    pub fn create_incremental(&mut self, snapshot: Snapshot) -> CoreResult<String> {
+        let Some(parent_id) = snapshot.metadata.parent_id.clone() else {
+            return self.create(snapshot);
+        };
+        let parent = self.resolve(&parent_id)?;
+        let mut delta = Snapshot::delta(snapshot.metadata.id.clone(), &parent, &snapshot);
+        delta.metadata.timestamp = snapshot.metadata.timestamp;
+        self.create(delta)
+    }
+
+    /// Materialize the full view of a snapshot
+    ///
+    /// Deltas are applied on top of their parents, starting from the
+    /// nearest full snapshot. The result is a full snapshot carrying the
+    /// requested snapshot's metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a snapshot in the chain is missing or the chain
+    /// loops
+    pub fn resolve(&self, id: &str) -> CoreResult<Snapshot> {
+        let mut chain = vec![self.get(id)?];
+        let mut seen = HashSet::from([id.to_string()]);
+        while chain.last().is_some_and(|s| s.is_delta()) {
+            let current = &chain[chain.len() - 1];
+            let parent_id = current.metadata.parent_id.clone().ok_or_else(|| {
+                SnapshotError::Invalid {
+                    reason: format!("delta snapshot {} has no parent", current.metadata.id),
+                }
+            })?;
+            if !seen.insert(parent_id.clone()) {
+                return Err(SnapshotError::Invalid {
+                    reason: format!("snapshot chain of {id} loops at {parent_id}"),
+                }
+                .into());
+            }
+            chain.push(self.get(&parent_id)?);
+        }
+
+        let mut view = match chain.pop() {
+            Some(base) => (*base).clone(),
+            None => Snapshot::new(id.to_string()),
+        };
+        for delta in chain.iter().rev() {
+            view.apply(delta);
+        }
+        if let Some(requested) = chain.first() {
+            view.metadata = requested.metadata.clone();
+        }
+        view.metadata.delta = false;
+        view.removed.clear();
+        view.update_metadata();
+        Ok(view)
+    }
+
+    /// Replace a snapshot with its full view, cutting its delta chain
+    ///
+    /// Deltas built on top of it stay valid, as its view does not change.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the snapshot cannot be resolved
+    pub fn compact(&mut self, id: &str) -> CoreResult<()> {
+        let mut full = self.resolve(id)?;
+        full.metadata.chain_depth = 0;
+        let shift = self.get(id)?.metadata.chain_depth;
+        self.snapshots.insert(id.to_string(), Arc::new(full));
+        if shift > 0 {
+            self.rebase_depths(id, shift);
+        }
+        Ok(())
+    }
+
+    /// Lower the recorded chain depth of every delta above `id`
+    fn rebase_depths(&mut self, id: &str, shift: u32) {
+        let mut pending = vec![id.to_string()];
+        while let Some(parent) = pending.pop() {
+            for (child_id, child) in self.snapshots.iter_mut() {
+                if child.is_delta() && child.metadata.parent_id.as_deref() == Some(&parent) {
+                    Arc::make_mut(child).metadata.chain_depth -= shift;
+                    pending.push(child_id.clone());
+                }
+            }
+        }
+    }
+
     /// Delete a snapshot
+    ///
+    /// Deltas built directly on the snapshot are flattened first so they
+    /// remain resolvable.
     pub fn delete(&mut self, id: &str) -> bool {
+        let children: Vec<String> = self
+            .snapshots
+            .values()
+            .filter(|s| s.is_delta() && s.metadata.parent_id.as_deref() == Some(id))
+            .map(|s| s.metadata.id.clone())
+            .collect();
+        for child in children {
+            // The parent exists, so the child resolves
+            let _ = self.compact(&child);
+        }
         self.snapshots.remove(id).is_some()
     }
 
@@ -345,6 +548,21 @@ impl SnapshotStore {
         self.snapshots.len()
     }
 
+    /// Content store holding the snapshot blobs
+    #[must_use]
+    pub fn content_store(&self) -> &Arc<ContentStore> {
+        &self.content_store
+    }
+
+    /// Blobs referenced by any registered snapshot
+    #[must_use]
+    pub fn referenced_blobs(&self) -> HashSet<BlobId> {
+        self.snapshots
+            .values()
+            .flat_map(|snapshot| snapshot.entries.values().map(|entry| entry.blob_id))
+            .collect()
+    }
+
     /// Create a snapshot from state entries
     ///
     /// # Errors
@@ -372,7 +590,7 @@ impl SnapshotStore {
     ///
     /// Returns error if restoration fails
     pub fn restore(&self, id: &str) -> CoreResult<HashMap<String, Vec<u8>>> {
-        let snapshot = self.get(id)?;
+        let snapshot = self.resolve(id)?;
         let mut state = HashMap::new();
 
         for (key, entry) in &snapshot.entries {
@@ -495,4 +713,86 @@ mod tests {
         let err = SnapshotError::NotFound { id: "test".to_string() };
         assert!(err.to_string().contains("not found"));
     }
+
+    #[test]
+    fn test_snapshot_store_incremental_chain() {
+        let content = Arc::new(ContentStore::new());
+        let mut store = SnapshotStore::new(Arc::clone(&content)).with_max_chain_depth(2);
+        let blob = |data: &[u8]| content.write(data.to_vec()).unwrap();
+        let (a, b, c) = (blob(b"a"), blob(b"b"), blob(b"c"));
+
+        let base = SnapshotBuilder::new("s0".to_string())
+            .entry("x".to_string(), a, 1)
+            .entry("y".to_string(), b, 1)
+            .build();
+        store.create_incremental(base).unwrap();
+
+        // Change x, drop y, add z
+        let child = SnapshotBuilder::new("s1".to_string())
+            .parent("s0".to_string())
+            .entry("x".to_string(), c, 1)
+            .entry("z".to_string(), a, 1)
+            .build();
+        store.create_incremental(child).unwrap();
+        let delta = store.get("s1").unwrap();
+        assert!(delta.is_delta());
+        assert_eq!(delta.metadata.chain_depth, 1);
+        assert_eq!(delta.entry_count(), 2);
+        assert_eq!(delta.removed, BTreeSet::from(["y".to_string()]));
+        assert_eq!(Snapshot::decode(&delta.encode().unwrap()).unwrap(), *delta);
+
+        let view = store.resolve("s1").unwrap();
+        assert!(!view.is_delta());
+        assert_eq!(view.metadata.id, "s1");
+        assert_eq!(view.get_entry("x").unwrap().blob_id, c);
+        assert!(!view.contains_key("y"));
+        let restored = store.restore("s1").unwrap();
+        assert_eq!(restored.get("z").unwrap(), b"a");
+
+        // Depth 2 is kept as a delta, depth 3 trips the limit and is flattened
+        for (id, parent) in [("s2", "s1"), ("s3", "s2")] {
+            let mut next = store.resolve(parent).unwrap();
+            next.metadata = SnapshotMetadata::new(id.to_string()).with_parent(parent.to_string());
+            next.add_entry(id.to_string(), b, 1);
+            store.create_incremental(next).unwrap();
+        }
+        assert_eq!(store.get("s2").unwrap().metadata.chain_depth, 2);
+        let flattened = store.get("s3").unwrap();
+        assert!(!flattened.is_delta());
+        assert_eq!(flattened.entry_count(), 4);
+        assert_eq!(flattened.metadata.parent_id.as_deref(), Some("s2"));
+    }
+
+    #[test]
+    fn test_snapshot_store_delete_and_compact_keep_children() {
+        let content = Arc::new(ContentStore::new());
+        let mut store = SnapshotStore::new(Arc::clone(&content));
+        let a = content.write(b"a".to_vec()).unwrap();
+        let b = content.write(b"b".to_vec()).unwrap();
+
+        let root = SnapshotBuilder::new("root".to_string()).entry("k".to_string(), a, 1).build();
+        store.create(root).unwrap();
+        let mut mid = Snapshot::with_parent("mid".to_string(), "root".to_string());
+        mid.add_entry("m".to_string(), b, 1);
+        mid.metadata.delta = true;
+        store.create(mid).unwrap();
+        let mut top = Snapshot::with_parent("top".to_string(), "mid".to_string());
+        top.removed.insert("k".to_string());
+        top.metadata.delta = true;
+        store.create(top).unwrap();
+        assert_eq!(store.get("top").unwrap().metadata.chain_depth, 2);
+
+        store.compact("mid").unwrap();
+        assert!(!store.get("mid").unwrap().is_delta());
+        assert_eq!(store.get("top").unwrap().metadata.chain_depth, 1);
+
+        assert!(store.delete("mid"));
+        let top = store.resolve("top").unwrap();
+        assert_eq!(top.keys(), vec!["m".to_string()]);
+        assert!(!store.get("top").unwrap().is_delta());
+
+        let mut orphan = Snapshot::with_parent("orphan".to_string(), "missing".to_string());
+        orphan.metadata.delta = true;
+        assert!(store.create(orphan).is_err());
+    }
 }
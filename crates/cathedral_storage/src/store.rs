@@ -1,9 +1,12 @@
 //! Content-addressed blob store.
 
-use crate::{Blob, BlobData, BlobId, address::ContentAddress};
-use cathedral_core::{CoreResult, CoreError, EventId};
+use crate::blob::{ChunkManifest, ChunkRef, ChunkingConfig};
+use crate::encrypt::{RunKey, SealedBlob};
+use crate::{Blob, BlobData, BlobId, address::AddressAlgorithm};
+use cathedral_core::{CoreResult, CoreError, RunId, TenantId};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 
@@ -18,6 +21,9 @@ pub struct StoreConfig {
     pub compression: bool,
     /// Storage directory
     pub storage_dir: String,
+    /// Split large blobs into deduplicated chunks (`None` = store whole)
+    #[serde(default)]
+    pub chunking: Option<ChunkingConfig>,
 }
 
 impl Default for StoreConfig {
@@ -27,6 +33,7 @@ impl Default for StoreConfig {
             max_storage: 10 * 1024 * 1024 * 1024, // 10 GB
             compression: true,
             storage_dir: ".cathedral/storage".to_string(),
+            chunking: Some(ChunkingConfig::default()),
         }
     }
 }
@@ -98,12 +105,103 @@ impl Default for StoreStats {
     }
 }
 
+/// A blob as held by the store: plaintext, or sealed under a run key
+enum Stored {
+    Plain(Arc<Blob>),
+    Sealed {
+        address: BlobId,
+        sealed: SealedBlob,
+        size: usize,
+        content_type: Option<String>,
+    },
+}
+
+impl Stored {
+    /// Plaintext size in bytes
+    fn size(&self) -> usize {
+        match self {
+            Self::Plain(blob) => blob.size(),
+            Self::Sealed { size, .. } => *size,
+        }
+    }
+}
+
+/// A stored chunk and the number of manifests that use it
+struct StoredChunk {
+    blob: Stored,
+    refs: usize,
+}
+
+/// A chunked blob: its manifest and the encoded manifest blob
+struct StoredManifest {
+    manifest: ChunkManifest,
+    blob: Blob,
+}
+
+/// Chunked blobs, keyed by the address of the whole payload
+#[derive(Default)]
+struct ChunkIndex {
+    manifests: HashMap<BlobId, StoredManifest>,
+    chunks: HashMap<BlobId, StoredChunk>,
+}
+
+impl ChunkIndex {
+    /// Remove a chunked blob, returning the bytes freed
+    ///
+    /// Chunks still used by another manifest are kept.
+    fn remove(&mut self, id: &BlobId) -> Option<u64> {
+        let stored = self.manifests.remove(id)?;
+        let mut freed = stored.blob.size() as u64;
+        for part in &stored.manifest.chunks {
+            if let Entry::Occupied(mut entry) = self.chunks.entry(part.id) {
+                entry.get_mut().refs -= 1;
+                if entry.get().refs == 0 {
+                    freed += entry.remove().blob.size() as u64;
+                }
+            }
+        }
+        Some(freed)
+    }
+
+    /// Reassemble a chunked blob, opening its chunks with `open`
+    fn read<F>(&self, id: &BlobId, open: F) -> CoreResult<Option<Arc<Blob>>>
+    where
+        F: Fn(&Stored) -> CoreResult<Arc<Blob>>,
+    {
+        let Some(stored) = self.manifests.get(id) else {
+            return Ok(None);
+        };
+        let mut parts = HashMap::new();
+        for part in &stored.manifest.chunks {
+            if let Some(chunk) = self.chunks.get(&part.id) {
+                parts.insert(part.id, open(&chunk.blob)?);
+            }
+        }
+        let data = stored.manifest.reassemble(|chunk| parts.get(chunk).cloned())?;
+        Ok(Some(Arc::new(Blob::from_data(data))))
+    }
+}
+
 /// In-memory content store
+///
+/// Blobs larger than the configured chunking threshold are split into
+/// content-defined chunks stored once each, plus a manifest describing how
+/// to reassemble them. Callers still read and write whole payloads by the
+/// payload's own address.
+///
+/// With a [`RunKey`] attached, blobs are sealed as they are written and
+/// opened again on read; addresses remain hashes of the plaintext.
 pub struct ContentStore {
     /// Store configuration
     config: StoreConfig,
     /// Blob storage indexed by content address
-    blobs: RwLock<HashMap<BlobId, Arc<Blob>>>,
+    blobs: RwLock<HashMap<BlobId, Stored>>,
+    /// Blobs stored as chunks
+    chunked: RwLock<ChunkIndex>,
+    /// Key sealing new writes
+    sealing: Option<RunKey>,
+    /// Keys for opening sealed blobs, by run
+    keys: RwLock<HashMap<RunId, RunKey>>,
     /// Store statistics
     stats: RwLock<StoreStats>,
 }
@@ -121,10 +219,100 @@ impl ContentStore {
         Self {
             config,
             blobs: RwLock::new(HashMap::new()),
+            chunked: RwLock::new(ChunkIndex::default()),
+            sealing: None,
+            keys: RwLock::new(HashMap::new()),
             stats: RwLock::new(StoreStats::default()),
         }
     }
 
+    /// Seal blobs written from now on with `key`
+    ///
+    /// The key is also used to open blobs the run wrote earlier.
+    #[must_use]
+    pub fn with_run_key(mut self, key: RunKey) -> Self {
+        self.add_run_key(key.clone());
+        self.sealing = Some(key);
+        self
+    }
+
+    /// Make blobs sealed by another run readable
+    pub fn add_run_key(&self, key: RunKey) {
+        self.keys.write().unwrap().insert(key.run_id(), key);
+    }
+
+    /// Whether new writes are sealed
+    #[must_use]
+    pub fn is_encrypted(&self) -> bool {
+        self.sealing.is_some()
+    }
+
+    /// Run whose key seals new writes, if any
+    pub(crate) fn sealing_run(&self) -> Option<RunId> {
+        self.sealing.as_ref().map(RunKey::run_id)
+    }
+
+    /// Runs whose sealed blobs the store can open, sealing run first
+    pub(crate) fn key_runs(&self) -> Vec<RunId> {
+        let sealing = self.sealing_run();
+        let mut runs: Vec<RunId> = self
+            .keys
+            .read()
+            .unwrap()
+            .keys()
+            .copied()
+            .filter(|run| Some(*run) != sealing)
+            .collect();
+        runs.sort();
+        sealing.into_iter().chain(runs).collect()
+    }
+
+    /// Seal `data` under the store's run key, if it has one
+    pub(crate) fn seal(&self, data: &BlobData) -> Option<SealedBlob> {
+        self.sealing.as_ref().map(|key| key.seal(&data.address, &data.data))
+    }
+
+    /// Open a blob sealed under one of the store's run keys
+    pub(crate) fn open_sealed(&self, address: &BlobId, sealed: &SealedBlob) -> CoreResult<Vec<u8>> {
+        let keys = self.keys.read().unwrap();
+        let key = keys.get(&sealed.run_id).ok_or_else(|| CoreError::PermissionDenied {
+            operation: format!("open blob {address} without the key for run {}", sealed.run_id),
+        })?;
+        key.open(address, sealed)
+    }
+
+    /// Hold `blob` as written, sealing it if the store has a run key
+    fn store(&self, blob: Blob) -> Stored {
+        match self.seal(&blob.to_data()) {
+            Some(sealed) => Stored::Sealed {
+                address: blob.id(),
+                sealed,
+                size: blob.size(),
+                content_type: blob.content_type().cloned(),
+            },
+            None => Stored::Plain(Arc::new(blob)),
+        }
+    }
+
+    /// Recover the plaintext blob
+    fn open(&self, stored: &Stored) -> CoreResult<Arc<Blob>> {
+        match stored {
+            Stored::Plain(blob) => Ok(Arc::clone(blob)),
+            Stored::Sealed {
+                address,
+                sealed,
+                content_type,
+                ..
+            } => {
+                let data = self.open_sealed(address, sealed)?;
+                let blob = BlobData::new(data, content_type.clone())
+                    .with_algorithm(address.algorithm());
+                blob.verify()?;
+                Ok(Arc::new(Blob::from_data(blob)))
+            }
+        }
+    }
+
     /// Write a blob to the store
     ///
     /// # Errors
@@ -140,8 +328,22 @@ impl ContentStore {
     ///
     /// Returns error if write fails
     pub fn write_with_type(&self, data: Vec<u8>, content_type: Option<String>) -> CoreResult<BlobId> {
+        self.insert(BlobData::new(data, content_type))
+    }
+
+    /// Write a blob addressed with `algorithm`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if write fails
+    pub fn write_with_algorithm(&self, data: Vec<u8>, algorithm: AddressAlgorithm) -> CoreResult<BlobId> {
+        self.insert(BlobData::new(data, None).with_algorithm(algorithm))
+    }
+
+    /// Insert blob data, keyed by its content address
+    fn insert(&self, data: BlobData) -> CoreResult<BlobId> {
         // Store size before moving data
-        let data_size = data.len();
+        let data_size = data.size;
 
         // Check blob size
         if self.config.max_blob_size > 0 && data_size > self.config.max_blob_size {
@@ -152,12 +354,12 @@ impl ContentStore {
             .into());
         }
 
+        if let Some(chunking) = self.config.chunking.filter(|c| c.applies_to(data_size)) {
+            return self.insert_chunked(data, &chunking);
+        }
+
         // Create blob
-        let blob = if let Some(ct) = content_type {
-            Blob::with_type(data, ct)
-        } else {
-            Blob::new(data)
-        };
+        let blob = Blob::from_data(data);
 
         let id = blob.id();
 
@@ -175,7 +377,7 @@ impl ContentStore {
             let mut blobs = self.blobs.write().unwrap();
             // Only increment stats if this is a new blob
             let is_new = !blobs.contains_key(&id);
-            blobs.insert(id, Arc::new(blob));
+            blobs.insert(id, self.store(blob));
 
             if is_new {
                 let mut stats = self.stats.write().unwrap();
@@ -188,15 +390,89 @@ impl ContentStore {
         Ok(id)
     }
 
+    /// Split blob data into chunks and store them with a manifest
+    fn insert_chunked(&self, data: BlobData, chunking: &ChunkingConfig) -> CoreResult<BlobId> {
+        let id = data.address;
+        let parts: Vec<Blob> = chunking
+            .split(&data.data)
+            .into_iter()
+            .map(|part| Blob::new(part.to_vec()))
+            .collect();
+        let manifest = ChunkManifest {
+            address: id,
+            size: data.size,
+            content_type: data.content_type,
+            chunks: parts
+                .iter()
+                .map(|part| ChunkRef {
+                    id: part.id(),
+                    size: part.size(),
+                })
+                .collect(),
+        };
+        let manifest_blob = manifest.to_blob()?;
+
+        let blobs = self.blobs.read().unwrap();
+        let mut index = self.chunked.write().unwrap();
+        if blobs.contains_key(&id) || index.manifests.contains_key(&id) {
+            return Ok(id);
+        }
+
+        // Only chunks not already held by another manifest take up space
+        let mut seen = HashSet::new();
+        let added = manifest_blob.size() as u64
+            + parts
+                .iter()
+                .filter(|part| !index.chunks.contains_key(&part.id()) && seen.insert(part.id()))
+                .map(|part| part.size() as u64)
+                .sum::<u64>();
+
+        let mut stats = self.stats.write().unwrap();
+        if self.config.max_storage > 0
+            && stats.total_bytes + added > self.config.max_storage as u64
+        {
+            return Err(StoreError::StorageFull.into());
+        }
+
+        for part in parts {
+            index
+                .chunks
+                .entry(part.id())
+                .or_insert_with(|| StoredChunk {
+                    blob: self.store(part),
+                    refs: 0,
+                })
+                .refs += 1;
+        }
+        index.manifests.insert(
+            id,
+            StoredManifest {
+                manifest,
+                blob: manifest_blob,
+            },
+        );
+        stats.blob_count += 1;
+        stats.total_bytes += added;
+        stats.write_count += 1;
+
+        Ok(id)
+    }
+
     /// Read a blob from the store
     ///
+    /// Sealed blobs are opened and chunked blobs reassembled; either is
+    /// verified against its address.
+    ///
     /// # Errors
     ///
     /// Returns error if blob not found
     pub fn read(&self, id: &BlobId) -> CoreResult<Arc<Blob>> {
-        let blobs = self.blobs.read().unwrap();
-        let blob = blobs
-            .get(id)
+        let whole = self.blobs.read().unwrap().get(id).map(|s| self.open(s)).transpose()?;
+        let blob = match whole {
+            Some(blob) => Some(blob),
+            None => self.chunked.read().unwrap().read(id, |s| self.open(s))?,
+        };
+        let blob = blob
             .ok_or_else(|| StoreError::NotFound {
                 id: id.to_string(),
             })
@@ -206,19 +482,33 @@ impl ContentStore {
             })?;
 
         // Update read stats
-        drop(blobs);
-        let mut stats = self.stats.write().unwrap();
-        stats.read_count += 1;
+        self.stats.write().unwrap().read_count += 1;
 
-        // Return the blob
-        let blobs = self.blobs.read().unwrap();
-        Ok(blobs.get(id).cloned().unwrap())
+        Ok(blob)
     }
 
     /// Check if a blob exists
     #[must_use]
     pub fn contains(&self, id: &BlobId) -> bool {
         self.blobs.read().unwrap().contains_key(id)
+            || self.chunked.read().unwrap().manifests.contains_key(id)
+    }
+
+    /// Get the chunk manifest of a chunked blob
+    #[must_use]
+    pub fn manifest(&self, id: &BlobId) -> Option<ChunkManifest> {
+        self.chunked
+            .read()
+            .unwrap()
+            .manifests
+            .get(id)
+            .map(|stored| stored.manifest.clone())
+    }
+
+    /// Get the number of distinct chunks held for chunked blobs
+    #[must_use]
+    pub fn chunk_count(&self) -> usize {
+        self.chunked.read().unwrap().chunks.len()
     }
 
     /// Delete a blob from the store
@@ -227,15 +517,32 @@ impl ContentStore {
     ///
     /// Returns error if deletion fails
     pub fn delete(&self, id: &BlobId) -> CoreResult<bool> {
+        Ok(self.delete_batch(std::slice::from_ref(id)).0 == 1)
+    }
+
+    /// Delete a set of blobs under a single write lock
+    ///
+    /// Readers observe either all of the blobs or none of them. Returns the
+    /// number of blobs deleted and the bytes they held.
+    pub fn delete_batch(&self, ids: &[BlobId]) -> (usize, u64) {
         let mut blobs = self.blobs.write().unwrap();
-        if let Some(blob) = blobs.remove(id) {
-            let mut stats = self.stats.write().unwrap();
-            stats.blob_count -= 1;
-            stats.total_bytes -= blob.size() as u64;
-            Ok(true)
-        } else {
-            Ok(false)
+        let mut index = self.chunked.write().unwrap();
+        let mut stats = self.stats.write().unwrap();
+        let mut deleted = 0;
+        let mut bytes = 0;
+        for id in ids {
+            let freed = match blobs.remove(id) {
+                Some(blob) => Some(blob.size() as u64),
+                None => index.remove(id),
+            };
+            if let Some(freed) = freed {
+                stats.blob_count -= 1;
+                stats.total_bytes -= freed;
+                deleted += 1;
+                bytes += freed;
+            }
         }
+        (deleted, bytes)
     }
 
     /// Get store statistics
@@ -245,19 +552,19 @@ impl ContentStore {
     }
 
     /// List all blob IDs
+    ///
+    /// Chunked blobs are listed by their payload address, not their chunks.
     #[must_use]
     pub fn list(&self) -> Vec<BlobId> {
-        self.blobs
-            .read()
-            .unwrap()
-            .keys()
-            .cloned()
-            .collect()
+        let mut ids: Vec<BlobId> = self.blobs.read().unwrap().keys().cloned().collect();
+        ids.extend(self.chunked.read().unwrap().manifests.keys().cloned());
+        ids
     }
 
     /// Clear all blobs from the store
     pub fn clear(&self) {
         self.blobs.write().unwrap().clear();
+        *self.chunked.write().unwrap() = ChunkIndex::default();
         *self.stats.write().unwrap() = StoreStats::default();
     }
 
@@ -286,6 +593,8 @@ pub struct FsContentStore {
     memory: ContentStore,
     /// Storage directory
     dir: String,
+    /// Whether blobs are also kept in memory
+    cache: bool,
 }
 
 impl FsContentStore {
@@ -303,23 +612,79 @@ impl FsContentStore {
         Ok(Self {
             memory: ContentStore::new(),
             dir,
+            cache: true,
         })
     }
 
+    /// Create a store in `tenant`'s own directory under `root`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if directory creation fails
+    pub fn for_tenant(root: &str, tenant: TenantId) -> CoreResult<Self> {
+        let dir = std::path::Path::new(root).join("tenants").join(tenant.to_string());
+        Self::new(dir.to_string_lossy().into_owned())
+    }
+
+    /// Keep blobs on disk only, so memory use does not grow with the store
+    #[must_use]
+    pub fn without_cache(mut self) -> Self {
+        self.cache = false;
+        self
+    }
+
+    /// Seal blob files written from now on with `key`
+    ///
+    /// Sealed files are kept in a directory of the run's own, so runs that
+    /// write the same content never overwrite each other's files.
+    #[must_use]
+    pub fn with_run_key(mut self, key: RunKey) -> Self {
+        self.memory = self.memory.with_run_key(key);
+        self
+    }
+
+    /// Make blob files sealed by another run readable
+    pub fn add_run_key(&self, key: RunKey) {
+        self.memory.add_run_key(key);
+    }
+
     /// Write a blob to persistent storage
     ///
     /// # Errors
     ///
     /// Returns error if write fails
     pub fn write(&self, data: Vec<u8>) -> CoreResult<BlobId> {
-        let id = self.memory.write(data.clone())?;
-        let path = self.blob_path(&id);
+        self.write_with_algorithm(data, AddressAlgorithm::Blake3)
+    }
+
+    /// Write a blob addressed with `algorithm` to persistent storage
+    ///
+    /// # Errors
+    ///
+    /// Returns error if write fails
+    pub fn write_with_algorithm(&self, data: Vec<u8>, algorithm: AddressAlgorithm) -> CoreResult<BlobId> {
+        let blob = BlobData::new(data, None).with_algorithm(algorithm);
+        let id = blob.address;
+        let path = self.written_path(&id);
 
-        std::fs::write(&path, data).map_err(|e| CoreError::Validation {
+        let contents = match self.memory.seal(&blob) {
+            Some(sealed) => sealed.encode(),
+            None => blob.data.clone(),
+        };
+        if let Some(parent) = Path::new(&path).parent() {
+            std::fs::create_dir_all(parent).map_err(|e| CoreError::Validation {
+                field: "write".to_string(),
+                reason: format!("Failed to create blob directory: {}", e),
+            })?;
+        }
+        std::fs::write(&path, contents).map_err(|e| CoreError::Validation {
             field: "write".to_string(),
             reason: format!("Failed to write blob: {}", e),
         })?;
 
+        if self.cache {
+            self.memory.insert(blob)?;
+        }
         Ok(id)
     }
 
@@ -334,22 +699,70 @@ impl FsContentStore {
             return self.memory.read(id);
         }
 
-        // Load from disk
-        let path = self.blob_path(id);
+        // Load from disk: the shared file, else one sealed by a run we hold the key of
+        let path = std::iter::once(self.blob_path(id))
+            .chain(self.memory.key_runs().into_iter().map(|run| self.sealed_path(run, id)))
+            .find(|path| Path::new(path).exists())
+            .unwrap_or_else(|| self.blob_path(id));
         let data = std::fs::read(&path).map_err(|e| CoreError::Validation {
             field: "read".to_string(),
             reason: format!("Failed to read blob: {}", e),
         })?;
 
+        // Rehash with the requested algorithm so corrupted files are rejected
+        let mut blob = BlobData::new(data, None).with_algorithm(id.algorithm());
+        if blob.address != *id
+            && let Some(sealed) = SealedBlob::decode(&blob.data)
+        {
+            let plaintext = self.memory.open_sealed(id, &sealed)?;
+            blob = BlobData::new(plaintext, None).with_algorithm(id.algorithm());
+        }
+        if blob.address != *id {
+            return Err(CoreError::Validation {
+                field: "read".to_string(),
+                reason: format!("Blob content does not match address {}", id),
+            });
+        }
+
+        if !self.cache {
+            return Ok(Arc::new(Blob::from_data(blob)));
+        }
+
         // Insert into memory and return
-        self.memory.write(data)?;
+        self.memory.insert(blob)?;
         self.memory.read(id)
     }
 
-    /// Get blob file path
+    /// Flush a written blob to durable storage
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the blob file cannot be opened or synced
+    pub fn sync(&self, id: &BlobId) -> CoreResult<()> {
+        std::fs::File::open(self.written_path(id))
+            .and_then(|file| file.sync_all())
+            .map_err(|e| CoreError::Validation {
+                field: "sync".to_string(),
+                reason: format!("Failed to sync blob: {}", e),
+            })
+    }
+
+    /// Get the path of a plaintext blob file
     fn blob_path(&self, id: &BlobId) -> String {
-        let hex = id.hash.to_hex();
-        format!("{}/{}.blob", self.dir, hex)
+        format!("{}/{}", self.dir, blob_file_name(id))
+    }
+
+    /// Get the path of a blob file sealed under `run`'s key
+    fn sealed_path(&self, run: RunId, id: &BlobId) -> String {
+        format!("{}/sealed/{}/{}", self.dir, run, blob_file_name(id))
+    }
+
+    /// Get the path this store writes `id` to
+    fn written_path(&self, id: &BlobId) -> String {
+        match self.memory.sealing_run() {
+            Some(run) => self.sealed_path(run, id),
+            None => self.blob_path(id),
+        }
     }
 
     /// Get store statistics
@@ -359,9 +772,23 @@ impl FsContentStore {
     }
 }
 
+/// File name of a blob
+///
+/// BLAKE3 blobs keep the bare hex name; other algorithms are prefixed
+/// so equal digests under different algorithms cannot collide.
+fn blob_file_name(id: &BlobId) -> String {
+    let hex = id.hash.to_hex();
+    match id.algorithm() {
+        AddressAlgorithm::Blake3 => format!("{}.blob", hex),
+        algorithm => format!("{}-{}.blob", algorithm, hex),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::address::ContentAddress;
+    use crate::encrypt::MasterKey;
 
     #[test]
     fn test_store_config_default() {
@@ -472,4 +899,132 @@ mod tests {
         // Stats should only count unique blobs
         assert_eq!(store.stats().blob_count, 1);
     }
+
+    #[test]
+    fn test_fs_store_mixed_algorithms() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_string_lossy().into_owned();
+        let data = b"mixed".to_vec();
+
+        let store = FsContentStore::new(path.clone()).unwrap();
+        let ids: Vec<BlobId> = [AddressAlgorithm::Blake3, AddressAlgorithm::Sha256, AddressAlgorithm::Sha512]
+            .into_iter()
+            .map(|algorithm| store.write_with_algorithm(data.clone(), algorithm).unwrap())
+            .collect();
+        assert_eq!(store.stats().blob_count, 3);
+
+        let reopened = FsContentStore::new(path).unwrap();
+        for id in &ids {
+            let blob = reopened.read(id).unwrap();
+            assert_eq!(blob.id(), *id);
+            assert_eq!(blob.as_bytes(), data.as_slice());
+        }
+
+        std::fs::write(reopened.blob_path(&ids[1]), b"corrupt").unwrap();
+        let fresh = FsContentStore::new(dir.path().to_string_lossy().into_owned()).unwrap();
+        assert!(fresh.read(&ids[1]).is_err());
+    }
+
+    #[test]
+    fn test_fs_store_without_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FsContentStore::new(dir.path().to_string_lossy().into_owned())
+            .unwrap()
+            .without_cache();
+
+        let id = store.write(b"spilled".to_vec()).unwrap();
+        assert_eq!(store.stats().blob_count, 0);
+        assert_eq!(store.read(&id).unwrap().as_bytes(), b"spilled");
+        assert_eq!(store.stats().blob_count, 0);
+    }
+
+    #[test]
+    fn test_store_chunks_large_blobs() {
+        let store = ContentStore::new();
+        let mut state = 3u64;
+        let base: Vec<u8> = (0..2 * 1024 * 1024)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let mut edited = base.clone();
+        edited.splice(1000..1000, b"inserted".iter().copied());
+
+        let first = store.write(base.clone()).unwrap();
+        let chunks = store.chunk_count();
+        assert!(chunks > 1);
+        assert_eq!(first, ContentAddress::compute(&base));
+        let second = store.write(edited.clone()).unwrap();
+        assert_eq!(store.write(edited.clone()).unwrap(), second);
+
+        // The edit shares all but a couple of chunks with the original
+        assert!(store.chunk_count() <= chunks + 2);
+        assert!(store.size() < (base.len() + edited.len()) as u64);
+        assert_eq!(store.count(), 2);
+        assert_eq!(store.list().len(), 2);
+        assert_eq!(store.manifest(&first).unwrap().size, base.len());
+
+        assert_eq!(store.read(&first).unwrap().as_bytes(), &base[..]);
+        assert!(store.delete(&first).unwrap());
+        assert!(!store.contains(&first));
+        assert_eq!(store.read(&second).unwrap().as_bytes(), &edited[..]);
+        assert!(store.delete(&second).unwrap());
+        assert_eq!(store.chunk_count(), 0);
+        assert_eq!(store.size(), 0);
+        assert_eq!(store.count(), 0);
+    }
+
+    #[test]
+    fn test_store_seals_blobs_with_run_key() {
+        let master = MasterKey::new("primary", [9; 32]);
+        let run_key = master.generate_run_key(RunId::new()).unwrap();
+        let store = ContentStore::new().with_run_key(run_key.clone());
+        assert!(store.is_encrypted());
+
+        let small = store.write(b"tool output".to_vec()).unwrap();
+        assert_eq!(small, ContentAddress::compute(b"tool output"));
+        assert_eq!(store.write(b"tool output".to_vec()).unwrap(), small);
+        assert_eq!(store.read(&small).unwrap().as_bytes(), b"tool output");
+        assert_eq!(store.size(), 11);
+
+        let large: Vec<u8> = (0..600_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let chunked = store.write(large.clone()).unwrap();
+        assert!(store.chunk_count() > 0);
+        assert_eq!(store.read(&chunked).unwrap().as_bytes(), &large[..]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap().to_string();
+        let fs = FsContentStore::new(path.clone()).unwrap().with_run_key(run_key.clone());
+        let id = fs.write(b"sealed on disk".to_vec()).unwrap();
+        let on_disk = std::fs::read(fs.written_path(&id)).unwrap();
+        assert!(SealedBlob::decode(&on_disk).is_some());
+
+        // A fresh process can read the blob once it unwraps the run key
+        let reopened = FsContentStore::new(path).unwrap();
+        assert!(reopened.read(&id).is_err());
+        reopened.add_run_key(master.unwrap_key(run_key.wrapped()).unwrap());
+        assert_eq!(reopened.read(&id).unwrap().as_bytes(), b"sealed on disk");
+    }
+
+    #[test]
+    fn test_fs_store_runs_sealing_same_content() {
+        let master = MasterKey::new("primary", [9; 32]);
+        let first_key = master.generate_run_key(RunId::new()).unwrap();
+        let second_key = master.generate_run_key(RunId::new()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap().to_string();
+
+        let first = FsContentStore::new(path.clone()).unwrap().without_cache().with_run_key(first_key);
+        let id = first.write(b"same output".to_vec()).unwrap();
+        let second = FsContentStore::new(path).unwrap().without_cache().with_run_key(second_key);
+        assert_eq!(second.write(b"same output".to_vec()).unwrap(), id);
+
+        // Neither run's file replaced the other's
+        assert_eq!(first.read(&id).unwrap().as_bytes(), b"same output");
+        assert_eq!(second.read(&id).unwrap().as_bytes(), b"same output");
+        assert_ne!(first.written_path(&id), second.written_path(&id));
+    }
 }
@@ -1,9 +1,18 @@
 //! Tool adapter for sandboxed execution.
 
-use cathedral_core::{Capability, CapabilitySet, CoreResult, CoreError};
+use cathedral_core::{CancellationToken, Capability, CapabilitySet, CoreResult, CoreError};
 use crate::trait_::{Tool, ToolOutput};
+use crate::normalize::{NormalizedOutput, Normalizer};
 use crate::registry::SharedRegistry;
+use crate::sandbox::{self, Access, Confinement};
+use crate::schema::{SideEffect, ToolSchema};
+use crate::validate::{SideEffectTracker, ToolValidator, ValidationError};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 /// Error from adapter operations
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -18,6 +27,8 @@ pub enum AdapterError {
     InvalidInput { reason: String },
     /// Invalid output
     InvalidOutput { reason: String },
+    /// The run was cancelled while the tool was executing
+    Cancelled,
 }
 
 impl std::fmt::Display for AdapterError {
@@ -30,6 +41,7 @@ impl std::fmt::Display for AdapterError {
             Self::Timeout => write!(f, "Execution timeout"),
             Self::InvalidInput { reason } => write!(f, "Invalid input: {}", reason),
             Self::InvalidOutput { reason } => write!(f, "Invalid output: {}", reason),
+            Self::Cancelled => write!(f, "Execution cancelled"),
         }
     }
 }
@@ -45,6 +57,47 @@ impl From<AdapterError> for CoreError {
     }
 }
 
+/// Report of what a tool invocation would need and do, produced without executing it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DryRunReport {
+    /// Tool name
+    pub tool: String,
+    /// Tool version
+    pub version: String,
+    /// Input validation failure, if the input does not match the schema
+    pub input_error: Option<String>,
+    /// Capabilities the tool declares it requires
+    pub required_capabilities: Vec<Capability>,
+    /// Required capabilities not covered by the provided set
+    pub missing_capabilities: Vec<Capability>,
+    /// Side effects the tool declares it performs
+    pub side_effects: Vec<SideEffect>,
+}
+
+impl DryRunReport {
+    /// Check if the invocation would pass input and capability checks
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.input_error.is_none() && self.missing_capabilities.is_empty()
+    }
+
+    /// Get the first problem that would stop execution, if any
+    #[must_use]
+    pub fn first_error(&self) -> Option<AdapterError> {
+        if let Some(reason) = &self.input_error {
+            return Some(AdapterError::InvalidInput {
+                reason: reason.clone(),
+            });
+        }
+
+        self.missing_capabilities
+            .first()
+            .map(|cap| AdapterError::CapabilityDenied {
+                capability: cap.to_string(),
+            })
+    }
+}
+
 /// Adapter for executing tools with capability checking
 pub struct ToolAdapter {
     /// The underlying tool
@@ -53,6 +106,10 @@ pub struct ToolAdapter {
     capabilities: CapabilitySet,
     /// Timeout in logical ticks (0 = no limit)
     timeout_ticks: u64,
+    /// Declared schema, if known
+    schema: Option<ToolSchema>,
+    /// Token that stops the tool when its run is cancelled
+    cancellation: Option<CancellationToken>,
 }
 
 impl ToolAdapter {
@@ -63,6 +120,8 @@ impl ToolAdapter {
             tool,
             capabilities: CapabilitySet::new(),
             timeout_ticks: 0,
+            schema: None,
+            cancellation: None,
         }
     }
 
@@ -80,6 +139,28 @@ impl ToolAdapter {
         self
     }
 
+    /// Set the tool's declared schema
+    #[must_use]
+    pub fn with_schema(mut self, schema: ToolSchema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Stop the tool when `token` is cancelled
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Run the tool, interruptible by the adapter's token if it has one
+    fn run_tool(&self, input: &[u8]) -> CoreResult<ToolOutput> {
+        match &self.cancellation {
+            Some(token) => self.tool.execute_cancellable(input, token),
+            None => self.tool.execute(input),
+        }
+    }
+
     /// Check if tool has required capabilities
     fn check_capabilities(&self, required: &[Capability]) -> Result<(), AdapterError> {
         for cap in required {
@@ -92,6 +173,39 @@ impl ToolAdapter {
         Ok(())
     }
 
+    /// Report what executing the tool on `input` would need and do, without executing it
+    ///
+    /// Validates the input against the schema, checks the schema's required
+    /// capabilities against the adapter's capability set, and lists declared
+    /// side effects. A tool without a schema requires nothing and declares nothing.
+    #[must_use]
+    pub fn dry_run(&self, input: &[u8]) -> DryRunReport {
+        let schema = self.schema.clone().unwrap_or_else(|| {
+            ToolSchema::new(self.tool.name().to_string(), self.tool.version().to_string())
+        });
+
+        let input_error = ToolValidator::new()
+            .validate_input(input, &schema)
+            .err()
+            .map(|e: ValidationError| e.to_string());
+
+        let required_capabilities: Vec<Capability> = schema.capabilities.iter().cloned().collect();
+        let missing_capabilities = required_capabilities
+            .iter()
+            .filter(|cap| !self.capabilities.covers(cap))
+            .cloned()
+            .collect();
+
+        DryRunReport {
+            tool: schema.name,
+            version: schema.version,
+            input_error,
+            required_capabilities,
+            missing_capabilities,
+            side_effects: schema.side_effects,
+        }
+    }
+
     /// Execute the tool with capability checking
     ///
     /// # Errors
@@ -100,7 +214,59 @@ impl ToolAdapter {
     pub fn execute(&self, input: &[u8]) -> CoreResult<ToolOutput> {
         // For now, we execute without specific capability requirements
         // In a full implementation, the tool would declare its required capabilities
-        self.tool.execute(input)
+        self.run_tool(input)
+    }
+
+    /// Execute the tool, enforcing its schema on both sides of the call
+    ///
+    /// Checks declared capabilities and validates input before execution,
+    /// and validates the output and reported side effects of a successful
+    /// run, then canonicalizes its data with the schema's normalization
+    /// rules. Without a schema this behaves like [`ToolAdapter::execute`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a capability is missing, input or output fails
+    /// validation or normalization, the tool performed an undeclared side
+    /// effect, or execution fails
+    pub fn execute_validated(&self, input: &[u8]) -> CoreResult<ToolOutput> {
+        let Some(schema) = &self.schema else {
+            return self.execute(input);
+        };
+
+        if let Some(err) = self.dry_run(input).first_error() {
+            return Err(err.into());
+        }
+
+        let mut output = self.run_tool(input)?;
+        if output.is_success() {
+            ToolValidator::new()
+                .validate_output(&output.data, schema)
+                .map_err(|e| AdapterError::InvalidOutput {
+                    reason: e.to_string(),
+                })?;
+
+            let mut tracker = SideEffectTracker::new(schema.side_effects.clone())
+                .with_budgets(schema.effect_budgets.clone());
+            let invalid = |e: ValidationError| AdapterError::InvalidOutput {
+                reason: e.to_string(),
+            };
+            for effect in &output.side_effects {
+                tracker.try_record_effect(effect.clone()).map_err(invalid)?;
+            }
+            tracker.check().map_err(invalid)?;
+
+            if !schema.normalization.is_empty() {
+                output.data = Normalizer::for_schema(schema)
+                    .normalize(&output.data)
+                    .and_then(|normalized| normalized.to_bytes())
+                    .map_err(|e| AdapterError::InvalidOutput {
+                        reason: e.to_string(),
+                    })?;
+            }
+        }
+
+        Ok(output)
     }
 }
 
@@ -110,6 +276,8 @@ pub struct HostAdapter {
     tools: Arc<SharedRegistry>,
     /// Global capability set
     capabilities: CapabilitySet,
+    /// Token that stops tools when their run is cancelled
+    cancellation: Option<CancellationToken>,
 }
 
 impl HostAdapter {
@@ -119,6 +287,7 @@ impl HostAdapter {
         Self {
             tools,
             capabilities: CapabilitySet::new(),
+            cancellation: None,
         }
     }
 
@@ -129,6 +298,22 @@ impl HostAdapter {
         self
     }
 
+    /// Stop executing tools when `token` is cancelled
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Adapter for a tool under this host's capabilities and token
+    fn adapter(&self, tool: Arc<dyn Tool>) -> ToolAdapter {
+        let adapter = ToolAdapter::new(tool).with_capabilities(self.capabilities.clone());
+        match &self.cancellation {
+            Some(token) => adapter.with_cancellation(token.clone()),
+            None => adapter,
+        }
+    }
+
     /// Execute a tool by name
     ///
     /// # Errors
@@ -136,8 +321,31 @@ impl HostAdapter {
     /// Returns error if tool not found or execution fails
     pub fn execute_tool(&self, name: &str, input: &[u8]) -> CoreResult<ToolOutput> {
         let tool = self.tools.get(name)?;
-        let adapter = ToolAdapter::new(tool).with_capabilities(self.capabilities.clone());
-        adapter.execute(input)
+        self.adapter(tool).execute(input)
+    }
+
+    /// Resolve a tool by name and version requirement, then run it with validated I/O
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the tool cannot be resolved, a capability is missing,
+    /// validation fails, or execution fails
+    pub fn invoke(&self, name: &str, version_req: &str, input: &[u8]) -> CoreResult<ToolOutput> {
+        let entry = self.tools.resolve(name, version_req)?;
+        self.adapter(entry.tool).with_schema(entry.schema).execute_validated(input)
+    }
+
+    /// Dry-run a tool by name against the global capability set
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tool not found
+    pub fn dry_run_tool(&self, name: &str, input: &[u8]) -> CoreResult<DryRunReport> {
+        let entry = self.tools.get_entry(name)?;
+        let adapter = ToolAdapter::new(entry.tool)
+            .with_capabilities(self.capabilities.clone())
+            .with_schema(entry.schema);
+        Ok(adapter.dry_run(input))
     }
 
     /// List available tools
@@ -154,6 +362,397 @@ impl HostAdapter {
     }
 }
 
+/// OS resource limits derived from a granted `Exec` capability
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// CPU time limit in seconds (`None` = no limit)
+    pub cpu_seconds: Option<u64>,
+    /// Address space limit in bytes (`None` = no limit)
+    pub memory_bytes: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Parse the limits of an `Exec` capability
+    ///
+    /// `cpu_limit` is CPU time: seconds, optionally suffixed `s`, `ms`
+    /// (rounded up to whole seconds), or `m`. `mem_limit` is bytes,
+    /// optionally suffixed `K`, `M`, or `G` (with or without a trailing `B`
+    /// or `iB`; all binary multiples). An empty limit means no limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if either limit is malformed or zero
+    pub fn parse(cpu_limit: &str, mem_limit: &str) -> Result<Self, AdapterError> {
+        Ok(Self {
+            cpu_seconds: parse_limit(cpu_limit, "cpu_limit", |unit| match unit {
+                "" | "s" => Some((1, 1)),
+                "ms" => Some((1, 1000)),
+                "m" => Some((60, 1)),
+                _ => None,
+            })?,
+            memory_bytes: parse_limit(mem_limit, "mem_limit", |unit| {
+                let unit = unit.trim_end_matches("iB").trim_end_matches('B');
+                match unit.to_ascii_uppercase().as_str() {
+                    "" => Some((1, 1)),
+                    "K" => Some((1 << 10, 1)),
+                    "M" => Some((1 << 20, 1)),
+                    "G" => Some((1 << 30, 1)),
+                    _ => None,
+                }
+            })?,
+        })
+    }
+
+    /// The tightest limits across every `Exec` capability in `capabilities`
+    ///
+    /// Returns `None` if no `Exec` capability is granted.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a granted `Exec` capability has malformed limits
+    pub fn from_capabilities(capabilities: &CapabilitySet) -> Result<Option<Self>, AdapterError> {
+        let mut limits: Option<Self> = None;
+        for cap in capabilities.iter() {
+            if let Capability::Exec { cpu_limit, mem_limit } = cap {
+                let parsed = Self::parse(cpu_limit, mem_limit)?;
+                limits = Some(match limits {
+                    Some(current) => current.tightest(parsed),
+                    None => parsed,
+                });
+            }
+        }
+        Ok(limits)
+    }
+
+    fn tightest(self, other: Self) -> Self {
+        let min = |a: Option<u64>, b: Option<u64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Self {
+            cpu_seconds: min(self.cpu_seconds, other.cpu_seconds),
+            memory_bytes: min(self.memory_bytes, other.memory_bytes),
+        }
+    }
+}
+
+/// Parse `<number><unit>`; `scale` maps a unit to a multiplier and divisor
+fn parse_limit(
+    value: &str,
+    field: &str,
+    scale: impl Fn(&str) -> Option<(u64, u64)>,
+) -> Result<Option<u64>, AdapterError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let invalid = || AdapterError::InvalidInput {
+        reason: format!("invalid {}: {:?}", field, value),
+    };
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let (multiplier, divisor) = scale(unit.trim()).ok_or_else(invalid)?;
+    let limit = number
+        .checked_mul(multiplier)
+        .ok_or_else(invalid)?
+        .div_ceil(divisor);
+    if limit == 0 {
+        return Err(invalid());
+    }
+    Ok(Some(limit))
+}
+
+/// Paths an external tool may read and execute regardless of its capabilities
+///
+/// These hold the shell, core utilities, and the dynamic loader's libraries
+/// that almost every binary needs to start.
+pub const DEFAULT_RUNTIME_PATHS: &[&str] = &["/bin", "/lib", "/lib64", "/usr", "/etc/ld.so.cache"];
+
+/// Default wall-clock length of one logical tick for subprocess timeouts
+pub const DEFAULT_TICK: Duration = Duration::from_millis(1);
+
+/// Adapter that runs an external binary as a tool
+///
+/// The input is written to the process's stdin. Stdout becomes the output
+/// data, normalized when it is JSON; raw stdout and stderr are kept
+/// alongside. The process is confined by its capability set:
+///
+/// - it must hold an `Exec` capability, whose limits become `RLIMIT_CPU`
+///   and `RLIMIT_AS`
+/// - filesystem access is limited, via Landlock, to the `FsRead` and
+///   `FsWrite` prefixes plus read/execute access to the runtime paths and
+///   read/write access to `/dev/null`
+/// - the environment is cleared except for `EnvRead` variables
+///
+/// A process that outlives its timeout is killed with its whole process
+/// group. Confinement is Linux only; elsewhere, and on kernels without
+/// Landlock, execution is refused rather than run unconfined.
+pub struct ProcessAdapter {
+    /// Tool name
+    name: String,
+    /// Tool version
+    version: String,
+    /// Binary to run
+    program: PathBuf,
+    /// Arguments passed to the binary
+    args: Vec<String>,
+    /// Allowed capabilities
+    capabilities: CapabilitySet,
+    /// Paths readable and executable regardless of capabilities
+    runtime_paths: Vec<PathBuf>,
+    /// Timeout in logical ticks (0 = no limit)
+    timeout_ticks: u64,
+    /// Wall-clock length of one tick
+    tick: Duration,
+    /// Token that kills the process when its run is cancelled
+    cancellation: Option<CancellationToken>,
+}
+
+impl ProcessAdapter {
+    /// Create an adapter running `program` as the tool `name`
+    #[must_use]
+    pub fn new(name: &str, program: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            program: program.into(),
+            args: Vec::new(),
+            capabilities: CapabilitySet::new(),
+            runtime_paths: DEFAULT_RUNTIME_PATHS.iter().map(PathBuf::from).collect(),
+            timeout_ticks: 0,
+            tick: DEFAULT_TICK,
+            cancellation: None,
+        }
+    }
+
+    /// Set the tool version
+    #[must_use]
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = version.to_string();
+        self
+    }
+
+    /// Append an argument passed to the binary
+    #[must_use]
+    pub fn with_arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// Set allowed capabilities
+    #[must_use]
+    pub fn with_capabilities(mut self, capabilities: CapabilitySet) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Replace the paths readable and executable regardless of capabilities
+    #[must_use]
+    pub fn with_runtime_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.runtime_paths = paths;
+        self
+    }
+
+    /// Set timeout in logical ticks
+    #[must_use]
+    pub fn with_timeout(mut self, ticks: u64) -> Self {
+        self.timeout_ticks = ticks;
+        self
+    }
+
+    /// Set the wall-clock length of one tick
+    #[must_use]
+    pub fn with_tick_duration(mut self, tick: Duration) -> Self {
+        self.tick = tick;
+        self
+    }
+
+    /// Kill the process group when `token` is cancelled
+    ///
+    /// The token passed to [`Tool::execute_cancellable`] kills it as well.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Resource limits the process would run under
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no `Exec` capability is granted or its limits are
+    /// malformed
+    pub fn limits(&self) -> Result<ResourceLimits, AdapterError> {
+        ResourceLimits::from_capabilities(&self.capabilities)?.ok_or_else(|| {
+            AdapterError::CapabilityDenied {
+                capability: "Exec".to_string(),
+            }
+        })
+    }
+
+    fn confinement(&self) -> Result<Confinement, AdapterError> {
+        let limits = self.limits()?;
+        let mut rules: Vec<(PathBuf, Access)> = self
+            .runtime_paths
+            .iter()
+            .chain(std::iter::once(&self.program))
+            .map(|path| (path.clone(), Access::Execute))
+            .collect();
+        // Discarding output is not a filesystem effect worth gating
+        rules.push((PathBuf::from("/dev/null"), Access::Read));
+        rules.push((PathBuf::from("/dev/null"), Access::Write));
+        for cap in self.capabilities.iter() {
+            let (prefixes, access) = match cap {
+                Capability::FsRead { prefixes } => (prefixes, Access::Read),
+                Capability::FsWrite { prefixes } => (prefixes, Access::Write),
+                _ => continue,
+            };
+            rules.extend(prefixes.iter().map(|prefix| {
+                // `*` grants the whole filesystem, as in `CapabilitySet::allows`
+                let path = if prefix == "*" { "/" } else { prefix.as_str() };
+                (PathBuf::from(path), access)
+            }));
+        }
+        Ok(Confinement {
+            cpu_seconds: limits.cpu_seconds,
+            memory_bytes: limits.memory_bytes,
+            rules,
+        })
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<ToolOutput, AdapterError> {
+        let confinement = self.confinement()?;
+        let failed = |e: std::io::Error| AdapterError::ExecutionFailed {
+            reason: format!("{}: {}", self.program.display(), e),
+        };
+
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .env_clear()
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for (key, value) in std::env::vars_os() {
+            if key.to_str().is_some_and(|key| self.capabilities.can_read_env(key)) {
+                command.env(key, value);
+            }
+        }
+        let mut child = sandbox::spawn(command, &confinement).map_err(failed)?;
+
+        // Pipes are drained on their own threads so a chatty tool cannot
+        // block on a full pipe while we wait for it to exit.
+        let stdin = child.stdin.take().map(|mut pipe| {
+            let input = input.to_vec();
+            std::thread::spawn(move || {
+                // A tool that exits without reading its input is not an error
+                let _ = pipe.write_all(&input);
+            })
+        });
+        let stdout = child.stdout.take().map(read_pipe);
+        let stderr = child.stderr.take().map(read_pipe);
+
+        let deadline = (self.timeout_ticks > 0).then(|| {
+            let ticks = u32::try_from(self.timeout_ticks).unwrap_or(u32::MAX);
+            Instant::now() + self.tick.saturating_mul(ticks)
+        });
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(failed)? {
+                break status;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                sandbox::kill_group(&mut child);
+                let _ = child.wait();
+                return Err(AdapterError::Timeout);
+            }
+            if self.cancellation.iter().chain(cancellation).any(CancellationToken::is_cancelled) {
+                sandbox::kill_group(&mut child);
+                let _ = child.wait();
+                return Err(AdapterError::Cancelled);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        };
+        // Processes the tool left behind share its pipes, so reading them to
+        // the end would wait for those processes as well
+        sandbox::kill_group(&mut child);
+
+        if let Some(handle) = stdin {
+            let _ = handle.join();
+        }
+        let join = |handle: Option<std::thread::JoinHandle<Vec<u8>>>| {
+            handle.and_then(|h| h.join().ok()).unwrap_or_default()
+        };
+        let stdout = join(stdout);
+        let stderr = join(stderr);
+
+        let mut output = match exit_code(status) {
+            0 => ToolOutput::success(normalize_stdout(&stdout)),
+            code => ToolOutput::failure(code, Vec::new()),
+        };
+        output.stdout = stdout;
+        output.stderr = stderr;
+        Ok(output)
+    }
+}
+
+impl Tool for ProcessAdapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn execute(&self, input: &[u8]) -> CoreResult<ToolOutput> {
+        Ok(self.run(input, None)?)
+    }
+
+    fn execute_cancellable(
+        &self,
+        input: &[u8],
+        cancellation: &CancellationToken,
+    ) -> CoreResult<ToolOutput> {
+        Ok(self.run(input, Some(cancellation))?)
+    }
+
+    fn timeout_ticks(&self) -> u64 {
+        self.timeout_ticks
+    }
+}
+
+fn read_pipe(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// Exit code of a finished process; death by signal `n` maps to `128 + n`
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(-1)
+}
+
+/// Canonical JSON if stdout is JSON, otherwise stdout unchanged
+fn normalize_stdout(stdout: &[u8]) -> Vec<u8> {
+    NormalizedOutput::from_bytes(stdout)
+        .and_then(|normalized| normalized.to_bytes())
+        .unwrap_or_else(|_| stdout.to_vec())
+}
+
 /// Builtin tools for common operations
 pub mod builtin {
     use super::*;
@@ -190,6 +789,27 @@ pub mod builtin {
         }
     }
 
+    /// Write tool - reports writing its input to the path given in its name
+    ///
+    /// Performs no I/O itself; it records the `FsWrite` effect so hosts can
+    /// exercise effect tracking deterministically.
+    pub struct WriteTool {
+        /// Path the tool reports writing to
+        pub path: String,
+    }
+
+    impl Tool for WriteTool {
+        fn name(&self) -> &str {
+            "write"
+        }
+
+        fn execute(&self, input: &[u8]) -> CoreResult<ToolOutput> {
+            Ok(ToolOutput::success(input.to_vec()).with_side_effect(SideEffect::FsWrite {
+                path: self.path.clone(),
+            }))
+        }
+    }
+
     /// Concat tool - concatenates input parts
     pub struct ConcatTool;
 
@@ -236,6 +856,117 @@ mod tests {
         assert_eq!(result.unwrap().data, b"hello");
     }
 
+    #[test]
+    fn test_tool_adapter_dry_run() {
+        use crate::schema::InputSchema;
+
+        let schema = ToolSchema::new("echo".to_string(), "1.0.0".to_string())
+            .with_input(InputSchema::new().with_max_size(16))
+            .with_capability(Capability::FsWrite {
+                prefixes: vec!["./outputs".to_string()],
+            })
+            .with_side_effect(SideEffect::FsWrite {
+                path: "./outputs/echo.txt".to_string(),
+            });
+
+        let adapter = ToolAdapter::new(make_arc_tool(EchoTool)).with_schema(schema.clone());
+        let report = adapter.dry_run(b"hello");
+        assert!(report.input_error.is_none());
+        assert_eq!(report.required_capabilities.len(), 1);
+        assert_eq!(report.missing_capabilities, report.required_capabilities);
+        assert_eq!(report.side_effects.len(), 1);
+        assert!(!report.is_ready());
+        assert!(matches!(
+            report.first_error(),
+            Some(AdapterError::CapabilityDenied { .. })
+        ));
+
+        let mut caps = CapabilitySet::new();
+        caps.grant(Capability::FsWrite {
+            prefixes: vec![".".to_string()],
+        });
+        let adapter = ToolAdapter::new(make_arc_tool(EchoTool))
+            .with_schema(schema)
+            .with_capabilities(caps);
+        assert!(adapter.dry_run(b"hello").is_ready());
+
+        let report = adapter.dry_run(&[0u8; 32]);
+        assert!(report.input_error.is_some());
+        assert!(matches!(
+            report.first_error(),
+            Some(AdapterError::InvalidInput { .. })
+        ));
+    }
+
+    #[test]
+    fn test_host_adapter_dry_run_tool() {
+        let registry = StdArc::new(SharedRegistry::new());
+        let schema = ToolSchema::new("echo".to_string(), "1.0.0".to_string())
+            .with_capability(Capability::ClockRead);
+        registry.register(make_arc_tool(EchoTool), schema).unwrap();
+
+        let host = HostAdapter::new(registry);
+        let report = host.dry_run_tool("echo", b"hi").unwrap();
+        assert_eq!(report.missing_capabilities, vec![Capability::ClockRead]);
+        assert!(host.dry_run_tool("missing", b"hi").is_err());
+    }
+
+    #[test]
+    fn test_host_adapter_invoke() {
+        use crate::schema::{InputSchema, OutputSchema};
+
+        let registry = StdArc::new(SharedRegistry::new());
+        let schema = ToolSchema::new("echo".to_string(), "1.0.0".to_string())
+            .with_input(InputSchema::new().with_json_schema("{}".to_string()))
+            .with_output(OutputSchema::new().with_json_schema("{}".to_string()));
+        registry.register(make_arc_tool(EchoTool), schema).unwrap();
+        let host = HostAdapter::new(registry);
+
+        let output = host.invoke("echo", "^1.0", br#"{"x":1}"#).unwrap();
+        assert_eq!(output.data, br#"{"x":1}"#);
+
+        assert!(host.invoke("echo", "^1.0", b"not json").is_err());
+        let err = host.invoke("echo", "^2", br#"{"x":1}"#).unwrap_err();
+        assert!(err.to_string().contains("does not satisfy"));
+        assert!(host.invoke("missing", "*", b"{}").is_err());
+    }
+
+    #[test]
+    fn test_execute_validated_effects() {
+        let tool = make_arc_tool(WriteTool {
+            path: "./outputs/a.txt".to_string(),
+        });
+        let mut caps = CapabilitySet::new();
+        caps.grant(Capability::FsWrite {
+            prefixes: vec!["./outputs".to_string()],
+        });
+
+        let declared = ToolSchema::new("write".to_string(), "1.0.0".to_string())
+            .with_capability(Capability::FsWrite {
+                prefixes: vec!["./outputs".to_string()],
+            })
+            .with_side_effect(SideEffect::FsWrite {
+                path: "./outputs/a.txt".to_string(),
+            });
+        let output = ToolAdapter::new(StdArc::clone(&tool))
+            .with_capabilities(caps)
+            .with_schema(declared)
+            .execute_validated(b"data")
+            .unwrap();
+        assert_eq!(
+            output.side_effects,
+            vec![SideEffect::FsWrite {
+                path: "./outputs/a.txt".to_string()
+            }]
+        );
+
+        let undeclared = ToolSchema::new("write".to_string(), "1.0.0".to_string());
+        let result = ToolAdapter::new(tool)
+            .with_schema(undeclared)
+            .execute_validated(b"data");
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_echo_tool() {
         let tool = EchoTool;
@@ -275,4 +1006,161 @@ mod tests {
         let result = tool.execute(b"not an array");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_resource_limits_parse() {
+        let limits = ResourceLimits::parse("1500ms", "64MB").unwrap();
+        assert_eq!(limits.cpu_seconds, Some(2));
+        assert_eq!(limits.memory_bytes, Some(64 << 20));
+        assert_eq!(ResourceLimits::parse("", "").unwrap(), ResourceLimits::default());
+        assert!(ResourceLimits::parse("fast", "1G").is_err());
+        assert!(ResourceLimits::parse("0", "1G").is_err());
+
+        let mut caps = CapabilitySet::new();
+        assert_eq!(ResourceLimits::from_capabilities(&caps).unwrap(), None);
+        caps.grant(Capability::Exec {
+            cpu_limit: "10".to_string(),
+            mem_limit: "1GiB".to_string(),
+        });
+        caps.grant(Capability::Exec {
+            cpu_limit: "2m".to_string(),
+            mem_limit: "256K".to_string(),
+        });
+        let limits = ResourceLimits::from_capabilities(&caps).unwrap().unwrap();
+        assert_eq!(limits.cpu_seconds, Some(10));
+        assert_eq!(limits.memory_bytes, Some(256 << 10));
+    }
+
+    #[cfg(target_os = "linux")]
+    fn exec_caps() -> CapabilitySet {
+        let mut caps = CapabilitySet::new();
+        caps.grant(Capability::Exec {
+            cpu_limit: "10s".to_string(),
+            mem_limit: "512MB".to_string(),
+        });
+        caps
+    }
+
+    #[cfg(target_os = "linux")]
+    fn shell(script: &str) -> ProcessAdapter {
+        ProcessAdapter::new("sh", "/bin/sh").with_arg("-c").with_arg(script)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_adapter_captures_output() {
+        let tool = shell(r#"/bin/cat >/dev/null; printf '{"b": 1, "a": 2}'; echo oops >&2"#)
+            .with_capabilities(exec_caps());
+        let output = tool.execute(b"input").unwrap();
+        assert!(output.is_success());
+        assert_eq!(output.data, br#"{"a":2,"b":1}"#);
+        assert_eq!(output.stdout, br#"{"b": 1, "a": 2}"#);
+        assert_eq!(output.stderr, b"oops\n");
+
+        let output = shell("/bin/cat; exit 3").with_capabilities(exec_caps());
+        let output = output.execute(b"echoed").unwrap();
+        assert_eq!(output.exit_code, 3);
+        assert_eq!(output.stdout, b"echoed");
+
+        assert!(shell("true").execute(b"").is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_adapter_confines_filesystem() {
+        let root = std::env::temp_dir().join(format!("cathedral-process-{}", std::process::id()));
+        let allowed = root.join("allowed");
+        let denied = root.join("denied");
+        std::fs::create_dir_all(&allowed).unwrap();
+        std::fs::create_dir_all(&denied).unwrap();
+        std::fs::write(allowed.join("in.txt"), b"visible").unwrap();
+        std::fs::write(denied.join("in.txt"), b"hidden").unwrap();
+
+        let mut caps = exec_caps();
+        caps.grant(Capability::FsRead {
+            prefixes: vec![allowed.display().to_string()],
+        });
+        let read = |dir: &std::path::Path| {
+            let script = format!("/bin/cat {}/in.txt", dir.display());
+            shell(&script).with_capabilities(caps.clone()).execute(b"").unwrap()
+        };
+        assert_eq!(read(&allowed).stdout, b"visible");
+        assert!(!read(&denied).is_success());
+
+        let script = format!("echo x > {}/out.txt", allowed.display());
+        let output = shell(&script).with_capabilities(caps.clone()).execute(b"").unwrap();
+        assert!(!output.is_success());
+        assert!(!allowed.join("out.txt").exists());
+
+        caps.grant(Capability::FsWrite {
+            prefixes: vec![allowed.display().to_string()],
+        });
+        let output = shell(&script).with_capabilities(caps).execute(b"").unwrap();
+        assert!(output.is_success());
+        assert!(allowed.join("out.txt").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_adapter_timeout_kills_group() {
+        let tool = shell("/bin/sleep 30 & /bin/sleep 30")
+            .with_capabilities(exec_caps())
+            .with_timeout(50)
+            .with_tick_duration(Duration::from_millis(2));
+        let started = Instant::now();
+        let err = tool.execute(b"").unwrap_err();
+        assert!(err.to_string().contains("timeout"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_adapter_exit_kills_group() {
+        let tool = shell("/bin/sleep 30 & echo done").with_capabilities(exec_caps());
+        let started = Instant::now();
+        let output = tool.execute(b"").unwrap();
+        assert!(output.is_success());
+        assert_eq!(output.stdout, b"done\n");
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_adapter_cancellation_kills_group() {
+        let token = CancellationToken::new();
+        let tool = shell("/bin/sleep 30 & /bin/sleep 30")
+            .with_capabilities(exec_caps())
+            .with_cancellation(token.clone());
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            token.cancel();
+        });
+        let started = Instant::now();
+        let err = tool.execute(b"").unwrap_err();
+        canceller.join().unwrap();
+        assert!(err.to_string().contains("cancelled"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_host_adapter_cancellation_reaches_process() {
+        let registry = StdArc::new(SharedRegistry::new());
+        let tool = shell("/bin/sleep 30").with_capabilities(exec_caps());
+        let schema = ToolSchema::new("sh".to_string(), "1.0.0".to_string());
+        registry.register(make_arc_tool(tool), schema).unwrap();
+
+        let token = CancellationToken::new();
+        let host = HostAdapter::new(registry).with_cancellation(token.clone());
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            token.cancel();
+        });
+        let started = Instant::now();
+        let err = host.invoke("sh", "*", b"").unwrap_err();
+        canceller.join().unwrap();
+        assert!(err.to_string().contains("cancelled"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
 }
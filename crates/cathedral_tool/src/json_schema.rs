@@ -0,0 +1,529 @@
+//! JSON Schema validation for tool inputs and outputs.
+//!
+//! Implements the subset of draft 2020-12 that tool contracts need:
+//! `type`, `enum`, `const`, object keywords (`properties`, `required`,
+//! `additionalProperties`, `minProperties`, `maxProperties`), array keywords
+//! (`items`, `prefixItems`, `minItems`, `maxItems`, `uniqueItems`), string
+//! lengths, numeric bounds and `multipleOf`, the `allOf`/`anyOf`/`oneOf`/`not`
+//! combinators, and local `$ref`s into the same document. Other keywords
+//! are ignored, as the specification requires of unknown keywords.
+//!
+//! Instance paths use the notation of [`crate::normalize::first_difference`],
+//! e.g. `$.items[2].name`.
+
+use serde_json::{Map, Value};
+
+/// Maximum `$ref` indirections followed without descending into the instance
+const MAX_REF_DEPTH: usize = 64;
+
+/// One way an instance fails its schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Path of the offending value, e.g. `$.user.age`
+    pub path: String,
+    /// Keyword that failed, e.g. `minimum`
+    pub keyword: String,
+    /// What was expected
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} ({})", self.path, self.message, self.keyword)
+    }
+}
+
+/// A parsed JSON Schema document
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonSchema {
+    root: Value,
+}
+
+impl JsonSchema {
+    /// Parse and check a schema document
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if `schema` is not JSON, or a
+    /// supported keyword has a value of the wrong shape
+    pub fn parse(schema: &str) -> Result<Self, String> {
+        let root: Value = serde_json::from_str(schema).map_err(|e| e.to_string())?;
+        check_schema(&root, "#")?;
+        Ok(Self { root })
+    }
+
+    /// Every violation of the schema by `instance`, in document order
+    #[must_use]
+    pub fn validate(&self, instance: &Value) -> Vec<SchemaViolation> {
+        let mut violations = Vec::new();
+        Validator {
+            root: &self.root,
+            violations: &mut violations,
+        }
+        .check(&self.root, instance, "$", 0);
+        violations
+    }
+
+    /// Check if `instance` satisfies the schema
+    #[must_use]
+    pub fn is_valid(&self, instance: &Value) -> bool {
+        self.validate(instance).is_empty()
+    }
+}
+
+struct Validator<'a> {
+    root: &'a Value,
+    violations: &'a mut Vec<SchemaViolation>,
+}
+
+impl Validator<'_> {
+    fn fail(&mut self, path: &str, keyword: &str, message: String) {
+        self.violations.push(SchemaViolation {
+            path: path.to_string(),
+            keyword: keyword.to_string(),
+            message,
+        });
+    }
+
+    /// Whether `instance` satisfies `schema`, without recording violations
+    fn passes(&self, schema: &Value, instance: &Value, path: &str, depth: usize) -> bool {
+        let mut scratch = Vec::new();
+        Validator {
+            root: self.root,
+            violations: &mut scratch,
+        }
+        .check(schema, instance, path, depth);
+        scratch.is_empty()
+    }
+
+    fn check(&mut self, schema: &Value, instance: &Value, path: &str, depth: usize) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                return self.fail(path, "false", "no value is allowed here".to_string());
+            }
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            match resolve_ref(self.root, reference) {
+                Some(_) if depth >= MAX_REF_DEPTH => {
+                    self.fail(path, "$ref", format!("{} recurses too deeply", reference));
+                }
+                Some(target) => self.check(target, instance, path, depth + 1),
+                None => self.fail(path, "$ref", format!("{} does not resolve", reference)),
+            }
+        }
+
+        self.check_generic(schema, instance, path, depth);
+        match instance {
+            Value::Object(object) => self.check_object(schema, object, path),
+            Value::Array(items) => self.check_array(schema, items, path),
+            Value::String(s) => check_string(self, schema, s, path),
+            Value::Number(_) => check_number(self, schema, instance, path),
+            _ => {}
+        }
+    }
+
+    fn check_generic(
+        &mut self,
+        schema: &Map<String, Value>,
+        instance: &Value,
+        path: &str,
+        depth: usize,
+    ) {
+        if let Some(expected) = schema.get("type") {
+            let allowed: Vec<&str> = match expected {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.iter().any(|t| has_type(instance, t)) {
+                self.fail(
+                    path,
+                    "type",
+                    format!("expected {}, found {}", allowed.join(" or "), type_name(instance)),
+                );
+            }
+        }
+        if let Some(Value::Array(options)) = schema.get("enum")
+            && !options.contains(instance)
+        {
+            let options = Value::from(options.clone());
+            self.fail(path, "enum", format!("{} is not one of {}", instance, options));
+        }
+        if let Some(expected) = schema.get("const")
+            && expected != instance
+        {
+            self.fail(path, "const", format!("expected {}", expected));
+        }
+
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            for sub in all {
+                self.check(sub, instance, path, depth);
+            }
+        }
+        if let Some(Value::Array(any)) = schema.get("anyOf")
+            && !any.iter().any(|sub| self.passes(sub, instance, path, depth))
+        {
+            self.fail(path, "anyOf", "does not match any of the allowed schemas".to_string());
+        }
+        if let Some(Value::Array(one)) = schema.get("oneOf") {
+            let matched = one
+                .iter()
+                .filter(|sub| self.passes(sub, instance, path, depth))
+                .count();
+            if matched != 1 {
+                let message = format!("matches {} schemas, expected exactly 1", matched);
+                self.fail(path, "oneOf", message);
+            }
+        }
+        if let Some(not) = schema.get("not")
+            && self.passes(not, instance, path, depth)
+        {
+            self.fail(path, "not", "matches a disallowed schema".to_string());
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &str,
+    ) {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for field in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(field) {
+                    self.fail(&field_path(path, field), "required", "is required".to_string());
+                }
+            }
+        }
+        check_count(self, schema, object.len(), "Properties", "properties", path);
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, value) in object {
+            let child = field_path(path, key);
+            match properties.and_then(|p| p.get(key)) {
+                Some(sub) => self.check(sub, value, &child, 0),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        self.fail(&child, "additionalProperties", "is not allowed".to_string());
+                    }
+                    Some(sub) => self.check(sub, value, &child, 0),
+                    None => {}
+                },
+            }
+        }
+    }
+
+    fn check_array(&mut self, schema: &Map<String, Value>, items: &[Value], path: &str) {
+        check_count(self, schema, items.len(), "Items", "items", path);
+
+        let prefix = schema.get("prefixItems").and_then(Value::as_array);
+        let prefix_len = prefix.map_or(0, Vec::len);
+        for (i, item) in items.iter().enumerate() {
+            let child = format!("{}[{}]", path, i);
+            match prefix.and_then(|p| p.get(i)) {
+                Some(sub) => self.check(sub, item, &child, 0),
+                None if i >= prefix_len => {
+                    if let Some(sub) = schema.get("items") {
+                        self.check(sub, item, &child, 0);
+                    }
+                }
+                None => {}
+            }
+        }
+
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            for (i, item) in items.iter().enumerate() {
+                if let Some(first) = items[..i].iter().position(|other| other == item) {
+                    self.fail(
+                        &format!("{}[{}]", path, i),
+                        "uniqueItems",
+                        format!("duplicates item {}", first),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn check_string(v: &mut Validator<'_>, schema: &Map<String, Value>, s: &str, path: &str) {
+    check_count(v, schema, s.chars().count(), "Length", "characters", path);
+}
+
+/// Check `min<suffix>`/`max<suffix>` against a count of `unit`s
+fn check_count(
+    v: &mut Validator<'_>,
+    schema: &Map<String, Value>,
+    count: usize,
+    suffix: &str,
+    unit: &str,
+    path: &str,
+) {
+    let count = count as u64;
+    let min_keyword = format!("min{}", suffix);
+    if let Some(min) = schema.get(&min_keyword).and_then(Value::as_u64)
+        && count < min
+    {
+        v.fail(path, &min_keyword, format!("has {} {}, minimum is {}", count, unit, min));
+    }
+    let max_keyword = format!("max{}", suffix);
+    if let Some(max) = schema.get(&max_keyword).and_then(Value::as_u64)
+        && count > max
+    {
+        v.fail(path, &max_keyword, format!("has {} {}, maximum is {}", count, unit, max));
+    }
+}
+
+fn check_number(v: &mut Validator<'_>, schema: &Map<String, Value>, instance: &Value, path: &str) {
+    let Some(x) = instance.as_f64() else {
+        return;
+    };
+    let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+
+    if let Some(min) = bound("minimum")
+        && x < min
+    {
+        v.fail(path, "minimum", format!("{} is less than {}", instance, min));
+    }
+    if let Some(max) = bound("maximum")
+        && x > max
+    {
+        v.fail(path, "maximum", format!("{} is greater than {}", instance, max));
+    }
+    if let Some(min) = bound("exclusiveMinimum")
+        && x <= min
+    {
+        v.fail(path, "exclusiveMinimum", format!("{} is not greater than {}", instance, min));
+    }
+    if let Some(max) = bound("exclusiveMaximum")
+        && x >= max
+    {
+        v.fail(path, "exclusiveMaximum", format!("{} is not less than {}", instance, max));
+    }
+    if let Some(step) = bound("multipleOf") {
+        let quotient = x / step;
+        if (quotient - quotient.round()).abs() > f64::EPSILON * quotient.abs().max(1.0) {
+            v.fail(path, "multipleOf", format!("{} is not a multiple of {}", instance, step));
+        }
+    }
+}
+
+fn has_type(instance: &Value, expected: &str) -> bool {
+    match expected {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "number" => instance.is_number(),
+        "integer" => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance.as_f64().is_some_and(|x| x.is_finite() && x.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(_) if has_type(instance, "integer") => "integer",
+        Value::Number(_) => "number",
+    }
+}
+
+fn field_path(path: &str, field: &str) -> String {
+    format!("{}.{}", path, field)
+}
+
+/// Resolve a same-document reference such as `#/$defs/user`
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    if pointer.is_empty() {
+        return Some(root);
+    }
+    root.pointer(pointer)
+}
+
+/// Check the shape of supported keywords, recursively
+fn check_schema(schema: &Value, location: &str) -> Result<(), String> {
+    let schema = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(schema) => schema,
+        _ => return Err(format!("{} must be an object or boolean", location)),
+    };
+    let bad = |keyword: &str, expected: &str| {
+        Err(format!("{}/{} must be {}", location, keyword, expected))
+    };
+
+    for (keyword, value) in schema {
+        let at = format!("{}/{}", location, keyword);
+        match keyword.as_str() {
+            "type" => {
+                let valid = |t: &Value| {
+                    t.as_str().is_some_and(|t| {
+                        matches!(
+                            t,
+                            "null" | "boolean" | "object" | "array" | "string" | "number"
+                                | "integer"
+                        )
+                    })
+                };
+                let ok = match value {
+                    Value::Array(types) => types.iter().all(valid),
+                    other => valid(other),
+                };
+                if !ok {
+                    return bad(keyword, "a JSON type name or an array of them");
+                }
+            }
+            "enum" if !value.is_array() => return bad(keyword, "an array"),
+            "required" if !value.as_array().is_some_and(|r| r.iter().all(Value::is_string)) => {
+                return bad(keyword, "an array of strings");
+            }
+            "properties" | "$defs" | "definitions" => {
+                let Some(map) = value.as_object() else {
+                    return bad(keyword, "an object");
+                };
+                for (name, sub) in map {
+                    check_schema(sub, &format!("{}/{}", at, name))?;
+                }
+            }
+            "allOf" | "anyOf" | "oneOf" | "prefixItems" => {
+                let Some(subs) = value.as_array().filter(|subs| !subs.is_empty()) else {
+                    return bad(keyword, "a non-empty array");
+                };
+                for (i, sub) in subs.iter().enumerate() {
+                    check_schema(sub, &format!("{}/{}", at, i))?;
+                }
+            }
+            "not" | "items" | "additionalProperties" => check_schema(value, &at)?,
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum"
+                if !value.is_number() =>
+            {
+                return bad(keyword, "a number");
+            }
+            "multipleOf" if !value.as_f64().is_some_and(|x| x > 0.0) => {
+                return bad(keyword, "a positive number");
+            }
+            "minLength" | "maxLength" | "minItems" | "maxItems" | "minProperties"
+            | "maxProperties"
+                if !value.is_u64() =>
+            {
+                return bad(keyword, "a non-negative integer");
+            }
+            "uniqueItems" if !value.is_boolean() => return bad(keyword, "a boolean"),
+            "$ref" if !value.as_str().is_some_and(|r| r.starts_with('#')) => {
+                return bad(keyword, "a reference within this document");
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: Value) -> JsonSchema {
+        JsonSchema::parse(&value.to_string()).unwrap()
+    }
+
+    fn paths(schema: &JsonSchema, instance: Value) -> Vec<(String, String)> {
+        schema
+            .validate(&instance)
+            .into_iter()
+            .map(|v| (v.path, v.keyword))
+            .collect()
+    }
+
+    #[test]
+    fn test_object_keywords() {
+        let user = schema(json!({
+            "type": "object",
+            "required": ["name", "age"],
+            "properties": {
+                "name": {"type": "string", "minLength": 1},
+                "age": {"type": "integer", "minimum": 18, "maximum": 150},
+                "role": {"enum": ["admin", "user"]},
+                "tags": {"type": "array", "items": {"type": "string"}, "uniqueItems": true}
+            },
+            "additionalProperties": false
+        }));
+
+        assert!(user.is_valid(&json!({"name": "ada", "age": 36, "tags": ["a", "b"]})));
+        assert_eq!(
+            paths(&user, json!({"name": "", "age": 12.0, "role": "root", "x": 1})),
+            [
+                ("$.age".to_string(), "minimum".to_string()),
+                ("$.name".to_string(), "minLength".to_string()),
+                ("$.role".to_string(), "enum".to_string()),
+                ("$.x".to_string(), "additionalProperties".to_string()),
+            ]
+        );
+        assert_eq!(
+            paths(&user, json!({"age": "old", "tags": ["a", 1, "a"]})),
+            [
+                ("$.name".to_string(), "required".to_string()),
+                ("$.age".to_string(), "type".to_string()),
+                ("$.tags[1]".to_string(), "type".to_string()),
+                ("$.tags[2]".to_string(), "uniqueItems".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combinators_and_refs() {
+        let shape = schema(json!({
+            "$defs": {
+                "point": {
+                    "type": "array",
+                    "prefixItems": [{"type": "number"}, {"type": "number"}],
+                    "items": false
+                }
+            },
+            "oneOf": [
+                {"$ref": "#/$defs/point"},
+                {"type": "object", "required": ["radius"]}
+            ],
+            "not": {"const": [0, 0]}
+        }));
+
+        assert!(shape.is_valid(&json!([1, 2.5])));
+        assert!(shape.is_valid(&json!({"radius": 3})));
+        assert_eq!(paths(&shape, json!([0, 0])), [("$".to_string(), "not".to_string())]);
+        assert_eq!(paths(&shape, json!([1, 2, 3])), [("$".to_string(), "oneOf".to_string())]);
+
+        let looping = schema(json!({"$ref": "#"}));
+        assert_eq!(looping.validate(&json!(1))[0].keyword, "$ref");
+    }
+
+    #[test]
+    fn test_numeric_bounds() {
+        let price = schema(json!({"exclusiveMinimum": 0, "multipleOf": 0.01}));
+        assert!(price.is_valid(&json!(19.99)));
+        assert!(price.is_valid(&json!("not a number")));
+        assert_eq!(paths(&price, json!(0)), [("$".to_string(), "exclusiveMinimum".to_string())]);
+        assert_eq!(paths(&price, json!(1.005)), [("$".to_string(), "multipleOf".to_string())]);
+    }
+
+    #[test]
+    fn test_malformed_schema() {
+        assert!(JsonSchema::parse("not json").is_err());
+        let err = JsonSchema::parse(r#"{"properties": {"a": {"type": "text"}}}"#).unwrap_err();
+        assert!(err.contains("#/properties/a/type"), "{err}");
+        assert!(JsonSchema::parse(r#"{"required": "name"}"#).is_err());
+        assert!(JsonSchema::parse(r#"{"$ref": "https://example.com/s.json"}"#).is_err());
+        assert!(JsonSchema::parse("{}").unwrap().is_valid(&json!(null)));
+    }
+}
@@ -12,10 +12,19 @@ pub mod normalize;
 pub mod registry;
 pub mod adapter;
 pub mod validate;
+pub mod json_schema;
+mod sandbox;
 
 pub use trait_::{Tool, ToolOutput, ToolError};
 pub use schema::{ToolSchema, InputSchema, OutputSchema, SideEffect};
-pub use normalize::{Normalizer, NormalizedOutput, NormalizationError};
+pub use normalize::{
+    assert_normalization_deterministic, check_normalization_deterministic,
+    check_normalization_deterministic_with, first_difference, NormalizationDivergence,
+    NormalizationError, NormalizationRule, NormalizedOutput, Normalizer,
+};
 pub use registry::{ToolRegistry, RegistryError, ToolEntry};
-pub use adapter::{ToolAdapter, HostAdapter, AdapterError};
+pub use adapter::{
+    AdapterError, DryRunReport, HostAdapter, ProcessAdapter, ResourceLimits, ToolAdapter,
+};
 pub use validate::{ToolValidator, ValidationError};
+pub use json_schema::{JsonSchema, SchemaViolation};
@@ -1,7 +1,18 @@
 //! Output normalization for deterministic tool results.
-
+//!
+//! Floats are always brought to the canonical form of
+//! [`cathedral_core::float`]: JSON cannot carry NaN, numbers are reprinted
+//! from their parsed value, and `-0.0` becomes `0.0`, so outputs that are
+//! numerically equal hash identically.
+
+use crate::schema::ToolSchema;
+use crate::trait_::Tool;
+use cathedral_core::{CoreError, CoreResult};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Number of times determinism checks run a tool per input
+pub const DETERMINISM_RUNS: usize = 3;
 
 /// Normalization error
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -14,6 +25,8 @@ pub enum NormalizationError {
     UnsupportedType { type_name: String },
     /// Circular reference
     CircularReference,
+    /// Malformed field path in a normalization rule
+    InvalidPath { path: String },
 }
 
 impl std::fmt::Display for NormalizationError {
@@ -25,6 +38,7 @@ impl std::fmt::Display for NormalizationError {
                 write!(f, "Unsupported type: {}", type_name)
             }
             Self::CircularReference => write!(f, "Circular reference detected"),
+            Self::InvalidPath { path } => write!(f, "Invalid field path: {}", path),
         }
     }
 }
@@ -90,6 +104,10 @@ impl NormalizedOutput {
         let mut transformations = Vec::new();
         let config = NormalizeConfig::default();
 
+        if Self::canonicalize_floats(&mut data) {
+            transformations.push("canonical_floats".to_string());
+        }
+
         // Apply normalization
         if config.sort_keys {
             data = Self::sort_keys(data);
@@ -137,6 +155,22 @@ impl NormalizedOutput {
         })
     }
 
+    /// Rewrite every float into canonical form, reporting whether any changed
+    fn canonicalize_floats(value: &mut serde_json::Value) -> bool {
+        let mut changed = false;
+        for_each_leaf(value, &mut |leaf| {
+            if leaf.is_f64()
+                && let Some(x) = leaf.as_f64()
+                && !cathedral_core::float::is_canonical_f64(x)
+                && let Some(n) = serde_json::Number::from_f64(cathedral_core::canonical_f64(x))
+            {
+                *leaf = serde_json::Value::Number(n);
+                changed = true;
+            }
+        });
+        changed
+    }
+
     /// Sort object keys recursively
     fn sort_keys(value: serde_json::Value) -> serde_json::Value {
         match value {
@@ -174,9 +208,353 @@ impl NormalizedOutput {
     }
 }
 
+/// Placeholder that [`NormalizationRule::StripTimestamps`] substitutes
+pub const TIMESTAMP_PLACEHOLDER: &str = "<timestamp>";
+
+/// Declarative rule canonicalizing a nondeterministic part of a tool's output
+///
+/// Rules address fields with paths in the notation of [`first_difference`]:
+/// `$` is the root, `.key` an object field, `[3]` an array element, `.*` and
+/// `[*]` any field or element, and `..` descends any number of levels (e.g.
+/// `$..timestamp`). Rules other than `StripField` and `SortArray` apply to
+/// every value at or beneath a matching field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum NormalizationRule {
+    /// Remove matching fields entirely
+    StripField {
+        /// Path pattern of the fields to remove
+        field: String,
+    },
+    /// Replace RFC 3339 timestamp strings with [`TIMESTAMP_PLACEHOLDER`]
+    StripTimestamps {
+        /// Path pattern of the fields to scan
+        field: String,
+    },
+    /// Sort arrays by the canonical encoding of their elements
+    SortArray {
+        /// Path pattern of the arrays to sort
+        field: String,
+    },
+    /// Round numbers to a fixed number of decimal places
+    RoundFloats {
+        /// Path pattern of the fields to round
+        field: String,
+        /// Decimal places to keep
+        precision: u32,
+    },
+    /// Replace a host-specific path prefix at the start of strings
+    ReplacePath {
+        /// Path pattern of the fields to rewrite
+        field: String,
+        /// Prefix to replace, e.g. the tool's working directory
+        prefix: String,
+        /// Stable replacement, e.g. `$WORKDIR`
+        replacement: String,
+    },
+}
+
+impl NormalizationRule {
+    /// Path pattern the rule applies to
+    #[must_use]
+    pub fn field(&self) -> &str {
+        match self {
+            Self::StripField { field }
+            | Self::StripTimestamps { field }
+            | Self::SortArray { field }
+            | Self::RoundFloats { field, .. }
+            | Self::ReplacePath { field, .. } => field,
+        }
+    }
+
+    /// Name recorded in [`NormalizedOutput::transformations`]
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let kind = match self {
+            Self::StripField { .. } => "strip_field",
+            Self::StripTimestamps { .. } => "strip_timestamps",
+            Self::SortArray { .. } => "sort_array",
+            Self::RoundFloats { .. } => "round_floats",
+            Self::ReplacePath { .. } => "replace_path",
+        };
+        format!("{} {}", kind, self.field())
+    }
+
+    /// Apply the rule to `value` in place
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the rule's path pattern is malformed
+    pub fn apply(&self, value: &mut serde_json::Value) -> Result<(), NormalizationError> {
+        let pattern = FieldPattern::parse(self.field())?;
+        match self {
+            Self::StripField { .. } => {
+                strip_matching(value, &pattern, &mut Vec::new());
+            }
+            _ => visit_matching(value, &pattern, &mut Vec::new(), &mut |v| self.rewrite(v)),
+        }
+        Ok(())
+    }
+
+    /// Rewrite a value at a matching field
+    fn rewrite(&self, value: &mut serde_json::Value) {
+        use serde_json::Value;
+
+        match self {
+            Self::StripField { .. } => {}
+            Self::SortArray { .. } => {
+                if let Value::Array(items) = value {
+                    items.sort_by_cached_key(|item| {
+                        serde_json::to_vec(&NormalizedOutput::sort_keys(item.clone()))
+                            .unwrap_or_default()
+                    });
+                }
+            }
+            Self::StripTimestamps { .. } => for_each_leaf(value, &mut |leaf| {
+                if leaf.as_str().is_some_and(is_rfc3339) {
+                    *leaf = Value::String(TIMESTAMP_PLACEHOLDER.to_string());
+                }
+            }),
+            Self::RoundFloats { precision, .. } => for_each_leaf(value, &mut |leaf| {
+                if leaf.is_f64()
+                    && let Some(x) = leaf.as_f64()
+                {
+                    let scale = 10f64.powi(i32::try_from(*precision).unwrap_or(i32::MAX));
+                    let rounded = (x * scale).round() / scale;
+                    if let Some(n) = serde_json::Number::from_f64(rounded) {
+                        *leaf = Value::Number(n);
+                    }
+                }
+            }),
+            Self::ReplacePath {
+                prefix,
+                replacement,
+                ..
+            } => for_each_leaf(value, &mut |leaf| {
+                if let Some(rest) = leaf.as_str().and_then(|s| strip_path_prefix(s, prefix)) {
+                    *leaf = Value::String(format!("{}{}", replacement, rest));
+                }
+            }),
+        }
+    }
+}
+
+/// `s` without `prefix`, if `prefix` is a whole-component prefix of `s`
+fn strip_path_prefix<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let prefix = prefix.strip_suffix('/').unwrap_or(prefix);
+    let rest = s.strip_prefix(prefix)?;
+    (prefix.is_empty() || rest.is_empty() || rest.starts_with('/')).then_some(rest)
+}
+
+/// Check for an RFC 3339 timestamp, e.g. `2024-01-02T03:04:05.678Z`
+fn is_rfc3339(s: &str) -> bool {
+    let b = s.as_bytes();
+    let digits = |range: std::ops::Range<usize>| {
+        b.get(range).is_some_and(|d| d.iter().all(u8::is_ascii_digit))
+    };
+    let date_time = b.len() >= 20
+        && digits(0..4)
+        && b[4] == b'-'
+        && digits(5..7)
+        && b[7] == b'-'
+        && digits(8..10)
+        && matches!(b[10], b'T' | b't' | b' ')
+        && digits(11..13)
+        && b[13] == b':'
+        && digits(14..16)
+        && b[16] == b':'
+        && digits(17..19);
+    if !date_time {
+        return false;
+    }
+
+    let mut rest = &b[19..];
+    if let Some(frac) = rest.strip_prefix(b".") {
+        let len = frac.iter().take_while(|c| c.is_ascii_digit()).count();
+        if len == 0 {
+            return false;
+        }
+        rest = &frac[len..];
+    }
+    match rest {
+        [b'Z' | b'z'] => true,
+        [b'+' | b'-', h1, h2, b':', m1, m2] => {
+            [h1, h2, m1, m2].iter().all(|c| c.is_ascii_digit())
+        }
+        _ => false,
+    }
+}
+
+/// One step of a concrete path through a JSON value
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// One segment of a field path pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    AnyKey,
+    Index(usize),
+    AnyIndex,
+    /// `..`: zero or more steps
+    Descend,
+}
+
+/// Parsed field path pattern, e.g. `$.items[*]..path`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FieldPattern {
+    segments: Vec<Segment>,
+}
+
+impl FieldPattern {
+    fn parse(pattern: &str) -> Result<Self, NormalizationError> {
+        let invalid = || NormalizationError::InvalidPath {
+            path: pattern.to_string(),
+        };
+        let mut rest = pattern.strip_prefix('$').ok_or_else(invalid)?;
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                segments.push(Segment::Descend);
+                // `..key` and `..*` name a field directly after the dots
+                rest = if after.starts_with('[') { after } else { &rest[1..] };
+                continue;
+            }
+            if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(invalid)?;
+                let index = &after[..end];
+                segments.push(if index == "*" {
+                    Segment::AnyIndex
+                } else {
+                    Segment::Index(index.parse().map_err(|_| invalid())?)
+                });
+                rest = &after[end + 1..];
+                continue;
+            }
+            let after = rest.strip_prefix('.').ok_or_else(invalid)?;
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            let key = &after[..end];
+            if key.is_empty() {
+                return Err(invalid());
+            }
+            segments.push(if key == "*" {
+                Segment::AnyKey
+            } else {
+                Segment::Key(key.to_string())
+            });
+            rest = &after[end..];
+        }
+
+        Ok(Self { segments })
+    }
+
+    fn matches(&self, path: &[Step]) -> bool {
+        matches_segments(&self.segments, path)
+    }
+}
+
+fn matches_segments(segments: &[Segment], path: &[Step]) -> bool {
+    let Some((first, rest)) = segments.split_first() else {
+        return path.is_empty();
+    };
+    if *first == Segment::Descend {
+        return matches_segments(rest, path)
+            || (!path.is_empty() && matches_segments(segments, &path[1..]));
+    }
+    let Some((step, path)) = path.split_first() else {
+        return false;
+    };
+    let step_matches = match (first, step) {
+        (Segment::Key(k), Step::Key(s)) => k == s,
+        (Segment::AnyKey, Step::Key(_)) | (Segment::AnyIndex, Step::Index(_)) => true,
+        (Segment::Index(i), Step::Index(s)) => i == s,
+        _ => false,
+    };
+    step_matches && matches_segments(rest, path)
+}
+
+/// Call `f` on every value whose path matches `pattern`, outermost first
+///
+/// Values beneath a match are not visited again, so `f` sees each subtree once.
+fn visit_matching(
+    value: &mut serde_json::Value,
+    pattern: &FieldPattern,
+    path: &mut Vec<Step>,
+    f: &mut dyn FnMut(&mut serde_json::Value),
+) {
+    use serde_json::Value;
+
+    if pattern.matches(path) {
+        f(value);
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                path.push(Step::Key(key.clone()));
+                visit_matching(child, pattern, path, f);
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                path.push(Step::Index(i));
+                visit_matching(child, pattern, path, f);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Remove every object field whose path matches `pattern`
+fn strip_matching(value: &mut serde_json::Value, pattern: &FieldPattern, path: &mut Vec<Step>) {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| {
+                path.push(Step::Key(key.clone()));
+                let keep = !pattern.matches(path);
+                path.pop();
+                keep
+            });
+            for (key, child) in map.iter_mut() {
+                path.push(Step::Key(key.clone()));
+                strip_matching(child, pattern, path);
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (i, child) in items.iter_mut().enumerate() {
+                path.push(Step::Index(i));
+                strip_matching(child, pattern, path);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Call `f` on every scalar at or beneath `value`
+fn for_each_leaf(value: &mut serde_json::Value, f: &mut dyn FnMut(&mut serde_json::Value)) {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => map.values_mut().for_each(|child| for_each_leaf(child, f)),
+        Value::Array(items) => items.iter_mut().for_each(|child| for_each_leaf(child, f)),
+        _ => f(value),
+    }
+}
+
 /// Normalizer for tool outputs
 pub struct Normalizer {
     config: NormalizeConfig,
+    rules: Vec<NormalizationRule>,
 }
 
 impl Normalizer {
@@ -185,28 +563,59 @@ impl Normalizer {
     pub fn new() -> Self {
         Self {
             config: NormalizeConfig::default(),
+            rules: Vec::new(),
         }
     }
 
     /// Create a normalizer with custom config
     #[must_use]
     pub fn with_config(config: NormalizeConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Create a normalizer applying the rules declared by a tool's schema
+    #[must_use]
+    pub fn for_schema(schema: &ToolSchema) -> Self {
+        Self::new().with_rules(schema.normalization.clone())
+    }
+
+    /// Append rules applied, in order, before keys are sorted
+    #[must_use]
+    pub fn with_rules(mut self, rules: Vec<NormalizationRule>) -> Self {
+        self.rules.extend(rules);
+        self
     }
 
     /// Normalize tool output
     ///
     /// # Errors
     ///
-    /// Returns error if normalization fails
+    /// Returns error if the output is not JSON or a rule is malformed
     pub fn normalize(&self, input: &[u8]) -> Result<NormalizedOutput, NormalizationError> {
-        NormalizedOutput::from_bytes(input)
+        let mut output = NormalizedOutput::from_bytes(input)?;
+        if self.rules.is_empty() {
+            return Ok(output);
+        }
+
+        for rule in &self.rules {
+            rule.apply(&mut output.data)?;
+            output.transformations.push(rule.describe());
+        }
+        // Rounding can produce a negative zero
+        NormalizedOutput::canonicalize_floats(&mut output.data);
+        output.data = NormalizedOutput::sort_keys(output.data);
+        output.normalized_size = output.to_bytes()?.len();
+        Ok(output)
     }
 
     /// Normalize a JSON value
     #[must_use]
     pub fn normalize_value(&self, value: serde_json::Value) -> serde_json::Value {
         let mut result = value;
+        NormalizedOutput::canonicalize_floats(&mut result);
         if self.config.sort_keys {
             result = NormalizedOutput::sort_keys(result);
         }
@@ -223,6 +632,132 @@ impl Default for Normalizer {
     }
 }
 
+/// Repeated runs of a tool that normalized to different outputs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizationDivergence {
+    /// Index of the input that produced differing outputs
+    pub input_index: usize,
+    /// Run that differed from the first run
+    pub run: usize,
+    /// Path of the first differing field, e.g. `$.meta.timestamp`
+    pub field: String,
+}
+
+impl std::fmt::Display for NormalizationDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "input {} run {} differs from run 0 at {}",
+            self.input_index, self.run, self.field
+        )
+    }
+}
+
+/// Path of the first field where two JSON values differ
+///
+/// Object keys are visited in sorted order, so the result is stable.
+#[must_use]
+pub fn first_difference(a: &serde_json::Value, b: &serde_json::Value) -> Option<String> {
+    diff_path(a, b, "$".to_string())
+}
+
+fn diff_path(a: &serde_json::Value, b: &serde_json::Value, path: String) -> Option<String> {
+    use serde_json::Value;
+
+    match (a, b) {
+        (Value::Object(x), Value::Object(y)) => {
+            let keys: BTreeSet<&String> = x.keys().chain(y.keys()).collect();
+            keys.into_iter().find_map(|key| {
+                let field = format!("{}.{}", path, key);
+                match (x.get(key), y.get(key)) {
+                    (Some(va), Some(vb)) => diff_path(va, vb, field),
+                    _ => Some(field),
+                }
+            })
+        }
+        (Value::Array(x), Value::Array(y)) => (0..x.len().max(y.len())).find_map(|i| {
+            let field = format!("{}[{}]", path, i);
+            match (x.get(i), y.get(i)) {
+                (Some(va), Some(vb)) => diff_path(va, vb, field),
+                _ => Some(field),
+            }
+        }),
+        _ if a == b => None,
+        _ => Some(path),
+    }
+}
+
+/// Run a tool [`DETERMINISM_RUNS`] times per input and compare normalized outputs
+///
+/// Returns the first divergence found, or `None` if every input normalized
+/// identically on every run.
+///
+/// # Errors
+///
+/// Returns error if the tool fails or its output cannot be normalized
+pub fn check_normalization_deterministic(
+    tool: &dyn Tool,
+    inputs: &[&[u8]],
+) -> CoreResult<Option<NormalizationDivergence>> {
+    check_normalization_deterministic_with(tool, &Normalizer::new(), inputs)
+}
+
+/// Like [`check_normalization_deterministic`], normalizing with `normalizer`
+///
+/// Pass [`Normalizer::for_schema`] so the tool's declared rules canonicalize
+/// fields such as timestamps before outputs are compared.
+///
+/// # Errors
+///
+/// Returns error if the tool fails or its output cannot be normalized
+pub fn check_normalization_deterministic_with(
+    tool: &dyn Tool,
+    normalizer: &Normalizer,
+    inputs: &[&[u8]],
+) -> CoreResult<Option<NormalizationDivergence>> {
+    let normalize = |input: &[u8]| -> CoreResult<serde_json::Value> {
+        let output = tool.execute(input)?;
+        let normalized = normalizer
+            .normalize(&output.data)
+            .map_err(|e| CoreError::ParseError {
+                message: format!("{} output: {}", tool.name(), e),
+            })?;
+        Ok(normalized.data)
+    };
+
+    for (input_index, input) in inputs.iter().enumerate() {
+        let first = normalize(input)?;
+        for run in 1..DETERMINISM_RUNS {
+            let again = normalize(input)?;
+            if let Some(field) = first_difference(&first, &again) {
+                return Ok(Some(NormalizationDivergence {
+                    input_index,
+                    run,
+                    field,
+                }));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Assert that a tool's normalized output is identical across repeated runs
+///
+/// # Panics
+///
+/// Panics naming the first differing field if any run diverges, or if the
+/// tool fails or its output cannot be normalized.
+pub fn assert_normalization_deterministic(tool: &dyn Tool, inputs: &[&[u8]]) {
+    match check_normalization_deterministic(tool, inputs) {
+        Ok(None) => {}
+        Ok(Some(divergence)) => {
+            panic!("{} is not deterministic: {}", tool.name(), divergence)
+        }
+        Err(e) => panic!("{} could not be checked: {}", tool.name(), e),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -279,6 +814,190 @@ mod tests {
         assert_eq!(cleaned, serde_json::json!({"a": 1, "c": {"e": 2}}));
     }
 
+    #[test]
+    fn test_negative_zero_normalizes_to_zero() {
+        let output = NormalizedOutput::from_bytes(br#"{"a": -0.0, "b": [-0, 1.5e0]}"#).unwrap();
+        assert_eq!(output.to_bytes().unwrap(), br#"{"a":0.0,"b":[0.0,1.5]}"#);
+        assert!(output.transformations.contains(&"canonical_floats".to_string()));
+
+        let plain = NormalizedOutput::from_bytes(br#"{"a": 0.0}"#).unwrap();
+        assert!(!plain.transformations.contains(&"canonical_floats".to_string()));
+
+        // Rounding a small negative value must not leave a negative zero
+        let rounding = Normalizer::new().with_rules(vec![NormalizationRule::RoundFloats {
+            field: "$.x".to_string(),
+            precision: 1,
+        }]);
+        let rounded = rounding.normalize(br#"{"x": -0.01}"#).unwrap();
+        assert_eq!(rounded.to_bytes().unwrap(), br#"{"x":0.0}"#);
+
+        let value = Normalizer::new().normalize_value(serde_json::json!({"x": -0.0}));
+        assert_eq!(serde_json::to_vec(&value).unwrap(), br#"{"x":0.0}"#);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_signed_floats_normalize_by_value(x: f64) {
+            proptest::prop_assume!(x.is_finite());
+            let encode = |v: f64| format!(r#"{{"v": {}}}"#, serde_json::to_string(&v).unwrap());
+            let a = NormalizedOutput::from_bytes(encode(x).as_bytes()).unwrap();
+            let b = NormalizedOutput::from_bytes(encode(-x).as_bytes()).unwrap();
+            let bytes = a.to_bytes().unwrap();
+            let v = a.data["v"].as_f64().unwrap();
+            proptest::prop_assert!(cathedral_core::float::is_canonical_f64(v));
+            if x == 0.0 {
+                proptest::prop_assert_eq!(bytes, b.to_bytes().unwrap());
+            } else {
+                proptest::prop_assert_ne!(bytes, b.to_bytes().unwrap());
+            }
+        }
+    }
+
+    /// Echoes its input, optionally stamped with a counter standing in for a clock
+    struct StampTool {
+        stamp: bool,
+        clock: std::sync::atomic::AtomicU64,
+    }
+
+    impl StampTool {
+        fn new(stamp: bool) -> Self {
+            Self {
+                stamp,
+                clock: std::sync::atomic::AtomicU64::new(0),
+            }
+        }
+    }
+
+    impl Tool for StampTool {
+        fn name(&self) -> &str {
+            "stamp"
+        }
+
+        fn execute(&self, input: &[u8]) -> CoreResult<crate::trait_::ToolOutput> {
+            let mut value: serde_json::Value = serde_json::from_slice(input).unwrap();
+            if self.stamp {
+                let now = self.clock.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                value["meta"] = serde_json::json!({"source": "stamp", "timestamp": now});
+            }
+            Ok(crate::trait_::ToolOutput::success(serde_json::to_vec(&value).unwrap()))
+        }
+    }
+
+    #[test]
+    fn test_first_difference() {
+        let a = serde_json::json!({"a": [1, {"b": 2}], "c": 3});
+        assert_eq!(first_difference(&a, &a), None);
+        assert_eq!(
+            first_difference(&a, &serde_json::json!({"a": [1, {"b": 5}], "c": 4})),
+            Some("$.a[1].b".to_string())
+        );
+        assert_eq!(
+            first_difference(&a, &serde_json::json!({"a": [1], "c": 3})),
+            Some("$.a[1]".to_string())
+        );
+    }
+
+    #[test]
+    fn test_normalization_deterministic_check() {
+        let inputs: &[&[u8]] = &[br#"{"x": 1}"#, br#"{"y": [2, 3]}"#];
+        assert_normalization_deterministic(&StampTool::new(false), inputs);
+
+        let divergence = check_normalization_deterministic(&StampTool::new(true), inputs)
+            .unwrap()
+            .unwrap();
+        assert_eq!(divergence.input_index, 0);
+        assert_eq!(divergence.run, 1);
+        assert_eq!(divergence.field, "$.meta.timestamp");
+    }
+
+    #[test]
+    #[should_panic(expected = "stamp is not deterministic: input 0 run 1 differs from run 0 at $.meta.timestamp")]
+    fn test_assert_normalization_deterministic_names_field() {
+        assert_normalization_deterministic(&StampTool::new(true), &[br#"{"x": 1}"#]);
+    }
+
+    #[test]
+    fn test_normalization_rules() {
+        let normalizer = Normalizer::new().with_rules(vec![
+            NormalizationRule::StripField {
+                field: "$..request_id".to_string(),
+            },
+            NormalizationRule::StripTimestamps {
+                field: "$".to_string(),
+            },
+            NormalizationRule::SortArray {
+                field: "$.tags".to_string(),
+            },
+            NormalizationRule::RoundFloats {
+                field: "$.stats.*".to_string(),
+                precision: 2,
+            },
+            NormalizationRule::ReplacePath {
+                field: "$.files[*]".to_string(),
+                prefix: "/home/ci/run-42/".to_string(),
+                replacement: "$WORKDIR".to_string(),
+            },
+        ]);
+        let run = |host: &str, at: &str, tags: &str, mean: f64| {
+            let output = serde_json::json!({
+                "meta": {"request_id": host, "at": at},
+                "tags": serde_json::from_str::<serde_json::Value>(tags).unwrap(),
+                "stats": {"mean": mean, "count": 3},
+                "files": [format!("/home/ci/{}/out.txt", host), "/home/ci/run-420/x"],
+            });
+            normalizer.normalize(&serde_json::to_vec(&output).unwrap()).unwrap()
+        };
+
+        let a = run("run-42", "2024-05-01T10:00:00Z", r#"["b", "a"]"#, 0.333_333_1);
+        let b = run("run-42", "2024-05-01T10:00:07.25+02:00", r#"["a", "b"]"#, 0.333_334);
+        assert_eq!(first_difference(&a.data, &b.data), None);
+        assert_eq!(
+            a.data,
+            serde_json::json!({
+                "meta": {"at": TIMESTAMP_PLACEHOLDER},
+                "tags": ["a", "b"],
+                "stats": {"count": 3, "mean": 0.33},
+                "files": ["$WORKDIR/out.txt", "/home/ci/run-420/x"],
+            })
+        );
+        assert!(a.transformations.contains(&"sort_array $.tags".to_string()));
+
+        let bad = Normalizer::new().with_rules(vec![NormalizationRule::SortArray {
+            field: "tags".to_string(),
+        }]);
+        assert!(matches!(
+            bad.normalize(b"{}"),
+            Err(NormalizationError::InvalidPath { .. })
+        ));
+    }
+
+    #[test]
+    fn test_field_pattern_matching() {
+        let path = [Step::Key("a".to_string()), Step::Index(2), Step::Key("b".to_string())];
+        for pattern in ["$.a[2].b", "$.a[*].b", "$..b", "$.*[*].*", "$..[2].b"] {
+            assert!(FieldPattern::parse(pattern).unwrap().matches(&path), "{pattern}");
+        }
+        for pattern in ["$.a[1].b", "$.a.b", "$..c", "$"] {
+            assert!(!FieldPattern::parse(pattern).unwrap().matches(&path), "{pattern}");
+        }
+        assert!(FieldPattern::parse("$.a[x]").is_err());
+        assert!(is_rfc3339("2024-01-02T03:04:05.678Z"));
+        assert!(!is_rfc3339("2024-01-02"));
+    }
+
+    #[test]
+    fn test_schema_rules_make_check_deterministic() {
+        let schema = ToolSchema::new("stamp".to_string(), "1.0.0".to_string())
+            .with_normalization_rule(NormalizationRule::StripField {
+                field: "$.meta.timestamp".to_string(),
+            });
+        let tool = StampTool::new(true);
+        let normalizer = Normalizer::for_schema(&schema);
+        let inputs: &[&[u8]] = &[br#"{"x": 1}"#];
+        let divergence = check_normalization_deterministic_with(&tool, &normalizer, inputs);
+        assert_eq!(divergence.unwrap(), None);
+    }
+
     #[test]
     fn test_normalization_error_display() {
         let err = NormalizationError::InvalidJson {
@@ -2,7 +2,7 @@
 
 use cathedral_core::Capability;
 use indexmap::IndexMap;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::{Arc, RwLock};
 use crate::trait_::{Tool, ToolError};
 use crate::schema::ToolSchema;
@@ -18,6 +18,19 @@ pub enum RegistryError {
     VersionConflict { name: String, existing: String, new: String },
     /// Schema mismatch
     SchemaMismatch { reason: String },
+    /// Same name and version registered with a different schema
+    SchemaConflict {
+        name: String,
+        version: String,
+        existing: String,
+        new: String,
+    },
+    /// Tool version is not valid semver
+    InvalidVersion {
+        name: String,
+        version: String,
+        reason: String,
+    },
 }
 
 impl std::fmt::Display for RegistryError {
@@ -33,6 +46,16 @@ impl std::fmt::Display for RegistryError {
                 )
             }
             Self::SchemaMismatch { reason } => write!(f, "Schema mismatch: {}", reason),
+            Self::SchemaConflict { name, version, existing, new } => {
+                write!(
+                    f,
+                    "Schema conflict for {}@{}: registered schema {}, new schema {}",
+                    name, version, existing, new
+                )
+            }
+            Self::InvalidVersion { name, version, reason } => {
+                write!(f, "Invalid version {} for {}: {}", version, name, reason)
+            }
         }
     }
 }
@@ -88,9 +111,13 @@ impl ToolEntry {
 ///
 /// The registry provides dynamic tool discovery and lazy loading.
 /// Tools are assumed to be potentially hostile.
+///
+/// Several versions of a tool may be registered side by side. Lookups by
+/// name alone pick the highest enabled version; [`ToolRegistry::resolve`]
+/// picks the highest enabled version satisfying a semver requirement.
 pub struct ToolRegistry {
-    /// Registered tools by name
-    tools: IndexMap<String, ToolEntry>,
+    /// Registered tools by name, then by version
+    tools: IndexMap<String, BTreeMap<String, ToolEntry>>,
 }
 
 impl ToolRegistry {
@@ -104,9 +131,13 @@ impl ToolRegistry {
 
     /// Register a tool
     ///
+    /// Other versions of the same tool stay registered.
+    ///
     /// # Errors
     ///
-    /// Returns error if tool already registered or version conflict
+    /// Returns error if the version is not valid semver, the schema does not
+    /// describe the tool, or the same name and version is already
+    /// registered (`SchemaConflict` if with a different schema)
     pub fn register(
         &mut self,
         tool: Arc<dyn Tool>,
@@ -115,39 +146,49 @@ impl ToolRegistry {
         let name = tool.name().to_string();
         let version = tool.version().to_string();
 
-        // Check for existing tool
-        if let Some(existing) = self.tools.get(&name) {
-            if existing.version != version {
-                return Err(RegistryError::VersionConflict {
+        semver::Version::parse(&version).map_err(|e| RegistryError::InvalidVersion {
+            name: name.clone(),
+            version: version.clone(),
+            reason: e.to_string(),
+        })?;
+        if schema.name != name || schema.version != version {
+            return Err(RegistryError::SchemaMismatch {
+                reason: format!(
+                    "schema describes {}@{}, tool is {}@{}",
+                    schema.name, schema.version, name, version
+                ),
+            });
+        }
+
+        let versions = self.tools.entry(name.clone()).or_default();
+        if let Some(existing) = versions.get(&version) {
+            let existing_hash = schema_hash(&existing.schema)?;
+            let new_hash = schema_hash(&schema)?;
+            if existing_hash != new_hash {
+                return Err(RegistryError::SchemaConflict {
                     name,
-                    existing: existing.version.clone(),
-                    new: version,
+                    version,
+                    existing: existing_hash.to_hex(),
+                    new: new_hash.to_hex(),
                 });
             }
             return Err(RegistryError::AlreadyRegistered { name });
         }
 
-        let entry = ToolEntry::new(tool, schema);
-        self.tools.insert(name, entry);
+        versions.insert(version, ToolEntry::new(tool, schema));
         Ok(())
     }
 
-    /// Get a tool by name
+    /// Get a tool by name, at its highest enabled version
     ///
     /// # Errors
     ///
     /// Returns error if tool not found
     pub fn get(&self, name: &str) -> Result<Arc<dyn Tool>, ToolError> {
-        self.tools
-            .get(name)
-            .filter(|e| e.enabled)
-            .map(|e| Arc::clone(&e.tool))
-            .ok_or_else(|| ToolError::NotFound {
-                name: name.to_string(),
-            })
+        self.get_entry(name).map(|e| e.tool)
     }
 
-    /// Get tool entry by name
+    /// Get tool entry by name, at its highest enabled version
     ///
     /// # Errors
     ///
@@ -155,19 +196,66 @@ impl ToolRegistry {
     pub fn get_entry(&self, name: &str) -> Result<ToolEntry, ToolError> {
         self.tools
             .get(name)
-            .filter(|e| e.enabled)
+            .and_then(|versions| highest(versions.values().filter(|e| e.enabled)))
             .cloned()
             .ok_or_else(|| ToolError::NotFound {
                 name: name.to_string(),
             })
     }
 
+    /// Resolve a tool entry by name and semver requirement (e.g. `^1.0`)
+    ///
+    /// The highest enabled version satisfying the requirement wins, so the
+    /// result depends only on what is registered, not on registration order.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tool not found, the requirement is malformed, or no
+    /// registered version satisfies it
+    pub fn resolve(&self, name: &str, version_req: &str) -> Result<ToolEntry, ToolError> {
+        let versions = self
+            .tools
+            .get(name)
+            .filter(|versions| versions.values().any(|e| e.enabled))
+            .ok_or_else(|| ToolError::NotFound {
+                name: name.to_string(),
+            })?;
+
+        let req = semver::VersionReq::parse(version_req).map_err(|e| ToolError::InvalidInput {
+            reason: format!("invalid version requirement {}: {}", version_req, e),
+        })?;
+        let matching = versions.values().filter(|e| {
+            e.enabled
+                && semver::Version::parse(&e.version)
+                    .map(|v| req.matches(&v))
+                    .unwrap_or(false)
+        });
+
+        highest(matching).cloned().ok_or_else(|| ToolError::VersionMismatch {
+            name: name.to_string(),
+            required: version_req.to_string(),
+            found: self.versions(name).join(", "),
+        })
+    }
+
+    /// Enabled versions of a tool, lowest first
+    #[must_use]
+    pub fn versions(&self, name: &str) -> Vec<String> {
+        let mut versions: Vec<&ToolEntry> = self
+            .tools
+            .get(name)
+            .map(|versions| versions.values().filter(|e| e.enabled).collect())
+            .unwrap_or_default();
+        versions.sort_by_key(|e| semver::Version::parse(&e.version).ok());
+        versions.into_iter().map(|e| e.version.clone()).collect()
+    }
+
     /// List all registered tool names
     #[must_use]
     pub fn list(&self) -> Vec<String> {
         self.tools
             .iter()
-            .filter(|(_, e)| e.enabled)
+            .filter(|(_, versions)| versions.values().any(|e| e.enabled))
             .map(|(name, _)| name.clone())
             .collect()
     }
@@ -177,7 +265,11 @@ impl ToolRegistry {
     pub fn list_by_capability(&self, capability: &Capability) -> Vec<String> {
         self.tools
             .iter()
-            .filter(|(_, e)| e.enabled && e.has_capability(capability))
+            .filter(|(_, versions)| {
+                versions
+                    .values()
+                    .any(|e| e.enabled && e.has_capability(capability))
+            })
             .map(|(name, _)| name.clone())
             .collect()
     }
@@ -187,39 +279,38 @@ impl ToolRegistry {
     pub fn contains(&self, name: &str) -> bool {
         self.tools
             .get(name)
-            .map(|e| e.enabled)
+            .map(|versions| versions.values().any(|e| e.enabled))
             .unwrap_or(false)
     }
 
-    /// Enable a tool
+    /// Enable every version of a tool
     ///
     /// # Errors
     ///
     /// Returns error if tool not found
     pub fn enable(&mut self, name: &str) -> Result<(), ToolError> {
-        self.tools
-            .get_mut(name)
-            .map(|e| e.enabled = true)
-            .ok_or_else(|| ToolError::NotFound {
-                name: name.to_string(),
-            })
+        self.set_enabled(name, true)
     }
 
-    /// Disable a tool
+    /// Disable every version of a tool
     ///
     /// # Errors
     ///
     /// Returns error if tool not found
     pub fn disable(&mut self, name: &str) -> Result<(), ToolError> {
+        self.set_enabled(name, false)
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), ToolError> {
         self.tools
             .get_mut(name)
-            .map(|e| e.enabled = false)
+            .map(|versions| versions.values_mut().for_each(|e| e.enabled = enabled))
             .ok_or_else(|| ToolError::NotFound {
                 name: name.to_string(),
             })
     }
 
-    /// Unregister a tool
+    /// Unregister every version of a tool
     ///
     /// # Errors
     ///
@@ -233,10 +324,28 @@ impl ToolRegistry {
             })
     }
 
+    /// Unregister one version of a tool
+    ///
+    /// # Errors
+    ///
+    /// Returns error if that version is not registered
+    pub fn unregister_version(&mut self, name: &str, version: &str) -> Result<(), ToolError> {
+        let versions = self.tools.get_mut(name).ok_or_else(|| ToolError::NotFound {
+            name: name.to_string(),
+        })?;
+        versions.remove(version).ok_or_else(|| ToolError::NotFound {
+            name: format!("{}@{}", name, version),
+        })?;
+        if versions.is_empty() {
+            self.tools.shift_remove(name);
+        }
+        Ok(())
+    }
+
     /// Get the count of registered tools
     #[must_use]
     pub fn count(&self) -> usize {
-        self.tools.iter().filter(|(_, e)| e.enabled).count()
+        self.list().len()
     }
 
     /// Check if registry is empty
@@ -246,6 +355,17 @@ impl ToolRegistry {
     }
 }
 
+/// The entry with the highest semver version; unparsable versions rank lowest
+fn highest<'a>(entries: impl Iterator<Item = &'a ToolEntry>) -> Option<&'a ToolEntry> {
+    entries.max_by_key(|e| semver::Version::parse(&e.version).ok())
+}
+
+fn schema_hash(schema: &ToolSchema) -> Result<cathedral_core::Hash, RegistryError> {
+    schema.hash().map_err(|e| RegistryError::SchemaMismatch {
+        reason: e.to_string(),
+    })
+}
+
 impl Default for ToolRegistry {
     fn default() -> Self {
         Self::new()
@@ -290,6 +410,26 @@ impl SharedRegistry {
         registry.get(name)
     }
 
+    /// Get tool entry by name
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tool not found
+    pub fn get_entry(&self, name: &str) -> Result<ToolEntry, ToolError> {
+        let registry = self.inner.read().unwrap();
+        registry.get_entry(name)
+    }
+
+    /// Resolve a tool entry by name and semver requirement
+    ///
+    /// # Errors
+    ///
+    /// Returns error if tool not found or version does not match
+    pub fn resolve(&self, name: &str, version_req: &str) -> Result<ToolEntry, ToolError> {
+        let registry = self.inner.read().unwrap();
+        registry.resolve(name, version_req)
+    }
+
     /// List all registered tool names
     #[must_use]
     pub fn list(&self) -> Vec<String> {
@@ -304,6 +444,15 @@ impl Default for SharedRegistry {
     }
 }
 
+impl std::fmt::Debug for SharedRegistry {
+    /// Lists the registered tool names
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedRegistry")
+            .field("tools", &self.list())
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -311,6 +460,7 @@ mod tests {
 
     struct DummyTool {
         name: String,
+        version: String,
     }
 
     impl Tool for DummyTool {
@@ -318,14 +468,23 @@ mod tests {
             &self.name
         }
 
+        fn version(&self) -> &str {
+            &self.version
+        }
+
         fn execute(&self, _input: &[u8]) -> cathedral_core::CoreResult<crate::trait_::ToolOutput> {
             Ok(crate::trait_::ToolOutput::success(b"ok".to_vec()))
         }
     }
 
     fn make_tool(name: &str) -> Arc<dyn Tool> {
+        make_versioned_tool(name, "1.0.0")
+    }
+
+    fn make_versioned_tool(name: &str, version: &str) -> Arc<dyn Tool> {
         Arc::new(DummyTool {
             name: name.to_string(),
+            version: version.to_string(),
         })
     }
 
@@ -405,6 +564,50 @@ mod tests {
         assert!(!registry.contains("test_tool"));
     }
 
+    #[test]
+    fn test_registry_multiple_versions() {
+        let mut registry = ToolRegistry::new();
+        for version in ["1.2.0", "2.0.0", "1.10.1", "1.2.5"] {
+            let schema = ToolSchema::new("fetch".to_string(), version.to_string());
+            registry.register(make_versioned_tool("fetch", version), schema).unwrap();
+        }
+        assert_eq!(registry.count(), 1);
+        assert_eq!(registry.versions("fetch"), ["1.2.0", "1.2.5", "1.10.1", "2.0.0"]);
+        assert_eq!(registry.get_entry("fetch").unwrap().version, "2.0.0");
+        assert_eq!(registry.resolve("fetch", "^1.2").unwrap().version, "1.10.1");
+        assert_eq!(registry.resolve("fetch", "~1.2").unwrap().version, "1.2.5");
+        assert!(matches!(
+            registry.resolve("fetch", "^3"),
+            Err(ToolError::VersionMismatch { .. })
+        ));
+
+        registry.unregister_version("fetch", "2.0.0").unwrap();
+        assert_eq!(registry.get_entry("fetch").unwrap().version, "1.10.1");
+        assert!(registry.unregister_version("fetch", "2.0.0").is_err());
+    }
+
+    #[test]
+    fn test_registry_schema_conflict() {
+        let mut registry = ToolRegistry::new();
+        let schema = ToolSchema::new("fetch".to_string(), "1.0.0".to_string());
+        registry.register(make_tool("fetch"), schema.clone()).unwrap();
+
+        assert!(matches!(
+            registry.register(make_tool("fetch"), schema.clone()),
+            Err(RegistryError::AlreadyRegistered { .. })
+        ));
+        let changed = schema.with_capability(Capability::FsRead { prefixes: vec![] });
+        assert!(matches!(
+            registry.register(make_tool("fetch"), changed),
+            Err(RegistryError::SchemaConflict { .. })
+        ));
+        let schema = ToolSchema::new("fetch".to_string(), "latest".to_string());
+        assert!(matches!(
+            registry.register(make_versioned_tool("fetch", "latest"), schema),
+            Err(RegistryError::InvalidVersion { .. })
+        ));
+    }
+
     #[test]
     fn test_shared_registry() {
         let shared = SharedRegistry::new();
@@ -0,0 +1,302 @@
+//! OS-level confinement for subprocess tools.
+//!
+//! On Linux, resource limits are applied with `setrlimit` and filesystem
+//! access is confined with Landlock. The ruleset is built in the parent and
+//! enforced in the child between `fork` and `exec`, so the child only makes
+//! async-signal-safe system calls. Other platforms cannot confine a process
+//! and refuse to spawn one.
+
+use std::io;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+
+/// Kind of filesystem access granted beneath a path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Access {
+    /// Read files and list directories
+    Read,
+    /// Create, modify, and remove files and directories
+    Write,
+    /// Read and execute files, for the program and its runtime
+    Execute,
+}
+
+/// Limits and filesystem rules a child process runs under
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Confinement {
+    /// `RLIMIT_CPU` in seconds
+    pub(crate) cpu_seconds: Option<u64>,
+    /// `RLIMIT_AS` in bytes
+    pub(crate) memory_bytes: Option<u64>,
+    /// Paths the process may access; everything else is denied
+    pub(crate) rules: Vec<(PathBuf, Access)>,
+}
+
+/// Spawn `command` under `confinement`, in its own process group
+///
+/// Fails closed: if the kernel cannot enforce the filesystem rules the
+/// process is not started.
+#[cfg(target_os = "linux")]
+pub(crate) fn spawn(mut command: Command, confinement: &Confinement) -> io::Result<Child> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::process::CommandExt;
+
+    let mut ruleset = landlock::Ruleset::new()?;
+    for (path, access) in &confinement.rules {
+        ruleset.allow(path, *access)?;
+    }
+    let ruleset_fd = ruleset.as_raw_fd();
+    let cpu_seconds = confinement.cpu_seconds;
+    let memory_bytes = confinement.memory_bytes;
+
+    command.process_group(0);
+    // SAFETY: the closure only calls setrlimit, prctl, and
+    // landlock_restrict_self, all async-signal-safe, and allocates nothing.
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(seconds) = cpu_seconds {
+                // The soft limit delivers SIGXCPU; the hard limit a second
+                // later guarantees termination if that signal is ignored.
+                let limit = rlimit(seconds, seconds.saturating_add(1));
+                check(libc::setrlimit(libc::RLIMIT_CPU, &limit))?;
+            }
+            if let Some(bytes) = memory_bytes {
+                check(libc::setrlimit(libc::RLIMIT_AS, &rlimit(bytes, bytes)))?;
+            }
+            landlock::restrict_self(ruleset_fd)
+        });
+    }
+    // The ruleset fd is close-on-exec, so it does not leak into the tool.
+    command.spawn()
+}
+
+/// Spawn `command` under `confinement`, in its own process group
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn spawn(_command: Command, _confinement: &Confinement) -> io::Result<Child> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "process confinement is only supported on Linux",
+    ))
+}
+
+/// Kill every process in the group led by `child`
+#[cfg(target_os = "linux")]
+pub(crate) fn kill_group(child: &mut Child) {
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: signalling a process group has no memory-safety
+        // requirements. The group ID is not reused while any process
+        // in the group remains, even after `child` is reaped.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+}
+
+/// Kill every process in the group led by `child`
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn kill_group(child: &mut Child) {
+    let _ = child.kill();
+}
+
+#[cfg(target_os = "linux")]
+const fn rlimit(soft: u64, hard: u64) -> libc::rlimit {
+    libc::rlimit {
+        rlim_cur: soft,
+        rlim_max: hard,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn check(ret: libc::c_int) -> io::Result<()> {
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Minimal Landlock bindings (`include/uapi/linux/landlock.h`)
+#[cfg(target_os = "linux")]
+mod landlock {
+    use super::Access;
+    use std::fs::{self, File};
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::Path;
+
+    const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+    const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+    const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+    const ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+    const ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+    const ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+    const ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+    const ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+    const ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+    const ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+    /// Added in ABI 2
+    const ACCESS_FS_REFER: u64 = 1 << 13;
+    /// Added in ABI 3
+    const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+    /// Added in ABI 5
+    const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+
+    /// Rights that may be granted on a regular file rather than a directory
+    const FILE_ACCESS: u64 = ACCESS_FS_EXECUTE
+        | ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_READ_FILE
+        | ACCESS_FS_TRUNCATE
+        | ACCESS_FS_IOCTL_DEV;
+    const READ_ACCESS: u64 = ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+    const WRITE_ACCESS: u64 = ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_REMOVE_DIR
+        | ACCESS_FS_REMOVE_FILE
+        | ACCESS_FS_MAKE_CHAR
+        | ACCESS_FS_MAKE_DIR
+        | ACCESS_FS_MAKE_REG
+        | ACCESS_FS_MAKE_SOCK
+        | ACCESS_FS_MAKE_FIFO
+        | ACCESS_FS_MAKE_BLOCK
+        | ACCESS_FS_MAKE_SYM
+        | ACCESS_FS_REFER
+        | ACCESS_FS_TRUNCATE;
+
+    const CREATE_RULESET_VERSION: u32 = 1 << 0;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    /// A ruleset handling every filesystem right the kernel knows about
+    pub(super) struct Ruleset {
+        fd: OwnedFd,
+        handled: u64,
+    }
+
+    impl Ruleset {
+        pub(super) fn new() -> io::Result<Self> {
+            // SAFETY: a null attribute with size 0 queries the ABI version.
+            let abi = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_create_ruleset,
+                    std::ptr::null::<RulesetAttr>(),
+                    0usize,
+                    CREATE_RULESET_VERSION,
+                )
+            };
+            if abi < 0 {
+                return Err(io::Error::other(format!(
+                    "Landlock is unavailable: {}",
+                    io::Error::last_os_error()
+                )));
+            }
+
+            let mut handled = (1 << 13) - 1;
+            if abi >= 2 {
+                handled |= ACCESS_FS_REFER;
+            }
+            if abi >= 3 {
+                handled |= ACCESS_FS_TRUNCATE;
+            }
+            if abi >= 5 {
+                handled |= ACCESS_FS_IOCTL_DEV;
+            }
+            let attr = RulesetAttr {
+                handled_access_fs: handled,
+            };
+            // SAFETY: `attr` is valid and its size is passed alongside it.
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_create_ruleset,
+                    &attr as *const RulesetAttr,
+                    std::mem::size_of::<RulesetAttr>(),
+                    0u32,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = RawFd::try_from(fd).map_err(io::Error::other)?;
+            // SAFETY: the kernel returned a fresh descriptor that we now own.
+            Ok(Self {
+                fd: unsafe { OwnedFd::from_raw_fd(fd) },
+                handled,
+            })
+        }
+
+        /// Allow `access` beneath `path`; paths that do not exist are skipped
+        pub(super) fn allow(&mut self, path: &Path, access: Access) -> io::Result<()> {
+            let metadata = match fs::metadata(path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            let mut allowed = match access {
+                Access::Read => READ_ACCESS,
+                Access::Write => WRITE_ACCESS,
+                Access::Execute => READ_ACCESS | ACCESS_FS_EXECUTE,
+            } & self.handled;
+            if !metadata.is_dir() {
+                allowed &= FILE_ACCESS;
+            }
+            if allowed == 0 {
+                return Ok(());
+            }
+
+            let file = File::options()
+                .read(true)
+                .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+                .open(path)?;
+            let attr = PathBeneathAttr {
+                allowed_access: allowed,
+                parent_fd: file.as_raw_fd(),
+            };
+            // SAFETY: `attr` and both descriptors are valid for the call.
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    self.fd.as_raw_fd(),
+                    RULE_PATH_BENEATH,
+                    &attr as *const PathBeneathAttr,
+                    0u32,
+                )
+            };
+            if ret < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+    }
+
+    impl AsRawFd for Ruleset {
+        fn as_raw_fd(&self) -> RawFd {
+            self.fd.as_raw_fd()
+        }
+    }
+
+    /// Enforce the ruleset on the calling process; async-signal-safe
+    pub(super) fn restrict_self(ruleset: RawFd) -> io::Result<()> {
+        // SAFETY: plain system calls on integer arguments.
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0u32) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
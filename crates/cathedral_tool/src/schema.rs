@@ -1,8 +1,9 @@
 //! Tool schemas for input/output validation.
 
-use cathedral_core::Capability;
+use crate::normalize::NormalizationRule;
+use cathedral_core::{Capability, CoreResult, Hash};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 /// Schema for a tool
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -19,6 +20,12 @@ pub struct ToolSchema {
     pub capabilities: BTreeSet<Capability>,
     /// Declared side effects
     pub side_effects: Vec<SideEffect>,
+    /// Maximum number of effects per kind (see [`SideEffect::kind`])
+    #[serde(default)]
+    pub effect_budgets: BTreeMap<String, usize>,
+    /// Rules canonicalizing nondeterministic output fields before hashing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub normalization: Vec<NormalizationRule>,
 }
 
 impl ToolSchema {
@@ -32,6 +39,8 @@ impl ToolSchema {
             output: OutputSchema::new(),
             capabilities: BTreeSet::new(),
             side_effects: Vec::new(),
+            effect_budgets: BTreeMap::new(),
+            normalization: Vec::new(),
         }
     }
 
@@ -49,6 +58,20 @@ impl ToolSchema {
         self
     }
 
+    /// Cap the number of effects of one kind a single execution may perform
+    #[must_use]
+    pub fn with_effect_budget(mut self, kind: &str, max: usize) -> Self {
+        self.effect_budgets.insert(kind.to_string(), max);
+        self
+    }
+
+    /// Add a rule canonicalizing part of the tool's output
+    #[must_use]
+    pub fn with_normalization_rule(mut self, rule: NormalizationRule) -> Self {
+        self.normalization.push(rule);
+        self
+    }
+
     /// Set input schema
     #[must_use]
     pub fn with_input(mut self, schema: InputSchema) -> Self {
@@ -62,6 +85,15 @@ impl ToolSchema {
         self.output = schema;
         self
     }
+
+    /// Content hash of the encoded schema
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn hash(&self) -> CoreResult<Hash> {
+        Ok(Hash::compute(&serde_json::to_vec(self)?))
+    }
 }
 
 /// Input schema for a tool
@@ -223,6 +255,22 @@ impl SideEffect {
         )
     }
 
+    /// Stable name of the effect kind, used as the budget key
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::FsRead { .. } => "fs_read",
+            Self::FsWrite { .. } => "fs_write",
+            Self::FsDelete { .. } => "fs_delete",
+            Self::NetRequest { .. } => "net_request",
+            Self::EnvRead { .. } => "env_read",
+            Self::EnvWrite { .. } => "env_write",
+            Self::Exec { .. } => "exec",
+            Self::DbQuery { .. } => "db_query",
+            Self::Custom { .. } => "custom",
+        }
+    }
+
     /// Get a description of the side effect
     #[must_use]
     pub fn describe(&self) -> String {
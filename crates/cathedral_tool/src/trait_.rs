@@ -1,6 +1,7 @@
 //! Tool trait for deterministic tool execution.
 
-use cathedral_core::{CoreResult, CoreError};
+use cathedral_core::{CancellationToken, CoreResult, CoreError};
+use crate::schema::SideEffect;
 use serde::{Deserialize, Serialize};
 
 /// Output from a tool execution
@@ -14,8 +15,11 @@ pub struct ToolOutput {
     pub stdout: Vec<u8>,
     /// Standard error captured
     pub stderr: Vec<u8>,
-    /// Side effects that occurred
-    pub side_effects: Vec<String>,
+    /// Side effects that occurred, in the order they were performed
+    pub side_effects: Vec<SideEffect>,
+    /// Encoded records of the host calls a sandboxed tool made, in call order
+    #[serde(default)]
+    pub host_calls: Vec<Vec<u8>>,
 }
 
 impl ToolOutput {
@@ -28,6 +32,7 @@ impl ToolOutput {
             stdout: Vec::new(),
             stderr: Vec::new(),
             side_effects: Vec::new(),
+            host_calls: Vec::new(),
         }
     }
 
@@ -40,9 +45,24 @@ impl ToolOutput {
             stdout: Vec::new(),
             stderr,
             side_effects: Vec::new(),
+            host_calls: Vec::new(),
         }
     }
 
+    /// Record a side effect performed by the tool
+    #[must_use]
+    pub fn with_side_effect(mut self, effect: SideEffect) -> Self {
+        self.side_effects.push(effect);
+        self
+    }
+
+    /// Attach the encoded records of the host calls the tool made
+    #[must_use]
+    pub fn with_host_calls(mut self, host_calls: Vec<Vec<u8>>) -> Self {
+        self.host_calls = host_calls;
+        self
+    }
+
     /// Check if the execution was successful
     #[must_use]
     pub fn is_success(&self) -> bool {
@@ -63,6 +83,15 @@ pub enum ToolError {
     Timeout,
     /// Capability denied
     CapabilityDenied { capability: String },
+    /// Registered version does not satisfy the requested requirement
+    VersionMismatch {
+        /// Tool name
+        name: String,
+        /// Requested version requirement
+        required: String,
+        /// Registered version
+        found: String,
+    },
 }
 
 impl std::fmt::Display for ToolError {
@@ -75,6 +104,9 @@ impl std::fmt::Display for ToolError {
             Self::CapabilityDenied { capability } => {
                 write!(f, "Capability denied: {}", capability)
             }
+            Self::VersionMismatch { name, required, found } => {
+                write!(f, "Tool {} version {} does not satisfy {}", name, found, required)
+            }
         }
     }
 }
@@ -110,6 +142,23 @@ pub trait Tool: Send + Sync {
     /// Returns error if execution fails
     fn execute(&self, input: &[u8]) -> CoreResult<ToolOutput>;
 
+    /// Execute the tool, stopping early once `cancellation` is cancelled
+    ///
+    /// Tools that cannot be interrupted run to completion, which is the
+    /// default.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if execution fails or is cancelled
+    fn execute_cancellable(
+        &self,
+        input: &[u8],
+        cancellation: &CancellationToken,
+    ) -> CoreResult<ToolOutput> {
+        let _ = cancellation;
+        self.execute(input)
+    }
+
     /// Get the tool's input schema (JSON Schema)
     fn input_schema(&self) -> Option<String> {
         None
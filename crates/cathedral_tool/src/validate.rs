@@ -1,7 +1,9 @@
 //! Tool validation for safety and correctness.
 
+use crate::json_schema::JsonSchema;
 use crate::schema::{ToolSchema, SideEffect};
 use crate::trait_::Tool;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 /// Validation error
@@ -19,6 +21,15 @@ pub enum ValidationError {
     SchemaError { field: String, reason: String },
     /// Resource limit exceeded
     ResourceLimit { resource: String, limit: u64 },
+    /// More effects of one kind than the budget allows
+    EffectBudgetExceeded {
+        /// Effect kind
+        kind: String,
+        /// Budget for the kind
+        limit: usize,
+        /// Effects of the kind attempted, including the rejected one
+        count: usize,
+    },
 }
 
 impl std::fmt::Display for ValidationError {
@@ -40,6 +51,9 @@ impl std::fmt::Display for ValidationError {
             Self::ResourceLimit { resource, limit } => {
                 write!(f, "Resource limit: {} exceeds {}", resource, limit)
             }
+            Self::EffectBudgetExceeded { kind, limit, count } => {
+                write!(f, "Effect budget exceeded: {} {} effects, budget {}", count, kind, limit)
+            }
         }
     }
 }
@@ -236,6 +250,14 @@ impl ToolValidator {
             });
         }
 
+        // Check declared JSON Schemas are well-formed
+        if let Some(ref json_schema) = schema.input.json_schema {
+            compile_json_schema(json_schema, "input")?;
+        }
+        if let Some(ref json_schema) = schema.output.json_schema {
+            compile_json_schema(json_schema, "output")?;
+        }
+
         Ok(())
     }
 
@@ -272,14 +294,8 @@ impl ToolValidator {
 
         // Validate against schema
         if let Some(ref json_schema) = schema.output.json_schema {
-            // For now, just check it's valid JSON
-            if serde_json::from_slice::<serde_json::Value>(output).is_err() {
-                return Err(ValidationError::SchemaError {
-                    field: "output".to_string(),
-                    reason: "Output is not valid JSON".to_string(),
-                });
-            }
-            let _ = json_schema;
+            let value = parse_json(output, "output")?;
+            check_json_schema(json_schema, &value, "output")?;
         }
 
         Ok(())
@@ -306,21 +322,67 @@ impl ToolValidator {
         }
 
         // Validate against schema
-        if let Some(ref json_schema) = schema.input.json_schema {
-            // For now, just check it's valid JSON
-            if serde_json::from_slice::<serde_json::Value>(input).is_err() {
-                return Err(ValidationError::SchemaError {
-                    field: "input".to_string(),
-                    reason: "Input is not valid JSON".to_string(),
-                });
+        if schema.input.json_schema.is_some() || !schema.input.required_fields.is_empty() {
+            let value = parse_json(input, "input")?;
+            for field in &schema.input.required_fields {
+                if value.get(field).is_none() {
+                    return Err(ValidationError::SchemaError {
+                        field: format!("input.{}", field),
+                        reason: "is required".to_string(),
+                    });
+                }
+            }
+            if let Some(ref json_schema) = schema.input.json_schema {
+                check_json_schema(json_schema, &value, "input")?;
             }
-            let _ = json_schema;
         }
 
         Ok(())
     }
 }
 
+fn parse_json(bytes: &[u8], side: &str) -> Result<serde_json::Value, ValidationError> {
+    serde_json::from_slice(bytes).map_err(|e| ValidationError::SchemaError {
+        field: side.to_string(),
+        reason: format!("{} is not valid JSON: {}", capitalize(side), e),
+    })
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+/// Parse a declared JSON Schema, naming the side it belongs to on failure
+fn compile_json_schema(json_schema: &str, side: &str) -> Result<JsonSchema, ValidationError> {
+    JsonSchema::parse(json_schema).map_err(|reason| ValidationError::SchemaError {
+        field: format!("{}_schema", side),
+        reason,
+    })
+}
+
+/// Check `value` against a declared JSON Schema
+///
+/// The first violation is reported with its path rooted at `side`, e.g.
+/// `input.user.age`.
+fn check_json_schema(
+    json_schema: &str,
+    value: &serde_json::Value,
+    side: &str,
+) -> Result<(), ValidationError> {
+    let compiled = compile_json_schema(json_schema, side)?;
+    match compiled.validate(value).into_iter().next() {
+        Some(violation) => Err(ValidationError::SchemaError {
+            field: format!("{}{}", side, &violation.path[1..]),
+            reason: format!("{} ({})", violation.message, violation.keyword),
+        }),
+        None => Ok(()),
+    }
+}
+
 impl Default for ToolValidator {
     fn default() -> Self {
         Self::new()
@@ -334,6 +396,12 @@ pub struct SideEffectTracker {
     declared: Vec<SideEffect>,
     /// Actual side effects observed
     actual: Vec<String>,
+    /// Structured effects applied, in the order they were recorded
+    applied: Vec<SideEffect>,
+    /// Maximum effects per kind
+    budgets: BTreeMap<String, usize>,
+    /// Effects attempted per kind
+    counts: BTreeMap<String, usize>,
 }
 
 impl SideEffectTracker {
@@ -343,14 +411,78 @@ impl SideEffectTracker {
         Self {
             declared,
             actual: Vec::new(),
+            applied: Vec::new(),
+            budgets: BTreeMap::new(),
+            counts: BTreeMap::new(),
         }
     }
 
+    /// Set per-kind effect budgets
+    #[must_use]
+    pub fn with_budgets(mut self, budgets: BTreeMap<String, usize>) -> Self {
+        self.budgets = budgets;
+        self
+    }
+
+    /// Set the budget for one effect kind
+    #[must_use]
+    pub fn with_budget(mut self, kind: &str, max: usize) -> Self {
+        self.budgets.insert(kind.to_string(), max);
+        self
+    }
+
     /// Record an actual side effect
     pub fn record(&mut self, effect: String) {
         self.actual.push(effect);
     }
 
+    /// Record a structured side effect that was applied
+    pub fn record_effect(&mut self, effect: SideEffect) {
+        self.actual.push(effect.describe());
+        self.applied.push(effect);
+    }
+
+    /// Record a structured side effect, enforcing its kind's budget
+    ///
+    /// An effect over budget is not recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidationError::EffectBudgetExceeded`] with the attempted
+    /// count once a kind exceeds its budget
+    pub fn try_record_effect(&mut self, effect: SideEffect) -> Result<(), ValidationError> {
+        let kind = effect.kind();
+        let count = self.counts.entry(kind.to_string()).or_insert(0);
+        *count += 1;
+        if let Some(limit) = self.budgets.get(kind).copied().filter(|limit| *count > *limit) {
+            return Err(ValidationError::EffectBudgetExceeded {
+                kind: kind.to_string(),
+                limit,
+                count: *count,
+            });
+        }
+        self.record_effect(effect);
+        Ok(())
+    }
+
+    /// Number of effects of a kind attempted so far
+    #[must_use]
+    pub fn count(&self, kind: &str) -> usize {
+        self.counts.get(kind).copied().unwrap_or(0)
+    }
+
+    /// Get the structured effects applied so far
+    #[must_use]
+    pub fn applied(&self) -> &[SideEffect] {
+        &self.applied
+    }
+
+    /// Consume the tracker, returning the structured effects applied
+    #[must_use]
+    pub fn into_applied(self) -> Vec<SideEffect> {
+        self.applied
+    }
+
     /// Check if all actual effects were declared
     ///
     /// # Errors
@@ -425,6 +557,47 @@ mod tests {
         assert!(validator.validate_output(&vec![0u8; 100], &schema).is_err());
     }
 
+    #[test]
+    fn test_validate_json_schema_paths() {
+        use crate::schema::{InputSchema, OutputSchema};
+
+        let items = r#"{"type": "object", "properties": {"items": {"type": "array",
+            "items": {"type": "object", "required": ["qty"],
+                "properties": {"qty": {"type": "integer", "minimum": 1}}}}}}"#;
+        let schema = ToolSchema::new("order".to_string(), "1.0.0".to_string())
+            .with_input(
+                InputSchema::new()
+                    .with_json_schema(items.to_string())
+                    .with_required_field("items".to_string()),
+            )
+            .with_output(OutputSchema::new().with_json_schema(r#"{"enum": ["ok"]}"#.to_string()));
+        let validator = ToolValidator::new();
+
+        assert!(validator.validate_input(br#"{"items": [{"qty": 2}]}"#, &schema).is_ok());
+        assert_eq!(
+            validator.validate_input(br#"{"items": [{"qty": 2}, {"qty": 0}]}"#, &schema),
+            Err(ValidationError::SchemaError {
+                field: "input.items[1].qty".to_string(),
+                reason: "0 is less than 1 (minimum)".to_string(),
+            })
+        );
+        assert_eq!(
+            validator.validate_input(b"{}", &schema).unwrap_err().to_string(),
+            "Schema error in input.items: is required"
+        );
+        assert!(validator.validate_output(br#""ok""#, &schema).is_ok());
+        assert!(validator.validate_output(br#""done""#, &schema).is_err());
+        assert!(validator.validate_output(b"ok", &schema).is_err());
+
+        let broken = ToolSchema::new("order".to_string(), "1.0.0".to_string())
+            .with_output(OutputSchema::new().with_json_schema(r#"{"type": 1}"#.to_string()));
+        let tool: Arc<dyn Tool> = Arc::new(EchoTool);
+        assert!(matches!(
+            validator.validate(&tool, &broken),
+            Err(ValidationError::SchemaError { field, .. }) if field == "output_schema"
+        ));
+    }
+
     #[test]
     fn test_side_effect_tracker() {
         let declared = vec![SideEffect::FsRead {
@@ -439,6 +612,58 @@ mod tests {
         assert!(tracker.check().is_err());
     }
 
+    #[test]
+    fn test_side_effect_tracker_applied() {
+        let write = SideEffect::FsWrite {
+            path: "./out.txt".to_string(),
+        };
+        let mut tracker = SideEffectTracker::new(vec![write.clone()]);
+
+        tracker.record_effect(write.clone());
+        assert!(tracker.check().is_ok());
+        assert_eq!(tracker.applied(), std::slice::from_ref(&write));
+
+        tracker.record_effect(SideEffect::FsDelete {
+            path: "./out.txt".to_string(),
+        });
+        assert!(tracker.check().is_err());
+        assert_eq!(tracker.into_applied().len(), 2);
+    }
+
+    #[test]
+    fn test_side_effect_tracker_budget() {
+        let mut tracker = SideEffectTracker::new(Vec::new()).with_budget("fs_write", 1000);
+
+        for i in 0..1000 {
+            let effect = SideEffect::FsWrite {
+                path: format!("./out/{}.txt", i),
+            };
+            assert!(tracker.try_record_effect(effect).is_ok());
+        }
+        let err = tracker
+            .try_record_effect(SideEffect::FsWrite {
+                path: "./out/1000.txt".to_string(),
+            })
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ValidationError::EffectBudgetExceeded {
+                kind: "fs_write".to_string(),
+                limit: 1000,
+                count: 1001,
+            }
+        );
+        assert_eq!(tracker.applied().len(), 1000);
+        assert_eq!(tracker.count("fs_write"), 1001);
+
+        // Other kinds are unbudgeted
+        assert!(tracker
+            .try_record_effect(SideEffect::FsRead {
+                path: "./in.txt".to_string(),
+            })
+            .is_ok());
+    }
+
     #[test]
     fn test_validation_error_display() {
         let err = ValidationError::InvalidName {
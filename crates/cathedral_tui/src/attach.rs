@@ -0,0 +1,587 @@
+//! Live-follow of a run on a running server.
+//!
+//! A background thread subscribes to the run's `GET /runs/{id}/stream`
+//! and hands every event to the app over a channel. When the connection
+//! drops it reconnects with `Last-Event-ID` set to the last event it
+//! received, so no event is missed or delivered twice. A second thread
+//! polls `GET /cluster` for the worker view.
+
+use crate::ui::TuiError;
+use crate::view::{WorkerState, WorkerStatus};
+use cathedral_core::{EventId, LogicalTime, NodeId, RunId};
+use cathedral_log::{Event, EventKind};
+use cathedral_plan::Dag;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+
+/// Server and run to follow
+#[derive(Debug, Clone)]
+pub struct AttachConfig {
+    /// Server host and port
+    host: String,
+    /// Run to follow
+    run_id: RunId,
+    /// Bearer token, if the server requires one
+    token: Option<String>,
+    /// Delay before reconnecting a dropped stream
+    retry: Duration,
+    /// Interval between polls of the cluster's workers
+    poll: Duration,
+}
+
+impl AttachConfig {
+    /// Follow `run` on the server at `url`, e.g. `http://127.0.0.1:8080`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the URL is not a plain `http` server URL or `run`
+    /// is not a run ID
+    pub fn new(url: &str, run: &str) -> Result<Self, TuiError> {
+        let invalid = |what: &str| TuiError::Connection(format!("invalid {}", what));
+        let (scheme, rest) = url.split_once("://").unwrap_or(("http", url));
+        let host = rest.trim_end_matches('/');
+        if scheme != "http" || host.is_empty() || host.contains('/') {
+            return Err(invalid(&format!("server URL {}", url)));
+        }
+        let run_id = parse_id(run, "run_")
+            .map(RunId::from_bytes)
+            .ok_or_else(|| invalid(&format!("run ID {}", run)))?;
+        Ok(Self {
+            host: host.to_string(),
+            run_id,
+            token: None,
+            retry: Duration::from_secs(2),
+            poll: Duration::from_secs(2),
+        })
+    }
+
+    /// Authenticate with a bearer token
+    #[must_use]
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Set the delay before reconnecting and between worker polls
+    #[must_use]
+    pub fn with_retry(mut self, retry: Duration) -> Self {
+        self.retry = retry;
+        self.poll = retry;
+        self
+    }
+
+    /// Get the followed run
+    #[must_use]
+    pub fn run_id(&self) -> RunId {
+        self.run_id
+    }
+
+    /// Get the server's host and port
+    #[must_use]
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+}
+
+/// Update from a followed run
+#[derive(Debug, Clone)]
+pub enum LiveUpdate {
+    /// The event stream (re)connected, resuming after a position if any
+    Connected {
+        /// Position of the last event received before reconnecting
+        resumed_after: Option<u64>,
+    },
+    /// DAG the run was submitted with
+    Dag(Dag),
+    /// Event logged by the run
+    Event {
+        /// Position of the event in the run's log
+        position: u64,
+        /// The event
+        event: Event,
+    },
+    /// Current workers of the server's cluster
+    Workers(Vec<WorkerStatus>),
+    /// The event stream dropped and will reconnect
+    Disconnected(String),
+    /// The run finished and every event was delivered
+    Finished,
+}
+
+/// Handle on the threads following a run
+///
+/// Updates queue up until taken, so a paused app misses nothing. The
+/// threads stop once the handle is dropped.
+pub struct LiveFollow {
+    updates: Receiver<LiveUpdate>,
+}
+
+impl LiveFollow {
+    /// Start following a run
+    #[must_use]
+    pub fn start(config: AttachConfig) -> Self {
+        let (sender, updates) = mpsc::channel();
+        let workers = sender.clone();
+        let cluster = config.clone();
+        std::thread::spawn(move || follow(&config, &sender));
+        std::thread::spawn(move || poll_workers(&cluster, &workers));
+        Self { updates }
+    }
+
+    /// Take the next queued update, if any
+    #[must_use]
+    pub fn next(&self) -> Option<LiveUpdate> {
+        self.updates.try_recv().ok()
+    }
+}
+
+/// Follow the run's event stream until it finishes or the app goes away
+fn follow(config: &AttachConfig, updates: &Sender<LiveUpdate>) {
+    let mut dag_sent = false;
+    let mut last = None;
+    loop {
+        if !dag_sent && let Ok(dag) = get_json(config, &format!("/runs/{}/dag", config.run_id)) {
+            if updates.send(LiveUpdate::Dag(dag)).is_err() {
+                return;
+            }
+            dag_sent = true;
+        }
+        let update = match stream(config, &mut last, updates) {
+            Ok(true) => {
+                let _ = updates.send(LiveUpdate::Finished);
+                return;
+            }
+            Ok(false) => return,
+            Err(e) => LiveUpdate::Disconnected(e.to_string()),
+        };
+        if updates.send(update).is_err() {
+            return;
+        }
+        std::thread::sleep(config.retry);
+    }
+}
+
+/// Read the event stream after position `last`, advancing it
+///
+/// Returns whether the stream ended with the run, rather than because the
+/// app went away.
+fn stream(
+    config: &AttachConfig,
+    last: &mut Option<u64>,
+    updates: &Sender<LiveUpdate>,
+) -> io::Result<bool> {
+    let path = format!("/runs/{}/stream", config.run_id);
+    let resume = last.map(|position| ("Last-Event-ID", position.to_string()));
+    let body = get(config, &path, "text/event-stream", resume.as_slice())?;
+    if updates.send(LiveUpdate::Connected { resumed_after: *last }).is_err() {
+        return Ok(false);
+    }
+
+    let mut reader = BufReader::new(body);
+    let mut data = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(true);
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if let Some(value) = line.strip_prefix("data:") {
+            data.push_str(value.strip_prefix(' ').unwrap_or(value));
+        } else if line.is_empty() && !data.is_empty() {
+            let view: StreamEvent = serde_json::from_str(&std::mem::take(&mut data))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let position = view.position;
+            let event = view.into_event(config.run_id).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "undecodable event")
+            })?;
+            *last = Some(position);
+            if updates.send(LiveUpdate::Event { position, event }).is_err() {
+                return Ok(false);
+            }
+        }
+    }
+}
+
+/// Poll the cluster's workers until the app goes away
+///
+/// A server without a cluster has no workers to show, so polling stops.
+fn poll_workers(config: &AttachConfig, updates: &Sender<LiveUpdate>) {
+    loop {
+        match get_json::<ClusterView>(config, "/cluster") {
+            Ok(cluster) => {
+                let workers = cluster.members.into_iter().map(WorkerStatus::from).collect();
+                if updates.send(LiveUpdate::Workers(workers)).is_err() {
+                    return;
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => return,
+            Err(_) => {}
+        }
+        std::thread::sleep(config.poll);
+    }
+}
+
+/// Event as sent by the server's event stream
+#[derive(Debug, Deserialize)]
+struct StreamEvent {
+    position: u64,
+    event_id: String,
+    node_id: String,
+    kind: EventKind,
+    logical_time: u64,
+    /// Payload as hex
+    payload: String,
+}
+
+impl StreamEvent {
+    fn into_event(self, run_id: RunId) -> Option<Event> {
+        let event = Event::new(
+            EventId::from_bytes(parse_id(&self.event_id, "evt_")?),
+            run_id,
+            NodeId::from_bytes(parse_id(&self.node_id, "node_")?),
+            LogicalTime::from_raw(self.logical_time),
+            self.kind,
+        );
+        Some(event.with_payload(decode_hex(&self.payload)?))
+    }
+}
+
+/// Body of `GET /cluster`, as far as the worker view needs it
+#[derive(Debug, Deserialize)]
+struct ClusterView {
+    members: Vec<MemberView>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MemberView {
+    node_id: String,
+    state: String,
+    in_flight: usize,
+}
+
+impl From<MemberView> for WorkerStatus {
+    /// The server reports the tasks a worker has in flight, not the ones
+    /// it completed, so `total` counts tasks in flight
+    fn from(member: MemberView) -> Self {
+        let status = match member.state.as_str() {
+            "Active" if member.in_flight > 0 => WorkerState::Busy,
+            "Active" => WorkerState::Idle,
+            _ => WorkerState::Offline,
+        };
+        Self { id: member.node_id, status, completed: 0, total: member.in_flight }
+    }
+}
+
+/// GET a JSON route
+///
+/// A 503 answer, the server saying it lacks the feature, is reported as
+/// [`io::ErrorKind::Unsupported`].
+fn get_json<T: DeserializeOwned>(config: &AttachConfig, path: &str) -> io::Result<T> {
+    let mut body = Vec::new();
+    get(config, path, "application/json", &[])?.read_to_end(&mut body)?;
+    serde_json::from_slice(&body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Send a GET over a fresh connection and return the body of a 200 answer
+fn get(
+    config: &AttachConfig,
+    path: &str,
+    accept: &str,
+    headers: &[(&str, String)],
+) -> io::Result<Body> {
+    let mut stream = TcpStream::connect(&config.host)?;
+    stream.set_read_timeout(Some(Duration::from_secs(60)))?;
+    let mut request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: {}\r\nConnection: close\r\n",
+        path, config.host, accept
+    );
+    if let Some(token) = &config.token {
+        request.push_str(&format!("Authorization: Bearer {}\r\n", token));
+    }
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let status: u16 = line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed status line"))?;
+    let mut framing = Framing::Close;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") && value.eq_ignore_ascii_case("chunked")
+        {
+            framing = Framing::Chunked;
+        } else if name.eq_ignore_ascii_case("content-length")
+            && framing != Framing::Chunked
+            && let Ok(length) = value.parse()
+        {
+            framing = Framing::Length(length);
+        }
+    }
+    match status {
+        200 => Ok(Body::new(reader, framing)),
+        503 => Err(io::Error::new(io::ErrorKind::Unsupported, format!("{} answered 503", path))),
+        _ => Err(io::Error::other(format!("{} answered {}", path, status))),
+    }
+}
+
+/// How a response body's end is marked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    /// `Transfer-Encoding: chunked`, ended by an empty chunk
+    Chunked,
+    /// `Content-Length` bytes
+    Length(u64),
+    /// Everything until the connection closes
+    Close,
+}
+
+/// Response body, decoded from its framing
+///
+/// A chunked body that breaks off before its final chunk is an error
+/// rather than a short body, which tells a dropped stream from one the
+/// server ended.
+struct Body {
+    reader: BufReader<TcpStream>,
+    framing: Framing,
+    /// Bytes left in the current chunk or the body
+    remaining: u64,
+    done: bool,
+}
+
+impl Body {
+    fn new(reader: BufReader<TcpStream>, framing: Framing) -> Self {
+        let remaining = match framing {
+            Framing::Length(length) => length,
+            Framing::Chunked | Framing::Close => 0,
+        };
+        Self { reader, framing, remaining, done: false }
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(line)
+    }
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            match self.framing {
+                Framing::Chunked => {
+                    let line = self.read_line()?;
+                    let size = line.split(';').next().unwrap_or_default().trim();
+                    let size = u64::from_str_radix(size, 16).map_err(|_| {
+                        io::Error::new(io::ErrorKind::InvalidData, "malformed chunk size")
+                    })?;
+                    if size == 0 {
+                        self.done = true;
+                        return Ok(0);
+                    }
+                    self.remaining = size;
+                }
+                Framing::Length(_) => {
+                    self.done = true;
+                    return Ok(0);
+                }
+                Framing::Close => {}
+            }
+        }
+
+        let limit = match self.framing {
+            Framing::Close => buf.len(),
+            _ => buf.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX)),
+        };
+        let read = self.reader.read(&mut buf[..limit])?;
+        if read == 0 {
+            if self.framing != Framing::Close {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.done = true;
+            return Ok(0);
+        }
+        if self.framing != Framing::Close {
+            self.remaining -= read as u64;
+            if self.framing == Framing::Chunked && self.remaining == 0 {
+                self.read_line()?;
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Parse an ID in its display form, with or without its prefix
+fn parse_id(value: &str, prefix: &str) -> Option<[u8; 16]> {
+    uuid::Uuid::parse_str(value.strip_prefix(prefix).unwrap_or(value))
+        .ok()
+        .map(|uuid| *uuid.as_bytes())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// Serve one canned response per connection, returning the requests
+    fn serve(responses: Vec<String>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = listener.local_addr().unwrap().to_string();
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    request.push_str(&line);
+                }
+                requests.push(request);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (host, handle)
+    }
+
+    fn chunk(data: &str) -> String {
+        format!("{:x}\r\n{}\r\n", data.len(), data)
+    }
+
+    fn frame(position: u64, node_id: NodeId, kind: &str) -> String {
+        let data = serde_json::json!({
+            "position": position,
+            "event_id": EventId::new().to_string(),
+            "node_id": node_id.to_string(),
+            "kind": kind,
+            "logical_time": position,
+            "payload_hash": "",
+            "payload": "6f6b",
+        });
+        format!("id: {}\nevent: {}\ndata: {}\n\n", position, kind, data)
+    }
+
+    #[test]
+    fn test_attach_config_parses_url_and_run() {
+        let run_id = RunId::new();
+        let config = AttachConfig::new("http://127.0.0.1:8080/", &run_id.to_string()).unwrap();
+        assert_eq!(config.host(), "127.0.0.1:8080");
+        assert_eq!(config.run_id(), run_id);
+        assert!(AttachConfig::new("https://example.com", &run_id.to_string()).is_err());
+        assert!(AttachConfig::new("http://example.com/api", &run_id.to_string()).is_err());
+        assert!(AttachConfig::new("http://example.com", "run_nope").is_err());
+    }
+
+    #[test]
+    fn test_follow_reconnects_after_last_event() {
+        let node_id = NodeId::new();
+        let sse = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+                   Transfer-Encoding: chunked\r\n\r\n";
+        let not_found = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_string();
+        let responses = vec![
+            not_found.clone(),
+            // Breaks off before the final chunk, as if the server went away
+            format!(
+                "{}{}{}",
+                sse,
+                chunk(&frame(0, node_id, "NodeStarted")),
+                chunk(&frame(1, node_id, "ToolInvoked")),
+            ),
+            not_found,
+            format!(
+                "{}{}{}0\r\n\r\n",
+                sse,
+                chunk(":keep-alive\n\n"),
+                chunk(&frame(2, node_id, "NodeCompleted")),
+            ),
+        ];
+        let (host, server) = serve(responses);
+        let config = AttachConfig::new(&format!("http://{}", host), &RunId::new().to_string())
+            .unwrap()
+            .with_token("s3cret")
+            .with_retry(Duration::from_millis(10));
+
+        let (sender, updates) = mpsc::channel();
+        follow(&config, &sender);
+        let updates: Vec<LiveUpdate> = updates.try_iter().collect();
+        let positions: Vec<u64> = updates
+            .iter()
+            .filter_map(|update| match update {
+                LiveUpdate::Event { position, event } => {
+                    assert_eq!(event.node_id, node_id);
+                    assert_eq!(event.payload, b"ok");
+                    Some(*position)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(positions, vec![0, 1, 2]);
+        assert!(matches!(updates[0], LiveUpdate::Connected { resumed_after: None }));
+        assert!(updates.iter().any(|update| matches!(update, LiveUpdate::Disconnected(_))));
+        assert!(updates.iter().any(|update| matches!(
+            update,
+            LiveUpdate::Connected { resumed_after: Some(1) }
+        )));
+        assert!(matches!(updates.last(), Some(LiveUpdate::Finished)));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /runs/"));
+        assert!(requests[1].contains("Authorization: Bearer s3cret"));
+        assert!(!requests[1].contains("Last-Event-ID"));
+        assert!(requests[3].contains("Last-Event-ID: 1\r\n"));
+    }
+
+    #[test]
+    fn test_member_maps_to_worker_status() {
+        let member = |state: &str, in_flight| MemberView {
+            node_id: "node_1".to_string(),
+            state: state.to_string(),
+            in_flight,
+        };
+        assert_eq!(WorkerStatus::from(member("Active", 0)).status, WorkerState::Idle);
+        assert_eq!(WorkerStatus::from(member("Active", 2)).status, WorkerState::Busy);
+        assert_eq!(WorkerStatus::from(member("Leaving", 2)).total, 2);
+        assert_eq!(WorkerStatus::from(member("Suspected", 0)).status, WorkerState::Offline);
+        assert_eq!(decode_hex("00ff"), Some(vec![0, 255]));
+        assert_eq!(decode_hex("0"), None);
+    }
+}
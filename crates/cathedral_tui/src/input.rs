@@ -34,12 +34,20 @@ pub enum InputEvent {
     GoBottom,
     /// Select current item
     Select,
+    /// Leave the current detail
+    Back,
     /// Search
     Search,
     /// Next search result
     SearchNext,
     /// Previous search result
     SearchPrev,
+    /// Filter the timeline
+    Filter,
+    /// Pause or resume live updates
+    Pause,
+    /// Trace the provenance of a node or output
+    Trace,
     /// Refresh
     Refresh,
     /// Unknown key
@@ -112,9 +120,13 @@ impl Default for KeyBinding {
 
         // Actions
         bindings.insert(KeyCombo::key(KeyCode::Enter), InputEvent::Select);
+        bindings.insert(KeyCombo::key(KeyCode::Esc), InputEvent::Back);
         bindings.insert(KeyCombo::key(KeyCode::Char('/')), InputEvent::Search);
         bindings.insert(KeyCombo::key(KeyCode::Char('n')), InputEvent::SearchNext);
         bindings.insert(KeyCombo::key(KeyCode::Char('p')), InputEvent::SearchPrev);
+        bindings.insert(KeyCombo::key(KeyCode::Char('f')), InputEvent::Filter);
+        bindings.insert(KeyCombo::key(KeyCode::Char(' ')), InputEvent::Pause);
+        bindings.insert(KeyCombo::key(KeyCode::Char('t')), InputEvent::Trace);
         bindings.insert(KeyCombo::key(KeyCode::Char('r')), InputEvent::Refresh);
         bindings.insert(KeyCombo::key(KeyCode::Char('?')), InputEvent::Help);
 
@@ -175,6 +187,22 @@ impl InputHandler {
         Ok(None)
     }
 
+    /// Get the next raw key press, bypassing key bindings
+    ///
+    /// Used while typing text such as a search query.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if reading from terminal fails
+    pub fn next_key(&self) -> Result<Option<KeyEvent>, InputError> {
+        if crossterm::event::poll(self.timeout)?
+            && let Event::Key(key) = crossterm::event::read()?
+        {
+            return Ok(Some(key));
+        }
+        Ok(None)
+    }
+
     /// Map a KeyEvent to an InputEvent using key bindings
     fn map_key(&self, key: KeyEvent) -> InputEvent {
         let combo = KeyCombo::new(key.code, key.modifiers);
@@ -329,9 +357,11 @@ mod tests {
         let _ = InputEvent::GoTop;
         let _ = InputEvent::GoBottom;
         let _ = InputEvent::Select;
+        let _ = InputEvent::Back;
         let _ = InputEvent::Search;
         let _ = InputEvent::SearchNext;
         let _ = InputEvent::SearchPrev;
+        let _ = InputEvent::Trace;
         let _ = InputEvent::Refresh;
         let _ = InputEvent::Unknown;
     }
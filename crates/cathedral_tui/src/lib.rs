@@ -7,13 +7,15 @@
 #![warn(clippy::all)]
 
 pub mod ui;
+pub mod attach;
 pub mod view;
 pub mod renderer;
 pub mod input;
 pub mod layout;
 
 pub use ui::{TuiApp, TuiConfig, TuiError};
-pub use view::{TimelineView, DagView, WorkerView, ProvenanceView};
+pub use attach::{AttachConfig, LiveFollow, LiveUpdate};
+pub use view::{TimelineView, TimelineFilter, DagView, WorkerView, ProvenanceView, PayloadView};
 pub use renderer::{Renderer, RenderConfig, RenderError};
 pub use input::{InputHandler, InputEvent, KeyBinding};
 pub use layout::{Layout, LayoutArea, LayoutConfig, CalculatedLayout};
@@ -6,23 +6,57 @@
 #![warn(clippy::all)]
 
 use std::process;
-use cathedral_tui::{TuiApp, TuiError};
+use cathedral_tui::{AttachConfig, TuiApp, TuiError};
 use clap::Parser;
 
 #[derive(Parser)]
 #[command(name = "cathedral-tui")]
 #[command(about = "CATHEDRAL.FABRIC TUI", long_about = None)]
 struct Args {
-    /// Path to bundle or log file
-    #[arg(short, long)]
-    input: String,
+    /// Path to a run directory, event log or replay bundle
+    #[arg(short, long, required_unless_present = "attach")]
+    input: Option<String>,
+    /// Follow a run on a server as it executes, e.g. http://127.0.0.1:8080
+    #[arg(long, requires = "run", conflicts_with = "input")]
+    attach: Option<String>,
+    /// Run to follow with --attach
+    #[arg(long, requires = "attach")]
+    run: Option<String>,
+    /// Bearer token for --attach
+    #[arg(long, requires = "attach")]
+    token: Option<String>,
+    /// Hex Ed25519 public key a bundle --input must be signed with
+    #[arg(long, requires = "input")]
+    signer: Option<String>,
 }
 
 fn main() {
     let args = Args::parse();
 
-    if let Err(e) = TuiApp::new(&args.input).and_then(|mut app| app.run()) {
+    let app = match (args.attach, args.run) {
+        (Some(server), Some(run)) => AttachConfig::new(&server, &run).map(|config| {
+            TuiApp::attach(match args.token {
+                Some(token) => config.with_token(token),
+                None => config,
+            })
+        }),
+        _ => signer_key(args.signer.as_deref())
+            .and_then(|signer| TuiApp::new(&args.input.unwrap_or_default(), signer.as_ref())),
+    };
+    if let Err(e) = app.and_then(|mut app| app.run()) {
         eprintln!("Error: {}", e);
         process::exit(1);
     }
 }
+
+/// Parse a hex-encoded 32-byte Ed25519 public key
+fn signer_key(hex_key: Option<&str>) -> Result<Option<[u8; 32]>, TuiError> {
+    hex_key
+        .map(|hex_key| {
+            hex::decode(hex_key.trim())
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| TuiError::Io("signer key must be 32 bytes of hex".to_string()))
+        })
+        .transpose()
+}
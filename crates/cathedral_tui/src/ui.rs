@@ -1,11 +1,15 @@
 //! TUI app for viewing traces and audit logs.
 
+use crate::attach::{AttachConfig, LiveFollow, LiveUpdate};
 use crate::input::{InputHandler, InputEvent, InputError};
 use crate::layout::{Layout, CalculatedLayout};
 use crate::renderer::{Renderer, RenderConfig};
-use crate::view::{TimelineView, DagView, WorkerView, ProvenanceView, View};
-use cathedral_core::{EventId, RunId};
-use cathedral_log::EventStream;
+use crate::view::{TimelineView, TimelineFilter, DagView, WorkerView, ProvenanceView, View};
+use cathedral_core::{EventId, Hash, NodeId, RunId};
+use cathedral_log::{stream, Event, EventKind, EventStream, IndexedLog};
+use cathedral_plan::Dag;
+use cathedral_replay::{build_graph, BundleReader, EntryKind, TraceEvent};
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     backend::CrosstermBackend,
     crossterm::{
@@ -15,10 +19,25 @@ use ratatui::{
     },
     Frame,
 };
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
 
+/// Text being typed at the status line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    /// Search query
+    Search,
+    /// Timeline filter
+    Filter,
+    /// Provenance target
+    Trace,
+}
+
+/// File a run directory keeps its compiled DAG in
+const DAG_FILE: &str = "dag.json";
+
 /// TUI application state
 pub struct TuiApp {
     /// Event stream
@@ -33,6 +52,8 @@ pub struct TuiApp {
     worker: WorkerView,
     /// Provenance view
     provenance: ProvenanceView,
+    /// Dependencies of the loaded DAG, which give the provenance edges
+    dependencies: Vec<(NodeId, NodeId)>,
     /// Input handler
     input: InputHandler,
     /// Renderer
@@ -45,6 +66,16 @@ pub struct TuiApp {
     selection: Selection,
     /// Status message
     status: String,
+    /// Search query or filter being typed, if any
+    prompt: Option<(Prompt, String)>,
+    /// Timeline rows matching the last search
+    search_results: Vec<usize>,
+    /// Current entry in `search_results`
+    search_cursor: usize,
+    /// Run followed on a server, if attached
+    live: Option<LiveFollow>,
+    /// Whether live updates are held back
+    paused: bool,
 }
 
 /// View mode
@@ -95,12 +126,18 @@ impl Default for TuiApp {
             dag: DagView::new(),
             worker: WorkerView::new(),
             provenance: ProvenanceView::new(),
+            dependencies: Vec::new(),
             input: InputHandler::new(),
             renderer: Renderer::new(RenderConfig::default()),
             layout: Layout::new(),
             should_quit: false,
             selection: Selection::default(),
             status: "Ready".to_string(),
+            prompt: None,
+            search_results: Vec::new(),
+            search_cursor: 0,
+            live: None,
+            paused: false,
         }
     }
 }
@@ -108,27 +145,213 @@ impl Default for TuiApp {
 impl TuiApp {
     /// Create new TUI app
     ///
+    /// A directory input is either a run directory, as written by
+    /// `cathedral run`, or a segmented event log, whose run directory is
+    /// then its parent. Any other input is opened as a replay bundle,
+    /// which must be signed by `signer`. The run's compiled DAG and node
+    /// outputs are loaded into the DAG view when present.
+    ///
     /// # Errors
     ///
-    /// Returns error if log loading fails
-    pub fn new(input: &str) -> Result<Self, TuiError> {
-        // Try to load the event stream from file
-        let stream = if std::path::Path::new(input).exists() {
-            // For now, create an empty stream
-            // TODO: Implement loading from file
-            Arc::new(RwLock::new(EventStream::new(Vec::new())))
+    /// Returns error if log or bundle loading fails, or a bundle is opened
+    /// without a signer or was signed by another key
+    pub fn new(input: &str, signer: Option<&[u8; 32]>) -> Result<Self, TuiError> {
+        let run = if std::path::Path::new(input).is_dir() {
+            load_dir(std::path::Path::new(input))?
         } else {
-            Arc::new(RwLock::new(EventStream::new(Vec::new())))
+            let signer = signer.ok_or_else(|| {
+                TuiError::Log(format!("{} is a bundle; a trusted signer key is required", input))
+            })?;
+            load_bundle(input, signer)?
         };
 
-        let event_count = 0; // TODO: Get from stream
         let mut app = Self::default();
-        app.stream = stream;
-        app.status = format!("Loaded {} events", event_count);
+        if let Some(dag) = &run.dag {
+            app.dag = DagView::from_dag(dag).with_events(&run.events);
+            app.dependencies = dag.dependency_edges();
+            for (node_id, output) in &run.artifacts {
+                app.dag.set_output(node_id, output);
+            }
+        }
+        let events = run.events;
+        app.timeline = TimelineView::from_events(&events);
+        app.status = format!("Loaded {} events", events.len());
+        let events = events
+            .iter()
+            .map(|event| stream::Event { logical_time: event.logical_time })
+            .collect();
+        app.stream = Arc::new(RwLock::new(EventStream::new(events)));
 
         Ok(app)
     }
 
+    /// Create an app following a run on a server as it executes
+    ///
+    /// Views start empty and fill in as the run's DAG, events and the
+    /// cluster's workers arrive.
+    #[must_use]
+    pub fn attach(config: AttachConfig) -> Self {
+        Self {
+            status: format!("Attaching to {} on {}", config.run_id(), config.host()),
+            live: Some(LiveFollow::start(config)),
+            ..Self::default()
+        }
+    }
+
+    /// Apply the live updates that arrived since the last call
+    ///
+    /// Does nothing while paused; updates wait until resumed. Returns the
+    /// number of updates applied.
+    pub fn poll_live(&mut self) -> usize {
+        /// Most updates applied per call, so a backlog cannot stall input
+        const MAX_UPDATES: usize = 10_000;
+
+        if self.paused {
+            return 0;
+        }
+        let mut applied = 0;
+        while applied < MAX_UPDATES {
+            let Some(update) = self.live.as_ref().and_then(LiveFollow::next) else {
+                break;
+            };
+            self.apply_live(update);
+            applied += 1;
+        }
+        applied
+    }
+
+    /// Hold back or resume live updates
+    pub fn toggle_pause(&mut self) {
+        if self.live.is_none() {
+            return;
+        }
+        self.paused = !self.paused;
+        self.status = if self.paused { "Paused" } else { "Resumed" }.to_string();
+    }
+
+    fn apply_live(&mut self, update: LiveUpdate) {
+        match update {
+            LiveUpdate::Connected { resumed_after: None } => {
+                self.status = "Following run".to_string();
+            }
+            LiveUpdate::Connected { resumed_after: Some(position) } => {
+                self.status = format!("Reconnected, resuming after event {}", position);
+            }
+            LiveUpdate::Dag(dag) => {
+                self.dag = DagView::from_dag(&dag).with_events(self.timeline.events());
+                self.dependencies = dag.dependency_edges();
+            }
+            LiveUpdate::Event { position, event } => {
+                // A selection on the last row follows new events
+                let at_tail = self.view_mode == ViewMode::Timeline
+                    && self.selection.line + 1 >= self.timeline.item_count();
+                self.timeline.push(position, &event);
+                self.dag.apply(&event);
+                if at_tail {
+                    self.selection.line = self.timeline.item_count().saturating_sub(1);
+                    self.update_scroll();
+                }
+            }
+            LiveUpdate::Workers(workers) => self.worker.set_workers(workers),
+            LiveUpdate::Disconnected(reason) => {
+                self.status = format!("Disconnected ({}), reconnecting", reason);
+            }
+            LiveUpdate::Finished => {
+                self.status = format!("Run finished after {} events", self.timeline.len());
+            }
+        }
+    }
+
+    /// Search the timeline rows shown and select the first match
+    ///
+    /// `query` uses the [`TimelineFilter`] syntax, e.g.
+    /// `kind=NodeFailed from=10 timeout`. Returns the number of matching
+    /// rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the query cannot be parsed
+    pub fn search(&mut self, query: &str) -> Result<usize, TuiError> {
+        let parsed = parse_filter(query)?;
+        self.view_mode = ViewMode::Timeline;
+        self.search_results = self.timeline.find(&parsed);
+        self.search_cursor = 0;
+        self.select_search_result();
+        self.status = format!("{} matches for {}", self.search_results.len(), query);
+        Ok(self.search_results.len())
+    }
+
+    /// Show only the timeline events matching `filter`
+    ///
+    /// `filter` uses the [`TimelineFilter`] syntax; an empty filter shows
+    /// every event again. Returns the number of rows shown.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the filter cannot be parsed
+    pub fn filter(&mut self, filter: &str) -> Result<usize, TuiError> {
+        let parsed = if filter.trim().is_empty() { None } else { Some(parse_filter(filter)?) };
+        self.timeline.set_filter(parsed);
+        self.view_mode = ViewMode::Timeline;
+        self.search_results.clear();
+        self.selection.line = 0;
+        self.selection.scroll = 0;
+        self.status = match self.timeline.filter() {
+            Some(filter) => format!("{} events match {}", self.timeline.item_count(), filter),
+            None => format!("Showing all {} events", self.timeline.len()),
+        };
+        Ok(self.timeline.item_count())
+    }
+
+    /// Show the provenance of a node or output
+    ///
+    /// `target` is a node ID or output hash, or a unique prefix of either.
+    /// Returns the number of inputs the target derives from.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the target is unknown or ambiguous
+    pub fn trace(&mut self, target: &str) -> Result<usize, TuiError> {
+        self.refresh_provenance();
+        self.view_mode = ViewMode::Provenance;
+        self.selection.line = 0;
+        self.selection.scroll = 0;
+        let tree = self
+            .provenance
+            .trace(target)
+            .map_err(|e| TuiError::Log(e.to_string()))?;
+        let inputs = tree.inputs().len();
+        self.status = format!("Provenance of {}: {} inputs", tree.target, inputs);
+        Ok(inputs)
+    }
+
+    /// Rebuild the provenance graph from the events loaded so far
+    ///
+    /// Edges follow the loaded DAG's dependencies rather than the order
+    /// nodes ran in.
+    fn refresh_provenance(&mut self) {
+        let trace: Vec<TraceEvent> = self
+            .timeline
+            .events()
+            .iter()
+            .filter_map(TraceEvent::from_log)
+            .collect();
+        let labels = self.dag.nodes().iter().map(|node| (node.id.clone(), node.label.clone()));
+        let graph = build_graph(trace).with_dependencies(self.dependencies.iter().copied());
+        self.provenance = ProvenanceView::from_graph(&graph).with_labels(labels);
+    }
+
+    /// Node selected in the timeline or DAG view, if any
+    fn selected_node(&self) -> Option<String> {
+        match self.view_mode {
+            ViewMode::Timeline => {
+                self.timeline.item(self.selection.line).map(|item| item.node_id.clone())
+            }
+            ViewMode::Dag => self.dag.nodes().get(self.selection.line).map(|n| n.id.clone()),
+            _ => None,
+        }
+    }
+
     /// Run the TUI
     ///
     /// # Errors
@@ -165,6 +388,7 @@ impl TuiApp {
         let tick_rate = Duration::from_millis(250);
 
         loop {
+            self.poll_live();
             terminal.draw(|f| self.draw(f))
                 .map_err(|e| TuiError::Render(e.to_string()))?;
 
@@ -174,7 +398,12 @@ impl TuiApp {
 
             if crossterm::event::poll(timeout)
                 .map_err(|e| TuiError::Io(e.to_string()))? {
-                if let Some(event) = self.input.next_event()
+                if self.prompt.is_some() {
+                    if let Some(key) = self.input.next_key()
+                        .map_err(|e| TuiError::Terminal(e.to_string()))? {
+                        self.handle_prompt_key(key);
+                    }
+                } else if let Some(event) = self.input.next_event()
                     .map_err(|e| TuiError::Terminal(e.to_string()))? {
                     self.handle_event(event);
                 }
@@ -201,16 +430,27 @@ impl TuiApp {
         let main_area = layout.main_area;
 
         match self.view_mode {
+            ViewMode::Timeline if !self.timeline.is_empty() => {
+                self.timeline.render(f, main_area, &self.selection);
+            }
             ViewMode::Timeline => {
-                // For now, render empty views since we don't have the event data
                 self.render_empty_view(f, main_area, "Timeline");
             }
+            ViewMode::Dag if self.dag.item_count() > 0 => {
+                self.dag.render(f, main_area, &self.selection);
+            }
             ViewMode::Dag => {
                 self.render_empty_view(f, main_area, "Execution DAG");
             }
+            ViewMode::Worker if self.worker.item_count() > 0 => {
+                self.worker.render(f, main_area, &self.selection);
+            }
             ViewMode::Worker => {
                 self.render_empty_view(f, main_area, "Workers");
             }
+            ViewMode::Provenance if self.provenance.item_count() > 0 => {
+                self.provenance.render(f, main_area, &self.selection);
+            }
             ViewMode::Provenance => {
                 self.render_empty_view(f, main_area, "Provenance");
             }
@@ -238,13 +478,21 @@ impl TuiApp {
         use ratatui::{widgets::Paragraph, widgets::Wrap};
 
         let status_area = layout.status_area;
-        let status_text = format!(
+        let status_text = if let Some((prompt, text)) = &self.prompt {
+            match prompt {
+                Prompt::Search => format!(" /{}", text),
+                Prompt::Filter => format!(" filter: {}", text),
+                Prompt::Trace => format!(" trace: {}", text),
+            }
+        } else {
+            format!(
             " {} | {} | {} | {}",
             self.view_mode_short(),
             self.selection_info(),
             self.status,
-            "Press ? for help"
-        );
+            if self.paused { "PAUSED" } else { "Press ? for help" }
+            )
+        };
 
         let status = Paragraph::new(status_text)
             .wrap(Wrap { trim: false });
@@ -280,6 +528,8 @@ impl TuiApp {
             Line::from("  k/↑    - Move up"),
             Line::from("  g      - Go to top"),
             Line::from("  G      - Go to bottom"),
+            Line::from("  h/l    - Previous/next node in a DAG layer"),
+            Line::from("           Collapse/expand a provenance node"),
             Line::from(""),
             Line::from("Views:"),
             Line::from("  1      - Timeline view"),
@@ -290,6 +540,10 @@ impl TuiApp {
             Line::from("Actions:"),
             Line::from("  Enter  - View details"),
             Line::from("  /      - Search"),
+            Line::from("  f      - Filter the timeline"),
+            Line::from("  t      - Trace provenance of a node or output"),
+            Line::from("  Esc    - Back to the provenance node list"),
+            Line::from("  space  - Pause/resume live updates"),
             Line::from("  n      - Next search result"),
             Line::from("  p      - Previous search result"),
             Line::from("  q      - Quit"),
@@ -336,18 +590,60 @@ impl TuiApp {
             }
             InputEvent::ViewDag => {
                 self.view_mode = ViewMode::Dag;
+                self.selection.line = self.selection.line.min(self.max_line());
                 self.status = "DAG view".to_string();
             }
+            InputEvent::Up | InputEvent::Down | InputEvent::Left | InputEvent::Right
+                if self.view_mode == ViewMode::Dag =>
+            {
+                self.selection.line = self.dag.step(self.selection.line, &event);
+            }
             InputEvent::ViewWorker => {
                 self.view_mode = ViewMode::Worker;
                 self.status = "Worker view".to_string();
             }
             InputEvent::ViewProvenance => {
-                self.view_mode = ViewMode::Provenance;
+                // Trace the node selected in the view being left, if any
+                let traced = self.selected_node().map(|node| self.trace(&node));
+                if !matches!(traced, Some(Ok(_))) {
+                    self.refresh_provenance();
+                    self.view_mode = ViewMode::Provenance;
+                    self.selection.line = 0;
+                    self.selection.scroll = 0;
+                    self.status = "Provenance view".to_string();
+                }
+            }
+            InputEvent::Left | InputEvent::Right
+                if self.view_mode == ViewMode::Provenance && self.provenance.tree().is_some() =>
+            {
+                let expand = event == InputEvent::Right;
+                self.selection.line = self.provenance.expand(self.selection.line, expand);
+                self.update_scroll();
+            }
+            InputEvent::Select if self.view_mode == ViewMode::Provenance => {
+                if self.provenance.tree().is_some() {
+                    self.selection.line = self.provenance.toggle(self.selection.line);
+                    self.update_scroll();
+                } else if let Some(entry) = self.provenance.entry(self.selection.line) {
+                    let node = entry.data_id.clone();
+                    if let Err(e) = self.trace(&node) {
+                        self.status = e.to_string();
+                    }
+                }
+            }
+            InputEvent::Back
+                if self.view_mode == ViewMode::Provenance && self.provenance.tree().is_some() =>
+            {
+                self.provenance.close();
+                self.selection.line = 0;
+                self.selection.scroll = 0;
                 self.status = "Provenance view".to_string();
             }
+            InputEvent::Trace => {
+                self.prompt = Some((Prompt::Trace, String::new()));
+            }
             InputEvent::Down => {
-                self.selection.line += 1;
+                self.selection.line = (self.selection.line + 1).min(self.max_line());
                 self.update_scroll();
             }
             InputEvent::Up => {
@@ -364,16 +660,71 @@ impl TuiApp {
                 self.selection.line = self.max_line();
                 self.update_scroll();
             }
+            InputEvent::Select if self.view_mode == ViewMode::Dag => {
+                if let Some(node) = self.dag.nodes().get(self.selection.line) {
+                    self.status = format!("Selected {} {}", node.label, node.id);
+                }
+            }
             InputEvent::Select => {
                 self.status = "Selected details".to_string();
             }
             InputEvent::Search => {
-                self.status = "Search not yet implemented".to_string();
+                self.prompt = Some((Prompt::Search, String::new()));
+            }
+            InputEvent::Pause => self.toggle_pause(),
+            InputEvent::Filter => {
+                let current = self.timeline.filter().map(ToString::to_string).unwrap_or_default();
+                self.prompt = Some((Prompt::Filter, current));
+            }
+            InputEvent::SearchNext if !self.search_results.is_empty() => {
+                self.search_cursor = (self.search_cursor + 1) % self.search_results.len();
+                self.select_search_result();
+            }
+            InputEvent::SearchPrev if !self.search_results.is_empty() => {
+                self.search_cursor = self
+                    .search_cursor
+                    .checked_sub(1)
+                    .unwrap_or(self.search_results.len() - 1);
+                self.select_search_result();
             }
             _ => {}
         }
     }
 
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some((_, text)) = self.prompt.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Esc => self.prompt = None,
+            KeyCode::Enter => {
+                let Some((prompt, text)) = self.prompt.take() else {
+                    return;
+                };
+                let result = match prompt {
+                    Prompt::Search => self.search(&text),
+                    Prompt::Filter => self.filter(&text),
+                    Prompt::Trace => self.trace(&text),
+                };
+                if let Err(e) = result {
+                    self.status = e.to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn select_search_result(&mut self) {
+        if let Some(&line) = self.search_results.get(self.search_cursor) {
+            self.selection.line = line;
+            self.update_scroll();
+        }
+    }
+
     fn update_scroll(&mut self) {
         let max_scroll = self.selection.line.saturating_sub(10);
         if self.selection.scroll > max_scroll {
@@ -395,6 +746,91 @@ impl TuiApp {
     }
 }
 
+/// Parse a [`TimelineFilter`] typed at the status line
+fn parse_filter(text: &str) -> Result<TimelineFilter, TuiError> {
+    text.parse().map_err(|e: cathedral_core::CoreError| TuiError::Log(e.to_string()))
+}
+
+/// Events, compiled DAG and node outputs of a run
+#[derive(Default)]
+struct LoadedRun {
+    events: Vec<Event>,
+    dag: Option<Dag>,
+    /// Node outputs by node ID
+    artifacts: Vec<(String, Vec<u8>)>,
+}
+
+/// Load a run directory, or an event log inside one
+fn load_dir(dir: &Path) -> Result<LoadedRun, TuiError> {
+    let (log_dir, run_dir) = if dir.join("events").is_dir() {
+        (dir.join("events"), dir.to_path_buf())
+    } else {
+        (dir.to_path_buf(), dir.parent().unwrap_or(dir).to_path_buf())
+    };
+    let mut run = LoadedRun::default();
+    // Served from the index file: the log is only read, never recovered
+    let log = IndexedLog::open(&log_dir).map_err(|e| TuiError::Log(e.to_string()))?;
+    for position in 0..log.len() {
+        if let Some(event) = log.read(position).map_err(|e| TuiError::Log(e.to_string()))? {
+            run.events.push(event);
+        }
+    }
+
+    let io = |e: std::io::Error| TuiError::Io(e.to_string());
+    let dag_file = run_dir.join(DAG_FILE);
+    if dag_file.is_file() {
+        let dag = serde_json::from_slice(&std::fs::read(dag_file).map_err(io)?)
+            .map_err(|e| TuiError::Log(format!("undecodable DAG: {}", e)))?;
+        run.dag = Some(dag);
+    }
+    let artifacts = run_dir.join("artifacts");
+    if artifacts.is_dir() {
+        for entry in std::fs::read_dir(artifacts).map_err(io)? {
+            let path = entry.map_err(io)?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            run.artifacts.push((name, std::fs::read(&path).map_err(io)?));
+        }
+    }
+    Ok(run)
+}
+
+/// Load a replay bundle signed by `signer`
+///
+/// Node outputs are the blobs the log's completion events reference, or
+/// the artifacts of bundles that name them by node.
+fn load_bundle(path: &str, signer: &[u8; 32]) -> Result<LoadedRun, TuiError> {
+    let bundle_error = |e: cathedral_core::CoreError| TuiError::Log(e.to_string());
+    let mut bundle = BundleReader::open(path).map_err(bundle_error)?;
+    bundle.verify_signer(signer).map_err(bundle_error)?;
+    let mut run = LoadedRun {
+        events: bundle.events().map_err(bundle_error)?,
+        dag: bundle.dag().map_err(bundle_error)?,
+        artifacts: Vec::new(),
+    };
+    let names: Vec<String> = bundle
+        .manifest()
+        .entries_of(EntryKind::Artifact)
+        .map(|entry| entry.name.clone())
+        .collect();
+    for name in names {
+        let data = bundle.read(&name).map_err(bundle_error)?;
+        let node_id = name.strip_prefix("artifacts/").unwrap_or(&name).to_string();
+        run.artifacts.push((node_id, data));
+    }
+    let outputs: Vec<(NodeId, Hash)> = run
+        .events
+        .iter()
+        .filter(|event| event.kind == EventKind::NodeCompleted)
+        .filter(|event| bundle.has_blob(&event.payload_hash))
+        .map(|event| (event.node_id, event.payload_hash))
+        .collect();
+    for (node_id, hash) in outputs {
+        let data = bundle.blob(&hash).map_err(bundle_error)?;
+        run.artifacts.push((node_id.to_string(), data));
+    }
+    Ok(run)
+}
+
 /// TUI configuration
 #[derive(Debug, Clone)]
 pub struct TuiConfig {
@@ -444,6 +880,9 @@ pub enum TuiError {
     /// Render error
     #[error("render error: {0}")]
     Render(String),
+    /// Server connection error
+    #[error("connection error: {0}")]
+    Connection(String),
 }
 
 #[cfg(test)]
@@ -477,6 +916,238 @@ mod tests {
         assert_eq!(scheme, ColorScheme::Dark);
     }
 
+    #[test]
+    fn test_search_selects_matches() {
+        use cathedral_core::{LogicalTime, NodeId};
+        use cathedral_log::{Event, EventKind, SegmentConfig, SegmentedStream};
+
+        let dir = tempfile::tempdir().unwrap();
+        let (mut log, _) = SegmentedStream::open(SegmentConfig::new(dir.path())).unwrap();
+        let run_id = RunId::new();
+        for (time, kind) in [
+            EventKind::NodeStarted,
+            EventKind::NodeFailed,
+            EventKind::NodeStarted,
+            EventKind::NodeFailed,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let event = Event::new(
+                EventId::new(),
+                run_id,
+                NodeId::new(),
+                LogicalTime::from_raw(time as u64),
+                kind,
+            );
+            log.append(&event).unwrap();
+        }
+        drop(log);
+
+        let mut app = TuiApp::new(dir.path().to_str().unwrap(), None).unwrap();
+        assert_eq!(app.search("kind=NodeFailed").unwrap(), 2);
+        assert_eq!(app.selection.line, 1);
+        app.handle_event(InputEvent::SearchNext);
+        assert_eq!(app.selection.line, 3);
+        app.handle_event(InputEvent::SearchNext);
+        assert_eq!(app.selection.line, 1);
+        app.handle_event(InputEvent::SearchPrev);
+        assert_eq!(app.selection.line, 3);
+        assert!(app.search("kind=Nope").is_err());
+
+        assert_eq!(app.filter("kind=NodeStarted").unwrap(), 2);
+        assert_eq!(app.search("kind=NodeStarted").unwrap(), 2);
+        assert_eq!(app.selection.line, 0);
+        app.handle_event(InputEvent::SearchNext);
+        assert_eq!(app.selection.line, 1);
+        app.handle_event(InputEvent::Down);
+        assert_eq!(app.selection.line, 1);
+        assert_eq!(app.filter("").unwrap(), 4);
+        assert!(app.filter("node=").is_ok());
+        assert!(app.filter("kind=Nope").is_err());
+    }
+
+    #[test]
+    fn test_loads_run_dir_into_dag_view() {
+        use cathedral_core::{LogicalTime, NodeId};
+        use cathedral_log::{Event, EventKind, SegmentConfig, SegmentedStream};
+        use cathedral_plan::dag::{Edge, Node, NodeKind, ResourceRequirements};
+
+        let dir = tempfile::tempdir().unwrap();
+        let (input, output) = (NodeId::new(), NodeId::new());
+        let mut dag = Dag::new();
+        for (id, kind) in [
+            (input, NodeKind::Input { schema: "any".to_string() }),
+            (output, NodeKind::Output { schema: "any".to_string() }),
+        ] {
+            let node = Node {
+                id,
+                kind,
+                dependencies: Default::default(),
+                capabilities: Vec::new(),
+                resources: ResourceRequirements::new(),
+            };
+            dag.add_node(node).unwrap();
+        }
+        dag.add_edge(Edge::new(input, output)).unwrap();
+        std::fs::write(dir.path().join(DAG_FILE), serde_json::to_vec(&dag).unwrap()).unwrap();
+        std::fs::create_dir(dir.path().join("artifacts")).unwrap();
+        std::fs::write(dir.path().join("artifacts").join(input.to_string()), b"data").unwrap();
+
+        let (mut log, _) =
+            SegmentedStream::open(SegmentConfig::new(dir.path().join("events"))).unwrap();
+        let run_id = RunId::new();
+        for (time, kind) in [(0, EventKind::NodeStarted), (1, EventKind::NodeCompleted)] {
+            let time = LogicalTime::from_raw(time);
+            log.append(&Event::new(EventId::new(), run_id, input, time, kind)).unwrap();
+        }
+        drop(log);
+
+        let mut app = TuiApp::new(dir.path().to_str().unwrap(), None).unwrap();
+        assert_eq!(app.dag.item_count(), 2);
+        let loaded = &app.dag.nodes()[0];
+        assert_eq!(loaded.status, crate::view::NodeStatus::Completed);
+        assert_eq!(loaded.artifact.as_ref().map(|(_, size)| *size), Some(4));
+
+        app.handle_event(InputEvent::ViewDag);
+        app.handle_event(InputEvent::Down);
+        assert_eq!(app.selection.line, 1);
+        app.handle_event(InputEvent::Down);
+        assert_eq!(app.selection.line, 1);
+        app.handle_event(InputEvent::Select);
+        assert!(app.status.contains(&output.to_string()));
+    }
+
+    #[test]
+    fn test_bundle_needs_trusted_signer() {
+        use cathedral_core::LogicalTime;
+        use cathedral_log::{Event, EventKind, SegmentConfig, SegmentedStream};
+        use cathedral_replay::BundleBuilder;
+
+        let dir = tempfile::tempdir().unwrap();
+        let node_id = NodeId::new();
+        let (mut log, _) =
+            SegmentedStream::open(SegmentConfig::new(dir.path().join("events"))).unwrap();
+        let time = LogicalTime::from_raw(0);
+        let kind = EventKind::NodeCompleted;
+        let completed = Event::new(EventId::new(), RunId::new(), node_id, time, kind)
+            .with_payload(b"data".to_vec());
+        log.append(&completed).unwrap();
+        drop(log);
+        let mut builder = BundleBuilder::new();
+        builder.add_log_dir(dir.path().join("events")).unwrap();
+        builder.add_blob(b"data".to_vec());
+        let path = dir.path().join("run.bundle");
+        let path = path.to_str().unwrap();
+        let signer = builder.write_to_file(path, &[7; 32]).unwrap().signer;
+
+        assert!(TuiApp::new(path, None).is_err());
+        assert!(TuiApp::new(path, Some(&[0; 32])).is_err());
+        let run = load_bundle(path, &signer).unwrap();
+        assert_eq!(run.events.len(), 1);
+        assert_eq!(run.artifacts, vec![(node_id.to_string(), b"data".to_vec())]);
+    }
+
+    #[test]
+    fn test_live_updates_follow_tail() {
+        use cathedral_core::{LogicalTime, NodeId};
+        use cathedral_log::{Event, EventKind};
+        use cathedral_plan::dag::{Node, NodeKind, ResourceRequirements};
+
+        let node_id = NodeId::new();
+        let run_id = RunId::new();
+        let event = |time: u64, kind: EventKind| {
+            Event::new(EventId::new(), run_id, node_id, LogicalTime::from_raw(time), kind)
+        };
+        let mut app = TuiApp::default();
+        app.apply_live(LiveUpdate::Connected { resumed_after: None });
+        app.apply_live(LiveUpdate::Event { position: 0, event: event(0, EventKind::NodeStarted) });
+
+        let mut dag = Dag::new();
+        let node = Node {
+            id: node_id,
+            kind: NodeKind::Input { schema: "any".to_string() },
+            dependencies: Default::default(),
+            capabilities: Vec::new(),
+            resources: ResourceRequirements::new(),
+        };
+        dag.add_node(node).unwrap();
+        app.apply_live(LiveUpdate::Dag(dag));
+        assert_eq!(app.dag.nodes()[0].status, crate::view::NodeStatus::Running);
+
+        app.apply_live(LiveUpdate::Event { position: 1, event: event(1, EventKind::Heartbeat) });
+        assert_eq!(app.selection.line, 1);
+        app.handle_event(InputEvent::Up);
+        let completed = event(2, EventKind::NodeCompleted);
+        app.apply_live(LiveUpdate::Event { position: 2, event: completed });
+        assert_eq!(app.selection.line, 0);
+        assert_eq!(app.timeline.item_count(), 3);
+        assert_eq!(app.dag.nodes()[0].status, crate::view::NodeStatus::Completed);
+
+        app.apply_live(LiveUpdate::Disconnected("reset".to_string()));
+        assert!(app.status.contains("reconnecting"));
+        app.apply_live(LiveUpdate::Connected { resumed_after: Some(2) });
+        assert!(app.status.contains("after event 2"));
+        app.apply_live(LiveUpdate::Finished);
+        assert_eq!(app.status, "Run finished after 3 events");
+
+        // Pausing needs a followed run
+        app.toggle_pause();
+        assert!(!app.paused);
+        assert_eq!(app.poll_live(), 0);
+    }
+
+    #[test]
+    fn test_provenance_traces_selected_node() {
+        use cathedral_core::{Capability, LogicalTime, NodeId};
+        use cathedral_log::{Event, EventKind};
+
+        let (input, tool) = (NodeId::new(), NodeId::new());
+        let run_id = RunId::new();
+        let event = |node_id, time: u64, kind| {
+            Event::new(EventId::new(), run_id, node_id, LogicalTime::from_raw(time), kind)
+        };
+        let produced = event(input, 0, EventKind::ToolCompleted).with_payload(b"data".to_vec());
+        let invoked = event(tool, 1, EventKind::ToolInvoked)
+            .with_parent(produced.event_id)
+            .with_payload(b"data".to_vec());
+        let proof = serde_json::json!({ "decision": true, "capability": Capability::IdGen });
+        let decision = event(tool, 2, EventKind::PolicyDecision)
+            .with_parent(invoked.event_id)
+            .with_payload(serde_json::to_vec(&proof).unwrap());
+
+        let mut app = TuiApp::default();
+        for (position, event) in [produced, invoked, decision].into_iter().enumerate() {
+            app.apply_live(LiveUpdate::Event { position: position as u64, event });
+        }
+        assert_eq!(app.selection.line, 2);
+
+        app.handle_event(InputEvent::ViewProvenance);
+        assert_eq!(app.view_mode, ViewMode::Provenance);
+        let tree = app.provenance.tree().unwrap();
+        assert_eq!(tree.root.node_id, tool);
+        assert_eq!(tree.inputs(), vec![input]);
+        assert!(app.status.ends_with("1 inputs"));
+        assert_eq!(app.provenance.item_count(), 4);
+
+        app.handle_event(InputEvent::Down);
+        app.handle_event(InputEvent::Left);
+        assert_eq!(app.selection.line, 0);
+        assert_eq!(app.provenance.item_count(), 1);
+        app.handle_event(InputEvent::Select);
+        assert_eq!(app.provenance.item_count(), 4);
+
+        app.handle_event(InputEvent::Back);
+        assert!(app.provenance.tree().is_none());
+        app.handle_event(InputEvent::Select);
+        assert_eq!(app.provenance.tree().unwrap().root.node_id, input);
+
+        let output = cathedral_core::Hash::compute(b"data").to_hex();
+        assert_eq!(app.trace(&output[..10]).unwrap(), 1);
+        assert_eq!(app.provenance.tree().unwrap().root.node_id, input);
+        assert!(app.trace("zz").is_err());
+    }
+
     #[test]
     fn test_tui_error_messages() {
         let err = TuiError::Terminal("test".to_string());
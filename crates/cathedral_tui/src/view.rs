@@ -1,14 +1,21 @@
 //! TUI views for traces, DAGs, and audit logs.
 
-use cathedral_core::{EventId, RunId};
+use crate::input::InputEvent;
+use cathedral_core::{CoreError, EventId, Hash, NodeId};
+use cathedral_log::{Event, EventKind, LogQuery};
+use cathedral_plan::{Dag, NodeKind};
+use cathedral_policy::RedactedView;
+use cathedral_replay::{ExecutionGraph, Provenance, ProvenanceNode};
 use ratatui::{
-    layout::Alignment,
+    layout::{Constraint, Direction},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap, Table},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Frame,
 };
 use ratatui::layout::Rect;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
 
 /// Trait for TUI views
 pub trait View {
@@ -20,8 +27,18 @@ pub trait View {
 }
 
 /// Timeline view showing events chronologically
+///
+/// Events are kept in logical time order, ties in the order they were
+/// added. A [`TimelineFilter`] narrows the rows shown; selection lines index
+/// the rows shown.
 pub struct TimelineView {
     items: Vec<TimelineItem>,
+    /// Events behind `items`, in the same order
+    events: Vec<Event>,
+    /// Filter narrowing the rows shown, if any
+    filter: Option<TimelineFilter>,
+    /// Positions in `items` of the rows shown
+    visible: Vec<usize>,
 }
 
 impl TimelineView {
@@ -30,8 +47,91 @@ impl TimelineView {
     pub fn new() -> Self {
         Self {
             items: Vec::new(),
+            events: Vec::new(),
+            filter: None,
+            visible: Vec::new(),
         }
     }
+
+    /// Create a timeline of events read from a log, in log order
+    #[must_use]
+    pub fn from_events(events: &[Event]) -> Self {
+        let mut view = Self::new();
+        for (position, event) in events.iter().enumerate() {
+            view.push(position as u64, event);
+        }
+        view
+    }
+
+    /// Add the event at `position` in its log, e.g. as it streams in
+    pub fn push(&mut self, position: u64, event: &Event) {
+        let tick = event.logical_time.as_u64();
+        let at = self.items.partition_point(|item| item.tick <= tick);
+        self.items.insert(at, TimelineItem::from_event(position, event));
+        self.events.insert(at, event.clone());
+        for row in &mut self.visible {
+            if *row >= at {
+                *row += 1;
+            }
+        }
+        if self.shows(at) {
+            let row = self.visible.partition_point(|&row| row < at);
+            self.visible.insert(row, at);
+        }
+    }
+
+    /// Show only the events matching `filter`, or every event for `None`
+    pub fn set_filter(&mut self, filter: Option<TimelineFilter>) {
+        self.filter = filter;
+        self.visible = (0..self.items.len()).filter(|&at| self.shows(at)).collect();
+    }
+
+    /// Get the filter narrowing the rows shown
+    #[must_use]
+    pub fn filter(&self) -> Option<&TimelineFilter> {
+        self.filter.as_ref()
+    }
+
+    /// Get the item shown at row `line`
+    #[must_use]
+    pub fn item(&self, line: usize) -> Option<&TimelineItem> {
+        self.visible.get(line).map(|&at| &self.items[at])
+    }
+
+    /// Get the rows shown whose events match `filter`
+    #[must_use]
+    pub fn find(&self, filter: &TimelineFilter) -> Vec<usize> {
+        self.visible
+            .iter()
+            .enumerate()
+            .filter(|&(_, &at)| filter.matches(&self.items[at], &self.events[at]))
+            .map(|(line, _)| line)
+            .collect()
+    }
+
+    /// Get every event, shown or not, in timeline order
+    #[must_use]
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Get the number of events, shown or not
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Check whether the timeline has no events
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn shows(&self, at: usize) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(&self.items[at], &self.events[at]))
+    }
 }
 
 impl Default for TimelineView {
@@ -43,6 +143,8 @@ impl Default for TimelineView {
 /// Timeline item
 #[derive(Debug, Clone)]
 pub struct TimelineItem {
+    /// Position of the event in its log
+    pub position: u64,
     /// Tick
     pub tick: u64,
     /// Node ID
@@ -53,42 +155,227 @@ pub struct TimelineItem {
     pub detail: String,
 }
 
+impl TimelineItem {
+    /// Describe the event at `position` in its log
+    #[must_use]
+    pub fn from_event(position: u64, event: &Event) -> Self {
+        Self {
+            position,
+            tick: event.logical_time.as_u64(),
+            node_id: event.node_id.to_string(),
+            kind: format!("{:?}", event.kind),
+            detail: payload_summary(event),
+        }
+    }
+}
+
+/// Filter over timeline events
+///
+/// Parsed from whitespace-separated terms, all of which must match:
+/// `node=<id prefix>`, `text=<word>` or a bare word, matched case
+/// insensitively against the kind, node ID and payload summary, and any
+/// [`LogQuery`] term, e.g. `kind=NodeFailed,ToolFailed from=10`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TimelineFilter {
+    /// Terms handled by the log query
+    query: LogQuery,
+    /// Prefix the node ID must start with
+    node: Option<String>,
+    /// Lowercase words the event must mention
+    words: Vec<String>,
+    /// The filter as typed
+    source: String,
+}
+
+impl TimelineFilter {
+    /// Check whether an event and its timeline item match
+    #[must_use]
+    pub fn matches(&self, item: &TimelineItem, event: &Event) -> bool {
+        if !self.query.matches_event(event) {
+            return false;
+        }
+        if let Some(node) = &self.node
+            && !item.node_id.starts_with(node.as_str())
+        {
+            return false;
+        }
+        self.words.iter().all(|word| {
+            [&item.kind, &item.node_id, &item.detail]
+                .iter()
+                .any(|field| field.to_lowercase().contains(word.as_str()))
+        })
+    }
+}
+
+impl FromStr for TimelineFilter {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = Self { source: s.trim().to_string(), ..Self::default() };
+        let mut query_terms = Vec::new();
+        for term in s.split_whitespace() {
+            match term.split_once('=') {
+                Some(("node", prefix)) => filter.node = Some(node_prefix(prefix)),
+                Some(("text", word)) => filter.words.push(word.to_lowercase()),
+                Some(_) => query_terms.push(term),
+                None => filter.words.push(term.to_lowercase()),
+            }
+        }
+        filter.query = query_terms.join(" ").parse()?;
+        Ok(filter)
+    }
+}
+
+impl std::fmt::Display for TimelineFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Node ID prefix as displayed, with the `node_` prefix added if missing
+fn node_prefix(prefix: &str) -> String {
+    if prefix.starts_with("node_") {
+        prefix.to_string()
+    } else {
+        format!("node_{}", prefix)
+    }
+}
+
+/// One-line summary of an event's payload
+fn payload_summary(event: &Event) -> String {
+    const MAX_CHARS: usize = 60;
+    if let Some(redaction) = &event.redaction {
+        return format!("redacted by {}", redaction.rules.join(","));
+    }
+    match std::str::from_utf8(&event.payload) {
+        Ok(text) if !text.chars().any(|c| c.is_control() && c != '\n') => {
+            let line = text.replace('\n', " ");
+            truncate(line.trim(), MAX_CHARS)
+        }
+        _ => format!("{} bytes {}", event.payload.len(), short_hash(&event.payload_hash.to_hex())),
+    }
+}
+
+/// Symbol and color marking an event kind
+fn kind_icon(kind: EventKind) -> (char, Color) {
+    use EventKind as K;
+    match kind {
+        _ if kind.is_error() => ('✗', Color::Red),
+        K::RunCreated | K::RunStarted | K::NodeScheduled | K::NodeStarted => ('▶', Color::Cyan),
+        K::RunCompleted | K::NodeCompleted | K::ToolCompleted => ('●', Color::Green),
+        K::NodeSkipped | K::NodeCancelled | K::RunCancelled | K::Superseded => {
+            ('–', Color::DarkGray)
+        }
+        K::ToolInvoked => ('⚙', Color::Blue),
+        K::ToolTimedOut | K::TaskTimedOut | K::Shed | K::QuotaDenied => ('⧗', Color::Yellow),
+        K::CapabilityCheck | K::PolicyDecision | K::PolicyActivated => ('⚖', Color::Magenta),
+        K::TaskAssigned | K::TaskAccepted | K::TaskRejected | K::TaskStolen => ('⇄', Color::White),
+        K::SnapshotCreated | K::SnapshotRestored | K::BlobStored => ('◆', Color::White),
+        K::Truncation => ('!', Color::Red),
+        _ => ('·', Color::Gray),
+    }
+}
+
 impl View for TimelineView {
     fn render(&self, f: &mut Frame, area: Rect, selection: &crate::ui::Selection) {
-        let title = Block::default()
-            .title(" Timeline ")
-            .borders(Borders::ALL);
+        let title = match &self.filter {
+            Some(filter) => {
+                format!(" Timeline {}/{} [{}] ", self.visible.len(), self.items.len(), filter)
+            }
+            None => format!(" Timeline {} ", self.items.len()),
+        };
+        let block = Block::default().title(title).borders(Borders::ALL);
 
-        let items: Vec<ListItem> = self.items
+        // Scroll just far enough to keep the selected row in view
+        let height = usize::from(area.height.saturating_sub(2)).max(1);
+        let offset = (selection.line + 1).saturating_sub(height);
+        let items: Vec<ListItem> = self.visible
             .iter()
             .enumerate()
-            .map(|(i, item)| {
-                let style = if i == selection.line {
+            .skip(offset)
+            .take(height)
+            .map(|(line, &at)| {
+                let item = &self.items[at];
+                let (icon, color) = kind_icon(self.events[at].kind);
+                let style = if line == selection.line {
                     Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD)
                 } else {
                     Style::default()
                 };
-                ListItem::new(format!("{} | {:12} | {}", item.tick, item.node_id, item.kind))
-                    .style(style)
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{:>8} ", item.tick)),
+                    Span::styled(format!("{} {:<16}", icon, item.kind), Style::default().fg(color)),
+                    Span::raw(format!(" {:<13} ", short_id(&item.node_id))),
+                    Span::styled(item.detail.clone(), Style::default().fg(Color::DarkGray)),
+                ]))
+                .style(style)
             })
             .collect();
 
         let list = List::new(items)
-            .block(title)
+            .block(block)
             .highlight_style(Style::default().add_modifier(Modifier::UNDERLINED));
 
         f.render_widget(list, area);
     }
 
     fn item_count(&self) -> usize {
-        self.items.len()
+        self.visible.len()
     }
 }
 
+/// Width of a node box, borders included
+const BOX_WIDTH: usize = 18;
+/// Columns from one slot of a layer to the next
+const SLOT_WIDTH: usize = BOX_WIDTH + 2;
+/// Rows from one layer to the next: a row of boxes, then the edges below it
+const LAYER_HEIGHT: usize = 6;
+/// Width of the label inside a node box
+const LABEL_WIDTH: usize = BOX_WIDTH - 6;
+
 /// DAG view showing execution graph
+///
+/// Nodes are laid out in topological layers, each a row of boxes below the
+/// layers it depends on. An edge spanning several layers passes through
+/// the layers in between, and each layer is ordered by the mean slot of its
+/// predecessors to limit crossings. The same DAG always gets the same
+/// layout. Nodes are kept in reading order, layer by layer, so a selection
+/// line is an index into them.
 pub struct DagView {
     nodes: Vec<DagNode>,
     edges: Vec<DagEdge>,
+    /// Node position by ID
+    index: HashMap<String, usize>,
+    /// Layer and slot of every edge passing through a layer
+    passes: Vec<(usize, usize)>,
+    /// Connections between adjacent layers
+    links: Vec<Link>,
+    /// Slots in the widest layer
+    width: usize,
+    /// Number of layers
+    depth: usize,
+}
+
+/// Connection from a slot of one layer to a slot of the next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Link {
+    /// Upper layer
+    layer: usize,
+    /// Slot in the upper layer
+    from: usize,
+    /// Slot in the lower layer
+    to: usize,
+    /// Whether the connection ends at a node rather than passing through
+    arrow: bool,
+}
+
+/// Occupant of a slot in a layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Item {
+    /// Node, by its position in the DAG
+    Node(usize),
+    /// Edge passing through, by its position in the edge list
+    Pass(usize),
 }
 
 impl DagView {
@@ -98,7 +385,352 @@ impl DagView {
         Self {
             nodes: Vec::new(),
             edges: Vec::new(),
+            index: HashMap::new(),
+            passes: Vec::new(),
+            links: Vec::new(),
+            width: 0,
+            depth: 0,
+        }
+    }
+
+    /// Lay out a DAG with every node pending
+    #[must_use]
+    pub fn from_dag(dag: &Dag) -> Self {
+        let ids: Vec<NodeId> = dag.nodes.keys().copied().collect();
+        let position: HashMap<NodeId, usize> =
+            ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for (from, to) in dag.dependency_edges() {
+            if let (Some(&from), Some(&to)) = (position.get(&from), position.get(&to))
+                && from != to
+                && !edges.contains(&(from, to))
+            {
+                edges.push((from, to));
+            }
+        }
+
+        let layer = layers(ids.len(), &edges);
+        let depth = layer.iter().max().map_or(0, |max| max + 1);
+        let mut rows: Vec<Vec<Item>> = vec![Vec::new(); depth];
+        for (node, &l) in layer.iter().enumerate() {
+            rows[l].push(Item::Node(node));
+        }
+        for (e, &(from, to)) in edges.iter().enumerate() {
+            for row in rows.iter_mut().take(layer[to]).skip(layer[from] + 1) {
+                row.push(Item::Pass(e));
+            }
+        }
+
+        // Items in the layer above that `item`, in layer `l`, is drawn from
+        let upper = |item: Item, l: usize| -> Vec<Item> {
+            let via = |e: usize| {
+                let from = edges[e].0;
+                if layer[from] + 1 == l { Item::Node(from) } else { Item::Pass(e) }
+            };
+            match item {
+                Item::Node(node) => (0..edges.len())
+                    .filter(|&e| edges[e].1 == node && layer[edges[e].0] < l)
+                    .map(via)
+                    .collect(),
+                Item::Pass(e) => vec![via(e)],
+            }
+        };
+
+        for l in 1..depth {
+            let above: HashMap<Item, usize> =
+                rows[l - 1].iter().enumerate().map(|(slot, item)| (*item, slot)).collect();
+            let mut keyed: Vec<(Item, usize, usize)> = rows[l]
+                .iter()
+                .enumerate()
+                .map(|(slot, &item)| {
+                    let slots: Vec<usize> = upper(item, l).iter().map(|up| above[up]).collect();
+                    match slots.len() {
+                        0 => (item, slot, 1),
+                        count => (item, slots.iter().sum(), count),
+                    }
+                })
+                .collect();
+            // Compare mean slots exactly; the stable sort keeps DAG order on ties
+            keyed.sort_by(|a, b| (a.1 * b.2).cmp(&(b.1 * a.2)));
+            rows[l] = keyed.into_iter().map(|(item, _, _)| item).collect();
+        }
+
+        let mut view = Self::new();
+        view.depth = depth;
+        view.width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut placed: Vec<(usize, usize, usize)> = Vec::new();
+        for (l, row) in rows.iter().enumerate() {
+            for (slot, &item) in row.iter().enumerate() {
+                match item {
+                    Item::Node(node) => placed.push((l, slot, node)),
+                    Item::Pass(_) => view.passes.push((l, slot)),
+                }
+                if l > 0 {
+                    for up in upper(item, l) {
+                        view.links.push(Link {
+                            layer: l - 1,
+                            from: rows[l - 1].iter().position(|other| *other == up).unwrap_or(0),
+                            to: slot,
+                            arrow: matches!(item, Item::Node(_)),
+                        });
+                    }
+                }
+            }
+        }
+
+        for (l, slot, node) in placed {
+            let id = ids[node];
+            let dag_node = &dag.nodes[&id];
+            let neighbours = |pick: fn(&(usize, usize)) -> (usize, usize)| -> Vec<String> {
+                edges
+                    .iter()
+                    .map(pick)
+                    .filter(|&(this, _)| this == node)
+                    .map(|(_, other)| ids[other].to_string())
+                    .collect()
+            };
+            view.index.insert(id.to_string(), view.nodes.len());
+            view.nodes.push(DagNode {
+                id: id.to_string(),
+                label: kind_label(&dag_node.kind),
+                status: NodeStatus::Pending,
+                layer: l,
+                slot,
+                inputs: neighbours(|&(from, to)| (to, from)),
+                binding: input_binding(&dag_node.kind),
+                outputs: neighbours(|&(from, to)| (from, to)),
+                artifact: None,
+                capabilities: dag_node.capabilities.iter().map(ToString::to_string).collect(),
+                started: None,
+                finished: None,
+            });
+        }
+        view.edges = edges
+            .iter()
+            .map(|&(from, to)| DagEdge {
+                from: ids[from].to_string(),
+                to: ids[to].to_string(),
+            })
+            .collect();
+        view
+    }
+
+    /// Apply a run's events to its nodes' status and timing
+    #[must_use]
+    pub fn with_events(mut self, events: &[Event]) -> Self {
+        for event in events {
+            self.apply(event);
+        }
+        self
+    }
+
+    /// Update a node's status and timing from one of its events
+    ///
+    /// Events of other kinds, or of nodes not in the DAG, are ignored.
+    pub fn apply(&mut self, event: &Event) {
+        let Some(&position) = self.index.get(&event.node_id.to_string()) else {
+            return;
+        };
+        let node = &mut self.nodes[position];
+        let time = event.logical_time.as_u64();
+        node.status = match event.kind {
+            EventKind::NodeStarted => {
+                node.started = Some(time);
+                NodeStatus::Running
+            }
+            EventKind::NodeCompleted => NodeStatus::Completed,
+            EventKind::NodeFailed | EventKind::BudgetExceeded | EventKind::Shed => {
+                NodeStatus::Failed
+            }
+            EventKind::NodeSkipped | EventKind::NodeCancelled => NodeStatus::Skipped,
+            _ => return,
+        };
+        if node.status != NodeStatus::Running {
+            node.finished = Some(time);
+        }
+    }
+
+    /// Record a node's output, shown in the inspector as its hash and size
+    pub fn set_output(&mut self, node_id: &str, output: &[u8]) {
+        if let Some(&position) = self.index.get(node_id) {
+            self.nodes[position].artifact = Some((Hash::compute(output).to_hex(), output.len()));
+        }
+    }
+
+    /// Get the nodes in reading order
+    #[must_use]
+    pub fn nodes(&self) -> &[DagNode] {
+        &self.nodes
+    }
+
+    /// Get the edges between nodes
+    #[must_use]
+    pub fn edges(&self) -> &[DagEdge] {
+        &self.edges
+    }
+
+    /// Get the line of the node reached from line `index` by a move
+    ///
+    /// Up and down go to the nearest node in the layer above or below,
+    /// left and right to the neighbouring node in the same layer. Moves
+    /// past the edge of the graph, and other events, stay put.
+    #[must_use]
+    pub fn step(&self, index: usize, event: &InputEvent) -> usize {
+        let Some(current) = self.nodes.get(index) else {
+            return 0;
+        };
+        let nearest = |layer: usize| {
+            self.nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.layer == layer)
+                .min_by_key(|(_, node)| (node.slot.abs_diff(current.slot), node.slot))
+                .map(|(i, _)| i)
+        };
+        let same_layer = |i: usize| self.nodes.get(i).is_some_and(|n| n.layer == current.layer);
+        match event {
+            InputEvent::Down => nearest(current.layer + 1),
+            InputEvent::Up => current.layer.checked_sub(1).and_then(nearest),
+            InputEvent::Left => index.checked_sub(1).filter(|&i| same_layer(i)),
+            InputEvent::Right => Some(index + 1).filter(|&i| same_layer(i)),
+            _ => None,
+        }
+        .unwrap_or(index)
+    }
+
+    /// Draw the graph with the node at line `selected` highlighted
+    #[must_use]
+    pub fn draw(&self, selected: usize) -> Vec<Line<'static>> {
+        let width = (self.width * SLOT_WIDTH).saturating_sub(SLOT_WIDTH - BOX_WIDTH);
+        let height = (self.depth * LAYER_HEIGHT).saturating_sub(LAYER_HEIGHT - 3);
+        let mut canvas = Canvas::new(width, height);
+        let edge = Style::default().fg(Color::DarkGray);
+
+        for &(layer, slot) in &self.passes {
+            for row in 0..3 {
+                canvas.join(center(slot), layer * LAYER_HEIGHT + row, UP | DOWN);
+            }
+        }
+        for link in &self.links {
+            let top = link.layer * LAYER_HEIGHT + 3;
+            let (from, to) = (center(link.from), center(link.to));
+            canvas.join(from, top, UP | DOWN);
+            canvas.join(from, top + 1, UP);
+            canvas.join(to, top + 1, DOWN);
+            for x in from.min(to)..=from.max(to) {
+                let mut joint = 0;
+                if x > from.min(to) {
+                    joint |= LEFT;
+                }
+                if x < from.max(to) {
+                    joint |= RIGHT;
+                }
+                canvas.join(x, top + 1, joint);
+            }
+            canvas.join(to, top + 2, UP | DOWN);
+        }
+        canvas.draw_joints(edge);
+        for link in self.links.iter().filter(|link| link.arrow) {
+            canvas.put(center(link.to), link.layer * LAYER_HEIGHT + 5, '▼', edge);
         }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut style = Style::default().fg(node.status.color());
+            if i == selected {
+                style = style.bg(Color::Blue).add_modifier(Modifier::BOLD);
+            }
+            let (x, y) = (node.slot * SLOT_WIDTH, node.layer * LAYER_HEIGHT);
+            let rule = "─".repeat(BOX_WIDTH - 2);
+            canvas.text(x, y, &format!("┌{}┐", rule), style);
+            let label = truncate(&node.label, LABEL_WIDTH);
+            let text = format!("│ {} {:<LABEL_WIDTH$} │", node.status.glyph(), label);
+            canvas.text(x, y + 1, &text, style);
+            canvas.text(x, y + 2, &format!("└{}┘", rule), style);
+        }
+        canvas.lines()
+    }
+
+    /// Describe the node at line `selected` for the inspector pane
+    #[must_use]
+    pub fn inspect(&self, selected: usize) -> Vec<Line<'static>> {
+        let Some(node) = self.nodes.get(selected) else {
+            return vec![Line::from("No node selected")];
+        };
+        let heading = |text: &str| {
+            let bold = Style::default().add_modifier(Modifier::BOLD);
+            Line::from(Span::styled(text.to_string(), bold))
+        };
+        let neighbour = |arrow: &str, id: &String| {
+            let label = self.index.get(id).map_or("?", |&i| self.nodes[i].label.as_str());
+            Line::from(format!("  {} {}  {}", arrow, label, short_id(id)))
+        };
+
+        let mut lines = vec![
+            heading(&node.label),
+            Line::from(node.id.clone()),
+            Line::from(vec![
+                Span::raw("Status  "),
+                Span::styled(
+                    format!("{} {:?}", node.status.glyph(), node.status),
+                    Style::default().fg(node.status.color()),
+                ),
+            ]),
+            Line::from(""),
+            heading("Inputs"),
+        ];
+        lines.extend(node.inputs.iter().map(|id| neighbour("←", id)));
+        if let Some(binding) = &node.binding {
+            lines.push(Line::from(format!("  binding {}", binding)));
+        }
+        if node.inputs.is_empty() && node.binding.is_none() {
+            lines.push(Line::from("  none"));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(heading("Outputs"));
+        lines.extend(node.outputs.iter().map(|id| neighbour("→", id)));
+        match &node.artifact {
+            Some((hash, size)) => {
+                lines.push(Line::from(format!("  artifact {} ({} bytes)", short_hash(hash), size)));
+            }
+            None if node.outputs.is_empty() => lines.push(Line::from("  none")),
+            None => {}
+        }
+
+        lines.push(Line::from(""));
+        lines.push(heading("Capabilities"));
+        lines.extend(node.capabilities.iter().map(|c| Line::from(format!("  {}", c))));
+        if node.capabilities.is_empty() {
+            lines.push(Line::from("  none"));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(heading("Timing"));
+        let tick = |time: Option<u64>| time.map_or_else(|| "-".to_string(), |t| format!("t={}", t));
+        lines.push(Line::from(format!("  started   {}", tick(node.started))));
+        lines.push(Line::from(format!("  finished  {}", tick(node.finished))));
+        if let (Some(started), Some(finished)) = (node.started, node.finished) {
+            let took = finished.saturating_sub(started);
+            lines.push(Line::from(format!("  took      {} ticks", took)));
+        }
+        lines
+    }
+
+    /// Scroll offset keeping the node at line `selected` in view
+    fn scroll_to(&self, selected: usize, view: Rect) -> (u16, u16) {
+        let Some(node) = self.nodes.get(selected) else {
+            return (0, 0);
+        };
+        let (view_width, view_height) = (usize::from(view.width), usize::from(view.height));
+        let width = self.width * SLOT_WIDTH;
+        let height = self.depth * LAYER_HEIGHT;
+        let y = (node.layer * LAYER_HEIGHT + 1)
+            .saturating_sub(view_height / 2)
+            .min(height.saturating_sub(view_height));
+        let x = (center(node.slot))
+            .saturating_sub(view_width / 2)
+            .min(width.saturating_sub(view_width));
+        (y as u16, x as u16)
     }
 }
 
@@ -117,6 +749,24 @@ pub struct DagNode {
     pub label: String,
     /// Status
     pub status: NodeStatus,
+    /// Topological layer, 0 for nodes without dependencies
+    pub layer: usize,
+    /// Position within the layer
+    pub slot: usize,
+    /// IDs of the nodes this node depends on
+    pub inputs: Vec<String>,
+    /// Binding supplying the node's input, if any
+    pub binding: Option<String>,
+    /// IDs of the nodes depending on this node
+    pub outputs: Vec<String>,
+    /// Hex hash and size in bytes of the node's output, once known
+    pub artifact: Option<(String, usize)>,
+    /// Capabilities the node is granted
+    pub capabilities: Vec<String>,
+    /// Logical time the node started
+    pub started: Option<u64>,
+    /// Logical time the node finished
+    pub finished: Option<u64>,
 }
 
 /// DAG edge
@@ -139,39 +789,54 @@ pub enum NodeStatus {
     Completed,
     /// Failed
     Failed,
+    /// Skipped or cancelled
+    Skipped,
+}
+
+impl NodeStatus {
+    /// Color the status is drawn in
+    #[must_use]
+    pub fn color(self) -> Color {
+        match self {
+            Self::Pending => Color::Yellow,
+            Self::Running => Color::Cyan,
+            Self::Completed => Color::Green,
+            Self::Failed => Color::Red,
+            Self::Skipped => Color::DarkGray,
+        }
+    }
+
+    /// Symbol marking the status inside a node box
+    #[must_use]
+    pub fn glyph(self) -> char {
+        match self {
+            Self::Pending => '○',
+            Self::Running => '◐',
+            Self::Completed => '●',
+            Self::Failed => '✗',
+            Self::Skipped => '–',
+        }
+    }
 }
 
 impl View for DagView {
     fn render(&self, f: &mut Frame, area: Rect, selection: &crate::ui::Selection) {
-        let title = Block::default()
+        let panes = ratatui::layout::Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(area);
+
+        let block = Block::default()
             .title(" Execution DAG ")
             .borders(Borders::ALL);
+        let scroll = self.scroll_to(selection.line, block.inner(panes[0]));
+        let graph = Paragraph::new(self.draw(selection.line)).block(block).scroll(scroll);
+        f.render_widget(graph, panes[0]);
 
-        let rows: Vec<Line> = self.nodes
-            .iter()
-            .enumerate()
-            .map(|(i, node)| {
-                let style = if i == selection.line {
-                    Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default()
-                };
-                let status_color = match node.status {
-                    NodeStatus::Pending => Color::Yellow,
-                    NodeStatus::Running => Color::Cyan,
-                    NodeStatus::Completed => Color::Green,
-                    NodeStatus::Failed => Color::Red,
-                };
-                Line::from(vec![
-                    Span::raw(format!("{} ", node.id)),
-                    Span::styled(format!("[{}]", node.label), Style::default().fg(status_color)),
-                ])
-                .style(style)
-            })
-            .collect();
-
-        let paragraph = Paragraph::new(rows).block(title).wrap(Wrap { trim: false });
-        f.render_widget(paragraph, area);
+        let inspector = Paragraph::new(self.inspect(selection.line))
+            .block(Block::default().title(" Node ").borders(Borders::ALL))
+            .wrap(Wrap { trim: false });
+        f.render_widget(inspector, panes[1]);
     }
 
     fn item_count(&self) -> usize {
@@ -179,6 +844,174 @@ impl View for DagView {
     }
 }
 
+/// Longest-path layer of every node
+///
+/// Nodes on a cycle are never reached, and go in a layer below the rest.
+fn layers(count: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut indegree = vec![0; count];
+    for &(_, to) in edges {
+        indegree[to] += 1;
+    }
+    let mut ready: VecDeque<usize> = (0..count).filter(|&node| indegree[node] == 0).collect();
+    let mut layer = vec![0; count];
+    let mut placed = vec![false; count];
+    while let Some(node) = ready.pop_front() {
+        placed[node] = true;
+        for &(_, to) in edges.iter().filter(|(from, _)| *from == node) {
+            layer[to] = layer[to].max(layer[node] + 1);
+            indegree[to] -= 1;
+            if indegree[to] == 0 {
+                ready.push_back(to);
+            }
+        }
+    }
+    let below = layer.iter().max().map_or(0, |max| max + 1);
+    for node in (0..count).filter(|&node| !placed[node]) {
+        layer[node] = below;
+    }
+    layer
+}
+
+/// Short description of a node kind
+fn kind_label(kind: &NodeKind) -> String {
+    let debug = format!("{:?}", kind);
+    let name = debug.split([' ', '{', '(']).next().unwrap_or_default().to_lowercase();
+    match kind {
+        NodeKind::Tool { name: tool, .. } | NodeKind::MapReduce { name: tool, .. } => {
+            format!("{} {}", name, tool)
+        }
+        _ => name,
+    }
+}
+
+/// Binding supplying a node's input, if its kind takes one
+fn input_binding(kind: &NodeKind) -> Option<String> {
+    match kind {
+        NodeKind::Tool { input_binding, .. } | NodeKind::SubWorkflow { input_binding, .. } => {
+            input_binding.clone()
+        }
+        NodeKind::Conditional { input_binding, .. } | NodeKind::MapReduce { input_binding, .. } => {
+            Some(input_binding.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Column of the center of a slot
+fn center(slot: usize) -> usize {
+    slot * SLOT_WIDTH + BOX_WIDTH / 2
+}
+
+/// Cut `text` to `width` characters, marking the cut with an ellipsis
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        let mut cut: String = text.chars().take(width - 1).collect();
+        cut.push('…');
+        cut
+    }
+}
+
+/// Node ID cut to its prefix and first eight hex digits
+fn short_id(id: &str) -> &str {
+    id.get(..13).unwrap_or(id)
+}
+
+/// Hash cut to its first twelve hex digits
+fn short_hash(hash: &str) -> &str {
+    hash.get(..12).unwrap_or(hash)
+}
+
+/// Line joint reaching up
+const UP: u8 = 1;
+/// Line joint reaching down
+const DOWN: u8 = 2;
+/// Line joint reaching left
+const LEFT: u8 = 4;
+/// Line joint reaching right
+const RIGHT: u8 = 8;
+
+/// Character grid the DAG is drawn on
+///
+/// Edges are collected as joints, the directions lines leave each cell in,
+/// so crossing and merging edges get the right box-drawing character.
+struct Canvas {
+    cells: Vec<Vec<(char, Style)>>,
+    joints: Vec<Vec<u8>>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![vec![(' ', Style::default()); width]; height],
+            joints: vec![vec![0; width]; height],
+        }
+    }
+
+    fn join(&mut self, x: usize, y: usize, joint: u8) {
+        if let Some(cell) = self.joints.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *cell |= joint;
+        }
+    }
+
+    fn put(&mut self, x: usize, y: usize, c: char, style: Style) {
+        if let Some(cell) = self.cells.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *cell = (c, style);
+        }
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, style: Style) {
+        for (i, c) in text.chars().enumerate() {
+            self.put(x + i, y, c, style);
+        }
+    }
+
+    /// Turn the collected joints into box-drawing characters
+    fn draw_joints(&mut self, style: Style) {
+        for y in 0..self.joints.len() {
+            for x in 0..self.joints[y].len() {
+                let c = match self.joints[y][x] {
+                    0 => continue,
+                    j if j == UP | DOWN || j == UP || j == DOWN => '│',
+                    j if j & (UP | DOWN) == 0 => '─',
+                    j if j == DOWN | RIGHT => '┌',
+                    j if j == DOWN | LEFT => '┐',
+                    j if j == UP | RIGHT => '└',
+                    j if j == UP | LEFT => '┘',
+                    j if j == UP | DOWN | RIGHT => '├',
+                    j if j == UP | DOWN | LEFT => '┤',
+                    j if j == DOWN | LEFT | RIGHT => '┬',
+                    j if j == UP | LEFT | RIGHT => '┴',
+                    _ => '┼',
+                };
+                self.put(x, y, c, style);
+            }
+        }
+    }
+
+    /// Get the rows as lines, with runs of one style merged into a span
+    fn lines(self) -> Vec<Line<'static>> {
+        self.cells
+            .into_iter()
+            .map(|row| {
+                let mut spans: Vec<Span<'static>> = Vec::new();
+                let mut run = String::new();
+                let mut style = Style::default();
+                for (c, cell_style) in row {
+                    if cell_style != style && !run.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut run), style));
+                    }
+                    style = cell_style;
+                    run.push(c);
+                }
+                spans.push(Span::styled(run.trim_end().to_string(), style));
+                Line::from(spans)
+            })
+            .collect()
+    }
+}
+
 /// Worker view showing worker status
 pub struct WorkerView {
     workers: Vec<WorkerStatus>,
@@ -192,6 +1025,11 @@ impl WorkerView {
             workers: Vec::new(),
         }
     }
+
+    /// Replace the workers shown
+    pub fn set_workers(&mut self, workers: Vec<WorkerStatus>) {
+        self.workers = workers;
+    }
 }
 
 impl Default for WorkerView {
@@ -263,16 +1101,209 @@ impl View for WorkerView {
 }
 
 /// Provenance view showing data lineage
+///
+/// Lists every node of the execution graph until a node or output is
+/// traced; then shows its provenance as a tree whose nodes expand to their
+/// tools, host calls, policy, capabilities and upstream nodes.
 pub struct ProvenanceView {
     entries: Vec<ProvenanceEntry>,
+    graph: ExecutionGraph,
+    /// Node labels by node ID
+    labels: HashMap<String, String>,
+    /// Provenance being shown, if a target was traced
+    tree: Option<Provenance>,
+    /// Paths of the collapsed tree nodes, as indices into `upstream`
+    collapsed: HashSet<Vec<usize>>,
+    /// Tree rows shown
+    rows: Vec<ProvenanceRow>,
 }
 
 impl ProvenanceView {
     /// Create new provenance view
     #[must_use]
     pub fn new() -> Self {
+        Self::from_graph(&ExecutionGraph::default())
+    }
+
+    /// Create a provenance view from a reconstructed execution graph
+    ///
+    /// Entries follow the graph's node order and mirror
+    /// [`ExecutionGraph::lines`], which the CLI trace prints.
+    #[must_use]
+    pub fn from_graph(graph: &ExecutionGraph) -> Self {
+        let entries = graph
+            .nodes
+            .values()
+            .map(|node| {
+                ProvenanceEntry {
+                    data_id: node.node_id.to_string(),
+                    source: graph.source_label(node.node_id),
+                    hash: node.hash_label(),
+                    timestamp: node
+                        .events
+                        .last()
+                        .map(|e| e.time.to_string())
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
         Self {
-            entries: Vec::new(),
+            entries,
+            graph: graph.clone(),
+            labels: HashMap::new(),
+            tree: None,
+            collapsed: HashSet::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Label nodes in the tree, e.g. with their DAG labels
+    #[must_use]
+    pub fn with_labels<I>(mut self, labels: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        self.labels = labels.into_iter().collect();
+        self.rebuild();
+        self
+    }
+
+    /// Entry listed at `line` while no target is traced
+    #[must_use]
+    pub fn entry(&self, line: usize) -> Option<&ProvenanceEntry> {
+        self.entries.get(line)
+    }
+
+    /// Trace a node ID or output hash, or a unique prefix of either
+    ///
+    /// The tree starts fully expanded.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the target is unknown or ambiguous
+    pub fn trace(&mut self, target: &str) -> Result<&Provenance, CoreError> {
+        let target = self.graph.resolve(target)?;
+        self.tree = Some(self.graph.provenance(target)?);
+        self.collapsed.clear();
+        self.rebuild();
+        Ok(self.tree.as_ref().expect("tree was just set"))
+    }
+
+    /// Go back to the list of nodes
+    pub fn close(&mut self) {
+        self.tree = None;
+        self.rows.clear();
+    }
+
+    /// Provenance being shown, if a target was traced
+    #[must_use]
+    pub fn tree(&self) -> Option<&Provenance> {
+        self.tree.as_ref()
+    }
+
+    /// Tree row at `line`
+    #[must_use]
+    pub fn row(&self, line: usize) -> Option<&ProvenanceRow> {
+        self.rows.get(line)
+    }
+
+    /// Expand or collapse the tree node at or owning the row at `line`
+    ///
+    /// Returns the line of that node's row afterwards.
+    pub fn toggle(&mut self, line: usize) -> usize {
+        match self.owner(line) {
+            Some(path) => {
+                let expand = self.collapsed.contains(&path);
+                self.set_expanded(path, expand)
+            }
+            None => line,
+        }
+    }
+
+    /// Expand (`true`) or collapse the tree node at or owning the row at `line`
+    ///
+    /// Returns the line of that node's row afterwards.
+    pub fn expand(&mut self, line: usize, expand: bool) -> usize {
+        match self.owner(line) {
+            Some(path) => self.set_expanded(path, expand),
+            None => line,
+        }
+    }
+
+    /// Path of the tree node whose row is at `line`, or that the detail
+    /// row at `line` belongs to
+    fn owner(&self, line: usize) -> Option<Vec<usize>> {
+        // Detail rows directly follow the row of their node
+        self.rows
+            .get(..=line)?
+            .iter()
+            .rev()
+            .find(|row| row.node.is_some())
+            .filter(|row| row.expandable)
+            .and_then(|row| row.node.clone())
+    }
+
+    fn set_expanded(&mut self, path: Vec<usize>, expand: bool) -> usize {
+        if expand {
+            self.collapsed.remove(&path);
+        } else {
+            self.collapsed.insert(path.clone());
+        }
+        self.rebuild();
+        self.rows
+            .iter()
+            .position(|row| row.node.as_ref() == Some(&path))
+            .unwrap_or(0)
+    }
+
+    fn rebuild(&mut self) {
+        self.rows.clear();
+        let Some(tree) = &self.tree else {
+            return;
+        };
+        let mut pending = vec![(&tree.root, Vec::new())];
+        while let Some((node, path)) = pending.pop() {
+            let details = provenance_details(node);
+            let expandable = !details.is_empty() || !node.upstream.is_empty();
+            let expanded = expandable && !self.collapsed.contains(&path);
+            let depth = path.len();
+
+            let id = node.node_id.to_string();
+            let output = node.output_hash.map(|h| h.to_hex()).unwrap_or_else(|| "-".to_string());
+            let mut text = short_id(&id).to_string();
+            if let Some(label) = self.labels.get(&id) {
+                text = format!("{} {}", text, label);
+            }
+            text = format!("{} -> {}", text, short_hash(&output));
+            if node.repeated {
+                text.push_str(" (see above)");
+            }
+            let color = if node.repeated { Color::DarkGray } else { Color::White };
+            self.rows.push(ProvenanceRow {
+                depth,
+                text,
+                color,
+                node: Some(path.clone()),
+                expandable,
+                expanded,
+            });
+
+            if expanded {
+                self.rows.extend(details.into_iter().map(|(text, color)| ProvenanceRow {
+                    depth: depth + 1,
+                    text,
+                    color,
+                    node: None,
+                    expandable: false,
+                    expanded: false,
+                }));
+                for (index, upstream) in node.upstream.iter().enumerate().rev() {
+                    let mut path = path.clone();
+                    path.push(index);
+                    pending.push((upstream, path));
+                }
+            }
         }
     }
 }
@@ -296,10 +1327,213 @@ pub struct ProvenanceEntry {
     pub timestamp: String,
 }
 
+impl ProvenanceEntry {
+    /// Render the entry as a single line of text
+    #[must_use]
+    pub fn line(&self) -> String {
+        format!("{} <- {} ({})", self.data_id, self.source, self.hash)
+    }
+}
+
+/// Row of a provenance tree
+#[derive(Debug, Clone)]
+pub struct ProvenanceRow {
+    /// Nesting depth, 0 for the traced node
+    pub depth: usize,
+    /// Text shown
+    pub text: String,
+    /// Text color
+    pub color: Color,
+    /// Path of the tree node the row heads, `None` for detail rows
+    pub node: Option<Vec<usize>>,
+    /// Whether the node has details or upstream nodes to show
+    pub expandable: bool,
+    /// Whether the node's details and upstream nodes are shown
+    pub expanded: bool,
+}
+
+/// Detail rows of a provenance node, with their colors
+fn provenance_details(node: &ProvenanceNode) -> Vec<(String, Color)> {
+    let hash = |hash: Option<Hash>| hash.map(|h| h.to_hex()).unwrap_or_else(|| "-".to_string());
+    let mut details = Vec::new();
+    for tool in &node.tools {
+        let name = if tool.tool.is_empty() { "?" } else { tool.tool.as_str() };
+        details.push((
+            format!(
+                "tool {} schema {} input {}",
+                name,
+                short_hash(&hash(tool.schema_hash)),
+                short_hash(&tool.input_hash.to_hex())
+            ),
+            Color::Yellow,
+        ));
+    }
+    for call in &node.host_calls {
+        details.push((
+            format!("host {} -> {}", call.function, short_hash(&call.result_hash.to_hex())),
+            Color::Magenta,
+        ));
+    }
+    if let Some(policy) = &node.policy {
+        details.push((
+            format!(
+                "policy v{} bundle {}",
+                policy.version,
+                short_hash(&policy.bundle_hash.to_hex())
+            ),
+            Color::Cyan,
+        ));
+    }
+    for decision in &node.capabilities {
+        let (outcome, color) = if decision.allowed {
+            ("allowed", Color::Green)
+        } else {
+            ("denied", Color::Red)
+        };
+        details.push((format!("capability {} {}", decision.capability, outcome), color));
+    }
+    details
+}
+
 impl View for ProvenanceView {
+    fn render(&self, f: &mut Frame, area: Rect, selection: &crate::ui::Selection) {
+        let title = match &self.tree {
+            Some(tree) => format!(
+                " Provenance of {} ({} inputs) ",
+                short_id(&tree.target.to_string()),
+                tree.inputs().len()
+            ),
+            None => " Provenance ".to_string(),
+        };
+        let title = Block::default()
+            .title(title)
+            .borders(Borders::ALL);
+        let selected = |line: usize| {
+            if line == selection.line {
+                Style::default().bg(Color::Blue).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            }
+        };
+
+        // Scroll just far enough to keep the selected row in view
+        let height = usize::from(area.height.saturating_sub(2)).max(1);
+        let offset = (selection.line + 1).saturating_sub(height);
+        let rows: Vec<Line> = if self.tree.is_some() {
+            self.rows
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(height)
+                .map(|(i, row)| {
+                    let marker = match (row.expandable, row.expanded) {
+                        (false, _) => "  ",
+                        (true, true) => "▾ ",
+                        (true, false) => "▸ ",
+                    };
+                    let mut style = Style::default().fg(row.color);
+                    if row.node.is_some() {
+                        style = style.add_modifier(Modifier::BOLD);
+                    }
+                    Line::from(vec![
+                        Span::raw("  ".repeat(row.depth)),
+                        Span::raw(marker),
+                        Span::styled(row.text.clone(), style),
+                    ])
+                    .style(selected(i))
+                })
+                .collect()
+        } else {
+            self.entries
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(height)
+                .map(|(i, entry)| {
+                    Line::from(vec![
+                        Span::raw(format!("{} ", entry.data_id)),
+                        Span::raw(format!("<- {} ", entry.source)),
+                        Span::raw(format!("({})", entry.hash)),
+                    ])
+                    .style(selected(i))
+                })
+                .collect()
+        };
+
+        let paragraph = Paragraph::new(rows).block(title).wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+    }
+
+    fn item_count(&self) -> usize {
+        if self.tree.is_some() {
+            self.rows.len()
+        } else {
+            self.entries.len()
+        }
+    }
+}
+
+/// Payload view showing stored event payloads as redacted views
+pub struct PayloadView {
+    entries: Vec<PayloadEntry>,
+}
+
+impl PayloadView {
+    /// Create new payload view
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Create a payload view from stored events
+    ///
+    /// Payloads are shown as stored; for redacted events the original is
+    /// withheld and only its commitment hash is displayed.
+    #[must_use]
+    pub fn from_events(events: &[Event]) -> Self {
+        let entries = events
+            .iter()
+            .map(|event| PayloadEntry {
+                event_id: event.event_id,
+                kind: format!("{:?}", event.kind),
+                view: RedactedView::from_event(event),
+                commitment: event
+                    .redaction
+                    .as_ref()
+                    .map(|redaction| redaction.original_hash.to_hex())
+                    .unwrap_or_default(),
+            })
+            .collect();
+
+        Self { entries }
+    }
+}
+
+impl Default for PayloadView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Payload entry
+#[derive(Debug, Clone)]
+pub struct PayloadEntry {
+    /// Event ID
+    pub event_id: EventId,
+    /// Event kind
+    pub kind: String,
+    /// Redacted payload
+    pub view: RedactedView,
+    /// Hash of the original payload, empty if not redacted
+    pub commitment: String,
+}
+
+impl View for PayloadView {
     fn render(&self, f: &mut Frame, area: Rect, selection: &crate::ui::Selection) {
         let title = Block::default()
-            .title(" Provenance ")
+            .title(" Payloads ")
             .borders(Borders::ALL);
 
         let rows: Vec<Line> = self.entries
@@ -311,12 +1545,21 @@ impl View for ProvenanceView {
                 } else {
                     Style::default()
                 };
-                Line::from(vec![
-                    Span::raw(format!("{} ", entry.data_id)),
-                    Span::raw(format!("<- {} ", entry.source)),
-                    Span::raw(format!("({})", entry.hash)),
-                ])
-                .style(style)
+                let mut spans = vec![
+                    Span::raw(format!("{:16} ", entry.kind)),
+                    Span::raw(entry.view.as_str().to_string()),
+                ];
+                if entry.view.is_redacted() {
+                    spans.push(Span::styled(
+                        format!(
+                            " [redacted: {}] {}",
+                            entry.view.applied_rules.join(", "),
+                            entry.commitment
+                        ),
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                Line::from(spans).style(style)
             })
             .collect();
 
@@ -332,6 +1575,7 @@ impl View for ProvenanceView {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use cathedral_core::RunId;
 
     #[test]
     fn test_timeline_view_new() {
@@ -339,12 +1583,211 @@ mod tests {
         assert_eq!(view.items.len(), 0);
     }
 
+    #[test]
+    fn test_timeline_orders_and_filters() {
+        use cathedral_core::LogicalTime;
+
+        let (a, b) = (NodeId::new(), NodeId::new());
+        let run_id = RunId::new();
+        let event = |node: NodeId, time: u64, kind: EventKind, payload: &[u8]| {
+            Event::new(EventId::new(), run_id, node, LogicalTime::from_raw(time), kind)
+                .with_payload(payload.to_vec())
+        };
+        let mut view = TimelineView::from_events(&[
+            event(a, 2, EventKind::NodeStarted, b""),
+            event(a, 1, EventKind::NodeScheduled, b""),
+            event(b, 2, EventKind::NodeFailed, b"connection timeout"),
+        ]);
+        let order = |view: &TimelineView| -> Vec<u64> {
+            (0..view.item_count()).map(|line| view.item(line).unwrap().position).collect()
+        };
+        assert_eq!(order(&view), vec![1, 0, 2]);
+        assert_eq!(view.item(2).unwrap().detail, "connection timeout");
+
+        let filter = |text: &str| text.parse::<TimelineFilter>().unwrap();
+        view.set_filter(Some(filter(&format!("node={}", &a.to_string()[5..13]))));
+        assert_eq!(order(&view), vec![1, 0]);
+        view.set_filter(Some(filter("kind=NodeFailed,NodeScheduled")));
+        assert_eq!(order(&view), vec![1, 2]);
+        view.set_filter(Some(filter("TIMEOUT from=2")));
+        assert_eq!(order(&view), vec![2]);
+        assert_eq!(view.filter().unwrap().to_string(), "TIMEOUT from=2");
+
+        view.push(3, &event(a, 0, EventKind::Error, b"timeout"));
+        view.push(4, &event(a, 5, EventKind::NodeCompleted, b""));
+        assert_eq!(order(&view), vec![2]);
+        view.set_filter(None);
+        assert_eq!(order(&view), vec![3, 1, 0, 2, 4]);
+        assert_eq!(view.find(&filter("text=timeout")), vec![0, 3]);
+        assert!("kind=Nope".parse::<TimelineFilter>().is_err());
+    }
+
+    #[test]
+    fn test_timeline_render_scrolls_to_selection() {
+        use cathedral_core::LogicalTime;
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let run_id = RunId::new();
+        let events: Vec<Event> = (0..20)
+            .map(|time| {
+                let kind = if time == 19 { EventKind::NodeFailed } else { EventKind::Heartbeat };
+                Event::new(EventId::new(), run_id, NodeId::new(), LogicalTime::from_raw(time), kind)
+            })
+            .collect();
+        let view = TimelineView::from_events(&events);
+        let mut terminal = Terminal::new(TestBackend::new(80, 7)).unwrap();
+        let selection = crate::ui::Selection { line: 19, ..Default::default() };
+        terminal.draw(|f| view.render(f, f.area(), &selection)).unwrap();
+
+        let screen: String =
+            terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("Timeline 20"));
+        assert!(screen.contains("✗ NodeFailed"));
+        assert!(!screen.contains("       0 "));
+    }
+
     #[test]
     fn test_dag_view_new() {
         let view = DagView::new();
         assert_eq!(view.nodes.len(), 0);
     }
 
+    /// Diamond a -> {b, c} -> d, plus an edge a -> d skipping a layer
+    fn diamond() -> (Dag, [NodeId; 4]) {
+        use cathedral_core::Capability;
+        use cathedral_plan::dag::{Edge, Node, ResourceRequirements};
+
+        let ids = [NodeId::new(), NodeId::new(), NodeId::new(), NodeId::new()];
+        let mut dag = Dag::new();
+        for (i, id) in ids.iter().enumerate() {
+            let kind = match i {
+                0 => NodeKind::Input { schema: "any".to_string() },
+                3 => NodeKind::Output { schema: "any".to_string() },
+                _ => NodeKind::Tool {
+                    name: format!("fetch{}", i),
+                    version_req: "^1".to_string(),
+                    input_binding: Some("url".to_string()),
+                },
+            };
+            dag.add_node(Node {
+                id: *id,
+                kind,
+                dependencies: Default::default(),
+                capabilities: if i == 1 { vec![Capability::ClockRead] } else { Vec::new() },
+                resources: ResourceRequirements::new(),
+            })
+            .unwrap();
+        }
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3), (0, 3)] {
+            dag.add_edge(Edge::new(ids[from], ids[to])).unwrap();
+        }
+        (dag, ids)
+    }
+
+    #[test]
+    fn test_dag_view_layout() {
+        let (dag, ids) = diamond();
+        let view = DagView::from_dag(&dag);
+
+        let placed: Vec<(String, usize, usize)> =
+            view.nodes().iter().map(|n| (n.id.clone(), n.layer, n.slot)).collect();
+        assert_eq!(
+            placed,
+            vec![
+                (ids[0].to_string(), 0, 0),
+                (ids[1].to_string(), 1, 0),
+                (ids[2].to_string(), 1, 1),
+                (ids[3].to_string(), 2, 0),
+            ]
+        );
+        assert_eq!(view.passes, vec![(1, 2)]);
+        assert_eq!((view.width, view.depth), (3, 3));
+        assert_eq!(view.edges().len(), 5);
+
+        let fetch = &view.nodes()[1];
+        assert_eq!(fetch.label, "tool fetch1");
+        assert_eq!(fetch.inputs, vec![ids[0].to_string()]);
+        assert_eq!(fetch.outputs, vec![ids[3].to_string()]);
+        assert_eq!(fetch.binding.as_deref(), Some("url"));
+        assert_eq!(fetch.capabilities.len(), 1);
+        assert_eq!(view.nodes()[3].inputs.len(), 3);
+    }
+
+    #[test]
+    fn test_dag_view_applies_events() {
+        use cathedral_core::{EventId, LogicalTime};
+
+        let (dag, ids) = diamond();
+        let run_id = RunId::new();
+        let event = |node: usize, time: u64, kind: EventKind| {
+            Event::new(EventId::new(), run_id, ids[node], LogicalTime::from_raw(time), kind)
+        };
+        let mut view = DagView::from_dag(&dag).with_events(&[
+            event(0, 1, EventKind::NodeStarted),
+            event(0, 3, EventKind::NodeCompleted),
+            event(1, 4, EventKind::NodeStarted),
+            event(1, 5, EventKind::NodeFailed),
+            event(2, 6, EventKind::NodeStarted),
+            event(3, 7, EventKind::NodeSkipped),
+            event(2, 8, EventKind::ToolInvoked),
+        ]);
+        view.set_output(&ids[0].to_string(), b"hello");
+
+        let statuses: Vec<NodeStatus> = view.nodes().iter().map(|n| n.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                NodeStatus::Completed,
+                NodeStatus::Failed,
+                NodeStatus::Running,
+                NodeStatus::Skipped,
+            ]
+        );
+        let input = &view.nodes()[0];
+        assert_eq!((input.started, input.finished), (Some(1), Some(3)));
+        assert_eq!(input.artifact, Some((Hash::compute(b"hello").to_hex(), 5)));
+        assert_eq!(view.nodes()[2].finished, None);
+    }
+
+    #[test]
+    fn test_dag_view_step() {
+        let (dag, _) = diamond();
+        let view = DagView::from_dag(&dag);
+
+        assert_eq!(view.step(0, &InputEvent::Down), 1);
+        assert_eq!(view.step(0, &InputEvent::Up), 0);
+        assert_eq!(view.step(0, &InputEvent::Left), 0);
+        assert_eq!(view.step(1, &InputEvent::Right), 2);
+        assert_eq!(view.step(2, &InputEvent::Right), 2);
+        assert_eq!(view.step(2, &InputEvent::Left), 1);
+        assert_eq!(view.step(2, &InputEvent::Down), 3);
+        assert_eq!(view.step(3, &InputEvent::Up), 1);
+        assert_eq!(view.step(3, &InputEvent::Down), 3);
+    }
+
+    #[test]
+    fn test_dag_view_render() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let (dag, ids) = diamond();
+        let view = DagView::from_dag(&dag);
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        let selection = crate::ui::Selection { line: 1, ..Default::default() };
+        terminal.draw(|f| view.render(f, f.area(), &selection)).unwrap();
+
+        let screen: String =
+            terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        let expected = ["┌────", "│ ○ input", "tool fetch1", "▼", "┴", "Capabilities", "ClockRead"];
+        for expected in expected {
+            assert!(screen.contains(expected), "missing {:?}", expected);
+        }
+        assert!(screen.contains(&format!("← input  {}", short_id(&ids[0].to_string()))));
+
+        let graph: Vec<String> = view.draw(0).iter().map(ToString::to_string).collect();
+        assert_eq!(graph.len(), 15);
+        assert!(graph[3].ends_with('│'));
+    }
+
     #[test]
     fn test_worker_view_new() {
         let view = WorkerView::new();
@@ -357,6 +1800,134 @@ mod tests {
         assert_eq!(view.entries.len(), 0);
     }
 
+    #[test]
+    fn test_provenance_view_matches_graph_lines() {
+        use cathedral_core::{LogicalTime, NodeId};
+        use cathedral_replay::{build_graph, TraceEvent};
+        use cathedral_replay::trace::TraceEventKind;
+
+        let a = NodeId::new();
+        let b = NodeId::new();
+        let start = TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::zero(),
+            node_id: a,
+            kind: TraceEventKind::NodeCompleted,
+            data: b"x".to_vec(),
+            parent_id: None,
+        };
+        let next = TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(1),
+            node_id: b,
+            kind: TraceEventKind::NodeStarted,
+            data: Vec::new(),
+            parent_id: Some(start.id),
+        };
+
+        let graph = build_graph(vec![start, next]);
+        let view = ProvenanceView::from_graph(&graph);
+        let lines: Vec<String> = view.entries.iter().map(ProvenanceEntry::line).collect();
+        assert_eq!(lines, graph.lines());
+    }
+
+    #[test]
+    fn test_provenance_view_tree() {
+        use cathedral_core::{LogicalTime, NodeId};
+        use cathedral_replay::{build_graph, TraceEvent};
+        use cathedral_replay::trace::TraceEventKind;
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let (a, b) = (NodeId::new(), NodeId::new());
+        let event = |node_id, time, kind, parent_id| TraceEvent {
+            id: EventId::new(),
+            time: LogicalTime::from_raw(time),
+            node_id,
+            kind,
+            data: b"x".to_vec(),
+            parent_id,
+        };
+        let a_done = event(a, 0, TraceEventKind::NodeCompleted, None);
+        let tool = TraceEventKind::ToolInvoked { tool: "echo".to_string(), schema_hash: None };
+        let b_tool = event(b, 1, tool, Some(a_done.id));
+        let check = TraceEventKind::CapabilityCheck {
+            capability: "ClockRead".to_string(),
+            allowed: false,
+        };
+        let b_check = event(b, 2, check, Some(b_tool.id));
+        let graph = build_graph(vec![a_done, b_tool, b_check]);
+
+        let labels = [(b.to_string(), "tool echo".to_string())];
+        let mut view = ProvenanceView::from_graph(&graph).with_labels(labels);
+        assert_eq!(view.item_count(), 2);
+        assert!(view.trace("nope").is_err());
+        assert_eq!(view.trace(&b.to_string()).unwrap().inputs(), vec![a]);
+
+        // b, its tool and capability rows, then a
+        let rows: Vec<String> = view.rows.iter().map(|row| row.text.clone()).collect();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0].contains("tool echo"));
+        assert!(rows[1].starts_with("tool echo schema -"));
+        assert_eq!(rows[2], "capability ClockRead denied");
+        assert_eq!(view.row(3).unwrap().node, Some(vec![0]));
+
+        // Collapsing from a detail row folds its node
+        assert_eq!(view.expand(2, false), 0);
+        assert_eq!(view.item_count(), 1);
+        assert!(!view.row(0).unwrap().expanded);
+        assert_eq!(view.toggle(0), 0);
+        assert_eq!(view.item_count(), 4);
+        // A node without details or upstream does not fold
+        assert_eq!(view.toggle(3), 3);
+        assert_eq!(view.item_count(), 4);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 8)).unwrap();
+        let selection = crate::ui::Selection { line: 2, ..Default::default() };
+        terminal.draw(|f| view.render(f, f.area(), &selection)).unwrap();
+        let screen: String =
+            terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("(1 inputs)"));
+        assert!(screen.contains("▾ "));
+        assert!(screen.contains("capability ClockRead denied"));
+
+        view.close();
+        assert!(view.tree().is_none());
+        assert_eq!(view.item_count(), 2);
+    }
+
+    #[test]
+    fn test_payload_view_from_events() {
+        use cathedral_core::{LogicalTime, NodeId};
+        use cathedral_log::EventKind;
+
+        let event = |payload: &[u8]| {
+            Event::new(
+                EventId::new(),
+                RunId::new(),
+                NodeId::new(),
+                LogicalTime::zero(),
+                EventKind::ToolInvoked,
+            )
+            .with_payload(payload.to_vec())
+        };
+        let plain = event(b"{}");
+        let redacted = event(b"secret").with_redaction(b"***".to_vec(), vec!["mask".to_string()]);
+
+        let view = PayloadView::from_events(&[plain, redacted.clone()]);
+        assert_eq!(view.item_count(), 2);
+        assert!(!view.entries[0].view.is_redacted());
+        assert!(view.entries[0].commitment.is_empty());
+
+        let entry = &view.entries[1];
+        assert_eq!(entry.view.as_str(), "***");
+        assert!(entry.view.original.is_empty());
+        assert_eq!(entry.view.applied_rules, vec!["mask".to_string()]);
+        assert_eq!(
+            entry.commitment,
+            redacted.redaction.unwrap().original_hash.to_hex()
+        );
+    }
+
     #[test]
     fn test_node_status_copy() {
         let status = NodeStatus::Completed;
@@ -372,6 +1943,7 @@ mod tests {
     #[test]
     fn test_timeline_item_clone() {
         let item = TimelineItem {
+            position: 0,
             tick: 1,
             node_id: "node1".to_string(),
             kind: "Test".to_string(),
@@ -1,7 +1,9 @@
 //! Deterministic ABI for WASM host-guest communication.
 
+use crate::host::ClockGranularity;
 use crate::memory::MemoryLimit;
-use cathedral_core::{EventId, NodeId};
+use cathedral_core::float::{canonical_nan_f32, canonical_nan_f64};
+use cathedral_core::{EventId, Hash, NodeId};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -87,9 +89,9 @@ pub enum AbiValue {
     I32(i32),
     /// 64-bit integer
     I64(i64),
-    /// 32-bit float (as bits for determinism)
+    /// 32-bit float (as bits for determinism, NaNs canonical)
     F32(u32),
-    /// 64-bit float (as bits for determinism)
+    /// 64-bit float (as bits for determinism, NaNs canonical)
     F64(u64),
     /// Boolean
     Bool(bool),
@@ -150,7 +152,7 @@ impl DeterministicAbi {
             "clock_read".to_string(),
             AbiSignature {
                 name: "clock_read".to_string(),
-                params: vec![],
+                params: vec![AbiType::I64],
                 returns: AbiType::I64,
                 deterministic: true,
                 fuel_cost: 10,
@@ -204,6 +206,41 @@ impl DeterministicAbi {
             },
         );
 
+        // Database operations (table allowlists, in-memory store)
+        functions.insert(
+            "db_read".to_string(),
+            AbiSignature {
+                name: "db_read".to_string(),
+                params: vec![AbiType::String, AbiType::String],
+                returns: AbiType::Option(Box::new(AbiType::Bytes)),
+                deterministic: true,
+                fuel_cost: 100,
+            },
+        );
+
+        functions.insert(
+            "db_write".to_string(),
+            AbiSignature {
+                name: "db_write".to_string(),
+                params: vec![AbiType::String, AbiType::String, AbiType::Bytes],
+                returns: AbiType::I32,
+                deterministic: true,
+                fuel_cost: 100,
+            },
+        );
+
+        // ID generation (seeded, per-call counter)
+        functions.insert(
+            "gen_id".to_string(),
+            AbiSignature {
+                name: "gen_id".to_string(),
+                params: vec![],
+                returns: AbiType::String,
+                deterministic: true,
+                fuel_cost: 20,
+            },
+        );
+
         // Network operations (deterministic with mocking)
         functions.insert(
             "net_http".to_string(),
@@ -222,6 +259,29 @@ impl DeterministicAbi {
         }
     }
 
+    /// Canonical text form of every signature, sorted by function name
+    ///
+    /// The version string is not included; it is compared separately.
+    #[must_use]
+    pub fn canonical_form(&self) -> String {
+        let mut signatures: Vec<&AbiSignature> = self.functions.values().collect();
+        signatures.sort_by(|a, b| a.name.cmp(&b.name));
+        signatures
+            .iter()
+            .map(|sig| sig.canonical_form())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Hash of the canonical signature set
+    ///
+    /// Two hosts on the same ABI version but with different signatures
+    /// produce different hashes.
+    #[must_use]
+    pub fn content_hash(&self) -> Hash {
+        Hash::compute(self.canonical_form().as_bytes())
+    }
+
     /// Get function signature
     #[must_use]
     pub fn get_function(&self, name: &str) -> Option<&AbiSignature> {
@@ -259,6 +319,13 @@ impl DeterministicAbi {
             }
         }
 
+        if let Some(position) = call.args.iter().position(|arg| !arg.is_canonical()) {
+            return Err(AbiError::DeterminismViolation(format!(
+                "argument {} holds a non-canonical NaN",
+                position
+            )));
+        }
+
         Ok(())
     }
 
@@ -297,6 +364,48 @@ impl Default for DeterministicAbi {
     }
 }
 
+impl AbiSignature {
+    /// Canonical text form, e.g. `log_write(string,i32)->i32 det=true fuel=50`
+    #[must_use]
+    pub fn canonical_form(&self) -> String {
+        let params: Vec<String> = self.params.iter().map(AbiType::canonical_form).collect();
+        format!(
+            "{}({})->{} det={} fuel={}",
+            self.name,
+            params.join(","),
+            self.returns.canonical_form(),
+            self.deterministic,
+            self.fuel_cost
+        )
+    }
+}
+
+impl AbiType {
+    /// Canonical text form, e.g. `list<option<i64>>`
+    #[must_use]
+    pub fn canonical_form(&self) -> String {
+        match self {
+            Self::I32 => "i32".to_string(),
+            Self::I64 => "i64".to_string(),
+            Self::F32 => "f32".to_string(),
+            Self::F64 => "f64".to_string(),
+            Self::Bool => "bool".to_string(),
+            Self::String => "string".to_string(),
+            Self::Bytes => "bytes".to_string(),
+            Self::Void => "void".to_string(),
+            Self::Option(inner) => format!("option<{}>", inner.canonical_form()),
+            Self::List(inner) => format!("list<{}>", inner.canonical_form()),
+            Self::Struct(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, ty)| format!("{}:{}", name, ty.canonical_form()))
+                    .collect();
+                format!("struct{{{}}}", fields.join(","))
+            }
+        }
+    }
+}
+
 impl AbiCall {
     /// Create a new ABI call
     #[must_use]
@@ -323,10 +432,16 @@ impl AbiCall {
         }
     }
 
-    /// Create a clock read call
+    /// Create a clock read call at tick granularity
     #[must_use]
     pub fn clock_read() -> Self {
-        Self::simple("clock_read", vec![])
+        Self::clock_read_with(ClockGranularity::Tick)
+    }
+
+    /// Create a clock read call at the given granularity
+    #[must_use]
+    pub fn clock_read_with(granularity: ClockGranularity) -> Self {
+        Self::simple("clock_read", vec![AbiValue::I64(granularity.to_abi())])
     }
 
     /// Create a log write call
@@ -382,6 +497,57 @@ impl Default for AbiContext {
     }
 }
 
+impl AbiValue {
+    /// Create an `F32` value, canonicalizing NaN
+    #[must_use]
+    pub fn from_f32(value: f32) -> Self {
+        AbiValue::F32(canonical_nan_f32(value.to_bits()))
+    }
+
+    /// Create an `F64` value, canonicalizing NaN
+    #[must_use]
+    pub fn from_f64(value: f64) -> Self {
+        AbiValue::F64(canonical_nan_f64(value.to_bits()))
+    }
+
+    /// Replace every NaN, however deeply nested, with the canonical NaN
+    ///
+    /// Guest arithmetic already yields canonical NaNs, but a guest can
+    /// reinterpret any integer as a float, so values crossing the ABI are
+    /// canonicalized too. Signed zeros are deterministic and kept.
+    #[must_use]
+    pub fn canonicalize(self) -> Self {
+        match self {
+            AbiValue::F32(bits) => AbiValue::F32(canonical_nan_f32(bits)),
+            AbiValue::F64(bits) => AbiValue::F64(canonical_nan_f64(bits)),
+            AbiValue::Option(inner) => AbiValue::Option(Box::new(inner.map(Self::canonicalize))),
+            AbiValue::List(items) => {
+                AbiValue::List(items.into_iter().map(Self::canonicalize).collect())
+            }
+            AbiValue::Struct(fields) => AbiValue::Struct(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, value.canonicalize()))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    /// Check that every NaN in the value is the canonical NaN
+    #[must_use]
+    pub fn is_canonical(&self) -> bool {
+        match self {
+            AbiValue::F32(bits) => canonical_nan_f32(*bits) == *bits,
+            AbiValue::F64(bits) => canonical_nan_f64(*bits) == *bits,
+            AbiValue::Option(inner) => inner.as_ref().as_ref().is_none_or(Self::is_canonical),
+            AbiValue::List(items) => items.iter().all(Self::is_canonical),
+            AbiValue::Struct(fields) => fields.iter().all(|(_, value)| value.is_canonical()),
+            _ => true,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -393,6 +559,27 @@ mod tests {
         assert!(abi.functions.contains_key("log_write"));
     }
 
+    #[test]
+    fn test_abi_content_hash() {
+        let abi = DeterministicAbi::new();
+        assert_eq!(abi.content_hash(), DeterministicAbi::new().content_hash());
+        assert!(abi.canonical_form().starts_with("clock_read(i64)->i64 det=true fuel=10\n"));
+
+        let mut drifted = DeterministicAbi::new();
+        drifted.functions.get_mut("log_write").unwrap().params[1] = AbiType::I64;
+        assert_eq!(drifted.version, abi.version);
+        assert_ne!(drifted.content_hash(), abi.content_hash());
+
+        assert_eq!(
+            AbiType::Struct(vec![(
+                "xs".to_string(),
+                AbiType::List(Box::new(AbiType::Option(Box::new(AbiType::I64))))
+            )])
+            .canonical_form(),
+            "struct{xs:list<option<i64>>}"
+        );
+    }
+
     #[test]
     fn test_abi_get_function() {
         let abi = DeterministicAbi::new();
@@ -486,6 +673,64 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_abi_value_canonical_nan() {
+        let payload_nan = f64::from_bits(0x7ff8_0000_0000_00ff);
+        assert_eq!(
+            AbiValue::from_f64(payload_nan),
+            AbiValue::F64(cathedral_core::float::CANONICAL_NAN_F64)
+        );
+        // Signed zeros are deterministic and pass through
+        assert_eq!(AbiValue::from_f32(-0.0), AbiValue::F32(0x8000_0000));
+
+        let nested = AbiValue::Struct(vec![(
+            "readings".to_string(),
+            AbiValue::List(vec![
+                AbiValue::F64(1.5f64.to_bits()),
+                AbiValue::Option(Box::new(Some(AbiValue::F32(0xffc0_0001)))),
+            ]),
+        )]);
+        assert!(!nested.is_canonical());
+        let canonical = nested.canonicalize();
+        assert!(canonical.is_canonical());
+        assert_eq!(canonical.clone().canonicalize(), canonical);
+
+        let mut abi = DeterministicAbi::new();
+        abi.functions.insert(
+            "scale".to_string(),
+            AbiSignature {
+                name: "scale".to_string(),
+                params: vec![AbiType::F64],
+                returns: AbiType::F64,
+                deterministic: true,
+                fuel_cost: 5,
+            },
+        );
+        let call = AbiCall::simple("scale", vec![AbiValue::F64(payload_nan.to_bits())]);
+        assert!(matches!(
+            abi.validate_call(&call),
+            Err(AbiError::DeterminismViolation(_))
+        ));
+        let call = AbiCall::simple("scale", vec![AbiValue::from_f64(payload_nan)]);
+        assert!(abi.validate_call(&call).is_ok());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_abi_value_canonicalize(bits: u64) {
+            let value = AbiValue::List(vec![
+                AbiValue::F64(bits),
+                AbiValue::F32(bits as u32),
+            ]);
+            let canonical = value.clone().canonicalize();
+            proptest::prop_assert!(canonical.is_canonical());
+            // Only NaNs change
+            if !f64::from_bits(bits).is_nan() && !f32::from_bits(bits as u32).is_nan() {
+                proptest::prop_assert_eq!(canonical, value);
+            }
+        }
+    }
+
     #[test]
     fn test_abi_default() {
         let abi = DeterministicAbi::default();
@@ -3,8 +3,10 @@
 use crate::fuel::FuelLimiter;
 use crate::memory::MemoryLimit;
 use cathedral_core::{CoreResult, CoreError, Hash};
+use cathedral_storage::{ContentAddress, ContentStore};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 /// WASM compiler for validating and compiling modules
 pub struct WasmCompiler {
@@ -153,6 +155,22 @@ impl WasmCompiler {
         Ok(wasm_bytes.to_vec())
     }
 
+    /// Compile a module to the artifact a [`CompileCache`] stores
+    ///
+    /// With the engine, this is wasmtime code as `Module::serialize` writes
+    /// it, which the sandbox loads with `Module::deserialize`. Without it,
+    /// the artifact is the validated module itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if validation or compilation fails
+    pub fn precompile(&self, wasm_bytes: &[u8]) -> CoreResult<Vec<u8>> {
+        let compiled = self.compile(wasm_bytes)?;
+        #[cfg(feature = "wasmtime")]
+        let compiled = crate::engine::precompile(&compiled)?;
+        Ok(compiled)
+    }
+
     /// Get the fuel limiter from config
     #[must_use]
     pub fn fuel_limiter(&self) -> FuelLimiter {
@@ -248,6 +266,29 @@ impl CompileConfig {
     pub fn memory_limit(&self) -> MemoryLimit {
         MemoryLimit::new(self.memory_limit)
     }
+
+    /// Hash of every setting, with allowed features sorted
+    ///
+    /// Configs allowing the same feature set hash equally regardless of
+    /// insertion order.
+    #[must_use]
+    pub fn content_hash(&self) -> Hash {
+        let mut features: Vec<String> = self
+            .allowed_features
+            .iter()
+            .map(|f| format!("{:?}", f))
+            .collect();
+        features.sort();
+        let canonical = format!(
+            "fuel={} memory={} validate={} optimize={} features={}",
+            self.max_fuel,
+            self.memory_limit,
+            self.validate,
+            self.optimize,
+            features.join(",")
+        );
+        Hash::compute(canonical.as_bytes())
+    }
 }
 
 impl Default for CompileConfig {
@@ -267,6 +308,10 @@ pub struct CompiledModule {
     pub config: CompileConfig,
     /// Module size in bytes
     pub size: usize,
+    /// Artifact from [`WasmCompiler::precompile`], if the module came from
+    /// a compile cache
+    #[serde(default)]
+    pub artifact: Option<Vec<u8>>,
 }
 
 impl CompiledModule {
@@ -275,7 +320,14 @@ impl CompiledModule {
     pub fn new(bytes: Vec<u8>, config: CompileConfig) -> Self {
         let size = bytes.len();
         let hash = Hash::compute(&bytes);
-        Self { bytes, hash, config, size }
+        Self { bytes, hash, config, size, artifact: None }
+    }
+
+    /// Attach the module's precompiled artifact
+    #[must_use]
+    pub fn with_artifact(mut self, artifact: Vec<u8>) -> Self {
+        self.artifact = Some(artifact);
+        self
     }
 
     /// Get the module hash
@@ -303,6 +355,294 @@ impl CompiledModule {
     }
 }
 
+/// Compile cache shared between sandboxes
+pub type SharedCompileCache = Arc<Mutex<CompileCache>>;
+
+/// Key of a cached artifact: the source module, the config it was compiled
+/// with and the engine it was compiled for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// Content address of the source module
+    pub module: ContentAddress,
+    /// [`CompileConfig::content_hash`] of the compile config
+    pub config: Hash,
+    /// Fingerprint of the engine settings the artifact depends on; empty
+    /// without the engine
+    pub engine: Hash,
+}
+
+impl CacheKey {
+    /// Key for compiling `wasm_bytes` under `config`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the engine cannot be created to fingerprint
+    pub fn new(wasm_bytes: &[u8], config: &CompileConfig) -> CoreResult<Self> {
+        #[cfg(feature = "wasmtime")]
+        let engine = crate::engine::fingerprint()?;
+        #[cfg(not(feature = "wasmtime"))]
+        let engine = Hash::empty();
+        Ok(Self {
+            module: ContentAddress::compute(wasm_bytes),
+            config: config.content_hash(),
+            engine,
+        })
+    }
+}
+
+/// Eviction limits for a [`CompileCache`] (0 = unlimited)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheLimits {
+    /// Maximum number of cached artifacts
+    pub max_entries: usize,
+    /// Maximum total artifact bytes
+    pub max_bytes: u64,
+}
+
+impl CacheLimits {
+    /// Create limits
+    #[must_use]
+    pub fn new(max_entries: usize, max_bytes: u64) -> Self {
+        Self { max_entries, max_bytes }
+    }
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self::new(256, 256 * 1024 * 1024) // 256 modules, 256MB
+    }
+}
+
+/// Compile cache statistics
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Lookups served from the cache
+    pub hits: u64,
+    /// Lookups that had to compile
+    pub misses: u64,
+    /// Entries evicted to stay within limits
+    pub evictions: u64,
+    /// Entries dropped because the stored artifact failed verification
+    pub verify_failures: u64,
+}
+
+/// A cached artifact
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    /// Address of the artifact blob
+    artifact: ContentAddress,
+    /// Hash of the compiled bytes, checked on load
+    hash: Hash,
+    /// Artifact size in bytes
+    size: u64,
+    /// Access tick of the last hit or insert
+    last_used: u64,
+}
+
+/// Cache of compiled modules keyed by source content address, config hash
+/// and engine fingerprint
+///
+/// Artifacts from [`WasmCompiler::precompile`] are stored as blobs in a
+/// [`ContentStore`]. Every load rehashes
+/// the blob and compares it to the hash recorded at insert, and an artifact
+/// that fails the check is dropped and recompiled. When a limit is exceeded
+/// the least recently used entries are evicted; recency is an access counter,
+/// so eviction order does not depend on wall-clock time.
+pub struct CompileCache {
+    /// Artifact storage
+    store: ContentStore,
+    /// Index from key to stored artifact
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Eviction limits
+    limits: CacheLimits,
+    /// Access counter
+    tick: u64,
+    /// Cache statistics
+    stats: CacheStats,
+}
+
+impl CompileCache {
+    /// Create an empty cache
+    #[must_use]
+    pub fn new(limits: CacheLimits) -> Self {
+        Self::with_store(ContentStore::new(), limits)
+    }
+
+    /// Create a cache storing artifacts in `store`
+    #[must_use]
+    pub fn with_store(store: ContentStore, limits: CacheLimits) -> Self {
+        Self {
+            store,
+            entries: HashMap::new(),
+            limits,
+            tick: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Wrap the cache for sharing between sandboxes
+    #[must_use]
+    pub fn shared(self) -> SharedCompileCache {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Look up the compiled module of `wasm_bytes`, with its artifact
+    ///
+    /// Returns `None` on a miss or when the stored artifact fails
+    /// verification; a failed entry is removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the artifact store cannot be read
+    pub fn get(
+        &mut self,
+        key: &CacheKey,
+        wasm_bytes: &[u8],
+        config: &CompileConfig,
+    ) -> CoreResult<Option<CompiledModule>> {
+        let Some(entry) = self.entries.get(key).cloned() else {
+            return Ok(None);
+        };
+
+        let blob = self.store.read(&entry.artifact)?;
+        if blob.verify().is_err() || Hash::compute(blob.as_bytes()) != entry.hash {
+            tracing::warn!("Compiled artifact for {} failed verification", key.module);
+            self.remove(key)?;
+            self.stats.verify_failures += 1;
+            return Ok(None);
+        }
+
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            entry.last_used = self.tick;
+        }
+        let module = CompiledModule::new(wasm_bytes.to_vec(), config.clone());
+        Ok(Some(module.with_artifact(blob.as_bytes().to_vec())))
+    }
+
+    /// Store a module's artifact, evicting entries to stay within limits
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the artifact cannot be written
+    pub fn insert(&mut self, key: CacheKey, artifact: Vec<u8>) -> CoreResult<()> {
+        self.remove(&key)?;
+        let hash = Hash::compute(&artifact);
+        let size = artifact.len() as u64;
+        let artifact = self.store.write(artifact)?;
+        self.tick += 1;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                artifact,
+                hash,
+                size,
+                last_used: self.tick,
+            },
+        );
+        self.evict()
+    }
+
+    /// Return the cached module for `wasm_bytes`, compiling it on a miss
+    ///
+    /// # Errors
+    ///
+    /// Returns error if compilation fails or the cache cannot be updated
+    pub fn get_or_compile(
+        &mut self,
+        compiler: &WasmCompiler,
+        wasm_bytes: &[u8],
+    ) -> CoreResult<CompiledModule> {
+        let key = CacheKey::new(wasm_bytes, &compiler.config)?;
+        if let Some(module) = self.get(&key, wasm_bytes, &compiler.config)? {
+            self.stats.hits += 1;
+            return Ok(module);
+        }
+
+        self.stats.misses += 1;
+        let artifact = compiler.precompile(wasm_bytes)?;
+        self.insert(key, artifact.clone())?;
+        let module = CompiledModule::new(wasm_bytes.to_vec(), compiler.config.clone());
+        Ok(module.with_artifact(artifact))
+    }
+
+    /// Check whether a key is cached
+    #[must_use]
+    pub fn contains(&self, key: &CacheKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Number of cached artifacts
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total artifact bytes
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.entries.values().map(|e| e.size).sum()
+    }
+
+    /// Eviction limits
+    #[must_use]
+    pub fn limits(&self) -> CacheLimits {
+        self.limits
+    }
+
+    /// Cache statistics
+    #[must_use]
+    pub fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+
+    /// Remove an entry, deleting its blob unless another entry shares it
+    fn remove(&mut self, key: &CacheKey) -> CoreResult<()> {
+        if let Some(entry) = self.entries.remove(key) {
+            let shared = self.entries.values().any(|e| e.artifact == entry.artifact);
+            if !shared {
+                self.store.delete(&entry.artifact)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Evict least recently used entries until within limits
+    fn evict(&mut self) -> CoreResult<()> {
+        loop {
+            let over_entries =
+                self.limits.max_entries > 0 && self.entries.len() > self.limits.max_entries;
+            let over_bytes = self.limits.max_bytes > 0 && self.size() > self.limits.max_bytes;
+            if !over_entries && !over_bytes {
+                return Ok(());
+            }
+
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            else {
+                return Ok(());
+            };
+            self.remove(&oldest)?;
+            self.stats.evictions += 1;
+        }
+    }
+}
+
+impl Default for CompileCache {
+    fn default() -> Self {
+        Self::new(CacheLimits::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -454,4 +794,111 @@ mod tests {
         let compiler = WasmCompiler::default();
         assert_eq!(compiler.config.max_fuel, 10_000_000);
     }
+
+    fn make_cache_wasm(tag: u8) -> Vec<u8> {
+        // Valid header followed by a custom section so modules differ
+        vec![0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00, 0x00, 0x02, 0x01, tag]
+    }
+
+    #[test]
+    fn test_compile_config_content_hash() {
+        let a = CompileConfig::new()
+            .with_feature(WasmFeature::Simd)
+            .with_feature(WasmFeature::TailCalls);
+        let b = CompileConfig::new()
+            .with_feature(WasmFeature::TailCalls)
+            .with_feature(WasmFeature::Simd);
+        assert_eq!(a.content_hash(), b.content_hash());
+        assert_ne!(a.content_hash(), a.clone().with_max_fuel(1).content_hash());
+    }
+
+    #[test]
+    fn test_compile_cache_hit() {
+        let mut cache = CompileCache::default();
+        let compiler = WasmCompiler::default_config();
+        let wasm = make_cache_wasm(1);
+
+        let first = cache.get_or_compile(&compiler, &wasm).unwrap();
+        let second = cache.get_or_compile(&compiler, &wasm).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cache.stats().misses, 1);
+        assert_eq!(cache.stats().hits, 1);
+
+        // A different config is a different key
+        let other = WasmCompiler::new(CompileConfig::new().with_max_fuel(1_000));
+        cache.get_or_compile(&other, &wasm).unwrap();
+        assert_eq!(cache.stats().misses, 2);
+        assert_eq!(cache.len(), 2);
+
+        assert!(cache.get_or_compile(&compiler, b"nope").is_err());
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_compile_cache_eviction() {
+        let mut cache = CompileCache::new(CacheLimits::new(2, 0));
+        let compiler = WasmCompiler::default_config();
+        let key = |tag| CacheKey::new(&make_cache_wasm(tag), &compiler.config).unwrap();
+
+        cache.get_or_compile(&compiler, &make_cache_wasm(1)).unwrap();
+        cache.get_or_compile(&compiler, &make_cache_wasm(2)).unwrap();
+        // Touch 1 so 2 is least recently used
+        cache.get_or_compile(&compiler, &make_cache_wasm(1)).unwrap();
+        cache.get_or_compile(&compiler, &make_cache_wasm(3)).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(&key(1)));
+        assert!(!cache.contains(&key(2)));
+        assert!(cache.contains(&key(3)));
+        assert_eq!(cache.stats().evictions, 1);
+
+        // Room for one artifact but not two
+        let mut cache = CompileCache::new(CacheLimits::new(0, 0));
+        cache.get_or_compile(&compiler, &make_cache_wasm(1)).unwrap();
+        let limit = cache.size() * 3 / 2;
+        let mut cache = CompileCache::new(CacheLimits::new(0, limit));
+        cache.get_or_compile(&compiler, &make_cache_wasm(1)).unwrap();
+        cache.get_or_compile(&compiler, &make_cache_wasm(2)).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.size() <= limit);
+    }
+
+    #[test]
+    fn test_compile_cache_verify_on_load() {
+        let mut cache = CompileCache::default();
+        let compiler = WasmCompiler::default_config();
+        let wasm = make_cache_wasm(1);
+        let key = CacheKey::new(&wasm, &compiler.config).unwrap();
+        cache.get_or_compile(&compiler, &wasm).unwrap();
+
+        // Point the entry at an artifact that does not match its recorded hash
+        let bogus = cache.store.write(b"tampered".to_vec()).unwrap();
+        cache.entries.get_mut(&key).unwrap().artifact = bogus;
+
+        assert!(cache.get(&key, &wasm, &compiler.config).unwrap().is_none());
+        assert_eq!(cache.stats().verify_failures, 1);
+        assert!(!cache.contains(&key));
+
+        let module = cache.get_or_compile(&compiler, &wasm).unwrap();
+        assert_eq!(module.bytes, wasm);
+        assert_eq!(cache.stats().misses, 2);
+    }
+
+    #[cfg(feature = "wasmtime")]
+    #[test]
+    fn test_compile_cache_stores_engine_code() {
+        let mut cache = CompileCache::default();
+        let compiler = WasmCompiler::default_config();
+        let wasm = make_cache_wasm(1);
+        let key = CacheKey::new(&wasm, &compiler.config).unwrap();
+        assert_ne!(key.engine, Hash::empty());
+        assert_eq!(key, CacheKey::new(&wasm, &compiler.config).unwrap());
+
+        let compiled = cache.get_or_compile(&compiler, &wasm).unwrap();
+        let cached = cache.get_or_compile(&compiler, &wasm).unwrap();
+        let artifact = cached.artifact.as_ref().unwrap();
+        assert_ne!(artifact, &wasm);
+        assert_eq!(compiled.artifact.as_ref(), Some(artifact));
+        assert_eq!(cached.bytes, wasm);
+    }
 }
@@ -0,0 +1,224 @@
+//! Deterministic in-memory table store backing the `db_*` host functions.
+
+use crate::abi::AbiValue;
+use crate::host::{HostContext, HostFunction};
+use cathedral_core::{Capability, CoreError, CoreResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Table store shared between host functions
+pub type SharedTableStore = Arc<Mutex<TableStore>>;
+
+/// In-memory key/value tables
+///
+/// Tables and rows are kept in sorted maps, so a store seeded with the same
+/// rows always iterates and serializes identically.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableStore {
+    /// Table name -> row key -> row value
+    tables: BTreeMap<String, BTreeMap<String, Vec<u8>>>,
+}
+
+impl TableStore {
+    /// Create an empty store
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seed a row before the run starts
+    #[must_use]
+    pub fn with_row(mut self, table: &str, key: &str, value: Vec<u8>) -> Self {
+        self.write(table, key, value);
+        self
+    }
+
+    /// Read a row
+    #[must_use]
+    pub fn read(&self, table: &str, key: &str) -> Option<&Vec<u8>> {
+        self.tables.get(table).and_then(|rows| rows.get(key))
+    }
+
+    /// Write a row, replacing any previous value
+    pub fn write(&mut self, table: &str, key: &str, value: Vec<u8>) {
+        self.tables
+            .entry(table.to_string())
+            .or_default()
+            .insert(key.to_string(), value);
+    }
+
+    /// Get the rows of a table in key order
+    #[must_use]
+    pub fn rows(&self, table: &str) -> Option<&BTreeMap<String, Vec<u8>>> {
+        self.tables.get(table)
+    }
+
+    /// Wrap the store for sharing with host functions
+    #[must_use]
+    pub fn shared(self) -> SharedTableStore {
+        Arc::new(Mutex::new(self))
+    }
+}
+
+/// Build the `db_read` and `db_write` host functions over `store`
+///
+/// Each call needs a `DbRead`/`DbWrite` grant listing the table it names;
+/// tables not on the allowlist are denied.
+#[must_use]
+pub fn db_functions(store: SharedTableStore) -> Vec<HostFunction> {
+    let read_store = store.clone();
+    vec![
+        HostFunction::new(
+            "db_read".to_string(),
+            vec![Capability::DbRead { tables: Vec::new() }],
+            100,
+            Arc::new(move |args, ctx| {
+                let (table, key) = table_and_key(args)?;
+                require(ctx, Capability::DbRead {
+                    tables: vec![table.to_string()],
+                })?;
+                let store = read_store.lock()?;
+                let row = store.read(table, key).cloned().map(AbiValue::Bytes);
+                Ok(AbiValue::Option(Box::new(row)))
+            }),
+        ),
+        HostFunction::new(
+            "db_write".to_string(),
+            vec![Capability::DbWrite { tables: Vec::new() }],
+            100,
+            Arc::new(move |args, ctx| {
+                let (table, key) = table_and_key(args)?;
+                let value = match args.get(2) {
+                    Some(AbiValue::Bytes(value)) => value.clone(),
+                    other => return Err(invalid_arg(2, "Bytes", other)),
+                };
+                require(ctx, Capability::DbWrite {
+                    tables: vec![table.to_string()],
+                })?;
+                store.lock()?.write(table, key, value);
+                Ok(AbiValue::I32(0))
+            }),
+        ),
+    ]
+}
+
+/// Extract the table and key arguments
+fn table_and_key(args: &[AbiValue]) -> CoreResult<(&str, &str)> {
+    match (args.first(), args.get(1)) {
+        (Some(AbiValue::String(table)), Some(AbiValue::String(key))) => Ok((table, key)),
+        (Some(AbiValue::String(_)), other) => Err(invalid_arg(1, "String", other)),
+        (other, _) => Err(invalid_arg(0, "String", other)),
+    }
+}
+
+/// Check that a granted capability covers the table being accessed
+pub(crate) fn require(ctx: &HostContext, needed: Capability) -> CoreResult<()> {
+    if ctx.covers(&needed) {
+        Ok(())
+    } else {
+        Err(CoreError::InvalidCapability {
+            reason: format!("Missing capability: {}", needed),
+        })
+    }
+}
+
+pub(crate) fn invalid_arg(
+    position: usize,
+    expected: &str,
+    actual: Option<&AbiValue>,
+) -> CoreError {
+    CoreError::Validation {
+        field: format!("args[{}]", position),
+        reason: format!("Expected {}, got {:?}", expected, actual),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::AbiCall;
+    use crate::host::{HostExecutor, HostRegistry};
+
+    async fn executor(store: &SharedTableStore, capabilities: Vec<Capability>) -> HostExecutor {
+        let registry = HostRegistry::new();
+        registry.register_db(store.clone()).await;
+        HostExecutor::new(registry)
+            .with_context(HostContext::new().with_capabilities(capabilities))
+    }
+
+    fn read(table: &str, key: &str) -> AbiCall {
+        AbiCall::simple(
+            "db_read",
+            vec![AbiValue::String(table.to_string()), AbiValue::String(key.to_string())],
+        )
+    }
+
+    #[test]
+    fn test_table_store_seeded() {
+        let a = TableStore::new()
+            .with_row("users", "b", b"2".to_vec())
+            .with_row("users", "a", b"1".to_vec());
+        let b = TableStore::new()
+            .with_row("users", "a", b"1".to_vec())
+            .with_row("users", "b", b"2".to_vec());
+        assert_eq!(a, b);
+        assert_eq!(serde_json::to_vec(&a).unwrap(), serde_json::to_vec(&b).unwrap());
+        assert_eq!(a.read("users", "a"), Some(&b"1".to_vec()));
+        assert_eq!(a.rows("users").unwrap().keys().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn test_db_read_allowlist() {
+        let store = TableStore::new()
+            .with_row("users", "alice", b"admin=false".to_vec())
+            .with_row("admin", "root", b"secret".to_vec())
+            .shared();
+        let executor = executor(&store, vec![Capability::DbRead {
+            tables: vec!["users".to_string()],
+        }])
+        .await;
+
+        for _ in 0..2 {
+            let row = executor.execute(&read("users", "alice")).await.unwrap();
+            assert_eq!(
+                row,
+                AbiValue::Option(Box::new(Some(AbiValue::Bytes(b"admin=false".to_vec()))))
+            );
+        }
+        assert_eq!(
+            executor.execute(&read("users", "bob")).await.unwrap(),
+            AbiValue::Option(Box::new(None))
+        );
+
+        let err = executor.execute(&read("admin", "root")).await.unwrap_err();
+        assert!(matches!(err, CoreError::InvalidCapability { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_db_write_allowlist() {
+        let store = TableStore::new().shared();
+        let executor = executor(&store, vec![Capability::DbWrite {
+            tables: vec!["users".to_string()],
+        }])
+        .await;
+
+        let write = |table: &str| {
+            AbiCall::simple(
+                "db_write",
+                vec![
+                    AbiValue::String(table.to_string()),
+                    AbiValue::String("carol".to_string()),
+                    AbiValue::Bytes(b"1".to_vec()),
+                ],
+            )
+        };
+        assert_eq!(executor.execute(&write("users")).await.unwrap(), AbiValue::I32(0));
+        assert!(executor.execute(&write("admin")).await.is_err());
+        assert!(executor.execute(&read("users", "carol")).await.is_err());
+
+        let store = store.lock().unwrap();
+        assert_eq!(store.read("users", "carol"), Some(&b"1".to_vec()));
+        assert!(store.rows("admin").is_none());
+    }
+}
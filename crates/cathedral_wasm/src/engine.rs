@@ -0,0 +1,611 @@
+//! Wasmtime-backed execution for the sandbox.
+//!
+//! Guest fuel is wasmtime fuel, so one unit of [`crate::fuel::FuelMeter`]
+//! fuel is one unit of wasmtime fuel. Linear memory growth is checked against
+//! [`MemoryLimit`], and every [`DeterministicAbi`] function that has a host
+//! implementation is importable from the `cathedral` module.
+//!
+//! Scalar ABI types map to their core WASM types (`bool` is an `i32`).
+//! Floats crossing the boundary in either direction have their NaNs
+//! canonicalized, matching the NaN canonicalization of guest arithmetic.
+//! `string` and `bytes` values cross the boundary through [`crate::marshal`]:
+//! parameters are a `(ptr, len)` pair into the guest's exported `memory`, and
+//! results are copied into a buffer from the guest's `alloc` export and
+//! returned as a packed [`GuestBuffer`] `i64`, or `-1` for `None`.
+//!
+//! When the sandbox enables WASI, the [`crate::wasi`] subset is linked as
+//! well and a `proc_exit(0)` counts as a successful run.
+//!
+//! Modules are compiled to wasmtime code by [`precompile`], which the
+//! compile cache stores; a run given that code loads it with
+//! [`Module::deserialize`] instead of compiling the module again.
+//!
+//! A cancelled run is stopped by epoch interruption. While a guest with a
+//! cancellation token runs, a watcher thread bumps the engine's epoch once
+//! the token trips, and the store's deadline callback traps when its own
+//! token is cancelled, so even a guest that never calls the host stops
+//! promptly. A host call made after cancellation drains the fuel and traps.
+
+use crate::abi::{AbiSignature, AbiType, AbiValue, DeterministicAbi};
+use crate::host::{HostContext, HostFunction};
+use crate::marshal::{guest_read, guest_write, GuestBuffer, ALLOC_EXPORT, NONE_BUFFER};
+use crate::memory::{MemoryLimit, MemoryRegionMap};
+use crate::sandbox::SandboxError;
+use crate::wasi::{WasiConfig, WasiState};
+use cathedral_core::float::{canonical_nan_f32, canonical_nan_f64};
+use cathedral_core::{CancellationToken, CoreError, CoreResult, Hash};
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use wasmtime::{
+    Caller, Config, Engine, Extern, FuncType, Linker, Memory, Module, ResourceLimiter, Store,
+    Trap, UpdateDeadline, Val, ValType,
+};
+
+/// Import module name for ABI host functions
+pub(crate) const IMPORT_MODULE: &str = "cathedral";
+
+/// Entry point run by [`crate::sandbox::Sandbox::execute`] when exported
+pub(crate) const DEFAULT_ENTRY: &str = "_start";
+
+/// How often a running guest's cancellation token is polled
+const CANCEL_POLL: Duration = Duration::from_millis(1);
+
+/// Outcome of running a module on the engine
+pub(crate) struct Execution {
+    /// Return value of the entry point, or the trap message
+    pub(crate) result: Result<Option<i64>, String>,
+    /// Fuel consumed by guest instructions and host calls
+    pub(crate) fuel_consumed: u64,
+    /// Peak linear memory in bytes
+    pub(crate) peak_memory: u64,
+    /// Host calls made by the guest, in call order
+    pub(crate) host_calls: Vec<String>,
+    /// Final WASI state, when WASI was linked
+    pub(crate) wasi: Option<WasiState>,
+}
+
+/// Store data visible to host functions
+struct GuestState {
+    limiter: MemoryLimiter,
+    functions: HashMap<String, HostFunction>,
+    context: HostContext,
+    /// Guest buffers allocated for host results
+    regions: MemoryRegionMap,
+    host_calls: Vec<String>,
+    /// Host error that caused the current trap
+    error: Option<String>,
+    wasi: WasiState,
+    /// Token checked on every host call
+    cancellation: Option<CancellationToken>,
+}
+
+/// Resource limiter enforcing a [`MemoryLimit`] across all linear memories
+struct MemoryLimiter {
+    max_bytes: usize,
+    current: usize,
+    peak: usize,
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        let total = self.current - current.min(self.current) + desired;
+        if total > self.max_bytes {
+            return Ok(false);
+        }
+        self.current = total;
+        self.peak = self.peak.max(total);
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Compile a module to wasmtime code, as [`Module::serialize`] writes it
+///
+/// # Errors
+///
+/// Returns error if the engine cannot be created or the module does not
+/// compile
+pub(crate) fn precompile(bytes: &[u8]) -> CoreResult<Vec<u8>> {
+    new_engine()?.precompile_module(bytes).map_err(|e| CoreError::Validation {
+        field: "wasm".to_string(),
+        reason: format!("{e:#}"),
+    })
+}
+
+/// Hash of the engine settings code from [`precompile`] depends on
+///
+/// Code is only loaded by an engine with the same fingerprint, so it is
+/// part of every compile cache key.
+///
+/// # Errors
+///
+/// Returns error if the engine cannot be created
+pub(crate) fn fingerprint() -> CoreResult<Hash> {
+    /// Collects the bytes a [`std::hash::Hash`] value feeds its hasher
+    #[derive(Default)]
+    struct Collect(Vec<u8>);
+
+    impl Hasher for Collect {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
+
+    let engine = new_engine()?;
+    let mut collect = Collect::default();
+    std::hash::Hash::hash(&engine.precompile_compatibility_hash(), &mut collect);
+    Ok(Hash::compute(&collect.0))
+}
+
+/// Load a module from its code from [`precompile`], or compile it from its
+/// bytes
+fn load(engine: &Engine, bytes: &[u8], precompiled: Option<&[u8]>) -> CoreResult<Module> {
+    if let Some(code) = precompiled {
+        // SAFETY: the code was produced by `precompile` on an engine with
+        // the same fingerprint, and the compile cache rehashes it against
+        // the hash recorded when it was produced before handing it out
+        match unsafe { Module::deserialize(engine, code) } {
+            Ok(module) => return Ok(module),
+            Err(e) => tracing::warn!("Failed to load precompiled module: {e:#}"),
+        }
+    }
+    Module::new(engine, bytes).map_err(|e| CoreError::Validation {
+        field: "wasm".to_string(),
+        reason: format!("{e:#}"),
+    })
+}
+
+/// Run `entry` of a module with the given fuel budget
+///
+/// The module is loaded from `precompiled` code when given. A missing
+/// `entry` is only an error when `required` is set; otherwise the module is
+/// instantiated (running its start function) and nothing else.
+///
+/// # Errors
+///
+/// Returns error if the engine cannot be created or the module does not
+/// compile. Traps during instantiation or the call are reported in
+/// [`Execution::result`].
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run(
+    bytes: &[u8],
+    precompiled: Option<&[u8]>,
+    entry: &str,
+    required: bool,
+    args: &[i64],
+    fuel: u64,
+    memory_limit: &MemoryLimit,
+    abi: &DeterministicAbi,
+    functions: HashMap<String, HostFunction>,
+    context: HostContext,
+    wasi: Option<WasiState>,
+    cancellation: Option<CancellationToken>,
+) -> CoreResult<Execution> {
+    let engine = new_engine()?;
+    let module = load(&engine, bytes, precompiled)?;
+
+    let state = GuestState {
+        limiter: MemoryLimiter {
+            max_bytes: usize::try_from(memory_limit.max_bytes).unwrap_or(usize::MAX),
+            current: 0,
+            peak: 0,
+        },
+        functions,
+        context,
+        regions: MemoryRegionMap::new(),
+        host_calls: Vec::new(),
+        error: None,
+        wasi: wasi.clone().unwrap_or_else(|| WasiState::new(&WasiConfig::new(), &[])),
+        cancellation,
+    };
+    let mut store = Store::new(&engine, state);
+    store.limiter(|state| &mut state.limiter);
+    store.set_fuel(fuel).map_err(engine_error)?;
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_callback(|store| {
+        if store.data().cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            return Err(Trap::Interrupt.into());
+        }
+        Ok(UpdateDeadline::Continue(1))
+    });
+
+    let linker = link(&engine, abi, &store.data().functions, wasi.is_some())?;
+    let watcher = store.data().cancellation.clone().map(|token| watch(&engine, token));
+    let result = call(&mut store, &linker, &module, entry, required, args);
+    if let Some((done, handle)) = watcher {
+        drop(done);
+        let _ = handle.join();
+    }
+
+    let remaining = store.get_fuel().map_err(engine_error)?;
+    let state = store.into_data();
+    Ok(Execution {
+        result,
+        fuel_consumed: fuel.saturating_sub(remaining),
+        peak_memory: state.limiter.peak as u64,
+        host_calls: state.host_calls,
+        wasi: wasi.is_some().then_some(state.wasi),
+    })
+}
+
+/// Bump the engine's epoch once `token` is cancelled
+///
+/// The watcher stops when the returned sender is dropped.
+fn watch(engine: &Engine, token: CancellationToken) -> (mpsc::Sender<()>, JoinHandle<()>) {
+    let engine = engine.clone();
+    let (done, finished) = mpsc::channel();
+    let handle = std::thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(CANCEL_POLL) {
+            if token.is_cancelled() {
+                engine.increment_epoch();
+                return;
+            }
+        }
+    });
+    (done, handle)
+}
+
+/// Create an engine with fuel metering, epoch interruption and
+/// deterministic float behaviour
+fn new_engine() -> CoreResult<Engine> {
+    let mut config = Config::new();
+    config
+        .consume_fuel(true)
+        .epoch_interruption(true)
+        .cranelift_nan_canonicalization(true)
+        .relaxed_simd_deterministic(true);
+    Engine::new(&config).map_err(engine_error)
+}
+
+fn engine_error(err: wasmtime::Error) -> CoreError {
+    CoreError::Validation {
+        field: "engine".to_string(),
+        reason: format!("{err:#}"),
+    }
+}
+
+/// Instantiate the module and call its entry point
+fn call(
+    store: &mut Store<GuestState>,
+    linker: &Linker<GuestState>,
+    module: &Module,
+    entry: &str,
+    required: bool,
+    args: &[i64],
+) -> Result<Option<i64>, String> {
+    let instance = match linker.instantiate(&mut *store, module) {
+        Ok(instance) => instance,
+        Err(err) => return exit_or_trap(store, &err),
+    };
+
+    let Some(func) = instance.get_func(&mut *store, entry) else {
+        if required {
+            return Err(SandboxError::ExecutionFailed(format!("no exported function `{entry}`"))
+                .to_string());
+        }
+        return Ok(None);
+    };
+
+    let ty = func.ty(&*store);
+    if ty.params().len() != args.len() {
+        return Err(SandboxError::ExecutionFailed(format!(
+            "`{entry}` takes {} arguments, got {}",
+            ty.params().len(),
+            args.len()
+        ))
+        .to_string());
+    }
+
+    let params = ty
+        .params()
+        .zip(args)
+        .map(|(param, &arg)| match param {
+            ValType::I32 => i32::try_from(arg).map(Val::I32).map_err(|_| {
+                SandboxError::ExecutionFailed(format!("argument {arg} does not fit in i32"))
+                    .to_string()
+            }),
+            ValType::I64 => Ok(Val::I64(arg)),
+            other => Err(SandboxError::ExecutionFailed(format!(
+                "unsupported parameter type {other}"
+            ))
+            .to_string()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut results = vec![Val::I32(0); ty.results().len()];
+    if let Err(err) = func.call(&mut *store, &params, &mut results) {
+        return exit_or_trap(store, &err);
+    }
+
+    Ok(match results.first() {
+        Some(Val::I32(value)) => Some(i64::from(*value)),
+        Some(Val::I64(value)) => Some(*value),
+        _ => None,
+    })
+}
+
+/// Outcome of a trap, treating a WASI `proc_exit(0)` as success
+fn exit_or_trap(
+    store: &mut Store<GuestState>,
+    err: &wasmtime::Error,
+) -> Result<Option<i64>, String> {
+    match store.data().wasi.exit_code() {
+        Some(0) => Ok(Some(0)),
+        Some(code) => {
+            Err(SandboxError::ExecutionFailed(format!("exit with code {code}")).to_string())
+        }
+        None => Err(trap_message(store, err)),
+    }
+}
+
+/// Describe a trap, preferring the host error that raised it
+fn trap_message(store: &mut Store<GuestState>, err: &wasmtime::Error) -> String {
+    if err.downcast_ref::<Trap>() == Some(&Trap::Interrupt) {
+        return SandboxError::Cancelled.to_string();
+    }
+    if let Some(message) = store.data_mut().error.take() {
+        return SandboxError::HostCallFailed(message).to_string();
+    }
+    if err.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) {
+        return SandboxError::FuelExhausted.to_string();
+    }
+    SandboxError::ExecutionFailed(format!("{err:#}")).to_string()
+}
+
+/// Define an import for every ABI function with a host implementation
+///
+/// Functions are linked in name order. Functions without an implementation
+/// (for example filesystem access in pure-compute mode) are left undefined,
+/// so a module importing them fails to instantiate.
+fn link(
+    engine: &Engine,
+    abi: &DeterministicAbi,
+    functions: &HashMap<String, HostFunction>,
+    wasi: bool,
+) -> CoreResult<Linker<GuestState>> {
+    let mut linker = Linker::new(engine);
+    if wasi {
+        crate::wasi::add_to_linker(&mut linker, |state: &mut GuestState| &mut state.wasi)
+            .map_err(engine_error)?;
+    }
+    let mut signatures: Vec<&AbiSignature> = abi
+        .functions
+        .values()
+        .filter(|sig| functions.contains_key(&sig.name))
+        .collect();
+    signatures.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for sig in signatures {
+        let Some(ty) = func_type(engine, sig) else {
+            tracing::debug!("ABI function {} has no core WASM signature", sig.name);
+            continue;
+        };
+        let sig = sig.clone();
+        linker
+            .func_new(IMPORT_MODULE, &sig.name.clone(), ty, move |caller, params, results| {
+                host_call(caller, &sig, params, results)
+            })
+            .map_err(engine_error)?;
+    }
+
+    Ok(linker)
+}
+
+/// Core WASM type of an ABI function, if it has one
+fn func_type(engine: &Engine, sig: &AbiSignature) -> Option<FuncType> {
+    let mut params = Vec::new();
+    for param in &sig.params {
+        match param {
+            AbiType::I32 | AbiType::Bool => params.push(ValType::I32),
+            AbiType::I64 => params.push(ValType::I64),
+            AbiType::F32 => params.push(ValType::F32),
+            AbiType::F64 => params.push(ValType::F64),
+            AbiType::String | AbiType::Bytes => params.extend([ValType::I32, ValType::I32]),
+            _ => return None,
+        }
+    }
+
+    let results = match &sig.returns {
+        AbiType::Void => vec![],
+        AbiType::I32 | AbiType::Bool => vec![ValType::I32],
+        AbiType::I64 | AbiType::String | AbiType::Bytes => vec![ValType::I64],
+        AbiType::F32 => vec![ValType::F32],
+        AbiType::F64 => vec![ValType::F64],
+        AbiType::Option(inner) if matches!(**inner, AbiType::String | AbiType::Bytes) => {
+            vec![ValType::I64]
+        }
+        _ => return None,
+    };
+
+    Some(FuncType::new(engine, params, results))
+}
+
+/// Dispatch a guest import to its host function
+fn host_call(
+    mut caller: Caller<'_, GuestState>,
+    sig: &AbiSignature,
+    params: &[Val],
+    results: &mut [Val],
+) -> wasmtime::Result<()> {
+    let cancelled = caller
+        .data()
+        .cancellation
+        .as_ref()
+        .is_some_and(CancellationToken::is_cancelled);
+    let outcome = if cancelled {
+        // Drain the fuel so the guest cannot run on past the trap
+        caller
+            .set_fuel(0)
+            .map_err(|e| format!("{e:#}"))
+            .and_then(|()| Err(SandboxError::Cancelled.to_string()))
+    } else {
+        decode_args(&mut caller, sig, params)
+    };
+    let outcome = outcome.and_then(|args| {
+        let fuel = caller.get_fuel().map_err(|e| format!("{e:#}"))?;
+        if fuel < sig.fuel_cost {
+            return Err(SandboxError::FuelExhausted.to_string());
+        }
+        caller
+            .set_fuel(fuel - sig.fuel_cost)
+            .map_err(|e| format!("{e:#}"))?;
+
+        let state = caller.data_mut();
+        let func = state
+            .functions
+            .get(&sig.name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown host function: {}", sig.name))?;
+        let value = func
+            .call(&args, &mut state.context)
+            .map_err(|e| e.to_string())?;
+        state.host_calls.push(sig.name.clone());
+        encode_result(&mut caller, sig, &value, results)
+    });
+
+    outcome.map_err(|message| {
+        let error = wasmtime::Error::msg(message.clone());
+        caller.data_mut().error = Some(message);
+        error
+    })
+}
+
+/// Convert guest parameters into ABI values
+fn decode_args(
+    caller: &mut Caller<'_, GuestState>,
+    sig: &AbiSignature,
+    params: &[Val],
+) -> Result<Vec<AbiValue>, String> {
+    let mut params = params.iter();
+    let mut next = || params.next().cloned().ok_or_else(|| "missing argument".to_string());
+    let mut args = Vec::with_capacity(sig.params.len());
+
+    for param in &sig.params {
+        let value = match (param, next()?) {
+            (AbiType::I32, Val::I32(v)) => AbiValue::I32(v),
+            (AbiType::Bool, Val::I32(v)) => AbiValue::Bool(v != 0),
+            (AbiType::I64, Val::I64(v)) => AbiValue::I64(v),
+            (AbiType::F32, Val::F32(bits)) => AbiValue::F32(canonical_nan_f32(bits)),
+            (AbiType::F64, Val::F64(bits)) => AbiValue::F64(canonical_nan_f64(bits)),
+            (AbiType::String | AbiType::Bytes, Val::I32(ptr)) => {
+                let Val::I32(len) = next()? else {
+                    return Err("expected i32 length".to_string());
+                };
+                let memory = guest_memory(caller)?;
+                let bytes = guest_read(memory.data(&*caller), offset(ptr)?, offset(len)?)
+                    .map_err(|e| e.to_string())?;
+                if *param == AbiType::Bytes {
+                    AbiValue::Bytes(bytes)
+                } else {
+                    AbiValue::String(String::from_utf8(bytes).map_err(|e| e.to_string())?)
+                }
+            }
+            (expected, actual) => {
+                return Err(format!("expected {expected:?}, got {:?}", actual.ty(&*caller)));
+            }
+        };
+        args.push(value);
+    }
+
+    Ok(args)
+}
+
+/// Write a host result into the guest's result slots
+fn encode_result(
+    caller: &mut Caller<'_, GuestState>,
+    sig: &AbiSignature,
+    value: &AbiValue,
+    results: &mut [Val],
+) -> Result<(), String> {
+    let encoded = match value {
+        AbiValue::Void => None,
+        AbiValue::I32(v) => Some(Val::I32(*v)),
+        AbiValue::Bool(v) => Some(Val::I32(i32::from(*v))),
+        AbiValue::I64(v) => Some(Val::I64(*v)),
+        AbiValue::F32(bits) => Some(Val::F32(canonical_nan_f32(*bits))),
+        AbiValue::F64(bits) => Some(Val::F64(canonical_nan_f64(*bits))),
+        AbiValue::String(s) => Some(write_buffer(caller, sig, s.as_bytes())?),
+        AbiValue::Bytes(bytes) => Some(write_buffer(caller, sig, bytes)?),
+        AbiValue::Option(inner) => match inner.as_ref() {
+            None => Some(Val::I64(NONE_BUFFER)),
+            Some(AbiValue::String(s)) => Some(write_buffer(caller, sig, s.as_bytes())?),
+            Some(AbiValue::Bytes(bytes)) => Some(write_buffer(caller, sig, bytes)?),
+            Some(other) => return Err(format!("unsupported host result {other:?}")),
+        },
+        other => return Err(format!("unsupported host result {other:?}")),
+    };
+
+    match (encoded, results.first_mut()) {
+        (Some(val), Some(slot)) => *slot = val,
+        (None, None) => {}
+        _ => return Err("host result does not match the import signature".to_string()),
+    }
+    Ok(())
+}
+
+/// Copy `bytes` into a buffer allocated by the guest
+///
+/// Any earlier result regions the new buffer overlaps are dropped, since the
+/// guest can only hand them out again after freeing them.
+fn write_buffer(
+    caller: &mut Caller<'_, GuestState>,
+    sig: &AbiSignature,
+    bytes: &[u8],
+) -> Result<Val, String> {
+    let alloc = caller
+        .get_export(ALLOC_EXPORT)
+        .and_then(Extern::into_func)
+        .ok_or_else(|| format!("guest exports no `{ALLOC_EXPORT}` function"))?
+        .typed::<i32, i32>(&*caller)
+        .map_err(|e| format!("{e:#}"))?;
+    let len = i32::try_from(bytes.len()).map_err(|_| format!("{} bytes", bytes.len()))?;
+    let ptr = alloc.call(&mut *caller, len).map_err(|e| format!("{e:#}"))?;
+    let buffer = GuestBuffer::new(
+        u32::try_from(ptr).map_err(|_| format!("`{ALLOC_EXPORT}` returned {ptr}"))?,
+        len.unsigned_abs(),
+    );
+
+    let memory = guest_memory(caller)?;
+    let (data, state) = memory.data_and_store_mut(&mut *caller);
+    state.regions.remove_overlapping(u64::from(buffer.ptr), buffer.end());
+    state
+        .regions
+        .add_region(buffer.region(format!("{}:result", sig.name)))
+        .map_err(|e| e.to_string())?;
+    guest_write(data, &state.regions, u64::from(buffer.ptr), bytes).map_err(|e| e.to_string())?;
+
+    Ok(Val::I64(buffer.pack()))
+}
+
+/// The guest's exported linear memory
+fn guest_memory(caller: &mut Caller<'_, GuestState>) -> Result<Memory, String> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| "guest exports no memory".to_string())
+}
+
+/// Interpret a guest `i32` as an address or length
+fn offset(value: i32) -> Result<u64, String> {
+    u64::try_from(value).map_err(|_| format!("invalid offset {value}"))
+}
@@ -3,17 +3,25 @@
 use crate::abi::{AbiCall, AbiValue};
 use crate::fuel::FuelMeter;
 use crate::memory::MemoryLimit;
-use cathedral_core::{Capability, CoreResult, EventId, NodeId};
+use cathedral_core::{
+    Capability, CoreError, CoreResult, EventId, Hash, LogicalTime, NodeId, RunId,
+};
+use cathedral_log::{Event, EventKind};
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 
 /// Host function that can be called from WASM
 pub type HostFn = Arc<dyn Fn(&[AbiValue], &mut HostContext) -> CoreResult<AbiValue> + Send + Sync>;
 
 /// Context for host function execution
+///
+/// Serialization is canonical: fields are written in declaration order and
+/// capabilities are sorted and deduplicated, so equivalent contexts produce
+/// identical bytes and [`HostContext::content_hash`] values.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct HostContext {
     /// Node ID making the call
@@ -23,6 +31,7 @@ pub struct HostContext {
     /// Timestamp of the call
     pub timestamp: u64,
     /// Available capabilities
+    #[serde(serialize_with = "serialize_sorted_capabilities")]
     pub capabilities: Vec<Capability>,
     /// Memory limit for the guest
     pub memory_limit: MemoryLimit,
@@ -65,13 +74,32 @@ impl HostContext {
         self
     }
 
-    /// Set capabilities
+    /// Set capabilities, stored sorted and deduplicated
     #[must_use]
     pub fn with_capabilities(mut self, capabilities: Vec<Capability>) -> Self {
         self.capabilities = capabilities;
+        self.canonicalize();
         self
     }
 
+    /// Sort and deduplicate capabilities in place
+    pub fn canonicalize(&mut self) {
+        self.capabilities.sort();
+        self.capabilities.dedup();
+    }
+
+    /// Hash of the canonical serialized form
+    ///
+    /// # Errors
+    ///
+    /// Returns error if serialization fails
+    pub fn content_hash(&self) -> CoreResult<Hash> {
+        let bytes = serde_json::to_vec(self).map_err(|e| CoreError::ParseError {
+            message: format!("Failed to serialize host context: {}", e),
+        })?;
+        Ok(Hash::compute(&bytes))
+    }
+
     /// Set memory limit
     #[must_use]
     pub fn with_memory_limit(mut self, limit: MemoryLimit) -> Self {
@@ -92,6 +120,12 @@ impl HostContext {
         self.capabilities.contains(cap)
     }
 
+    /// Check if any granted capability covers `cap`
+    #[must_use]
+    pub fn covers(&self, cap: &Capability) -> bool {
+        self.capabilities.iter().any(|granted| granted.covers(cap))
+    }
+
     /// Consume fuel if meter is present
     ///
     /// # Errors
@@ -111,6 +145,100 @@ impl Default for HostContext {
     }
 }
 
+/// Serialize capabilities in sorted order without duplicates
+fn serialize_sorted_capabilities<S: Serializer>(
+    capabilities: &[Capability],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(capabilities.iter().collect::<BTreeSet<_>>())
+}
+
+/// Logical ticks per logical second
+pub const TICKS_PER_SECOND: u64 = 1000;
+
+/// Granularity at which `clock_read` reports logical time
+///
+/// Readings are floored to a multiple of the granularity's quantum, so
+/// the same logical timestamp always yields the same value. Guests select
+/// a granularity with a single `I64` argument: `0` for raw ticks, a
+/// positive count for that many ticks, `-1` for seconds, `-2` for minutes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockGranularity {
+    /// Raw logical ticks
+    #[default]
+    Tick,
+    /// Fixed number of ticks
+    Ticks(u64),
+    /// Logical seconds
+    Seconds,
+    /// Logical minutes
+    Minutes,
+}
+
+impl ClockGranularity {
+    /// Number of ticks in one quantum
+    #[must_use]
+    pub fn quantum(&self) -> u64 {
+        match self {
+            ClockGranularity::Tick => 1,
+            ClockGranularity::Ticks(n) => (*n).max(1),
+            ClockGranularity::Seconds => TICKS_PER_SECOND,
+            ClockGranularity::Minutes => 60 * TICKS_PER_SECOND,
+        }
+    }
+
+    /// Quantize a logical timestamp, rounding down
+    #[must_use]
+    pub fn quantize(&self, ticks: u64) -> u64 {
+        ticks - ticks % self.quantum()
+    }
+
+    /// Encode as the `clock_read` argument
+    #[must_use]
+    pub fn to_abi(&self) -> i64 {
+        match self {
+            ClockGranularity::Tick => 0,
+            ClockGranularity::Ticks(n) => i64::try_from(*n).unwrap_or(i64::MAX),
+            ClockGranularity::Seconds => -1,
+            ClockGranularity::Minutes => -2,
+        }
+    }
+
+    /// Decode a `clock_read` argument
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the code is not a known granularity
+    pub fn from_abi(code: i64) -> CoreResult<Self> {
+        match code {
+            0 => Ok(ClockGranularity::Tick),
+            -1 => Ok(ClockGranularity::Seconds),
+            -2 => Ok(ClockGranularity::Minutes),
+            n if n > 0 => Ok(ClockGranularity::Ticks(n.unsigned_abs())),
+            n => Err(CoreError::Validation {
+                field: "granularity".to_string(),
+                reason: format!("Unknown clock granularity code: {}", n),
+            }),
+        }
+    }
+
+    /// Decode granularity from `clock_read` arguments, defaulting to ticks
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the argument is not a known granularity code
+    pub fn from_args(args: &[AbiValue]) -> CoreResult<Self> {
+        match args.first() {
+            None => Ok(ClockGranularity::Tick),
+            Some(AbiValue::I64(code)) => Self::from_abi(*code),
+            Some(other) => Err(CoreError::Validation {
+                field: "granularity".to_string(),
+                reason: format!("Expected I64 granularity, got {:?}", other),
+            }),
+        }
+    }
+}
+
 /// Host function definition
 #[derive(Clone)]
 pub struct HostFunction {
@@ -161,6 +289,22 @@ impl HostFunction {
         }
     }
 
+    /// Check that the function needs no filesystem, network, or database access
+    #[must_use]
+    pub fn is_pure(&self) -> bool {
+        !self.required_capabilities.iter().any(|cap| {
+            matches!(
+                cap,
+                Capability::FsRead { .. }
+                    | Capability::FsWrite { .. }
+                    | Capability::NetRead { .. }
+                    | Capability::NetWrite { .. }
+                    | Capability::DbRead { .. }
+                    | Capability::DbWrite { .. }
+            )
+        })
+    }
+
     /// Call the host function
     ///
     /// # Errors
@@ -169,7 +313,7 @@ impl HostFunction {
     pub fn call(&self, args: &[AbiValue], ctx: &mut HostContext) -> CoreResult<AbiValue> {
         // Check capabilities
         for cap in &self.required_capabilities {
-            if !ctx.has_capability(cap) {
+            if !ctx.covers(cap) {
                 return Err(cathedral_core::CoreError::InvalidCapability {
                     reason: format!("Missing capability: {:?}", cap),
                 });
@@ -231,20 +375,84 @@ impl HostRegistry {
     /// Create a registry with standard cathedral host functions
     pub async fn with_standard_functions() -> Self {
         let registry = Self::new();
-
-        // Clock read function
+        for func in Self::standard_functions() {
+            registry.register(func).await;
+        }
         registry
-            .register(HostFunction::new(
+    }
+
+    /// Create a registry for pure-compute runs
+    ///
+    /// Only pure standard functions are registered; no filesystem or
+    /// network functions exist, so any call to one fails as unknown.
+    #[must_use]
+    pub fn pure_compute() -> Self {
+        let functions = Self::standard_functions()
+            .into_iter()
+            .filter(HostFunction::is_pure)
+            .map(|func| (func.name.clone(), func))
+            .collect();
+        Self {
+            functions: Arc::new(RwLock::new(functions)),
+        }
+    }
+
+    /// Register the `db_read`/`db_write` functions backed by `store`
+    pub async fn register_db(&self, store: crate::db::SharedTableStore) {
+        for func in crate::db::db_functions(store) {
+            self.register(func).await;
+        }
+    }
+
+    /// Register the `gen_id` function backed by `generator`
+    pub async fn register_id_gen(&self, generator: crate::idgen::SharedIdGenerator) {
+        self.register(crate::idgen::gen_id_function(generator)).await;
+    }
+
+    /// Check that every registered function is pure
+    pub async fn is_pure(&self) -> bool {
+        self.functions.read().await.values().all(HostFunction::is_pure)
+    }
+
+    /// Copy the registry, replacing each implementation with `wrap(func)`
+    ///
+    /// The copy keeps each function's capabilities and fuel cost.
+    async fn wrapped(&self, wrap: impl Fn(&HostFunction) -> HostFn) -> Self {
+        let functions = self
+            .functions
+            .read()
+            .await
+            .iter()
+            .map(|(name, func)| {
+                let mut wrapped = func.clone();
+                wrapped.implementation = wrap(func);
+                (name.clone(), wrapped)
+            })
+            .collect();
+        Self {
+            functions: Arc::new(RwLock::new(functions)),
+        }
+    }
+
+    /// Standard cathedral host functions
+    ///
+    /// Includes `fs_read` and `net_http`, which are impure and left out of
+    /// [`HostRegistry::pure_compute`] registries.
+    fn standard_functions() -> Vec<HostFunction> {
+        let mut functions = vec![
+            // Clock read function
+            HostFunction::new(
                 "clock_read".to_string(),
                 vec![Capability::ClockRead],
                 10,
-                Arc::new(|_args, _ctx| Ok(AbiValue::I64(0))),
-            ))
-            .await;
-
-        // Log write function
-        registry
-            .register(HostFunction::new(
+                Arc::new(|args, ctx| {
+                    let granularity = ClockGranularity::from_args(args)?;
+                    let ticks = granularity.quantize(ctx.timestamp);
+                    Ok(AbiValue::I64(i64::try_from(ticks).unwrap_or(i64::MAX)))
+                }),
+            ),
+            // Log write function
+            HostFunction::new(
                 "log_write".to_string(),
                 vec![],
                 50,
@@ -256,12 +464,9 @@ impl HostRegistry {
                     }
                     Ok(AbiValue::I32(0))
                 }),
-            ))
-            .await;
-
-        // Has capability check
-        registry
-            .register(HostFunction::new(
+            ),
+            // Has capability check
+            HostFunction::new(
                 "has_capability".to_string(),
                 vec![],
                 20,
@@ -278,10 +483,10 @@ impl HostRegistry {
                     }
                     Ok(AbiValue::Bool(false))
                 }),
-            ))
-            .await;
-
-        registry
+            ),
+        ];
+        functions.extend(crate::io::io_functions());
+        functions
     }
 }
 
@@ -291,6 +496,312 @@ impl Default for HostRegistry {
     }
 }
 
+/// A host call made by a guest, with its outcome
+///
+/// Recorded during a live run so that a replay can answer the same calls
+/// with the same results instead of touching the clock, filesystem or
+/// network again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostCallRecord {
+    /// Position of the call among the node's host calls
+    pub sequence: u64,
+    /// Host function name
+    pub function: String,
+    /// Arguments passed by the guest
+    pub args: Vec<AbiValue>,
+    /// Value returned, or the message of the error the call failed with
+    pub result: Result<AbiValue, String>,
+    /// Logical timestamp the call was made at
+    pub timestamp: u64,
+}
+
+impl HostCallRecord {
+    /// Encode the record as a `HostCall` event of `node_id` in `run_id`
+    ///
+    /// The event ID is derived from the run, node and sequence, so
+    /// recording the same calls again yields the same events.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the record cannot be encoded
+    pub fn to_event(&self, run_id: RunId, node_id: NodeId) -> CoreResult<Event> {
+        Ok(Event::new(
+            self.event_id(run_id, node_id),
+            run_id,
+            node_id,
+            LogicalTime::from_raw(self.timestamp),
+            EventKind::HostCall,
+        )
+        .with_payload(serde_json::to_vec(self)?))
+    }
+
+    /// ID of the event recording this call of `node_id` in `run_id`
+    #[must_use]
+    pub fn event_id(&self, run_id: RunId, node_id: NodeId) -> EventId {
+        let mut seed = Vec::with_capacity(40);
+        seed.extend_from_slice(run_id.as_bytes());
+        seed.extend_from_slice(node_id.as_bytes());
+        seed.extend_from_slice(&self.sequence.to_le_bytes());
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&Hash::compute(&seed).as_bytes()[..16]);
+        EventId::from_bytes(bytes)
+    }
+
+    /// Decode the record carried by a `HostCall` event
+    #[must_use]
+    pub fn from_event(event: &Event) -> Option<Self> {
+        if event.kind != EventKind::HostCall {
+            return None;
+        }
+        serde_json::from_slice(&event.payload).ok()
+    }
+
+    /// Check that a call is the one this record describes
+    #[must_use]
+    pub fn matches(&self, function: &str, args: &[AbiValue]) -> bool {
+        self.function == function && self.args == args
+    }
+
+    /// Replay the recorded outcome
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the recorded call failed
+    pub fn outcome(&self) -> CoreResult<AbiValue> {
+        self.result.clone().map_err(|reason| CoreError::Validation {
+            field: self.function.clone(),
+            reason,
+        })
+    }
+}
+
+/// Lock shared host call state, failing the call if the lock is poisoned
+fn lock<T>(state: &Mutex<T>) -> CoreResult<std::sync::MutexGuard<'_, T>> {
+    state.lock().map_err(|_| CoreError::Validation {
+        field: "host_calls".to_string(),
+        reason: "Host call log lock poisoned".to_string(),
+    })
+}
+
+/// Records every host call a guest makes, with its result
+///
+/// [`HostCallRecorder::instrument`] wraps a registry so that each call is
+/// forwarded to the real implementation and logged. Calls refused for a
+/// missing capability or lack of fuel never reach the implementation and
+/// are not recorded; they fail the same way on replay.
+#[derive(Debug, Clone, Default)]
+pub struct HostCallRecorder {
+    /// Calls recorded so far, in call order
+    records: Arc<Mutex<Vec<HostCallRecord>>>,
+}
+
+impl HostCallRecorder {
+    /// Create an empty recorder
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy `registry` with every function recording into this recorder
+    ///
+    /// Functions registered with `registry` afterwards are not recorded.
+    pub async fn instrument(&self, registry: &HostRegistry) -> HostRegistry {
+        registry
+            .wrapped(|func| {
+                let records = Arc::clone(&self.records);
+                let name = func.name.clone();
+                let inner = Arc::clone(&func.implementation);
+                Arc::new(move |args, ctx| {
+                    let result = inner(args, ctx);
+                    let mut records = lock(&records)?;
+                    let sequence = records.len() as u64;
+                    records.push(HostCallRecord {
+                        sequence,
+                        function: name.clone(),
+                        args: args.to_vec(),
+                        result: result.as_ref().cloned().map_err(ToString::to_string),
+                        timestamp: ctx.timestamp,
+                    });
+                    result
+                })
+            })
+            .await
+    }
+
+    /// Calls recorded so far, in call order
+    #[must_use]
+    pub fn records(&self) -> Vec<HostCallRecord> {
+        self.records.lock().map(|records| records.clone()).unwrap_or_default()
+    }
+
+    /// Encode the recorded calls as `HostCall` events for the event log
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a record cannot be encoded
+    pub fn events(&self, run_id: RunId, node_id: NodeId) -> CoreResult<Vec<Event>> {
+        self.records()
+            .iter()
+            .map(|record| record.to_event(run_id, node_id))
+            .collect()
+    }
+}
+
+/// A replayed host call that departed from the recorded sequence
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostCallDivergence {
+    /// Position of the call among the node's host calls
+    pub sequence: u64,
+    /// Call recorded at this position, if the recording had not run out
+    pub expected: Option<HostCallRecord>,
+    /// Function the guest called
+    pub function: String,
+    /// Arguments the guest passed
+    pub args: Vec<AbiValue>,
+}
+
+impl fmt::Display for HostCallDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.expected {
+            Some(expected) => write!(
+                f,
+                "host call {}: expected {}({:?}), got {}({:?})",
+                self.sequence, expected.function, expected.args, self.function, self.args
+            ),
+            None => write!(
+                f,
+                "host call {}: {}({:?}) was never recorded",
+                self.sequence, self.function, self.args
+            ),
+        }
+    }
+}
+
+/// Replay cursor over a recording
+#[derive(Debug, Default)]
+struct ReplayCursor {
+    /// Recorded calls, in call order
+    records: Vec<HostCallRecord>,
+    /// Index of the next call to serve
+    position: usize,
+    /// Calls that departed from the recording
+    divergences: Vec<HostCallDivergence>,
+}
+
+/// Serves recorded host call results during replay
+///
+/// Each call is matched against the next recorded call; a match returns
+/// the recorded result without running the function. A call whose name
+/// or arguments differ is flagged as a divergence and fails, so the guest
+/// traps instead of continuing on answers meant for another call.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayHostRegistry {
+    /// Shared replay position
+    cursor: Arc<Mutex<ReplayCursor>>,
+}
+
+impl ReplayHostRegistry {
+    /// Replay `records`, ordered by sequence
+    #[must_use]
+    pub fn new(mut records: Vec<HostCallRecord>) -> Self {
+        records.sort_by_key(|record| record.sequence);
+        Self {
+            cursor: Arc::new(Mutex::new(ReplayCursor {
+                records,
+                ..ReplayCursor::default()
+            })),
+        }
+    }
+
+    /// Replay the `HostCall` events of a node's event log
+    #[must_use]
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a Event>) -> Self {
+        Self::new(events.into_iter().filter_map(HostCallRecord::from_event).collect())
+    }
+
+    /// Copy `registry` with every function answered from the recording
+    ///
+    /// Capabilities and fuel costs come from `registry`, so replayed calls
+    /// are gated and metered exactly as they were live.
+    pub async fn instrument(&self, registry: &HostRegistry) -> HostRegistry {
+        registry
+            .wrapped(|func| {
+                let cursor = Arc::clone(&self.cursor);
+                let name = func.name.clone();
+                Arc::new(move |args, _ctx| {
+                    let mut cursor = lock(&cursor)?;
+                    let sequence = cursor.position as u64;
+                    let expected = cursor.records.get(cursor.position).cloned();
+                    match expected {
+                        Some(record) if record.matches(&name, args) => {
+                            cursor.position += 1;
+                            record.outcome()
+                        }
+                        expected => {
+                            let divergence = HostCallDivergence {
+                                sequence,
+                                expected,
+                                function: name.clone(),
+                                args: args.to_vec(),
+                            };
+                            let reason = divergence.to_string();
+                            cursor.divergences.push(divergence);
+                            Err(CoreError::Validation {
+                                field: "host_call".to_string(),
+                                reason,
+                            })
+                        }
+                    }
+                })
+            })
+            .await
+    }
+
+    /// Calls that departed from the recording
+    #[must_use]
+    pub fn divergences(&self) -> Vec<HostCallDivergence> {
+        self.cursor
+            .lock()
+            .map(|cursor| cursor.divergences.clone())
+            .unwrap_or_default()
+    }
+
+    /// Number of recorded calls not yet replayed
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.cursor
+            .lock()
+            .map(|cursor| cursor.records.len() - cursor.position)
+            .unwrap_or_default()
+    }
+
+    /// Check that the replay made exactly the recorded calls
+    ///
+    /// # Errors
+    ///
+    /// Returns error on the first divergence, or if recorded calls were
+    /// never made
+    pub fn verify(&self) -> CoreResult<()> {
+        let cursor = lock(&self.cursor)?;
+        if let Some(divergence) = cursor.divergences.first() {
+            return Err(CoreError::Validation {
+                field: "host_call".to_string(),
+                reason: divergence.to_string(),
+            });
+        }
+        if let Some(missed) = cursor.records.get(cursor.position) {
+            return Err(CoreError::Validation {
+                field: "host_call".to_string(),
+                reason: format!(
+                    "host call {}: {}({:?}) was recorded but never made",
+                    missed.sequence, missed.function, missed.args
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
 /// Async host function trait
 #[async_trait]
 pub trait AsyncHostFunction: Send + Sync {
@@ -389,6 +900,34 @@ mod tests {
         assert!(ctx.has_capability(&Capability::ClockRead));
     }
 
+    #[test]
+    fn test_host_context_canonical_serialization() {
+        let node_id = NodeId::new();
+        let fs = Capability::FsRead {
+            prefixes: vec!["./data".to_string()],
+        };
+
+        let a = HostContext::new()
+            .with_node(node_id)
+            .with_capabilities(vec![fs.clone(), Capability::ClockRead]);
+        let mut b = HostContext::new().with_node(node_id);
+        b.capabilities.push(Capability::ClockRead);
+        b.capabilities.push(fs.clone());
+        b.capabilities.push(Capability::ClockRead);
+
+        assert_eq!(
+            serde_json::to_vec(&a).unwrap(),
+            serde_json::to_vec(&b).unwrap()
+        );
+        assert_eq!(a.content_hash().unwrap(), b.content_hash().unwrap());
+
+        b.canonicalize();
+        assert_eq!(a, b);
+
+        let c = a.clone().with_timestamp(1);
+        assert_ne!(a.content_hash().unwrap(), c.content_hash().unwrap());
+    }
+
     #[test]
     fn test_host_context_consume_fuel() {
         let mut ctx = HostContext::new().with_fuel_meter(FuelMeter::new(100));
@@ -473,6 +1012,189 @@ mod tests {
         assert!(matches!(result, AbiValue::I64(_)));
     }
 
+    #[tokio::test]
+    async fn test_host_clock_read_granularity() {
+        let executor = HostExecutor::with_standard()
+            .await
+            .with_context(HostContext::new().with_capabilities(vec![
+                Capability::ClockRead,
+            ]));
+
+        let mut call = AbiCall::clock_read_with(ClockGranularity::Seconds);
+        call.context.timestamp = 12_345;
+        assert_eq!(executor.execute(&call).await.unwrap(), AbiValue::I64(12_000));
+        // Any time within the same second reads the same value
+        call.context.timestamp = 12_999;
+        assert_eq!(executor.execute(&call).await.unwrap(), AbiValue::I64(12_000));
+
+        let mut call = AbiCall::clock_read_with(ClockGranularity::Ticks(100));
+        call.context.timestamp = 12_345;
+        assert_eq!(executor.execute(&call).await.unwrap(), AbiValue::I64(12_300));
+
+        let mut call = AbiCall::clock_read();
+        call.context.timestamp = 12_345;
+        assert_eq!(executor.execute(&call).await.unwrap(), AbiValue::I64(12_345));
+
+        let bad = AbiCall::simple("clock_read", vec![AbiValue::I64(-9)]);
+        assert!(executor.execute(&bad).await.is_err());
+
+        // Still gated by ClockRead
+        let ungated = HostExecutor::with_standard().await;
+        assert!(ungated.execute(&call).await.is_err());
+    }
+
+    #[test]
+    fn test_clock_granularity_abi_round_trip() {
+        for granularity in [
+            ClockGranularity::Tick,
+            ClockGranularity::Ticks(250),
+            ClockGranularity::Seconds,
+            ClockGranularity::Minutes,
+        ] {
+            assert_eq!(
+                ClockGranularity::from_abi(granularity.to_abi()).unwrap(),
+                granularity
+            );
+        }
+        assert_eq!(ClockGranularity::Minutes.quantize(125_000), 120_000);
+    }
+
+    #[tokio::test]
+    async fn test_host_registry_pure_compute() {
+        let registry = HostRegistry::pure_compute();
+        assert!(registry.is_pure().await);
+        assert!(registry.has("clock_read").await);
+        assert!(!registry.has("fs_read").await);
+
+        registry
+            .register(HostFunction::new(
+                "fs_read".to_string(),
+                vec![Capability::FsRead {
+                    prefixes: vec!["./data".to_string()],
+                }],
+                100,
+                Arc::new(|_args, _ctx| Ok(AbiValue::Bytes(Vec::new()))),
+            ))
+            .await;
+        assert!(!registry.is_pure().await);
+    }
+
+    /// Registry whose `clock_read` answers with the live clock's next reading
+    fn ticking_registry() -> HostRegistry {
+        let ticks = Arc::new(Mutex::new(0i64));
+        let func = HostFunction::new(
+            "clock_read".to_string(),
+            vec![Capability::ClockRead],
+            10,
+            Arc::new(move |_args, _ctx| {
+                let mut ticks = ticks.lock().unwrap();
+                *ticks += 7;
+                Ok(AbiValue::I64(*ticks))
+            }),
+        );
+        let refusing = HostFunction::new(
+            "fs_read".to_string(),
+            vec![],
+            100,
+            Arc::new(|_args, _ctx| {
+                Err(CoreError::NotFound {
+                    kind: "file".to_string(),
+                    id: "./data/input".to_string(),
+                })
+            }),
+        );
+        let functions = [func, refusing]
+            .into_iter()
+            .map(|func| (func.name.clone(), func))
+            .collect();
+        HostRegistry {
+            functions: Arc::new(RwLock::new(functions)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_host_call_record_and_replay() {
+        let ctx = HostContext::new().with_capabilities(vec![Capability::ClockRead]);
+        let recorder = HostCallRecorder::new();
+        let live = recorder.instrument(&ticking_registry()).await;
+        let clock = live.get("clock_read").await.unwrap();
+        let fs = live.get("fs_read").await.unwrap();
+
+        let mut live_ctx = ctx.clone().with_timestamp(5);
+        assert_eq!(clock.call(&[], &mut live_ctx).unwrap(), AbiValue::I64(7));
+        assert!(fs.call(&[AbiValue::String("input".to_string())], &mut live_ctx).is_err());
+        assert_eq!(clock.call(&[], &mut live_ctx).unwrap(), AbiValue::I64(14));
+
+        let records = recorder.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].function, "fs_read");
+        assert!(records[1].result.is_err());
+        assert_eq!(records[2].timestamp, 5);
+
+        // Recorded calls round trip through the event log
+        let run_id = RunId::new();
+        let node_id = NodeId::new();
+        let events = recorder.events(run_id, node_id).unwrap();
+        assert!(events.iter().all(|event| event.kind == EventKind::HostCall));
+        assert_eq!(HostCallRecord::from_event(&events[2]), Some(records[2].clone()));
+
+        // Event IDs derive from run, node and sequence
+        let again = recorder.events(run_id, node_id).unwrap();
+        assert_eq!(again[2].event_id, events[2].event_id);
+        assert_ne!(events[1].event_id, events[2].event_id);
+        assert_ne!(recorder.events(RunId::new(), node_id).unwrap()[2].event_id, events[2].event_id);
+
+        // Replay serves the recorded answers, not a fresh clock's
+        let replay = ReplayHostRegistry::from_events(events.iter().rev());
+        let replayed = replay.instrument(&ticking_registry()).await;
+        let clock = replayed.get("clock_read").await.unwrap();
+        let fs = replayed.get("fs_read").await.unwrap();
+        let mut replay_ctx = ctx.clone();
+        assert_eq!(clock.call(&[], &mut replay_ctx).unwrap(), AbiValue::I64(7));
+        assert_eq!(replay.remaining(), 2);
+        assert!(replay.verify().is_err());
+        assert!(fs.call(&[AbiValue::String("input".to_string())], &mut replay_ctx).is_err());
+        assert_eq!(clock.call(&[], &mut replay_ctx).unwrap(), AbiValue::I64(14));
+        assert!(replay.divergences().is_empty());
+        replay.verify().unwrap();
+
+        // Replayed calls are still gated by capability
+        let mut ungated = HostContext::new();
+        assert!(clock.call(&[], &mut ungated).is_err());
+        assert!(replay.divergences().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_host_call_replay_divergence() {
+        let ctx = HostContext::new().with_capabilities(vec![Capability::ClockRead]);
+        let recorder = HostCallRecorder::new();
+        let live = recorder.instrument(&ticking_registry()).await;
+        let clock = live.get("clock_read").await.unwrap();
+        clock.call(&[], &mut ctx.clone()).unwrap();
+
+        let replay = ReplayHostRegistry::new(recorder.records());
+        let replayed = replay.instrument(&ticking_registry()).await;
+
+        // Different arguments than recorded
+        let clock = replayed.get("clock_read").await.unwrap();
+        let seconds = [AbiValue::I64(ClockGranularity::Seconds.to_abi())];
+        assert!(clock.call(&seconds, &mut ctx.clone()).is_err());
+        let divergences = replay.divergences();
+        assert_eq!(divergences.len(), 1);
+        assert_eq!(divergences[0].sequence, 0);
+        assert_eq!(divergences[0].expected.as_ref().unwrap().function, "clock_read");
+
+        // The recorded call still matches, then the recording runs out
+        assert_eq!(clock.call(&[], &mut ctx.clone()).unwrap(), AbiValue::I64(7));
+        let fs = replayed.get("fs_read").await.unwrap();
+        assert!(fs.call(&[], &mut ctx.clone()).is_err());
+        let divergences = replay.divergences();
+        assert_eq!(divergences.len(), 2);
+        assert!(divergences[1].expected.is_none());
+        assert!(divergences[1].to_string().contains("never recorded"));
+        assert!(replay.verify().is_err());
+    }
+
     #[test]
     fn test_host_context_default() {
         let ctx = HostContext::default();
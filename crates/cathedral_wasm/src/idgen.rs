@@ -0,0 +1,147 @@
+//! Seeded ID generation backing the `gen_id` host function.
+
+use crate::abi::AbiValue;
+use crate::host::HostFunction;
+use cathedral_core::{Capability, CoreError, Hash};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// ID generator shared between host function calls
+pub type SharedIdGenerator = Arc<Mutex<IdGenerator>>;
+
+/// Generator of run-unique IDs derived from the run seed
+///
+/// Each ID combines a prefix hashed from the seed with a per-call counter,
+/// formatted as a version 8 (custom) UUID. A replay seeded identically
+/// yields the same sequence, and the counter keeps IDs unique within a run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdGenerator {
+    /// Run seed
+    seed: u64,
+    /// Number of IDs generated so far
+    counter: u64,
+}
+
+impl IdGenerator {
+    /// Create a generator for a run seed
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self { seed, counter: 0 }
+    }
+
+    /// Generate the next ID
+    pub fn next_id(&mut self) -> String {
+        let prefix = Hash::compute(&[b"gen_id:".as_slice(), &self.seed.to_be_bytes()].concat());
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&prefix.as_bytes()[..8]);
+        bytes[8..].copy_from_slice(&self.counter.to_be_bytes());
+        // Version 8 and RFC 4122 variant; the counter stays below 2^62, so
+        // the variant bits never overwrite it
+        bytes[6] = (bytes[6] & 0x0f) | 0x80;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        self.counter += 1;
+        format_uuid(&bytes)
+    }
+
+    /// Number of IDs generated so far
+    #[must_use]
+    pub fn counter(&self) -> u64 {
+        self.counter
+    }
+
+    /// Wrap the generator for sharing with host functions
+    #[must_use]
+    pub fn shared(self) -> SharedIdGenerator {
+        Arc::new(Mutex::new(self))
+    }
+}
+
+/// Build the `gen_id` host function over `generator`
+///
+/// Calls need the `IdGen` capability.
+#[must_use]
+pub fn gen_id_function(generator: SharedIdGenerator) -> HostFunction {
+    HostFunction::new(
+        "gen_id".to_string(),
+        vec![Capability::IdGen],
+        20,
+        Arc::new(move |_args, _ctx| {
+            let mut generator = generator.lock().map_err(|_| CoreError::Validation {
+                field: "id_generator".to_string(),
+                reason: "ID generator lock poisoned".to_string(),
+            })?;
+            Ok(AbiValue::String(generator.next_id()))
+        }),
+    )
+}
+
+/// Format bytes in the hyphenated 8-4-4-4-12 UUID form
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::AbiCall;
+    use crate::host::{HostContext, HostExecutor, HostRegistry};
+    use std::collections::BTreeSet;
+
+    async fn executor(seed: u64, capabilities: Vec<Capability>) -> HostExecutor {
+        let registry = HostRegistry::new();
+        registry.register_id_gen(IdGenerator::new(seed).shared()).await;
+        HostExecutor::new(registry)
+            .with_context(HostContext::new().with_capabilities(capabilities))
+    }
+
+    async fn gen_ids(executor: &HostExecutor, count: usize) -> Vec<String> {
+        let mut ids = Vec::new();
+        for _ in 0..count {
+            match executor.execute(&AbiCall::simple("gen_id", vec![])).await.unwrap() {
+                AbiValue::String(id) => ids.push(id),
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        ids
+    }
+
+    #[test]
+    fn test_id_generator_format() {
+        let mut generator = IdGenerator::new(42);
+        let id = generator.next_id();
+        assert_eq!(id.len(), 36);
+        assert_eq!(id.split('-').map(str::len).collect::<Vec<_>>(), [8, 4, 4, 4, 12]);
+        assert_eq!(&id[14..15], "8");
+        assert!(matches!(&id[19..20], "8" | "9" | "a" | "b"));
+        assert_eq!(generator.counter(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gen_id_replays_and_is_unique() {
+        let run = gen_ids(&executor(7, vec![Capability::IdGen]).await, 500).await;
+        let replay = gen_ids(&executor(7, vec![Capability::IdGen]).await, 500).await;
+        assert_eq!(run, replay);
+        assert_eq!(run.iter().collect::<BTreeSet<_>>().len(), run.len());
+
+        let other = gen_ids(&executor(8, vec![Capability::IdGen]).await, 1).await;
+        assert_ne!(other[0], run[0]);
+    }
+
+    #[tokio::test]
+    async fn test_gen_id_requires_capability() {
+        let executor = executor(7, vec![Capability::ClockRead]).await;
+        let err = executor
+            .execute(&AbiCall::simple("gen_id", vec![]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CoreError::InvalidCapability { .. }));
+    }
+}
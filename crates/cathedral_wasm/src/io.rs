@@ -0,0 +1,252 @@
+//! Filesystem and network host functions.
+//!
+//! `fs_read` and `net_http` reach outside the sandbox, so each call is
+//! gated on a grant covering the exact path or host it names. Their
+//! results are not reproducible on their own; runs that use them are
+//! replayed from the recorded host calls instead of touching the disk or
+//! network again.
+
+use crate::abi::AbiValue;
+use crate::db::{invalid_arg, require};
+use crate::host::HostFunction;
+use cathedral_core::{Capability, CoreError, CoreResult};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Largest response body `net_http` returns
+pub const MAX_RESPONSE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Connect, read and write timeout for `net_http`
+const NET_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Build the `fs_read` and `net_http` host functions
+///
+/// `fs_read(path, max_len)` returns up to `max_len` bytes of the file at
+/// `path` and needs an `FsRead` grant covering it. `net_http(method, url)`
+/// sends a body-less plain HTTP request and returns the response body;
+/// `GET` and `HEAD` need a `NetRead` grant for the host, other methods a
+/// `NetWrite` grant.
+#[must_use]
+pub fn io_functions() -> Vec<HostFunction> {
+    vec![
+        HostFunction::new(
+            "fs_read".to_string(),
+            vec![Capability::FsRead { prefixes: Vec::new() }],
+            100,
+            Arc::new(|args, ctx| {
+                let path = string_arg(args, 0)?;
+                let max_len = match args.get(1) {
+                    Some(AbiValue::I32(len)) if *len >= 0 => len.unsigned_abs(),
+                    other => return Err(invalid_arg(1, "non-negative I32", other)),
+                };
+                require(ctx, Capability::FsRead {
+                    prefixes: vec![path.to_string()],
+                })?;
+                let mut data = Vec::new();
+                std::fs::File::open(path)
+                    .and_then(|file| file.take(u64::from(max_len)).read_to_end(&mut data))
+                    .map_err(|e| io_error("fs_read", &e))?;
+                Ok(AbiValue::Bytes(data))
+            }),
+        ),
+        HostFunction::new(
+            "net_http".to_string(),
+            vec![Capability::NetRead { allowlist: Vec::new() }],
+            500,
+            Arc::new(|args, ctx| {
+                let method = string_arg(args, 0)?.to_ascii_uppercase();
+                let url = HttpUrl::parse(string_arg(args, 1)?)?;
+                let allowlist = vec![url.host.clone()];
+                require(ctx, match method.as_str() {
+                    "GET" | "HEAD" => Capability::NetRead { allowlist },
+                    _ => Capability::NetWrite { allowlist },
+                })?;
+                http_request(&method, &url).map(AbiValue::Bytes)
+            }),
+        ),
+    ]
+}
+
+/// Extract a string argument
+fn string_arg(args: &[AbiValue], position: usize) -> CoreResult<&str> {
+    match args.get(position) {
+        Some(AbiValue::String(value)) => Ok(value),
+        other => Err(invalid_arg(position, "String", other)),
+    }
+}
+
+/// Fail a call on an I/O error
+fn io_error(function: &str, err: &std::io::Error) -> CoreError {
+    CoreError::Validation {
+        field: function.to_string(),
+        reason: err.to_string(),
+    }
+}
+
+/// A parsed `http://` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HttpUrl {
+    /// Host name, checked against the network allowlist
+    host: String,
+    /// TCP port
+    port: u16,
+    /// Path and query, starting with `/`
+    path: String,
+}
+
+impl HttpUrl {
+    /// Parse a plain HTTP URL
+    fn parse(url: &str) -> CoreResult<Self> {
+        let invalid = |reason: &str| CoreError::Validation {
+            field: "url".to_string(),
+            reason: format!("{}: {}", reason, url),
+        };
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| invalid("Only http:// URLs are supported"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid("Invalid port"))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid("Missing host"));
+        }
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// Send a body-less HTTP/1.0 request and return the response body
+///
+/// Responses other than 2xx fail the call.
+fn http_request(method: &str, url: &HttpUrl) -> CoreResult<Vec<u8>> {
+    let failed = |reason: String| CoreError::Validation {
+        field: "net_http".to_string(),
+        reason,
+    };
+    let mut response = Vec::new();
+    let limit = MAX_RESPONSE_BYTES + 64 * 1024;
+    TcpStream::connect((url.host.as_str(), url.port))
+        .and_then(|mut stream| {
+            stream.set_read_timeout(Some(NET_TIMEOUT))?;
+            stream.set_write_timeout(Some(NET_TIMEOUT))?;
+            write!(
+                stream,
+                "{} {} HTTP/1.0\r\nHost: {}\r\nConnection: close\r\n\r\n",
+                method, url.path, url.host
+            )?;
+            stream.take(limit).read_to_end(&mut response)
+        })
+        .map_err(|e| io_error("net_http", &e))?;
+    let split = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| failed("Malformed HTTP response".to_string()))?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| failed("Malformed HTTP status line".to_string()))?;
+    if !(200..300).contains(&status) {
+        return Err(failed(format!("HTTP status {}", status)));
+    }
+    let body = response.split_off(split + 4);
+    if body.len() as u64 > MAX_RESPONSE_BYTES {
+        return Err(failed(format!("Response exceeds {} bytes", MAX_RESPONSE_BYTES)));
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{HostContext, HostRegistry};
+    use std::net::TcpListener;
+
+    async fn call(
+        name: &str,
+        args: &[AbiValue],
+        capabilities: Vec<Capability>,
+    ) -> CoreResult<AbiValue> {
+        let registry = HostRegistry::with_standard_functions().await;
+        let func = registry.get(name).await.unwrap();
+        func.call(args, &mut HostContext::new().with_capabilities(capabilities))
+    }
+
+    #[tokio::test]
+    async fn test_fs_read_within_grant() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("input.txt");
+        std::fs::write(&path, b"hello world").unwrap();
+        let path = path.to_string_lossy().to_string();
+        let prefix = dir.path().to_string_lossy().to_string();
+        let args = [AbiValue::String(path), AbiValue::I32(5)];
+
+        let granted = vec![Capability::FsRead { prefixes: vec![prefix] }];
+        assert_eq!(
+            call("fs_read", &args, granted).await.unwrap(),
+            AbiValue::Bytes(b"hello".to_vec())
+        );
+
+        let elsewhere = vec![Capability::FsRead {
+            prefixes: vec!["/nonexistent".to_string()],
+        }];
+        assert!(matches!(
+            call("fs_read", &args, elsewhere).await,
+            Err(CoreError::InvalidCapability { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_net_http_get() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 512];
+            let read = stream.read(&mut request).unwrap();
+            stream.write_all(b"HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\nok").unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+
+        let url = format!("http://127.0.0.1:{}/status?full=1", port);
+        let args = [AbiValue::String("get".to_string()), AbiValue::String(url)];
+        let granted = vec![Capability::NetRead {
+            allowlist: vec!["127.0.0.1".to_string()],
+        }];
+        assert_eq!(
+            call("net_http", &args, granted).await.unwrap(),
+            AbiValue::Bytes(b"ok".to_vec())
+        );
+        assert!(server.join().unwrap().starts_with("GET /status?full=1 HTTP/1.0\r\n"));
+
+        // Writes need a NetWrite grant
+        let post = [AbiValue::String("POST".to_string()), args[1].clone()];
+        let read_only = vec![Capability::NetRead { allowlist: vec!["*".to_string()] }];
+        assert!(matches!(
+            call("net_http", &post, read_only).await,
+            Err(CoreError::InvalidCapability { .. })
+        ));
+    }
+
+    #[test]
+    fn test_http_url_parse() {
+        let url = HttpUrl::parse("http://Example.com:8080/a/b").unwrap();
+        assert_eq!(url.host, "example.com");
+        assert_eq!(url.port, 8080);
+        assert_eq!(url.path, "/a/b");
+        assert_eq!(HttpUrl::parse("http://example.com").unwrap().path, "/");
+        assert!(HttpUrl::parse("https://example.com").is_err());
+        assert!(HttpUrl::parse("http://:80/").is_err());
+    }
+}
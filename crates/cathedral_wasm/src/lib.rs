@@ -12,10 +12,29 @@ pub mod memory;
 pub mod abi;
 pub mod host;
 pub mod compile;
+pub mod db;
+pub mod idgen;
+pub mod io;
+pub mod marshal;
+pub mod wasi;
+pub mod tool;
+#[cfg(feature = "wasmtime")]
+mod engine;
 
 pub use sandbox::{Sandbox, SandboxConfig, SandboxError};
 pub use fuel::{FuelMeter, FuelLimiter, FuelError};
 pub use memory::{MemoryLimit, MemoryRegion, MemoryError};
-pub use abi::{DeterministicAbi, AbiError, AbiCall};
-pub use host::{HostFunction, HostContext, HostRegistry};
-pub use compile::{WasmCompiler, CompileConfig, CompileError};
+pub use abi::{DeterministicAbi, AbiError, AbiCall, AbiSignature, AbiType};
+pub use host::{
+    HostFunction, HostContext, HostRegistry, ClockGranularity, HostCallRecord, HostCallRecorder,
+    HostCallDivergence, ReplayHostRegistry,
+};
+pub use compile::{
+    WasmCompiler, CompileConfig, CompileError, CompileCache, CacheKey, CacheLimits, CacheStats,
+    SharedCompileCache,
+};
+pub use db::{TableStore, SharedTableStore};
+pub use idgen::{IdGenerator, SharedIdGenerator};
+pub use marshal::{GuestBuffer, guest_read, guest_write};
+pub use wasi::{WasiConfig, WasiState, Errno, OpenOptions};
+pub use tool::WasmTool;
@@ -0,0 +1,177 @@
+//! Marshalling of strings and byte buffers across guest linear memory.
+//!
+//! Guests pass `string` and `bytes` arguments as a `(ptr, len)` pair that the
+//! host copies out with [`guest_read`]. To hand bytes back, the host asks the
+//! guest for a buffer through its exported [`ALLOC_EXPORT`] function, records
+//! the buffer as a [`MemoryRegion`], copies the bytes in with [`guest_write`],
+//! and returns the buffer to the guest as a packed [`GuestBuffer`].
+
+use crate::memory::{MemoryError, MemoryRegion, MemoryRegionMap};
+
+/// Guest export called as `alloc(len: i32) -> i32` to obtain a host buffer
+pub const ALLOC_EXPORT: &str = "alloc";
+
+/// Packed value returned to the guest for an absent `option` result
+pub const NONE_BUFFER: i64 = -1;
+
+/// A buffer in guest linear memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GuestBuffer {
+    /// Start address
+    pub ptr: u32,
+    /// Length in bytes
+    pub len: u32,
+}
+
+impl GuestBuffer {
+    /// Create a buffer descriptor
+    #[must_use]
+    pub fn new(ptr: u32, len: u32) -> Self {
+        Self { ptr, len }
+    }
+
+    /// Pack into a single `i64`, pointer in the high 32 bits
+    #[must_use]
+    pub fn pack(self) -> i64 {
+        ((i64::from(self.ptr)) << 32) | i64::from(self.len)
+    }
+
+    /// Unpack a value produced by [`GuestBuffer::pack`]
+    ///
+    /// Returns `None` for [`NONE_BUFFER`] and other negative values, so a
+    /// buffer at or above 2 GiB cannot be returned to the guest.
+    #[must_use]
+    pub fn unpack(packed: i64) -> Option<Self> {
+        let packed = u64::try_from(packed).ok()?;
+        Some(Self {
+            ptr: (packed >> 32) as u32,
+            len: packed as u32,
+        })
+    }
+
+    /// Exclusive end address
+    #[must_use]
+    pub fn end(&self) -> u64 {
+        u64::from(self.ptr) + u64::from(self.len)
+    }
+
+    /// Writable region covering this buffer
+    #[must_use]
+    pub fn region(&self, name: String) -> MemoryRegion {
+        MemoryRegion::new(u64::from(self.ptr), u64::from(self.len), name)
+    }
+}
+
+/// Copy `len` bytes starting at `ptr` out of guest memory
+///
+/// # Errors
+///
+/// Returns error if the range lies outside `memory`
+pub fn guest_read(memory: &[u8], ptr: u64, len: u64) -> Result<Vec<u8>, MemoryError> {
+    let range = checked_range(memory.len(), ptr, len)?;
+    Ok(memory[range].to_vec())
+}
+
+/// Copy `bytes` into guest memory at `ptr`
+///
+/// The whole destination must lie inside one writable region of `regions`,
+/// so the host only ever writes into buffers the guest handed out.
+///
+/// # Errors
+///
+/// Returns error if the range is outside `memory` or not inside a writable
+/// region
+pub fn guest_write(
+    memory: &mut [u8],
+    regions: &MemoryRegionMap,
+    ptr: u64,
+    bytes: &[u8],
+) -> Result<(), MemoryError> {
+    if bytes.is_empty() {
+        return Ok(());
+    }
+
+    let len = bytes.len() as u64;
+    let region = regions
+        .find_region(ptr)
+        .ok_or_else(|| MemoryError::AccessViolation {
+            address: ptr,
+            reason: "no region allocated".to_string(),
+        })?;
+    if !region.can_write() {
+        return Err(MemoryError::AccessViolation {
+            address: ptr,
+            reason: format!("region '{}' is read-only", region.name),
+        });
+    }
+    if ptr + len > region.end {
+        return Err(MemoryError::AccessViolation {
+            address: region.end,
+            reason: format!("write of {} bytes overruns region '{}'", len, region.name),
+        });
+    }
+
+    let range = checked_range(memory.len(), ptr, len)?;
+    memory[range].copy_from_slice(bytes);
+    Ok(())
+}
+
+/// Bounds-check `ptr..ptr + len` against a memory of `size` bytes
+fn checked_range(size: usize, ptr: u64, len: u64) -> Result<std::ops::Range<usize>, MemoryError> {
+    let end = ptr
+        .checked_add(len)
+        .ok_or(MemoryError::InvalidAddress { address: ptr })?;
+    if end > size as u64 {
+        return Err(MemoryError::AccessViolation {
+            address: ptr,
+            reason: format!("{} bytes exceed memory size {}", len, size),
+        });
+    }
+    Ok(ptr as usize..end as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_buffer_pack() {
+        let buffer = GuestBuffer::new(1024, 11);
+        assert_eq!(GuestBuffer::unpack(buffer.pack()), Some(buffer));
+        assert_eq!(buffer.pack() >> 32, 1024);
+        assert_eq!(buffer.end(), 1035);
+
+        let high = GuestBuffer::new(u32::MAX, u32::MAX);
+        assert_eq!(GuestBuffer::unpack(high.pack()), None);
+        assert_eq!(GuestBuffer::unpack(NONE_BUFFER), None);
+    }
+
+    #[test]
+    fn test_guest_read() {
+        let memory = b"hello world".to_vec();
+        assert_eq!(guest_read(&memory, 6, 5).unwrap(), b"world");
+        assert_eq!(guest_read(&memory, 11, 0).unwrap(), b"");
+        assert!(guest_read(&memory, 6, 6).is_err());
+        assert!(guest_read(&memory, u64::MAX, 2).is_err());
+    }
+
+    #[test]
+    fn test_guest_write() {
+        let mut memory = vec![0u8; 64];
+        let mut regions = MemoryRegionMap::new();
+        let buffer = GuestBuffer::new(16, 8);
+        regions.add_region(buffer.region("result".to_string())).unwrap();
+        regions
+            .add_region(MemoryRegion::read_only(32, 8, "data".to_string()))
+            .unwrap();
+
+        guest_write(&mut memory, &regions, 16, b"payload").unwrap();
+        assert_eq!(guest_read(&memory, 16, 7).unwrap(), b"payload");
+
+        // Outside any region, overrunning a region, or into a read-only region
+        assert!(guest_write(&mut memory, &regions, 0, b"x").is_err());
+        assert!(guest_write(&mut memory, &regions, 20, b"too long").is_err());
+        assert!(guest_write(&mut memory, &regions, 32, b"x").is_err());
+        assert!(guest_write(&mut memory, &regions, 0, b"").is_ok());
+    }
+}
@@ -191,6 +191,20 @@ impl MemoryRegionMap {
         }
     }
 
+    /// Remove every region overlapping `start..end`
+    pub fn remove_overlapping(&mut self, start: u64, end: u64) -> Vec<MemoryRegion> {
+        let starts: Vec<u64> = self
+            .regions
+            .values()
+            .filter(|r| r.overlaps(start, end))
+            .map(|r| r.start)
+            .collect();
+        starts
+            .into_iter()
+            .filter_map(|start| self.remove_region(start))
+            .collect()
+    }
+
     /// Find region containing an address
     #[must_use]
     pub fn find_region(&self, addr: u64) -> Option<&MemoryRegion> {
@@ -368,6 +382,19 @@ mod tests {
         assert!(!map.can_write(1536));
     }
 
+    #[test]
+    fn test_memory_region_map_remove_overlapping() {
+        let mut map = MemoryRegionMap::new();
+        map.add_region(MemoryRegion::new(0, 100, "a".to_string())).unwrap();
+        map.add_region(MemoryRegion::new(100, 100, "b".to_string())).unwrap();
+        map.add_region(MemoryRegion::new(300, 100, "c".to_string())).unwrap();
+
+        let removed = map.remove_overlapping(50, 150);
+        assert_eq!(removed.len(), 2);
+        assert_eq!(map.region_count(), 1);
+        assert_eq!(map.total_bytes(), 100);
+    }
+
     #[test]
     fn test_memory_limit_default() {
         let limit = MemoryLimit::default();
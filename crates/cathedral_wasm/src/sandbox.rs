@@ -1,11 +1,12 @@
 //! WASM sandbox for secure execution.
 
 use crate::abi::{AbiCall, DeterministicAbi};
-use crate::compile::{CompileConfig, CompiledModule, WasmCompiler};
+use crate::compile::{CompileConfig, CompiledModule, SharedCompileCache, WasmCompiler};
 use crate::fuel::FuelMeter;
-use crate::host::{HostExecutor, HostRegistry};
+use crate::host::{HostContext, HostExecutor, HostRegistry};
 use crate::memory::MemoryLimit;
-use cathedral_core::{Capability, CoreError, CoreResult, Hash};
+use crate::wasi::WasiConfig;
+use cathedral_core::{CancellationToken, Capability, CoreError, CoreResult, Hash};
 use serde::{Deserialize, Serialize};
 
 /// Sandbox configuration
@@ -19,6 +20,11 @@ pub struct SandboxConfig {
     pub capabilities: Vec<Capability>,
     /// Enable WASI
     pub enable_wasi: bool,
+    /// Inputs for WASI runs
+    #[serde(default)]
+    pub wasi: WasiConfig,
+    /// Register only pure host functions (no filesystem or network)
+    pub pure_compute: bool,
     /// Compilation config
     pub compile_config: CompileConfig,
 }
@@ -32,6 +38,8 @@ impl SandboxConfig {
             memory_limit: 16 * 1024 * 1024, // 16MB
             capabilities: Vec::new(),
             enable_wasi: false,
+            wasi: WasiConfig::new(),
+            pure_compute: false,
             compile_config: CompileConfig::new(),
         }
     }
@@ -72,6 +80,21 @@ impl SandboxConfig {
         self.enable_wasi = enable;
         self
     }
+
+    /// Enable WASI with the given inputs
+    #[must_use]
+    pub fn with_wasi_config(mut self, wasi: WasiConfig) -> Self {
+        self.enable_wasi = true;
+        self.wasi = wasi;
+        self
+    }
+
+    /// Enable/disable pure-compute mode
+    #[must_use]
+    pub fn with_pure_compute(mut self, enable: bool) -> Self {
+        self.pure_compute = enable;
+        self
+    }
 }
 
 impl Default for SandboxConfig {
@@ -97,6 +120,8 @@ pub struct SandboxResult {
     pub output: Vec<u8>,
     /// Host calls made during execution
     pub host_calls: Vec<String>,
+    /// Whether the sandbox ran in pure-compute mode
+    pub pure_compute: bool,
 }
 
 impl SandboxResult {
@@ -111,6 +136,7 @@ impl SandboxResult {
             error: None,
             output: Vec::new(),
             host_calls: Vec::new(),
+            pure_compute: false,
         }
     }
 
@@ -125,6 +151,7 @@ impl SandboxResult {
             error: Some(error),
             output: Vec::new(),
             host_calls: Vec::new(),
+            pure_compute: false,
         }
     }
 }
@@ -145,6 +172,12 @@ pub struct Sandbox {
     memory_limit: Option<MemoryLimit>,
     /// Execution state
     state: SandboxState,
+    /// Host calls completed so far
+    host_calls: Vec<String>,
+    /// Cache of compiled modules, if shared with other sandboxes
+    compile_cache: Option<SharedCompileCache>,
+    /// Token that stops execution when its run is cancelled
+    cancellation: Option<CancellationToken>,
 }
 
 /// Sandbox execution state
@@ -166,17 +199,53 @@ impl Sandbox {
     /// Create a new sandbox
     #[must_use]
     pub fn new(config: SandboxConfig) -> Self {
+        let host_registry = if config.pure_compute {
+            HostRegistry::pure_compute()
+        } else {
+            HostRegistry::new()
+        };
+
         Self {
-            host_registry: HostRegistry::new(),
+            host_registry,
             abi: DeterministicAbi::new(),
             module: None,
             fuel_meter: None,
             memory_limit: None,
             state: SandboxState::Uninitialized,
+            host_calls: Vec::new(),
+            compile_cache: None,
+            cancellation: None,
             config,
         }
     }
 
+    /// Reuse compiled modules from `cache`
+    #[must_use]
+    pub fn with_compile_cache(mut self, cache: SharedCompileCache) -> Self {
+        self.compile_cache = Some(cache);
+        self
+    }
+
+    /// Serve host calls from `registry`
+    ///
+    /// Used to record a live run's host calls, or to answer them from a
+    /// recording when replaying it.
+    #[must_use]
+    pub fn with_host_registry(mut self, registry: HostRegistry) -> Self {
+        self.host_registry = registry;
+        self
+    }
+
+    /// Stop execution when `token` is cancelled
+    ///
+    /// A cancelled sandbox refuses to start, and a running guest is
+    /// interrupted with a trap.
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
     /// Create with default configuration
     #[must_use]
     pub fn default_config() -> Self {
@@ -208,9 +277,19 @@ impl Sandbox {
     /// Returns error if loading fails
     pub fn load_module(&mut self, wasm_bytes: Vec<u8>) -> CoreResult<()> {
         let compiler = WasmCompiler::new(self.config.compile_config.clone());
-        let compiled_bytes = compiler.compile(&wasm_bytes)?;
-
-        let module = CompiledModule::new(compiled_bytes, self.config.compile_config.clone());
+        let module = match &self.compile_cache {
+            Some(cache) => cache
+                .lock()
+                .map_err(|_| CoreError::Validation {
+                    field: "compile_cache".to_string(),
+                    reason: "compile cache lock poisoned".to_string(),
+                })?
+                .get_or_compile(&compiler, &wasm_bytes)?,
+            None => {
+                let compiled_bytes = compiler.compile(&wasm_bytes)?;
+                CompiledModule::new(compiled_bytes, self.config.compile_config.clone())
+            }
+        };
 
         // Set up fuel and memory limits
         self.fuel_meter = Some(FuelMeter::new(self.config.max_fuel));
@@ -230,23 +309,59 @@ impl Sandbox {
 
     /// Execute the loaded module
     ///
+    /// With the `wasmtime` feature the module's `_start` export is run if it
+    /// has one; a module without it is only instantiated.
+    ///
     /// # Errors
     ///
     /// Returns error if execution fails
     pub fn execute(&mut self) -> CoreResult<SandboxResult> {
+        self.run(None, &[])
+    }
+
+    /// Execute with a specific function entry point
+    ///
+    /// Arguments are passed as the function's `i32`/`i64` parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if execution fails
+    pub fn execute_function(&mut self, function: &str, args: &[i64]) -> CoreResult<SandboxResult> {
+        self.run(Some(function), args)
+    }
+
+    /// Run the loaded module and record the resulting state
+    fn run(&mut self, entry: Option<&str>, args: &[i64]) -> CoreResult<SandboxResult> {
         if !matches!(self.state, SandboxState::Ready) {
             return Ok(SandboxResult::error(
                 "Sandbox not ready".to_string(),
                 0,
             ));
         }
+        if self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            let error = SandboxError::Cancelled.to_string();
+            self.state = SandboxState::Error(error.clone());
+            return Ok(SandboxResult::error(error, 0));
+        }
 
         self.state = SandboxState::Running;
 
-        // For now, simulate execution since we don't have actual WASM runtime
-        // In a real implementation, this would use wasmtime
-        let result = match self.simulate_execution() {
-            Ok(result) => result,
+        #[cfg(feature = "wasmtime")]
+        let outcome = self.run_engine(entry, args);
+        #[cfg(not(feature = "wasmtime"))]
+        let outcome = {
+            let _ = (entry, args);
+            self.simulate_execution()
+        };
+
+        match outcome {
+            Ok(result) => {
+                self.state = match &result.error {
+                    Some(error) => SandboxState::Error(error.clone()),
+                    None => SandboxState::Finished,
+                };
+                Ok(result)
+            }
             Err(e) => {
                 self.state = SandboxState::Error(e.to_string());
                 let consumed = self
@@ -254,36 +369,9 @@ impl Sandbox {
                     .as_ref()
                     .map(|f| f.consumed())
                     .unwrap_or(0);
-                return Ok(SandboxResult::error(e.to_string(), consumed));
+                Ok(SandboxResult::error(e.to_string(), consumed))
             }
-        };
-
-        let consumed = self
-            .fuel_meter
-            .as_ref()
-            .map(|f| f.consumed())
-            .unwrap_or(0);
-
-        self.state = SandboxState::Finished;
-
-        Ok(SandboxResult {
-            success: true,
-            return_value: Some(0),
-            fuel_consumed: consumed,
-            peak_memory: 0,
-            error: None,
-            output: result,
-            host_calls: Vec::new(),
-        })
-    }
-
-    /// Execute with a specific function entry point
-    ///
-    /// # Errors
-    ///
-    /// Returns error if execution fails
-    pub fn execute_function(&mut self, _function: &str, _args: &[i64]) -> CoreResult<SandboxResult> {
-        self.execute()
+        }
     }
 
     /// Make a host call from within the sandbox
@@ -312,14 +400,12 @@ impl Sandbox {
         }
 
         // Execute through host registry using handle
-        let runtime = tokio::runtime::Runtime::new().map_err(|e| {
-            CoreError::Validation {
-                field: "runtime".to_string(),
-                reason: format!("Failed to create runtime: {}", e),
-            }
-        })?;
-        let executor = HostExecutor::new(self.host_registry.clone());
+        let runtime = new_runtime()?;
+        let executor = HostExecutor::new(self.host_registry.clone()).with_context(
+            HostContext::new().with_capabilities(self.config.capabilities.clone()),
+        );
         let result = runtime.block_on(executor.execute(call))?;
+        self.host_calls.push(call.function_name.clone());
 
         Ok(result)
     }
@@ -348,10 +434,90 @@ impl Sandbox {
         self.fuel_meter = Some(FuelMeter::new(self.config.max_fuel));
         self.memory_limit = Some(MemoryLimit::new(self.config.memory_limit));
         self.state = SandboxState::Uninitialized;
+        self.host_calls.clear();
+    }
+
+    /// Execute the module on wasmtime, charging its fuel to the fuel meter
+    #[cfg(feature = "wasmtime")]
+    fn run_engine(&mut self, entry: Option<&str>, args: &[i64]) -> CoreResult<SandboxResult> {
+        let module = self.module.as_ref().ok_or_else(|| CoreError::Validation {
+            field: "module".to_string(),
+            reason: SandboxError::NoModule.to_string(),
+        })?;
+        let fuel = self.fuel_meter.as_ref().map_or(self.config.max_fuel, FuelMeter::remaining);
+        let memory_limit = self
+            .memory_limit
+            .clone()
+            .unwrap_or_else(|| MemoryLimit::new(self.config.memory_limit));
+
+        let registry = self.host_registry.clone();
+        let names: Vec<String> = self.abi.functions.keys().cloned().collect();
+        let functions = new_runtime()?.block_on(async move {
+            let mut functions = std::collections::HashMap::new();
+            for name in names {
+                if let Some(func) = registry.get(&name).await {
+                    functions.insert(name, func);
+                }
+            }
+            functions
+        });
+        let context = HostContext::new()
+            .with_capabilities(self.config.capabilities.clone())
+            .with_memory_limit(memory_limit.clone());
+        let wasi = self.config.enable_wasi.then(|| {
+            crate::wasi::WasiState::new(&self.config.wasi, &self.config.capabilities)
+        });
+
+        let execution = crate::engine::run(
+            &module.bytes,
+            module.artifact.as_deref(),
+            entry.unwrap_or(crate::engine::DEFAULT_ENTRY),
+            entry.is_some(),
+            args,
+            fuel,
+            &memory_limit,
+            &self.abi,
+            functions,
+            context,
+            wasi,
+            self.cancellation.clone(),
+        )?;
+
+        if let Some(ref mut meter) = self.fuel_meter {
+            meter.consume(execution.fuel_consumed).map_err(|_e| {
+                CoreError::CapacityExceeded {
+                    resource: "fuel".to_string(),
+                    limit: 0,
+                }
+            })?;
+        }
+        self.host_calls.extend(execution.host_calls);
+
+        let output = execution.wasi.map_or_else(Vec::new, |wasi| {
+            if !wasi.stderr().is_empty() {
+                tracing::debug!("guest stderr: {}", String::from_utf8_lossy(wasi.stderr()));
+            }
+            wasi.stdout().to_vec()
+        });
+        let (return_value, error) = match execution.result {
+            Ok(value) => (value, None),
+            Err(error) => (None, Some(error)),
+        };
+        Ok(SandboxResult {
+            success: error.is_none(),
+            return_value,
+            fuel_consumed: self.fuel_consumed().unwrap_or(execution.fuel_consumed),
+            peak_memory: execution.peak_memory,
+            error,
+            output,
+            host_calls: self.host_calls.clone(),
+            pure_compute: self.config.pure_compute,
+        })
     }
 
-    /// Simulate WASM execution (placeholder)
-    fn simulate_execution(&mut self) -> CoreResult<Vec<u8>> {
+    /// Simulate WASM execution on platforms without the engine
+    #[cfg(not(feature = "wasmtime"))]
+    fn simulate_execution(&mut self) -> CoreResult<SandboxResult> {
         // Consume some fuel
         if let Some(ref mut meter) = self.fuel_meter {
             meter.consume(1000).map_err(|_e| {
@@ -362,11 +528,29 @@ impl Sandbox {
             })?;
         }
 
-        // Return simulated output
-        Ok(b"execution successful".to_vec())
+        Ok(SandboxResult {
+            success: true,
+            return_value: Some(0),
+            fuel_consumed: self.fuel_consumed().unwrap_or(0),
+            peak_memory: 0,
+            error: None,
+            output: b"execution successful".to_vec(),
+            host_calls: self.host_calls.clone(),
+            pure_compute: self.config.pure_compute,
+        })
     }
 }
 
+/// Create a runtime for driving the async host registry
+pub(crate) fn new_runtime() -> CoreResult<tokio::runtime::Runtime> {
+    tokio::runtime::Runtime::new().map_err(|e| {
+        CoreError::Validation {
+            field: "runtime".to_string(),
+            reason: format!("Failed to create runtime: {}", e),
+        }
+    })
+}
+
 impl Default for Sandbox {
     fn default() -> Self {
         Self::default_config()
@@ -403,6 +587,10 @@ pub enum SandboxError {
     /// Invalid state
     #[error("Invalid state: {0}")]
     InvalidState(String),
+
+    /// The run was cancelled
+    #[error("Execution cancelled")]
+    Cancelled,
 }
 
 #[cfg(test)]
@@ -432,6 +620,20 @@ mod tests {
     }
 
     #[test]
+    fn test_sandbox_cancelled_before_start() {
+        let token = CancellationToken::new();
+        let mut sandbox = Sandbox::default_config().with_cancellation(token.clone());
+        sandbox.load_module(make_valid_wasm()).unwrap();
+        token.cancel();
+
+        let result = sandbox.execute().unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error, Some(SandboxError::Cancelled.to_string()));
+        assert_eq!(result.fuel_consumed, 0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "wasmtime"))]
     fn test_sandbox_execute() {
         let mut sandbox = Sandbox::default_config();
         let wasm = make_valid_wasm();
@@ -442,6 +644,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "wasmtime"))]
     fn test_sandbox_remaining_fuel() {
         let mut sandbox = Sandbox::default_config();
         sandbox.load_module(make_valid_wasm()).unwrap();
@@ -450,6 +653,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "wasmtime"))]
     fn test_sandbox_fuel_consumed() {
         let mut sandbox = Sandbox::default_config();
         sandbox.load_module(make_valid_wasm()).unwrap();
@@ -457,6 +661,313 @@ mod tests {
         assert_eq!(sandbox.fuel_consumed(), Some(1000));
     }
 
+    #[cfg(feature = "wasmtime")]
+    fn load_wat(config: SandboxConfig, source: &str) -> Sandbox {
+        let mut sandbox = Sandbox::new(config);
+        sandbox.load_module(wat::parse_str(source).unwrap()).unwrap();
+        sandbox
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_execute_engine() {
+        let mut sandbox = Sandbox::default_config();
+        sandbox.load_module(make_valid_wasm()).unwrap();
+        let result = sandbox.execute().unwrap();
+        assert!(result.success);
+        assert_eq!(result.return_value, None);
+        assert!(matches!(sandbox.state, SandboxState::Finished));
+
+        let mut sandbox = load_wat(
+            SandboxConfig::new(),
+            r#"(module
+                (func (export "_start") (result i32)
+                    (local i32)
+                    (loop
+                        (local.set 0 (i32.add (local.get 0) (i32.const 1)))
+                        (br_if 0 (i32.lt_u (local.get 0) (i32.const 100))))
+                    (local.get 0)))"#,
+        );
+        let result = sandbox.execute().unwrap();
+        assert!(result.success);
+        assert_eq!(result.return_value, Some(100));
+        assert!(result.fuel_consumed > 100);
+        assert_eq!(sandbox.fuel_consumed(), Some(result.fuel_consumed));
+        assert_eq!(sandbox.remaining_fuel(), Some(10_000_000 - result.fuel_consumed));
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_execute_function_args() {
+        let source = r#"(module
+            (func (export "add") (param i32 i64) (result i64)
+                (i64.add (i64.extend_i32_s (local.get 0)) (local.get 1))))"#;
+        let mut sandbox = load_wat(SandboxConfig::new(), source);
+        let result = sandbox.execute_function("add", &[2, 40]).unwrap();
+        assert_eq!(result.return_value, Some(42));
+
+        let mut sandbox = load_wat(SandboxConfig::new(), source);
+        let result = sandbox.execute_function("missing", &[]).unwrap();
+        assert!(!result.success);
+        assert!(matches!(sandbox.state, SandboxState::Error(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_fuel_exhausted() {
+        let mut sandbox = load_wat(
+            SandboxConfig::new().with_max_fuel(10_000),
+            r#"(module (func (export "_start") (loop (br 0))))"#,
+        );
+        let result = sandbox.execute().unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error, Some(SandboxError::FuelExhausted.to_string()));
+        assert_eq!(result.fuel_consumed, 10_000);
+        assert_eq!(sandbox.remaining_fuel(), Some(0));
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_memory_limit() {
+        let mut sandbox = load_wat(
+            SandboxConfig::new().with_memory_limit(2 * 65536),
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "_start") (result i32)
+                    (drop (memory.grow (i32.const 1)))
+                    (memory.grow (i32.const 1))))"#,
+        );
+        let result = sandbox.execute().unwrap();
+        assert!(result.success);
+        assert_eq!(result.return_value, Some(-1));
+        assert_eq!(result.peak_memory, 2 * 65536);
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_host_imports() {
+        let source = r#"(module
+            (import "cathedral" "clock_read" (func $clock (param i64) (result i64)))
+            (import "cathedral" "log_write" (func $log (param i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "hello")
+            (func (export "_start") (result i64)
+                (drop (call $log (i32.const 0) (i32.const 5) (i32.const 1)))
+                (call $clock (i64.const 0))))"#;
+
+        let config = SandboxConfig::new()
+            .with_pure_compute(true)
+            .with_capability(Capability::ClockRead);
+        let mut sandbox = load_wat(config, source);
+        let result = sandbox.execute().unwrap();
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.return_value, Some(0));
+        assert_eq!(
+            result.host_calls,
+            vec!["log_write".to_string(), "clock_read".to_string()]
+        );
+
+        // Without the capability the host call traps
+        let mut sandbox = load_wat(SandboxConfig::new().with_pure_compute(true), source);
+        let result = sandbox.execute().unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("Host call failed"));
+        assert_eq!(result.host_calls, vec!["log_write".to_string()]);
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_host_returns_bytes() {
+        let source = r#"(module
+            (import "cathedral" "db_read" (func $read (param i32 i32 i32 i32) (result i64)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (data (i32.const 0) "users")
+            (data (i32.const 16) "alice")
+            (data (i32.const 32) "bob")
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "read_alice") (result i64)
+                (call $read (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 5)))
+            (func (export "last_byte") (result i32)
+                (local $buffer i64)
+                (local.set $buffer (call $read
+                    (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 5)))
+                (i32.load8_u (i32.sub
+                    (i32.add
+                        (i32.wrap_i64 (i64.shr_u (local.get $buffer) (i64.const 32)))
+                        (i32.wrap_i64 (local.get $buffer)))
+                    (i32.const 1))))
+            (func (export "read_bob") (result i64)
+                (call $read (i32.const 0) (i32.const 5) (i32.const 32) (i32.const 3))))"#;
+        let store = crate::db::TableStore::new()
+            .with_row("users", "alice", b"admin=false".to_vec())
+            .shared();
+        let config = SandboxConfig::new().with_capability(Capability::DbRead {
+            tables: vec!["users".to_string()],
+        });
+        let run = |function: &str| {
+            let mut sandbox = load_wat(config.clone(), source);
+            new_runtime()
+                .unwrap()
+                .block_on(sandbox.host_registry().register_db(store.clone()));
+            sandbox.execute_function(function, &[]).unwrap()
+        };
+
+        let buffer = crate::marshal::GuestBuffer::unpack(run("read_alice").return_value.unwrap());
+        assert_eq!(buffer, Some(crate::marshal::GuestBuffer::new(1024, 11)));
+        assert_eq!(run("last_byte").return_value, Some(i64::from(b'e')));
+        assert_eq!(run("read_bob").return_value, Some(crate::marshal::NONE_BUFFER));
+
+        // A guest without an allocator cannot receive byte results
+        let source = source.replace(r#"(export "alloc")"#, "");
+        let mut sandbox = load_wat(config.clone(), &source);
+        new_runtime()
+            .unwrap()
+            .block_on(sandbox.host_registry().register_db(store.clone()));
+        let result = sandbox.execute_function("read_alice", &[]).unwrap();
+        assert!(result.error.unwrap().contains("alloc"));
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_pure_compute_imports() {
+        let mut sandbox = load_wat(
+            SandboxConfig::new().with_pure_compute(true),
+            r#"(module
+                (import "cathedral" "fs_read" (func (param i32 i32 i32) (result i32))))"#,
+        );
+        let result = sandbox.execute().unwrap();
+        assert!(!result.success);
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_wasi() {
+        let source = r#"(module
+            (import "wasi_snapshot_preview1" "fd_write"
+                (func $fd_write (param i32 i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "random_get"
+                (func $random_get (param i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "clock_time_get"
+                (func $clock_time_get (param i32 i64 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "\10\00\00\00\06\00\00\00")
+            (data (i32.const 16) "hello\n")
+            (func (export "_start")
+                (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+                (call $proc_exit (i32.const 0)))
+            (func (export "random") (result i64)
+                (drop (call $random_get (i32.const 32) (i32.const 8)))
+                (i64.load (i32.const 32)))
+            (func (export "clock") (result i64)
+                (drop (call $clock_time_get (i32.const 0) (i64.const 0) (i32.const 32)))
+                (drop (call $clock_time_get (i32.const 0) (i64.const 0) (i32.const 32)))
+                (i64.load (i32.const 32)))
+            (func (export "fail") (call $proc_exit (i32.const 3))))"#;
+        let config = |seed: u64| {
+            SandboxConfig::new()
+                .with_capability(Capability::ClockRead)
+                .with_wasi_config(crate::wasi::WasiConfig::new().with_seed(seed))
+        };
+
+        let result = load_wat(config(7), source).execute().unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, b"hello\n");
+
+        // Randomness is a function of the seed, and clocks tick per read
+        let random = |seed: u64| {
+            let mut sandbox = load_wat(config(seed), source);
+            sandbox.execute_function("random", &[]).unwrap().return_value
+        };
+        assert_eq!(random(7), random(7));
+        assert_ne!(random(7), random(8));
+        let mut sandbox = load_wat(config(7), source);
+        let clock = sandbox.execute_function("clock", &[]).unwrap();
+        assert_eq!(clock.return_value, Some(2 * crate::wasi::NANOS_PER_TICK as i64));
+
+        let mut sandbox = load_wat(config(7), source);
+        let result = sandbox.execute_function("fail", &[]).unwrap();
+        assert!(result.error.unwrap().contains("exit with code 3"));
+
+        // WASI imports are unresolved unless enabled
+        let result = load_wat(SandboxConfig::new(), source).execute().unwrap();
+        assert!(!result.success);
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_wasi_charges_fuel() {
+        let source = r#"(module
+            (import "wasi_snapshot_preview1" "random_get"
+                (func $random_get (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (func (export "huge") (result i32)
+                (call $random_get (i32.const 0) (i32.const -1)))
+            (func (export "page") (result i32)
+                (call $random_get (i32.const 0) (i32.const 60000))))"#;
+        let run = |fuel: u64, entry: &str| {
+            let config = SandboxConfig::new()
+                .with_max_fuel(fuel)
+                .with_wasi_config(crate::wasi::WasiConfig::new());
+            load_wat(config, source).execute_function(entry, &[]).unwrap()
+        };
+
+        // An out-of-bounds buffer faults before any bytes are generated
+        let huge = run(1_000, "huge");
+        assert_eq!(huge.return_value, Some(i64::from(crate::wasi::Errno::Fault.code())));
+
+        let page = run(1_000_000, "page");
+        assert!(page.success);
+        assert!(page.fuel_consumed >= 60_000 * crate::wasi::WASI_BYTE_FUEL);
+
+        let starved = run(10_000, "page");
+        assert!(!starved.success);
+        assert_eq!(starved.error, Some(SandboxError::FuelExhausted.to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "wasmtime")]
+    fn test_sandbox_cancellation_interrupts_guest() {
+        let token = CancellationToken::new();
+        let config = SandboxConfig::new().with_max_fuel(1 << 40);
+        let mut sandbox = load_wat(
+            config,
+            r#"(module (func (export "_start") (loop (br 0))))"#,
+        )
+        .with_cancellation(token.clone());
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            token.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let result = sandbox.execute().unwrap();
+        canceller.join().unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error, Some(SandboxError::Cancelled.to_string()));
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_sandbox_compile_cache() {
+        let cache = crate::compile::CompileCache::default().shared();
+        let mut first = Sandbox::default_config().with_compile_cache(cache.clone());
+        let mut second = Sandbox::default_config().with_compile_cache(cache.clone());
+        first.load_module(make_valid_wasm()).unwrap();
+        second.load_module(make_valid_wasm()).unwrap();
+        assert_eq!(first.module_hash(), second.module_hash());
+        // The cache hit runs from the stored engine artifact
+        assert!(second.module.as_ref().unwrap().artifact.is_some());
+        assert_eq!(first.execute().unwrap(), second.execute().unwrap());
+
+        let cache = cache.lock().unwrap();
+        assert_eq!(cache.stats().misses, 1);
+        assert_eq!(cache.stats().hits, 1);
+    }
+
     #[test]
     fn test_sandbox_reset() {
         let mut sandbox = Sandbox::default_config();
@@ -536,6 +1047,30 @@ mod tests {
         assert!(sandbox.module_hash().is_some());
     }
 
+    #[test]
+    fn test_sandbox_pure_compute() {
+        let config = SandboxConfig::new()
+            .with_pure_compute(true)
+            .with_capability(Capability::ClockRead);
+        let mut sandbox = Sandbox::new(config);
+        sandbox.load_module(make_valid_wasm()).unwrap();
+
+        assert!(sandbox.host_call(&AbiCall::clock_read()).is_ok());
+        assert!(sandbox
+            .host_call(&AbiCall::simple(
+                "fs_read",
+                vec![
+                    crate::abi::AbiValue::String("./data".to_string()),
+                    crate::abi::AbiValue::I32(0),
+                ],
+            ))
+            .is_err());
+
+        let result = sandbox.execute().unwrap();
+        assert!(result.pure_compute);
+        assert_eq!(result.host_calls, vec!["clock_read".to_string()]);
+    }
+
     #[test]
     fn test_default() {
         let sandbox = Sandbox::default();
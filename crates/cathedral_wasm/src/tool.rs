@@ -0,0 +1,212 @@
+//! WASM modules as tools.
+//!
+//! A [`WasmTool`] runs its module in a fresh [`Sandbox`] for every call. The
+//! tool's input is the guest's WASI stdin and the guest's stdout is the
+//! output, so modules are written like command-line filters. Executed
+//! through [`Tool::execute_cancellable`], the sandbox shares the run's
+//! cancellation token and a cancelled run interrupts the guest.
+//!
+//! Every host call the guest makes is recorded and returned with the
+//! output as encoded [`HostCallRecord`]s, so the executor can log them
+//! and a replay can answer the same calls from the log.
+//!
+//! [`HostCallRecord`]: crate::host::HostCallRecord
+
+use crate::compile::SharedCompileCache;
+use crate::host::{HostCallRecorder, HostRegistry};
+use crate::sandbox::{new_runtime, Sandbox, SandboxConfig, SandboxError};
+use cathedral_core::{CancellationToken, CoreError, CoreResult};
+use cathedral_tool::{Tool, ToolOutput};
+
+/// Exit code reported for a guest that trapped or exited with an error
+const FAILURE_CODE: i32 = 1;
+
+/// Tool that runs a WASM module in a sandbox
+pub struct WasmTool {
+    /// Tool name
+    name: String,
+    /// Tool version
+    version: String,
+    /// Module bytes
+    module: Vec<u8>,
+    /// Sandbox configuration, with WASI enabled
+    config: SandboxConfig,
+    /// Cache of compiled modules, if shared with other tools
+    compile_cache: Option<SharedCompileCache>,
+    /// Host functions offered to the guest, if not the sandbox's default
+    host_registry: Option<HostRegistry>,
+}
+
+impl WasmTool {
+    /// Create a tool running `module` as the tool `name`
+    #[must_use]
+    pub fn new(name: &str, module: Vec<u8>) -> Self {
+        Self {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            module,
+            config: SandboxConfig::new().with_wasi(true),
+            compile_cache: None,
+            host_registry: None,
+        }
+    }
+
+    /// Set the tool version
+    #[must_use]
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = version.to_string();
+        self
+    }
+
+    /// Run the module under `config`; WASI is always enabled
+    #[must_use]
+    pub fn with_config(mut self, config: SandboxConfig) -> Self {
+        self.config = config.with_wasi(true);
+        self
+    }
+
+    /// Reuse compiled modules from `cache`
+    #[must_use]
+    pub fn with_compile_cache(mut self, cache: SharedCompileCache) -> Self {
+        self.compile_cache = Some(cache);
+        self
+    }
+
+    /// Serve the guest's host calls from `registry`
+    #[must_use]
+    pub fn with_host_registry(mut self, registry: HostRegistry) -> Self {
+        self.host_registry = Some(registry);
+        self
+    }
+
+    fn run(
+        &self,
+        input: &[u8],
+        cancellation: Option<&CancellationToken>,
+    ) -> CoreResult<ToolOutput> {
+        let wasi = self.config.wasi.clone().with_stdin(input.to_vec());
+        let mut sandbox = Sandbox::new(self.config.clone().with_wasi_config(wasi));
+        if let Some(cache) = &self.compile_cache {
+            sandbox = sandbox.with_compile_cache(cache.clone());
+        }
+        if let Some(token) = cancellation {
+            sandbox = sandbox.with_cancellation(token.clone());
+        }
+        let registry = match &self.host_registry {
+            Some(registry) => registry.clone(),
+            None => sandbox.host_registry().clone(),
+        };
+        let recorder = HostCallRecorder::new();
+        let recording = new_runtime()?.block_on(recorder.instrument(&registry));
+        sandbox = sandbox.with_host_registry(recording);
+        sandbox.load_module(self.module.clone())?;
+
+        let result = sandbox.execute()?;
+        let output = match result.error {
+            None => ToolOutput::success(result.output),
+            Some(error) if error == SandboxError::Cancelled.to_string() => {
+                return Err(CoreError::Cancelled);
+            }
+            Some(error) => ToolOutput::failure(FAILURE_CODE, error.into_bytes()),
+        };
+        let host_calls = recorder
+            .records()
+            .iter()
+            .map(serde_json::to_vec)
+            .collect::<Result<_, _>>()?;
+        Ok(output.with_host_calls(host_calls))
+    }
+}
+
+impl Tool for WasmTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn execute(&self, input: &[u8]) -> CoreResult<ToolOutput> {
+        self.run(input, None)
+    }
+
+    fn execute_cancellable(
+        &self,
+        input: &[u8],
+        cancellation: &CancellationToken,
+    ) -> CoreResult<ToolOutput> {
+        self.run(input, Some(cancellation))
+    }
+}
+
+#[cfg(all(test, feature = "wasmtime"))]
+mod tests {
+    use super::*;
+    use crate::host::HostCallRecord;
+    use cathedral_core::Capability;
+    use std::time::{Duration, Instant};
+
+    /// Copies up to 64 bytes of stdin to stdout
+    const CAT: &str = r#"(module
+        (import "wasi_snapshot_preview1" "fd_read"
+            (func $read (param i32 i32 i32 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "fd_write"
+            (func $write (param i32 i32 i32 i32) (result i32)))
+        (memory (export "memory") 1)
+        (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 64))
+            (i32.store (i32.const 4) (i32.const 64))
+            (drop (call $read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.store (i32.const 4) (i32.load (i32.const 8)))
+            (drop (call $write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))"#;
+
+    #[test]
+    fn test_wasm_tool_filters_stdin() {
+        let tool = WasmTool::new("cat", wat::parse_str(CAT).unwrap());
+        let output = tool.execute(b"hello").unwrap();
+        assert!(output.is_success());
+        assert_eq!(output.data, b"hello");
+    }
+
+    #[test]
+    fn test_wasm_tool_records_host_calls() {
+        let clock = r#"(module
+            (import "cathedral" "clock_read" (func $clock (param i64) (result i64)))
+            (memory (export "memory") 1)
+            (func (export "_start") (drop (call $clock (i64.const 0)))))"#;
+        let registry = new_runtime().unwrap().block_on(HostRegistry::with_standard_functions());
+        let tool = WasmTool::new("clock", wat::parse_str(clock).unwrap())
+            .with_config(SandboxConfig::new().with_capability(Capability::ClockRead))
+            .with_host_registry(registry);
+
+        let output = tool.execute(b"").unwrap();
+        assert!(output.is_success());
+        assert_eq!(output.host_calls.len(), 1);
+        let record: HostCallRecord = serde_json::from_slice(&output.host_calls[0]).unwrap();
+        assert_eq!(record.function, "clock_read");
+        assert_eq!(record.sequence, 0);
+        assert!(record.result.is_ok());
+    }
+
+    #[test]
+    fn test_wasm_tool_cancellation_interrupts_guest() {
+        let spin = r#"(module (func (export "_start") (loop (br 0))))"#;
+        let tool = WasmTool::new("spin", wat::parse_str(spin).unwrap())
+            .with_config(SandboxConfig::new().with_max_fuel(1 << 40));
+        let token = CancellationToken::new();
+        let canceller = {
+            let token = token.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                token.cancel();
+            })
+        };
+
+        let started = Instant::now();
+        let err = tool.execute_cancellable(b"", &token).unwrap_err();
+        canceller.join().unwrap();
+        assert_eq!(err, CoreError::Cancelled);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
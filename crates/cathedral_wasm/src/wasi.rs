@@ -0,0 +1,1048 @@
+//! Deterministic WASI preview1 subset.
+//!
+//! Every source of nondeterminism in WASI is replaced by run input:
+//!
+//! - clocks read a [`LogicalTime`] that advances one tick per read, and need
+//!   the `ClockRead` capability
+//! - `random_get` draws from a hash chain seeded by [`WasiConfig::seed`]
+//! - the environment holds only the configured variables that an `EnvRead`
+//!   grant names
+//! - files live in an in-memory tree seeded from [`WasiConfig::files`] and
+//!   preopened as `.`; opening a path for reading or writing needs an
+//!   `FsRead`/`FsWrite` grant covering it
+//!
+//! Standard output and error are captured rather than written to the host.
+//! Every call is charged fuel, per call and per byte it moves.
+
+use cathedral_core::{Capability, Hash, LogicalTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Import module name of WASI preview1
+pub const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+/// Nanoseconds reported per logical clock tick
+pub const NANOS_PER_TICK: u64 = 1_000_000;
+
+/// File descriptor of the preopened root directory
+pub const ROOT_FD: u32 = 3;
+
+/// Largest size, in bytes, a guest may grow an in-memory file to
+pub const MAX_FILE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Fuel charged for every WASI call
+pub const WASI_CALL_FUEL: u64 = 10;
+
+/// Fuel charged per byte a WASI call moves between guest and host
+pub const WASI_BYTE_FUEL: u64 = 1;
+
+/// WASI error numbers used by the subset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[repr(u16)]
+pub enum Errno {
+    /// Permission denied
+    #[error("permission denied")]
+    Acces = 2,
+    /// Bad file descriptor
+    #[error("bad file descriptor")]
+    Badf = 8,
+    /// File exists
+    #[error("file exists")]
+    Exist = 20,
+    /// Bad address
+    #[error("bad address")]
+    Fault = 21,
+    /// File too large
+    #[error("file too large")]
+    Fbig = 22,
+    /// Invalid argument
+    #[error("invalid argument")]
+    Inval = 28,
+    /// Is a directory
+    #[error("is a directory")]
+    Isdir = 31,
+    /// No such file or directory
+    #[error("no such file or directory")]
+    Noent = 44,
+    /// Function not supported
+    #[error("function not supported")]
+    Nosys = 52,
+    /// Invalid seek
+    #[error("invalid seek")]
+    Spipe = 70,
+    /// Capability insufficient
+    #[error("capability insufficient")]
+    Notcapable = 76,
+}
+
+impl Errno {
+    /// Numeric code returned to the guest
+    #[must_use]
+    pub fn code(self) -> i32 {
+        i32::from(self as u16)
+    }
+}
+
+/// WASI file types reported by `fd_fdstat_get` and `fd_filestat_get`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FileType {
+    /// Standard streams
+    CharacterDevice = 2,
+    /// The preopened root
+    Directory = 3,
+    /// An in-memory file
+    RegularFile = 4,
+}
+
+/// Inputs that seed a WASI run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasiConfig {
+    /// Command-line arguments, starting with the program name
+    pub args: Vec<String>,
+    /// Environment variables offered to the guest
+    pub env: BTreeMap<String, String>,
+    /// Initial file tree, keyed by path relative to the preopened root
+    pub files: BTreeMap<String, Vec<u8>>,
+    /// Standard input
+    pub stdin: Vec<u8>,
+    /// Seed for `random_get`
+    pub seed: u64,
+}
+
+impl WasiConfig {
+    /// Create an empty config
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a command-line argument
+    #[must_use]
+    pub fn with_arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    /// Offer an environment variable
+    #[must_use]
+    pub fn with_env(mut self, name: &str, value: &str) -> Self {
+        self.env.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Seed a file
+    #[must_use]
+    pub fn with_file(mut self, path: &str, contents: Vec<u8>) -> Self {
+        self.files.insert(path.to_string(), contents);
+        self
+    }
+
+    /// Set standard input
+    #[must_use]
+    pub fn with_stdin(mut self, stdin: Vec<u8>) -> Self {
+        self.stdin = stdin;
+        self
+    }
+
+    /// Set the random seed
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+}
+
+/// How `path_open` opens a file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpenOptions {
+    /// Open for reading
+    pub read: bool,
+    /// Open for writing
+    pub write: bool,
+    /// Create the file if missing
+    pub create: bool,
+    /// Fail if the file exists
+    pub exclusive: bool,
+    /// Truncate the file
+    pub truncate: bool,
+    /// Start every write at the end of the file
+    pub append: bool,
+}
+
+/// An open file descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Descriptor {
+    Stdin { offset: usize },
+    Stdout,
+    Stderr,
+    Root,
+    File {
+        path: String,
+        offset: u64,
+        options: OpenOptions,
+    },
+}
+
+/// State of one WASI run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WasiState {
+    args: Vec<String>,
+    env: Vec<String>,
+    stdin: Vec<u8>,
+    files: BTreeMap<String, Vec<u8>>,
+    fds: BTreeMap<u32, Descriptor>,
+    capabilities: Vec<Capability>,
+    clock: LogicalTime,
+    seed: u64,
+    random_blocks: u64,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+    exit_code: Option<u32>,
+}
+
+impl WasiState {
+    /// Create the state for a run granted `capabilities`
+    #[must_use]
+    pub fn new(config: &WasiConfig, capabilities: &[Capability]) -> Self {
+        let env = config
+            .env
+            .iter()
+            .filter(|(name, _)| {
+                let wanted = Capability::EnvRead {
+                    vars: vec![(*name).clone()],
+                };
+                capabilities.iter().any(|cap| cap.covers(&wanted))
+            })
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+
+        let fds = BTreeMap::from([
+            (0, Descriptor::Stdin { offset: 0 }),
+            (1, Descriptor::Stdout),
+            (2, Descriptor::Stderr),
+            (ROOT_FD, Descriptor::Root),
+        ]);
+
+        Self {
+            args: config.args.clone(),
+            env,
+            stdin: config.stdin.clone(),
+            files: config.files.clone(),
+            fds,
+            capabilities: capabilities.to_vec(),
+            clock: LogicalTime::zero(),
+            seed: config.seed,
+            random_blocks: 0,
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+            exit_code: None,
+        }
+    }
+
+    /// Command-line arguments
+    #[must_use]
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    /// Granted environment as `NAME=value` strings, sorted by name
+    #[must_use]
+    pub fn environ(&self) -> &[String] {
+        &self.env
+    }
+
+    /// Read a clock, advancing logical time by one tick
+    ///
+    /// # Errors
+    ///
+    /// Returns `Inval` for an unknown clock and `Notcapable` without
+    /// `ClockRead`
+    pub fn clock_time(&mut self, clock_id: u32) -> Result<u64, Errno> {
+        if clock_id > 3 {
+            return Err(Errno::Inval);
+        }
+        if !self.capabilities.contains(&Capability::ClockRead) {
+            return Err(Errno::Notcapable);
+        }
+        self.clock.increment();
+        Ok(self.clock.as_u64().saturating_mul(NANOS_PER_TICK))
+    }
+
+    /// Deterministic random bytes
+    pub fn random(&mut self, len: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len);
+        while bytes.len() < len {
+            let block = Hash::compute(
+                &[
+                    b"random_get:".as_slice(),
+                    &self.seed.to_be_bytes(),
+                    &self.random_blocks.to_be_bytes(),
+                ]
+                .concat(),
+            );
+            self.random_blocks += 1;
+            let take = (len - bytes.len()).min(block.as_bytes().len());
+            bytes.extend_from_slice(&block.as_bytes()[..take]);
+        }
+        bytes
+    }
+
+    /// Open `path` relative to the directory `dirfd`
+    ///
+    /// # Errors
+    ///
+    /// Returns `Notcapable` if the path escapes the root or no grant covers
+    /// it, `Noent` if it is missing and not created, and `Exist` if it
+    /// exists and `exclusive` is set
+    pub fn open(&mut self, dirfd: u32, path: &str, options: OpenOptions) -> Result<u32, Errno> {
+        match self.fds.get(&dirfd) {
+            Some(Descriptor::Root) => {}
+            Some(_) => return Err(Errno::Notcapable),
+            None => return Err(Errno::Badf),
+        }
+        let path = normalize_path(path)?;
+        let creates = options.create || options.truncate;
+        if options.read && !self.grants(&path, false) {
+            return Err(Errno::Notcapable);
+        }
+        if (options.write || creates) && !self.grants(&path, true) {
+            return Err(Errno::Notcapable);
+        }
+
+        match self.files.get_mut(&path) {
+            Some(_) if options.create && options.exclusive => return Err(Errno::Exist),
+            Some(contents) if options.truncate => contents.clear(),
+            Some(_) => {}
+            None if options.create => {
+                self.files.insert(path.clone(), Vec::new());
+            }
+            None => return Err(Errno::Noent),
+        }
+
+        let fd = self.fds.keys().next_back().map_or(0, |fd| fd + 1);
+        self.fds.insert(fd, Descriptor::File { path, offset: 0, options });
+        Ok(fd)
+    }
+
+    /// Read up to `len` bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `fd` is not readable
+    pub fn read(&mut self, fd: u32, len: usize) -> Result<Vec<u8>, Errno> {
+        match self.fds.get_mut(&fd).ok_or(Errno::Badf)? {
+            Descriptor::Stdin { offset } => {
+                let start = (*offset).min(self.stdin.len());
+                let end = start.saturating_add(len).min(self.stdin.len());
+                *offset = end;
+                Ok(self.stdin[start..end].to_vec())
+            }
+            Descriptor::File { path, offset, options } if options.read => {
+                let contents = self.files.get(path.as_str()).ok_or(Errno::Noent)?;
+                let start = usize::try_from(*offset).unwrap_or(usize::MAX).min(contents.len());
+                let end = start.saturating_add(len).min(contents.len());
+                *offset = end as u64;
+                Ok(contents[start..end].to_vec())
+            }
+            Descriptor::Root => Err(Errno::Isdir),
+            _ => Err(Errno::Badf),
+        }
+    }
+
+    /// Write `bytes`, returning the number written
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `fd` is not writable, and `Fbig` if the write would
+    /// grow a file past [`MAX_FILE_SIZE`]
+    pub fn write(&mut self, fd: u32, bytes: &[u8]) -> Result<usize, Errno> {
+        match self.fds.get_mut(&fd).ok_or(Errno::Badf)? {
+            Descriptor::Stdout => self.stdout.extend_from_slice(bytes),
+            Descriptor::Stderr => self.stderr.extend_from_slice(bytes),
+            Descriptor::File { path, offset, options } if options.write => {
+                let contents = self.files.get_mut(path.as_str()).ok_or(Errno::Noent)?;
+                if options.append {
+                    *offset = contents.len() as u64;
+                }
+                let start = usize::try_from(*offset).map_err(|_| Errno::Fbig)?;
+                let end = start
+                    .checked_add(bytes.len())
+                    .filter(|end| *end <= MAX_FILE_SIZE)
+                    .ok_or(Errno::Fbig)?;
+                if contents.len() < end {
+                    contents.resize(end, 0);
+                }
+                contents[start..end].copy_from_slice(bytes);
+                *offset = end as u64;
+            }
+            Descriptor::Root => return Err(Errno::Isdir),
+            _ => return Err(Errno::Badf),
+        }
+        Ok(bytes.len())
+    }
+
+    /// Move a file offset; `whence` is 0 (start), 1 (current) or 2 (end)
+    ///
+    /// # Errors
+    ///
+    /// Returns `Spipe` for streams and `Inval` for a negative result
+    pub fn seek(&mut self, fd: u32, delta: i64, whence: u8) -> Result<u64, Errno> {
+        let Descriptor::File { path, offset, .. } = self.fds.get_mut(&fd).ok_or(Errno::Badf)?
+        else {
+            return Err(Errno::Spipe);
+        };
+        let size = self.files.get(path.as_str()).map_or(0, Vec::len) as u64;
+        let base = match whence {
+            0 => 0,
+            1 => *offset,
+            2 => size,
+            _ => return Err(Errno::Inval),
+        };
+        *offset = base.checked_add_signed(delta).ok_or(Errno::Inval)?;
+        Ok(*offset)
+    }
+
+    /// Close a descriptor
+    ///
+    /// # Errors
+    ///
+    /// Returns `Badf` if `fd` is not open
+    pub fn close(&mut self, fd: u32) -> Result<(), Errno> {
+        self.fds.remove(&fd).map(|_| ()).ok_or(Errno::Badf)
+    }
+
+    /// File type and size of a descriptor
+    ///
+    /// # Errors
+    ///
+    /// Returns `Badf` if `fd` is not open
+    pub fn stat(&self, fd: u32) -> Result<(FileType, u64), Errno> {
+        match self.fds.get(&fd).ok_or(Errno::Badf)? {
+            Descriptor::Stdin { .. } | Descriptor::Stdout | Descriptor::Stderr => {
+                Ok((FileType::CharacterDevice, 0))
+            }
+            Descriptor::Root => Ok((FileType::Directory, 0)),
+            Descriptor::File { path, .. } => {
+                let size = self.files.get(path.as_str()).map_or(0, Vec::len);
+                Ok((FileType::RegularFile, size as u64))
+            }
+        }
+    }
+
+    /// Name of a preopened directory
+    ///
+    /// # Errors
+    ///
+    /// Returns `Badf` if `fd` is not a preopen
+    pub fn prestat_name(&self, fd: u32) -> Result<&'static str, Errno> {
+        match self.fds.get(&fd) {
+            Some(Descriptor::Root) => Ok("."),
+            _ => Err(Errno::Badf),
+        }
+    }
+
+    /// Record the guest's exit code
+    pub fn exit(&mut self, code: u32) {
+        self.exit_code = Some(code);
+    }
+
+    /// Exit code passed to `proc_exit`, if the guest exited
+    #[must_use]
+    pub fn exit_code(&self) -> Option<u32> {
+        self.exit_code
+    }
+
+    /// Captured standard output
+    #[must_use]
+    pub fn stdout(&self) -> &[u8] {
+        &self.stdout
+    }
+
+    /// Captured standard error
+    #[must_use]
+    pub fn stderr(&self) -> &[u8] {
+        &self.stderr
+    }
+
+    /// File tree, including files written by the guest
+    #[must_use]
+    pub fn files(&self) -> &BTreeMap<String, Vec<u8>> {
+        &self.files
+    }
+
+    /// Check for a grant covering `path`, as given or with a `./` prefix
+    fn grants(&self, path: &str, write: bool) -> bool {
+        [path.to_string(), format!("./{}", path)].into_iter().any(|path| {
+            let wanted = if write {
+                Capability::FsWrite { prefixes: vec![path] }
+            } else {
+                Capability::FsRead { prefixes: vec![path] }
+            };
+            self.capabilities.iter().any(|cap| cap.covers(&wanted))
+        })
+    }
+}
+
+/// Normalize a guest path relative to the root, rejecting escapes
+fn normalize_path(path: &str) -> Result<String, Errno> {
+    if path.starts_with('/') {
+        return Err(Errno::Notcapable);
+    }
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return Err(Errno::Notcapable),
+            part => parts.push(part),
+        }
+    }
+    if parts.is_empty() {
+        return Err(Errno::Isdir);
+    }
+    Ok(parts.join("/"))
+}
+
+#[cfg(feature = "wasmtime")]
+pub(crate) use linker::add_to_linker;
+
+/// Preview1 imports over a [`WasiState`] held in the store data
+#[cfg(feature = "wasmtime")]
+mod linker {
+    use super::{Errno, OpenOptions, WasiState, NANOS_PER_TICK, WASI_MODULE};
+    use super::{WASI_BYTE_FUEL, WASI_CALL_FUEL};
+    use crate::marshal::guest_read;
+    use wasmtime::{Caller, Extern, Linker, Trap};
+
+    const SUCCESS: i32 = 0;
+    const RIGHT_FD_READ: i64 = 1 << 1;
+    const RIGHT_FD_WRITE: i64 = 1 << 6;
+    const OFLAG_CREAT: i32 = 1;
+    const OFLAG_EXCL: i32 = 4;
+    const OFLAG_TRUNC: i32 = 8;
+    const FDFLAG_APPEND: i32 = 1;
+
+    /// Accessor for the WASI state inside the store data
+    type Getter<T> = fn(&mut T) -> &mut WasiState;
+
+    /// Define the supported preview1 functions on `linker`
+    ///
+    /// Every call is charged [`WASI_CALL_FUEL`] from the store's fuel, plus
+    /// [`WASI_BYTE_FUEL`] per byte for calls that move data; a call the
+    /// remaining fuel cannot cover traps with `OutOfFuel`.
+    pub(crate) fn add_to_linker<T: 'static>(
+        linker: &mut Linker<T>,
+        get: Getter<T>,
+    ) -> wasmtime::Result<()> {
+        linker.func_wrap(
+            WASI_MODULE,
+            "args_sizes_get",
+            move |mut caller: Caller<'_, T>, count_ptr: i32, size_ptr: i32| {
+                charge(&mut caller, 0)?;
+                let args = get(caller.data_mut()).args().to_vec();
+                Ok(errno(write_sizes(&mut caller, &args, count_ptr, size_ptr)))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "args_get",
+            move |mut caller: Caller<'_, T>, argv: i32, buf: i32| {
+                let args = get(caller.data_mut()).args().to_vec();
+                charge(&mut caller, strings_size(&args))?;
+                Ok(errno(write_strings(&mut caller, &args, argv, buf)))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "environ_sizes_get",
+            move |mut caller: Caller<'_, T>, count_ptr: i32, size_ptr: i32| {
+                charge(&mut caller, 0)?;
+                let env = get(caller.data_mut()).environ().to_vec();
+                Ok(errno(write_sizes(&mut caller, &env, count_ptr, size_ptr)))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "environ_get",
+            move |mut caller: Caller<'_, T>, environ: i32, buf: i32| {
+                let env = get(caller.data_mut()).environ().to_vec();
+                charge(&mut caller, strings_size(&env))?;
+                Ok(errno(write_strings(&mut caller, &env, environ, buf)))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "clock_res_get",
+            move |mut caller: Caller<'_, T>, clock_id: i32, res_ptr: i32| {
+                charge(&mut caller, 0)?;
+                Ok(errno(if (0..=3).contains(&clock_id) {
+                    write_guest(&mut caller, res_ptr, &NANOS_PER_TICK.to_le_bytes())
+                } else {
+                    Err(Errno::Inval)
+                }))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "clock_time_get",
+            move |mut caller: Caller<'_, T>, clock_id: i32, _precision: i64, time_ptr: i32| {
+                charge(&mut caller, 0)?;
+                let time = u32::try_from(clock_id)
+                    .map_err(|_| Errno::Inval)
+                    .and_then(|id| get(caller.data_mut()).clock_time(id));
+                Ok(errno(time.and_then(|t| write_guest(&mut caller, time_ptr, &t.to_le_bytes()))))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "random_get",
+            move |mut caller: Caller<'_, T>, buf: i32, len: i32| {
+                // Check the buffer before charging for or generating its bytes
+                if !guest_fits(&mut caller, buf, len) {
+                    return Ok(Errno::Fault.code());
+                }
+                charge(&mut caller, u64::from(len as u32))?;
+                let bytes = get(caller.data_mut()).random(len as u32 as usize);
+                Ok(errno(write_guest(&mut caller, buf, &bytes)))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "fd_write",
+            move |mut caller: Caller<'_, T>, fd: i32, iovs: i32, iovs_len: i32, nwritten: i32| {
+                let bytes = iovec_bytes(&mut caller, iovs, iovs_len);
+                charge(&mut caller, bytes)?;
+                Ok(errno(fd_write(&mut caller, get, fd, iovs, iovs_len, nwritten)))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "fd_read",
+            move |mut caller: Caller<'_, T>, fd: i32, iovs: i32, iovs_len: i32, nread: i32| {
+                let bytes = iovec_bytes(&mut caller, iovs, iovs_len);
+                charge(&mut caller, bytes)?;
+                Ok(errno(fd_read(&mut caller, get, fd, iovs, iovs_len, nread)))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "fd_seek",
+            move |mut caller: Caller<'_, T>, fd: i32, delta: i64, whence: i32, new_ptr: i32| {
+                charge(&mut caller, 0)?;
+                let whence = u8::try_from(whence).map_err(|_| Errno::Inval);
+                let offset = whence.and_then(|whence| {
+                    get(caller.data_mut()).seek(descriptor(fd)?, delta, whence)
+                });
+                Ok(errno(offset.and_then(|o| write_guest(&mut caller, new_ptr, &o.to_le_bytes()))))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "fd_close",
+            move |mut caller: Caller<'_, T>, fd: i32| {
+                charge(&mut caller, 0)?;
+                Ok(errno(descriptor(fd).and_then(|fd| get(caller.data_mut()).close(fd))))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "fd_fdstat_get",
+            move |mut caller: Caller<'_, T>, fd: i32, stat_ptr: i32| {
+                charge(&mut caller, 0)?;
+                let stat = descriptor(fd).and_then(|fd| get(caller.data_mut()).stat(fd));
+                Ok(errno(stat.and_then(|(filetype, _)| {
+                    // filetype u8, flags u16, rights_base u64, rights_inheriting u64
+                    let mut fdstat = [0u8; 24];
+                    fdstat[0] = filetype as u8;
+                    fdstat[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+                    fdstat[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+                    write_guest(&mut caller, stat_ptr, &fdstat)
+                })))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "fd_filestat_get",
+            move |mut caller: Caller<'_, T>, fd: i32, stat_ptr: i32| {
+                charge(&mut caller, 0)?;
+                let stat = descriptor(fd).and_then(|fd| get(caller.data_mut()).stat(fd));
+                Ok(errno(stat.and_then(|(filetype, size)| {
+                    // dev, ino, filetype, nlink, size, atim, mtim, ctim
+                    let mut filestat = [0u8; 64];
+                    filestat[16] = filetype as u8;
+                    filestat[24..32].copy_from_slice(&1u64.to_le_bytes());
+                    filestat[32..40].copy_from_slice(&size.to_le_bytes());
+                    write_guest(&mut caller, stat_ptr, &filestat)
+                })))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "fd_prestat_get",
+            move |mut caller: Caller<'_, T>, fd: i32, prestat_ptr: i32| {
+                charge(&mut caller, 0)?;
+                let name = descriptor(fd).and_then(|fd| get(caller.data_mut()).prestat_name(fd));
+                Ok(errno(name.and_then(|name| {
+                    // tag u8 (0 = dir), name_len u32
+                    let mut prestat = [0u8; 8];
+                    prestat[4..8].copy_from_slice(&(name.len() as u32).to_le_bytes());
+                    write_guest(&mut caller, prestat_ptr, &prestat)
+                })))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "fd_prestat_dir_name",
+            move |mut caller: Caller<'_, T>, fd: i32, path_ptr: i32, path_len: i32| {
+                charge(&mut caller, 0)?;
+                let name = descriptor(fd).and_then(|fd| get(caller.data_mut()).prestat_name(fd));
+                Ok(errno(name.and_then(|name| {
+                    let bytes = name.as_bytes();
+                    let len = (path_len as u32 as usize).min(bytes.len());
+                    write_guest(&mut caller, path_ptr, &bytes[..len])
+                })))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "path_open",
+            move |mut caller: Caller<'_, T>,
+                  dirfd: i32,
+                  _dirflags: i32,
+                  path_ptr: i32,
+                  path_len: i32,
+                  oflags: i32,
+                  rights: i64,
+                  _inheriting: i64,
+                  fdflags: i32,
+                  fd_ptr: i32| {
+                charge(&mut caller, u64::from(path_len as u32))?;
+                let options = OpenOptions {
+                    read: rights & RIGHT_FD_READ != 0,
+                    write: rights & RIGHT_FD_WRITE != 0,
+                    create: oflags & OFLAG_CREAT != 0,
+                    exclusive: oflags & OFLAG_EXCL != 0,
+                    truncate: oflags & OFLAG_TRUNC != 0,
+                    append: fdflags & FDFLAG_APPEND != 0,
+                };
+                Ok(errno(path_open(&mut caller, get, dirfd, path_ptr, path_len, options, fd_ptr)))
+            },
+        )?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "proc_exit",
+            move |mut caller: Caller<'_, T>, code: i32| -> wasmtime::Result<()> {
+                charge(&mut caller, 0)?;
+                get(caller.data_mut()).exit(code as u32);
+                Err(wasmtime::Error::msg(format!("exit with code {code}")))
+            },
+        )?;
+        linker.func_wrap(WASI_MODULE, "sched_yield", |mut caller: Caller<'_, T>| {
+            charge(&mut caller, 0)?;
+            Ok(SUCCESS)
+        })?;
+        linker.func_wrap(
+            WASI_MODULE,
+            "poll_oneoff",
+            |mut caller: Caller<'_, T>, _: i32, _: i32, _: i32, _: i32| {
+                charge(&mut caller, 0)?;
+                Ok(Errno::Nosys.code())
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Charge a call moving `bytes` bytes against the store's fuel
+    ///
+    /// Traps with `OutOfFuel`, leaving no fuel, if the fuel cannot cover it.
+    fn charge<T>(caller: &mut Caller<'_, T>, bytes: u64) -> wasmtime::Result<()> {
+        let cost = WASI_CALL_FUEL.saturating_add(bytes.saturating_mul(WASI_BYTE_FUEL));
+        let fuel = caller.get_fuel()?;
+        if fuel < cost {
+            caller.set_fuel(0)?;
+            return Err(Trap::OutOfFuel.into());
+        }
+        caller.set_fuel(fuel - cost)
+    }
+
+    /// Total NUL-terminated size of `strings`
+    fn strings_size(strings: &[String]) -> u64 {
+        strings.iter().map(|s| s.len() as u64 + 1).sum()
+    }
+
+    /// Bytes the iovecs at `iovs` cover; zero if they are out of bounds
+    fn iovec_bytes<T>(caller: &mut Caller<'_, T>, iovs: i32, iovs_len: i32) -> u64 {
+        read_iovecs(caller, iovs, iovs_len)
+            .map_or(0, |iovs| iovs.iter().map(|&(_, len)| u64::from(len as u32)).sum())
+    }
+
+    /// Check that `len` bytes at `ptr` lie within guest memory
+    fn guest_fits<T>(caller: &mut Caller<'_, T>, ptr: i32, len: i32) -> bool {
+        let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) else {
+            return false;
+        };
+        let end = u64::from(ptr as u32).checked_add(u64::from(len as u32));
+        end.is_some_and(|end| end <= memory.data_size(&*caller) as u64)
+    }
+
+    fn errno(result: Result<(), Errno>) -> i32 {
+        result.map_or_else(Errno::code, |()| SUCCESS)
+    }
+
+    fn descriptor(fd: i32) -> Result<u32, Errno> {
+        u32::try_from(fd).map_err(|_| Errno::Badf)
+    }
+
+    /// Gather the iovecs and write them to `fd`
+    fn fd_write<T>(
+        caller: &mut Caller<'_, T>,
+        get: Getter<T>,
+        fd: i32,
+        iovs: i32,
+        iovs_len: i32,
+        nwritten: i32,
+    ) -> Result<(), Errno> {
+        let fd = descriptor(fd)?;
+        let mut total = 0u32;
+        for (ptr, len) in read_iovecs(caller, iovs, iovs_len)? {
+            let bytes = read_guest(caller, ptr, len)?;
+            let written = get(caller.data_mut()).write(fd, &bytes)?;
+            total = total.saturating_add(written as u32);
+        }
+        write_guest(caller, nwritten, &total.to_le_bytes())
+    }
+
+    /// Read from `fd` into the iovecs, stopping at a short read
+    fn fd_read<T>(
+        caller: &mut Caller<'_, T>,
+        get: Getter<T>,
+        fd: i32,
+        iovs: i32,
+        iovs_len: i32,
+        nread: i32,
+    ) -> Result<(), Errno> {
+        let fd = descriptor(fd)?;
+        let mut total = 0u32;
+        for (ptr, len) in read_iovecs(caller, iovs, iovs_len)? {
+            let bytes = get(caller.data_mut()).read(fd, len as usize)?;
+            write_guest(caller, ptr, &bytes)?;
+            total = total.saturating_add(bytes.len() as u32);
+            if bytes.len() < len as usize {
+                break;
+            }
+        }
+        write_guest(caller, nread, &total.to_le_bytes())
+    }
+
+    /// Open the guest path and write the new descriptor
+    fn path_open<T>(
+        caller: &mut Caller<'_, T>,
+        get: Getter<T>,
+        dirfd: i32,
+        path_ptr: i32,
+        path_len: i32,
+        options: OpenOptions,
+        fd_ptr: i32,
+    ) -> Result<(), Errno> {
+        let path = read_guest(caller, path_ptr, path_len)?;
+        let path = String::from_utf8(path).map_err(|_| Errno::Inval)?;
+        let fd = get(caller.data_mut()).open(descriptor(dirfd)?, &path, options)?;
+        write_guest(caller, fd_ptr, &fd.to_le_bytes())
+    }
+
+    /// Copy bytes out of guest memory; pointers are unsigned 32-bit offsets
+    fn read_guest<T>(caller: &mut Caller<'_, T>, ptr: i32, len: i32) -> Result<Vec<u8>, Errno> {
+        let memory = caller
+            .get_export("memory")
+            .and_then(Extern::into_memory)
+            .ok_or(Errno::Fault)?;
+        let (ptr, len) = (u64::from(ptr as u32), u64::from(len as u32));
+        guest_read(memory.data(&*caller), ptr, len).map_err(|_| Errno::Fault)
+    }
+
+    /// Copy bytes into guest memory at a guest-supplied out pointer
+    fn write_guest<T>(caller: &mut Caller<'_, T>, ptr: i32, bytes: &[u8]) -> Result<(), Errno> {
+        let memory = caller
+            .get_export("memory")
+            .and_then(Extern::into_memory)
+            .ok_or(Errno::Fault)?;
+        memory
+            .write(&mut *caller, ptr as u32 as usize, bytes)
+            .map_err(|_| Errno::Fault)
+    }
+
+    /// Read an array of `(buf u32, len u32)` iovecs
+    fn read_iovecs<T>(
+        caller: &mut Caller<'_, T>,
+        iovs: i32,
+        iovs_len: i32,
+    ) -> Result<Vec<(i32, i32)>, Errno> {
+        let size = i32::try_from(i64::from(iovs_len) * 8).map_err(|_| Errno::Inval)?;
+        let raw = read_guest(caller, iovs, size)?;
+        Ok(raw
+            .chunks_exact(8)
+            .map(|iov| {
+                let ptr = i32::from_le_bytes([iov[0], iov[1], iov[2], iov[3]]);
+                let len = i32::from_le_bytes([iov[4], iov[5], iov[6], iov[7]]);
+                (ptr, len)
+            })
+            .collect())
+    }
+
+    /// Write the count and total NUL-terminated size of `strings`
+    fn write_sizes<T>(
+        caller: &mut Caller<'_, T>,
+        strings: &[String],
+        count_ptr: i32,
+        size_ptr: i32,
+    ) -> Result<(), Errno> {
+        let size: usize = strings.iter().map(|s| s.len() + 1).sum();
+        write_guest(caller, count_ptr, &(strings.len() as u32).to_le_bytes())?;
+        write_guest(caller, size_ptr, &(size as u32).to_le_bytes())
+    }
+
+    /// Write `strings` NUL-terminated into `buf` and their addresses into `list`
+    fn write_strings<T>(
+        caller: &mut Caller<'_, T>,
+        strings: &[String],
+        list: i32,
+        buf: i32,
+    ) -> Result<(), Errno> {
+        let mut offset = buf as u32;
+        for (i, string) in strings.iter().enumerate() {
+            let entry = (list as u32).wrapping_add(i as u32 * 4);
+            write_guest(caller, entry as i32, &offset.to_le_bytes())?;
+            let mut bytes = string.as_bytes().to_vec();
+            bytes.push(0);
+            write_guest(caller, offset as i32, &bytes)?;
+            offset = offset.wrapping_add(bytes.len() as u32);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(capabilities: &[Capability]) -> WasiState {
+        let config = WasiConfig::new()
+            .with_arg("guest")
+            .with_env("HOME", "/home/guest")
+            .with_env("SECRET", "hunter2")
+            .with_file("data/input.txt", b"hello".to_vec())
+            .with_stdin(b"line\n".to_vec())
+            .with_seed(7);
+        WasiState::new(&config, capabilities)
+    }
+
+    #[test]
+    fn test_wasi_environ_filtered() {
+        let wasi = state(&[Capability::EnvRead {
+            vars: vec!["HOME".to_string()],
+        }]);
+        assert_eq!(wasi.environ(), ["HOME=/home/guest".to_string()]);
+        assert_eq!(wasi.args(), ["guest".to_string()]);
+        assert!(state(&[]).environ().is_empty());
+    }
+
+    #[test]
+    fn test_wasi_clock_logical() {
+        let mut wasi = state(&[Capability::ClockRead]);
+        assert_eq!(wasi.clock_time(0), Ok(NANOS_PER_TICK));
+        assert_eq!(wasi.clock_time(1), Ok(2 * NANOS_PER_TICK));
+        assert_eq!(wasi.clock_time(9), Err(Errno::Inval));
+        assert_eq!(state(&[]).clock_time(0), Err(Errno::Notcapable));
+    }
+
+    #[test]
+    fn test_wasi_random_seeded() {
+        let mut a = state(&[]);
+        let mut b = state(&[]);
+        let first = a.random(40);
+        assert_eq!(first.len(), 40);
+        assert_eq!(first, b.random(40));
+        assert_ne!(first, a.random(40));
+
+        let mut other = WasiState::new(&WasiConfig::new().with_seed(8), &[]);
+        assert_ne!(first, other.random(40));
+    }
+
+    #[test]
+    fn test_wasi_file_access() {
+        let read = Capability::FsRead {
+            prefixes: vec!["data".to_string()],
+        };
+        let write = Capability::FsWrite {
+            prefixes: vec!["./out".to_string()],
+        };
+        let mut wasi = state(&[read, write]);
+        let reading = OpenOptions {
+            read: true,
+            ..OpenOptions::default()
+        };
+        let writing = OpenOptions {
+            write: true,
+            create: true,
+            ..OpenOptions::default()
+        };
+
+        let fd = wasi.open(ROOT_FD, "./data/input.txt", reading).unwrap();
+        assert_eq!(wasi.read(fd, 3).unwrap(), b"hel");
+        assert_eq!(wasi.read(fd, 10).unwrap(), b"lo");
+        assert_eq!(wasi.seek(fd, 1, 0), Ok(1));
+        assert_eq!(wasi.read(fd, 1).unwrap(), b"e");
+        assert_eq!(wasi.write(fd, b"x"), Err(Errno::Badf));
+        wasi.close(fd).unwrap();
+        assert_eq!(wasi.read(fd, 1), Err(Errno::Badf));
+
+        let fd = wasi.open(ROOT_FD, "out/result.txt", writing).unwrap();
+        assert_eq!(wasi.write(fd, b"done"), Ok(4));
+        assert_eq!(wasi.files()["out/result.txt"], b"done");
+
+        assert_eq!(wasi.open(ROOT_FD, "data/input.txt", writing), Err(Errno::Notcapable));
+        assert_eq!(wasi.open(ROOT_FD, "data/missing", reading), Err(Errno::Noent));
+        assert_eq!(wasi.open(ROOT_FD, "../etc/passwd", reading), Err(Errno::Notcapable));
+        assert_eq!(wasi.open(ROOT_FD, "/etc/passwd", reading), Err(Errno::Notcapable));
+        assert_eq!(wasi.open(0, "data/input.txt", reading), Err(Errno::Notcapable));
+    }
+
+    #[test]
+    fn test_wasi_write_bounded() {
+        let write = Capability::FsWrite {
+            prefixes: vec!["out".to_string()],
+        };
+        let mut wasi = state(&[write]);
+        let writing = OpenOptions {
+            write: true,
+            create: true,
+            ..OpenOptions::default()
+        };
+        let fd = wasi.open(ROOT_FD, "out/big.bin", writing).unwrap();
+
+        assert_eq!(wasi.seek(fd, MAX_FILE_SIZE as i64, 0), Ok(MAX_FILE_SIZE as u64));
+        assert_eq!(wasi.write(fd, b"x"), Err(Errno::Fbig));
+        assert_eq!(wasi.seek(fd, i64::MAX, 0), Ok(i64::MAX as u64));
+        assert_eq!(wasi.write(fd, b"x"), Err(Errno::Fbig));
+        assert!(wasi.files()["out/big.bin"].is_empty());
+
+        assert_eq!(wasi.seek(fd, MAX_FILE_SIZE as i64 - 1, 0), Ok(MAX_FILE_SIZE as u64 - 1));
+        assert_eq!(wasi.write(fd, b"x"), Ok(1));
+        assert_eq!(wasi.files()["out/big.bin"].len(), MAX_FILE_SIZE);
+    }
+
+    #[test]
+    fn test_wasi_stdio() {
+        let mut wasi = state(&[]);
+        assert_eq!(wasi.read(0, 64).unwrap(), b"line\n");
+        assert!(wasi.read(0, 64).unwrap().is_empty());
+        wasi.write(1, b"out").unwrap();
+        wasi.write(2, b"err").unwrap();
+        assert_eq!(wasi.stdout(), b"out");
+        assert_eq!(wasi.stderr(), b"err");
+        assert_eq!(wasi.seek(1, 0, 0), Err(Errno::Spipe));
+        assert_eq!(wasi.stat(ROOT_FD), Ok((FileType::Directory, 0)));
+        assert_eq!(wasi.prestat_name(ROOT_FD), Ok("."));
+        assert_eq!(wasi.prestat_name(1), Err(Errno::Badf));
+    }
+}